@@ -129,6 +129,16 @@ pub fn checksum_session(session: &Session) -> u64 {
                 acc.wrapping_mul(131).wrapping_add(checksum_flowchart(ast))
             }
             DiagramAst::Sequence(ast) => acc.wrapping_mul(131).wrapping_add(checksum_sequence(ast)),
+            DiagramAst::State(ast) => acc
+                .wrapping_mul(131)
+                .wrapping_add(ast.states().len() as u64)
+                .wrapping_mul(131)
+                .wrapping_add(ast.transitions().len() as u64),
+            DiagramAst::Class(ast) => acc
+                .wrapping_mul(131)
+                .wrapping_add(ast.classes().len() as u64)
+                .wrapping_mul(131)
+                .wrapping_add(ast.relations().len() as u64),
         };
     }
 
@@ -8,13 +8,17 @@
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use nereid::layout::{flowchart::layout_flowchart, sequence::layout_sequence};
-use nereid::render::{flowchart::render_flowchart_unicode, sequence::render_sequence_unicode};
+use nereid::model::{Diagram, DiagramAst, DiagramId};
+use nereid::render::budget::{render_diagram_within_budget, RenderBudget};
+use nereid::render::flowchart::{render_flowchart_unicode, render_flowchart_unicode_with_options};
+use nereid::render::{sequence::render_sequence_unicode, RenderOptions};
 
 mod fixtures;
 mod profiler;
 
 // Benchmark identity (keep stable):
-// - Group names in this file: `render.sequence`, `render.flow`
+// - Group names in this file: `render.sequence`, `render.flow`, `render.flow.compact`,
+//   `render.budget`
 // - Case IDs (the string after the `/`) must remain stable across refactors so
 //   results stay comparable over time (e.g. `small`, `medium_dense`, `large_long_labels`).
 // - If implementations move/deduplicate, update the wiring but do not rename
@@ -81,6 +85,74 @@ fn benches_render(c: &mut Criterion) {
         });
     }
     group.finish();
+
+    let mut group = c.benchmark_group("render.flow.compact");
+    let compact_options = RenderOptions { flowchart_compact: true, ..RenderOptions::default() };
+    for case in [
+        fixtures::flow::Case::Small,
+        fixtures::flow::Case::MediumDense,
+        fixtures::flow::Case::DenseCrossing,
+        fixtures::flow::Case::RoutingStress,
+    ] {
+        let flow = fixtures::flow::fixture(case);
+        let layout = layout_flowchart(&flow).expect("layout_flowchart");
+        let base = render_flowchart_unicode(&flow, &layout).expect("render_flowchart_unicode");
+        let compact = render_flowchart_unicode_with_options(&flow, &layout, compact_options)
+            .expect("render_flowchart_unicode_with_options");
+        let (base_w, base_h) = text_dims(&base);
+        let (compact_w, compact_h) = text_dims(&compact);
+        assert!(compact_w <= base_w && compact_h <= base_h, "compaction grew {}", case.id());
+
+        group.bench_function(case.id(), move |b| {
+            b.iter(|| {
+                let rendered = render_flowchart_unicode_with_options(
+                    black_box(&flow),
+                    black_box(&layout),
+                    compact_options,
+                )
+                .expect("render_flowchart_unicode_with_options");
+                black_box(rendered.len())
+            })
+        });
+    }
+    group.finish();
+
+    // Budget thresholds come from `NEREID_MAX_RENDER_MS` / `NEREID_MAX_CANVAS_CELLS`, so the
+    // measured time includes the level-of-detail re-render the TUI does when a case is over budget.
+    let mut group = c.benchmark_group("render.budget");
+    let budget = RenderBudget::from_env().expect("render budget");
+    let budget_cases = [
+        (
+            fixtures::seq::Case::Medium.id(),
+            DiagramAst::Sequence(fixtures::seq::fixture(fixtures::seq::Case::Medium)),
+        ),
+        (
+            fixtures::flow::Case::LargeLongLabels.id(),
+            DiagramAst::Flowchart(fixtures::flow::fixture(fixtures::flow::Case::LargeLongLabels)),
+        ),
+        (
+            fixtures::flow::Case::RoutingStress.id(),
+            DiagramAst::Flowchart(fixtures::flow::fixture(fixtures::flow::Case::RoutingStress)),
+        ),
+    ];
+    for (case_id, ast) in budget_cases {
+        let diagram = Diagram::new(DiagramId::new("bench").expect("diagram id"), case_id, ast);
+        let options = RenderOptions::default();
+        group.bench_function(case_id, move |b| {
+            b.iter(|| {
+                let budgeted =
+                    render_diagram_within_budget(black_box(&diagram), options, black_box(&budget))
+                        .expect("render_diagram_within_budget");
+                black_box(budgeted.rendered.text.len())
+            })
+        });
+    }
+    group.finish();
+}
+
+fn text_dims(text: &str) -> (usize, usize) {
+    let width = text.lines().map(|line| line.chars().count()).max().unwrap_or(0);
+    (width, text.lines().count())
 }
 
 criterion_group! {
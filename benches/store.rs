@@ -7,10 +7,18 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
-use nereid::format::mermaid::{export_flowchart, export_sequence_diagram};
-use nereid::layout::{flowchart::layout_flowchart, sequence::layout_sequence};
+use nereid::format::mermaid::{
+    export_class_diagram, export_flowchart, export_sequence_diagram, export_state_diagram,
+};
+use nereid::layout::{
+    class::layout_class, flowchart::layout_flowchart, sequence::layout_sequence,
+    state::layout_state,
+};
 use nereid::model::{DiagramAst, Session};
-use nereid::render::{flowchart::render_flowchart_unicode, sequence::render_sequence_unicode};
+use nereid::render::{
+    class::render_class_unicode, flowchart::render_flowchart_unicode,
+    sequence::render_sequence_unicode, state::render_state_unicode,
+};
 use nereid::store::SessionFolder;
 
 mod fixtures;
@@ -45,6 +53,26 @@ fn checksum_compute_only_save(session: &Session) -> u64 {
                 acc = acc.wrapping_mul(131).wrapping_add(layout.node_placements().len() as u64);
                 acc = acc.wrapping_mul(131).wrapping_add(rendered.len() as u64);
             }
+            DiagramAst::State(ast) => {
+                let mmd = export_state_diagram(black_box(ast)).expect("export_state_diagram");
+                let layout = layout_state(black_box(ast)).expect("layout_state");
+                let rendered =
+                    render_state_unicode(black_box(&layout)).expect("render_state_unicode");
+
+                acc = acc.wrapping_mul(131).wrapping_add(mmd.len() as u64);
+                acc = acc.wrapping_mul(131).wrapping_add(layout.layout().layers().len() as u64);
+                acc = acc.wrapping_mul(131).wrapping_add(rendered.len() as u64);
+            }
+            DiagramAst::Class(ast) => {
+                let mmd = export_class_diagram(black_box(ast)).expect("export_class_diagram");
+                let layout = layout_class(black_box(ast)).expect("layout_class");
+                let rendered = render_class_unicode(black_box(ast), black_box(&layout))
+                    .expect("render_class_unicode");
+
+                acc = acc.wrapping_mul(131).wrapping_add(mmd.len() as u64);
+                acc = acc.wrapping_mul(131).wrapping_add(layout.columns().len() as u64);
+                acc = acc.wrapping_mul(131).wrapping_add(rendered.len() as u64);
+            }
         }
     }
 
@@ -0,0 +1,108 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Builds a session from Mermaid and op batches, links two diagrams with an xref, and saves it
+//! as a session folder the TUI and MCP server can open.
+//!
+//! ```sh
+//! cargo run --example build_session --features cookbook -- [<session-dir>]
+//! nereid <session-dir>
+//! ```
+
+use std::env;
+use std::error::Error;
+use std::path::PathBuf;
+
+use nereid::format::mermaid::{parse_flowchart, parse_sequence_diagram};
+use nereid::model::{
+    Diagram, DiagramAst, DiagramId, ObjectId, ObjectRef, Session, SessionId, XRefId, XRefStatus,
+};
+use nereid::ops::{apply_ops, apply_session_ops, FlowOp, Op, XRefOp};
+use nereid::render::render_diagram_unicode;
+use nereid::store::SessionFolder;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let session_dir = env::args().nth(1).map(PathBuf::from).unwrap_or_else(|| {
+        env::temp_dir().join(format!("nereid-cookbook-session-{}", std::process::id()))
+    });
+
+    let mut session = Session::new(SessionId::new("s:cookbook")?);
+
+    // Diagrams start from Mermaid text...
+    let flow_id = DiagramId::new("checkout-flow")?;
+    let flow = parse_flowchart(
+        "flowchart LR\n  client[Client] --> api[API]\n  api --> db[(Orders DB)]\n",
+    )?;
+    session.diagrams_mut().insert(
+        flow_id.clone(),
+        Diagram::new(flow_id.clone(), "Checkout flow", DiagramAst::Flowchart(flow)),
+    );
+
+    let seq_id = DiagramId::new("checkout-seq")?;
+    let seq = parse_sequence_diagram(
+        "sequenceDiagram\n  participant Client\n  participant API\n  Client->>API: POST /checkout\n  API-->>Client: 201 Created\n",
+    )?;
+    session.diagrams_mut().insert(
+        seq_id.clone(),
+        Diagram::new(seq_id.clone(), "Checkout sequence", DiagramAst::Sequence(seq)),
+    );
+
+    // ...and change through op batches, each checked against the revision it was made for.
+    let diagram = session.diagrams_mut().get_mut(&flow_id).expect("flow diagram");
+    let base_rev = diagram.rev();
+    let result = apply_ops(
+        diagram,
+        base_rev,
+        &[
+            Op::Flow(FlowOp::AddNode {
+                node_id: ObjectId::new("n:payments")?,
+                label: "Payments".to_owned(),
+                shape: Some("round".to_owned()),
+            }),
+            Op::Flow(FlowOp::AddEdge {
+                edge_id: ObjectId::new("e:charge")?,
+                from_node_id: ObjectId::new("n:api")?,
+                to_node_id: ObjectId::new("n:payments")?,
+                label: Some("charge".to_owned()),
+                connector: None,
+                style: None,
+            }),
+        ],
+    )?;
+    assert_eq!(result.new_rev, base_rev + 1);
+    assert!(apply_ops(diagram, base_rev, &[]).is_err(), "stale batches are rejected");
+
+    // Xrefs link objects across diagrams; their status tracks whether both ends exist.
+    let xref_id = XRefId::new("x:api-implements")?;
+    apply_session_ops(
+        &mut session,
+        &[Op::XRef(XRefOp::Add {
+            xref_id: xref_id.clone(),
+            from: ObjectRef::parse("d:checkout-seq/seq/participant/p:API")?,
+            to: ObjectRef::parse("d:checkout-flow/flow/node/n:api")?,
+            kind: "implements".to_owned(),
+            label: None,
+        })],
+    )?;
+    assert_eq!(session.xrefs()[&xref_id].status(), XRefStatus::Ok);
+    session.set_active_diagram_id(Some(flow_id.clone()));
+
+    let folder = SessionFolder::new(&session_dir);
+    folder.save_session(&session)?;
+
+    let loaded = folder.load_session()?;
+    assert_eq!(loaded.diagrams().len(), 2);
+    assert_eq!(loaded.xrefs().len(), 1);
+    assert_eq!(loaded.active_diagram_id(), Some(&flow_id));
+    let flow = &loaded.diagrams()[&flow_id];
+    assert_eq!(flow.rev(), result.new_rev);
+
+    println!("{}", render_diagram_unicode(flow)?);
+    println!("saved session to {}", session_dir.display());
+    Ok(())
+}
@@ -0,0 +1,112 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Talks to an in-process Nereid MCP server the way an agent would: lists the tools, reads the
+//! diagrams, applies an op batch and renders the result.
+//!
+//! ```sh
+//! cargo run --example mcp_client --features cookbook
+//! ```
+//!
+//! The server and client share an in-memory pipe here; the same calls work against
+//! `nereid --mcp` over stdio or the TUI's streamable HTTP endpoint.
+
+use std::error::Error;
+
+use nereid::format::mermaid::parse_flowchart;
+use nereid::mcp::NereidMcp;
+use nereid::model::{Diagram, DiagramAst, DiagramId, Session, SessionId};
+use rmcp::model::CallToolRequestParams;
+use rmcp::service::{Peer, RoleClient};
+use rmcp::ServiceExt;
+use serde_json::{json, Value};
+
+/// Calls `name` with `arguments` and returns its structured result.
+async fn call(
+    peer: &Peer<RoleClient>,
+    name: &'static str,
+    arguments: Value,
+) -> Result<Value, Box<dyn Error>> {
+    let result = peer
+        .call_tool(CallToolRequestParams {
+            meta: None,
+            name: name.into(),
+            arguments: arguments.as_object().cloned(),
+            task: None,
+        })
+        .await?;
+    if result.is_error == Some(true) {
+        return Err(format!("{name} failed: {:?}", result.content).into());
+    }
+    result.structured_content.ok_or_else(|| format!("{name} returned no structured content").into())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let mut session = Session::new(SessionId::new("s:cookbook")?);
+    let diagram_id = DiagramId::new("login")?;
+    let ast = parse_flowchart(
+        "flowchart LR\n  form[Login form] --> auth{Valid?}\n  auth --> home[Home]\n",
+    )?;
+    session
+        .diagrams_mut()
+        .insert(diagram_id.clone(), Diagram::new(diagram_id, "Login", DiagramAst::Flowchart(ast)));
+    session.set_active_diagram_id(Some(DiagramId::new("login")?));
+
+    let (server_io, client_io) = tokio::io::duplex(64 * 1024);
+    let server = tokio::spawn(async move {
+        let service = NereidMcp::new(session).serve(tokio::io::split(server_io)).await?;
+        service.waiting().await?;
+        Ok::<_, Box<dyn Error + Send + Sync>>(())
+    });
+    let client = ().serve(tokio::io::split(client_io)).await?;
+
+    let tools = client.list_all_tools().await?;
+    assert!(tools.iter().any(|tool| tool.name == "diagram.apply_ops"));
+    println!("server offers {} tools", tools.len());
+
+    let listed = call(&client, "diagram.list", json!({})).await?;
+    let rev = listed["diagrams"][0]["rev"].as_u64().expect("diagram rev");
+
+    let applied = call(
+        &client,
+        "diagram.apply_ops",
+        json!({
+            "diagram_id": "login",
+            "base_rev": rev,
+            "ops": [
+                { "type": "flow_add_node", "node_id": "n:retry", "label": "Retry", "shape": "round" },
+                {
+                    "type": "flow_add_edge",
+                    "edge_id": "e:retry",
+                    "from_node_id": "n:auth",
+                    "to_node_id": "n:retry",
+                    "label": "no"
+                }
+            ]
+        }),
+    )
+    .await?;
+    assert_eq!(applied["new_rev"].as_u64(), Some(rev + 1));
+
+    // Batches made against an older revision are rejected instead of clobbering newer edits.
+    let stale = call(
+        &client,
+        "diagram.apply_ops",
+        json!({ "diagram_id": "login", "base_rev": rev, "ops": [] }),
+    )
+    .await;
+    assert!(stale.is_err(), "stale base_rev is rejected");
+
+    let rendered = call(&client, "diagram.render_text", json!({ "diagram_id": "login" })).await?;
+    println!("{}", rendered["text"].as_str().unwrap_or_default());
+
+    client.cancel().await?;
+    server.await?.map_err(|err| err.to_string())?;
+    Ok(())
+}
@@ -0,0 +1,88 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Parses a Mermaid file, prints its Unicode rendering and writes it as an SVG with one node or
+//! participant marked as selected.
+//!
+//! ```sh
+//! cargo run --example render_to_svg --features cookbook -- [<input.mmd> [<output.svg>]]
+//! ```
+//!
+//! Without arguments a built-in flowchart is rendered into the temp directory.
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use nereid::format::mermaid::{
+    parse_class_diagram, parse_flowchart, parse_sequence_diagram, parse_state_diagram,
+};
+use nereid::format::svg::diagram_svg;
+use nereid::format::theme::{ExportSelection, ExportStyle, ExportTheme};
+use nereid::model::{CategoryPath, Diagram, DiagramAst, DiagramId, ObjectRef};
+use nereid::render::render_diagram_unicode;
+
+const SAMPLE: &str = "flowchart TD\n  start([Start]) --> check{Valid?}\n  check -->|yes| save[Save]\n  check -->|no| reject[Reject]\n";
+
+/// Parses `mermaid` by the diagram type on its first non-empty line.
+fn parse_mermaid(mermaid: &str) -> Result<DiagramAst, Box<dyn Error>> {
+    let header = mermaid.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or_default();
+    Ok(if header.starts_with("sequenceDiagram") {
+        DiagramAst::Sequence(parse_sequence_diagram(mermaid)?)
+    } else if header.starts_with("stateDiagram") {
+        DiagramAst::State(parse_state_diagram(mermaid)?)
+    } else if header.starts_with("classDiagram") {
+        DiagramAst::Class(parse_class_diagram(mermaid)?)
+    } else {
+        DiagramAst::Flowchart(parse_flowchart(mermaid)?)
+    })
+}
+
+/// The flowchart node or sequence participant with the smallest id, as an object ref.
+fn first_object_ref(diagram: &Diagram) -> Option<ObjectRef> {
+    let (segments, object_id) = match diagram.ast() {
+        DiagramAst::Flowchart(ast) => (["flow", "node"], ast.nodes().keys().next()?.clone()),
+        DiagramAst::Sequence(ast) => {
+            (["seq", "participant"], ast.participants().keys().next()?.clone())
+        }
+        DiagramAst::State(_) | DiagramAst::Class(_) => return None,
+    };
+    let category = CategoryPath::new(segments.map(str::to_owned).to_vec()).ok()?;
+    Some(ObjectRef::new(diagram.diagram_id().clone(), category, object_id))
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let input = args.next();
+    let mermaid = match input.as_deref() {
+        Some(path) => fs::read_to_string(path)?,
+        None => SAMPLE.to_owned(),
+    };
+    let output = args.next().map(PathBuf::from).unwrap_or_else(|| {
+        env::temp_dir().join(format!("nereid-cookbook-{}.svg", std::process::id()))
+    });
+
+    let diagram = Diagram::new(DiagramId::new("example")?, "Example", parse_mermaid(&mermaid)?);
+    println!("{}", render_diagram_unicode(&diagram)?);
+
+    let style = ExportStyle {
+        theme: ExportTheme::named("print").expect("bundled theme"),
+        selection: ExportSelection::selected(first_object_ref(&diagram).into_iter().collect()),
+        ..ExportStyle::default()
+    };
+    let svg = diagram_svg(&diagram, &style)?;
+    assert!(svg.starts_with("<svg") || svg.starts_with("<?xml"), "standalone SVG document");
+    if !style.selection.selected.is_empty() {
+        assert!(svg.contains("selected"), "selection is marked");
+    }
+    fs::write(&output, svg)?;
+
+    println!("wrote {}", output.display());
+    Ok(())
+}
@@ -0,0 +1,281 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Graphviz DOT export.
+//!
+//! Node names are the object ids, so DOT output lines up with object refs. Flowchart subgraphs
+//! become `cluster_` subgraphs; sequence diagrams become participants joined by numbered message
+//! edges, since DOT has no notion of lifelines.
+
+use std::collections::BTreeMap;
+
+use crate::model::class_ast::{ClassAst, ClassRelationKind};
+use crate::model::flow_ast::FlowchartAst;
+use crate::model::seq_ast::{SequenceAst, SequenceMessageKind};
+use crate::model::state_ast::{StateAst, StateNodeKind};
+use crate::model::{Diagram, DiagramAst, ObjectId};
+
+pub fn export_dot(diagram: &Diagram) -> String {
+    let mut out = format!("digraph {} {{\n", quote(diagram.name()));
+    match diagram.ast() {
+        DiagramAst::Flowchart(ast) => flowchart_dot(ast, &mut out),
+        DiagramAst::Sequence(ast) => sequence_dot(ast, &mut out),
+        DiagramAst::State(ast) => state_dot(ast, &mut out),
+        DiagramAst::Class(ast) => class_dot(ast, &mut out),
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn flowchart_dot(ast: &FlowchartAst, out: &mut String) {
+    out.push_str("  rankdir=LR;\n  node [shape=box];\n");
+
+    let mut children = BTreeMap::<Option<&ObjectId>, Vec<&ObjectId>>::new();
+    for (subgraph_id, subgraph) in ast.subgraphs() {
+        children.entry(subgraph.parent()).or_default().push(subgraph_id);
+    }
+    let mut members = BTreeMap::<Option<&ObjectId>, Vec<&ObjectId>>::new();
+    for node_id in ast.nodes().keys() {
+        members.entry(ast.node_subgraph(node_id)).or_default().push(node_id);
+    }
+    write_flow_scope(ast, None, &children, &members, 1, out);
+
+    for edge in ast.edges().values() {
+        let connector = edge.connector().unwrap_or("-->");
+        let mut attrs = Vec::new();
+        if let Some(label) = edge.label() {
+            attrs.push(format!("label={}", quote(label)));
+        }
+        if connector.contains('.') {
+            attrs.push("style=dashed".to_owned());
+        } else if connector.contains("==") {
+            attrs.push("penwidth=2".to_owned());
+        }
+        if !connector.ends_with(['>', 'o', 'x']) {
+            attrs.push("arrowhead=none".to_owned());
+        } else if connector.starts_with('<') {
+            attrs.push("dir=both".to_owned());
+        }
+        out.push_str(&format!(
+            "  {} -> {}{};\n",
+            quote(edge.from_node_id().as_str()),
+            quote(edge.to_node_id().as_str()),
+            attr_list(&attrs)
+        ));
+    }
+}
+
+fn write_flow_scope(
+    ast: &FlowchartAst,
+    scope: Option<&ObjectId>,
+    children: &BTreeMap<Option<&ObjectId>, Vec<&ObjectId>>,
+    members: &BTreeMap<Option<&ObjectId>, Vec<&ObjectId>>,
+    depth: usize,
+    out: &mut String,
+) {
+    let indent = "  ".repeat(depth);
+    for subgraph_id in children.get(&scope).into_iter().flatten() {
+        let subgraph = &ast.subgraphs()[*subgraph_id];
+        out.push_str(&format!(
+            "{indent}subgraph {} {{\n{indent}  label={};\n",
+            quote(&format!("cluster_{subgraph_id}")),
+            quote(subgraph.label())
+        ));
+        write_flow_scope(ast, Some(subgraph_id), children, members, depth + 1, out);
+        out.push_str(&format!("{indent}}}\n"));
+    }
+    for node_id in members.get(&scope).into_iter().flatten() {
+        let node = &ast.nodes()[*node_id];
+        let mut attrs = vec![format!("label={}", quote(node.label()))];
+        match node.shape() {
+            "round" => attrs.push("style=rounded".to_owned()),
+            "diamond" => attrs.push("shape=diamond".to_owned()),
+            _ => {}
+        }
+        out.push_str(&format!("{indent}{}{};\n", quote(node_id.as_str()), attr_list(&attrs)));
+    }
+}
+
+fn sequence_dot(ast: &SequenceAst, out: &mut String) {
+    out.push_str("  rankdir=LR;\n  node [shape=box];\n  { rank=same;");
+    for participant_id in ast.participants().keys() {
+        out.push_str(&format!(" {};", quote(participant_id.as_str())));
+    }
+    out.push_str(" }\n");
+    for (participant_id, participant) in ast.participants() {
+        out.push_str(&format!(
+            "  {} [label={}];\n",
+            quote(participant_id.as_str()),
+            quote(participant.mermaid_name())
+        ));
+    }
+    for (idx, message) in ast.messages_in_order().into_iter().enumerate() {
+        let mut attrs =
+            vec![format!("label={}", quote(&format!("{}. {}", idx + 1, message.text())))];
+        match message.kind() {
+            SequenceMessageKind::Sync => {}
+            SequenceMessageKind::Async => attrs.push("arrowhead=open".to_owned()),
+            SequenceMessageKind::Return => attrs.push("style=dashed".to_owned()),
+        }
+        out.push_str(&format!(
+            "  {} -> {}{};\n",
+            quote(message.from_participant_id().as_str()),
+            quote(message.to_participant_id().as_str()),
+            attr_list(&attrs)
+        ));
+    }
+}
+
+fn state_dot(ast: &StateAst, out: &mut String) {
+    out.push_str("  node [shape=box, style=rounded];\n");
+    for (state_id, state) in ast.states() {
+        let attrs = match state.kind() {
+            StateNodeKind::State => vec![format!("label={}", quote(state.label()))],
+            StateNodeKind::Start => vec!["shape=point".to_owned(), "width=0.2".to_owned()],
+            StateNodeKind::End => vec!["shape=doublecircle".to_owned(), "label=\"\"".to_owned()],
+            StateNodeKind::Choice => vec!["shape=diamond".to_owned(), "label=\"\"".to_owned()],
+            StateNodeKind::Fork | StateNodeKind::Join => vec![
+                "shape=box".to_owned(),
+                "style=filled".to_owned(),
+                "label=\"\"".to_owned(),
+                "height=0.1".to_owned(),
+            ],
+        };
+        out.push_str(&format!("  {}{};\n", quote(state_id.as_str()), attr_list(&attrs)));
+    }
+    for transition in ast.transitions().values() {
+        let attrs = transition
+            .label()
+            .map(|label| vec![format!("label={}", quote(label))])
+            .unwrap_or_default();
+        out.push_str(&format!(
+            "  {} -> {}{};\n",
+            quote(transition.from_state_id().as_str()),
+            quote(transition.to_state_id().as_str()),
+            attr_list(&attrs)
+        ));
+    }
+}
+
+fn class_dot(ast: &ClassAst, out: &mut String) {
+    out.push_str("  rankdir=BT;\n  node [shape=box];\n");
+    for (class_id, class) in ast.classes() {
+        let mut lines = Vec::new();
+        if let Some(annotation) = class.annotation() {
+            lines.push(format!("«{annotation}»"));
+        }
+        lines.push(class.label().to_owned());
+        lines.extend(class.members().iter().map(|member| member.text().to_owned()));
+        let label = format!("label={}", quote(&lines.join("\n")));
+        out.push_str(&format!("  {}{};\n", quote(class_id.as_str()), attr_list(&[label])));
+    }
+    for relation in ast.relations().values() {
+        let mut attrs = Vec::new();
+        let arrowhead = match relation.kind() {
+            ClassRelationKind::Inheritance | ClassRelationKind::Realization => Some("empty"),
+            ClassRelationKind::Composition => Some("diamond"),
+            ClassRelationKind::Aggregation => Some("odiamond"),
+            ClassRelationKind::Association | ClassRelationKind::Dependency => None,
+            ClassRelationKind::Link | ClassRelationKind::DashedLink => Some("none"),
+        };
+        if let Some(arrowhead) = arrowhead {
+            attrs.push(format!("arrowhead={arrowhead}"));
+        }
+        if relation.kind().is_dashed() {
+            attrs.push("style=dashed".to_owned());
+        }
+        if let Some(label) = relation.label() {
+            attrs.push(format!("label={}", quote(label)));
+        }
+        if let Some(cardinality) = relation.from_cardinality() {
+            attrs.push(format!("taillabel={}", quote(cardinality)));
+        }
+        if let Some(cardinality) = relation.to_cardinality() {
+            attrs.push(format!("headlabel={}", quote(cardinality)));
+        }
+        out.push_str(&format!(
+            "  {} -> {}{};\n",
+            quote(relation.from_class_id().as_str()),
+            quote(relation.to_class_id().as_str()),
+            attr_list(&attrs)
+        ));
+    }
+}
+
+fn attr_list(attrs: &[String]) -> String {
+    if attrs.is_empty() {
+        String::new()
+    } else {
+        format!(" [{}]", attrs.join(", "))
+    }
+}
+
+/// DOT double-quoted string.
+fn quote(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            _ => out.push(ch),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::export_dot;
+    use crate::format::mermaid::{parse_flowchart, parse_sequence_diagram, parse_state_diagram};
+    use crate::model::{Diagram, DiagramAst, DiagramId};
+
+    fn diagram(ast: DiagramAst) -> Diagram {
+        Diagram::new(DiagramId::new("d").expect("diagram id"), "Demo \"x\"", ast)
+    }
+
+    #[test]
+    fn exports_flowchart_clusters_and_edge_styles() {
+        let ast = parse_flowchart(
+            "flowchart LR\nsubgraph api [API]\nA[Start] -.-> B{Ok?}\nend\nB == yes ==> C(Done)\n",
+        )
+        .expect("parse");
+        let dot = export_dot(&diagram(DiagramAst::Flowchart(ast)));
+
+        assert!(dot.starts_with("digraph \"Demo \\\"x\\\"\" {\n"), "{dot}");
+        assert!(dot.contains("subgraph \"cluster_sg:api\" {\n    label=\"API\";\n"), "{dot}");
+        assert!(dot.contains("    \"n:A\" [label=\"Start\"];\n"), "{dot}");
+        assert!(dot.contains("    \"n:B\" [label=\"Ok?\", shape=diamond];\n"), "{dot}");
+        assert!(dot.contains("  \"n:C\" [label=\"Done\", style=rounded];\n"), "{dot}");
+        assert!(dot.contains("  \"n:A\" -> \"n:B\" [style=dashed];\n"), "{dot}");
+        assert!(dot.contains("  \"n:B\" -> \"n:C\" [label=\"yes\", penwidth=2];\n"), "{dot}");
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    fn exports_sequence_messages_in_order_and_state_pseudo_states() {
+        let ast = parse_sequence_diagram(
+            "sequenceDiagram\nparticipant A\nparticipant B\nA->>B: ask\nB-->>A: answer\n",
+        )
+        .expect("parse");
+        let dot = export_dot(&diagram(DiagramAst::Sequence(ast)));
+        assert!(dot.contains("{ rank=same; \"p:A\"; \"p:B\"; }"), "{dot}");
+        let ask = dot.find("label=\"1. ask\"").expect("first message");
+        let answer = dot.find("label=\"2. answer\", style=dashed").expect("second message");
+        assert!(ask < answer);
+
+        let ast = parse_state_diagram("stateDiagram-v2\n[*] --> Idle\nIdle --> [*] : stop\n")
+            .expect("parse");
+        let dot = export_dot(&diagram(DiagramAst::State(ast)));
+        assert!(dot.contains("[shape=point, width=0.2];"), "{dot}");
+        assert!(dot.contains("\"s:Idle\" [label=\"Idle\"];"), "{dot}");
+        assert!(dot.contains("[label=\"stop\"];"), "{dot}");
+    }
+}
@@ -0,0 +1,37 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! `%% nereid:id=<object-id>` anchor comments.
+//!
+//! Flowchart edge and sequence message ids are positional when parsed, so exports write an anchor
+//! comment above each edge/message line and the parsers give the object on the next statement
+//! line the anchored id. Mermaid itself treats the anchors as plain comments.
+
+use std::collections::BTreeSet;
+
+use crate::model::ObjectId;
+
+const ANCHOR_PREFIX: &str = "nereid:id=";
+
+/// The id named by an anchor comment line, if `trimmed` is one with a valid id.
+pub(super) fn parse_anchor(trimmed: &str) -> Option<ObjectId> {
+    let id = trimmed.strip_prefix("%%")?.trim_start().strip_prefix(ANCHOR_PREFIX)?;
+    ObjectId::new(id.trim()).ok()
+}
+
+/// Every id anchored in `input`; positional ids skip these so they never take an anchored one.
+pub fn anchored_ids(input: &str) -> BTreeSet<ObjectId> {
+    input.lines().filter_map(|line| parse_anchor(line.trim())).collect()
+}
+
+pub(super) fn push_anchor(out: &mut String, id: &ObjectId) {
+    out.push_str("%% ");
+    out.push_str(ANCHOR_PREFIX);
+    out.push_str(id.as_str());
+    out.push('\n');
+}
@@ -0,0 +1,645 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use super::ident::validate_mermaid_ident;
+pub use super::ident::MermaidIdentError;
+
+use crate::model::class_ast::{ClassAst, ClassMember, ClassNode, ClassRelation, ClassRelationKind};
+use crate::model::ids::ObjectId;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MermaidClassParseError {
+    MissingHeader,
+    UnsupportedSyntax { line_no: usize, line: String },
+    InvalidClassId { line_no: usize, name: String, reason: MermaidIdentError },
+    UnclosedClassBody { line_no: usize, class: String },
+}
+
+impl fmt::Display for MermaidClassParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingHeader => {
+                f.write_str("expected 'classDiagram' as the first non-empty line")
+            }
+            Self::UnsupportedSyntax { line_no, line } => {
+                write!(f, "unsupported Mermaid syntax on line {line_no}: {line}")
+            }
+            Self::InvalidClassId { line_no, name, reason } => {
+                write!(f, "invalid class id on line {line_no}: {name} ({reason})")
+            }
+            Self::UnclosedClassBody { line_no, class } => {
+                write!(f, "body of class '{class}' opened on line {line_no} is missing its '}}'")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MermaidClassParseError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MermaidClassExportError {
+    MissingClass { class_id: ObjectId },
+    InvalidClassId { class_id: ObjectId },
+    InvalidClassLabel { class_id: ObjectId, label: String },
+    InvalidMember { class_id: ObjectId, member: String },
+    InvalidNote { class_id: ObjectId, note: String },
+    InvalidRelationText { relation_id: ObjectId, text: String },
+}
+
+impl fmt::Display for MermaidClassExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingClass { class_id } => {
+                write!(f, "relation references missing class id: {class_id}")
+            }
+            Self::InvalidClassId { class_id } => write!(
+                f,
+                "cannot export class id as Mermaid identifier: {class_id} (expected 'c:<ident>')"
+            ),
+            Self::InvalidClassLabel { class_id, label } => write!(
+                f,
+                "cannot export class label for {class_id}: contains unsupported characters: {label:?}"
+            ),
+            Self::InvalidMember { class_id, member } => {
+                write!(f, "cannot export member of {class_id}: {member:?}")
+            }
+            Self::InvalidNote { class_id, note } => write!(
+                f,
+                "cannot export note for {class_id}: contains unsupported characters: {note:?}"
+            ),
+            Self::InvalidRelationText { relation_id, text } => write!(
+                f,
+                "cannot export relation text for {relation_id}: contains unsupported characters: {text:?}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MermaidClassExportError {}
+
+fn class_id_from_mermaid_id(name: &str) -> Result<ObjectId, MermaidIdentError> {
+    validate_mermaid_ident(name)?;
+    ObjectId::new(format!("c:{name}")).map_err(|_| MermaidIdentError::ContainsSlash)
+}
+
+fn relation_id_from_index(index: usize) -> ObjectId {
+    ObjectId::new(format!("r:{index:04}")).expect("valid relation id")
+}
+
+fn is_ignorable_line(trimmed: &str) -> bool {
+    trimmed.starts_with("direction ")
+        || trimmed.starts_with("style ")
+        || trimmed.starts_with("classDef ")
+        || trimmed.starts_with("cssClass ")
+}
+
+/// Declares the class `name` if needed.
+fn ensure_class(
+    ast: &mut ClassAst,
+    name: &str,
+    line_no: usize,
+) -> Result<ObjectId, MermaidClassParseError> {
+    let class_id = class_id_from_mermaid_id(name).map_err(|reason| {
+        MermaidClassParseError::InvalidClassId { line_no, name: name.to_owned(), reason }
+    })?;
+    ast.classes_mut()
+        .entry(class_id.clone())
+        .or_insert_with(|| ClassNode::new_with(name, Some(name.to_owned())));
+    Ok(class_id)
+}
+
+/// Byte index of the first `needle` outside double quotes.
+fn find_unquoted(text: &str, needles: &[&str]) -> Option<(usize, usize)> {
+    let mut in_quotes = false;
+    for (idx, ch) in text.char_indices() {
+        if ch == '"' {
+            in_quotes = !in_quotes;
+        } else if !in_quotes {
+            if let Some(needle) = needles.iter().position(|needle| text[idx..].starts_with(needle))
+            {
+                return Some((idx, needle));
+            }
+        }
+    }
+    None
+}
+
+fn is_marker_boundary(ch: Option<char>) -> bool {
+    ch.map_or(true, |ch| ch.is_whitespace() || ch == '"')
+}
+
+/// Splits `Name "card"` (or `"card" Name` when `card_first`) into the name and cardinality.
+fn split_cardinality(text: &str, card_first: bool) -> Option<(&str, Option<&str>)> {
+    let text = text.trim();
+    if card_first {
+        let Some(rest) = text.strip_prefix('"') else {
+            return Some((text, None));
+        };
+        let (card, name) = rest.split_once('"')?;
+        Some((name.trim(), Some(card)))
+    } else {
+        let Some(rest) = text.strip_suffix('"') else {
+            return Some((text, None));
+        };
+        let (name, card) = rest.rsplit_once('"')?;
+        Some((name.trim(), Some(card)))
+    }
+}
+
+/// Parses `<a> ["card"] <arrow> ["card"] <b> [: label]` into a relation.
+fn parse_relation(
+    ast: &mut ClassAst,
+    text: &str,
+    line_no: usize,
+) -> Result<Option<ClassRelation>, MermaidClassParseError> {
+    let unsupported =
+        || MermaidClassParseError::UnsupportedSyntax { line_no, line: text.to_owned() };
+
+    let (body, label) = match find_unquoted(text, &[":"]) {
+        Some((idx, _)) => {
+            (&text[..idx], Some(text[idx + 1..].trim()).filter(|label| !label.is_empty()))
+        }
+        None => (text, None),
+    };
+    let Some((link_idx, link)) = find_unquoted(body, &["--", ".."]) else {
+        return Ok(None);
+    };
+    let dashed = link == 1;
+    let (mut left, mut right) = (&body[..link_idx], &body[link_idx + 2..]);
+
+    let left_marker = ["<|", "*", "o", "<"]
+        .into_iter()
+        .find(|marker| {
+            left.strip_suffix(marker)
+                .is_some_and(|rest| is_marker_boundary(rest.chars().next_back()))
+        })
+        .unwrap_or("");
+    left = &left[..left.len() - left_marker.len()];
+    let right_marker = ["|>", "*", "o", ">"]
+        .into_iter()
+        .find(|marker| {
+            right.strip_prefix(marker).is_some_and(|rest| is_marker_boundary(rest.chars().next()))
+        })
+        .unwrap_or("");
+    right = &right[right_marker.len()..];
+
+    let (left_name, left_card) = split_cardinality(left, false).ok_or_else(unsupported)?;
+    let (right_name, right_card) = split_cardinality(right, true).ok_or_else(unsupported)?;
+    let marker_kind = |marker: &str| match (marker, dashed) {
+        ("<|" | "|>", false) => Some(ClassRelationKind::Inheritance),
+        ("<|" | "|>", true) => Some(ClassRelationKind::Realization),
+        ("*", false) => Some(ClassRelationKind::Composition),
+        ("o", false) => Some(ClassRelationKind::Aggregation),
+        ("<" | ">", false) => Some(ClassRelationKind::Association),
+        ("<" | ">", true) => Some(ClassRelationKind::Dependency),
+        _ => None,
+    };
+    // `to` is the marked end; unmarked links keep their written order.
+    let (kind, marker_on_left) = match (left_marker, right_marker) {
+        ("", "") if dashed => (ClassRelationKind::DashedLink, false),
+        ("", "") => (ClassRelationKind::Link, false),
+        (marker, "") => (marker_kind(marker).ok_or_else(unsupported)?, true),
+        ("", marker) => (marker_kind(marker).ok_or_else(unsupported)?, false),
+        _ => return Err(unsupported()),
+    };
+
+    let left_id = ensure_class(ast, left_name, line_no)?;
+    let right_id = ensure_class(ast, right_name, line_no)?;
+    let mut relation = if marker_on_left {
+        let mut relation = ClassRelation::new(right_id, left_id, kind);
+        relation.set_cardinalities(right_card, left_card);
+        relation
+    } else {
+        let mut relation = ClassRelation::new(left_id, right_id, kind);
+        relation.set_cardinalities(left_card, right_card);
+        relation
+    };
+    relation.set_label(label);
+    Ok(Some(relation))
+}
+
+/// Parses the rest of a `class` line: `<id>`, `<id>["<label>"]`, optionally followed by `{`.
+/// Returns the class id and whether a body was opened.
+fn parse_class_statement(
+    ast: &mut ClassAst,
+    rest: &str,
+    line_no: usize,
+    line: &str,
+) -> Result<(ObjectId, bool), MermaidClassParseError> {
+    let unsupported =
+        || MermaidClassParseError::UnsupportedSyntax { line_no, line: line.to_owned() };
+
+    let (rest, opens_body) = match rest.strip_suffix("{}") {
+        Some(rest) => (rest.trim(), false),
+        None => match rest.strip_suffix('{') {
+            Some(rest) => (rest.trim(), true),
+            None => (rest, false),
+        },
+    };
+    let (name, label) = match rest.split_once("[\"") {
+        Some((name, label)) => {
+            (name.trim(), Some(label.strip_suffix("\"]").ok_or_else(unsupported)?))
+        }
+        None => (rest, None),
+    };
+    let class_id = ensure_class(ast, name, line_no)?;
+    if let Some(label) = label {
+        if let Some(class) = ast.classes_mut().get_mut(&class_id) {
+            class.set_label(label.trim());
+        }
+    }
+    Ok((class_id, opens_body))
+}
+
+fn parse_annotation(text: &str) -> Option<(&str, &str)> {
+    let rest = text.strip_prefix("<<")?;
+    let (annotation, tail) = rest.split_once(">>")?;
+    Some((annotation.trim(), tail.trim()))
+}
+
+/// Parse a deliberately limited Mermaid `classDiagram` subset.
+///
+/// Supported:
+/// - `classDiagram` header
+/// - comment lines starting with `%%`; `direction`, `style`, `classDef` and `cssClass` lines
+///   (ignored)
+/// - class declarations: `class <id>`, `class <id>["<label>"]`, optionally opening a `{ ... }`
+///   body of member lines and `<<annotation>>` lines
+/// - members: `<id> : <member>`; annotations: `<<annotation>> <id>`
+/// - relations `<a> <arrow> <b>` with optional `"cardinality"` on either side and `: <label>`,
+///   where the arrow is `<|--`, `*--`, `o--`, `-->`, `<|..`, `..>`, `--` or `..` (or mirrored)
+/// - notes: `note for <id> "<text>"`, with `\n` for line breaks
+///
+/// Namespaces, generics, two-way relations and other Mermaid syntax are rejected with an error.
+pub fn parse_class_diagram(input: &str) -> Result<ClassAst, MermaidClassParseError> {
+    let mut ast = ClassAst::default();
+    let mut saw_header = false;
+    let mut relation_index = 0usize;
+    // (line_no, class id, name) of a class body being read.
+    let mut open_body: Option<(usize, ObjectId, String)> = None;
+
+    for (idx, raw_line) in input.lines().enumerate() {
+        let line_no = idx + 1;
+        let trimmed = raw_line.trim();
+
+        if trimmed.is_empty() || trimmed.starts_with("%%") {
+            continue;
+        }
+
+        if let Some((_, class_id, _)) = open_body.as_ref() {
+            let Some(class) = ast.classes_mut().get_mut(class_id) else {
+                continue;
+            };
+            if trimmed == "}" {
+                open_body = None;
+            } else if let Some((annotation, "")) = parse_annotation(trimmed) {
+                class.set_annotation(Some(annotation));
+            } else {
+                class.members_mut().push(ClassMember::new(trimmed));
+            }
+            continue;
+        }
+
+        if !saw_header {
+            if trimmed != "classDiagram" && trimmed != "classDiagram-v2" {
+                return Err(MermaidClassParseError::MissingHeader);
+            }
+            saw_header = true;
+            continue;
+        }
+
+        if is_ignorable_line(trimmed) {
+            continue;
+        }
+        let unsupported =
+            || MermaidClassParseError::UnsupportedSyntax { line_no, line: trimmed.to_owned() };
+
+        if let Some(rest) = trimmed.strip_prefix("class ") {
+            let (class_id, opens_body) =
+                parse_class_statement(&mut ast, rest.trim(), line_no, trimmed)?;
+            if opens_body {
+                let name = rest.trim().trim_end_matches('{').trim().to_owned();
+                open_body = Some((line_no, class_id, name));
+            }
+            continue;
+        }
+
+        if let Some(rest) = trimmed.strip_prefix("note for ") {
+            let (name, text) = rest.trim().split_once(' ').ok_or_else(unsupported)?;
+            let text = text.trim();
+            let text = text
+                .strip_prefix('"')
+                .and_then(|text| text.strip_suffix('"'))
+                .ok_or_else(unsupported)?;
+            let class_id = ensure_class(&mut ast, name, line_no)?;
+            if let Some(class) = ast.classes_mut().get_mut(&class_id) {
+                class.set_note(Some(text.replace("\\n", "\n")));
+            }
+            continue;
+        }
+
+        if let Some((annotation, name)) = parse_annotation(trimmed) {
+            let class_id = ensure_class(&mut ast, name, line_no)?;
+            if let Some(class) = ast.classes_mut().get_mut(&class_id) {
+                class.set_annotation(Some(annotation));
+            }
+            continue;
+        }
+
+        if let Some((name, member)) = trimmed.split_once(':') {
+            if validate_mermaid_ident(name.trim()).is_ok() {
+                let class_id = ensure_class(&mut ast, name.trim(), line_no)?;
+                let member = member.trim();
+                if let Some(class) = ast.classes_mut().get_mut(&class_id) {
+                    match parse_annotation(member) {
+                        Some((annotation, "")) => class.set_annotation(Some(annotation)),
+                        _ => class.members_mut().push(ClassMember::new(member)),
+                    }
+                }
+                continue;
+            }
+        }
+
+        match parse_relation(&mut ast, trimmed, line_no)? {
+            Some(relation) => {
+                ast.relations_mut().insert(relation_id_from_index(relation_index), relation);
+                relation_index += 1;
+            }
+            None => return Err(unsupported()),
+        }
+    }
+
+    if let Some((line_no, _, class)) = open_body {
+        return Err(MermaidClassParseError::UnclosedClassBody { line_no, class });
+    }
+    if !saw_header {
+        return Err(MermaidClassParseError::MissingHeader);
+    }
+    Ok(ast)
+}
+
+fn mermaid_id_for_class<'a>(class_id: &'a ObjectId, class: &'a ClassNode) -> Option<&'a str> {
+    class
+        .mermaid_id()
+        .or_else(|| class_id.as_str().strip_prefix("c:"))
+        .filter(|mermaid_id| validate_mermaid_ident(mermaid_id).is_ok())
+}
+
+fn export_endpoint<'a>(
+    ast: &'a ClassAst,
+    class_id: &'a ObjectId,
+) -> Result<&'a str, MermaidClassExportError> {
+    let class = ast
+        .classes()
+        .get(class_id)
+        .ok_or_else(|| MermaidClassExportError::MissingClass { class_id: class_id.clone() })?;
+    mermaid_id_for_class(class_id, class)
+        .ok_or_else(|| MermaidClassExportError::InvalidClassId { class_id: class_id.clone() })
+}
+
+fn relation_arrow(kind: ClassRelationKind) -> &'static str {
+    match kind {
+        ClassRelationKind::Inheritance => "<|--",
+        ClassRelationKind::Realization => "<|..",
+        ClassRelationKind::Composition => "*--",
+        ClassRelationKind::Aggregation => "o--",
+        ClassRelationKind::Association => "-->",
+        ClassRelationKind::Dependency => "..>",
+        ClassRelationKind::Link => "--",
+        ClassRelationKind::DashedLink => "..",
+    }
+}
+
+pub fn export_class_diagram(ast: &ClassAst) -> Result<String, MermaidClassExportError> {
+    let mut out = String::new();
+    out.push_str("classDiagram\n");
+
+    let mut connected = BTreeSet::new();
+    for relation in ast.relations().values() {
+        connected.insert(relation.from_class_id());
+        connected.insert(relation.to_class_id());
+    }
+
+    let mut notes = Vec::new();
+    for (class_id, class) in ast.classes() {
+        let Some(mermaid_id) = mermaid_id_for_class(class_id, class) else {
+            return Err(MermaidClassExportError::InvalidClassId { class_id: class_id.clone() });
+        };
+        let label = class.label();
+        if label != mermaid_id {
+            if label.contains(['"', '\n', ']']) {
+                return Err(MermaidClassExportError::InvalidClassLabel {
+                    class_id: class_id.clone(),
+                    label: label.to_owned(),
+                });
+            }
+            out.push_str(&format!("class {mermaid_id}[\"{label}\"]\n"));
+        }
+
+        if class.annotation().is_some() || !class.members().is_empty() {
+            out.push_str(&format!("class {mermaid_id} {{\n"));
+            if let Some(annotation) = class.annotation() {
+                out.push_str(&format!("    <<{annotation}>>\n"));
+            }
+            for member in class.members() {
+                let text = member.text();
+                if text.contains('\n') || text.trim() == "}" || text.trim().is_empty() {
+                    return Err(MermaidClassExportError::InvalidMember {
+                        class_id: class_id.clone(),
+                        member: text.to_owned(),
+                    });
+                }
+                out.push_str(&format!("    {}\n", text.trim()));
+            }
+            out.push_str("}\n");
+        } else if label == mermaid_id && !connected.contains(class_id) {
+            out.push_str(&format!("class {mermaid_id}\n"));
+        }
+
+        if let Some(note) = class.note() {
+            if note.contains('"') {
+                return Err(MermaidClassExportError::InvalidNote {
+                    class_id: class_id.clone(),
+                    note: note.to_owned(),
+                });
+            }
+            notes.push((mermaid_id, note.replace('\n', "\\n")));
+        }
+    }
+
+    for (relation_id, relation) in ast.relations() {
+        let invalid = |text: &str| MermaidClassExportError::InvalidRelationText {
+            relation_id: relation_id.clone(),
+            text: text.to_owned(),
+        };
+        let from = export_endpoint(ast, relation.from_class_id())?;
+        let to = export_endpoint(ast, relation.to_class_id())?;
+        let arrow = relation_arrow(relation.kind());
+        let ((left, left_card), (right, right_card)) = if relation.kind().is_structural() {
+            ((to, relation.to_cardinality()), (from, relation.from_cardinality()))
+        } else {
+            ((from, relation.from_cardinality()), (to, relation.to_cardinality()))
+        };
+
+        out.push_str(left);
+        for card in [left_card, right_card].into_iter().flatten() {
+            if card.contains(['"', '\n']) {
+                return Err(invalid(card));
+            }
+        }
+        if let Some(card) = left_card {
+            out.push_str(&format!(" \"{card}\""));
+        }
+        out.push_str(&format!(" {arrow} "));
+        if let Some(card) = right_card {
+            out.push_str(&format!("\"{card}\" "));
+        }
+        out.push_str(right);
+        if let Some(label) = relation.label() {
+            if label.contains('\n') {
+                return Err(invalid(label));
+            }
+            out.push_str(&format!(" : {label}"));
+        }
+        out.push('\n');
+    }
+
+    for (mermaid_id, note) in notes {
+        out.push_str(&format!("note for {mermaid_id} \"{note}\"\n"));
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export_class_diagram, parse_class_diagram, MermaidClassParseError};
+    use crate::model::class_ast::{ClassMemberKind, ClassRelationKind};
+    use crate::model::ObjectId;
+
+    fn oid(value: &str) -> ObjectId {
+        ObjectId::new(value).expect("object id")
+    }
+
+    #[test]
+    fn parses_classes_members_annotations_relations_and_notes() {
+        let ast = parse_class_diagram(
+            "classDiagram\n\
+             %% comment\n\
+             direction RL\n\
+             Animal <|-- Duck\n\
+             Car \"1\" *-- \"4\" Wheel : mounts\n\
+             Pond o-- Duck\n\
+             Duck --> Pond : swims in\n\
+             Duck ..> Food\n\
+             Shape <|.. Circle\n\
+             Duck -- Egg\n\
+             class Animal {\n\
+             \x20 <<abstract>>\n\
+             \x20 +int age\n\
+             \x20 +isMammal() bool\n\
+             }\n\
+             class Food[\"Duck food\"]\n\
+             <<interface>> Shape\n\
+             Duck : +swim()\n\
+             note for Duck \"quacks\\nloudly\"\n",
+        )
+        .expect("parse");
+
+        let animal = &ast.classes()[&oid("c:Animal")];
+        assert_eq!(animal.annotation(), Some("abstract"));
+        let kinds = animal.members().iter().map(|member| member.kind()).collect::<Vec<_>>();
+        assert_eq!(kinds, [ClassMemberKind::Attribute, ClassMemberKind::Method]);
+        assert_eq!(ast.classes()[&oid("c:Food")].label(), "Duck food");
+        assert_eq!(ast.classes()[&oid("c:Shape")].annotation(), Some("interface"));
+        let duck = &ast.classes()[&oid("c:Duck")];
+        assert_eq!(duck.members()[0].text(), "+swim()");
+        assert_eq!(duck.note(), Some("quacks\nloudly"));
+
+        let relation = |id: &str| {
+            let relation = &ast.relations()[&oid(id)];
+            (
+                relation.from_class_id().as_str().to_owned(),
+                relation.to_class_id().as_str().to_owned(),
+                relation.kind(),
+            )
+        };
+        let expect = |from: &str, to: &str, kind| (from.to_owned(), to.to_owned(), kind);
+        assert_eq!(
+            relation("r:0000"),
+            expect("c:Duck", "c:Animal", ClassRelationKind::Inheritance)
+        );
+        assert_eq!(relation("r:0001"), expect("c:Wheel", "c:Car", ClassRelationKind::Composition));
+        assert_eq!(relation("r:0002"), expect("c:Duck", "c:Pond", ClassRelationKind::Aggregation));
+        assert_eq!(relation("r:0003"), expect("c:Duck", "c:Pond", ClassRelationKind::Association));
+        assert_eq!(relation("r:0004"), expect("c:Duck", "c:Food", ClassRelationKind::Dependency));
+        assert_eq!(
+            relation("r:0005"),
+            expect("c:Circle", "c:Shape", ClassRelationKind::Realization)
+        );
+        assert_eq!(relation("r:0006"), expect("c:Duck", "c:Egg", ClassRelationKind::Link));
+
+        let wheel = &ast.relations()[&oid("r:0001")];
+        assert_eq!(wheel.from_cardinality(), Some("4"));
+        assert_eq!(wheel.to_cardinality(), Some("1"));
+        assert_eq!(wheel.label(), Some("mounts"));
+    }
+
+    #[test]
+    fn mirrored_arrows_parse_like_their_canonical_form() {
+        let canonical =
+            parse_class_diagram("classDiagram\nAnimal <|-- Duck\nCar *-- Wheel\n").expect("parse");
+        let mirrored =
+            parse_class_diagram("classDiagram\nDuck --|> Animal\nWheel --* Car\n").expect("parse");
+        assert_eq!(canonical, mirrored);
+    }
+
+    #[test]
+    fn export_round_trips() {
+        let input = "classDiagram\n\
+                     class Animal {\n\
+                     \x20   <<abstract>>\n\
+                     \x20   +int age\n\
+                     \x20   +isMammal() bool\n\
+                     }\n\
+                     class Food[\"Duck food\"]\n\
+                     class Lonely\n\
+                     Animal <|-- Duck\n\
+                     Car \"1\" *-- \"many\" Wheel : has\n\
+                     Duck ..> Food\n\
+                     Duck .. Egg\n\
+                     note for Duck \"quacks\\nloudly\"\n";
+        let ast = parse_class_diagram(input).expect("parse");
+        let exported = export_class_diagram(&ast).expect("export");
+        assert_eq!(parse_class_diagram(&exported).expect("reparse"), ast);
+        assert!(exported.contains("Car \"1\" *-- \"many\" Wheel : has\n"), "{exported}");
+    }
+
+    #[test]
+    fn rejects_namespaces_two_way_relations_and_unclosed_bodies() {
+        assert_eq!(
+            parse_class_diagram("classDiagram\nnamespace Zoo {\n}\n"),
+            Err(MermaidClassParseError::UnsupportedSyntax {
+                line_no: 2,
+                line: "namespace Zoo {".to_owned()
+            })
+        );
+        assert!(matches!(
+            parse_class_diagram("classDiagram\nA <|--|> B\n"),
+            Err(MermaidClassParseError::UnsupportedSyntax { line_no: 2, .. })
+        ));
+        assert_eq!(
+            parse_class_diagram("classDiagram\nclass A {\n+int x\n"),
+            Err(MermaidClassParseError::UnclosedClassBody { line_no: 2, class: "A".to_owned() })
+        );
+        assert_eq!(parse_class_diagram("flowchart\n"), Err(MermaidClassParseError::MissingHeader));
+    }
+}
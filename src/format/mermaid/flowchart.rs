@@ -6,12 +6,15 @@
 // This file is part of Nereid and is proprietary software.
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use std::collections::BTreeSet;
 use std::fmt;
 
+use super::anchor::{anchored_ids, parse_anchor, push_anchor};
 use super::ident::validate_mermaid_ident;
 pub use super::ident::MermaidIdentError;
 
-use crate::model::flow_ast::{FlowEdge, FlowNode, FlowchartAst};
+use crate::model::flow_ast::{FlowDirection, FlowEdge, FlowNode, FlowSubgraph, FlowchartAst};
+use crate::model::id_prefix_rule::{conventional_object_id, IdPrefixRule};
 use crate::model::ids::ObjectId;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -59,6 +62,15 @@ pub enum MermaidFlowchartParseError {
         existing_shape: String,
         new_shape: String,
     },
+    UnclosedSubgraph {
+        line_no: usize,
+        subgraph: String,
+    },
+    /// A subgraph id is used as a node, e.g. as an edge endpoint.
+    SubgraphUsedAsNode {
+        line_no: usize,
+        subgraph: String,
+    },
 }
 
 impl fmt::Display for MermaidFlowchartParseError {
@@ -113,6 +125,13 @@ impl fmt::Display for MermaidFlowchartParseError {
                 f,
                 "conflicting shape for node '{mermaid_id}' on line {line_no}: '{existing_shape}' vs '{new_shape}'"
             ),
+            Self::UnclosedSubgraph { line_no, subgraph } => {
+                write!(f, "subgraph '{subgraph}' opened on line {line_no} is missing its 'end'")
+            }
+            Self::SubgraphUsedAsNode { line_no, subgraph } => write!(
+                f,
+                "subgraph '{subgraph}' is used as a node on line {line_no} (edges to subgraphs are not supported)"
+            ),
         }
     }
 }
@@ -126,6 +145,7 @@ pub enum MermaidFlowchartExportError {
     InvalidNodeLabel { node_id: ObjectId, label: String },
     InvalidNodeShape { node_id: ObjectId, shape: String },
     InvalidEdgeLabel { edge_id: ObjectId, label: String },
+    InvalidSubgraphLabel { subgraph_id: ObjectId, label: String },
 }
 
 impl fmt::Display for MermaidFlowchartExportError {
@@ -150,22 +170,100 @@ impl fmt::Display for MermaidFlowchartExportError {
                 f,
                 "cannot export edge label for {edge_id}: contains unsupported characters: {label:?}"
             ),
+            Self::InvalidSubgraphLabel { subgraph_id, label } => write!(
+                f,
+                "cannot export subgraph label for {subgraph_id}: contains unsupported characters: {label:?}"
+            ),
         }
     }
 }
 
 impl std::error::Error for MermaidFlowchartExportError {}
 
-fn node_id_from_mermaid_id(name: &str) -> Result<ObjectId, MermaidIdentError> {
+fn node_id_from_mermaid_id(
+    name: &str,
+    id_rules: &[IdPrefixRule],
+) -> Result<ObjectId, MermaidIdentError> {
     validate_mermaid_ident(name)?;
     // Stable and human-friendly by default; long-term stability is carried in `.meta.json` sidecars.
-    ObjectId::new(format!("n:{name}")).map_err(|_| MermaidIdentError::ContainsSlash)
+    Ok(conventional_object_id(id_rules, ["flow", "node"], "n", name))
 }
 
 fn edge_id_from_index(index: usize) -> ObjectId {
     ObjectId::new(format!("e:{index:04}")).expect("valid edge id")
 }
 
+/// The anchored id for the next edge if it is still free, else the next positional `e:<index>`
+/// that is neither taken nor anchored elsewhere in the input.
+fn next_edge_id(
+    ast: &FlowchartAst,
+    anchor: Option<ObjectId>,
+    reserved: &BTreeSet<ObjectId>,
+    edge_index: &mut usize,
+) -> ObjectId {
+    if let Some(anchor) = anchor.filter(|id| !ast.edges().contains_key(id)) {
+        return anchor;
+    }
+    loop {
+        *edge_index += 1;
+        let edge_id = edge_id_from_index(*edge_index);
+        if !reserved.contains(&edge_id) && !ast.edges().contains_key(&edge_id) {
+            return edge_id;
+        }
+    }
+}
+
+/// Parses the rest of a `subgraph` line: `<id>`, `<id>[<label>]`, or a free-form title.
+///
+/// Titles that are not Mermaid identifiers get a positional `sg:<index>` id and no Mermaid id.
+fn parse_subgraph_header(
+    header: &str,
+    auto_index: &mut usize,
+    line_no: usize,
+) -> Result<(ObjectId, FlowSubgraph), MermaidFlowchartParseError> {
+    let header = header.trim();
+    let unsupported = || MermaidFlowchartParseError::UnsupportedSyntax {
+        line_no,
+        line: format!("subgraph {header}"),
+    };
+    if header.is_empty() {
+        return Err(unsupported());
+    }
+
+    let unquote = |text: &str| {
+        let text = text.trim();
+        text.strip_prefix('"').and_then(|text| text.strip_suffix('"')).unwrap_or(text).to_owned()
+    };
+    let (mermaid_id, label) = match header.split_once('[') {
+        Some((id_raw, rest)) if rest.ends_with(']') => {
+            let id_raw = id_raw.trim();
+            validate_mermaid_ident(id_raw).map_err(|reason| {
+                MermaidFlowchartParseError::InvalidNodeId {
+                    line_no,
+                    name: id_raw.to_owned(),
+                    reason,
+                }
+            })?;
+            (Some(id_raw.to_owned()), unquote(&rest[..rest.len() - 1]))
+        }
+        _ if validate_mermaid_ident(header).is_ok() => (Some(header.to_owned()), header.to_owned()),
+        _ => (None, unquote(header)),
+    };
+    if label.is_empty() {
+        return Err(unsupported());
+    }
+
+    let subgraph_id = match &mermaid_id {
+        Some(mermaid_id) => format!("sg:{mermaid_id}"),
+        None => {
+            *auto_index += 1;
+            format!("sg:{:04}", *auto_index)
+        }
+    };
+    let subgraph_id = ObjectId::new(subgraph_id).map_err(|_| unsupported())?;
+    Ok((subgraph_id, FlowSubgraph::new_with(label, mermaid_id)))
+}
+
 fn is_edge_op_start_char(ch: char) -> bool {
     matches!(ch, '<' | '-' | '=' | '.')
 }
@@ -343,8 +441,7 @@ fn is_comment_line(trimmed: &str) -> bool {
 }
 
 fn is_ignorable_line(trimmed: &str) -> bool {
-    trimmed.starts_with("subgraph ")
-        || trimmed == "end"
+    trimmed.starts_with("direction ")
         || trimmed.starts_with("style ")
         || trimmed.starts_with("class ")
         || trimmed.starts_with("classDef ")
@@ -453,14 +550,36 @@ fn parse_node_spec(token: &str, line_no: usize) -> Result<NodeSpec, MermaidFlowc
     })
 }
 
+/// Like [`upsert_node`], and puts a node that is not grouped yet into `subgraph`.
 fn ensure_node(
     ast: &mut FlowchartAst,
+    id_rules: &[IdPrefixRule],
+    subgraph: Option<&ObjectId>,
+    spec: NodeSpec,
+    line_no: usize,
+) -> Result<ObjectId, MermaidFlowchartParseError> {
+    if ast.subgraphs().values().any(|subgraph| subgraph.mermaid_id() == Some(&spec.mermaid_id)) {
+        return Err(MermaidFlowchartParseError::SubgraphUsedAsNode {
+            line_no,
+            subgraph: spec.mermaid_id,
+        });
+    }
+    let node_id = upsert_node(ast, id_rules, spec, line_no)?;
+    if let Some(subgraph_id) = subgraph {
+        ast.node_subgraphs_mut().entry(node_id.clone()).or_insert_with(|| subgraph_id.clone());
+    }
+    Ok(node_id)
+}
+
+fn upsert_node(
+    ast: &mut FlowchartAst,
+    id_rules: &[IdPrefixRule],
     spec: NodeSpec,
     line_no: usize,
 ) -> Result<ObjectId, MermaidFlowchartParseError> {
     let NodeSpec { mermaid_id, label, shape } = spec;
 
-    let node_id = node_id_from_mermaid_id(&mermaid_id).map_err(|reason| {
+    let node_id = node_id_from_mermaid_id(&mermaid_id, id_rules).map_err(|reason| {
         MermaidFlowchartParseError::InvalidNodeId { line_no, name: mermaid_id.clone(), reason }
     })?;
 
@@ -530,27 +649,51 @@ fn ensure_node(
 /// Parse a deliberately limited modern Mermaid `flowchart` subset.
 ///
 /// Supported:
-/// - `flowchart`/`graph` header with optional direction (`TD`, `TB`, `LR`, `RL`, `BT`) (ignored)
-/// - comment lines starting with `%%`
+/// - `flowchart`/`graph` header with optional direction (`TD`, `TB`, `LR`, `RL`, `BT`), kept on
+///   [`FlowchartAst::direction`]
+/// - comment lines starting with `%%`; a `%% nereid:id=<edge_id>` anchor gives the first edge on
+///   the next line that id (unless another edge already has it)
 /// - node declarations: `<id>`, `<id>[<label>]`, `<id>(<label>)`, `<id>{<label>}`
 /// - edges:
 ///   - supports a variety of Mermaid flowchart edge operators (normalized to `-->` internally)
 ///   - optional edge labels: `<lhs> -->|<label>| <rhs>` or `<lhs> -- <label> --> <rhs>`
 ///   - chain edges: `<a> --> <b> --> <c>`
 /// - `linkStyle` statements are accepted and preserved on export (rendering currently ignores them)
+/// - `subgraph <id>[<label>]` / `subgraph <title>` ... `end` blocks (nestable); a node belongs to
+///   the innermost subgraph it is first mentioned in (`direction` lines inside are ignored);
+///   edges connect nodes only, so a subgraph id used as a node or edge endpoint is rejected
 ///
 /// Unsupported Mermaid syntax is rejected with an actionable error.
 pub fn parse_flowchart(input: &str) -> Result<FlowchartAst, MermaidFlowchartParseError> {
+    parse_flowchart_with_id_rules(input, &[])
+}
+
+/// Like [`parse_flowchart`], with node ids following the session's id prefix conventions:
+/// under `flow/node => svc`, the Mermaid node `svc_orders` gets the id `svc:orders`.
+pub fn parse_flowchart_with_id_rules(
+    input: &str,
+    id_rules: &[IdPrefixRule],
+) -> Result<FlowchartAst, MermaidFlowchartParseError> {
     let mut ast = FlowchartAst::default();
     let mut saw_header = false;
     let mut edge_index = 0usize;
     let mut parsed_edges: Vec<ObjectId> = Vec::new();
     let mut pending_link_styles: Vec<(usize, Option<Vec<usize>>, String)> = Vec::new();
+    let mut subgraph_stack: Vec<(usize, ObjectId)> = Vec::new();
+    let mut subgraph_auto_index = 0usize;
+    let reserved_edge_ids = anchored_ids(input);
+    let mut pending_anchor = None::<ObjectId>;
 
     for (idx, raw_line) in input.lines().enumerate() {
         let line_no = idx + 1;
         let trimmed = raw_line.trim();
-        if trimmed.is_empty() || is_comment_line(trimmed) {
+        if trimmed.is_empty() {
+            continue;
+        }
+        if is_comment_line(trimmed) {
+            if saw_header {
+                pending_anchor = parse_anchor(trimmed).or(pending_anchor);
+            }
             continue;
         }
 
@@ -565,15 +708,13 @@ pub fn parse_flowchart(input: &str) -> Result<FlowchartAst, MermaidFlowchartPars
             }
 
             if let Some(direction) = parts.next() {
-                match direction {
-                    "TD" | "TB" | "LR" | "RL" | "BT" => {}
-                    _ => {
-                        return Err(MermaidFlowchartParseError::InvalidDirection {
-                            line_no,
-                            direction: direction.to_owned(),
-                        });
-                    }
-                }
+                let Some(direction) = FlowDirection::from_mermaid(direction) else {
+                    return Err(MermaidFlowchartParseError::InvalidDirection {
+                        line_no,
+                        direction: direction.to_owned(),
+                    });
+                };
+                ast.set_direction(Some(direction));
                 if parts.next().is_some() {
                     return Err(MermaidFlowchartParseError::UnsupportedSyntax {
                         line_no,
@@ -585,6 +726,8 @@ pub fn parse_flowchart(input: &str) -> Result<FlowchartAst, MermaidFlowchartPars
             saw_header = true;
             continue;
         }
+        // An anchor only names an edge written on the very next statement line.
+        let mut anchor = pending_anchor.take();
 
         if trimmed.split_whitespace().next() == Some("linkStyle") {
             let (targets, style) = parse_link_style_statement(trimmed, line_no)?;
@@ -592,9 +735,45 @@ pub fn parse_flowchart(input: &str) -> Result<FlowchartAst, MermaidFlowchartPars
             continue;
         }
 
+        if let Some(header) = trimmed
+            .strip_prefix("subgraph")
+            .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+        {
+            let (subgraph_id, mut subgraph) =
+                parse_subgraph_header(header, &mut subgraph_auto_index, line_no)?;
+            if ast.subgraphs().contains_key(&subgraph_id) {
+                return Err(MermaidFlowchartParseError::UnsupportedSyntax {
+                    line_no,
+                    line: trimmed.to_owned(),
+                });
+            }
+            if let Some(mermaid_id) = subgraph.mermaid_id().filter(|mermaid_id| {
+                ast.nodes().values().any(|node| node.mermaid_id() == Some(*mermaid_id))
+            }) {
+                return Err(MermaidFlowchartParseError::SubgraphUsedAsNode {
+                    line_no,
+                    subgraph: mermaid_id.to_owned(),
+                });
+            }
+            subgraph.set_parent(subgraph_stack.last().map(|(_, parent_id)| parent_id.clone()));
+            ast.subgraphs_mut().insert(subgraph_id.clone(), subgraph);
+            subgraph_stack.push((line_no, subgraph_id));
+            continue;
+        }
+        if trimmed == "end" {
+            if subgraph_stack.pop().is_none() {
+                return Err(MermaidFlowchartParseError::UnsupportedSyntax {
+                    line_no,
+                    line: trimmed.to_owned(),
+                });
+            }
+            continue;
+        }
+
         if is_ignorable_line(trimmed) {
             continue;
         }
+        let open_subgraph = subgraph_stack.last().map(|(_, subgraph_id)| subgraph_id);
 
         // Inline label syntax: `<lhs> -- <label> <op> <rhs>`
         if let Some((lhs_raw, op1, rest1)) = split_once_edge_operator(trimmed) {
@@ -613,11 +792,13 @@ pub fn parse_flowchart(input: &str) -> Result<FlowchartAst, MermaidFlowchartPars
                             EdgeDirection::Reverse => (rhs_spec, lhs_spec),
                         };
 
-                        let from_node_id = ensure_node(&mut ast, from_spec, line_no)?;
-                        let to_node_id = ensure_node(&mut ast, to_spec, line_no)?;
+                        let from_node_id =
+                            ensure_node(&mut ast, id_rules, open_subgraph, from_spec, line_no)?;
+                        let to_node_id =
+                            ensure_node(&mut ast, id_rules, open_subgraph, to_spec, line_no)?;
 
-                        edge_index += 1;
-                        let edge_id = edge_id_from_index(edge_index);
+                        let edge_id =
+                            next_edge_id(&ast, anchor.take(), &reserved_edge_ids, &mut edge_index);
                         parsed_edges.push(edge_id.clone());
                         let mut edge = FlowEdge::new_with(
                             from_node_id.clone(),
@@ -636,7 +817,7 @@ pub fn parse_flowchart(input: &str) -> Result<FlowchartAst, MermaidFlowchartPars
         // Parse simple edge or edge chain.
         let Some((first_raw, first_op, tail)) = split_once_edge_operator(trimmed) else {
             let node_spec = parse_node_spec(trimmed, line_no)?;
-            ensure_node(&mut ast, node_spec, line_no)?;
+            ensure_node(&mut ast, id_rules, open_subgraph, node_spec, line_no)?;
             continue;
         };
 
@@ -681,11 +862,10 @@ pub fn parse_flowchart(input: &str) -> Result<FlowchartAst, MermaidFlowchartPars
                 EdgeDirection::Reverse => (rhs_spec.clone(), current_spec.clone()),
             };
 
-            let from_node_id = ensure_node(&mut ast, from_spec, line_no)?;
-            let to_node_id = ensure_node(&mut ast, to_spec, line_no)?;
+            let from_node_id = ensure_node(&mut ast, id_rules, open_subgraph, from_spec, line_no)?;
+            let to_node_id = ensure_node(&mut ast, id_rules, open_subgraph, to_spec, line_no)?;
 
-            edge_index += 1;
-            let edge_id = edge_id_from_index(edge_index);
+            let edge_id = next_edge_id(&ast, anchor.take(), &reserved_edge_ids, &mut edge_index);
             parsed_edges.push(edge_id.clone());
             let mut edge =
                 FlowEdge::new_with(from_node_id.clone(), to_node_id.clone(), edge_label, None);
@@ -705,6 +885,12 @@ pub fn parse_flowchart(input: &str) -> Result<FlowchartAst, MermaidFlowchartPars
     if !saw_header {
         return Err(MermaidFlowchartParseError::MissingHeader);
     }
+    if let Some((line_no, subgraph_id)) = subgraph_stack.pop() {
+        return Err(MermaidFlowchartParseError::UnclosedSubgraph {
+            line_no,
+            subgraph: ast.subgraphs()[&subgraph_id].label().to_owned(),
+        });
+    }
 
     for (line_no, targets, style) in pending_link_styles {
         match targets {
@@ -732,7 +918,10 @@ pub fn parse_flowchart(input: &str) -> Result<FlowchartAst, MermaidFlowchartPars
     Ok(ast)
 }
 
-fn mermaid_id_for_node<'a>(node_id: &'a ObjectId, node: &'a FlowNode) -> Option<&'a str> {
+pub(crate) fn mermaid_id_for_node<'a>(
+    node_id: &'a ObjectId,
+    node: &'a FlowNode,
+) -> Option<&'a str> {
     node.mermaid_id().or_else(|| node_id.as_str().strip_prefix("n:"))
 }
 
@@ -760,63 +949,140 @@ fn validate_export_edge_operator(op: &str) -> bool {
     is_probable_edge_operator(op)
 }
 
+fn export_node_line(
+    out: &mut String,
+    node_id: &ObjectId,
+    node: &FlowNode,
+    indent: &str,
+) -> Result<(), MermaidFlowchartExportError> {
+    let Some(mermaid_id) = mermaid_id_for_node(node_id, node) else {
+        return Err(MermaidFlowchartExportError::InvalidNodeId { node_id: node_id.clone() });
+    };
+    validate_mermaid_ident(mermaid_id)
+        .map_err(|_| MermaidFlowchartExportError::InvalidNodeId { node_id: node_id.clone() })?;
+
+    let shape = NodeShape::from_model_shape(node.shape()).ok_or_else(|| {
+        MermaidFlowchartExportError::InvalidNodeShape {
+            node_id: node_id.clone(),
+            shape: node.shape().to_owned(),
+        }
+    })?;
+
+    let label = node.label();
+    let closing = match shape {
+        NodeShape::Rect => ']',
+        NodeShape::Round => ')',
+        NodeShape::Diamond => '}',
+    };
+    if !validate_export_node_label(label, closing) {
+        return Err(MermaidFlowchartExportError::InvalidNodeLabel {
+            node_id: node_id.clone(),
+            label: label.to_owned(),
+        });
+    }
+
+    out.push_str(indent);
+    out.push_str(mermaid_id);
+    match shape {
+        NodeShape::Rect => {
+            if label != mermaid_id {
+                out.push('[');
+                out.push_str(label);
+                out.push(']');
+            }
+        }
+        NodeShape::Round => {
+            out.push('(');
+            out.push_str(label);
+            out.push(')');
+        }
+        NodeShape::Diamond => {
+            out.push('{');
+            out.push_str(label);
+            out.push('}');
+        }
+    }
+    out.push('\n');
+    Ok(())
+}
+
+/// Emits `subgraph_id` with its member nodes and nested subgraphs, indented by `depth`.
+fn export_subgraph_block(
+    out: &mut String,
+    ast: &FlowchartAst,
+    subgraph_id: &ObjectId,
+    depth: usize,
+) -> Result<(), MermaidFlowchartExportError> {
+    let subgraph = &ast.subgraphs()[subgraph_id];
+    let indent = "  ".repeat(depth);
+    let label = subgraph.label();
+    if label.contains(['[', ']', '"', '\n', '\r']) {
+        return Err(MermaidFlowchartExportError::InvalidSubgraphLabel {
+            subgraph_id: subgraph_id.clone(),
+            label: label.to_owned(),
+        });
+    }
+
+    out.push_str(&indent);
+    out.push_str("subgraph ");
+    match subgraph.mermaid_id().filter(|id| validate_mermaid_ident(id).is_ok()) {
+        Some(mermaid_id) if mermaid_id == label => out.push_str(mermaid_id),
+        Some(mermaid_id) => {
+            out.push_str(mermaid_id);
+            out.push('[');
+            out.push_str(label);
+            out.push(']');
+        }
+        None => out.push_str(label),
+    }
+    out.push('\n');
+
+    let member_indent = "  ".repeat(depth + 1);
+    for (node_id, node) in ast.nodes() {
+        if ast.node_subgraph(node_id) == Some(subgraph_id) {
+            export_node_line(out, node_id, node, &member_indent)?;
+        }
+    }
+    for child_id in ast.subgraphs().keys() {
+        if ast.subgraphs()[child_id].parent() == Some(subgraph_id) {
+            export_subgraph_block(out, ast, child_id, depth + 1)?;
+        }
+    }
+
+    out.push_str(&indent);
+    out.push_str("end\n");
+    Ok(())
+}
+
 /// Export a `flowchart` to canonical Mermaid `.mmd`.
 ///
 /// Export is stable/deterministic:
-/// - Nodes are emitted in `ObjectId` order (typically lexical by `n:<id>`).
-/// - Edges are emitted sorted by `(from_node_id, to_node_id, edge_id)`.
+/// - Nodes are emitted in `ObjectId` order (typically lexical by `n:<id>`), grouped nodes inside
+///   their (nested) `subgraph` blocks after the ungrouped ones.
+/// - Edges are emitted sorted by `(from_node_id, to_node_id, edge_id)`, each preceded by a
+///   `%% nereid:id=<edge_id>` anchor comment so re-parsing the export keeps the edge ids.
 pub fn export_flowchart(ast: &FlowchartAst) -> Result<String, MermaidFlowchartExportError> {
     let mut out = String::new();
-    out.push_str("flowchart\n");
+    out.push_str("flowchart");
+    if let Some(direction) = ast.direction() {
+        out.push(' ');
+        out.push_str(direction.as_mermaid());
+    }
+    out.push('\n');
 
+    let grouped = |node_id: &ObjectId| {
+        ast.node_subgraph(node_id).is_some_and(|id| ast.subgraphs().contains_key(id))
+    };
     for (node_id, node) in ast.nodes() {
-        let Some(mermaid_id) = mermaid_id_for_node(node_id, node) else {
-            return Err(MermaidFlowchartExportError::InvalidNodeId { node_id: node_id.clone() });
-        };
-        validate_mermaid_ident(mermaid_id)
-            .map_err(|_| MermaidFlowchartExportError::InvalidNodeId { node_id: node_id.clone() })?;
-
-        let shape = NodeShape::from_model_shape(node.shape()).ok_or_else(|| {
-            MermaidFlowchartExportError::InvalidNodeShape {
-                node_id: node_id.clone(),
-                shape: node.shape().to_owned(),
-            }
-        })?;
-
-        let label = node.label();
-        let closing = match shape {
-            NodeShape::Rect => ']',
-            NodeShape::Round => ')',
-            NodeShape::Diamond => '}',
-        };
-        if !validate_export_node_label(label, closing) {
-            return Err(MermaidFlowchartExportError::InvalidNodeLabel {
-                node_id: node_id.clone(),
-                label: label.to_owned(),
-            });
+        if !grouped(node_id) {
+            export_node_line(&mut out, node_id, node, "")?;
         }
-
-        out.push_str(mermaid_id);
-        match shape {
-            NodeShape::Rect => {
-                if label != mermaid_id {
-                    out.push('[');
-                    out.push_str(label);
-                    out.push(']');
-                }
-            }
-            NodeShape::Round => {
-                out.push('(');
-                out.push_str(label);
-                out.push(')');
-            }
-            NodeShape::Diamond => {
-                out.push('{');
-                out.push_str(label);
-                out.push('}');
-            }
+    }
+    for (subgraph_id, subgraph) in ast.subgraphs() {
+        let nested = subgraph.parent().is_some_and(|id| ast.subgraphs().contains_key(id));
+        if !nested {
+            export_subgraph_block(&mut out, ast, subgraph_id, 0)?;
         }
-        out.push('\n');
     }
 
     let mut edges = ast.edges().iter().collect::<Vec<_>>();
@@ -855,6 +1121,7 @@ pub fn export_flowchart(ast: &FlowchartAst) -> Result<String, MermaidFlowchartEx
             MermaidFlowchartExportError::InvalidNodeId { node_id: to_node_id.clone() }
         })?;
 
+        push_anchor(&mut out, edge_id);
         out.push_str(from);
         out.push(' ');
         let op = edge.connector().filter(|op| validate_export_edge_operator(op)).unwrap_or("-->");
@@ -897,8 +1164,11 @@ pub fn export_flowchart(ast: &FlowchartAst) -> Result<String, MermaidFlowchartEx
 
 #[cfg(test)]
 mod tests {
-    use super::{export_flowchart, parse_flowchart, MermaidFlowchartParseError};
-    use crate::model::flow_ast::{FlowEdge, FlowNode, FlowchartAst};
+    use super::{
+        export_flowchart, parse_flowchart, parse_flowchart_with_id_rules,
+        MermaidFlowchartParseError,
+    };
+    use crate::model::flow_ast::{FlowDirection, FlowEdge, FlowNode, FlowchartAst};
     use crate::model::ObjectId;
     use std::collections::BTreeMap;
 
@@ -1130,6 +1400,8 @@ mod tests {
             D --- E
             E ---o F
             F ---x G
+            G <--> H
+            H <-.-> I
         "#;
 
         let ast1 = parse_flowchart(input).expect("parse 1");
@@ -1142,6 +1414,8 @@ mod tests {
                 ((String::from("D"), String::from("E")), Some(String::from("---"))),
                 ((String::from("E"), String::from("F")), Some(String::from("---o"))),
                 ((String::from("F"), String::from("G")), Some(String::from("---x"))),
+                ((String::from("G"), String::from("H")), Some(String::from("<-->"))),
+                ((String::from("H"), String::from("I")), Some(String::from("<-.->"))),
             ]
             .into_iter()
             .collect()
@@ -1174,9 +1448,226 @@ mod tests {
         assert!(out.contains("authz --> done"));
     }
 
+    #[test]
+    fn parses_nested_subgraphs_and_roundtrips_them() {
+        let input = r#"
+            flowchart LR
+            client[Client]
+            subgraph backend[Backend services]
+              direction TB
+              api[API] --> db
+              subgraph Data tier
+                db[Database]
+              end
+            end
+            client --> api
+        "#;
+        let ast = parse_flowchart(input).expect("parse");
+
+        let backend = ObjectId::new("sg:backend").expect("subgraph id");
+        let data_tier = ObjectId::new("sg:0001").expect("subgraph id");
+        assert_eq!(ast.subgraphs()[&backend].label(), "Backend services");
+        assert_eq!(ast.subgraphs()[&data_tier].label(), "Data tier");
+        assert_eq!(ast.subgraphs()[&data_tier].parent(), Some(&backend));
+
+        let node = |id: &str| ObjectId::new(id).expect("node id");
+        assert_eq!(ast.node_subgraph(&node("n:api")), Some(&backend));
+        // `db` is first mentioned by the edge inside `backend`, before the nested block.
+        assert_eq!(ast.node_subgraph(&node("n:db")), Some(&backend));
+        assert_eq!(ast.node_subgraph(&node("n:client")), None);
+        assert_eq!(
+            ast.subgraph_members(&backend).into_iter().collect::<Vec<_>>(),
+            vec![node("n:api"), node("n:db")]
+        );
+
+        let exported = export_flowchart(&ast).expect("export");
+        assert!(exported.contains("subgraph backend[Backend services]\n  api[API]\n"));
+        assert!(exported.contains("  subgraph Data tier\n  end\n"));
+        assert_eq!(parse_flowchart(&exported).expect("reparse"), ast);
+    }
+
+    #[test]
+    fn rejects_unbalanced_subgraph_blocks() {
+        let err = parse_flowchart("flowchart\nsubgraph one\nA --> B\n").unwrap_err();
+        assert_eq!(
+            err,
+            MermaidFlowchartParseError::UnclosedSubgraph { line_no: 2, subgraph: "one".to_owned() }
+        );
+        assert!(matches!(
+            parse_flowchart("flowchart\nA --> B\nend\n"),
+            Err(MermaidFlowchartParseError::UnsupportedSyntax { line_no: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn header_direction_is_kept_and_exported() {
+        for (header, direction, exported) in [
+            ("flowchart", None, "flowchart\n"),
+            ("flowchart TB", Some(FlowDirection::TopDown), "flowchart TD\n"),
+            ("graph BT", Some(FlowDirection::BottomUp), "flowchart BT\n"),
+            ("flowchart LR", Some(FlowDirection::LeftRight), "flowchart LR\n"),
+            ("flowchart RL", Some(FlowDirection::RightLeft), "flowchart RL\n"),
+        ] {
+            let ast = parse_flowchart(&format!("{header}\nA --> B\n")).expect("parse");
+            assert_eq!(ast.direction(), direction, "{header}");
+            let out = export_flowchart(&ast).expect("export");
+            assert!(out.starts_with(exported), "{header}: {out}");
+            assert_eq!(parse_flowchart(&out).expect("reparse"), ast);
+        }
+    }
+
+    #[test]
+    fn parses_deeply_nested_subgraphs_with_parent_chain() {
+        let input = "flowchart TB\nsubgraph outer\nsubgraph middle\nsubgraph inner\nA\nend\nB\nend\nend\nC\n";
+        let ast = parse_flowchart(input).expect("parse");
+
+        let id = |id: &str| ObjectId::new(id).expect("id");
+        assert_eq!(ast.subgraphs()[&id("sg:outer")].parent(), None);
+        assert_eq!(ast.subgraphs()[&id("sg:middle")].parent(), Some(&id("sg:outer")));
+        assert_eq!(ast.subgraphs()[&id("sg:inner")].parent(), Some(&id("sg:middle")));
+        assert_eq!(
+            ast.node_subgraph_chain(&id("n:A")),
+            vec![&id("sg:inner"), &id("sg:middle"), &id("sg:outer")]
+        );
+        assert_eq!(ast.node_subgraph(&id("n:B")), Some(&id("sg:middle")));
+        assert_eq!(ast.node_subgraph(&id("n:C")), None);
+        assert_eq!(
+            ast.subgraph_members(&id("sg:outer")).into_iter().collect::<Vec<_>>(),
+            vec![id("n:A"), id("n:B")]
+        );
+
+        let exported = export_flowchart(&ast).expect("export");
+        assert_eq!(
+            exported,
+            "flowchart TD\nC\nsubgraph outer\n  subgraph middle\n    B\n    subgraph inner\n      A\n    end\n  end\nend\n"
+        );
+        assert_eq!(parse_flowchart(&exported).expect("reparse"), ast);
+    }
+
+    #[test]
+    fn parses_subgraph_id_with_spaced_bracket_title() {
+        let ast = parse_flowchart("flowchart LR\nsubgraph api [Public API]\nA --> B\nend\n")
+            .expect("parse");
+
+        let api = ObjectId::new("sg:api").expect("subgraph id");
+        assert_eq!(ast.subgraphs()[&api].mermaid_id(), Some("api"));
+        assert_eq!(ast.subgraphs()[&api].label(), "Public API");
+        let exported = export_flowchart(&ast).expect("export");
+        assert!(exported.contains("subgraph api[Public API]\n"), "{exported}");
+
+        assert!(matches!(
+            parse_flowchart("flowchart LR\nsubgraph api []\nend\n"),
+            Err(MermaidFlowchartParseError::UnsupportedSyntax { line_no: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_edges_that_target_a_subgraph() {
+        let err = parse_flowchart("flowchart LR\nsubgraph api\nA\nend\nB --> api\n").unwrap_err();
+        assert_eq!(
+            err,
+            MermaidFlowchartParseError::SubgraphUsedAsNode {
+                line_no: 5,
+                subgraph: "api".to_owned()
+            }
+        );
+
+        // The subgraph may also be declared after the edge that mentions it.
+        let err = parse_flowchart("flowchart LR\nB --> api\nsubgraph api\nA\nend\n").unwrap_err();
+        assert_eq!(
+            err,
+            MermaidFlowchartParseError::SubgraphUsedAsNode {
+                line_no: 3,
+                subgraph: "api".to_owned()
+            }
+        );
+        assert!(err.to_string().contains("edges to subgraphs are not supported"));
+    }
+
+    #[test]
+    fn rejects_unclosed_nested_subgraph_at_its_opening_line() {
+        let err = parse_flowchart("flowchart\nsubgraph outer\nsubgraph inner [Inner]\nA\nend\n")
+            .unwrap_err();
+        assert_eq!(
+            err,
+            MermaidFlowchartParseError::UnclosedSubgraph {
+                line_no: 2,
+                subgraph: "outer".to_owned()
+            }
+        );
+
+        let err =
+            parse_flowchart("flowchart\nsubgraph outer\nsubgraph inner [Inner]\nA\n").unwrap_err();
+        assert_eq!(
+            err,
+            MermaidFlowchartParseError::UnclosedSubgraph {
+                line_no: 3,
+                subgraph: "Inner".to_owned()
+            }
+        );
+        assert_eq!(err.to_string(), "subgraph 'Inner' opened on line 3 is missing its 'end'");
+    }
+
     #[test]
     fn rejects_missing_header() {
         let err = parse_flowchart("A --> B\n").unwrap_err();
         assert_eq!(err, MermaidFlowchartParseError::MissingHeader);
     }
+
+    #[test]
+    fn id_prefix_rules_map_mermaid_node_ids_and_export_keeps_the_mermaid_ids() {
+        let rules = ["flow/node => svc|db".parse().expect("rule")];
+        let input = "flowchart LR\nsvc_orders[Orders] --> db_orders\norders --> svc_orders\n";
+        let ast = parse_flowchart_with_id_rules(input, &rules).expect("parse");
+
+        let node_ids = ast.nodes().keys().map(ObjectId::as_str).collect::<Vec<_>>();
+        assert_eq!(node_ids, vec!["db:orders", "n:orders", "svc:orders"]);
+        let exported = export_flowchart(&ast).expect("export");
+        assert!(exported.contains("\nsvc_orders[Orders]\n"), "{exported}");
+        assert!(exported.contains("\nsvc_orders --> db_orders\n"), "{exported}");
+    }
+
+    #[test]
+    fn anchor_comments_keep_edge_ids_across_an_edited_export() {
+        let mut ast = parse_flowchart("flowchart LR\nA --> B\nB --> C\nC --> D\n").expect("parse");
+        let edge = ast.edges_mut().remove(&ObjectId::new("e:0002").unwrap()).expect("edge");
+        ast.edges_mut().insert(ObjectId::new("e:keep").unwrap(), edge);
+
+        let exported = export_flowchart(&ast).expect("export");
+        assert_eq!(
+            exported,
+            "flowchart LR\nA\nB\nC\nD\n%% nereid:id=e:0001\nA --> B\n\
+             %% nereid:id=e:keep\nB --> C\n%% nereid:id=e:0003\nC --> D\n"
+        );
+        assert_eq!(parse_flowchart(&exported).expect("reparse"), ast);
+
+        // Drop the first edge, relabel one, and add two unanchored edges; an anchor above a node
+        // declaration reaches no edge, but its id stays reserved.
+        let edited = "flowchart LR\nA\nB\nC\n%% nereid:id=e:0002\nD\nD --> A\nA --> C\n\
+                      %% nereid:id=e:keep\nB -->|next| C\n%% nereid:id=e:0003\nC --> D\n";
+        let reparsed = parse_flowchart(edited).expect("parse edited");
+        let edges = reparsed
+            .edges()
+            .iter()
+            .map(|(edge_id, edge)| {
+                let from = edge.from_node_id().as_str();
+                let to = edge.to_node_id().as_str();
+                (edge_id.as_str(), format!("{from} -> {to}"), edge.label().map(str::to_owned))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            edges,
+            vec![
+                ("e:0001", "n:D -> n:A".to_owned(), None),
+                ("e:0003", "n:C -> n:D".to_owned(), None),
+                ("e:0004", "n:A -> n:C".to_owned(), None),
+                ("e:keep", "n:B -> n:C".to_owned(), Some("next".to_owned())),
+            ]
+        );
+
+        // An id already taken by an earlier edge falls back to a positional one.
+        let duplicated = "flowchart\n%% nereid:id=e:x\nA --> B\n%% nereid:id=e:x\nB --> C\n";
+        let ast = parse_flowchart(duplicated).expect("parse duplicated");
+        assert_eq!(ast.edges().keys().map(ObjectId::as_str).collect::<Vec<_>>(), ["e:0001", "e:x"]);
+    }
 }
@@ -8,15 +8,49 @@
 
 //! Mermaid-ish parsing and exporting for supported diagram kinds.
 
+use crate::model::DiagramKind;
+
+mod anchor;
+pub mod class;
 pub mod flowchart;
 mod ident;
 pub mod sequence;
+pub mod state;
+
+pub use anchor::anchored_ids;
+
+pub use class::{
+    export_class_diagram, parse_class_diagram, MermaidClassExportError, MermaidClassParseError,
+};
 
 pub use sequence::{
-    export_sequence_diagram, parse_sequence_diagram, MermaidSequenceExportError,
-    MermaidSequenceParseError,
+    export_sequence_diagram, parse_sequence_diagram, parse_sequence_diagram_with_id_rules,
+    MermaidSequenceExportError, MermaidSequenceParseError,
+};
+
+pub use state::{
+    export_state_diagram, parse_state_diagram, MermaidStateExportError, MermaidStateParseError,
 };
 
 pub use flowchart::{
-    export_flowchart, parse_flowchart, MermaidFlowchartExportError, MermaidFlowchartParseError,
+    export_flowchart, parse_flowchart, parse_flowchart_with_id_rules, MermaidFlowchartExportError,
+    MermaidFlowchartParseError,
 };
+
+/// The diagram kind named by the first line that is neither blank nor a `%%` comment, if it is a
+/// supported one.
+pub fn detect_diagram_kind(input: &str) -> Option<DiagramKind> {
+    let header =
+        input.lines().map(str::trim).find(|line| !line.is_empty() && !line.starts_with("%%"))?;
+    if header.starts_with("sequenceDiagram") {
+        Some(DiagramKind::Sequence)
+    } else if header.starts_with("flowchart") || header.starts_with("graph") {
+        Some(DiagramKind::Flowchart)
+    } else if header.starts_with("stateDiagram") {
+        Some(DiagramKind::State)
+    } else if header.starts_with("classDiagram") {
+        Some(DiagramKind::Class)
+    } else {
+        None
+    }
+}
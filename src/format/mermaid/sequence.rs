@@ -9,13 +9,16 @@
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 
+use super::anchor::{anchored_ids, parse_anchor, push_anchor};
 use super::ident::validate_mermaid_ident;
 pub use super::ident::MermaidIdentError;
 
+use crate::model::id_prefix_rule::{conventional_object_id, IdPrefixRule};
 use crate::model::ids::ObjectId;
 use crate::model::seq_ast::{
-    SequenceAst, SequenceBlock, SequenceBlockKind, SequenceMessage, SequenceMessageKind,
-    SequenceParticipant, SequenceSection, SequenceSectionKind,
+    SequenceActivation, SequenceAst, SequenceBlock, SequenceBlockKind, SequenceGroup,
+    SequenceMessage, SequenceMessageBoundary, SequenceMessageKind, SequenceNote,
+    SequenceNotePlacement, SequenceParticipant, SequenceSection, SequenceSectionKind,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -27,12 +30,17 @@ pub enum MermaidSequenceParseError {
     InvalidMessageLine { line_no: usize, line: String },
     InvalidMessageParticipant { line_no: usize, name: String, reason: MermaidIdentError },
     MissingMessageText { line_no: usize, line: String },
+    InvalidNoteLine { line_no: usize, line: String },
+    InvalidBoxLine { line_no: usize, line: String },
+    UnclosedBox { opened_on_line_no: usize },
     UnmatchedEnd { line_no: usize },
     ElseOutsideAlt { line_no: usize, line: String },
     AndOutsidePar { line_no: usize, line: String },
     BlockNestingTooDeep { line_no: usize, max_depth: usize },
     EmptyBlockSection { line_no: usize, section_id: ObjectId },
     UnclosedBlock { opened_on_line_no: usize, block_id: ObjectId, kind: SequenceBlockKind },
+    InvalidActivationLine { line_no: usize, line: String },
+    InactiveParticipant { line_no: usize, name: String },
 }
 
 impl fmt::Display for MermaidSequenceParseError {
@@ -73,6 +81,18 @@ impl fmt::Display for MermaidSequenceParseError {
             Self::MissingMessageText { line_no, line } => {
                 write!(f, "missing message text on line {line_no}: {line}")
             }
+            Self::InvalidNoteLine { line_no, line } => write!(
+                f,
+                "invalid note syntax on line {line_no}: {line} (expected 'Note left of|right of|over <participant>[,<participant>]: <text>')"
+            ),
+            Self::InvalidBoxLine { line_no, line } => write!(
+                f,
+                "invalid line on line {line_no}: {line} ('box' is top-level only and may only contain participant declarations)"
+            ),
+            Self::UnclosedBox { opened_on_line_no } => write!(
+                f,
+                "unclosed 'box': missing 'end' for box opened on line {opened_on_line_no}"
+            ),
             Self::UnmatchedEnd { line_no } => {
                 write!(
                     f,
@@ -107,6 +127,14 @@ impl fmt::Display for MermaidSequenceParseError {
                 "unclosed '{}' block {block_id}: missing 'end' for block opened on line {opened_on_line_no}",
                 block_kind_keyword(*kind)
             ),
+            Self::InvalidActivationLine { line_no, line } => write!(
+                f,
+                "invalid activation on line {line_no}: {line} (expected 'activate|deactivate <participant>')"
+            ),
+            Self::InactiveParticipant { line_no, name } => write!(
+                f,
+                "invalid 'deactivate' on line {line_no}: {name} is not active"
+            ),
         }
     }
 }
@@ -118,6 +146,7 @@ pub enum MermaidSequenceExportError {
     MissingParticipant { participant_id: ObjectId },
     InvalidMessageText { message_id: ObjectId, text: String },
     InvalidBlockMembership { block_id: ObjectId, reason: String },
+    InvalidNote { note_id: ObjectId, reason: String },
 }
 
 impl fmt::Display for MermaidSequenceExportError {
@@ -133,22 +162,272 @@ impl fmt::Display for MermaidSequenceExportError {
             Self::InvalidBlockMembership { block_id, reason } => {
                 write!(f, "cannot export block {block_id}: {reason}")
             }
+            Self::InvalidNote { note_id, reason } => {
+                write!(f, "cannot export note {note_id}: {reason}")
+            }
         }
     }
 }
 
 impl std::error::Error for MermaidSequenceExportError {}
 
-fn participant_id_from_mermaid_name(name: &str) -> Result<ObjectId, MermaidIdentError> {
+fn participant_id_from_mermaid_name(
+    name: &str,
+    id_rules: &[IdPrefixRule],
+) -> Result<ObjectId, MermaidIdentError> {
     validate_mermaid_ident(name)?;
     // Stable and human-friendly by default; long-term stability is carried in `.meta.json` sidecars.
-    ObjectId::new(format!("p:{name}")).map_err(|_| MermaidIdentError::ContainsSlash)
+    Ok(conventional_object_id(id_rules, ["seq", "participant"], "p", name))
+}
+
+const BOUNDARY_ANNOTATION_PREFIX: &str = "nereid:boundary=";
+
+/// The boundary named by a `%% nereid:boundary=found|lost` comment line.
+fn parse_boundary_annotation(trimmed: &str) -> Option<SequenceMessageBoundary> {
+    let value =
+        trimmed.strip_prefix("%%")?.trim_start().strip_prefix(BOUNDARY_ANNOTATION_PREFIX)?;
+    match value.trim() {
+        "found" => Some(SequenceMessageBoundary::Found),
+        "lost" => Some(SequenceMessageBoundary::Lost),
+        _ => None,
+    }
+}
+
+fn push_boundary_annotation(out: &mut String, boundary: SequenceMessageBoundary) {
+    out.push_str("%% ");
+    out.push_str(BOUNDARY_ANNOTATION_PREFIX);
+    out.push_str(match boundary {
+        SequenceMessageBoundary::Found => "found",
+        SequenceMessageBoundary::Lost => "lost",
+    });
+    out.push('\n');
 }
 
 fn message_id_from_index(index: usize) -> ObjectId {
     ObjectId::new(format!("m:{index:04}")).expect("valid message id")
 }
 
+fn note_id_from_index(index: usize) -> ObjectId {
+    ObjectId::new(format!("note:{index:04}")).expect("valid note id")
+}
+
+/// CSS named colors (lowercase, sorted for binary search).
+const CSS_NAMED_COLORS: &[&str] = &[
+    "aliceblue",
+    "antiquewhite",
+    "aqua",
+    "aquamarine",
+    "azure",
+    "beige",
+    "bisque",
+    "black",
+    "blanchedalmond",
+    "blue",
+    "blueviolet",
+    "brown",
+    "burlywood",
+    "cadetblue",
+    "chartreuse",
+    "chocolate",
+    "coral",
+    "cornflowerblue",
+    "cornsilk",
+    "crimson",
+    "cyan",
+    "darkblue",
+    "darkcyan",
+    "darkgoldenrod",
+    "darkgray",
+    "darkgreen",
+    "darkgrey",
+    "darkkhaki",
+    "darkmagenta",
+    "darkolivegreen",
+    "darkorange",
+    "darkorchid",
+    "darkred",
+    "darksalmon",
+    "darkseagreen",
+    "darkslateblue",
+    "darkslategray",
+    "darkslategrey",
+    "darkturquoise",
+    "darkviolet",
+    "deeppink",
+    "deepskyblue",
+    "dimgray",
+    "dimgrey",
+    "dodgerblue",
+    "firebrick",
+    "floralwhite",
+    "forestgreen",
+    "fuchsia",
+    "gainsboro",
+    "ghostwhite",
+    "gold",
+    "goldenrod",
+    "gray",
+    "green",
+    "greenyellow",
+    "grey",
+    "honeydew",
+    "hotpink",
+    "indianred",
+    "indigo",
+    "ivory",
+    "khaki",
+    "lavender",
+    "lavenderblush",
+    "lawngreen",
+    "lemonchiffon",
+    "lightblue",
+    "lightcoral",
+    "lightcyan",
+    "lightgoldenrodyellow",
+    "lightgray",
+    "lightgreen",
+    "lightgrey",
+    "lightpink",
+    "lightsalmon",
+    "lightseagreen",
+    "lightskyblue",
+    "lightslategray",
+    "lightslategrey",
+    "lightsteelblue",
+    "lightyellow",
+    "lime",
+    "limegreen",
+    "linen",
+    "magenta",
+    "maroon",
+    "mediumaquamarine",
+    "mediumblue",
+    "mediumorchid",
+    "mediumpurple",
+    "mediumseagreen",
+    "mediumslateblue",
+    "mediumspringgreen",
+    "mediumturquoise",
+    "mediumvioletred",
+    "midnightblue",
+    "mintcream",
+    "mistyrose",
+    "moccasin",
+    "navajowhite",
+    "navy",
+    "oldlace",
+    "olive",
+    "olivedrab",
+    "orange",
+    "orangered",
+    "orchid",
+    "palegoldenrod",
+    "palegreen",
+    "paleturquoise",
+    "palevioletred",
+    "papayawhip",
+    "peachpuff",
+    "peru",
+    "pink",
+    "plum",
+    "powderblue",
+    "purple",
+    "rebeccapurple",
+    "red",
+    "rosybrown",
+    "royalblue",
+    "saddlebrown",
+    "salmon",
+    "sandybrown",
+    "seagreen",
+    "seashell",
+    "sienna",
+    "silver",
+    "skyblue",
+    "slateblue",
+    "slategray",
+    "slategrey",
+    "snow",
+    "springgreen",
+    "steelblue",
+    "tan",
+    "teal",
+    "thistle",
+    "tomato",
+    "turquoise",
+    "violet",
+    "wheat",
+    "white",
+    "whitesmoke",
+    "yellow",
+    "yellowgreen",
+];
+
+/// Splits a `box` header into an optional leading color and an optional title.
+///
+/// Recognized colors are `transparent`, CSS named colors (any case), `#hex`, and `rgb(...)`/
+/// `rgba(...)`/`hsl(...)`/`hsla(...)`; any other first word is part of the title.
+fn split_box_header(header: &str) -> (Option<String>, Option<String>) {
+    let header = header.trim();
+    let is_function_color =
+        ["rgb(", "rgba(", "hsl(", "hsla("].iter().any(|prefix| header.starts_with(prefix));
+    let color_len = if is_function_color {
+        header.find(')').map(|idx| idx + 1)
+    } else {
+        let first = header.split_whitespace().next().unwrap_or_default();
+        let is_named_color = first == "transparent"
+            || CSS_NAMED_COLORS.binary_search(&first.to_ascii_lowercase().as_str()).is_ok();
+        (is_named_color || (first.starts_with('#') && first.len() > 1)).then_some(first.len())
+    };
+
+    let (color, title) = match color_len {
+        Some(len) => (Some(header[..len].to_owned()), header[len..].trim()),
+        None => (None, header),
+    };
+    (color, (!title.is_empty()).then(|| title.to_owned()))
+}
+
+#[derive(Debug, Clone)]
+struct OpenGroup {
+    opened_on_line_no: usize,
+    color: Option<String>,
+    title: Option<String>,
+    participant_ids: Vec<ObjectId>,
+}
+
+fn note_placement_keyword(placement: SequenceNotePlacement) -> &'static str {
+    match placement {
+        SequenceNotePlacement::LeftOf => "left of",
+        SequenceNotePlacement::RightOf => "right of",
+        SequenceNotePlacement::Over => "over",
+    }
+}
+
+/// Parses the part of a `Note` line after the keyword into placement, anchor names, and text.
+fn parse_note_body(body: &str) -> Option<(SequenceNotePlacement, Vec<&str>, &str)> {
+    let body = body.trim_start();
+    let (placement, rest) = [
+        SequenceNotePlacement::LeftOf,
+        SequenceNotePlacement::RightOf,
+        SequenceNotePlacement::Over,
+    ]
+    .into_iter()
+    .find_map(|placement| {
+        let rest = body.strip_prefix(note_placement_keyword(placement))?;
+        rest.starts_with(char::is_whitespace).then_some((placement, rest))
+    })?;
+
+    let (anchors, text) = rest.split_once(':')?;
+    let anchors = anchors.split(',').map(str::trim).collect::<Vec<_>>();
+    let max_anchors = if placement == SequenceNotePlacement::Over { 2 } else { 1 };
+    if anchors.len() > max_anchors || anchors.iter().any(|name| name.is_empty()) {
+        return None;
+    }
+
+    let text = text.trim();
+    (!text.is_empty()).then_some((placement, anchors, text))
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Arrow {
     Sync,
@@ -224,10 +503,11 @@ fn is_comment_line(trimmed: &str) -> bool {
 
 fn ensure_participant(
     participants: &mut BTreeMap<ObjectId, SequenceParticipant>,
+    id_rules: &[IdPrefixRule],
     name: &str,
     line_no: usize,
 ) -> Result<ObjectId, MermaidSequenceParseError> {
-    let participant_id = participant_id_from_mermaid_name(name).map_err(|reason| {
+    let participant_id = participant_id_from_mermaid_name(name, id_rules).map_err(|reason| {
         MermaidSequenceParseError::InvalidMessageParticipant {
             line_no,
             name: name.to_owned(),
@@ -242,6 +522,44 @@ fn ensure_participant(
     Ok(participant_id)
 }
 
+/// Starts an activation bar of `participant_id` at `start_message_id` (`None`: before the first
+/// message).
+fn open_activation(
+    ast: &mut SequenceAst,
+    activation_index: usize,
+    participant_id: ObjectId,
+    start_message_id: Option<ObjectId>,
+) {
+    ast.activations_mut().push(SequenceActivation::new(
+        SequenceActivation::make_activation_id(activation_index),
+        participant_id,
+        start_message_id,
+        None,
+    ));
+}
+
+/// Ends the innermost open activation of `participant_id` at `end_message_id`; returns `false`
+/// when the participant is not active. An activation ended before any message has no extent and
+/// is dropped.
+fn close_activation(
+    ast: &mut SequenceAst,
+    participant_id: &ObjectId,
+    end_message_id: Option<ObjectId>,
+) -> bool {
+    let Some(idx) = ast.activations().iter().rposition(|activation| {
+        activation.participant_id() == participant_id && activation.end_message_id().is_none()
+    }) else {
+        return false;
+    };
+    match end_message_id {
+        Some(end_message_id) => ast.activations_mut()[idx].set_end_message_id(Some(end_message_id)),
+        None => {
+            ast.activations_mut().remove(idx);
+        }
+    }
+    true
+}
+
 const MAX_BLOCK_NEST_DEPTH: usize = 8;
 
 #[derive(Debug, Clone)]
@@ -334,23 +652,51 @@ fn keyword_header(trimmed: &str, keyword: &str) -> Option<String> {
 /// - `participant <name>` (identifier must not contain whitespace or `/`)
 /// - `<from><arrow><to>: <text>` where `<arrow>` is one of Mermaid's documented message arrows
 ///   (normalized internally; export uses `->>`, `-)`, `-->>`)
+/// - `%% nereid:id=<message_id>` anchor comments, giving a message on the next line that id (unless
+///   another message already has it)
+/// - `%% nereid:boundary=found|lost` comments, turning a self-message on the next line into a
+///   found or lost message; other `%%` comments are ignored
 /// - `alt [header...]` / `opt [header...]` / `loop [header...]` / `par [header...]`
 /// - `else [header...]` (only inside `alt`)
 /// - `and [header...]` (only inside `par`)
 /// - `end` (closes the most recently opened block)
+/// - `box [color] [title]` ... `end` around `participant` declarations (top-level only)
+/// - `Note left of <p>: <text>` / `Note right of <p>: <text>` / `Note over <p>[,<q>]: <text>`
+///   (notes are positioned on the message timeline but never belong to block sections)
 ///
 /// All other Mermaid syntax is rejected with an actionable error.
 pub fn parse_sequence_diagram(input: &str) -> Result<SequenceAst, MermaidSequenceParseError> {
+    parse_sequence_diagram_with_id_rules(input, &[])
+}
+
+/// Like [`parse_sequence_diagram`], with participant ids following the session's id prefix
+/// conventions: under `seq/participant => svc`, `svc_api` gets the id `svc:api`.
+pub fn parse_sequence_diagram_with_id_rules(
+    input: &str,
+    id_rules: &[IdPrefixRule],
+) -> Result<SequenceAst, MermaidSequenceParseError> {
     let mut ast = SequenceAst::default();
 
     let mut saw_header = false;
-    let mut used_message_ids = BTreeSet::<ObjectId>::new();
     let mut open_blocks = Vec::<OpenBlock>::new();
     let mut next_block_index = 0usize;
+    let mut notes_since_last_message = 0usize;
+    let mut open_group = None::<OpenGroup>;
+    let mut next_activation_index = 0usize;
+    let mut used_message_ids = anchored_ids(input);
+    let mut pending_anchor = None::<ObjectId>;
+    let mut pending_boundary = None::<SequenceMessageBoundary>;
     for (idx, raw_line) in input.lines().enumerate() {
         let line_no = idx + 1;
         let trimmed = raw_line.trim();
-        if trimmed.is_empty() || is_comment_line(trimmed) {
+        if trimmed.is_empty() {
+            continue;
+        }
+        if is_comment_line(trimmed) {
+            if saw_header {
+                pending_anchor = parse_anchor(trimmed).or(pending_anchor);
+                pending_boundary = parse_boundary_annotation(trimmed).or(pending_boundary);
+            }
             continue;
         }
 
@@ -361,8 +707,41 @@ pub fn parse_sequence_diagram(input: &str) -> Result<SequenceAst, MermaidSequenc
             }
             return Err(MermaidSequenceParseError::MissingHeader);
         }
+        // An anchor only names a message written on the very next statement line.
+        let anchor = pending_anchor.take();
+        let boundary = pending_boundary.take();
 
         if let Some(keyword) = trimmed.split_whitespace().next() {
+            let invalid_box_line =
+                || MermaidSequenceParseError::InvalidBoxLine { line_no, line: trimmed.to_owned() };
+            if keyword == "box" {
+                if open_group.is_some() || !open_blocks.is_empty() {
+                    return Err(invalid_box_line());
+                }
+                let (color, title) = split_box_header(&trimmed[keyword.len()..]);
+                open_group = Some(OpenGroup {
+                    opened_on_line_no: line_no,
+                    color,
+                    title,
+                    participant_ids: Vec::new(),
+                });
+                continue;
+            }
+            if open_group.is_some() && trimmed == "end" {
+                let group = open_group.take().expect("present");
+                let group_id = SequenceGroup::make_group_id(ast.groups().len() + 1);
+                ast.groups_mut().push(SequenceGroup::new(
+                    group_id,
+                    group.title,
+                    group.color,
+                    group.participant_ids,
+                ));
+                continue;
+            }
+            if open_group.is_some() && keyword != "participant" {
+                return Err(invalid_box_line());
+            }
+
             if keyword == "participant" {
                 let mut parts = trimmed.split_whitespace();
                 parts.next(); // keyword
@@ -387,13 +766,19 @@ pub fn parse_sequence_diagram(input: &str) -> Result<SequenceAst, MermaidSequenc
                     }
                 })?;
 
-                let participant_id = participant_id_from_mermaid_name(name).map_err(|reason| {
-                    MermaidSequenceParseError::InvalidParticipantName {
-                        line_no,
-                        name: name.to_owned(),
-                        reason,
+                let participant_id =
+                    participant_id_from_mermaid_name(name, id_rules).map_err(|reason| {
+                        MermaidSequenceParseError::InvalidParticipantName {
+                            line_no,
+                            name: name.to_owned(),
+                            reason,
+                        }
+                    })?;
+                if let Some(group) = open_group.as_mut() {
+                    if !group.participant_ids.contains(&participant_id) {
+                        group.participant_ids.push(participant_id.clone());
                     }
-                })?;
+                }
                 ast.participants_mut()
                     .entry(participant_id)
                     .or_insert_with(|| SequenceParticipant::new(name.to_owned()));
@@ -493,6 +878,85 @@ pub fn parse_sequence_diagram(input: &str) -> Result<SequenceAst, MermaidSequenc
                     }
                     continue;
                 }
+                "Note" | "note" => {
+                    let invalid_note = || MermaidSequenceParseError::InvalidNoteLine {
+                        line_no,
+                        line: trimmed.to_owned(),
+                    };
+                    let (placement, anchors, text) =
+                        parse_note_body(&trimmed[keyword.len()..]).ok_or_else(invalid_note)?;
+
+                    let mut participant_ids = Vec::<ObjectId>::with_capacity(anchors.len());
+                    for name in anchors {
+                        validate_mermaid_ident(name).map_err(|reason| {
+                            MermaidSequenceParseError::InvalidParticipantName {
+                                line_no,
+                                name: name.to_owned(),
+                                reason,
+                            }
+                        })?;
+                        participant_ids.push(ensure_participant(
+                            ast.participants_mut(),
+                            id_rules,
+                            name,
+                            line_no,
+                        )?);
+                    }
+
+                    notes_since_last_message += 1;
+                    let note_index = ast.notes().len() + 1;
+                    // Notes sort between the previous message (`index * 1000`) and the next one.
+                    let order_key =
+                        (ast.messages().len() as i64) * 1000 + notes_since_last_message as i64;
+                    let mut note = SequenceNote::new(
+                        note_id_from_index(note_index),
+                        placement,
+                        participant_ids,
+                        text.to_owned(),
+                        order_key,
+                    );
+                    note.set_section_id(
+                        open_blocks.last().map(|top| top.current_section().section_id().clone()),
+                    );
+                    ast.notes_mut().push(note);
+                    continue;
+                }
+                "activate" | "deactivate" => {
+                    let mut parts = trimmed.split_whitespace().skip(1);
+                    let (Some(name), None) = (parts.next(), parts.next()) else {
+                        return Err(MermaidSequenceParseError::InvalidActivationLine {
+                            line_no,
+                            line: trimmed.to_owned(),
+                        });
+                    };
+                    validate_mermaid_ident(name).map_err(|reason| {
+                        MermaidSequenceParseError::InvalidParticipantName {
+                            line_no,
+                            name: name.to_owned(),
+                            reason,
+                        }
+                    })?;
+                    let participant_id =
+                        ensure_participant(ast.participants_mut(), id_rules, name, line_no)?;
+                    // Statements anchor to the message right before them.
+                    let last_message_id =
+                        ast.messages().last().map(|message| message.message_id().clone());
+                    if keyword == "activate" {
+                        next_activation_index += 1;
+                        open_activation(
+                            &mut ast,
+                            next_activation_index,
+                            participant_id,
+                            last_message_id,
+                        );
+                    } else if !close_activation(&mut ast, &participant_id, last_message_id) {
+                        return Err(MermaidSequenceParseError::InactiveParticipant {
+                            line_no,
+                            name: name.to_owned(),
+                        });
+                    }
+                    continue;
+                }
                 _ => {}
             }
         }
@@ -524,8 +988,10 @@ pub fn parse_sequence_diagram(input: &str) -> Result<SequenceAst, MermaidSequenc
             MermaidSequenceParseError::InvalidMessageLine { line_no, line: trimmed.to_owned() }
         })?;
 
-        let from_name = from_raw.trim();
-        let to_name = to_raw.trim();
+        let (from_name, to_name) = (from_raw.trim(), to_raw.trim());
+        // Mermaid has no found/lost arrows: they are written as self-messages on the lifeline
+        // they touch, so a boundary comment above a message between two lifelines is dropped.
+        let boundary = boundary.filter(|_| from_name == to_name);
         validate_mermaid_ident(from_name).map_err(|reason| {
             MermaidSequenceParseError::InvalidMessageParticipant {
                 line_no,
@@ -549,41 +1015,75 @@ pub fn parse_sequence_diagram(input: &str) -> Result<SequenceAst, MermaidSequenc
             });
         }
 
-        let from_participant_id = ensure_participant(ast.participants_mut(), from_name, line_no)?;
-        let to_participant_id = ensure_participant(ast.participants_mut(), to_name, line_no)?;
+        let from_participant_id =
+            ensure_participant(ast.participants_mut(), id_rules, from_name, line_no)?;
+        let to_participant_id =
+            ensure_participant(ast.participants_mut(), id_rules, to_name, line_no)?;
 
         let message_index = ast.messages().len() + 1;
-        let mut message_id = message_id_from_index(message_index);
-        let mut bump = 0usize;
-        while used_message_ids.contains(&message_id) {
-            bump += 1;
-            message_id = message_id_from_index(message_index + bump);
-        }
-        used_message_ids.insert(message_id.clone());
+        let anchor =
+            anchor.filter(|id| ast.messages().iter().all(|message| message.message_id() != id));
+        let message_id = anchor.unwrap_or_else(|| {
+            let mut message_id = message_id_from_index(message_index);
+            let mut bump = 0usize;
+            while used_message_ids.contains(&message_id) {
+                bump += 1;
+                message_id = message_id_from_index(message_index + bump);
+            }
+            used_message_ids.insert(message_id.clone());
+            message_id
+        });
         let message_id_for_membership = message_id.clone();
         let order_key = (message_index as i64) * 1000;
         let mut message = SequenceMessage::new(
             message_id,
-            from_participant_id,
-            to_participant_id,
+            from_participant_id.clone(),
+            to_participant_id.clone(),
             arrow.kind(),
             text.to_owned(),
             order_key,
         );
         let canonical = Arrow::from_kind(arrow.kind()).as_token();
+        let activation_suffix = raw_arrow.chars().last().filter(|ch| matches!(ch, '+' | '-'));
         message.set_raw_arrow((raw_arrow != canonical).then_some(raw_arrow));
+        message.set_boundary(boundary);
 
         for open_block in &mut open_blocks {
             open_block.push_message_id(message_id_for_membership.clone());
         }
 
         ast.messages_mut().push(message);
+        notes_since_last_message = 0;
+
+        // `+` activates the receiver and `-` deactivates the sender at this message. A `-` on a
+        // participant that is not active is kept in the raw arrow only.
+        match activation_suffix {
+            Some('+') => {
+                next_activation_index += 1;
+                open_activation(
+                    &mut ast,
+                    next_activation_index,
+                    to_participant_id,
+                    Some(message_id_for_membership),
+                );
+            }
+            Some('-') => {
+                close_activation(&mut ast, &from_participant_id, Some(message_id_for_membership));
+            }
+            _ => {}
+        }
     }
 
     if !saw_header {
         return Err(MermaidSequenceParseError::MissingHeader);
     }
 
+    if let Some(group) = open_group {
+        return Err(MermaidSequenceParseError::UnclosedBox {
+            opened_on_line_no: group.opened_on_line_no,
+        });
+    }
+
     if let Some(unclosed) = open_blocks.last() {
         return Err(MermaidSequenceParseError::UnclosedBlock {
             opened_on_line_no: unclosed.opened_on_line_no,
@@ -838,22 +1338,59 @@ fn export_schedule_block<'a>(
 /// Export a `sequenceDiagram` to canonical Mermaid `.mmd`.
 ///
 /// Export is stable/deterministic:
-/// - Participants are emitted in `ObjectId` order (typically lexical by `p:<name>`).
-/// - Messages are emitted in `(order_key, message_id)` order.
+/// - Participants are emitted in [`SequenceAst::participants_in_order`] order: the explicit
+///   participant order first, the rest in `ObjectId` order (typically lexical by `p:<name>`),
+///   with a grouped participant pulling its whole `box` to its position.
+/// - Messages are emitted in `(order_key, message_id)` order, each preceded by a
+///   `%% nereid:id=<message_id>` anchor comment so re-parsing the export keeps the message ids;
+///   found/lost messages are written as self-messages below a `%% nereid:boundary=` comment.
+/// - Notes are emitted between the last message whose `order_key` does not exceed theirs and the
+///   next one, inside the block section they were written in (so a note after `end`, after `else`
+///   or at the start of a block stays there).
 pub fn export_sequence_diagram(ast: &SequenceAst) -> Result<String, MermaidSequenceExportError> {
     let mut out = String::new();
     out.push_str("sequenceDiagram\n");
 
-    for participant in ast.participants().values() {
-        if let Some(role) = participant.role() {
-            out.push_str(role);
-            out.push(' ');
-            out.push_str(participant.mermaid_name());
-        } else {
-            out.push_str("participant ");
-            out.push_str(participant.mermaid_name());
-        }
+    let push_participant = |out: &mut String, participant: &SequenceParticipant| {
+        out.push_str(participant.role().unwrap_or("participant"));
+        out.push(' ');
+        out.push_str(participant.mermaid_name());
         out.push('\n');
+    };
+    let ordered = ast.participants_in_order();
+    let mut emitted_groups = BTreeSet::<&ObjectId>::new();
+    for participant_id in &ordered {
+        let Some(group) = ast.participant_group(participant_id) else {
+            push_participant(&mut out, &ast.participants()[*participant_id]);
+            continue;
+        };
+        if !emitted_groups.insert(group.group_id()) {
+            continue;
+        }
+        if let Some(member_id) =
+            group.participant_ids().iter().find(|id| !ast.participants().contains_key(*id))
+        {
+            return Err(MermaidSequenceExportError::MissingParticipant {
+                participant_id: member_id.clone(),
+            });
+        }
+
+        export_box_header(group, &mut out);
+        for member_id in &ordered {
+            if ast
+                .participant_group(member_id)
+                .is_some_and(|owner| owner.group_id() == group.group_id())
+            {
+                push_participant(&mut out, &ast.participants()[*member_id]);
+            }
+        }
+        out.push_str("end\n");
+    }
+    for group in ast.groups() {
+        if !emitted_groups.contains(group.group_id()) && group.participant_ids().is_empty() {
+            export_box_header(group, &mut out);
+            out.push_str("end\n");
+        }
     }
 
     let mut messages = ast.messages().iter().collect::<Vec<_>>();
@@ -902,42 +1439,35 @@ pub fn export_sequence_diagram(ast: &SequenceAst) -> Result<String, MermaidSeque
         }
     }
 
-    for (idx, msg) in messages.into_iter().enumerate() {
-        let mut before_events = std::mem::take(&mut before[idx]);
-        before_events
-            .sort_by(|a, b| export_event_sort_key_before(a).cmp(&export_event_sort_key_before(b)));
-        for event in before_events {
-            match event {
-                ExportEvent::BlockOpen { block, depth: _ } => {
-                    out.push_str(block_kind_keyword(block.kind()));
-                    if let Some(header) = block.header() {
-                        if !header.is_empty() {
-                            out.push(' ');
-                            out.push_str(header);
-                        }
-                    }
-                    out.push('\n');
-                }
-                ExportEvent::SectionSplit { block: _, section, depth: _ } => {
-                    let keyword = section_kind_keyword(section.kind());
-                    if keyword.is_empty() {
-                        continue;
-                    }
-                    out.push_str(keyword);
-                    if let Some(header) = section.header() {
-                        if !header.is_empty() {
-                            out.push(' ');
-                            out.push_str(header);
-                        }
-                    }
-                    out.push('\n');
-                }
-                ExportEvent::BlockClose { .. } => {
-                    // Block closes are emitted from `after`.
-                }
-            }
+    let mut message_sections = BTreeMap::<&ObjectId, &ObjectId>::new();
+    let mut block_parent_sections = BTreeMap::<&ObjectId, Option<&ObjectId>>::new();
+    export_section_context(ast.blocks(), None, &mut message_sections, &mut block_parent_sections);
+
+    // Notes sorting before `bound` (a message order key; `None` takes the rest).
+    let mut notes = ast.notes_in_order().into_iter().peekable();
+    let mut take_notes = |bound: Option<i64>| {
+        let mut taken = Vec::new();
+        while let Some(note) =
+            notes.next_if(|note| bound.map_or(true, |key| note.order_key() < key))
+        {
+            taken.push(note);
         }
-
+        taken
+    };
+    export_activation_lines(ast, None, &mut out)?;
+    let gap_notes = take_notes(messages.first().map(|msg| msg.order_key()));
+    let first_events = before.first_mut().map(std::mem::take).unwrap_or_default();
+    export_gap(
+        ast,
+        None,
+        sorted_before_events(first_events),
+        gap_notes,
+        &block_parent_sections,
+        &mut out,
+    )?;
+
+    let message_keys = messages.iter().map(|msg| msg.order_key()).collect::<Vec<_>>();
+    for (idx, msg) in messages.into_iter().enumerate() {
         let from_name = ast
             .participants()
             .get(msg.from_participant_id())
@@ -951,7 +1481,10 @@ pub fn export_sequence_diagram(ast: &SequenceAst) -> Result<String, MermaidSeque
                     participant_id: msg.to_participant_id().clone(),
                 },
             )?;
-
+        push_anchor(&mut out, msg.message_id());
+        if let Some(boundary) = msg.boundary() {
+            push_boundary_annotation(&mut out, boundary);
+        }
         out.push_str(from_name);
         let arrow = msg
             .raw_arrow()
@@ -969,41 +1502,265 @@ pub fn export_sequence_diagram(ast: &SequenceAst) -> Result<String, MermaidSeque
         }
         out.push_str(text);
         out.push('\n');
+        export_activation_lines(ast, Some((msg, arrow)), &mut out)?;
 
-        let mut after_events = std::mem::take(&mut after[idx]);
-        after_events
-            .sort_by(|a, b| export_event_sort_key_after(a).cmp(&export_event_sort_key_after(b)));
-        for event in after_events {
-            if let ExportEvent::BlockClose { .. } = event {
-                out.push_str("end\n");
-            }
+        let gap_notes = take_notes(message_keys.get(idx + 1).copied());
+        let mut events = sorted_after_events(std::mem::take(&mut after[idx]));
+        if let Some(next_before) = before.get_mut(idx + 1) {
+            events.extend(sorted_before_events(std::mem::take(next_before)));
         }
+        let context = message_sections.get(msg.message_id()).copied();
+        export_gap(ast, context, events, gap_notes, &block_parent_sections, &mut out)?;
     }
 
     Ok(out)
 }
 
+/// Writes `deactivate`/`activate` lines for the activations ending or starting at `message` (`None`:
+/// starting before the first message), skipping the one the exported arrow's `+`/`-` suffix
+/// already expresses.
+fn export_activation_lines(
+    ast: &SequenceAst,
+    message: Option<(&SequenceMessage, &str)>,
+    out: &mut String,
+) -> Result<(), MermaidSequenceExportError> {
+    let message_id = message.map(|(msg, _)| msg.message_id());
+    let suffix = message.and_then(|(_, arrow)| arrow.chars().last());
+    // A `-` suffix ends the sender's innermost activation, a `+` suffix starts the receiver's
+    // first one.
+    let mut implied_end =
+        message.filter(|_| suffix == Some('-')).map(|(msg, _)| msg.from_participant_id());
+    let mut implied_start =
+        message.filter(|_| suffix == Some('+')).map(|(msg, _)| msg.to_participant_id());
+
+    let mut lines = Vec::<(&str, &ObjectId)>::new();
+    if message_id.is_some() {
+        for activation in ast.activations().iter().rev() {
+            if activation.end_message_id() != message_id {
+                continue;
+            }
+            if implied_end == Some(activation.participant_id()) {
+                implied_end = None;
+            } else {
+                lines.push(("deactivate", activation.participant_id()));
+            }
+        }
+    }
+    for activation in ast.activations() {
+        if activation.start_message_id() != message_id {
+            continue;
+        }
+        if implied_start == Some(activation.participant_id()) {
+            implied_start = None;
+        } else {
+            lines.push(("activate", activation.participant_id()));
+        }
+    }
+
+    for (keyword, participant_id) in lines {
+        let participant = ast.participants().get(participant_id).ok_or_else(|| {
+            MermaidSequenceExportError::MissingParticipant {
+                participant_id: participant_id.clone(),
+            }
+        })?;
+        out.push_str(keyword);
+        out.push(' ');
+        out.push_str(participant.mermaid_name());
+        out.push('\n');
+    }
+    Ok(())
+}
+
+/// Records the innermost section of every message and the section (`None` at the top level) each
+/// block sits in.
+fn export_section_context<'a>(
+    blocks: &'a [SequenceBlock],
+    parent_section: Option<&'a ObjectId>,
+    message_sections: &mut BTreeMap<&'a ObjectId, &'a ObjectId>,
+    block_parent_sections: &mut BTreeMap<&'a ObjectId, Option<&'a ObjectId>>,
+) {
+    for block in blocks {
+        block_parent_sections.insert(block.block_id(), parent_section);
+        for section in block.sections() {
+            for message_id in section.message_ids() {
+                message_sections.insert(message_id, section.section_id());
+            }
+        }
+        // Children run after the parent's sections so they overwrite with the deeper section.
+        for child in block.blocks() {
+            let first_message = child.sections().first().and_then(|s| s.message_ids().first());
+            let child_parent = block
+                .sections()
+                .iter()
+                .find(|section| first_message.is_some_and(|id| section.message_ids().contains(id)))
+                .map(SequenceSection::section_id);
+            export_section_context(
+                std::slice::from_ref(child),
+                child_parent.or(parent_section),
+                message_sections,
+                block_parent_sections,
+            );
+        }
+    }
+}
+
+fn sorted_before_events(mut events: Vec<ExportEvent<'_>>) -> Vec<ExportEvent<'_>> {
+    // Block closes are emitted from the `after` events.
+    events.retain(|event| !matches!(event, ExportEvent::BlockClose { .. }));
+    events.sort_by(|a, b| export_event_sort_key_before(a).cmp(&export_event_sort_key_before(b)));
+    events
+}
+
+fn sorted_after_events(mut events: Vec<ExportEvent<'_>>) -> Vec<ExportEvent<'_>> {
+    events.retain(|event| matches!(event, ExportEvent::BlockClose { .. }));
+    events.sort_by(|a, b| export_event_sort_key_after(a).cmp(&export_event_sort_key_after(b)));
+    events
+}
+
+/// Emits the block events between two messages, placing each note at the first point where its
+/// section is open (right after the previous message when that section never is, e.g. for notes
+/// built without one).
+fn export_gap(
+    ast: &SequenceAst,
+    start_section: Option<&ObjectId>,
+    events: Vec<ExportEvent<'_>>,
+    notes: Vec<&SequenceNote>,
+    block_parent_sections: &BTreeMap<&ObjectId, Option<&ObjectId>>,
+    out: &mut String,
+) -> Result<(), MermaidSequenceExportError> {
+    let mut sections = vec![start_section];
+    for event in &events {
+        let section = match *event {
+            ExportEvent::BlockOpen { block, .. } => {
+                block.sections().first().map(SequenceSection::section_id)
+            }
+            ExportEvent::SectionSplit { section, .. } => Some(section.section_id()),
+            ExportEvent::BlockClose { block, .. } => {
+                block_parent_sections.get(block.block_id()).copied().flatten()
+            }
+        };
+        sections.push(section);
+    }
+    let note_positions = notes
+        .iter()
+        .map(|note| sections.iter().position(|section| *section == note.section_id()).unwrap_or(0))
+        .collect::<Vec<_>>();
+
+    for position in 0..sections.len() {
+        for (note, _) in notes.iter().zip(&note_positions).filter(|(_, p)| **p == position) {
+            export_note(ast, note, out)?;
+        }
+        if let Some(event) = events.get(position) {
+            export_block_event(event, out);
+        }
+    }
+    Ok(())
+}
+
+fn export_block_event(event: &ExportEvent<'_>, out: &mut String) {
+    let (keyword, header) = match *event {
+        ExportEvent::BlockOpen { block, .. } => (block_kind_keyword(block.kind()), block.header()),
+        ExportEvent::SectionSplit { section, .. } => {
+            (section_kind_keyword(section.kind()), section.header())
+        }
+        ExportEvent::BlockClose { .. } => ("end", None),
+    };
+    if keyword.is_empty() {
+        return;
+    }
+    out.push_str(keyword);
+    if let Some(header) = header.filter(|header| !header.is_empty()) {
+        out.push(' ');
+        out.push_str(header);
+    }
+    out.push('\n');
+}
+
+fn export_box_header(group: &SequenceGroup, out: &mut String) {
+    out.push_str("box");
+    for part in [group.color(), group.title()].into_iter().flatten() {
+        out.push(' ');
+        out.push_str(part);
+    }
+    out.push('\n');
+}
+
+fn export_note(
+    ast: &SequenceAst,
+    note: &SequenceNote,
+    out: &mut String,
+) -> Result<(), MermaidSequenceExportError> {
+    let invalid_note = |reason: &str| MermaidSequenceExportError::InvalidNote {
+        note_id: note.note_id().clone(),
+        reason: reason.to_owned(),
+    };
+
+    let max_anchors = if note.placement() == SequenceNotePlacement::Over { 2 } else { 1 };
+    let anchor_count = note.participant_ids().len();
+    if anchor_count == 0 || anchor_count > max_anchors {
+        return Err(invalid_note(&format!(
+            "'{}' expects 1..={max_anchors} participants, got {anchor_count}",
+            note_placement_keyword(note.placement())
+        )));
+    }
+    if !validate_export_message_text(note.text()) || note.text().trim().is_empty() {
+        return Err(invalid_note(&format!("unsupported note text: {:?}", note.text())));
+    }
+
+    let mut anchor_names = Vec::<&str>::with_capacity(anchor_count);
+    for participant_id in note.participant_ids() {
+        let participant = ast.participants().get(participant_id).ok_or_else(|| {
+            MermaidSequenceExportError::MissingParticipant {
+                participant_id: participant_id.clone(),
+            }
+        })?;
+        anchor_names.push(participant.mermaid_name());
+    }
+
+    out.push_str("Note ");
+    out.push_str(note_placement_keyword(note.placement()));
+    out.push(' ');
+    out.push_str(&anchor_names.join(","));
+    out.push_str(": ");
+    out.push_str(note.text());
+    out.push('\n');
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        export_sequence_diagram, parse_sequence_diagram, MermaidSequenceExportError,
-        MermaidSequenceParseError,
+        export_sequence_diagram, parse_sequence_diagram, split_box_header,
+        MermaidSequenceExportError, MermaidSequenceParseError, CSS_NAMED_COLORS,
+    };
+    use crate::model::ids::ObjectId;
+    use crate::model::seq_ast::{
+        SequenceAst, SequenceMessageBoundary, SequenceMessageKind, SequenceNotePlacement,
+    };
+    use crate::model::seq_ast::{
+        SequenceBlockKind, SequenceMessage, SequenceParticipant, SequenceSectionKind,
     };
-    use crate::model::seq_ast::{SequenceAst, SequenceMessageKind};
-    use crate::model::seq_ast::{SequenceBlockKind, SequenceMessage, SequenceSectionKind};
     use std::collections::BTreeSet;
 
     type SequenceParticipantSemanticView = BTreeSet<String>;
     type SequenceMessageSemanticView = Vec<(String, String, SequenceMessageKind, String)>;
 
+    /// Exported Mermaid without its `%% nereid:id=` anchor lines.
+    fn without_anchors(out: &str) -> String {
+        out.lines()
+            .filter(|line| !line.starts_with("%% nereid:id="))
+            .flat_map(|line| [line, "\n"])
+            .collect()
+    }
+
     fn assert_canonical_roundtrip(input: &str, expected: &str) {
         let ast1 = parse_sequence_diagram(input).expect("parse 1");
         let out1 = export_sequence_diagram(&ast1).expect("export 1");
-        assert_eq!(out1, expected);
+        assert_eq!(without_anchors(&out1), expected);
 
         let ast2 = parse_sequence_diagram(&out1).expect("parse 2");
         let out2 = export_sequence_diagram(&ast2).expect("export 2");
-        assert_eq!(out2, expected);
+        assert_eq!(out2, out1);
     }
 
     fn semantic_view(
@@ -1059,6 +1816,259 @@ mod tests {
         assert_eq!(messages[2].2, SequenceMessageKind::Async);
     }
 
+    #[test]
+    fn parses_and_exports_self_found_and_lost_messages() {
+        let input = r#"
+            sequenceDiagram
+            participant Alice
+            participant Bob
+            Alice->>Alice: think
+            %% nereid:boundary=found
+            Alice->>Alice: wake up
+            %% nereid:boundary=lost
+            Bob-)Bob: fire and forget
+        "#;
+
+        let ast = parse_sequence_diagram(input).expect("parse");
+        let boundaries = ast.messages().iter().map(|msg| msg.boundary()).collect::<Vec<_>>();
+        assert_eq!(
+            boundaries,
+            vec![None, Some(SequenceMessageBoundary::Found), Some(SequenceMessageBoundary::Lost)]
+        );
+        assert!(ast.messages()[0].is_self_message());
+        assert!(!ast.messages()[1].is_self_message());
+        assert_eq!(ast.messages()[1].sender(), None);
+        assert_eq!(ast.messages()[1].receiver().map(ObjectId::as_str), Some("p:Alice"));
+        assert_eq!(ast.messages()[2].sender().map(ObjectId::as_str), Some("p:Bob"));
+        assert_eq!(ast.messages()[2].receiver(), None);
+
+        let out = export_sequence_diagram(&ast).expect("export");
+        assert!(out.contains("Alice->>Alice: think\n"), "{out}");
+        assert!(out.contains("%% nereid:boundary=found\nAlice->>Alice: wake up\n"), "{out}");
+        assert!(out.contains("%% nereid:boundary=lost\nBob-)Bob: fire and forget\n"), "{out}");
+        assert!(!out.contains("[->>") && !out.contains(")]"), "{out}");
+        assert_eq!(parse_sequence_diagram(&out).expect("reparse"), ast);
+    }
+
+    #[test]
+    fn boundary_messages_round_trip_through_plain_mermaid() {
+        let oid = |id: &str| ObjectId::new(id).expect("valid id");
+        let mut ast = SequenceAst::default();
+        ast.participants_mut().insert(oid("p:A"), SequenceParticipant::new("A"));
+        ast.participants_mut().insert(oid("p:B"), SequenceParticipant::new("B"));
+        ast.messages_mut().push(SequenceMessage::new_boundary(
+            oid("m:0001"),
+            oid("p:A"),
+            SequenceMessageBoundary::Found,
+            SequenceMessageKind::Sync,
+            "request",
+            1000,
+        ));
+        ast.messages_mut().push(SequenceMessage::new(
+            oid("m:0002"),
+            oid("p:A"),
+            oid("p:B"),
+            SequenceMessageKind::Sync,
+            "forward",
+            2000,
+        ));
+        ast.messages_mut().push(SequenceMessage::new_boundary(
+            oid("m:0003"),
+            oid("p:B"),
+            SequenceMessageBoundary::Lost,
+            SequenceMessageKind::Async,
+            "drop",
+            3000,
+        ));
+
+        let out = export_sequence_diagram(&ast).expect("export");
+        // Stripping the nereid comments leaves diagram text Mermaid itself accepts.
+        let plain = without_anchors(&out).replace("%% nereid:boundary=found\n", "");
+        let plain = plain.replace("%% nereid:boundary=lost\n", "");
+        assert_eq!(
+            plain,
+            "sequenceDiagram\nparticipant A\nparticipant B\n\
+             A->>A: request\nA->>B: forward\nB-)B: drop\n"
+        );
+
+        let reparsed = parse_sequence_diagram(&out).expect("reparse");
+        let boundaries = reparsed.messages().iter().map(|msg| msg.boundary()).collect::<Vec<_>>();
+        assert_eq!(
+            boundaries,
+            vec![Some(SequenceMessageBoundary::Found), None, Some(SequenceMessageBoundary::Lost)]
+        );
+
+        // Without the annotations the same lines are ordinary self-messages.
+        let plain_ast = parse_sequence_diagram(&plain).expect("parse plain");
+        assert!(plain_ast.messages().iter().all(|msg| msg.boundary().is_none()));
+        assert!(plain_ast.messages()[0].is_self_message());
+
+        // A boundary annotation above a message between two lifelines is ignored.
+        let mixed = "sequenceDiagram\n%% nereid:boundary=lost\nA->>B: hi\n";
+        let mixed_ast = parse_sequence_diagram(mixed).expect("parse mixed");
+        assert_eq!(mixed_ast.messages()[0].boundary(), None);
+    }
+
+    #[test]
+    fn parses_and_exports_note_placements_in_timeline_order() {
+        let input = "sequenceDiagram\nNote over A: intro\nA->>B: hi\nNote left of A: l\nNote right of B: r\nalt ok\nB->>A: yes\nNote over B,A: both\nend\n";
+        let ast = parse_sequence_diagram(input).expect("parse");
+
+        let notes = ast
+            .notes_in_order()
+            .into_iter()
+            .map(|note| {
+                let anchors =
+                    note.participant_ids().iter().map(|id| id.as_str()).collect::<Vec<_>>();
+                (note.note_id().as_str(), note.placement(), anchors, note.text(), note.order_key())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            notes,
+            vec![
+                ("note:0001", SequenceNotePlacement::Over, vec!["p:A"], "intro", 1),
+                ("note:0002", SequenceNotePlacement::LeftOf, vec!["p:A"], "l", 1001),
+                ("note:0003", SequenceNotePlacement::RightOf, vec!["p:B"], "r", 1002),
+                ("note:0004", SequenceNotePlacement::Over, vec!["p:B", "p:A"], "both", 2001),
+            ]
+        );
+
+        assert_canonical_roundtrip(
+            input,
+            "sequenceDiagram\nparticipant A\nparticipant B\nNote over A: intro\nA->>B: hi\nNote left of A: l\nNote right of B: r\nalt ok\nB->>A: yes\nNote over B,A: both\nend\n",
+        );
+    }
+
+    #[test]
+    fn exports_notes_in_the_block_section_they_were_written_in() {
+        let header = "sequenceDiagram\nparticipant A\nparticipant B\n";
+        for body in [
+            "alt x\nA->>B: m\nend\nNote over A: after end\n",
+            "alt yes\nA->>B: m\nelse no\nNote over A: after else\nB->>A: n\nend\n",
+            "loop poll\nNote right of B: loop start\nA->>B: m\nend\n",
+            "A->>B: first\nopt retry\nNote left of A: opt start\nB->>A: m\nend\n",
+            "alt x\nloop y\nA->>B: m\nend\nNote over A,B: between ends\nend\n",
+            "par a\nA->>B: m\nand b\nNote over B: after and\nB->>A: n\nend\nNote over A: tail\n",
+        ] {
+            let input = format!("{header}{body}");
+            assert_canonical_roundtrip(&input, &input);
+        }
+
+        let ast = parse_sequence_diagram(
+            "sequenceDiagram\nalt x\nA->>B: m\nelse y\nNote over A: in else\nB->>A: n\nend\n",
+        )
+        .expect("parse");
+        let note = ast.notes_in_order()[0];
+        let else_section = ast.blocks()[0].sections()[1].section_id();
+        assert_eq!(note.section_id(), Some(else_section));
+    }
+
+    #[test]
+    fn parses_and_exports_participant_boxes() {
+        let input = "sequenceDiagram\nbox rgb(10, 20, 30) Front end\nparticipant Web\nparticipant App\nend\nbox transparent\nparticipant Db\nend\nbox Aqua Team\nend\nparticipant Api\nWeb->>Api: call\n";
+        let ast = parse_sequence_diagram(input).expect("parse");
+
+        let groups = ast
+            .groups()
+            .iter()
+            .map(|group| {
+                let members =
+                    group.participant_ids().iter().map(|id| id.as_str()).collect::<Vec<_>>();
+                (group.group_id().as_str(), group.color(), group.title(), members)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            groups,
+            vec![
+                ("g:0001", Some("rgb(10, 20, 30)"), Some("Front end"), vec!["p:Web", "p:App"]),
+                ("g:0002", Some("transparent"), None, vec!["p:Db"]),
+                ("g:0003", Some("Aqua"), Some("Team"), vec![]),
+            ]
+        );
+
+        assert_canonical_roundtrip(
+            input,
+            "sequenceDiagram\nparticipant Api\nbox rgb(10, 20, 30) Front end\nparticipant Web\nparticipant App\nend\nbox transparent\nparticipant Db\nend\nbox Aqua Team\nend\nWeb->>Api: call\n",
+        );
+    }
+
+    #[test]
+    fn export_follows_explicit_participant_order_and_keeps_boxes_together() {
+        let input = "sequenceDiagram\nparticipant Api\nbox Front\nparticipant Web\nparticipant App\nend\nparticipant Db\nWeb->>Api: call\n";
+        let mut ast = parse_sequence_diagram(input).expect("parse");
+        let id = |id: &str| crate::model::ObjectId::new(id).expect("participant id");
+        *ast.participant_order_mut() = vec![id("p:Db"), id("p:App"), id("p:Api"), id("p:Web")];
+
+        assert_eq!(
+            ast.participants_in_order().into_iter().map(|id| id.as_str()).collect::<Vec<_>>(),
+            vec!["p:Db", "p:App", "p:Web", "p:Api"]
+        );
+        assert_eq!(
+            without_anchors(&export_sequence_diagram(&ast).expect("export")),
+            "sequenceDiagram\nparticipant Db\nbox Front\nparticipant App\nparticipant Web\nend\nparticipant Api\nWeb->>Api: call\n"
+        );
+    }
+
+    #[test]
+    fn box_headers_accept_css_named_colors_in_any_case() {
+        let cases = [
+            ("Aqua Team", Some("Aqua"), Some("Team")),
+            ("lightSteelBlue Ops crew", Some("lightSteelBlue"), Some("Ops crew")),
+            ("Tan", Some("Tan"), None),
+            ("Tangerine Team", None, Some("Tangerine Team")),
+            ("Back end", None, Some("Back end")),
+        ];
+        for (header, color, title) in cases {
+            assert_eq!(
+                split_box_header(header),
+                (color.map(str::to_owned), title.map(str::to_owned)),
+                "{header}"
+            );
+        }
+        assert!(CSS_NAMED_COLORS.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn rejects_invalid_participant_box_usage() {
+        for (input, line_no) in [
+            ("sequenceDiagram\nbox T\nparticipant A\nA->>A: hi\nend\n", 4),
+            ("sequenceDiagram\nbox T\nbox U\nend\n", 3),
+            ("sequenceDiagram\nA->>B: hi\nloop l\nbox T\nend\n", 4),
+        ] {
+            let err = parse_sequence_diagram(input).expect_err(input);
+            assert_eq!(
+                err,
+                MermaidSequenceParseError::InvalidBoxLine {
+                    line_no,
+                    line: input.lines().nth(line_no - 1).expect("line").to_owned(),
+                }
+            );
+        }
+
+        assert_eq!(
+            parse_sequence_diagram("sequenceDiagram\nbox T\nparticipant A\n"),
+            Err(MermaidSequenceParseError::UnclosedBox { opened_on_line_no: 2 })
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_note_lines() {
+        for line in [
+            "Note over A",
+            "Note beside A: text",
+            "Note left of A,B: text",
+            "Note over A,B,C: text",
+            "Note right of A:",
+        ] {
+            let input = format!("sequenceDiagram\nparticipant A\n{line}\n");
+            let err = parse_sequence_diagram(&input).expect_err(line);
+            assert!(
+                matches!(err, MermaidSequenceParseError::InvalidNoteLine { line_no: 3, .. }),
+                "{line}: {err:?}"
+            );
+        }
+    }
+
     #[test]
     fn accepts_additional_mermaid_arrow_variants_and_activation_suffixes() {
         let input = r#"
@@ -1175,6 +2185,66 @@ mod tests {
         assert_eq!(arrows2, arrows1);
     }
 
+    #[test]
+    fn parses_and_roundtrips_activation_statements_and_suffixes() {
+        let input = r#"
+            sequenceDiagram
+            activate Alice
+            Alice->>+Bob: Request
+            activate Bob
+            Bob->>Carol: Forward
+            deactivate Bob
+            Bob-->>-Alice: Reply
+            deactivate Alice
+            Carol-->>-Bob: Not active
+        "#;
+
+        let ast1 = parse_sequence_diagram(input).expect("parse 1");
+        let activations = |ast: &SequenceAst| {
+            ast.activations()
+                .iter()
+                .map(|activation| {
+                    format!(
+                        "{} {} {:?} {:?}",
+                        activation.activation_id(),
+                        activation.participant_id(),
+                        activation.start_message_id().map(ObjectId::as_str),
+                        activation.end_message_id().map(ObjectId::as_str),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            activations(&ast1),
+            vec![
+                "act:0001 p:Alice None Some(\"m:0003\")",
+                "act:0002 p:Bob Some(\"m:0001\") Some(\"m:0003\")",
+                "act:0003 p:Bob Some(\"m:0001\") Some(\"m:0002\")",
+            ]
+        );
+
+        let out = export_sequence_diagram(&ast1).expect("export");
+        assert!(
+            without_anchors(&out).ends_with(
+                "activate Alice\nAlice->>+Bob: Request\nactivate Bob\nBob->>Carol: Forward\n\
+                 deactivate Bob\nBob-->>-Alice: Reply\ndeactivate Alice\n\
+                 Carol-->>-Bob: Not active\n"
+            ),
+            "{out}"
+        );
+        let ast2 = parse_sequence_diagram(&out).expect("parse 2");
+        assert_eq!(activations(&ast2), activations(&ast1));
+
+        let err = parse_sequence_diagram("sequenceDiagram\nAlice->>Bob: Hi\ndeactivate Bob\n")
+            .unwrap_err();
+        assert_eq!(
+            err,
+            MermaidSequenceParseError::InactiveParticipant { line_no: 3, name: "Bob".to_owned() }
+        );
+        let err = parse_sequence_diagram("sequenceDiagram\nactivate\n").unwrap_err();
+        assert!(matches!(err, MermaidSequenceParseError::InvalidActivationLine { line_no: 2, .. }));
+    }
+
     #[test]
     fn rejects_missing_header() {
         let err = parse_sequence_diagram("participant Alice\n").unwrap_err();
@@ -1424,4 +2494,50 @@ Alice->>Bob: Hi
             }
         );
     }
+
+    #[test]
+    fn anchor_comments_keep_message_ids_across_an_edited_export() {
+        let mut ast = parse_sequence_diagram(
+            "sequenceDiagram\nAlice->>Bob: Hi\nBob-->>Alice: Hello\nAlice-)Bob: Bye\n",
+        )
+        .expect("parse");
+        let hello = ObjectId::new("m:hello").expect("message id");
+        let original = ast.messages()[1].clone();
+        ast.messages_mut()[1] = SequenceMessage::new(
+            hello,
+            original.from_participant_id().clone(),
+            original.to_participant_id().clone(),
+            original.kind(),
+            original.text(),
+            original.order_key(),
+        );
+
+        let exported = export_sequence_diagram(&ast).expect("export");
+        assert_eq!(
+            exported,
+            "sequenceDiagram\nparticipant Alice\nparticipant Bob\n%% nereid:id=m:0001\n\
+             Alice->>Bob: Hi\n%% nereid:id=m:hello\nBob-->>Alice: Hello\n\
+             %% nereid:id=m:0003\nAlice-)Bob: Bye\n"
+        );
+        assert_eq!(parse_sequence_diagram(&exported).expect("reparse"), ast);
+
+        // Drop the first message, reword one and insert an unanchored one before it: positional
+        // ids skip every anchored id.
+        let edited = "sequenceDiagram\nparticipant Alice\nparticipant Bob\nAlice->>Bob: New\n\
+                      %% nereid:id=m:hello\nBob-->>Alice: Hello there\n\
+                      %% nereid:id=m:0003\nAlice-)Bob: Bye\n";
+        let reparsed = parse_sequence_diagram(edited).expect("parse edited");
+        let messages = reparsed
+            .messages()
+            .iter()
+            .map(|message| (message.message_id().as_str(), message.text()))
+            .collect::<Vec<_>>();
+        assert_eq!(messages, [("m:0001", "New"), ("m:hello", "Hello there"), ("m:0003", "Bye")]);
+
+        let duplicated =
+            "sequenceDiagram\n%% nereid:id=m:x\nA->>B: one\n%% nereid:id=m:x\nB->>A: two\n";
+        let ast = parse_sequence_diagram(duplicated).expect("parse duplicated");
+        let ids = ast.messages().iter().map(|message| message.message_id().as_str());
+        assert_eq!(ids.collect::<Vec<_>>(), ["m:x", "m:0002"]);
+    }
 }
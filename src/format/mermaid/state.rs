@@ -0,0 +1,451 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::fmt;
+
+use super::ident::validate_mermaid_ident;
+pub use super::ident::MermaidIdentError;
+
+use crate::model::ids::ObjectId;
+use crate::model::state_ast::{StateAst, StateNode, StateNodeKind, StateTransition};
+
+const PSEUDO_STATE: &str = "[*]";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MermaidStateParseError {
+    MissingHeader,
+    UnsupportedSyntax { line_no: usize, line: String },
+    InvalidStateId { line_no: usize, name: String, reason: MermaidIdentError },
+    UnclosedNote { line_no: usize, state: String },
+}
+
+impl fmt::Display for MermaidStateParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingHeader => {
+                f.write_str("expected 'stateDiagram-v2' as the first non-empty line")
+            }
+            Self::UnsupportedSyntax { line_no, line } => {
+                write!(f, "unsupported Mermaid syntax on line {line_no}: {line}")
+            }
+            Self::InvalidStateId { line_no, name, reason } => {
+                write!(f, "invalid state id on line {line_no}: {name} ({reason})")
+            }
+            Self::UnclosedNote { line_no, state } => {
+                write!(f, "note for '{state}' opened on line {line_no} is missing its 'end note'")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MermaidStateParseError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MermaidStateExportError {
+    MissingState { state_id: ObjectId },
+    InvalidStateId { state_id: ObjectId },
+    InvalidStateLabel { state_id: ObjectId, label: String },
+    InvalidTransitionLabel { transition_id: ObjectId, label: String },
+}
+
+impl fmt::Display for MermaidStateExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingState { state_id } => {
+                write!(f, "transition references missing state id: {state_id}")
+            }
+            Self::InvalidStateId { state_id } => write!(
+                f,
+                "cannot export state id as Mermaid identifier: {state_id} (expected 's:<ident>')"
+            ),
+            Self::InvalidStateLabel { state_id, label } => write!(
+                f,
+                "cannot export state label for {state_id}: contains unsupported characters: {label:?}"
+            ),
+            Self::InvalidTransitionLabel { transition_id, label } => write!(
+                f,
+                "cannot export transition label for {transition_id}: contains unsupported characters: {label:?}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MermaidStateExportError {}
+
+fn state_id_from_mermaid_id(name: &str) -> Result<ObjectId, MermaidIdentError> {
+    validate_mermaid_ident(name)?;
+    ObjectId::new(format!("s:{name}")).map_err(|_| MermaidIdentError::ContainsSlash)
+}
+
+fn transition_id_from_index(index: usize) -> ObjectId {
+    ObjectId::new(format!("t:{index:04}")).expect("valid transition id")
+}
+
+fn is_ignorable_line(trimmed: &str) -> bool {
+    trimmed.starts_with("direction ")
+        || trimmed.starts_with("style ")
+        || trimmed.starts_with("class ")
+        || trimmed.starts_with("classDef ")
+}
+
+/// Declares `name` (a Mermaid id, or `[*]` on the given side of a transition) if needed.
+fn ensure_state(
+    ast: &mut StateAst,
+    name: &str,
+    pseudo_kind: StateNodeKind,
+    line_no: usize,
+) -> Result<ObjectId, MermaidStateParseError> {
+    if name == PSEUDO_STATE {
+        let state_id = match pseudo_kind {
+            StateNodeKind::Start => StateAst::start_state_id(),
+            StateNodeKind::End => StateAst::end_state_id(),
+            _ => {
+                return Err(MermaidStateParseError::InvalidStateId {
+                    line_no,
+                    name: name.to_owned(),
+                    reason: MermaidIdentError::InvalidChar { ch: '[' },
+                })
+            }
+        };
+        ast.states_mut()
+            .entry(state_id.clone())
+            .or_insert_with(|| StateNode::new_with(PSEUDO_STATE, pseudo_kind, None));
+        return Ok(state_id);
+    }
+
+    let state_id = state_id_from_mermaid_id(name).map_err(|reason| {
+        MermaidStateParseError::InvalidStateId { line_no, name: name.to_owned(), reason }
+    })?;
+    ast.states_mut()
+        .entry(state_id.clone())
+        .or_insert_with(|| StateNode::new_with(name, StateNodeKind::State, Some(name.to_owned())));
+    Ok(state_id)
+}
+
+/// Parses the rest of a `state` line: `"<label>" as <id>`, `<id> <<choice|fork|join>>` or `<id>`.
+fn parse_state_statement(
+    ast: &mut StateAst,
+    rest: &str,
+    line_no: usize,
+    line: &str,
+) -> Result<(), MermaidStateParseError> {
+    let unsupported =
+        || MermaidStateParseError::UnsupportedSyntax { line_no, line: line.to_owned() };
+
+    if let Some(quoted) = rest.strip_prefix('"') {
+        let (label, tail) = quoted.split_once('"').ok_or_else(unsupported)?;
+        let name = tail.trim().strip_prefix("as ").map(str::trim).ok_or_else(unsupported)?;
+        let state_id = ensure_state(ast, name, StateNodeKind::State, line_no)?;
+        if let Some(state) = ast.states_mut().get_mut(&state_id) {
+            state.set_label(label.trim());
+        }
+        return Ok(());
+    }
+
+    let (name, stereotype) = match rest.split_once("<<") {
+        Some((name, stereotype)) => {
+            (name.trim(), Some(stereotype.strip_suffix(">>").ok_or_else(unsupported)?.trim()))
+        }
+        None => (rest.trim(), None),
+    };
+    let kind = match stereotype {
+        None => StateNodeKind::State,
+        Some("choice") => StateNodeKind::Choice,
+        Some("fork") => StateNodeKind::Fork,
+        Some("join") => StateNodeKind::Join,
+        Some(_) => return Err(unsupported()),
+    };
+    let state_id = ensure_state(ast, name, StateNodeKind::State, line_no)?;
+    if let Some(state) = ast.states_mut().get_mut(&state_id) {
+        state.set_kind(kind);
+    }
+    Ok(())
+}
+
+/// Parse a deliberately limited Mermaid `stateDiagram-v2` subset.
+///
+/// Supported:
+/// - `stateDiagram-v2`/`stateDiagram` header
+/// - comment lines starting with `%%`; `direction`, `style`, `class` and `classDef` lines (ignored)
+/// - transitions `<a> --> <b>` and `<a> --> <b> : <label>`, with `[*]` as start/end pseudo-state
+/// - state declarations: `<id>`, `state <id>`, `state "<label>" as <id>`, `<id> : <label>`
+/// - `state <id> <<choice>>`, `<<fork>>` and `<<join>>`
+/// - notes: `note left of <id> : <text>` or multi-line `note right of <id>` ... `end note`
+///
+/// Composite states, concurrency regions and other Mermaid syntax are rejected with an error.
+pub fn parse_state_diagram(input: &str) -> Result<StateAst, MermaidStateParseError> {
+    let mut ast = StateAst::default();
+    let mut saw_header = false;
+    let mut transition_index = 0usize;
+    // (line_no, state id, name, lines) of a multi-line note being read.
+    let mut open_note: Option<(usize, ObjectId, String, Vec<String>)> = None;
+
+    for (idx, raw_line) in input.lines().enumerate() {
+        let line_no = idx + 1;
+        let trimmed = raw_line.trim();
+
+        if let Some((_, state_id, _, lines)) = open_note.as_mut() {
+            if trimmed == "end note" {
+                let note = lines.join("\n");
+                if let Some(state) = ast.states_mut().get_mut(state_id) {
+                    state.set_note(Some(note));
+                }
+                open_note = None;
+            } else {
+                lines.push(trimmed.to_owned());
+            }
+            continue;
+        }
+
+        if trimmed.is_empty() || trimmed.starts_with("%%") {
+            continue;
+        }
+
+        if !saw_header {
+            if trimmed != "stateDiagram-v2" && trimmed != "stateDiagram" {
+                return Err(MermaidStateParseError::MissingHeader);
+            }
+            saw_header = true;
+            continue;
+        }
+
+        if is_ignorable_line(trimmed) {
+            continue;
+        }
+        let unsupported =
+            || MermaidStateParseError::UnsupportedSyntax { line_no, line: trimmed.to_owned() };
+        if trimmed.ends_with('{') || trimmed == "}" || trimmed == "--" {
+            return Err(unsupported());
+        }
+
+        if let Some(rest) = trimmed.strip_prefix("note ") {
+            let rest = rest
+                .strip_prefix("left of ")
+                .or_else(|| rest.strip_prefix("right of "))
+                .ok_or_else(unsupported)?;
+            let (name, text) = match rest.split_once(':') {
+                Some((name, text)) => (name.trim(), Some(text.trim())),
+                None => (rest.trim(), None),
+            };
+            let state_id = ensure_state(&mut ast, name, StateNodeKind::State, line_no)?;
+            match text {
+                Some(text) => {
+                    if let Some(state) = ast.states_mut().get_mut(&state_id) {
+                        state.set_note(Some(text));
+                    }
+                }
+                None => open_note = Some((line_no, state_id, name.to_owned(), Vec::new())),
+            }
+            continue;
+        }
+
+        if let Some(rest) = trimmed.strip_prefix("state ") {
+            parse_state_statement(&mut ast, rest.trim(), line_no, trimmed)?;
+            continue;
+        }
+
+        if let Some((lhs, rhs)) = trimmed.split_once("-->") {
+            let (rhs, label) = match rhs.split_once(':') {
+                Some((rhs, label)) => (rhs, Some(label.trim()).filter(|label| !label.is_empty())),
+                None => (rhs, None),
+            };
+            let from = ensure_state(&mut ast, lhs.trim(), StateNodeKind::Start, line_no)?;
+            let to = ensure_state(&mut ast, rhs.trim(), StateNodeKind::End, line_no)?;
+            let mut transition = StateTransition::new(from, to);
+            transition.set_label(label);
+            ast.transitions_mut().insert(transition_id_from_index(transition_index), transition);
+            transition_index += 1;
+            continue;
+        }
+
+        if let Some((name, description)) = trimmed.split_once(':') {
+            let state_id = ensure_state(&mut ast, name.trim(), StateNodeKind::State, line_no)?;
+            if let Some(state) = ast.states_mut().get_mut(&state_id) {
+                state.set_label(description.trim());
+            }
+            continue;
+        }
+
+        ensure_state(&mut ast, trimmed, StateNodeKind::State, line_no)?;
+    }
+
+    if let Some((line_no, _, state, _)) = open_note {
+        return Err(MermaidStateParseError::UnclosedNote { line_no, state });
+    }
+    if !saw_header {
+        return Err(MermaidStateParseError::MissingHeader);
+    }
+    Ok(ast)
+}
+
+fn mermaid_id_for_state<'a>(state_id: &'a ObjectId, state: &'a StateNode) -> Option<&'a str> {
+    match state.kind() {
+        StateNodeKind::Start | StateNodeKind::End => Some(PSEUDO_STATE),
+        _ => state
+            .mermaid_id()
+            .or_else(|| state_id.as_str().strip_prefix("s:"))
+            .filter(|mermaid_id| validate_mermaid_ident(mermaid_id).is_ok()),
+    }
+}
+
+fn export_endpoint<'a>(
+    ast: &'a StateAst,
+    state_id: &'a ObjectId,
+) -> Result<&'a str, MermaidStateExportError> {
+    let state = ast
+        .states()
+        .get(state_id)
+        .ok_or_else(|| MermaidStateExportError::MissingState { state_id: state_id.clone() })?;
+    mermaid_id_for_state(state_id, state)
+        .ok_or_else(|| MermaidStateExportError::InvalidStateId { state_id: state_id.clone() })
+}
+
+pub fn export_state_diagram(ast: &StateAst) -> Result<String, MermaidStateExportError> {
+    let mut out = String::new();
+    out.push_str("stateDiagram-v2\n");
+
+    let mut connected = std::collections::BTreeSet::new();
+    for transition in ast.transitions().values() {
+        connected.insert(transition.from_state_id());
+        connected.insert(transition.to_state_id());
+    }
+
+    let mut notes = Vec::new();
+    for (state_id, state) in ast.states() {
+        let Some(mermaid_id) = mermaid_id_for_state(state_id, state) else {
+            return Err(MermaidStateExportError::InvalidStateId { state_id: state_id.clone() });
+        };
+        match state.kind() {
+            StateNodeKind::Start | StateNodeKind::End => continue,
+            StateNodeKind::Choice | StateNodeKind::Fork | StateNodeKind::Join => {
+                out.push_str(&format!("state {mermaid_id} <<{}>>\n", state.kind().as_str()));
+            }
+            StateNodeKind::State if state.label() != mermaid_id => {
+                let label = state.label();
+                if label.contains(['"', '\n']) {
+                    return Err(MermaidStateExportError::InvalidStateLabel {
+                        state_id: state_id.clone(),
+                        label: label.to_owned(),
+                    });
+                }
+                out.push_str(&format!("state \"{label}\" as {mermaid_id}\n"));
+            }
+            StateNodeKind::State if !connected.contains(state_id) => {
+                out.push_str(mermaid_id);
+                out.push('\n');
+            }
+            StateNodeKind::State => {}
+        }
+        if let Some(note) = state.note() {
+            notes.push((mermaid_id, note));
+        }
+    }
+
+    for (transition_id, transition) in ast.transitions() {
+        let from = export_endpoint(ast, transition.from_state_id())?;
+        let to = export_endpoint(ast, transition.to_state_id())?;
+        out.push_str(&format!("{from} --> {to}"));
+        if let Some(label) = transition.label() {
+            if label.contains('\n') {
+                return Err(MermaidStateExportError::InvalidTransitionLabel {
+                    transition_id: transition_id.clone(),
+                    label: label.to_owned(),
+                });
+            }
+            out.push_str(&format!(" : {label}"));
+        }
+        out.push('\n');
+    }
+
+    for (mermaid_id, note) in notes {
+        if note.contains('\n') {
+            out.push_str(&format!("note right of {mermaid_id}\n"));
+            for line in note.lines() {
+                out.push_str(&format!("    {line}\n"));
+            }
+            out.push_str("end note\n");
+        } else {
+            out.push_str(&format!("note right of {mermaid_id} : {note}\n"));
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export_state_diagram, parse_state_diagram, MermaidStateParseError};
+    use crate::model::state_ast::{StateAst, StateNodeKind};
+    use crate::model::ObjectId;
+
+    fn oid(value: &str) -> ObjectId {
+        ObjectId::new(value).expect("object id")
+    }
+
+    #[test]
+    fn parses_states_transitions_pseudo_states_and_notes() {
+        let ast = parse_state_diagram(
+            "stateDiagram-v2\n\
+             %% comment\n\
+             direction LR\n\
+             [*] --> Still\n\
+             Still --> Moving : push\n\
+             state \"In motion\" as Moving\n\
+             state Check <<choice>>\n\
+             Moving --> Check\n\
+             Check --> [*]\n\
+             Crash : Crashed hard\n\
+             note right of Still\n\
+             \x20 idle\n\
+             \x20 waiting\n\
+             end note\n",
+        )
+        .expect("parse");
+
+        let start = &ast.states()[&StateAst::start_state_id()];
+        assert_eq!(start.kind(), StateNodeKind::Start);
+        assert_eq!(ast.states()[&StateAst::end_state_id()].kind(), StateNodeKind::End);
+        assert_eq!(ast.states()[&oid("s:Moving")].label(), "In motion");
+        assert_eq!(ast.states()[&oid("s:Check")].kind(), StateNodeKind::Choice);
+        assert_eq!(ast.states()[&oid("s:Crash")].label(), "Crashed hard");
+        assert_eq!(ast.states()[&oid("s:Still")].note(), Some("idle\nwaiting"));
+
+        let push = &ast.transitions()[&oid("t:0001")];
+        assert_eq!(push.from_state_id(), &oid("s:Still"));
+        assert_eq!(push.to_state_id(), &oid("s:Moving"));
+        assert_eq!(push.label(), Some("push"));
+        assert_eq!(ast.transitions().len(), 4);
+    }
+
+    #[test]
+    fn export_round_trips() {
+        let input = "stateDiagram-v2\n\
+                     state Check <<choice>>\n\
+                     Idle\n\
+                     state \"In motion\" as Moving\n\
+                     [*] --> Moving : go\n\
+                     Moving --> Check\n\
+                     Check --> [*]\n\
+                     note right of Moving : fast\n";
+        let ast = parse_state_diagram(input).expect("parse");
+        let exported = export_state_diagram(&ast).expect("export");
+        assert_eq!(parse_state_diagram(&exported).expect("reparse"), ast);
+    }
+
+    #[test]
+    fn rejects_composite_states_and_missing_header() {
+        assert_eq!(
+            parse_state_diagram("stateDiagram-v2\nstate Outer {\n}\n"),
+            Err(MermaidStateParseError::UnsupportedSyntax {
+                line_no: 2,
+                line: "state Outer {".to_owned()
+            })
+        );
+        assert_eq!(parse_state_diagram("flowchart\n"), Err(MermaidStateParseError::MissingHeader));
+    }
+}
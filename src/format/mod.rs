@@ -8,6 +8,16 @@
 
 //! Diagram format parsing/export.
 //!
-//! Currently this module focuses on a Mermaid-ish syntax for flowcharts and sequence diagrams.
+//! Currently this module focuses on a Mermaid-ish syntax for flowcharts and sequence diagrams,
+//! plus tabular (CSV/JSON) import for flowcharts. Diagrams also export to Graphviz DOT, rendered
+//! diagrams to SVG, PNG and PDF, and whole sessions to a static HTML site; those exports take
+//! their colors from a named [`theme::ExportTheme`].
 
+pub mod dot;
 pub mod mermaid;
+pub mod pdf;
+pub mod png;
+pub mod site;
+pub mod svg;
+pub mod tabular;
+pub mod theme;
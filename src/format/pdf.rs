@@ -0,0 +1,515 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! PDF handouts (`nereid export --pdf`).
+//!
+//! Diagrams are drawn from the same [`Scene`] as the SVG export, one diagram per A4 landscape
+//! page and scaled to fit. Walkthroughs follow with their step text; each step shows snapshots of
+//! the diagrams it references with those objects highlighted. Only the standard PDF fonts are
+//! used (Courier for diagram text), so characters outside WinAnsi print as `?`.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::model::{Diagram, DiagramId, DiagramKind, ObjectRef, Session, Walkthrough};
+use crate::render::{render_diagram_unicode_annotated, AnnotatedRender, DiagramRenderError};
+
+use super::svg::{Scene, Shape, Stroke, BASELINE_OFFSET, CELL_HEIGHT, CELL_WIDTH, FONT_SIZE};
+use super::theme::{rgb, ExportSelection, ExportStyle, ExportTheme};
+
+const PAGE_WIDTH: f32 = 842.0;
+const PAGE_HEIGHT: f32 = 595.0;
+const MARGIN: f32 = 36.0;
+const MAX_DIAGRAM_SCALE: f32 = 1.5;
+const SNAPSHOT_MAX_HEIGHT: f32 = 260.0;
+/// Upper bound for the advance of a character in the Helvetica faces, in ems; used for wrapping.
+const WRAP_EM: f32 = 0.6;
+
+#[derive(Debug, Clone, Copy)]
+enum Font {
+    Courier,
+    Helvetica,
+    HelveticaBold,
+}
+
+impl Font {
+    fn resource(self) -> &'static str {
+        match self {
+            Self::Courier => "F1",
+            Self::Helvetica => "F2",
+            Self::HelveticaBold => "F3",
+        }
+    }
+}
+
+/// Builds a PDF with every diagram of `session` followed by its walkthroughs, drawing diagrams
+/// in the colors of `style` with its selection marked.
+pub fn session_pdf(session: &Session, style: &ExportStyle) -> Vec<u8> {
+    let mut pages = PageWriter { theme: style.theme, ..PageWriter::default() };
+    let renders = session
+        .diagrams()
+        .iter()
+        .map(|(diagram_id, diagram)| (diagram_id, render_diagram_unicode_annotated(diagram)))
+        .collect::<BTreeMap<_, _>>();
+
+    for (diagram_id, diagram) in session.diagrams() {
+        pages.new_page();
+        diagram_page(&mut pages, diagram, &renders[diagram_id], style);
+    }
+    for walkthrough in session.walkthroughs().values() {
+        pages.new_page();
+        walkthrough_pages(&mut pages, session, walkthrough, &renders);
+    }
+    if pages.pages.is_empty() && pages.content.is_empty() {
+        pages.new_page();
+        pages.paragraph(Font::Helvetica, 11.0, "This session has no diagrams.");
+    }
+
+    write_document(&pages.finish(), session.title())
+}
+
+fn diagram_page(
+    pages: &mut PageWriter,
+    diagram: &Diagram,
+    render: &Result<AnnotatedRender, DiagramRenderError>,
+    style: &ExportStyle,
+) {
+    pages.paragraph(Font::HelveticaBold, 16.0, diagram.name());
+    pages.paragraph(
+        Font::Helvetica,
+        9.0,
+        &format!(
+            "{} · {} · rev {}",
+            diagram_kind_label(diagram),
+            diagram.diagram_id(),
+            diagram.rev()
+        ),
+    );
+    if let Some(description) = diagram.description() {
+        pages.paragraph(Font::Helvetica, 10.0, description);
+    }
+    pages.gap(8.0);
+
+    match render {
+        Ok(rendered) => {
+            let mut scene =
+                Scene::from_text(&rendered.text, &rendered.highlight_index, &style.selection);
+            scene.apply_object_styles(&style.object_styles);
+            let available = (PAGE_HEIGHT - MARGIN - pages.cursor).max(CELL_HEIGHT);
+            pages.scene(&scene, available);
+        }
+        Err(err) => pages.paragraph(Font::Helvetica, 10.0, &format!("Render failed: {err}")),
+    }
+}
+
+fn walkthrough_pages(
+    pages: &mut PageWriter,
+    session: &Session,
+    walkthrough: &Walkthrough,
+    renders: &BTreeMap<&DiagramId, Result<AnnotatedRender, DiagramRenderError>>,
+) {
+    pages.paragraph(Font::HelveticaBold, 16.0, walkthrough.title());
+    pages.paragraph(
+        Font::Helvetica,
+        9.0,
+        &format!("Walkthrough · {} steps", walkthrough.nodes().len()),
+    );
+
+    for (step, node) in walkthrough.nodes().iter().enumerate() {
+        pages.gap(10.0);
+        pages.ensure_space(40.0);
+        pages.paragraph(Font::HelveticaBold, 12.0, &format!("{}. {}", step + 1, node.title()));
+        if let Some(body) = node.body_md() {
+            pages.paragraph(Font::Helvetica, 10.0, body);
+        }
+
+        let mut refs_by_diagram = BTreeMap::<&DiagramId, BTreeSet<ObjectRef>>::new();
+        for object_ref in node.refs() {
+            refs_by_diagram.entry(object_ref.diagram_id()).or_default().insert(object_ref.clone());
+        }
+        for (diagram_id, refs) in refs_by_diagram {
+            let Some(Ok(rendered)) = renders.get(diagram_id) else {
+                continue;
+            };
+            let name = session.diagrams().get(diagram_id).map_or("", |diagram| diagram.name());
+            let selection = ExportSelection::selected(refs);
+            let scene = Scene::from_text(&rendered.text, &rendered.highlight_index, &selection);
+            let height =
+                (scene.height() * fit_scale(&scene, SNAPSHOT_MAX_HEIGHT)).min(SNAPSHOT_MAX_HEIGHT);
+            pages.gap(4.0);
+            pages.ensure_space(height + 14.0);
+            pages.paragraph(Font::Helvetica, 9.0, &format!("{name} ({diagram_id})"));
+            pages.scene(&scene, SNAPSHOT_MAX_HEIGHT);
+        }
+    }
+}
+
+fn diagram_kind_label(diagram: &Diagram) -> &'static str {
+    match diagram.kind() {
+        DiagramKind::Sequence => "Sequence",
+        DiagramKind::Flowchart => "Flowchart",
+        DiagramKind::State => "State",
+        DiagramKind::Class => "Class",
+    }
+}
+
+/// Scale at which `scene` fits the page width and `max_height`.
+fn fit_scale(scene: &Scene, max_height: f32) -> f32 {
+    let width = scene.width().max(1.0);
+    let height = scene.height().max(1.0);
+    ((PAGE_WIDTH - 2.0 * MARGIN) / width).min(max_height / height).min(MAX_DIAGRAM_SCALE)
+}
+
+/// Lays out content top to bottom, starting a new page when the current one is full.
+#[derive(Default)]
+struct PageWriter {
+    pages: Vec<Vec<u8>>,
+    content: Vec<u8>,
+    /// Distance from the top edge of the page to the next free line.
+    cursor: f32,
+    /// Colors for diagram scenes; page text stays black.
+    theme: ExportTheme,
+}
+
+impl PageWriter {
+    fn new_page(&mut self) {
+        if !self.content.is_empty() {
+            self.pages.push(std::mem::take(&mut self.content));
+        }
+        self.cursor = MARGIN;
+    }
+
+    fn ensure_space(&mut self, height: f32) {
+        if self.cursor + height > PAGE_HEIGHT - MARGIN && self.cursor > MARGIN {
+            self.new_page();
+        }
+    }
+
+    fn gap(&mut self, height: f32) {
+        self.cursor += height;
+    }
+
+    fn finish(mut self) -> Vec<Vec<u8>> {
+        self.new_page();
+        self.pages
+    }
+
+    /// Writes `text` wrapped to the page width, one source line per paragraph line.
+    fn paragraph(&mut self, font: Font, size: f32, text: &str) {
+        let max_chars = ((PAGE_WIDTH - 2.0 * MARGIN) / (size * WRAP_EM)) as usize;
+        let leading = size * 1.3;
+        for line in text.lines().flat_map(|line| wrap(line, max_chars)) {
+            self.ensure_space(leading);
+            let baseline = PAGE_HEIGHT - self.cursor - size;
+            self.content.extend_from_slice(
+                format!(
+                    "BT /{} {} Tf {} {} Td (",
+                    font.resource(),
+                    num(size),
+                    num(MARGIN),
+                    num(baseline)
+                )
+                .as_bytes(),
+            );
+            push_pdf_string(&mut self.content, &line);
+            self.content.extend_from_slice(b") Tj ET\n");
+            self.cursor += leading;
+        }
+    }
+
+    /// Draws `scene` at the cursor, scaled to the page width and `max_height`.
+    fn scene(&mut self, scene: &Scene, max_height: f32) {
+        let scale = fit_scale(scene, max_height);
+        let out = &mut self.content;
+        out.extend_from_slice(
+            format!(
+                "q {} 0 0 {} {} {} cm\n",
+                num(scale),
+                num(-scale),
+                num(MARGIN),
+                num(PAGE_HEIGHT - self.cursor)
+            )
+            .as_bytes(),
+        );
+
+        out.extend_from_slice(
+            format!(
+                "{} rg 0 0 {} {} re f\n",
+                pdf_color(self.theme.background),
+                num(scene.width()),
+                num(scene.height())
+            )
+            .as_bytes(),
+        );
+        let mut fill = None;
+        for &((y, x0, x1), highlight) in &scene.highlights {
+            if fill != Some(highlight) {
+                fill = Some(highlight);
+                out.extend_from_slice(
+                    format!("{} rg\n", pdf_color(self.theme.highlight_fill(highlight))).as_bytes(),
+                );
+            }
+            out.extend_from_slice(
+                format!(
+                    "{} {} {} {} re f\n",
+                    num(x0 as f32 * CELL_WIDTH),
+                    num(y as f32 * CELL_HEIGHT),
+                    num((x1 + 1 - x0) as f32 * CELL_WIDTH),
+                    num(CELL_HEIGHT)
+                )
+                .as_bytes(),
+            );
+        }
+
+        let ink = pdf_color(self.theme.ink);
+        out.extend_from_slice(format!("{ink} RG {ink} rg 0.8 w 1 J 1 j\n").as_bytes());
+        let mut dimmed = false;
+        for item in &scene.items {
+            if scene.is_dimmed(item) != dimmed {
+                dimmed = !dimmed;
+                let color = pdf_color(if dimmed { self.theme.muted } else { self.theme.ink });
+                out.extend_from_slice(format!("{color} RG {color} rg\n").as_bytes());
+            }
+            match &item.shape {
+                Shape::Line { from, to, stroke } => {
+                    let style = match stroke {
+                        Stroke::Thin => "",
+                        Stroke::Thick => "1.6 w ",
+                        Stroke::Dashed => "[2 2] 0 d ",
+                    };
+                    out.extend_from_slice(
+                        format!(
+                            "{style}{} {} m {} {} l S{}\n",
+                            num(from.0),
+                            num(from.1),
+                            num(to.0),
+                            num(to.1),
+                            if style.is_empty() { "" } else { " 0.8 w [] 0 d" }
+                        )
+                        .as_bytes(),
+                    );
+                }
+                Shape::Polygon { points, filled } => {
+                    for (idx, (x, y)) in points.iter().enumerate() {
+                        let op = if idx == 0 { "m" } else { "l" };
+                        out.extend_from_slice(format!("{} {} {op} ", num(*x), num(*y)).as_bytes());
+                    }
+                    out.extend_from_slice(if *filled { b"h f\n" } else { b"h S\n" });
+                }
+                Shape::Circle { center, radius, filled } => {
+                    push_circle(out, *center, *radius);
+                    out.extend_from_slice(if *filled { b"f\n" } else { b"S\n" });
+                }
+                Shape::Text { row, column, text } => {
+                    // The scene is drawn with a flipped y axis; flip the text matrix back.
+                    out.extend_from_slice(
+                        format!(
+                            "BT /{} {} Tf 1 0 0 -1 {} {} Tm (",
+                            Font::Courier.resource(),
+                            num(FONT_SIZE),
+                            num(*column as f32 * CELL_WIDTH),
+                            num(*row as f32 * CELL_HEIGHT + BASELINE_OFFSET)
+                        )
+                        .as_bytes(),
+                    );
+                    push_pdf_string(out, text);
+                    out.extend_from_slice(b") Tj ET\n");
+                }
+            }
+        }
+        out.extend_from_slice(b"Q\n");
+        self.cursor += scene.height() * scale;
+    }
+}
+
+fn push_circle(out: &mut Vec<u8>, (cx, cy): (f32, f32), r: f32) {
+    // Four cubic Béziers, one per quadrant.
+    let k = 0.552_284_8 * r;
+    let quadrants = [
+        [(cx + r, cy + k), (cx + k, cy + r), (cx, cy + r)],
+        [(cx - k, cy + r), (cx - r, cy + k), (cx - r, cy)],
+        [(cx - r, cy - k), (cx - k, cy - r), (cx, cy - r)],
+        [(cx + k, cy - r), (cx + r, cy - k), (cx + r, cy)],
+    ];
+    out.extend_from_slice(format!("{} {} m ", num(cx + r), num(cy)).as_bytes());
+    for points in quadrants {
+        for (x, y) in points {
+            out.extend_from_slice(format!("{} {} ", num(x), num(y)).as_bytes());
+        }
+        out.extend_from_slice(b"c ");
+    }
+}
+
+fn wrap(line: &str, max_chars: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in line.split(' ') {
+        let current_len = current.chars().count();
+        if current_len > 0 && current_len + 1 + word.chars().count() > max_chars {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    lines.push(current);
+    lines
+}
+
+/// Appends `text` as the body of a PDF literal string in WinAnsi encoding.
+fn push_pdf_string(out: &mut Vec<u8>, text: &str) {
+    for ch in text.chars() {
+        let byte = match ch {
+            '(' | ')' | '\\' => {
+                out.push(b'\\');
+                ch as u8
+            }
+            ' '..='~' => ch as u8,
+            '\u{a0}'..='\u{ff}' => ch as u32 as u8,
+            '…' => 0x85,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            _ => b'?',
+        };
+        out.push(byte);
+    }
+}
+
+fn write_document(pages: &[Vec<u8>], title: Option<&str>) -> Vec<u8> {
+    const FONTS: [&str; 3] = ["Courier", "Helvetica", "Helvetica-Bold"];
+    // Objects: 1 catalog, 2 page tree, 3 info, 4..=6 fonts, then a page and its content per page.
+    let first_page_id = 4 + FONTS.len();
+    let mut objects = Vec::<Vec<u8>>::new();
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    let kids = (0..pages.len())
+        .map(|idx| format!("{} 0 R", first_page_id + 2 * idx))
+        .collect::<Vec<_>>()
+        .join(" ");
+    objects.push(format!("<< /Type /Pages /Kids [{kids}] /Count {} >>", pages.len()).into_bytes());
+    let mut info = b"<< /Producer (nereid)".to_vec();
+    if let Some(title) = title {
+        info.extend_from_slice(b" /Title (");
+        push_pdf_string(&mut info, title);
+        info.push(b')');
+    }
+    info.extend_from_slice(b" >>");
+    objects.push(info);
+    for font in FONTS {
+        objects.push(
+            format!(
+                "<< /Type /Font /Subtype /Type1 /BaseFont /{font} /Encoding /WinAnsiEncoding >>"
+            )
+            .into_bytes(),
+        );
+    }
+    for (idx, content) in pages.iter().enumerate() {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << \
+                 /F1 4 0 R /F2 5 0 R /F3 6 0 R >> >> /Contents {} 0 R >>",
+                num(PAGE_WIDTH),
+                num(PAGE_HEIGHT),
+                first_page_id + 2 * idx + 1
+            )
+            .into_bytes(),
+        );
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend_from_slice(content);
+        stream.extend_from_slice(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut out = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (idx, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n", idx + 1).as_bytes());
+        out.extend_from_slice(object);
+        out.extend_from_slice(b"\nendobj\n");
+    }
+    let xref_offset = out.len();
+    out.extend_from_slice(
+        format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+    );
+    for offset in offsets {
+        out.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+    }
+    out.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R /Info 3 0 R >>\nstartxref\n{xref_offset}\n%%EOF\n",
+            objects.len() + 1
+        )
+        .as_bytes(),
+    );
+    out
+}
+
+/// `#rrggbb` as PDF color operands (`r g b`).
+fn pdf_color(hex: &str) -> String {
+    rgb(hex).map(num).join(" ")
+}
+
+fn num(value: f32) -> String {
+    let formatted = format!("{value:.3}");
+    formatted.trim_end_matches('0').trim_end_matches('.').to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{push_pdf_string, session_pdf, wrap};
+    use crate::format::theme::{ExportStyle, ExportTheme};
+    use crate::tui::demo_session;
+
+    #[test]
+    fn writes_one_page_per_diagram_plus_walkthrough_pages_with_a_valid_xref() {
+        let session = demo_session();
+        let pdf = session_pdf(&session, &ExportStyle::default());
+        let text = String::from_utf8_lossy(&pdf);
+
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        let page_count = text.matches("/Type /Page ").count();
+        assert!(page_count >= session.diagrams().len() + session.walkthroughs().len());
+        assert!(text.contains(&format!("/Count {page_count} >>")));
+
+        let startxref = text.rfind("startxref\n").expect("startxref") + "startxref\n".len();
+        let xref_offset =
+            text[startxref..].lines().next().expect("offset").parse::<usize>().expect("number");
+        assert!(pdf[xref_offset..].starts_with(b"xref\n"));
+        let xref_table = std::str::from_utf8(&pdf[xref_offset..]).expect("ascii xref table");
+        let first_entry = xref_table.lines().nth(3).expect("object 1 entry");
+        let object_offset = first_entry[..10].parse::<usize>().expect("offset");
+        assert!(pdf[object_offset..].starts_with(b"1 0 obj\n"));
+
+        for diagram in session.diagrams().values() {
+            let mut title = b"(".to_vec();
+            push_pdf_string(&mut title, diagram.name());
+            title.extend_from_slice(b") Tj");
+            assert!(pdf.windows(title.len()).any(|window| window == title.as_slice()));
+        }
+
+        let style = ExportStyle { theme: ExportTheme::DARK, ..ExportStyle::default() };
+        let dark = String::from_utf8_lossy(&session_pdf(&session, &style)).into_owned();
+        assert!(dark.contains("0.051 0.067 0.09 rg 0 0 "));
+        assert!(dark.contains("0.902 0.929 0.953 RG 0.902 0.929 0.953 rg"));
+        assert!(!dark.contains("0.141 0.161 0.184 RG"));
+    }
+
+    #[test]
+    fn escapes_and_encodes_text_for_win_ansi() {
+        let mut out = Vec::new();
+        push_pdf_string(&mut out, "a(b)\\ é… α");
+        assert_eq!(out, b"a\\(b\\)\\\\ \xe9\x85 ?");
+        assert_eq!(wrap("one two three", 7), vec!["one two", "three"]);
+    }
+}
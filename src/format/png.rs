@@ -0,0 +1,131 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! PNG export: rasterizes the SVG export with `resvg`.
+//!
+//! Text is set in an embedded DejaVu Sans Mono (see `assets/fonts/LICENSE-DejaVu.txt`) instead of
+//! system fonts, so the output is identical on every machine.
+
+use std::fmt;
+use std::sync::Arc;
+
+use resvg::{tiny_skia, usvg};
+
+use crate::model::Diagram;
+use crate::render::DiagramRenderError;
+
+use super::svg::diagram_svg;
+use super::theme::ExportStyle;
+
+const EMBEDDED_MONOSPACE_FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSansMono.ttf");
+const EMBEDDED_MONOSPACE_FAMILY: &str = "DejaVu Sans Mono";
+
+#[derive(Debug)]
+pub enum PngExportError {
+    Render(DiagramRenderError),
+    Svg(usvg::Error),
+    /// The scale is not a positive finite number, or the scaled image has no pixels.
+    InvalidScale {
+        scale: f32,
+    },
+    Encode(String),
+}
+
+impl fmt::Display for PngExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Render(err) => write!(f, "{err}"),
+            Self::Svg(err) => write!(f, "svg error: {err}"),
+            Self::InvalidScale { scale } => write!(f, "invalid png scale: {scale}"),
+            Self::Encode(message) => write!(f, "png encode error: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for PngExportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Render(err) => Some(err),
+            Self::Svg(err) => Some(err),
+            Self::InvalidScale { .. } | Self::Encode(_) => None,
+        }
+    }
+}
+
+impl From<DiagramRenderError> for PngExportError {
+    fn from(err: DiagramRenderError) -> Self {
+        Self::Render(err)
+    }
+}
+
+/// Renders `diagram` as a PNG at `scale` times the SVG export's size, in the colors of `style`.
+pub fn diagram_png(
+    diagram: &Diagram,
+    scale: f32,
+    style: &ExportStyle,
+) -> Result<Vec<u8>, PngExportError> {
+    svg_to_png(&diagram_svg(diagram, style)?, scale)
+}
+
+/// Rasterizes an SVG document at `scale` times its intrinsic size.
+pub fn svg_to_png(svg: &str, scale: f32) -> Result<Vec<u8>, PngExportError> {
+    rasterize(svg, scale)?.encode_png().map_err(|err| PngExportError::Encode(err.to_string()))
+}
+
+fn rasterize(svg: &str, scale: f32) -> Result<tiny_skia::Pixmap, PngExportError> {
+    if !scale.is_finite() || scale <= 0.0 {
+        return Err(PngExportError::InvalidScale { scale });
+    }
+
+    let mut options = usvg::Options::default();
+    let mut fontdb = usvg::fontdb::Database::new();
+    fontdb.load_font_data(EMBEDDED_MONOSPACE_FONT.to_vec());
+    fontdb.set_monospace_family(EMBEDDED_MONOSPACE_FAMILY);
+    options.fontdb = Arc::new(fontdb);
+    options.font_family = EMBEDDED_MONOSPACE_FAMILY.to_owned();
+
+    let tree = usvg::Tree::from_str(svg, &options).map_err(PngExportError::Svg)?;
+    let size =
+        tree.size().to_int_size().scale_by(scale).ok_or(PngExportError::InvalidScale { scale })?;
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or(PngExportError::InvalidScale { scale })?;
+    resvg::render(&tree, tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+    Ok(pixmap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diagram_png, rasterize, svg_to_png, PngExportError};
+    use crate::format::theme::ExportStyle;
+    use crate::tui::demo_session;
+
+    fn png_size(png: &[u8]) -> (u32, u32) {
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        let width = u32::from_be_bytes(png[16..20].try_into().expect("width"));
+        let height = u32::from_be_bytes(png[20..24].try_into().expect("height"));
+        (width, height)
+    }
+
+    #[test]
+    fn rasterizes_at_the_requested_scale_with_the_embedded_font() {
+        let svg = "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"40\" height=\"20\" \
+                   font-family=\"monospace\"><text x=\"2\" y=\"14\">Hi</text></svg>";
+        assert_eq!(png_size(&svg_to_png(svg, 1.0).expect("png")), (40, 20));
+        assert_eq!(png_size(&svg_to_png(svg, 2.0).expect("png")), (80, 40));
+        assert!(matches!(svg_to_png(svg, 0.0), Err(PngExportError::InvalidScale { .. })));
+        // Nothing but the text is drawn, so ink means the embedded font resolved `monospace`.
+        let pixmap = rasterize(svg, 1.0).expect("pixmap");
+        assert!(pixmap.pixels().iter().any(|pixel| pixel.alpha() > 0));
+
+        let session = demo_session();
+        let diagram = session.diagrams().values().next().expect("demo diagram");
+        let (width, height) =
+            png_size(&diagram_png(diagram, 2.0, &ExportStyle::default()).expect("diagram png"));
+        assert!(width > 0 && height > 0);
+    }
+}
@@ -0,0 +1,674 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Static documentation site export (`nereid publish`).
+//!
+//! The site is flat so it can be served from any subpath (e.g. GitHub Pages): an `index.html`,
+//! one page per diagram and walkthrough, plus a shared stylesheet and script. Diagram pages
+//! embed the Unicode render with every cell tagged by the object it belongs to, so hovering or
+//! clicking highlights an object; `#<object-ref>` in the URL pins one, which is how xrefs and
+//! walkthrough steps link into diagrams. Colors come from the export theme, and cells of objects
+//! in the export selection carry `selected`/`agent` classes.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::model::{
+    Diagram, DiagramId, DiagramKind, ObjectRef, Session, Walkthrough, WalkthroughId,
+};
+use crate::render::{
+    highlight_cell_owners, render_diagram_unicode_annotated, render_walkthrough_unicode,
+    HighlightIndex,
+};
+
+use super::theme::{ExportStyle, ExportTheme};
+
+fn style_css(theme: &ExportTheme) -> String {
+    format!(
+        "\
+body {{ font-family: system-ui, sans-serif; margin: 2rem auto; max-width: 72rem; padding: 0 1rem; \
+background: {background}; color: {ink}; }}
+a {{ color: inherit; }}
+pre.diagram {{ font-family: ui-monospace, monospace; line-height: 1.15; overflow-x: auto; \
+padding: 1rem; background: {panel}; border-radius: 6px; }}
+pre.diagram [data-ref] {{ cursor: pointer; }}
+pre.diagram .selected {{ background: {selected}; }}
+pre.diagram .agent {{ background: {agent}; }}
+pre.diagram .selected.agent {{ background: {both}; }}
+pre.diagram .hl {{ background: {selected}; }}
+pre.diagram .dim {{ opacity: 0.4; }}
+pre.diagram .heavy {{ font-weight: bold; }}
+#status {{ font-family: ui-monospace, monospace; min-height: 1.2em; color: {muted}; }}
+.dangling {{ color: {error}; }}
+.step-body {{ white-space: pre-wrap; }}
+",
+        background = theme.background,
+        ink = theme.ink,
+        panel = theme.panel,
+        selected = theme.selected,
+        agent = theme.agent,
+        both = theme.both,
+        muted = theme.muted,
+        error = theme.error,
+    )
+}
+
+const SITE_JS: &str = r##"document.addEventListener("DOMContentLoaded", () => {
+  const status = document.getElementById("status");
+  const cells = (ref) =>
+    ref ? document.querySelectorAll(`pre.diagram [data-ref="${CSS.escape(ref)}"]`) : [];
+  const mark = (ref, on) => cells(ref).forEach((el) => el.classList.toggle("hl", on));
+  const show = (ref) => { if (status) status.textContent = ref || ""; };
+  let pinned = decodeURIComponent(location.hash.slice(1)) || null;
+  document.querySelectorAll("pre.diagram [data-ref]").forEach((el) => {
+    const ref = el.dataset.ref;
+    el.addEventListener("mouseenter", () => { mark(ref, true); show(ref); });
+    el.addEventListener("mouseleave", () => {
+      if (ref !== pinned) mark(ref, false);
+      show(pinned);
+    });
+    el.addEventListener("click", () => {
+      mark(pinned, false);
+      pinned = ref;
+      mark(pinned, true);
+      show(pinned);
+      history.replaceState(null, "", "#" + encodeURIComponent(pinned));
+    });
+  });
+  if (pinned) {
+    mark(pinned, true);
+    show(pinned);
+    const first = cells(pinned)[0];
+    if (first) first.scrollIntoView({ block: "center" });
+  }
+});
+"##;
+
+#[derive(Debug)]
+pub enum PublishError {
+    Io { path: PathBuf, source: io::Error },
+}
+
+impl fmt::Display for PublishError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "io error at {path:?}: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for PublishError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+        }
+    }
+}
+
+/// Files written by [`publish_site`], relative to the output directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublishedSite {
+    pub files: Vec<PathBuf>,
+}
+
+/// File name of the page for `diagram_id`.
+pub fn diagram_page_name(diagram_id: &DiagramId) -> String {
+    format!("diagram-{}.html", page_slug(diagram_id.as_str()))
+}
+
+/// File name of the page for `walkthrough_id`.
+pub fn walkthrough_page_name(walkthrough_id: &WalkthroughId) -> String {
+    format!("walkthrough-{}.html", page_slug(walkthrough_id.as_str()))
+}
+
+/// Link to `object_ref` on its diagram page, pinned via the URL fragment.
+pub fn object_ref_href(object_ref: &ObjectRef) -> String {
+    format!(
+        "{}#{}",
+        diagram_page_name(object_ref.diagram_id()),
+        percent_encode_fragment(&object_ref.to_string())
+    )
+}
+
+/// Writes the static site for `session` into `out_dir` in the colors of `style`, creating it if
+/// needed.
+///
+/// Diagrams that fail to render still get a page, showing the render error instead.
+pub fn publish_site(
+    session: &Session,
+    out_dir: &Path,
+    style: &ExportStyle,
+) -> Result<PublishedSite, PublishError> {
+    fs::create_dir_all(out_dir)
+        .map_err(|source| PublishError::Io { path: out_dir.to_path_buf(), source })?;
+
+    let mut pages = BTreeMap::<String, String>::new();
+    pages.insert("style.css".to_owned(), style_css(&style.theme));
+    pages.insert("site.js".to_owned(), SITE_JS.to_owned());
+    pages.insert("index.html".to_owned(), index_page(session));
+    for (diagram_id, diagram) in session.diagrams() {
+        pages.insert(diagram_page_name(diagram_id), diagram_page(session, diagram, style));
+    }
+    for (walkthrough_id, walkthrough) in session.walkthroughs() {
+        pages.insert(walkthrough_page_name(walkthrough_id), walkthrough_page(session, walkthrough));
+    }
+
+    let mut files = Vec::with_capacity(pages.len());
+    for (name, contents) in pages {
+        let path = out_dir.join(&name);
+        fs::write(&path, contents).map_err(|source| PublishError::Io { path, source })?;
+        files.push(PathBuf::from(name));
+    }
+    Ok(PublishedSite { files })
+}
+
+fn index_page(session: &Session) -> String {
+    let title = session.title().unwrap_or(session.session_id().as_str());
+    let mut body = format!("<h1>{}</h1>\n", escape_html(title));
+    if let Some(description) = session.description() {
+        body.push_str(&markdown_html(description));
+    }
+
+    body.push_str("<h2>Diagrams</h2>\n<ul>\n");
+    for (diagram_id, diagram) in session.diagrams() {
+        body.push_str(&format!(
+            "<li><a href=\"{}\">{}</a> <small>{} · {}</small>",
+            diagram_page_name(diagram_id),
+            escape_html(diagram.name()),
+            diagram_kind_label(diagram.kind()),
+            escape_html(diagram_id.as_str()),
+        ));
+        // The list only has room for the description's first line.
+        if let Some(line) = diagram.description().and_then(|text| text.lines().next()) {
+            let line = line.trim_start_matches(['#', '-', '*', ' ']);
+            body.push_str(&format!(" — {}", inline_markdown_html(line)));
+        }
+        body.push_str("</li>\n");
+    }
+    body.push_str("</ul>\n");
+
+    if !session.walkthroughs().is_empty() {
+        body.push_str("<h2>Walkthroughs</h2>\n<ul>\n");
+        for (walkthrough_id, walkthrough) in session.walkthroughs() {
+            body.push_str(&format!(
+                "<li><a href=\"{}\">{}</a> <small>{} steps</small></li>\n",
+                walkthrough_page_name(walkthrough_id),
+                escape_html(walkthrough.title()),
+                walkthrough.nodes().len(),
+            ));
+        }
+        body.push_str("</ul>\n");
+    }
+
+    html_page(title, &body)
+}
+
+fn diagram_page(session: &Session, diagram: &Diagram, style: &ExportStyle) -> String {
+    let diagram_id = diagram.diagram_id();
+    let mut body = format!(
+        "<p><a href=\"index.html\">← Index</a></p>\n<h1>{}</h1>\n\
+         <p><small>{} · {} · rev {}</small></p>\n",
+        escape_html(diagram.name()),
+        diagram_kind_label(diagram.kind()),
+        escape_html(diagram_id.as_str()),
+        diagram.rev(),
+    );
+    if let Some(description) = diagram.description() {
+        body.push_str(&markdown_html(description));
+    }
+
+    match render_diagram_unicode_annotated(diagram) {
+        Ok(rendered) => {
+            body.push_str("<pre class=\"diagram\">");
+            body.push_str(&annotated_html(&rendered.text, &rendered.highlight_index, style));
+            body.push_str("</pre>\n<p id=\"status\"></p>\n");
+        }
+        Err(err) => {
+            body.push_str(&format!(
+                "<p class=\"dangling\">Render failed: {}</p>\n",
+                escape_html(&err.to_string())
+            ));
+        }
+    }
+
+    let xrefs = session
+        .xrefs()
+        .values()
+        .filter(|xref| {
+            xref.from().diagram_id() == diagram_id || xref.to().diagram_id() == diagram_id
+        })
+        .collect::<Vec<_>>();
+    if !xrefs.is_empty() {
+        body.push_str("<h2>Cross references</h2>\n<ul>\n");
+        for xref in xrefs {
+            let class = if xref.status().is_dangling() { " class=\"dangling\"" } else { "" };
+            body.push_str(&format!(
+                "<li{class}>{} <code>{}</code> → {}{}</li>\n",
+                object_ref_link(session, xref.from()),
+                escape_html(xref.kind()),
+                object_ref_link(session, xref.to()),
+                xref.label().map(|label| format!(" — {}", escape_html(label))).unwrap_or_default(),
+            ));
+        }
+        body.push_str("</ul>\n");
+    }
+
+    html_page(diagram.name(), &body)
+}
+
+fn walkthrough_page(session: &Session, walkthrough: &Walkthrough) -> String {
+    let mut body = format!(
+        "<p><a href=\"index.html\">← Index</a></p>\n<h1>{}</h1>\n",
+        escape_html(walkthrough.title())
+    );
+    match render_walkthrough_unicode(walkthrough) {
+        Ok(text) => {
+            body.push_str(&format!("<pre class=\"diagram\">{}</pre>\n", escape_html(&text)))
+        }
+        Err(err) => body.push_str(&format!(
+            "<p class=\"dangling\">Render failed: {}</p>\n",
+            escape_html(&err.to_string())
+        )),
+    }
+
+    body.push_str("<ol>\n");
+    for node in walkthrough.nodes() {
+        body.push_str(&format!(
+            "<li id=\"{}\"><h2>{}</h2>\n",
+            escape_html(node.node_id().as_str()),
+            escape_html(node.title())
+        ));
+        if let Some(body_md) = node.body_md() {
+            body.push_str(&format!("<div class=\"step-body\">{}</div>\n", escape_html(body_md)));
+        }
+        if !node.refs().is_empty() {
+            let links = node
+                .refs()
+                .iter()
+                .map(|object_ref| object_ref_link(session, object_ref))
+                .collect::<Vec<_>>();
+            body.push_str(&format!("<p>See: {}</p>\n", links.join(", ")));
+        }
+        body.push_str("</li>\n");
+    }
+    body.push_str("</ol>\n");
+
+    html_page(walkthrough.title(), &body)
+}
+
+/// Link into the diagram page for refs whose diagram exists; plain (dangling) text otherwise.
+fn object_ref_link(session: &Session, object_ref: &ObjectRef) -> String {
+    let text = escape_html(&object_ref.to_string());
+    if session.object_ref_is_missing(object_ref) {
+        return format!("<span class=\"dangling\">{text}</span>");
+    }
+    format!("<a href=\"{}\"><code>{text}</code></a>", escape_html(&object_ref_href(object_ref)))
+}
+
+/// Renders `text` as HTML with each highlighted cell wrapped in a `data-ref` span, classed by how
+/// `style` marks and styles its object.
+fn annotated_html(text: &str, highlight_index: &HighlightIndex, style: &ExportStyle) -> String {
+    let owners = highlight_cell_owners(text, highlight_index);
+    let mut out = String::new();
+    for (y, line) in text.split('\n').enumerate() {
+        if y > 0 {
+            out.push('\n');
+        }
+        let line = line.chars().collect::<Vec<_>>();
+        let row = &owners[y];
+        let mut x = 0;
+        while x < line.len() {
+            let owner = row.get(x).copied().flatten();
+            let run_end = (x..line.len())
+                .find(|&end| row.get(end).copied().flatten() != owner)
+                .unwrap_or(line.len());
+            let run = escape_html(&line[x..run_end].iter().collect::<String>());
+            match owner {
+                Some(object_ref) => out.push_str(&format!(
+                    "<span{} data-ref=\"{}\">{run}</span>",
+                    span_class(object_ref, style),
+                    escape_html(&object_ref.to_string())
+                )),
+                None => out.push_str(&run),
+            }
+            x = run_end;
+        }
+    }
+    out
+}
+
+/// `class` attribute for the cells of `object_ref`: its export mark plus styling rule effects.
+fn span_class(object_ref: &ObjectRef, style: &ExportStyle) -> String {
+    let object_style = style.object_styles.get(object_ref).copied().unwrap_or_default();
+    let classes = [
+        style.selection.highlight(object_ref).map(|highlight| highlight.class_name()),
+        object_style.dim.then_some("dim"),
+        object_style.heavy.then_some("heavy"),
+    ];
+    let classes = classes.into_iter().flatten().collect::<Vec<_>>();
+    if classes.is_empty() {
+        String::new()
+    } else {
+        format!(" class=\"{}\"", classes.join(" "))
+    }
+}
+
+fn html_page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{}</title>\n<link rel=\"stylesheet\" href=\"style.css\">\n\
+         <script src=\"site.js\" defer></script>\n</head>\n<body>\n{body}</body>\n</html>\n",
+        escape_html(title)
+    )
+}
+
+fn diagram_kind_label(kind: DiagramKind) -> &'static str {
+    match kind {
+        DiagramKind::Sequence => "Sequence",
+        DiagramKind::Flowchart => "Flowchart",
+        DiagramKind::State => "State",
+        DiagramKind::Class => "Class",
+    }
+}
+
+/// File-name-safe form of an id: ASCII letters, digits, `-` and `_` pass through, every other byte
+/// becomes `~xx`, so distinct ids never share a page.
+pub fn page_slug(id: &str) -> String {
+    let mut out = String::with_capacity(id.len());
+    for byte in id.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("~{byte:02x}"));
+        }
+    }
+    out
+}
+
+fn percent_encode_fragment(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
+
+/// Renders the Markdown used in descriptions: `#` headings (shifted below the page's `h1`/`h2`),
+/// `-`/`*`/`1.` lists, fenced code blocks and paragraphs, with inline code, `**strong**`, `*em*`
+/// and `[links](url)`. Everything else is escaped, raw HTML included, and links only keep
+/// `http(s)`, `mailto` and relative targets, so the result is safe to embed.
+fn markdown_html(markdown: &str) -> String {
+    fn flush_paragraph(out: &mut String, paragraph: &mut Vec<&str>) {
+        if !paragraph.is_empty() {
+            out.push_str(&format!("<p>{}</p>\n", inline_markdown_html(&paragraph.join(" "))));
+            paragraph.clear();
+        }
+    }
+    fn close_list(out: &mut String, list: &mut Option<&'static str>) {
+        if let Some(tag) = list.take() {
+            out.push_str(&format!("</{tag}>\n"));
+        }
+    }
+
+    let mut out = String::new();
+    let mut paragraph = Vec::<&str>::new();
+    let mut list = None::<&'static str>;
+    let mut code = None::<Vec<&str>>;
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if let Some(lines) = code.as_mut() {
+            if trimmed.starts_with("```") {
+                out.push_str(&format!(
+                    "<pre><code>{}</code></pre>\n",
+                    escape_html(&lines.join("\n"))
+                ));
+                code = None;
+            } else {
+                lines.push(line);
+            }
+            continue;
+        }
+
+        let heading = trimmed.split_once(' ').filter(|(hashes, _)| {
+            (1..=4).contains(&hashes.len()) && hashes.bytes().all(|b| b == b'#')
+        });
+        let unordered = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* "));
+        let ordered = trimmed
+            .split_once(". ")
+            .filter(|(number, _)| !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()))
+            .map(|(_, item)| item);
+
+        if trimmed.starts_with("```") || trimmed.is_empty() || heading.is_some() {
+            flush_paragraph(&mut out, &mut paragraph);
+            close_list(&mut out, &mut list);
+        }
+        if trimmed.starts_with("```") {
+            code = Some(Vec::new());
+        } else if let Some((hashes, text)) = heading {
+            let level = hashes.len() + 2;
+            out.push_str(&format!("<h{level}>{}</h{level}>\n", inline_markdown_html(text.trim())));
+        } else if let Some((tag, item)) =
+            unordered.map(|item| ("ul", item)).or(ordered.map(|item| ("ol", item)))
+        {
+            flush_paragraph(&mut out, &mut paragraph);
+            if list != Some(tag) {
+                close_list(&mut out, &mut list);
+                out.push_str(&format!("<{tag}>\n"));
+                list = Some(tag);
+            }
+            out.push_str(&format!("<li>{}</li>\n", inline_markdown_html(item.trim())));
+        } else if !trimmed.is_empty() {
+            close_list(&mut out, &mut list);
+            paragraph.push(trimmed);
+        }
+    }
+    if let Some(lines) = code {
+        out.push_str(&format!("<pre><code>{}</code></pre>\n", escape_html(&lines.join("\n"))));
+    }
+    flush_paragraph(&mut out, &mut paragraph);
+    close_list(&mut out, &mut list);
+    out
+}
+
+/// The inline part of [`markdown_html`] for one line or paragraph of text.
+fn inline_markdown_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(ch) = rest.chars().next() {
+        let closed = |marker: &str| {
+            rest.strip_prefix(marker)
+                .and_then(|after| {
+                    after.find(marker).map(|end| (&after[..end], &after[end + marker.len()..]))
+                })
+                .filter(|(inner, _)| !inner.is_empty() && inner.trim() == *inner)
+        };
+        if let Some((inner, after)) = closed("`") {
+            out.push_str(&format!("<code>{}</code>", escape_html(inner)));
+            rest = after;
+        } else if let Some((inner, after)) = closed("**") {
+            out.push_str(&format!("<strong>{}</strong>", inline_markdown_html(inner)));
+            rest = after;
+        } else if let Some((inner, after)) = closed("*") {
+            out.push_str(&format!("<em>{}</em>", inline_markdown_html(inner)));
+            rest = after;
+        } else if let Some((label, url, after)) = markdown_link(rest) {
+            let label = inline_markdown_html(label);
+            if is_safe_link(url) {
+                out.push_str(&format!("<a href=\"{}\">{label}</a>", escape_html(url)));
+            } else {
+                out.push_str(&label);
+            }
+            rest = after;
+        } else {
+            out.push_str(&escape_html(&rest[..ch.len_utf8()]));
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+    out
+}
+
+/// Splits `[label](url)` off the start of `text`, returning the label, url and remaining text;
+/// the url may hold balanced parentheses, as Wikipedia links do.
+fn markdown_link(text: &str) -> Option<(&str, &str, &str)> {
+    let after_open = text.strip_prefix('[')?;
+    let (label, after_label) = after_open.split_once("](")?;
+    let mut depth = 0usize;
+    let close = after_label.char_indices().find_map(|(idx, ch)| match ch {
+        '(' => {
+            depth += 1;
+            None
+        }
+        ')' if depth == 0 => Some(idx),
+        ')' => {
+            depth -= 1;
+            None
+        }
+        _ => None,
+    })?;
+    let (url, after) = (&after_label[..close], &after_label[close + 1..]);
+    (!label.is_empty() && !label.contains('[') && !url.contains(char::is_whitespace))
+        .then_some((label, url, after))
+}
+
+/// Keeps links that cannot run script: `http(s)`/`mailto` URLs and scheme-less relative ones.
+fn is_safe_link(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    if ["http://", "https://", "mailto:"].iter().any(|scheme| lower.starts_with(scheme)) {
+        return true;
+    }
+    let path_start = url.find(['/', '?', '#']).unwrap_or(url.len());
+    !url.is_empty() && !url[..path_start].contains(':')
+}
+
+fn escape_html(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use super::{
+        annotated_html, diagram_page_name, markdown_html, object_ref_href, page_slug, publish_site,
+    };
+    use crate::format::theme::{ExportSelection, ExportStyle, ExportTheme};
+    use crate::model::{ObjectRef, ObjectStyle};
+    use crate::render::HighlightIndex;
+    use crate::tui::demo_session;
+
+    #[test]
+    fn annotated_html_tags_cells_with_the_narrowest_owner() {
+        let node = ObjectRef::parse("d:flow/flow/node/n:a").expect("ref");
+        let edge = ObjectRef::parse("d:flow/flow/edge/e:ab").expect("ref");
+        let mut index = HighlightIndex::new();
+        index.insert(node.clone(), vec![(0, 0, 4)]);
+        index.insert(edge.clone(), vec![(0, 2, 2)]);
+        let style = ExportStyle {
+            selection: ExportSelection { selected: BTreeSet::new(), agent: BTreeSet::from([edge]) },
+            object_styles: BTreeMap::from([(
+                node.clone(),
+                ObjectStyle { dim: true, heavy: false },
+            )]),
+            ..ExportStyle::default()
+        };
+
+        assert_eq!(
+            annotated_html("a<b>c\nxy", &index, &style),
+            "<span class=\"dim\" data-ref=\"d:flow/flow/node/n:a\">a&lt;</span>\
+             <span class=\"agent\" data-ref=\"d:flow/flow/edge/e:ab\">b</span>\
+             <span class=\"dim\" data-ref=\"d:flow/flow/node/n:a\">&gt;c</span>\nxy"
+        );
+        assert_eq!(page_slug("demo-flow"), "demo-flow");
+        assert_eq!(page_slug("a:b"), "a~3ab");
+        assert_eq!(object_ref_href(&node), "diagram-flow.html#d%3Aflow%2Fflow%2Fnode%2Fn%3Aa");
+    }
+
+    #[test]
+    fn markdown_descriptions_render_as_sanitized_html() {
+        let markdown = "# Checkout\n\nHandles **payment** and `retry_count` <script>x</script>.\n\
+                        See [docs](https://example.com/a?b=1&c) or [bad](javascript:alert(1)).\n\
+                        Also [Rust](https://en.wikipedia.org/wiki/Rust_(language)).\n\
+                        \n- one *two*\n- 3 * 4 * 5\n1. first\n\n```\n<b>raw</b>\n```";
+        assert_eq!(
+            markdown_html(markdown),
+            "<h3>Checkout</h3>\n\
+             <p>Handles <strong>payment</strong> and <code>retry_count</code> \
+             &lt;script&gt;x&lt;/script&gt;. See \
+             <a href=\"https://example.com/a?b=1&amp;c\">docs</a> or bad. Also \
+             <a href=\"https://en.wikipedia.org/wiki/Rust_(language)\">Rust</a>.</p>\n\
+             <ul>\n<li>one <em>two</em></li>\n<li>3 * 4 * 5</li>\n</ul>\n\
+             <ol>\n<li>first</li>\n</ol>\n\
+             <pre><code>&lt;b&gt;raw&lt;/b&gt;</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn publishes_index_diagram_and_walkthrough_pages_with_xref_links() {
+        let session = demo_session();
+        let out_dir = std::env::temp_dir().join(format!(
+            "nereid-site-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        ));
+        let style = ExportStyle { theme: ExportTheme::PRINT, ..ExportStyle::default() };
+        let site = publish_site(&session, &out_dir, &style).expect("publish");
+
+        let names = site.files.iter().map(|path| path.to_string_lossy()).collect::<Vec<_>>();
+        assert!(names.contains(&"index.html".into()));
+        assert!(names.contains(&"style.css".into()));
+        assert!(names.contains(&"site.js".into()));
+        assert_eq!(
+            names.iter().filter(|name| name.starts_with("diagram-")).count(),
+            session.diagrams().len()
+        );
+        assert_eq!(
+            names.iter().filter(|name| name.starts_with("walkthrough-")).count(),
+            session.walkthroughs().len()
+        );
+
+        let css = std::fs::read_to_string(out_dir.join("style.css")).expect("style");
+        assert!(
+            css.contains(&format!(".selected {{ background: {}; }}", ExportTheme::PRINT.selected))
+        );
+
+        let index = std::fs::read_to_string(out_dir.join("index.html")).expect("index");
+        for diagram_id in session.diagrams().keys() {
+            assert!(index.contains(&diagram_page_name(diagram_id)));
+        }
+
+        let xref = session.xrefs().values().next().expect("demo xref");
+        let from_page =
+            std::fs::read_to_string(out_dir.join(diagram_page_name(xref.from().diagram_id())))
+                .expect("diagram page");
+        assert!(from_page.contains(&format!("data-ref=\"{}\"", xref.from())));
+        assert!(from_page.contains(&object_ref_href(xref.to())));
+
+        let _ = std::fs::remove_dir_all(&out_dir);
+    }
+}
@@ -0,0 +1,544 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Vector (SVG) export of rendered diagrams.
+//!
+//! A Unicode render is turned into a [`Scene`] first: box-drawing characters, arrowheads and
+//! pseudo-state markers become strokes and shapes, everything else stays text on the cell grid.
+//! Lines therefore join up regardless of the viewer's fonts, and other vector outputs (PDF) can
+//! reuse the same scene.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::model::{Diagram, ObjectRef, ObjectStyle};
+use crate::render::{
+    highlight_cell_owners, render_diagram_unicode_annotated, DiagramRenderError, HighlightIndex,
+    LineSpan,
+};
+
+use super::theme::{ExportSelection, ExportStyle, ExportTheme, Highlight};
+
+/// Width of one character cell in scene units (a 10pt Courier advance).
+pub const CELL_WIDTH: f32 = 6.0;
+/// Height of one character cell in scene units.
+pub const CELL_HEIGHT: f32 = 12.0;
+/// Font size used for text cells, in scene units.
+pub const FONT_SIZE: f32 = 10.0;
+/// Offset from the top of a cell to the text baseline, in scene units.
+pub const BASELINE_OFFSET: f32 = 9.0;
+
+const SVG_SCALE: f32 = 1.4;
+
+/// A point in scene units; `y` grows downwards.
+pub type Point = (f32, f32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stroke {
+    Thin,
+    Thick,
+    Dashed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Line {
+        from: Point,
+        to: Point,
+        stroke: Stroke,
+    },
+    Polygon {
+        points: Vec<Point>,
+        filled: bool,
+    },
+    Circle {
+        center: Point,
+        radius: f32,
+        filled: bool,
+    },
+    /// Text starting at the left edge of `column` on `row`, one cell per character.
+    Text {
+        row: usize,
+        column: usize,
+        text: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneItem {
+    pub object_ref: Option<ObjectRef>,
+    pub shape: Shape,
+}
+
+/// Vector form of a rendered diagram or walkthrough.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scene {
+    pub columns: usize,
+    pub rows: usize,
+    /// Cell spans to paint as highlighted behind the drawing, with how they are marked.
+    pub highlights: Vec<(LineSpan, Highlight)>,
+    pub items: Vec<SceneItem>,
+    /// Objects whose items are drawn faded, from dimming styling rules.
+    pub dimmed: BTreeSet<ObjectRef>,
+}
+
+impl Scene {
+    /// Builds the scene for rendered `text`, tagging items with the object owning their cells
+    /// and highlighting the spans of objects marked in `selection`.
+    pub fn from_text(
+        text: &str,
+        highlight_index: &HighlightIndex,
+        selection: &ExportSelection,
+    ) -> Self {
+        let owners = highlight_cell_owners(text, highlight_index);
+        let mut items = Vec::new();
+        let mut columns = 0;
+        let lines = text.split('\n').collect::<Vec<_>>();
+
+        for (y, line) in lines.iter().enumerate() {
+            let cells = line.chars().collect::<Vec<_>>();
+            columns = columns.max(cells.len());
+            let owner_at = |x: usize| owners[y].get(x).copied().flatten();
+
+            let mut x = 0;
+            while x < cells.len() {
+                if let Some(shapes) = glyph_shapes(cells[x], x, y) {
+                    let object_ref = owner_at(x).cloned();
+                    items.extend(
+                        shapes
+                            .into_iter()
+                            .map(|shape| SceneItem { object_ref: object_ref.clone(), shape }),
+                    );
+                    x += 1;
+                    continue;
+                }
+
+                let owner = owner_at(x);
+                let run_end = (x..cells.len())
+                    .find(|&end| {
+                        glyph_shapes(cells[end], end, y).is_some() || owner_at(end) != owner
+                    })
+                    .unwrap_or(cells.len());
+                let run = cells[x..run_end].iter().collect::<String>();
+                let leading = run.chars().take_while(|ch| *ch == ' ').count();
+                let text = run.trim().to_owned();
+                if !text.is_empty() {
+                    items.push(SceneItem {
+                        object_ref: owner.cloned(),
+                        shape: Shape::Text { row: y, column: x + leading, text },
+                    });
+                }
+                x = run_end;
+            }
+        }
+
+        let highlights = highlight_index
+            .iter()
+            .filter_map(|(object_ref, spans)| Some((selection.highlight(object_ref)?, spans)))
+            .flat_map(|(highlight, spans)| spans.iter().map(move |&span| (span, highlight)))
+            .collect();
+
+        Self {
+            columns,
+            rows: lines.len(),
+            highlights,
+            items: merge_lines(items),
+            dimmed: BTreeSet::new(),
+        }
+    }
+
+    /// Applies styling rule effects: thin strokes of heavy objects turn thick and dim objects
+    /// are recorded in [`Scene::dimmed`].
+    pub fn apply_object_styles(&mut self, object_styles: &BTreeMap<ObjectRef, ObjectStyle>) {
+        for item in &mut self.items {
+            let Some(style) = item.object_ref.as_ref().and_then(|r| object_styles.get(r)) else {
+                continue;
+            };
+            if let Shape::Line { stroke: stroke @ Stroke::Thin, .. } = &mut item.shape {
+                if style.heavy {
+                    *stroke = Stroke::Thick;
+                }
+            }
+        }
+        self.dimmed = object_styles
+            .iter()
+            .filter(|(_, style)| style.dim)
+            .map(|(object_ref, _)| object_ref.clone())
+            .collect();
+    }
+
+    /// Whether `item` belongs to a dimmed object.
+    pub fn is_dimmed(&self, item: &SceneItem) -> bool {
+        item.object_ref.as_ref().is_some_and(|object_ref| self.dimmed.contains(object_ref))
+    }
+
+    pub fn width(&self) -> f32 {
+        self.columns as f32 * CELL_WIDTH
+    }
+
+    pub fn height(&self) -> f32 {
+        self.rows as f32 * CELL_HEIGHT
+    }
+}
+
+/// Renders `diagram` as a standalone SVG document in the colors of `style`, marking its
+/// selection and applying its styling rule effects.
+pub fn diagram_svg(diagram: &Diagram, style: &ExportStyle) -> Result<String, DiagramRenderError> {
+    let rendered = render_diagram_unicode_annotated(diagram)?;
+    let mut scene = Scene::from_text(&rendered.text, &rendered.highlight_index, &style.selection);
+    scene.apply_object_styles(&style.object_styles);
+    Ok(scene_svg(&scene, &style.theme))
+}
+
+/// Serializes `scene` as a standalone SVG document; items owned by an object carry its ref in a
+/// `data-ref` attribute, and highlight backgrounds are grouped by their semantic class
+/// (`selected`, `agent`). Items of dimmed objects are drawn at reduced opacity.
+pub fn scene_svg(scene: &Scene, theme: &ExportTheme) -> String {
+    let ink = theme.ink;
+    let (width, height) = (scene.width(), scene.height());
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {} {}\" width=\"{}\" \
+         height=\"{}\" font-family=\"ui-monospace, Menlo, Consolas, monospace\" \
+         font-size=\"{}\">\n<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n",
+        num(width),
+        num(height),
+        num(width * SVG_SCALE),
+        num(height * SVG_SCALE),
+        num(FONT_SIZE),
+        theme.background,
+    );
+
+    let mut by_highlight = BTreeMap::<Highlight, Vec<LineSpan>>::new();
+    for &(span, highlight) in &scene.highlights {
+        by_highlight.entry(highlight).or_default().push(span);
+    }
+    for (highlight, spans) in by_highlight {
+        out.push_str(&format!(
+            "<g class=\"{}\" fill=\"{}\">\n",
+            highlight.class_name(),
+            theme.highlight_fill(highlight)
+        ));
+        for (y, x0, x1) in spans {
+            out.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n",
+                num(x0 as f32 * CELL_WIDTH),
+                num(y as f32 * CELL_HEIGHT),
+                num((x1 + 1 - x0) as f32 * CELL_WIDTH),
+                num(CELL_HEIGHT),
+            ));
+        }
+        out.push_str("</g>\n");
+    }
+
+    out.push_str(&format!("<g stroke=\"{ink}\" fill=\"none\" stroke-linecap=\"round\">\n"));
+    for item in &scene.items {
+        let data_ref = item
+            .object_ref
+            .as_ref()
+            .map(|object_ref| format!(" data-ref=\"{}\"", escape_xml(&object_ref.to_string())))
+            .unwrap_or_default();
+        let data_ref =
+            if scene.is_dimmed(item) { format!(" opacity=\"0.4\"{data_ref}") } else { data_ref };
+        match &item.shape {
+            Shape::Line { from, to, stroke } => {
+                let style = match stroke {
+                    Stroke::Thin => String::new(),
+                    Stroke::Thick => " stroke-width=\"2\"".to_owned(),
+                    Stroke::Dashed => {
+                        " stroke-dasharray=\"2 2\" stroke-linecap=\"butt\"".to_owned()
+                    }
+                };
+                out.push_str(&format!(
+                    "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"{style}{data_ref}/>\n",
+                    num(from.0),
+                    num(from.1),
+                    num(to.0),
+                    num(to.1),
+                ));
+            }
+            Shape::Polygon { points, filled } => {
+                let points = points
+                    .iter()
+                    .map(|(x, y)| format!("{},{}", num(*x), num(*y)))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let fill = if *filled { format!(" fill=\"{ink}\"") } else { String::new() };
+                out.push_str(&format!("<polygon points=\"{points}\"{fill}{data_ref}/>\n"));
+            }
+            Shape::Circle { center, radius, filled } => {
+                let fill = if *filled { format!(" fill=\"{ink}\"") } else { String::new() };
+                out.push_str(&format!(
+                    "<circle cx=\"{}\" cy=\"{}\" r=\"{}\"{fill}{data_ref}/>\n",
+                    num(center.0),
+                    num(center.1),
+                    num(*radius),
+                ));
+            }
+            Shape::Text { row, column, text } => {
+                out.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" textLength=\"{}\" lengthAdjust=\"spacingAndGlyphs\" \
+                     xml:space=\"preserve\" stroke=\"none\" fill=\"{ink}\"{data_ref}>{}</text>\n",
+                    num(*column as f32 * CELL_WIDTH),
+                    num(*row as f32 * CELL_HEIGHT + BASELINE_OFFSET),
+                    num(text.chars().count() as f32 * CELL_WIDTH),
+                    escape_xml(text),
+                ));
+            }
+        }
+    }
+    out.push_str("</g>\n</svg>\n");
+    out
+}
+
+/// Vector shapes drawing `ch` in the cell at (`x`, `y`); `None` for characters drawn as text.
+fn glyph_shapes(ch: char, x: usize, y: usize) -> Option<Vec<Shape>> {
+    let (left, top) = (x as f32 * CELL_WIDTH, y as f32 * CELL_HEIGHT);
+    let at = |fx: f32, fy: f32| (left + fx * CELL_WIDTH, top + fy * CELL_HEIGHT);
+    let polygon = |points: &[(f32, f32)], filled: bool| Shape::Polygon {
+        points: points.iter().map(|&(fx, fy)| at(fx, fy)).collect(),
+        filled,
+    };
+    let circle = |radius: f32, filled: bool| Shape::Circle {
+        center: at(0.5, 0.5),
+        radius: radius * CELL_WIDTH,
+        filled,
+    };
+
+    if let Some((arms, stroke)) = box_arms(ch) {
+        return Some(box_lines(arms, stroke, at));
+    }
+    let shapes = match ch {
+        '▶' => vec![polygon(&[(0.1, 0.25), (0.1, 0.75), (0.95, 0.5)], true)],
+        '◀' => vec![polygon(&[(0.9, 0.25), (0.9, 0.75), (0.05, 0.5)], true)],
+        '▲' => vec![polygon(&[(0.1, 0.8), (0.9, 0.8), (0.5, 0.1)], true)],
+        '▼' => vec![polygon(&[(0.1, 0.2), (0.9, 0.2), (0.5, 0.9)], true)],
+        '▸' => vec![polygon(&[(0.25, 0.35), (0.25, 0.65), (0.8, 0.5)], true)],
+        '▴' => vec![polygon(&[(0.2, 0.65), (0.8, 0.65), (0.5, 0.35)], true)],
+        '▷' => vec![polygon(&[(0.1, 0.25), (0.1, 0.75), (0.95, 0.5)], false)],
+        '◁' => vec![polygon(&[(0.9, 0.25), (0.9, 0.75), (0.05, 0.5)], false)],
+        '◇' => vec![polygon(&[(0.5, 0.2), (0.95, 0.5), (0.5, 0.8), (0.05, 0.5)], false)],
+        '●' => vec![circle(0.4, true)],
+        '○' => vec![circle(0.4, false)],
+        '◉' => vec![circle(0.45, false), circle(0.25, true)],
+        '✕' => vec![
+            Shape::Line { from: at(0.15, 0.3), to: at(0.85, 0.7), stroke: Stroke::Thin },
+            Shape::Line { from: at(0.85, 0.3), to: at(0.15, 0.7), stroke: Stroke::Thin },
+        ],
+        _ => return None,
+    };
+    Some(shapes)
+}
+
+/// Joins line segments that continue each other in the same direction with the same stroke and
+/// owner, so a run of `─` or `│` cells becomes one stroke instead of many abutting ones.
+fn merge_lines(items: Vec<SceneItem>) -> Vec<SceneItem> {
+    type LineEnd = (bool, u32, u32, Stroke, Option<ObjectRef>);
+    let key = |vertical: bool, (x, y): Point, stroke: Stroke, object_ref: &Option<ObjectRef>| {
+        (vertical, x.to_bits(), y.to_bits(), stroke, object_ref.clone())
+    };
+
+    let mut merged = Vec::<SceneItem>::with_capacity(items.len());
+    // Open line ends, keyed by direction and end point, pointing into `merged`.
+    let mut ends = std::collections::HashMap::<LineEnd, usize>::new();
+    for item in items {
+        let Shape::Line { from, to, stroke } = item.shape else {
+            merged.push(item);
+            continue;
+        };
+        let vertical = from.0 == to.0;
+        if let Some(idx) = ends.remove(&key(vertical, from, stroke, &item.object_ref)) {
+            if let Shape::Line { to: end, .. } = &mut merged[idx].shape {
+                *end = to;
+            }
+            ends.insert(key(vertical, to, stroke, &item.object_ref), idx);
+            continue;
+        }
+        ends.insert(key(vertical, to, stroke, &item.object_ref), merged.len());
+        merged.push(item);
+    }
+    merged
+}
+
+/// Arms (left, right, up, down) of a box-drawing character and the stroke they are drawn with.
+fn box_arms(ch: char) -> Option<([bool; 4], Stroke)> {
+    let arms = match ch {
+        '─' | '━' | '┄' => [true, true, false, false],
+        '│' | '┃' | '┆' => [false, false, true, true],
+        '┌' | '╭' => [false, true, false, true],
+        '┐' | '╮' => [true, false, false, true],
+        '└' | '╰' => [false, true, true, false],
+        '┘' | '╯' => [true, false, true, false],
+        '├' => [false, true, true, true],
+        '┤' => [true, false, true, true],
+        '┬' => [true, true, false, true],
+        '┴' => [true, true, true, false],
+        '┼' => [true, true, true, true],
+        _ => return None,
+    };
+    let stroke = match ch {
+        '━' | '┃' => Stroke::Thick,
+        '┄' | '┆' => Stroke::Dashed,
+        _ => Stroke::Thin,
+    };
+    Some((arms, stroke))
+}
+
+fn box_lines(arms: [bool; 4], stroke: Stroke, at: impl Fn(f32, f32) -> Point) -> Vec<Shape> {
+    let [left, right, up, down] = arms;
+    let line = |from: (f32, f32), to: (f32, f32)| Shape::Line {
+        from: at(from.0, from.1),
+        to: at(to.0, to.1),
+        stroke,
+    };
+    let mut shapes = Vec::new();
+    match (up, down) {
+        (true, true) => shapes.push(line((0.5, 0.0), (0.5, 1.0))),
+        (true, false) => shapes.push(line((0.5, 0.0), (0.5, 0.5))),
+        (false, true) => shapes.push(line((0.5, 0.5), (0.5, 1.0))),
+        (false, false) => {}
+    }
+    match (left, right) {
+        (true, true) => shapes.push(line((0.0, 0.5), (1.0, 0.5))),
+        (true, false) => shapes.push(line((0.0, 0.5), (0.5, 0.5))),
+        (false, true) => shapes.push(line((0.5, 0.5), (1.0, 0.5))),
+        (false, false) => {}
+    }
+    shapes
+}
+
+/// Compact decimal form (at most two fractional digits, no trailing zeros).
+fn num(value: f32) -> String {
+    let formatted = format!("{value:.2}");
+    formatted.trim_end_matches('0').trim_end_matches('.').to_owned()
+}
+
+fn escape_xml(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use super::{diagram_svg, scene_svg, Scene, Shape, Stroke, CELL_HEIGHT, CELL_WIDTH};
+    use crate::format::theme::{ExportSelection, ExportStyle, ExportTheme, Highlight};
+    use crate::model::{ObjectRef, ObjectStyle};
+    use crate::render::HighlightIndex;
+    use crate::tui::demo_session;
+
+    #[test]
+    fn scene_turns_box_glyphs_into_strokes_and_keeps_labels_as_text() {
+        let node = ObjectRef::parse("d:flow/flow/node/n:a").expect("ref");
+        let mut index = HighlightIndex::new();
+        index.insert(node.clone(), vec![(0, 0, 4), (1, 0, 4)]);
+        let selection = ExportSelection::selected(BTreeSet::from([node.clone()]));
+        let scene = Scene::from_text("┌───┐─▶\n│ A │", &index, &selection);
+
+        assert_eq!((scene.columns, scene.rows), (7, 2));
+        assert_eq!(
+            scene.highlights,
+            vec![((0, 0, 4), Highlight::Selected), ((1, 0, 4), Highlight::Selected)]
+        );
+        let text = scene
+            .items
+            .iter()
+            .find(|item| matches!(item.shape, Shape::Text { .. }))
+            .expect("label text");
+        assert_eq!(text.shape, Shape::Text { row: 1, column: 2, text: "A".to_owned() });
+        assert_eq!(text.object_ref.as_ref(), Some(&node));
+        assert!(scene.items.iter().any(|item| item.shape
+            == Shape::Line {
+                from: (0.5 * CELL_WIDTH, 0.5 * CELL_HEIGHT),
+                to: (4.5 * CELL_WIDTH, 0.5 * CELL_HEIGHT),
+                stroke: Stroke::Thin,
+            }));
+        assert!(scene.items.iter().any(|item| item.shape
+            == Shape::Line {
+                from: (0.5 * CELL_WIDTH, 0.5 * CELL_HEIGHT),
+                to: (0.5 * CELL_WIDTH, 2.0 * CELL_HEIGHT),
+                stroke: Stroke::Thin,
+            }));
+        assert!(scene.items.iter().any(|item| item.object_ref.is_none()
+            && matches!(item.shape, Shape::Polygon { filled: true, .. })));
+
+        let svg = scene_svg(&scene, &ExportTheme::DEFAULT);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 42 24\""));
+        assert!(svg.contains("<rect x=\"0\" y=\"12\" width=\"30\" height=\"12\"/>"));
+        assert!(svg.contains(">A</text>"));
+        assert!(svg.contains("data-ref=\"d:flow/flow/node/n:a\""));
+    }
+
+    #[test]
+    fn styling_rules_thicken_heavy_strokes_and_fade_dim_items() {
+        let heavy = ObjectRef::parse("d:flow/flow/node/n:a").expect("ref");
+        let dim = ObjectRef::parse("d:flow/flow/node/n:b").expect("ref");
+        let mut index = HighlightIndex::new();
+        index.insert(heavy.clone(), vec![(0, 0, 2)]);
+        index.insert(dim.clone(), vec![(0, 4, 4)]);
+        let mut scene = Scene::from_text("┌─┐ B", &index, &ExportSelection::default());
+        scene.apply_object_styles(&BTreeMap::from([
+            (heavy.clone(), ObjectStyle { dim: false, heavy: true }),
+            (dim.clone(), ObjectStyle { dim: true, heavy: false }),
+        ]));
+
+        assert!(scene.items.iter().all(|item| match &item.shape {
+            Shape::Line { stroke, .. } => *stroke == Stroke::Thick,
+            _ => true,
+        }));
+        assert_eq!(scene.dimmed, BTreeSet::from([dim.clone()]));
+        let svg = scene_svg(&scene, &ExportTheme::DEFAULT);
+        assert!(svg.contains(&format!("opacity=\"0.4\" data-ref=\"{dim}\">B</text>")));
+        assert!(svg.contains(&format!("stroke-width=\"2\" data-ref=\"{heavy}\"")));
+    }
+
+    #[test]
+    fn exports_every_demo_diagram() {
+        for diagram in demo_session().diagrams().values() {
+            let svg = diagram_svg(diagram, &ExportStyle::default()).expect("svg");
+            assert!(svg.ends_with("</svg>\n"));
+            assert!(svg.contains("<line "));
+        }
+    }
+
+    #[test]
+    fn themes_color_the_drawing_and_group_selection_and_agent_marks_by_class() {
+        let a = ObjectRef::parse("d:flow/flow/node/n:a").expect("ref");
+        let b = ObjectRef::parse("d:flow/flow/node/n:b").expect("ref");
+        let mut index = HighlightIndex::new();
+        index.insert(a.clone(), vec![(0, 0, 0)]);
+        index.insert(b.clone(), vec![(0, 2, 2)]);
+        let selection = ExportSelection {
+            selected: BTreeSet::from([a.clone(), b.clone()]),
+            agent: BTreeSet::from([b.clone()]),
+        };
+        let scene = Scene::from_text("A B", &index, &selection);
+        assert_eq!(
+            scene.highlights,
+            vec![((0, 0, 0), Highlight::Selected), ((0, 2, 2), Highlight::Both)]
+        );
+
+        let theme = ExportTheme::named("dark").expect("dark theme");
+        let svg = scene_svg(&scene, &theme);
+        assert!(svg.contains(&format!("fill=\"{}\"/>", theme.background)));
+        assert!(svg.contains(&format!("<g class=\"selected\" fill=\"{}\">", theme.selected)));
+        assert!(svg.contains(&format!("<g class=\"selected agent\" fill=\"{}\">", theme.both)));
+        assert!(svg.contains(&format!("fill=\"{}\" data-ref=\"{a}\">A</text>", theme.ink)));
+        assert!(!svg.contains(ExportTheme::DEFAULT.ink));
+        assert_eq!(ExportTheme::named("print"), Some(ExportTheme::PRINT));
+        assert_eq!(ExportTheme::named("neon"), None);
+    }
+}
@@ -0,0 +1,405 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Tabular (CSV/JSON rows) import for flowcharts.
+//!
+//! A node table and an edge table are mapped onto a `FlowchartAst` via a `ColumnMapping`.
+//! Node ids are derived from the mapped id column (`n:<ident>`), and edge ids follow row order
+//! (`e:0000`, `e:0001`, ...), so re-importing the same tables yields the same object refs.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::format::mermaid::{export_flowchart, MermaidFlowchartExportError};
+use crate::model::flow_ast::{FlowEdge, FlowNode, FlowchartAst};
+use crate::model::ids::ObjectId;
+use crate::ops::idgen;
+
+/// One table row keyed by column name.
+pub type TabularRow = BTreeMap<String, String>;
+
+/// Which columns carry node/edge fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMapping {
+    pub node_id: String,
+    pub node_label: String,
+    pub node_shape: String,
+    pub edge_from: String,
+    pub edge_to: String,
+    pub edge_label: String,
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        Self {
+            node_id: "id".to_owned(),
+            node_label: "label".to_owned(),
+            node_shape: "shape".to_owned(),
+            edge_from: "from".to_owned(),
+            edge_to: "to".to_owned(),
+            edge_label: "label".to_owned(),
+        }
+    }
+}
+
+impl ColumnMapping {
+    /// Overrides one mapping entry by key (`node_id`, `node_label`, `node_shape`, `edge_from`,
+    /// `edge_to`, `edge_label`).
+    pub fn set(&mut self, key: &str, column: impl Into<String>) -> Result<(), TabularImportError> {
+        let slot = match key {
+            "node_id" => &mut self.node_id,
+            "node_label" => &mut self.node_label,
+            "node_shape" => &mut self.node_shape,
+            "edge_from" => &mut self.edge_from,
+            "edge_to" => &mut self.edge_to,
+            "edge_label" => &mut self.edge_label,
+            _ => return Err(TabularImportError::UnknownMappingKey { key: key.to_owned() }),
+        };
+        *slot = column.into();
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabularTable {
+    Nodes,
+    Edges,
+}
+
+impl TabularTable {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Nodes => "nodes",
+            Self::Edges => "edges",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TabularImportError {
+    Csv { line_no: usize, reason: String },
+    Json { reason: String },
+    UnknownMappingKey { key: String },
+    MissingValue { table: TabularTable, row: usize, column: String },
+    DuplicateNode { row: usize, id: String },
+    UnknownShape { row: usize, shape: String },
+    UnknownNode { row: usize, id: String },
+    Export(MermaidFlowchartExportError),
+}
+
+impl fmt::Display for TabularImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Csv { line_no, reason } => write!(f, "invalid CSV on line {line_no}: {reason}"),
+            Self::Json { reason } => write!(f, "invalid JSON rows: {reason}"),
+            Self::UnknownMappingKey { key } => write!(
+                f,
+                "unknown column mapping key: {key} (expected node_id, node_label, node_shape, edge_from, edge_to, edge_label)"
+            ),
+            Self::MissingValue { table, row, column } => {
+                write!(f, "{} row {row} has no value for column '{column}'", table.label())
+            }
+            Self::DuplicateNode { row, id } => write!(f, "nodes row {row} repeats node id '{id}'"),
+            Self::UnknownShape { row, shape } => write!(
+                f,
+                "nodes row {row} has unsupported shape '{shape}' (expected rect, round, diamond)"
+            ),
+            Self::UnknownNode { row, id } => {
+                write!(f, "edges row {row} references unknown node id '{id}'")
+            }
+            Self::Export(err) => write!(f, "imported flowchart is not exportable: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for TabularImportError {}
+
+/// Parses CSV text with a header row into rows keyed by header name.
+///
+/// Supports quoted fields (`"a, b"`), escaped quotes (`""`) and quoted newlines. Blank lines are
+/// skipped and empty cells are omitted from the row.
+pub fn parse_csv_rows(input: &str) -> Result<Vec<TabularRow>, TabularImportError> {
+    let records = parse_csv_records(input)?;
+    let mut records = records.into_iter();
+    let Some((_, header)) = records.next() else {
+        return Ok(Vec::new());
+    };
+    let header = header.into_iter().map(|name| name.trim().to_owned()).collect::<Vec<_>>();
+
+    let mut rows = Vec::new();
+    for (line_no, record) in records {
+        if record.len() > header.len() {
+            return Err(TabularImportError::Csv {
+                line_no,
+                reason: format!("expected at most {} fields, found {}", header.len(), record.len()),
+            });
+        }
+        let row = header
+            .iter()
+            .zip(record)
+            .filter(|(_, value)| !value.trim().is_empty())
+            .map(|(name, value)| (name.clone(), value.trim().to_owned()))
+            .collect::<TabularRow>();
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+fn parse_csv_records(input: &str) -> Result<Vec<(usize, Vec<String>)>, TabularImportError> {
+    let mut records = Vec::new();
+    let mut record = Vec::<String>::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line_no = 1usize;
+    let mut record_line_no = 1usize;
+    let mut chars = input.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if in_quotes {
+            match ch {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                '\n' => {
+                    line_no += 1;
+                    field.push('\n');
+                }
+                _ => field.push(ch),
+            }
+            continue;
+        }
+
+        match ch {
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                in_quotes = true;
+            }
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|value| !value.trim().is_empty()) {
+                    records.push((record_line_no, std::mem::take(&mut record)));
+                } else {
+                    record.clear();
+                }
+                line_no += 1;
+                record_line_no = line_no;
+            }
+            _ => field.push(ch),
+        }
+    }
+
+    if in_quotes {
+        return Err(TabularImportError::Csv {
+            line_no: record_line_no,
+            reason: "unterminated quoted field".to_owned(),
+        });
+    }
+
+    record.push(field);
+    if record.iter().any(|value| !value.trim().is_empty()) {
+        records.push((record_line_no, record));
+    }
+    Ok(records)
+}
+
+/// Parses a JSON array of flat objects into rows.
+///
+/// Strings, numbers and booleans are stringified; `null` and empty strings are omitted.
+pub fn parse_json_rows(input: &str) -> Result<Vec<TabularRow>, TabularImportError> {
+    let value: serde_json::Value = serde_json::from_str(input)
+        .map_err(|err| TabularImportError::Json { reason: err.to_string() })?;
+    let serde_json::Value::Array(items) = value else {
+        return Err(TabularImportError::Json { reason: "expected a top-level array".to_owned() });
+    };
+
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(idx, item)| match item {
+            serde_json::Value::Object(map) => Ok(json_object_row(map)),
+            _ => Err(TabularImportError::Json {
+                reason: format!("row {} is not an object", idx + 1),
+            }),
+        })
+        .collect()
+}
+
+/// Converts one JSON object into a row (see `parse_json_rows`).
+pub fn json_object_row(map: serde_json::Map<String, serde_json::Value>) -> TabularRow {
+    map.into_iter()
+        .filter_map(|(key, value)| {
+            let value = match value {
+                serde_json::Value::String(value) => value.trim().to_owned(),
+                serde_json::Value::Number(value) => value.to_string(),
+                serde_json::Value::Bool(value) => value.to_string(),
+                _ => return None,
+            };
+            (!value.is_empty()).then_some((key, value))
+        })
+        .collect()
+}
+
+/// Builds a flowchart from node and edge rows.
+///
+/// Row numbers in errors are 1-based and exclude the header. Node labels default to the raw id,
+/// shapes default to `rect`.
+pub fn flowchart_from_rows(
+    nodes: &[TabularRow],
+    edges: &[TabularRow],
+    mapping: &ColumnMapping,
+) -> Result<FlowchartAst, TabularImportError> {
+    let mut ast = FlowchartAst::default();
+    let mut node_ids_by_raw = BTreeMap::<String, ObjectId>::new();
+    let mut taken_idents = BTreeSet::<String>::new();
+
+    for (idx, row) in nodes.iter().enumerate() {
+        let row_no = idx + 1;
+        let raw_id = required_value(row, &mapping.node_id, TabularTable::Nodes, row_no)?;
+        if node_ids_by_raw.contains_key(raw_id) {
+            return Err(TabularImportError::DuplicateNode { row: row_no, id: raw_id.to_owned() });
+        }
+
+        let shape = match row.get(&mapping.node_shape).map(String::as_str) {
+            None => "rect",
+            Some(shape) => match shape.to_ascii_lowercase().as_str() {
+                "rect" => "rect",
+                "round" => "round",
+                "diamond" => "diamond",
+                _ => {
+                    return Err(TabularImportError::UnknownShape {
+                        row: row_no,
+                        shape: shape.to_owned(),
+                    })
+                }
+            },
+        };
+        let label = row.get(&mapping.node_label).map(String::as_str).unwrap_or(raw_id);
+
+        let ident = idgen::mermaid_ident(raw_id, |ident| taken_idents.contains(ident));
+        taken_idents.insert(ident.clone());
+        let node_id = ObjectId::new(format!("n:{ident}")).expect("valid node id");
+        ast.nodes_mut().insert(node_id.clone(), FlowNode::new_with(label, shape, Some(ident)));
+        node_ids_by_raw.insert(raw_id.to_owned(), node_id);
+    }
+
+    for (idx, row) in edges.iter().enumerate() {
+        let row_no = idx + 1;
+        let lookup = |column: &str| -> Result<ObjectId, TabularImportError> {
+            let raw = required_value(row, column, TabularTable::Edges, row_no)?;
+            node_ids_by_raw
+                .get(raw)
+                .cloned()
+                .ok_or_else(|| TabularImportError::UnknownNode { row: row_no, id: raw.to_owned() })
+        };
+        let from = lookup(&mapping.edge_from)?;
+        let to = lookup(&mapping.edge_to)?;
+        let label = row.get(&mapping.edge_label).cloned();
+
+        let edge_id =
+            idgen::numbered_object_id("e", idx, |edge_id| ast.edges().contains_key(edge_id));
+        ast.edges_mut().insert(edge_id, FlowEdge::new_with(from, to, label, None));
+    }
+
+    export_flowchart(&ast).map_err(TabularImportError::Export)?;
+    Ok(ast)
+}
+
+fn required_value<'a>(
+    row: &'a TabularRow,
+    column: &str,
+    table: TabularTable,
+    row_no: usize,
+) -> Result<&'a str, TabularImportError> {
+    row.get(column).map(String::as_str).ok_or_else(|| TabularImportError::MissingValue {
+        table,
+        row: row_no,
+        column: column.to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        flowchart_from_rows, parse_csv_rows, parse_json_rows, ColumnMapping, TabularImportError,
+    };
+    use crate::model::ids::ObjectId;
+
+    fn oid(value: &str) -> ObjectId {
+        ObjectId::new(value).expect("object id")
+    }
+
+    #[test]
+    fn parses_csv_with_quotes_and_blank_lines() {
+        let rows = parse_csv_rows("id,label\r\nA,\"Start, here\"\n\nB,\"Say \"\"hi\"\"\"\nC,\n")
+            .expect("parse");
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].get("label").map(String::as_str), Some("Start, here"));
+        assert_eq!(rows[1].get("label").map(String::as_str), Some("Say \"hi\""));
+        assert_eq!(rows[2].get("label"), None);
+    }
+
+    #[test]
+    fn rejects_unterminated_csv_quote() {
+        let err = parse_csv_rows("id,label\nA,\"oops\n").unwrap_err();
+        assert!(matches!(err, TabularImportError::Csv { line_no: 2, .. }));
+    }
+
+    #[test]
+    fn builds_flowchart_with_deterministic_ids() {
+        let nodes =
+            parse_csv_rows("Key,Name,Kind\nweb-1,Web,round\nweb 1,Web B,\ndb,Database,diamond\n")
+                .expect("nodes");
+        let edges = parse_json_rows(
+            r#"[{"src":"web-1","dst":"db","what":"sql"},{"src":"web 1","dst":"db"}]"#,
+        )
+        .expect("edges");
+
+        let mut mapping = ColumnMapping::default();
+        mapping.set("node_id", "Key").expect("map");
+        mapping.set("node_label", "Name").expect("map");
+        mapping.set("node_shape", "Kind").expect("map");
+        mapping.set("edge_from", "src").expect("map");
+        mapping.set("edge_to", "dst").expect("map");
+        mapping.set("edge_label", "what").expect("map");
+
+        let ast = flowchart_from_rows(&nodes, &edges, &mapping).expect("import");
+        let again = flowchart_from_rows(&nodes, &edges, &mapping).expect("import");
+        assert_eq!(ast, again);
+
+        let web = ast.nodes().get(&oid("n:web_1")).expect("web");
+        assert_eq!(web.label(), "Web");
+        assert_eq!(web.shape(), "round");
+        assert_eq!(ast.nodes().get(&oid("n:web_1_2")).expect("web b").label(), "Web B");
+        assert_eq!(ast.nodes().get(&oid("n:db")).expect("db").shape(), "diamond");
+
+        let first = ast.edges().get(&oid("e:0000")).expect("edge");
+        assert_eq!(first.from_node_id(), &oid("n:web_1"));
+        assert_eq!(first.label(), Some("sql"));
+        assert_eq!(
+            ast.edges().get(&oid("e:0001")).expect("edge").from_node_id(),
+            &oid("n:web_1_2")
+        );
+    }
+
+    #[test]
+    fn reports_unknown_edge_endpoints_and_mapping_keys() {
+        let nodes = parse_csv_rows("id\nA\n").expect("nodes");
+        let edges = parse_csv_rows("from,to\nA,B\n").expect("edges");
+
+        let err = flowchart_from_rows(&nodes, &edges, &ColumnMapping::default()).unwrap_err();
+        assert_eq!(err, TabularImportError::UnknownNode { row: 1, id: "B".to_owned() });
+
+        let err = ColumnMapping::default().set("nope", "x").unwrap_err();
+        assert!(matches!(err, TabularImportError::UnknownMappingKey { .. }));
+    }
+}
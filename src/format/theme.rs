@@ -0,0 +1,155 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Named color themes and highlight marks for exports.
+//!
+//! Exports pick their colors from an [`ExportTheme`] rather than the TUI palette, so a session
+//! browsed in a dark terminal can still be printed on paper. An [`ExportStyle`] pairs the theme
+//! with the objects to mark: the human selection and the agent highlights get their own fills
+//! and semantic class names (`selected`, `agent`), mirroring how the TUI tells them apart.
+//! It also carries the effects of the session's conditional styling rules per object.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::model::{ObjectRef, ObjectStyle};
+
+/// Colors used by SVG, PNG, PDF and HTML exports, as `#rrggbb` strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportTheme {
+    pub name: &'static str,
+    pub background: &'static str,
+    /// Backdrop of diagram blocks embedded in HTML pages.
+    pub panel: &'static str,
+    pub ink: &'static str,
+    pub muted: &'static str,
+    pub error: &'static str,
+    pub selected: &'static str,
+    pub agent: &'static str,
+    /// Fill for objects both selected and agent-highlighted.
+    pub both: &'static str,
+}
+
+impl ExportTheme {
+    pub const DEFAULT: Self = Self {
+        name: "default",
+        background: "#ffffff",
+        panel: "#f6f8fa",
+        ink: "#24292f",
+        muted: "#57606a",
+        error: "#cf222e",
+        selected: "#ffe58f",
+        agent: "#b6e3ff",
+        both: "#f2c6f5",
+    };
+
+    /// Black on white with light fills that stay distinguishable when printed in grayscale.
+    pub const PRINT: Self = Self {
+        name: "print",
+        background: "#ffffff",
+        panel: "#ffffff",
+        ink: "#000000",
+        muted: "#444444",
+        error: "#000000",
+        selected: "#e0e0e0",
+        agent: "#f2f2f2",
+        both: "#c8c8c8",
+    };
+
+    pub const DARK: Self = Self {
+        name: "dark",
+        background: "#0d1117",
+        panel: "#161b22",
+        ink: "#e6edf3",
+        muted: "#8b949e",
+        error: "#ff7b72",
+        selected: "#6e5a00",
+        agent: "#0b4f71",
+        both: "#5b2a63",
+    };
+
+    pub const ALL: [Self; 3] = [Self::DEFAULT, Self::PRINT, Self::DARK];
+
+    /// Looks up a theme by name (`default`, `print` or `dark`).
+    pub fn named(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|theme| theme.name == name)
+    }
+
+    pub fn highlight_fill(&self, highlight: Highlight) -> &'static str {
+        match highlight {
+            Highlight::Selected => self.selected,
+            Highlight::Agent => self.agent,
+            Highlight::Both => self.both,
+        }
+    }
+}
+
+impl Default for ExportTheme {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// How an exported object is marked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Highlight {
+    Selected,
+    Agent,
+    Both,
+}
+
+impl Highlight {
+    /// Space-separated class names for the mark, usable as an SVG/HTML `class` attribute.
+    pub fn class_name(self) -> &'static str {
+        match self {
+            Self::Selected => "selected",
+            Self::Agent => "agent",
+            Self::Both => "selected agent",
+        }
+    }
+}
+
+/// Objects to mark in an export.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportSelection {
+    pub selected: BTreeSet<ObjectRef>,
+    pub agent: BTreeSet<ObjectRef>,
+}
+
+impl ExportSelection {
+    pub fn selected(selected: BTreeSet<ObjectRef>) -> Self {
+        Self { selected, agent: BTreeSet::new() }
+    }
+
+    pub fn highlight(&self, object_ref: &ObjectRef) -> Option<Highlight> {
+        match (self.selected.contains(object_ref), self.agent.contains(object_ref)) {
+            (true, true) => Some(Highlight::Both),
+            (true, false) => Some(Highlight::Selected),
+            (false, true) => Some(Highlight::Agent),
+            (false, false) => None,
+        }
+    }
+}
+
+/// Theme plus marked objects for one export.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportStyle {
+    pub theme: ExportTheme,
+    pub selection: ExportSelection,
+    /// Styling rule effects per object; objects without an effect are absent.
+    pub object_styles: BTreeMap<ObjectRef, ObjectStyle>,
+}
+
+/// Red, green and blue components (0..=1) of a `#rrggbb` color; black if malformed.
+pub(crate) fn rgb(hex: &str) -> [f32; 3] {
+    let component = |idx: usize| {
+        hex.get(1 + idx * 2..3 + idx * 2)
+            .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+            .map_or(0.0, |value| f32::from(value) / 255.0)
+    };
+    [component(0), component(1), component(2)]
+}
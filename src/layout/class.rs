@@ -0,0 +1,117 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Class diagrams reuse flowchart layering to place classes in columns: parents and wholes of
+//! structural relations come first, other relations run from `from` to `to` where that keeps the
+//! columns acyclic. Relations closing a cycle and self-relations do not constrain the layering.
+
+use std::collections::BTreeSet;
+
+use crate::model::class_ast::ClassAst;
+use crate::model::flow_ast::{FlowEdge, FlowNode, FlowchartAst};
+use crate::model::ids::ObjectId;
+
+use super::flowchart::{layout_flowchart, FlowchartLayoutError};
+use super::state::back_edge_ids;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassLayout {
+    columns: Vec<Vec<ObjectId>>,
+}
+
+impl ClassLayout {
+    /// Class ids per column, left to right, each column top to bottom.
+    pub fn columns(&self) -> &[Vec<ObjectId>] {
+        &self.columns
+    }
+
+    /// Column and row of `class_id`.
+    pub fn position(&self, class_id: &ObjectId) -> Option<(usize, usize)> {
+        self.columns.iter().enumerate().find_map(|(column, class_ids)| {
+            class_ids.iter().position(|id| id == class_id).map(|row| (column, row))
+        })
+    }
+}
+
+pub fn layout_class(ast: &ClassAst) -> Result<ClassLayout, FlowchartLayoutError> {
+    let mut flowchart = FlowchartAst::default();
+    for (class_id, class) in ast.classes() {
+        flowchart.nodes_mut().insert(class_id.clone(), FlowNode::new(class.label()));
+    }
+    let (structural, other) = ast
+        .relations()
+        .iter()
+        .filter(|(_, relation)| relation.from_class_id() != relation.to_class_id())
+        .partition::<Vec<_>, _>(|(_, relation)| relation.kind().is_structural());
+    for (relation_id, relation) in structural {
+        let edge = FlowEdge::new(relation.to_class_id().clone(), relation.from_class_id().clone());
+        flowchart.edges_mut().insert(relation_id.clone(), edge);
+    }
+    for edge_id in back_edge_ids(&flowchart) {
+        flowchart.edges_mut().remove(&edge_id);
+    }
+    // Other relations only add order where they keep the hierarchy acyclic.
+    for (relation_id, relation) in other {
+        let (from, to) = (relation.from_class_id(), relation.to_class_id());
+        if !reaches(&flowchart, to, from) {
+            flowchart
+                .edges_mut()
+                .insert(relation_id.clone(), FlowEdge::new(from.clone(), to.clone()));
+        }
+    }
+
+    let layout = layout_flowchart(&flowchart)?;
+    Ok(ClassLayout { columns: layout.layers().to_vec() })
+}
+
+fn reaches(flowchart: &FlowchartAst, from: &ObjectId, to: &ObjectId) -> bool {
+    let mut visited = BTreeSet::from([from]);
+    let mut stack = vec![from];
+    while let Some(node_id) = stack.pop() {
+        if node_id == to {
+            return true;
+        }
+        for edge in flowchart.edges().values() {
+            if edge.from_node_id() == node_id && visited.insert(edge.to_node_id()) {
+                stack.push(edge.to_node_id());
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::layout_class;
+    use crate::format::mermaid::parse_class_diagram;
+
+    fn columns(input: &str) -> Vec<Vec<String>> {
+        let ast = parse_class_diagram(input).expect("parse");
+        let layout = layout_class(&ast).expect("layout");
+        layout
+            .columns()
+            .iter()
+            .map(|column| column.iter().map(ToString::to_string).collect())
+            .collect()
+    }
+
+    #[test]
+    fn parents_and_wholes_lead_and_cycles_do_not_fail() {
+        assert_eq!(
+            columns(
+                "classDiagram\nAnimal <|-- Duck\nAnimal <|-- Fish\nPond o-- Duck\n\
+                 Duck --> Pond\nDuck --> Duck\n"
+            ),
+            vec![vec!["c:Animal", "c:Pond"], vec!["c:Fish", "c:Duck"]]
+        );
+        assert_eq!(
+            columns("classDiagram\nA --> B\nB --> C\nC --> A\n"),
+            vec![vec!["c:A"], vec!["c:B"], vec!["c:C"]]
+        );
+    }
+}
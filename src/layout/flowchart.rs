@@ -9,16 +9,25 @@
 use std::cmp::{Ordering, Reverse};
 use std::collections::{BTreeMap, BTreeSet, BinaryHeap};
 
-use crate::model::flow_ast::{FlowEdge, FlowchartAst};
+use crate::model::flow_ast::{FlowDirection, FlowEdge, FlowNodePin, FlowchartAst};
 use crate::model::ids::ObjectId;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FlowchartLayout {
+    direction: FlowDirection,
     layers: Vec<Vec<ObjectId>>,
     node_placements: BTreeMap<ObjectId, FlowNodePlacement>,
 }
 
 impl FlowchartLayout {
+    /// Direction the layers run in; diagrams without a header direction are laid out
+    /// left-to-right.
+    pub fn direction(&self) -> FlowDirection {
+        self.direction
+    }
+
+    /// Layers in drawing order: left to right, or top to bottom for vertical directions. For
+    /// `RL`/`BT` the sources therefore sit in the last layer.
     pub fn layers(&self) -> &[Vec<ObjectId>] {
         &self.layers
     }
@@ -31,19 +40,31 @@ impl FlowchartLayout {
         self.node_placements.get(node_id)
     }
 
+    /// Returns in-layer positions suitable for `FlowchartAst::node_order_hints`.
+    pub fn order_hints(&self) -> BTreeMap<ObjectId, u32> {
+        self.node_placements
+            .iter()
+            .map(|(node_id, placement)| (node_id.clone(), placement.index_in_layer as u32))
+            .collect()
+    }
+
     /// Returns the node's anchor point in the routing grid coordinate system.
     ///
     /// Grid coordinates are integer points. Nodes are placed on even coordinates:
     /// - `x = layer * 2`
     /// - `y = index_in_layer * 2`
     ///
-    /// The extra spacing leaves odd coordinates available for edge routing.
+    /// Vertical directions swap the axes (`x = index_in_layer * 2`, `y = layer * 2`). The extra
+    /// spacing leaves odd coordinates available for edge routing.
     pub fn node_grid_point(&self, node_id: &ObjectId) -> Option<GridPoint> {
         let placement = self.placement(node_id)?;
-        Some(GridPoint::new(
-            (placement.layer() * 2) as i32,
-            (placement.index_in_layer() * 2) as i32,
-        ))
+        let (along, across) =
+            ((placement.layer() * 2) as i32, (placement.index_in_layer() * 2) as i32);
+        Some(if self.direction.is_vertical() {
+            GridPoint::new(across, along)
+        } else {
+            GridPoint::new(along, across)
+        })
     }
 }
 
@@ -236,6 +257,11 @@ fn topo_sort_nodes(ast: &FlowchartAst) -> Result<Vec<ObjectId>, FlowchartLayoutE
             });
         }
 
+        if from == to {
+            // Self-loops never constrain layering; the renderer draws them beside the node.
+            continue;
+        }
+
         outgoing.get_mut(from).expect("node exists (validated)").push(to.clone());
         *indegree.get_mut(to).expect("node exists (validated)") += 1;
     }
@@ -277,9 +303,12 @@ fn topo_sort_nodes(ast: &FlowchartAst) -> Result<Vec<ObjectId>, FlowchartLayoutE
     Ok(topo)
 }
 
+/// Longest-path layering; a pinned node sits no closer to the sources than its pinned layer, and
+/// its successors move along with it.
 fn assign_layers(
     topo: &[ObjectId],
     outgoing: &BTreeMap<ObjectId, Vec<ObjectId>>,
+    pins: &BTreeMap<ObjectId, FlowNodePin>,
 ) -> BTreeMap<ObjectId, usize> {
     let mut layers = BTreeMap::<ObjectId, usize>::new();
     for node_id in topo {
@@ -287,7 +316,11 @@ fn assign_layers(
     }
 
     for from in topo {
-        let from_layer = *layers.get(from).expect("node exists");
+        let layer = layers.get_mut(from).expect("node exists");
+        if let Some(pin) = pins.get(from) {
+            *layer = (*layer).max(pin.layer() as usize);
+        }
+        let from_layer = *layer;
         let tos = outgoing.get(from).map(|v| v.as_slice()).unwrap_or(&[]);
         for to in tos {
             let to_layer = layers.get(to).copied().unwrap_or(0);
@@ -306,13 +339,317 @@ fn sort_layer_by_barycenter(
     layer_nodes.sort_by(|a, b| cmp_layer_nodes_by_barycenter(a, b, prev_positions, predecessors));
 }
 
+/// Iteration budget [`layout_flowchart`] spends on crossing reduction.
+pub const DEFAULT_CROSSING_SWEEPS: usize = 8;
+
+/// Tuning for [`layout_flowchart_with_options`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlowchartLayoutOptions {
+    /// Number of crossing-reduction sweeps; each reorders every layer once, alternating upward and
+    /// downward and median and barycenter ordering, with equal keys swapped in every other round
+    /// of four. `0` keeps the single initial barycenter pass.
+    pub crossing_sweeps: usize,
+}
+
+impl Default for FlowchartLayoutOptions {
+    fn default() -> Self {
+        Self { crossing_sweeps: DEFAULT_CROSSING_SWEEPS }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SweepHeuristic {
+    Median,
+    Barycenter,
+}
+
+/// Position of `node_id` implied by its neighbors in the fixed layer, as a `(numerator,
+/// denominator)` fraction. Nodes without neighbors there keep their current index.
+fn sweep_key(
+    node_id: &ObjectId,
+    current_index: usize,
+    fixed_positions: &BTreeMap<ObjectId, usize>,
+    neighbors: &BTreeMap<ObjectId, Vec<ObjectId>>,
+    heuristic: SweepHeuristic,
+) -> (u64, u64) {
+    let mut positions = neighbors
+        .get(node_id)
+        .into_iter()
+        .flatten()
+        .filter_map(|neighbor| fixed_positions.get(neighbor).map(|pos| *pos as u64))
+        .collect::<Vec<_>>();
+    if positions.is_empty() {
+        return (current_index as u64, 1);
+    }
+
+    match heuristic {
+        SweepHeuristic::Median => {
+            positions.sort_unstable();
+            let mid = positions.len() / 2;
+            if positions.len() % 2 == 1 {
+                (positions[mid], 1)
+            } else {
+                (positions[mid - 1] + positions[mid], 2)
+            }
+        }
+        SweepHeuristic::Barycenter => (positions.iter().sum(), positions.len() as u64),
+    }
+}
+
+fn sort_layer_by_sweep_key(
+    layer_nodes: &mut Vec<ObjectId>,
+    fixed_positions: &BTreeMap<ObjectId, usize>,
+    neighbors: &BTreeMap<ObjectId, Vec<ObjectId>>,
+    heuristic: SweepHeuristic,
+    reverse_ties: bool,
+) {
+    let mut keyed = layer_nodes
+        .drain(..)
+        .enumerate()
+        .map(|(idx, node_id)| {
+            let key = sweep_key(&node_id, idx, fixed_positions, neighbors, heuristic);
+            (key, idx, node_id)
+        })
+        .collect::<Vec<_>>();
+    // Compare the fractions without floats; ties keep the current order, or swap it when
+    // `reverse_ties` is set.
+    keyed.sort_by(|((num_a, den_a), idx_a, _), ((num_b, den_b), idx_b, _)| {
+        let ties = if reverse_ties { idx_b.cmp(idx_a) } else { idx_a.cmp(idx_b) };
+        (*num_a as u128 * *den_b as u128).cmp(&(*num_b as u128 * *den_a as u128)).then(ties)
+    });
+    *layer_nodes = keyed.into_iter().map(|(_, _, node_id)| node_id).collect();
+}
+
+/// Counts crossings between edges that join adjacent layers (sources-first `layers`).
+fn count_adjacent_layer_crossings(
+    layers: &[Vec<ObjectId>],
+    successors: &BTreeMap<ObjectId, Vec<ObjectId>>,
+) -> usize {
+    let mut crossings = 0usize;
+    for pair in layers.windows(2) {
+        let lower_positions = layer_positions(&pair[1]);
+        let mut edges = pair[0]
+            .iter()
+            .enumerate()
+            .flat_map(|(from_pos, from)| {
+                successors
+                    .get(from)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|to| lower_positions.get(to).map(|to_pos| (from_pos, *to_pos)))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        edges.sort_unstable();
+
+        for (idx, (from_a, to_a)) in edges.iter().enumerate() {
+            crossings += edges[idx + 1..]
+                .iter()
+                .filter(|(from_b, to_b)| from_b > from_a && to_b < to_a)
+                .count();
+        }
+    }
+    crossings
+}
+
+/// Runs up to `sweeps` alternating upward/downward sweeps over sources-first `layers` and keeps
+/// the ordering with the fewest crossings as counted by `count` (ties keep the earlier ordering).
+///
+/// `constrain` applies the orderings that must win over crossing reduction (order hints, subgraph
+/// clusters) to every candidate before it is counted.
+fn reduce_crossings(
+    mut layers: Vec<Vec<ObjectId>>,
+    predecessors: &BTreeMap<ObjectId, Vec<ObjectId>>,
+    successors: &BTreeMap<ObjectId, Vec<ObjectId>>,
+    sweeps: usize,
+    constrain: impl Fn(&mut [Vec<ObjectId>]),
+    count: impl Fn(&[Vec<ObjectId>]) -> usize,
+) -> Vec<Vec<ObjectId>> {
+    let mut best = layers.clone();
+    constrain(&mut best);
+    let mut best_crossings = count(&best);
+
+    for sweep in 0..sweeps {
+        if best_crossings == 0 {
+            break;
+        }
+        // Up, down with medians; then up, down with barycenters; then all four again with equal
+        // keys swapped, which lets nodes without a clear preference trade places.
+        let heuristic =
+            if sweep / 2 % 2 == 0 { SweepHeuristic::Median } else { SweepHeuristic::Barycenter };
+        let reverse_ties = sweep / 4 % 2 == 1;
+        if sweep % 2 == 0 {
+            for layer_idx in (0..layers.len().saturating_sub(1)).rev() {
+                let fixed_positions = layer_positions(&layers[layer_idx + 1]);
+                sort_layer_by_sweep_key(
+                    &mut layers[layer_idx],
+                    &fixed_positions,
+                    successors,
+                    heuristic,
+                    reverse_ties,
+                );
+            }
+        } else {
+            for layer_idx in 1..layers.len() {
+                let fixed_positions = layer_positions(&layers[layer_idx - 1]);
+                sort_layer_by_sweep_key(
+                    &mut layers[layer_idx],
+                    &fixed_positions,
+                    predecessors,
+                    heuristic,
+                    reverse_ties,
+                );
+            }
+        }
+
+        let mut candidate = layers.clone();
+        constrain(&mut candidate);
+        let crossings = count(&candidate);
+        if crossings < best_crossings {
+            best = candidate;
+            best_crossings = crossings;
+        }
+    }
+
+    best
+}
+
 /// Deterministic layered layout for flowcharts (DAG-first).
 ///
 /// Baseline:
-/// - Rejects cycles.
+/// - Rejects cycles (self-loops are ignored for layering).
 /// - Assigns node layers via longest-path layering over a deterministic topological order.
-/// - Orders nodes within each layer deterministically (with a simple barycenter sweep).
+/// - Orders nodes within each layer deterministically (with a simple barycenter sweep), then
+///   reduces crossings with [`DEFAULT_CROSSING_SWEEPS`] median/barycenter sweeps.
+/// - Applies persisted `node_order_hints` (hinted nodes first, by hint).
+/// - Keeps members of each subgraph adjacent within a layer (see `cluster_subgraph_members`).
+/// - Keeps pinned nodes at their `node_pins` position (see `pin_nodes`), over both of the above.
 pub fn layout_flowchart(ast: &FlowchartAst) -> Result<FlowchartLayout, FlowchartLayoutError> {
+    layout_flowchart_with_options(ast, FlowchartLayoutOptions::default())
+}
+
+/// [`layout_flowchart`] with an explicit crossing-reduction budget.
+pub fn layout_flowchart_with_options(
+    ast: &FlowchartAst,
+    options: FlowchartLayoutOptions,
+) -> Result<FlowchartLayout, FlowchartLayoutError> {
+    let LayeredNodes { layers, predecessors, successors } = layered_nodes(ast)?;
+
+    let constrain = |layers: &mut [Vec<ObjectId>]| {
+        if !ast.node_order_hints().is_empty() {
+            for layer_nodes in layers.iter_mut() {
+                // Stable sort keeps the swept order for unhinted nodes.
+                layer_nodes.sort_by_key(|node_id| {
+                    ast.node_order_hints().get(node_id).copied().unwrap_or(u32::MAX)
+                });
+            }
+        }
+        cluster_subgraph_members(ast, layers);
+        pin_nodes(ast, layers);
+    };
+    let count = |layers: &[Vec<ObjectId>]| count_adjacent_layer_crossings(layers, &successors);
+    let layers = reduce_crossings(
+        layers,
+        &predecessors,
+        &successors,
+        options.crossing_sweeps,
+        constrain,
+        count,
+    );
+
+    Ok(layout_from_layers(ast, layers))
+}
+
+/// Layered layout with extra crossing minimization ("tidy").
+///
+/// Ignores existing order hints but keeps pinned nodes in place. Starts from the crossing-reduced
+/// order [`layout_flowchart`] uses and runs the same number of sweeps again, now keeping the
+/// ordering with the fewest crossings as counted by [`count_flowchart_crossings`], which also sees
+/// edges that skip layers (ties keep the earlier ordering).
+pub fn layout_flowchart_tidy(ast: &FlowchartAst) -> Result<FlowchartLayout, FlowchartLayoutError> {
+    layout_flowchart_tidy_with_options(ast, FlowchartLayoutOptions::default())
+}
+
+/// [`layout_flowchart_tidy`] with an explicit crossing-reduction budget, spent once per pass.
+pub fn layout_flowchart_tidy_with_options(
+    ast: &FlowchartAst,
+    options: FlowchartLayoutOptions,
+) -> Result<FlowchartLayout, FlowchartLayoutError> {
+    let LayeredNodes { layers, predecessors, successors } = layered_nodes(ast)?;
+
+    let constrain = |layers: &mut [Vec<ObjectId>]| {
+        cluster_subgraph_members(ast, layers);
+        pin_nodes(ast, layers);
+    };
+    let sweeps = options.crossing_sweeps;
+    let layers =
+        reduce_crossings(layers, &predecessors, &successors, sweeps, constrain, |layers| {
+            count_adjacent_layer_crossings(layers, &successors)
+        });
+    let layers =
+        reduce_crossings(layers, &predecessors, &successors, sweeps, constrain, |layers| {
+            count_flowchart_crossings(ast, &layout_from_layers(ast, layers.to_vec()))
+        });
+
+    Ok(layout_from_layers(ast, layers))
+}
+
+/// Counts pairwise crossings of straight edge segments between node anchors.
+///
+/// Edges sharing an endpoint never count as crossing. This is a cheap proxy for visual clutter
+/// that does not depend on orthogonal routing.
+pub fn count_flowchart_crossings(ast: &FlowchartAst, layout: &FlowchartLayout) -> usize {
+    let segments = ast
+        .edges()
+        .values()
+        .filter_map(|edge| {
+            let from = layout.node_grid_point(edge.from_node_id())?;
+            let to = layout.node_grid_point(edge.to_node_id())?;
+            (from.x() != to.x()).then(|| if from.x() < to.x() { (from, to) } else { (to, from) })
+        })
+        .collect::<Vec<_>>();
+
+    let mut crossings = 0usize;
+    for (idx, a) in segments.iter().enumerate() {
+        for b in &segments[idx + 1..] {
+            if segments_cross(*a, *b) {
+                crossings += 1;
+            }
+        }
+    }
+    crossings
+}
+
+fn segments_cross(a: (GridPoint, GridPoint), b: (GridPoint, GridPoint)) -> bool {
+    let x0 = a.0.x().max(b.0.x());
+    let x1 = a.1.x().min(b.1.x());
+    if x0 >= x1 {
+        return false;
+    }
+
+    // Compare `y_a(x) - y_b(x)` at both ends of the shared x-range, scaled by both dx values to
+    // stay in integers.
+    let diff_at = |x: i32| -> i64 {
+        let (a_dx, b_dx) = ((a.1.x() - a.0.x()) as i64, (b.1.x() - b.0.x()) as i64);
+        let a_y = a.0.y() as i64 * a_dx + (a.1.y() - a.0.y()) as i64 * (x - a.0.x()) as i64;
+        let b_y = b.0.y() as i64 * b_dx + (b.1.y() - b.0.y()) as i64 * (x - b.0.x()) as i64;
+        a_y * b_dx - b_y * a_dx
+    };
+    let (start, end) = (diff_at(x0), diff_at(x1));
+    (start < 0 && end > 0) || (start > 0 && end < 0)
+}
+
+struct LayeredNodes {
+    layers: Vec<Vec<ObjectId>>,
+    predecessors: BTreeMap<ObjectId, Vec<ObjectId>>,
+    successors: BTreeMap<ObjectId, Vec<ObjectId>>,
+}
+
+fn layer_positions(layer_nodes: &[ObjectId]) -> BTreeMap<ObjectId, usize> {
+    layer_nodes.iter().enumerate().map(|(idx, node_id)| (node_id.clone(), idx)).collect()
+}
+
+fn layered_nodes(ast: &FlowchartAst) -> Result<LayeredNodes, FlowchartLayoutError> {
     let topo = topo_sort_nodes(ast)?;
 
     // Rebuild adjacency + predecessors with validated nodes.
@@ -322,7 +659,7 @@ pub fn layout_flowchart(ast: &FlowchartAst) -> Result<FlowchartLayout, Flowchart
         outgoing.insert(node_id.clone(), Vec::new());
         predecessors.insert(node_id.clone(), Vec::new());
     }
-    for edge in ast.edges().values() {
+    for edge in ast.edges().values().filter(|edge| edge.from_node_id() != edge.to_node_id()) {
         outgoing
             .get_mut(edge.from_node_id())
             .expect("node exists (validated)")
@@ -339,7 +676,7 @@ pub fn layout_flowchart(ast: &FlowchartAst) -> Result<FlowchartLayout, Flowchart
         sort_object_ids_lexical(preds);
     }
 
-    let node_layers = assign_layers(&topo, &outgoing);
+    let node_layers = assign_layers(&topo, &outgoing, ast.node_pins());
 
     let max_layer = node_layers.values().copied().max().unwrap_or(0);
     let mut layers = vec![Vec::<ObjectId>::new(); max_layer + 1];
@@ -355,13 +692,97 @@ pub fn layout_flowchart(ast: &FlowchartAst) -> Result<FlowchartLayout, Flowchart
 
     // One downward barycenter sweep for readability (deterministic).
     for layer_idx in 1..layers.len() {
-        let prev_positions = layers[layer_idx - 1]
+        let prev_positions = layer_positions(&layers[layer_idx - 1]);
+        sort_layer_by_barycenter(&mut layers[layer_idx], &prev_positions, &predecessors);
+    }
+
+    cluster_subgraph_members(ast, &mut layers);
+
+    Ok(LayeredNodes { layers, predecessors, successors: outgoing })
+}
+
+/// Stable in-layer reorder that makes the members of every subgraph (at each nesting level)
+/// contiguous, anchored where the subgraph's first member sits. Other nodes keep their order.
+fn cluster_subgraph_members(ast: &FlowchartAst, layers: &mut [Vec<ObjectId>]) {
+    if ast.node_subgraphs().is_empty() {
+        return;
+    }
+
+    for layer_nodes in layers.iter_mut() {
+        let chains = layer_nodes
+            .iter()
+            .map(|node_id| {
+                let mut chain = ast.node_subgraph_chain(node_id);
+                chain.reverse();
+                chain
+            })
+            .collect::<Vec<_>>();
+        let mut first_seen = BTreeMap::<&ObjectId, usize>::new();
+        for (idx, chain) in chains.iter().enumerate() {
+            for subgraph_id in chain {
+                first_seen.entry(subgraph_id).or_insert(idx);
+            }
+        }
+
+        let mut keyed = chains
             .iter()
             .enumerate()
-            .map(|(idx, node_id)| (node_id.clone(), idx))
-            .collect::<BTreeMap<_, _>>();
+            .map(|(idx, chain)| {
+                let mut key = chain.iter().map(|id| first_seen[id]).collect::<Vec<_>>();
+                key.push(idx);
+                (key, layer_nodes[idx].clone())
+            })
+            .collect::<Vec<_>>();
+        keyed.sort();
+        *layer_nodes = keyed.into_iter().map(|(_, node_id)| node_id).collect();
+    }
+}
 
-        sort_layer_by_barycenter(&mut layers[layer_idx], &prev_positions, &predecessors);
+/// Moves pinned nodes to their pinned in-layer index (clamped to the layer); the other nodes keep
+/// their relative order in the remaining slots. Pins claiming the same index go in id order.
+fn pin_nodes(ast: &FlowchartAst, layers: &mut [Vec<ObjectId>]) {
+    if ast.node_pins().is_empty() {
+        return;
+    }
+
+    for layer_nodes in layers.iter_mut() {
+        let mut pinned = layer_nodes
+            .iter()
+            .filter_map(|node_id| {
+                ast.node_pins().get(node_id).map(|pin| (pin.index() as usize, node_id.clone()))
+            })
+            .collect::<Vec<_>>();
+        if pinned.is_empty() {
+            continue;
+        }
+        pinned.sort();
+        let mut pinned = pinned.into_iter().peekable();
+        let mut free = layer_nodes
+            .iter()
+            .filter(|node_id| !ast.node_pins().contains_key(*node_id))
+            .cloned()
+            .peekable();
+
+        let mut placed = Vec::with_capacity(layer_nodes.len());
+        while placed.len() < layer_nodes.len() {
+            let take_pinned = match (pinned.peek(), free.peek()) {
+                (Some((index, _)), Some(_)) => *index <= placed.len(),
+                (Some(_), None) => true,
+                (None, _) => false,
+            };
+            let next =
+                if take_pinned { pinned.next().map(|(_, node_id)| node_id) } else { free.next() };
+            placed.push(next.expect("every node is pinned or free"));
+        }
+        *layer_nodes = placed;
+    }
+}
+
+/// Places `layers` (sources first) in drawing order for the AST's direction.
+fn layout_from_layers(ast: &FlowchartAst, mut layers: Vec<Vec<ObjectId>>) -> FlowchartLayout {
+    let direction = ast.direction().unwrap_or(FlowDirection::LeftRight);
+    if direction.is_reversed() {
+        layers.reverse();
     }
 
     let mut node_placements = BTreeMap::<ObjectId, FlowNodePlacement>::new();
@@ -371,7 +792,7 @@ pub fn layout_flowchart(ast: &FlowchartAst) -> Result<FlowchartLayout, Flowchart
         }
     }
 
-    Ok(FlowchartLayout { layers, node_placements })
+    FlowchartLayout { direction, layers, node_placements }
 }
 
 #[derive(Debug, Clone)]
@@ -1399,6 +1820,44 @@ pub fn route_flowchart_edges_orthogonal_key_order(
     routes
 }
 
+/// Columns each gap between adjacent layers needs so edge labels fit on a straight run of their
+/// connector, indexed like the gaps (gap `i` lies between layers `i` and `i + 1`).
+///
+/// `routes` are the key-order routes of [`route_flowchart_edges_orthogonal_key_order`]. A labeled
+/// edge reserves room in the first gap it crosses: its label plus a connector cell on either side
+/// and the arrowhead when the route runs straight into the adjacent layer, and twice that when it
+/// bends there, since the label then has to fit between the box and the lane in the middle.
+pub fn reserve_flowchart_edge_label_gaps(
+    ast: &FlowchartAst,
+    layout: &FlowchartLayout,
+    routes: &[Vec<GridPoint>],
+) -> Vec<usize> {
+    let mut gaps = vec![0usize; layout.layers().len().saturating_sub(1)];
+    for (edge, route) in ast.edges().values().zip(routes) {
+        let Some(label) = edge.label().filter(|label| !label.trim().is_empty()) else {
+            continue;
+        };
+        let (Some(from), Some(to)) =
+            (layout.placement(edge.from_node_id()), layout.placement(edge.to_node_id()))
+        else {
+            continue;
+        };
+        let gap_idx = match from.layer().cmp(&to.layer()) {
+            Ordering::Less => from.layer(),
+            Ordering::Greater => from.layer() - 1,
+            Ordering::Equal => continue,
+        };
+        let straight = from.layer().abs_diff(to.layer()) == 1
+            && route.windows(2).all(|pair| pair[0].y() == pair[1].y());
+        let run = label.chars().count() + 3;
+        let width = if straight { run } else { 2 * run };
+        if let Some(gap) = gaps.get_mut(gap_idx) {
+            *gap = (*gap).max(width);
+        }
+    }
+    gaps
+}
+
 #[cfg(test)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub(crate) struct RoutingQualityDiagnostics {
@@ -1621,14 +2080,16 @@ mod tests {
     use std::collections::{BTreeMap, BTreeSet};
 
     use super::{
-        cmp_edge_routing_order, cmp_layer_nodes_by_barycenter, layout_flowchart,
-        projected_node_obstacle_points, route_flowchart_edges_orthogonal,
+        cmp_edge_routing_order, cmp_layer_nodes_by_barycenter, count_flowchart_crossings,
+        layout_flowchart, layout_flowchart_tidy, layout_flowchart_with_options,
+        projected_node_obstacle_points, reserve_flowchart_edge_label_gaps,
+        route_flowchart_edges_orthogonal, route_flowchart_edges_orthogonal_key_order,
         route_flowchart_edges_orthogonal_with_diagnostics, route_orthogonal,
         route_orthogonal_with_scratch, routing_obstacle_projection, shortest_path_4dir,
         shortest_path_4dir_soft_occupancy, FlowEdgeEndpoint, FlowchartLayout, FlowchartLayoutError,
-        GridBounds, GridPoint, ShortestPathScratch,
+        FlowchartLayoutOptions, GridBounds, GridPoint, ShortestPathScratch,
     };
-    use crate::model::flow_ast::{FlowEdge, FlowNode, FlowchartAst};
+    use crate::model::flow_ast::{FlowDirection, FlowEdge, FlowNode, FlowNodePin, FlowchartAst};
     use crate::model::ids::ObjectId;
 
     fn oid(value: &str) -> ObjectId {
@@ -1699,6 +2160,35 @@ mod tests {
         assert_eq!(layout.placement(&n_d).unwrap().layer(), 2);
     }
 
+    #[test]
+    fn direction_orders_layers_and_orients_grid_points() {
+        let n_a = oid("n:a");
+        let n_c = oid("n:c");
+        let n_d = oid("n:d");
+        let mut ast = crate::model::fixtures::flowchart_small_dag();
+
+        let layout = layout_flowchart(&ast).expect("layout");
+        assert_eq!(layout.direction(), FlowDirection::LeftRight);
+        assert_eq!(layout.node_grid_point(&n_c), Some(gp(2, 2)));
+
+        ast.set_direction(Some(FlowDirection::TopDown));
+        let layout = layout_flowchart(&ast).expect("layout");
+        assert_eq!(layout.placement(&n_a).unwrap().layer(), 0);
+        assert_eq!(layout.node_grid_point(&n_c), Some(gp(2, 2)));
+        assert_eq!(layout.node_grid_point(&n_d), Some(gp(0, 4)));
+
+        ast.set_direction(Some(FlowDirection::RightLeft));
+        let layout = layout_flowchart(&ast).expect("layout");
+        assert_eq!(layout.placement(&n_a).unwrap().layer(), 2);
+        assert_eq!(layout.placement(&n_d).unwrap().layer(), 0);
+        assert_eq!(layout.node_grid_point(&n_a), Some(gp(4, 0)));
+
+        ast.set_direction(Some(FlowDirection::BottomUp));
+        let layout = layout_flowchart_tidy(&ast).expect("layout");
+        assert_eq!(layout.layers()[0], vec![n_d.clone()]);
+        assert_eq!(layout.node_grid_point(&n_a), Some(gp(0, 4)));
+    }
+
     #[test]
     fn orders_nodes_within_layer_using_barycenter_sweep() {
         let mut ast = FlowchartAst::default();
@@ -1726,6 +2216,82 @@ mod tests {
         assert_eq!(layer2, vec!["n:e".to_owned(), "n:d".to_owned()]);
     }
 
+    #[test]
+    fn crossing_sweeps_reorder_earlier_layers_within_the_budget() {
+        let mut ast = FlowchartAst::default();
+        for id in ["n:a", "n:b", "n:c", "n:x", "n:y"] {
+            ast.nodes_mut().insert(oid(id), FlowNode::new(id));
+        }
+        ast.edges_mut().insert(oid("e:ay"), FlowEdge::new(oid("n:a"), oid("n:y")));
+        ast.edges_mut().insert(oid("e:bx"), FlowEdge::new(oid("n:b"), oid("n:x")));
+        ast.edges_mut().insert(oid("e:cy"), FlowEdge::new(oid("n:c"), oid("n:y")));
+
+        let unswept =
+            layout_flowchart_with_options(&ast, FlowchartLayoutOptions { crossing_sweeps: 0 })
+                .expect("layout");
+        assert_eq!(count_flowchart_crossings(&ast, &unswept), 1);
+
+        // The first (upward) sweep moves `n:b` above `n:a`, next to its only target `n:x`.
+        let swept = layout_flowchart(&ast).expect("layout");
+        assert_eq!(count_flowchart_crossings(&ast, &swept), 0);
+        assert_eq!(swept.layers()[0], vec![oid("n:b"), oid("n:a"), oid("n:c")]);
+        assert_eq!(swept.layers()[1], vec![oid("n:x"), oid("n:y")]);
+    }
+
+    #[test]
+    fn tidy_layout_reduces_crossings_and_round_trips_through_order_hints() {
+        let mut ast = FlowchartAst::default();
+        for id in ["n:a", "n:b", "n:c", "n:x", "n:y"] {
+            ast.nodes_mut().insert(oid(id), FlowNode::new(id));
+        }
+        ast.edges_mut().insert(oid("e:ay"), FlowEdge::new(oid("n:a"), oid("n:y")));
+        ast.edges_mut().insert(oid("e:bx"), FlowEdge::new(oid("n:b"), oid("n:x")));
+        ast.edges_mut().insert(oid("e:cy"), FlowEdge::new(oid("n:c"), oid("n:y")));
+
+        let baseline =
+            layout_flowchart_with_options(&ast, FlowchartLayoutOptions { crossing_sweeps: 0 })
+                .expect("layout");
+        assert_eq!(count_flowchart_crossings(&ast, &baseline), 1);
+
+        let tidy = layout_flowchart_tidy(&ast).expect("tidy layout");
+        assert_eq!(count_flowchart_crossings(&ast, &tidy), 0);
+
+        *ast.node_order_hints_mut() = tidy.order_hints();
+        let hinted = layout_flowchart(&ast).expect("hinted layout");
+        assert_eq!(hinted.layers(), tidy.layers());
+    }
+
+    #[test]
+    fn pinned_nodes_keep_their_grid_position_where_the_edges_allow() {
+        let mut ast = FlowchartAst::default();
+        for id in ["n:a", "n:b", "n:c", "n:x", "n:y"] {
+            ast.nodes_mut().insert(oid(id), FlowNode::new(id));
+        }
+        ast.edges_mut().insert(oid("e:ay"), FlowEdge::new(oid("n:a"), oid("n:y")));
+        ast.edges_mut().insert(oid("e:bx"), FlowEdge::new(oid("n:b"), oid("n:x")));
+        ast.edges_mut().insert(oid("e:cy"), FlowEdge::new(oid("n:c"), oid("n:y")));
+
+        // `n:c` moves to the top of its layer; `n:x` one layer further out, past the end of it.
+        ast.node_pins_mut().insert(oid("n:c"), FlowNodePin::new(0, 0));
+        ast.node_pins_mut().insert(oid("n:x"), FlowNodePin::new(2, 5));
+        let pinned = layout_flowchart(&ast).expect("layout");
+        assert_eq!(pinned.layers()[0][0], oid("n:c"));
+        assert_eq!(pinned.layers()[1], vec![oid("n:y")]);
+        assert_eq!(pinned.layers()[2], vec![oid("n:x")]);
+
+        // A pin cannot pull a node in front of its sources, and reversed directions only flip the
+        // drawing order.
+        ast.node_pins_mut().insert(oid("n:y"), FlowNodePin::new(0, 0));
+        ast.set_direction(Some(FlowDirection::RightLeft));
+        let reversed = layout_flowchart(&ast).expect("layout");
+        assert_eq!(reversed.placement(&oid("n:y")).expect("placed").layer(), 1);
+        assert_eq!(reversed.placement(&oid("n:x")).expect("placed").layer(), 0);
+        assert_eq!(reversed.layers()[2][0], oid("n:c"));
+
+        let tidy = layout_flowchart_tidy(&ast).expect("tidy layout");
+        assert_eq!(tidy.layers()[2][0], oid("n:c"));
+    }
+
     #[test]
     fn barycenter_ties_break_by_node_id() {
         let n_prev_a = oid("n:prev:a");
@@ -1782,6 +2348,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn self_loops_do_not_count_as_cycles() {
+        let mut ast = FlowchartAst::default();
+        let n_a = oid("n:a");
+        let n_b = oid("n:b");
+
+        ast.nodes_mut().insert(n_a.clone(), FlowNode::new("A"));
+        ast.nodes_mut().insert(n_b.clone(), FlowNode::new("B"));
+
+        ast.edges_mut().insert(oid("e:aa"), FlowEdge::new(n_a.clone(), n_a.clone()));
+        ast.edges_mut().insert(oid("e:ab"), FlowEdge::new(n_a.clone(), n_b.clone()));
+
+        let layout = layout_flowchart(&ast).expect("layout");
+        assert_eq!(layout.layers(), &[vec![n_a], vec![n_b]]);
+    }
+
     #[test]
     fn errors_on_cycles() {
         let mut ast = FlowchartAst::default();
@@ -1942,6 +2524,7 @@ mod tests {
 
         // Manual layout: A (layer 0) -> B (layer 1) -> C (layer 2), single row.
         let layout = FlowchartLayout {
+            direction: FlowDirection::LeftRight,
             layers: vec![vec![n_a.clone()], vec![n_b.clone()], vec![n_c.clone()]],
             node_placements: BTreeMap::from([
                 (n_a.clone(), super::FlowNodePlacement { layer: 0, index_in_layer: 0 }),
@@ -2156,11 +2739,45 @@ mod tests {
         ast.nodes_mut().insert(n_b.clone(), FlowNode::new("B"));
         ast.edges_mut().insert(oid("e:ab"), FlowEdge::new(n_a, n_b));
 
-        let layout = FlowchartLayout { layers: Vec::new(), node_placements: BTreeMap::new() };
+        let layout = FlowchartLayout {
+            direction: FlowDirection::LeftRight,
+            layers: Vec::new(),
+            node_placements: BTreeMap::new(),
+        };
 
         let routes = route_flowchart_edges_orthogonal(&ast, &layout);
         let route = routes.get(&oid("e:ab")).expect("route");
 
         assert_eq!(route, &vec![gp(0, 0), gp(1, 0)]);
     }
+
+    #[test]
+    fn subgraph_members_stay_contiguous_within_a_layer() {
+        let ast = crate::format::mermaid::parse_flowchart(
+            "flowchart LR\nS --> a\nS --> b\nS --> c\nsubgraph grp\na\nc\nend\n",
+        )
+        .expect("parse");
+
+        for layout in
+            [layout_flowchart(&ast).expect("layout"), layout_flowchart_tidy(&ast).expect("tidy")]
+        {
+            let layer = layout.layers()[1].iter().map(|id| id.as_str()).collect::<Vec<_>>();
+            let a = layer.iter().position(|id| *id == "n:a").expect("a");
+            let c = layer.iter().position(|id| *id == "n:c").expect("c");
+            assert_eq!(a.abs_diff(c), 1, "{layer:?}");
+        }
+    }
+
+    #[test]
+    fn labeled_edges_reserve_room_in_the_first_gap_they_cross() {
+        let ast = crate::format::mermaid::parse_flowchart(
+            "flowchart LR\nA -->|yes| B\nB --> C\nA -->|skip ahead| C\n",
+        )
+        .expect("parse");
+        let layout = layout_flowchart(&ast).expect("layout");
+        let routes = route_flowchart_edges_orthogonal_key_order(&ast, &layout);
+
+        // "yes" runs straight into the next layer; "skip ahead" bends around B in the first gap.
+        assert_eq!(reserve_flowchart_edge_label_gaps(&ast, &layout, &routes), vec![26, 0]);
+    }
 }
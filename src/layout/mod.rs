@@ -10,11 +10,17 @@
 //!
 //! This module computes node placement and edge routing for supported diagram kinds.
 
+pub mod class;
 pub mod flowchart;
 pub mod sequence;
+pub mod state;
 
+pub use class::{layout_class, ClassLayout};
 pub use flowchart::{
-    layout_flowchart, route_flowchart_edges_orthogonal, FlowNodePlacement, FlowchartLayout,
-    FlowchartLayoutError, GridPoint,
+    count_flowchart_crossings, layout_flowchart, layout_flowchart_tidy,
+    layout_flowchart_tidy_with_options, layout_flowchart_with_options,
+    route_flowchart_edges_orthogonal, FlowNodePlacement, FlowchartLayout, FlowchartLayoutError,
+    FlowchartLayoutOptions, GridPoint, DEFAULT_CROSSING_SWEEPS,
 };
 pub use sequence::{layout_sequence, SequenceLayout, SequenceLayoutError};
+pub use state::{layout_state, state_flowchart, StateLayout};
@@ -10,7 +10,10 @@ use std::cmp::Ordering;
 use std::collections::BTreeMap;
 
 use crate::model::ids::ObjectId;
-use crate::model::seq_ast::{SequenceAst, SequenceBlock, SequenceMessage, SequenceSection};
+use crate::model::seq_ast::{
+    SequenceActivation, SequenceAst, SequenceBlock, SequenceMessage, SequenceMessageBoundary,
+    SequenceNote, SequenceNotePlacement, SequenceSection,
+};
 
 const BASE_MESSAGE_LABEL_CAPACITY_PER_SPAN: usize = 16;
 const BASE_PARTICIPANT_LABEL_CAPACITY: usize = 10;
@@ -25,6 +28,8 @@ const PRESSURE_PER_EXTRA_ROW: usize = 8;
 pub struct SequenceLayout {
     participant_cols: BTreeMap<ObjectId, usize>,
     messages: Vec<SequenceMessageLayout>,
+    notes: Vec<SequenceNoteLayout>,
+    activations: Vec<SequenceActivationLayout>,
     spacing_budget: SequenceSpacingBudget,
 }
 
@@ -37,9 +42,23 @@ impl SequenceLayout {
         &self.messages
     }
 
+    pub fn notes(&self) -> &[SequenceNoteLayout] {
+        &self.notes
+    }
+
+    pub fn activations(&self) -> &[SequenceActivationLayout] {
+        &self.activations
+    }
+
     pub fn spacing_budget(&self) -> &SequenceSpacingBudget {
         &self.spacing_budget
     }
+
+    /// Returns the last timeline row used by a message or note.
+    pub fn last_row(&self) -> Option<usize> {
+        let message_rows = self.messages.iter().map(SequenceMessageLayout::row);
+        message_rows.chain(self.notes.iter().map(SequenceNoteLayout::row)).max()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -50,6 +69,7 @@ pub struct SequenceMessageLayout {
     from_col: usize,
     to_col: usize,
     row: usize,
+    boundary: Option<SequenceMessageBoundary>,
 }
 
 impl SequenceMessageLayout {
@@ -76,6 +96,82 @@ impl SequenceMessageLayout {
     pub fn row(&self) -> usize {
         self.row
     }
+
+    pub fn boundary(&self) -> Option<SequenceMessageBoundary> {
+        self.boundary
+    }
+
+    /// Returns `true` for self-messages drawn as a loop (found/lost messages are not loops).
+    pub fn is_self_loop(&self) -> bool {
+        self.boundary.is_none() && self.from_col == self.to_col
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceNoteLayout {
+    note_id: ObjectId,
+    placement: SequenceNotePlacement,
+    from_col: usize,
+    to_col: usize,
+    row: usize,
+}
+
+impl SequenceNoteLayout {
+    pub fn note_id(&self) -> &ObjectId {
+        &self.note_id
+    }
+
+    pub fn placement(&self) -> SequenceNotePlacement {
+        self.placement
+    }
+
+    /// Leftmost anchored participant column.
+    pub fn from_col(&self) -> usize {
+        self.from_col
+    }
+
+    /// Rightmost anchored participant column (equal to `from_col` unless the note spans two).
+    pub fn to_col(&self) -> usize {
+        self.to_col
+    }
+
+    pub fn row(&self) -> usize {
+        self.row
+    }
+}
+
+/// An activation bar on a participant's lifeline, between the rows of its anchoring messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceActivationLayout {
+    activation_id: ObjectId,
+    participant_id: ObjectId,
+    col: usize,
+    start_row: Option<usize>,
+    end_row: Option<usize>,
+}
+
+impl SequenceActivationLayout {
+    pub fn activation_id(&self) -> &ObjectId {
+        &self.activation_id
+    }
+
+    pub fn participant_id(&self) -> &ObjectId {
+        &self.participant_id
+    }
+
+    pub fn col(&self) -> usize {
+        self.col
+    }
+
+    /// Row of the activating message; `None` when active from the top of the lifeline.
+    pub fn start_row(&self) -> Option<usize> {
+        self.start_row
+    }
+
+    /// Row of the deactivating message; `None` when active to the bottom of the lifeline.
+    pub fn end_row(&self) -> Option<usize> {
+        self.end_row
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
@@ -132,6 +228,10 @@ struct SequenceSpacingMeasurement {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SequenceLayoutError {
     UnknownParticipant { message_id: ObjectId, participant_id: ObjectId },
+    UnknownNoteParticipant { note_id: ObjectId, participant_id: ObjectId },
+    MissingNoteParticipant { note_id: ObjectId },
+    UnknownActivationParticipant { activation_id: ObjectId, participant_id: ObjectId },
+    UnknownActivationMessage { activation_id: ObjectId, message_id: ObjectId },
 }
 
 impl std::fmt::Display for SequenceLayoutError {
@@ -140,6 +240,21 @@ impl std::fmt::Display for SequenceLayoutError {
             Self::UnknownParticipant { message_id, participant_id } => {
                 write!(f, "message {message_id} references unknown participant {participant_id}")
             }
+            Self::UnknownNoteParticipant { note_id, participant_id } => {
+                write!(f, "note {note_id} references unknown participant {participant_id}")
+            }
+            Self::MissingNoteParticipant { note_id } => {
+                write!(f, "note {note_id} is not anchored on any participant")
+            }
+            Self::UnknownActivationParticipant { activation_id, participant_id } => {
+                write!(
+                    f,
+                    "activation {activation_id} references unknown participant {participant_id}"
+                )
+            }
+            Self::UnknownActivationMessage { activation_id, message_id } => {
+                write!(f, "activation {activation_id} references unknown message {message_id}")
+            }
         }
     }
 }
@@ -230,7 +345,7 @@ fn measure_sequence_spacing(
 
         measurement.message_span_pressure_by_id.insert(msg.message_id().clone(), span_pressure);
 
-        if msg_layout.from_col() == msg_layout.to_col() {
+        if msg_layout.is_self_loop() {
             let stub_len_target = label_width
                 .saturating_add(SELF_LOOP_CORNER_RESERVE)
                 .clamp(BASE_SELF_LOOP_STUB_LEN, MAX_SELF_LOOP_STUB_LEN);
@@ -391,20 +506,21 @@ fn build_sequence_spacing_budget(
 /// Deterministic “coordinates-only” layout for a sequence diagram.
 ///
 /// Baseline grid:
-/// - `col`: assigned by participant `ObjectId` order (lexical by id)
-/// - `row`: assigned by message `(order_key, message_id)` order
+/// - `col`: assigned by participant `ObjectId` order (lexical by id); members of a `box` group
+///   take adjacent columns (in group order) starting where the first of them would sit
+/// - `row`: assigned by message `(order_key, message_id)` order, with notes interleaved by their
+///   `order_key` (a note sorts after a message with an equal key)
 pub fn layout_sequence(ast: &SequenceAst) -> Result<SequenceLayout, SequenceLayoutError> {
-    let mut participant_cols = BTreeMap::<ObjectId, usize>::new();
-    for (idx, participant_id) in ast.participants().keys().enumerate() {
-        participant_cols.insert(participant_id.clone(), idx);
-    }
+    let participant_cols = assign_participant_cols(ast);
 
     let ordered_messages = messages_in_layout_order(ast);
+    let ordered_notes = ast.notes_in_order();
+    let (message_rows, note_rows) = interleave_timeline_rows(&ordered_messages, &ordered_notes);
     let messages = ordered_messages
         .iter()
         .copied()
-        .enumerate()
-        .map(|(row, msg)| {
+        .zip(message_rows)
+        .map(|(msg, row)| {
             let from_participant_id = msg.from_participant_id().clone();
             let to_participant_id = msg.to_participant_id().clone();
 
@@ -428,15 +544,135 @@ pub fn layout_sequence(ast: &SequenceAst) -> Result<SequenceLayout, SequenceLayo
                 from_col,
                 to_col,
                 row,
+                boundary: msg.boundary(),
             })
         })
         .collect::<Result<Vec<_>, _>>()?;
+    let notes = ordered_notes
+        .iter()
+        .copied()
+        .zip(note_rows)
+        .map(|(note, row)| layout_note(note, row, &participant_cols))
+        .collect::<Result<Vec<_>, _>>()?;
+    let message_row_by_id = messages
+        .iter()
+        .map(|msg| (msg.message_id().clone(), msg.row()))
+        .collect::<BTreeMap<_, _>>();
+    let activations = ast
+        .activations()
+        .iter()
+        .map(|activation| layout_activation(activation, &participant_cols, &message_row_by_id))
+        .collect::<Result<Vec<_>, _>>()?;
 
     let spacing_measurement = measure_sequence_spacing(ast, &messages, &ordered_messages);
     let spacing_budget =
         build_sequence_spacing_budget(ast, &participant_cols, &messages, &spacing_measurement);
 
-    Ok(SequenceLayout { participant_cols, messages, spacing_budget })
+    Ok(SequenceLayout { participant_cols, messages, notes, activations, spacing_budget })
+}
+
+fn assign_participant_cols(ast: &SequenceAst) -> BTreeMap<ObjectId, usize> {
+    ast.participants_in_order()
+        .into_iter()
+        .enumerate()
+        .map(|(col, participant_id)| (participant_id.clone(), col))
+        .collect()
+}
+
+/// Assigns shared timeline rows to ordered messages and notes.
+fn interleave_timeline_rows(
+    messages: &[&SequenceMessage],
+    notes: &[&SequenceNote],
+) -> (Vec<usize>, Vec<usize>) {
+    let mut message_rows = Vec::with_capacity(messages.len());
+    let mut note_rows = Vec::with_capacity(notes.len());
+    let (mut msg_idx, mut note_idx) = (0usize, 0usize);
+    for row in 0..messages.len() + notes.len() {
+        let take_note = match (messages.get(msg_idx), notes.get(note_idx)) {
+            (Some(msg), Some(note)) => note.order_key() < msg.order_key(),
+            (None, Some(_)) => true,
+            _ => false,
+        };
+        if take_note {
+            note_rows.push(row);
+            note_idx += 1;
+        } else {
+            message_rows.push(row);
+            msg_idx += 1;
+        }
+    }
+    (message_rows, note_rows)
+}
+
+fn layout_activation(
+    activation: &SequenceActivation,
+    participant_cols: &BTreeMap<ObjectId, usize>,
+    message_row_by_id: &BTreeMap<ObjectId, usize>,
+) -> Result<SequenceActivationLayout, SequenceLayoutError> {
+    let col = participant_cols.get(activation.participant_id()).copied().ok_or_else(|| {
+        SequenceLayoutError::UnknownActivationParticipant {
+            activation_id: activation.activation_id().clone(),
+            participant_id: activation.participant_id().clone(),
+        }
+    })?;
+    let row_of = |message_id: Option<&ObjectId>| {
+        message_id
+            .map(|message_id| {
+                message_row_by_id.get(message_id).copied().ok_or_else(|| {
+                    SequenceLayoutError::UnknownActivationMessage {
+                        activation_id: activation.activation_id().clone(),
+                        message_id: message_id.clone(),
+                    }
+                })
+            })
+            .transpose()
+    };
+
+    Ok(SequenceActivationLayout {
+        activation_id: activation.activation_id().clone(),
+        participant_id: activation.participant_id().clone(),
+        col,
+        start_row: row_of(activation.start_message_id())?,
+        end_row: row_of(activation.end_message_id())?,
+    })
+}
+
+fn layout_note(
+    note: &SequenceNote,
+    row: usize,
+    participant_cols: &BTreeMap<ObjectId, usize>,
+) -> Result<SequenceNoteLayout, SequenceLayoutError> {
+    let cols = note
+        .participant_ids()
+        .iter()
+        .map(|participant_id| {
+            participant_cols.get(participant_id).copied().ok_or_else(|| {
+                SequenceLayoutError::UnknownNoteParticipant {
+                    note_id: note.note_id().clone(),
+                    participant_id: participant_id.clone(),
+                }
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let (Some(&first), Some(&last)) = (cols.first(), cols.last()) else {
+        return Err(SequenceLayoutError::MissingNoteParticipant {
+            note_id: note.note_id().clone(),
+        });
+    };
+
+    // Side placements only look at the first anchor; `Over` spans every anchor.
+    let (from_col, to_col) = match note.placement() {
+        SequenceNotePlacement::Over => (first.min(last), first.max(last)),
+        SequenceNotePlacement::LeftOf | SequenceNotePlacement::RightOf => (first, first),
+    };
+
+    Ok(SequenceNoteLayout {
+        note_id: note.note_id().clone(),
+        placement: note.placement(),
+        from_col,
+        to_col,
+        row,
+    })
 }
 
 #[cfg(test)]
@@ -449,10 +685,33 @@ mod tests {
     };
     use crate::model::ids::ObjectId;
     use crate::model::seq_ast::{
-        SequenceAst, SequenceBlock, SequenceBlockKind, SequenceMessage, SequenceMessageKind,
-        SequenceParticipant, SequenceSection, SequenceSectionKind,
+        SequenceAst, SequenceBlock, SequenceBlockKind, SequenceGroup, SequenceMessage,
+        SequenceMessageKind, SequenceNote, SequenceNotePlacement, SequenceParticipant,
+        SequenceSection, SequenceSectionKind,
     };
 
+    #[test]
+    fn explicit_participant_order_sets_lifeline_columns() {
+        let mut ast = fixture_ast_messages_out_of_order();
+        let id = |id: &str| ObjectId::new(id).expect("participant id");
+        let cols = |ast: &SequenceAst| {
+            let layout = layout_sequence(ast).expect("layout");
+            ["p:alice", "p:bob", "p:carol"]
+                .map(|participant| layout.participant_cols()[&id(participant)])
+        };
+        assert_eq!(cols(&ast), [0, 1, 2]);
+
+        // Participants missing from the explicit order follow in id order.
+        *ast.participant_order_mut() = vec![id("p:carol")];
+        assert_eq!(cols(&ast), [1, 2, 0]);
+
+        *ast.participant_order_mut() = vec![id("p:bob"), id("p:carol"), id("p:alice")];
+        assert_eq!(cols(&ast), [2, 0, 1]);
+        let layout = layout_sequence(&ast).expect("layout");
+        let first = &layout.messages()[0];
+        assert_eq!((first.from_col(), first.to_col()), (2, 0));
+    }
+
     fn fixture_ast_messages_out_of_order() -> SequenceAst {
         let mut ast = SequenceAst::default();
 
@@ -713,6 +972,60 @@ mod tests {
         );
     }
 
+    #[test]
+    fn layout_interleaves_note_rows_with_messages() {
+        let mut ast = fixture_ast_messages_out_of_order();
+        let p_alice = ObjectId::new("p:alice").expect("participant id");
+        let p_carol = ObjectId::new("p:carol").expect("participant id");
+        ast.notes_mut().push(SequenceNote::new(
+            ObjectId::new("note:0002").expect("note id"),
+            SequenceNotePlacement::Over,
+            vec![p_carol, p_alice.clone()],
+            "span",
+            2000,
+        ));
+        ast.notes_mut().push(SequenceNote::new(
+            ObjectId::new("note:0001").expect("note id"),
+            SequenceNotePlacement::LeftOf,
+            vec![p_alice],
+            "first",
+            0,
+        ));
+
+        let layout = layout_sequence(&ast).expect("layout");
+        let message_rows = layout.messages().iter().map(|msg| msg.row()).collect::<Vec<_>>();
+        assert_eq!(message_rows, vec![1, 2, 3]);
+        let notes = layout
+            .notes()
+            .iter()
+            .map(|note| (note.note_id().as_str(), note.from_col(), note.to_col(), note.row()))
+            .collect::<Vec<_>>();
+        // A note with the same order key as a message follows it.
+        assert_eq!(notes, vec![("note:0001", 0, 0, 0), ("note:0002", 0, 2, 4)]);
+        assert_eq!(layout.last_row(), Some(4));
+    }
+
+    #[test]
+    fn layout_keeps_group_members_in_adjacent_columns() {
+        let mut ast = fixture_ast_messages_out_of_order();
+        let p_alice = ObjectId::new("p:alice").expect("participant id");
+        let p_carol = ObjectId::new("p:carol").expect("participant id");
+        ast.groups_mut().push(SequenceGroup::new(
+            SequenceGroup::make_group_id(1),
+            Some("edge".to_owned()),
+            None,
+            vec![p_carol, p_alice],
+        ));
+
+        let layout = layout_sequence(&ast).expect("layout");
+        let cols = layout
+            .participant_cols()
+            .iter()
+            .map(|(id, col)| (id.as_str(), *col))
+            .collect::<Vec<_>>();
+        assert_eq!(cols, vec![("p:alice", 1), ("p:bob", 2), ("p:carol", 0)]);
+    }
+
     #[test]
     fn layout_errors_on_unknown_participants() {
         let mut ast = SequenceAst::default();
@@ -0,0 +1,226 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! State diagrams are laid out as flowcharts: every state becomes a node under its own id and
+//! every transition an edge, so layering, ordering and routing are shared with flowcharts.
+//!
+//! Unlike flowcharts, state machines loop. Transitions that close a cycle (back edges of a
+//! depth-first walk from the start state) become reversed `<--` edges, so the flowchart stays
+//! acyclic while the arrowhead still points at the transition's target.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::model::flow_ast::{FlowEdge, FlowNode, FlowchartAst};
+use crate::model::ids::ObjectId;
+use crate::model::state_ast::{StateAst, StateNode, StateNodeKind};
+
+use super::flowchart::{layout_flowchart, FlowchartLayout, FlowchartLayoutError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateLayout {
+    flowchart: FlowchartAst,
+    layout: FlowchartLayout,
+}
+
+impl StateLayout {
+    /// The flowchart the state diagram was laid out as.
+    pub fn flowchart(&self) -> &FlowchartAst {
+        &self.flowchart
+    }
+
+    pub fn layout(&self) -> &FlowchartLayout {
+        &self.layout
+    }
+}
+
+/// Text shown inside a state's box.
+pub fn state_box_label(state: &StateNode) -> &str {
+    match state.kind() {
+        StateNodeKind::Start => "●",
+        StateNodeKind::End => "◉",
+        StateNodeKind::Choice => "◇",
+        StateNodeKind::Fork | StateNodeKind::Join => "━━━",
+        StateNodeKind::State => state.label(),
+    }
+}
+
+/// Acyclic flowchart equivalent of `ast`; node and edge ids are the state and transition ids.
+pub fn state_flowchart(ast: &StateAst) -> FlowchartAst {
+    let mut flowchart = FlowchartAst::default();
+    for (state_id, state) in ast.states() {
+        let shape = match state.kind() {
+            StateNodeKind::State => "round",
+            StateNodeKind::Choice => "diamond",
+            _ => "rect",
+        };
+        let mut node = FlowNode::new_with(state_box_label(state), shape, None);
+        node.set_note(state.note());
+        flowchart.nodes_mut().insert(state_id.clone(), node);
+        if state.kind() == StateNodeKind::Start {
+            flowchart.entry_nodes_mut().insert(state_id.clone());
+        }
+    }
+    for (transition_id, transition) in ast.transitions() {
+        let mut edge =
+            FlowEdge::new(transition.from_state_id().clone(), transition.to_state_id().clone());
+        edge.set_label(transition.label());
+        flowchart.edges_mut().insert(transition_id.clone(), edge);
+    }
+    for edge_id in back_edge_ids(&flowchart) {
+        let edge = flowchart.edges_mut().get_mut(&edge_id).expect("back edge exists");
+        let mut reversed = FlowEdge::new(edge.to_node_id().clone(), edge.from_node_id().clone());
+        reversed.set_label(edge.label());
+        reversed.set_connector(Some("<--"));
+        *edge = reversed;
+    }
+    flowchart
+}
+
+pub fn layout_state(ast: &StateAst) -> Result<StateLayout, FlowchartLayoutError> {
+    let flowchart = state_flowchart(ast);
+    let layout = layout_flowchart(&flowchart)?;
+    Ok(StateLayout { flowchart, layout })
+}
+
+/// Edges closing a cycle in a depth-first walk that starts at the entry nodes, then visits the
+/// remaining nodes in id order.
+pub(super) fn back_edge_ids(flowchart: &FlowchartAst) -> BTreeSet<ObjectId> {
+    let mut outgoing = BTreeMap::<&ObjectId, Vec<(&ObjectId, &ObjectId)>>::new();
+    for (edge_id, edge) in flowchart.edges() {
+        outgoing.entry(edge.from_node_id()).or_default().push((edge_id, edge.to_node_id()));
+    }
+    let roots = flowchart
+        .entry_nodes()
+        .iter()
+        .chain(flowchart.nodes().keys().filter(|id| !flowchart.is_entry_node(id)));
+
+    let mut back_edges = BTreeSet::new();
+    let mut visited = BTreeSet::<&ObjectId>::new();
+    for root in roots {
+        if !visited.insert(root) {
+            continue;
+        }
+        // Each frame is a node on the current path and the index of its next outgoing edge.
+        let mut on_path = BTreeSet::from([root]);
+        let mut stack = vec![(root, 0usize)];
+        while let Some(frame) = stack.last_mut() {
+            let (node_id, index) = *frame;
+            frame.1 += 1;
+            let Some(&(edge_id, to)) = outgoing.get(node_id).and_then(|edges| edges.get(index))
+            else {
+                on_path.remove(node_id);
+                stack.pop();
+                continue;
+            };
+            if on_path.contains(to) {
+                back_edges.insert(edge_id.clone());
+            } else if visited.insert(to) {
+                on_path.insert(to);
+                stack.push((to, 0));
+            }
+        }
+    }
+    back_edges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{layout_state, state_flowchart};
+    use crate::format::mermaid::parse_state_diagram;
+    use crate::model::ids::ObjectId;
+    use crate::model::state_ast::StateAst;
+
+    fn oid(value: &str) -> ObjectId {
+        ObjectId::new(value).expect("object id")
+    }
+
+    fn layer_ids(ast: &StateAst) -> Vec<Vec<String>> {
+        let layout = layout_state(ast).expect("layout");
+        layout
+            .layout()
+            .layers()
+            .iter()
+            .map(|layer| layer.iter().map(ToString::to_string).collect())
+            .collect()
+    }
+
+    #[test]
+    fn loops_are_laid_out_top_down_with_reversed_back_edges() {
+        let ast = parse_state_diagram(
+            "stateDiagram-v2\n[*] --> Idle\nIdle --> Busy : work\nBusy --> Idle : done\nBusy --> [*]\n",
+        )
+        .expect("parse");
+
+        assert_eq!(layer_ids(&ast), [["s:[start]"], ["s:Idle"], ["s:Busy"], ["s:[end]"]]);
+
+        let flowchart = state_flowchart(&ast);
+        let back = &flowchart.edges()[&oid("t:0002")];
+        assert_eq!((back.from_node_id(), back.to_node_id()), (&oid("s:Idle"), &oid("s:Busy")));
+        assert_eq!(back.connector(), Some("<--"));
+        assert_eq!(back.label(), Some("done"));
+        let forward = &flowchart.edges()[&oid("t:0001")];
+        assert_eq!(forward.connector(), None);
+    }
+
+    #[test]
+    fn pseudo_states_get_their_own_shapes_and_the_start_state_leads() {
+        let ast = parse_state_diagram(
+            "stateDiagram-v2\nstate check <<choice>>\nstate split <<fork>>\nstate merge <<join>>\n\
+             [*] --> check\ncheck --> Zed\ncheck --> Alpha\nZed --> split\nsplit --> Left\n\
+             split --> Right\nLeft --> merge\nRight --> merge\nmerge --> [*]\nAlpha --> [*]\n",
+        )
+        .expect("parse");
+
+        assert_eq!(
+            layer_ids(&ast),
+            vec![
+                vec!["s:[start]"],
+                vec!["s:check"],
+                vec!["s:Alpha", "s:Zed"],
+                vec!["s:split"],
+                vec!["s:Left", "s:Right"],
+                vec!["s:merge"],
+                vec!["s:[end]"],
+            ]
+        );
+
+        let flowchart = state_flowchart(&ast);
+        assert_eq!(flowchart.entry_nodes().iter().collect::<Vec<_>>(), [&oid("s:[start]")]);
+        let node = |id: &str| {
+            let node = &flowchart.nodes()[&oid(id)];
+            (node.label().to_owned(), node.shape().to_owned())
+        };
+        assert_eq!(node("s:[start]"), ("●".to_owned(), "rect".to_owned()));
+        assert_eq!(node("s:[end]"), ("◉".to_owned(), "rect".to_owned()));
+        assert_eq!(node("s:check"), ("◇".to_owned(), "diamond".to_owned()));
+        assert_eq!(node("s:split"), ("━━━".to_owned(), "rect".to_owned()));
+        assert_eq!(node("s:merge"), ("━━━".to_owned(), "rect".to_owned()));
+        assert_eq!(node("s:Alpha"), ("Alpha".to_owned(), "round".to_owned()));
+    }
+
+    #[test]
+    fn layout_is_deterministic_and_ignores_declaration_order() {
+        let ast = parse_state_diagram(
+            "stateDiagram-v2\n[*] --> B\n[*] --> A\nA --> C\nB --> C\nC --> A\nC --> [*]\n",
+        )
+        .expect("parse");
+        let reordered = parse_state_diagram(
+            "stateDiagram-v2\nstate C\nstate B\nstate A\n[*] --> B\n[*] --> A\nA --> C\nB --> C\nC --> A\nC --> [*]\n",
+        )
+        .expect("parse");
+
+        let layers = layer_ids(&ast);
+        assert_eq!(layers, layer_ids(&ast));
+        assert_eq!(layers, layer_ids(&reordered));
+        // The walk from `[*]` reaches `A` through `B` and `C`, so `A --> C` is the back edge.
+        assert_eq!(
+            layers,
+            vec![vec!["s:[start]"], vec!["s:B"], vec!["s:C"], vec!["s:A", "s:[end]"]]
+        );
+    }
+}
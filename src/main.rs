@@ -12,12 +12,25 @@
 //! `http://127.0.0.1:<port>/mcp`.
 //!
 //! Use `--mcp` to run the MCP server over stdio instead (intended for tool integrations).
+//!
+//! `import` creates a flowchart in a session folder from CSV/JSON node and edge tables.
+//!
+//! `publish` writes a session as a static HTML site (e.g. for GitHub Pages).
+//!
+//! `export` writes diagrams as SVG files or a session as a PDF handout.
+//!
+//! `render-check` compares renders with committed golden snapshots (for CI).
+//!
+//! `archive` packs a session into a single `.nereid.tar.zst` file or unpacks one.
+//!
+//! `new` scaffolds a session folder from a built-in template.
 
 use std::collections::BTreeSet;
 use std::error::Error;
 use std::sync::Arc;
 
 use axum::Router;
+use nereid::format::theme::{ExportSelection, ExportStyle, ExportTheme};
 use rmcp::transport::{
     streamable_http_server::session::local::LocalSessionManager, StreamableHttpServerConfig,
     StreamableHttpService,
@@ -27,8 +40,14 @@ use tokio::sync::Mutex;
 const DEFAULT_MCP_HTTP_PORT: u16 = 27435;
 
 fn print_usage(program: &str) {
+    let default_attention_ttl = nereid::mcp::DEFAULT_ATTENTION_TTL.as_secs();
+    let templates = nereid::store::SessionTemplate::ALL
+        .iter()
+        .map(|template| format!("  {:<18} {}", template.name(), template.summary()))
+        .collect::<Vec<_>>()
+        .join("\n");
     eprintln!(
-        "Usage:\n  {program} [<session-dir>] [--durable-writes] [--mcp-http-port <port>]\n  {program} [--session <dir>] [--durable-writes] [--mcp-http-port <port>]\n  {program} --demo [--mcp-http-port <port>]\n  {program} [<session-dir>] [--durable-writes] --mcp\n  {program} [--session <dir>] [--durable-writes] --mcp\n  {program} --demo --mcp\n\nTUI mode (default) serves MCP over streamable HTTP at `http://127.0.0.1:<port>/mcp`.\n--mcp-http-port selects the port (0 = ephemeral; default {DEFAULT_MCP_HTTP_PORT}).\n\nIf session-dir/--session is omitted, the current working directory is used.\n--demo uses a built-in demo session and cannot be combined with session-dir/--session.\n\n--durable-writes opts into slower, best-effort durable persistence (fsync/sync where supported)."
+        "Usage:\n  {program} [<session-dir>] [--durable-writes] [--lazy] [--mcp-http-port <port>]\n  {program} [--session <dir>] [--durable-writes] [--lazy] [--mcp-http-port <port>]\n  {program} --workspace <dir> [<session-name>] [--durable-writes] [--lazy] [--mcp-http-port <port>]\n  {program} --demo [--mcp-http-port <port>]\n  {program} [<session-dir>] [--durable-writes] [--lazy] --mcp\n  {program} [--session <dir>] [--durable-writes] [--lazy] --mcp\n  {program} --workspace <dir> [<session-name>] [--durable-writes] [--lazy] --mcp\n  {program} --demo --mcp\n  {program} import (--csv|--json) <nodes-file> <edges-file> [--session <dir>] [--diagram-id <id>] [--name <name>] [--map <key>=<column>]...\n  {program} publish --out <dir> [--session <dir>] [--theme <name>] [--selection]\n  {program} export (--svg|--png [--scale <factor>]|--pdf) --out <path> [--session <dir>] [--theme <name>] [--selection]\n  {program} render-check --goldens <dir> [--session <dir>] [--update]\n  {program} archive (export|import) <file> [--session <dir>]\n  {program} new <template> [--session <dir>]\n\nTUI mode (default) serves MCP over streamable HTTP at `http://127.0.0.1:<port>/mcp`.\n--mcp-http-port selects the port (0 = ephemeral; default {DEFAULT_MCP_HTTP_PORT}).\n\nIf session-dir/--session is omitted, the current working directory is used.\n--demo uses a built-in demo session and cannot be combined with session-dir/--session.\n\n--workspace points at a parent directory of session folders; session-dir/--session then names\nthe folder to open first (default: the first by name). The TUI switches with :sessions, agents\nwith workspace.open.\n\n--durable-writes opts into slower, best-effort durable persistence (fsync/sync where supported).\n\n--lazy parses each diagram file on first access instead of while opening the session, so large\nsessions open instantly; diagrams that fail to parse show up empty and their files are left alone.\n\n--attention-ttl <seconds> clears the agent spotlight when the agent sends no heartbeat for that\nlong (0 = never; default {default_attention_ttl}).\n\nimport maps table columns via --map (keys: node_id, node_label, node_shape, edge_from, edge_to,\nedge_label; defaults: id, label, shape, from, to, label).\n\npublish writes index.html plus one page per diagram and walkthrough into --out.\n\nexport --svg/--png writes <diagram-id>.svg/.png per diagram into the --out directory (--scale\nsizes PNGs relative to the SVG, default 1); export --pdf writes one PDF (a page per diagram, then walkthrough steps with diagram snapshots) to the --out file.\n\npublish/export --theme picks the colors (default, print, dark); --selection marks the session's\ncurrent selection.\n\nrender-check compares every diagram and walkthrough render with the goldens in --goldens and\nexits with status 1 on any difference; --update rewrites the goldens instead.\n\narchive export writes the session (meta, diagrams, walkthroughs, xrefs) to one versioned\n.nereid.tar.zst file; archive import unpacks one into a folder without a session.\n\nnew scaffolds a session from a built-in template into a folder without a session:\n{templates}"
     );
 }
 
@@ -37,8 +56,13 @@ struct CliOptions {
     mcp: bool,
     demo: bool,
     session_dir: Option<String>,
+    /// Parent directory of session folders; `session_dir` then names the one to open first.
+    workspace_dir: Option<String>,
     mcp_http_port: Option<u16>,
     durable_writes: bool,
+    /// Parse diagram files on first access instead of while opening the session.
+    lazy: bool,
+    attention_ttl_secs: Option<u64>,
 }
 
 fn parse_options(mut args: impl Iterator<Item = String>) -> Result<CliOptions, ()> {
@@ -65,6 +89,12 @@ fn parse_options(mut args: impl Iterator<Item = String>) -> Result<CliOptions, (
                 let dir = args.next().ok_or(())?;
                 options.session_dir = Some(dir);
             }
+            "--workspace" => {
+                if options.workspace_dir.is_some() {
+                    return Err(());
+                }
+                options.workspace_dir = Some(args.next().ok_or(())?);
+            }
             "--mcp-http-port" => {
                 if options.mcp_http_port.is_some() {
                     return Err(());
@@ -79,6 +109,19 @@ fn parse_options(mut args: impl Iterator<Item = String>) -> Result<CliOptions, (
                 }
                 options.durable_writes = true;
             }
+            "--lazy" => {
+                if options.lazy {
+                    return Err(());
+                }
+                options.lazy = true;
+            }
+            "--attention-ttl" => {
+                if options.attention_ttl_secs.is_some() {
+                    return Err(());
+                }
+                let raw = args.next().ok_or(())?;
+                options.attention_ttl_secs = Some(raw.parse().map_err(|_| ())?);
+            }
             _ if arg.starts_with('-') => return Err(()),
             _ => {
                 if options.session_dir.is_some() {
@@ -89,7 +132,7 @@ fn parse_options(mut args: impl Iterator<Item = String>) -> Result<CliOptions, (
         }
     }
 
-    if options.demo && options.session_dir.is_some() {
+    if options.demo && (options.session_dir.is_some() || options.workspace_dir.is_some()) {
         return Err(());
     }
 
@@ -100,11 +143,573 @@ fn parse_options(mut args: impl Iterator<Item = String>) -> Result<CliOptions, (
     Ok(options)
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ImportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ImportOptions {
+    format: ImportFormat,
+    nodes_path: String,
+    edges_path: String,
+    session_dir: Option<String>,
+    diagram_id: Option<String>,
+    name: Option<String>,
+    mappings: Vec<(String, String)>,
+}
+
+fn parse_import_options(mut args: impl Iterator<Item = String>) -> Result<ImportOptions, ()> {
+    let mut format = None;
+    let mut session_dir = None;
+    let mut diagram_id = None;
+    let mut name = None;
+    let mut mappings = Vec::new();
+    let mut paths = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--csv" | "--json" => {
+                if format.is_some() {
+                    return Err(());
+                }
+                format = Some(if arg == "--csv" { ImportFormat::Csv } else { ImportFormat::Json });
+            }
+            "--session" | "--diagram-id" | "--name" => {
+                let slot = match arg.as_str() {
+                    "--session" => &mut session_dir,
+                    "--diagram-id" => &mut diagram_id,
+                    _ => &mut name,
+                };
+                if slot.is_some() {
+                    return Err(());
+                }
+                *slot = Some(args.next().ok_or(())?);
+            }
+            "--map" => {
+                let raw = args.next().ok_or(())?;
+                let (key, column) = raw.split_once('=').ok_or(())?;
+                mappings.push((key.to_owned(), column.to_owned()));
+            }
+            _ if arg.starts_with('-') => return Err(()),
+            _ => paths.push(arg),
+        }
+    }
+
+    let (Some(format), [nodes_path, edges_path]) = (format, paths.as_slice()) else {
+        return Err(());
+    };
+
+    Ok(ImportOptions {
+        format,
+        nodes_path: nodes_path.clone(),
+        edges_path: edges_path.clone(),
+        session_dir,
+        diagram_id,
+        name,
+        mappings,
+    })
+}
+
+fn run_import(options: ImportOptions) -> Result<(), Box<dyn Error>> {
+    use nereid::format::tabular::{
+        flowchart_from_rows, parse_csv_rows, parse_json_rows, ColumnMapping,
+    };
+    use nereid::model::{Diagram, DiagramAst, DiagramId};
+
+    let mut mapping = ColumnMapping::default();
+    for (key, column) in options.mappings {
+        mapping.set(&key, column)?;
+    }
+
+    let parse_rows = match options.format {
+        ImportFormat::Csv => parse_csv_rows,
+        ImportFormat::Json => parse_json_rows,
+    };
+    let nodes = parse_rows(&std::fs::read_to_string(&options.nodes_path)?)?;
+    let edges = parse_rows(&std::fs::read_to_string(&options.edges_path)?)?;
+    let ast = flowchart_from_rows(&nodes, &edges, &mapping)?;
+    let (node_count, edge_count) = (ast.nodes().len(), ast.edges().len());
+
+    let folder =
+        nereid::store::SessionFolder::new(options.session_dir.unwrap_or_else(|| ".".to_owned()));
+    let mut session = folder.load_or_init_session()?;
+
+    let diagram_id = match options.diagram_id {
+        Some(diagram_id) => DiagramId::new(diagram_id)?,
+        None => (1..)
+            .map(|idx| if idx == 1 { "flow".to_owned() } else { format!("flow-{idx}") })
+            .find(|candidate| !session.diagrams().contains_key(candidate.as_str()))
+            .map(|candidate| DiagramId::new(candidate).expect("valid diagram id"))
+            .expect("exhausted diagram id space"),
+    };
+    if session.diagrams().contains_key(&diagram_id) {
+        return Err(format!("diagram already exists: {diagram_id}").into());
+    }
+
+    let name = options.name.unwrap_or_else(|| diagram_id.as_str().to_owned());
+    let diagram = Diagram::new(diagram_id.clone(), name, DiagramAst::Flowchart(ast));
+    nereid::render::render_diagram_unicode(&diagram)?;
+
+    session.diagrams_mut().insert(diagram_id.clone(), diagram);
+    session.set_active_diagram_id(Some(diagram_id.clone()));
+    folder.save_session(&session)?;
+
+    println!("imported {node_count} nodes and {edge_count} edges into diagram {diagram_id}");
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PublishOptions {
+    out_dir: String,
+    session_dir: Option<String>,
+    theme: Option<ExportTheme>,
+    /// Mark the session's current selection in the output.
+    selection: bool,
+}
+
+fn parse_publish_options(mut args: impl Iterator<Item = String>) -> Result<PublishOptions, ()> {
+    let mut out_dir = None;
+    let mut session_dir = None;
+    let mut theme = None;
+    let mut selection = false;
+
+    while let Some(arg) = args.next() {
+        let slot = match arg.as_str() {
+            "--out" => &mut out_dir,
+            "--session" => &mut session_dir,
+            "--theme" => {
+                parse_theme_option(&mut theme, args.next())?;
+                continue;
+            }
+            "--selection" if !selection => {
+                selection = true;
+                continue;
+            }
+            _ => return Err(()),
+        };
+        if slot.is_some() {
+            return Err(());
+        }
+        *slot = Some(args.next().ok_or(())?);
+    }
+
+    Ok(PublishOptions { out_dir: out_dir.ok_or(())?, session_dir, theme, selection })
+}
+
+/// Sets `slot` to the export theme named `value`; fails on unknown names and repeats.
+fn parse_theme_option(slot: &mut Option<ExportTheme>, value: Option<String>) -> Result<(), ()> {
+    if slot.is_some() {
+        return Err(());
+    }
+    *slot = Some(ExportTheme::named(&value.ok_or(())?).ok_or(())?);
+    Ok(())
+}
+
+fn export_style(
+    session: &nereid::model::Session,
+    theme: Option<ExportTheme>,
+    selection: bool,
+) -> ExportStyle {
+    let selection = if selection {
+        ExportSelection::selected(session.selected_object_refs().clone())
+    } else {
+        ExportSelection::default()
+    };
+    ExportStyle {
+        theme: theme.unwrap_or_default(),
+        selection,
+        object_styles: nereid::query::objects::session_object_styles(session),
+    }
+}
+
+fn run_publish(options: PublishOptions) -> Result<(), Box<dyn Error>> {
+    let folder =
+        nereid::store::SessionFolder::new(options.session_dir.unwrap_or_else(|| ".".to_owned()));
+    let session = folder.load_session()?;
+    let style = export_style(&session, options.theme, options.selection);
+    let site = nereid::format::site::publish_site(&session, options.out_dir.as_ref(), &style)?;
+
+    println!("published {} files to {}", site.files.len(), options.out_dir);
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    Svg,
+    Png,
+    Pdf,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ExportOptions {
+    format: ExportFormat,
+    out_path: String,
+    session_dir: Option<String>,
+    /// PNG size relative to the SVG export; only valid with `--png`.
+    scale: Option<f32>,
+    theme: Option<ExportTheme>,
+    /// Mark the session's current selection in the output.
+    selection: bool,
+}
+
+fn parse_export_options(mut args: impl Iterator<Item = String>) -> Result<ExportOptions, ()> {
+    let mut format = None;
+    let mut out_path = None;
+    let mut session_dir = None;
+    let mut scale = None;
+    let mut theme = None;
+    let mut selection = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--svg" | "--png" | "--pdf" => {
+                if format.is_some() {
+                    return Err(());
+                }
+                format = Some(match arg.as_str() {
+                    "--svg" => ExportFormat::Svg,
+                    "--png" => ExportFormat::Png,
+                    _ => ExportFormat::Pdf,
+                });
+            }
+            "--scale" => {
+                let value = args.next().ok_or(())?.parse::<f32>().map_err(|_| ())?;
+                if scale.is_some() || !value.is_finite() || value <= 0.0 {
+                    return Err(());
+                }
+                scale = Some(value);
+            }
+            "--theme" => parse_theme_option(&mut theme, args.next())?,
+            "--selection" if !selection => selection = true,
+            "--out" | "--session" => {
+                let slot = if arg == "--out" { &mut out_path } else { &mut session_dir };
+                if slot.is_some() {
+                    return Err(());
+                }
+                *slot = Some(args.next().ok_or(())?);
+            }
+            _ => return Err(()),
+        }
+    }
+
+    let format = format.ok_or(())?;
+    if scale.is_some() && format != ExportFormat::Png {
+        return Err(());
+    }
+
+    Ok(ExportOptions {
+        format,
+        out_path: out_path.ok_or(())?,
+        session_dir,
+        scale,
+        theme,
+        selection,
+    })
+}
+
+fn run_export(options: ExportOptions) -> Result<(), Box<dyn Error>> {
+    use nereid::format::{pdf, png, site, svg};
+
+    let folder =
+        nereid::store::SessionFolder::new(options.session_dir.unwrap_or_else(|| ".".to_owned()));
+    let session = folder.load_session()?;
+    let style = export_style(&session, options.theme, options.selection);
+    let out_path = std::path::Path::new(&options.out_path);
+
+    match options.format {
+        ExportFormat::Svg => {
+            std::fs::create_dir_all(out_path)?;
+            for (diagram_id, diagram) in session.diagrams() {
+                let file_name = format!("{}.svg", site::page_slug(diagram_id.as_str()));
+                std::fs::write(out_path.join(file_name), svg::diagram_svg(diagram, &style)?)?;
+            }
+            println!("exported {} diagrams to {}", session.diagrams().len(), options.out_path);
+        }
+        ExportFormat::Png => {
+            let scale = options.scale.unwrap_or(1.0);
+            std::fs::create_dir_all(out_path)?;
+            for (diagram_id, diagram) in session.diagrams() {
+                let file_name = format!("{}.png", site::page_slug(diagram_id.as_str()));
+                std::fs::write(
+                    out_path.join(file_name),
+                    png::diagram_png(diagram, scale, &style)?,
+                )?;
+            }
+            println!("exported {} diagrams to {}", session.diagrams().len(), options.out_path);
+        }
+        ExportFormat::Pdf => {
+            std::fs::write(out_path, pdf::session_pdf(&session, &style))?;
+            println!("exported session to {}", options.out_path);
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RenderCheckOptions {
+    goldens_dir: String,
+    session_dir: Option<String>,
+    /// Rewrite the goldens from the current renders instead of comparing.
+    update: bool,
+}
+
+fn parse_render_check_options(
+    mut args: impl Iterator<Item = String>,
+) -> Result<RenderCheckOptions, ()> {
+    let mut goldens_dir = None;
+    let mut session_dir = None;
+    let mut update = false;
+
+    while let Some(arg) = args.next() {
+        let slot = match arg.as_str() {
+            "--goldens" => &mut goldens_dir,
+            "--session" => &mut session_dir,
+            "--update" if !update => {
+                update = true;
+                continue;
+            }
+            _ => return Err(()),
+        };
+        if slot.is_some() {
+            return Err(());
+        }
+        *slot = Some(args.next().ok_or(())?);
+    }
+
+    Ok(RenderCheckOptions { goldens_dir: goldens_dir.ok_or(())?, session_dir, update })
+}
+
+fn run_render_check(options: RenderCheckOptions) -> Result<(), Box<dyn Error>> {
+    use nereid::render::golden::{check_goldens, write_goldens};
+
+    let folder =
+        nereid::store::SessionFolder::new(options.session_dir.unwrap_or_else(|| ".".to_owned()));
+    let session = folder.load_session()?;
+    let dir = std::path::Path::new(&options.goldens_dir);
+
+    if options.update {
+        let written = write_goldens(&session, dir)?;
+        println!("wrote {} goldens to {}", written.len(), options.goldens_dir);
+        return Ok(());
+    }
+
+    let report = check_goldens(&session, dir)?;
+    if report.is_clean() {
+        println!("{} renders match the goldens in {}", report.matched.len(), options.goldens_dir);
+        return Ok(());
+    }
+    for mismatch in &report.mismatched {
+        eprintln!("changed: {} (line {})", mismatch.file_name, mismatch.line);
+        eprintln!("  - {}", mismatch.expected.as_deref().unwrap_or("<end of file>"));
+        eprintln!("  + {}", mismatch.actual.as_deref().unwrap_or("<end of file>"));
+    }
+    for file_name in &report.missing {
+        eprintln!("missing golden: {file_name}");
+    }
+    for file_name in &report.stale {
+        eprintln!("stale golden: {file_name}");
+    }
+    eprintln!("render-check failed; run with --update to accept the current renders");
+    std::process::exit(1);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ArchiveOptions {
+    /// `true` for `archive import`, `false` for `archive export`.
+    import: bool,
+    archive_path: String,
+    session_dir: Option<String>,
+}
+
+fn parse_archive_options(mut args: impl Iterator<Item = String>) -> Result<ArchiveOptions, ()> {
+    let import = match args.next().as_deref() {
+        Some("export") => false,
+        Some("import") => true,
+        _ => return Err(()),
+    };
+    let mut archive_path = None;
+    let mut session_dir = None;
+
+    while let Some(arg) = args.next() {
+        let slot = match arg.as_str() {
+            "--session" => {
+                if session_dir.is_some() {
+                    return Err(());
+                }
+                session_dir = Some(args.next().ok_or(())?);
+                continue;
+            }
+            _ if arg.starts_with('-') => return Err(()),
+            _ => &mut archive_path,
+        };
+        if slot.is_some() {
+            return Err(());
+        }
+        *slot = Some(arg);
+    }
+
+    Ok(ArchiveOptions { import, archive_path: archive_path.ok_or(())?, session_dir })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct NewOptions {
+    template: nereid::store::SessionTemplate,
+    session_dir: Option<String>,
+}
+
+fn parse_new_options(mut args: impl Iterator<Item = String>) -> Result<NewOptions, ()> {
+    let template =
+        args.next().as_deref().and_then(nereid::store::SessionTemplate::parse).ok_or(())?;
+    let mut session_dir = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--session" if session_dir.is_none() => session_dir = Some(args.next().ok_or(())?),
+            _ => return Err(()),
+        }
+    }
+
+    Ok(NewOptions { template, session_dir })
+}
+
+fn run_new(options: NewOptions) -> Result<(), Box<dyn Error>> {
+    let folder =
+        nereid::store::SessionFolder::new(options.session_dir.unwrap_or_else(|| ".".to_owned()));
+    let session = folder.init_from_template(options.template)?;
+    println!(
+        "created session {} from template {} with {} diagrams in {}",
+        session.session_id(),
+        options.template,
+        session.diagrams().len(),
+        folder.root().display()
+    );
+    Ok(())
+}
+
+fn run_archive(options: ArchiveOptions) -> Result<(), Box<dyn Error>> {
+    let folder =
+        nereid::store::SessionFolder::new(options.session_dir.unwrap_or_else(|| ".".to_owned()));
+    let archive_path = std::path::Path::new(&options.archive_path);
+
+    if options.import {
+        let session = folder.import_archive(archive_path)?;
+        println!(
+            "imported session {} with {} diagrams into {}",
+            session.session_id(),
+            session.diagrams().len(),
+            folder.root().display()
+        );
+    } else {
+        let files = folder.export_archive(archive_path)?;
+        println!("archived {files} session files to {}", options.archive_path);
+    }
+    Ok(())
+}
+
+/// Opens the workspace session `name`, or the first one when `None`.
+fn open_workspace_session(
+    workspace: &nereid::store::Workspace,
+    name: Option<String>,
+) -> Result<(nereid::store::SessionFolder, nereid::model::Session), Box<dyn Error>> {
+    let name = match name {
+        Some(name) => name,
+        None => {
+            workspace.list_sessions()?.into_iter().next().map(|session| session.name).ok_or_else(
+                || format!("no session folders in workspace {}", workspace.root().display()),
+            )?
+        }
+    };
+    let (folder, session, recovered) = workspace.open_session(&name)?;
+    if recovered > 0 {
+        eprintln!("nereid: recovered {recovered} unsynced edit(s) from ops.log");
+    }
+    Ok((folder, session))
+}
+
+fn write_durability(options: &CliOptions) -> nereid::store::WriteDurability {
+    if options.durable_writes {
+        nereid::store::WriteDurability::Durable
+    } else {
+        nereid::store::WriteDurability::BestEffort
+    }
+}
+
+fn load_mode(options: &CliOptions) -> nereid::store::LoadMode {
+    if options.lazy {
+        nereid::store::LoadMode::Lazy
+    } else {
+        nereid::store::LoadMode::Eager
+    }
+}
+
+/// Loads the session and replays edits from `ops.log` that never reached the diagram files.
+fn load_and_recover_session(
+    folder: &nereid::store::SessionFolder,
+) -> Result<nereid::model::Session, Box<dyn Error>> {
+    let mut session = folder.load_or_init_session()?;
+    let recovered = folder.recover_from_ops_journal(&mut session)?;
+    if recovered > 0 {
+        eprintln!("nereid: recovered {recovered} unsynced edit(s) from ops.log");
+    }
+    Ok(session)
+}
+
 fn main() {
     let result = (|| -> Result<(), Box<dyn Error>> {
-        let mut args = std::env::args();
+        let mut args = std::env::args().peekable();
         let program = args.next().unwrap_or_else(|| "nereid".to_owned());
 
+        if args.peek().map(String::as_str) == Some("import") {
+            args.next();
+            let Ok(options) = parse_import_options(args) else {
+                print_usage(&program);
+                std::process::exit(2);
+            };
+            return run_import(options);
+        }
+        if args.peek().map(String::as_str) == Some("publish") {
+            args.next();
+            let Ok(options) = parse_publish_options(args) else {
+                print_usage(&program);
+                std::process::exit(2);
+            };
+            return run_publish(options);
+        }
+        if args.peek().map(String::as_str) == Some("export") {
+            args.next();
+            let Ok(options) = parse_export_options(args) else {
+                print_usage(&program);
+                std::process::exit(2);
+            };
+            return run_export(options);
+        }
+        if args.peek().map(String::as_str) == Some("render-check") {
+            args.next();
+            let Ok(options) = parse_render_check_options(args) else {
+                print_usage(&program);
+                std::process::exit(2);
+            };
+            return run_render_check(options);
+        }
+        if args.peek().map(String::as_str) == Some("new") {
+            args.next();
+            let Ok(options) = parse_new_options(args) else {
+                print_usage(&program);
+                std::process::exit(2);
+            };
+            return run_new(options);
+        }
+        if args.peek().map(String::as_str) == Some("archive") {
+            args.next();
+            let Ok(options) = parse_archive_options(args) else {
+                print_usage(&program);
+                std::process::exit(2);
+            };
+            return run_archive(options);
+        }
+
         let options = match parse_options(args) {
             Ok(options) => options,
             Err(()) => {
@@ -113,21 +718,33 @@ fn main() {
             }
         };
 
+        let attention_ttl = match options.attention_ttl_secs {
+            None => Some(nereid::mcp::DEFAULT_ATTENTION_TTL),
+            Some(0) => None,
+            Some(secs) => Some(std::time::Duration::from_secs(secs)),
+        };
+        let workspace = options.workspace_dir.clone().map(|dir| {
+            nereid::store::Workspace::new(dir)
+                .with_durability(write_durability(&options))
+                .with_load_mode(load_mode(&options))
+        });
+
         if options.mcp {
             let mcp = if options.demo {
                 let session = nereid::tui::demo_session();
                 nereid::mcp::NereidMcp::new(session)
+            } else if let Some(workspace) = workspace {
+                let (folder, session) = open_workspace_session(&workspace, options.session_dir)?;
+                nereid::mcp::NereidMcp::new_persistent(session, folder).with_workspace(workspace)
             } else {
-                let dir = options.session_dir.unwrap_or_else(|| ".".to_owned());
-                let folder = if options.durable_writes {
-                    nereid::store::SessionFolder::new(dir)
-                        .with_durability(nereid::store::WriteDurability::Durable)
-                } else {
-                    nereid::store::SessionFolder::new(dir)
-                };
-                let session = folder.load_or_init_session()?;
+                let dir = options.session_dir.clone().unwrap_or_else(|| ".".to_owned());
+                let folder = nereid::store::SessionFolder::new(dir)
+                    .with_durability(write_durability(&options))
+                    .with_load_mode(load_mode(&options));
+                let session = load_and_recover_session(&folder)?;
                 nereid::mcp::NereidMcp::new_persistent(session, folder)
             };
+            let mcp = mcp.with_attention_ttl(attention_ttl);
 
             let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
 
@@ -148,12 +765,8 @@ fn main() {
                 .unwrap_or(0);
             let demo_dir = std::env::temp_dir()
                 .join(format!("nereid-demo-session-{}-{now_millis}", std::process::id()));
-            let folder = if options.durable_writes {
-                nereid::store::SessionFolder::new(demo_dir)
-                    .with_durability(nereid::store::WriteDurability::Durable)
-            } else {
-                nereid::store::SessionFolder::new(demo_dir)
-            };
+            let folder = nereid::store::SessionFolder::new(demo_dir)
+                .with_durability(write_durability(&options));
             let session = nereid::tui::demo_session();
             folder.save_session(&session)?;
             let tui_session = session.clone();
@@ -163,17 +776,31 @@ fn main() {
                 folder,
                 agent_highlights.clone(),
                 Some(ui_state.clone()),
-            );
+            )
+            .with_attention_ttl(attention_ttl);
+            (tui_session, Some(tui_session_folder), mcp)
+        } else if let Some(workspace) = workspace.clone() {
+            let (folder, session) = open_workspace_session(&workspace, options.session_dir)?;
+            if let Some(name) = workspace.session_name(&folder) {
+                ui_state.blocking_lock().set_workspace_session(name);
+            }
+            let tui_session = session.clone();
+            let tui_session_folder = folder.clone();
+            let mcp = nereid::mcp::NereidMcp::new_persistent_with_agent_highlights_and_ui_state(
+                session,
+                folder,
+                agent_highlights.clone(),
+                Some(ui_state.clone()),
+            )
+            .with_attention_ttl(attention_ttl)
+            .with_workspace(workspace);
             (tui_session, Some(tui_session_folder), mcp)
         } else {
-            let dir = options.session_dir.unwrap_or_else(|| ".".to_owned());
-            let folder = if options.durable_writes {
-                nereid::store::SessionFolder::new(dir)
-                    .with_durability(nereid::store::WriteDurability::Durable)
-            } else {
-                nereid::store::SessionFolder::new(dir)
-            };
-            let session = folder.load_or_init_session()?;
+            let dir = options.session_dir.clone().unwrap_or_else(|| ".".to_owned());
+            let folder = nereid::store::SessionFolder::new(dir)
+                .with_durability(write_durability(&options))
+                .with_load_mode(load_mode(&options));
+            let session = load_and_recover_session(&folder)?;
             let tui_session = session.clone();
             let tui_session_folder = folder.clone();
             let mcp = nereid::mcp::NereidMcp::new_persistent_with_agent_highlights_and_ui_state(
@@ -181,7 +808,8 @@ fn main() {
                 folder,
                 agent_highlights.clone(),
                 Some(ui_state.clone()),
-            );
+            )
+            .with_attention_ttl(attention_ttl);
             (tui_session, Some(tui_session_folder), mcp)
         };
 
@@ -216,11 +844,12 @@ fn main() {
             let tui_agent_highlights = agent_highlights.clone();
             let tui_ui_state = ui_state.clone();
             let tui_join = tokio::task::spawn_blocking(move || {
-                nereid::tui::run_with_session_with_ui_state(
+                nereid::tui::run_with_session_in_workspace(
                     tui_session,
                     tui_agent_highlights,
                     Some(tui_ui_state),
                     tui_session_folder,
+                    workspace,
                 )
                 .map_err(|err| err.to_string())
             })
@@ -247,7 +876,12 @@ fn main() {
 
 #[cfg(test)]
 mod tests {
-    use super::{parse_options, CliOptions};
+    use super::{
+        parse_archive_options, parse_export_options, parse_import_options, parse_new_options,
+        parse_options, parse_publish_options, parse_render_check_options, ArchiveOptions,
+        CliOptions, ExportFormat, ExportOptions, ExportTheme, ImportFormat, NewOptions,
+        PublishOptions, RenderCheckOptions,
+    };
 
     #[test]
     fn parses_empty_args() {
@@ -291,6 +925,23 @@ mod tests {
         assert!(!options.mcp);
     }
 
+    #[test]
+    fn parses_attention_ttl() {
+        let options = parse_options(["--attention-ttl", "30"].into_iter().map(str::to_owned))
+            .expect("parse options");
+        assert_eq!(options.attention_ttl_secs, Some(30));
+        parse_options(["--attention-ttl", "soon"].into_iter().map(str::to_owned)).unwrap_err();
+    }
+
+    #[test]
+    fn parses_lazy_flag() {
+        let options = parse_options(["--lazy", "--mcp"].into_iter().map(str::to_owned))
+            .expect("parse options");
+        assert!(options.lazy);
+        assert!(options.mcp);
+        parse_options(["--lazy", "--lazy"].into_iter().map(str::to_owned)).unwrap_err();
+    }
+
     #[test]
     fn rejects_mcp_http_port_with_stdio_mcp_mode() {
         parse_options(
@@ -318,6 +969,20 @@ mod tests {
             .unwrap_err();
     }
 
+    #[test]
+    fn parses_workspace_with_session_name() {
+        let options = parse_options(
+            ["--workspace".to_owned(), "sessions".to_owned(), "billing".to_owned()].into_iter(),
+        )
+        .expect("parse options");
+        assert_eq!(options.workspace_dir.as_deref(), Some("sessions"));
+        assert_eq!(options.session_dir.as_deref(), Some("billing"));
+
+        parse_options(["--demo".to_owned(), "--workspace".to_owned(), ".".to_owned()].into_iter())
+            .unwrap_err();
+        parse_options(["--workspace".to_owned()].into_iter()).unwrap_err();
+    }
+
     #[test]
     fn parses_positional_session_dir() {
         let options = parse_options(["some/dir".to_owned()].into_iter()).expect("parse options");
@@ -368,4 +1033,191 @@ mod tests {
     fn rejects_missing_session_value() {
         parse_options(["--session".to_owned()].into_iter()).unwrap_err();
     }
+
+    #[test]
+    fn parses_import_options() {
+        let options = parse_import_options(
+            [
+                "--csv",
+                "nodes.csv",
+                "edges.csv",
+                "--session",
+                "dir",
+                "--map",
+                "node_id=Key",
+                "--map",
+                "edge_from=Source",
+            ]
+            .into_iter()
+            .map(str::to_owned),
+        )
+        .expect("parse import options");
+
+        assert_eq!(options.format, ImportFormat::Csv);
+        assert_eq!(options.nodes_path, "nodes.csv");
+        assert_eq!(options.edges_path, "edges.csv");
+        assert_eq!(options.session_dir.as_deref(), Some("dir"));
+        assert_eq!(
+            options.mappings,
+            vec![
+                ("node_id".to_owned(), "Key".to_owned()),
+                ("edge_from".to_owned(), "Source".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_incomplete_import_options() {
+        parse_import_options(["nodes.csv".to_owned(), "edges.csv".to_owned()].into_iter())
+            .unwrap_err();
+        parse_import_options(["--json".to_owned(), "nodes.json".to_owned()].into_iter())
+            .unwrap_err();
+        parse_import_options(["--csv", "--json", "a", "b"].into_iter().map(str::to_owned))
+            .unwrap_err();
+        parse_import_options(
+            ["--csv", "a", "b", "--map", "node_id"].into_iter().map(str::to_owned),
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn parses_publish_options() {
+        let options = parse_publish_options(
+            ["--out", "site", "--session", "dir"].into_iter().map(str::to_owned),
+        )
+        .expect("parse publish options");
+        assert_eq!(
+            options,
+            PublishOptions {
+                out_dir: "site".to_owned(),
+                session_dir: Some("dir".to_owned()),
+                theme: None,
+                selection: false,
+            }
+        );
+        let options = parse_publish_options(
+            ["--out", "site", "--theme", "print", "--selection"].into_iter().map(str::to_owned),
+        )
+        .expect("parse themed publish options");
+        assert_eq!((options.theme, options.selection), (Some(ExportTheme::PRINT), true));
+
+        parse_publish_options(["--session", "dir"].into_iter().map(str::to_owned)).unwrap_err();
+        parse_publish_options(["--out"].into_iter().map(str::to_owned)).unwrap_err();
+        parse_publish_options(["--out", "a", "--out", "b"].into_iter().map(str::to_owned))
+            .unwrap_err();
+        parse_publish_options(["site"].into_iter().map(str::to_owned)).unwrap_err();
+        parse_publish_options(["--out", "a", "--theme", "neon"].into_iter().map(str::to_owned))
+            .unwrap_err();
+    }
+
+    #[test]
+    fn parses_export_options() {
+        let options =
+            parse_export_options(["--pdf", "--out", "review.pdf"].into_iter().map(str::to_owned))
+                .expect("parse export options");
+        assert_eq!(
+            options,
+            ExportOptions {
+                format: ExportFormat::Pdf,
+                out_path: "review.pdf".to_owned(),
+                session_dir: None,
+                scale: None,
+                theme: None,
+                selection: false,
+            }
+        );
+        let options = parse_export_options(
+            ["--png", "--scale", "2", "--out", "png"].into_iter().map(str::to_owned),
+        )
+        .expect("parse png export options");
+        assert_eq!((options.format, options.scale), (ExportFormat::Png, Some(2.0)));
+        let options = parse_export_options(
+            ["--svg", "--out", "svg", "--selection", "--theme", "dark"]
+                .into_iter()
+                .map(str::to_owned),
+        )
+        .expect("parse themed export options");
+        assert_eq!((options.theme, options.selection), (Some(ExportTheme::DARK), true));
+
+        parse_export_options(["--out", "x"].into_iter().map(str::to_owned)).unwrap_err();
+        parse_export_options(["--svg"].into_iter().map(str::to_owned)).unwrap_err();
+        parse_export_options(["--svg", "--pdf", "--out", "x"].into_iter().map(str::to_owned))
+            .unwrap_err();
+        parse_export_options(
+            ["--svg", "--scale", "2", "--out", "x"].into_iter().map(str::to_owned),
+        )
+        .unwrap_err();
+        parse_export_options(
+            ["--png", "--scale", "0", "--out", "x"].into_iter().map(str::to_owned),
+        )
+        .unwrap_err();
+        parse_export_options(
+            ["--svg", "--theme", "dark", "--theme", "print", "--out", "x"]
+                .into_iter()
+                .map(str::to_owned),
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn parses_render_check_options() {
+        let options = parse_render_check_options(
+            ["--goldens", "goldens", "--update"].into_iter().map(str::to_owned),
+        )
+        .expect("parse render-check options");
+        assert_eq!(
+            options,
+            RenderCheckOptions {
+                goldens_dir: "goldens".to_owned(),
+                session_dir: None,
+                update: true,
+            }
+        );
+
+        parse_render_check_options(["--session", "dir"].into_iter().map(str::to_owned))
+            .unwrap_err();
+        parse_render_check_options(
+            ["--goldens", "a", "--update", "--update"].into_iter().map(str::to_owned),
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn parses_archive_options() {
+        let options = parse_archive_options(
+            ["import", "team.nereid.tar.zst", "--session", "copy"].into_iter().map(str::to_owned),
+        )
+        .expect("parse archive options");
+        assert_eq!(
+            options,
+            ArchiveOptions {
+                import: true,
+                archive_path: "team.nereid.tar.zst".to_owned(),
+                session_dir: Some("copy".to_owned()),
+            }
+        );
+
+        parse_archive_options(["export"].into_iter().map(str::to_owned)).unwrap_err();
+        parse_archive_options(["pack", "a.tar.zst"].into_iter().map(str::to_owned)).unwrap_err();
+        parse_archive_options(["export", "a", "b"].into_iter().map(str::to_owned)).unwrap_err();
+    }
+
+    #[test]
+    fn parses_new_options() {
+        let options = parse_new_options(
+            ["request-lifecycle", "--session", "api"].into_iter().map(str::to_owned),
+        )
+        .expect("parse new options");
+        assert_eq!(
+            options,
+            NewOptions {
+                template: nereid::store::SessionTemplate::RequestLifecycle,
+                session_dir: Some("api".to_owned()),
+            }
+        );
+
+        parse_new_options(std::iter::empty()).unwrap_err();
+        parse_new_options(["c4"].into_iter().map(str::to_owned)).unwrap_err();
+        parse_new_options(["context", "extra"].into_iter().map(str::to_owned)).unwrap_err();
+    }
 }
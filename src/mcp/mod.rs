@@ -13,4 +13,5 @@
 mod server;
 mod types;
 
-pub use server::NereidMcp;
+pub use server::{NereidMcp, DEFAULT_ATTENTION_TTL};
+pub use types::SessionChanged;
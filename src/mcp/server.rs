@@ -7,31 +7,75 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use std::collections::{BTreeMap, BTreeSet, VecDeque};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 use rmcp::handler::server::tool::ToolRouter;
 use rmcp::handler::server::wrapper::{Json, Parameters};
-use rmcp::model::{ServerCapabilities, ServerInfo};
+use rmcp::model::{
+    CustomNotification, GetPromptRequestParams, GetPromptResult, ListPromptsResult,
+    ListResourceTemplatesResult, ListResourcesResult, PaginatedRequestParams,
+    ReadResourceRequestParams, ReadResourceResult, ResourceUpdatedNotificationParam,
+    ServerCapabilities, ServerInfo, ServerNotification, SubscribeRequestParams,
+    UnsubscribeRequestParams,
+};
+use rmcp::service::{NotificationContext, RequestContext, RoleServer};
 use rmcp::{tool, tool_handler, tool_router, ErrorData, ServerHandler, ServiceExt};
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex};
+use tokio::time::Instant;
 
-use crate::format::mermaid::{parse_flowchart, parse_sequence_diagram};
+use crate::format::mermaid::{
+    detect_diagram_kind, export_class_diagram, export_state_diagram, parse_class_diagram,
+    parse_flowchart_with_id_rules, parse_sequence_diagram_with_id_rules, parse_state_diagram,
+};
+use crate::format::tabular::{flowchart_from_rows, json_object_row, ColumnMapping};
+use crate::format::theme::{ExportSelection, ExportStyle, ExportTheme};
 use crate::model::{
-    CategoryPath, Diagram, DiagramAst, DiagramId, DiagramKind, ObjectId, ObjectRef, Session,
-    Walkthrough, WalkthroughEdge, WalkthroughId, WalkthroughNode, WalkthroughNodeId, XRef, XRefId,
-    XRefStatus,
+    CategoryPath, Diagram, DiagramAst, DiagramId, DiagramKind, FlowchartAst, IdPrefixRule,
+    ObjectAnnotations, ObjectId, ObjectRef, Session, StyleRule, Walkthrough, WalkthroughEdge,
+    WalkthroughId, WalkthroughNode, WalkthroughNodeId, XRef, XRefId, XRefStatus,
 };
 use crate::ops::{
-    apply_ops, ApplyError, FlowEdgePatch, FlowNodePatch, FlowOp, Op, SeqMessagePatch, SeqOp,
-    SeqParticipantPatch,
+    apply_ops,
+    history::{diff_delta, diff_ops},
+    idgen, ApplyError, FlowEdgePatch, FlowNodePatch, FlowOp, Op, SeqMessagePatch, SeqNotePatch,
+    SeqOp, SeqParticipantPatch,
+};
+use crate::query::diff::{diff_diagrams, DiffStatus};
+use crate::query::id_lint::id_prefix_violations;
+use crate::query::objects::object_facts;
+use crate::query::scaffold::{
+    flowchart_from_sequence, scaffold_diagram_id, scaffold_xrefs, sequence_from_flow_path,
+    ScaffoldLink,
+};
+use crate::query::search::SearchIndex;
+use crate::query::validate::{validate_session, Severity};
+use crate::render::diagram::{
+    render_diagram_unicode_annotated_with_options, render_diagram_unicode_with_options,
 };
-use crate::render::{render_diagram_unicode, render_walkthrough_unicode};
-use crate::store::SessionFolder;
-use crate::ui::UiState;
+use crate::render::tidy::{tidy_flowchart, TidyMetrics};
+use crate::render::{
+    render_diagram_unicode, render_diagram_unicode_annotated, render_walkthrough_unicode,
+    FlowchartEdgeGlyphs, RenderOptions,
+};
+use crate::store::{
+    JournalActor, JournalEntry, SessionFolder, SessionTemplate, Workspace, ARCHIVE_VERSION,
+};
+use crate::ui::{AgentCursor, DiagramProposal, ProposalStatus, UiState, ViewRequest, ViewZoom};
 
 use super::types::*;
 
 const DELTA_HISTORY_LIMIT: usize = 64;
+/// Buffered change notifications per client before the slowest one starts skipping.
+const CHANGE_CHANNEL_CAPACITY: usize = 64;
+/// How often the shared view state is checked for changes while clients are connected.
+const VIEW_WATCH_INTERVAL: Duration = Duration::from_millis(250);
+/// Client id of agents that do not pass one to `attention.agent.set`.
+const DEFAULT_AGENT_CLIENT_ID: &str = "agent";
+
+/// How long the agent spotlight survives without `attention.agent.set` or a heartbeat.
+pub const DEFAULT_ATTENTION_TTL: Duration = Duration::from_secs(120);
 
 #[derive(Debug, Clone)]
 struct LastDelta {
@@ -64,9 +108,17 @@ struct McpState {
 #[derive(Clone)]
 pub struct NereidMcp {
     state: Arc<Mutex<McpState>>,
-    session_folder: Option<Arc<SessionFolder>>,
+    /// Swapped by `workspace.open`, so it is shared by every clone serving a connection.
+    session_folder: Arc<RwLock<Option<Arc<SessionFolder>>>>,
+    workspace: Option<Workspace>,
     agent_highlights: Arc<Mutex<BTreeSet<ObjectRef>>>,
     ui_state: Option<Arc<Mutex<UiState>>>,
+    resource_clients: Arc<Mutex<ResourceClients>>,
+    changes: broadcast::Sender<SessionChanged>,
+    last_changes: Arc<Mutex<Option<ChangeSnapshot>>>,
+    view_watcher_started: Arc<AtomicBool>,
+    attention_ttl: Option<Duration>,
+    attention_deadline: Arc<Mutex<Option<Instant>>>,
     tool_router: ToolRouter<Self>,
 }
 
@@ -94,9 +146,16 @@ impl NereidMcp {
                 delta_history: BTreeMap::new(),
                 walkthrough_delta_history: BTreeMap::new(),
             })),
-            session_folder: None,
+            session_folder: Arc::new(RwLock::new(None)),
+            workspace: None,
             agent_highlights,
             ui_state,
+            resource_clients: Arc::new(Mutex::new(ResourceClients::default())),
+            changes: broadcast::channel(CHANGE_CHANNEL_CAPACITY).0,
+            last_changes: Arc::new(Mutex::new(None)),
+            view_watcher_started: Arc::new(AtomicBool::new(false)),
+            attention_ttl: Some(DEFAULT_ATTENTION_TTL),
+            attention_deadline: Arc::new(Mutex::new(None)),
             tool_router: Self::tool_router(),
         }
     }
@@ -134,9 +193,16 @@ impl NereidMcp {
                 delta_history: BTreeMap::new(),
                 walkthrough_delta_history: BTreeMap::new(),
             })),
-            session_folder: Some(Arc::new(session_folder)),
+            session_folder: Arc::new(RwLock::new(Some(Arc::new(session_folder)))),
+            workspace: None,
             agent_highlights,
             ui_state,
+            resource_clients: Arc::new(Mutex::new(ResourceClients::default())),
+            changes: broadcast::channel(CHANGE_CHANNEL_CAPACITY).0,
+            last_changes: Arc::new(Mutex::new(None)),
+            view_watcher_started: Arc::new(AtomicBool::new(false)),
+            attention_ttl: Some(DEFAULT_ATTENTION_TTL),
+            attention_deadline: Arc::new(Mutex::new(None)),
             tool_router: Self::tool_router(),
         }
     }
@@ -147,10 +213,270 @@ impl NereidMcp {
         Ok(())
     }
 
-    async fn notify_ui_session_changed(&self) {
+    /// Sets how long the agent spotlight lasts without a heartbeat; `None` keeps it until
+    /// cleared. Defaults to [`DEFAULT_ATTENTION_TTL`].
+    pub fn with_attention_ttl(mut self, attention_ttl: Option<Duration>) -> Self {
+        self.attention_ttl = attention_ttl;
+        self
+    }
+
+    /// Lets `workspace.list_sessions`/`workspace.open` switch between the sessions of
+    /// `workspace`; the server should have been created persistent on one of them.
+    pub fn with_workspace(mut self, workspace: Workspace) -> Self {
+        self.workspace = Some(workspace);
+        self
+    }
+
+    fn session_folder(&self) -> Option<Arc<SessionFolder>> {
+        self.session_folder.read().unwrap_or_else(|err| err.into_inner()).clone()
+    }
+
+    fn workspace(&self) -> Result<&Workspace, ErrorData> {
+        self.workspace.as_ref().ok_or_else(|| {
+            ErrorData::invalid_request(
+                "no workspace; start nereid with --workspace <dir> to switch sessions",
+                None,
+            )
+        })
+    }
+
+    /// Name of the workspace session currently open, if any.
+    fn workspace_session_name(&self) -> Option<String> {
+        self.workspace.as_ref()?.session_name(self.session_folder()?.as_ref())
+    }
+
+    /// Opens the workspace session `name` in place of the current one; the caller holds `state`.
+    /// Returns the number of batches recovered from the session's ops journal.
+    fn switch_workspace_session(
+        &self,
+        state: &mut McpState,
+        name: &str,
+    ) -> Result<usize, ErrorData> {
+        let (folder, mut session, recovered) =
+            self.workspace()?.open_session(name).map_err(|err| {
+                ErrorData::invalid_params(
+                    format!("cannot open session: {err}"),
+                    Some(serde_json::json!({ "name": name })),
+                )
+            })?;
+        retain_existing_selected_object_refs(&mut session);
+        refresh_xref_statuses(&mut session);
+        *self.session_folder.write().unwrap_or_else(|err| err.into_inner()) =
+            Some(Arc::new(folder));
+        state.session = session;
+        state.delta_history.clear();
+        state.walkthrough_delta_history.clear();
+        Ok(recovered)
+    }
+
+    /// Pushes the spotlight expiry out by the TTL and returns the time left.
+    async fn refresh_attention_deadline(&self) -> Option<Duration> {
+        let ttl = self.attention_ttl?;
+        let deadline = Instant::now() + ttl;
+        let refreshed_at_ms = unix_now_ms();
+        *self.attention_deadline.lock().await = Some(deadline);
+        let server = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep_until(deadline).await;
+            server.expire_agent_cursors(refreshed_at_ms).await;
+            server.expire_agent_attention().await;
+        });
+        Some(ttl)
+    }
+
+    /// Drops agent cursors idle since `refreshed_at_ms`, one TTL ago, moving the spotlight off
+    /// them.
+    async fn expire_agent_cursors(&self, refreshed_at_ms: u64) {
+        let Some(ui_state) = self.ui_state.as_ref() else {
+            return;
+        };
+        let (expired, cursors) = {
+            let mut ui_state = ui_state.lock().await;
+            let expired = ui_state.expire_agent_cursors(refreshed_at_ms);
+            (expired, ui_state.agent_cursors().clone())
+        };
+        if expired.is_empty() {
+            return;
+        }
+        let mut agent_highlights = self.agent_highlights.lock().await;
+        let previous = agent_highlights.iter().next().cloned();
+        move_spotlight_off_released_cursors(&mut agent_highlights, &expired, &cursors);
+        let gone = agent_highlights.is_empty();
+        drop(agent_highlights);
+        if gone && previous.is_some() {
+            // Nobody is left to follow; report the expiry like the spotlight's own deadline does.
+            *self.attention_deadline.lock().await = None;
+            ui_state.lock().await.set_expired_agent_highlight(previous);
+        }
+        self.publish_session_changes().await;
+    }
+
+    /// Drops every agent cursor, e.g. when the session they point into goes away.
+    async fn clear_agent_cursors(&self) {
+        if let Some(ui_state) = self.ui_state.as_ref() {
+            ui_state.lock().await.retain_agent_cursors(|_| false);
+        }
+    }
+
+    async fn attention_expires_in(&self) -> Option<Duration> {
+        let deadline = (*self.attention_deadline.lock().await)?;
+        Some(deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Clears the spotlight once its deadline passed without a heartbeat and tells the TUI, which
+    /// logs the expiry in its timeline.
+    async fn expire_agent_attention(&self) {
+        let mut deadline = self.attention_deadline.lock().await;
+        if !deadline.is_some_and(|deadline| deadline <= Instant::now()) {
+            return;
+        }
+        *deadline = None;
+        drop(deadline);
+
+        let expired = {
+            let mut agent_highlights = self.agent_highlights.lock().await;
+            let expired = agent_highlights.iter().next().cloned();
+            agent_highlights.clear();
+            expired
+        };
+        if expired.is_none() {
+            return;
+        }
+        if let Some(ui_state) = self.ui_state.as_ref() {
+            ui_state.lock().await.set_expired_agent_highlight(expired);
+        }
+        self.publish_session_changes().await;
+    }
+
+    /// Receives a [`SessionChanged`] for every change the server publishes; connected MCP
+    /// clients get the same as `nereid/sessionChanged` notifications.
+    pub fn subscribe_session_changes(&self) -> broadcast::Receiver<SessionChanged> {
+        self.changes.subscribe()
+    }
+
+    /// Broadcasts what changed since the last call (nothing on the first call, which only records
+    /// the baseline).
+    async fn publish_session_changes(&self) {
+        let agent_highlights = self.agent_highlights.lock().await.clone();
+        let ui_state = match self.ui_state.as_ref() {
+            Some(ui_state) => Some(ui_state.lock().await.clone()),
+            None => None,
+        };
+        let next = {
+            let state = self.state.lock().await;
+            ChangeSnapshot::capture(&state.session, &agent_highlights, ui_state.as_ref())
+        };
+        let mut last = self.last_changes.lock().await;
+        let changed = last.as_ref().map(|last| last.changes_to(&next));
+        *last = Some(next);
+        drop(last);
+        if let Some(changed) = changed.filter(|changed| !changed.is_empty()) {
+            // No receivers just means nobody is listening right now.
+            let _ = self.changes.send(changed);
+        }
+    }
+
+    /// Relays published changes to `peer` until its transport closes; the first client also
+    /// starts a watcher that publishes changes the TUI makes to the shared view state.
+    fn forward_session_changes(&self, peer: rmcp::service::Peer<RoleServer>) {
+        let mut changes = self.changes.subscribe();
+        tokio::spawn(async move {
+            loop {
+                let changed = match changes.recv().await {
+                    Ok(changed) => changed,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if peer.is_transport_closed() {
+                    break;
+                }
+                let notification = CustomNotification::new(
+                    SESSION_CHANGED_METHOD,
+                    serde_json::to_value(&changed).ok(),
+                );
+                let _ = peer
+                    .send_notification(ServerNotification::CustomNotification(notification))
+                    .await;
+            }
+        });
+
+        if self.ui_state.is_none() || self.view_watcher_started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let server = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(VIEW_WATCH_INTERVAL);
+            loop {
+                interval.tick().await;
+                if server.changes.receiver_count() > 0 {
+                    server.publish_session_changes().await;
+                }
+            }
+        });
+    }
+
+    async fn notify_session_changed(&self) {
         if let Some(ui_state) = self.ui_state.as_ref() {
             ui_state.lock().await.bump_session_rev();
         }
+        self.notify_resource_changes().await;
+    }
+
+    /// Tells subscribed clients about resources whose revision changed since the last call, and
+    /// every client when resources were added or removed.
+    async fn notify_resource_changes(&self) {
+        let notifications = {
+            let state = self.state.lock().await;
+            self.resource_clients.lock().await.record(&state.session)
+        };
+        for (peer, uri) in notifications.updated {
+            let _ = peer.notify_resource_updated(ResourceUpdatedNotificationParam { uri }).await;
+        }
+        for peer in notifications.list_changed {
+            let _ = peer.notify_resource_list_changed().await;
+        }
+        self.publish_session_changes().await;
+    }
+
+    async fn set_resource_subscription(
+        &self,
+        uri: String,
+        peer: rmcp::service::Peer<RoleServer>,
+        subscribed: bool,
+    ) -> Result<(), ErrorData> {
+        if ResourceUri::parse(&uri).is_none() {
+            return Err(ErrorData::invalid_params(
+                "invalid resource uri",
+                Some(serde_json::json!({ "uri": uri })),
+            ));
+        }
+        let state = self.lock_state_synced().await?;
+        self.resource_clients.lock().await.set_subscribed(peer, uri, subscribed, &state.session);
+        Ok(())
+    }
+
+    /// Queues a viewport request for the TUI; returns `false` (and drops it) unless a TUI shares
+    /// the view state and follow-AI is enabled.
+    async fn push_view_request_if_following(&self, request: ViewRequest) -> bool {
+        let Some(ui_state) = self.ui_state.as_ref() else {
+            return false;
+        };
+        let mut ui_state = ui_state.lock().await;
+        if !ui_state.follow_ai() {
+            return false;
+        }
+        ui_state.push_view_request(request);
+        true
+    }
+
+    /// Proposals live in the view state shared with the TUI, where the human settles them.
+    fn proposal_ui_state(&self) -> Result<&Arc<Mutex<UiState>>, ErrorData> {
+        self.ui_state.as_ref().ok_or_else(|| {
+            ErrorData::invalid_request(
+                "proposals need a TUI to review them; run nereid with its TUI instead of --mcp",
+                None,
+            )
+        })
     }
 
     async fn read_context(&self, session_active_diagram_id: Option<String>) -> ReadContext {
@@ -178,8 +504,22 @@ impl NereidMcp {
     }
 
     async fn lock_state_synced(&self) -> Result<tokio::sync::MutexGuard<'_, McpState>, ErrorData> {
+        // Follow a session switch made in the TUI before reading the (possibly other) folder.
+        let requested = match (self.workspace.as_ref(), self.ui_state.as_ref()) {
+            (Some(_), Some(ui_state)) => {
+                ui_state.lock().await.workspace_session().map(str::to_owned)
+            }
+            _ => None,
+        };
         let mut state = self.state.lock().await;
-        if let Some(session_folder) = &self.session_folder {
+        if let Some(name) =
+            requested.filter(|name| self.workspace_session_name().as_deref() != Some(name.as_str()))
+        {
+            self.switch_workspace_session(&mut state, &name)?;
+            self.agent_highlights.lock().await.clear();
+            self.clear_agent_cursors().await;
+        }
+        if let Some(session_folder) = &self.session_folder() {
             self.sync_state_with_session_folder(&mut state, session_folder)?;
         }
         Ok(state)
@@ -197,6 +537,9 @@ impl NereidMcp {
         refresh_xref_statuses(&mut disk_session);
 
         if disk_session == state.session {
+            // Adopting the equal disk session keeps its source fingerprints, so the next sync
+            // compares lazy diagrams without parsing them again.
+            state.session = disk_session;
             return Ok(());
         }
 
@@ -219,70 +562,33 @@ impl NereidMcp {
         Ok(())
     }
 
-    /// List diagrams in the current session; start here, then call `diagram.current` or
-    /// `diagram.open` (bootstrap with `diagram.create_from_mermaid` if empty).
-    #[tool(name = "diagram.list")]
-    async fn diagram_list(&self) -> Result<Json<ListDiagramsResponse>, ErrorData> {
-        let state = self.lock_state_synced().await?;
-        let session_active_diagram_id =
-            state.session.active_diagram_id().map(|diagram_id| diagram_id.as_str().to_owned());
-        let diagrams = state
-            .session
-            .diagrams()
-            .iter()
-            .map(|(diagram_id, diagram)| DiagramSummary {
-                diagram_id: diagram_id.as_str().to_owned(),
-                name: diagram.name().to_owned(),
-                kind: diagram_kind_label(diagram.kind()).to_owned(),
-                rev: diagram.rev(),
-            })
-            .collect::<Vec<_>>();
-        drop(state);
-        let context = self.read_context(session_active_diagram_id).await;
-
-        Ok(Json(ListDiagramsResponse { diagrams, context }))
+    async fn insert_created_diagram(
+        &self,
+        ast: DiagramAst,
+        diagram_id: Option<String>,
+        name: Option<String>,
+        make_active: bool,
+    ) -> Result<(DiagramSummary, Option<String>), ErrorData> {
+        let (summary, active_diagram_id, _) =
+            self.insert_scaffolded_diagram(ast, |_| diagram_id, name, make_active, &[]).await?;
+        Ok((summary, active_diagram_id))
     }
 
-    /// Create a diagram from raw Mermaid; use to bootstrap a session, then continue with
-    /// `diagram.open`/`diagram.stat`.
-    #[tool(name = "diagram.create_from_mermaid")]
-    async fn diagram_create_from_mermaid(
+    /// Inserts a new diagram plus `derived_from` xrefs for `links`; `diagram_id` picks the id
+    /// (`None` allocates one) while the session is locked.
+    async fn insert_scaffolded_diagram(
         &self,
-        params: Parameters<DiagramCreateFromMermaidParams>,
-    ) -> Result<Json<DiagramCreateFromMermaidResponse>, ErrorData> {
-        let DiagramCreateFromMermaidParams { mermaid, diagram_id, name, make_active } = params.0;
-
-        let Some(kind) = detect_mermaid_kind(&mermaid) else {
-            return Err(ErrorData::invalid_params(
-                "expected 'flowchart'/'graph' or 'sequenceDiagram' as the first non-empty line",
-                None,
-            ));
-        };
-
-        let ast = match kind {
-            DiagramKind::Sequence => {
-                DiagramAst::Sequence(parse_sequence_diagram(&mermaid).map_err(|err| {
-                    ErrorData::invalid_params(
-                        format!("cannot parse Mermaid sequence diagram: {err}"),
-                        None,
-                    )
-                })?)
-            }
-            DiagramKind::Flowchart => {
-                DiagramAst::Flowchart(parse_flowchart(&mermaid).map_err(|err| {
-                    ErrorData::invalid_params(
-                        format!("cannot parse Mermaid flowchart diagram: {err}"),
-                        None,
-                    )
-                })?)
-            }
-        };
-
+        ast: DiagramAst,
+        diagram_id: impl FnOnce(&Session) -> Option<String>,
+        name: Option<String>,
+        make_active: bool,
+        links: &[ScaffoldLink],
+    ) -> Result<(DiagramSummary, Option<String>, Vec<String>), ErrorData> {
+        let kind = ast.kind();
         let kind_label = diagram_kind_label(kind).to_owned();
-        let make_active = make_active.unwrap_or(true);
 
         let mut state = self.lock_state_synced().await?;
-        let diagram_id = match diagram_id {
+        let diagram_id = match diagram_id(&state.session) {
             Some(diagram_id) => {
                 let parsed = DiagramId::new(diagram_id.clone()).map_err(|err| {
                     ErrorData::invalid_params(
@@ -298,7 +604,7 @@ impl NereidMcp {
                 }
                 parsed
             }
-            None => allocate_diagram_id(&state.session, kind),
+            None => idgen::diagram_id_for_kind(&state.session, kind),
         };
 
         let name = name.unwrap_or_else(|| diagram_id.as_str().to_owned());
@@ -313,101 +619,1304 @@ impl NereidMcp {
                 })),
             )
         })?;
+        let mut candidate = state.session.clone();
+        let xrefs = scaffold_xrefs(&mut candidate, &diagram_id, links);
+        let xref_ids = xrefs.iter().map(|(xref_id, _)| xref_id.as_str().to_owned()).collect();
+
+        candidate.diagrams_mut().insert(diagram_id.clone(), diagram);
+        candidate.xrefs_mut().extend(xrefs);
+        if make_active {
+            candidate.set_active_diagram_id(Some(diagram_id.clone()));
+        }
 
-        if let Some(session_folder) = &self.session_folder {
-            let mut candidate = state.session.clone();
-            candidate.diagrams_mut().insert(diagram_id.clone(), diagram);
-            if make_active {
-                candidate.set_active_diagram_id(Some(diagram_id.clone()));
-            }
-
+        if let Some(session_folder) = &self.session_folder() {
             let meta = session_folder.load_meta().map_err(|err| {
                 ErrorData::internal_error(
                     format!("failed to load session meta: {err}"),
                     Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
                 )
             })?;
-            candidate.set_selected_object_refs(meta.selected_object_refs.into_iter().collect());
+            candidate.set_selection_log(meta.selection_log);
             session_folder.save_session(&candidate).map_err(|err| {
                 ErrorData::internal_error(
                     format!("failed to persist session: {err}"),
                     Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
                 )
             })?;
-
-            state.session = candidate;
-        } else {
-            state.session.diagrams_mut().insert(diagram_id.clone(), diagram);
-            if make_active {
-                state.session.set_active_diagram_id(Some(diagram_id.clone()));
-            }
         }
+        state.session = candidate;
 
-        let response = Json(DiagramCreateFromMermaidResponse {
-            diagram: DiagramSummary {
-                diagram_id: diagram_id.as_str().to_owned(),
-                name,
-                kind: kind_label,
-                rev: 0,
-            },
-            active_diagram_id: state
-                .session
-                .active_diagram_id()
-                .map(|diagram_id| diagram_id.as_str().to_owned()),
-        });
+        let summary = DiagramSummary {
+            diagram_id: diagram_id.as_str().to_owned(),
+            name,
+            kind: kind_label,
+            rev: 0,
+            description: None,
+            archived: false,
+            read_only: false,
+        };
+        let active_diagram_id =
+            state.session.active_diagram_id().map(|diagram_id| diagram_id.as_str().to_owned());
         drop(state);
-        self.notify_ui_session_changed().await;
-        Ok(response)
+        self.notify_session_changed().await;
+        Ok((summary, active_diagram_id, xref_ids))
     }
 
-    /// Set the active diagram default for diagram-scoped tools; typically follows `diagram.list`
-    /// or `diagram.create_from_mermaid`.
-    #[tool(name = "diagram.open")]
-    async fn diagram_open(
+    async fn apply_diagram_ops(
         &self,
-        params: Parameters<DiagramOpenParams>,
-    ) -> Result<Json<DiagramOpenResponse>, ErrorData> {
-        let diagram_id = params.0.diagram_id;
-        let parsed = DiagramId::new(diagram_id.clone()).map_err(|err| {
-            ErrorData::invalid_params(
-                format!("invalid diagram_id: {err}"),
-                Some(serde_json::json!({ "diagram_id": diagram_id })),
-            )
-        })?;
-
+        diagram_id: Option<&str>,
+        base_rev: u64,
+        ops: Vec<Op>,
+    ) -> Result<ApplyOpsResponse, ErrorData> {
         let mut state = self.lock_state_synced().await?;
-        if !state.session.diagrams().contains_key(&parsed) {
-            return Err(ErrorData::resource_not_found(
-                "diagram not found",
-                Some(serde_json::json!({ "diagram_id": diagram_id })),
+        let diagram_id = resolve_diagram_id(&state.session, diagram_id)?;
+        let diagram = state
+            .session
+            .diagrams()
+            .get(&diagram_id)
+            .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?;
+
+        let current_rev = diagram.rev();
+        if base_rev != current_rev {
+            let digest = digest_for_diagram(diagram);
+            return Err(ErrorData::invalid_request(
+                "conflict: stale base_rev",
+                Some(serde_json::json!({
+                    "base_rev": base_rev,
+                    "current_rev": current_rev,
+                    "snapshot_tool": "diagram.stat",
+                    "digest": {
+                        "rev": digest.rev,
+                        "counts": {
+                            "participants": digest.counts.participants,
+                            "messages": digest.counts.messages,
+                            "nodes": digest.counts.nodes,
+                            "edges": digest.counts.edges,
+                        },
+                        "key_names": digest.key_names,
+                    },
+                })),
             ));
         }
 
-        if let Some(session_folder) = &self.session_folder {
-            let mut candidate = state.session.clone();
-            candidate.set_active_diagram_id(Some(parsed.clone()));
+        if let Some(session_folder) = &self.session_folder() {
+            let mut candidate_session = state.session.clone();
+            let mut candidate_diagram = candidate_session
+                .diagrams()
+                .get(&diagram_id)
+                .cloned()
+                .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?;
+
+            let result =
+                apply_ops(&mut candidate_diagram, base_rev, &ops).map_err(map_apply_error)?;
+            render_diagram_unicode(&candidate_diagram).map_err(|err| {
+                ErrorData::invalid_request(
+                    format!("cannot render diagram after apply_ops: {err}"),
+                    Some(serde_json::json!({
+                        "diagram_id": diagram_id.as_str(),
+                        "base_rev": base_rev,
+                        "op_count": ops.len() as u64,
+                        "render_error": err.to_string(),
+                    })),
+                )
+            })?;
+            candidate_session.diagrams_mut().insert(diagram_id.clone(), candidate_diagram);
+
+            let mut history =
+                state.delta_history.get(&diagram_id).cloned().unwrap_or_else(VecDeque::new);
+            history.push_back(LastDelta {
+                from_rev: base_rev,
+                to_rev: result.new_rev,
+                delta: result.delta.clone(),
+            });
+            while history.len() > DELTA_HISTORY_LIMIT {
+                history.pop_front();
+            }
+
             let meta = session_folder.load_meta().map_err(|err| {
                 ErrorData::internal_error(
                     format!("failed to load session meta: {err}"),
-                    Some(serde_json::json!({ "diagram_id": diagram_id })),
+                    Some(serde_json::json!({ "diagram_id": diagram_id.as_str(), "base_rev": base_rev })),
                 )
             })?;
-            candidate.set_selected_object_refs(meta.selected_object_refs.into_iter().collect());
-            session_folder.save_session(&candidate).map_err(|err| {
+            candidate_session.set_selection_log(meta.selection_log);
+            let entry = JournalEntry::new(
+                JournalActor::Agent,
+                diagram_id.clone(),
+                base_rev,
+                result.new_rev,
+                ops,
+            );
+            session_folder.append_ops_journal(&entry).map_err(|err| {
+                ErrorData::internal_error(
+                    format!("failed to journal ops: {err}"),
+                    Some(serde_json::json!({ "diagram_id": diagram_id.as_str(), "base_rev": base_rev })),
+                )
+            })?;
+            session_folder.save_session(&candidate_session).map_err(|err| {
                 ErrorData::internal_error(
                     format!("failed to persist session: {err}"),
-                    Some(serde_json::json!({ "diagram_id": diagram_id })),
+                    Some(serde_json::json!({ "diagram_id": diagram_id.as_str(), "base_rev": base_rev })),
                 )
             })?;
-            state.session = candidate;
-        } else {
-            state.session.set_active_diagram_id(Some(parsed.clone()));
-        }
 
-        let response = Json(DiagramOpenResponse { active_diagram_id: parsed.as_str().to_owned() });
+            state.session = candidate_session;
+            state.delta_history.insert(diagram_id, history);
+
+            let response = ApplyOpsResponse {
+                new_rev: result.new_rev,
+                applied: result.applied as u64,
+                delta: DeltaSummary {
+                    added: result.delta.added.iter().map(ToString::to_string).collect(),
+                    removed: result.delta.removed.iter().map(ToString::to_string).collect(),
+                    updated: result.delta.updated.iter().map(ToString::to_string).collect(),
+                },
+            };
+            drop(state);
+            self.notify_session_changed().await;
+            return Ok(response);
+        }
+
+        let mut candidate_diagram = state
+            .session
+            .diagrams()
+            .get(&diagram_id)
+            .cloned()
+            .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?;
+
+        let result = apply_ops(&mut candidate_diagram, base_rev, &ops).map_err(map_apply_error)?;
+        render_diagram_unicode(&candidate_diagram).map_err(|err| {
+            ErrorData::invalid_request(
+                format!("cannot render diagram after apply_ops: {err}"),
+                Some(serde_json::json!({
+                    "diagram_id": diagram_id.as_str(),
+                    "base_rev": base_rev,
+                    "op_count": ops.len() as u64,
+                    "render_error": err.to_string(),
+                })),
+            )
+        })?;
+        state.session.diagrams_mut().insert(diagram_id.clone(), candidate_diagram);
+        let history = state.delta_history.entry(diagram_id).or_insert_with(VecDeque::new);
+        history.push_back(LastDelta {
+            from_rev: base_rev,
+            to_rev: result.new_rev,
+            delta: result.delta.clone(),
+        });
+        while history.len() > DELTA_HISTORY_LIMIT {
+            history.pop_front();
+        }
+
+        let response = ApplyOpsResponse {
+            new_rev: result.new_rev,
+            applied: result.applied as u64,
+            delta: DeltaSummary {
+                added: result.delta.added.iter().map(ToString::to_string).collect(),
+                removed: result.delta.removed.iter().map(ToString::to_string).collect(),
+                updated: result.delta.updated.iter().map(ToString::to_string).collect(),
+            },
+        };
+        drop(state);
+        self.notify_session_changed().await;
+        Ok(response)
+    }
+
+    /// Replaces the AST of a diagram still at `base_rev` in one revision step, for changes ops
+    /// cannot express. `diagram.diff` cannot span such a step, so the diagram's delta history is
+    /// dropped.
+    async fn replace_diagram_ast(
+        &self,
+        diagram_id: &DiagramId,
+        base_rev: u64,
+        ast: DiagramAst,
+    ) -> Result<u64, ErrorData> {
+        let mut state = self.lock_state_synced().await?;
+        let mut candidate = state.session.clone();
+        let diagram = candidate
+            .diagrams_mut()
+            .get_mut(diagram_id)
+            .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?;
+        check_diagram_writable(diagram)?;
+        if diagram.rev() != base_rev {
+            return Err(map_apply_error(ApplyError::Conflict {
+                base_rev,
+                current_rev: diagram.rev(),
+            }));
+        }
+        diagram.set_ast(ast).map_err(|err| ErrorData::invalid_params(err.to_string(), None))?;
+        diagram.bump_rev();
+        let new_rev = diagram.rev();
+        render_diagram_unicode(diagram).map_err(|err| {
+            ErrorData::invalid_request(
+                format!("cannot render diagram after set_mermaid: {err}"),
+                Some(serde_json::json!({
+                    "diagram_id": diagram_id.as_str(),
+                    "base_rev": base_rev,
+                    "render_error": err.to_string(),
+                })),
+            )
+        })?;
+        retain_existing_selected_object_refs(&mut candidate);
+        refresh_xref_statuses(&mut candidate);
+
+        if let Some(session_folder) = &self.session_folder() {
+            let meta = session_folder.load_meta().map_err(|err| {
+                ErrorData::internal_error(
+                    format!("failed to load session meta: {err}"),
+                    Some(serde_json::json!({ "diagram_id": diagram_id.as_str(), "base_rev": base_rev })),
+                )
+            })?;
+            candidate.set_selection_log(meta.selection_log);
+            session_folder.save_session(&candidate).map_err(|err| {
+                ErrorData::internal_error(
+                    format!("failed to persist session: {err}"),
+                    Some(serde_json::json!({ "diagram_id": diagram_id.as_str(), "base_rev": base_rev })),
+                )
+            })?;
+        }
+        state.session = candidate;
+        state.delta_history.remove(diagram_id);
+        drop(state);
+        self.notify_session_changed().await;
+        Ok(new_rev)
+    }
+
+    /// Applies `ops` to a walkthrough still at `base_rev`, then persists and notifies.
+    async fn commit_walkthrough_ops(
+        &self,
+        walkthrough_id: &str,
+        base_rev: u64,
+        ops: &[McpWalkthroughOp],
+    ) -> Result<ApplyOpsResponse, ErrorData> {
+        let parsed = parse_walkthrough_id(walkthrough_id)?;
+
+        let mut state = self.lock_state_synced().await?;
+
+        if let Some(session_folder) = &self.session_folder() {
+            let mut candidate_session = state.session.clone();
+            let walkthrough =
+                candidate_session.walkthroughs_mut().get_mut(&parsed).ok_or_else(|| {
+                    ErrorData::resource_not_found(
+                        "walkthrough not found",
+                        Some(serde_json::json!({ "walkthrough_id": walkthrough_id })),
+                    )
+                })?;
+
+            let current_rev = walkthrough.rev();
+            if base_rev != current_rev {
+                let digest = digest_for_walkthrough(walkthrough);
+                return Err(ErrorData::invalid_request(
+                    "conflict: stale base_rev",
+                    Some(serde_json::json!({
+                        "base_rev": base_rev,
+                        "current_rev": current_rev,
+                        "snapshot_tool": "walkthrough.stat",
+                        "digest": {
+                            "rev": digest.rev,
+                            "counts": {
+                                "nodes": digest.counts.nodes,
+                                "edges": digest.counts.edges,
+                            },
+                        },
+                    })),
+                ));
+            }
+
+            if ops.is_empty() {
+                return Ok(ApplyOpsResponse {
+                    new_rev: current_rev,
+                    applied: 0,
+                    delta: DeltaSummary {
+                        added: Vec::new(),
+                        removed: Vec::new(),
+                        updated: Vec::new(),
+                    },
+                });
+            }
+
+            let delta = apply_walkthrough_ops(walkthrough, &parsed, ops)?;
+            walkthrough.bump_rev();
+            let new_rev = walkthrough.rev();
+
+            let mut history =
+                state.walkthrough_delta_history.get(&parsed).cloned().unwrap_or_else(VecDeque::new);
+            history.push_back(WalkthroughLastDelta {
+                from_rev: base_rev,
+                to_rev: new_rev,
+                delta: delta.clone(),
+            });
+            while history.len() > DELTA_HISTORY_LIMIT {
+                history.pop_front();
+            }
+
+            let meta = session_folder.load_meta().map_err(|err| {
+                ErrorData::internal_error(
+                    format!("failed to load session meta: {err}"),
+                    Some(serde_json::json!({ "walkthrough_id": walkthrough_id, "base_rev": base_rev })),
+                )
+            })?;
+            candidate_session.set_selection_log(meta.selection_log);
+            session_folder.save_session(&candidate_session).map_err(|err| {
+                ErrorData::internal_error(
+                    format!("failed to persist session: {err}"),
+                    Some(serde_json::json!({ "walkthrough_id": walkthrough_id, "base_rev": base_rev })),
+                )
+            })?;
+
+            state.session = candidate_session;
+            state.walkthrough_delta_history.insert(parsed, history);
+
+            let response = ApplyOpsResponse {
+                new_rev,
+                applied: ops.len() as u64,
+                delta: DeltaSummary {
+                    added: delta.added.iter().cloned().collect(),
+                    removed: delta.removed.iter().cloned().collect(),
+                    updated: delta.updated.iter().cloned().collect(),
+                },
+            };
+            drop(state);
+            self.notify_session_changed().await;
+            return Ok(response);
+        }
+
+        let walkthrough = state.session.walkthroughs_mut().get_mut(&parsed).ok_or_else(|| {
+            ErrorData::resource_not_found(
+                "walkthrough not found",
+                Some(serde_json::json!({ "walkthrough_id": walkthrough_id })),
+            )
+        })?;
+
+        let current_rev = walkthrough.rev();
+        if base_rev != current_rev {
+            let digest = digest_for_walkthrough(walkthrough);
+            return Err(ErrorData::invalid_request(
+                "conflict: stale base_rev",
+                Some(serde_json::json!({
+                    "base_rev": base_rev,
+                    "current_rev": current_rev,
+                    "snapshot_tool": "walkthrough.stat",
+                    "digest": {
+                        "rev": digest.rev,
+                        "counts": {
+                            "nodes": digest.counts.nodes,
+                            "edges": digest.counts.edges,
+                        },
+                    },
+                })),
+            ));
+        }
+
+        if ops.is_empty() {
+            return Ok(ApplyOpsResponse {
+                new_rev: current_rev,
+                applied: 0,
+                delta: DeltaSummary { added: Vec::new(), removed: Vec::new(), updated: Vec::new() },
+            });
+        }
+
+        let delta = apply_walkthrough_ops(walkthrough, &parsed, ops)?;
+        walkthrough.bump_rev();
+        let new_rev = walkthrough.rev();
+
+        let history = state.walkthrough_delta_history.entry(parsed).or_insert_with(VecDeque::new);
+        history.push_back(WalkthroughLastDelta {
+            from_rev: base_rev,
+            to_rev: new_rev,
+            delta: delta.clone(),
+        });
+        while history.len() > DELTA_HISTORY_LIMIT {
+            history.pop_front();
+        }
+
+        let response = ApplyOpsResponse {
+            new_rev,
+            applied: ops.len() as u64,
+            delta: DeltaSummary {
+                added: delta.added.iter().cloned().collect(),
+                removed: delta.removed.iter().cloned().collect(),
+                updated: delta.updated.iter().cloned().collect(),
+            },
+        };
+        drop(state);
+        self.notify_session_changed().await;
+        Ok(response)
+    }
+
+    /// Describe what this server supports: diagram kinds with their ops, walkthrough ops,
+    /// import/export formats, optional features and every tool with its parameters. Call once up
+    /// front to skip tools or ops an older build lacks.
+    #[tool(name = "server.capabilities")]
+    async fn server_capabilities(&self) -> Result<Json<ServerCapabilitiesResponse>, ErrorData> {
+        Ok(Json(server_capabilities(
+            self.tool_router.list_all(),
+            self.session_folder().is_some(),
+            self.attention_ttl,
+        )))
+    }
+
+    /// Read the session title, description, tags, styling rules and id prefix conventions; use to
+    /// orient in an unfamiliar session folder.
+    #[tool(name = "session.read_meta")]
+    async fn session_read_meta(&self) -> Result<Json<SessionInfo>, ErrorData> {
+        let state = self.lock_state_synced().await?;
+        Ok(Json(session_info(&state.session)))
+    }
+
+    /// Update the session title, description, tags, styling rules and/or id prefix conventions;
+    /// omitted fields are kept, empty strings clear them. Styling rules (`tag=deprecated => dim`,
+    /// `degree>=8 => heavy`) dim or thicken matching objects in the TUI and in exports; id prefix
+    /// conventions (`flow/node => svc|db`) map Mermaid ids like `svc_orders` to `svc:orders` on
+    /// import and are checked by `session.lint_ids`.
+    #[tool(name = "session.update_meta")]
+    async fn session_update_meta(
+        &self,
+        params: Parameters<SessionUpdateMetaParams>,
+    ) -> Result<Json<SessionInfo>, ErrorData> {
+        let SessionUpdateMetaParams { title, description, tags, style_rules, id_prefix_rules } =
+            params.0;
+        let style_rules = style_rules
+            .map(|rules| {
+                rules
+                    .iter()
+                    .map(|rule| {
+                        rule.parse::<StyleRule>().map_err(|err| {
+                            ErrorData::invalid_params(
+                                format!("invalid style rule: {err}"),
+                                Some(serde_json::json!({ "style_rule": rule })),
+                            )
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+        let id_prefix_rules = id_prefix_rules
+            .map(|rules| {
+                rules
+                    .iter()
+                    .map(|rule| {
+                        rule.parse::<IdPrefixRule>().map_err(|err| {
+                            ErrorData::invalid_params(
+                                format!("invalid id prefix rule: {err}"),
+                                Some(serde_json::json!({ "id_prefix_rule": rule })),
+                            )
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+
+        let mut state = self.lock_state_synced().await?;
+        let mut candidate = state.session.clone();
+        if let Some(title) = title {
+            candidate.set_title(Some(title));
+        }
+        if let Some(description) = description {
+            candidate.set_description(Some(description));
+        }
+        if let Some(tags) = tags {
+            candidate.set_tags(tags);
+        }
+        let style_rules_changed = style_rules.is_some();
+        if let Some(style_rules) = style_rules {
+            candidate.set_style_rules(style_rules);
+        }
+        let id_prefix_rules_changed = id_prefix_rules.is_some();
+        if let Some(id_prefix_rules) = id_prefix_rules {
+            candidate.set_id_prefix_rules(id_prefix_rules);
+        }
+
+        if let Some(session_folder) = &self.session_folder() {
+            let persisted = session_folder
+                .save_session_info(&candidate)
+                .and_then(|()| {
+                    if style_rules_changed {
+                        session_folder.save_style_rules(&candidate)
+                    } else {
+                        Ok(())
+                    }
+                })
+                .and_then(|()| {
+                    if id_prefix_rules_changed {
+                        session_folder.save_id_prefix_rules(&candidate)
+                    } else {
+                        Ok(())
+                    }
+                });
+            persisted.map_err(|err| {
+                ErrorData::internal_error(format!("failed to persist session meta: {err}"), None)
+            })?;
+        }
+        state.session = candidate;
+
+        let response = Json(session_info(&state.session));
+        drop(state);
+        self.notify_session_changed().await;
+        Ok(response)
+    }
+
+    /// List objects whose ids break the session's id prefix conventions (see
+    /// `session.update_meta`); categories without a convention are not checked.
+    #[tool(name = "session.lint_ids")]
+    async fn session_lint_ids(&self) -> Result<Json<SessionLintIdsResponse>, ErrorData> {
+        let state = self.lock_state_synced().await?;
+        let violations = id_prefix_violations(&state.session)
+            .into_iter()
+            .map(|violation| IdPrefixViolation {
+                object_ref: violation.object_ref.to_string(),
+                rule: violation.rule.to_string(),
+            })
+            .collect();
+        Ok(Json(SessionLintIdsResponse { violations }))
+    }
+
+    /// Check the session's integrity: dangling xrefs, duplicate Mermaid ids, orphan walkthrough
+    /// refs, messages referencing missing participants and edges referencing missing nodes.
+    #[tool(name = "session.validate")]
+    async fn session_validate(&self) -> Result<Json<SessionValidateResponse>, ErrorData> {
+        let state = self.lock_state_synced().await?;
+        let diagnostics = validate_session(&state.session);
+        let errors = diagnostics.iter().filter(|d| d.severity() == Severity::Error).count();
+        let diagnostics = diagnostics
+            .into_iter()
+            .map(|diagnostic| SessionDiagnostic {
+                severity: diagnostic.severity().to_string(),
+                code: diagnostic.code.to_string(),
+                subject: diagnostic.subject,
+                object_refs: diagnostic.object_refs.iter().map(ToString::to_string).collect(),
+                message: diagnostic.message,
+            })
+            .collect::<Vec<_>>();
+        Ok(Json(SessionValidateResponse {
+            ok: diagnostics.is_empty(),
+            errors: errors as u64,
+            warnings: (diagnostics.len() - errors) as u64,
+            diagnostics,
+        }))
+    }
+
+    /// Pack the session (meta, diagrams, walkthroughs and xrefs) into one versioned
+    /// `.nereid.tar.zst` file to share; `session.import_archive` or `nereid archive import` unpacks
+    /// it into a new folder.
+    #[tool(name = "session.export_archive")]
+    async fn session_export_archive(
+        &self,
+        params: Parameters<SessionExportArchiveParams>,
+    ) -> Result<Json<SessionExportArchiveResponse>, ErrorData> {
+        let SessionExportArchiveParams { path } = params.0;
+        // Sync first so a session switch made in the TUI picks the folder to archive.
+        let state = self.lock_state_synced().await?;
+        let Some(session_folder) = self.session_folder() else {
+            return Err(ErrorData::invalid_request(
+                "no session folder; start nereid on a session folder to export archives",
+                None,
+            ));
+        };
+        let files = session_folder.export_archive(std::path::Path::new(&path)).map_err(|err| {
+            ErrorData::internal_error(format!("failed to export session archive: {err}"), None)
+        })?;
+        drop(state);
+        Ok(Json(SessionExportArchiveResponse {
+            path,
+            version: ARCHIVE_VERSION,
+            files: files as u64,
+        }))
+    }
+
+    /// Unpack a `.nereid.tar.zst` session archive into a new workspace session folder `name`
+    /// and open it like `workspace.open`; needs `--workspace`, since the current session folder
+    /// already holds a session.
+    #[tool(name = "session.import_archive")]
+    async fn session_import_archive(
+        &self,
+        params: Parameters<SessionImportArchiveParams>,
+    ) -> Result<Json<SessionImportArchiveResponse>, ErrorData> {
+        let SessionImportArchiveParams { path, name } = params.0;
+        let workspace = self.workspace()?;
+
+        let mut state = self.lock_state_synced().await?;
+        workspace.import_session(&name, std::path::Path::new(&path)).map_err(|err| {
+            ErrorData::invalid_params(
+                format!("cannot import session archive: {err}"),
+                Some(serde_json::json!({ "path": path, "name": name })),
+            )
+        })?;
+        self.switch_workspace_session(&mut state, &name)?;
+        if let Some(ui_state) = self.ui_state.as_ref() {
+            ui_state.lock().await.set_workspace_session(name.clone());
+        }
+        let response = Json(SessionImportArchiveResponse {
+            session_id: state.session.session_id().as_str().to_owned(),
+            active_diagram_id: state
+                .session
+                .active_diagram_id()
+                .map(|diagram_id| diagram_id.as_str().to_owned()),
+            diagrams: state.session.diagrams().len() as u64,
+            name,
+        });
+        drop(state);
+        self.agent_highlights.lock().await.clear();
+        self.clear_agent_cursors().await;
+        self.notify_session_changed().await;
+        Ok(response)
+    }
+
+    /// Scaffold diagrams from a built-in template (`microservices`, `request-lifecycle`,
+    /// `context`). With `name`, create that session folder in the workspace and open it like
+    /// `workspace.open`; without, add the template's diagrams and xrefs to the current session.
+    #[tool(name = "session.init_from_template")]
+    async fn session_init_from_template(
+        &self,
+        params: Parameters<SessionInitFromTemplateParams>,
+    ) -> Result<Json<SessionInitFromTemplateResponse>, ErrorData> {
+        let SessionInitFromTemplateParams { template, name } = params.0;
+        let Some(template) = SessionTemplate::parse(&template) else {
+            return Err(ErrorData::invalid_params(
+                "unknown template",
+                Some(serde_json::json!({
+                    "template": template,
+                    "templates": SessionTemplate::ALL.map(SessionTemplate::name),
+                })),
+            ));
+        };
+
+        let mut state = self.lock_state_synced().await?;
+        let (diagram_ids, xref_ids) = if let Some(name) = name.as_deref() {
+            self.workspace()?.create_session(name, template).map_err(|err| {
+                ErrorData::invalid_params(
+                    format!("cannot create session: {err}"),
+                    Some(serde_json::json!({ "name": name })),
+                )
+            })?;
+            self.switch_workspace_session(&mut state, name)?;
+            if let Some(ui_state) = self.ui_state.as_ref() {
+                ui_state.lock().await.set_workspace_session(name.to_owned());
+            }
+            let diagram_ids = state.session.ordered_diagram_ids().into_iter().cloned().collect();
+            (diagram_ids, state.session.xrefs().keys().cloned().collect())
+        } else {
+            let scaffold = template.session(state.session.session_id().clone());
+            let taken = scaffold
+                .diagrams()
+                .keys()
+                .filter(|diagram_id| state.session.diagrams().contains_key(*diagram_id))
+                .map(|diagram_id| diagram_id.as_str().to_owned())
+                .collect::<Vec<_>>();
+            if !taken.is_empty() {
+                return Err(ErrorData::invalid_params(
+                    "template diagram ids already exist in this session",
+                    Some(serde_json::json!({ "diagram_ids": taken })),
+                ));
+            }
+
+            let mut candidate = state.session.clone();
+            let diagram_ids =
+                scaffold.ordered_diagram_ids().into_iter().cloned().collect::<Vec<_>>();
+            let order = candidate
+                .ordered_diagram_ids()
+                .into_iter()
+                .cloned()
+                .chain(diagram_ids.iter().cloned())
+                .collect::<Vec<_>>();
+            candidate.diagrams_mut().extend(scaffold.diagrams().clone());
+            candidate.set_diagram_order(order);
+            candidate.set_active_diagram_id(scaffold.active_diagram_id().cloned());
+            if candidate.title().is_none() {
+                candidate.set_title(scaffold.title());
+            }
+            let mut xref_ids = Vec::new();
+            for xref in scaffold.xrefs().values() {
+                let xref_id = idgen::next_xref_id(&mut candidate);
+                candidate.xrefs_mut().insert(xref_id.clone(), xref.clone());
+                xref_ids.push(xref_id);
+            }
+
+            if let Some(session_folder) = &self.session_folder() {
+                let meta = session_folder.load_meta().map_err(|err| {
+                    ErrorData::internal_error(format!("failed to load session meta: {err}"), None)
+                })?;
+                candidate.set_selection_log(meta.selection_log);
+                session_folder.save_session(&candidate).map_err(|err| {
+                    ErrorData::internal_error(format!("failed to persist session: {err}"), None)
+                })?;
+            }
+            state.session = candidate;
+            (diagram_ids, xref_ids)
+        };
+        let response = SessionInitFromTemplateResponse {
+            template: template.name().to_owned(),
+            session_id: state.session.session_id().as_str().to_owned(),
+            diagram_ids: diagram_ids.iter().map(|id| id.as_str().to_owned()).collect(),
+            xref_ids: xref_ids.iter().map(|id| id.as_str().to_owned()).collect(),
+            name,
+        };
+        let switched = response.name.is_some();
+        drop(state);
+        if switched {
+            self.agent_highlights.lock().await.clear();
+            self.clear_agent_cursors().await;
+        }
+        self.notify_session_changed().await;
+        Ok(Json(response))
+    }
+
+    /// List the session folders of the workspace the server was started on (`--workspace`).
+    #[tool(name = "workspace.list_sessions")]
+    async fn workspace_list_sessions(
+        &self,
+    ) -> Result<Json<WorkspaceListSessionsResponse>, ErrorData> {
+        let workspace = self.workspace()?;
+        // Sync first so a switch made in the TUI shows up as the active session.
+        drop(self.lock_state_synced().await?);
+        let active_session = self.workspace_session_name();
+        let sessions = workspace
+            .list_sessions()
+            .map_err(|err| {
+                ErrorData::internal_error(format!("failed to list workspace sessions: {err}"), None)
+            })?
+            .into_iter()
+            .map(|session| WorkspaceSessionSummary {
+                active: active_session.as_deref() == Some(session.name.as_str()),
+                name: session.name,
+                session_id: session.session_id.as_str().to_owned(),
+                title: session.title,
+                diagrams: session.diagram_count as u64,
+            })
+            .collect();
+        Ok(Json(WorkspaceListSessionsResponse {
+            workspace_dir: workspace.root().display().to_string(),
+            active_session,
+            sessions,
+        }))
+    }
+
+    /// Make another session of the workspace the active one, for this server and the TUI; ids,
+    /// revisions and proposals from the previous session no longer apply afterwards.
+    #[tool(name = "workspace.open")]
+    async fn workspace_open(
+        &self,
+        params: Parameters<WorkspaceOpenParams>,
+    ) -> Result<Json<WorkspaceOpenResponse>, ErrorData> {
+        let WorkspaceOpenParams { name } = params.0;
+        self.workspace()?;
+
+        let mut state = self.lock_state_synced().await?;
+        let recovered = self.switch_workspace_session(&mut state, &name)?;
+        if let Some(ui_state) = self.ui_state.as_ref() {
+            ui_state.lock().await.set_workspace_session(name.clone());
+        }
+        let response = Json(WorkspaceOpenResponse {
+            session_id: state.session.session_id().as_str().to_owned(),
+            active_diagram_id: state
+                .session
+                .active_diagram_id()
+                .map(|diagram_id| diagram_id.as_str().to_owned()),
+            diagrams: state.session.diagrams().len() as u64,
+            recovered_batches: recovered as u64,
+            name,
+        });
+        drop(state);
+        self.agent_highlights.lock().await.clear();
+        self.clear_agent_cursors().await;
+        self.notify_session_changed().await;
+        Ok(response)
+    }
+
+    /// Full-text search across the session: object labels, message text and notes of every
+    /// non-archived diagram, walkthrough node titles/bodies and xref labels; ranked and paged.
+    #[tool(name = "session.search")]
+    async fn session_search(
+        &self,
+        params: Parameters<SessionSearchParams>,
+    ) -> Result<Json<SessionSearchResponse>, ErrorData> {
+        const DEFAULT_LIMIT: u64 = 20;
+
+        let SessionSearchParams { query, offset, limit } = params.0;
+        let offset = offset.unwrap_or(0);
+        let limit = limit.unwrap_or(DEFAULT_LIMIT);
+
+        let state = self.lock_state_synced().await?;
+        let hits = SearchIndex::build(&state.session).search(&query);
+        drop(state);
+
+        let total = hits.len() as u64;
+        let results = hits
+            .into_iter()
+            .skip(offset.min(usize::MAX as u64) as usize)
+            .take(limit.min(usize::MAX as u64) as usize)
+            .map(|hit| SessionSearchHit {
+                kind: hit.target.kind().to_owned(),
+                id: hit.target.id(),
+                field: hit.field.as_str().to_owned(),
+                text: hit.text,
+                score: hit.score,
+            })
+            .collect::<Vec<_>>();
+        let end = offset.saturating_add(results.len() as u64);
+        let next_offset = (end < total).then_some(end);
+
+        Ok(Json(SessionSearchResponse { total, results, next_offset }))
+    }
+
+    /// List diagrams in the current session; start here, then call `diagram.current` or
+    /// `diagram.open` (bootstrap with `diagram.create_from_mermaid` if empty). Archived diagrams
+    /// are skipped unless `include_archived` is set.
+    #[tool(name = "diagram.list")]
+    async fn diagram_list(
+        &self,
+        params: Parameters<DiagramListParams>,
+    ) -> Result<Json<ListDiagramsResponse>, ErrorData> {
+        let state = self.lock_state_synced().await?;
+        let session_active_diagram_id =
+            state.session.active_diagram_id().map(|diagram_id| diagram_id.as_str().to_owned());
+        let diagram_ids = if params.0.include_archived.unwrap_or(false) {
+            state.session.ordered_diagram_ids()
+        } else {
+            state.session.listed_diagram_ids()
+        };
+        let diagrams = diagram_ids
+            .into_iter()
+            .map(|diagram_id| (diagram_id, &state.session.diagrams()[diagram_id]))
+            .map(|(diagram_id, diagram)| DiagramSummary {
+                diagram_id: diagram_id.as_str().to_owned(),
+                name: diagram.name().to_owned(),
+                kind: diagram_kind_label(diagram.kind()).to_owned(),
+                rev: diagram.rev(),
+                description: diagram.description().map(ToOwned::to_owned),
+                archived: diagram.is_archived(),
+                read_only: diagram.is_read_only(),
+            })
+            .collect::<Vec<_>>();
+        drop(state);
+        let context = self.read_context(session_active_diagram_id).await;
+
+        Ok(Json(ListDiagramsResponse { diagrams, context }))
+    }
+
+    /// Archive or restore a diagram (default: active). Archived diagrams stay readable and can
+    /// still be opened, but are left out of `diagram.list`, TUI cycling and search.
+    #[tool(name = "diagram.set_archived")]
+    async fn diagram_set_archived(
+        &self,
+        params: Parameters<DiagramSetArchivedParams>,
+    ) -> Result<Json<DiagramArchivedResponse>, ErrorData> {
+        let DiagramSetArchivedParams { diagram_id, archived } = params.0;
+
+        let mut state = self.lock_state_synced().await?;
+        let diagram_id = resolve_diagram_id(&state.session, diagram_id.as_deref())?;
+        let mut candidate = state.session.clone();
+        candidate
+            .diagrams_mut()
+            .get_mut(&diagram_id)
+            .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?
+            .set_archived(archived);
+
+        if let Some(session_folder) = &self.session_folder() {
+            session_folder.save_diagram_archived(&candidate, &diagram_id).map_err(|err| {
+                ErrorData::internal_error(
+                    format!("failed to persist archived flag: {err}"),
+                    Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+                )
+            })?;
+        }
+        state.session = candidate;
+        drop(state);
+
+        self.notify_session_changed().await;
+        Ok(Json(DiagramArchivedResponse { diagram_id: diagram_id.to_string(), archived }))
+    }
+
+    /// Lock or unlock a diagram (default: active). Read-only diagrams reject ops, tidy, description
+    /// updates and deletion, here and in the TUI, until unlocked.
+    #[tool(name = "diagram.set_read_only")]
+    async fn diagram_set_read_only(
+        &self,
+        params: Parameters<DiagramSetReadOnlyParams>,
+    ) -> Result<Json<DiagramReadOnlyResponse>, ErrorData> {
+        let DiagramSetReadOnlyParams { diagram_id, read_only } = params.0;
+
+        let mut state = self.lock_state_synced().await?;
+        let diagram_id = resolve_diagram_id(&state.session, diagram_id.as_deref())?;
+        let mut candidate = state.session.clone();
+        candidate
+            .diagrams_mut()
+            .get_mut(&diagram_id)
+            .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?
+            .set_read_only(read_only);
+
+        if let Some(session_folder) = &self.session_folder() {
+            session_folder.save_diagram_read_only(&candidate, &diagram_id).map_err(|err| {
+                ErrorData::internal_error(
+                    format!("failed to persist read-only flag: {err}"),
+                    Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+                )
+            })?;
+        }
+        state.session = candidate;
+        drop(state);
+
+        self.notify_session_changed().await;
+        Ok(Json(DiagramReadOnlyResponse { diagram_id: diagram_id.to_string(), read_only }))
+    }
+
+    /// Set the Markdown description of a diagram (default: active); an empty string clears it.
+    /// Descriptions are diagram-level notes and do not bump the diagram rev.
+    #[tool(name = "diagram.update_description")]
+    async fn diagram_update_description(
+        &self,
+        params: Parameters<DiagramUpdateDescriptionParams>,
+    ) -> Result<Json<DiagramDescriptionResponse>, ErrorData> {
+        let DiagramUpdateDescriptionParams { diagram_id, description } = params.0;
+
+        let mut state = self.lock_state_synced().await?;
+        let diagram_id = resolve_diagram_id(&state.session, diagram_id.as_deref())?;
+        ensure_diagram_writable(&state.session, &diagram_id)?;
+        let mut candidate = state.session.clone();
+        let diagram = candidate
+            .diagrams_mut()
+            .get_mut(&diagram_id)
+            .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?;
+        diagram.set_description(Some(description));
+
+        if let Some(session_folder) = &self.session_folder() {
+            session_folder.save_diagram_description(&candidate, &diagram_id).map_err(|err| {
+                ErrorData::internal_error(
+                    format!("failed to persist diagram description: {err}"),
+                    Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+                )
+            })?;
+        }
+        state.session = candidate;
+
+        let description = state
+            .session
+            .diagrams()
+            .get(&diagram_id)
+            .and_then(|diagram| diagram.description())
+            .map(ToOwned::to_owned);
+        drop(state);
+        self.notify_session_changed().await;
+        Ok(Json(DiagramDescriptionResponse {
+            diagram_id: diagram_id.as_str().to_owned(),
+            description,
+        }))
+    }
+
+    /// Create a diagram from raw Mermaid; use to bootstrap a session, then continue with
+    /// `diagram.open`/`diagram.stat`.
+    #[tool(name = "diagram.create_from_mermaid")]
+    async fn diagram_create_from_mermaid(
+        &self,
+        params: Parameters<DiagramCreateFromMermaidParams>,
+    ) -> Result<Json<DiagramCreateFromMermaidResponse>, ErrorData> {
+        let DiagramCreateFromMermaidParams { mermaid, diagram_id, name, make_active } = params.0;
+
+        let Some(kind) = detect_diagram_kind(&mermaid) else {
+            return Err(ErrorData::invalid_params(
+                "expected 'flowchart'/'graph', 'sequenceDiagram', 'stateDiagram-v2' or 'classDiagram' \
+                 as the first non-empty line",
+                None,
+            ));
+        };
+
+        let id_prefix_rules = self.lock_state_synced().await?.session.id_prefix_rules().to_vec();
+        let ast = parse_mermaid_ast(kind, &mermaid, &id_prefix_rules)?;
+
+        let (diagram, active_diagram_id) =
+            self.insert_created_diagram(ast, diagram_id, name, make_active.unwrap_or(true)).await?;
+        Ok(Json(DiagramCreateFromMermaidResponse { diagram, active_diagram_id }))
+    }
+
+    /// Create an empty diagram of `kind`, or one seeded with Mermaid of that kind; remove it
+    /// again with `diagram.delete`.
+    #[tool(name = "diagram.create")]
+    async fn diagram_create(
+        &self,
+        params: Parameters<DiagramCreateParams>,
+    ) -> Result<Json<DiagramCreateFromMermaidResponse>, ErrorData> {
+        let DiagramCreateParams { kind, diagram_id, name, mermaid, make_active } = params.0;
+
+        let Some(kind) =
+            [DiagramKind::Sequence, DiagramKind::Flowchart, DiagramKind::State, DiagramKind::Class]
+                .into_iter()
+                .find(|candidate| diagram_kind_label(*candidate).eq_ignore_ascii_case(kind.trim()))
+        else {
+            return Err(ErrorData::invalid_params(
+                "expected kind 'sequence', 'flowchart', 'state' or 'class'",
+                Some(serde_json::json!({ "kind": kind })),
+            ));
+        };
+
+        let ast = match mermaid {
+            Some(mermaid) => {
+                let found = detect_diagram_kind(&mermaid);
+                if found != Some(kind) {
+                    return Err(ErrorData::invalid_params(
+                        "mermaid does not start with a header of the requested kind",
+                        Some(serde_json::json!({
+                            "kind": diagram_kind_label(kind).to_lowercase(),
+                            "found": found.map(|found| diagram_kind_label(found).to_lowercase()),
+                        })),
+                    ));
+                }
+                let id_prefix_rules =
+                    self.lock_state_synced().await?.session.id_prefix_rules().to_vec();
+                parse_mermaid_ast(kind, &mermaid, &id_prefix_rules)?
+            }
+            None => match kind {
+                DiagramKind::Sequence => DiagramAst::Sequence(Default::default()),
+                DiagramKind::Flowchart => DiagramAst::Flowchart(Default::default()),
+                DiagramKind::State => DiagramAst::State(Default::default()),
+                DiagramKind::Class => DiagramAst::Class(Default::default()),
+            },
+        };
+
+        let (diagram, active_diagram_id) =
+            self.insert_created_diagram(ast, diagram_id, name, make_active.unwrap_or(true)).await?;
+        Ok(Json(DiagramCreateFromMermaidResponse { diagram, active_diagram_id }))
+    }
+
+    /// Create a flowchart from node/edge rows (e.g. spreadsheet exports) with an optional column
+    /// mapping; node ids derive from the id column so re-imports are stable.
+    #[tool(name = "diagram.import_table")]
+    async fn diagram_import_table(
+        &self,
+        params: Parameters<DiagramImportTableParams>,
+    ) -> Result<Json<DiagramImportTableResponse>, ErrorData> {
+        let DiagramImportTableParams { nodes, edges, mapping, diagram_id, name, make_active } =
+            params.0;
+
+        let mut column_mapping = ColumnMapping::default();
+        for (key, column) in mapping.unwrap_or_default() {
+            column_mapping.set(&key, column).map_err(|err| {
+                ErrorData::invalid_params(
+                    err.to_string(),
+                    Some(serde_json::json!({ "mapping_key": key })),
+                )
+            })?;
+        }
+
+        let nodes = nodes.into_iter().map(json_object_row).collect::<Vec<_>>();
+        let edges = edges.unwrap_or_default().into_iter().map(json_object_row).collect::<Vec<_>>();
+        let ast = flowchart_from_rows(&nodes, &edges, &column_mapping).map_err(|err| {
+            ErrorData::invalid_params(format!("cannot import table rows: {err}"), None)
+        })?;
+        let node_count = ast.nodes().len() as u64;
+        let edge_count = ast.edges().len() as u64;
+
+        let (diagram, active_diagram_id) = self
+            .insert_created_diagram(
+                DiagramAst::Flowchart(ast),
+                diagram_id,
+                name,
+                make_active.unwrap_or(true),
+            )
+            .await?;
+        Ok(Json(DiagramImportTableResponse { diagram, active_diagram_id, node_count, edge_count }))
+    }
+
+    /// Scaffold a sequence diagram from a flowchart path: nodes become participants, hops become
+    /// messages in path order, and every new object gets a `derived_from` xref to its source.
+    #[tool(name = "diagram.scaffold_sequence")]
+    async fn diagram_scaffold_sequence(
+        &self,
+        params: Parameters<DiagramScaffoldSequenceParams>,
+    ) -> Result<Json<DiagramScaffoldResponse>, ErrorData> {
+        let DiagramScaffoldSequenceParams {
+            diagram_id,
+            node_ids,
+            target_diagram_id,
+            name,
+            make_active,
+        } = params.0;
+        let path = node_ids
+            .iter()
+            .map(String::as_str)
+            .map(parse_object_id)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let state = self.lock_state_synced().await?;
+        let diagram_id = resolve_diagram_id(&state.session, diagram_id.as_deref())?;
+        let flow = state
+            .session
+            .diagrams()
+            .get(&diagram_id)
+            .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?;
+        let (ast, links) = sequence_from_flow_path(flow, &path).map_err(|err| {
+            ErrorData::invalid_params(
+                format!("cannot scaffold sequence diagram: {err}"),
+                Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+            )
+        })?;
+        drop(state);
+
+        let target_diagram_id = |session: &Session| {
+            target_diagram_id
+                .or_else(|| Some(scaffold_diagram_id(session, &diagram_id, "seq").into_string()))
+        };
+        let (diagram, active_diagram_id, xref_ids) = self
+            .insert_scaffolded_diagram(
+                DiagramAst::Sequence(ast),
+                target_diagram_id,
+                name,
+                make_active.unwrap_or(true),
+                &links,
+            )
+            .await?;
+        Ok(Json(DiagramScaffoldResponse { diagram, active_diagram_id, xref_ids }))
+    }
+
+    /// Scaffold a flowchart of participant interactions from a sequence diagram: one node per
+    /// participant, one edge per sender/receiver pair weighted by its message count. Pass
+    /// `persist: false` for a virtual preview that only returns the Mermaid source.
+    #[tool(name = "diagram.scaffold_flowchart")]
+    async fn diagram_scaffold_flowchart(
+        &self,
+        params: Parameters<DiagramScaffoldFlowchartParams>,
+    ) -> Result<Json<DiagramScaffoldFlowchartResponse>, ErrorData> {
+        let DiagramScaffoldFlowchartParams {
+            diagram_id,
+            persist,
+            target_diagram_id,
+            name,
+            make_active,
+        } = params.0;
+
+        let state = self.lock_state_synced().await?;
+        let diagram_id = resolve_diagram_id(&state.session, diagram_id.as_deref())?;
+        let seq = state
+            .session
+            .diagrams()
+            .get(&diagram_id)
+            .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?;
+        let (ast, links) = flowchart_from_sequence(seq).map_err(|err| {
+            ErrorData::invalid_params(
+                format!("cannot scaffold flowchart: {err}"),
+                Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+            )
+        })?;
+        drop(state);
+
+        let mermaid = mermaid_for_flowchart(&ast);
+        if !persist.unwrap_or(true) {
+            return Ok(Json(DiagramScaffoldFlowchartResponse {
+                diagram: None,
+                active_diagram_id: None,
+                xref_ids: Vec::new(),
+                mermaid,
+            }));
+        }
+
+        let target_diagram_id = |session: &Session| {
+            target_diagram_id
+                .or_else(|| Some(scaffold_diagram_id(session, &diagram_id, "flow").into_string()))
+        };
+        let (diagram, active_diagram_id, xref_ids) = self
+            .insert_scaffolded_diagram(
+                DiagramAst::Flowchart(ast),
+                target_diagram_id,
+                name,
+                make_active.unwrap_or(true),
+                &links,
+            )
+            .await?;
+        Ok(Json(DiagramScaffoldFlowchartResponse {
+            diagram: Some(diagram),
+            active_diagram_id,
+            xref_ids,
+            mermaid,
+        }))
+    }
+
+    /// Set the active diagram default for diagram-scoped tools; typically follows `diagram.list`
+    /// or `diagram.create_from_mermaid`.
+    #[tool(name = "diagram.open")]
+    async fn diagram_open(
+        &self,
+        params: Parameters<DiagramOpenParams>,
+    ) -> Result<Json<DiagramOpenResponse>, ErrorData> {
+        let diagram_id = params.0.diagram_id;
+        let parsed = DiagramId::new(diagram_id.clone()).map_err(|err| {
+            ErrorData::invalid_params(
+                format!("invalid diagram_id: {err}"),
+                Some(serde_json::json!({ "diagram_id": diagram_id })),
+            )
+        })?;
+
+        let mut state = self.lock_state_synced().await?;
+        if !state.session.diagrams().contains_key(&parsed) {
+            return Err(ErrorData::resource_not_found(
+                "diagram not found",
+                Some(serde_json::json!({ "diagram_id": diagram_id })),
+            ));
+        }
+
+        if let Some(session_folder) = &self.session_folder() {
+            let mut candidate = state.session.clone();
+            candidate.set_active_diagram_id(Some(parsed.clone()));
+            let meta = session_folder.load_meta().map_err(|err| {
+                ErrorData::internal_error(
+                    format!("failed to load session meta: {err}"),
+                    Some(serde_json::json!({ "diagram_id": diagram_id })),
+                )
+            })?;
+            candidate.set_selection_log(meta.selection_log);
+            session_folder.save_session(&candidate).map_err(|err| {
+                ErrorData::internal_error(
+                    format!("failed to persist session: {err}"),
+                    Some(serde_json::json!({ "diagram_id": diagram_id })),
+                )
+            })?;
+            state.session = candidate;
+        } else {
+            state.session.set_active_diagram_id(Some(parsed.clone()));
+        }
+
+        let response = Json(DiagramOpenResponse { active_diagram_id: parsed.as_str().to_owned() });
+        drop(state);
+        self.notify_session_changed().await;
+        Ok(response)
+    }
+
+    /// Set the diagram order followed by `diagram.list` and the TUI's previous/next diagram keys;
+    /// diagrams left out keep following in id order.
+    #[tool(name = "diagram.reorder")]
+    async fn diagram_reorder(
+        &self,
+        params: Parameters<DiagramReorderParams>,
+    ) -> Result<Json<DiagramReorderResponse>, ErrorData> {
+        let mut state = self.lock_state_synced().await?;
+        let mut diagram_order = Vec::with_capacity(params.0.diagram_ids.len());
+        for diagram_id in params.0.diagram_ids {
+            let parsed = DiagramId::new(diagram_id.clone()).map_err(|err| {
+                ErrorData::invalid_params(
+                    format!("invalid diagram_id: {err}"),
+                    Some(serde_json::json!({ "diagram_id": diagram_id })),
+                )
+            })?;
+            if !state.session.diagrams().contains_key(&parsed) {
+                return Err(ErrorData::resource_not_found(
+                    "diagram not found",
+                    Some(serde_json::json!({ "diagram_id": diagram_id })),
+                ));
+            }
+            diagram_order.push(parsed);
+        }
+
+        let mut candidate = state.session.clone();
+        candidate.set_diagram_order(diagram_order);
+        if let Some(session_folder) = &self.session_folder() {
+            session_folder.save_diagram_order(&candidate).map_err(|err| {
+                ErrorData::internal_error(format!("failed to persist diagram order: {err}"), None)
+            })?;
+        }
+        state.session = candidate;
+
+        let diagram_ids =
+            state.session.ordered_diagram_ids().into_iter().map(ToString::to_string).collect();
         drop(state);
-        self.notify_ui_session_changed().await;
-        Ok(response)
+        self.notify_session_changed().await;
+        Ok(Json(DiagramReorderResponse { diagram_ids }))
     }
 
     /// Remove a diagram by id and retarget active diagram when needed.
@@ -431,13 +1940,14 @@ impl NereidMcp {
                 Some(serde_json::json!({ "diagram_id": diagram_id })),
             ));
         }
+        ensure_diagram_writable(&state.session, &parsed)?;
 
-        if let Some(session_folder) = &self.session_folder {
+        if let Some(session_folder) = &self.session_folder() {
             let mut candidate = state.session.clone();
             candidate.diagrams_mut().remove(&parsed);
 
             if candidate.active_diagram_id().is_some_and(|active| active == &parsed) {
-                let next_active = candidate.diagrams().keys().next().cloned();
+                let next_active = candidate.listed_diagram_ids().first().cloned().cloned();
                 candidate.set_active_diagram_id(next_active);
             }
 
@@ -447,7 +1957,7 @@ impl NereidMcp {
                     Some(serde_json::json!({ "diagram_id": diagram_id })),
                 )
             })?;
-            candidate.set_selected_object_refs(meta.selected_object_refs.into_iter().collect());
+            candidate.set_selection_log(meta.selection_log);
             retain_existing_selected_object_refs(&mut candidate);
             refresh_xref_statuses(&mut candidate);
 
@@ -461,7 +1971,7 @@ impl NereidMcp {
         } else {
             state.session.diagrams_mut().remove(&parsed);
             if state.session.active_diagram_id().is_some_and(|active| active == &parsed) {
-                let next_active = state.session.diagrams().keys().next().cloned();
+                let next_active = state.session.listed_diagram_ids().first().cloned().cloned();
                 state.session.set_active_diagram_id(next_active);
             }
 
@@ -476,12 +1986,19 @@ impl NereidMcp {
 
         let mut agent_highlights = self.agent_highlights.lock().await;
         agent_highlights.retain(|object_ref| object_ref.diagram_id() != &parsed);
+        drop(agent_highlights);
+        if let Some(ui_state) = self.ui_state.as_ref() {
+            ui_state
+                .lock()
+                .await
+                .retain_agent_cursors(|object_ref| object_ref.diagram_id() != &parsed);
+        }
 
         let response = Json(DiagramDeleteResponse {
             deleted_diagram_id: parsed.as_str().to_owned(),
             active_diagram_id,
         });
-        self.notify_ui_session_changed().await;
+        self.notify_session_changed().await;
         Ok(response)
     }
 
@@ -516,7 +2033,7 @@ impl NereidMcp {
             ));
         }
 
-        if let Some(session_folder) = &self.session_folder {
+        if let Some(session_folder) = &self.session_folder() {
             let mut candidate = state.session.clone();
             candidate.set_active_walkthrough_id(Some(parsed.clone()));
             let meta = session_folder.load_meta().map_err(|err| {
@@ -525,7 +2042,7 @@ impl NereidMcp {
                     Some(serde_json::json!({ "walkthrough_id": walkthrough_id })),
                 )
             })?;
-            candidate.set_selected_object_refs(meta.selected_object_refs.into_iter().collect());
+            candidate.set_selection_log(meta.selection_log);
             session_folder.save_session(&candidate).map_err(|err| {
                 ErrorData::internal_error(
                     format!("failed to persist session: {err}"),
@@ -540,7 +2057,7 @@ impl NereidMcp {
         let response =
             Json(WalkthroughOpenResponse { active_walkthrough_id: parsed.as_str().to_owned() });
         drop(state);
-        self.notify_ui_session_changed().await;
+        self.notify_session_changed().await;
         Ok(response)
     }
 
@@ -573,12 +2090,14 @@ impl NereidMcp {
         Ok(Json(AttentionReadResponse {
             object_ref: context.human_active_object_ref.clone(),
             diagram_id: context.human_active_diagram_id.clone(),
+            expires_in_ms: None,
+            agents: Vec::new(),
             context,
         }))
     }
 
-    /// Read agent-owned attention (single spotlight); call before `attention.agent.set`/`clear`
-    /// to avoid unnecessary spotlight churn.
+    /// Read agent-owned attention: the shared spotlight (last set) plus each agent's cursor; call
+    /// before `attention.agent.set`/`clear` to avoid unnecessary spotlight churn.
     #[tool(name = "attention.agent.read")]
     async fn attention_agent_read(&self) -> Result<Json<AttentionReadResponse>, ErrorData> {
         let state = self.lock_state_synced().await?;
@@ -588,24 +2107,39 @@ impl NereidMcp {
         let object_ref = self.agent_highlights.lock().await.iter().next().cloned();
         let diagram_id =
             object_ref.as_ref().map(|object_ref| object_ref.diagram_id().as_str().to_owned());
+        let expires_in_ms = match object_ref {
+            Some(_) => self.attention_expires_in().await.map(duration_ms),
+            None => None,
+        };
+        let agents = match self.ui_state.as_ref() {
+            Some(ui_state) => {
+                agent_cursor_summaries(ui_state.lock().await.agent_cursors(), unix_now_ms())
+            }
+            None => Vec::new(),
+        };
         let context = self.read_context(session_active_diagram_id).await;
 
         Ok(Json(AttentionReadResponse {
             object_ref: object_ref.map(|object_ref| object_ref.to_string()),
             diagram_id,
+            expires_in_ms,
+            agents,
             context,
         }))
     }
 
     /// Set agent-owned attention to one object; call before explanations/edits so the user can
-    /// follow the agent in real time.
+    /// follow the agent in real time. Pass a stable `client_id` (and `name`) when several agents
+    /// work at once: each gets its own colored cursor in the TUI, the last set one is followed.
+    /// The spotlight expires after `expires_in_ms` unless renewed with `attention.agent.heartbeat`.
     #[tool(name = "attention.agent.set")]
     async fn attention_agent_set(
         &self,
         params: Parameters<AttentionAgentSetParams>,
     ) -> Result<Json<AttentionSetResponse>, ErrorData> {
-        let AttentionAgentSetParams { object_ref } = params.0;
+        let AttentionAgentSetParams { object_ref, client_id, name } = params.0;
         let parsed = parse_object_ref(&object_ref)?;
+        let client_id = client_id.unwrap_or_else(|| DEFAULT_AGENT_CLIENT_ID.to_owned());
 
         let state = self.lock_state_synced().await?;
         if object_ref_is_missing(&state.session, &parsed) {
@@ -619,19 +2153,84 @@ impl NereidMcp {
         let mut agent_highlights = self.agent_highlights.lock().await;
         agent_highlights.clear();
         agent_highlights.insert(parsed.clone());
+        drop(agent_highlights);
+        if let Some(ui_state) = self.ui_state.as_ref() {
+            let mut ui_state = ui_state.lock().await;
+            ui_state.set_expired_agent_highlight(None);
+            let name = name.unwrap_or_else(|| client_id.clone());
+            ui_state.set_agent_cursor(client_id.clone(), name, parsed.clone(), unix_now_ms());
+        }
+        let expires_in_ms = self.refresh_attention_deadline().await.map(duration_ms);
+        self.publish_session_changes().await;
 
         Ok(Json(AttentionSetResponse {
             object_ref: parsed.to_string(),
             diagram_id: parsed.diagram_id().as_str().to_owned(),
+            client_id,
+            expires_in_ms,
+        }))
+    }
+
+    /// Keep the agent spotlight (and the `client_id` cursor) alive while still working on it; call
+    /// more often than `expires_in_ms`, or it is cleared as if the agent had gone away.
+    #[tool(name = "attention.agent.heartbeat")]
+    async fn attention_agent_heartbeat(
+        &self,
+        params: Parameters<AttentionAgentParams>,
+    ) -> Result<Json<AttentionHeartbeatResponse>, ErrorData> {
+        let client_id = params.0.client_id.unwrap_or_else(|| DEFAULT_AGENT_CLIENT_ID.to_owned());
+        let object_ref = match self.ui_state.as_ref() {
+            Some(ui_state) => {
+                let mut ui_state = ui_state.lock().await;
+                ui_state.touch_agent_cursor(&client_id, unix_now_ms());
+                ui_state.agent_cursors().get(&client_id).map(|cursor| cursor.object_ref.clone())
+            }
+            None => self.agent_highlights.lock().await.iter().next().cloned(),
+        };
+        let expires_in_ms = match object_ref {
+            Some(_) => self.refresh_attention_deadline().await.map(duration_ms),
+            None => None,
+        };
+        Ok(Json(AttentionHeartbeatResponse {
+            object_ref: object_ref.map(|object_ref| object_ref.to_string()),
+            expires_in_ms,
         }))
     }
 
-    /// Clear agent-owned attention; use when done with a topic or before changing context.
+    /// Clear agent-owned attention; use when done with a topic or before changing context. With
+    /// `client_id` only that agent's cursor goes, and the spotlight moves to another agent's.
     #[tool(name = "attention.agent.clear")]
-    async fn attention_agent_clear(&self) -> Result<Json<AttentionClearResponse>, ErrorData> {
+    async fn attention_agent_clear(
+        &self,
+        params: Parameters<AttentionAgentParams>,
+    ) -> Result<Json<AttentionClearResponse>, ErrorData> {
+        if let (Some(client_id), Some(ui_state)) = (params.0.client_id, self.ui_state.as_ref()) {
+            let (released, cursors) = {
+                let mut ui_state = ui_state.lock().await;
+                let released =
+                    ui_state.clear_agent_cursor(&client_id).into_iter().collect::<Vec<_>>();
+                (released, ui_state.agent_cursors().clone())
+            };
+            let mut agent_highlights = self.agent_highlights.lock().await;
+            move_spotlight_off_released_cursors(&mut agent_highlights, &released, &cursors);
+            if agent_highlights.is_empty() {
+                *self.attention_deadline.lock().await = None;
+            }
+            drop(agent_highlights);
+            self.publish_session_changes().await;
+            return Ok(Json(AttentionClearResponse { cleared: released.len() as u64 }));
+        }
+
+        self.clear_agent_cursors().await;
         let mut agent_highlights = self.agent_highlights.lock().await;
         let cleared = agent_highlights.len() as u64;
         agent_highlights.clear();
+        drop(agent_highlights);
+        *self.attention_deadline.lock().await = None;
+        if let Some(ui_state) = self.ui_state.as_ref() {
+            ui_state.lock().await.set_expired_agent_highlight(None);
+        }
+        self.publish_session_changes().await;
 
         Ok(Json(AttentionClearResponse { cleared }))
     }
@@ -660,6 +2259,7 @@ impl NereidMcp {
         if let Some(ui_state) = self.ui_state.as_ref() {
             ui_state.lock().await.set_follow_ai(enabled);
         }
+        self.publish_session_changes().await;
         Ok(Json(FollowAiSetResponse { enabled }))
     }
 
@@ -668,11 +2268,11 @@ impl NereidMcp {
     #[tool(name = "selection.read")]
     async fn selection_get(&self) -> Result<Json<SelectionGetResponse>, ErrorData> {
         let mut state = self.lock_state_synced().await?;
-        if let Some(session_folder) = &self.session_folder {
+        if let Some(session_folder) = &self.session_folder() {
             let meta = session_folder.load_meta().map_err(|err| {
                 ErrorData::internal_error(format!("failed to load session meta: {err}"), None)
             })?;
-            state.session.set_selected_object_refs(meta.selected_object_refs.into_iter().collect());
+            state.session.set_selection_log(meta.selection_log);
             retain_existing_selected_object_refs(&mut state.session);
         }
         let object_refs = state
@@ -732,15 +2332,15 @@ impl NereidMcp {
             }
         }
 
-        if let Some(session_folder) = &self.session_folder {
+        if let Some(session_folder) = &self.session_folder() {
             let mut candidate = state.session.clone();
             let meta = session_folder.load_meta().map_err(|err| {
                 ErrorData::internal_error(format!("failed to load session meta: {err}"), None)
             })?;
-            candidate.set_selected_object_refs(meta.selected_object_refs.into_iter().collect());
+            candidate.set_selection_log(meta.selection_log);
             retain_existing_selected_object_refs(&mut candidate);
             apply_mode(&mut candidate, mode, &applied_refs);
-            session_folder.save_selected_object_refs(&candidate).map_err(|err| {
+            session_folder.save_selected_object_refs(&mut candidate).map_err(|err| {
                 ErrorData::internal_error(
                     format!("failed to persist selected object refs: {err}"),
                     Some(serde_json::json!({
@@ -756,7 +2356,7 @@ impl NereidMcp {
         let response =
             Json(SelectionUpdateResponse { applied, ignored: ignored_refs.into_iter().collect() });
         drop(state);
-        self.notify_ui_session_changed().await;
+        self.notify_session_changed().await;
         Ok(response)
     }
 
@@ -769,15 +2369,141 @@ impl NereidMcp {
             state.session.active_diagram_id().map(|diagram_id| diagram_id.as_str().to_owned());
         drop(state);
         let context = self.read_context(active_diagram_id.clone()).await;
+        let viewport = match self.ui_state.as_ref() {
+            Some(ui_state) => ui_state.lock().await.human_viewport(),
+            None => Default::default(),
+        };
 
         Ok(Json(ViewGetStateResponse {
             active_diagram_id,
-            scroll: ViewScroll { x: 0.0, y: 0.0 },
+            scroll: ViewScroll { x: f64::from(viewport.scroll_x), y: f64::from(viewport.scroll_y) },
+            zoom: map_view_zoom_to_mcp(viewport.zoom),
             panes: BTreeMap::new(),
             context,
         }))
     }
 
+    /// Read exactly what the human sees in the diagram pane: the visible rendered lines (pan
+    /// offsets applied) and the objects intersecting the viewport with their on-screen cells; use
+    /// to resolve references like "the box in the top-right". Empty without a TUI.
+    #[tool(name = "view.get_viewport")]
+    async fn view_get_viewport(&self) -> Result<Json<ViewGetViewportResponse>, ErrorData> {
+        let state = self.lock_state_synced().await?;
+        let session_active_diagram_id =
+            state.session.active_diagram_id().map(|diagram_id| diagram_id.as_str().to_owned());
+        drop(state);
+        let (viewport, screen) = match self.ui_state.as_ref() {
+            Some(ui_state) => {
+                let ui_state = ui_state.lock().await;
+                (ui_state.human_viewport(), ui_state.human_screen().clone())
+            }
+            None => Default::default(),
+        };
+        let context = self.read_context(session_active_diagram_id).await;
+
+        Ok(Json(ViewGetViewportResponse {
+            diagram_id: screen.diagram_id.map(|diagram_id| diagram_id.as_str().to_owned()),
+            scroll: ViewScroll { x: f64::from(viewport.scroll_x), y: f64::from(viewport.scroll_y) },
+            width: viewport.width as u64,
+            height: viewport.height as u64,
+            zoom: map_view_zoom_to_mcp(viewport.zoom),
+            lines: screen.lines,
+            objects: screen
+                .objects
+                .into_iter()
+                .map(|(object_ref, spans)| {
+                    let spans =
+                        spans.iter().map(|&(y, x0, x1)| [y as u64, x0 as u64, x1 as u64]).collect();
+                    (object_ref.to_string(), spans)
+                })
+                .collect(),
+            context,
+        }))
+    }
+
+    /// Pan the human's viewport by `dx`/`dy` cells; only applied while follow-AI is enabled.
+    /// Prefer `view.center_on` when the goal is a specific object.
+    #[tool(name = "view.scroll")]
+    async fn view_scroll(
+        &self,
+        params: Parameters<ViewScrollParams>,
+    ) -> Result<Json<ViewScrollResponse>, ErrorData> {
+        let ViewScrollParams { dx, dy } = params.0;
+        let applied = self.push_view_request_if_following(ViewRequest::Scroll { dx, dy }).await;
+        Ok(Json(ViewScrollResponse { dx, dy, applied }))
+    }
+
+    /// Show a diagram in the human's diagram pane; only applied while follow-AI is enabled.
+    /// Unlike `diagram.open`, this moves the human's view rather than the tool default.
+    #[tool(name = "view.set_active_diagram")]
+    async fn view_set_active_diagram(
+        &self,
+        params: Parameters<ViewSetActiveDiagramParams>,
+    ) -> Result<Json<ViewSetActiveDiagramResponse>, ErrorData> {
+        let diagram_id = params.0.diagram_id;
+        let parsed = DiagramId::new(diagram_id.clone()).map_err(|err| {
+            ErrorData::invalid_params(
+                format!("invalid diagram_id: {err}"),
+                Some(serde_json::json!({ "diagram_id": diagram_id })),
+            )
+        })?;
+
+        let state = self.lock_state_synced().await?;
+        if !state.session.diagrams().contains_key(&parsed) {
+            return Err(ErrorData::resource_not_found(
+                "diagram not found",
+                Some(serde_json::json!({ "diagram_id": diagram_id })),
+            ));
+        }
+        drop(state);
+
+        let applied =
+            self.push_view_request_if_following(ViewRequest::SetActiveDiagram(parsed)).await;
+        Ok(Json(ViewSetActiveDiagramResponse { diagram_id, applied }))
+    }
+
+    /// Switch the human's rendering density (`glyph`/`mini` squashed flowchart nodes, or
+    /// `compact`/`normal`/`spacious` flowchart spacing); only applied while follow-AI is enabled.
+    #[tool(name = "view.zoom")]
+    async fn view_zoom(
+        &self,
+        params: Parameters<ViewZoomParams>,
+    ) -> Result<Json<ViewZoomResponse>, ErrorData> {
+        let ViewZoomParams { level } = params.0;
+        let applied =
+            self.push_view_request_if_following(ViewRequest::Zoom(map_view_zoom(level))).await;
+        Ok(Json(ViewZoomResponse { level, applied }))
+    }
+
+    /// Center the human's viewport on one object (switching diagrams if needed); only applied
+    /// while follow-AI is enabled, so check `applied` and fall back to `attention.agent.set`.
+    #[tool(name = "view.center_on")]
+    async fn view_center_on(
+        &self,
+        params: Parameters<ViewCenterOnParams>,
+    ) -> Result<Json<ViewCenterOnResponse>, ErrorData> {
+        let ViewCenterOnParams { object_ref } = params.0;
+        let parsed = parse_object_ref(&object_ref)?;
+
+        let state = self.lock_state_synced().await?;
+        if object_ref_is_missing(&state.session, &parsed) {
+            return Err(ErrorData::resource_not_found(
+                "object not found",
+                Some(serde_json::json!({ "object_ref": object_ref })),
+            ));
+        }
+        drop(state);
+
+        let applied =
+            self.push_view_request_if_following(ViewRequest::CenterOn(parsed.clone())).await;
+
+        Ok(Json(ViewCenterOnResponse {
+            object_ref: parsed.to_string(),
+            diagram_id: parsed.diagram_id().as_str().to_owned(),
+            applied,
+        }))
+    }
+
     /// List walkthroughs in the current session; start here, then `walkthrough.open`,
     /// `walkthrough.stat`, or `walkthrough.read`.
     #[tool(name = "walkthrough.list")]
@@ -824,36 +2550,7 @@ impl NereidMcp {
             )
         })?;
 
-        let nodes = walkthrough
-            .nodes()
-            .iter()
-            .map(|node| McpWalkthroughNode {
-                node_id: node.node_id().as_str().to_owned(),
-                title: node.title().to_owned(),
-                body_md: node.body_md().map(|body| body.to_owned()),
-                refs: node.refs().iter().map(ToString::to_string).collect(),
-                tags: node.tags().to_vec(),
-                status: node.status().map(|status| status.to_owned()),
-            })
-            .collect::<Vec<_>>();
-
-        let edges = walkthrough
-            .edges()
-            .iter()
-            .map(|edge| McpWalkthroughEdge {
-                from_node_id: edge.from_node_id().as_str().to_owned(),
-                to_node_id: edge.to_node_id().as_str().to_owned(),
-                kind: edge.kind().to_owned(),
-                label: edge.label().map(|label| label.to_owned()),
-            })
-            .collect::<Vec<_>>();
-        let walkthrough = McpWalkthrough {
-            walkthrough_id: walkthrough.walkthrough_id().as_str().to_owned(),
-            title: walkthrough.title().to_owned(),
-            rev: walkthrough.rev(),
-            nodes,
-            edges,
-        };
+        let walkthrough = mcp_walkthrough(walkthrough);
 
         drop(state);
         let context = self.read_context(session_active_diagram_id).await;
@@ -1025,159 +2722,218 @@ impl NereidMcp {
         params: Parameters<WalkthroughApplyOpsParams>,
     ) -> Result<Json<ApplyOpsResponse>, ErrorData> {
         let WalkthroughApplyOpsParams { walkthrough_id, base_rev, ops } = params.0;
-        let parsed = parse_walkthrough_id(&walkthrough_id)?;
+        Ok(Json(self.commit_walkthrough_ops(&walkthrough_id, base_rev, &ops).await?))
+    }
 
-        let mut state = self.lock_state_synced().await?;
+    /// Create an empty walkthrough (made active unless `make_active` is false); then add steps
+    /// with `walkthrough.node.add` and link them with `walkthrough.edge.add`.
+    #[tool(name = "walkthrough.create")]
+    async fn walkthrough_create(
+        &self,
+        params: Parameters<WalkthroughCreateParams>,
+    ) -> Result<Json<WalkthroughCreateResponse>, ErrorData> {
+        let WalkthroughCreateParams { title, walkthrough_id, make_active } = params.0;
 
-        if let Some(session_folder) = &self.session_folder {
-            let mut candidate_session = state.session.clone();
-            let walkthrough =
-                candidate_session.walkthroughs_mut().get_mut(&parsed).ok_or_else(|| {
-                    ErrorData::resource_not_found(
-                        "walkthrough not found",
+        let mut state = self.lock_state_synced().await?;
+        let walkthrough_id = match walkthrough_id {
+            Some(walkthrough_id) => {
+                let parsed = parse_walkthrough_id(&walkthrough_id)?;
+                if state.session.walkthroughs().contains_key(&parsed) {
+                    return Err(ErrorData::invalid_params(
+                        "walkthrough_id already exists",
                         Some(serde_json::json!({ "walkthrough_id": walkthrough_id })),
-                    )
-                })?;
-
-            let current_rev = walkthrough.rev();
-            if base_rev != current_rev {
-                let digest = digest_for_walkthrough(walkthrough);
-                return Err(ErrorData::invalid_request(
-                    "conflict: stale base_rev",
-                    Some(serde_json::json!({
-                        "base_rev": base_rev,
-                        "current_rev": current_rev,
-                        "snapshot_tool": "walkthrough.stat",
-                        "digest": {
-                            "rev": digest.rev,
-                            "counts": {
-                                "nodes": digest.counts.nodes,
-                                "edges": digest.counts.edges,
-                            },
-                        },
-                    })),
-                ));
-            }
-
-            if ops.is_empty() {
-                return Ok(Json(ApplyOpsResponse {
-                    new_rev: current_rev,
-                    applied: 0,
-                    delta: DeltaSummary {
-                        added: Vec::new(),
-                        removed: Vec::new(),
-                        updated: Vec::new(),
-                    },
-                }));
+                    ));
+                }
+                parsed
             }
+            None => idgen::walkthrough_id(&state.session),
+        };
 
-            let delta = apply_walkthrough_ops(walkthrough, &parsed, &ops)?;
-            walkthrough.bump_rev();
-            let new_rev = walkthrough.rev();
-
-            let mut history =
-                state.walkthrough_delta_history.get(&parsed).cloned().unwrap_or_else(VecDeque::new);
-            history.push_back(WalkthroughLastDelta {
-                from_rev: base_rev,
-                to_rev: new_rev,
-                delta: delta.clone(),
-            });
-            while history.len() > DELTA_HISTORY_LIMIT {
-                history.pop_front();
-            }
+        let mut candidate = state.session.clone();
+        candidate.walkthroughs_mut().insert(
+            walkthrough_id.clone(),
+            Walkthrough::new(walkthrough_id.clone(), title.clone()),
+        );
+        if make_active.unwrap_or(true) {
+            candidate.set_active_walkthrough_id(Some(walkthrough_id.clone()));
+        }
 
+        if let Some(session_folder) = &self.session_folder() {
             let meta = session_folder.load_meta().map_err(|err| {
                 ErrorData::internal_error(
                     format!("failed to load session meta: {err}"),
-                    Some(serde_json::json!({ "walkthrough_id": walkthrough_id, "base_rev": base_rev })),
+                    Some(serde_json::json!({ "walkthrough_id": walkthrough_id.as_str() })),
                 )
             })?;
-            candidate_session
-                .set_selected_object_refs(meta.selected_object_refs.into_iter().collect());
-            session_folder.save_session(&candidate_session).map_err(|err| {
+            candidate.set_selection_log(meta.selection_log);
+            session_folder.save_session(&candidate).map_err(|err| {
                 ErrorData::internal_error(
                     format!("failed to persist session: {err}"),
-                    Some(serde_json::json!({ "walkthrough_id": walkthrough_id, "base_rev": base_rev })),
+                    Some(serde_json::json!({ "walkthrough_id": walkthrough_id.as_str() })),
                 )
             })?;
+        }
+        state.session = candidate;
 
-            state.session = candidate_session;
-            state.walkthrough_delta_history.insert(parsed, history);
+        let response = Json(WalkthroughCreateResponse {
+            walkthrough: WalkthroughSummary {
+                walkthrough_id: walkthrough_id.as_str().to_owned(),
+                title,
+                rev: 0,
+                nodes: 0,
+                edges: 0,
+            },
+            active_walkthrough_id: state
+                .session
+                .active_walkthrough_id()
+                .map(|walkthrough_id| walkthrough_id.as_str().to_owned()),
+        });
+        drop(state);
+        self.notify_session_changed().await;
+        Ok(response)
+    }
 
-            let response = Json(ApplyOpsResponse {
-                new_rev,
-                applied: ops.len() as u64,
-                delta: DeltaSummary {
-                    added: delta.added.iter().cloned().collect(),
-                    removed: delta.removed.iter().cloned().collect(),
-                    updated: delta.updated.iter().cloned().collect(),
-                },
-            });
-            drop(state);
-            self.notify_ui_session_changed().await;
-            return Ok(response);
-        }
+    /// Add a step to a walkthrough at `base_rev` (from `walkthrough.stat`); `node_id` defaults to
+    /// the next free `wn:N`. Link steps afterwards with `walkthrough.edge.add`.
+    #[tool(name = "walkthrough.node.add")]
+    async fn walkthrough_node_add(
+        &self,
+        params: Parameters<WalkthroughNodeAddParams>,
+    ) -> Result<Json<WalkthroughNodeAddResponse>, ErrorData> {
+        let WalkthroughNodeAddParams {
+            walkthrough_id,
+            base_rev,
+            title,
+            node_id,
+            body_md,
+            refs,
+            tags,
+            status,
+        } = params.0;
 
-        let walkthrough = state.session.walkthroughs_mut().get_mut(&parsed).ok_or_else(|| {
-            ErrorData::resource_not_found(
-                "walkthrough not found",
-                Some(serde_json::json!({ "walkthrough_id": walkthrough_id })),
-            )
-        })?;
+        let node_id = match node_id {
+            Some(node_id) => node_id,
+            None => {
+                let parsed = parse_walkthrough_id(&walkthrough_id)?;
+                let state = self.lock_state_synced().await?;
+                let walkthrough = state.session.walkthroughs().get(&parsed).ok_or_else(|| {
+                    ErrorData::resource_not_found(
+                        "walkthrough not found",
+                        Some(serde_json::json!({ "walkthrough_id": walkthrough_id })),
+                    )
+                })?;
+                idgen::walkthrough_node_id(walkthrough).as_str().to_owned()
+            }
+        };
 
-        let current_rev = walkthrough.rev();
-        if base_rev != current_rev {
-            let digest = digest_for_walkthrough(walkthrough);
-            return Err(ErrorData::invalid_request(
-                "conflict: stale base_rev",
-                Some(serde_json::json!({
-                    "base_rev": base_rev,
-                    "current_rev": current_rev,
-                    "snapshot_tool": "walkthrough.stat",
-                    "digest": {
-                        "rev": digest.rev,
-                        "counts": {
-                            "nodes": digest.counts.nodes,
-                            "edges": digest.counts.edges,
-                        },
-                    },
-                })),
-            ));
-        }
+        let op = McpWalkthroughOp::AddNode {
+            node_id: node_id.clone(),
+            title,
+            body_md,
+            refs,
+            tags,
+            status,
+        };
+        let ApplyOpsResponse { new_rev, delta, .. } =
+            self.commit_walkthrough_ops(&walkthrough_id, base_rev, &[op]).await?;
+        Ok(Json(WalkthroughNodeAddResponse { node_id, new_rev, delta }))
+    }
 
-        if ops.is_empty() {
-            return Ok(Json(ApplyOpsResponse {
-                new_rev: current_rev,
-                applied: 0,
-                delta: DeltaSummary { added: Vec::new(), removed: Vec::new(), updated: Vec::new() },
-            }));
+    /// Update a walkthrough step at `base_rev`; only the given fields change (`refs` and `tags`
+    /// are replaced as a whole, `null` clears `body_md`/`status`).
+    #[tool(name = "walkthrough.node.update")]
+    async fn walkthrough_node_update(
+        &self,
+        params: Parameters<WalkthroughNodeUpdateParams>,
+    ) -> Result<Json<ApplyOpsResponse>, ErrorData> {
+        let WalkthroughNodeUpdateParams {
+            walkthrough_id,
+            base_rev,
+            node_id,
+            title,
+            body_md,
+            refs,
+            tags,
+            status,
+        } = params.0;
+        let op = McpWalkthroughOp::UpdateNode { node_id, title, body_md, refs, tags, status };
+        Ok(Json(self.commit_walkthrough_ops(&walkthrough_id, base_rev, &[op]).await?))
+    }
+
+    /// Link two walkthrough steps at `base_rev` (`kind` defaults to `next`).
+    #[tool(name = "walkthrough.edge.add")]
+    async fn walkthrough_edge_add(
+        &self,
+        params: Parameters<WalkthroughEdgeAddParams>,
+    ) -> Result<Json<ApplyOpsResponse>, ErrorData> {
+        let WalkthroughEdgeAddParams {
+            walkthrough_id,
+            base_rev,
+            from_node_id,
+            to_node_id,
+            kind,
+            label,
+        } = params.0;
+        let op = McpWalkthroughOp::AddEdge {
+            from_node_id,
+            to_node_id,
+            kind: kind.unwrap_or_else(|| "next".to_owned()),
+            label,
+        };
+        Ok(Json(self.commit_walkthrough_ops(&walkthrough_id, base_rev, &[op]).await?))
+    }
+
+    /// Remove a walkthrough by id and retarget the active walkthrough when needed.
+    #[tool(name = "walkthrough.delete")]
+    async fn walkthrough_delete(
+        &self,
+        params: Parameters<WalkthroughDeleteParams>,
+    ) -> Result<Json<WalkthroughDeleteResponse>, ErrorData> {
+        let walkthrough_id = params.0.walkthrough_id;
+        let parsed = parse_walkthrough_id(&walkthrough_id)?;
+
+        let mut state = self.lock_state_synced().await?;
+        if !state.session.walkthroughs().contains_key(&parsed) {
+            return Err(ErrorData::resource_not_found(
+                "walkthrough not found",
+                Some(serde_json::json!({ "walkthrough_id": walkthrough_id })),
+            ));
         }
 
-        let delta = apply_walkthrough_ops(walkthrough, &parsed, &ops)?;
-        walkthrough.bump_rev();
-        let new_rev = walkthrough.rev();
+        let mut candidate = state.session.clone();
+        candidate.walkthroughs_mut().remove(&parsed);
+        if candidate.active_walkthrough_id().is_some_and(|active| active == &parsed) {
+            let next_active = candidate.walkthroughs().keys().next().cloned();
+            candidate.set_active_walkthrough_id(next_active);
+        }
 
-        let history = state.walkthrough_delta_history.entry(parsed).or_insert_with(VecDeque::new);
-        history.push_back(WalkthroughLastDelta {
-            from_rev: base_rev,
-            to_rev: new_rev,
-            delta: delta.clone(),
-        });
-        while history.len() > DELTA_HISTORY_LIMIT {
-            history.pop_front();
+        if let Some(session_folder) = &self.session_folder() {
+            let meta = session_folder.load_meta().map_err(|err| {
+                ErrorData::internal_error(
+                    format!("failed to load session meta: {err}"),
+                    Some(serde_json::json!({ "walkthrough_id": walkthrough_id })),
+                )
+            })?;
+            candidate.set_selection_log(meta.selection_log);
+            session_folder.save_session(&candidate).map_err(|err| {
+                ErrorData::internal_error(
+                    format!("failed to persist session: {err}"),
+                    Some(serde_json::json!({ "walkthrough_id": walkthrough_id })),
+                )
+            })?;
         }
+        state.session = candidate;
+        state.walkthrough_delta_history.remove(&parsed);
 
-        let response = Json(ApplyOpsResponse {
-            new_rev,
-            applied: ops.len() as u64,
-            delta: DeltaSummary {
-                added: delta.added.iter().cloned().collect(),
-                removed: delta.removed.iter().cloned().collect(),
-                updated: delta.updated.iter().cloned().collect(),
-            },
+        let response = Json(WalkthroughDeleteResponse {
+            deleted_walkthrough_id: parsed.as_str().to_owned(),
+            active_walkthrough_id: state
+                .session
+                .active_walkthrough_id()
+                .map(|walkthrough_id| walkthrough_id.as_str().to_owned()),
         });
         drop(state);
-        self.notify_ui_session_changed().await;
+        self.notify_session_changed().await;
         Ok(response)
     }
 
@@ -1330,14 +3086,7 @@ impl NereidMcp {
                     return None;
                 }
 
-                Some(XRefSummary {
-                    xref_id: xref_id.as_str().to_owned(),
-                    from: xref.from().to_string(),
-                    to: xref.to().to_string(),
-                    kind: xref.kind().to_owned(),
-                    label: xref.label().map(|label| label.to_owned()),
-                    status: xref.status().as_str().to_owned(),
-                })
+                Some(xref_summary(xref_id, xref))
             })
             .collect::<Vec<_>>();
         xrefs.sort_by(|a, b| a.xref_id.cmp(&b.xref_id));
@@ -1391,7 +3140,7 @@ impl NereidMcp {
     }
 
     /// Add a cross-diagram xref; use to persist discovered relationships from route/trace analysis
-    /// and walkthrough work.
+    /// and walkthrough work. Omit `xref_id` to mint the next `x:N` id.
     #[tool(name = "xref.add")]
     async fn xref_add(
         &self,
@@ -1399,19 +3148,23 @@ impl NereidMcp {
     ) -> Result<Json<XRefAddResponse>, ErrorData> {
         let XRefAddParams { xref_id, from, to, kind, label } = params.0;
 
-        let xref_id_parsed = parse_xref_id(&xref_id)?;
+        let xref_id_parsed = xref_id.as_deref().map(parse_xref_id).transpose()?;
         let from = parse_object_ref_from(&from)?;
         let to = parse_object_ref_to(&to)?;
 
         let mut state = self.lock_state_synced().await?;
-        if let Some(session_folder) = &self.session_folder {
+        if let Some(session_folder) = &self.session_folder() {
             let mut candidate = state.session.clone();
-            if candidate.xrefs().contains_key(&xref_id_parsed) {
-                return Err(ErrorData::invalid_params(
-                    "xref_id already exists",
-                    Some(serde_json::json!({ "xref_id": xref_id })),
-                ));
-            }
+            let xref_id_parsed = match xref_id_parsed {
+                Some(xref_id_parsed) if candidate.xrefs().contains_key(&xref_id_parsed) => {
+                    return Err(ErrorData::invalid_params(
+                        "xref_id already exists",
+                        Some(serde_json::json!({ "xref_id": xref_id })),
+                    ));
+                }
+                Some(xref_id_parsed) => xref_id_parsed,
+                None => idgen::next_xref_id(&mut candidate),
+            };
 
             let from_missing = object_ref_is_missing(&candidate, &from);
             let to_missing = object_ref_is_missing(&candidate, &to);
@@ -1426,7 +3179,7 @@ impl NereidMcp {
                     Some(serde_json::json!({ "xref_id": xref_id })),
                 )
             })?;
-            candidate.set_selected_object_refs(meta.selected_object_refs.into_iter().collect());
+            candidate.set_selection_log(meta.selection_log);
 
             session_folder.save_session(&candidate).map_err(|err| {
                 ErrorData::internal_error(
@@ -1441,16 +3194,20 @@ impl NereidMcp {
                 status: status.as_str().to_owned(),
             });
             drop(state);
-            self.notify_ui_session_changed().await;
+            self.notify_session_changed().await;
             return Ok(response);
         }
 
-        if state.session.xrefs().contains_key(&xref_id_parsed) {
-            return Err(ErrorData::invalid_params(
-                "xref_id already exists",
-                Some(serde_json::json!({ "xref_id": xref_id })),
-            ));
-        }
+        let xref_id_parsed = match xref_id_parsed {
+            Some(xref_id_parsed) if state.session.xrefs().contains_key(&xref_id_parsed) => {
+                return Err(ErrorData::invalid_params(
+                    "xref_id already exists",
+                    Some(serde_json::json!({ "xref_id": xref_id })),
+                ));
+            }
+            Some(xref_id_parsed) => xref_id_parsed,
+            None => idgen::next_xref_id(&mut state.session),
+        };
 
         let from_missing = object_ref_is_missing(&state.session, &from);
         let to_missing = object_ref_is_missing(&state.session, &to);
@@ -1465,7 +3222,7 @@ impl NereidMcp {
             status: status.as_str().to_owned(),
         });
         drop(state);
-        self.notify_ui_session_changed().await;
+        self.notify_session_changed().await;
         Ok(response)
     }
 
@@ -1479,7 +3236,7 @@ impl NereidMcp {
         let xref_id_parsed = parse_xref_id(&xref_id)?;
 
         let mut state = self.lock_state_synced().await?;
-        if let Some(session_folder) = &self.session_folder {
+        if let Some(session_folder) = &self.session_folder() {
             let mut candidate = state.session.clone();
             let removed = candidate.xrefs_mut().remove(&xref_id_parsed).is_some();
             if !removed {
@@ -1495,7 +3252,7 @@ impl NereidMcp {
                     Some(serde_json::json!({ "xref_id": xref_id })),
                 )
             })?;
-            candidate.set_selected_object_refs(meta.selected_object_refs.into_iter().collect());
+            candidate.set_selection_log(meta.selection_log);
             session_folder.save_session(&candidate).map_err(|err| {
                 ErrorData::internal_error(
                     format!("failed to persist session: {err}"),
@@ -1505,7 +3262,7 @@ impl NereidMcp {
             state.session = candidate;
             let response = Json(XRefRemoveResponse { removed: true });
             drop(state);
-            self.notify_ui_session_changed().await;
+            self.notify_session_changed().await;
             return Ok(response);
         }
 
@@ -1519,7 +3276,7 @@ impl NereidMcp {
 
         let response = Json(XRefRemoveResponse { removed: true });
         drop(state);
-        self.notify_ui_session_changed().await;
+        self.notify_session_changed().await;
         Ok(response)
     }
 
@@ -1569,143 +3326,150 @@ impl NereidMcp {
                 )
             })?;
 
-            let segments = parsed.category().segments();
-            let object_id = parsed.object_id();
-
-            let object = match (segments, diagram.ast()) {
-                ([left, right], DiagramAst::Sequence(ast))
-                    if left == "seq" && right == "participant" =>
-                {
-                    let participant = ast.participants().get(object_id).ok_or_else(|| {
-                        ErrorData::resource_not_found(
-                            "seq participant not found",
-                            Some(serde_json::json!({ "object_ref": object_ref.as_str() })),
-                        )
-                    })?;
+            let object = mcp_object_for_ref(diagram, &parsed)?;
+            objects.push(ObjectGetItem { object_ref, object });
+        }
 
-                    McpObject::SeqParticipant {
-                        mermaid_name: participant.mermaid_name().to_owned(),
-                        role: participant.role().map(|r| r.to_owned()),
-                    }
-                }
-                ([left, right], DiagramAst::Sequence(ast)) if left == "seq" && right == "block" => {
-                    let block = ast.find_block(object_id).ok_or_else(|| {
-                        ErrorData::resource_not_found(
-                            "seq block not found",
-                            Some(serde_json::json!({ "object_ref": object_ref.as_str() })),
-                        )
-                    })?;
+        drop(state);
+        let context = self.read_context(session_active_diagram_id).await;
 
-                    McpObject::SeqBlock {
-                        kind: map_seq_block_kind_to_mcp(block.kind()),
-                        header: block.header().map(|h| h.to_owned()),
-                        section_ids: block
-                            .sections()
-                            .iter()
-                            .map(|section| section.section_id().to_string())
-                            .collect(),
-                        child_block_ids: block
-                            .blocks()
-                            .iter()
-                            .map(|child| child.block_id().to_string())
-                            .collect(),
-                    }
-                }
-                ([left, right], DiagramAst::Sequence(ast))
-                    if left == "seq" && right == "section" =>
-                {
-                    let section = ast.find_section(object_id).ok_or_else(|| {
-                        ErrorData::resource_not_found(
-                            "seq section not found",
-                            Some(serde_json::json!({ "object_ref": object_ref.as_str() })),
-                        )
-                    })?;
+        Ok(Json(ObjectGetResponse { objects, context }))
+    }
 
-                    McpObject::SeqSection {
-                        kind: map_seq_section_kind_to_mcp(section.kind()),
-                        header: section.header().map(|h| h.to_owned()),
-                        message_ids: section
-                            .message_ids()
-                            .iter()
-                            .map(|message_id| message_id.to_string())
-                            .collect(),
-                    }
-                }
-                ([left, right], DiagramAst::Sequence(ast))
-                    if left == "seq" && right == "message" =>
-                {
-                    let message =
-                        ast.messages().iter().find(|m| m.message_id() == object_id).ok_or_else(
-                            || {
-                                ErrorData::resource_not_found(
-                                    "seq message not found",
-                                    Some(serde_json::json!({ "object_ref": object_ref.as_str() })),
-                                )
-                            },
-                        )?;
-
-                    McpObject::SeqMessage {
-                        from_participant_id: message.from_participant_id().to_string(),
-                        to_participant_id: message.to_participant_id().to_string(),
-                        kind: map_message_kind_to_mcp(message.kind()),
-                        arrow: message.raw_arrow().map(ToOwned::to_owned),
-                        text: message.text().to_owned(),
-                        order_key: message.order_key(),
-                    }
-                }
-                ([left, right], DiagramAst::Flowchart(ast))
-                    if left == "flow" && right == "node" =>
-                {
-                    let node = ast.nodes().get(object_id).ok_or_else(|| {
-                        ErrorData::resource_not_found(
-                            "flow node not found",
-                            Some(serde_json::json!({ "object_ref": object_ref.as_str() })),
-                        )
-                    })?;
+    /// Full detail for one object ref: fields, rendered cell spans, xrefs in/out, enclosing
+    /// blocks/subgraphs and walkthrough steps; use before proposing edits to it.
+    #[tool(name = "object.get")]
+    async fn object_get(
+        &self,
+        params: Parameters<ObjectDetailParams>,
+    ) -> Result<Json<ObjectDetailResponse>, ErrorData> {
+        let object_ref = parse_object_ref(&params.0.object_ref)?;
+        let state = self.lock_state_synced().await?;
+        let session_active_diagram_id =
+            state.session.active_diagram_id().map(|diagram_id| diagram_id.as_str().to_owned());
+        let mut detail = object_detail(&state.session, &object_ref)?;
+        drop(state);
+        detail.context = self.read_context(session_active_diagram_id).await;
 
-                    McpObject::FlowNode {
-                        label: node.label().to_owned(),
-                        shape: node.shape().to_owned(),
-                        mermaid_id: node.mermaid_id().map(|s| s.to_owned()),
-                    }
-                }
-                ([left, right], DiagramAst::Flowchart(ast))
-                    if left == "flow" && right == "edge" =>
-                {
-                    let edge = ast.edges().get(object_id).ok_or_else(|| {
-                        ErrorData::resource_not_found(
-                            "flow edge not found",
-                            Some(serde_json::json!({ "object_ref": object_ref.as_str() })),
-                        )
-                    })?;
+        Ok(Json(detail))
+    }
 
-                    McpObject::FlowEdge {
-                        from_node_id: edge.from_node_id().to_string(),
-                        to_node_id: edge.to_node_id().to_string(),
-                        label: edge.label().map(|s| s.to_owned()),
-                        connector: edge.connector().map(|s| s.to_owned()),
-                        style: edge.style().map(|s| s.to_owned()),
-                    }
-                }
-                _ => {
-                    return Err(ErrorData::invalid_params(
-                        "unsupported category for diagram kind",
-                        Some(serde_json::json!({
-                            "object_ref": object_ref.as_str(),
-                            "diagram_kind": diagram_kind_label(diagram.kind()),
-                            "category": segments.to_vec(),
-                        })),
-                    ));
-                }
-            };
+    /// Same facts as `object.get` as a short Markdown summary; use to quote an object to a human.
+    #[tool(name = "object.describe")]
+    async fn object_describe(
+        &self,
+        params: Parameters<ObjectDetailParams>,
+    ) -> Result<Json<ObjectDescribeResponse>, ErrorData> {
+        let object_ref = parse_object_ref(&params.0.object_ref)?;
+        let state = self.lock_state_synced().await?;
+        let session_active_diagram_id =
+            state.session.active_diagram_id().map(|diagram_id| diagram_id.as_str().to_owned());
+        let detail = object_detail(&state.session, &object_ref)?;
+        drop(state);
+        let context = self.read_context(session_active_diagram_id).await;
 
-            objects.push(ObjectGetItem { object_ref, object });
+        Ok(Json(ObjectDescribeResponse {
+            object_ref: detail.object_ref.clone(),
+            description: describe_object(&detail),
+            context,
+        }))
+    }
+
+    /// List a diagram's objects with labels, notes, derived tags, anchors and link counts,
+    /// filtered by category/tags/label and paged; use to enumerate contents without parsing
+    /// Mermaid.
+    #[tool(name = "object.list")]
+    async fn object_list(
+        &self,
+        params: Parameters<ObjectListParams>,
+    ) -> Result<Json<ObjectListResponse>, ErrorData> {
+        const DEFAULT_LIMIT: u64 = 100;
+
+        let ObjectListParams { diagram_id, category, tags, label_contains, offset, limit } =
+            params.0;
+        let category = category.filter(|category| !category.is_empty());
+        let tags = tags.unwrap_or_default();
+        let label_contains = label_contains
+            .filter(|label_contains| !label_contains.is_empty())
+            .map(|label_contains| label_contains.to_lowercase());
+        let offset = offset.unwrap_or(0);
+        let limit = limit.unwrap_or(DEFAULT_LIMIT);
+
+        let state = self.lock_state_synced().await?;
+        let diagram_id = resolve_diagram_id(&state.session, diagram_id.as_deref())?;
+        let diagram = state
+            .session
+            .diagrams()
+            .get(&diagram_id)
+            .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?;
+
+        let matching = object_list_items(&state.session, diagram)
+            .into_iter()
+            .filter(|item| match category.as_deref() {
+                Some(category) if category.contains('/') => item.category == category,
+                Some(category) => item.category.split('/').next() == Some(category),
+                None => true,
+            })
+            .filter(|item| tags.iter().all(|tag| item.tags.contains(tag)))
+            .filter(|item| {
+                label_contains
+                    .as_deref()
+                    .map_or(true, |needle| item.label.to_lowercase().contains(needle))
+            })
+            .collect::<Vec<_>>();
+
+        let total = matching.len() as u64;
+        let objects = matching
+            .into_iter()
+            .skip(offset.min(usize::MAX as u64) as usize)
+            .take(limit.min(usize::MAX as u64) as usize)
+            .collect::<Vec<_>>();
+        let end = offset.saturating_add(objects.len() as u64);
+        let next_offset = (end < total).then_some(end);
+
+        Ok(Json(ObjectListResponse {
+            diagram_id: diagram_id.to_string(),
+            rev: diagram.rev(),
+            total,
+            objects,
+            next_offset,
+        }))
+    }
+
+    /// Find objects carrying a tag across the session (or one diagram); matches user-assigned
+    /// tags set via `*_set_*_annotations` ops as well as derived tags.
+    #[tool(name = "object.find_by_tag")]
+    async fn object_find_by_tag(
+        &self,
+        params: Parameters<ObjectFindByTagParams>,
+    ) -> Result<Json<ObjectFindByTagResponse>, ErrorData> {
+        let ObjectFindByTagParams { tag, diagram_id } = params.0;
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err(ErrorData::invalid_params("tag must not be empty", None));
         }
 
-        drop(state);
-        let context = self.read_context(session_active_diagram_id).await;
+        let state = self.lock_state_synced().await?;
+        let diagrams = match diagram_id.as_deref() {
+            Some(diagram_id) => {
+                let diagram_id = resolve_diagram_id(&state.session, Some(diagram_id))?;
+                let diagram = state
+                    .session
+                    .diagrams()
+                    .get(&diagram_id)
+                    .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?;
+                vec![diagram]
+            }
+            None => state.session.diagrams().values().filter(|d| !d.is_archived()).collect(),
+        };
 
-        Ok(Json(ObjectGetResponse { objects, context }))
+        let objects = diagrams
+            .into_iter()
+            .flat_map(|diagram| object_list_items(&state.session, diagram))
+            .filter(|item| item.tags.iter().any(|candidate| candidate == tag))
+            .collect::<Vec<_>>();
+
+        Ok(Json(ObjectFindByTagResponse { objects }))
     }
 
     /// Trace sequence message order before/after a message id (returns refs); use for timeline
@@ -1909,9 +3673,143 @@ impl NereidMcp {
             )
         })?;
 
-        let DiagramAst::Sequence(ast) = diagram.ast() else {
+        let DiagramAst::Sequence(ast) = diagram.ast() else {
+            return Err(ErrorData::invalid_params(
+                "diagram is not a sequence diagram",
+                Some(serde_json::json!({
+                    "diagram_id": diagram_id.as_str(),
+                    "diagram_kind": diagram_kind_label(diagram.kind()),
+                })),
+            ));
+        };
+
+        let mut messages = ast
+            .messages()
+            .iter()
+            .filter(|msg| {
+                from_participant_id.as_ref().map_or(true, |from| msg.sender() == Some(from))
+            })
+            .filter(|msg| to_participant_id.as_ref().map_or(true, |to| msg.receiver() == Some(to)))
+            .collect::<Vec<_>>();
+        messages.sort_by(|a, b| crate::model::SequenceMessage::cmp_in_order(a, b));
+
+        let messages = messages
+            .into_iter()
+            .map(|msg| format!("d:{}/seq/message/{}", diagram_id.as_str(), msg.message_id()))
+            .collect::<Vec<_>>();
+
+        Ok(Json(SeqMessagesResponse { messages }))
+    }
+
+    /// List flow nodes reachable from a node id, or from the entry points when omitted (returns
+    /// refs); pair with `flow.paths` and `diagram.get_slice` for local traversal.
+    #[tool(name = "flow.reachable")]
+    async fn flow_reachable(
+        &self,
+        params: Parameters<FlowReachableParams>,
+    ) -> Result<Json<FlowReachableResponse>, ErrorData> {
+        let FlowReachableParams { diagram_id, from_node_id, direction } = params.0;
+
+        let direction_label = direction.as_deref().unwrap_or("out");
+        let direction = match direction_label {
+            "out" => crate::query::flow::ReachDirection::Out,
+            "in" => crate::query::flow::ReachDirection::In,
+            "both" => crate::query::flow::ReachDirection::Both,
+            other => {
+                return Err(ErrorData::invalid_params(
+                    "invalid direction (expected out|in|both)",
+                    Some(serde_json::json!({ "direction": other })),
+                ));
+            }
+        };
+
+        let from_node_id_parsed = from_node_id
+            .as_deref()
+            .map(|from_node_id| {
+                ObjectId::new(from_node_id.to_owned()).map_err(|err| {
+                    ErrorData::invalid_params(
+                        format!("invalid from_node_id: {err}"),
+                        Some(serde_json::json!({ "from_node_id": from_node_id })),
+                    )
+                })
+            })
+            .transpose()?;
+
+        let state = self.lock_state_synced().await?;
+        let diagram_id = resolve_diagram_id(&state.session, diagram_id.as_deref())?;
+        let diagram = state.session.diagrams().get(&diagram_id).ok_or_else(|| {
+            ErrorData::resource_not_found(
+                "diagram not found",
+                Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+            )
+        })?;
+
+        let DiagramAst::Flowchart(ast) = diagram.ast() else {
+            return Err(ErrorData::invalid_params(
+                "diagram is not a flowchart",
+                Some(serde_json::json!({
+                    "diagram_id": diagram_id.as_str(),
+                    "diagram_kind": diagram_kind_label(diagram.kind()),
+                })),
+            ));
+        };
+
+        let roots = match from_node_id_parsed {
+            Some(from_node_id) if !ast.nodes().contains_key(&from_node_id) => {
+                return Ok(Json(FlowReachableResponse { nodes: Vec::new() }));
+            }
+            Some(from_node_id) => vec![from_node_id],
+            None => crate::query::flow::entry_points(ast),
+        };
+
+        let reachable = roots
+            .iter()
+            .flat_map(|root| crate::query::flow::reachable_with_direction(ast, root, direction))
+            .collect::<BTreeSet<_>>();
+
+        let mut nodes = reachable
+            .into_iter()
+            .map(|node_id| format!("d:{}/flow/node/{}", diagram_id.as_str(), node_id))
+            .collect::<Vec<_>>();
+        nodes.sort();
+
+        Ok(Json(FlowReachableResponse { nodes }))
+    }
+
+    /// Find the cheapest path between two flow nodes using edge weights (explicit weight, numeric
+    /// label, else 1); returns refs with cumulative costs. Set weights with `flow_set_edge_weight`.
+    #[tool(name = "flow.shortest_path")]
+    async fn flow_shortest_path(
+        &self,
+        params: Parameters<FlowShortestPathParams>,
+    ) -> Result<Json<FlowShortestPathResponse>, ErrorData> {
+        let FlowShortestPathParams { diagram_id, from_node_id, to_node_id } = params.0;
+
+        let from_node_id_parsed = ObjectId::new(from_node_id.clone()).map_err(|err| {
+            ErrorData::invalid_params(
+                format!("invalid from_node_id: {err}"),
+                Some(serde_json::json!({ "from_node_id": from_node_id })),
+            )
+        })?;
+        let to_node_id_parsed = ObjectId::new(to_node_id.clone()).map_err(|err| {
+            ErrorData::invalid_params(
+                format!("invalid to_node_id: {err}"),
+                Some(serde_json::json!({ "to_node_id": to_node_id })),
+            )
+        })?;
+
+        let state = self.lock_state_synced().await?;
+        let diagram_id = resolve_diagram_id(&state.session, diagram_id.as_deref())?;
+        let diagram = state.session.diagrams().get(&diagram_id).ok_or_else(|| {
+            ErrorData::resource_not_found(
+                "diagram not found",
+                Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+            )
+        })?;
+
+        let DiagramAst::Flowchart(ast) = diagram.ast() else {
             return Err(ErrorData::invalid_params(
-                "diagram is not a sequence diagram",
+                "diagram is not a flowchart",
                 Some(serde_json::json!({
                     "diagram_id": diagram_id.as_str(),
                     "diagram_kind": diagram_kind_label(diagram.kind()),
@@ -1919,57 +3817,147 @@ impl NereidMcp {
             ));
         };
 
-        let mut messages = ast
-            .messages()
+        for (field, node_id) in
+            [("from_node_id", &from_node_id_parsed), ("to_node_id", &to_node_id_parsed)]
+        {
+            if !ast.nodes().contains_key(node_id) {
+                return Err(ErrorData::resource_not_found(
+                    "node not found",
+                    Some(serde_json::json!({
+                        "diagram_id": diagram_id.as_str(),
+                        field: node_id.as_str(),
+                    })),
+                ));
+            }
+        }
+
+        let Some(path) = crate::query::flow::weighted_shortest_path(
+            ast,
+            &from_node_id_parsed,
+            &to_node_id_parsed,
+        ) else {
+            return Ok(Json(FlowShortestPathResponse {
+                cost: None,
+                steps: Vec::new(),
+                summary: String::new(),
+            }));
+        };
+
+        let steps = path
+            .steps
             .iter()
-            .filter(|msg| {
-                from_participant_id.as_ref().map_or(true, |from| msg.from_participant_id() == from)
-            })
-            .filter(|msg| {
-                to_participant_id.as_ref().map_or(true, |to| msg.to_participant_id() == to)
+            .map(|step| FlowWeightedStep {
+                node_ref: format!("d:{}/flow/node/{}", diagram_id.as_str(), step.node_id),
+                edge_ref: step
+                    .via_edge_id
+                    .as_ref()
+                    .map(|edge_id| format!("d:{}/flow/edge/{}", diagram_id.as_str(), edge_id)),
+                cumulative_cost: step.cumulative_cost,
             })
-            .collect::<Vec<_>>();
-        messages.sort_by(|a, b| crate::model::SequenceMessage::cmp_in_order(a, b));
-
-        let messages = messages
-            .into_iter()
-            .map(|msg| format!("d:{}/seq/message/{}", diagram_id.as_str(), msg.message_id()))
-            .collect::<Vec<_>>();
+            .collect();
 
-        Ok(Json(SeqMessagesResponse { messages }))
+        Ok(Json(FlowShortestPathResponse {
+            cost: Some(path.cost),
+            steps,
+            summary: path.summary(ast, " -> "),
+        }))
     }
 
-    /// List flow nodes reachable from a node id (returns refs); pair with `flow.paths` and
-    /// `diagram.get_slice` for local traversal.
-    #[tool(name = "flow.reachable")]
-    async fn flow_reachable(
+    /// Compute the critical (longest-duration) path of a sequence or acyclic flowchart diagram.
+    /// Sequence messages use their `duration` (default 1) in `order_key` order; flowchart edges
+    /// use their weight.
+    #[tool(name = "diagram.critical_path")]
+    async fn diagram_critical_path(
         &self,
-        params: Parameters<FlowReachableParams>,
-    ) -> Result<Json<FlowReachableResponse>, ErrorData> {
-        let FlowReachableParams { diagram_id, from_node_id, direction } = params.0;
+        params: Parameters<DiagramCriticalPathParams>,
+    ) -> Result<Json<DiagramCriticalPathResponse>, ErrorData> {
+        let DiagramCriticalPathParams { diagram_id } = params.0;
 
-        let direction_label = direction.as_deref().unwrap_or("out");
-        let direction = match direction_label {
-            "out" => crate::query::flow::ReachDirection::Out,
-            "in" => crate::query::flow::ReachDirection::In,
-            "both" => crate::query::flow::ReachDirection::Both,
-            other => {
+        let state = self.lock_state_synced().await?;
+        let diagram_id = resolve_diagram_id(&state.session, diagram_id.as_deref())?;
+        let diagram = state.session.diagrams().get(&diagram_id).ok_or_else(|| {
+            ErrorData::resource_not_found(
+                "diagram not found",
+                Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+            )
+        })?;
+        let kind = diagram_kind_label(diagram.kind()).to_owned();
+
+        let (total, steps, summary) = match diagram.ast() {
+            DiagramAst::Sequence(ast) => {
+                let path = crate::query::sequence::critical_path(ast);
+                let steps = path
+                    .iter()
+                    .map(|step| CriticalPathStep {
+                        object_ref: format!(
+                            "d:{}/seq/message/{}",
+                            diagram_id.as_str(),
+                            step.message.message_id()
+                        ),
+                        duration: step.duration,
+                        finish: step.finish,
+                    })
+                    .collect();
+                (path.last().map(|step| step.finish), steps, sequence_critical_path_summary(&path))
+            }
+            DiagramAst::Flowchart(ast) => {
+                if ast.nodes().is_empty() {
+                    (None, Vec::new(), String::new())
+                } else {
+                    let path = crate::query::flow::critical_path(ast).ok_or_else(|| {
+                        ErrorData::invalid_params(
+                            "flowchart has a cycle; critical path needs a DAG",
+                            Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+                        )
+                    })?;
+                    let mut steps = Vec::new();
+                    for step in &path.steps {
+                        if let Some(edge_id) = &step.via_edge_id {
+                            let weight =
+                                ast.edges().get(edge_id).map_or(1, |e| e.effective_weight());
+                            steps.push(CriticalPathStep {
+                                object_ref: format!(
+                                    "d:{}/flow/edge/{}",
+                                    diagram_id.as_str(),
+                                    edge_id
+                                ),
+                                duration: weight,
+                                finish: step.cumulative_cost,
+                            });
+                        }
+                        steps.push(CriticalPathStep {
+                            object_ref: format!(
+                                "d:{}/flow/node/{}",
+                                diagram_id.as_str(),
+                                step.node_id
+                            ),
+                            duration: 0,
+                            finish: step.cumulative_cost,
+                        });
+                    }
+                    (Some(path.cost), steps, path.summary(ast, " -> "))
+                }
+            }
+            DiagramAst::State(_) | DiagramAst::Class(_) => {
                 return Err(ErrorData::invalid_params(
-                    "invalid direction (expected out|in|both)",
-                    Some(serde_json::json!({ "direction": other })),
+                    "critical path needs a flowchart or sequence diagram",
+                    Some(serde_json::json!({ "diagram_id": diagram_id.as_str(), "kind": kind })),
                 ));
             }
         };
 
-        let from_node_id_parsed = ObjectId::new(from_node_id.clone()).map_err(|err| {
-            ErrorData::invalid_params(
-                format!("invalid from_node_id: {err}"),
-                Some(serde_json::json!({ "from_node_id": from_node_id })),
-            )
-        })?;
+        Ok(Json(DiagramCriticalPathResponse { kind, total, steps, summary }))
+    }
 
+    /// Summarize a diagram's structure as text plus refs (counts, entry points, main paths,
+    /// cycles, dangling xrefs); computed without sampling, so use it as a cheap first orientation.
+    #[tool(name = "diagram.summarize")]
+    async fn diagram_summarize(
+        &self,
+        params: Parameters<DiagramTargetParams>,
+    ) -> Result<Json<DiagramSummarizeResponse>, ErrorData> {
         let state = self.lock_state_synced().await?;
-        let diagram_id = resolve_diagram_id(&state.session, diagram_id.as_deref())?;
+        let diagram_id = resolve_diagram_id(&state.session, params.0.diagram_id.as_deref())?;
         let diagram = state.session.diagrams().get(&diagram_id).ok_or_else(|| {
             ErrorData::resource_not_found(
                 "diagram not found",
@@ -1977,30 +3965,7 @@ impl NereidMcp {
             )
         })?;
 
-        let DiagramAst::Flowchart(ast) = diagram.ast() else {
-            return Err(ErrorData::invalid_params(
-                "diagram is not a flowchart",
-                Some(serde_json::json!({
-                    "diagram_id": diagram_id.as_str(),
-                    "diagram_kind": diagram_kind_label(diagram.kind()),
-                })),
-            ));
-        };
-
-        if !ast.nodes().contains_key(&from_node_id_parsed) {
-            return Ok(Json(FlowReachableResponse { nodes: Vec::new() }));
-        }
-
-        let reachable =
-            crate::query::flow::reachable_with_direction(ast, &from_node_id_parsed, direction);
-
-        let mut nodes = reachable
-            .into_iter()
-            .map(|node_id| format!("d:{}/flow/node/{}", diagram_id.as_str(), node_id))
-            .collect::<Vec<_>>();
-        nodes.sort();
-
-        Ok(Json(FlowReachableResponse { nodes }))
+        Ok(Json(diagram_summary(&state.session, diagram)))
     }
 
     /// Find bounded paths between two flow nodes (returns ref paths); use after
@@ -2098,14 +4063,251 @@ impl NereidMcp {
         Ok(Json(FlowPathsResponse { paths }))
     }
 
+    /// Add every node and edge on the shortest path (or up to `limit` paths) between two flow
+    /// nodes to the selection, so the human sees the end-to-end route.
+    #[tool(name = "flow.highlight_path")]
+    async fn flow_highlight_path(
+        &self,
+        params: Parameters<FlowHighlightPathParams>,
+    ) -> Result<Json<FlowHighlightPathResponse>, ErrorData> {
+        let FlowHighlightPathParams { diagram_id, from_node_id, to_node_id, limit, max_extra_hops } =
+            params.0;
+
+        let limit_u64 = limit.unwrap_or(1);
+        let limit = usize::try_from(limit_u64).map_err(|_| {
+            ErrorData::invalid_params(
+                "limit is too large",
+                Some(serde_json::json!({ "limit": limit_u64 })),
+            )
+        })?;
+        let max_extra_hops_u64 = max_extra_hops.unwrap_or(0);
+        let max_extra_hops = usize::try_from(max_extra_hops_u64).map_err(|_| {
+            ErrorData::invalid_params(
+                "max_extra_hops is too large",
+                Some(serde_json::json!({ "max_extra_hops": max_extra_hops_u64 })),
+            )
+        })?;
+        let from_node_id = parse_object_id(&from_node_id)?;
+        let to_node_id = parse_object_id(&to_node_id)?;
+
+        let mut state = self.lock_state_synced().await?;
+        let diagram_id = resolve_diagram_id(&state.session, diagram_id.as_deref())?;
+        let diagram = state.session.diagrams().get(&diagram_id).ok_or_else(|| {
+            ErrorData::resource_not_found(
+                "diagram not found",
+                Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+            )
+        })?;
+        let DiagramAst::Flowchart(ast) = diagram.ast() else {
+            return Err(ErrorData::invalid_params(
+                "diagram is not a flowchart",
+                Some(serde_json::json!({
+                    "diagram_id": diagram_id.as_str(),
+                    "diagram_kind": diagram_kind_label(diagram.kind()),
+                })),
+            ));
+        };
+        for node_id in [&from_node_id, &to_node_id] {
+            if !ast.nodes().contains_key(node_id) {
+                return Err(ErrorData::resource_not_found(
+                    "node not found",
+                    Some(serde_json::json!({
+                        "diagram_id": diagram_id.as_str(),
+                        "node_id": node_id.as_str(),
+                    })),
+                ));
+            }
+        }
+
+        let route =
+            crate::query::flow::route(ast, &from_node_id, &to_node_id, limit, max_extra_hops);
+        let node_category = CategoryPath::new(vec!["flow".into(), "node".into()])
+            .expect("valid flow node category");
+        let edge_category = CategoryPath::new(vec!["flow".into(), "edge".into()])
+            .expect("valid flow edge category");
+        let refs = route
+            .node_ids
+            .iter()
+            .map(|node_id| {
+                ObjectRef::new(diagram_id.clone(), node_category.clone(), node_id.clone())
+            })
+            .chain(route.edge_ids.iter().map(|edge_id| {
+                ObjectRef::new(diagram_id.clone(), edge_category.clone(), edge_id.clone())
+            }))
+            .collect::<Vec<_>>();
+        let paths = route
+            .paths
+            .iter()
+            .map(|path| {
+                path.iter()
+                    .map(|node_id| format!("d:{}/flow/node/{}", diagram_id.as_str(), node_id))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        if !refs.is_empty() {
+            if let Some(session_folder) = &self.session_folder() {
+                let mut candidate = state.session.clone();
+                let meta = session_folder.load_meta().map_err(|err| {
+                    ErrorData::internal_error(format!("failed to load session meta: {err}"), None)
+                })?;
+                candidate.set_selection_log(meta.selection_log);
+                retain_existing_selected_object_refs(&mut candidate);
+                candidate.selected_object_refs_mut().extend(refs.iter().cloned());
+                session_folder.save_selected_object_refs(&mut candidate).map_err(|err| {
+                    ErrorData::internal_error(
+                        format!("failed to persist selected object refs: {err}"),
+                        None,
+                    )
+                })?;
+                state.session = candidate;
+            } else {
+                state.session.selected_object_refs_mut().extend(refs.iter().cloned());
+            }
+        }
+
+        let response = Json(FlowHighlightPathResponse {
+            paths,
+            object_refs: refs.iter().map(ToString::to_string).collect(),
+            selected_count: state.session.selected_object_refs().len() as u64,
+        });
+        drop(state);
+        if !refs.is_empty() {
+            self.notify_session_changed().await;
+        }
+        Ok(response)
+    }
+
     /// Detect flowchart cycles (returns node ref cycles); use for risk checks before edits.
     #[tool(name = "flow.cycles")]
     async fn flow_cycles(
         &self,
-        params: Parameters<DiagramTargetParams>,
-    ) -> Result<Json<FlowCyclesResponse>, ErrorData> {
+        params: Parameters<DiagramTargetParams>,
+    ) -> Result<Json<FlowCyclesResponse>, ErrorData> {
+        let state = self.lock_state_synced().await?;
+        let diagram_id = resolve_diagram_id(&state.session, params.0.diagram_id.as_deref())?;
+        let diagram = state.session.diagrams().get(&diagram_id).ok_or_else(|| {
+            ErrorData::resource_not_found(
+                "diagram not found",
+                Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+            )
+        })?;
+
+        let DiagramAst::Flowchart(ast) = diagram.ast() else {
+            return Err(ErrorData::invalid_params(
+                "diagram is not a flowchart",
+                Some(serde_json::json!({
+                    "diagram_id": diagram_id.as_str(),
+                    "diagram_kind": diagram_kind_label(diagram.kind()),
+                })),
+            ));
+        };
+
+        let cycles = crate::query::flow::cycles(ast)
+            .into_iter()
+            .map(|cycle| {
+                cycle
+                    .into_iter()
+                    .map(|node_id| format!("d:{}/flow/node/{}", diagram_id.as_str(), node_id))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Json(FlowCyclesResponse { cycles }))
+    }
+
+    /// List the flowchart entry points used as default traversal roots (returns refs); `marked` is
+    /// false when no node is marked and source nodes are inferred instead.
+    #[tool(name = "flow.entry_points")]
+    async fn flow_entry_points(
+        &self,
+        params: Parameters<DiagramTargetParams>,
+    ) -> Result<Json<FlowEntryPointsResponse>, ErrorData> {
+        let state = self.lock_state_synced().await?;
+        let diagram_id = resolve_diagram_id(&state.session, params.0.diagram_id.as_deref())?;
+        let diagram = state.session.diagrams().get(&diagram_id).ok_or_else(|| {
+            ErrorData::resource_not_found(
+                "diagram not found",
+                Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+            )
+        })?;
+
+        let DiagramAst::Flowchart(ast) = diagram.ast() else {
+            return Err(ErrorData::invalid_params(
+                "diagram is not a flowchart",
+                Some(serde_json::json!({
+                    "diagram_id": diagram_id.as_str(),
+                    "diagram_kind": diagram_kind_label(diagram.kind()),
+                })),
+            ));
+        };
+
+        let marked = ast.entry_nodes().iter().any(|node_id| ast.nodes().contains_key(node_id));
+        let nodes = crate::query::flow::entry_points(ast)
+            .into_iter()
+            .map(|node_id| format!("d:{}/flow/node/{}", diagram_id.as_str(), node_id))
+            .collect::<Vec<_>>();
+
+        Ok(Json(FlowEntryPointsResponse { nodes, marked }))
+    }
+
+    /// List terminal flowchart nodes (returns refs); combine with `flow.unreachable` to identify
+    /// dead routes.
+    #[tool(name = "flow.dead_ends")]
+    async fn flow_dead_ends(
+        &self,
+        params: Parameters<DiagramTargetParams>,
+    ) -> Result<Json<FlowDeadEndsResponse>, ErrorData> {
+        let state = self.lock_state_synced().await?;
+        let diagram_id = resolve_diagram_id(&state.session, params.0.diagram_id.as_deref())?;
+        let diagram = state.session.diagrams().get(&diagram_id).ok_or_else(|| {
+            ErrorData::resource_not_found(
+                "diagram not found",
+                Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+            )
+        })?;
+
+        let DiagramAst::Flowchart(ast) = diagram.ast() else {
+            return Err(ErrorData::invalid_params(
+                "diagram is not a flowchart",
+                Some(serde_json::json!({
+                    "diagram_id": diagram_id.as_str(),
+                    "diagram_kind": diagram_kind_label(diagram.kind()),
+                })),
+            ));
+        };
+
+        let mut nodes = crate::query::flow::dead_ends(ast)
+            .into_iter()
+            .map(|node_id| format!("d:{}/flow/node/{}", diagram_id.as_str(), node_id))
+            .collect::<Vec<_>>();
+        nodes.sort();
+
+        Ok(Json(FlowDeadEndsResponse { nodes }))
+    }
+
+    /// Compute the dominator tree from an entry node (or the entry points): which nodes every
+    /// path must pass. Use for impact analysis of a node failing or being removed.
+    #[tool(name = "flow.dominators")]
+    async fn flow_dominators(
+        &self,
+        params: Parameters<FlowDominatorsParams>,
+    ) -> Result<Json<FlowDominatorsResponse>, ErrorData> {
+        let FlowDominatorsParams { diagram_id, entry_node_id } = params.0;
+        let entry_node_id = entry_node_id
+            .as_deref()
+            .map(|entry_node_id| {
+                ObjectId::new(entry_node_id.to_owned()).map_err(|err| {
+                    ErrorData::invalid_params(
+                        format!("invalid entry_node_id: {err}"),
+                        Some(serde_json::json!({ "entry_node_id": entry_node_id })),
+                    )
+                })
+            })
+            .transpose()?;
+
         let state = self.lock_state_synced().await?;
-        let diagram_id = resolve_diagram_id(&state.session, params.0.diagram_id.as_deref())?;
+        let diagram_id = resolve_diagram_id(&state.session, diagram_id.as_deref())?;
         let diagram = state.session.diagrams().get(&diagram_id).ok_or_else(|| {
             ErrorData::resource_not_found(
                 "diagram not found",
@@ -2123,26 +4325,51 @@ impl NereidMcp {
             ));
         };
 
-        let cycles = crate::query::flow::cycles(ast)
-            .into_iter()
-            .map(|cycle| {
-                cycle
-                    .into_iter()
-                    .map(|node_id| format!("d:{}/flow/node/{}", diagram_id.as_str(), node_id))
-                    .collect::<Vec<_>>()
+        let entries = match entry_node_id {
+            Some(entry_node_id) if !ast.nodes().contains_key(&entry_node_id) => {
+                return Err(ErrorData::resource_not_found(
+                    "entry node not found",
+                    Some(serde_json::json!({ "entry_node_id": entry_node_id.as_str() })),
+                ));
+            }
+            Some(entry_node_id) => vec![entry_node_id],
+            None => crate::query::flow::entry_points(ast),
+        };
+        let idoms = crate::query::flow::immediate_dominators(ast, &entries);
+        let mut dominates = BTreeMap::<&ObjectId, u64>::new();
+        for node_id in idoms.keys() {
+            let mut dominator = idoms[node_id].as_ref();
+            while let Some(node_id) = dominator {
+                *dominates.entry(node_id).or_default() += 1;
+                dominator = idoms[node_id].as_ref();
+            }
+        }
+
+        let node_ref =
+            |node_id: &ObjectId| format!("d:{}/flow/node/{}", diagram_id.as_str(), node_id);
+        let mut nodes = idoms
+            .iter()
+            .map(|(node_id, idom)| FlowDominatorNode {
+                node: node_ref(node_id),
+                immediate_dominator: idom.as_ref().map(node_ref),
+                dominates: dominates.get(node_id).copied().unwrap_or(0),
             })
             .collect::<Vec<_>>();
+        nodes.sort_by(|a, b| b.dominates.cmp(&a.dominates).then_with(|| a.node.cmp(&b.node)));
 
-        Ok(Json(FlowCyclesResponse { cycles }))
+        Ok(Json(FlowDominatorsResponse {
+            entry_nodes: entries.iter().map(node_ref).collect(),
+            nodes,
+        }))
     }
 
-    /// List terminal flowchart nodes (returns refs); combine with `flow.unreachable` to identify
-    /// dead routes.
-    #[tool(name = "flow.dead_ends")]
-    async fn flow_dead_ends(
+    /// List articulation nodes and bridge edges, ignoring edge direction: single points whose
+    /// removal splits the diagram in two.
+    #[tool(name = "flow.cut_points")]
+    async fn flow_cut_points(
         &self,
         params: Parameters<DiagramTargetParams>,
-    ) -> Result<Json<FlowDeadEndsResponse>, ErrorData> {
+    ) -> Result<Json<FlowCutPointsResponse>, ErrorData> {
         let state = self.lock_state_synced().await?;
         let diagram_id = resolve_diagram_id(&state.session, params.0.diagram_id.as_deref())?;
         let diagram = state.session.diagrams().get(&diagram_id).ok_or_else(|| {
@@ -2162,13 +4389,19 @@ impl NereidMcp {
             ));
         };
 
-        let mut nodes = crate::query::flow::dead_ends(ast)
-            .into_iter()
-            .map(|node_id| format!("d:{}/flow/node/{}", diagram_id.as_str(), node_id))
-            .collect::<Vec<_>>();
-        nodes.sort();
-
-        Ok(Json(FlowDeadEndsResponse { nodes }))
+        let cuts = crate::query::flow::cut_points(ast);
+        Ok(Json(FlowCutPointsResponse {
+            nodes: cuts
+                .nodes
+                .iter()
+                .map(|node_id| format!("d:{}/flow/node/{}", diagram_id.as_str(), node_id))
+                .collect(),
+            bridges: cuts
+                .bridges
+                .iter()
+                .map(|edge_id| format!("d:{}/flow/edge/{}", diagram_id.as_str(), edge_id))
+                .collect(),
+        }))
     }
 
     /// Compute flow fan-in/fan-out degrees (returns refs + counts); use to identify hubs and
@@ -2264,8 +4497,8 @@ impl NereidMcp {
         Ok(Json(FlowDegreesResponse { nodes }))
     }
 
-    /// List nodes unreachable from start nodes (returns refs); use for cleanup/TODO mapping and
-    /// follow with `diagram.get_slice`.
+    /// List nodes unreachable from a start node or, by default, the entry points (returns refs);
+    /// use for cleanup/TODO mapping and follow with `diagram.get_slice`.
     #[tool(name = "flow.unreachable")]
     async fn flow_unreachable(
         &self,
@@ -2305,11 +4538,9 @@ impl NereidMcp {
         };
 
         let mut outgoing: BTreeMap<ObjectId, BTreeSet<ObjectId>> = BTreeMap::new();
-        let mut indegree: BTreeMap<ObjectId, usize> = BTreeMap::new();
 
         for node_id in ast.nodes().keys() {
             outgoing.insert(node_id.clone(), BTreeSet::new());
-            indegree.insert(node_id.clone(), 0);
         }
 
         for edge in ast.edges().values() {
@@ -2317,7 +4548,6 @@ impl NereidMcp {
             let to = edge.to_node_id();
             if outgoing.contains_key(from) && outgoing.contains_key(to) {
                 outgoing.get_mut(from).expect("node exists").insert(to.clone());
-                *indegree.get_mut(to).expect("node exists") += 1;
             }
         }
 
@@ -2333,17 +4563,7 @@ impl NereidMcp {
             }
             vec![start_node_id.clone()]
         } else {
-            let mut starts = indegree
-                .iter()
-                .filter(|(_node_id, degree)| **degree == 0)
-                .map(|(node_id, _degree)| node_id.clone())
-                .collect::<Vec<_>>();
-
-            if starts.is_empty() {
-                starts = outgoing.keys().cloned().collect();
-            }
-
-            starts
+            crate::query::flow::entry_points(ast)
         };
 
         fn bfs(
@@ -2437,6 +4657,92 @@ impl NereidMcp {
         Ok(Json(response))
     }
 
+    /// Read a diagram as Mermaid with its rev, to edit as text and write back with
+    /// `diagram.set_mermaid`.
+    #[tool(name = "diagram.get_mermaid")]
+    async fn diagram_get_mermaid(
+        &self,
+        params: Parameters<DiagramTargetParams>,
+    ) -> Result<Json<DiagramGetMermaidResponse>, ErrorData> {
+        let state = self.lock_state_synced().await?;
+        let diagram_id = resolve_diagram_id(&state.session, params.0.diagram_id.as_deref())?;
+        let diagram = state
+            .session
+            .diagrams()
+            .get(&diagram_id)
+            .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?;
+        Ok(Json(DiagramGetMermaidResponse {
+            diagram_id: diagram_id.as_str().to_owned(),
+            rev: diagram.rev(),
+            kind: diagram_kind_label(diagram.kind()).to_owned(),
+            mermaid: mermaid_for_diagram(diagram),
+        }))
+    }
+
+    /// Replace a diagram wholesale with Mermaid of the same kind, gated by `base_rev` from
+    /// `diagram.get_mermaid`. Applied as ops where possible (journaled, visible in
+    /// `diagram.diff`); edits to subgraphs or sequence blocks replace the AST whole.
+    #[tool(name = "diagram.set_mermaid")]
+    async fn diagram_set_mermaid(
+        &self,
+        params: Parameters<DiagramSetMermaidParams>,
+    ) -> Result<Json<DiagramSetMermaidResponse>, ErrorData> {
+        let DiagramSetMermaidParams { diagram_id, base_rev, mermaid } = params.0;
+        let state = self.lock_state_synced().await?;
+        let diagram_id = resolve_diagram_id(&state.session, diagram_id.as_deref())?;
+        let diagram = state
+            .session
+            .diagrams()
+            .get(&diagram_id)
+            .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?;
+        check_diagram_writable(diagram)?;
+        if diagram.rev() != base_rev {
+            return Err(map_apply_error(ApplyError::Conflict {
+                base_rev,
+                current_rev: diagram.rev(),
+            }));
+        }
+        let Some(kind) = detect_diagram_kind(&mermaid).filter(|kind| *kind == diagram.kind())
+        else {
+            return Err(ErrorData::invalid_params(
+                "mermaid must be of the same diagram kind as the original",
+                Some(serde_json::json!({ "diagram_kind": format!("{:?}", diagram.kind()) })),
+            ));
+        };
+        let ast = parse_mermaid_ast(kind, &mermaid, state.session.id_prefix_rules())?;
+        if &ast == diagram.ast() {
+            return Ok(Json(DiagramSetMermaidResponse {
+                new_rev: base_rev,
+                applied: 0,
+                delta: Some(DeltaSummary {
+                    added: Vec::new(),
+                    removed: Vec::new(),
+                    updated: Vec::new(),
+                }),
+            }));
+        }
+        let ops = diff_ops(diagram.ast(), &ast);
+        drop(state);
+
+        let response = match ops {
+            Some(ops) => {
+                let applied =
+                    self.apply_diagram_ops(Some(diagram_id.as_str()), base_rev, ops).await?;
+                DiagramSetMermaidResponse {
+                    new_rev: applied.new_rev,
+                    applied: applied.applied,
+                    delta: Some(applied.delta),
+                }
+            }
+            None => DiagramSetMermaidResponse {
+                new_rev: self.replace_diagram_ast(&diagram_id, base_rev, ast).await?,
+                applied: 0,
+                delta: None,
+            },
+        };
+        Ok(Json(response))
+    }
+
     /// Read full diagram AST for id/label resolution; prefer this over session-file reads.
     #[tool(name = "diagram.get_ast")]
     async fn diagram_get_ast(
@@ -2894,6 +5200,122 @@ impl NereidMcp {
 
                 (objects, edges)
             }
+            DiagramAst::State(ast) => {
+                let mut adjacency: BTreeMap<ObjectId, BTreeSet<ObjectId>> = ast
+                    .states()
+                    .keys()
+                    .map(|state_id| (state_id.clone(), BTreeSet::new()))
+                    .collect();
+                for transition in ast.transitions().values() {
+                    let (from, to) = (transition.from_state_id(), transition.to_state_id());
+                    if adjacency.contains_key(from) && adjacency.contains_key(to) {
+                        adjacency.get_mut(from).expect("from state exists").insert(to.clone());
+                        adjacency.get_mut(to).expect("to state exists").insert(from.clone());
+                    }
+                }
+
+                let center_id = center_ref_parsed.object_id();
+                let starts = match center_ref_parsed.category().segments() {
+                    [a, b] if a == "state" && b == "node" => {
+                        if !ast.states().contains_key(center_id) {
+                            return Err(ErrorData::resource_not_found(
+                                "state not found",
+                                Some(serde_json::json!({ "center_ref": center_ref })),
+                            ));
+                        }
+                        vec![center_id.clone()]
+                    }
+                    [a, b] if a == "state" && b == "transition" => {
+                        let transition = ast.transitions().get(center_id).ok_or_else(|| {
+                            ErrorData::resource_not_found(
+                                "state transition not found",
+                                Some(serde_json::json!({ "center_ref": center_ref })),
+                            )
+                        })?;
+                        vec![transition.from_state_id().clone(), transition.to_state_id().clone()]
+                    }
+                    _ => {
+                        return Err(ErrorData::invalid_params(
+                            "center_ref is not a state diagram object",
+                            Some(serde_json::json!({ "center_ref": center_ref })),
+                        ));
+                    }
+                };
+
+                let states = bfs_within_radius(&adjacency, starts, max_hops);
+                let edges = ast
+                    .transitions()
+                    .iter()
+                    .filter(|(_, transition)| {
+                        states.contains(transition.from_state_id())
+                            && states.contains(transition.to_state_id())
+                    })
+                    .map(|(id, _)| format!("d:{}/state/transition/{}", diagram_id.as_str(), id))
+                    .collect::<Vec<_>>();
+                let objects = states
+                    .into_iter()
+                    .map(|state_id| format!("d:{}/state/node/{}", diagram_id.as_str(), state_id))
+                    .collect::<Vec<_>>();
+                (objects, edges)
+            }
+            DiagramAst::Class(ast) => {
+                let mut adjacency: BTreeMap<ObjectId, BTreeSet<ObjectId>> = ast
+                    .classes()
+                    .keys()
+                    .map(|class_id| (class_id.clone(), BTreeSet::new()))
+                    .collect();
+                for relation in ast.relations().values() {
+                    let (from, to) = (relation.from_class_id(), relation.to_class_id());
+                    if adjacency.contains_key(from) && adjacency.contains_key(to) {
+                        adjacency.get_mut(from).expect("from class exists").insert(to.clone());
+                        adjacency.get_mut(to).expect("to class exists").insert(from.clone());
+                    }
+                }
+
+                let center_id = center_ref_parsed.object_id();
+                let starts = match center_ref_parsed.category().segments() {
+                    [a, b] if a == "class" && b == "node" => {
+                        if !ast.classes().contains_key(center_id) {
+                            return Err(ErrorData::resource_not_found(
+                                "class not found",
+                                Some(serde_json::json!({ "center_ref": center_ref })),
+                            ));
+                        }
+                        vec![center_id.clone()]
+                    }
+                    [a, b] if a == "class" && b == "relation" => {
+                        let relation = ast.relations().get(center_id).ok_or_else(|| {
+                            ErrorData::resource_not_found(
+                                "class relation not found",
+                                Some(serde_json::json!({ "center_ref": center_ref })),
+                            )
+                        })?;
+                        vec![relation.from_class_id().clone(), relation.to_class_id().clone()]
+                    }
+                    _ => {
+                        return Err(ErrorData::invalid_params(
+                            "center_ref is not a class diagram object",
+                            Some(serde_json::json!({ "center_ref": center_ref })),
+                        ));
+                    }
+                };
+
+                let classes = bfs_within_radius(&adjacency, starts, max_hops);
+                let edges = ast
+                    .relations()
+                    .iter()
+                    .filter(|(_, relation)| {
+                        classes.contains(relation.from_class_id())
+                            && classes.contains(relation.to_class_id())
+                    })
+                    .map(|(id, _)| format!("d:{}/class/relation/{}", diagram_id.as_str(), id))
+                    .collect::<Vec<_>>();
+                let objects = classes
+                    .into_iter()
+                    .map(|class_id| format!("d:{}/class/node/{}", diagram_id.as_str(), class_id))
+                    .collect::<Vec<_>>();
+                (objects, edges)
+            }
         };
 
         objects.sort();
@@ -2944,31 +5366,309 @@ impl NereidMcp {
 
     /// Render diagram as deterministic text (Unicode allowed); use for human-readable snapshots
     /// and review, then return to `diagram.stat`/`diagram.get_slice` for targeted reasoning.
+    /// With a small context budget, clip to a window around `focus_ref` or pass `since_rev` to
+    /// get only the lines of objects changed since that rev. Set `legend` to append what the
+    /// on-screen markers (▴/▾ prefixes, highlights, styling rules) mean.
     #[tool(name = "diagram.render_text")]
     async fn diagram_render_text(
         &self,
-        params: Parameters<DiagramTargetParams>,
+        params: Parameters<DiagramRenderTextParams>,
     ) -> Result<Json<DiagramRenderTextResponse>, ErrorData> {
+        let DiagramRenderTextParams {
+            diagram_id,
+            focus_ref,
+            width,
+            height,
+            since_rev,
+            legend,
+            edge_glyphs,
+        } = params.0;
+        if focus_ref.is_some() && since_rev.is_some() {
+            return Err(ErrorData::invalid_params(
+                "focus_ref and since_rev cannot be combined",
+                None,
+            ));
+        }
+        let flowchart_edge_glyphs = match edge_glyphs.as_deref() {
+            None => FlowchartEdgeGlyphs::default(),
+            Some(value) => FlowchartEdgeGlyphs::parse(value).ok_or_else(|| {
+                ErrorData::invalid_params(
+                    "edge_glyphs must be box, braille or blocks",
+                    Some(serde_json::json!({ "edge_glyphs": value })),
+                )
+            })?,
+        };
+        let options = RenderOptions { flowchart_edge_glyphs, ..RenderOptions::default() };
+
         let state = self.lock_state_synced().await?;
         let session_active_diagram_id =
             state.session.active_diagram_id().map(|active| active.as_str().to_owned());
-        let diagram_id = resolve_diagram_id(&state.session, params.0.diagram_id.as_deref())?;
+        let diagram_id = resolve_diagram_id(&state.session, diagram_id.as_deref())?;
+        let diagram = state
+            .session
+            .diagrams()
+            .get(&diagram_id)
+            .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?;
+        let render_error = |err: crate::render::DiagramRenderError| {
+            ErrorData::internal_error(
+                format!("render error: {err}"),
+                Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+            )
+        };
+        let legend = legend.unwrap_or(false).then(|| {
+            crate::render::legend::legend_block(&crate::render::legend::diagram_legend(
+                &state.session,
+                diagram,
+            ))
+        });
+
+        let (text, clip, regions) = if let Some(focus_ref) = focus_ref {
+            let object_ref = ObjectRef::parse(&focus_ref).map_err(|err| {
+                ErrorData::invalid_params(
+                    format!("invalid focus_ref: {err}"),
+                    Some(serde_json::json!({ "focus_ref": focus_ref })),
+                )
+            })?;
+            let rendered = render_diagram_unicode_annotated_with_options(diagram, options)
+                .map_err(render_error)?;
+            let spans = rendered.highlight_index.get(&object_ref);
+            let spans = spans.filter(|spans| !spans.is_empty()).ok_or_else(|| {
+                ErrorData::resource_not_found(
+                    "focus_ref is not rendered in this diagram",
+                    Some(serde_json::json!({
+                        "diagram_id": diagram_id.as_str(),
+                        "focus_ref": focus_ref,
+                    })),
+                )
+            })?;
+            let width = usize::try_from(width.unwrap_or(80)).unwrap_or(usize::MAX).max(1);
+            let height = usize::try_from(height.unwrap_or(24)).unwrap_or(usize::MAX).max(1);
+            let (text, clip) = clip_render_around(&rendered.text, spans, width, height);
+            (text, Some(clip), None)
+        } else if let Some(since_rev) = since_rev {
+            let delta =
+                diagram_delta_since(&state.delta_history, &diagram_id, diagram.rev(), since_rev)?;
+            let changed = delta
+                .changes
+                .iter()
+                .filter(|change| change.kind != DeltaChangeKind::Removed)
+                .flat_map(|change| &change.refs)
+                .filter_map(|object_ref| ObjectRef::parse(object_ref).ok())
+                .collect::<Vec<_>>();
+            let rendered = render_diagram_unicode_annotated_with_options(diagram, options)
+                .map_err(render_error)?;
+            let (text, regions) =
+                changed_render_regions(&rendered.text, &rendered.highlight_index, &changed);
+            (text, None, Some(regions))
+        } else {
+            (
+                render_diagram_unicode_with_options(diagram, options).map_err(render_error)?,
+                None,
+                None,
+            )
+        };
+        drop(state);
+        let text = match legend {
+            Some(legend) => format!("{text}\n\n{legend}"),
+            None => text,
+        };
+        let context = self.read_context(session_active_diagram_id).await;
+
+        Ok(Json(DiagramRenderTextResponse { text, clip, regions, context }))
+    }
+
+    /// Render a diagram to the annotated Unicode text the TUI shows, plus the cells of every object
+    /// (`object_ref` → `[[line, start_col, end_col]]`); use for spatial questions such as "what is
+    /// left of node X". Pass `line`/`col`/`width`/`height` to crop to a viewport; spans are then
+    /// relative to the cropped text and objects outside it are omitted.
+    #[tool(name = "diagram.render")]
+    async fn diagram_render(
+        &self,
+        params: Parameters<DiagramRenderParams>,
+    ) -> Result<Json<DiagramRenderResponse>, ErrorData> {
+        let DiagramRenderParams { diagram_id, line, col, width, height } = params.0;
+
+        let state = self.lock_state_synced().await?;
+        let session_active_diagram_id =
+            state.session.active_diagram_id().map(|active| active.as_str().to_owned());
+        let diagram_id = resolve_diagram_id(&state.session, diagram_id.as_deref())?;
+        let diagram = state
+            .session
+            .diagrams()
+            .get(&diagram_id)
+            .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?;
+        let rendered = render_diagram_unicode_annotated(diagram).map_err(|err| {
+            ErrorData::internal_error(
+                format!("render error: {err}"),
+                Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+            )
+        })?;
+        drop(state);
+
+        let cell =
+            |value: Option<u64>| value.map(|value| usize::try_from(value).unwrap_or(usize::MAX));
+        let (text, highlights, clip) = if [line, col, width, height].iter().any(Option::is_some) {
+            let (text, highlights, clip) = crop_render(
+                &rendered.text,
+                &rendered.highlight_index,
+                (cell(line).unwrap_or(0), cell(col).unwrap_or(0)),
+                (cell(width).unwrap_or(usize::MAX), cell(height).unwrap_or(usize::MAX)),
+            );
+            (text, highlights, Some(clip))
+        } else {
+            let highlights = rendered
+                .highlight_index
+                .iter()
+                .filter(|(_, spans)| !spans.is_empty())
+                .map(|(object_ref, spans)| {
+                    let spans =
+                        spans.iter().map(|&(y, x0, x1)| [y as u64, x0 as u64, x1 as u64]).collect();
+                    (object_ref.to_string(), spans)
+                })
+                .collect();
+            (rendered.text, highlights, None)
+        };
+        let context = self.read_context(session_active_diagram_id).await;
+
+        Ok(Json(DiagramRenderResponse { text, highlights, clip, context }))
+    }
+
+    /// Export only the given object refs (default: current selection) plus induced edges/messages
+    /// as Mermaid, text, Markdown, Graphviz DOT or SVG; use to share a fragment of a large diagram.
+    #[tool(name = "diagram.export_subset")]
+    async fn diagram_export_subset(
+        &self,
+        params: Parameters<DiagramExportSubsetParams>,
+    ) -> Result<Json<DiagramExportSubsetResponse>, ErrorData> {
+        let DiagramExportSubsetParams { diagram_id, object_refs, format, theme } = params.0;
+        let format = format.unwrap_or_default();
+        let theme = match theme.as_deref() {
+            Some(name) => ExportTheme::named(name).ok_or_else(|| {
+                ErrorData::invalid_params(
+                    "unknown export theme",
+                    Some(serde_json::json!({
+                        "theme": name,
+                        "themes": ExportTheme::ALL.map(|theme| theme.name),
+                    })),
+                )
+            })?,
+            None => ExportTheme::default(),
+        };
+
+        let state = self.lock_state_synced().await?;
+        let diagram_id = resolve_diagram_id(&state.session, diagram_id.as_deref())?;
+        let diagram = state
+            .session
+            .diagrams()
+            .get(&diagram_id)
+            .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?;
+
+        let object_refs = match object_refs {
+            Some(object_refs) => object_refs
+                .iter()
+                .map(|object_ref| parse_object_ref(object_ref))
+                .collect::<Result<BTreeSet<_>, _>>()?,
+            None => state.session.selected_object_refs().clone(),
+        };
+        let subset = crate::query::subgraph::induced_subdiagram(diagram, &object_refs);
+        let session = session_info(&state.session);
+        let selected = state.session.selected_object_refs().clone();
+        let object_styles = crate::query::objects::object_styles(&state.session, diagram);
+        drop(state);
+        let agent = self.agent_highlights.lock().await.clone();
+        let style =
+            ExportStyle { theme, selection: ExportSelection { selected, agent }, object_styles };
+
+        let text = match format {
+            ExportFormat::Mermaid => mermaid_for_diagram(&subset),
+            ExportFormat::Markdown => markdown_for_diagram(&subset),
+            ExportFormat::Dot => crate::format::dot::export_dot(&subset),
+            ExportFormat::Unicode => render_diagram_unicode(&subset).map_err(|err| {
+                ErrorData::internal_error(
+                    format!("render error: {err}"),
+                    Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+                )
+            })?,
+            ExportFormat::Svg => {
+                crate::format::svg::diagram_svg(&subset, &style).map_err(|err| {
+                    ErrorData::internal_error(
+                        format!("render error: {err}"),
+                        Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+                    )
+                })?
+            }
+        };
+
+        Ok(Json(DiagramExportSubsetResponse {
+            diagram_id: diagram_id.as_str().to_owned(),
+            format,
+            text,
+            object_refs: object_refs_in_diagram(&subset)
+                .into_iter()
+                .map(|object_ref| object_ref.to_string())
+                .collect(),
+            session,
+        }))
+    }
+
+    /// Preview a tidier flowchart layout (fewer edge crossings, compacted) with before/after size
+    /// and crossing counts; re-call with `apply=true` and `base_rev` to persist it as layout hints.
+    #[tool(name = "diagram.tidy_layout")]
+    async fn diagram_tidy_layout(
+        &self,
+        params: Parameters<DiagramTidyLayoutParams>,
+    ) -> Result<Json<DiagramTidyLayoutResponse>, ErrorData> {
+        let DiagramTidyLayoutParams { diagram_id, apply, base_rev } = params.0;
+        let apply = apply.unwrap_or(false);
+        if apply && base_rev.is_none() {
+            return Err(ErrorData::invalid_params("base_rev is required when apply=true", None));
+        }
+
+        let state = self.lock_state_synced().await?;
+        let diagram_id = resolve_diagram_id(&state.session, diagram_id.as_deref())?;
         let diagram = state
             .session
             .diagrams()
             .get(&diagram_id)
             .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?;
+        let DiagramAst::Flowchart(ast) = diagram.ast() else {
+            return Err(ErrorData::invalid_params(
+                "tidy_layout supports flowcharts only",
+                Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+            ));
+        };
 
-        let text = render_diagram_unicode(diagram).map_err(|err| {
+        let render_error = |err: String| {
             ErrorData::internal_error(
                 format!("render error: {err}"),
                 Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
             )
-        })?;
+        };
+        let rev = diagram.rev();
+        let tidy = tidy_flowchart(ast, RenderOptions::default())
+            .map_err(|err| render_error(err.to_string()))?;
         drop(state);
-        let context = self.read_context(session_active_diagram_id).await;
 
-        Ok(Json(DiagramRenderTextResponse { text, context }))
+        let new_rev = match base_rev {
+            Some(base_rev) if apply && tidy.hints_changed => {
+                let ops =
+                    vec![Op::Flow(FlowOp::SetNodeOrderHints { order_hints: tidy.order_hints })];
+                let applied =
+                    self.apply_diagram_ops(Some(diagram_id.as_str()), base_rev, ops).await?;
+                Some(applied.new_rev)
+            }
+            _ => None,
+        };
+
+        Ok(Json(DiagramTidyLayoutResponse {
+            diagram_id: diagram_id.as_str().to_owned(),
+            rev,
+            before: tidy_layout_metrics(tidy.before),
+            after: tidy_layout_metrics(tidy.after),
+            preview: tidy.preview,
+            applied: new_rev.is_some(),
+            new_rev,
+        }))
     }
 
     /// Get diagram delta since a revision; default refresh step after `diagram.apply_ops` or
@@ -2986,37 +5686,91 @@ impl NereidMcp {
             .get(&diagram_id)
             .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?;
 
-        let current_rev = diagram.rev();
-        let since_rev = params.0.since_rev;
-        if since_rev > current_rev {
-            return Err(ErrorData::invalid_params(
-                "since_rev must be <= current rev",
-                Some(serde_json::json!({ "since_rev": since_rev, "current_rev": current_rev })),
-            ));
-        }
-
-        if since_rev == current_rev {
-            return Ok(Json(DiagramDeltaResponse {
-                from_rev: current_rev,
-                to_rev: current_rev,
-                changes: Vec::new(),
-            }));
-        }
+        let delta = diagram_delta_since(
+            &state.delta_history,
+            &diagram_id,
+            diagram.rev(),
+            params.0.since_rev,
+        )?;
+        Ok(Json(delta))
+    }
 
-        let Some(history) = state.delta_history.get(&diagram_id) else {
-            return Err(delta_unavailable(since_rev, current_rev, current_rev));
+    /// Compare two versions of a diagram object by object (added/removed/changed with the facts
+    /// that changed); by default the saved version on disk against the current one.
+    #[tool(name = "diagram.compare")]
+    async fn diagram_compare(
+        &self,
+        params: Parameters<DiagramCompareParams>,
+    ) -> Result<Json<DiagramCompareResponse>, ErrorData> {
+        let DiagramCompareParams { diagram_id, from_mermaid, to_mermaid } = params.0;
+        let state = self.lock_state_synced().await?;
+        let diagram_id = resolve_diagram_id(&state.session, diagram_id.as_deref())?;
+        let diagram = state
+            .session
+            .diagrams()
+            .get(&diagram_id)
+            .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?;
+        let from_mermaid_version = |mermaid: &str| {
+            let Some(kind) = detect_diagram_kind(mermaid).filter(|kind| *kind == diagram.kind())
+            else {
+                return Err(ErrorData::invalid_params(
+                    "mermaid must be of the same diagram kind as the original",
+                    Some(serde_json::json!({ "diagram_kind": format!("{:?}", diagram.kind()) })),
+                ));
+            };
+            let ast = parse_mermaid_ast(kind, mermaid, state.session.id_prefix_rules())?;
+            let mut version = diagram.clone();
+            version.set_ast(ast).map_err(|err| ErrorData::invalid_params(err.to_string(), None))?;
+            Ok(version)
         };
 
-        let supported_since_rev = history.front().map(|d| d.from_rev).unwrap_or(current_rev);
-        if since_rev < supported_since_rev {
-            return Err(delta_unavailable(since_rev, current_rev, supported_since_rev));
-        }
-
-        let Some(delta) = delta_response_from_history(history, since_rev, current_rev) else {
-            return Err(delta_unavailable(since_rev, current_rev, supported_since_rev));
+        let (from, before) = match from_mermaid.as_deref() {
+            Some(mermaid) => ("mermaid", from_mermaid_version(mermaid)?),
+            None => {
+                let Some(session_folder) = self.session_folder() else {
+                    return Err(ErrorData::invalid_params(
+                        "no saved version without a session folder; pass from_mermaid",
+                        None,
+                    ));
+                };
+                let disk_session = session_folder.load_session().map_err(|err| {
+                    ErrorData::internal_error(
+                        format!("failed to load session from disk: {err}"),
+                        None,
+                    )
+                })?;
+                let saved = disk_session.diagrams().get(&diagram_id).cloned().ok_or_else(|| {
+                    ErrorData::resource_not_found(
+                        "diagram not saved on disk",
+                        Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+                    )
+                })?;
+                ("disk", saved)
+            }
+        };
+        let (to, after) = match to_mermaid.as_deref() {
+            Some(mermaid) => ("mermaid", from_mermaid_version(mermaid)?),
+            None => ("current", diagram.clone()),
         };
 
-        Ok(Json(delta))
+        let diff = diff_diagrams(&state.session, &before, &after);
+        let refs = |status| diff.refs(status).map(ToString::to_string).collect::<Vec<_>>();
+        Ok(Json(DiagramCompareResponse {
+            diagram_id: diagram_id.as_str().to_owned(),
+            from: from.to_owned(),
+            to: to.to_owned(),
+            added: refs(DiffStatus::Added),
+            removed: refs(DiffStatus::Removed),
+            changed: diff
+                .objects
+                .iter()
+                .filter(|object| object.status == DiffStatus::Changed)
+                .map(|object| McpChangedObject {
+                    object_ref: object.object_ref.to_string(),
+                    fields: object.fields.iter().map(|field| (*field).to_owned()).collect(),
+                })
+                .collect(),
+        }))
     }
 
     /// Apply structured diagram ops gated by `base_rev`; prefer `diagram.propose_ops` first, then
@@ -3026,148 +5780,128 @@ impl NereidMcp {
         &self,
         params: Parameters<ApplyOpsParams>,
     ) -> Result<Json<ApplyOpsResponse>, ErrorData> {
-        let mut state = self.lock_state_synced().await?;
-        let diagram_id = resolve_diagram_id(&state.session, params.0.diagram_id.as_deref())?;
+        let ops = params.0.ops.iter().map(mcp_op_to_internal).collect::<Result<Vec<_>, _>>()?;
+        let response =
+            self.apply_diagram_ops(params.0.diagram_id.as_deref(), params.0.base_rev, ops).await?;
+        Ok(Json(response))
+    }
+
+    /// Same as `diagram.apply_ops`: apply typed seq/flow ops gated by `base_rev`, returning the
+    /// delta and new rev.
+    #[tool(name = "diagram.ops.apply")]
+    async fn diagram_ops_apply(
+        &self,
+        params: Parameters<ApplyOpsParams>,
+    ) -> Result<Json<ApplyOpsResponse>, ErrorData> {
+        self.diagram_apply_ops(params).await
+    }
+
+    /// Propose a rewrite (full Mermaid or ops) of a diagram without touching it; the human
+    /// previews it in the TUI (`:proposal`) and accepts or discards it. Use for large rewrites
+    /// instead of `diagram.apply_ops`, then poll `diagram.list_proposals` for the outcome.
+    #[tool(name = "diagram.propose_rewrite")]
+    async fn diagram_propose_rewrite(
+        &self,
+        params: Parameters<DiagramProposeRewriteParams>,
+    ) -> Result<Json<DiagramProposeRewriteResponse>, ErrorData> {
+        let DiagramProposeRewriteParams { diagram_id, base_rev, mermaid, ops, summary } = params.0;
+        let ui_state = self.proposal_ui_state()?;
+
+        let state = self.lock_state_synced().await?;
+        let diagram_id = resolve_diagram_id(&state.session, diagram_id.as_deref())?;
         let diagram = state
             .session
             .diagrams()
             .get(&diagram_id)
             .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?;
-
-        let ops = params.0.ops.iter().map(mcp_op_to_internal).collect::<Result<Vec<_>, _>>()?;
-
-        let base_rev = params.0.base_rev;
-        let current_rev = diagram.rev();
-        if base_rev != current_rev {
-            let digest = digest_for_diagram(diagram);
-            return Err(ErrorData::invalid_request(
-                "conflict: stale base_rev",
-                Some(serde_json::json!({
-                    "base_rev": base_rev,
-                    "current_rev": current_rev,
-                    "snapshot_tool": "diagram.stat",
-                    "digest": {
-                        "rev": digest.rev,
-                        "counts": {
-                            "participants": digest.counts.participants,
-                            "messages": digest.counts.messages,
-                            "nodes": digest.counts.nodes,
-                            "edges": digest.counts.edges,
-                        },
-                        "key_names": digest.key_names,
-                    },
-                })),
-            ));
+        check_diagram_writable(diagram)?;
+        if diagram.rev() != base_rev {
+            return Err(map_apply_error(ApplyError::Conflict {
+                base_rev,
+                current_rev: diagram.rev(),
+            }));
         }
 
-        if let Some(session_folder) = &self.session_folder {
-            let mut candidate_session = state.session.clone();
-            let mut candidate_diagram = candidate_session
-                .diagrams()
-                .get(&diagram_id)
-                .cloned()
-                .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?;
-
-            let result =
-                apply_ops(&mut candidate_diagram, base_rev, &ops).map_err(map_apply_error)?;
-            render_diagram_unicode(&candidate_diagram).map_err(|err| {
-                ErrorData::invalid_request(
-                    format!("cannot render diagram after apply_ops: {err}"),
-                    Some(serde_json::json!({
-                        "diagram_id": diagram_id.as_str(),
-                        "base_rev": base_rev,
-                        "op_count": ops.len() as u64,
-                        "render_error": err.to_string(),
-                    })),
-                )
-            })?;
-            candidate_session.diagrams_mut().insert(diagram_id.clone(), candidate_diagram);
-
-            let mut history =
-                state.delta_history.get(&diagram_id).cloned().unwrap_or_else(VecDeque::new);
-            history.push_back(LastDelta {
-                from_rev: base_rev,
-                to_rev: result.new_rev,
-                delta: result.delta.clone(),
-            });
-            while history.len() > DELTA_HISTORY_LIMIT {
-                history.pop_front();
+        let ast = match (mermaid, ops) {
+            (Some(mermaid), None) => {
+                let kind = detect_diagram_kind(&mermaid).filter(|kind| *kind == diagram.kind());
+                let Some(kind) = kind else {
+                    return Err(ErrorData::invalid_params(
+                        "mermaid must be of the same diagram kind as the original",
+                        Some(
+                            serde_json::json!({ "diagram_kind": format!("{:?}", diagram.kind()) }),
+                        ),
+                    ));
+                };
+                parse_mermaid_ast(kind, &mermaid, state.session.id_prefix_rules())?
             }
-
-            let meta = session_folder.load_meta().map_err(|err| {
-                ErrorData::internal_error(
-                    format!("failed to load session meta: {err}"),
-                    Some(serde_json::json!({ "diagram_id": diagram_id.as_str(), "base_rev": base_rev })),
-                )
-            })?;
-            candidate_session
-                .set_selected_object_refs(meta.selected_object_refs.into_iter().collect());
-            session_folder.save_session(&candidate_session).map_err(|err| {
-                ErrorData::internal_error(
-                    format!("failed to persist session: {err}"),
-                    Some(serde_json::json!({ "diagram_id": diagram_id.as_str(), "base_rev": base_rev })),
-                )
-            })?;
-
-            state.session = candidate_session;
-            state.delta_history.insert(diagram_id, history);
-
-            let response = Json(ApplyOpsResponse {
-                new_rev: result.new_rev,
-                applied: result.applied as u64,
-                delta: DeltaSummary {
-                    added: result.delta.added.iter().map(ToString::to_string).collect(),
-                    removed: result.delta.removed.iter().map(ToString::to_string).collect(),
-                    updated: result.delta.updated.iter().map(ToString::to_string).collect(),
-                },
-            });
-            drop(state);
-            self.notify_ui_session_changed().await;
-            return Ok(response);
+            (None, Some(ops)) => {
+                let ops = ops.iter().map(mcp_op_to_internal).collect::<Result<Vec<_>, _>>()?;
+                let mut candidate = diagram.clone();
+                apply_ops(&mut candidate, base_rev, &ops).map_err(map_apply_error)?;
+                candidate.ast().clone()
+            }
+            _ => {
+                return Err(ErrorData::invalid_params("give exactly one of mermaid or ops", None));
+            }
+        };
+        if &ast == diagram.ast() {
+            return Err(ErrorData::invalid_params("proposal does not change the diagram", None));
         }
 
-        let mut candidate_diagram = state
-            .session
-            .diagrams()
-            .get(&diagram_id)
-            .cloned()
-            .ok_or_else(|| ErrorData::resource_not_found("diagram not found", None))?;
+        let summary = summary.map(|summary| summary.trim().to_owned()).filter(|s| !s.is_empty());
+        let mut ui_state = ui_state.lock().await;
+        let proposal_id = ui_state.add_proposal(diagram_id, base_rev, summary, ast);
+        let proposal = mcp_proposal(&state.session, &ui_state.proposals()[&proposal_id]);
+        Ok(Json(DiagramProposeRewriteResponse { proposal }))
+    }
 
-        let result = apply_ops(&mut candidate_diagram, base_rev, &ops).map_err(map_apply_error)?;
-        render_diagram_unicode(&candidate_diagram).map_err(|err| {
-            ErrorData::invalid_request(
-                format!("cannot render diagram after apply_ops: {err}"),
-                Some(serde_json::json!({
-                    "diagram_id": diagram_id.as_str(),
-                    "base_rev": base_rev,
-                    "op_count": ops.len() as u64,
-                    "render_error": err.to_string(),
-                })),
-            )
-        })?;
-        state.session.diagrams_mut().insert(diagram_id.clone(), candidate_diagram);
-        let history = state.delta_history.entry(diagram_id).or_insert_with(VecDeque::new);
-        history.push_back(LastDelta {
-            from_rev: base_rev,
-            to_rev: result.new_rev,
-            delta: result.delta.clone(),
-        });
-        while history.len() > DELTA_HISTORY_LIMIT {
-            history.pop_front();
-        }
+    /// List agent proposals with their status (`pending`, `accepted`, `discarded`); use after
+    /// `diagram.propose_rewrite` to learn whether the human took it.
+    #[tool(name = "diagram.list_proposals")]
+    async fn diagram_list_proposals(
+        &self,
+        params: Parameters<DiagramListProposalsParams>,
+    ) -> Result<Json<DiagramListProposalsResponse>, ErrorData> {
+        let ui_state = self.proposal_ui_state()?;
+        let state = self.lock_state_synced().await?;
+        let proposals = ui_state
+            .lock()
+            .await
+            .proposals()
+            .values()
+            .filter(|proposal| {
+                params.0.diagram_id.as_deref().map_or(true, |id| proposal.diagram_id.as_str() == id)
+            })
+            .map(|proposal| mcp_proposal(&state.session, proposal))
+            .collect();
+        Ok(Json(DiagramListProposalsResponse { proposals }))
+    }
 
-        let response = Json(ApplyOpsResponse {
-            new_rev: result.new_rev,
-            applied: result.applied as u64,
-            delta: DeltaSummary {
-                added: result.delta.added.iter().map(ToString::to_string).collect(),
-                removed: result.delta.removed.iter().map(ToString::to_string).collect(),
-                updated: result.delta.updated.iter().map(ToString::to_string).collect(),
-            },
-        });
-        drop(state);
-        self.notify_ui_session_changed().await;
-        Ok(response)
+    /// Withdraw a pending proposal so the human no longer sees it.
+    #[tool(name = "diagram.discard_proposal")]
+    async fn diagram_discard_proposal(
+        &self,
+        params: Parameters<DiagramDiscardProposalParams>,
+    ) -> Result<Json<DiagramDiscardProposalResponse>, ErrorData> {
+        let ui_state = self.proposal_ui_state()?;
+        let state = self.lock_state_synced().await?;
+        let mut ui_state = ui_state.lock().await;
+        let Some(proposal) = ui_state.proposals().get(&params.0.proposal_id) else {
+            return Err(ErrorData::resource_not_found(
+                "proposal not found",
+                Some(serde_json::json!({ "proposal_id": params.0.proposal_id })),
+            ));
+        };
+        if proposal.status != ProposalStatus::Pending {
+            return Err(ErrorData::invalid_request(
+                "proposal is already settled",
+                Some(serde_json::json!({ "proposal": mcp_proposal(&state.session, proposal) })),
+            ));
+        }
+        ui_state.resolve_proposal(&params.0.proposal_id, ProposalStatus::Discarded);
+        let proposal = mcp_proposal(&state.session, &ui_state.proposals()[&params.0.proposal_id]);
+        Ok(Json(DiagramDiscardProposalResponse { proposal }))
     }
 
     /// Validate ops against `base_rev` and return predicted delta without mutation; use immediately
@@ -3221,18 +5955,112 @@ impl ServerHandler for NereidMcp {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             instructions: Some(
-                "Nereid diagram collaboration server (tools: diagram.list, diagram.open, diagram.delete, diagram.current, diagram.read, diagram.stat, diagram.diff, diagram.render_text, diagram.get_ast, diagram.get_slice, diagram.create_from_mermaid, diagram.apply_ops, diagram.propose_ops, walkthrough.list, walkthrough.open, walkthrough.current, walkthrough.read, walkthrough.stat, walkthrough.diff, walkthrough.render_text, walkthrough.get_node, walkthrough.apply_ops, route.find, attention.human.read, attention.agent.read, attention.agent.set, attention.agent.clear, follow_ai.read, follow_ai.set, selection.read, selection.update, view.read_state, object.read, xref.list, xref.neighbors, xref.add, xref.remove, seq.messages, seq.trace, seq.search, flow.reachable, flow.unreachable, flow.paths, flow.cycles, flow.dead_ends, flow.degrees)"
+                "Nereid diagram collaboration server (tools: server.capabilities, session.read_meta, session.update_meta, session.lint_ids, session.validate, session.search, session.export_archive, session.import_archive, session.init_from_template, workspace.list_sessions, workspace.open, diagram.list, diagram.set_archived, diagram.set_read_only, diagram.update_description, diagram.open, diagram.reorder, diagram.delete, diagram.current, diagram.read, diagram.get_mermaid, diagram.set_mermaid, diagram.stat, diagram.summarize, diagram.diff, diagram.compare, diagram.render, diagram.render_text, diagram.export_subset, diagram.critical_path, diagram.tidy_layout, diagram.get_ast, diagram.get_slice, diagram.create, diagram.create_from_mermaid, diagram.import_table, diagram.scaffold_sequence, diagram.scaffold_flowchart, diagram.apply_ops, diagram.ops.apply, diagram.propose_ops, diagram.propose_rewrite, diagram.list_proposals, diagram.discard_proposal, walkthrough.list, walkthrough.open, walkthrough.current, walkthrough.read, walkthrough.stat, walkthrough.diff, walkthrough.render_text, walkthrough.get_node, walkthrough.apply_ops, walkthrough.create, walkthrough.node.add, walkthrough.node.update, walkthrough.edge.add, walkthrough.delete, route.find, attention.human.read, attention.agent.read, attention.agent.set, attention.agent.heartbeat, attention.agent.clear, follow_ai.read, follow_ai.set, selection.read, selection.update, view.read_state, view.get_viewport, view.center_on, view.scroll, view.set_active_diagram, view.zoom, object.read, object.get, object.describe, object.list, object.find_by_tag, xref.list, xref.neighbors, xref.add, xref.remove, seq.messages, seq.trace, seq.search, flow.entry_points, flow.reachable, flow.unreachable, flow.paths, flow.highlight_path, flow.shortest_path, flow.cycles, flow.dead_ends, flow.degrees, flow.dominators, flow.cut_points; prompts: document-flow, explain-selection, author-walkthrough)"
                     .into(),
             ),
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_prompts()
+                .enable_resources()
+                .enable_resources_subscribe()
+                .enable_resources_list_changed()
+                .build(),
             ..Default::default()
         }
     }
+
+    async fn on_initialized(&self, context: NotificationContext<RoleServer>) {
+        {
+            let state = self.state.lock().await;
+            self.resource_clients.lock().await.connect(context.peer.clone(), &state.session);
+        }
+        self.forward_session_changes(context.peer);
+        self.publish_session_changes().await;
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, ErrorData> {
+        Ok(ListPromptsResult::with_all_items(list_prompts()))
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        let mut state = self.lock_state_synced().await?;
+        if let Some(session_folder) = &self.session_folder() {
+            let meta = session_folder.load_meta().map_err(|err| {
+                ErrorData::internal_error(format!("failed to load session meta: {err}"), None)
+            })?;
+            state.session.set_selection_log(meta.selection_log);
+            retain_existing_selected_object_refs(&mut state.session);
+        }
+        get_prompt(&state.session, &request.name, request.arguments.as_ref())
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, ErrorData> {
+        let resources = list_resources(&self.lock_state_synced().await?.session);
+        self.notify_resource_changes().await;
+        Ok(ListResourcesResult::with_all_items(resources))
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, ErrorData> {
+        Ok(ListResourceTemplatesResult::with_all_items(list_resource_templates()))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        let contents = read_resource(&self.lock_state_synced().await?.session, &request.uri)?;
+        self.notify_resource_changes().await;
+        Ok(ReadResourceResult { contents: vec![contents] })
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        self.set_resource_subscription(request.uri, context.peer, true).await
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        self.set_resource_subscription(request.uri, context.peer, false).await
+    }
 }
 
 // Extracted mapping/parsing/delta helpers for MCP tool handlers.
 include!("server/helpers.rs");
 
+mod changes;
+use changes::{ChangeSnapshot, SESSION_CHANGED_METHOD};
+
+mod prompts;
+use prompts::{get_prompt, list_prompts};
+
+mod resources;
+use resources::{
+    list_resource_templates, list_resources, read_resource, ResourceClients, ResourceUri,
+};
+
 #[cfg(test)]
 mod e2e;
 
@@ -0,0 +1,137 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! `nereid/sessionChanged` notifications, so agents learn about changes without polling.
+//!
+//! Server-side mutations (ops, selection, attention, metadata) and changes to the view state
+//! shared with the TUI are diffed against the last published [`ChangeSnapshot`] and broadcast as
+//! [`SessionChanged`]; every connected client has a task relaying the broadcast to it.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::{Hash, Hasher};
+
+use super::SessionChanged;
+use crate::model::{DiagramId, ObjectRef, Session, WalkthroughId};
+use crate::ui::UiState;
+
+pub(super) const SESSION_CHANGED_METHOD: &str = "nereid/sessionChanged";
+
+/// The parts of the session and shared view state that notifications report on.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(super) struct ChangeSnapshot {
+    diagrams: BTreeMap<DiagramId, u64>,
+    walkthroughs: BTreeMap<WalkthroughId, u64>,
+    meta: u64,
+    selection: BTreeSet<ObjectRef>,
+    attention: (BTreeSet<ObjectRef>, Option<ObjectRef>),
+    view: u64,
+}
+
+impl ChangeSnapshot {
+    pub(super) fn capture(
+        session: &Session,
+        agent_highlights: &BTreeSet<ObjectRef>,
+        ui_state: Option<&UiState>,
+    ) -> Self {
+        let mut meta = DefaultHasher::new();
+        (session.title(), session.description(), session.tags(), session.active_diagram_id())
+            .hash(&mut meta);
+        session.style_rules().iter().map(ToString::to_string).for_each(|rule| rule.hash(&mut meta));
+        session
+            .id_prefix_rules()
+            .iter()
+            .map(ToString::to_string)
+            .for_each(|rule| rule.hash(&mut meta));
+
+        let mut view = DefaultHasher::new();
+        if let Some(ui_state) = ui_state {
+            let viewport = ui_state.human_viewport();
+            (ui_state.human_active_diagram_id(), ui_state.follow_ai()).hash(&mut view);
+            (viewport.scroll_x, viewport.scroll_y, viewport.width, viewport.height).hash(&mut view);
+            (viewport.zoom as u8).hash(&mut view);
+        }
+
+        Self {
+            diagrams: session.diagrams().iter().map(|(id, d)| (id.clone(), d.rev())).collect(),
+            walkthroughs: session
+                .walkthroughs()
+                .iter()
+                .map(|(id, walkthrough)| (id.clone(), walkthrough.rev()))
+                .collect(),
+            meta: meta.finish(),
+            selection: session.selected_object_refs().clone(),
+            attention: (
+                agent_highlights.clone(),
+                ui_state.and_then(|ui_state| ui_state.human_active_object_ref().cloned()),
+            ),
+            view: view.finish(),
+        }
+    }
+
+    /// What changed from `self` to `next`.
+    pub(super) fn changes_to(&self, next: &Self) -> SessionChanged {
+        SessionChanged {
+            diagrams: changed_revs(&self.diagrams, &next.diagrams),
+            walkthroughs: changed_revs(&self.walkthroughs, &next.walkthroughs),
+            meta: self.meta != next.meta,
+            selection: self.selection != next.selection,
+            attention: self.attention != next.attention,
+            view: self.view != next.view,
+        }
+    }
+}
+
+fn changed_revs<K: Ord + ToString>(
+    before: &BTreeMap<K, u64>,
+    after: &BTreeMap<K, u64>,
+) -> BTreeMap<String, Option<u64>> {
+    let removed = before.keys().filter(|id| !after.contains_key(*id)).map(|id| (id, None));
+    let changed = after
+        .iter()
+        .filter(|(id, rev)| before.get(*id) != Some(*rev))
+        .map(|(id, rev)| (id, Some(*rev)));
+    removed.chain(changed).map(|(id, rev)| (id.to_string(), rev)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use super::ChangeSnapshot;
+    use crate::model::{DiagramId, ObjectRef};
+    use crate::tui::demo_session;
+    use crate::ui::UiState;
+
+    #[test]
+    fn reports_changed_revisions_selection_attention_and_view() {
+        let mut session = demo_session();
+        let mut ui_state = UiState::default();
+        let before = ChangeSnapshot::capture(&session, &BTreeSet::new(), Some(&ui_state));
+        assert!(before.changes_to(&before).is_empty());
+
+        let diagram_id = DiagramId::new("demo-seq").expect("diagram id");
+        session.diagrams_mut().get_mut(&diagram_id).expect("diagram").bump_rev();
+        let removed = session.diagrams().keys().next().cloned().expect("first diagram");
+        session.diagrams_mut().remove(&removed);
+        let target: ObjectRef = "d:demo-seq/seq/participant/p:alice".parse().expect("object ref");
+        session.set_selected_object_refs(BTreeSet::from([target.clone()]));
+        ui_state.set_follow_ai(false);
+        let after = ChangeSnapshot::capture(&session, &BTreeSet::from([target]), Some(&ui_state));
+
+        let changed = before.changes_to(&after);
+        let rev = session.diagrams()[&diagram_id].rev();
+        assert_eq!(
+            changed.diagrams,
+            BTreeMap::from([(removed.to_string(), None), ("demo-seq".to_owned(), Some(rev))])
+        );
+        assert!(changed.walkthroughs.is_empty());
+        assert!(!changed.meta);
+        assert!(changed.selection && changed.attention && changed.view);
+    }
+}
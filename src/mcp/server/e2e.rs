@@ -162,6 +162,8 @@ fn e2e_human_and_agent_collaborate_on_persisted_session() {
         server
             .attention_agent_set(Parameters(AttentionAgentSetParams {
                 object_ref: participant_b_ref.clone(),
+                client_id: None,
+                name: None,
             }))
             .await
             .expect("attention.agent.set")
@@ -283,6 +285,8 @@ fn e2e_tui_reloads_when_mcp_creates_diagram_and_sets_attention() {
         server
             .attention_agent_set(Parameters(AttentionAgentSetParams {
                 object_ref: node_ref.clone(),
+                client_id: None,
+                name: None,
             }))
             .await
             .expect("attention.agent.set");
@@ -319,8 +323,9 @@ fn e2e_diagram_and_sequence_tools_cover_full_surface() {
         runtime.block_on(async { server.view_get_state().await.expect("view.read_state") });
     assert_eq!(view.active_diagram_id.as_deref(), Some(diagram_id));
 
-    let Json(diagrams) =
-        runtime.block_on(async { server.diagram_list().await.expect("diagram.list") });
+    let Json(diagrams) = runtime.block_on(async {
+        server.diagram_list(Parameters(DiagramListParams::default())).await.expect("diagram.list")
+    });
     assert!(
         diagrams.diagrams.iter().any(|d| d.diagram_id == diagram_id),
         "expected diagram.list to include created diagram"
@@ -353,7 +358,10 @@ fn e2e_diagram_and_sequence_tools_cover_full_surface() {
 
     let Json(render) = runtime.block_on(async {
         server
-            .diagram_render_text(Parameters(DiagramTargetParams { diagram_id: None }))
+            .diagram_render_text(Parameters(DiagramRenderTextParams {
+                diagram_id: None,
+                ..Default::default()
+            }))
             .await
             .expect("diagram.render_text")
     });
@@ -468,6 +476,8 @@ fn e2e_diagram_and_sequence_tools_cover_full_surface() {
         server
             .attention_agent_set(Parameters(AttentionAgentSetParams {
                 object_ref: participant_b_ref.clone(),
+                client_id: None,
+                name: None,
             }))
             .await
             .expect("attention.agent.set")
@@ -480,8 +490,12 @@ fn e2e_diagram_and_sequence_tools_cover_full_surface() {
     assert_eq!(attention.object_ref.as_deref(), Some(participant_b_ref.as_str()));
     assert_eq!(attention.diagram_id.as_deref(), Some(diagram_id));
 
-    let Json(cleared) = runtime
-        .block_on(async { server.attention_agent_clear().await.expect("attention.agent.clear") });
+    let Json(cleared) = runtime.block_on(async {
+        server
+            .attention_agent_clear(Parameters(AttentionAgentParams::default()))
+            .await
+            .expect("attention.agent.clear")
+    });
     assert_eq!(cleared.cleared, 1);
     let Json(attention) = runtime.block_on(async {
         server.attention_agent_read().await.expect("attention.agent.read (cleared)")
@@ -601,6 +615,8 @@ fn e2e_diagram_and_sequence_tools_cover_full_surface() {
         server
             .attention_agent_set(Parameters(AttentionAgentSetParams {
                 object_ref: new_message_ref.clone(),
+                client_id: None,
+                name: None,
             }))
             .await
             .expect("attention.agent.set");
@@ -641,8 +657,9 @@ fn e2e_flow_xref_route_and_attention_agent_set_cover_full_surface() {
             .expect("create seq");
     });
 
-    let Json(diagrams) =
-        runtime.block_on(async { server.diagram_list().await.expect("diagram.list") });
+    let Json(diagrams) = runtime.block_on(async {
+        server.diagram_list(Parameters(DiagramListParams::default())).await.expect("diagram.list")
+    });
     assert_eq!(diagrams.diagrams.len(), 2);
 
     let Json(opened) = runtime.block_on(async {
@@ -707,8 +724,9 @@ fn e2e_flow_xref_route_and_attention_agent_set_cover_full_surface() {
 
     let Json(render) = runtime.block_on(async {
         server
-            .diagram_render_text(Parameters(DiagramTargetParams {
+            .diagram_render_text(Parameters(DiagramRenderTextParams {
                 diagram_id: Some(flow_id.to_owned()),
+                ..Default::default()
             }))
             .await
             .expect("diagram.render_text flow")
@@ -734,7 +752,7 @@ fn e2e_flow_xref_route_and_attention_agent_set_cover_full_surface() {
         server
             .flow_reachable(Parameters(FlowReachableParams {
                 diagram_id: Some(flow_id.to_owned()),
-                from_node_id: "n:a".to_owned(),
+                from_node_id: Some("n:a".to_owned()),
                 direction: Some("out".to_owned()),
             }))
             .await
@@ -806,7 +824,7 @@ fn e2e_flow_xref_route_and_attention_agent_set_cover_full_surface() {
     runtime.block_on(async {
         server
             .xref_add(Parameters(XRefAddParams {
-                xref_id: xref_1.to_owned(),
+                xref_id: Some(xref_1.to_owned()),
                 from: flow_node_d_ref.clone(),
                 to: seq_participant_a_ref.clone(),
                 kind: "rel".to_owned(),
@@ -816,7 +834,7 @@ fn e2e_flow_xref_route_and_attention_agent_set_cover_full_surface() {
             .expect("xref.add");
         server
             .xref_add(Parameters(XRefAddParams {
-                xref_id: xref_2.to_owned(),
+                xref_id: Some(xref_2.to_owned()),
                 from: seq_participant_a_ref.clone(),
                 to: seq_participant_b_ref.clone(),
                 kind: "rel".to_owned(),
@@ -874,6 +892,8 @@ fn e2e_flow_xref_route_and_attention_agent_set_cover_full_surface() {
         server
             .attention_agent_set(Parameters(AttentionAgentSetParams {
                 object_ref: flow_node_d_ref.clone(),
+                client_id: None,
+                name: None,
             }))
             .await
             .expect("attention.agent.set");
@@ -12,44 +12,54 @@ fn diagram_kind_label(kind: DiagramKind) -> &'static str {
     match kind {
         DiagramKind::Sequence => "Sequence",
         DiagramKind::Flowchart => "Flowchart",
+        DiagramKind::State => "State",
+        DiagramKind::Class => "Class",
     }
 }
 
-fn detect_mermaid_kind(input: &str) -> Option<DiagramKind> {
-    for raw_line in input.lines() {
-        let trimmed = raw_line.trim();
-        if trimmed.is_empty() || trimmed.starts_with("%%") {
-            continue;
-        }
-        if trimmed.starts_with("sequenceDiagram") {
-            return Some(DiagramKind::Sequence);
-        }
-        if trimmed.starts_with("flowchart") || trimmed.starts_with("graph") {
-            return Some(DiagramKind::Flowchart);
-        }
-        return None;
+fn session_info(session: &Session) -> SessionInfo {
+    SessionInfo {
+        session_id: session.session_id().as_str().to_owned(),
+        title: session.title().map(ToOwned::to_owned),
+        description: session.description().map(ToOwned::to_owned),
+        tags: session.tags().to_vec(),
+        style_rules: session.style_rules().iter().map(ToString::to_string).collect(),
+        id_prefix_rules: session.id_prefix_rules().iter().map(ToString::to_string).collect(),
     }
-    None
 }
 
-fn allocate_diagram_id(session: &Session, kind: DiagramKind) -> DiagramId {
-    let base = match kind {
-        DiagramKind::Sequence => "seq",
-        DiagramKind::Flowchart => "flow",
-    };
+fn mcp_walkthrough(walkthrough: &Walkthrough) -> McpWalkthrough {
+    let nodes = walkthrough
+        .nodes()
+        .iter()
+        .map(|node| McpWalkthroughNode {
+            node_id: node.node_id().as_str().to_owned(),
+            title: node.title().to_owned(),
+            body_md: node.body_md().map(|body| body.to_owned()),
+            refs: node.refs().iter().map(ToString::to_string).collect(),
+            tags: node.tags().to_vec(),
+            status: node.status().map(|status| status.to_owned()),
+        })
+        .collect::<Vec<_>>();
 
-    if !session.diagrams().contains_key(base) {
-        return DiagramId::new(base.to_owned()).expect("valid diagram id");
-    }
+    let edges = walkthrough
+        .edges()
+        .iter()
+        .map(|edge| McpWalkthroughEdge {
+            from_node_id: edge.from_node_id().as_str().to_owned(),
+            to_node_id: edge.to_node_id().as_str().to_owned(),
+            kind: edge.kind().to_owned(),
+            label: edge.label().map(|label| label.to_owned()),
+        })
+        .collect::<Vec<_>>();
 
-    for idx in 2.. {
-        let candidate = format!("{base}-{idx}");
-        if !session.diagrams().contains_key(candidate.as_str()) {
-            return DiagramId::new(candidate).expect("valid diagram id");
-        }
+    McpWalkthrough {
+        walkthrough_id: walkthrough.walkthrough_id().as_str().to_owned(),
+        title: walkthrough.title().to_owned(),
+        rev: walkthrough.rev(),
+        nodes,
+        edges,
     }
-
-    unreachable!("exhausted diagram id space")
 }
 
 fn resolve_diagram_id(session: &Session, diagram_id: Option<&str>) -> Result<DiagramId, ErrorData> {
@@ -95,6 +105,28 @@ fn digest_for_diagram(diagram: &Diagram) -> DiagramDigest {
             key_names: ast.nodes().values().map(|n| n.label().to_owned()).collect(),
             context: ReadContext::default(),
         },
+        DiagramAst::State(ast) => DiagramDigest {
+            rev: diagram.rev(),
+            counts: DiagramCounts {
+                participants: 0,
+                messages: 0,
+                nodes: ast.states().len() as u64,
+                edges: ast.transitions().len() as u64,
+            },
+            key_names: ast.states().values().map(|s| s.label().to_owned()).collect(),
+            context: ReadContext::default(),
+        },
+        DiagramAst::Class(ast) => DiagramDigest {
+            rev: diagram.rev(),
+            counts: DiagramCounts {
+                participants: 0,
+                messages: 0,
+                nodes: ast.classes().len() as u64,
+                edges: ast.relations().len() as u64,
+            },
+            key_names: ast.classes().values().map(|c| c.label().to_owned()).collect(),
+            context: ReadContext::default(),
+        },
     }
 }
 
@@ -112,7 +144,747 @@ fn mermaid_for_diagram(diagram: &Diagram) -> String {
     match diagram.ast() {
         DiagramAst::Sequence(ast) => mermaid_for_sequence(ast),
         DiagramAst::Flowchart(ast) => mermaid_for_flowchart(ast),
+        DiagramAst::State(ast) => {
+            export_state_diagram(ast).unwrap_or_else(|_| "stateDiagram-v2\n".to_owned())
+        }
+        DiagramAst::Class(ast) => {
+            export_class_diagram(ast).unwrap_or_else(|_| "classDiagram\n".to_owned())
+        }
+    }
+}
+
+fn sequence_critical_path_summary(path: &[crate::query::sequence::CriticalMessage<'_>]) -> String {
+    path.iter()
+        .map(|step| format!("{} ({})", step.message.text(), step.finish))
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+/// Cap on entry points walked, paths and cycles listed by `diagram.summarize`.
+const SUMMARY_LIMIT: usize = 5;
+
+/// Builds the `diagram.summarize` response from structure alone, so the same diagram always
+/// yields the same summary.
+fn diagram_summary(session: &Session, diagram: &Diagram) -> DiagramSummarizeResponse {
+    let diagram_id = diagram.diagram_id();
+    let d = diagram_id.as_str();
+    let mut counts = Vec::<(&str, u64)>::new();
+    let mut labels = BTreeMap::<String, String>::new();
+    let (entry_points, main_paths, cycles) = match diagram.ast() {
+        DiagramAst::Flowchart(ast) => {
+            counts.push(("nodes", ast.nodes().len() as u64));
+            counts.push(("edges", ast.edges().len() as u64));
+            let node_ref = |node_id: &ObjectId| format!("d:{d}/flow/node/{node_id}");
+            for (node_id, node) in ast.nodes() {
+                labels.insert(node_ref(node_id), node.label().to_owned());
+            }
+            graph_orientation(ast, node_ref)
+        }
+        DiagramAst::State(ast) => {
+            counts.push(("states", ast.states().len() as u64));
+            counts.push(("transitions", ast.transitions().len() as u64));
+            let state_ref = |state_id: &ObjectId| format!("d:{d}/state/node/{state_id}");
+            for (state_id, state) in ast.states() {
+                labels.insert(state_ref(state_id), state.label().to_owned());
+            }
+            graph_orientation(&state_transition_graph(ast), state_ref)
+        }
+        DiagramAst::Class(ast) => {
+            counts.push(("classes", ast.classes().len() as u64));
+            counts.push(("relations", ast.relations().len() as u64));
+            let class_ref = |class_id: &ObjectId| format!("d:{d}/class/node/{class_id}");
+            for (class_id, class) in ast.classes() {
+                labels.insert(class_ref(class_id), class.label().to_owned());
+            }
+            graph_orientation(&class_relation_graph(ast), class_ref)
+        }
+        DiagramAst::Sequence(ast) => {
+            counts.push(("participants", ast.participants().len() as u64));
+            counts.push(("messages", ast.messages().len() as u64));
+            let participant_ref =
+                |participant_id: &ObjectId| format!("d:{d}/seq/participant/{participant_id}");
+            for (participant_id, participant) in ast.participants() {
+                labels
+                    .insert(participant_ref(participant_id), participant.mermaid_name().to_owned());
+            }
+            let messages = ast.messages_in_order();
+            let entry_points = messages
+                .first()
+                .map(|message| participant_ref(message.from_participant_id()))
+                .into_iter()
+                .collect::<Vec<_>>();
+            let mut hops = Vec::<String>::new();
+            for message in &messages {
+                for participant_id in [message.from_participant_id(), message.to_participant_id()] {
+                    let participant_ref = participant_ref(participant_id);
+                    if hops.last() != Some(&participant_ref) {
+                        hops.push(participant_ref);
+                    }
+                }
+            }
+            let main_paths = if hops.is_empty() { Vec::new() } else { vec![hops] };
+            (entry_points, main_paths, Vec::new())
+        }
+    };
+
+    let dangling_xrefs = session
+        .xrefs()
+        .iter()
+        .filter(|(_, xref)| {
+            xref.status().is_dangling()
+                && (xref.from().diagram_id() == diagram_id || xref.to().diagram_id() == diagram_id)
+        })
+        .map(|(xref_id, _)| xref_id.as_str().to_owned())
+        .collect::<Vec<_>>();
+
+    let kind = diagram_kind_label(diagram.kind()).to_owned();
+    let label = |object_ref: &String| labels.get(object_ref).unwrap_or(object_ref).clone();
+    let path_text = |path: &Vec<String>| path.iter().map(label).collect::<Vec<_>>().join(" -> ");
+    let list_or_none = |items: Vec<String>, sep: &str| {
+        if items.is_empty() {
+            "none".to_owned()
+        } else {
+            items.join(sep)
+        }
+    };
+    let counts_text = counts
+        .iter()
+        .map(|&(kind, count)| match count {
+            1 => format!("1 {}", kind.trim_end_matches('s')),
+            _ => format!("{count} {kind}"),
+        })
+        .collect::<Vec<_>>();
+    let summary = [
+        format!("{kind} \"{}\" ({d}, rev {})", diagram.name(), diagram.rev()),
+        format!("Counts: {}", counts_text.join(", ")),
+        format!("Entry points: {}", list_or_none(entry_points.iter().map(label).collect(), ", ")),
+        format!("Main paths: {}", list_or_none(main_paths.iter().map(path_text).collect(), "; ")),
+        format!("Cycles: {}", list_or_none(cycles.iter().map(path_text).collect(), "; ")),
+        format!("Dangling xrefs: {}", list_or_none(dangling_xrefs.clone(), ", ")),
+    ]
+    .join("\n");
+
+    DiagramSummarizeResponse {
+        diagram_id: d.to_owned(),
+        kind,
+        rev: diagram.rev(),
+        counts: counts.into_iter().map(|(kind, count)| (kind.to_owned(), count)).collect(),
+        entry_points,
+        main_paths,
+        cycles,
+        dangling_xrefs,
+        summary,
+    }
+}
+
+/// Entry point refs, one main path per entry point and the cycles of a node/edge graph.
+///
+/// A main path runs from an entry point to the dead end it reaches in the most hops (ties go to
+/// the lowest node id), following the shortest route there.
+fn graph_orientation(
+    ast: &FlowchartAst,
+    node_ref: impl Fn(&ObjectId) -> String,
+) -> (Vec<String>, Vec<Vec<String>>, Vec<Vec<String>>) {
+    let entry_ids = crate::query::flow::entry_points(ast);
+    let dead_ends = crate::query::flow::dead_ends(ast);
+    let refs = |path: Vec<ObjectId>| path.iter().map(&node_ref).collect::<Vec<_>>();
+
+    let main_paths = entry_ids
+        .iter()
+        .take(SUMMARY_LIMIT)
+        .filter_map(|entry_id| {
+            dead_ends
+                .iter()
+                .filter_map(|end_id| {
+                    crate::query::flow::paths(ast, entry_id, end_id, 1, 0).into_iter().next()
+                })
+                .max_by_key(|path| (path.len(), std::cmp::Reverse(path.last().cloned())))
+        })
+        .map(refs)
+        .collect();
+    let cycles =
+        crate::query::flow::cycles(ast).into_iter().take(SUMMARY_LIMIT).map(refs).collect();
+    (entry_ids.iter().map(&node_ref).collect(), main_paths, cycles)
+}
+
+/// State transitions as a plain directed graph (unlike the layout graph, loops stay loops), with
+/// the start pseudo-state marked as entry point.
+fn state_transition_graph(ast: &crate::model::StateAst) -> FlowchartAst {
+    let mut graph = FlowchartAst::default();
+    for (state_id, state) in ast.states() {
+        graph.nodes_mut().insert(state_id.clone(), crate::model::FlowNode::new(state.label()));
+    }
+    let start_id = crate::model::StateAst::start_state_id();
+    if ast.states().contains_key(&start_id) {
+        graph.entry_nodes_mut().insert(start_id);
+    }
+    for (transition_id, transition) in ast.transitions() {
+        let edge = crate::model::FlowEdge::new(
+            transition.from_state_id().clone(),
+            transition.to_state_id().clone(),
+        );
+        graph.edges_mut().insert(transition_id.clone(), edge);
+    }
+    graph
+}
+
+/// Class relations as a flowchart graph, parents and wholes first like the class layout.
+fn class_relation_graph(ast: &crate::model::ClassAst) -> FlowchartAst {
+    let mut graph = FlowchartAst::default();
+    for (class_id, class) in ast.classes() {
+        graph.nodes_mut().insert(class_id.clone(), crate::model::FlowNode::new(class.label()));
+    }
+    for (relation_id, relation) in ast.relations() {
+        let (from, to) = (relation.from_class_id().clone(), relation.to_class_id().clone());
+        let edge = if relation.kind().is_structural() {
+            crate::model::FlowEdge::new(to, from)
+        } else {
+            crate::model::FlowEdge::new(from, to)
+        };
+        graph.edges_mut().insert(relation_id.clone(), edge);
+    }
+    graph
+}
+
+fn markdown_for_diagram(diagram: &Diagram) -> String {
+    let mut out = format!("# {}\n\n", diagram.name());
+    if let Some(description) = diagram.description() {
+        out.push_str(description);
+        out.push_str("\n\n");
+    }
+    out.push_str("```mermaid\n");
+    out.push_str(mermaid_for_diagram(diagram).trim_end());
+    out.push_str("\n```\n");
+    out
+}
+
+fn tidy_layout_metrics(metrics: TidyMetrics) -> TidyLayoutMetrics {
+    TidyLayoutMetrics {
+        width: metrics.width as u64,
+        height: metrics.height as u64,
+        compacted_width: metrics.compacted_width as u64,
+        compacted_height: metrics.compacted_height as u64,
+        crossings: metrics.crossings as u64,
+    }
+}
+
+fn object_refs_in_diagram(diagram: &Diagram) -> Vec<ObjectRef> {
+    let object_ref = |category: [&str; 2], object_id: &ObjectId| {
+        let category =
+            CategoryPath::new(category.iter().map(|segment| (*segment).to_owned()).collect())
+                .expect("valid category");
+        ObjectRef::new(diagram.diagram_id().clone(), category, object_id.clone())
+    };
+
+    match diagram.ast() {
+        DiagramAst::Sequence(ast) => ast
+            .participants()
+            .keys()
+            .map(|participant_id| object_ref(["seq", "participant"], participant_id))
+            .chain(
+                ast.messages_in_order()
+                    .into_iter()
+                    .map(|message| object_ref(["seq", "message"], message.message_id())),
+            )
+            .collect(),
+        DiagramAst::Flowchart(ast) => ast
+            .nodes()
+            .keys()
+            .map(|node_id| object_ref(["flow", "node"], node_id))
+            .chain(ast.edges().keys().map(|edge_id| object_ref(["flow", "edge"], edge_id)))
+            .chain(ast.subgraphs().keys().map(|id| object_ref(["flow", "subgraph"], id)))
+            .collect(),
+        DiagramAst::State(ast) => ast
+            .states()
+            .keys()
+            .map(|state_id| object_ref(["state", "node"], state_id))
+            .chain(ast.transitions().keys().map(|id| object_ref(["state", "transition"], id)))
+            .collect(),
+        DiagramAst::Class(ast) => ast
+            .classes()
+            .keys()
+            .map(|class_id| object_ref(["class", "node"], class_id))
+            .chain(ast.relations().keys().map(|id| object_ref(["class", "relation"], id)))
+            .collect(),
+    }
+}
+
+/// [`object_facts`] in their MCP shape.
+fn object_list_items(session: &Session, diagram: &Diagram) -> Vec<ObjectListItem> {
+    object_facts(session, diagram)
+        .into_iter()
+        .map(|facts| ObjectListItem {
+            object_ref: facts.object_ref.to_string(),
+            category: facts.object_ref.category().segments().join("/"),
+            label: facts.label,
+            note: facts.note,
+            tags: facts.tags,
+            metadata: facts.metadata,
+            anchors: facts.anchors.iter().map(ToString::to_string).collect(),
+            adjacency: ObjectAdjacency {
+                incoming: facts.incoming,
+                outgoing: facts.outgoing,
+                xrefs_incoming: facts.xrefs_incoming,
+                xrefs_outgoing: facts.xrefs_outgoing,
+            },
+        })
+        .collect()
+}
+
+fn mcp_object_for_ref(diagram: &Diagram, object_ref: &ObjectRef) -> Result<McpObject, ErrorData> {
+    let segments = object_ref.category().segments();
+    let object_id = object_ref.object_id();
+
+    let object = match (segments, diagram.ast()) {
+        ([left, right], DiagramAst::Sequence(ast)) if left == "seq" && right == "participant" => {
+            let participant = ast.participants().get(object_id).ok_or_else(|| {
+                ErrorData::resource_not_found(
+                    "seq participant not found",
+                    Some(serde_json::json!({ "object_ref": object_ref.to_string() })),
+                )
+            })?;
+
+            McpObject::SeqParticipant {
+                mermaid_name: participant.mermaid_name().to_owned(),
+                role: participant.role().map(|r| r.to_owned()),
+            }
+        }
+        ([left, right], DiagramAst::Sequence(ast)) if left == "seq" && right == "block" => {
+            let block = ast.find_block(object_id).ok_or_else(|| {
+                ErrorData::resource_not_found(
+                    "seq block not found",
+                    Some(serde_json::json!({ "object_ref": object_ref.to_string() })),
+                )
+            })?;
+
+            McpObject::SeqBlock {
+                kind: map_seq_block_kind_to_mcp(block.kind()),
+                header: block.header().map(|h| h.to_owned()),
+                section_ids: block
+                    .sections()
+                    .iter()
+                    .map(|section| section.section_id().to_string())
+                    .collect(),
+                child_block_ids: block
+                    .blocks()
+                    .iter()
+                    .map(|child| child.block_id().to_string())
+                    .collect(),
+            }
+        }
+        ([left, right], DiagramAst::Sequence(ast)) if left == "seq" && right == "section" => {
+            let section = ast.find_section(object_id).ok_or_else(|| {
+                ErrorData::resource_not_found(
+                    "seq section not found",
+                    Some(serde_json::json!({ "object_ref": object_ref.to_string() })),
+                )
+            })?;
+
+            McpObject::SeqSection {
+                kind: map_seq_section_kind_to_mcp(section.kind()),
+                header: section.header().map(|h| h.to_owned()),
+                message_ids: section
+                    .message_ids()
+                    .iter()
+                    .map(|message_id| message_id.to_string())
+                    .collect(),
+            }
+        }
+        ([left, right], DiagramAst::Sequence(ast)) if left == "seq" && right == "group" => {
+            let group = ast.find_group(object_id).ok_or_else(|| {
+                ErrorData::resource_not_found(
+                    "seq group not found",
+                    Some(serde_json::json!({ "object_ref": object_ref.to_string() })),
+                )
+            })?;
+
+            McpObject::SeqGroup {
+                title: group.title().map(|title| title.to_owned()),
+                participant_ids: group
+                    .participant_ids()
+                    .iter()
+                    .map(|participant_id| participant_id.to_string())
+                    .collect(),
+            }
+        }
+        ([left, right], DiagramAst::Sequence(ast)) if left == "seq" && right == "activation" => {
+            let activation = ast.find_activation(object_id).ok_or_else(|| {
+                ErrorData::resource_not_found(
+                    "seq activation not found",
+                    Some(serde_json::json!({ "object_ref": object_ref.to_string() })),
+                )
+            })?;
+
+            McpObject::SeqActivation {
+                participant_id: activation.participant_id().to_string(),
+                start_message_id: activation.start_message_id().map(ToString::to_string),
+                end_message_id: activation.end_message_id().map(ToString::to_string),
+            }
+        }
+        ([left, right], DiagramAst::Sequence(ast)) if left == "seq" && right == "message" => {
+            let message =
+                ast.messages().iter().find(|m| m.message_id() == object_id).ok_or_else(|| {
+                    ErrorData::resource_not_found(
+                        "seq message not found",
+                        Some(serde_json::json!({ "object_ref": object_ref.to_string() })),
+                    )
+                })?;
+
+            McpObject::SeqMessage {
+                from_participant_id: message.from_participant_id().to_string(),
+                to_participant_id: message.to_participant_id().to_string(),
+                kind: map_message_kind_to_mcp(message.kind()),
+                arrow: message.raw_arrow().map(ToOwned::to_owned),
+                text: message.text().to_owned(),
+                order_key: message.order_key(),
+            }
+        }
+        ([left, right], DiagramAst::Flowchart(ast)) if left == "flow" && right == "node" => {
+            let node = ast.nodes().get(object_id).ok_or_else(|| {
+                ErrorData::resource_not_found(
+                    "flow node not found",
+                    Some(serde_json::json!({ "object_ref": object_ref.to_string() })),
+                )
+            })?;
+
+            McpObject::FlowNode {
+                label: node.label().to_owned(),
+                shape: node.shape().to_owned(),
+                mermaid_id: node.mermaid_id().map(|s| s.to_owned()),
+            }
+        }
+        ([left, right], DiagramAst::Flowchart(ast)) if left == "flow" && right == "edge" => {
+            let edge = ast.edges().get(object_id).ok_or_else(|| {
+                ErrorData::resource_not_found(
+                    "flow edge not found",
+                    Some(serde_json::json!({ "object_ref": object_ref.to_string() })),
+                )
+            })?;
+
+            McpObject::FlowEdge {
+                from_node_id: edge.from_node_id().to_string(),
+                to_node_id: edge.to_node_id().to_string(),
+                label: edge.label().map(|s| s.to_owned()),
+                connector: edge.connector().map(|s| s.to_owned()),
+                style: edge.style().map(|s| s.to_owned()),
+            }
+        }
+        ([left, right], DiagramAst::Flowchart(ast)) if left == "flow" && right == "subgraph" => {
+            let subgraph = ast.subgraphs().get(object_id).ok_or_else(|| {
+                ErrorData::resource_not_found(
+                    "flow subgraph not found",
+                    Some(serde_json::json!({ "object_ref": object_ref.to_string() })),
+                )
+            })?;
+
+            McpObject::FlowSubgraph {
+                label: subgraph.label().to_owned(),
+                mermaid_id: subgraph.mermaid_id().map(|s| s.to_owned()),
+                parent_id: subgraph.parent().map(ToString::to_string),
+                node_ids: ast.subgraph_members(object_id).iter().map(ToString::to_string).collect(),
+            }
+        }
+        ([left, right], DiagramAst::State(ast)) if left == "state" && right == "node" => {
+            let state = ast.states().get(object_id).ok_or_else(|| {
+                ErrorData::resource_not_found(
+                    "state not found",
+                    Some(serde_json::json!({ "object_ref": object_ref.to_string() })),
+                )
+            })?;
+
+            McpObject::StateNode {
+                label: state.label().to_owned(),
+                kind: state.kind().as_str().to_owned(),
+                mermaid_id: state.mermaid_id().map(ToOwned::to_owned),
+            }
+        }
+        ([left, right], DiagramAst::State(ast)) if left == "state" && right == "transition" => {
+            let transition = ast.transitions().get(object_id).ok_or_else(|| {
+                ErrorData::resource_not_found(
+                    "state transition not found",
+                    Some(serde_json::json!({ "object_ref": object_ref.to_string() })),
+                )
+            })?;
+
+            McpObject::StateTransition {
+                from_state_id: transition.from_state_id().to_string(),
+                to_state_id: transition.to_state_id().to_string(),
+                label: transition.label().map(ToOwned::to_owned),
+            }
+        }
+        ([left, right], DiagramAst::Class(ast)) if left == "class" && right == "node" => {
+            let class = ast.classes().get(object_id).ok_or_else(|| {
+                ErrorData::resource_not_found(
+                    "class not found",
+                    Some(serde_json::json!({ "object_ref": object_ref.to_string() })),
+                )
+            })?;
+
+            McpObject::ClassNode {
+                label: class.label().to_owned(),
+                mermaid_id: class.mermaid_id().map(ToOwned::to_owned),
+                annotation: class.annotation().map(ToOwned::to_owned),
+                members: class.members().iter().map(|m| m.text().to_owned()).collect(),
+            }
+        }
+        ([left, right], DiagramAst::Class(ast)) if left == "class" && right == "relation" => {
+            let relation = ast.relations().get(object_id).ok_or_else(|| {
+                ErrorData::resource_not_found(
+                    "class relation not found",
+                    Some(serde_json::json!({ "object_ref": object_ref.to_string() })),
+                )
+            })?;
+
+            McpObject::ClassRelation {
+                from_class_id: relation.from_class_id().to_string(),
+                to_class_id: relation.to_class_id().to_string(),
+                kind: relation.kind().as_str().to_owned(),
+                label: relation.label().map(ToOwned::to_owned),
+                from_cardinality: relation.from_cardinality().map(ToOwned::to_owned),
+                to_cardinality: relation.to_cardinality().map(ToOwned::to_owned),
+            }
+        }
+        _ => {
+            return Err(ErrorData::invalid_params(
+                "unsupported category for diagram kind",
+                Some(serde_json::json!({
+                    "object_ref": object_ref.to_string(),
+                    "diagram_kind": diagram_kind_label(diagram.kind()),
+                    "category": segments.to_vec(),
+                })),
+            ));
+        }
+    };
+    Ok(object)
+}
+
+fn diagram_object_ref(
+    diagram_id: &DiagramId,
+    category: [&str; 2],
+    object_id: &ObjectId,
+) -> ObjectRef {
+    let category =
+        CategoryPath::new(category.iter().map(|segment| (*segment).to_owned()).collect())
+            .expect("valid category");
+    ObjectRef::new(diagram_id.clone(), category, object_id.clone())
+}
+
+fn xref_summary(xref_id: &XRefId, xref: &XRef) -> XRefSummary {
+    XRefSummary {
+        xref_id: xref_id.as_str().to_owned(),
+        from: xref.from().to_string(),
+        to: xref.to().to_string(),
+        kind: xref.kind().to_owned(),
+        label: xref.label().map(|label| label.to_owned()),
+        status: xref.status().as_str().to_owned(),
+    }
+}
+
+/// Blocks from the outermost one down to the first block (depth-first) matching `holds`.
+fn seq_block_path<'a>(
+    blocks: &'a [crate::model::seq_ast::SequenceBlock],
+    holds: &dyn Fn(&crate::model::seq_ast::SequenceBlock) -> bool,
+) -> Option<Vec<&'a crate::model::seq_ast::SequenceBlock>> {
+    blocks.iter().find_map(|block| {
+        if holds(block) {
+            return Some(vec![block]);
+        }
+        let mut path = seq_block_path(block.blocks(), holds)?;
+        path.insert(0, block);
+        Some(path)
+    })
+}
+
+/// Refs of the objects enclosing `object_ref`, innermost first.
+fn object_containers(diagram: &Diagram, object_ref: &ObjectRef) -> Vec<String> {
+    let to_ref = |category: [&str; 2], object_id: &ObjectId| {
+        diagram_object_ref(diagram.diagram_id(), category, object_id).to_string()
+    };
+    let object_id = object_ref.object_id();
+    let kind = match object_ref.category().segments() {
+        [_, kind] => kind.as_str(),
+        _ => return Vec::new(),
+    };
+
+    match diagram.ast() {
+        DiagramAst::Sequence(ast) => {
+            if kind == "participant" {
+                return ast
+                    .participant_group(object_id)
+                    .map(|group| to_ref(["seq", "group"], group.group_id()))
+                    .into_iter()
+                    .collect();
+            }
+            let holds_message = |section: &crate::model::seq_ast::SequenceSection| {
+                section.message_ids().contains(object_id)
+            };
+            let holds = |block: &crate::model::seq_ast::SequenceBlock| match kind {
+                "message" => block.sections().iter().any(holds_message),
+                "section" => {
+                    block.sections().iter().any(|section| section.section_id() == object_id)
+                }
+                "block" => block.blocks().iter().any(|child| child.block_id() == object_id),
+                _ => false,
+            };
+            let path = seq_block_path(ast.blocks(), &holds).unwrap_or_default();
+            let section = path
+                .last()
+                .filter(|_| kind == "message")
+                .and_then(|block| block.sections().iter().find(|section| holds_message(section)));
+            section
+                .map(|section| to_ref(["seq", "section"], section.section_id()))
+                .into_iter()
+                .chain(path.iter().rev().map(|block| to_ref(["seq", "block"], block.block_id())))
+                .collect()
+        }
+        DiagramAst::Flowchart(ast) => {
+            let chain = match kind {
+                "node" => ast.node_subgraph_chain(object_id),
+                "subgraph" => {
+                    let mut chain = Vec::new();
+                    let mut current = ast.subgraphs().get(object_id).and_then(|sg| sg.parent());
+                    while let Some(parent) = current.filter(|parent| !chain.contains(parent)) {
+                        chain.push(parent);
+                        current = ast.subgraphs().get(parent).and_then(|sg| sg.parent());
+                    }
+                    chain
+                }
+                _ => Vec::new(),
+            };
+            chain.into_iter().map(|subgraph_id| to_ref(["flow", "subgraph"], subgraph_id)).collect()
+        }
+        DiagramAst::State(_) | DiagramAst::Class(_) => Vec::new(),
+    }
+}
+
+/// Everything `object.get` reports about `object_ref`; the caller fills in the read context.
+fn object_detail(
+    session: &Session,
+    object_ref: &ObjectRef,
+) -> Result<ObjectDetailResponse, ErrorData> {
+    let diagram = session.diagrams().get(object_ref.diagram_id()).ok_or_else(|| {
+        ErrorData::resource_not_found(
+            "diagram not found",
+            Some(serde_json::json!({
+                "diagram_id": object_ref.diagram_id().as_str(),
+                "object_ref": object_ref.to_string(),
+            })),
+        )
+    })?;
+    let object = mcp_object_for_ref(diagram, object_ref)?;
+
+    let rendered = render_diagram_unicode_annotated(diagram).map_err(|err| {
+        ErrorData::internal_error(
+            format!("render error: {err}"),
+            Some(serde_json::json!({ "diagram_id": diagram.diagram_id().as_str() })),
+        )
+    })?;
+    let render_spans = rendered
+        .highlight_index
+        .get(object_ref)
+        .into_iter()
+        .flatten()
+        .map(|&(line, start_col, end_col)| RenderSpan {
+            line: line as u64,
+            start_col: start_col as u64,
+            end_col: end_col as u64,
+        })
+        .collect();
+
+    let xrefs_out = session
+        .xrefs()
+        .iter()
+        .filter(|(_, xref)| xref.from() == object_ref)
+        .map(|(xref_id, xref)| xref_summary(xref_id, xref))
+        .collect();
+    let xrefs_in = session
+        .xrefs()
+        .iter()
+        .filter(|(_, xref)| xref.to() == object_ref)
+        .map(|(xref_id, xref)| xref_summary(xref_id, xref))
+        .collect();
+
+    let walkthrough_steps = session
+        .walkthroughs()
+        .values()
+        .flat_map(|walkthrough| {
+            walkthrough.nodes().iter().filter(|node| node.refs().contains(object_ref)).map(|node| {
+                WalkthroughStepRef {
+                    walkthrough_id: walkthrough.walkthrough_id().to_string(),
+                    node_id: node.node_id().to_string(),
+                    title: node.title().to_owned(),
+                }
+            })
+        })
+        .collect();
+
+    Ok(ObjectDetailResponse {
+        object_ref: object_ref.to_string(),
+        diagram_id: diagram.diagram_id().to_string(),
+        rev: diagram.rev(),
+        object,
+        render_spans,
+        xrefs_out,
+        xrefs_in,
+        containers: object_containers(diagram, object_ref),
+        walkthrough_steps,
+        context: ReadContext::default(),
+    })
+}
+
+/// Markdown rendering of an [`ObjectDetailResponse`] for `object.describe`.
+fn describe_object(detail: &ObjectDetailResponse) -> String {
+    let fields = serde_json::to_value(&detail.object).unwrap_or_default();
+    let mut out = format!("# {}\n\n", detail.object_ref);
+    if let Some(fields) = fields.as_object() {
+        for (key, value) in fields {
+            let value = match value {
+                serde_json::Value::Null => continue,
+                serde_json::Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            out.push_str(&format!("- {key}: {value}\n"));
+        }
+    }
+    out.push_str(&format!("- diagram: {} (rev {})\n", detail.diagram_id, detail.rev));
+
+    let lines = detail.render_spans.iter().map(|span| span.line).collect::<BTreeSet<_>>();
+    match (lines.first(), lines.last()) {
+        (Some(first), Some(last)) if first == last => {
+            out.push_str(&format!("- rendered on line {first}\n"))
+        }
+        (Some(first), Some(last)) => out.push_str(&format!("- rendered on lines {first}-{last}\n")),
+        _ => out.push_str("- not visible in the rendered diagram\n"),
+    }
+    if !detail.containers.is_empty() {
+        out.push_str(&format!("- inside: {}\n", detail.containers.join(" < ")));
     }
+
+    for (heading, xrefs, other_end) in
+        [("Xrefs out", &detail.xrefs_out, true), ("Xrefs in", &detail.xrefs_in, false)]
+    {
+        if xrefs.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n## {heading}\n\n"));
+        for xref in xrefs {
+            let other = if other_end { &xref.to } else { &xref.from };
+            let label =
+                xref.label.as_deref().map(|label| format!(" \"{label}\"")).unwrap_or_default();
+            out.push_str(&format!(
+                "- {} {other}{label} ({}, {})\n",
+                xref.kind, xref.xref_id, xref.status
+            ));
+        }
+    }
+
+    if !detail.walkthrough_steps.is_empty() {
+        out.push_str("\n## Walkthrough steps\n\n");
+        for step in &detail.walkthrough_steps {
+            out.push_str(&format!("- {}/{}: {}\n", step.walkthrough_id, step.node_id, step.title));
+        }
+    }
+    out
 }
 
 fn mcp_ast_for_diagram(diagram: &Diagram) -> McpDiagramAst {
@@ -141,6 +913,7 @@ fn mcp_ast_for_diagram(diagram: &Diagram) -> McpDiagramAst {
                     arrow: message.raw_arrow().map(ToOwned::to_owned),
                     text: message.text().to_owned(),
                     order_key: message.order_key(),
+                    boundary: message.boundary().map(map_message_boundary_to_mcp),
                 })
                 .collect::<Vec<_>>();
             messages.sort_by(|a, b| {
@@ -192,6 +965,60 @@ fn mcp_ast_for_diagram(diagram: &Diagram) -> McpDiagramAst {
 
             McpDiagramAst::Flowchart { nodes, edges }
         }
+        DiagramAst::State(ast) => {
+            let states = ast
+                .states()
+                .iter()
+                .map(|(state_id, state)| McpStateNodeAst {
+                    state_id: state_id.to_string(),
+                    label: state.label().to_owned(),
+                    kind: state.kind().as_str().to_owned(),
+                    mermaid_id: state.mermaid_id().map(ToOwned::to_owned),
+                    note: state.note().map(ToOwned::to_owned),
+                })
+                .collect();
+            let transitions = ast
+                .transitions()
+                .iter()
+                .map(|(transition_id, transition)| McpStateTransitionAst {
+                    transition_id: transition_id.to_string(),
+                    from_state_id: transition.from_state_id().to_string(),
+                    to_state_id: transition.to_state_id().to_string(),
+                    label: transition.label().map(ToOwned::to_owned),
+                })
+                .collect();
+
+            McpDiagramAst::State { states, transitions }
+        }
+        DiagramAst::Class(ast) => {
+            let classes = ast
+                .classes()
+                .iter()
+                .map(|(class_id, class)| McpClassNodeAst {
+                    class_id: class_id.to_string(),
+                    label: class.label().to_owned(),
+                    mermaid_id: class.mermaid_id().map(ToOwned::to_owned),
+                    annotation: class.annotation().map(ToOwned::to_owned),
+                    members: class.members().iter().map(|m| m.text().to_owned()).collect(),
+                    note: class.note().map(ToOwned::to_owned),
+                })
+                .collect();
+            let relations = ast
+                .relations()
+                .iter()
+                .map(|(relation_id, relation)| McpClassRelationAst {
+                    relation_id: relation_id.to_string(),
+                    from_class_id: relation.from_class_id().to_string(),
+                    to_class_id: relation.to_class_id().to_string(),
+                    kind: relation.kind().as_str().to_owned(),
+                    label: relation.label().map(ToOwned::to_owned),
+                    from_cardinality: relation.from_cardinality().map(ToOwned::to_owned),
+                    to_cardinality: relation.to_cardinality().map(ToOwned::to_owned),
+                })
+                .collect();
+
+            McpDiagramAst::Class { classes, relations }
+        }
     }
 }
 
@@ -296,7 +1123,8 @@ fn mermaid_for_sequence(ast: &crate::model::SequenceAst) -> String {
 
 fn mermaid_for_flowchart(ast: &crate::model::FlowchartAst) -> String {
     let mut out = String::new();
-    out.push_str("flowchart TD\n");
+    let direction = ast.direction().unwrap_or(crate::model::FlowDirection::TopDown);
+    out.push_str(&format!("flowchart {}\n", direction.as_mermaid()));
 
     for (node_id, node) in ast.nodes().iter() {
         let mermaid_id = mermaid_safe_id(node_id.as_str());
@@ -385,6 +1213,177 @@ fn mermaid_node_shape(label: &str, shape: Option<&str>) -> String {
     }
 }
 
+/// Delta of `diagram_id` from `since_rev` to `current_rev`, as served by `diagram.diff`.
+fn diagram_delta_since(
+    delta_history: &BTreeMap<DiagramId, VecDeque<LastDelta>>,
+    diagram_id: &DiagramId,
+    current_rev: u64,
+    since_rev: u64,
+) -> Result<DiagramDeltaResponse, ErrorData> {
+    if since_rev > current_rev {
+        return Err(ErrorData::invalid_params(
+            "since_rev must be <= current rev",
+            Some(serde_json::json!({ "since_rev": since_rev, "current_rev": current_rev })),
+        ));
+    }
+
+    if since_rev == current_rev {
+        return Ok(DiagramDeltaResponse {
+            from_rev: current_rev,
+            to_rev: current_rev,
+            changes: Vec::new(),
+        });
+    }
+
+    let Some(history) = delta_history.get(diagram_id) else {
+        return Err(delta_unavailable(since_rev, current_rev, current_rev));
+    };
+
+    let supported_since_rev = history.front().map(|d| d.from_rev).unwrap_or(current_rev);
+    if since_rev < supported_since_rev {
+        return Err(delta_unavailable(since_rev, current_rev, supported_since_rev));
+    }
+
+    delta_response_from_history(history, since_rev, current_rev)
+        .ok_or_else(|| delta_unavailable(since_rev, current_rev, supported_since_rev))
+}
+
+/// Cuts a `width`×`height` window out of `text`, centred on the bounding box of `spans` and
+/// shifted inwards at the edges. Trailing blanks of the clipped lines are dropped.
+fn clip_render_around(
+    text: &str,
+    spans: &[crate::render::LineSpan],
+    width: usize,
+    height: usize,
+) -> (String, RenderClip) {
+    let lines = text.lines().collect::<Vec<_>>();
+    let total_lines = lines.len();
+    let total_cols = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+
+    let (y0, y1) =
+        spans.iter().fold((usize::MAX, 0), |(lo, hi), &(y, _, _)| (lo.min(y), hi.max(y)));
+    let (x0, x1) =
+        spans.iter().fold((usize::MAX, 0), |(lo, hi), &(_, x0, x1)| (lo.min(x0), hi.max(x1)));
+    let start = |lo: usize, hi: usize, size: usize, total: usize| {
+        let center = lo + hi.saturating_sub(lo) / 2;
+        center.saturating_sub(size / 2).min(total.saturating_sub(size))
+    };
+    let line = start(y0, y1, height, total_lines);
+    let col = start(x0, x1, width, total_cols);
+
+    let clipped = lines
+        .iter()
+        .skip(line)
+        .take(height)
+        .map(|text| text.chars().skip(col).take(width).collect::<String>().trim_end().to_owned())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let clip = RenderClip {
+        line: line as u64,
+        col: col as u64,
+        width: width.min(total_cols.saturating_sub(col)) as u64,
+        height: height.min(total_lines.saturating_sub(line)) as u64,
+        total_lines: total_lines as u64,
+        total_cols: total_cols as u64,
+    };
+    (clipped, clip)
+}
+
+/// Crops `text` to the `width`×`height` window at (`line`, `col`) and moves the spans of
+/// `highlight_index` into it; spans outside the window are dropped, partial ones shortened.
+fn crop_render(
+    text: &str,
+    highlight_index: &crate::render::HighlightIndex,
+    (line, col): (usize, usize),
+    (width, height): (usize, usize),
+) -> (String, BTreeMap<String, Vec<[u64; 3]>>, RenderClip) {
+    let lines = text.split('\n').collect::<Vec<_>>();
+    let total_lines = lines.len();
+    let total_cols = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+    let line = line.min(total_lines);
+    let col = col.min(total_cols);
+    let height = height.min(total_lines - line);
+    let width = width.min(total_cols - col);
+
+    let cropped = lines
+        .iter()
+        .skip(line)
+        .take(height)
+        .map(|text| text.chars().skip(col).take(width).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let highlights = highlight_index
+        .iter()
+        .filter_map(|(object_ref, spans)| {
+            let spans = spans
+                .iter()
+                .filter(|&&(y, x0, x1)| {
+                    (line..line + height).contains(&y) && x1 >= col && x0 < col + width
+                })
+                .map(|&(y, x0, x1)| {
+                    let x1 = x1.min(col + width - 1);
+                    [(y - line) as u64, (x0.max(col) - col) as u64, (x1 - col) as u64]
+                })
+                .collect::<Vec<_>>();
+            (!spans.is_empty()).then(|| (object_ref.to_string(), spans))
+        })
+        .collect();
+    let clip = RenderClip {
+        line: line as u64,
+        col: col as u64,
+        width: width as u64,
+        height: height as u64,
+        total_lines: total_lines as u64,
+        total_cols: total_cols as u64,
+    };
+    (cropped, highlights, clip)
+}
+
+/// Line ranges of `text` that show any of `refs` (touching ranges merged), plus those lines
+/// joined with a `⋯` line between regions. Refs without spans are skipped.
+fn changed_render_regions(
+    text: &str,
+    highlight_index: &crate::render::HighlightIndex,
+    refs: &[ObjectRef],
+) -> (String, Vec<RenderRegion>) {
+    let mut ranges = refs
+        .iter()
+        .filter_map(|object_ref| {
+            let spans = highlight_index.get(object_ref).filter(|spans| !spans.is_empty())?;
+            let start = spans.iter().map(|&(y, _, _)| y).min()?;
+            let end = spans.iter().map(|&(y, _, _)| y).max()?;
+            Some((start, end, object_ref.to_string()))
+        })
+        .collect::<Vec<_>>();
+    ranges.sort();
+
+    let mut regions = Vec::<RenderRegion>::new();
+    for (start, end, object_ref) in ranges {
+        match regions.last_mut() {
+            Some(region) if start as u64 <= region.end_line + 1 => {
+                region.end_line = region.end_line.max(end as u64);
+                region.object_refs.push(object_ref);
+            }
+            _ => regions.push(RenderRegion {
+                start_line: start as u64,
+                end_line: end as u64,
+                object_refs: vec![object_ref],
+            }),
+        }
+    }
+
+    let lines = text.lines().collect::<Vec<_>>();
+    let text = regions
+        .iter()
+        .map(|region| {
+            let (start, end) = (region.start_line as usize, region.end_line as usize);
+            lines.get(start..=end.min(lines.len().saturating_sub(1))).unwrap_or_default().join("\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n⋯\n");
+    (text, regions)
+}
+
 fn delta_response_from_history(
     history: &VecDeque<LastDelta>,
     since_rev: u64,
@@ -889,6 +1888,8 @@ fn apply_walkthrough_ops(
 
 fn map_apply_error(err: ApplyError) -> ErrorData {
     match err {
+        ApplyError::ReadOnly { diagram_id } => read_only_error(&diagram_id),
+        ApplyError::LoadFailed { diagram_id, error } => load_failed_error(&diagram_id, &error),
         ApplyError::Conflict {
             base_rev,
             current_rev,
@@ -925,6 +1926,18 @@ fn map_apply_error(err: ApplyError) -> ErrorData {
             "flow node not found",
             Some(serde_json::json!({ "node_id": node_id.to_string() })),
         ),
+        ApplyError::InvalidSeqNoteParticipants {
+            note_id,
+            placement,
+            count,
+        } => ErrorData::invalid_params(
+            "note needs one participant (one or two for over)",
+            Some(serde_json::json!({
+                "note_id": note_id.to_string(),
+                "placement": format!("{placement:?}"),
+                "count": count,
+            })),
+        ),
         ApplyError::InvalidFlowNodeMermaidId { mermaid_id, reason } => ErrorData::invalid_params(
             "invalid flow node Mermaid id",
             Some(serde_json::json!({ "mermaid_id": mermaid_id, "reason": reason.to_string() })),
@@ -936,6 +1949,105 @@ fn map_apply_error(err: ApplyError) -> ErrorData {
             "flow node Mermaid id already in use",
             Some(serde_json::json!({ "mermaid_id": mermaid_id, "node_id": node_id.to_string() })),
         ),
+        ApplyError::XRefAlreadyExists { xref_id } => ErrorData::invalid_params(
+            "xref_id already exists",
+            Some(serde_json::json!({ "xref_id": xref_id.as_str() })),
+        ),
+        ApplyError::XRefNotFound { xref_id } => ErrorData::resource_not_found(
+            "xref not found",
+            Some(serde_json::json!({ "xref_id": xref_id.as_str() })),
+        ),
+    }
+}
+
+fn parse_mermaid_ast(
+    kind: DiagramKind,
+    mermaid: &str,
+    id_prefix_rules: &[IdPrefixRule],
+) -> Result<DiagramAst, ErrorData> {
+    let parse_error = |what: &str, err: &dyn std::fmt::Display| {
+        ErrorData::invalid_params(format!("cannot parse Mermaid {what} diagram: {err}"), None)
+    };
+    Ok(match kind {
+        DiagramKind::Sequence => DiagramAst::Sequence(
+            parse_sequence_diagram_with_id_rules(mermaid, id_prefix_rules)
+                .map_err(|err| parse_error("sequence", &err))?,
+        ),
+        DiagramKind::Flowchart => DiagramAst::Flowchart(
+            parse_flowchart_with_id_rules(mermaid, id_prefix_rules)
+                .map_err(|err| parse_error("flowchart", &err))?,
+        ),
+        DiagramKind::State => DiagramAst::State(
+            parse_state_diagram(mermaid).map_err(|err| parse_error("state", &err))?,
+        ),
+        DiagramKind::Class => DiagramAst::Class(
+            parse_class_diagram(mermaid).map_err(|err| parse_error("class", &err))?,
+        ),
+    })
+}
+
+/// `proposal` in its MCP shape; the delta is computed against the original while it is still at
+/// the proposal's base revision.
+fn mcp_proposal(session: &Session, proposal: &DiagramProposal) -> McpProposal {
+    let original = session.diagrams().get(&proposal.diagram_id);
+    let stale = original.map_or(true, |diagram| diagram.rev() != proposal.base_rev);
+    let (status, accepted_rev) = match proposal.status {
+        ProposalStatus::Pending => ("pending", None),
+        ProposalStatus::Accepted { rev } => ("accepted", Some(rev)),
+        ProposalStatus::Discarded => ("discarded", None),
+    };
+    let delta = original
+        .filter(|_| !stale && proposal.status == ProposalStatus::Pending)
+        .and_then(|diagram| diff_delta(&proposal.diagram_id, diagram.ast(), &proposal.ast))
+        .map(|delta| DeltaSummary {
+            added: delta.added.iter().map(ToString::to_string).collect(),
+            removed: delta.removed.iter().map(ToString::to_string).collect(),
+            updated: delta.updated.iter().map(ToString::to_string).collect(),
+        });
+    McpProposal {
+        proposal_id: proposal.proposal_id.clone(),
+        diagram_id: proposal.diagram_id.to_string(),
+        base_rev: proposal.base_rev,
+        summary: proposal.summary.clone(),
+        status: status.to_owned(),
+        accepted_rev,
+        stale: stale && proposal.status == ProposalStatus::Pending,
+        delta,
+    }
+}
+
+fn read_only_error(diagram_id: &DiagramId) -> ErrorData {
+    ErrorData::invalid_request(
+        "diagram is read-only; unlock it with diagram.set_read_only first",
+        Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+    )
+}
+
+fn load_failed_error(diagram_id: &DiagramId, error: &str) -> ErrorData {
+    ErrorData::invalid_request(
+        "diagram failed to load; fix its .mmd file before editing",
+        Some(serde_json::json!({ "diagram_id": diagram_id.as_str(), "error": error })),
+    )
+}
+
+/// Rejects edits to read-only diagrams and to diagrams whose file failed to parse; saving
+/// those would drop the edit, since their files are never overwritten.
+fn check_diagram_writable(diagram: &Diagram) -> Result<(), ErrorData> {
+    if diagram.is_read_only() {
+        return Err(read_only_error(diagram.diagram_id()));
+    }
+    diagram.ast();
+    match diagram.load_error() {
+        Some(error) => Err(load_failed_error(diagram.diagram_id(), error)),
+        None => Ok(()),
+    }
+}
+
+/// [`check_diagram_writable`] for edits that do not go through ops.
+fn ensure_diagram_writable(session: &Session, diagram_id: &DiagramId) -> Result<(), ErrorData> {
+    match session.diagrams().get(diagram_id) {
+        Some(diagram) => check_diagram_writable(diagram),
+        None => Ok(()),
     }
 }
 
@@ -1077,6 +2189,14 @@ fn mcp_op_to_internal(op: &McpOp) -> Result<Op, ErrorData> {
             participant_id: parse_object_id(participant_id)?,
             note: note.clone(),
         }),
+        McpOp::SeqSetParticipantAnnotations {
+            participant_id,
+            tags,
+            metadata,
+        } => Op::Seq(SeqOp::SetParticipantAnnotations {
+            participant_id: parse_object_id(participant_id)?,
+            annotations: ObjectAnnotations::new(tags.iter().cloned(), metadata.clone()),
+        }),
         McpOp::SeqRemoveParticipant { participant_id } => Op::Seq(SeqOp::RemoveParticipant {
             participant_id: parse_object_id(participant_id)?,
         }),
@@ -1122,9 +2242,71 @@ fn mcp_op_to_internal(op: &McpOp) -> Result<Op, ErrorData> {
                 order_key: *order_key,
             },
         }),
+        McpOp::SeqSetMessageDuration { message_id, duration } => {
+            Op::Seq(SeqOp::SetMessageDuration {
+                message_id: parse_object_id(message_id)?,
+                duration: *duration,
+            })
+        }
+        McpOp::SeqSetMessageBoundary { message_id, boundary } => {
+            Op::Seq(SeqOp::SetMessageBoundary {
+                message_id: parse_object_id(message_id)?,
+                boundary: boundary.map(map_message_boundary),
+            })
+        }
+        McpOp::SeqSetMessageAnnotations {
+            message_id,
+            tags,
+            metadata,
+        } => Op::Seq(SeqOp::SetMessageAnnotations {
+            message_id: parse_object_id(message_id)?,
+            annotations: ObjectAnnotations::new(tags.iter().cloned(), metadata.clone()),
+        }),
         McpOp::SeqRemoveMessage { message_id } => Op::Seq(SeqOp::RemoveMessage {
             message_id: parse_object_id(message_id)?,
         }),
+        McpOp::SeqAddNote {
+            note_id,
+            placement,
+            participant_ids,
+            text,
+            order_key,
+        } => Op::Seq(SeqOp::AddNote {
+            note_id: parse_object_id(note_id)?,
+            placement: map_note_placement(*placement),
+            participant_ids: participant_ids
+                .iter()
+                .map(|participant_id| parse_object_id(participant_id))
+                .collect::<Result<_, _>>()?,
+            text: text.clone(),
+            order_key: *order_key,
+        }),
+        McpOp::SeqUpdateNote {
+            note_id,
+            placement,
+            participant_ids,
+            text,
+            order_key,
+        } => Op::Seq(SeqOp::UpdateNote {
+            note_id: parse_object_id(note_id)?,
+            patch: SeqNotePatch {
+                placement: placement.map(map_note_placement),
+                participant_ids: participant_ids
+                    .as_ref()
+                    .map(|participant_ids| {
+                        participant_ids
+                            .iter()
+                            .map(|participant_id| parse_object_id(participant_id))
+                            .collect::<Result<_, _>>()
+                    })
+                    .transpose()?,
+                text: text.clone(),
+                order_key: *order_key,
+            },
+        }),
+        McpOp::SeqRemoveNote { note_id } => Op::Seq(SeqOp::RemoveNote {
+            note_id: parse_object_id(note_id)?,
+        }),
         McpOp::FlowAddNode {
             node_id,
             label,
@@ -1156,6 +2338,18 @@ fn mcp_op_to_internal(op: &McpOp) -> Result<Op, ErrorData> {
             node_id: parse_object_id(node_id)?,
             note: note.clone(),
         }),
+        McpOp::FlowSetNodeEntry { node_id, entry } => Op::Flow(FlowOp::SetNodeEntry {
+            node_id: parse_object_id(node_id)?,
+            entry: *entry,
+        }),
+        McpOp::FlowSetNodeAnnotations {
+            node_id,
+            tags,
+            metadata,
+        } => Op::Flow(FlowOp::SetNodeAnnotations {
+            node_id: parse_object_id(node_id)?,
+            annotations: ObjectAnnotations::new(tags.iter().cloned(), metadata.clone()),
+        }),
         McpOp::FlowRemoveNode { node_id } => Op::Flow(FlowOp::RemoveNode {
             node_id: parse_object_id(node_id)?,
         }),
@@ -1191,6 +2385,18 @@ fn mcp_op_to_internal(op: &McpOp) -> Result<Op, ErrorData> {
                 style: style.clone(),
             },
         }),
+        McpOp::FlowSetEdgeWeight { edge_id, weight } => Op::Flow(FlowOp::SetEdgeWeight {
+            edge_id: parse_object_id(edge_id)?,
+            weight: *weight,
+        }),
+        McpOp::FlowSetEdgeAnnotations {
+            edge_id,
+            tags,
+            metadata,
+        } => Op::Flow(FlowOp::SetEdgeAnnotations {
+            edge_id: parse_object_id(edge_id)?,
+            annotations: ObjectAnnotations::new(tags.iter().cloned(), metadata.clone()),
+        }),
         McpOp::FlowRemoveEdge { edge_id } => Op::Flow(FlowOp::RemoveEdge {
             edge_id: parse_object_id(edge_id)?,
         }),
@@ -1205,6 +2411,21 @@ fn map_message_kind(kind: MessageKind) -> crate::model::SequenceMessageKind {
     }
 }
 
+fn map_message_boundary(boundary: MessageBoundary) -> crate::model::SequenceMessageBoundary {
+    match boundary {
+        MessageBoundary::Found => crate::model::SequenceMessageBoundary::Found,
+        MessageBoundary::Lost => crate::model::SequenceMessageBoundary::Lost,
+    }
+}
+
+fn map_note_placement(placement: NotePlacement) -> crate::model::SequenceNotePlacement {
+    match placement {
+        NotePlacement::LeftOf => crate::model::SequenceNotePlacement::LeftOf,
+        NotePlacement::RightOf => crate::model::SequenceNotePlacement::RightOf,
+        NotePlacement::Over => crate::model::SequenceNotePlacement::Over,
+    }
+}
+
 fn map_message_kind_to_mcp(kind: crate::model::SequenceMessageKind) -> MessageKind {
     match kind {
         crate::model::SequenceMessageKind::Sync => MessageKind::Sync,
@@ -1212,3 +2433,170 @@ fn map_message_kind_to_mcp(kind: crate::model::SequenceMessageKind) -> MessageKi
         crate::model::SequenceMessageKind::Return => MessageKind::Return,
     }
 }
+
+fn map_message_boundary_to_mcp(
+    boundary: crate::model::SequenceMessageBoundary,
+) -> MessageBoundary {
+    match boundary {
+        crate::model::SequenceMessageBoundary::Found => MessageBoundary::Found,
+        crate::model::SequenceMessageBoundary::Lost => MessageBoundary::Lost,
+    }
+}
+
+fn map_view_zoom(level: ViewZoomLevel) -> ViewZoom {
+    match level {
+        ViewZoomLevel::Glyph => ViewZoom::Glyph,
+        ViewZoomLevel::Mini => ViewZoom::Mini,
+        ViewZoomLevel::Compact => ViewZoom::Compact,
+        ViewZoomLevel::Normal => ViewZoom::Normal,
+        ViewZoomLevel::Spacious => ViewZoom::Spacious,
+    }
+}
+
+fn map_view_zoom_to_mcp(zoom: ViewZoom) -> ViewZoomLevel {
+    match zoom {
+        ViewZoom::Glyph => ViewZoomLevel::Glyph,
+        ViewZoom::Mini => ViewZoomLevel::Mini,
+        ViewZoom::Compact => ViewZoomLevel::Compact,
+        ViewZoom::Normal => ViewZoomLevel::Normal,
+        ViewZoom::Spacious => ViewZoomLevel::Spacious,
+    }
+}
+
+fn duration_ms(duration: Duration) -> u64 {
+    duration.as_millis().min(u128::from(u64::MAX)) as u64
+}
+
+fn unix_now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(duration_ms)
+        .unwrap_or_default()
+}
+
+/// Moves the shared spotlight off `released` agent cursors onto the most recently active cursor
+/// left, or clears it; returns whether it changed.
+fn move_spotlight_off_released_cursors(
+    spotlight: &mut BTreeSet<ObjectRef>,
+    released: &[AgentCursor],
+    cursors: &BTreeMap<String, AgentCursor>,
+) -> bool {
+    let Some(current) = spotlight.iter().next().cloned() else {
+        return false;
+    };
+    if !released.iter().any(|cursor| cursor.object_ref == current)
+        || cursors.values().any(|cursor| cursor.object_ref == current)
+    {
+        return false;
+    }
+    spotlight.clear();
+    if let Some(next) = cursors.values().max_by_key(|cursor| cursor.updated_at_ms) {
+        spotlight.insert(next.object_ref.clone());
+    }
+    true
+}
+
+fn agent_cursor_summaries(
+    cursors: &BTreeMap<String, AgentCursor>,
+    now_ms: u64,
+) -> Vec<AgentCursorSummary> {
+    cursors
+        .iter()
+        .map(|(client_id, cursor)| AgentCursorSummary {
+            client_id: client_id.clone(),
+            name: cursor.name.clone(),
+            color: cursor.color as u64,
+            object_ref: cursor.object_ref.to_string(),
+            idle_ms: now_ms.saturating_sub(cursor.updated_at_ms),
+        })
+        .collect()
+}
+
+/// Behaviours agents may want to check for before relying on them.
+const SERVER_FEATURES: &[&str] = &[
+    "resources",
+    "prompts",
+    "session_changed_notifications",
+    "proposals",
+    "attention_heartbeat",
+    "agent_cursors",
+    "selection_merge",
+    "object_tags",
+    "walkthrough_authoring",
+];
+
+/// The `type` tags of an internally tagged op enum, read from its JSON schema.
+fn op_type_names<T: schemars::JsonSchema>() -> Vec<String> {
+    let schema = serde_json::to_value(schemars::schema_for!(T)).unwrap_or_default();
+    schema
+        .get("oneOf")
+        .and_then(serde_json::Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|variant| variant.pointer("/properties/type/const")?.as_str())
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+fn server_capabilities(
+    tools: Vec<rmcp::model::Tool>,
+    persisted: bool,
+    attention_ttl: Option<Duration>,
+) -> ServerCapabilitiesResponse {
+    let diagram_ops = op_type_names::<McpOp>();
+    let diagram_kinds = [
+        (DiagramKind::Sequence, "sequenceDiagram", "seq_"),
+        (DiagramKind::Flowchart, "flowchart", "flow_"),
+        (DiagramKind::State, "stateDiagram-v2", ""),
+        (DiagramKind::Class, "classDiagram", ""),
+    ]
+    .into_iter()
+    .map(|(kind, mermaid_header, op_prefix)| DiagramKindCapabilities {
+        kind: diagram_kind_label(kind).to_lowercase(),
+        mermaid_header: mermaid_header.to_owned(),
+        ops: diagram_ops
+            .iter()
+            .filter(|op| !op_prefix.is_empty() && op.starts_with(op_prefix))
+            .cloned()
+            .collect(),
+    })
+    .collect();
+
+    let export_formats = [
+        ExportFormat::Mermaid,
+        ExportFormat::Unicode,
+        ExportFormat::Markdown,
+        ExportFormat::Dot,
+        ExportFormat::Svg,
+    ]
+    .into_iter()
+    .filter_map(|format| serde_json::to_value(format).ok()?.as_str().map(ToOwned::to_owned))
+    .collect();
+
+    let mut tools = tools
+        .into_iter()
+        .map(|tool| ToolCapability {
+            group: tool.name.split('.').next().unwrap_or_default().to_owned(),
+            params: tool
+                .input_schema
+                .get("properties")
+                .and_then(serde_json::Value::as_object)
+                .map(|properties| properties.keys().cloned().collect())
+                .unwrap_or_default(),
+            name: tool.name.into_owned(),
+        })
+        .collect::<Vec<_>>();
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+
+    ServerCapabilitiesResponse {
+        version: env!("CARGO_PKG_VERSION").to_owned(),
+        diagram_kinds,
+        walkthrough_ops: op_type_names::<McpWalkthroughOp>(),
+        import_formats: vec!["mermaid".to_owned(), "table".to_owned()],
+        export_formats,
+        features: SERVER_FEATURES.iter().map(|feature| (*feature).to_owned()).collect(),
+        persisted,
+        attention_ttl_ms: attention_ttl.map(duration_ms),
+        tools,
+    }
+}
@@ -0,0 +1,334 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! MCP prompts: guided workflows that package session context into a ready-to-send request.
+//!
+//! - `document-flow` (`diagram_id`): summary, render, Mermaid and xrefs of a diagram, asking for
+//!   a Markdown description saved with `diagram.update_description`
+//! - `explain-selection`: facts, xrefs, walkthrough steps and rendered lines of every selected
+//!   object, asking what the selection does
+//! - `author-walkthrough` (`diagram_id`, `goal`): summary and render of a diagram plus the
+//!   existing walkthroughs, asking for a walkthrough built with the `walkthrough.*` tools
+//!
+//! `diagram_id` defaults to the active diagram.
+
+use std::collections::BTreeMap;
+
+use rmcp::model::{
+    GetPromptResult, JsonObject, Prompt, PromptArgument, PromptMessage, PromptMessageRole,
+};
+use rmcp::ErrorData;
+
+use super::{describe_object, diagram_summary, mermaid_for_diagram, object_detail};
+use crate::model::{Diagram, DiagramId, ObjectRef, Session};
+use crate::render::{render_diagram_unicode, render_diagram_unicode_annotated, AnnotatedRender};
+
+pub(super) const DOCUMENT_FLOW: &str = "document-flow";
+pub(super) const EXPLAIN_SELECTION: &str = "explain-selection";
+pub(super) const AUTHOR_WALKTHROUGH: &str = "author-walkthrough";
+
+/// Longest diagram render quoted in a prompt; longer ones point at the render resource.
+const MAX_RENDER_LINES: usize = 120;
+/// Longest excerpt of rendered lines quoted per selected object.
+const MAX_EXCERPT_LINES: usize = 24;
+
+pub(super) fn list_prompts() -> Vec<Prompt> {
+    let argument = |name: &str, description: &str| PromptArgument {
+        name: name.to_owned(),
+        title: None,
+        description: Some(description.to_owned()),
+        required: Some(false),
+    };
+    let diagram_id = || argument("diagram_id", "Diagram to use; defaults to the active diagram");
+
+    vec![
+        Prompt::new(
+            DOCUMENT_FLOW,
+            Some("Write a Markdown description of a diagram from its render, Mermaid and xrefs"),
+            Some(vec![diagram_id()]),
+        ),
+        Prompt::new(
+            EXPLAIN_SELECTION,
+            Some("Explain the objects in the shared selection with their facts and xrefs"),
+            None,
+        ),
+        Prompt::new(
+            AUTHOR_WALKTHROUGH,
+            Some("Author a step-by-step walkthrough of a diagram with the walkthrough tools"),
+            Some(vec![
+                diagram_id(),
+                argument("goal", "What the walkthrough should teach, e.g. `the retry path`"),
+            ]),
+        ),
+    ]
+}
+
+pub(super) fn get_prompt(
+    session: &Session,
+    name: &str,
+    arguments: Option<&JsonObject>,
+) -> Result<GetPromptResult, ErrorData> {
+    let argument = |key: &str| {
+        arguments
+            .and_then(|arguments| arguments.get(key))
+            .and_then(serde_json::Value::as_str)
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+    let (description, text) = match name {
+        DOCUMENT_FLOW => {
+            let diagram = prompt_diagram(session, argument("diagram_id"))?;
+            (format!("Document {}", diagram.diagram_id()), document_flow(session, diagram)?)
+        }
+        EXPLAIN_SELECTION => ("Explain the selection".to_owned(), explain_selection(session)?),
+        AUTHOR_WALKTHROUGH => {
+            let diagram = prompt_diagram(session, argument("diagram_id"))?;
+            (
+                format!("Author a walkthrough of {}", diagram.diagram_id()),
+                author_walkthrough(session, diagram, argument("goal"))?,
+            )
+        }
+        _ => {
+            return Err(ErrorData::invalid_params(
+                "unknown prompt (expected document-flow|explain-selection|author-walkthrough)",
+                Some(serde_json::json!({ "name": name })),
+            ));
+        }
+    };
+
+    Ok(GetPromptResult {
+        description: Some(description),
+        messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+    })
+}
+
+fn prompt_diagram<'a>(
+    session: &'a Session,
+    diagram_id: Option<&str>,
+) -> Result<&'a Diagram, ErrorData> {
+    let diagram_id = super::resolve_diagram_id(session, diagram_id)?;
+    session.diagrams().get(&diagram_id).ok_or_else(|| {
+        ErrorData::resource_not_found(
+            "diagram not found",
+            Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+        )
+    })
+}
+
+fn render_error(diagram_id: &DiagramId, err: impl std::fmt::Display) -> ErrorData {
+    ErrorData::internal_error(
+        format!("render error: {err}"),
+        Some(serde_json::json!({ "diagram_id": diagram_id.as_str() })),
+    )
+}
+
+fn document_flow(session: &Session, diagram: &Diagram) -> Result<String, ErrorData> {
+    let diagram_id = diagram.diagram_id();
+    let summary = diagram_summary(session, diagram);
+    let mut out = format!(
+        "Document the diagram \"{}\" ({diagram_id}, {}, rev {}).\n\n\
+         Write a Markdown description for readers who have not seen it: what it models, the main \
+         path through it, branches and failure cases, and how it connects to other diagrams. \
+         Cite object refs (`d:...`) for the objects you mention. Save it with \
+         `diagram.update_description` unless you are only asked for a draft.\n",
+        diagram.name(),
+        summary.kind,
+        diagram.rev(),
+    );
+
+    out.push_str("\n## Current description\n\n");
+    out.push_str(diagram.description().unwrap_or("None."));
+    out.push('\n');
+    push_section(&mut out, "Summary", &summary.summary);
+    push_render(&mut out, diagram)?;
+    out.push_str("\n## Mermaid\n\n```mermaid\n");
+    out.push_str(&mermaid_for_diagram(diagram));
+    out.push_str("```\n");
+
+    out.push_str("\n## Xrefs\n\n");
+    let mut any = false;
+    for (xref_id, xref) in session.xrefs() {
+        if xref.from().diagram_id() != diagram_id && xref.to().diagram_id() != diagram_id {
+            continue;
+        }
+        any = true;
+        let label = xref.label().map(|label| format!(" \"{label}\"")).unwrap_or_default();
+        out.push_str(&format!(
+            "- {} -> {}{label} ({}, {xref_id}, {})\n",
+            xref.from(),
+            xref.to(),
+            xref.kind(),
+            xref.status().as_str()
+        ));
+    }
+    if !any {
+        out.push_str("None.\n");
+    }
+    Ok(out)
+}
+
+fn explain_selection(session: &Session) -> Result<String, ErrorData> {
+    let selected = session.selected_object_refs();
+    if selected.is_empty() {
+        return Err(ErrorData::invalid_params(
+            "the selection is empty; select objects in the TUI or with selection.update first",
+            None,
+        ));
+    }
+
+    let mut out = format!(
+        "Explain the {} selected object(s) to the human: what each one is, what it does in its \
+         diagram, and how the selection fits together. Use `object.get`, `xref.neighbors` or \
+         `flow.paths` when you need more context, and cite object refs.\n",
+        selected.len()
+    );
+    let mut renders = BTreeMap::<DiagramId, AnnotatedRender>::new();
+    for object_ref in selected {
+        let detail = object_detail(session, object_ref)?;
+        out.push('\n');
+        out.push_str(&describe_object(&detail).replacen("# ", "## ", 1));
+
+        let diagram_id = object_ref.diagram_id();
+        if !renders.contains_key(diagram_id) {
+            let diagram = &session.diagrams()[diagram_id];
+            let render = render_diagram_unicode_annotated(diagram)
+                .map_err(|err| render_error(diagram_id, err))?;
+            renders.insert(diagram_id.clone(), render);
+        }
+        if let Some(excerpt) = render_excerpt(&renders[diagram_id], object_ref) {
+            out.push_str("\nRendered:\n\n```text\n");
+            out.push_str(&excerpt);
+            out.push_str("```\n");
+        }
+    }
+    Ok(out)
+}
+
+/// Rendered lines showing `object_ref`, with one line of context on either side.
+fn render_excerpt(render: &AnnotatedRender, object_ref: &ObjectRef) -> Option<String> {
+    let spans = render.highlight_index.get(object_ref)?;
+    let first = spans.iter().map(|&(line, _, _)| line).min()?.saturating_sub(1);
+    let last = spans.iter().map(|&(line, _, _)| line).max()? + 1;
+    let last = last.min(first + MAX_EXCERPT_LINES - 1);
+    let mut excerpt = String::new();
+    for line in render.text.lines().skip(first).take(last + 1 - first) {
+        excerpt.push_str(line.trim_end());
+        excerpt.push('\n');
+    }
+    Some(excerpt)
+}
+
+fn author_walkthrough(
+    session: &Session,
+    diagram: &Diagram,
+    goal: Option<&str>,
+) -> Result<String, ErrorData> {
+    let diagram_id = diagram.diagram_id();
+    let goal = goal.map(|goal| format!(" that explains {goal}")).unwrap_or_default();
+    let mut out = format!(
+        "Author a walkthrough of \"{}\" ({diagram_id}){goal}.\n\n\
+         Create it with `walkthrough.create`, add one step per stop with `walkthrough.node.add` \
+         (a short title, a Markdown `body_md` and the object refs the step is about) and link \
+         consecutive steps with `walkthrough.edge.add`. Start at an entry point, follow the main \
+         path, and keep each step to one idea.\n",
+        diagram.name()
+    );
+
+    push_section(&mut out, "Summary", &diagram_summary(session, diagram).summary);
+    push_render(&mut out, diagram)?;
+
+    out.push_str("\n## Existing walkthroughs\n\n");
+    if session.walkthroughs().is_empty() {
+        out.push_str("None.\n");
+    }
+    for (walkthrough_id, walkthrough) in session.walkthroughs() {
+        out.push_str(&format!(
+            "- {walkthrough_id}: {} ({} steps)\n",
+            walkthrough.title(),
+            walkthrough.nodes().len()
+        ));
+    }
+    Ok(out)
+}
+
+fn push_section(out: &mut String, heading: &str, body: &str) {
+    out.push_str(&format!("\n## {heading}\n\n{}\n", body.trim_end()));
+}
+
+fn push_render(out: &mut String, diagram: &Diagram) -> Result<(), ErrorData> {
+    let diagram_id = diagram.diagram_id();
+    let text = render_diagram_unicode(diagram).map_err(|err| render_error(diagram_id, err))?;
+    out.push_str("\n## Render\n\n```text\n");
+    for line in text.lines().take(MAX_RENDER_LINES) {
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out.push_str("```\n");
+    let lines = text.lines().count();
+    if lines > MAX_RENDER_LINES {
+        out.push_str(&format!(
+            "\n({} more lines; read `nereid://diagram/{diagram_id}/render` for the rest.)\n",
+            lines - MAX_RENDER_LINES
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rmcp::model::PromptMessageContent;
+
+    use super::{get_prompt, list_prompts};
+    use crate::tui::demo_session;
+
+    fn prompt_text(result: rmcp::model::GetPromptResult) -> String {
+        let [message] = <[_; 1]>::try_from(result.messages).expect("one message");
+        let PromptMessageContent::Text { text } = message.content else {
+            panic!("expected text content");
+        };
+        text
+    }
+
+    #[test]
+    fn prompts_package_diagram_selection_and_walkthrough_context() {
+        let mut session = demo_session();
+        let names = list_prompts().into_iter().map(|prompt| prompt.name).collect::<Vec<_>>();
+        assert_eq!(names, ["document-flow", "explain-selection", "author-walkthrough"]);
+
+        let diagram_id = session.active_diagram_id().cloned().expect("active diagram");
+        let text = prompt_text(get_prompt(&session, "document-flow", None).expect("prompt"));
+        assert!(text.contains(&format!("({diagram_id}, ")), "{text}");
+        for heading in ["## Summary", "## Render", "## Mermaid", "## Xrefs"] {
+            assert!(text.contains(heading), "{heading}: {text}");
+        }
+
+        let other_id = session.diagrams().keys().find(|id| **id != diagram_id).expect("diagram");
+        let arguments = serde_json::json!({ "diagram_id": other_id.as_str(), "goal": "the build" });
+        let text = prompt_text(
+            get_prompt(&session, "author-walkthrough", arguments.as_object()).expect("prompt"),
+        );
+        assert!(text.contains(&format!("({other_id}) that explains the build.")), "{text}");
+        assert!(text.contains("## Existing walkthroughs"), "{text}");
+
+        assert!(get_prompt(&session, "explain-selection", None).is_err());
+        let object_ref = session
+            .diagrams()
+            .values()
+            .find_map(|diagram| {
+                let render = crate::render::render_diagram_unicode_annotated(diagram).ok()?;
+                render.highlight_index.keys().next().cloned()
+            })
+            .expect("rendered object");
+        session.set_selected_object_refs([object_ref.clone()].into_iter().collect());
+        let text = prompt_text(get_prompt(&session, "explain-selection", None).expect("prompt"));
+        assert!(text.contains(&format!("## {object_ref}\n")), "{text}");
+        assert!(text.contains("Rendered:\n\n```text\n"), "{text}");
+
+        assert!(get_prompt(&session, "nope", None).is_err());
+    }
+}
@@ -0,0 +1,355 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! MCP resources: read-only views of the session for clients that prefer resources over tools.
+//!
+//! - `nereid://session`: session metadata (JSON)
+//! - `nereid://diagram/<id>`: Mermaid source
+//! - `nereid://diagram/<id>/render`: Unicode render
+//! - `nereid://walkthrough/<id>`: walkthrough nodes and edges (JSON)
+//!
+//! Each resource has a revision (diagram/walkthrough rev, a digest for session meta); clients
+//! that subscribe to a URI are notified when its revision changes.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::{Hash, Hasher};
+
+use rmcp::model::{
+    AnnotateAble, RawResource, RawResourceTemplate, Resource, ResourceContents, ResourceTemplate,
+};
+use rmcp::service::{Peer, RoleServer};
+use rmcp::ErrorData;
+
+use super::{mcp_walkthrough, mermaid_for_diagram, session_info};
+use crate::model::{DiagramId, Session, WalkthroughId};
+use crate::render::render_diagram_unicode;
+
+const SCHEME: &str = "nereid://";
+pub(super) const SESSION_URI: &str = "nereid://session";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum ResourceUri {
+    Session,
+    Diagram(DiagramId),
+    DiagramRender(DiagramId),
+    Walkthrough(WalkthroughId),
+}
+
+impl ResourceUri {
+    pub(super) fn parse(uri: &str) -> Option<Self> {
+        if uri == SESSION_URI {
+            return Some(Self::Session);
+        }
+        let path = uri.strip_prefix(SCHEME)?;
+        if let Some(id) = path.strip_prefix("diagram/") {
+            return match id.strip_suffix("/render") {
+                Some(id) => DiagramId::new(id).ok().map(Self::DiagramRender),
+                None => DiagramId::new(id).ok().map(Self::Diagram),
+            };
+        }
+        WalkthroughId::new(path.strip_prefix("walkthrough/")?).ok().map(Self::Walkthrough)
+    }
+}
+
+impl std::fmt::Display for ResourceUri {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Session => f.write_str(SESSION_URI),
+            Self::Diagram(diagram_id) => write!(f, "{SCHEME}diagram/{diagram_id}"),
+            Self::DiagramRender(diagram_id) => write!(f, "{SCHEME}diagram/{diagram_id}/render"),
+            Self::Walkthrough(walkthrough_id) => {
+                write!(f, "{SCHEME}walkthrough/{walkthrough_id}")
+            }
+        }
+    }
+}
+
+pub(super) fn list_resources(session: &Session) -> Vec<Resource> {
+    let resource = |uri: ResourceUri, name: String, description: String, mime_type: &str| {
+        RawResource {
+            description: Some(description),
+            mime_type: Some(mime_type.to_owned()),
+            ..RawResource::new(uri.to_string(), name)
+        }
+        .no_annotation()
+    };
+
+    let mut resources = vec![resource(
+        ResourceUri::Session,
+        "session".to_owned(),
+        "Session title, description and tags".to_owned(),
+        "application/json",
+    )];
+    for (diagram_id, diagram) in session.diagrams().iter().filter(|(_, d)| !d.is_archived()) {
+        resources.push(resource(
+            ResourceUri::Diagram(diagram_id.clone()),
+            diagram.name().to_owned(),
+            format!("Mermaid source of {diagram_id} (rev {})", diagram.rev()),
+            "text/vnd.mermaid",
+        ));
+        resources.push(resource(
+            ResourceUri::DiagramRender(diagram_id.clone()),
+            format!("{} (render)", diagram.name()),
+            format!("Unicode render of {diagram_id} (rev {})", diagram.rev()),
+            "text/plain",
+        ));
+    }
+    for (walkthrough_id, walkthrough) in session.walkthroughs() {
+        resources.push(resource(
+            ResourceUri::Walkthrough(walkthrough_id.clone()),
+            walkthrough.title().to_owned(),
+            format!("Walkthrough {walkthrough_id} (rev {})", walkthrough.rev()),
+            "application/json",
+        ));
+    }
+    resources
+}
+
+pub(super) fn list_resource_templates() -> Vec<ResourceTemplate> {
+    [
+        ("nereid://diagram/{diagram_id}", "diagram", "Mermaid source of a diagram"),
+        ("nereid://diagram/{diagram_id}/render", "diagram render", "Unicode render of a diagram"),
+        ("nereid://walkthrough/{walkthrough_id}", "walkthrough", "Walkthrough nodes and edges"),
+    ]
+    .into_iter()
+    .map(|(uri_template, name, description)| {
+        RawResourceTemplate {
+            uri_template: uri_template.to_owned(),
+            name: name.to_owned(),
+            title: None,
+            description: Some(description.to_owned()),
+            mime_type: None,
+            icons: None,
+        }
+        .no_annotation()
+    })
+    .collect()
+}
+
+pub(super) fn read_resource(session: &Session, uri: &str) -> Result<ResourceContents, ErrorData> {
+    let not_found = || {
+        ErrorData::resource_not_found("resource not found", Some(serde_json::json!({ "uri": uri })))
+    };
+    let parsed = ResourceUri::parse(uri).ok_or_else(|| {
+        ErrorData::invalid_params("invalid resource uri", Some(serde_json::json!({ "uri": uri })))
+    })?;
+
+    let (text, mime_type) = match &parsed {
+        ResourceUri::Session => (to_json(&session_info(session))?, "application/json"),
+        ResourceUri::Diagram(diagram_id) => {
+            let diagram = session.diagrams().get(diagram_id).ok_or_else(not_found)?;
+            (mermaid_for_diagram(diagram), "text/vnd.mermaid")
+        }
+        ResourceUri::DiagramRender(diagram_id) => {
+            let diagram = session.diagrams().get(diagram_id).ok_or_else(not_found)?;
+            let text = render_diagram_unicode(diagram).map_err(|err| {
+                ErrorData::internal_error(
+                    format!("render error: {err}"),
+                    Some(serde_json::json!({ "uri": uri })),
+                )
+            })?;
+            (text, "text/plain")
+        }
+        ResourceUri::Walkthrough(walkthrough_id) => {
+            let walkthrough = session.walkthroughs().get(walkthrough_id).ok_or_else(not_found)?;
+            (to_json(&mcp_walkthrough(walkthrough))?, "application/json")
+        }
+    };
+
+    Ok(ResourceContents::TextResourceContents {
+        uri: uri.to_owned(),
+        mime_type: Some(mime_type.to_owned()),
+        text,
+        meta: None,
+    })
+}
+
+fn to_json(value: &impl serde::Serialize) -> Result<String, ErrorData> {
+    serde_json::to_string_pretty(value)
+        .map_err(|err| ErrorData::internal_error(format!("serialize error: {err}"), None))
+}
+
+/// Revision of every resource in `session`, keyed by URI.
+pub(super) fn resource_revs(session: &Session) -> BTreeMap<String, u64> {
+    let mut hasher = DefaultHasher::new();
+    (session.title(), session.description(), session.tags()).hash(&mut hasher);
+
+    let mut revs = BTreeMap::from([(SESSION_URI.to_owned(), hasher.finish())]);
+    for (diagram_id, diagram) in session.diagrams() {
+        revs.insert(ResourceUri::Diagram(diagram_id.clone()).to_string(), diagram.rev());
+        revs.insert(ResourceUri::DiagramRender(diagram_id.clone()).to_string(), diagram.rev());
+    }
+    for (walkthrough_id, walkthrough) in session.walkthroughs() {
+        revs.insert(
+            ResourceUri::Walkthrough(walkthrough_id.clone()).to_string(),
+            walkthrough.rev(),
+        );
+    }
+    revs
+}
+
+/// URIs whose revision differs between `before` and `after` (including removed ones), and
+/// whether resources were added or removed.
+pub(super) fn changed_resources(
+    before: &BTreeMap<String, u64>,
+    after: &BTreeMap<String, u64>,
+) -> (Vec<String>, bool) {
+    let list_changed = !before.keys().eq(after.keys());
+    let changed = before
+        .iter()
+        .filter(|(uri, rev)| after.get(*uri) != Some(rev))
+        .map(|(uri, _)| uri.clone())
+        .collect();
+    (changed, list_changed)
+}
+
+struct ResourceClient {
+    peer: Peer<RoleServer>,
+    subscriptions: BTreeSet<String>,
+}
+
+/// Connected clients with their resource subscriptions, plus the revisions they were last told
+/// about.
+#[derive(Default)]
+pub(super) struct ResourceClients {
+    clients: Vec<ResourceClient>,
+    revs: Option<BTreeMap<String, u64>>,
+}
+
+impl ResourceClients {
+    pub(super) fn connect(&mut self, peer: Peer<RoleServer>, session: &Session) {
+        self.clients.retain(|client| !client.peer.is_transport_closed());
+        if !self.clients.iter().any(|client| same_peer(&client.peer, &peer)) {
+            self.clients.push(ResourceClient { peer, subscriptions: BTreeSet::new() });
+        }
+        self.revs.get_or_insert_with(|| resource_revs(session));
+    }
+
+    pub(super) fn set_subscribed(
+        &mut self,
+        peer: Peer<RoleServer>,
+        uri: String,
+        subscribed: bool,
+        session: &Session,
+    ) {
+        self.connect(peer.clone(), session);
+        if let Some(client) = self.clients.iter_mut().find(|client| same_peer(&client.peer, &peer))
+        {
+            if subscribed {
+                client.subscriptions.insert(uri);
+            } else {
+                client.subscriptions.remove(&uri);
+            }
+        }
+    }
+
+    /// Records the current revisions and returns the notifications they call for: subscribed
+    /// URIs that changed per peer, and peers to tell that the resource list changed.
+    pub(super) fn record(&mut self, session: &Session) -> ResourceNotifications {
+        let revs = resource_revs(session);
+        let Some(before) = self.revs.replace(revs.clone()) else {
+            return ResourceNotifications::default();
+        };
+        let (changed, list_changed) = changed_resources(&before, &revs);
+
+        self.clients.retain(|client| !client.peer.is_transport_closed());
+        let mut notifications = ResourceNotifications::default();
+        for client in &self.clients {
+            for uri in changed.iter().filter(|uri| client.subscriptions.contains(*uri)) {
+                notifications.updated.push((client.peer.clone(), uri.clone()));
+            }
+            if list_changed {
+                notifications.list_changed.push(client.peer.clone());
+            }
+        }
+        notifications
+    }
+}
+
+#[derive(Default)]
+pub(super) struct ResourceNotifications {
+    pub(super) updated: Vec<(Peer<RoleServer>, String)>,
+    pub(super) list_changed: Vec<Peer<RoleServer>>,
+}
+
+/// Peers have no identity of their own; clones of one peer share the client info recorded at
+/// initialization, so compare that.
+fn same_peer(a: &Peer<RoleServer>, b: &Peer<RoleServer>) -> bool {
+    match (a.peer_info(), b.peer_info()) {
+        (Some(a), Some(b)) => std::ptr::eq(a, b),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{
+        changed_resources, list_resources, mermaid_for_diagram, read_resource, resource_revs,
+        ResourceUri,
+    };
+    use crate::tui::demo_session;
+
+    #[test]
+    fn lists_and_reads_session_diagram_and_walkthrough_resources() {
+        let session = demo_session();
+        let uris = list_resources(&session)
+            .into_iter()
+            .map(|resource| resource.raw.uri)
+            .collect::<Vec<_>>();
+        assert_eq!(uris.len(), 1 + 2 * session.diagrams().len() + session.walkthroughs().len());
+
+        for uri in &uris {
+            let parsed = ResourceUri::parse(uri).expect("listed uri parses");
+            assert_eq!(&parsed.to_string(), uri);
+            read_resource(&session, uri).expect("listed uri reads");
+        }
+
+        let (diagram_id, diagram) = session.diagrams().iter().next().expect("demo diagram");
+        let uri = format!("nereid://diagram/{diagram_id}/render");
+        assert_eq!(ResourceUri::parse(&uri), Some(ResourceUri::DiagramRender(diagram_id.clone())));
+        let rmcp::model::ResourceContents::TextResourceContents { text, .. } =
+            read_resource(&session, &format!("nereid://diagram/{diagram_id}")).expect("read")
+        else {
+            panic!("expected text contents");
+        };
+        assert_eq!(text, mermaid_for_diagram(diagram));
+
+        assert!(read_resource(&session, "nereid://diagram/missing").is_err());
+        assert!(read_resource(&session, "file:///etc/passwd").is_err());
+        assert_eq!(ResourceUri::parse("nereid://diagram/a/b"), None);
+    }
+
+    #[test]
+    fn reports_changed_revisions_and_list_changes() {
+        let mut session = demo_session();
+        let before = resource_revs(&session);
+        let diagram_id = session.diagrams().keys().next().cloned().expect("demo diagram");
+        session.diagrams_mut().get_mut(&diagram_id).expect("diagram").bump_rev();
+
+        let (changed, list_changed) = changed_resources(&before, &resource_revs(&session));
+        assert_eq!(
+            changed,
+            vec![
+                format!("nereid://diagram/{diagram_id}"),
+                format!("nereid://diagram/{diagram_id}/render"),
+            ]
+        );
+        assert!(!list_changed);
+
+        session.set_title(Some("Renamed".to_owned()));
+        session.diagrams_mut().remove(&diagram_id);
+        let (changed, list_changed) = changed_resources(&before, &resource_revs(&session));
+        assert!(changed.contains(&"nereid://session".to_owned()));
+        assert!(list_changed);
+        assert_eq!(changed_resources(&before, &before), (Vec::new(), false));
+        assert_eq!(changed_resources(&BTreeMap::new(), &BTreeMap::new()), (Vec::new(), false));
+    }
+}
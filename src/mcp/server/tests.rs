@@ -13,6 +13,8 @@ use crate::model::{
     SequenceMessageKind, SequenceParticipant, SessionId, Walkthrough, WalkthroughEdge,
     WalkthroughId, WalkthroughNode, WalkthroughNodeId, XRef, XRefId, XRefStatus,
 };
+use crate::store::LoadMode;
+use crate::ui::{HumanScreen, HumanViewport};
 use std::str::FromStr;
 
 fn temp_session_dir(test_name: &str) -> std::path::PathBuf {
@@ -586,6 +588,206 @@ async fn attention_human_and_follow_ai_read_return_stable_defaults_without_ui_st
     assert_eq!(follow_ai.context.ui_session_rev, None);
 }
 
+#[tokio::test]
+async fn diagram_propose_rewrite_stores_proposals_outside_the_session() {
+    let mut session = Session::new(SessionId::new("s:proposals").expect("session id"));
+    let diagram_id = DiagramId::new("d-flow").expect("diagram id");
+    let ast = parse_flowchart_with_id_rules("flowchart TD\nA --> B\n", &[]).expect("parse");
+    let diagram = Diagram::new(diagram_id.clone(), "Flow", DiagramAst::Flowchart(ast));
+    session.diagrams_mut().insert(diagram_id, diagram);
+    let ui_state = Arc::new(Mutex::new(UiState::default()));
+    let server = NereidMcp::new_with_agent_highlights_and_ui_state(
+        session.clone(),
+        Arc::new(Mutex::new(BTreeSet::new())),
+        Some(ui_state.clone()),
+    );
+    let propose = |mermaid: Option<&str>, ops: Option<Vec<McpOp>>| DiagramProposeRewriteParams {
+        diagram_id: Some("d-flow".into()),
+        base_rev: 0,
+        mermaid: mermaid.map(Into::into),
+        ops,
+        summary: Some(" add C ".into()),
+    };
+
+    let Json(by_mermaid) = server
+        .diagram_propose_rewrite(Parameters(propose(
+            Some("flowchart TD\nA --> B\nB --> C\n"),
+            None,
+        )))
+        .await
+        .expect("propose mermaid");
+    let proposal = by_mermaid.proposal;
+    assert_eq!(proposal.proposal_id, "proposal:1");
+    assert_eq!(proposal.status, "pending");
+    assert_eq!(proposal.summary.as_deref(), Some("add C"));
+    assert!(!proposal.stale);
+    let delta = proposal.delta.expect("delta");
+    assert!(delta.added.contains(&"d:d-flow/flow/node/n:C".to_owned()));
+
+    let Json(current) = server
+        .diagram_stat(Parameters(DiagramTargetParams { diagram_id: Some("d-flow".into()) }))
+        .await
+        .expect("stat");
+    assert_eq!(current.rev, 0);
+
+    let Json(by_ops) = server
+        .diagram_propose_rewrite(Parameters(propose(
+            None,
+            Some(vec![McpOp::FlowRemoveEdge { edge_id: "e:0001".into() }]),
+        )))
+        .await
+        .expect("propose ops");
+    assert_eq!(by_ops.proposal.delta.expect("delta").removed, vec!["d:d-flow/flow/edge/e:0001"]);
+
+    let Json(discarded) = server
+        .diagram_discard_proposal(Parameters(DiagramDiscardProposalParams {
+            proposal_id: "proposal:2".into(),
+        }))
+        .await
+        .expect("discard");
+    assert_eq!(discarded.proposal.status, "discarded");
+    assert!(server
+        .diagram_discard_proposal(Parameters(DiagramDiscardProposalParams {
+            proposal_id: "proposal:2".into(),
+        }))
+        .await
+        .is_err());
+
+    ui_state
+        .lock()
+        .await
+        .resolve_proposal("proposal:1", crate::ui::ProposalStatus::Accepted { rev: 1 });
+    let Json(listed) = server
+        .diagram_list_proposals(Parameters(DiagramListProposalsParams { diagram_id: None }))
+        .await
+        .expect("list");
+    let statuses = listed
+        .proposals
+        .iter()
+        .map(|proposal| (proposal.status.as_str(), proposal.accepted_rev))
+        .collect::<Vec<_>>();
+    assert_eq!(statuses, vec![("accepted", Some(1)), ("discarded", None)]);
+
+    assert!(server
+        .diagram_propose_rewrite(Parameters(propose(Some("sequenceDiagram\nA->>B: hi\n"), None)))
+        .await
+        .is_err());
+    assert!(server
+        .diagram_propose_rewrite(Parameters(DiagramProposeRewriteParams {
+            base_rev: 7,
+            ..propose(Some("flowchart TD\nA --> C\n"), None)
+        }))
+        .await
+        .is_err());
+    let without_tui = NereidMcp::new(session);
+    assert!(without_tui
+        .diagram_propose_rewrite(Parameters(propose(Some("flowchart TD\nA --> C\n"), None)))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn syncing_a_lazy_session_leaves_unloaded_diagrams_unparsed() {
+    let dir = temp_session_dir("lazy-sync");
+    let mut session = Session::new(SessionId::new("s:lazy").expect("session id"));
+    for (diagram_id, node_id) in [("d1", "n:a"), ("d2", "n:b")] {
+        let diagram_id = DiagramId::new(diagram_id).expect("diagram id");
+        let mut ast = FlowchartAst::default();
+        ast.nodes_mut().insert(ObjectId::new(node_id).expect("node id"), FlowNode::new(node_id));
+        session.diagrams_mut().insert(
+            diagram_id.clone(),
+            Diagram::new(diagram_id, "Flow", DiagramAst::Flowchart(ast)),
+        );
+    }
+    let from = ObjectRef::from_str("d:d1/flow/node/n:a").expect("from ref");
+    let to = ObjectRef::from_str("d:d2/flow/node/n:b").expect("to ref");
+    session.selected_object_refs_mut().insert(from.clone());
+    session
+        .xrefs_mut()
+        .insert(XRefId::new("x:1").expect("xref id"), XRef::new(from, to, "flow", XRefStatus::Ok));
+    SessionFolder::new(dir.clone()).save_session(&session).expect("save session");
+
+    let folder = SessionFolder::new(dir).with_load_mode(LoadMode::Lazy);
+    let lazy = folder.load_session().expect("lazy load");
+    let server = NereidMcp::new_persistent(lazy, folder);
+    for _ in 0..2 {
+        let state = server.lock_state_synced().await.expect("sync");
+        assert!(state.session.diagrams().values().all(|diagram| !diagram.is_loaded()));
+        assert_eq!(state.session.selected_object_refs().len(), 1);
+        assert_eq!(state.session.xrefs().values().next().map(XRef::status), Some(XRefStatus::Ok));
+    }
+}
+
+#[tokio::test]
+async fn diagram_set_mermaid_round_trips_with_rev_checks_and_persists() {
+    let dir = temp_session_dir("set-mermaid");
+    let folder = SessionFolder::new(dir.clone());
+    let mut session = Session::new(SessionId::new("s:mermaid").expect("session id"));
+    let diagram_id = DiagramId::new("d-flow").expect("diagram id");
+    let ast = parse_flowchart_with_id_rules("flowchart TD\nA --> B\n", &[]).expect("parse");
+    let diagram = Diagram::new(diagram_id.clone(), "Flow", DiagramAst::Flowchart(ast));
+    session.diagrams_mut().insert(diagram_id.clone(), diagram);
+    session.set_active_diagram_id(Some(diagram_id.clone()));
+    folder.save_session(&session).expect("save initial session");
+    let server = NereidMcp::new_persistent(session, folder);
+    let set = |base_rev: u64, mermaid: &str| DiagramSetMermaidParams {
+        diagram_id: Some("d-flow".into()),
+        base_rev,
+        mermaid: mermaid.into(),
+    };
+
+    let Json(read) = server
+        .diagram_get_mermaid(Parameters(DiagramTargetParams { diagram_id: None }))
+        .await
+        .expect("get mermaid");
+    assert_eq!(
+        (read.diagram_id.as_str(), read.rev, read.kind.as_str()),
+        ("d-flow", 0, "Flowchart")
+    );
+    let edited = format!("{}B --> C\n", read.mermaid);
+
+    let Json(by_ops) =
+        server.diagram_set_mermaid(Parameters(set(read.rev, &edited))).await.expect("set mermaid");
+    assert_eq!(by_ops.new_rev, 1);
+    assert!(by_ops.applied > 0);
+    assert!(by_ops.delta.expect("delta").added.contains(&"d:d-flow/flow/node/n:C".to_owned()));
+    let Json(diff) = server
+        .diagram_diff(Parameters(GetDeltaParams { diagram_id: None, since_rev: 0 }))
+        .await
+        .expect("diff after ops");
+    assert_eq!(diff.to_rev, 1);
+
+    // A stale rev is a conflict, and unchanged Mermaid keeps the rev.
+    assert!(server.diagram_set_mermaid(Parameters(set(0, &edited))).await.is_err());
+    let Json(unchanged) =
+        server.diagram_set_mermaid(Parameters(set(1, &edited))).await.expect("unchanged");
+    assert_eq!((unchanged.new_rev, unchanged.applied), (1, 0));
+    assert!(server
+        .diagram_set_mermaid(Parameters(set(1, "sequenceDiagram\nA->>B: hi\n")))
+        .await
+        .is_err());
+
+    // Subgraphs are beyond ops, so the AST is replaced whole.
+    let Json(replaced) = server
+        .diagram_set_mermaid(Parameters(set(
+            1,
+            "flowchart TD\nsubgraph G\nA\nB\nend\nA --> B\nB --> C\n",
+        )))
+        .await
+        .expect("replace");
+    assert_eq!((replaced.new_rev, replaced.applied), (2, 0));
+    assert!(replaced.delta.is_none());
+
+    let loaded = SessionFolder::new(dir.clone()).load_session().expect("load session");
+    let diagram = &loaded.diagrams()[&diagram_id];
+    assert_eq!(diagram.rev(), 2);
+    let DiagramAst::Flowchart(ast) = diagram.ast() else { panic!("flowchart") };
+    assert!(ast.nodes().contains_key(&oid("n:C")));
+    assert_eq!(ast.subgraphs().len(), 1);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
 #[tokio::test]
 async fn follow_ai_set_updates_shared_ui_state_when_available() {
     let ui_state = Arc::new(Mutex::new(UiState::default()));
@@ -611,6 +813,208 @@ async fn follow_ai_set_updates_shared_ui_state_when_available() {
     assert!(!ui_state.lock().await.follow_ai());
 }
 
+#[tokio::test]
+async fn view_center_on_queues_request_only_while_following_ai() {
+    let ui_state = Arc::new(Mutex::new(UiState::default()));
+    let server = NereidMcp::new_with_agent_highlights_and_ui_state(
+        demo_session(),
+        Arc::new(Mutex::new(BTreeSet::new())),
+        Some(ui_state.clone()),
+    );
+    let params =
+        || Parameters(ViewCenterOnParams { object_ref: "d:d-flow/flow/node/n:b".to_owned() });
+
+    let Json(centered) = server.view_center_on(params()).await.expect("view.center_on");
+    assert!(centered.applied);
+    assert_eq!(centered.diagram_id, "d-flow");
+    assert_eq!(
+        ui_state.lock().await.view_requests(),
+        &[ViewRequest::CenterOn(ObjectRef::from_str("d:d-flow/flow/node/n:b").expect("ref"))]
+    );
+
+    ui_state.lock().await.take_view_requests();
+    ui_state.lock().await.set_follow_ai(false);
+    let Json(ignored) = server.view_center_on(params()).await.expect("view.center_on");
+    assert!(!ignored.applied);
+    assert!(ui_state.lock().await.view_requests().is_empty());
+
+    let err = match server
+        .view_center_on(Parameters(ViewCenterOnParams {
+            object_ref: "d:d-flow/flow/node/n:missing".to_owned(),
+        }))
+        .await
+    {
+        Ok(_) => panic!("expected not found error"),
+        Err(err) => err,
+    };
+    assert_eq!(err.code, rmcp::model::ErrorCode::RESOURCE_NOT_FOUND);
+
+    let headless = NereidMcp::new(demo_session());
+    let Json(headless) = headless.view_center_on(params()).await.expect("view.center_on");
+    assert!(!headless.applied);
+}
+
+#[tokio::test]
+async fn view_tools_queue_requests_only_while_following_ai() {
+    let ui_state = Arc::new(Mutex::new(UiState::default()));
+    let server = NereidMcp::new_with_agent_highlights_and_ui_state(
+        demo_session(),
+        Arc::new(Mutex::new(BTreeSet::new())),
+        Some(ui_state.clone()),
+    );
+
+    let Json(scrolled) = server
+        .view_scroll(Parameters(ViewScrollParams { dx: -3, dy: 10 }))
+        .await
+        .expect("view.scroll");
+    assert!(scrolled.applied);
+    let Json(switched) = server
+        .view_set_active_diagram(Parameters(ViewSetActiveDiagramParams {
+            diagram_id: "d-flow".to_owned(),
+        }))
+        .await
+        .expect("view.set_active_diagram");
+    assert!(switched.applied);
+    let Json(zoomed) = server
+        .view_zoom(Parameters(ViewZoomParams { level: ViewZoomLevel::Compact }))
+        .await
+        .expect("view.zoom");
+    assert!(zoomed.applied);
+    assert_eq!(zoomed.level, ViewZoomLevel::Compact);
+    assert_eq!(
+        ui_state.lock().await.take_view_requests(),
+        vec![
+            ViewRequest::Scroll { dx: -3, dy: 10 },
+            ViewRequest::SetActiveDiagram(DiagramId::new("d-flow").expect("diagram id")),
+            ViewRequest::Zoom(ViewZoom::Compact),
+        ]
+    );
+
+    let err = match server
+        .view_set_active_diagram(Parameters(ViewSetActiveDiagramParams {
+            diagram_id: "d-missing".to_owned(),
+        }))
+        .await
+    {
+        Ok(_) => panic!("expected not found error"),
+        Err(err) => err,
+    };
+    assert_eq!(err.code, rmcp::model::ErrorCode::RESOURCE_NOT_FOUND);
+
+    ui_state.lock().await.set_follow_ai(false);
+    let Json(ignored) = server
+        .view_scroll(Parameters(ViewScrollParams { dx: 1, dy: 1 }))
+        .await
+        .expect("view.scroll");
+    assert!(!ignored.applied);
+    let Json(ignored) = server
+        .view_zoom(Parameters(ViewZoomParams { level: ViewZoomLevel::Spacious }))
+        .await
+        .expect("view.zoom");
+    assert!(!ignored.applied);
+    assert!(ui_state.lock().await.view_requests().is_empty());
+}
+
+#[tokio::test]
+async fn view_get_state_reports_published_human_viewport() {
+    let ui_state = Arc::new(Mutex::new(UiState::default()));
+    ui_state.lock().await.set_human_viewport(HumanViewport {
+        scroll_x: -1,
+        scroll_y: 12,
+        width: 80,
+        height: 24,
+        zoom: ViewZoom::Spacious,
+    });
+    let server = NereidMcp::new_with_agent_highlights_and_ui_state(
+        demo_session(),
+        Arc::new(Mutex::new(BTreeSet::new())),
+        Some(ui_state),
+    );
+
+    let Json(result) = server.view_get_state().await.expect("view state");
+    assert_eq!(result.scroll.x, -1.0);
+    assert_eq!(result.scroll.y, 12.0);
+    assert_eq!(result.zoom, ViewZoomLevel::Spacious);
+}
+
+#[tokio::test]
+async fn view_get_viewport_reports_published_human_screen() {
+    let ui_state = Arc::new(Mutex::new(UiState::default()));
+    let object_ref = ObjectRef::parse("d:d-seq/seq/participant/p:a").expect("object ref");
+    {
+        let mut ui_state = ui_state.lock().await;
+        ui_state.set_human_viewport(HumanViewport {
+            scroll_x: 2,
+            scroll_y: 0,
+            width: 8,
+            height: 2,
+            zoom: ViewZoom::Normal,
+        });
+        ui_state.set_human_screen(HumanScreen {
+            diagram_id: Some(DiagramId::new("d-seq").expect("diagram id")),
+            lines: vec!["┌─────┐".to_owned(), "│Alice│".to_owned()],
+            objects: BTreeMap::from([(object_ref, vec![(0, 0, 6), (1, 0, 6)])]),
+        });
+    }
+    let server = NereidMcp::new_with_agent_highlights_and_ui_state(
+        demo_session(),
+        Arc::new(Mutex::new(BTreeSet::new())),
+        Some(ui_state),
+    );
+
+    let Json(result) = server.view_get_viewport().await.expect("view viewport");
+    assert_eq!(result.diagram_id.as_deref(), Some("d-seq"));
+    assert_eq!(result.scroll.x, 2.0);
+    assert_eq!((result.width, result.height), (8, 2));
+    assert_eq!(result.lines, vec!["┌─────┐", "│Alice│"]);
+    assert_eq!(
+        result.objects,
+        BTreeMap::from([("d:d-seq/seq/participant/p:a".to_owned(), vec![[0, 0, 6], [1, 0, 6]])])
+    );
+
+    let server = NereidMcp::new(demo_session());
+    let Json(result) = server.view_get_viewport().await.expect("view viewport");
+    assert_eq!(result.diagram_id, None);
+    assert!(result.lines.is_empty());
+    assert!(result.objects.is_empty());
+}
+
+#[tokio::test]
+async fn server_capabilities_describe_kinds_ops_and_tools() {
+    let server = NereidMcp::new(demo_session()).with_attention_ttl(None);
+    let Json(result) = server.server_capabilities().await.expect("capabilities");
+
+    assert_eq!(result.version, env!("CARGO_PKG_VERSION"));
+    assert!(!result.persisted);
+    assert_eq!(result.attention_ttl_ms, None);
+    let kinds = result
+        .diagram_kinds
+        .iter()
+        .map(|kind| (kind.kind.as_str(), kind))
+        .collect::<BTreeMap<_, _>>();
+    assert_eq!(
+        kinds.keys().copied().collect::<Vec<_>>(),
+        ["class", "flowchart", "sequence", "state"]
+    );
+    assert!(kinds["sequence"].ops.iter().any(|op| op == "seq_add_message"));
+    assert!(kinds["flowchart"].ops.iter().all(|op| op.starts_with("flow_")));
+    assert!(kinds["flowchart"].ops.iter().any(|op| op == "flow_add_edge"));
+    assert!(kinds["class"].ops.is_empty());
+    assert_eq!(kinds["state"].mermaid_header, "stateDiagram-v2");
+    assert!(result.walkthrough_ops.iter().any(|op| op == "add_node"));
+    assert!(result.export_formats.iter().any(|format| format == "svg"));
+    assert!(result.features.iter().any(|feature| feature == "proposals"));
+
+    let tool = |name: &str| {
+        result.tools.iter().find(|tool| tool.name == name).unwrap_or_else(|| panic!("{name}"))
+    };
+    assert_eq!(tool("server.capabilities").group, "server");
+    let apply_ops = tool("diagram.apply_ops");
+    assert_eq!(apply_ops.group, "diagram");
+    assert!(apply_ops.params.iter().any(|param| param == "base_rev"), "{:?}", apply_ops.params);
+    assert!(tool("walkthrough.node.add").params.iter().any(|param| param == "walkthrough_id"));
+}
+
 #[tokio::test]
 async fn view_get_state_returns_stable_defaults() {
     let server = NereidMcp::new(demo_session());
@@ -619,6 +1023,7 @@ async fn view_get_state_returns_stable_defaults() {
     assert_eq!(result.active_diagram_id.as_deref(), Some("d-seq"));
     assert_eq!(result.scroll.x, 0.0);
     assert_eq!(result.scroll.y, 0.0);
+    assert_eq!(result.zoom, ViewZoomLevel::Normal);
     assert!(result.panes.is_empty());
     assert_eq!(result.context.session_active_diagram_id.as_deref(), Some("d-seq"));
     assert_eq!(result.context.human_active_diagram_id, None);
@@ -737,6 +1142,8 @@ async fn attention_agent_set_read_clear_validates_refs() {
     let err = match server
         .attention_agent_set(Parameters(AttentionAgentSetParams {
             object_ref: "d:d-seq/seq/message/m:999".to_owned(),
+            client_id: None,
+            name: None,
         }))
         .await
     {
@@ -747,7 +1154,11 @@ async fn attention_agent_set_read_clear_validates_refs() {
 
     let object_ref = "d:d-flow/flow/edge/e:ab".to_owned();
     let Json(set) = server
-        .attention_agent_set(Parameters(AttentionAgentSetParams { object_ref: object_ref.clone() }))
+        .attention_agent_set(Parameters(AttentionAgentSetParams {
+            object_ref: object_ref.clone(),
+            client_id: None,
+            name: None,
+        }))
         .await
         .expect("attention.agent.set");
     assert_eq!(set.object_ref, object_ref);
@@ -758,7 +1169,10 @@ async fn attention_agent_set_read_clear_validates_refs() {
     assert_eq!(read.diagram_id.as_deref(), Some("d-flow"));
     assert_eq!(read.context.session_active_diagram_id.as_deref(), Some("d-seq"));
 
-    let Json(cleared) = server.attention_agent_clear().await.expect("attention.agent.clear");
+    let Json(cleared) = server
+        .attention_agent_clear(Parameters(AttentionAgentParams::default()))
+        .await
+        .expect("attention.agent.clear");
     assert_eq!(cleared.cleared, 1);
 
     let Json(after_clear) =
@@ -775,6 +1189,8 @@ async fn attention_agent_set_overwrites_previous_value() {
     let Json(first) = server
         .attention_agent_set(Parameters(AttentionAgentSetParams {
             object_ref: "d:d-seq/seq/participant/p:a".to_owned(),
+            client_id: None,
+            name: None,
         }))
         .await
         .expect("attention.agent.set first");
@@ -784,6 +1200,8 @@ async fn attention_agent_set_overwrites_previous_value() {
     let Json(second) = server
         .attention_agent_set(Parameters(AttentionAgentSetParams {
             object_ref: "d:d-seq/seq/message/m:1".to_owned(),
+            client_id: None,
+            name: None,
         }))
         .await
         .expect("attention.agent.set second");
@@ -795,11 +1213,16 @@ async fn attention_agent_set_overwrites_previous_value() {
     assert_eq!(read.diagram_id.as_deref(), Some("d-seq"));
     assert_eq!(read.context.session_active_diagram_id.as_deref(), Some("d-seq"));
 
-    let Json(cleared) = server.attention_agent_clear().await.expect("attention.agent.clear");
+    let Json(cleared) = server
+        .attention_agent_clear(Parameters(AttentionAgentParams::default()))
+        .await
+        .expect("attention.agent.clear");
     assert_eq!(cleared.cleared, 1);
 
-    let Json(cleared_again) =
-        server.attention_agent_clear().await.expect("attention.agent.clear again");
+    let Json(cleared_again) = server
+        .attention_agent_clear(Parameters(AttentionAgentParams::default()))
+        .await
+        .expect("attention.agent.clear again");
     assert_eq!(cleared_again.cleared, 0);
 }
 
@@ -877,105 +1300,357 @@ async fn streamable_http_tools_call_updates_shared_agent_attention_state() {
 #[tokio::test]
 async fn list_diagrams_returns_deterministic_order() {
     let server = NereidMcp::new(demo_session());
-    let Json(result) = server.diagram_list().await.expect("list");
+    let Json(result) =
+        server.diagram_list(Parameters(DiagramListParams::default())).await.expect("list");
     let ids = result.diagrams.iter().map(|d| d.diagram_id.as_str()).collect::<Vec<_>>();
     assert_eq!(ids, vec!["d-flow", "d-seq"]);
     assert_eq!(result.context.session_active_diagram_id.as_deref(), Some("d-seq"));
 }
 
 #[tokio::test]
-async fn walkthrough_list_returns_deterministic_order_and_counts() {
-    let server = NereidMcp::new(demo_session_with_walkthroughs());
-    let Json(result) = server.walkthrough_list().await.expect("walkthrough list");
-
-    let ids = result.walkthroughs.iter().map(|w| w.walkthrough_id.as_str()).collect::<Vec<_>>();
-    assert_eq!(ids, vec!["w:1", "w:2"]);
+async fn diagram_reorder_sets_list_order_and_rejects_unknown_ids() {
+    let server = NereidMcp::new(demo_session());
+    let Json(result) = server
+        .diagram_reorder(Parameters(DiagramReorderParams { diagram_ids: vec!["d-seq".into()] }))
+        .await
+        .expect("reorder");
+    assert_eq!(result.diagram_ids, vec!["d-seq", "d-flow"]);
 
-    assert_eq!(result.walkthroughs[0].title, "First walkthrough");
-    assert_eq!(result.walkthroughs[0].rev, 0);
-    assert_eq!(result.walkthroughs[0].nodes, 2);
-    assert_eq!(result.walkthroughs[0].edges, 1);
+    let Json(listed) =
+        server.diagram_list(Parameters(DiagramListParams::default())).await.expect("list");
+    let ids = listed.diagrams.iter().map(|d| d.diagram_id.as_str()).collect::<Vec<_>>();
+    assert_eq!(ids, vec!["d-seq", "d-flow"]);
 
-    assert_eq!(result.walkthroughs[1].title, "Second walkthrough");
-    assert_eq!(result.walkthroughs[1].rev, 2);
-    assert_eq!(result.walkthroughs[1].nodes, 1);
-    assert_eq!(result.walkthroughs[1].edges, 0);
-    assert_eq!(result.context.session_active_diagram_id, None);
+    let err = match server
+        .diagram_reorder(Parameters(DiagramReorderParams { diagram_ids: vec!["d-none".into()] }))
+        .await
+    {
+        Ok(_) => panic!("expected unknown diagram to be rejected"),
+        Err(err) => err,
+    };
+    assert_eq!(err.code, rmcp::model::ErrorCode::RESOURCE_NOT_FOUND);
 }
 
 #[tokio::test]
-async fn walkthrough_read_returns_nodes_edges_and_refs() {
-    let server = NereidMcp::new(demo_session_with_walkthroughs());
+async fn archived_diagrams_are_listed_only_on_request() {
+    let server = NereidMcp::new(demo_session());
     let Json(result) = server
-        .walkthrough_read(Parameters(WalkthroughGetParams { walkthrough_id: "w:1".into() }))
+        .diagram_set_archived(Parameters(DiagramSetArchivedParams {
+            diagram_id: Some("d-flow".into()),
+            archived: true,
+        }))
         .await
-        .expect("walkthrough read");
+        .expect("archive");
+    assert!(result.archived);
 
-    assert_eq!(result.walkthrough.walkthrough_id, "w:1");
-    assert_eq!(result.walkthrough.title, "First walkthrough");
-    assert_eq!(result.walkthrough.rev, 0);
+    let Json(listed) =
+        server.diagram_list(Parameters(DiagramListParams::default())).await.expect("list");
+    let ids = listed.diagrams.iter().map(|d| d.diagram_id.as_str()).collect::<Vec<_>>();
+    assert_eq!(ids, vec!["d-seq"]);
 
-    assert_eq!(result.walkthrough.nodes.len(), 2);
-    assert_eq!(result.walkthrough.nodes[0].node_id, "wn:2");
-    assert_eq!(result.walkthrough.nodes[0].title, "Start");
-    assert_eq!(result.walkthrough.nodes[0].body_md.as_deref(), Some("Start body"));
-    assert_eq!(
-        result.walkthrough.nodes[0].refs,
-        vec!["d:d-seq/seq/message/m:1", "d:d-flow/flow/node/n:a"]
-    );
-    assert_eq!(result.walkthrough.nodes[0].tags, vec!["intro", "evidence"]);
-    assert_eq!(result.walkthrough.nodes[0].status.as_deref(), Some("draft"));
+    let Json(listed) = server
+        .diagram_list(Parameters(DiagramListParams { include_archived: Some(true) }))
+        .await
+        .expect("list with archived");
+    let archived = listed.diagrams.iter().map(|d| (d.diagram_id.as_str(), d.archived));
+    assert_eq!(archived.collect::<Vec<_>>(), vec![("d-flow", true), ("d-seq", false)]);
 
-    assert_eq!(result.walkthrough.nodes[1].node_id, "wn:3");
-    assert_eq!(result.walkthrough.nodes[1].title, "End");
-    assert_eq!(result.walkthrough.nodes[1].body_md, None);
-    assert_eq!(result.walkthrough.nodes[1].refs, vec!["d:d-flow/flow/edge/e:ab"]);
-    assert!(result.walkthrough.nodes[1].tags.is_empty());
-    assert_eq!(result.walkthrough.nodes[1].status, None);
+    let Json(opened) = server
+        .diagram_open(Parameters(DiagramOpenParams { diagram_id: "d-flow".into() }))
+        .await
+        .expect("archived diagrams still open");
+    assert_eq!(opened.active_diagram_id, "d-flow");
+}
 
-    assert_eq!(result.walkthrough.edges.len(), 1);
-    assert_eq!(result.walkthrough.edges[0].from_node_id, "wn:2");
-    assert_eq!(result.walkthrough.edges[0].to_node_id, "wn:3");
-    assert_eq!(result.walkthrough.edges[0].kind, "next");
-    assert_eq!(result.walkthrough.edges[0].label.as_deref(), Some("continue"));
-    assert_eq!(result.context.session_active_diagram_id, None);
+fn agent_set_params(object_ref: &str, client_id: &str) -> Parameters<AttentionAgentSetParams> {
+    Parameters(AttentionAgentSetParams {
+        object_ref: object_ref.to_owned(),
+        client_id: Some(client_id.to_owned()),
+        name: None,
+    })
 }
 
 #[tokio::test]
-async fn walkthrough_get_node_returns_single_node() {
-    let server = NereidMcp::new(demo_session_with_walkthroughs());
-    let Json(result) = server
-        .walkthrough_get_node(Parameters(WalkthroughGetNodeParams {
-            walkthrough_id: "w:1".into(),
-            node_id: "wn:2".into(),
+async fn concurrent_agents_get_own_cursors_and_hand_over_the_spotlight() {
+    let ui_state = Arc::new(Mutex::new(UiState::default()));
+    let server = NereidMcp::new_with_agent_highlights_and_ui_state(
+        demo_session(),
+        Arc::new(Mutex::new(BTreeSet::new())),
+        Some(ui_state.clone()),
+    )
+    .with_attention_ttl(Some(Duration::from_millis(80)));
+    let edge = "d:d-flow/flow/edge/e:ab";
+    let message = "d:d-seq/seq/message/m:1";
+
+    let Json(set) = server
+        .attention_agent_set(Parameters(AttentionAgentSetParams {
+            object_ref: edge.to_owned(),
+            client_id: Some("a".to_owned()),
+            name: Some("Alpha".to_owned()),
         }))
         .await
-        .expect("walkthrough get node");
+        .expect("set a");
+    assert_eq!(set.client_id, "a");
+    server.attention_agent_set(agent_set_params(message, "b")).await.expect("set b");
 
-    assert_eq!(result.node.node_id, "wn:2");
-    assert_eq!(result.node.title, "Start");
-    assert_eq!(result.node.body_md.as_deref(), Some("Start body"));
-    assert_eq!(result.node.refs, vec!["d:d-seq/seq/message/m:1", "d:d-flow/flow/node/n:a"]);
-    assert_eq!(result.node.tags, vec!["intro", "evidence"]);
-    assert_eq!(result.node.status.as_deref(), Some("draft"));
-    assert_eq!(result.context.session_active_diagram_id, None);
-}
+    let Json(read) = server.attention_agent_read().await.expect("read");
+    assert_eq!(read.object_ref.as_deref(), Some(message), "the last set cursor is followed");
+    let agents = read
+        .agents
+        .iter()
+        .map(|agent| (agent.client_id.as_str(), agent.name.as_str(), agent.color))
+        .collect::<Vec<_>>();
+    assert_eq!(agents, [("a", "Alpha", 0), ("b", "b", 1)]);
 
-#[tokio::test]
-async fn walkthrough_get_node_rejects_invalid_walkthrough_id() {
-    let server = NereidMcp::new(demo_session_with_walkthroughs());
-    let err = match server
-        .walkthrough_get_node(Parameters(WalkthroughGetNodeParams {
-            walkthrough_id: "w/1".into(),
-            node_id: "wn:2".into(),
-        }))
+    let Json(cleared) = server
+        .attention_agent_clear(Parameters(AttentionAgentParams { client_id: Some("b".into()) }))
         .await
-    {
-        Ok(_) => panic!("expected invalid id error"),
-        Err(err) => err,
-    };
-    assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
-}
+        .expect("clear b");
+    assert_eq!(cleared.cleared, 1);
+    let Json(read) = server.attention_agent_read().await.expect("read after clear b");
+    assert_eq!(read.object_ref.as_deref(), Some(edge), "the spotlight moves to a");
+    assert_eq!(read.agents.len(), 1);
+
+    server.attention_agent_set(agent_set_params(message, "c")).await.expect("set c");
+    assert_eq!(ui_state.lock().await.agent_cursors()["c"].color, 1, "freed colors are reused");
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    server
+        .attention_agent_heartbeat(Parameters(AttentionAgentParams { client_id: Some("a".into()) }))
+        .await
+        .expect("heartbeat a");
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    let Json(read) = server.attention_agent_read().await.expect("read after c went idle");
+    assert_eq!(read.agents.iter().map(|agent| agent.client_id.as_str()).collect::<Vec<_>>(), ["a"]);
+    assert_eq!(read.object_ref.as_deref(), Some(edge));
+
+    server.attention_agent_clear(Parameters(AttentionAgentParams::default())).await.expect("clear");
+    let Json(read) = server.attention_agent_read().await.expect("read after clear");
+    assert_eq!((read.object_ref, read.agents.len()), (None, 0));
+}
+
+#[tokio::test]
+async fn agent_attention_expires_without_heartbeat_and_tells_the_tui() {
+    let ui_state = Arc::new(Mutex::new(UiState::default()));
+    let server = NereidMcp::new_with_agent_highlights_and_ui_state(
+        demo_session(),
+        Arc::new(Mutex::new(BTreeSet::new())),
+        Some(ui_state.clone()),
+    )
+    .with_attention_ttl(Some(Duration::from_millis(80)));
+    let object_ref = "d:d-flow/flow/edge/e:ab".to_owned();
+
+    let Json(set) = server
+        .attention_agent_set(Parameters(AttentionAgentSetParams {
+            object_ref: object_ref.clone(),
+            client_id: None,
+            name: None,
+        }))
+        .await
+        .expect("attention.agent.set");
+    assert_eq!(set.expires_in_ms, Some(80));
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let Json(beat) = server
+        .attention_agent_heartbeat(Parameters(AttentionAgentParams::default()))
+        .await
+        .expect("heartbeat");
+    assert_eq!(beat.object_ref.as_deref(), Some(object_ref.as_str()));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let Json(read) = server.attention_agent_read().await.expect("attention.agent.read");
+    assert_eq!(read.object_ref.as_deref(), Some(object_ref.as_str()), "heartbeat extends it");
+    assert!(read.expires_in_ms.is_some_and(|ms| ms <= 80));
+
+    tokio::time::sleep(Duration::from_millis(120)).await;
+    let Json(read) = server.attention_agent_read().await.expect("attention.agent.read expired");
+    assert_eq!(read.object_ref, None);
+    assert_eq!(read.expires_in_ms, None);
+    assert_eq!(
+        ui_state.lock().await.expired_agent_highlight().map(ToString::to_string),
+        Some(object_ref)
+    );
+
+    let Json(beat) = server
+        .attention_agent_heartbeat(Parameters(AttentionAgentParams::default()))
+        .await
+        .expect("heartbeat after expiry");
+    assert_eq!((beat.object_ref, beat.expires_in_ms), (None, None));
+}
+
+#[tokio::test]
+async fn session_change_notifications_report_attention_and_diagram_revisions() {
+    let server = NereidMcp::new(demo_session());
+    let mut changes = server.subscribe_session_changes();
+    server.publish_session_changes().await;
+    assert!(changes.try_recv().is_err(), "the first publish only records a baseline");
+
+    server
+        .attention_agent_set(Parameters(AttentionAgentSetParams {
+            object_ref: "d:d-flow/flow/edge/e:ab".to_owned(),
+            client_id: None,
+            name: None,
+        }))
+        .await
+        .expect("attention.agent.set");
+    let changed = changes.try_recv().expect("attention change");
+    assert_eq!(changed, SessionChanged { attention: true, ..SessionChanged::default() });
+
+    server
+        .diagram_apply_ops(Parameters(ApplyOpsParams {
+            diagram_id: Some("d-flow".into()),
+            base_rev: 0,
+            ops: vec![McpOp::FlowAddNode { node_id: "n:z".into(), label: "Z".into(), shape: None }],
+        }))
+        .await
+        .expect("apply ops");
+    let changed = changes.try_recv().expect("diagram change");
+    assert_eq!(changed.diagrams.get("d-flow"), Some(&Some(1)));
+    assert!(!changed.attention);
+
+    server.publish_session_changes().await;
+    assert!(changes.try_recv().is_err(), "unchanged sessions publish nothing");
+}
+
+#[tokio::test]
+async fn read_only_diagrams_reject_mutating_tools_until_unlocked() {
+    let server = NereidMcp::new(demo_session());
+    let set_read_only = |read_only| {
+        server.diagram_set_read_only(Parameters(DiagramSetReadOnlyParams {
+            diagram_id: Some("d-flow".into()),
+            read_only,
+        }))
+    };
+    let add_node = || {
+        server.diagram_apply_ops(Parameters(ApplyOpsParams {
+            diagram_id: Some("d-flow".into()),
+            base_rev: 0,
+            ops: vec![McpOp::FlowAddNode { node_id: "n:z".into(), label: "Z".into(), shape: None }],
+        }))
+    };
+    let Json(locked) = set_read_only(true).await.expect("lock");
+    assert!(locked.read_only);
+
+    let err = match add_node().await {
+        Ok(_) => panic!("expected read-only error"),
+        Err(err) => err,
+    };
+    assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_REQUEST);
+    assert!(err.message.contains("read-only"), "unexpected message: {}", err.message);
+    let description = server
+        .diagram_update_description(Parameters(DiagramUpdateDescriptionParams {
+            diagram_id: Some("d-flow".into()),
+            description: "changed".into(),
+        }))
+        .await;
+    assert!(description.is_err());
+    let deleted = server
+        .diagram_delete(Parameters(DiagramDeleteParams { diagram_id: "d-flow".into() }))
+        .await;
+    assert!(deleted.is_err());
+
+    let Json(listed) =
+        server.diagram_list(Parameters(DiagramListParams::default())).await.expect("list");
+    let read_only = listed.diagrams.iter().map(|d| (d.diagram_id.as_str(), d.read_only));
+    assert_eq!(read_only.collect::<Vec<_>>(), vec![("d-flow", true), ("d-seq", false)]);
+
+    set_read_only(false).await.expect("unlock");
+    let Json(applied) = add_node().await.expect("apply after unlock");
+    assert_eq!(applied.new_rev, 1);
+}
+
+#[tokio::test]
+async fn walkthrough_list_returns_deterministic_order_and_counts() {
+    let server = NereidMcp::new(demo_session_with_walkthroughs());
+    let Json(result) = server.walkthrough_list().await.expect("walkthrough list");
+
+    let ids = result.walkthroughs.iter().map(|w| w.walkthrough_id.as_str()).collect::<Vec<_>>();
+    assert_eq!(ids, vec!["w:1", "w:2"]);
+
+    assert_eq!(result.walkthroughs[0].title, "First walkthrough");
+    assert_eq!(result.walkthroughs[0].rev, 0);
+    assert_eq!(result.walkthroughs[0].nodes, 2);
+    assert_eq!(result.walkthroughs[0].edges, 1);
+
+    assert_eq!(result.walkthroughs[1].title, "Second walkthrough");
+    assert_eq!(result.walkthroughs[1].rev, 2);
+    assert_eq!(result.walkthroughs[1].nodes, 1);
+    assert_eq!(result.walkthroughs[1].edges, 0);
+    assert_eq!(result.context.session_active_diagram_id, None);
+}
+
+#[tokio::test]
+async fn walkthrough_read_returns_nodes_edges_and_refs() {
+    let server = NereidMcp::new(demo_session_with_walkthroughs());
+    let Json(result) = server
+        .walkthrough_read(Parameters(WalkthroughGetParams { walkthrough_id: "w:1".into() }))
+        .await
+        .expect("walkthrough read");
+
+    assert_eq!(result.walkthrough.walkthrough_id, "w:1");
+    assert_eq!(result.walkthrough.title, "First walkthrough");
+    assert_eq!(result.walkthrough.rev, 0);
+
+    assert_eq!(result.walkthrough.nodes.len(), 2);
+    assert_eq!(result.walkthrough.nodes[0].node_id, "wn:2");
+    assert_eq!(result.walkthrough.nodes[0].title, "Start");
+    assert_eq!(result.walkthrough.nodes[0].body_md.as_deref(), Some("Start body"));
+    assert_eq!(
+        result.walkthrough.nodes[0].refs,
+        vec!["d:d-seq/seq/message/m:1", "d:d-flow/flow/node/n:a"]
+    );
+    assert_eq!(result.walkthrough.nodes[0].tags, vec!["intro", "evidence"]);
+    assert_eq!(result.walkthrough.nodes[0].status.as_deref(), Some("draft"));
+
+    assert_eq!(result.walkthrough.nodes[1].node_id, "wn:3");
+    assert_eq!(result.walkthrough.nodes[1].title, "End");
+    assert_eq!(result.walkthrough.nodes[1].body_md, None);
+    assert_eq!(result.walkthrough.nodes[1].refs, vec!["d:d-flow/flow/edge/e:ab"]);
+    assert!(result.walkthrough.nodes[1].tags.is_empty());
+    assert_eq!(result.walkthrough.nodes[1].status, None);
+
+    assert_eq!(result.walkthrough.edges.len(), 1);
+    assert_eq!(result.walkthrough.edges[0].from_node_id, "wn:2");
+    assert_eq!(result.walkthrough.edges[0].to_node_id, "wn:3");
+    assert_eq!(result.walkthrough.edges[0].kind, "next");
+    assert_eq!(result.walkthrough.edges[0].label.as_deref(), Some("continue"));
+    assert_eq!(result.context.session_active_diagram_id, None);
+}
+
+#[tokio::test]
+async fn walkthrough_get_node_returns_single_node() {
+    let server = NereidMcp::new(demo_session_with_walkthroughs());
+    let Json(result) = server
+        .walkthrough_get_node(Parameters(WalkthroughGetNodeParams {
+            walkthrough_id: "w:1".into(),
+            node_id: "wn:2".into(),
+        }))
+        .await
+        .expect("walkthrough get node");
+
+    assert_eq!(result.node.node_id, "wn:2");
+    assert_eq!(result.node.title, "Start");
+    assert_eq!(result.node.body_md.as_deref(), Some("Start body"));
+    assert_eq!(result.node.refs, vec!["d:d-seq/seq/message/m:1", "d:d-flow/flow/node/n:a"]);
+    assert_eq!(result.node.tags, vec!["intro", "evidence"]);
+    assert_eq!(result.node.status.as_deref(), Some("draft"));
+    assert_eq!(result.context.session_active_diagram_id, None);
+}
+
+#[tokio::test]
+async fn walkthrough_get_node_rejects_invalid_walkthrough_id() {
+    let server = NereidMcp::new(demo_session_with_walkthroughs());
+    let err = match server
+        .walkthrough_get_node(Parameters(WalkthroughGetNodeParams {
+            walkthrough_id: "w/1".into(),
+            node_id: "wn:2".into(),
+        }))
+        .await
+    {
+        Ok(_) => panic!("expected invalid id error"),
+        Err(err) => err,
+    };
+    assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+}
 
 #[tokio::test]
 async fn walkthrough_get_node_rejects_invalid_node_id() {
@@ -1115,6 +1790,92 @@ async fn walkthrough_render_text_returns_not_found_when_missing() {
     assert_eq!(err.code, rmcp::model::ErrorCode::RESOURCE_NOT_FOUND);
 }
 
+#[tokio::test]
+async fn walkthrough_authoring_tools_create_edit_and_delete_persisted_walkthroughs() {
+    let dir = temp_session_dir("mcp-walkthrough-authoring");
+    let folder = SessionFolder::new(dir.clone());
+    let session = demo_session_with_walkthroughs();
+    folder.save_session(&session).expect("save initial session");
+    let server = NereidMcp::new_persistent(session, folder);
+
+    let Json(created) = server
+        .walkthrough_create(Parameters(WalkthroughCreateParams {
+            title: "Checkout tour".into(),
+            walkthrough_id: None,
+            make_active: None,
+        }))
+        .await
+        .expect("walkthrough.create");
+    assert_eq!(created.walkthrough.walkthrough_id, "w:3");
+    assert_eq!(created.active_walkthrough_id.as_deref(), Some("w:3"));
+
+    let add_node = |base_rev, title: &str| {
+        server.walkthrough_node_add(Parameters(WalkthroughNodeAddParams {
+            walkthrough_id: "w:3".into(),
+            base_rev,
+            title: title.into(),
+            node_id: None,
+            body_md: None,
+            refs: Some(vec!["d:d-flow/flow/node/n:a".into()]),
+            tags: None,
+            status: None,
+        }))
+    };
+    let Json(first) = add_node(0, "Start").await.expect("add first node");
+    assert_eq!((first.node_id.as_str(), first.new_rev), ("wn:1", 1));
+    let Json(second) = add_node(1, "Pay").await.expect("add second node");
+    assert_eq!((second.node_id.as_str(), second.new_rev), ("wn:2", 2));
+    let Err(err) = add_node(0, "Stale").await else {
+        panic!("expected stale base_rev conflict");
+    };
+    assert!(err.message.contains("stale base_rev"));
+
+    let Json(linked) = server
+        .walkthrough_edge_add(Parameters(WalkthroughEdgeAddParams {
+            walkthrough_id: "w:3".into(),
+            base_rev: 2,
+            from_node_id: "wn:1".into(),
+            to_node_id: "wn:2".into(),
+            kind: None,
+            label: None,
+        }))
+        .await
+        .expect("walkthrough.edge.add");
+    assert_eq!(linked.new_rev, 3);
+    server
+        .walkthrough_node_update(Parameters(WalkthroughNodeUpdateParams {
+            walkthrough_id: "w:3".into(),
+            base_rev: 3,
+            node_id: "wn:2".into(),
+            title: Some("Pay and confirm".into()),
+            body_md: Some(Some("Card or invoice.".into())),
+            refs: None,
+            tags: None,
+            status: None,
+        }))
+        .await
+        .expect("walkthrough.node.update");
+
+    let loaded = SessionFolder::new(dir.clone()).load_session().expect("load session");
+    let walkthrough = &loaded.walkthroughs()[&WalkthroughId::new("w:3").expect("id")];
+    assert_eq!(walkthrough.rev(), 4);
+    assert_eq!(walkthrough.nodes()[1].title(), "Pay and confirm");
+    assert_eq!(walkthrough.nodes()[1].body_md(), Some("Card or invoice."));
+    assert_eq!(walkthrough.edges()[0].kind(), "next");
+    assert_eq!(loaded.active_walkthrough_id().map(|id| id.as_str()), Some("w:3"));
+
+    let Json(deleted) = server
+        .walkthrough_delete(Parameters(WalkthroughDeleteParams { walkthrough_id: "w:3".into() }))
+        .await
+        .expect("walkthrough.delete");
+    assert_eq!(deleted.active_walkthrough_id.as_deref(), Some("w:1"));
+    let loaded = SessionFolder::new(dir).load_session().expect("reload session");
+    assert_eq!(
+        loaded.walkthroughs().keys().map(|id| id.as_str()).collect::<Vec<_>>(),
+        ["w:1", "w:2"]
+    );
+}
+
 #[tokio::test]
 async fn walkthrough_apply_ops_conflicts_on_stale_base_rev() {
     let server = NereidMcp::new(demo_session_with_walkthroughs());
@@ -1561,7 +2322,7 @@ async fn xref_add_returns_ok_status_when_endpoints_exist() {
     let server = NereidMcp::new(demo_session());
     let Json(result) = server
         .xref_add(Parameters(XRefAddParams {
-            xref_id: "x:new".into(),
+            xref_id: Some("x:new".into()),
             from: "d:d-seq/seq/participant/p:a".into(),
             to: "d:d-flow/flow/node/n:a".into(),
             kind: "relates_to".into(),
@@ -1584,7 +2345,7 @@ async fn xref_add_marks_dangling_to_when_target_missing() {
     let server = NereidMcp::new(demo_session());
     let Json(result) = server
         .xref_add(Parameters(XRefAddParams {
-            xref_id: "x:dangling".into(),
+            xref_id: Some("x:dangling".into()),
             from: "d:d-seq/seq/participant/p:a".into(),
             to: "d:d-flow/flow/node/n:missing".into(),
             kind: "relates_to".into(),
@@ -1596,6 +2357,27 @@ async fn xref_add_marks_dangling_to_when_target_missing() {
     assert_eq!(result.status, "dangling_to");
 }
 
+#[tokio::test]
+async fn xref_add_mints_next_xref_id_and_does_not_reuse_removed_ones() {
+    let server = NereidMcp::new(demo_session_with_xrefs());
+    let add = || XRefAddParams {
+        xref_id: None,
+        from: "d:d-seq/seq/participant/p:a".into(),
+        to: "d:d-flow/flow/node/n:a".into(),
+        kind: "relates_to".into(),
+        label: None,
+    };
+    let Json(first) = server.xref_add(Parameters(add())).await.expect("xref add");
+    assert_eq!(first.xref_id, "x:3");
+
+    server
+        .xref_remove(Parameters(XRefRemoveParams { xref_id: first.xref_id }))
+        .await
+        .expect("xref remove");
+    let Json(second) = server.xref_add(Parameters(add())).await.expect("xref add");
+    assert_eq!(second.xref_id, "x:4");
+}
+
 #[tokio::test]
 async fn xref_remove_deletes_existing_xref() {
     let server = NereidMcp::new(demo_session_with_xrefs());
@@ -2069,7 +2851,7 @@ async fn flow_reachable_out_returns_reachable_nodes() {
     let Json(result) = server
         .flow_reachable(Parameters(FlowReachableParams {
             diagram_id: None,
-            from_node_id: "n:b".into(),
+            from_node_id: Some("n:b".into()),
             direction: Some("out".into()),
         }))
         .await
@@ -2084,7 +2866,7 @@ async fn flow_reachable_in_returns_inbound_reachable_nodes() {
     let Json(result) = server
         .flow_reachable(Parameters(FlowReachableParams {
             diagram_id: None,
-            from_node_id: "n:b".into(),
+            from_node_id: Some("n:b".into()),
             direction: Some("in".into()),
         }))
         .await
@@ -2099,7 +2881,7 @@ async fn flow_reachable_both_unions_in_and_out() {
     let Json(result) = server
         .flow_reachable(Parameters(FlowReachableParams {
             diagram_id: None,
-            from_node_id: "n:b".into(),
+            from_node_id: Some("n:b".into()),
             direction: Some("both".into()),
         }))
         .await
@@ -2121,7 +2903,7 @@ async fn flow_reachable_rejects_invalid_direction() {
     let err = match server
         .flow_reachable(Parameters(FlowReachableParams {
             diagram_id: None,
-            from_node_id: "n:b".into(),
+            from_node_id: Some("n:b".into()),
             direction: Some("sideways".into()),
         }))
         .await
@@ -2134,57 +2916,352 @@ async fn flow_reachable_rejects_invalid_direction() {
 }
 
 #[tokio::test]
-async fn flow_paths_returns_multiple_paths_in_deterministic_order() {
+async fn flow_shortest_path_uses_edge_weights_and_reports_cumulative_costs() {
     let server = NereidMcp::new(demo_session_for_flow_paths());
-    let Json(result) = server
-        .flow_paths(Parameters(FlowPathsParams {
+    let params = || FlowShortestPathParams {
+        diagram_id: None,
+        from_node_id: "n:a".into(),
+        to_node_id: "n:d".into(),
+    };
+
+    let Json(unweighted) =
+        server.flow_shortest_path(Parameters(params())).await.expect("shortest path");
+    assert_eq!(unweighted.cost, Some(2));
+
+    server
+        .diagram_apply_ops(Parameters(ApplyOpsParams {
             diagram_id: None,
-            from_node_id: "n:a".into(),
-            to_node_id: "n:d".into(),
-            limit: None,
-            max_extra_hops: None,
+            base_rev: 0,
+            ops: vec![McpOp::FlowSetEdgeWeight { edge_id: "e:ab".into(), weight: Some(10) }],
         }))
         .await
-        .expect("flow paths");
+        .expect("set weight");
 
+    let Json(weighted) =
+        server.flow_shortest_path(Parameters(params())).await.expect("shortest path");
+    assert_eq!(weighted.cost, Some(2));
+    let hops = weighted
+        .steps
+        .iter()
+        .map(|step| (step.node_ref.as_str(), step.edge_ref.as_deref(), step.cumulative_cost))
+        .collect::<Vec<_>>();
     assert_eq!(
-        result.paths,
+        hops,
         vec![
-            vec![
-                "d:d-flow-paths/flow/node/n:a",
-                "d:d-flow-paths/flow/node/n:b",
-                "d:d-flow-paths/flow/node/n:d",
-            ],
-            vec![
-                "d:d-flow-paths/flow/node/n:a",
-                "d:d-flow-paths/flow/node/n:c",
-                "d:d-flow-paths/flow/node/n:d",
-            ],
+            ("d:d-flow-paths/flow/node/n:a", None, 0),
+            ("d:d-flow-paths/flow/node/n:c", Some("d:d-flow-paths/flow/edge/e:ac"), 1),
+            ("d:d-flow-paths/flow/node/n:d", Some("d:d-flow-paths/flow/edge/e:cd"), 2),
         ]
     );
+    assert_eq!(weighted.summary, "A (0) -> C (1) -> D (2)");
 }
 
 #[tokio::test]
-async fn flow_paths_returns_empty_when_no_path_exists() {
-    let server = NereidMcp::new(demo_session_for_flow_paths());
-    let Json(result) = server
-        .flow_paths(Parameters(FlowPathsParams {
-            diagram_id: None,
-            from_node_id: "n:d".into(),
-            to_node_id: "n:a".into(),
-            limit: None,
-            max_extra_hops: None,
+async fn diagram_scaffold_flowchart_previews_or_persists_interaction_graph() {
+    let server = NereidMcp::new(demo_session());
+    let params = |persist: bool| DiagramScaffoldFlowchartParams {
+        diagram_id: None,
+        persist: Some(persist),
+        target_diagram_id: None,
+        name: Some("Who talks".into()),
+        make_active: Some(false),
+    };
+
+    let Json(preview) =
+        server.diagram_scaffold_flowchart(Parameters(params(false))).await.expect("preview");
+    assert!(preview.diagram.is_none());
+    assert!(preview.xref_ids.is_empty());
+    assert!(preview.mermaid.starts_with("flowchart"));
+    assert!(preview.mermaid.contains("1 message"));
+
+    let Json(stored) =
+        server.diagram_scaffold_flowchart(Parameters(params(true))).await.expect("scaffold");
+    let diagram = stored.diagram.expect("stored diagram");
+    assert_eq!(diagram.diagram_id, "d-seq-flow");
+    assert_eq!(diagram.name, "Who talks");
+    assert_eq!(stored.active_diagram_id.as_deref(), Some("d-seq"));
+    assert_eq!(stored.xref_ids.len(), 3);
+
+    let Json(xrefs) = server.xref_list(Parameters(xref_list_params())).await.expect("xref list");
+    assert!(xrefs.xrefs.iter().any(|x| x.from == "d:d-seq-flow/flow/edge/e:0001"
+        && x.to == "d:d-seq/seq/message/m:1"
+        && x.kind == "derived_from"));
+
+    let err = match server
+        .diagram_scaffold_flowchart(Parameters(DiagramScaffoldFlowchartParams {
+            diagram_id: Some("d-flow".into()),
+            ..params(false)
         }))
         .await
-        .expect("flow paths");
-
-    assert!(result.paths.is_empty());
+    {
+        Ok(_) => panic!("expected a sequence diagram to be required"),
+        Err(err) => err,
+    };
+    assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
 }
 
 #[tokio::test]
-async fn flow_paths_rejects_invalid_node_id() {
+async fn diagram_scaffold_sequence_turns_flow_path_into_linked_messages() {
     let server = NereidMcp::new(demo_session_for_flow_paths());
-    let err = match server
+    let params = |node_ids: &[&str]| DiagramScaffoldSequenceParams {
+        diagram_id: None,
+        node_ids: node_ids.iter().map(|id| (*id).to_owned()).collect(),
+        target_diagram_id: None,
+        name: None,
+        make_active: None,
+    };
+
+    let Json(scaffold) = server
+        .diagram_scaffold_sequence(Parameters(params(&["n:a", "n:b", "n:d"])))
+        .await
+        .expect("scaffold sequence");
+    assert_eq!(scaffold.diagram.diagram_id, "d-flow-paths-seq");
+    assert_eq!(scaffold.diagram.kind, "Sequence");
+    assert_eq!(scaffold.active_diagram_id.as_deref(), Some("d-flow-paths-seq"));
+    assert_eq!(scaffold.xref_ids, vec!["x:1", "x:2", "x:3", "x:4", "x:5"]);
+
+    let Json(xrefs) = server.xref_list(Parameters(xref_list_params())).await.expect("xref list");
+    let links = xrefs
+        .xrefs
+        .iter()
+        .map(|x| (x.from.as_str(), x.to.as_str(), x.kind.as_str()))
+        .collect::<Vec<_>>();
+    assert!(links.contains(&(
+        "d:d-flow-paths-seq/seq/message/m:0002",
+        "d:d-flow-paths/flow/edge/e:bd",
+        "derived_from",
+    )));
+
+    let err = match server
+        .diagram_scaffold_sequence(Parameters(DiagramScaffoldSequenceParams {
+            diagram_id: Some("d-flow-paths".into()),
+            ..params(&["n:a", "n:d"])
+        }))
+        .await
+    {
+        Ok(_) => panic!("expected n:a and n:d to be non-adjacent"),
+        Err(err) => err,
+    };
+    assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+}
+
+#[tokio::test]
+async fn diagram_critical_path_uses_message_durations_and_edge_weights() {
+    let server = NereidMcp::new(demo_session());
+    server
+        .diagram_apply_ops(Parameters(ApplyOpsParams {
+            diagram_id: None,
+            base_rev: 0,
+            ops: vec![McpOp::SeqSetMessageDuration {
+                message_id: "m:1".into(),
+                duration: Some(40),
+            }],
+        }))
+        .await
+        .expect("set duration");
+
+    let Json(seq) = server
+        .diagram_critical_path(Parameters(DiagramCriticalPathParams { diagram_id: None }))
+        .await
+        .expect("sequence critical path");
+    assert_eq!(seq.kind, "Sequence");
+    assert_eq!(seq.total, Some(40));
+    assert_eq!(seq.steps.len(), 1);
+    assert_eq!(seq.steps[0].object_ref, "d:d-seq/seq/message/m:1");
+    assert_eq!(seq.summary, "Hi (40)");
+
+    let server = NereidMcp::new(demo_session_for_flow_paths());
+    server
+        .diagram_apply_ops(Parameters(ApplyOpsParams {
+            diagram_id: None,
+            base_rev: 0,
+            ops: vec![McpOp::FlowSetEdgeWeight { edge_id: "e:ab".into(), weight: Some(10) }],
+        }))
+        .await
+        .expect("set weight");
+
+    let Json(flow) = server
+        .diagram_critical_path(Parameters(DiagramCriticalPathParams { diagram_id: None }))
+        .await
+        .expect("flow critical path");
+    assert_eq!(flow.total, Some(11));
+    let steps = flow
+        .steps
+        .iter()
+        .map(|step| (step.object_ref.as_str(), step.duration, step.finish))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        steps,
+        vec![
+            ("d:d-flow-paths/flow/node/n:a", 0, 0),
+            ("d:d-flow-paths/flow/edge/e:ab", 10, 10),
+            ("d:d-flow-paths/flow/node/n:b", 0, 10),
+            ("d:d-flow-paths/flow/edge/e:bd", 1, 11),
+            ("d:d-flow-paths/flow/node/n:d", 0, 11),
+        ]
+    );
+    assert_eq!(flow.summary, "A (0) -> B (10) -> D (11)");
+}
+
+#[tokio::test]
+async fn diagram_summarize_reports_structure_without_sampling() {
+    let mut session = demo_session();
+    session.xrefs_mut().insert(
+        XRefId::new("x:gone").expect("xref id"),
+        XRef::new(
+            oref("d-flow", "n:gone"),
+            oref("d-seq", "p:a"),
+            "relates_to",
+            XRefStatus::DanglingFrom,
+        ),
+    );
+    let server = NereidMcp::new(session);
+
+    let Json(flow) = server
+        .diagram_summarize(Parameters(DiagramTargetParams { diagram_id: Some("d-flow".into()) }))
+        .await
+        .expect("flow summary");
+    assert_eq!(flow.kind, "Flowchart");
+    assert_eq!(flow.counts.get("nodes"), Some(&2));
+    assert_eq!(flow.entry_points, vec!["d:d-flow/flow/node/n:a"]);
+    assert_eq!(
+        flow.main_paths,
+        vec![vec!["d:d-flow/flow/node/n:a".to_owned(), "d:d-flow/flow/node/n:b".to_owned()]]
+    );
+    assert!(flow.cycles.is_empty());
+    assert_eq!(flow.dangling_xrefs, vec!["x:gone"]);
+    assert_eq!(
+        flow.summary,
+        "Flowchart \"Flow\" (d-flow, rev 0)\n\
+         Counts: 2 nodes, 1 edge\n\
+         Entry points: A\n\
+         Main paths: A -> B\n\
+         Cycles: none\n\
+         Dangling xrefs: x:gone"
+    );
+
+    let Json(seq) = server
+        .diagram_summarize(Parameters(DiagramTargetParams { diagram_id: None }))
+        .await
+        .expect("sequence summary");
+    assert_eq!(seq.entry_points, vec!["d:d-seq/seq/participant/p:a"]);
+    assert!(seq.summary.contains("Main paths: A -> B\n"), "{}", seq.summary);
+
+    server
+        .diagram_create_from_mermaid(Parameters(DiagramCreateFromMermaidParams {
+            mermaid: "stateDiagram-v2\n[*] --> Idle\nIdle --> Busy\nBusy --> Idle\nBusy --> [*]\n"
+                .into(),
+            diagram_id: None,
+            name: Some("Worker".into()),
+            make_active: Some(true),
+        }))
+        .await
+        .expect("create state diagram");
+    let Json(state) = server
+        .diagram_summarize(Parameters(DiagramTargetParams { diagram_id: None }))
+        .await
+        .expect("state summary");
+    assert_eq!(state.entry_points, vec!["d:state/state/node/s:[start]"]);
+    assert_eq!(state.main_paths[0].last().map(String::as_str), Some("d:state/state/node/s:[end]"));
+    assert_eq!(state.cycles.len(), 1);
+    assert!(state.summary.contains("Cycles: "), "{}", state.summary);
+}
+
+#[tokio::test]
+async fn flow_paths_returns_multiple_paths_in_deterministic_order() {
+    let server = NereidMcp::new(demo_session_for_flow_paths());
+    let Json(result) = server
+        .flow_paths(Parameters(FlowPathsParams {
+            diagram_id: None,
+            from_node_id: "n:a".into(),
+            to_node_id: "n:d".into(),
+            limit: None,
+            max_extra_hops: None,
+        }))
+        .await
+        .expect("flow paths");
+
+    assert_eq!(
+        result.paths,
+        vec![
+            vec![
+                "d:d-flow-paths/flow/node/n:a",
+                "d:d-flow-paths/flow/node/n:b",
+                "d:d-flow-paths/flow/node/n:d",
+            ],
+            vec![
+                "d:d-flow-paths/flow/node/n:a",
+                "d:d-flow-paths/flow/node/n:c",
+                "d:d-flow-paths/flow/node/n:d",
+            ],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn flow_highlight_path_adds_route_nodes_and_edges_to_the_selection() {
+    let server = NereidMcp::new(demo_session_for_flow_paths());
+    let params = |from: &str, to: &str, limit| FlowHighlightPathParams {
+        diagram_id: None,
+        from_node_id: from.into(),
+        to_node_id: to.into(),
+        limit,
+        max_extra_hops: None,
+    };
+
+    let Json(shortest) =
+        server.flow_highlight_path(Parameters(params("n:a", "n:d", None))).await.expect("path");
+    assert_eq!(shortest.paths.len(), 1);
+    assert_eq!(
+        shortest.object_refs,
+        vec![
+            "d:d-flow-paths/flow/node/n:a",
+            "d:d-flow-paths/flow/node/n:b",
+            "d:d-flow-paths/flow/node/n:d",
+            "d:d-flow-paths/flow/edge/e:ab",
+            "d:d-flow-paths/flow/edge/e:bd",
+        ]
+    );
+    assert_eq!(shortest.selected_count, 5);
+
+    let Json(all) =
+        server.flow_highlight_path(Parameters(params("n:a", "n:d", Some(10)))).await.expect("all");
+    assert_eq!(all.paths.len(), 2);
+    assert_eq!(all.selected_count, 8);
+
+    let Json(none) =
+        server.flow_highlight_path(Parameters(params("n:d", "n:a", None))).await.expect("none");
+    assert!(none.paths.is_empty() && none.object_refs.is_empty());
+    assert_eq!(none.selected_count, 8);
+
+    let Err(err) = server.flow_highlight_path(Parameters(params("n:a", "n:missing", None))).await
+    else {
+        panic!("expected missing node error");
+    };
+    assert_eq!(err.code, rmcp::model::ErrorCode::RESOURCE_NOT_FOUND);
+}
+
+#[tokio::test]
+async fn flow_paths_returns_empty_when_no_path_exists() {
+    let server = NereidMcp::new(demo_session_for_flow_paths());
+    let Json(result) = server
+        .flow_paths(Parameters(FlowPathsParams {
+            diagram_id: None,
+            from_node_id: "n:d".into(),
+            to_node_id: "n:a".into(),
+            limit: None,
+            max_extra_hops: None,
+        }))
+        .await
+        .expect("flow paths");
+
+    assert!(result.paths.is_empty());
+}
+
+#[tokio::test]
+async fn flow_paths_rejects_invalid_node_id() {
+    let server = NereidMcp::new(demo_session_for_flow_paths());
+    let err = match server
         .flow_paths(Parameters(FlowPathsParams {
             diagram_id: None,
             from_node_id: "n/a".into(),
@@ -2252,6 +3329,64 @@ async fn flow_dead_ends_returns_terminal_nodes() {
     );
 }
 
+#[tokio::test]
+async fn flow_dominators_and_cut_points_find_the_nodes_everything_passes() {
+    let mut session = Session::new(SessionId::new("s:dominators").expect("session id"));
+    let diagram_id = DiagramId::new("d-flow").expect("diagram id");
+    let ast =
+        parse_flowchart_with_id_rules("flowchart TD\na --> b\nb --> c\na --> c\nc --> d\n", &[])
+            .expect("parse");
+    session.diagrams_mut().insert(
+        diagram_id.clone(),
+        Diagram::new(diagram_id.clone(), "Flow", DiagramAst::Flowchart(ast)),
+    );
+    session.set_active_diagram_id(Some(diagram_id));
+    let server = NereidMcp::new(session);
+
+    let Json(result) = server
+        .flow_dominators(Parameters(FlowDominatorsParams { diagram_id: None, entry_node_id: None }))
+        .await
+        .expect("flow dominators");
+    assert_eq!(result.entry_nodes, vec!["d:d-flow/flow/node/n:a"]);
+    let nodes = result
+        .nodes
+        .iter()
+        .map(|node| (node.node.as_str(), node.immediate_dominator.as_deref(), node.dominates))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        nodes,
+        vec![
+            ("d:d-flow/flow/node/n:a", None, 3),
+            ("d:d-flow/flow/node/n:c", Some("d:d-flow/flow/node/n:a"), 1),
+            ("d:d-flow/flow/node/n:b", Some("d:d-flow/flow/node/n:a"), 0),
+            ("d:d-flow/flow/node/n:d", Some("d:d-flow/flow/node/n:c"), 0),
+        ]
+    );
+
+    let Json(from_c) = server
+        .flow_dominators(Parameters(FlowDominatorsParams {
+            diagram_id: None,
+            entry_node_id: Some("n:c".into()),
+        }))
+        .await
+        .expect("flow dominators from c");
+    assert_eq!(from_c.nodes.len(), 2);
+    assert!(server
+        .flow_dominators(Parameters(FlowDominatorsParams {
+            diagram_id: None,
+            entry_node_id: Some("n:missing".into()),
+        }))
+        .await
+        .is_err());
+
+    let Json(cuts) = server
+        .flow_cut_points(Parameters(DiagramTargetParams { diagram_id: None }))
+        .await
+        .expect("flow cut points");
+    assert_eq!(cuts.nodes, vec!["d:d-flow/flow/node/n:c"]);
+    assert_eq!(cuts.bridges, vec!["d:d-flow/flow/edge/e:0004"]);
+}
+
 #[tokio::test]
 async fn flow_degrees_defaults_to_sort_by_out_and_truncates() {
     let server = NereidMcp::new(demo_session_for_flow_degrees());
@@ -2427,6 +3562,56 @@ async fn flow_unreachable_returns_unreachable_nodes_in_deterministic_order() {
     assert_eq!(again.nodes, result.nodes);
 }
 
+#[tokio::test]
+async fn flow_entry_points_are_marked_via_ops_and_used_as_default_roots() {
+    let server = NereidMcp::new(demo_session_for_flow_reachable());
+
+    let Json(inferred) = server
+        .flow_entry_points(Parameters(DiagramTargetParams { diagram_id: None }))
+        .await
+        .expect("entry points");
+    assert!(!inferred.marked);
+    assert_eq!(inferred.nodes, vec!["d:d-flow-reach/flow/node/n:a"]);
+
+    server
+        .diagram_apply_ops(Parameters(ApplyOpsParams {
+            diagram_id: None,
+            base_rev: 0,
+            ops: vec![McpOp::FlowSetNodeEntry { node_id: "n:b".into(), entry: true }],
+        }))
+        .await
+        .expect("mark entry");
+
+    let Json(marked) = server
+        .flow_entry_points(Parameters(DiagramTargetParams { diagram_id: None }))
+        .await
+        .expect("entry points");
+    assert!(marked.marked);
+    assert_eq!(marked.nodes, vec!["d:d-flow-reach/flow/node/n:b"]);
+
+    let Json(reachable) = server
+        .flow_reachable(Parameters(FlowReachableParams {
+            diagram_id: None,
+            from_node_id: None,
+            direction: None,
+        }))
+        .await
+        .expect("flow reachable");
+    assert_eq!(
+        reachable.nodes,
+        vec!["d:d-flow-reach/flow/node/n:b", "d:d-flow-reach/flow/node/n:c"]
+    );
+
+    let Json(unreachable) = server
+        .flow_unreachable(Parameters(FlowUnreachableParams {
+            diagram_id: None,
+            start_node_id: None,
+        }))
+        .await
+        .expect("flow unreachable");
+    assert_eq!(unreachable.nodes, vec!["d:d-flow-reach/flow/node/n:a"]);
+}
+
 #[tokio::test]
 async fn flow_unreachable_rejects_non_flowchart_diagram() {
     let server = NereidMcp::new(demo_session_for_seq_trace());
@@ -2570,6 +3755,194 @@ async fn object_read_rejects_when_object_ref_and_object_refs_are_both_set() {
     assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
 }
 
+#[tokio::test]
+async fn object_get_and_describe_report_context_of_one_object() {
+    let mut session = demo_session_with_seq_blocks();
+    let message_ref = "d:d-seq-blocks/seq/message/m:2";
+    session.xrefs_mut().insert(
+        XRefId::new("x:1").expect("xref id"),
+        XRef::new(
+            ObjectRef::from_str(message_ref).expect("from ref"),
+            oref("d-flow", "n:a"),
+            "calls",
+            XRefStatus::DanglingTo,
+        ),
+    );
+    let mut walkthrough =
+        Walkthrough::new(WalkthroughId::new("w:1").expect("walkthrough id"), "Tour");
+    let mut step =
+        WalkthroughNode::new(WalkthroughNodeId::new("wn:1").expect("node id"), "Fallback");
+    step.refs_mut().push(ObjectRef::from_str(message_ref).expect("ref"));
+    walkthrough.nodes_mut().push(step);
+    session.walkthroughs_mut().insert(walkthrough.walkthrough_id().clone(), walkthrough);
+    let server = NereidMcp::new(session);
+
+    let params =
+        |object_ref: &str| Parameters(ObjectDetailParams { object_ref: object_ref.into() });
+    let Json(detail) = server.object_get(params(message_ref)).await.expect("object get");
+    assert_eq!(detail.diagram_id, "d-seq-blocks");
+    assert!(matches!(&detail.object, McpObject::SeqMessage { text, .. } if text == "Else"));
+    assert!(!detail.render_spans.is_empty());
+    assert_eq!(
+        detail.containers,
+        vec!["d:d-seq-blocks/seq/section/sec:0000:01", "d:d-seq-blocks/seq/block/b:0000"]
+    );
+    assert_eq!(detail.xrefs_out.len(), 1);
+    assert!(detail.xrefs_in.is_empty());
+    assert_eq!(
+        detail.walkthrough_steps,
+        vec![WalkthroughStepRef {
+            walkthrough_id: "w:1".into(),
+            node_id: "wn:1".into(),
+            title: "Fallback".into(),
+        }]
+    );
+
+    let Json(described) = server.object_describe(params(message_ref)).await.expect("describe");
+    assert!(described.description.starts_with("# d:d-seq-blocks/seq/message/m:2\n"));
+    assert!(described.description.contains("- text: Else\n"));
+    assert!(described.description.contains("- calls d:d-flow/obj/n:a (x:1, dangling_to)\n"));
+    assert!(described.description.contains("- w:1/wn:1: Fallback\n"));
+
+    let err = match server.object_get(params("d:d-seq-blocks/seq/message/m:404")).await {
+        Ok(_) => panic!("expected resource_not_found"),
+        Err(err) => err,
+    };
+    assert_eq!(err.code, rmcp::model::ErrorCode::RESOURCE_NOT_FOUND);
+}
+
+#[tokio::test]
+async fn object_list_filters_pages_and_reports_links() {
+    let mut session = demo_session();
+    session.xrefs_mut().insert(
+        XRefId::new("x:1").expect("xref id"),
+        XRef::new(
+            ObjectRef::from_str("d:d-seq/seq/participant/p:a").expect("from ref"),
+            ObjectRef::from_str("d:d-flow/flow/node/n:a").expect("to ref"),
+            "implements",
+            XRefStatus::Ok,
+        ),
+    );
+    let server = NereidMcp::new(session);
+    let list =
+        |category: Option<&str>, tags: Option<Vec<&str>>, label: Option<&str>| ObjectListParams {
+            diagram_id: Some("d-flow".into()),
+            category: category.map(Into::into),
+            tags: tags.map(|tags| tags.into_iter().map(Into::into).collect()),
+            label_contains: label.map(Into::into),
+            offset: None,
+            limit: None,
+        };
+
+    let Json(all) = server.object_list(Parameters(list(None, None, None))).await.expect("list");
+    assert_eq!(all.diagram_id, "d-flow");
+    assert_eq!(all.total, 3);
+    assert_eq!(all.next_offset, None);
+    let node_a = &all.objects[0];
+    assert_eq!(node_a.object_ref, "d:d-flow/flow/node/n:a");
+    assert_eq!(node_a.category, "flow/node");
+    assert_eq!(node_a.label, "A");
+    assert!(node_a.tags.iter().any(|tag| tag == "xref"));
+    assert_eq!(
+        node_a.adjacency,
+        ObjectAdjacency { incoming: 0, outgoing: 1, xrefs_incoming: 1, xrefs_outgoing: 0 }
+    );
+    let edge = &all.objects[2];
+    assert_eq!(edge.anchors, vec!["d:d-flow/flow/node/n:a", "d:d-flow/flow/node/n:b"]);
+
+    let Json(nodes) = server
+        .object_list(Parameters(list(Some("flow/node"), Some(vec!["xref"]), Some("a"))))
+        .await
+        .expect("filtered list");
+    assert_eq!(nodes.total, 1);
+    assert_eq!(nodes.objects[0].object_ref, "d:d-flow/flow/node/n:a");
+
+    let Json(page) = server
+        .object_list(Parameters(ObjectListParams {
+            offset: Some(1),
+            limit: Some(1),
+            ..list(Some("flow"), None, None)
+        }))
+        .await
+        .expect("paged list");
+    assert_eq!(page.total, 3);
+    assert_eq!(page.objects.len(), 1);
+    assert_eq!(page.objects[0].object_ref, "d:d-flow/flow/node/n:b");
+    assert_eq!(page.next_offset, Some(2));
+
+    let Json(seq) = server
+        .object_list(Parameters(ObjectListParams {
+            diagram_id: None,
+            ..list(Some("seq/participant"), None, None)
+        }))
+        .await
+        .expect("seq list");
+    assert_eq!(seq.diagram_id, "d-seq");
+    assert_eq!(seq.objects[1].adjacency.incoming, 1);
+}
+
+#[tokio::test]
+async fn object_find_by_tag_searches_annotations_across_diagrams() {
+    let server = NereidMcp::new(demo_session());
+    let metadata = BTreeMap::from([("owner".to_owned(), "team-a".to_owned())]);
+    server
+        .diagram_apply_ops(Parameters(ApplyOpsParams {
+            diagram_id: Some("d-flow".into()),
+            base_rev: 0,
+            ops: vec![McpOp::FlowSetEdgeAnnotations {
+                edge_id: "e:ab".into(),
+                tags: vec!["billing".into(), " billing ".into()],
+                metadata: metadata.clone(),
+            }],
+        }))
+        .await
+        .expect("annotate edge");
+    server
+        .diagram_apply_ops(Parameters(ApplyOpsParams {
+            diagram_id: Some("d-seq".into()),
+            base_rev: 0,
+            ops: vec![McpOp::SeqSetMessageAnnotations {
+                message_id: "m:1".into(),
+                tags: vec!["billing".into()],
+                metadata: BTreeMap::new(),
+            }],
+        }))
+        .await
+        .expect("annotate message");
+
+    let find = |tag: &str, diagram_id: Option<&str>| ObjectFindByTagParams {
+        tag: tag.into(),
+        diagram_id: diagram_id.map(Into::into),
+    };
+    let Json(all) =
+        server.object_find_by_tag(Parameters(find("billing", None))).await.expect("find");
+    let refs = all.objects.iter().map(|item| item.object_ref.as_str()).collect::<Vec<_>>();
+    assert_eq!(refs, vec!["d:d-flow/flow/edge/e:ab", "d:d-seq/seq/message/m:1"]);
+    assert_eq!(all.objects[0].metadata, metadata);
+    assert_eq!(all.objects[0].tags.iter().filter(|tag| *tag == "billing").count(), 1);
+
+    let Json(flow_only) = server
+        .object_find_by_tag(Parameters(find("billing", Some("d-flow"))))
+        .await
+        .expect("find in diagram");
+    assert_eq!(flow_only.objects.len(), 1);
+
+    let Json(list) = server
+        .object_list(Parameters(ObjectListParams {
+            diagram_id: Some("d-flow".into()),
+            category: None,
+            tags: Some(vec!["billing".into()]),
+            label_contains: None,
+            offset: None,
+            limit: None,
+        }))
+        .await
+        .expect("list by tag");
+    assert_eq!(list.total, 1);
+
+    assert!(server.object_find_by_tag(Parameters(find(" ", None))).await.is_err());
+}
+
 #[tokio::test]
 async fn diagram_current_returns_null_when_unset() {
     let session = Session::new(SessionId::new("s:mcp-unset").expect("session id"));
@@ -2672,6 +4045,206 @@ async fn diagram_open_sets_active_diagram() {
     assert_eq!(digest.counts.edges, 1);
 }
 
+#[tokio::test]
+async fn diagram_create_adds_empty_or_seeded_diagrams_and_persists_them() {
+    let dir = temp_session_dir("diagram-create");
+    let folder = SessionFolder::new(dir.clone());
+    let session = folder.load_or_init_session().expect("init session");
+    let server = NereidMcp::new_persistent(session, folder.clone());
+
+    let Json(empty) = server
+        .diagram_create(Parameters(DiagramCreateParams {
+            kind: "Sequence".into(),
+            diagram_id: Some("checkout".into()),
+            name: Some("Checkout".into()),
+            mermaid: None,
+            make_active: None,
+        }))
+        .await
+        .expect("create empty sequence");
+    assert_eq!(empty.diagram.kind, "Sequence");
+    assert_eq!(empty.active_diagram_id.as_deref(), Some("checkout"));
+
+    let Json(seeded) = server
+        .diagram_create(Parameters(DiagramCreateParams {
+            kind: "flowchart".into(),
+            diagram_id: None,
+            name: None,
+            mermaid: Some("flowchart LR\n  a --> b\n".into()),
+            make_active: Some(false),
+        }))
+        .await
+        .expect("create seeded flowchart");
+    assert_eq!(seeded.active_diagram_id.as_deref(), Some("checkout"));
+
+    for (kind, mermaid) in [("gantt", None), ("state", Some("flowchart LR\n  a --> b\n"))] {
+        let Err(err) = server
+            .diagram_create(Parameters(DiagramCreateParams {
+                kind: kind.into(),
+                diagram_id: None,
+                name: None,
+                mermaid: mermaid.map(Into::into),
+                make_active: None,
+            }))
+            .await
+        else {
+            panic!("expected invalid params for {kind}");
+        };
+        assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS, "{kind}");
+    }
+
+    let reloaded = folder.load_session().expect("reload session");
+    let checkout = reloaded.diagrams().get("checkout").expect("persisted empty diagram");
+    assert_eq!(checkout.name(), "Checkout");
+    let flow = reloaded.diagrams().get(seeded.diagram.diagram_id.as_str()).expect("persisted");
+    assert_eq!(flow.kind(), DiagramKind::Flowchart);
+    assert_eq!(reloaded.active_diagram_id().map(DiagramId::as_str), Some("checkout"));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn diagram_create_from_mermaid_supports_state_diagrams() {
+    let session = Session::new(SessionId::new("s:mcp-create-state").expect("session id"));
+    let server = NereidMcp::new(session);
+
+    let Json(created) = server
+        .diagram_create_from_mermaid(Parameters(DiagramCreateFromMermaidParams {
+            mermaid: "stateDiagram-v2\n[*] --> Idle\nIdle --> Busy : work\nBusy --> Idle\n".into(),
+            diagram_id: None,
+            name: Some("Worker".into()),
+            make_active: Some(true),
+        }))
+        .await
+        .expect("create state diagram");
+    assert_eq!(created.diagram.diagram_id, "state");
+    assert_eq!(created.diagram.kind, "State");
+
+    let Json(ast) = server
+        .diagram_get_ast(Parameters(DiagramTargetParams { diagram_id: None }))
+        .await
+        .expect("diagram ast");
+    let McpDiagramAst::State { states, transitions } = ast.ast else {
+        panic!("expected state ast");
+    };
+    let state_ids = states.iter().map(|s| s.state_id.as_str()).collect::<Vec<_>>();
+    assert_eq!(state_ids, vec!["s:Busy", "s:Idle", "s:[start]"]);
+    assert_eq!(states[2].kind, "start");
+    assert_eq!(transitions.len(), 3);
+    assert_eq!(transitions[1].label.as_deref(), Some("work"));
+
+    let Json(listed) = server
+        .object_list(Parameters(ObjectListParams {
+            diagram_id: None,
+            category: Some("state/node".into()),
+            tags: None,
+            label_contains: Some("idle".into()),
+            offset: None,
+            limit: None,
+        }))
+        .await
+        .expect("object list");
+    assert_eq!(listed.total, 1);
+    assert_eq!(listed.objects[0].object_ref, "d:state/state/node/s:Idle");
+    assert_eq!(listed.objects[0].adjacency.incoming, 2);
+
+    let Json(slice) = server
+        .diagram_get_slice(Parameters(DiagramGetSliceParams {
+            diagram_id: None,
+            center_ref: "d:state/state/node/s:Busy".into(),
+            radius: None,
+            depth: None,
+            filters: None,
+        }))
+        .await
+        .expect("diagram slice");
+    assert_eq!(slice.objects, vec!["d:state/state/node/s:Busy", "d:state/state/node/s:Idle"]);
+    assert_eq!(
+        slice.edges,
+        vec!["d:state/state/transition/t:0001", "d:state/state/transition/t:0002"]
+    );
+
+    let Json(rendered) = server
+        .diagram_render_text(Parameters(DiagramRenderTextParams {
+            diagram_id: None,
+            ..Default::default()
+        }))
+        .await
+        .expect("diagram render");
+    assert!(rendered.text.contains("Busy"), "unexpected render:\n{}", rendered.text);
+}
+
+#[tokio::test]
+async fn diagram_create_from_mermaid_supports_class_diagrams() {
+    let session = Session::new(SessionId::new("s:mcp-create-class").expect("session id"));
+    let server = NereidMcp::new(session);
+
+    let Json(created) = server
+        .diagram_create_from_mermaid(Parameters(DiagramCreateFromMermaidParams {
+            mermaid:
+                "classDiagram\nAnimal <|-- Duck\nDuck : +swim()\nPond o-- Duck\nZoo --> Pond\n"
+                    .into(),
+            diagram_id: None,
+            name: Some("Zoo".into()),
+            make_active: Some(true),
+        }))
+        .await
+        .expect("create class diagram");
+    assert_eq!(created.diagram.diagram_id, "class");
+    assert_eq!(created.diagram.kind, "Class");
+
+    let Json(ast) = server
+        .diagram_get_ast(Parameters(DiagramTargetParams { diagram_id: None }))
+        .await
+        .expect("diagram ast");
+    let McpDiagramAst::Class { classes, relations } = ast.ast else {
+        panic!("expected class ast");
+    };
+    let class_ids = classes.iter().map(|c| c.class_id.as_str()).collect::<Vec<_>>();
+    assert_eq!(class_ids, vec!["c:Animal", "c:Duck", "c:Pond", "c:Zoo"]);
+    assert_eq!(classes[1].members, vec!["+swim()"]);
+    let kinds = relations.iter().map(|r| r.kind.as_str()).collect::<Vec<_>>();
+    assert_eq!(kinds, vec!["inheritance", "aggregation", "association"]);
+    assert_eq!(relations[0].to_class_id, "c:Animal");
+
+    let Json(listed) = server
+        .object_list(Parameters(ObjectListParams {
+            diagram_id: None,
+            category: Some("class/node".into()),
+            tags: None,
+            label_contains: Some("duck".into()),
+            offset: None,
+            limit: None,
+        }))
+        .await
+        .expect("object list");
+    assert_eq!(listed.total, 1);
+    assert_eq!(listed.objects[0].object_ref, "d:class/class/node/c:Duck");
+    assert_eq!(listed.objects[0].adjacency.outgoing, 2);
+
+    let Json(slice) = server
+        .diagram_get_slice(Parameters(DiagramGetSliceParams {
+            diagram_id: None,
+            center_ref: "d:class/class/node/c:Zoo".into(),
+            radius: None,
+            depth: None,
+            filters: None,
+        }))
+        .await
+        .expect("diagram slice");
+    assert_eq!(slice.objects, vec!["d:class/class/node/c:Pond", "d:class/class/node/c:Zoo"]);
+    assert_eq!(slice.edges, vec!["d:class/class/relation/r:0002"]);
+
+    let Json(rendered) = server
+        .diagram_render_text(Parameters(DiagramRenderTextParams {
+            diagram_id: None,
+            ..Default::default()
+        }))
+        .await
+        .expect("diagram render");
+    assert!(rendered.text.contains("+swim()"), "unexpected render:\n{}", rendered.text);
+}
+
 #[tokio::test]
 async fn diagram_create_from_mermaid_rejects_unrenderable_flowchart() {
     let session = Session::new(SessionId::new("s:mcp-create-unrenderable").expect("session id"));
@@ -2699,11 +4272,234 @@ async fn diagram_create_from_mermaid_rejects_unrenderable_flowchart() {
     assert!(err.message.contains("flowchart layout error"), "unexpected message: {}", err.message);
     assert!(err.message.contains("contains a cycle"), "unexpected message: {}", err.message);
 
-    let Json(diagrams) = server.diagram_list().await.expect("diagram list");
-    assert!(diagrams.diagrams.is_empty());
+    let Json(diagrams) =
+        server.diagram_list(Parameters(DiagramListParams::default())).await.expect("diagram list");
+    assert!(diagrams.diagrams.is_empty());
+
+    let Json(current) = server.diagram_current().await.expect("diagram current");
+    assert!(current.active_diagram_id.is_none());
+}
+
+#[tokio::test]
+async fn diagram_import_table_creates_flowchart_from_rows() {
+    let session = Session::new(SessionId::new("s:mcp-import-table").expect("session id"));
+    let server = NereidMcp::new(session);
+
+    let rows = |value: serde_json::Value| -> Vec<serde_json::Map<String, serde_json::Value>> {
+        serde_json::from_value(value).expect("rows")
+    };
+    let Json(imported) = server
+        .diagram_import_table(Parameters(DiagramImportTableParams {
+            nodes: rows(serde_json::json!([
+                { "key": "api", "name": "API" },
+                { "key": "db", "name": "Database", "shape": "diamond" },
+            ])),
+            edges: Some(rows(serde_json::json!([{ "from": "api", "to": "db", "label": "sql" }]))),
+            mapping: Some(BTreeMap::from([
+                ("node_id".to_owned(), "key".to_owned()),
+                ("node_label".to_owned(), "name".to_owned()),
+            ])),
+            diagram_id: None,
+            name: Some("Inventory".into()),
+            make_active: None,
+        }))
+        .await
+        .expect("import");
+
+    assert_eq!(imported.diagram.diagram_id, "flow");
+    assert_eq!(imported.diagram.kind, "Flowchart");
+    assert_eq!(imported.active_diagram_id.as_deref(), Some("flow"));
+    assert_eq!((imported.node_count, imported.edge_count), (2, 1));
+
+    let err = match server
+        .diagram_import_table(Parameters(DiagramImportTableParams {
+            nodes: rows(serde_json::json!([{ "id": "a" }])),
+            edges: Some(rows(serde_json::json!([{ "from": "a", "to": "missing" }]))),
+            mapping: None,
+            diagram_id: None,
+            name: None,
+            make_active: None,
+        }))
+        .await
+    {
+        Ok(_) => panic!("expected unknown node error"),
+        Err(err) => err,
+    };
+    assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+    assert!(err.message.contains("unknown node id 'missing'"), "unexpected: {}", err.message);
+}
+
+#[tokio::test]
+async fn diagram_export_subset_uses_selection_or_explicit_refs() {
+    let mut session = demo_session_for_flow_reachable();
+    for object_ref in ["d:d-flow-reach/flow/node/n:b", "d:d-flow-reach/flow/node/n:c"] {
+        session.selected_object_refs_mut().insert(ObjectRef::from_str(object_ref).expect("ref"));
+    }
+    let server = NereidMcp::new(session);
+
+    let Json(selected) = server
+        .diagram_export_subset(Parameters(DiagramExportSubsetParams {
+            diagram_id: None,
+            object_refs: None,
+            format: None,
+            theme: None,
+        }))
+        .await
+        .expect("export selection");
+    assert_eq!(selected.format, ExportFormat::Mermaid);
+    assert_eq!(
+        selected.object_refs,
+        vec![
+            "d:d-flow-reach/flow/node/n:b",
+            "d:d-flow-reach/flow/node/n:c",
+            "d:d-flow-reach/flow/edge/e:bc",
+        ]
+    );
+    assert!(!selected.text.contains("n_a"), "unexpected node in export: {}", selected.text);
+    assert_eq!(selected.session.session_id, "s:mcp-flow-reachable");
+
+    let Json(explicit) = server
+        .diagram_export_subset(Parameters(DiagramExportSubsetParams {
+            diagram_id: Some("d-flow-reach".into()),
+            object_refs: Some(vec!["d:d-flow-reach/flow/edge/e:ab".into()]),
+            format: Some(ExportFormat::Unicode),
+            theme: None,
+        }))
+        .await
+        .expect("export edge");
+    assert_eq!(
+        explicit.object_refs,
+        vec![
+            "d:d-flow-reach/flow/node/n:a",
+            "d:d-flow-reach/flow/node/n:b",
+            "d:d-flow-reach/flow/edge/e:ab",
+        ]
+    );
+    assert!(explicit.text.contains('A') && !explicit.text.contains('C'));
+}
+
+#[tokio::test]
+async fn diagram_export_subset_supports_dot_and_svg() {
+    let server = NereidMcp::new(demo_session_for_flow_reachable());
+    let object_refs = Some(vec!["d:d-flow-reach/flow/edge/e:ab".into()]);
+
+    let Json(dot) = server
+        .diagram_export_subset(Parameters(DiagramExportSubsetParams {
+            diagram_id: Some("d-flow-reach".into()),
+            object_refs: object_refs.clone(),
+            format: Some(ExportFormat::Dot),
+            theme: None,
+        }))
+        .await
+        .expect("export dot");
+    assert_eq!(dot.format, ExportFormat::Dot);
+    assert!(dot.text.starts_with("digraph "), "unexpected dot: {}", dot.text);
+    assert!(dot.text.contains("\"n:a\" -> \"n:b\""), "unexpected dot: {}", dot.text);
+    assert!(!dot.text.contains("n:c"), "unexpected node in dot: {}", dot.text);
+
+    let Json(svg) = server
+        .diagram_export_subset(Parameters(DiagramExportSubsetParams {
+            diagram_id: Some("d-flow-reach".into()),
+            object_refs,
+            format: Some(ExportFormat::Svg),
+            theme: Some("print".into()),
+        }))
+        .await
+        .expect("export svg");
+    assert_eq!(svg.format, ExportFormat::Svg);
+    assert!(svg.text.starts_with("<svg"), "unexpected svg: {}", &svg.text[..80]);
+    assert!(svg.text.contains("data-ref=\"d:d-flow-reach/flow/edge/e:ab\""));
+    assert!(!svg.text.contains("n:c"));
+    assert!(svg.text.contains("stroke=\"#000000\""), "svg not in print theme");
+
+    let err = match server
+        .diagram_export_subset(Parameters(DiagramExportSubsetParams {
+            diagram_id: Some("d-flow-reach".into()),
+            object_refs: None,
+            format: Some(ExportFormat::Svg),
+            theme: Some("neon".into()),
+        }))
+        .await
+    {
+        Ok(_) => panic!("expected unknown theme error"),
+        Err(err) => err,
+    };
+    assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+}
+
+#[tokio::test]
+async fn diagram_tidy_layout_previews_then_applies_order_hints() {
+    let mut session = Session::new(SessionId::new("s:mcp-tidy").expect("session id"));
+    let flow_id = DiagramId::new("d-tidy").expect("diagram id");
+    let mut flow_ast = FlowchartAst::default();
+    for id in ["n:a", "n:b", "n:c", "n:d", "n:e"] {
+        flow_ast.nodes_mut().insert(oid(id), FlowNode::new(id.trim_start_matches("n:")));
+    }
+    // `e:be` skips a layer, so only the tidy's crossing count sees it cross `e:ad`.
+    for (edge_id, from, to) in [
+        ("e:ad", "n:a", "n:d"),
+        ("e:ac", "n:a", "n:c"),
+        ("e:be", "n:b", "n:e"),
+        ("e:de", "n:d", "n:e"),
+        ("e:bd", "n:b", "n:d"),
+    ] {
+        flow_ast.edges_mut().insert(oid(edge_id), FlowEdge::new(oid(from), oid(to)));
+    }
+    session.diagrams_mut().insert(
+        flow_id.clone(),
+        Diagram::new(flow_id.clone(), "Tidy", DiagramAst::Flowchart(flow_ast)),
+    );
+    session.set_active_diagram_id(Some(flow_id));
+    let server = NereidMcp::new(session);
+
+    let Json(preview) = server
+        .diagram_tidy_layout(Parameters(DiagramTidyLayoutParams {
+            diagram_id: None,
+            apply: None,
+            base_rev: None,
+        }))
+        .await
+        .expect("preview");
+    assert_eq!((preview.before.crossings, preview.after.crossings), (1, 0));
+    assert!(preview.after.compacted_width <= preview.after.width);
+    assert_eq!(preview.preview.lines().count() as u64, preview.after.compacted_height);
+    assert!(!preview.applied);
+    assert_eq!(preview.new_rev, None);
+
+    let err = match server
+        .diagram_tidy_layout(Parameters(DiagramTidyLayoutParams {
+            diagram_id: None,
+            apply: Some(true),
+            base_rev: None,
+        }))
+        .await
+    {
+        Ok(_) => panic!("expected missing base_rev error"),
+        Err(err) => err,
+    };
+    assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+
+    let Json(applied) = server
+        .diagram_tidy_layout(Parameters(DiagramTidyLayoutParams {
+            diagram_id: None,
+            apply: Some(true),
+            base_rev: Some(0),
+        }))
+        .await
+        .expect("apply");
+    assert!(applied.applied);
+    assert_eq!(applied.new_rev, Some(1));
 
-    let Json(current) = server.diagram_current().await.expect("diagram current");
-    assert!(current.active_diagram_id.is_none());
+    let Json(again) = server
+        .diagram_tidy_layout(Parameters(DiagramTidyLayoutParams {
+            diagram_id: None,
+            apply: Some(true),
+            base_rev: Some(1),
+        }))
+        .await
+        .expect("re-tidy");
+    assert_eq!(again.before.crossings, 0);
+    assert!(!again.applied);
 }
 
 #[tokio::test]
@@ -2767,6 +4563,8 @@ async fn diagram_delete_retargets_active_and_prunes_selection() {
     server
         .attention_agent_set(Parameters(AttentionAgentSetParams {
             object_ref: "d:d-flow/flow/node/n:a".into(),
+            client_id: None,
+            name: None,
         }))
         .await
         .expect("set agent attention");
@@ -2778,7 +4576,8 @@ async fn diagram_delete_retargets_active_and_prunes_selection() {
     assert_eq!(result.deleted_diagram_id, "d-flow");
     assert_eq!(result.active_diagram_id.as_deref(), Some("d-seq"));
 
-    let Json(diagrams) = server.diagram_list().await.expect("diagram list");
+    let Json(diagrams) =
+        server.diagram_list(Parameters(DiagramListParams::default())).await.expect("diagram list");
     assert_eq!(diagrams.diagrams.len(), 1);
     assert_eq!(diagrams.diagrams[0].diagram_id, "d-seq");
 
@@ -2817,7 +4616,8 @@ async fn diagram_delete_last_diagram_clears_active_diagram() {
     assert_eq!(result.deleted_diagram_id, "d-only");
     assert_eq!(result.active_diagram_id, None);
 
-    let Json(diagrams) = server.diagram_list().await.expect("diagram list");
+    let Json(diagrams) =
+        server.diagram_list(Parameters(DiagramListParams::default())).await.expect("diagram list");
     assert!(diagrams.diagrams.is_empty());
 
     let Json(current) = server.diagram_current().await.expect("diagram current");
@@ -3268,7 +5068,10 @@ async fn diagram_render_text_renders_diagram_and_matches_renderer() {
 
     let server = NereidMcp::new(session);
     let Json(result) = server
-        .diagram_render_text(Parameters(DiagramTargetParams { diagram_id: Some("d-seq".into()) }))
+        .diagram_render_text(Parameters(DiagramRenderTextParams {
+            diagram_id: Some("d-seq".into()),
+            ..Default::default()
+        }))
         .await
         .expect("diagram render");
 
@@ -3291,13 +5094,147 @@ async fn diagram_render_text_uses_active_diagram_when_diagram_id_is_omitted() {
         .expect("set active diagram");
 
     let Json(result) = server
-        .diagram_render_text(Parameters(DiagramTargetParams { diagram_id: None }))
+        .diagram_render_text(Parameters(DiagramRenderTextParams {
+            diagram_id: None,
+            ..Default::default()
+        }))
         .await
         .expect("diagram render");
 
     assert_eq!(result.text, expected);
 }
 
+#[tokio::test]
+async fn diagram_render_returns_highlight_spans_and_crops_to_viewport() {
+    let session = demo_session();
+    let expected = {
+        let diagram_id = DiagramId::new("d-flow").expect("diagram id");
+        let diagram = session.diagrams().get(&diagram_id).expect("diagram");
+        crate::render::render_diagram_unicode_annotated(diagram).expect("render")
+    };
+    let node_b = "d:d-flow/flow/node/n:b".to_owned();
+    let node_b_ref = ObjectRef::parse(&node_b).expect("ref");
+    let &(line, col, _) = expected.highlight_index[&node_b_ref].first().expect("node b span");
+
+    let server = NereidMcp::new(session);
+    let render = |params: DiagramRenderParams| {
+        server.diagram_render(Parameters(DiagramRenderParams {
+            diagram_id: Some("d-flow".into()),
+            ..params
+        }))
+    };
+    let Json(full) = render(DiagramRenderParams::default()).await.expect("full render");
+    assert_eq!(full.text, expected.text);
+    assert!(full.clip.is_none());
+    assert_eq!(
+        full.highlights[&node_b],
+        expected.highlight_index[&node_b_ref]
+            .iter()
+            .map(|&(y, x0, x1)| [y as u64, x0 as u64, x1 as u64])
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(full.highlights.len(), expected.highlight_index.len());
+
+    let Json(cropped) = render(DiagramRenderParams {
+        line: Some(line as u64),
+        col: Some(col as u64),
+        width: Some(3),
+        height: Some(2),
+        ..Default::default()
+    })
+    .await
+    .expect("cropped render");
+    let clip = cropped.clip.expect("clip");
+    assert_eq!((clip.line, clip.col, clip.width, clip.height), (line as u64, col as u64, 3, 2));
+    let lines = cropped.text.split('\n').collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2);
+    assert!(lines.iter().all(|line| line.chars().count() == 3));
+    assert_eq!(cropped.highlights[&node_b][0][..2], [0, 0]);
+    assert!(cropped
+        .highlights
+        .values()
+        .flatten()
+        .all(|&[line, start, end]| line < 2 && start <= end && end < 3));
+}
+
+#[tokio::test]
+async fn diagram_render_text_clips_to_focus_ref_and_reports_changed_regions() {
+    let server = NereidMcp::new(demo_session());
+    let render = |params: DiagramRenderTextParams| {
+        server.diagram_render_text(Parameters(DiagramRenderTextParams {
+            diagram_id: Some("d-flow".into()),
+            ..params
+        }))
+    };
+
+    let Json(full) = render(DiagramRenderTextParams::default()).await.expect("full render");
+    assert!(full.clip.is_none() && full.regions.is_none());
+
+    let Json(with_legend) =
+        render(DiagramRenderTextParams { legend: Some(true), ..Default::default() })
+            .await
+            .expect("render with legend");
+    let (text, legend) = with_legend.text.split_once("\n\nLegend\n").expect("legend block");
+    assert_eq!(text, full.text);
+    assert!(legend.contains("highlighted by the agent"), "{legend}");
+
+    let Json(clipped) = render(DiagramRenderTextParams {
+        focus_ref: Some("d:d-flow/flow/node/n:b".into()),
+        width: Some(5),
+        height: Some(3),
+        ..Default::default()
+    })
+    .await
+    .expect("clipped render");
+    let clip = clipped.clip.expect("clip");
+    assert_eq!((clip.width, clip.height), (5, 3));
+    assert!(clip.total_cols > 5);
+    assert!(clipped.text.contains('B'), "unexpected clip:\n{}", clipped.text);
+    assert!(!clipped.text.contains('A'), "unexpected clip:\n{}", clipped.text);
+    assert_eq!(clipped.text.lines().count(), 3);
+
+    server
+        .diagram_apply_ops(Parameters(ApplyOpsParams {
+            diagram_id: Some("d-flow".into()),
+            base_rev: 0,
+            ops: vec![McpOp::FlowUpdateNode {
+                node_id: "n:b".into(),
+                label: Some("Bee".into()),
+                shape: None,
+            }],
+        }))
+        .await
+        .expect("apply");
+
+    let Json(changed) =
+        render(DiagramRenderTextParams { since_rev: Some(0), ..Default::default() })
+            .await
+            .expect("changed render");
+    let regions = changed.regions.expect("regions");
+    assert_eq!(regions.len(), 1);
+    assert_eq!(regions[0].object_refs, vec!["d:d-flow/flow/node/n:b"]);
+    assert!(changed.text.contains("Bee"), "unexpected regions:\n{}", changed.text);
+
+    let Json(unchanged) =
+        render(DiagramRenderTextParams { since_rev: Some(1), ..Default::default() })
+            .await
+            .expect("unchanged render");
+    assert_eq!(unchanged.regions.map(|regions| regions.len()), Some(0));
+    assert!(unchanged.text.is_empty());
+
+    let err = match render(DiagramRenderTextParams {
+        focus_ref: Some("d:d-flow/flow/node/n:b".into()),
+        since_rev: Some(0),
+        ..Default::default()
+    })
+    .await
+    {
+        Ok(_) => panic!("expected invalid params"),
+        Err(err) => err,
+    };
+    assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+}
+
 #[tokio::test]
 async fn apply_ops_conflicts_on_stale_base_rev() {
     let server = NereidMcp::new(demo_session());
@@ -3321,6 +5258,27 @@ async fn apply_ops_conflicts_on_stale_base_rev() {
     assert_eq!(data["digest"]["rev"].as_u64().unwrap(), 0);
 }
 
+#[tokio::test]
+async fn diagram_ops_apply_matches_apply_ops() {
+    let server = NereidMcp::new(demo_session());
+    let params = |base_rev| ApplyOpsParams {
+        diagram_id: Some("d-flow".into()),
+        base_rev,
+        ops: vec![McpOp::FlowAddNode { node_id: "n:c".into(), label: "C".into(), shape: None }],
+    };
+
+    let Json(applied) = server.diagram_ops_apply(Parameters(params(0))).await.expect("apply");
+    assert_eq!(applied.new_rev, 1);
+    assert_eq!(applied.applied, 1);
+    assert_eq!(applied.delta.added, vec!["d:d-flow/flow/node/n:c"]);
+
+    let err = match server.diagram_ops_apply(Parameters(params(0))).await {
+        Ok(_) => panic!("expected conflict error"),
+        Err(err) => err,
+    };
+    assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_REQUEST);
+}
+
 #[tokio::test]
 async fn apply_ops_maps_kind_mismatch_to_invalid_params() {
     let server = NereidMcp::new(demo_session());
@@ -3465,6 +5423,137 @@ async fn apply_ops_supports_setting_and_clearing_sequence_participant_note() {
     assert!(participants[0].note.is_none());
 }
 
+#[tokio::test]
+async fn diagram_compare_classifies_objects_between_versions() {
+    let dir = temp_session_dir("compare");
+    let folder = SessionFolder::new(dir.clone());
+    let mut session = Session::new(SessionId::new("s:compare").expect("session id"));
+    let diagram_id = DiagramId::new("d-flow").expect("diagram id");
+    let ast = parse_flowchart_with_id_rules("flowchart TD\nA --> B\n", &[]).expect("parse");
+    let diagram = Diagram::new(diagram_id.clone(), "Flow", DiagramAst::Flowchart(ast));
+    session.diagrams_mut().insert(diagram_id.clone(), diagram);
+    session.set_active_diagram_id(Some(diagram_id.clone()));
+    folder.save_session(&session).expect("save initial session");
+    let server = NereidMcp::new_persistent(session.clone(), folder);
+
+    let Json(saved) = server
+        .diagram_compare(Parameters(DiagramCompareParams::default()))
+        .await
+        .expect("compare with disk");
+    assert_eq!((saved.from.as_str(), saved.to.as_str()), ("disk", "current"));
+    assert!(saved.added.is_empty() && saved.removed.is_empty() && saved.changed.is_empty());
+
+    let Json(diff) = server
+        .diagram_compare(Parameters(DiagramCompareParams {
+            diagram_id: Some("d-flow".into()),
+            from_mermaid: None,
+            to_mermaid: Some("flowchart TD\nA[Start] --> C\n".into()),
+        }))
+        .await
+        .expect("compare with mermaid");
+    assert_eq!(diff.to, "mermaid");
+    assert_eq!(diff.added, vec!["d:d-flow/flow/node/n:C".to_owned()]);
+    assert_eq!(diff.removed, vec!["d:d-flow/flow/node/n:B".to_owned()]);
+    let changed = diff
+        .changed
+        .iter()
+        .map(|object| (object.object_ref.as_str(), object.fields.clone()))
+        .collect::<Vec<_>>();
+    assert!(changed.contains(&("d:d-flow/flow/node/n:A", vec!["label".to_owned()])), "{changed:?}");
+
+    let err = match server
+        .diagram_compare(Parameters(DiagramCompareParams {
+            to_mermaid: Some("sequenceDiagram\nA->>B: hi\n".into()),
+            ..DiagramCompareParams::default()
+        }))
+        .await
+    {
+        Ok(_) => panic!("expected kind mismatch"),
+        Err(err) => err,
+    };
+    assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+
+    let in_memory = NereidMcp::new(session);
+    let err = match in_memory.diagram_compare(Parameters(DiagramCompareParams::default())).await {
+        Ok(_) => panic!("expected missing saved version"),
+        Err(err) => err,
+    };
+    assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+}
+
+#[tokio::test]
+async fn apply_ops_adds_updates_and_removes_sequence_notes() {
+    let server = NereidMcp::new(demo_session());
+    let note_ref = "d:d-seq/seq/note/note:1".to_owned();
+    let apply = |base_rev: u64, ops: Vec<McpOp>| ApplyOpsParams { diagram_id: None, base_rev, ops };
+
+    let err = match server
+        .diagram_apply_ops(Parameters(apply(
+            0,
+            vec![McpOp::SeqAddNote {
+                note_id: "note:1".into(),
+                placement: NotePlacement::RightOf,
+                participant_ids: vec!["p:a".into(), "p:b".into()],
+                text: "spans both".into(),
+                order_key: 0,
+            }],
+        )))
+        .await
+    {
+        Ok(_) => panic!("expected participant count error"),
+        Err(err) => err,
+    };
+    assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+
+    let Json(result) = server
+        .diagram_apply_ops(Parameters(apply(
+            0,
+            vec![McpOp::SeqAddNote {
+                note_id: "note:1".into(),
+                placement: NotePlacement::Over,
+                participant_ids: vec!["p:a".into(), "p:b".into()],
+                text: "handshake".into(),
+                order_key: 0,
+            }],
+        )))
+        .await
+        .expect("add note");
+    assert_eq!(result.delta.added, vec![note_ref.clone()]);
+
+    let Json(result) = server
+        .diagram_apply_ops(Parameters(apply(
+            1,
+            vec![McpOp::SeqUpdateNote {
+                note_id: "note:1".into(),
+                placement: Some(NotePlacement::LeftOf),
+                participant_ids: Some(vec!["p:b".into()]),
+                text: Some("retry later".into()),
+                order_key: None,
+            }],
+        )))
+        .await
+        .expect("update note");
+    assert_eq!(result.delta.updated, vec![note_ref.clone()]);
+
+    let Json(rendered) = server
+        .diagram_render_text(Parameters(DiagramRenderTextParams {
+            diagram_id: None,
+            ..Default::default()
+        }))
+        .await
+        .expect("diagram render");
+    assert!(rendered.text.contains("retry later"), "unexpected render:\n{}", rendered.text);
+
+    let Json(result) = server
+        .diagram_apply_ops(Parameters(apply(
+            2,
+            vec![McpOp::SeqRemoveNote { note_id: "note:1".into() }],
+        )))
+        .await
+        .expect("remove note");
+    assert_eq!(result.delta.removed, vec![note_ref]);
+}
+
 #[tokio::test]
 async fn apply_ops_supports_setting_flow_node_note() {
     let server = NereidMcp::new(demo_session());
@@ -3859,7 +5948,7 @@ async fn diagram_diff_history_survives_external_selection_only_updates() {
             .collect(),
     );
     SessionFolder::new(dir_str)
-        .save_selected_object_refs(&external)
+        .save_selected_object_refs(&mut external)
         .expect("persist external selection");
 
     let Json(delta) = server
@@ -3962,7 +6051,8 @@ async fn diagram_list_refreshes_from_session_folder() {
     );
     SessionFolder::new(dir_str).save_session(&external).expect("persist external diagram");
 
-    let Json(list) = server.diagram_list().await.expect("diagram.list");
+    let Json(list) =
+        server.diagram_list(Parameters(DiagramListParams::default())).await.expect("diagram.list");
     assert!(list.diagrams.iter().any(|diagram| diagram.diagram_id == extra_id.as_str()));
 }
 
@@ -4050,6 +6140,313 @@ async fn selection_update_persists_to_session_folder() {
     assert!(loaded.selected_object_refs().contains(&expected));
 }
 
+#[tokio::test]
+async fn session_update_meta_persists_and_keeps_omitted_fields() {
+    let dir = temp_session_dir("mcp-persist-session-meta");
+    let dir_str = dir.to_string_lossy().to_string();
+    let folder = SessionFolder::new(dir_str.clone());
+
+    let session = demo_session();
+    folder.save_session(&session).expect("save initial session");
+
+    let server = NereidMcp::new_persistent(session, folder);
+    let Json(updated) = server
+        .session_update_meta(Parameters(SessionUpdateMetaParams {
+            title: Some("Checkout".to_owned()),
+            description: Some("Payment flows".to_owned()),
+            tags: Some(vec!["payments".to_owned(), " payments ".to_owned()]),
+            style_rules: Some(vec!["tag = deprecated => dim".to_owned()]),
+            id_prefix_rules: Some(vec!["flow/node=>svc".to_owned()]),
+        }))
+        .await
+        .expect("update meta");
+    assert_eq!(updated.title.as_deref(), Some("Checkout"));
+    assert_eq!(updated.tags, vec!["payments".to_owned()]);
+    assert_eq!(updated.style_rules, vec!["tag=deprecated => dim".to_owned()]);
+    assert_eq!(updated.id_prefix_rules, vec!["flow/node => svc".to_owned()]);
+
+    let err = match server
+        .session_update_meta(Parameters(SessionUpdateMetaParams {
+            title: Some("Ignored".to_owned()),
+            description: None,
+            tags: None,
+            style_rules: Some(vec!["degree => heavy".to_owned()]),
+            id_prefix_rules: None,
+        }))
+        .await
+    {
+        Ok(_) => panic!("invalid style rule must be rejected"),
+        Err(err) => err,
+    };
+    assert!(err.message.contains("invalid style rule"), "{}", err.message);
+
+    let Json(cleared) = server
+        .session_update_meta(Parameters(SessionUpdateMetaParams {
+            title: None,
+            description: Some(String::new()),
+            tags: None,
+            style_rules: None,
+            id_prefix_rules: None,
+        }))
+        .await
+        .expect("clear description");
+    assert_eq!(cleared.title.as_deref(), Some("Checkout"));
+    assert_eq!(cleared.description, None);
+
+    let loaded = SessionFolder::new(dir_str).load_session().expect("load session");
+    assert_eq!(loaded.title(), Some("Checkout"));
+    assert_eq!(loaded.description(), None);
+    assert_eq!(loaded.tags(), ["payments".to_owned()]);
+    assert_eq!(loaded.style_rules(), ["tag=deprecated => dim".parse().expect("rule")]);
+    assert_eq!(loaded.id_prefix_rules(), ["flow/node => svc".parse().expect("rule")]);
+
+    let Json(read) = server.session_read_meta().await.expect("read meta");
+    assert_eq!(read.session_id, loaded.session_id().as_str());
+    assert_eq!(read.title.as_deref(), Some("Checkout"));
+}
+
+#[tokio::test]
+async fn session_lint_ids_reports_prefix_violations_and_imports_follow_the_conventions() {
+    let mut session = demo_session();
+    session.set_id_prefix_rules(["flow/node => svc|db".parse().expect("rule")]);
+    let server = NereidMcp::new(session);
+
+    let Json(lint) = server.session_lint_ids().await.expect("lint ids");
+    let violations =
+        lint.violations.iter().map(|violation| violation.object_ref.as_str()).collect::<Vec<_>>();
+    assert!(violations.contains(&"d:d-flow/flow/node/n:a"), "{violations:?}");
+    assert!(lint.violations.iter().all(|violation| violation.rule == "flow/node => svc|db"));
+
+    let Json(created) = server
+        .diagram_create_from_mermaid(Parameters(DiagramCreateFromMermaidParams {
+            mermaid: "flowchart LR\nsvc_orders --> db_orders\n".to_owned(),
+            diagram_id: Some("orders".to_owned()),
+            name: None,
+            make_active: Some(false),
+        }))
+        .await
+        .expect("create from mermaid");
+    assert_eq!(created.diagram.diagram_id, "orders");
+    let Json(lint_after) = server.session_lint_ids().await.expect("lint ids");
+    assert_eq!(lint_after.violations.len(), lint.violations.len());
+}
+
+#[tokio::test]
+async fn session_validate_reports_dangling_xrefs_with_severity() {
+    let mut session = demo_session();
+    let baseline = NereidMcp::new(session.clone());
+    let Json(clean) = baseline.session_validate().await.expect("validate");
+    assert!(clean.ok, "{:?}", clean.diagnostics);
+
+    session.xrefs_mut().insert(
+        XRefId::new("x:gone").expect("xref id"),
+        XRef::new(
+            ObjectRef::from_str("d:d-flow/flow/node/n:a").expect("from ref"),
+            ObjectRef::from_str("d:d-flow/flow/node/n:missing").expect("to ref"),
+            "uses",
+            XRefStatus::DanglingTo,
+        ),
+    );
+    let server = NereidMcp::new(session);
+    let Json(report) = server.session_validate().await.expect("validate");
+    assert!(!report.ok);
+    assert_eq!((report.errors, report.warnings), (0, 1));
+    let diagnostic = &report.diagnostics[0];
+    assert_eq!(
+        (diagnostic.severity.as_str(), diagnostic.code.as_str(), diagnostic.subject.as_str()),
+        ("warning", "dangling_xref", "x:gone")
+    );
+    assert!(diagnostic.object_refs.contains(&"d:d-flow/flow/node/n:missing".to_owned()));
+}
+
+#[tokio::test]
+async fn workspace_open_swaps_the_session_and_follows_switches_from_the_tui() {
+    let root = temp_session_dir("workspace-open");
+    let alpha = SessionFolder::new(root.join("alpha"));
+    let alpha_session = alpha.load_or_init_session().expect("init alpha");
+    SessionFolder::new(root.join("beta")).save_session(&demo_session()).expect("save beta");
+
+    let Err(err) = NereidMcp::new(demo_session()).workspace_list_sessions().await else {
+        panic!("a server without workspace cannot list sessions");
+    };
+    assert!(err.message.contains("--workspace"), "{}", err.message);
+
+    let ui_state = Arc::new(Mutex::new(UiState::default()));
+    let server = NereidMcp::new_persistent_with_agent_highlights_and_ui_state(
+        alpha_session,
+        alpha,
+        Arc::new(Mutex::new(BTreeSet::new())),
+        Some(ui_state.clone()),
+    )
+    .with_workspace(Workspace::new(&root));
+
+    let Json(listed) = server.workspace_list_sessions().await.expect("list");
+    assert_eq!(listed.active_session.as_deref(), Some("alpha"));
+    let listed = listed
+        .sessions
+        .iter()
+        .map(|session| (session.name.as_str(), session.diagrams, session.active))
+        .collect::<Vec<_>>();
+    assert_eq!(listed, [("alpha", 1, true), ("beta", 2, false)]);
+
+    let Json(opened) = server
+        .workspace_open(Parameters(WorkspaceOpenParams { name: "beta".to_owned() }))
+        .await
+        .expect("open beta");
+    assert_eq!((opened.session_id.as_str(), opened.diagrams), ("s:mcp-demo", 2));
+    assert_eq!(ui_state.lock().await.workspace_session(), Some("beta"));
+    let Json(current) = server.diagram_current().await.expect("current");
+    assert_eq!(current.active_diagram_id, opened.active_diagram_id);
+
+    ui_state.lock().await.set_workspace_session("alpha".to_owned());
+    let Json(listed) = server.workspace_list_sessions().await.expect("list after tui switch");
+    assert_eq!(listed.active_session.as_deref(), Some("alpha"));
+    assert!(server
+        .workspace_open(Parameters(WorkspaceOpenParams { name: "../beta".to_owned() }))
+        .await
+        .is_err());
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn session_init_from_template_extends_the_session_or_opens_a_new_workspace_session() {
+    let root = temp_session_dir("init-from-template");
+    let alpha = SessionFolder::new(root.join("alpha"));
+    let alpha_session = alpha.load_or_init_session().expect("init alpha");
+    let ui_state = Arc::new(Mutex::new(UiState::default()));
+    let server = NereidMcp::new_persistent_with_agent_highlights_and_ui_state(
+        alpha_session,
+        alpha.clone(),
+        Arc::new(Mutex::new(BTreeSet::new())),
+        Some(ui_state.clone()),
+    )
+    .with_workspace(Workspace::new(&root));
+    let init = |template: &str, name: Option<&str>| SessionInitFromTemplateParams {
+        template: template.to_owned(),
+        name: name.map(str::to_owned),
+    };
+
+    let Json(added) = server
+        .session_init_from_template(Parameters(init("microservices", None)))
+        .await
+        .expect("add template");
+    assert_eq!(added.session_id, "s:alpha");
+    assert_eq!(added.diagram_ids, ["services", "checkout"]);
+    assert_eq!(added.xref_ids.len(), 6);
+    let persisted = alpha.load_session().expect("reload alpha");
+    assert_eq!(persisted.diagrams().len(), 3);
+    assert_eq!(persisted.active_diagram_id().map(|id| id.as_str()), Some("services"));
+    assert_eq!(persisted.ordered_diagram_ids()[0].as_str(), "flow");
+
+    let Err(err) = server.session_init_from_template(Parameters(init("microservices", None))).await
+    else {
+        panic!("template diagrams cannot be added twice");
+    };
+    assert!(err.message.contains("already exist"), "{}", err.message);
+    assert!(server.session_init_from_template(Parameters(init("c4", None))).await.is_err());
+
+    let Json(created) = server
+        .session_init_from_template(Parameters(init("context", Some("shop"))))
+        .await
+        .expect("create workspace session");
+    assert_eq!(created.session_id, "s:shop");
+    assert_eq!(created.diagram_ids, ["context", "containers"]);
+    assert_eq!(ui_state.lock().await.workspace_session(), Some("shop"));
+    let Json(current) = server.diagram_current().await.expect("current");
+    assert_eq!(current.active_diagram_id.as_deref(), Some("context"));
+    assert!(server
+        .session_init_from_template(Parameters(init("context", Some("shop"))))
+        .await
+        .is_err());
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn session_search_ranks_and_pages_hits_across_diagrams_and_xrefs() {
+    let mut session = demo_session();
+    let mut xref = XRef::new(
+        ObjectRef::from_str("d:d-seq/seq/participant/p:a").expect("from ref"),
+        ObjectRef::from_str("d:d-flow/flow/node/n:a").expect("to ref"),
+        "implements",
+        XRefStatus::Ok,
+    );
+    xref.set_label(Some("hi handler".to_owned()));
+    session.xrefs_mut().insert(XRefId::new("x:1").expect("xref id"), xref);
+    let server = NereidMcp::new(session);
+    let search = |query: &str, offset: Option<u64>| SessionSearchParams {
+        query: query.to_owned(),
+        offset,
+        limit: Some(1),
+    };
+
+    let Json(first) = server.session_search(Parameters(search("HI", None))).await.expect("search");
+    assert_eq!(first.total, 2);
+    assert_eq!(first.next_offset, Some(1));
+    assert_eq!(first.results[0].kind, "object");
+    assert_eq!(first.results[0].id, "d:d-seq/seq/message/m:1");
+    assert_eq!(first.results[0].field, "label");
+    assert_eq!(first.results[0].text, "Hi");
+
+    let Json(second) =
+        server.session_search(Parameters(search("hi", Some(1)))).await.expect("search");
+    assert_eq!(second.next_offset, None);
+    assert_eq!(second.results[0].kind, "xref");
+    assert_eq!(second.results[0].id, "x:1");
+
+    let Json(none) = server.session_search(Parameters(search(" ", None))).await.expect("search");
+    assert_eq!(none.total, 0);
+    assert!(none.results.is_empty());
+}
+
+#[tokio::test]
+async fn diagram_update_description_persists_and_leads_markdown_export() {
+    let dir = temp_session_dir("mcp-persist-diagram-description");
+    let dir_str = dir.to_string_lossy().to_string();
+    let folder = SessionFolder::new(dir_str.clone());
+
+    let session = demo_session();
+    folder.save_session(&session).expect("save initial session");
+
+    let server = NereidMcp::new_persistent(session, folder);
+    let Json(updated) = server
+        .diagram_update_description(Parameters(DiagramUpdateDescriptionParams {
+            diagram_id: Some("d-flow".to_owned()),
+            description: "Covers the **happy path** only.\n".to_owned(),
+        }))
+        .await
+        .expect("update description");
+    assert_eq!(updated.description.as_deref(), Some("Covers the **happy path** only."));
+
+    let loaded = SessionFolder::new(dir_str).load_session().expect("load session");
+    let flow_id = DiagramId::new("d-flow").expect("diagram id");
+    let loaded_flow = loaded.diagrams().get(&flow_id).expect("flow diagram");
+    assert_eq!(loaded_flow.description(), Some("Covers the **happy path** only."));
+    assert_eq!(loaded_flow.rev(), 0);
+
+    let Json(listed) =
+        server.diagram_list(Parameters(DiagramListParams::default())).await.expect("list");
+    let summary = listed.diagrams.iter().find(|d| d.diagram_id == "d-flow").expect("summary");
+    assert_eq!(summary.description.as_deref(), Some("Covers the **happy path** only."));
+
+    let Json(exported) = server
+        .diagram_export_subset(Parameters(DiagramExportSubsetParams {
+            diagram_id: Some("d-flow".to_owned()),
+            object_refs: Some(vec!["d:d-flow/flow/node/n:a".to_owned()]),
+            format: Some(ExportFormat::Markdown),
+            theme: None,
+        }))
+        .await
+        .expect("export markdown");
+    assert!(
+        exported.text.starts_with("# Flow\n\nCovers the **happy path** only.\n\n```mermaid\n"),
+        "unexpected markdown: {}",
+        exported.text
+    );
+    assert!(exported.text.ends_with("```\n"));
+}
+
 #[tokio::test]
 async fn selection_get_refreshes_from_session_folder_meta() {
     let dir = temp_session_dir("mcp-selection-read-refreshes-meta");
@@ -4073,7 +6470,7 @@ async fn selection_get_refreshes_from_session_folder_meta() {
         .collect(),
     );
     SessionFolder::new(dir_str)
-        .save_selected_object_refs(&on_disk)
+        .save_selected_object_refs(&mut on_disk)
         .expect("persist external selection");
 
     let Json(selection) = server.selection_get().await.expect("selection.read");
@@ -4102,7 +6499,7 @@ async fn selection_update_add_uses_latest_meta_selection_in_persistent_mode() {
             .collect(),
     );
     SessionFolder::new(dir_str.clone())
-        .save_selected_object_refs(&on_disk)
+        .save_selected_object_refs(&mut on_disk)
         .expect("persist external selection");
 
     server
@@ -4134,7 +6531,7 @@ async fn xref_add_persists_to_session_folder() {
     let server = NereidMcp::new_persistent(session, folder);
     server
         .xref_add(Parameters(XRefAddParams {
-            xref_id: "x:new".into(),
+            xref_id: Some("x:new".into()),
             from: "d:d-seq/seq/participant/p:a".into(),
             to: "d:d-flow/flow/node/n:a".into(),
             kind: "relates_to".into(),
@@ -4167,3 +6564,70 @@ async fn xref_remove_persists_to_session_folder() {
     let removed_id = XRefId::new("x:1").expect("xref id");
     assert!(!loaded.xrefs().contains_key(&removed_id));
 }
+
+#[tokio::test]
+async fn session_export_archive_writes_an_importable_bundle() {
+    let dir = temp_session_dir("export-archive");
+    let folder = SessionFolder::new(dir.clone());
+    let session = folder.load_or_init_session().expect("init session");
+    let archive_path = dir.with_extension("nereid.tar.zst");
+    let session_id = session.session_id().clone();
+
+    let in_memory = NereidMcp::new(session.clone());
+    let params = || SessionExportArchiveParams { path: archive_path.display().to_string() };
+    assert!(in_memory.session_export_archive(Parameters(params())).await.is_err());
+
+    let server = NereidMcp::new_persistent(session, folder);
+    let Json(exported) =
+        server.session_export_archive(Parameters(params())).await.expect("export archive");
+    assert_eq!((exported.version, exported.files), (1, 3));
+
+    let copy = SessionFolder::new(dir.with_extension("copy"));
+    let imported = copy.import_archive(&archive_path).expect("import archive");
+    assert_eq!(imported.session_id(), &session_id);
+    assert!(imported.diagrams().contains_key("flow"));
+
+    let _ = std::fs::remove_dir_all(&dir);
+    let _ = std::fs::remove_dir_all(copy.root());
+    let _ = std::fs::remove_file(&archive_path);
+}
+
+#[tokio::test]
+async fn session_import_archive_opens_the_bundle_as_a_new_workspace_session() {
+    let root = temp_session_dir("import-archive");
+    let alpha = SessionFolder::new(root.join("alpha"));
+    let alpha_session = alpha.load_or_init_session().expect("init alpha");
+    let archive_path = root.join("alpha.nereid.tar.zst");
+    alpha.export_archive(&archive_path).expect("export archive");
+    let params = |name: &str| SessionImportArchiveParams {
+        path: archive_path.display().to_string(),
+        name: name.to_owned(),
+    };
+
+    let standalone = NereidMcp::new_persistent(alpha_session.clone(), alpha.clone());
+    assert!(standalone.session_import_archive(Parameters(params("copy"))).await.is_err());
+
+    let ui_state = Arc::new(Mutex::new(UiState::default()));
+    let server = NereidMcp::new_persistent_with_agent_highlights_and_ui_state(
+        alpha_session,
+        alpha,
+        Arc::new(Mutex::new(BTreeSet::new())),
+        Some(ui_state.clone()),
+    )
+    .with_workspace(Workspace::new(&root));
+    let Json(imported) =
+        server.session_import_archive(Parameters(params("copy"))).await.expect("import archive");
+    assert_eq!(imported.name, "copy");
+    assert_eq!(imported.session_id, "s:alpha");
+    assert_eq!(imported.diagrams, 1);
+    assert_eq!(ui_state.lock().await.workspace_session(), Some("copy"));
+    let Json(listed) = server.workspace_list_sessions().await.expect("list sessions");
+    assert_eq!(listed.active_session.as_deref(), Some("copy"));
+
+    let Err(err) = server.session_import_archive(Parameters(params("alpha"))).await else {
+        panic!("importing into a folder with a session must fail");
+    };
+    assert!(err.message.contains("cannot import"), "{}", err.message);
+
+    let _ = std::fs::remove_dir_all(&root);
+}
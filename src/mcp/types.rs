@@ -11,12 +11,321 @@ use std::collections::BTreeMap;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+/// Payload of the `nereid/sessionChanged` notification pushed to connected clients.
+///
+/// Only what changed since the previous notification is listed; re-read the named parts with the
+/// usual tools instead of polling them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SessionChanged {
+    /// Diagrams whose revision changed, with the new rev (`null` when removed).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub diagrams: BTreeMap<String, Option<u64>>,
+    /// Walkthroughs whose revision changed, with the new rev (`null` when removed).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub walkthroughs: BTreeMap<String, Option<u64>>,
+    /// Session metadata (title, description, tags, style and id prefix rules, active diagram).
+    #[serde(default)]
+    pub meta: bool,
+    /// The shared selection (`selection.read`).
+    #[serde(default)]
+    pub selection: bool,
+    /// Agent or human attention (`attention.agent.read`, `attention.human.read`).
+    #[serde(default)]
+    pub attention: bool,
+    /// The human's view: active diagram, viewport, zoom or follow-AI (`view.read_state`).
+    #[serde(default)]
+    pub view: bool,
+}
+
+impl SessionChanged {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Human-facing session metadata (title, description, tags).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SessionInfo {
+    pub session_id: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    /// Conditional styling rules such as `tag=deprecated => dim` or `degree>=8 => heavy`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub style_rules: Vec<String>,
+    /// Id prefix conventions such as `flow/node => svc|db`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub id_prefix_rules: Vec<String>,
+}
+
+/// What this nereid build supports, so agents can pick tools and ops without probing.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ServerCapabilitiesResponse {
+    /// Crate version of the running server.
+    pub version: String,
+    pub diagram_kinds: Vec<DiagramKindCapabilities>,
+    /// `type` values accepted by `walkthrough.apply_ops`.
+    pub walkthrough_ops: Vec<String>,
+    /// Formats accepted on import (`mermaid` for `diagram.create_from_mermaid`, `table` for
+    /// `diagram.import_table`) and produced by `diagram.export_subset`.
+    pub import_formats: Vec<String>,
+    pub export_formats: Vec<String>,
+    /// Optional behaviours of this build, e.g. `proposals` or `session_changed_notifications`.
+    pub features: Vec<String>,
+    /// Whether edits are saved to a session folder (otherwise they live in memory only).
+    pub persisted: bool,
+    /// Agent spotlight lifetime without `attention.agent.heartbeat`; `None` never expires.
+    pub attention_ttl_ms: Option<u64>,
+    pub tools: Vec<ToolCapability>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiagramKindCapabilities {
+    /// `sequence`, `flowchart`, `state` or `class`.
+    pub kind: String,
+    /// Mermaid header that starts a diagram of this kind.
+    pub mermaid_header: String,
+    /// `type` values accepted by `diagram.apply_ops`; empty for read-only kinds.
+    pub ops: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ToolCapability {
+    pub name: String,
+    /// Tool name up to the first `.`, e.g. `diagram` or `flow`.
+    pub group: String,
+    /// Top-level parameter names.
+    pub params: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct SessionUpdateMetaParams {
+    /// New title; omit to keep the current one, pass an empty string to clear it.
+    pub title: Option<String>,
+    /// New description; omit to keep the current one, pass an empty string to clear it.
+    pub description: Option<String>,
+    /// Replacement tag list; omit to keep the current tags.
+    pub tags: Option<Vec<String>>,
+    /// Replacement styling rules (`<tag=NAME|degree OP N> => <dim|heavy>`); omit to keep the
+    /// current rules.
+    #[serde(default)]
+    pub style_rules: Option<Vec<String>>,
+    /// Replacement id prefix conventions (`<category> => <prefix>|<prefix>...`, e.g.
+    /// `flow/node => svc|db`); omit to keep the current conventions.
+    #[serde(default)]
+    pub id_prefix_rules: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct SessionSearchParams {
+    /// Words to find; every word must occur (case-insensitive, prefix or substring).
+    pub query: String,
+    /// Number of hits to skip.
+    pub offset: Option<u64>,
+    /// Page size; defaults to 20.
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SessionSearchHit {
+    /// `object`, `walkthrough_node` or `xref`.
+    pub kind: String,
+    /// Object ref, `<walkthrough_id>/<node_id>`, or xref id.
+    pub id: String,
+    /// Best matching field: `label`, `note`, `title` or `body`.
+    pub field: String,
+    pub text: String,
+    pub score: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SessionSearchResponse {
+    /// Number of hits across all pages.
+    pub total: u64,
+    /// Hits ranked best first.
+    pub results: Vec<SessionSearchHit>,
+    /// Offset of the next page, when more hits match.
+    pub next_offset: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct IdPrefixViolation {
+    pub object_ref: String,
+    /// The broken convention, e.g. `flow/node => svc|db`.
+    pub rule: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SessionLintIdsResponse {
+    pub violations: Vec<IdPrefixViolation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SessionDiagnostic {
+    /// `error` (the diagram cannot be exported faithfully) or `warning` (a link points nowhere).
+    pub severity: String,
+    /// `dangling_xref`, `duplicate_mermaid_id`, `orphan_walkthrough_ref`, `missing_participant`
+    /// or `missing_node`.
+    pub code: String,
+    /// What carries the problem: an object ref, xref id or `walkthrough_id/node_id`.
+    pub subject: String,
+    /// Objects involved, missing ones included.
+    pub object_refs: Vec<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SessionValidateResponse {
+    pub ok: bool,
+    pub errors: u64,
+    pub warnings: u64,
+    pub diagnostics: Vec<SessionDiagnostic>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WorkspaceSessionSummary {
+    /// Name of the session's folder inside the workspace; pass it to `workspace.open`.
+    pub name: String,
+    pub session_id: String,
+    pub title: Option<String>,
+    pub diagrams: u64,
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WorkspaceListSessionsResponse {
+    pub workspace_dir: String,
+    pub active_session: Option<String>,
+    pub sessions: Vec<WorkspaceSessionSummary>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct WorkspaceOpenParams {
+    /// Session folder name from `workspace.list_sessions`.
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WorkspaceOpenResponse {
+    pub name: String,
+    pub session_id: String,
+    pub active_diagram_id: Option<String>,
+    pub diagrams: u64,
+    /// Unsynced edits replayed from the session's `ops.log` while opening it.
+    pub recovered_batches: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct SessionInitFromTemplateParams {
+    /// Built-in template: `microservices`, `request-lifecycle` or `context`.
+    pub template: String,
+    /// Create a new workspace session folder with this name and open it (needs `--workspace`);
+    /// omit to add the template's diagrams and xrefs to the current session.
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SessionInitFromTemplateResponse {
+    pub template: String,
+    /// Workspace session created and opened, when `name` was given.
+    pub name: Option<String>,
+    pub session_id: String,
+    /// Diagrams the template added, in template order; the first is now active.
+    pub diagram_ids: Vec<String>,
+    pub xref_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct SessionExportArchiveParams {
+    /// File to write, conventionally ending in `.nereid.tar.zst`; relative paths resolve against
+    /// the server's working directory.
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SessionExportArchiveResponse {
+    pub path: String,
+    /// Archive format version; `nereid archive import` refuses other versions.
+    pub version: u32,
+    /// Session files in the archive (meta, diagrams with sidecars, walkthroughs).
+    pub files: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct SessionImportArchiveParams {
+    /// Archive written by `session.export_archive` or `nereid archive export`; relative paths
+    /// resolve against the server's working directory.
+    pub path: String,
+    /// New workspace session folder to unpack into and open (needs `--workspace`).
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SessionImportArchiveResponse {
+    pub name: String,
+    pub session_id: String,
+    pub active_diagram_id: Option<String>,
+    pub diagrams: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DiagramSummary {
     pub diagram_id: String,
     pub name: String,
     pub kind: String,
     pub rev: u64,
+    /// Long-form Markdown notes about the diagram, when set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Set for archived diagrams, which `diagram.list` only returns on request.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+    /// Set for read-only diagrams, which reject ops and edits until unlocked.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub read_only: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct DiagramListParams {
+    /// Also list archived diagrams; defaults to false.
+    pub include_archived: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DiagramSetArchivedParams {
+    pub diagram_id: Option<String>,
+    pub archived: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiagramArchivedResponse {
+    pub diagram_id: String,
+    pub archived: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DiagramSetReadOnlyParams {
+    pub diagram_id: Option<String>,
+    pub read_only: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiagramReadOnlyResponse {
+    pub diagram_id: String,
+    pub read_only: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DiagramUpdateDescriptionParams {
+    pub diagram_id: Option<String>,
+    /// Markdown description; pass an empty string to clear it.
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiagramDescriptionResponse {
+    pub diagram_id: String,
+    pub description: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -114,12 +423,151 @@ pub struct WalkthroughRenderTextResponse {
     pub context: ReadContext,
 }
 
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct DiagramRenderTextParams {
+    pub diagram_id: Option<String>,
+    /// Clip the render to a `width`×`height` window centred on this object.
+    pub focus_ref: Option<String>,
+    /// Window width in cells when clipping to `focus_ref`; defaults to 80.
+    pub width: Option<u64>,
+    /// Window height in lines when clipping to `focus_ref`; defaults to 24.
+    pub height: Option<u64>,
+    /// Return only the lines of objects added or updated since this rev (see `diagram.diff`).
+    pub since_rev: Option<u64>,
+    /// Append a legend of the markers the TUI draws on this diagram: xref direction prefixes,
+    /// notes, sequence blocks, highlights and active styling rules.
+    pub legend: Option<bool>,
+    /// How flowchart edges are drawn: `box` (default), `braille` or `blocks` (quadrant blocks);
+    /// the latter two cut corners diagonally on a finer dot grid.
+    pub edge_glyphs: Option<String>,
+}
+
+/// Part of the full render covered by a clipped `text`, in character cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RenderClip {
+    pub line: u64,
+    pub col: u64,
+    pub width: u64,
+    pub height: u64,
+    pub total_lines: u64,
+    pub total_cols: u64,
+}
+
+/// Inclusive line range of the full render that shows changed objects.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RenderRegion {
+    pub start_line: u64,
+    pub end_line: u64,
+    pub object_refs: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DiagramRenderTextResponse {
     pub text: String,
+    /// Set when `focus_ref` clipped the render.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clip: Option<RenderClip>,
+    /// Set with `since_rev`; `text` then holds only these lines, regions separated by `⋯`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regions: Option<Vec<RenderRegion>>,
+    pub context: ReadContext,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct DiagramRenderParams {
+    pub diagram_id: Option<String>,
+    /// First line of the viewport; omit all viewport fields for the whole render.
+    pub line: Option<u64>,
+    /// First column of the viewport.
+    pub col: Option<u64>,
+    /// Viewport width in cells; defaults to the rest of the render.
+    pub width: Option<u64>,
+    /// Viewport height in lines; defaults to the rest of the render.
+    pub height: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiagramRenderResponse {
+    /// The annotated Unicode render, cropped to the viewport when one was given.
+    pub text: String,
+    /// Cells of each visible object as `[line, start_col, end_col]` (0-based, inclusive columns),
+    /// relative to `text`.
+    pub highlights: BTreeMap<String, Vec<[u64; 3]>>,
+    /// Set when a viewport cropped the render.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clip: Option<RenderClip>,
     pub context: ReadContext,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    #[default]
+    Mermaid,
+    Unicode,
+    /// Markdown document: diagram name, description, then a fenced Mermaid block.
+    Markdown,
+    /// Graphviz DOT; node names are object ids.
+    Dot,
+    /// The rendered diagram as SVG, with `data-ref` attributes on each object's shapes.
+    Svg,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DiagramExportSubsetParams {
+    pub diagram_id: Option<String>,
+    /// Object refs to keep; defaults to the current selection. Pass query results (e.g. from
+    /// `flow.reachable`) to export a filtered fragment.
+    pub object_refs: Option<Vec<String>>,
+    /// Output format (default `mermaid`; `markdown` prepends the diagram description; `dot` and
+    /// `svg` suit external tools).
+    pub format: Option<ExportFormat>,
+    /// Export color theme for `svg`: `default`, `print` (black on white) or `dark`. SVG output
+    /// marks the current selection and agent highlights with `selected`/`agent` classes.
+    pub theme: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DiagramTidyLayoutParams {
+    pub diagram_id: Option<String>,
+    /// When true, persists the tidy ordering as layout hints (requires `base_rev`).
+    pub apply: Option<bool>,
+    pub base_rev: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TidyLayoutMetrics {
+    pub width: u64,
+    pub height: u64,
+    /// Size after the compaction pass drops redundant blank/straight-connector rows and columns.
+    pub compacted_width: u64,
+    pub compacted_height: u64,
+    pub crossings: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiagramTidyLayoutResponse {
+    pub diagram_id: String,
+    pub rev: u64,
+    pub before: TidyLayoutMetrics,
+    pub after: TidyLayoutMetrics,
+    /// Compacted text render using the tidy ordering.
+    pub preview: String,
+    pub applied: bool,
+    pub new_rev: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiagramExportSubsetResponse {
+    pub diagram_id: String,
+    pub format: ExportFormat,
+    pub text: String,
+    /// Object refs present in the exported fragment, including induced edges/messages.
+    pub object_refs: Vec<String>,
+    /// Metadata of the session the fragment was exported from.
+    pub session: SessionInfo,
+}
+
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct RouteFindParams {
     pub from_ref: String,
@@ -144,6 +592,18 @@ pub struct DiagramOpenResponse {
     pub active_diagram_id: String,
 }
 
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DiagramReorderParams {
+    /// Diagram ids in the desired order; diagrams left out follow in id order.
+    pub diagram_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiagramReorderResponse {
+    /// All diagram ids in the resulting order.
+    pub diagram_ids: Vec<String>,
+}
+
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct DiagramDeleteParams {
     pub diagram_id: String,
@@ -181,18 +641,62 @@ pub struct WalkthroughCurrentResponse {
 pub struct AttentionReadResponse {
     pub object_ref: Option<String>,
     pub diagram_id: Option<String>,
+    /// Milliseconds until the agent spotlight expires without a heartbeat (`null` when it does
+    /// not expire).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_in_ms: Option<u64>,
+    /// Cursor of every agent that set attention while the TUI runs, oldest client id first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub agents: Vec<AgentCursorSummary>,
     pub context: ReadContext,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AgentCursorSummary {
+    pub client_id: String,
+    pub name: String,
+    /// Palette slot of the agent's highlight color in the TUI.
+    pub color: u64,
+    pub object_ref: String,
+    /// Milliseconds since the agent last set its cursor or sent a heartbeat.
+    pub idle_ms: u64,
+}
+
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct AttentionAgentSetParams {
     pub object_ref: String,
+    /// Stable id of the calling agent, so concurrent agents get separate cursors; defaults to
+    /// `agent`.
+    #[serde(default)]
+    pub client_id: Option<String>,
+    /// Name shown for the agent in the TUI legend; defaults to the client id.
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct AttentionAgentParams {
+    /// The agent whose cursor to keep alive (defaults to `agent`) or clear (omitted clears every
+    /// agent's).
+    #[serde(default)]
+    pub client_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AttentionSetResponse {
     pub object_ref: String,
     pub diagram_id: String,
+    pub client_id: String,
+    /// Milliseconds until the spotlight expires unless refreshed by `attention.agent.heartbeat`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_in_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AttentionHeartbeatResponse {
+    /// The spotlight that was kept alive (`null` when there is none to refresh).
+    pub object_ref: Option<String>,
+    pub expires_in_ms: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -250,14 +754,97 @@ pub struct ViewScroll {
     pub y: f64,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ViewZoomLevel {
+    Glyph,
+    Mini,
+    Compact,
+    #[default]
+    Normal,
+    Spacious,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ViewGetStateResponse {
     pub active_diagram_id: Option<String>,
     pub scroll: ViewScroll,
+    pub zoom: ViewZoomLevel,
     pub panes: BTreeMap<String, bool>,
     pub context: ReadContext,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ViewGetViewportResponse {
+    /// Diagram shown in the human's diagram pane; `None` when no TUI shares the view state.
+    pub diagram_id: Option<String>,
+    pub scroll: ViewScroll,
+    pub width: u64,
+    pub height: u64,
+    pub zoom: ViewZoomLevel,
+    /// One entry per viewport row, trailing blanks trimmed.
+    pub lines: Vec<String>,
+    /// Cells of each object intersecting the viewport as `[line, start_col, end_col]` (0-based,
+    /// inclusive columns), relative to `lines`.
+    pub objects: BTreeMap<String, Vec<[u64; 3]>>,
+    pub context: ReadContext,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ViewCenterOnParams {
+    pub object_ref: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ViewCenterOnResponse {
+    pub object_ref: String,
+    pub diagram_id: String,
+    /// `false` when follow-AI is disabled or no TUI shares the view state.
+    pub applied: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ViewScrollParams {
+    /// Columns to pan right (negative pans left).
+    #[serde(default)]
+    pub dx: i32,
+    /// Rows to pan down (negative pans up).
+    #[serde(default)]
+    pub dy: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ViewScrollResponse {
+    pub dx: i32,
+    pub dy: i32,
+    /// `false` when follow-AI is disabled or no TUI shares the view state.
+    pub applied: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ViewSetActiveDiagramParams {
+    pub diagram_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ViewSetActiveDiagramResponse {
+    pub diagram_id: String,
+    /// `false` when follow-AI is disabled or no TUI shares the view state.
+    pub applied: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ViewZoomParams {
+    pub level: ViewZoomLevel,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ViewZoomResponse {
+    pub level: ViewZoomLevel,
+    /// `false` when follow-AI is disabled or no TUI shares the view state.
+    pub applied: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DiagramCounts {
     pub participants: u64,
@@ -298,6 +885,20 @@ pub struct ReadContext {
     pub ui_session_rev: Option<u64>,
 }
 
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DiagramCreateParams {
+    /// `sequence`, `flowchart`, `state` or `class`.
+    pub kind: String,
+    /// Optional explicit diagram id to use; when omitted a unique id is allocated.
+    pub diagram_id: Option<String>,
+    /// Optional display name; defaults to the chosen diagram id.
+    pub name: Option<String>,
+    /// Optional initial Mermaid of the same kind; when omitted the diagram starts empty.
+    pub mermaid: Option<String>,
+    /// When true (default), sets the created diagram as active.
+    pub make_active: Option<bool>,
+}
+
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct DiagramCreateFromMermaidParams {
     /// Raw Mermaid diagram source (`flowchart`/`graph` or `sequenceDiagram`).
@@ -316,6 +917,31 @@ pub struct DiagramCreateFromMermaidResponse {
     pub active_diagram_id: Option<String>,
 }
 
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DiagramImportTableParams {
+    /// Node rows as flat objects (e.g. `{ "id": "api", "label": "API", "shape": "round" }`).
+    pub nodes: Vec<serde_json::Map<String, serde_json::Value>>,
+    /// Edge rows as flat objects (e.g. `{ "from": "api", "to": "db", "label": "sql" }`).
+    pub edges: Option<Vec<serde_json::Map<String, serde_json::Value>>>,
+    /// Optional column overrides keyed by `node_id`, `node_label`, `node_shape`, `edge_from`,
+    /// `edge_to`, `edge_label` (defaults: `id`, `label`, `shape`, `from`, `to`, `label`).
+    pub mapping: Option<BTreeMap<String, String>>,
+    /// Optional explicit diagram id to use; when omitted a unique id is allocated.
+    pub diagram_id: Option<String>,
+    /// Optional display name; defaults to the chosen diagram id.
+    pub name: Option<String>,
+    /// When true (default), sets the created diagram as active.
+    pub make_active: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiagramImportTableResponse {
+    pub diagram: DiagramSummary,
+    pub active_diagram_id: Option<String>,
+    pub node_count: u64,
+    pub edge_count: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DiagramGetAstResponse {
     pub diagram_id: String,
@@ -336,6 +962,14 @@ pub enum McpDiagramAst {
         nodes: Vec<McpFlowNodeAst>,
         edges: Vec<McpFlowEdgeAst>,
     },
+    State {
+        states: Vec<McpStateNodeAst>,
+        transitions: Vec<McpStateTransitionAst>,
+    },
+    Class {
+        classes: Vec<McpClassNodeAst>,
+        relations: Vec<McpClassRelationAst>,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -355,6 +989,8 @@ pub struct McpSeqMessageAst {
     pub arrow: Option<String>,
     pub text: String,
     pub order_key: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub boundary: Option<MessageBoundary>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -410,17 +1046,60 @@ pub struct McpFlowEdgeAst {
     pub style: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
-#[serde(rename_all = "snake_case")]
-pub enum DeltaChangeKind {
-    Added,
-    Removed,
-    Updated,
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct McpStateNodeAst {
+    pub state_id: String,
+    pub label: String,
+    /// `state`, `start`, `end`, `choice`, `fork` or `join`.
+    pub kind: String,
+    pub mermaid_id: Option<String>,
+    pub note: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-pub struct DeltaChange {
-    pub kind: DeltaChangeKind,
+pub struct McpStateTransitionAst {
+    pub transition_id: String,
+    pub from_state_id: String,
+    pub to_state_id: String,
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct McpClassNodeAst {
+    pub class_id: String,
+    pub label: String,
+    pub mermaid_id: Option<String>,
+    pub annotation: Option<String>,
+    /// Member lines as written; entries containing `(` are methods.
+    pub members: Vec<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct McpClassRelationAst {
+    pub relation_id: String,
+    /// The `to` class carries the marker: parent, whole or target.
+    pub from_class_id: String,
+    pub to_class_id: String,
+    /// `inheritance`, `realization`, `composition`, `aggregation`, `association`,
+    /// `dependency`, `link` or `dashed_link`.
+    pub kind: String,
+    pub label: Option<String>,
+    pub from_cardinality: Option<String>,
+    pub to_cardinality: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeltaChangeKind {
+    Added,
+    Removed,
+    Updated,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DeltaChange {
+    pub kind: DeltaChangeKind,
     pub refs: Vec<String>,
 }
 
@@ -490,6 +1169,63 @@ pub struct ApplyOpsParams {
     pub ops: Vec<McpOp>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiagramGetMermaidResponse {
+    pub diagram_id: String,
+    /// Pass as `base_rev` to `diagram.set_mermaid`.
+    pub rev: u64,
+    pub kind: String,
+    pub mermaid: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct DiagramCompareParams {
+    /// Defaults to the session's active diagram.
+    pub diagram_id: Option<String>,
+    /// Mermaid of the earlier version; defaults to the diagram as saved on disk.
+    pub from_mermaid: Option<String>,
+    /// Mermaid of the later version; defaults to the diagram as it is now.
+    pub to_mermaid: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct McpChangedObject {
+    pub object_ref: String,
+    /// Facts that differ: `label`, `note`, `tags`, `metadata`, `anchors`, `links`.
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiagramCompareResponse {
+    pub diagram_id: String,
+    /// `disk` or `mermaid`.
+    pub from: String,
+    /// `current` or `mermaid`.
+    pub to: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<McpChangedObject>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DiagramSetMermaidParams {
+    /// Defaults to the session's active diagram.
+    pub diagram_id: Option<String>,
+    pub base_rev: u64,
+    /// Complete replacement Mermaid of the same diagram kind.
+    pub mermaid: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiagramSetMermaidResponse {
+    pub new_rev: u64,
+    /// Ops the change was applied as; `0` when nothing changed or the AST was replaced whole.
+    pub applied: u64,
+    /// Changed objects; `None` when the change touches parts ops do not cover (flowchart
+    /// subgraphs, sequence blocks and the block section of notes) and the AST was replaced whole.
+    pub delta: Option<DeltaSummary>,
+}
+
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct WalkthroughApplyOpsParams {
     pub walkthrough_id: String,
@@ -497,6 +1233,79 @@ pub struct WalkthroughApplyOpsParams {
     pub ops: Vec<McpWalkthroughOp>,
 }
 
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct WalkthroughCreateParams {
+    /// Display title of the walkthrough.
+    pub title: String,
+    /// Optional explicit walkthrough id; when omitted the next free `w:N` is allocated.
+    pub walkthrough_id: Option<String>,
+    /// When true (default), sets the created walkthrough as active.
+    pub make_active: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WalkthroughCreateResponse {
+    pub walkthrough: WalkthroughSummary,
+    pub active_walkthrough_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct WalkthroughDeleteParams {
+    pub walkthrough_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WalkthroughDeleteResponse {
+    pub deleted_walkthrough_id: String,
+    pub active_walkthrough_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct WalkthroughNodeAddParams {
+    pub walkthrough_id: String,
+    pub base_rev: u64,
+    pub title: String,
+    /// Optional explicit node id; when omitted the next free `wn:N` is allocated.
+    pub node_id: Option<String>,
+    pub body_md: Option<String>,
+    /// Canonical `object_ref`s the step points at.
+    pub refs: Option<Vec<String>>,
+    pub tags: Option<Vec<String>>,
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WalkthroughNodeAddResponse {
+    pub node_id: String,
+    pub new_rev: u64,
+    pub delta: DeltaSummary,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct WalkthroughNodeUpdateParams {
+    pub walkthrough_id: String,
+    pub base_rev: u64,
+    pub node_id: String,
+    pub title: Option<String>,
+    pub body_md: Option<Option<String>>,
+    /// Replaces the node's refs when set.
+    pub refs: Option<Vec<String>>,
+    /// Replaces the node's tags when set.
+    pub tags: Option<Vec<String>>,
+    pub status: Option<Option<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct WalkthroughEdgeAddParams {
+    pub walkthrough_id: String,
+    pub base_rev: u64,
+    pub from_node_id: String,
+    pub to_node_id: String,
+    /// Edge kind; defaults to `next`.
+    pub kind: Option<String>,
+    pub label: Option<String>,
+}
+
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct DiagramProposeOpsParams {
     pub diagram_id: Option<String>,
@@ -511,6 +1320,62 @@ pub struct DiagramProposeOpsResponse {
     pub delta: DeltaSummary,
 }
 
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DiagramProposeRewriteParams {
+    /// Defaults to the session's active diagram.
+    pub diagram_id: Option<String>,
+    pub base_rev: u64,
+    /// Complete replacement Mermaid of the same diagram kind; give either this or `ops`.
+    pub mermaid: Option<String>,
+    /// Ops applied to a copy of the diagram; give either this or `mermaid`.
+    pub ops: Option<Vec<McpOp>>,
+    /// One line shown to the human next to the proposal.
+    pub summary: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct McpProposal {
+    pub proposal_id: String,
+    pub diagram_id: String,
+    pub base_rev: u64,
+    pub summary: Option<String>,
+    /// `pending`, `accepted` or `discarded`.
+    pub status: String,
+    /// Revision the original got when the human accepted the proposal.
+    pub accepted_rev: Option<u64>,
+    /// The original moved past `base_rev`, so the human can no longer accept the proposal.
+    pub stale: bool,
+    /// Changes the proposal makes to the original; `null` once settled or stale, or when they
+    /// cannot be expressed as ops (e.g. state and class diagrams).
+    pub delta: Option<DeltaSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiagramProposeRewriteResponse {
+    pub proposal: McpProposal,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DiagramListProposalsParams {
+    /// Only proposals for this diagram; all by default.
+    pub diagram_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiagramListProposalsResponse {
+    pub proposals: Vec<McpProposal>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DiagramDiscardProposalParams {
+    pub proposal_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiagramDiscardProposalResponse {
+    pub proposal: McpProposal,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct WalkthroughDeltaResponse {
     pub from_rev: u64,
@@ -558,7 +1423,8 @@ pub struct XRefNeighborsResponse {
 
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct XRefAddParams {
-    pub xref_id: String,
+    /// Omit to mint the next free `x:N` id.
+    pub xref_id: Option<String>,
     pub from: String,
     pub to: String,
     pub kind: String,
@@ -622,7 +1488,8 @@ pub struct SeqMessagesResponse {
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct FlowReachableParams {
     pub diagram_id: Option<String>,
-    pub from_node_id: String,
+    /// Start node; omit to start from the diagram's entry points (see `flow.entry_points`).
+    pub from_node_id: Option<String>,
     pub direction: Option<String>,
 }
 
@@ -637,6 +1504,13 @@ pub struct FlowUnreachableParams {
     pub start_node_id: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FlowEntryPointsResponse {
+    pub nodes: Vec<String>,
+    /// True when the nodes are explicitly marked entry points rather than inferred sources.
+    pub marked: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FlowUnreachableResponse {
     pub nodes: Vec<String>,
@@ -656,6 +1530,138 @@ pub struct FlowPathsResponse {
     pub paths: Vec<Vec<String>>,
 }
 
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct FlowHighlightPathParams {
+    pub diagram_id: Option<String>,
+    pub from_node_id: String,
+    pub to_node_id: String,
+    /// Paths to highlight; defaults to 1 (the shortest path).
+    pub limit: Option<u64>,
+    /// Also take paths up to this many hops longer than the shortest one; defaults to 0.
+    pub max_extra_hops: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FlowHighlightPathResponse {
+    /// Node ref paths that were highlighted; empty when `to_node_id` is unreachable.
+    pub paths: Vec<Vec<String>>,
+    /// Node and edge refs along the paths, now part of the selection.
+    pub object_refs: Vec<String>,
+    pub selected_count: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct FlowShortestPathParams {
+    pub diagram_id: Option<String>,
+    pub from_node_id: String,
+    pub to_node_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FlowWeightedStep {
+    pub node_ref: String,
+    /// Edge taken to reach this node (`null` for the start node).
+    pub edge_ref: Option<String>,
+    pub cumulative_cost: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FlowShortestPathResponse {
+    /// Total path cost; `null` when the target is unreachable.
+    pub cost: Option<u64>,
+    pub steps: Vec<FlowWeightedStep>,
+    /// One-line rendering such as `A (0) -> B (2) -> C (5)`.
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DiagramScaffoldSequenceParams {
+    /// Flowchart holding the path; defaults to the active diagram.
+    pub diagram_id: Option<String>,
+    /// Consecutive node ids of the path (e.g. the `node_ref`s of `flow.shortest_path`).
+    pub node_ids: Vec<String>,
+    /// Optional id for the new sequence diagram; defaults to `<diagram_id>-seq`.
+    pub target_diagram_id: Option<String>,
+    /// Optional display name; defaults to the chosen diagram id.
+    pub name: Option<String>,
+    /// When true (default), sets the created diagram as active.
+    pub make_active: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiagramScaffoldResponse {
+    pub diagram: DiagramSummary,
+    pub active_diagram_id: Option<String>,
+    /// `derived_from` xrefs from every scaffolded object back to its source object.
+    pub xref_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DiagramScaffoldFlowchartParams {
+    /// Sequence diagram to summarize; defaults to the active diagram.
+    pub diagram_id: Option<String>,
+    /// When false, only returns the Mermaid source without storing a diagram (default: true).
+    pub persist: Option<bool>,
+    /// Optional id for the new flowchart; defaults to `<diagram_id>-flow`.
+    pub target_diagram_id: Option<String>,
+    /// Optional display name; defaults to the chosen diagram id.
+    pub name: Option<String>,
+    /// When true (default), sets the created diagram as active.
+    pub make_active: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiagramScaffoldFlowchartResponse {
+    /// The stored flowchart; `null` when `persist` was false.
+    pub diagram: Option<DiagramSummary>,
+    pub active_diagram_id: Option<String>,
+    /// `derived_from` xrefs from every node and edge back to its participant or messages.
+    pub xref_ids: Vec<String>,
+    pub mermaid: String,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DiagramCriticalPathParams {
+    pub diagram_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CriticalPathStep {
+    /// A message ref (sequence) or alternating node/edge refs (flowchart).
+    pub object_ref: String,
+    /// Message duration or edge weight; `0` for flowchart nodes.
+    pub duration: u64,
+    /// Time at which this step completes.
+    pub finish: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiagramCriticalPathResponse {
+    pub kind: String,
+    /// Total duration of the critical path; `null` when the diagram has nothing to chain.
+    pub total: Option<u64>,
+    pub steps: Vec<CriticalPathStep>,
+    /// One-line rendering such as `login (5) -> lookup (7)`.
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiagramSummarizeResponse {
+    pub diagram_id: String,
+    pub kind: String,
+    pub rev: u64,
+    /// Object counts keyed by plural object kind, e.g. `nodes` or `messages`.
+    pub counts: BTreeMap<String, u64>,
+    pub entry_points: Vec<String>,
+    /// Ref paths from entry points to their farthest end; for sequences, the participant hops.
+    pub main_paths: Vec<Vec<String>>,
+    pub cycles: Vec<Vec<String>>,
+    /// Ids of dangling xrefs that start or end in this diagram.
+    pub dangling_xrefs: Vec<String>,
+    /// The same facts as plain text, one line per fact, using labels instead of refs.
+    pub summary: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct FlowCyclesResponse {
     pub cycles: Vec<Vec<String>>,
@@ -666,6 +1672,38 @@ pub struct FlowDeadEndsResponse {
     pub nodes: Vec<String>,
 }
 
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct FlowDominatorsParams {
+    pub diagram_id: Option<String>,
+    /// Entry node; omit to use the diagram's entry points (see `flow.entry_points`).
+    pub entry_node_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FlowDominatorNode {
+    pub node: String,
+    /// Last node every path from the entries passes first; `None` for entries and for nodes
+    /// reached from several entries without a shared node.
+    pub immediate_dominator: Option<String>,
+    /// Nodes that cannot be reached without passing this one.
+    pub dominates: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FlowDominatorsResponse {
+    pub entry_nodes: Vec<String>,
+    /// Nodes reachable from the entries, most dominating first.
+    pub nodes: Vec<FlowDominatorNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FlowCutPointsResponse {
+    /// Nodes whose removal splits the diagram (edge directions ignored).
+    pub nodes: Vec<String>,
+    /// Edges whose removal splits the diagram.
+    pub bridges: Vec<String>,
+}
+
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct FlowDegreesParams {
     pub diagram_id: Option<String>,
@@ -694,6 +1732,25 @@ pub enum MessageKind {
     Return,
 }
 
+/// Which end of a found/lost message lies outside the diagram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageBoundary {
+    /// Arrives from outside; only the receiver is known.
+    Found,
+    /// Leaves the diagram; only the sender is known.
+    Lost,
+}
+
+/// Where a sequence note sits relative to its participant lifeline(s).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NotePlacement {
+    LeftOf,
+    RightOf,
+    Over,
+}
+
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct ObjectGetParams {
     pub object_ref: Option<String>,
@@ -712,6 +1769,51 @@ pub struct ObjectGetResponse {
     pub context: ReadContext,
 }
 
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ObjectDetailParams {
+    pub object_ref: String,
+}
+
+/// Cells covered by an object in `diagram.render_text` output: 0-based line, inclusive columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct RenderSpan {
+    pub line: u64,
+    pub start_col: u64,
+    pub end_col: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct WalkthroughStepRef {
+    pub walkthrough_id: String,
+    pub node_id: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ObjectDetailResponse {
+    pub object_ref: String,
+    pub diagram_id: String,
+    pub rev: u64,
+    pub object: McpObject,
+    pub render_spans: Vec<RenderSpan>,
+    pub xrefs_out: Vec<XRefSummary>,
+    pub xrefs_in: Vec<XRefSummary>,
+    /// Enclosing objects, innermost first: block sections and blocks, participant groups or
+    /// flowchart subgraphs.
+    pub containers: Vec<String>,
+    /// Walkthrough nodes that reference the object.
+    pub walkthrough_steps: Vec<WalkthroughStepRef>,
+    pub context: ReadContext,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ObjectDescribeResponse {
+    pub object_ref: String,
+    /// Markdown summary of the same facts `object.get` returns.
+    pub description: String,
+    pub context: ReadContext,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum McpObject {
@@ -730,6 +1832,15 @@ pub enum McpObject {
         header: Option<String>,
         message_ids: Vec<String>,
     },
+    SeqGroup {
+        title: Option<String>,
+        participant_ids: Vec<String>,
+    },
+    SeqActivation {
+        participant_id: String,
+        start_message_id: Option<String>,
+        end_message_id: Option<String>,
+    },
     SeqMessage {
         from_participant_id: String,
         to_participant_id: String,
@@ -750,6 +1861,105 @@ pub enum McpObject {
         connector: Option<String>,
         style: Option<String>,
     },
+    FlowSubgraph {
+        label: String,
+        mermaid_id: Option<String>,
+        parent_id: Option<String>,
+        node_ids: Vec<String>,
+    },
+    StateNode {
+        label: String,
+        kind: String,
+        mermaid_id: Option<String>,
+    },
+    StateTransition {
+        from_state_id: String,
+        to_state_id: String,
+        label: Option<String>,
+    },
+    ClassNode {
+        label: String,
+        mermaid_id: Option<String>,
+        annotation: Option<String>,
+        members: Vec<String>,
+    },
+    ClassRelation {
+        from_class_id: String,
+        to_class_id: String,
+        kind: String,
+        label: Option<String>,
+        from_cardinality: Option<String>,
+        to_cardinality: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ObjectListParams {
+    /// Defaults to the session's active diagram.
+    pub diagram_id: Option<String>,
+    /// Category path such as `flow/node`, or its first segment (`seq`, `flow`).
+    pub category: Option<String>,
+    /// Only objects carrying every listed tag (see `ObjectListItem::tags`).
+    pub tags: Option<Vec<String>>,
+    /// Case-insensitive substring of the object label.
+    pub label_contains: Option<String>,
+    /// Number of matching objects to skip.
+    pub offset: Option<u64>,
+    /// Page size; defaults to 100.
+    pub limit: Option<u64>,
+}
+
+/// Link counts of one object. Structural counts are edges (flow nodes), messages (participants)
+/// or edges crossing the member boundary (subgraphs); other objects report zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ObjectAdjacency {
+    pub incoming: u64,
+    pub outgoing: u64,
+    pub xrefs_incoming: u64,
+    pub xrefs_outgoing: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ObjectListItem {
+    pub object_ref: String,
+    pub category: String,
+    pub label: String,
+    pub note: Option<String>,
+    /// Derived tags, e.g. `shape:round`, `entry`, `subgraph:sg:api`, `role:actor`, `kind:async`,
+    /// `self`, `note` or `xref`, followed by the object's own tags.
+    pub tags: Vec<String>,
+    /// User-assigned key/value metadata.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+    /// Refs this object hangs off: edge/message endpoints, note and group participants, the
+    /// enclosing subgraph or block section.
+    pub anchors: Vec<String>,
+    pub adjacency: ObjectAdjacency,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ObjectListResponse {
+    pub diagram_id: String,
+    pub rev: u64,
+    /// Number of objects matching the filters, across all pages.
+    pub total: u64,
+    pub objects: Vec<ObjectListItem>,
+    /// Offset of the next page, when more objects match.
+    pub next_offset: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ObjectFindByTagParams {
+    /// Tag to match exactly, either user-assigned or derived (see `ObjectListItem::tags`).
+    pub tag: String,
+    /// Restricts the search to one diagram; by default every non-archived diagram is searched.
+    pub diagram_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ObjectFindByTagResponse {
+    /// Matching objects in diagram order; refs carry their diagram id.
+    pub objects: Vec<ObjectListItem>,
 }
 
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
@@ -767,6 +1977,14 @@ pub enum McpOp {
         participant_id: String,
         note: Option<String>,
     },
+    /// Replaces the participant's tags and metadata; omitted fields are cleared.
+    SeqSetParticipantAnnotations {
+        participant_id: String,
+        #[serde(default)]
+        tags: Vec<String>,
+        #[serde(default)]
+        metadata: BTreeMap<String, String>,
+    },
     SeqRemoveParticipant {
         participant_id: String,
     },
@@ -788,9 +2006,46 @@ pub enum McpOp {
         text: Option<String>,
         order_key: Option<i64>,
     },
+    /// Sets (or clears with `null`) the duration of a message used by `diagram.critical_path`.
+    SeqSetMessageDuration {
+        message_id: String,
+        duration: Option<u64>,
+    },
+    /// Makes a message found (keeping its receiver) or lost (keeping its sender); `null` turns it
+    /// back into a self-message on the lifeline it touched.
+    SeqSetMessageBoundary {
+        message_id: String,
+        boundary: Option<MessageBoundary>,
+    },
+    /// Replaces the message's tags and metadata; omitted fields are cleared.
+    SeqSetMessageAnnotations {
+        message_id: String,
+        #[serde(default)]
+        tags: Vec<String>,
+        #[serde(default)]
+        metadata: BTreeMap<String, String>,
+    },
     SeqRemoveMessage {
         message_id: String,
     },
+    /// Adds a timeline note: one participant for `left_of`/`right_of`, one or two for `over`.
+    SeqAddNote {
+        note_id: String,
+        placement: NotePlacement,
+        participant_ids: Vec<String>,
+        text: String,
+        order_key: i64,
+    },
+    SeqUpdateNote {
+        note_id: String,
+        placement: Option<NotePlacement>,
+        participant_ids: Option<Vec<String>>,
+        text: Option<String>,
+        order_key: Option<i64>,
+    },
+    SeqRemoveNote {
+        note_id: String,
+    },
     FlowAddNode {
         node_id: String,
         label: String,
@@ -809,6 +2064,19 @@ pub enum McpOp {
         node_id: String,
         note: Option<String>,
     },
+    /// Marks (`entry: true`) or unmarks a node as a traversal entry point.
+    FlowSetNodeEntry {
+        node_id: String,
+        entry: bool,
+    },
+    /// Replaces the node's tags and metadata; omitted fields are cleared.
+    FlowSetNodeAnnotations {
+        node_id: String,
+        #[serde(default)]
+        tags: Vec<String>,
+        #[serde(default)]
+        metadata: BTreeMap<String, String>,
+    },
     FlowRemoveNode {
         node_id: String,
     },
@@ -828,6 +2096,20 @@ pub enum McpOp {
         connector: Option<String>,
         style: Option<String>,
     },
+    /// Sets (or clears with `null`) the traversal cost of an edge used by `flow.shortest_path`;
+    /// `diagram.critical_path` reads it as the edge duration.
+    FlowSetEdgeWeight {
+        edge_id: String,
+        weight: Option<u64>,
+    },
+    /// Replaces the edge's tags and metadata; omitted fields are cleared.
+    FlowSetEdgeAnnotations {
+        edge_id: String,
+        #[serde(default)]
+        tags: Vec<String>,
+        #[serde(default)]
+        metadata: BTreeMap<String, String>,
+    },
     FlowRemoveEdge {
         edge_id: String,
     },
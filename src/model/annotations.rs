@@ -0,0 +1,86 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! User-assigned tags and key/value metadata on flow nodes/edges and sequence
+//! participants/messages.
+//!
+//! Annotations are not part of the Mermaid source; they live in the diagram sidecar and are
+//! edited through ops.
+
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObjectAnnotations {
+    tags: Vec<String>,
+    metadata: BTreeMap<String, String>,
+}
+
+impl ObjectAnnotations {
+    /// Tags are trimmed and deduplicated (first occurrence wins); blank tags and blank metadata
+    /// keys are dropped.
+    pub fn new<T: Into<String>>(
+        tags: impl IntoIterator<Item = T>,
+        metadata: BTreeMap<String, String>,
+    ) -> Self {
+        let mut out = Self::default();
+        for tag in tags {
+            let tag = tag.into();
+            let tag = tag.trim();
+            if !tag.is_empty() && !out.has_tag(tag) {
+                out.tags.push(tag.to_owned());
+            }
+        }
+        out.metadata = metadata
+            .into_iter()
+            .map(|(key, value)| (key.trim().to_owned(), value))
+            .filter(|(key, _)| !key.is_empty())
+            .collect();
+        out
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|candidate| candidate == tag)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.metadata.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::ObjectAnnotations;
+
+    #[test]
+    fn annotations_normalize_tags_and_metadata_keys() {
+        let annotations = ObjectAnnotations::new(
+            [" billing ", "", "billing", "legacy"],
+            BTreeMap::from([
+                (" owner ".to_owned(), "team-a".to_owned()),
+                (" ".to_owned(), "dropped".to_owned()),
+            ]),
+        );
+        assert_eq!(annotations.tags(), ["billing", "legacy"]);
+        assert_eq!(
+            annotations.metadata(),
+            &BTreeMap::from([("owner".to_owned(), "team-a".to_owned())])
+        );
+        assert!(annotations.has_tag("legacy"));
+        assert!(ObjectAnnotations::default().is_empty());
+    }
+}
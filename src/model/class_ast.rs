@@ -0,0 +1,314 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::collections::BTreeMap;
+
+use super::ids::ObjectId;
+
+/// A Mermaid `classDiagram`: classes with their members plus the relations between them.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ClassAst {
+    classes: BTreeMap<ObjectId, ClassNode>,
+    relations: BTreeMap<ObjectId, ClassRelation>,
+}
+
+impl ClassAst {
+    pub fn classes(&self) -> &BTreeMap<ObjectId, ClassNode> {
+        &self.classes
+    }
+
+    pub fn classes_mut(&mut self) -> &mut BTreeMap<ObjectId, ClassNode> {
+        &mut self.classes
+    }
+
+    pub fn relations(&self) -> &BTreeMap<ObjectId, ClassRelation> {
+        &self.relations
+    }
+
+    pub fn relations_mut(&mut self) -> &mut BTreeMap<ObjectId, ClassRelation> {
+        &mut self.relations
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClassMemberKind {
+    Attribute,
+    Method,
+}
+
+/// One line of a class body, kept verbatim (e.g. `+String name` or `+speak(int times) bool`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassMember {
+    text: String,
+}
+
+impl ClassMember {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self { text: text.into() }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Members with a parameter list are methods, like in Mermaid.
+    pub fn kind(&self) -> ClassMemberKind {
+        if self.text.contains('(') {
+            ClassMemberKind::Method
+        } else {
+            ClassMemberKind::Attribute
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassNode {
+    mermaid_id: Option<String>,
+    label: String,
+    annotation: Option<String>,
+    members: Vec<ClassMember>,
+    note: Option<String>,
+}
+
+impl ClassNode {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self::new_with(label, None)
+    }
+
+    pub fn new_with(label: impl Into<String>, mermaid_id: Option<String>) -> Self {
+        Self { mermaid_id, label: label.into(), annotation: None, members: Vec::new(), note: None }
+    }
+
+    pub fn set_mermaid_id<T: Into<String>>(&mut self, mermaid_id: Option<T>) {
+        self.mermaid_id = mermaid_id.map(Into::into);
+    }
+
+    pub fn set_label(&mut self, label: impl Into<String>) {
+        self.label = label.into();
+    }
+
+    /// Sets the `<<annotation>>` (e.g. `interface`), without the angle brackets.
+    pub fn set_annotation<T: Into<String>>(&mut self, annotation: Option<T>) {
+        self.annotation = annotation.map(Into::into);
+    }
+
+    pub fn set_note<T: Into<String>>(&mut self, note: Option<T>) {
+        self.note = note.map(Into::into);
+    }
+
+    pub fn members_mut(&mut self) -> &mut Vec<ClassMember> {
+        &mut self.members
+    }
+
+    pub fn mermaid_id(&self) -> Option<&str> {
+        self.mermaid_id.as_deref()
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn annotation(&self) -> Option<&str> {
+        self.annotation.as_deref()
+    }
+
+    pub fn members(&self) -> &[ClassMember] {
+        &self.members
+    }
+
+    pub fn attributes(&self) -> impl Iterator<Item = &ClassMember> {
+        self.members.iter().filter(|member| member.kind() == ClassMemberKind::Attribute)
+    }
+
+    pub fn methods(&self) -> impl Iterator<Item = &ClassMember> {
+        self.members.iter().filter(|member| member.kind() == ClassMemberKind::Method)
+    }
+
+    pub fn note(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClassRelationKind {
+    /// `<|--`: `from` extends `to`.
+    Inheritance,
+    /// `<|..`: `from` implements `to`.
+    Realization,
+    /// `*--`: `from` is part of `to` and shares its lifetime.
+    Composition,
+    /// `o--`: `from` is part of `to`.
+    Aggregation,
+    /// `-->`: `from` knows `to`.
+    Association,
+    /// `..>`: `from` depends on `to`.
+    Dependency,
+    /// `--`
+    Link,
+    /// `..`
+    DashedLink,
+}
+
+impl ClassRelationKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Inheritance => "inheritance",
+            Self::Realization => "realization",
+            Self::Composition => "composition",
+            Self::Aggregation => "aggregation",
+            Self::Association => "association",
+            Self::Dependency => "dependency",
+            Self::Link => "link",
+            Self::DashedLink => "dashed_link",
+        }
+    }
+
+    pub fn is_dashed(self) -> bool {
+        matches!(self, Self::Realization | Self::Dependency | Self::DashedLink)
+    }
+
+    /// Whether `to` is a parent or whole, which is written (and laid out) before `from`.
+    pub fn is_structural(self) -> bool {
+        matches!(
+            self,
+            Self::Inheritance | Self::Realization | Self::Composition | Self::Aggregation
+        )
+    }
+
+    /// Whether the `to` end carries a marker (arrowhead, triangle or diamond).
+    pub fn has_marker(self) -> bool {
+        !matches!(self, Self::Link | Self::DashedLink)
+    }
+}
+
+/// A relation between two classes.
+///
+/// `to` is the end carrying the marker: the parent of an inheritance or realization, the whole of
+/// a composition or aggregation, the target of an association or dependency. Unmarked links keep
+/// the order they were written in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassRelation {
+    from_class_id: ObjectId,
+    to_class_id: ObjectId,
+    kind: ClassRelationKind,
+    label: Option<String>,
+    from_cardinality: Option<String>,
+    to_cardinality: Option<String>,
+}
+
+impl ClassRelation {
+    pub fn new(from_class_id: ObjectId, to_class_id: ObjectId, kind: ClassRelationKind) -> Self {
+        Self {
+            from_class_id,
+            to_class_id,
+            kind,
+            label: None,
+            from_cardinality: None,
+            to_cardinality: None,
+        }
+    }
+
+    pub fn set_label<T: Into<String>>(&mut self, label: Option<T>) {
+        self.label = label.map(Into::into);
+    }
+
+    pub fn set_cardinalities<T: Into<String>>(&mut self, from: Option<T>, to: Option<T>) {
+        self.from_cardinality = from.map(Into::into);
+        self.to_cardinality = to.map(Into::into);
+    }
+
+    pub fn from_class_id(&self) -> &ObjectId {
+        &self.from_class_id
+    }
+
+    pub fn to_class_id(&self) -> &ObjectId {
+        &self.to_class_id
+    }
+
+    pub fn kind(&self) -> ClassRelationKind {
+        self.kind
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    pub fn from_cardinality(&self) -> Option<&str> {
+        self.from_cardinality.as_deref()
+    }
+
+    pub fn to_cardinality(&self) -> Option<&str> {
+        self.to_cardinality.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClassMember, ClassMemberKind, ClassNode, ClassRelation, ClassRelationKind};
+    use crate::model::ObjectId;
+
+    #[test]
+    fn members_split_into_attributes_and_methods() {
+        let mut node = ClassNode::new("Animal");
+        node.members_mut().push(ClassMember::new("+int age"));
+        node.members_mut().push(ClassMember::new("+isMammal() bool"));
+        node.members_mut().push(ClassMember::new("-String gender"));
+        node.set_annotation(Some("abstract"));
+
+        assert_eq!(node.members()[1].kind(), ClassMemberKind::Method);
+        let attributes = node.attributes().map(ClassMember::text).collect::<Vec<_>>();
+        assert_eq!(attributes, ["+int age", "-String gender"]);
+        let methods = node.methods().map(ClassMember::text).collect::<Vec<_>>();
+        assert_eq!(methods, ["+isMammal() bool"]);
+        assert_eq!(node.annotation(), Some("abstract"));
+    }
+
+    #[test]
+    fn relation_kinds_have_stable_names_and_styles() {
+        let kinds = [
+            ClassRelationKind::Inheritance,
+            ClassRelationKind::Realization,
+            ClassRelationKind::Composition,
+            ClassRelationKind::Aggregation,
+            ClassRelationKind::Association,
+            ClassRelationKind::Dependency,
+            ClassRelationKind::Link,
+            ClassRelationKind::DashedLink,
+        ];
+        let names = kinds.map(ClassRelationKind::as_str);
+        assert_eq!(
+            names,
+            [
+                "inheritance",
+                "realization",
+                "composition",
+                "aggregation",
+                "association",
+                "dependency",
+                "link",
+                "dashed_link"
+            ]
+        );
+        let dashed = kinds.iter().filter(|kind| kind.is_dashed()).count();
+        assert_eq!(dashed, 3);
+        assert!(!ClassRelationKind::Link.has_marker());
+        let structural = kinds.iter().filter(|kind| kind.is_structural()).count();
+        assert_eq!(structural, 4);
+
+        let mut relation = ClassRelation::new(
+            ObjectId::new("c:Wheel").expect("class id"),
+            ObjectId::new("c:Car").expect("class id"),
+            ClassRelationKind::Composition,
+        );
+        relation.set_cardinalities(Some("4"), Some("1"));
+        relation.set_label(Some("mounts"));
+        assert_eq!(relation.from_cardinality(), Some("4"));
+        assert_eq!(relation.to_cardinality(), Some("1"));
+        assert_eq!(relation.label(), Some("mounts"));
+    }
+}
@@ -6,22 +6,31 @@
 // This file is part of Nereid and is proprietary software.
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use super::class_ast::ClassAst;
 use super::flow_ast::FlowchartAst;
-use super::ids::DiagramId;
-use super::seq_ast::SequenceAst;
+use super::ids::{DiagramId, ObjectId};
+use super::object_ref::CategoryPath;
+use super::seq_ast::{SequenceAst, SequenceBlock};
+use super::state_ast::StateAst;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
+use std::sync::{Arc, OnceLock};
 
 /// The type of diagram.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum DiagramKind {
     Sequence,
     Flowchart,
+    State,
+    Class,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum DiagramAst {
     Sequence(SequenceAst),
     Flowchart(FlowchartAst),
+    State(StateAst),
+    Class(ClassAst),
 }
 
 impl DiagramAst {
@@ -29,10 +38,248 @@ impl DiagramAst {
         match self {
             Self::Sequence(_) => DiagramKind::Sequence,
             Self::Flowchart(_) => DiagramKind::Flowchart,
+            Self::State(_) => DiagramKind::State,
+            Self::Class(_) => DiagramKind::Class,
         }
     }
+
+    /// Whether the AST has an object with `object_id` in the category given by `segments`.
+    pub fn contains_object(&self, segments: &[String], object_id: &ObjectId) -> bool {
+        match (self, segments) {
+            (Self::Sequence(ast), [left, right]) if left == "seq" && right == "participant" => {
+                ast.participants().contains_key(object_id)
+            }
+            (Self::Sequence(ast), [left, right]) if left == "seq" && right == "message" => {
+                ast.messages().iter().any(|m| m.message_id() == object_id)
+            }
+            (Self::Sequence(ast), [left, right]) if left == "seq" && right == "block" => {
+                ast.find_block(object_id).is_some()
+            }
+            (Self::Sequence(ast), [left, right]) if left == "seq" && right == "section" => {
+                ast.find_section(object_id).is_some()
+            }
+            (Self::Sequence(ast), [left, right]) if left == "seq" && right == "group" => {
+                ast.find_group(object_id).is_some()
+            }
+            (Self::Sequence(ast), [left, right]) if left == "seq" && right == "activation" => {
+                ast.find_activation(object_id).is_some()
+            }
+            (Self::Flowchart(ast), [left, right]) if left == "flow" && right == "node" => {
+                ast.nodes().contains_key(object_id)
+            }
+            (Self::Flowchart(ast), [left, right]) if left == "flow" && right == "edge" => {
+                ast.edges().contains_key(object_id)
+            }
+            (Self::Flowchart(ast), [left, right]) if left == "flow" && right == "subgraph" => {
+                ast.subgraphs().contains_key(object_id)
+            }
+            _ => false,
+        }
+    }
+
+    /// Every object [`Self::contains_object`] can find, keyed by category path (`seq/message`).
+    pub fn object_index(&self) -> DiagramObjectIndex {
+        fn collect_blocks(blocks: &[SequenceBlock], index: &mut DiagramObjectIndex) {
+            for block in blocks {
+                index.entry("seq/block".to_owned()).or_default().insert(block.block_id().clone());
+                for section in block.sections() {
+                    index
+                        .entry("seq/section".to_owned())
+                        .or_default()
+                        .insert(section.section_id().clone());
+                }
+                collect_blocks(block.blocks(), index);
+            }
+        }
+
+        let mut index = DiagramObjectIndex::new();
+        let mut insert_all = |category: &str, ids: Vec<&ObjectId>| {
+            if !ids.is_empty() {
+                index.entry(category.to_owned()).or_default().extend(ids.into_iter().cloned());
+            }
+        };
+        match self {
+            Self::Sequence(ast) => {
+                insert_all("seq/participant", ast.participants().keys().collect());
+                insert_all("seq/message", ast.messages().iter().map(|m| m.message_id()).collect());
+                insert_all("seq/group", ast.groups().iter().map(|g| g.group_id()).collect());
+                insert_all(
+                    "seq/activation",
+                    ast.activations().iter().map(|a| a.activation_id()).collect(),
+                );
+                collect_blocks(ast.blocks(), &mut index);
+            }
+            Self::Flowchart(ast) => {
+                insert_all("flow/node", ast.nodes().keys().collect());
+                insert_all("flow/edge", ast.edges().keys().collect());
+                insert_all("flow/subgraph", ast.subgraphs().keys().collect());
+            }
+            Self::State(_) | Self::Class(_) => {}
+        }
+        index
+    }
+
+    fn empty(kind: DiagramKind) -> Self {
+        match kind {
+            DiagramKind::Sequence => Self::Sequence(SequenceAst::default()),
+            DiagramKind::Flowchart => Self::Flowchart(FlowchartAst::default()),
+            DiagramKind::State => Self::State(StateAst::default()),
+            DiagramKind::Class => Self::Class(ClassAst::default()),
+        }
+    }
+}
+
+/// Object ids grouped by category path, e.g. `flow/node` -> `{n:a, n:b}`.
+pub type DiagramObjectIndex = BTreeMap<String, BTreeSet<ObjectId>>;
+
+type DiagramLoader = Arc<dyn Fn() -> Result<DiagramAst, String> + Send + Sync>;
+
+/// A diagram's AST, either parsed up front or parsed by a loader on first access.
+///
+/// A loader that fails leaves an empty AST of the diagram's kind and records the error; the error
+/// stays set after the AST is replaced so the diagram's files are never overwritten.
+///
+/// Lazy handles can carry a fingerprint of the files they load from and an index of the objects
+/// those files hold, so comparing handles and checking object refs does not force a parse.
+#[derive(Clone)]
+pub struct DiagramHandle {
+    kind: DiagramKind,
+    ast: OnceLock<DiagramAst>,
+    loader: Option<DiagramLoader>,
+    load_error: OnceLock<String>,
+    source_fingerprint: Option<u64>,
+    object_index: Option<Arc<DiagramObjectIndex>>,
+}
+
+impl DiagramHandle {
+    pub fn loaded(ast: DiagramAst) -> Self {
+        Self {
+            kind: ast.kind(),
+            ast: OnceLock::from(ast),
+            loader: None,
+            load_error: OnceLock::new(),
+            source_fingerprint: None,
+            object_index: None,
+        }
+    }
+
+    /// A handle whose AST is produced by `loader` the first time it is accessed.
+    pub fn lazy(
+        kind: DiagramKind,
+        loader: impl Fn() -> Result<DiagramAst, String> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            kind,
+            ast: OnceLock::new(),
+            loader: Some(Arc::new(loader)),
+            load_error: OnceLock::new(),
+            source_fingerprint: None,
+            object_index: None,
+        }
+    }
+
+    /// Records a fingerprint of the files the loader reads; handles with equal fingerprints
+    /// compare equal without parsing.
+    pub fn with_source_fingerprint(mut self, fingerprint: u64) -> Self {
+        self.source_fingerprint = Some(fingerprint);
+        self
+    }
+
+    /// Records which objects the loader's files hold, answering existence checks before parsing.
+    pub fn with_object_index(mut self, object_index: DiagramObjectIndex) -> Self {
+        self.object_index = Some(Arc::new(object_index));
+        self
+    }
+
+    pub fn kind(&self) -> DiagramKind {
+        self.kind
+    }
+
+    /// The fingerprint of the files this handle was loaded from, until its AST is replaced.
+    pub fn source_fingerprint(&self) -> Option<u64> {
+        self.source_fingerprint
+    }
+
+    /// Checks for an object without parsing when the handle is unloaded and carries an index.
+    pub fn contains_object(&self, category: &CategoryPath, object_id: &ObjectId) -> bool {
+        match self.object_index.as_ref() {
+            Some(index) if !self.is_loaded() => {
+                index.get(&category.segments().join("/")).is_some_and(|ids| ids.contains(object_id))
+            }
+            _ => self.get().contains_object(category.segments(), object_id),
+        }
+    }
+
+    /// Returns `true` once the AST has been parsed (or its loader has failed).
+    pub fn is_loaded(&self) -> bool {
+        self.ast.get().is_some()
+    }
+
+    /// The loader's error, if it has run and failed; does not trigger loading.
+    pub fn load_error(&self) -> Option<&str> {
+        self.load_error.get().map(String::as_str)
+    }
+
+    pub fn get(&self) -> &DiagramAst {
+        self.ast.get_or_init(|| {
+            let loaded = match self.loader.as_ref() {
+                Some(load) => load(),
+                None => Err("diagram has no loader".to_owned()),
+            };
+            match loaded {
+                Ok(ast) if ast.kind() == self.kind => ast,
+                Ok(ast) => {
+                    let mismatch =
+                        DiagramAstKindMismatch { expected: self.kind, found: ast.kind() };
+                    let _ = self.load_error.set(mismatch.to_string());
+                    DiagramAst::empty(self.kind)
+                }
+                Err(err) => {
+                    let _ = self.load_error.set(err);
+                    DiagramAst::empty(self.kind)
+                }
+            }
+        })
+    }
+
+    fn replace(&mut self, ast: DiagramAst) -> DiagramAst {
+        self.get();
+        let previous = self.ast.take().expect("diagram ast loaded");
+        self.ast = OnceLock::from(ast);
+        self.loader = None;
+        self.source_fingerprint = None;
+        self.object_index = None;
+        previous
+    }
 }
 
+impl fmt::Debug for DiagramHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DiagramHandle")
+            .field("kind", &self.kind)
+            .field("ast", &self.ast.get())
+            .field("load_error", &self.load_error())
+            .field("source_fingerprint", &self.source_fingerprint)
+            .finish()
+    }
+}
+
+impl PartialEq for DiagramHandle {
+    /// Handles loaded from the same files are equal whether or not either has been parsed;
+    /// otherwise the ASTs are compared, parsing them if needed.
+    fn eq(&self, other: &Self) -> bool {
+        if self.kind != other.kind {
+            return false;
+        }
+        match (self.source_fingerprint, other.source_fingerprint) {
+            (Some(left), Some(right)) => left == right,
+            _ => self.get() == other.get(),
+        }
+    }
+}
+
+impl Eq for DiagramHandle {}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct DiagramAstKindMismatch {
     expected: DiagramKind,
@@ -67,14 +314,33 @@ pub struct Diagram {
     diagram_id: DiagramId,
     name: String,
     kind: DiagramKind,
-    ast: DiagramAst,
     rev: u64,
+    description: Option<String>,
+    archived: bool,
+    read_only: bool,
+    // Last, so the derived `PartialEq` only compares (and possibly parses) ASTs when all the
+    // cheap fields already match.
+    ast: DiagramHandle,
 }
 
 impl Diagram {
     pub fn new(diagram_id: DiagramId, name: impl Into<String>, ast: DiagramAst) -> Self {
+        Self::from_handle(diagram_id, name, DiagramHandle::loaded(ast))
+    }
+
+    /// Builds a diagram around `ast`, which may still be waiting to be parsed.
+    pub fn from_handle(diagram_id: DiagramId, name: impl Into<String>, ast: DiagramHandle) -> Self {
         let kind = ast.kind();
-        Self { diagram_id, name: name.into(), kind, ast, rev: 0 }
+        Self {
+            diagram_id,
+            name: name.into(),
+            kind,
+            rev: 0,
+            description: None,
+            archived: false,
+            read_only: false,
+            ast,
+        }
     }
 
     pub fn diagram_id(&self) -> &DiagramId {
@@ -89,8 +355,54 @@ impl Diagram {
         self.kind
     }
 
+    /// Long-form Markdown notes about the diagram as a whole (distinct from per-object notes).
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Sets the diagram description; blank text clears it.
+    pub fn set_description<T: Into<String>>(&mut self, description: Option<T>) {
+        self.description = description
+            .map(|description| description.into().trim().to_owned())
+            .filter(|description| !description.is_empty());
+    }
+
+    /// Archived diagrams stay loadable but are left out of cycling, search and default listings.
+    pub fn is_archived(&self) -> bool {
+        self.archived
+    }
+
+    pub fn set_archived(&mut self, archived: bool) {
+        self.archived = archived;
+    }
+
+    /// Read-only diagrams reject ops and edits until unlocked.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// The diagram's AST; parses it first when the diagram was loaded lazily.
     pub fn ast(&self) -> &DiagramAst {
-        &self.ast
+        self.ast.get()
+    }
+
+    /// Returns `true` once the AST is in memory; lazily loaded diagrams start out unloaded.
+    pub fn is_loaded(&self) -> bool {
+        self.ast.is_loaded()
+    }
+
+    /// See [`DiagramHandle::contains_object`].
+    pub fn contains_object(&self, category: &CategoryPath, object_id: &ObjectId) -> bool {
+        self.ast.contains_object(category, object_id)
+    }
+
+    /// Why a lazily loaded AST could not be parsed; such diagrams show up empty.
+    pub fn load_error(&self) -> Option<&str> {
+        self.ast.load_error()
     }
 
     pub fn replace_ast(&mut self, ast: DiagramAst) -> Result<DiagramAst, DiagramAstKindMismatch> {
@@ -99,7 +411,7 @@ impl Diagram {
             return Err(DiagramAstKindMismatch { expected: self.kind, found });
         }
 
-        Ok(std::mem::replace(&mut self.ast, ast))
+        Ok(self.ast.replace(ast))
     }
 
     pub fn set_ast(&mut self, ast: DiagramAst) -> Result<(), DiagramAstKindMismatch> {
@@ -121,7 +433,10 @@ impl Diagram {
 
 #[cfg(test)]
 mod tests {
-    use super::{Diagram, DiagramAst, DiagramAstKindMismatch, DiagramKind};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::{Diagram, DiagramAst, DiagramAstKindMismatch, DiagramHandle, DiagramKind};
     use crate::model::{DiagramId, FlowchartAst, SequenceAst};
 
     #[test]
@@ -166,4 +481,41 @@ mod tests {
         assert_eq!(diagram.kind(), DiagramKind::Sequence);
         assert_eq!(diagram.ast().kind(), DiagramKind::Sequence);
     }
+
+    #[test]
+    fn lazy_diagram_parses_its_ast_once_on_first_access() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let loader_calls = calls.clone();
+        let handle = DiagramHandle::lazy(DiagramKind::Flowchart, move || {
+            loader_calls.fetch_add(1, Ordering::SeqCst);
+            Ok(DiagramAst::Flowchart(FlowchartAst::default()))
+        });
+        let diagram =
+            Diagram::from_handle(DiagramId::new("d1").expect("diagram id"), "Lazy", handle);
+
+        assert_eq!(diagram.kind(), DiagramKind::Flowchart);
+        assert!(!diagram.is_loaded());
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        assert_eq!(diagram.ast().kind(), DiagramKind::Flowchart);
+        assert_eq!(diagram.ast().kind(), DiagramKind::Flowchart);
+        assert!(diagram.is_loaded());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(diagram.load_error(), None);
+    }
+
+    #[test]
+    fn lazy_diagram_that_fails_to_load_is_empty_and_keeps_the_error() {
+        let handle =
+            DiagramHandle::lazy(DiagramKind::Sequence, || Err("unexpected token".to_owned()));
+        let mut diagram =
+            Diagram::from_handle(DiagramId::new("d1").expect("diagram id"), "Broken", handle);
+
+        assert_eq!(diagram.load_error(), None);
+        assert_eq!(diagram.ast(), &DiagramAst::Sequence(SequenceAst::default()));
+        assert_eq!(diagram.load_error(), Some("unexpected token"));
+
+        diagram.set_ast(DiagramAst::Sequence(SequenceAst::default())).expect("set_ast");
+        assert_eq!(diagram.load_error(), Some("unexpected token"));
+    }
 }
@@ -6,8 +6,9 @@
 // This file is part of Nereid and is proprietary software.
 // Unauthorized copying, modification, or distribution is prohibited.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
+use super::annotations::ObjectAnnotations;
 use super::ids::ObjectId;
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
@@ -15,8 +16,12 @@ pub struct FlowchartAst {
     nodes: BTreeMap<ObjectId, FlowNode>,
     edges: BTreeMap<ObjectId, FlowEdge>,
     default_edge_style: Option<String>,
-    groups: BTreeMap<ObjectId, FlowGroup>,
-    node_groups: BTreeMap<ObjectId, ObjectId>,
+    direction: Option<FlowDirection>,
+    subgraphs: BTreeMap<ObjectId, FlowSubgraph>,
+    node_subgraphs: BTreeMap<ObjectId, ObjectId>,
+    node_order_hints: BTreeMap<ObjectId, u32>,
+    node_pins: BTreeMap<ObjectId, FlowNodePin>,
+    entry_nodes: BTreeSet<ObjectId>,
 }
 
 impl FlowchartAst {
@@ -44,24 +49,161 @@ impl FlowchartAst {
         self.default_edge_style = style.map(Into::into);
     }
 
-    pub fn groups(&self) -> &BTreeMap<ObjectId, FlowGroup> {
-        &self.groups
+    /// Direction from the `flowchart <dir>` header; `None` when the header has none.
+    pub fn direction(&self) -> Option<FlowDirection> {
+        self.direction
     }
 
-    pub fn groups_mut(&mut self) -> &mut BTreeMap<ObjectId, FlowGroup> {
-        &mut self.groups
+    pub fn set_direction(&mut self, direction: Option<FlowDirection>) {
+        self.direction = direction;
     }
 
-    pub fn node_groups(&self) -> &BTreeMap<ObjectId, ObjectId> {
-        &self.node_groups
+    /// Mermaid `subgraph ... end` blocks, keyed by `sg:<id>`.
+    pub fn subgraphs(&self) -> &BTreeMap<ObjectId, FlowSubgraph> {
+        &self.subgraphs
     }
 
-    pub fn node_groups_mut(&mut self) -> &mut BTreeMap<ObjectId, ObjectId> {
-        &mut self.node_groups
+    pub fn subgraphs_mut(&mut self) -> &mut BTreeMap<ObjectId, FlowSubgraph> {
+        &mut self.subgraphs
     }
 
-    pub fn node_group(&self, node_id: &ObjectId) -> Option<&ObjectId> {
-        self.node_groups.get(node_id)
+    /// Innermost subgraph of each grouped node.
+    pub fn node_subgraphs(&self) -> &BTreeMap<ObjectId, ObjectId> {
+        &self.node_subgraphs
+    }
+
+    pub fn node_subgraphs_mut(&mut self) -> &mut BTreeMap<ObjectId, ObjectId> {
+        &mut self.node_subgraphs
+    }
+
+    pub fn node_subgraph(&self, node_id: &ObjectId) -> Option<&ObjectId> {
+        self.node_subgraphs.get(node_id)
+    }
+
+    /// Subgraphs enclosing `node_id`, innermost first.
+    pub fn node_subgraph_chain(&self, node_id: &ObjectId) -> Vec<&ObjectId> {
+        let mut chain = Vec::new();
+        let mut current = self.node_subgraph(node_id);
+        while let Some(subgraph_id) = current {
+            if chain.contains(&subgraph_id) {
+                break;
+            }
+            chain.push(subgraph_id);
+            current = self.subgraphs.get(subgraph_id).and_then(FlowSubgraph::parent);
+        }
+        chain
+    }
+
+    /// Nodes inside `subgraph_id`, including those of nested subgraphs.
+    pub fn subgraph_members(&self, subgraph_id: &ObjectId) -> BTreeSet<ObjectId> {
+        self.nodes
+            .keys()
+            .filter(|node_id| self.node_subgraph_chain(node_id).contains(&subgraph_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Persisted in-layer ordering hints (lower sorts first), e.g. from a tidy layout pass.
+    pub fn node_order_hints(&self) -> &BTreeMap<ObjectId, u32> {
+        &self.node_order_hints
+    }
+
+    pub fn node_order_hints_mut(&mut self) -> &mut BTreeMap<ObjectId, u32> {
+        &mut self.node_order_hints
+    }
+
+    /// Grid positions the user pinned nodes to; layout keeps them where the edges allow.
+    pub fn node_pins(&self) -> &BTreeMap<ObjectId, FlowNodePin> {
+        &self.node_pins
+    }
+
+    pub fn node_pins_mut(&mut self) -> &mut BTreeMap<ObjectId, FlowNodePin> {
+        &mut self.node_pins
+    }
+
+    /// Nodes explicitly marked as entry points (preferred traversal roots).
+    pub fn entry_nodes(&self) -> &BTreeSet<ObjectId> {
+        &self.entry_nodes
+    }
+
+    pub fn entry_nodes_mut(&mut self) -> &mut BTreeSet<ObjectId> {
+        &mut self.entry_nodes
+    }
+
+    pub fn is_entry_node(&self, node_id: &ObjectId) -> bool {
+        self.entry_nodes.contains(node_id)
+    }
+}
+
+/// Mermaid flowchart direction.
+///
+/// Layers run along the direction: sources sit at its start and edges point towards its end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowDirection {
+    /// `TD` / `TB`
+    TopDown,
+    /// `BT`
+    BottomUp,
+    /// `LR`
+    LeftRight,
+    /// `RL`
+    RightLeft,
+}
+
+impl FlowDirection {
+    /// Parses a Mermaid direction token (`TD`, `TB`, `BT`, `LR`, `RL`).
+    pub fn from_mermaid(token: &str) -> Option<Self> {
+        match token {
+            "TD" | "TB" => Some(Self::TopDown),
+            "BT" => Some(Self::BottomUp),
+            "LR" => Some(Self::LeftRight),
+            "RL" => Some(Self::RightLeft),
+            _ => None,
+        }
+    }
+
+    pub fn as_mermaid(self) -> &'static str {
+        match self {
+            Self::TopDown => "TD",
+            Self::BottomUp => "BT",
+            Self::LeftRight => "LR",
+            Self::RightLeft => "RL",
+        }
+    }
+
+    /// Whether layers stack as rows (`TD`/`BT`) rather than columns.
+    pub fn is_vertical(self) -> bool {
+        matches!(self, Self::TopDown | Self::BottomUp)
+    }
+
+    /// Whether layers are drawn from the end of the axis back to its start (`BT`/`RL`).
+    pub fn is_reversed(self) -> bool {
+        matches!(self, Self::BottomUp | Self::RightLeft)
+    }
+}
+
+/// A manually chosen grid position for a flowchart node.
+///
+/// `layer` counts from the sources (so it does not depend on the direction) and `index` is the
+/// position within that layer. Layout never moves a node closer to the sources than its incoming
+/// edges allow, and clamps `index` to the layer's size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FlowNodePin {
+    layer: u32,
+    index: u32,
+}
+
+impl FlowNodePin {
+    pub fn new(layer: u32, index: u32) -> Self {
+        Self { layer, index }
+    }
+
+    pub fn layer(&self) -> u32 {
+        self.layer
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
     }
 }
 
@@ -71,11 +213,12 @@ pub struct FlowNode {
     label: String,
     shape: String,
     note: Option<String>,
+    annotations: ObjectAnnotations,
 }
 
 impl FlowNode {
     pub fn new(label: impl Into<String>) -> Self {
-        Self { mermaid_id: None, label: label.into(), shape: "rect".to_owned(), note: None }
+        Self::new_with(label, "rect", None)
     }
 
     pub fn new_with(
@@ -83,7 +226,13 @@ impl FlowNode {
         shape: impl Into<String>,
         mermaid_id: Option<String>,
     ) -> Self {
-        Self { mermaid_id, label: label.into(), shape: shape.into(), note: None }
+        Self {
+            mermaid_id,
+            label: label.into(),
+            shape: shape.into(),
+            note: None,
+            annotations: ObjectAnnotations::default(),
+        }
     }
 
     pub fn set_mermaid_id<T: Into<String>>(&mut self, mermaid_id: Option<T>) {
@@ -117,6 +266,14 @@ impl FlowNode {
     pub fn note(&self) -> Option<&str> {
         self.note.as_deref()
     }
+
+    pub fn annotations(&self) -> &ObjectAnnotations {
+        &self.annotations
+    }
+
+    pub fn set_annotations(&mut self, annotations: ObjectAnnotations) {
+        self.annotations = annotations;
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -126,21 +283,33 @@ pub struct FlowEdge {
     label: Option<String>,
     connector: Option<String>,
     style: Option<String>,
+    weight: Option<u64>,
+    annotations: ObjectAnnotations,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct FlowGroup {
+pub struct FlowSubgraph {
     mermaid_id: Option<String>,
     label: String,
+    parent: Option<ObjectId>,
 }
 
-impl FlowGroup {
+impl FlowSubgraph {
     pub fn new(label: impl Into<String>) -> Self {
-        Self { mermaid_id: None, label: label.into() }
+        Self { mermaid_id: None, label: label.into(), parent: None }
     }
 
     pub fn new_with(label: impl Into<String>, mermaid_id: Option<String>) -> Self {
-        Self { mermaid_id, label: label.into() }
+        Self { mermaid_id, label: label.into(), parent: None }
+    }
+
+    /// Enclosing subgraph for nested `subgraph` blocks.
+    pub fn parent(&self) -> Option<&ObjectId> {
+        self.parent.as_ref()
+    }
+
+    pub fn set_parent(&mut self, parent: Option<ObjectId>) {
+        self.parent = parent;
     }
 
     pub fn set_mermaid_id<T: Into<String>>(&mut self, mermaid_id: Option<T>) {
@@ -162,7 +331,7 @@ impl FlowGroup {
 
 impl FlowEdge {
     pub fn new(from_node_id: ObjectId, to_node_id: ObjectId) -> Self {
-        Self { from_node_id, to_node_id, label: None, connector: None, style: None }
+        Self::new_with(from_node_id, to_node_id, None, None)
     }
 
     pub fn new_with(
@@ -171,7 +340,15 @@ impl FlowEdge {
         label: Option<String>,
         style: Option<String>,
     ) -> Self {
-        Self { from_node_id, to_node_id, label, connector: None, style }
+        Self {
+            from_node_id,
+            to_node_id,
+            label,
+            connector: None,
+            style,
+            weight: None,
+            annotations: ObjectAnnotations::default(),
+        }
     }
 
     pub fn set_label<T: Into<String>>(&mut self, label: Option<T>) {
@@ -186,6 +363,10 @@ impl FlowEdge {
         self.style = style.map(Into::into);
     }
 
+    pub fn set_weight(&mut self, weight: Option<u64>) {
+        self.weight = weight;
+    }
+
     pub fn from_node_id(&self) -> &ObjectId {
         &self.from_node_id
     }
@@ -205,6 +386,36 @@ impl FlowEdge {
     pub fn style(&self) -> Option<&str> {
         self.style.as_deref()
     }
+
+    /// Explicit traversal cost stored as edge metadata.
+    pub fn weight(&self) -> Option<u64> {
+        self.weight
+    }
+
+    /// Cost used by weighted queries: the explicit weight, else a weight parsed from the label
+    /// (`5`, `w=5` or `cost=5`), else `1`.
+    pub fn effective_weight(&self) -> u64 {
+        self.weight.or_else(|| self.label().and_then(weight_from_label)).unwrap_or(1)
+    }
+
+    pub fn annotations(&self) -> &ObjectAnnotations {
+        &self.annotations
+    }
+
+    pub fn set_annotations(&mut self, annotations: ObjectAnnotations) {
+        self.annotations = annotations;
+    }
+}
+
+fn weight_from_label(label: &str) -> Option<u64> {
+    let label = label.trim();
+    if let Ok(weight) = label.parse::<u64>() {
+        return Some(weight);
+    }
+    label.split_whitespace().find_map(|token| {
+        let (key, value) = token.split_once('=')?;
+        matches!(key, "w" | "cost").then(|| value.parse::<u64>().ok()).flatten()
+    })
 }
 
 #[cfg(test)]
@@ -291,4 +502,23 @@ mod tests {
         assert_eq!(edge.label(), Some("maybe"));
         assert_eq!(edge.style(), Some("thick"));
     }
+
+    #[test]
+    fn flow_edge_effective_weight_prefers_explicit_weight_then_label() {
+        let from = ObjectId::new("n1").expect("from node id");
+        let to = ObjectId::new("n2").expect("to node id");
+        let mut edge = FlowEdge::new(from, to);
+        assert_eq!(edge.effective_weight(), 1);
+
+        edge.set_label(Some(" 7 "));
+        assert_eq!(edge.effective_weight(), 7);
+        edge.set_label(Some("retry cost=3"));
+        assert_eq!(edge.effective_weight(), 3);
+        edge.set_label(Some("yes"));
+        assert_eq!(edge.effective_weight(), 1);
+
+        edge.set_weight(Some(4));
+        assert_eq!(edge.weight(), Some(4));
+        assert_eq!(edge.effective_weight(), 4);
+    }
 }
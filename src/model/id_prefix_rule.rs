@@ -0,0 +1,146 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Per-session id prefix conventions such as `flow/node => svc|db`.
+//!
+//! A rule names an object category and the prefixes its object ids may carry. The Mermaid
+//! importers map identifiers like `svc_orders` to `svc:orders` for nodes and participants, and
+//! the id lint reports objects whose ids break a rule.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::ids::{id_prefix, validate_id_prefix};
+use super::object_ref::CategoryPath;
+use super::ObjectId;
+
+/// `<category> => <prefix>|<prefix>...`; ids of objects in the category must use one prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdPrefixRule {
+    category: CategoryPath,
+    prefixes: Vec<String>,
+}
+
+impl IdPrefixRule {
+    pub fn category(&self) -> &CategoryPath {
+        &self.category
+    }
+
+    pub fn prefixes(&self) -> &[String] {
+        &self.prefixes
+    }
+
+    /// Whether `object_id` starts with `<prefix>:` for one of the rule's prefixes.
+    pub fn allows(&self, object_id: &ObjectId) -> bool {
+        id_prefix(object_id.as_str())
+            .is_some_and(|prefix| self.prefixes.iter().any(|allowed| allowed == prefix))
+    }
+
+    /// `<prefix>:<rest>` when the Mermaid identifier `ident` reads `<prefix>_<rest>`.
+    pub fn map_mermaid_ident(&self, ident: &str) -> Option<ObjectId> {
+        self.prefixes.iter().find_map(|prefix| {
+            let rest = ident.strip_prefix(prefix.as_str())?.strip_prefix('_')?;
+            if rest.is_empty() {
+                return None;
+            }
+            ObjectId::new(format!("{prefix}:{rest}")).ok()
+        })
+    }
+}
+
+/// The object id for Mermaid identifier `ident` in `category`: mapped by the first rule for the
+/// category that recognizes the identifier, `<default_prefix>:<ident>` otherwise.
+///
+/// `ident` must already be a valid Mermaid identifier.
+pub fn conventional_object_id(
+    rules: &[IdPrefixRule],
+    category: [&str; 2],
+    default_prefix: &str,
+    ident: &str,
+) -> ObjectId {
+    rules
+        .iter()
+        .filter(|rule| rule.category.segments().iter().map(String::as_str).eq(category))
+        .find_map(|rule| rule.map_mermaid_ident(ident))
+        .unwrap_or_else(|| {
+            ObjectId::new(format!("{default_prefix}:{ident}")).expect("valid object id")
+        })
+}
+
+impl fmt::Display for IdPrefixRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} => {}", self.category.segments().join("/"), self.prefixes.join("|"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseIdPrefixRuleError {
+    input: String,
+}
+
+impl fmt::Display for ParseIdPrefixRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid id prefix rule {:?} (expected `<category> => <prefix>|<prefix>...`, e.g. \
+             `flow/node => svc|db`)",
+            self.input
+        )
+    }
+}
+
+impl std::error::Error for ParseIdPrefixRuleError {}
+
+impl FromStr for IdPrefixRule {
+    type Err = ParseIdPrefixRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseIdPrefixRuleError { input: s.to_owned() };
+        let (category, prefixes) = s.split_once("=>").ok_or_else(err)?;
+        let category = CategoryPath::new(
+            category.trim().split('/').map(|segment| segment.trim().to_owned()).collect(),
+        )
+        .map_err(|_| err())?;
+
+        let mut deduped = Vec::<String>::new();
+        for prefix in prefixes.split('|').map(str::trim) {
+            validate_id_prefix(prefix).map_err(|_| err())?;
+            if !deduped.iter().any(|existing| existing == prefix) {
+                deduped.push(prefix.to_owned());
+            }
+        }
+        Ok(Self { category, prefixes: deduped })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{conventional_object_id, IdPrefixRule};
+    use crate::model::ObjectId;
+
+    #[test]
+    fn id_prefix_rules_roundtrip_check_ids_and_map_mermaid_idents() {
+        let rule = " flow/node=>svc | db|svc ".parse::<IdPrefixRule>().expect("rule");
+        assert_eq!(rule.to_string(), "flow/node => svc|db");
+        assert!(rule.allows(&ObjectId::new("db:orders").expect("id")));
+        assert!(!rule.allows(&ObjectId::new("n:orders").expect("id")));
+        assert!(!rule.allows(&ObjectId::new("svc").expect("id")));
+
+        let rules = [rule];
+        let node = |ident| conventional_object_id(&rules, ["flow", "node"], "n", ident);
+        assert_eq!(node("svc_orders").as_str(), "svc:orders");
+        assert_eq!(node("orders").as_str(), "n:orders");
+        assert_eq!(node("svc_").as_str(), "n:svc_");
+        let participant = conventional_object_id(&rules, ["seq", "participant"], "p", "svc_api");
+        assert_eq!(participant.as_str(), "p:svc_api");
+
+        for invalid in ["flow/node", "flow//node => svc", "flow/node => ", "flow/node => a:b"] {
+            assert!(invalid.parse::<IdPrefixRule>().is_err(), "{invalid}");
+        }
+    }
+}
@@ -76,6 +76,7 @@ impl<T> TryFrom<String> for Id<T> {
 pub enum IdError {
     Empty,
     ContainsSlash,
+    InvalidPrefix,
 }
 
 impl fmt::Display for IdError {
@@ -83,6 +84,9 @@ impl fmt::Display for IdError {
         match self {
             Self::Empty => f.write_str("id must not be empty"),
             Self::ContainsSlash => f.write_str("id must not contain '/'"),
+            Self::InvalidPrefix => {
+                f.write_str("id prefix must be ASCII letters, digits, '_' or '-'")
+            }
         }
     }
 }
@@ -99,6 +103,22 @@ fn validate_id_segment(value: &str) -> Result<(), IdError> {
     Ok(())
 }
 
+/// The prefix of a conventional id such as `n` in `n:A` or `svc` in `svc:orders`.
+pub fn id_prefix(value: &str) -> Option<&str> {
+    value.split_once(':').map(|(prefix, _)| prefix).filter(|prefix| !prefix.is_empty())
+}
+
+/// Checks that `prefix` can start a conventional `<prefix>:<name>` id.
+pub fn validate_id_prefix(prefix: &str) -> Result<(), IdError> {
+    if prefix.is_empty() {
+        return Err(IdError::Empty);
+    }
+    if !prefix.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-') {
+        return Err(IdError::InvalidPrefix);
+    }
+    Ok(())
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum SessionIdTag {}
 pub type SessionId = Id<SessionIdTag>;
@@ -125,7 +145,7 @@ pub type ObjectId = Id<ObjectIdTag>;
 
 #[cfg(test)]
 mod tests {
-    use super::{Id, IdError};
+    use super::{id_prefix, validate_id_prefix, Id, IdError};
 
     #[test]
     fn id_rejects_empty() {
@@ -138,4 +158,14 @@ mod tests {
         let result: Result<Id<()>, _> = Id::new("a/b");
         assert_eq!(result, Err(IdError::ContainsSlash));
     }
+
+    #[test]
+    fn id_prefixes_are_split_off_and_validated() {
+        assert_eq!(id_prefix("svc:orders"), Some("svc"));
+        assert_eq!(id_prefix("orders"), None);
+        assert_eq!(id_prefix(":orders"), None);
+        assert_eq!(validate_id_prefix("db-2"), Ok(()));
+        assert_eq!(validate_id_prefix(""), Err(IdError::Empty));
+        assert_eq!(validate_id_prefix("a:b"), Err(IdError::InvalidPrefix));
+    }
 }
@@ -8,27 +8,46 @@
 
 //! Core data model and AST types.
 //!
-//! Sessions contain diagrams (flowchart/sequence) plus walkthroughs and cross-references.
+//! Sessions contain diagrams (flowchart/sequence/state/class) plus walkthroughs and cross-references.
 
+pub mod annotations;
+pub mod class_ast;
 pub mod diagram;
 pub(crate) mod fixtures;
 pub mod flow_ast;
+pub mod id_prefix_rule;
 pub mod ids;
 pub mod object_ref;
+pub mod selection;
 pub mod seq_ast;
 pub mod session;
+pub mod state_ast;
+pub mod style_rule;
 pub mod walkthrough;
 pub mod xref;
 
-pub use diagram::{Diagram, DiagramAst, DiagramAstKindMismatch, DiagramKind};
-pub use flow_ast::{FlowEdge, FlowNode, FlowchartAst};
+pub use annotations::ObjectAnnotations;
+pub use class_ast::{
+    ClassAst, ClassMember, ClassMemberKind, ClassNode, ClassRelation, ClassRelationKind,
+};
+pub use diagram::{
+    Diagram, DiagramAst, DiagramAstKindMismatch, DiagramHandle, DiagramKind, DiagramObjectIndex,
+};
+pub use flow_ast::{FlowDirection, FlowEdge, FlowNode, FlowNodePin, FlowSubgraph, FlowchartAst};
+pub use id_prefix_rule::{conventional_object_id, IdPrefixRule, ParseIdPrefixRuleError};
 pub use ids::{
     DiagramId, Id, IdError, ObjectId, SessionId, WalkthroughId, WalkthroughNodeId, XRefId,
 };
 pub use object_ref::{CategoryPath, CategoryPathError, ObjectRef, ParseObjectRefError};
+pub use selection::{SelectionEntry, SelectionLog};
 pub use seq_ast::{
-    SequenceAst, SequenceMessage, SequenceMessageKind, SequenceNote, SequenceParticipant,
+    SequenceActivation, SequenceAst, SequenceGroup, SequenceMessage, SequenceMessageBoundary,
+    SequenceMessageKind, SequenceNote, SequenceNotePlacement, SequenceParticipant,
 };
 pub use session::Session;
+pub use state_ast::{StateAst, StateNode, StateNodeKind, StateTransition};
+pub use style_rule::{
+    Comparison, ObjectStyle, ParseStyleRuleError, StyleCondition, StyleEffect, StyleRule,
+};
 pub use walkthrough::{Walkthrough, WalkthroughEdge, WalkthroughNode};
 pub use xref::{ParseXRefStatusError, XRef, XRefStatus};
@@ -0,0 +1,143 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Last-writer-wins bookkeeping for the shared multi-selection.
+//!
+//! The TUI and MCP clients persist the selection through the same session folder. Each side keeps
+//! a log of when it last selected or deselected every ref (deselections stay as tombstones), and
+//! merging two logs keeps the newer entry per ref, so toggles made on one side survive a save
+//! from the other side that had not seen them yet.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::object_ref::ObjectRef;
+
+/// Tombstones kept after a merge; older deselections are forgotten first.
+const MAX_TOMBSTONES: usize = 256;
+
+/// The last selection change recorded for one ref.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SelectionEntry {
+    /// Wall-clock milliseconds of the change, kept strictly increasing within one log.
+    pub stamp: u64,
+    pub selected: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelectionLog {
+    entries: BTreeMap<ObjectRef, SelectionEntry>,
+}
+
+impl SelectionLog {
+    /// A log for a selection with no recorded history (stamp `0`), e.g. from older session files.
+    pub fn from_selected<I: IntoIterator<Item = ObjectRef>>(selected: I) -> Self {
+        let entries = selected
+            .into_iter()
+            .map(|object_ref| (object_ref, SelectionEntry { stamp: 0, selected: true }))
+            .collect();
+        Self { entries }
+    }
+
+    pub fn entries(&self) -> &BTreeMap<ObjectRef, SelectionEntry> {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Keeps `entry` unless the log already has a newer one for `object_ref`.
+    pub fn insert(&mut self, object_ref: ObjectRef, entry: SelectionEntry) {
+        let current = self.entries.entry(object_ref).or_insert(entry);
+        if entry > *current {
+            *current = entry;
+        }
+    }
+
+    pub fn selected(&self) -> BTreeSet<ObjectRef> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| entry.selected)
+            .map(|(object_ref, _)| object_ref.clone())
+            .collect()
+    }
+
+    /// Stamps every ref whose membership in `selected` differs from the log; returns whether any
+    /// did. Stamps are at least `now` and newer than anything already in the log.
+    pub fn record(&mut self, selected: &BTreeSet<ObjectRef>, now: u64) -> bool {
+        let stamp = self.entries.values().map(|entry| entry.stamp + 1).max().unwrap_or(0).max(now);
+        let mut changed = false;
+        for object_ref in selected {
+            if !self.entries.get(object_ref).is_some_and(|entry| entry.selected) {
+                self.entries.insert(object_ref.clone(), SelectionEntry { stamp, selected: true });
+                changed = true;
+            }
+        }
+        for (object_ref, entry) in &mut self.entries {
+            if entry.selected && !selected.contains(object_ref) {
+                *entry = SelectionEntry { stamp, selected: false };
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Keeps the newer entry per ref (a selection wins a tie) and drops the oldest tombstones.
+    pub fn merge(&mut self, other: &SelectionLog) {
+        for (object_ref, entry) in &other.entries {
+            self.insert(object_ref.clone(), *entry);
+        }
+
+        let mut tombstones = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| !entry.selected)
+            .map(|(object_ref, entry)| (entry.stamp, object_ref.clone()))
+            .collect::<Vec<_>>();
+        if tombstones.len() > MAX_TOMBSTONES {
+            tombstones.sort();
+            for (_, object_ref) in &tombstones[..tombstones.len() - MAX_TOMBSTONES] {
+                self.entries.remove(object_ref);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn oref(value: &str) -> ObjectRef {
+        ObjectRef::parse(value).expect("object ref")
+    }
+
+    #[test]
+    fn merge_keeps_concurrent_toggles_from_both_sides() {
+        let a = oref("d:d-flow/flow/node/n:a");
+        let b = oref("d:d-flow/flow/node/n:b");
+        let c = oref("d:d-flow/flow/node/n:c");
+        let base = SelectionLog::from_selected([a.clone(), b.clone()]);
+
+        // The agent deselects `a` and adds `c`; the human, unaware, deselects `b`.
+        let mut agent = base.clone();
+        agent.record(&BTreeSet::from([b.clone(), c.clone()]), 10);
+        let mut human = base;
+        human.record(&BTreeSet::from([a.clone()]), 20);
+
+        human.merge(&agent);
+        assert_eq!(human.selected(), BTreeSet::from([c.clone()]));
+        agent.merge(&human);
+        assert_eq!(agent, human);
+
+        // Re-selecting a tombstoned ref is newer than its tombstone.
+        human.record(&BTreeSet::from([a.clone(), c]), 5);
+        assert!(human.entries()[&a].selected);
+        assert!(human.entries()[&a].stamp > 20);
+        assert!(!human.record(&human.selected(), 30));
+    }
+}
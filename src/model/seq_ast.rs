@@ -7,8 +7,9 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use std::cmp::Ordering;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
+use super::annotations::ObjectAnnotations;
 use super::ids::ObjectId;
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
@@ -17,6 +18,9 @@ pub struct SequenceAst {
     messages: Vec<SequenceMessage>,
     notes: Vec<SequenceNote>,
     blocks: Vec<SequenceBlock>,
+    groups: Vec<SequenceGroup>,
+    activations: Vec<SequenceActivation>,
+    participant_order: Vec<ObjectId>,
 }
 
 impl SequenceAst {
@@ -28,6 +32,56 @@ impl SequenceAst {
         &mut self.participants
     }
 
+    /// Explicit left-to-right participant order; participants missing from it follow in
+    /// `ObjectId` order.
+    pub fn participant_order(&self) -> &[ObjectId] {
+        &self.participant_order
+    }
+
+    pub fn participant_order_mut(&mut self) -> &mut Vec<ObjectId> {
+        &mut self.participant_order
+    }
+
+    /// Participant ids from left to right: by [`Self::participant_order`], with each `box` group
+    /// pulled together at the position of its first member.
+    pub fn participants_in_order(&self) -> Vec<&ObjectId> {
+        let rank = |participant_id: &ObjectId| {
+            self.participant_order.iter().position(|id| id == participant_id).unwrap_or(usize::MAX)
+        };
+        let mut ids = self.participants.keys().collect::<Vec<_>>();
+        ids.sort_by_key(|participant_id| rank(participant_id));
+
+        let mut ordered = Vec::with_capacity(ids.len());
+        let mut placed = BTreeSet::new();
+        for participant_id in ids {
+            if placed.contains(participant_id) {
+                continue;
+            }
+            let Some(group) = self.participant_group(participant_id) else {
+                placed.insert(participant_id);
+                ordered.push(participant_id);
+                continue;
+            };
+            let mut members = group
+                .participant_ids()
+                .iter()
+                .filter(|member_id| {
+                    self.participants.contains_key(*member_id)
+                        && !placed.contains(member_id)
+                        && self
+                            .participant_group(member_id)
+                            .is_some_and(|owner| owner.group_id() == group.group_id())
+                })
+                .collect::<Vec<_>>();
+            members.sort_by_key(|member_id| rank(member_id));
+            for member_id in members {
+                placed.insert(member_id);
+                ordered.push(member_id);
+            }
+        }
+        ordered
+    }
+
     pub fn messages(&self) -> &[SequenceMessage] {
         &self.messages
     }
@@ -51,6 +105,13 @@ impl SequenceAst {
         &mut self.notes
     }
 
+    /// Returns note references in deterministic `(order_key, note_id)` order.
+    pub fn notes_in_order(&self) -> Vec<&SequenceNote> {
+        let mut notes = self.notes.iter().collect::<Vec<_>>();
+        notes.sort_by(|a, b| SequenceNote::cmp_in_order(a, b));
+        notes
+    }
+
     pub fn blocks(&self) -> &[SequenceBlock] {
         &self.blocks
     }
@@ -59,6 +120,36 @@ impl SequenceAst {
         &mut self.blocks
     }
 
+    pub fn groups(&self) -> &[SequenceGroup] {
+        &self.groups
+    }
+
+    pub fn groups_mut(&mut self) -> &mut Vec<SequenceGroup> {
+        &mut self.groups
+    }
+
+    /// Lifeline activation bars in the order they were opened.
+    pub fn activations(&self) -> &[SequenceActivation] {
+        &self.activations
+    }
+
+    pub fn activations_mut(&mut self) -> &mut Vec<SequenceActivation> {
+        &mut self.activations
+    }
+
+    pub fn find_activation(&self, activation_id: &ObjectId) -> Option<&SequenceActivation> {
+        self.activations.iter().find(|activation| activation.activation_id() == activation_id)
+    }
+
+    pub fn find_group(&self, group_id: &ObjectId) -> Option<&SequenceGroup> {
+        self.groups.iter().find(|group| group.group_id() == group_id)
+    }
+
+    /// Returns the group a participant belongs to (the first one if several claim it).
+    pub fn participant_group(&self, participant_id: &ObjectId) -> Option<&SequenceGroup> {
+        self.groups.iter().find(|group| group.participant_ids().contains(participant_id))
+    }
+
     pub fn find_block(&self, block_id: &ObjectId) -> Option<&SequenceBlock> {
         fn find<'a>(blocks: &'a [SequenceBlock], block_id: &ObjectId) -> Option<&'a SequenceBlock> {
             for block in blocks {
@@ -102,11 +193,17 @@ pub struct SequenceParticipant {
     mermaid_name: String,
     role: Option<String>,
     note: Option<String>,
+    annotations: ObjectAnnotations,
 }
 
 impl SequenceParticipant {
     pub fn new(mermaid_name: impl Into<String>) -> Self {
-        Self { mermaid_name: mermaid_name.into(), role: None, note: None }
+        Self {
+            mermaid_name: mermaid_name.into(),
+            role: None,
+            note: None,
+            annotations: ObjectAnnotations::default(),
+        }
     }
 
     pub fn set_mermaid_name(&mut self, mermaid_name: impl Into<String>) {
@@ -132,6 +229,118 @@ impl SequenceParticipant {
     pub fn note(&self) -> Option<&str> {
         self.note.as_deref()
     }
+
+    pub fn annotations(&self) -> &ObjectAnnotations {
+        &self.annotations
+    }
+
+    pub fn set_annotations(&mut self, annotations: ObjectAnnotations) {
+        self.annotations = annotations;
+    }
+}
+
+/// A titled Mermaid `box` around adjacent participants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceGroup {
+    group_id: ObjectId,
+    title: Option<String>,
+    color: Option<String>,
+    participant_ids: Vec<ObjectId>,
+}
+
+impl SequenceGroup {
+    pub fn make_group_id(group_index: usize) -> ObjectId {
+        ObjectId::new(format!("g:{group_index:04}")).expect("valid group id")
+    }
+
+    pub fn new(
+        group_id: ObjectId,
+        title: Option<String>,
+        color: Option<String>,
+        participant_ids: Vec<ObjectId>,
+    ) -> Self {
+        Self { group_id, title, color, participant_ids }
+    }
+
+    pub fn group_id(&self) -> &ObjectId {
+        &self.group_id
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn set_title<T: Into<String>>(&mut self, title: Option<T>) {
+        self.title = title.map(Into::into);
+    }
+
+    /// Mermaid box color (`transparent`, `#hex`, `rgb(...)`, ...); kept for export only.
+    pub fn color(&self) -> Option<&str> {
+        self.color.as_deref()
+    }
+
+    pub fn participant_ids(&self) -> &[ObjectId] {
+        &self.participant_ids
+    }
+
+    pub fn participant_ids_mut(&mut self) -> &mut Vec<ObjectId> {
+        &mut self.participant_ids
+    }
+}
+
+/// A participant's activation bar (`activate`/`deactivate` or a `+`/`-` arrow suffix).
+///
+/// The bar starts at its start message (the top of the lifeline when `None`) and ends at its end
+/// message (the bottom of the lifeline when `None`, i.e. never deactivated).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceActivation {
+    activation_id: ObjectId,
+    participant_id: ObjectId,
+    start_message_id: Option<ObjectId>,
+    end_message_id: Option<ObjectId>,
+}
+
+impl SequenceActivation {
+    pub fn make_activation_id(activation_index: usize) -> ObjectId {
+        ObjectId::new(format!("act:{activation_index:04}")).expect("valid activation id")
+    }
+
+    pub fn new(
+        activation_id: ObjectId,
+        participant_id: ObjectId,
+        start_message_id: Option<ObjectId>,
+        end_message_id: Option<ObjectId>,
+    ) -> Self {
+        Self { activation_id, participant_id, start_message_id, end_message_id }
+    }
+
+    pub fn activation_id(&self) -> &ObjectId {
+        &self.activation_id
+    }
+
+    pub fn participant_id(&self) -> &ObjectId {
+        &self.participant_id
+    }
+
+    pub fn set_participant_id(&mut self, participant_id: ObjectId) {
+        self.participant_id = participant_id;
+    }
+
+    pub fn start_message_id(&self) -> Option<&ObjectId> {
+        self.start_message_id.as_ref()
+    }
+
+    pub fn set_start_message_id(&mut self, start_message_id: Option<ObjectId>) {
+        self.start_message_id = start_message_id;
+    }
+
+    pub fn end_message_id(&self) -> Option<&ObjectId> {
+        self.end_message_id.as_ref()
+    }
+
+    pub fn set_end_message_id(&mut self, end_message_id: Option<ObjectId>) {
+        self.end_message_id = end_message_id;
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -252,15 +461,31 @@ pub enum SequenceMessageKind {
     Return,
 }
 
+/// Marks a message whose other end lies outside the diagram.
+///
+/// A boundary message touches one lifeline only: [`SequenceMessage::sender`] or
+/// [`SequenceMessage::receiver`] is `None` for the missing end, and both participant ids name the
+/// lifeline it touches so lookups by participant stay valid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SequenceMessageBoundary {
+    /// Arrives from outside the diagram; only the receiver is known.
+    Found,
+    /// Leaves the diagram; only the sender is known.
+    Lost,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SequenceMessage {
     message_id: ObjectId,
     from_participant_id: ObjectId,
     to_participant_id: ObjectId,
     kind: SequenceMessageKind,
+    boundary: Option<SequenceMessageBoundary>,
     raw_arrow: Option<String>,
     text: String,
     order_key: i64,
+    duration: Option<u64>,
+    annotations: ObjectAnnotations,
 }
 
 impl SequenceMessage {
@@ -277,12 +502,45 @@ impl SequenceMessage {
             from_participant_id,
             to_participant_id,
             kind,
+            boundary: None,
             raw_arrow: None,
             text: text.into(),
             order_key,
+            duration: None,
+            annotations: ObjectAnnotations::default(),
         }
     }
 
+    /// A found or lost message touching only `participant_id`.
+    pub fn new_boundary(
+        message_id: ObjectId,
+        participant_id: ObjectId,
+        boundary: SequenceMessageBoundary,
+        kind: SequenceMessageKind,
+        text: impl Into<String>,
+        order_key: i64,
+    ) -> Self {
+        let mut message =
+            Self::new(message_id, participant_id.clone(), participant_id, kind, text, order_key);
+        message.boundary = Some(boundary);
+        message
+    }
+
+    /// Makes the message found (keeping its receiver) or lost (keeping its sender); `None` turns
+    /// a boundary message into a self-message on the lifeline it touched.
+    pub fn set_boundary(&mut self, boundary: Option<SequenceMessageBoundary>) {
+        match boundary {
+            Some(SequenceMessageBoundary::Found) => {
+                self.from_participant_id = self.to_participant_id.clone();
+            }
+            Some(SequenceMessageBoundary::Lost) => {
+                self.to_participant_id = self.from_participant_id.clone();
+            }
+            None => {}
+        }
+        self.boundary = boundary;
+    }
+
     pub fn set_raw_arrow<T: Into<String>>(&mut self, raw_arrow: Option<T>) {
         self.raw_arrow = raw_arrow.map(Into::into);
     }
@@ -303,6 +561,30 @@ impl SequenceMessage {
         self.kind
     }
 
+    pub fn boundary(&self) -> Option<SequenceMessageBoundary> {
+        self.boundary
+    }
+
+    /// The sending participant; `None` for found messages.
+    pub fn sender(&self) -> Option<&ObjectId> {
+        (self.boundary != Some(SequenceMessageBoundary::Found)).then_some(&self.from_participant_id)
+    }
+
+    /// The receiving participant; `None` for lost messages.
+    pub fn receiver(&self) -> Option<&ObjectId> {
+        (self.boundary != Some(SequenceMessageBoundary::Lost)).then_some(&self.to_participant_id)
+    }
+
+    /// Sender and receiver, for messages between two known lifelines (self-messages included).
+    pub fn endpoints(&self) -> Option<(&ObjectId, &ObjectId)> {
+        self.sender().zip(self.receiver())
+    }
+
+    /// Returns `true` for messages that start and end on the same participant's lifeline.
+    pub fn is_self_message(&self) -> bool {
+        self.boundary.is_none() && self.from_participant_id == self.to_participant_id
+    }
+
     pub fn raw_arrow(&self) -> Option<&str> {
         self.raw_arrow.as_deref()
     }
@@ -315,34 +597,117 @@ impl SequenceMessage {
         self.order_key
     }
 
+    /// Optional duration metadata used by critical path analysis (unitless, e.g. milliseconds).
+    pub fn duration(&self) -> Option<u64> {
+        self.duration
+    }
+
+    pub fn set_duration(&mut self, duration: Option<u64>) {
+        self.duration = duration;
+    }
+
+    pub fn annotations(&self) -> &ObjectAnnotations {
+        &self.annotations
+    }
+
+    pub fn set_annotations(&mut self, annotations: ObjectAnnotations) {
+        self.annotations = annotations;
+    }
+
     pub fn cmp_in_order(a: &Self, b: &Self) -> Ordering {
         a.order_key.cmp(&b.order_key).then_with(|| a.message_id.cmp(&b.message_id))
     }
 }
 
+/// Where a timeline note sits relative to its participant lifeline(s).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceNotePlacement {
+    LeftOf,
+    RightOf,
+    Over,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SequenceNote {
     note_id: ObjectId,
+    placement: SequenceNotePlacement,
+    participant_ids: Vec<ObjectId>,
     text: String,
+    order_key: i64,
+    section_id: Option<ObjectId>,
 }
 
 impl SequenceNote {
-    pub fn new(note_id: ObjectId, text: impl Into<String>) -> Self {
-        Self { note_id, text: text.into() }
+    /// Creates a note anchored on `participant_ids`.
+    ///
+    /// `LeftOf`/`RightOf` use the first participant only; `Over` spans from the first to the last
+    /// participant (Mermaid allows at most two).
+    pub fn new(
+        note_id: ObjectId,
+        placement: SequenceNotePlacement,
+        participant_ids: Vec<ObjectId>,
+        text: impl Into<String>,
+        order_key: i64,
+    ) -> Self {
+        Self { note_id, placement, participant_ids, text: text.into(), order_key, section_id: None }
     }
 
     pub fn note_id(&self) -> &ObjectId {
         &self.note_id
     }
 
+    pub fn placement(&self) -> SequenceNotePlacement {
+        self.placement
+    }
+
+    pub fn set_placement(&mut self, placement: SequenceNotePlacement) {
+        self.placement = placement;
+    }
+
+    pub fn participant_ids(&self) -> &[ObjectId] {
+        &self.participant_ids
+    }
+
+    pub fn set_participant_ids(&mut self, participant_ids: Vec<ObjectId>) {
+        self.participant_ids = participant_ids;
+    }
+
     pub fn text(&self) -> &str {
         &self.text
     }
+
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+    }
+
+    /// Position on the shared message timeline; a note sorts after messages with an equal key.
+    pub fn order_key(&self) -> i64 {
+        self.order_key
+    }
+
+    pub fn set_order_key(&mut self, order_key: i64) {
+        self.order_key = order_key;
+    }
+
+    /// Innermost block section the note was written in (`None` at the top level). The order key
+    /// alone cannot tell a note after `end`/before `else` from one just inside, so export uses
+    /// this to keep the note on the right side of block boundaries.
+    pub fn section_id(&self) -> Option<&ObjectId> {
+        self.section_id.as_ref()
+    }
+
+    pub fn set_section_id(&mut self, section_id: Option<ObjectId>) {
+        self.section_id = section_id;
+    }
+
+    pub fn cmp_in_order(a: &Self, b: &Self) -> Ordering {
+        a.order_key.cmp(&b.order_key).then_with(|| a.note_id.cmp(&b.note_id))
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{SequenceBlock, SequenceParticipant, SequenceSection};
+    use super::{SequenceActivation, SequenceBlock, SequenceParticipant, SequenceSection};
 
     #[test]
     fn sequence_participant_can_be_updated_in_place() {
@@ -372,5 +737,6 @@ mod tests {
     fn sequence_block_and_section_ids_are_allocated_deterministically() {
         assert_eq!(SequenceBlock::make_block_id(1).as_str(), "b:0001");
         assert_eq!(SequenceSection::make_section_id(1, 0).as_str(), "sec:0001:00");
+        assert_eq!(SequenceActivation::make_activation_id(2).as_str(), "act:0002");
     }
 }
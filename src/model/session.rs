@@ -8,9 +8,12 @@
 
 use std::collections::{BTreeMap, BTreeSet};
 
-use super::diagram::{Diagram, DiagramAst};
+use super::diagram::Diagram;
+use super::id_prefix_rule::IdPrefixRule;
 use super::ids::{DiagramId, SessionId, WalkthroughId, XRefId};
 use super::object_ref::ObjectRef;
+use super::selection::SelectionLog;
+use super::style_rule::StyleRule;
 use super::walkthrough::Walkthrough;
 use super::xref::XRef;
 
@@ -18,24 +21,40 @@ use super::xref::XRef;
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Session {
     session_id: SessionId,
+    title: Option<String>,
+    description: Option<String>,
+    tags: Vec<String>,
+    style_rules: Vec<StyleRule>,
+    id_prefix_rules: Vec<IdPrefixRule>,
     diagrams: BTreeMap<DiagramId, Diagram>,
+    diagram_order: Vec<DiagramId>,
     walkthroughs: BTreeMap<WalkthroughId, Walkthrough>,
     xrefs: BTreeMap<XRefId, XRef>,
+    xref_id_counter: u64,
     active_diagram_id: Option<DiagramId>,
     active_walkthrough_id: Option<WalkthroughId>,
     selected_object_refs: BTreeSet<ObjectRef>,
+    selection_log: SelectionLog,
 }
 
 impl Session {
     pub fn new(session_id: SessionId) -> Self {
         Self {
             session_id,
+            title: None,
+            description: None,
+            tags: Vec::new(),
+            style_rules: Vec::new(),
+            id_prefix_rules: Vec::new(),
             diagrams: BTreeMap::new(),
+            diagram_order: Vec::new(),
             walkthroughs: BTreeMap::new(),
             xrefs: BTreeMap::new(),
+            xref_id_counter: 0,
             active_diagram_id: None,
             active_walkthrough_id: None,
             selected_object_refs: BTreeSet::new(),
+            selection_log: SelectionLog::default(),
         }
     }
 
@@ -43,6 +62,69 @@ impl Session {
         &self.session_id
     }
 
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// Sets the human-readable session title; blank titles are stored as `None`.
+    pub fn set_title<T: Into<String>>(&mut self, title: Option<T>) {
+        self.title = non_blank(title);
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Sets the session description; blank descriptions are stored as `None`.
+    pub fn set_description<T: Into<String>>(&mut self, description: Option<T>) {
+        self.description = non_blank(description);
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Replaces the session tags, trimming them and dropping blanks and duplicates.
+    pub fn set_tags<T: Into<String>>(&mut self, tags: impl IntoIterator<Item = T>) {
+        self.tags.clear();
+        for tag in tags {
+            let tag = tag.into().trim().to_owned();
+            if !tag.is_empty() && !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
+        }
+    }
+
+    /// Conditional styling rules, applied in order when diagrams are drawn or exported.
+    pub fn style_rules(&self) -> &[StyleRule] {
+        &self.style_rules
+    }
+
+    /// Replaces the styling rules, dropping duplicates.
+    pub fn set_style_rules(&mut self, style_rules: impl IntoIterator<Item = StyleRule>) {
+        self.style_rules.clear();
+        for rule in style_rules {
+            if !self.style_rules.contains(&rule) {
+                self.style_rules.push(rule);
+            }
+        }
+    }
+
+    /// Id prefix conventions, used when importing Mermaid and by the id lint.
+    pub fn id_prefix_rules(&self) -> &[IdPrefixRule] {
+        &self.id_prefix_rules
+    }
+
+    /// Replaces the id prefix conventions, dropping duplicates.
+    pub fn set_id_prefix_rules(&mut self, id_prefix_rules: impl IntoIterator<Item = IdPrefixRule>) {
+        self.id_prefix_rules.clear();
+        for rule in id_prefix_rules {
+            if !self.id_prefix_rules.contains(&rule) {
+                self.id_prefix_rules.push(rule);
+            }
+        }
+    }
+
     pub fn diagrams(&self) -> &BTreeMap<DiagramId, Diagram> {
         &self.diagrams
     }
@@ -51,6 +133,62 @@ impl Session {
         &mut self.diagrams
     }
 
+    /// The curated diagram order as stored; may name missing diagrams and omit existing ones.
+    pub fn diagram_order(&self) -> &[DiagramId] {
+        &self.diagram_order
+    }
+
+    /// Replaces the curated diagram order, dropping duplicate ids.
+    pub fn set_diagram_order(&mut self, diagram_order: impl IntoIterator<Item = DiagramId>) {
+        self.diagram_order.clear();
+        for diagram_id in diagram_order {
+            if !self.diagram_order.contains(&diagram_id) {
+                self.diagram_order.push(diagram_id);
+            }
+        }
+    }
+
+    /// Diagram ids in display order: the curated order first, then unlisted diagrams by id.
+    pub fn ordered_diagram_ids(&self) -> Vec<&DiagramId> {
+        let mut ids = self
+            .diagram_order
+            .iter()
+            .filter(|diagram_id| self.diagrams.contains_key(*diagram_id))
+            .collect::<Vec<_>>();
+        for diagram_id in self.diagrams.keys() {
+            if !ids.contains(&diagram_id) {
+                ids.push(diagram_id);
+            }
+        }
+        ids
+    }
+
+    /// [`Session::ordered_diagram_ids`] without archived diagrams.
+    pub fn listed_diagram_ids(&self) -> Vec<&DiagramId> {
+        self.ordered_diagram_ids()
+            .into_iter()
+            .filter(|diagram_id| !self.diagrams[*diagram_id].is_archived())
+            .collect()
+    }
+
+    /// Moves `diagram_id` by `offset` places in the display order, clamped to its ends.
+    ///
+    /// Returns `false` when the diagram does not exist or is already at that end.
+    pub fn move_diagram(&mut self, diagram_id: &DiagramId, offset: isize) -> bool {
+        let mut ids = self.ordered_diagram_ids().into_iter().cloned().collect::<Vec<_>>();
+        let Some(from) = ids.iter().position(|id| id == diagram_id) else {
+            return false;
+        };
+        let to = from.saturating_add_signed(offset).min(ids.len() - 1);
+        if to == from {
+            return false;
+        }
+        let id = ids.remove(from);
+        ids.insert(to, id);
+        self.diagram_order = ids;
+        true
+    }
+
     pub fn walkthroughs(&self) -> &BTreeMap<WalkthroughId, Walkthrough> {
         &self.walkthroughs
     }
@@ -67,37 +205,21 @@ impl Session {
         &mut self.xrefs
     }
 
+    /// The highest `x:N` number minted so far; persisted so deleted xref ids are not reused.
+    pub fn xref_id_counter(&self) -> u64 {
+        self.xref_id_counter
+    }
+
+    pub fn set_xref_id_counter(&mut self, xref_id_counter: u64) {
+        self.xref_id_counter = xref_id_counter;
+    }
+
     pub fn object_ref_exists(&self, object_ref: &ObjectRef) -> bool {
         let Some(diagram) = self.diagrams.get(object_ref.diagram_id()) else {
             return false;
         };
 
-        let segments = object_ref.category().segments();
-        let object_id = object_ref.object_id();
-
-        match (diagram.ast(), segments) {
-            (DiagramAst::Sequence(ast), [left, right])
-                if left == "seq" && right == "participant" =>
-            {
-                ast.participants().contains_key(object_id)
-            }
-            (DiagramAst::Sequence(ast), [left, right]) if left == "seq" && right == "message" => {
-                ast.messages().iter().any(|m| m.message_id() == object_id)
-            }
-            (DiagramAst::Sequence(ast), [left, right]) if left == "seq" && right == "block" => {
-                ast.find_block(object_id).is_some()
-            }
-            (DiagramAst::Sequence(ast), [left, right]) if left == "seq" && right == "section" => {
-                ast.find_section(object_id).is_some()
-            }
-            (DiagramAst::Flowchart(ast), [left, right]) if left == "flow" && right == "node" => {
-                ast.nodes().contains_key(object_id)
-            }
-            (DiagramAst::Flowchart(ast), [left, right]) if left == "flow" && right == "edge" => {
-                ast.edges().contains_key(object_id)
-            }
-            _ => false,
-        }
+        diagram.contains_object(object_ref.category(), object_ref.object_id())
     }
 
     pub fn object_ref_is_missing(&self, object_ref: &ObjectRef) -> bool {
@@ -131,4 +253,28 @@ impl Session {
     pub fn set_selected_object_refs(&mut self, selected_object_refs: BTreeSet<ObjectRef>) {
         self.selected_object_refs = selected_object_refs;
     }
+
+    /// When each ref was last selected or deselected, as of the last load or merge.
+    pub fn selection_log(&self) -> &SelectionLog {
+        &self.selection_log
+    }
+
+    /// Adopts `selection_log` along with the selection it describes.
+    pub fn set_selection_log(&mut self, selection_log: SelectionLog) {
+        self.selected_object_refs = selection_log.selected();
+        self.selection_log = selection_log;
+    }
+
+    /// Stamps selection changes made since the last load or merge with `now`, merges `other`
+    /// (usually the log on disk) and adopts the merged selection.
+    pub fn merge_selection(&mut self, other: &SelectionLog, now: u64) {
+        let mut selection_log = self.selection_log.clone();
+        selection_log.record(&self.selected_object_refs, now);
+        selection_log.merge(other);
+        self.set_selection_log(selection_log);
+    }
+}
+
+fn non_blank<T: Into<String>>(value: Option<T>) -> Option<String> {
+    value.map(|value| value.into().trim().to_owned()).filter(|value| !value.is_empty())
 }
@@ -0,0 +1,232 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use std::collections::BTreeMap;
+
+use super::ids::ObjectId;
+
+/// A Mermaid `stateDiagram-v2`: states plus the transitions between them.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StateAst {
+    states: BTreeMap<ObjectId, StateNode>,
+    transitions: BTreeMap<ObjectId, StateTransition>,
+}
+
+impl StateAst {
+    /// Id of the `[*]` pseudo-state transitions start from.
+    pub fn start_state_id() -> ObjectId {
+        ObjectId::new("s:[start]").expect("valid state id")
+    }
+
+    /// Id of the `[*]` pseudo-state transitions end in.
+    pub fn end_state_id() -> ObjectId {
+        ObjectId::new("s:[end]").expect("valid state id")
+    }
+
+    pub fn states(&self) -> &BTreeMap<ObjectId, StateNode> {
+        &self.states
+    }
+
+    pub fn states_mut(&mut self) -> &mut BTreeMap<ObjectId, StateNode> {
+        &mut self.states
+    }
+
+    pub fn transitions(&self) -> &BTreeMap<ObjectId, StateTransition> {
+        &self.transitions
+    }
+
+    pub fn transitions_mut(&mut self) -> &mut BTreeMap<ObjectId, StateTransition> {
+        &mut self.transitions
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateNodeKind {
+    State,
+    /// The `[*]` a diagram starts from.
+    Start,
+    /// The `[*]` a diagram ends in.
+    End,
+    Choice,
+    Fork,
+    Join,
+}
+
+impl StateNodeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::State => "state",
+            Self::Start => "start",
+            Self::End => "end",
+            Self::Choice => "choice",
+            Self::Fork => "fork",
+            Self::Join => "join",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateNode {
+    mermaid_id: Option<String>,
+    label: String,
+    kind: StateNodeKind,
+    note: Option<String>,
+}
+
+impl StateNode {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self::new_with(label, StateNodeKind::State, None)
+    }
+
+    pub fn new_with(
+        label: impl Into<String>,
+        kind: StateNodeKind,
+        mermaid_id: Option<String>,
+    ) -> Self {
+        Self { mermaid_id, label: label.into(), kind, note: None }
+    }
+
+    pub fn set_mermaid_id<T: Into<String>>(&mut self, mermaid_id: Option<T>) {
+        self.mermaid_id = mermaid_id.map(Into::into);
+    }
+
+    pub fn set_label(&mut self, label: impl Into<String>) {
+        self.label = label.into();
+    }
+
+    pub fn set_kind(&mut self, kind: StateNodeKind) {
+        self.kind = kind;
+    }
+
+    pub fn set_note<T: Into<String>>(&mut self, note: Option<T>) {
+        self.note = note.map(Into::into);
+    }
+
+    pub fn mermaid_id(&self) -> Option<&str> {
+        self.mermaid_id.as_deref()
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn kind(&self) -> StateNodeKind {
+        self.kind
+    }
+
+    pub fn note(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateTransition {
+    from_state_id: ObjectId,
+    to_state_id: ObjectId,
+    label: Option<String>,
+}
+
+impl StateTransition {
+    pub fn new(from_state_id: ObjectId, to_state_id: ObjectId) -> Self {
+        Self { from_state_id, to_state_id, label: None }
+    }
+
+    pub fn set_label<T: Into<String>>(&mut self, label: Option<T>) {
+        self.label = label.map(Into::into);
+    }
+
+    pub fn from_state_id(&self) -> &ObjectId {
+        &self.from_state_id
+    }
+
+    pub fn to_state_id(&self) -> &ObjectId {
+        &self.to_state_id
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StateAst, StateNode, StateNodeKind, StateTransition};
+    use crate::model::ObjectId;
+
+    #[test]
+    fn pseudo_state_ids_are_fixed_and_distinct() {
+        assert_eq!(StateAst::start_state_id().as_str(), "s:[start]");
+        assert_eq!(StateAst::end_state_id().as_str(), "s:[end]");
+        assert_ne!(StateAst::start_state_id(), StateAst::end_state_id());
+    }
+
+    #[test]
+    fn state_node_can_be_constructed_and_updated() {
+        let mut node = StateNode::new("Idle");
+        assert_eq!(node.mermaid_id(), None);
+        assert_eq!(node.label(), "Idle");
+        assert_eq!(node.kind(), StateNodeKind::State);
+        assert_eq!(node.note(), None);
+
+        node.set_mermaid_id(Some("idle"));
+        node.set_label("Waiting");
+        node.set_kind(StateNodeKind::Choice);
+        node.set_note(Some("polls every second"));
+
+        assert_eq!(node.mermaid_id(), Some("idle"));
+        assert_eq!(node.label(), "Waiting");
+        assert_eq!(node.kind(), StateNodeKind::Choice);
+        assert_eq!(node.note(), Some("polls every second"));
+
+        node.set_mermaid_id::<&str>(None);
+        node.set_note::<&str>(None);
+        assert_eq!(node.mermaid_id(), None);
+        assert_eq!(node.note(), None);
+
+        let fork = StateNode::new_with("split", StateNodeKind::Fork, Some("split".to_owned()));
+        assert_eq!(fork.kind().as_str(), "fork");
+        assert_eq!(fork.mermaid_id(), Some("split"));
+    }
+
+    #[test]
+    fn state_node_kinds_have_stable_names() {
+        let names = [
+            StateNodeKind::State,
+            StateNodeKind::Start,
+            StateNodeKind::End,
+            StateNodeKind::Choice,
+            StateNodeKind::Fork,
+            StateNodeKind::Join,
+        ]
+        .map(StateNodeKind::as_str);
+        assert_eq!(names, ["state", "start", "end", "choice", "fork", "join"]);
+    }
+
+    #[test]
+    fn state_ast_tracks_states_and_transitions() {
+        let idle = ObjectId::new("s:Idle").expect("state id");
+        let mut ast = StateAst::default();
+        ast.states_mut().insert(
+            StateAst::start_state_id(),
+            StateNode::new_with("", StateNodeKind::Start, None),
+        );
+        ast.states_mut().insert(idle.clone(), StateNode::new("Idle"));
+
+        let mut transition = StateTransition::new(StateAst::start_state_id(), idle.clone());
+        assert_eq!(transition.label(), None);
+        transition.set_label(Some("boot"));
+        ast.transitions_mut().insert(ObjectId::new("t:0000").expect("transition id"), transition);
+
+        let transition = ast.transitions().values().next().expect("transition");
+        assert_eq!(transition.from_state_id(), &StateAst::start_state_id());
+        assert_eq!(transition.to_state_id(), &idle);
+        assert_eq!(transition.label(), Some("boot"));
+        assert_eq!(ast.states()[&idle].label(), "Idle");
+        assert_eq!(ast.states().len(), 2);
+    }
+}
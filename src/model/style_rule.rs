@@ -0,0 +1,215 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Conditional styling rules such as `tag=deprecated => dim` or `degree>8 => heavy`.
+//!
+//! Rules live in the session and are evaluated against each object's derived tags and degree
+//! whenever a diagram is drawn, so the TUI and the exports agree on what stands out.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// `<condition> => <effect>`; every matching rule contributes its effect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyleRule {
+    condition: StyleCondition,
+    effect: StyleEffect,
+}
+
+impl StyleRule {
+    pub fn new(condition: StyleCondition, effect: StyleEffect) -> Self {
+        Self { condition, effect }
+    }
+
+    pub fn condition(&self) -> &StyleCondition {
+        &self.condition
+    }
+
+    pub fn effect(&self) -> StyleEffect {
+        self.effect
+    }
+
+    /// Whether an object with `tags` and `degree` (incoming plus outgoing links) matches.
+    pub fn matches(&self, tags: &[String], degree: u64) -> bool {
+        match &self.condition {
+            StyleCondition::Tag(tag) => tags.iter().any(|candidate| candidate == tag),
+            StyleCondition::Degree(cmp, value) => cmp.holds(degree, *value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StyleCondition {
+    /// `tag=<tag>`: the object carries this derived tag (e.g. `shape:diamond`, `xref`, or a
+    /// `#hashtag` from its note).
+    Tag(String),
+    /// `degree<op><n>`: incoming plus outgoing links compared with `n`.
+    Degree(Comparison, u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+impl Comparison {
+    fn holds(self, left: u64, right: u64) -> bool {
+        match self {
+            Self::Lt => left < right,
+            Self::Le => left <= right,
+            Self::Eq => left == right,
+            Self::Ge => left >= right,
+            Self::Gt => left > right,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Eq => "=",
+            Self::Ge => ">=",
+            Self::Gt => ">",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StyleEffect {
+    /// Faded ink, for objects that should recede.
+    Dim,
+    /// Heavy borders and strokes, for objects that should stand out.
+    Heavy,
+}
+
+impl StyleEffect {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Dim => "dim",
+            Self::Heavy => "heavy",
+        }
+    }
+}
+
+/// The combined effect of every rule matching one object.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ObjectStyle {
+    pub dim: bool,
+    pub heavy: bool,
+}
+
+impl ObjectStyle {
+    pub fn apply(&mut self, effect: StyleEffect) {
+        match effect {
+            StyleEffect::Dim => self.dim = true,
+            StyleEffect::Heavy => self.heavy = true,
+        }
+    }
+}
+
+impl fmt::Display for StyleCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tag(tag) => write!(f, "tag={tag}"),
+            Self::Degree(cmp, value) => write!(f, "degree{}{value}", cmp.as_str()),
+        }
+    }
+}
+
+impl fmt::Display for StyleRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} => {}", self.condition, self.effect.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseStyleRuleError {
+    input: String,
+}
+
+impl fmt::Display for ParseStyleRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid style rule {:?} (expected `tag=<tag> => dim|heavy` or \
+             `degree<op><n> => dim|heavy`)",
+            self.input
+        )
+    }
+}
+
+impl std::error::Error for ParseStyleRuleError {}
+
+impl FromStr for StyleRule {
+    type Err = ParseStyleRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseStyleRuleError { input: s.to_owned() };
+        let (condition, effect) = s.split_once("=>").ok_or_else(err)?;
+        let effect = match effect.trim() {
+            "dim" => StyleEffect::Dim,
+            "heavy" => StyleEffect::Heavy,
+            _ => return Err(err()),
+        };
+
+        let condition = condition.trim();
+        let tag =
+            condition.strip_prefix("tag").and_then(|rest| rest.trim_start().strip_prefix('='));
+        let condition = if let Some(tag) = tag {
+            match tag.trim() {
+                "" => return Err(err()),
+                tag => StyleCondition::Tag(tag.to_owned()),
+            }
+        } else if let Some(rest) = condition.strip_prefix("degree") {
+            let rest = rest.trim_start();
+            // Two-character operators first so `>=` is not read as `>` followed by `=8`.
+            let (cmp, value) = [
+                (Comparison::Le, "<="),
+                (Comparison::Ge, ">="),
+                (Comparison::Lt, "<"),
+                (Comparison::Gt, ">"),
+                (Comparison::Eq, "="),
+            ]
+            .into_iter()
+            .find_map(|(cmp, op)| rest.strip_prefix(op).map(|value| (cmp, value)))
+            .ok_or_else(err)?;
+            StyleCondition::Degree(cmp, value.trim().parse().map_err(|_| err())?)
+        } else {
+            return Err(err());
+        };
+        Ok(Self { condition, effect })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Comparison, StyleCondition, StyleEffect, StyleRule};
+
+    #[test]
+    fn style_rules_roundtrip_via_str_and_match_tags_or_degree() {
+        let dim = "tag=deprecated => dim".parse::<StyleRule>().expect("tag rule");
+        assert_eq!(dim.condition(), &StyleCondition::Tag("deprecated".to_owned()));
+        assert_eq!(dim.effect(), StyleEffect::Dim);
+        assert!(dim.matches(&["entry".to_owned(), "deprecated".to_owned()], 0));
+        assert!(!dim.matches(&["entry".to_owned()], 12));
+
+        let heavy = "degree >= 8=>heavy".parse::<StyleRule>().expect("degree rule");
+        assert_eq!(heavy.condition(), &StyleCondition::Degree(Comparison::Ge, 8));
+        assert_eq!(heavy.to_string(), "degree>=8 => heavy");
+        assert!(heavy.matches(&[], 8));
+        assert!(!heavy.matches(&[], 7));
+
+        for invalid in ["tag= => dim", "degree>x => dim", "label=a => dim", "tag=a => blink"] {
+            assert!(invalid.parse::<StyleRule>().is_err(), "{invalid}");
+        }
+    }
+}
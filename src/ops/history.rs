@@ -0,0 +1,724 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Session-wide undo/redo for diagram mutations.
+//!
+//! Every recorded change keeps the op batch that produced it and an inverse batch that restores
+//! the previous AST. Undo and redo replay those batches through [`apply_ops`], so they bump the
+//! diagram revision like any other mutation: revisions only move forward, and a client holding a
+//! pre-undo `rev` gets a conflict instead of silently writing over the restored state.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::model::{
+    Diagram, DiagramAst, DiagramId, FlowchartAst, ObjectAnnotations, SequenceAst, Session,
+};
+
+use super::{
+    apply_ops, ApplyError, ApplyResult, Delta, FlowEdgePatch, FlowNodePatch, FlowOp, Op,
+    SeqMessagePatch, SeqNotePatch, SeqOp, SeqParticipantPatch,
+};
+
+/// Number of undo steps kept by [`History::default`].
+pub const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+/// One undoable change of a single diagram.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    diagram_id: DiagramId,
+    ops: Vec<Op>,
+    inverse: Vec<Op>,
+    rev: u64,
+}
+
+impl HistoryEntry {
+    pub fn diagram_id(&self) -> &DiagramId {
+        &self.diagram_id
+    }
+
+    /// Ops that (re)apply the change.
+    pub fn ops(&self) -> &[Op] {
+        &self.ops
+    }
+
+    /// Ops that revert the change.
+    pub fn inverse(&self) -> &[Op] {
+        &self.inverse
+    }
+
+    /// Diagram revision right after the change was last applied.
+    pub fn rev(&self) -> u64 {
+        self.rev
+    }
+}
+
+/// Result of a successful [`History::undo`] or [`History::redo`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryStep {
+    pub diagram_id: DiagramId,
+    /// Diagram revision before the step was replayed.
+    pub base_rev: u64,
+    pub result: ApplyResult,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryError {
+    Empty,
+    MissingDiagram {
+        diagram_id: DiagramId,
+    },
+    /// The diagram changed outside the history since the entry was recorded; its entries were
+    /// dropped.
+    Stale {
+        diagram_id: DiagramId,
+        expected_rev: u64,
+        current_rev: u64,
+    },
+    Apply {
+        diagram_id: DiagramId,
+        error: ApplyError,
+    },
+    /// The diagram is read-only; the entry stays queued until it is unlocked.
+    ReadOnly {
+        diagram_id: DiagramId,
+    },
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "nothing to replay"),
+            Self::MissingDiagram { diagram_id } => write!(f, "diagram not found: {diagram_id}"),
+            Self::ReadOnly { diagram_id } => write!(f, "{diagram_id} is read-only"),
+            Self::Stale { diagram_id, expected_rev, current_rev } => write!(
+                f,
+                "{diagram_id} changed since (rev {current_rev} != {expected_rev}); history dropped"
+            ),
+            Self::Apply { diagram_id, error } => {
+                write!(f, "cannot replay on {diagram_id}: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for HistoryError {}
+
+/// Undo and redo stacks shared by all diagrams of a session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct History {
+    undo: Vec<HistoryEntry>,
+    redo: Vec<HistoryEntry>,
+    limit: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::with_limit(DEFAULT_HISTORY_LIMIT)
+    }
+}
+
+impl History {
+    pub fn with_limit(limit: usize) -> Self {
+        Self { undo: Vec::new(), redo: Vec::new(), limit: limit.max(1) }
+    }
+
+    /// The entry [`History::undo`] would revert next.
+    pub fn peek_undo(&self) -> Option<&HistoryEntry> {
+        self.undo.last()
+    }
+
+    /// The entry [`History::redo`] would reapply next.
+    pub fn peek_redo(&self) -> Option<&HistoryEntry> {
+        self.redo.last()
+    }
+
+    /// Applies `ops` like [`apply_ops`] and records the change.
+    ///
+    /// If the change cannot be reverted with ops (see [`diff_ops`]), the diagram's history is
+    /// dropped instead so an undo never restores a partial state.
+    pub fn apply(
+        &mut self,
+        diagram: &mut Diagram,
+        base_rev: u64,
+        ops: &[Op],
+    ) -> Result<ApplyResult, ApplyError> {
+        let before = diagram.ast().clone();
+        let result = apply_ops(diagram, base_rev, ops)?;
+        if result.applied > 0 {
+            match diff_ops(diagram.ast(), &before) {
+                Some(inverse) => self.push(HistoryEntry {
+                    diagram_id: diagram.diagram_id().clone(),
+                    ops: ops.to_vec(),
+                    inverse,
+                    rev: result.new_rev,
+                }),
+                None => self.forget_diagram(diagram.diagram_id()),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Records a change made without ops (e.g. by replacing the AST with edited Mermaid).
+    ///
+    /// Returns `false` and drops the diagram's history when the change is not expressible as ops
+    /// in both directions.
+    pub fn record_change(&mut self, before: &DiagramAst, after: &Diagram) -> bool {
+        let forward = diff_ops(before, after.ast());
+        let inverse = diff_ops(after.ast(), before);
+        let (Some(ops), Some(inverse)) = (forward, inverse) else {
+            self.forget_diagram(after.diagram_id());
+            return false;
+        };
+        if !ops.is_empty() {
+            self.push(HistoryEntry {
+                diagram_id: after.diagram_id().clone(),
+                ops,
+                inverse,
+                rev: after.rev(),
+            });
+        }
+        true
+    }
+
+    /// Reverts the most recent change, whichever diagram it belongs to.
+    pub fn undo(&mut self, session: &mut Session) -> Result<HistoryStep, HistoryError> {
+        ensure_writable(session, self.undo.last())?;
+        let entry = self.undo.pop().ok_or(HistoryError::Empty)?;
+        let step = self.replay(session, &entry, &entry.inverse)?;
+        self.redo.push(HistoryEntry { rev: step.result.new_rev, ..entry });
+        Ok(step)
+    }
+
+    /// Reapplies the most recently undone change.
+    pub fn redo(&mut self, session: &mut Session) -> Result<HistoryStep, HistoryError> {
+        ensure_writable(session, self.redo.last())?;
+        let entry = self.redo.pop().ok_or(HistoryError::Empty)?;
+        let step = self.replay(session, &entry, &entry.ops)?;
+        self.undo.push(HistoryEntry { rev: step.result.new_rev, ..entry });
+        Ok(step)
+    }
+
+    /// Drops every entry of `diagram_id`, e.g. after it was replaced or deleted.
+    pub fn forget_diagram(&mut self, diagram_id: &DiagramId) {
+        self.undo.retain(|entry| &entry.diagram_id != diagram_id);
+        self.redo.retain(|entry| &entry.diagram_id != diagram_id);
+    }
+
+    fn push(&mut self, entry: HistoryEntry) {
+        self.redo.clear();
+        self.undo.push(entry);
+        if self.undo.len() > self.limit {
+            self.undo.remove(0);
+        }
+    }
+
+    fn replay(
+        &mut self,
+        session: &mut Session,
+        entry: &HistoryEntry,
+        ops: &[Op],
+    ) -> Result<HistoryStep, HistoryError> {
+        let diagram_id = entry.diagram_id.clone();
+        let Some(diagram) = session.diagrams_mut().get_mut(&diagram_id) else {
+            self.forget_diagram(&diagram_id);
+            return Err(HistoryError::MissingDiagram { diagram_id });
+        };
+        let base_rev = diagram.rev();
+        if base_rev != entry.rev {
+            self.forget_diagram(&diagram_id);
+            return Err(HistoryError::Stale {
+                diagram_id,
+                expected_rev: entry.rev,
+                current_rev: base_rev,
+            });
+        }
+        match apply_ops(diagram, base_rev, ops) {
+            Ok(result) => Ok(HistoryStep { diagram_id, base_rev, result }),
+            Err(error) => {
+                self.forget_diagram(&diagram_id);
+                Err(HistoryError::Apply { diagram_id, error })
+            }
+        }
+    }
+}
+
+fn ensure_writable(session: &Session, entry: Option<&HistoryEntry>) -> Result<(), HistoryError> {
+    match entry.and_then(|entry| session.diagrams().get(&entry.diagram_id)) {
+        Some(diagram) if diagram.is_read_only() => {
+            Err(HistoryError::ReadOnly { diagram_id: diagram.diagram_id().clone() })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Returns ops that turn `from` into `to`, or `None` when that is not possible with ops alone
+/// (different diagram kinds, or changes to parts ops do not cover such as flowchart subgraphs,
+/// sequence blocks or the block section of a note). The result is verified by applying it to a
+/// copy of `from`.
+pub fn diff_ops(from: &DiagramAst, to: &DiagramAst) -> Option<Vec<Op>> {
+    let diagram_id = DiagramId::new("history").expect("valid diagram id");
+    diff(&diagram_id, from, to).map(|(ops, _)| ops)
+}
+
+/// The objects of `diagram_id` that changed from `from` to `to`, as [`apply_ops`] reports them
+/// for the ops from [`diff_ops`]; `None` under the same conditions.
+pub fn diff_delta(diagram_id: &DiagramId, from: &DiagramAst, to: &DiagramAst) -> Option<Delta> {
+    diff(diagram_id, from, to).map(|(_, delta)| delta)
+}
+
+fn diff(diagram_id: &DiagramId, from: &DiagramAst, to: &DiagramAst) -> Option<(Vec<Op>, Delta)> {
+    let ops = match (from, to) {
+        (DiagramAst::Flowchart(from), DiagramAst::Flowchart(to)) => diff_flowchart(from, to),
+        (DiagramAst::Sequence(from), DiagramAst::Sequence(to)) => diff_sequence(from, to),
+        _ => return None,
+    };
+
+    let mut scratch = Diagram::new(diagram_id.clone(), "", from.clone());
+    let base_rev = scratch.rev();
+    let result = apply_ops(&mut scratch, base_rev, &ops).ok()?;
+    (scratch.ast() == to).then_some((ops, result.delta))
+}
+
+fn diff_flowchart(from: &FlowchartAst, to: &FlowchartAst) -> Vec<Op> {
+    let mut ops = Vec::new();
+    let mut push = |op: FlowOp| ops.push(Op::Flow(op));
+
+    // Patches cannot clear optional edge fields, and an edge must not keep pointing at a node
+    // removed below, so such edges are removed and re-added instead.
+    let readded_edges = from
+        .edges()
+        .iter()
+        .filter_map(|(edge_id, old)| {
+            let new = to.edges().get(edge_id)?;
+            let cleared = old.from_node_id() != new.from_node_id()
+                || old.to_node_id() != new.to_node_id()
+                || (old.label().is_some() && new.label().is_none())
+                || (old.connector().is_some() && new.connector().is_none())
+                || (old.style().is_some() && new.style().is_none());
+            cleared.then(|| edge_id.clone())
+        })
+        .collect::<BTreeSet<_>>();
+
+    for edge_id in from.edges().keys() {
+        if !to.edges().contains_key(edge_id) || readded_edges.contains(edge_id) {
+            push(FlowOp::RemoveEdge { edge_id: edge_id.clone() });
+        }
+    }
+    for node_id in from.nodes().keys() {
+        if !to.nodes().contains_key(node_id) {
+            push(FlowOp::RemoveNode { node_id: node_id.clone() });
+        }
+    }
+
+    for (node_id, node) in to.nodes() {
+        let old = from.nodes().get(node_id);
+        match old {
+            None => push(FlowOp::AddNode {
+                node_id: node_id.clone(),
+                label: node.label().to_owned(),
+                shape: Some(node.shape().to_owned()),
+            }),
+            Some(old) if old.label() != node.label() || old.shape() != node.shape() => {
+                push(FlowOp::UpdateNode {
+                    node_id: node_id.clone(),
+                    patch: FlowNodePatch {
+                        label: (old.label() != node.label()).then(|| node.label().to_owned()),
+                        shape: (old.shape() != node.shape()).then(|| node.shape().to_owned()),
+                    },
+                });
+            }
+            Some(_) => {}
+        }
+        if old.and_then(|old| old.mermaid_id()) != node.mermaid_id() {
+            push(FlowOp::SetNodeMermaidId {
+                node_id: node_id.clone(),
+                mermaid_id: node.mermaid_id().map(ToOwned::to_owned),
+            });
+        }
+        if old.and_then(|old| old.note()) != node.note() {
+            push(FlowOp::SetNodeNote {
+                node_id: node_id.clone(),
+                note: node.note().map(ToOwned::to_owned),
+            });
+        }
+        if old.map_or(&ObjectAnnotations::default(), |old| old.annotations()) != node.annotations()
+        {
+            push(FlowOp::SetNodeAnnotations {
+                node_id: node_id.clone(),
+                annotations: node.annotations().clone(),
+            });
+        }
+    }
+
+    for (edge_id, edge) in to.edges() {
+        let old = from.edges().get(edge_id).filter(|_| !readded_edges.contains(edge_id));
+        match old {
+            None => push(FlowOp::AddEdge {
+                edge_id: edge_id.clone(),
+                from_node_id: edge.from_node_id().clone(),
+                to_node_id: edge.to_node_id().clone(),
+                label: edge.label().map(ToOwned::to_owned),
+                connector: edge.connector().map(ToOwned::to_owned),
+                style: edge.style().map(ToOwned::to_owned),
+            }),
+            Some(old) => {
+                let patch = FlowEdgePatch {
+                    label: changed(old.label(), edge.label()),
+                    connector: changed(old.connector(), edge.connector()),
+                    style: changed(old.style(), edge.style()),
+                    ..FlowEdgePatch::default()
+                };
+                if patch != FlowEdgePatch::default() {
+                    push(FlowOp::UpdateEdge { edge_id: edge_id.clone(), patch });
+                }
+            }
+        }
+        if old.and_then(|old| old.weight()) != edge.weight() {
+            push(FlowOp::SetEdgeWeight { edge_id: edge_id.clone(), weight: edge.weight() });
+        }
+        if old.map_or(&ObjectAnnotations::default(), |old| old.annotations()) != edge.annotations()
+        {
+            push(FlowOp::SetEdgeAnnotations {
+                edge_id: edge_id.clone(),
+                annotations: edge.annotations().clone(),
+            });
+        }
+    }
+
+    if from.node_order_hints() != to.node_order_hints() {
+        push(FlowOp::SetNodeOrderHints { order_hints: to.node_order_hints().clone() });
+    }
+    for (node_id, pin) in to.node_pins() {
+        if from.node_pins().get(node_id) != Some(pin) {
+            push(FlowOp::SetNodePin { node_id: node_id.clone(), pin: Some(*pin) });
+        }
+    }
+    for node_id in from.node_pins().keys() {
+        if !to.node_pins().contains_key(node_id) && to.nodes().contains_key(node_id) {
+            push(FlowOp::SetNodePin { node_id: node_id.clone(), pin: None });
+        }
+    }
+    for node_id in from.entry_nodes().symmetric_difference(to.entry_nodes()) {
+        if to.nodes().contains_key(node_id) {
+            let entry = to.entry_nodes().contains(node_id);
+            push(FlowOp::SetNodeEntry { node_id: node_id.clone(), entry });
+        }
+    }
+    ops
+}
+
+fn diff_sequence(from: &SequenceAst, to: &SequenceAst) -> Vec<Op> {
+    let mut ops = Vec::new();
+    let mut push = |op: SeqOp| ops.push(Op::Seq(op));
+
+    let find = |ast: &'_ SequenceAst, message_id| {
+        ast.messages().iter().find(|message| message.message_id() == message_id).cloned()
+    };
+    // Patches cannot clear a raw arrow, and a message must not keep pointing at a participant
+    // removed below, so such messages are removed and re-added instead.
+    let readded_messages = from
+        .messages()
+        .iter()
+        .filter(|old| {
+            find(to, old.message_id()).is_some_and(|new| {
+                old.from_participant_id() != new.from_participant_id()
+                    || old.to_participant_id() != new.to_participant_id()
+                    || (old.raw_arrow().is_some() && new.raw_arrow().is_none())
+            })
+        })
+        .map(|old| old.message_id().clone())
+        .collect::<BTreeSet<_>>();
+
+    for message in from.messages() {
+        let message_id = message.message_id();
+        if find(to, message_id).is_none() || readded_messages.contains(message_id) {
+            push(SeqOp::RemoveMessage { message_id: message_id.clone() });
+        }
+    }
+    // Notes whose participants change are re-added for the same reason as messages above.
+    let find_note = |ast: &'_ SequenceAst, note_id| {
+        ast.notes().iter().find(|note| note.note_id() == note_id).cloned()
+    };
+    for note in from.notes() {
+        let new = find_note(to, note.note_id());
+        if new.map_or(true, |new| new.participant_ids() != note.participant_ids()) {
+            push(SeqOp::RemoveNote { note_id: note.note_id().clone() });
+        }
+    }
+    for participant_id in from.participants().keys() {
+        if !to.participants().contains_key(participant_id) {
+            push(SeqOp::RemoveParticipant { participant_id: participant_id.clone() });
+        }
+    }
+
+    for (participant_id, participant) in to.participants() {
+        let old = from.participants().get(participant_id);
+        match old {
+            None => push(SeqOp::AddParticipant {
+                participant_id: participant_id.clone(),
+                mermaid_name: participant.mermaid_name().to_owned(),
+            }),
+            Some(old) if old.mermaid_name() != participant.mermaid_name() => {
+                push(SeqOp::UpdateParticipant {
+                    participant_id: participant_id.clone(),
+                    patch: SeqParticipantPatch {
+                        mermaid_name: Some(participant.mermaid_name().to_owned()),
+                    },
+                });
+            }
+            Some(_) => {}
+        }
+        if old.and_then(|old| old.note()) != participant.note() {
+            push(SeqOp::SetParticipantNote {
+                participant_id: participant_id.clone(),
+                note: participant.note().map(ToOwned::to_owned),
+            });
+        }
+        if old.map_or(&ObjectAnnotations::default(), |old| old.annotations())
+            != participant.annotations()
+        {
+            push(SeqOp::SetParticipantAnnotations {
+                participant_id: participant_id.clone(),
+                annotations: participant.annotations().clone(),
+            });
+        }
+    }
+
+    if from.participant_order() != to.participant_order() {
+        let order = to
+            .participant_order()
+            .iter()
+            .filter(|participant_id| to.participants().contains_key(*participant_id))
+            .cloned()
+            .collect();
+        push(SeqOp::SetParticipantOrder { order });
+    }
+
+    for message in to.messages() {
+        let message_id = message.message_id();
+        let old = find(from, message_id).filter(|_| !readded_messages.contains(message_id));
+        match &old {
+            None => push(SeqOp::AddMessage {
+                message_id: message_id.clone(),
+                from_participant_id: message.from_participant_id().clone(),
+                to_participant_id: message.to_participant_id().clone(),
+                kind: message.kind(),
+                arrow: message.raw_arrow().map(ToOwned::to_owned),
+                text: message.text().to_owned(),
+                order_key: message.order_key(),
+            }),
+            Some(old) => {
+                let patch = SeqMessagePatch {
+                    kind: (old.kind() != message.kind()).then(|| message.kind()),
+                    arrow: changed(old.raw_arrow(), message.raw_arrow()),
+                    text: (old.text() != message.text()).then(|| message.text().to_owned()),
+                    order_key: (old.order_key() != message.order_key())
+                        .then(|| message.order_key()),
+                    ..SeqMessagePatch::default()
+                };
+                if patch != SeqMessagePatch::default() {
+                    push(SeqOp::UpdateMessage { message_id: message_id.clone(), patch });
+                }
+            }
+        }
+        if old.as_ref().and_then(|old| old.duration()) != message.duration() {
+            push(SeqOp::SetMessageDuration {
+                message_id: message_id.clone(),
+                duration: message.duration(),
+            });
+        }
+        if old.as_ref().and_then(|old| old.boundary()) != message.boundary() {
+            push(SeqOp::SetMessageBoundary {
+                message_id: message_id.clone(),
+                boundary: message.boundary(),
+            });
+        }
+        if old.as_ref().map_or(&ObjectAnnotations::default(), |old| old.annotations())
+            != message.annotations()
+        {
+            push(SeqOp::SetMessageAnnotations {
+                message_id: message_id.clone(),
+                annotations: message.annotations().clone(),
+            });
+        }
+    }
+
+    for note in to.notes() {
+        let old = find_note(from, note.note_id())
+            .filter(|old| old.participant_ids() == note.participant_ids());
+        match old {
+            None => push(SeqOp::AddNote {
+                note_id: note.note_id().clone(),
+                placement: note.placement(),
+                participant_ids: note.participant_ids().to_vec(),
+                text: note.text().to_owned(),
+                order_key: note.order_key(),
+            }),
+            Some(old) => {
+                let patch = SeqNotePatch {
+                    placement: (old.placement() != note.placement()).then(|| note.placement()),
+                    text: (old.text() != note.text()).then(|| note.text().to_owned()),
+                    order_key: (old.order_key() != note.order_key()).then(|| note.order_key()),
+                    ..SeqNotePatch::default()
+                };
+                if patch != SeqNotePatch::default() {
+                    push(SeqOp::UpdateNote { note_id: note.note_id().clone(), patch });
+                }
+            }
+        }
+    }
+    ops
+}
+
+fn changed(old: Option<&str>, new: Option<&str>) -> Option<String> {
+    (old != new).then(|| new.map(ToOwned::to_owned)).flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_ops, ApplyError, History, HistoryError};
+    use crate::format::mermaid::{parse_flowchart, parse_sequence_diagram};
+    use crate::model::{
+        Diagram, DiagramAst, DiagramId, ObjectId, SequenceMessageBoundary, Session, SessionId,
+    };
+    use crate::ops::{FlowOp, Op, SeqOp};
+
+    fn flow_session() -> (Session, DiagramId) {
+        let ast = parse_flowchart("flowchart LR\nA[Start] -->|go| B[End]\n").expect("parse");
+        let diagram_id = DiagramId::new("flow").expect("diagram id");
+        let mut session = Session::new(SessionId::new("s").expect("session id"));
+        session.diagrams_mut().insert(
+            diagram_id.clone(),
+            Diagram::new(diagram_id.clone(), "Flow", DiagramAst::Flowchart(ast)),
+        );
+        (session, diagram_id)
+    }
+
+    #[test]
+    fn undo_and_redo_replay_ops_with_forward_revs() {
+        let (mut session, diagram_id) = flow_session();
+        let mut history = History::default();
+        let before = session.diagrams()[&diagram_id].ast().clone();
+
+        let ops = [
+            Op::Flow(FlowOp::AddNode {
+                node_id: ObjectId::new("n:c").expect("node id"),
+                label: "Extra".to_owned(),
+                shape: None,
+            }),
+            Op::Flow(FlowOp::RemoveNode { node_id: ObjectId::new("n:B").expect("node id") }),
+        ];
+        let diagram = session.diagrams_mut().get_mut(&diagram_id).expect("diagram");
+        history.apply(diagram, 0, &ops).expect("apply");
+        let after = diagram.ast().clone();
+        assert_ne!(after, before);
+
+        let step = history.undo(&mut session).expect("undo");
+        assert_eq!((step.base_rev, step.result.new_rev), (1, 2));
+        assert_eq!(session.diagrams()[&diagram_id].ast(), &before);
+
+        let step = history.redo(&mut session).expect("redo");
+        assert_eq!(step.result.new_rev, 3);
+        assert_eq!(session.diagrams()[&diagram_id].ast(), &after);
+        assert_eq!(history.redo(&mut session), Err(HistoryError::Empty));
+    }
+
+    #[test]
+    fn out_of_band_change_makes_history_stale() {
+        let (mut session, diagram_id) = flow_session();
+        let mut history = History::default();
+        let ops = [Op::Flow(FlowOp::SetNodeNote {
+            node_id: ObjectId::new("n:A").expect("node id"),
+            note: Some("entry".to_owned()),
+        })];
+        let diagram = session.diagrams_mut().get_mut(&diagram_id).expect("diagram");
+        history.apply(diagram, 0, &ops).expect("apply");
+        diagram.bump_rev();
+
+        assert_eq!(
+            history.undo(&mut session),
+            Err(HistoryError::Stale { diagram_id, expected_rev: 1, current_rev: 2 })
+        );
+        assert_eq!(history.peek_undo(), None);
+    }
+
+    #[test]
+    fn read_only_diagrams_keep_history_until_unlocked() {
+        let (mut session, diagram_id) = flow_session();
+        let mut history = History::default();
+        let ops = [Op::Flow(FlowOp::SetNodeNote {
+            node_id: ObjectId::new("n:A").expect("node id"),
+            note: Some("entry".to_owned()),
+        })];
+        let diagram = session.diagrams_mut().get_mut(&diagram_id).expect("diagram");
+        history.apply(diagram, 0, &ops).expect("apply");
+        diagram.set_read_only(true);
+        assert_eq!(
+            history.apply(diagram, 1, &ops),
+            Err(ApplyError::ReadOnly { diagram_id: diagram_id.clone() })
+        );
+
+        assert_eq!(
+            history.undo(&mut session),
+            Err(HistoryError::ReadOnly { diagram_id: diagram_id.clone() })
+        );
+        session.diagrams_mut().get_mut(&diagram_id).expect("diagram").set_read_only(false);
+        assert_eq!(history.undo(&mut session).expect("undo").result.new_rev, 2);
+    }
+
+    #[test]
+    fn diff_ops_round_trips_sequence_edits() {
+        let parse = |src: &str| DiagramAst::Sequence(parse_sequence_diagram(src).expect("parse"));
+        let from = parse("sequenceDiagram\nparticipant A\nparticipant B\nA->>B: hi\nB-->>A: ok\n");
+        let to = parse("sequenceDiagram\nparticipant A\nparticipant C\nA-)C: hey\n");
+
+        assert!(diff_ops(&from, &to).is_some());
+        assert!(diff_ops(&to, &from).is_some());
+        assert_eq!(diff_ops(&from, &from), Some(Vec::new()));
+        assert_eq!(diff_ops(&from, &DiagramAst::Flowchart(Default::default())), None);
+    }
+
+    #[test]
+    fn diff_ops_round_trips_sequence_note_edits() {
+        let parse = |src: &str| DiagramAst::Sequence(parse_sequence_diagram(src).expect("parse"));
+        let from = parse(
+            "sequenceDiagram\nparticipant A\nparticipant B\nNote right of A: draft\nA->>B: hi\nNote over A,B: span\n",
+        );
+        let to = parse(
+            "sequenceDiagram\nparticipant A\nparticipant B\nNote left of A: final\nA->>B: hi\nNote over B: narrowed\n",
+        );
+
+        let ops = diff_ops(&from, &to).expect("forward ops");
+        assert!(!ops.is_empty());
+        assert!(diff_ops(&to, &from).is_some());
+        assert!(
+            diff_ops(&from, &parse("sequenceDiagram\nparticipant A\nparticipant B\n")).is_some()
+        );
+    }
+
+    #[test]
+    fn diff_ops_sets_and_clears_message_boundaries() {
+        let parse = |src: &str| DiagramAst::Sequence(parse_sequence_diagram(src).expect("parse"));
+        let from = parse("sequenceDiagram\nparticipant A\nA->>A: hi\n");
+        let to = parse("sequenceDiagram\nparticipant A\n%% nereid:boundary=lost\nA->>A: hi\n");
+        let message_id = ObjectId::new("m:0001").expect("message id");
+
+        assert_eq!(
+            diff_ops(&from, &to),
+            Some(vec![Op::Seq(SeqOp::SetMessageBoundary {
+                message_id: message_id.clone(),
+                boundary: Some(SequenceMessageBoundary::Lost),
+            })])
+        );
+        assert_eq!(
+            diff_ops(&to, &from),
+            Some(vec![Op::Seq(SeqOp::SetMessageBoundary { message_id, boundary: None })])
+        );
+    }
+}
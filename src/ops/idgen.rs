@@ -0,0 +1,173 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Id allocation for new objects, diagrams, walkthroughs and xrefs.
+//!
+//! Ids follow the prefix conventions of the Mermaid importers: `n:`/`p:` plus a Mermaid
+//! identifier for nodes and participants, `e:NNNN`/`m:NNNN` for edges and messages, `x:N`
+//! for xrefs and `w:N`/`wn:N` for walkthroughs and their nodes. Every helper is deterministic for a given session so the TUI, MCP tools and
+//! importers mint the same ids for the same edits.
+
+use crate::model::{
+    DiagramId, DiagramKind, ObjectId, Session, Walkthrough, WalkthroughId, WalkthroughNodeId,
+    XRefId,
+};
+
+/// A Mermaid identifier derived from `label`, suffixed with `_2`, `_3`, ... until `taken`
+/// rejects it. Labels without usable characters fall back to `node`.
+pub fn mermaid_ident(label: &str, taken: impl Fn(&str) -> bool) -> String {
+    let ident = label
+        .trim()
+        .chars()
+        .map(|ch| if ch.is_ascii_alphanumeric() || ch == '_' { ch } else { '_' })
+        .collect::<String>();
+    let base = match ident.trim_matches('_') {
+        "" => "node",
+        ident => ident,
+    };
+    let mut candidate = base.to_owned();
+    let mut suffix = 2usize;
+    while taken(&candidate) {
+        candidate = format!("{base}_{suffix}");
+        suffix += 1;
+    }
+    candidate
+}
+
+/// `<prefix>:<ident>` with the identifier derived from `label` as in [`mermaid_ident`].
+pub fn labeled_object_id(prefix: &str, label: &str, taken: impl Fn(&ObjectId) -> bool) -> ObjectId {
+    let ident = mermaid_ident(label, |ident| {
+        taken(&ObjectId::new(format!("{prefix}:{ident}")).expect("valid object id"))
+    });
+    ObjectId::new(format!("{prefix}:{ident}")).expect("valid object id")
+}
+
+/// The first free `<prefix>:NNNN` id from `next` on, matching Mermaid import numbering.
+pub fn numbered_object_id(
+    prefix: &str,
+    mut next: usize,
+    taken: impl Fn(&ObjectId) -> bool,
+) -> ObjectId {
+    loop {
+        let candidate = ObjectId::new(format!("{prefix}:{next:04}")).expect("valid object id");
+        if !taken(&candidate) {
+            return candidate;
+        }
+        next += 1;
+    }
+}
+
+/// `base` itself when no diagram uses it yet, otherwise the first free `<base>-N` from 2 on.
+pub fn diagram_id(session: &Session, base: &str) -> DiagramId {
+    let mut candidate = DiagramId::new(base.to_owned()).expect("valid diagram id");
+    let mut idx = 2usize;
+    while session.diagrams().contains_key(&candidate) {
+        candidate = DiagramId::new(format!("{base}-{idx}")).expect("valid diagram id");
+        idx += 1;
+    }
+    candidate
+}
+
+/// [`diagram_id`] based on the kind: `seq`, `flow`, `state` or `class`.
+pub fn diagram_id_for_kind(session: &Session, kind: DiagramKind) -> DiagramId {
+    let base = match kind {
+        DiagramKind::Sequence => "seq",
+        DiagramKind::Flowchart => "flow",
+        DiagramKind::State => "state",
+        DiagramKind::Class => "class",
+    };
+    diagram_id(session, base)
+}
+
+/// The first free `w:N` walkthrough id from 1 on.
+pub fn walkthrough_id(session: &Session) -> WalkthroughId {
+    (1usize..)
+        .map(|idx| WalkthroughId::new(format!("w:{idx}")).expect("valid walkthrough id"))
+        .find(|candidate| !session.walkthroughs().contains_key(candidate))
+        .expect("unbounded range")
+}
+
+/// The first free `wn:N` node id in `walkthrough` from 1 on.
+pub fn walkthrough_node_id(walkthrough: &Walkthrough) -> WalkthroughNodeId {
+    (1usize..)
+        .map(|idx| WalkthroughNodeId::new(format!("wn:{idx}")).expect("valid walkthrough node id"))
+        .find(|candidate| walkthrough.nodes().iter().all(|node| node.node_id() != candidate))
+        .expect("unbounded range")
+}
+
+/// Mints the next `x:N` xref id and advances the session's xref id counter.
+///
+/// Numbering continues after both the counter and the highest numeric xref id already present,
+/// so ids are never handed out twice, even after the xref holding them was removed.
+pub fn next_xref_id(session: &mut Session) -> XRefId {
+    let highest = session
+        .xrefs()
+        .keys()
+        .filter_map(|xref_id| xref_id.as_str().strip_prefix("x:")?.parse::<u64>().ok())
+        .max()
+        .unwrap_or(0);
+    let next = session.xref_id_counter().max(highest) + 1;
+    session.set_xref_id_counter(next);
+    XRefId::new(format!("x:{next}")).expect("valid xref id")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diagram_id, labeled_object_id, next_xref_id, numbered_object_id};
+    use crate::model::{
+        CategoryPath, Diagram, DiagramAst, DiagramId, FlowchartAst, ObjectId, ObjectRef, Session,
+        SessionId, XRef, XRefId, XRefStatus,
+    };
+
+    fn session() -> Session {
+        Session::new(SessionId::new("s").expect("session id"))
+    }
+
+    #[test]
+    fn object_ids_follow_prefix_conventions_and_skip_taken_ones() {
+        let taken = |id: &ObjectId| matches!(id.as_str(), "n:Load_cart" | "e:0003");
+        assert_eq!(labeled_object_id("n", " Load cart ", taken).as_str(), "n:Load_cart_2");
+        assert_eq!(labeled_object_id("p", "!!", taken).as_str(), "p:node");
+        assert_eq!(numbered_object_id("e", 3, taken).as_str(), "e:0004");
+    }
+
+    #[test]
+    fn diagram_ids_get_numbered_suffixes_once_the_base_is_taken() {
+        let mut session = session();
+        for id in ["flow", "flow-2"] {
+            let id = DiagramId::new(id).expect("diagram id");
+            let diagram =
+                Diagram::new(id.clone(), "Flow", DiagramAst::Flowchart(FlowchartAst::default()));
+            session.diagrams_mut().insert(id, diagram);
+        }
+        assert_eq!(diagram_id(&session, "seq").as_str(), "seq");
+        assert_eq!(diagram_id(&session, "flow").as_str(), "flow-3");
+    }
+
+    #[test]
+    fn xref_ids_continue_after_existing_ones_and_are_not_reused_after_removal() {
+        let mut session = session();
+        assert_eq!(next_xref_id(&mut session).as_str(), "x:1");
+
+        let node = |diagram_id: &str| {
+            ObjectRef::new(
+                DiagramId::new(diagram_id).expect("diagram id"),
+                CategoryPath::new(vec!["flow".to_owned(), "node".to_owned()]).expect("category"),
+                ObjectId::new("n:A").expect("node id"),
+            )
+        };
+        let x7 = XRefId::new("x:7").expect("xref id");
+        let xref = XRef::new(node("a"), node("b"), "calls", XRefStatus::Ok);
+        session.xrefs_mut().insert(x7.clone(), xref);
+        assert_eq!(next_xref_id(&mut session).as_str(), "x:8");
+
+        session.xrefs_mut().remove(&x7);
+        assert_eq!(next_xref_id(&mut session).as_str(), "x:9");
+        assert_eq!(session.xref_id_counter(), 9);
+    }
+}
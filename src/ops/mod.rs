@@ -11,15 +11,21 @@
 //! Operations are applied with optimistic concurrency (revision checks) and produce a minimal
 //! delta that the UI can use to refresh derived state.
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt;
 
 use crate::format::mermaid::flowchart::MermaidIdentError;
+use crate::model::SequenceMessageBoundary;
 use crate::model::{
-    CategoryPath, Diagram, DiagramAst, DiagramId, DiagramKind, FlowEdge, FlowNode, FlowchartAst,
+    CategoryPath, Diagram, DiagramAst, DiagramId, DiagramKind, FlowEdge, FlowNode, FlowNodePin,
+    FlowchartAst, ObjectAnnotations,
 };
 use crate::model::{ObjectId, ObjectRef, SequenceAst, SequenceMessage, SequenceMessageKind};
-use crate::model::{SequenceParticipant, XRefId};
+use crate::model::{SequenceNote, SequenceNotePlacement, SequenceParticipant, Session};
+use crate::model::{XRef, XRefId, XRefStatus};
+
+pub mod history;
+pub mod idgen;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Op {
@@ -42,9 +48,18 @@ pub enum SeqOp {
         participant_id: ObjectId,
         note: Option<String>,
     },
+    /// Replaces the participant's tags and metadata.
+    SetParticipantAnnotations {
+        participant_id: ObjectId,
+        annotations: ObjectAnnotations,
+    },
     RemoveParticipant {
         participant_id: ObjectId,
     },
+    /// Replaces the explicit left-to-right order (see `SequenceAst::participant_order`).
+    SetParticipantOrder {
+        order: Vec<ObjectId>,
+    },
     AddMessage {
         message_id: ObjectId,
         from_participant_id: ObjectId,
@@ -58,9 +73,38 @@ pub enum SeqOp {
         message_id: ObjectId,
         patch: SeqMessagePatch,
     },
+    SetMessageDuration {
+        message_id: ObjectId,
+        duration: Option<u64>,
+    },
+    /// Makes the message found or lost (see `SequenceMessage::set_boundary`); `None` clears it.
+    SetMessageBoundary {
+        message_id: ObjectId,
+        boundary: Option<SequenceMessageBoundary>,
+    },
+    /// Replaces the message's tags and metadata.
+    SetMessageAnnotations {
+        message_id: ObjectId,
+        annotations: ObjectAnnotations,
+    },
     RemoveMessage {
         message_id: ObjectId,
     },
+    /// Adds a timeline note; `LeftOf`/`RightOf` take one participant, `Over` one or two.
+    AddNote {
+        note_id: ObjectId,
+        placement: SequenceNotePlacement,
+        participant_ids: Vec<ObjectId>,
+        text: String,
+        order_key: i64,
+    },
+    UpdateNote {
+        note_id: ObjectId,
+        patch: SeqNotePatch,
+    },
+    RemoveNote {
+        note_id: ObjectId,
+    },
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
@@ -78,6 +122,14 @@ pub struct SeqMessagePatch {
     pub order_key: Option<i64>,
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SeqNotePatch {
+    pub placement: Option<SequenceNotePlacement>,
+    pub participant_ids: Option<Vec<ObjectId>>,
+    pub text: Option<String>,
+    pub order_key: Option<i64>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FlowOp {
     AddNode {
@@ -97,6 +149,25 @@ pub enum FlowOp {
         node_id: ObjectId,
         note: Option<String>,
     },
+    /// Replaces the node's tags and metadata.
+    SetNodeAnnotations {
+        node_id: ObjectId,
+        annotations: ObjectAnnotations,
+    },
+    /// Replaces all in-layer ordering hints (see `FlowchartAst::node_order_hints`).
+    SetNodeOrderHints {
+        order_hints: BTreeMap<ObjectId, u32>,
+    },
+    /// Pins a node to a grid position, or unpins it (see `FlowchartAst::node_pins`).
+    SetNodePin {
+        node_id: ObjectId,
+        pin: Option<FlowNodePin>,
+    },
+    /// Marks or unmarks a node as an entry point (see `FlowchartAst::entry_nodes`).
+    SetNodeEntry {
+        node_id: ObjectId,
+        entry: bool,
+    },
     RemoveNode {
         node_id: ObjectId,
     },
@@ -112,6 +183,16 @@ pub enum FlowOp {
         edge_id: ObjectId,
         patch: FlowEdgePatch,
     },
+    /// Sets or clears the explicit traversal cost of an edge (see `FlowEdge::effective_weight`).
+    SetEdgeWeight {
+        edge_id: ObjectId,
+        weight: Option<u64>,
+    },
+    /// Replaces the edge's tags and metadata.
+    SetEdgeAnnotations {
+        edge_id: ObjectId,
+        annotations: ObjectAnnotations,
+    },
     RemoveEdge {
         edge_id: ObjectId,
     },
@@ -135,9 +216,18 @@ pub struct FlowEdgePatch {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum XRefOp {
     Add { xref_id: XRefId, from: ObjectRef, to: ObjectRef, kind: String, label: Option<String> },
+    Update { xref_id: XRefId, patch: XRefPatch },
     Remove { xref_id: XRefId },
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XRefPatch {
+    pub from: Option<ObjectRef>,
+    pub to: Option<ObjectRef>,
+    pub kind: Option<String>,
+    pub label: Option<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ApplyResult {
     pub new_rev: u64,
@@ -145,6 +235,13 @@ pub struct ApplyResult {
     pub delta: Delta,
 }
 
+/// Result of [`apply_session_ops`]; sessions carry no revision of their own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionApplyResult {
+    pub applied: usize,
+    pub delta: Delta,
+}
+
 /// Minimal delta describing which objects changed as the result of applying ops.
 ///
 /// This is intentionally coarse: it reports only added/removed/updated `ObjectRef`s.
@@ -209,6 +306,16 @@ pub fn apply_ops(
     base_rev: u64,
     ops: &[Op],
 ) -> Result<ApplyResult, ApplyError> {
+    if diagram.is_read_only() {
+        return Err(ApplyError::ReadOnly { diagram_id: diagram.diagram_id().clone() });
+    }
+    diagram.ast();
+    if let Some(error) = diagram.load_error() {
+        return Err(ApplyError::LoadFailed {
+            diagram_id: diagram.diagram_id().clone(),
+            error: error.to_owned(),
+        });
+    }
     let current_rev = diagram.rev();
     if base_rev != current_rev {
         return Err(ApplyError::Conflict { base_rev, current_rev });
@@ -251,7 +358,8 @@ pub fn apply_ops(
     diagram.set_ast(new_ast).map_err(|mismatch| {
         let op_kind = match mismatch.found() {
             DiagramKind::Sequence => OpKind::Seq,
-            DiagramKind::Flowchart => OpKind::Flow,
+            // Ops never produce a state or class diagram, so a mismatch cannot report one.
+            DiagramKind::Flowchart | DiagramKind::State | DiagramKind::Class => OpKind::Flow,
         };
         ApplyError::KindMismatch { diagram_kind: mismatch.expected(), op_kind }
     })?;
@@ -261,6 +369,35 @@ pub fn apply_ops(
     Ok(ApplyResult { new_rev, applied: ops.len(), delta: delta.finish() })
 }
 
+/// Applies session-level ops (currently xref ops) atomically.
+///
+/// Diagram ops are rejected with `UnsupportedOp`; use [`apply_ops`] for those. Every xref status is
+/// recomputed afterwards, and the delta reports the endpoints of each touched xref as updated.
+pub fn apply_session_ops(
+    session: &mut Session,
+    ops: &[Op],
+) -> Result<SessionApplyResult, ApplyError> {
+    let mut xrefs = session.xrefs().clone();
+    let mut delta = DeltaBuilder::default();
+
+    for op in ops {
+        match op {
+            Op::XRef(xref_op) => apply_xref_op(&mut xrefs, xref_op, &mut delta)?,
+            Op::Seq(_) => return Err(ApplyError::UnsupportedOp { op_kind: OpKind::Seq }),
+            Op::Flow(_) => return Err(ApplyError::UnsupportedOp { op_kind: OpKind::Flow }),
+        }
+    }
+
+    for xref in xrefs.values_mut() {
+        let from_dangling = session.object_ref_is_missing(xref.from());
+        let to_dangling = session.object_ref_is_missing(xref.to());
+        xref.set_status(XRefStatus::from_flags(from_dangling, to_dangling));
+    }
+    *session.xrefs_mut() = xrefs;
+
+    Ok(SessionApplyResult { applied: ops.len(), delta: delta.finish() })
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OpKind {
     Seq,
@@ -272,25 +409,73 @@ pub enum OpKind {
 pub enum ObjectKind {
     SeqParticipant,
     SeqMessage,
+    SeqNote,
     FlowNode,
     FlowEdge,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ApplyError {
-    Conflict { base_rev: u64, current_rev: u64 },
-    KindMismatch { diagram_kind: DiagramKind, op_kind: OpKind },
-    UnsupportedOp { op_kind: OpKind },
-    AlreadyExists { kind: ObjectKind, object_id: ObjectId },
-    NotFound { kind: ObjectKind, object_id: ObjectId },
-    MissingFlowNode { node_id: ObjectId },
-    InvalidFlowNodeMermaidId { mermaid_id: String, reason: MermaidIdentError },
-    DuplicateFlowNodeMermaidId { mermaid_id: String, node_id: ObjectId },
+    ReadOnly {
+        diagram_id: DiagramId,
+    },
+    /// The diagram's file could not be parsed, so it holds an empty stand-in AST.
+    LoadFailed {
+        diagram_id: DiagramId,
+        error: String,
+    },
+    Conflict {
+        base_rev: u64,
+        current_rev: u64,
+    },
+    KindMismatch {
+        diagram_kind: DiagramKind,
+        op_kind: OpKind,
+    },
+    UnsupportedOp {
+        op_kind: OpKind,
+    },
+    AlreadyExists {
+        kind: ObjectKind,
+        object_id: ObjectId,
+    },
+    NotFound {
+        kind: ObjectKind,
+        object_id: ObjectId,
+    },
+    MissingFlowNode {
+        node_id: ObjectId,
+    },
+    InvalidSeqNoteParticipants {
+        note_id: ObjectId,
+        placement: SequenceNotePlacement,
+        count: usize,
+    },
+    InvalidFlowNodeMermaidId {
+        mermaid_id: String,
+        reason: MermaidIdentError,
+    },
+    DuplicateFlowNodeMermaidId {
+        mermaid_id: String,
+        node_id: ObjectId,
+    },
+    XRefAlreadyExists {
+        xref_id: XRefId,
+    },
+    XRefNotFound {
+        xref_id: XRefId,
+    },
 }
 
 impl fmt::Display for ApplyError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Self::ReadOnly { diagram_id } => {
+                write!(f, "diagram {diagram_id} is read-only; unlock it before editing")
+            }
+            Self::LoadFailed { diagram_id, error } => {
+                write!(f, "diagram {diagram_id} failed to load ({error}); fix its file first")
+            }
             Self::Conflict { base_rev, current_rev } => {
                 write!(f, "stale base_rev (base_rev={base_rev}, current_rev={current_rev})")
             }
@@ -305,19 +490,27 @@ impl fmt::Display for ApplyError {
                 write!(f, "object not found ({kind:?}, id={object_id})")
             }
             Self::MissingFlowNode { node_id } => write!(f, "flow node not found (id={node_id})"),
+            Self::InvalidSeqNoteParticipants { note_id, placement, count } => write!(
+                f,
+                "note {note_id} ({placement:?}) needs one participant (one or two for Over), got {count}"
+            ),
             Self::InvalidFlowNodeMermaidId { mermaid_id, reason } => {
                 write!(f, "invalid flow node Mermaid id '{mermaid_id}': {reason}")
             }
             Self::DuplicateFlowNodeMermaidId { mermaid_id, node_id } => {
                 write!(f, "flow node Mermaid id '{mermaid_id}' is already used by node {node_id}")
             }
+            Self::XRefAlreadyExists { xref_id } => {
+                write!(f, "xref already exists (id={xref_id})")
+            }
+            Self::XRefNotFound { xref_id } => write!(f, "xref not found (id={xref_id})"),
         }
     }
 }
 
 impl std::error::Error for ApplyError {}
 
-// Extracted op-application implementation for sequence/flow mutations.
+// Extracted op-application implementation for sequence/flow/xref mutations.
 include!("ops_impl.rs");
 
 #[cfg(test)]
@@ -6,7 +6,8 @@
 // This file is part of Nereid and is proprietary software.
 // Unauthorized copying, modification, or distribution is prohibited.
 
-/// Sequence/flow mutation implementation helpers used by `apply_ops`.
+/// Sequence/flow/xref mutation implementation helpers used by `apply_ops` and
+/// `apply_session_ops`.
 /// Keeps `ops::mod` focused on public op types and orchestration.
 fn apply_seq_op(
     diagram_id: &DiagramId,
@@ -64,6 +65,18 @@ fn apply_seq_op(
             delta.record_updated(seq_participant_ref(diagram_id, participant_id));
             Ok(())
         }
+        SeqOp::SetParticipantAnnotations { participant_id, annotations } => {
+            let Some(existing) = ast.participants_mut().get_mut(participant_id) else {
+                return Err(ApplyError::NotFound {
+                    kind: ObjectKind::SeqParticipant,
+                    object_id: participant_id.clone(),
+                });
+            };
+
+            existing.set_annotations(annotations.clone());
+            delta.record_updated(seq_participant_ref(diagram_id, participant_id));
+            Ok(())
+        }
         SeqOp::RemoveParticipant { participant_id } => {
             if ast.participants_mut().remove(participant_id).is_none() {
                 return Err(ApplyError::NotFound {
@@ -86,9 +99,40 @@ fn apply_seq_op(
             for message_id in removed_message_ids {
                 delta.record_removed(seq_message_ref(diagram_id, &message_id));
             }
+            let removed_note_ids = ast
+                .notes()
+                .iter()
+                .filter(|note| note.participant_ids().contains(participant_id))
+                .map(|note| note.note_id().clone())
+                .collect::<Vec<_>>();
+            ast.notes_mut().retain(|note| !note.participant_ids().contains(participant_id));
+            for note_id in removed_note_ids {
+                delta.record_removed(seq_note_ref(diagram_id, &note_id));
+            }
+            ast.participant_order_mut().retain(|id| id != participant_id);
+            prune_seq_activations(diagram_id, ast, delta);
             delta.record_removed(seq_participant_ref(diagram_id, participant_id));
             Ok(())
         }
+        SeqOp::SetParticipantOrder { order } => {
+            let unknown = order.iter().find(|id| !ast.participants().contains_key(*id));
+            if let Some(participant_id) = unknown {
+                return Err(ApplyError::NotFound {
+                    kind: ObjectKind::SeqParticipant,
+                    object_id: participant_id.clone(),
+                });
+            }
+
+            let before = ast.participants_in_order().into_iter().cloned().collect::<Vec<_>>();
+            *ast.participant_order_mut() = order.clone();
+            let after = ast.participants_in_order();
+            for (old, new) in before.iter().zip(after) {
+                if old != new {
+                    delta.record_updated(seq_participant_ref(diagram_id, new));
+                }
+            }
+            Ok(())
+        }
         SeqOp::AddMessage {
             message_id,
             from_participant_id,
@@ -161,6 +205,8 @@ fn apply_seq_op(
                 .clone()
                 .unwrap_or_else(|| existing.text().to_owned());
             let updated_order_key = patch.order_key.unwrap_or(existing.order_key());
+            let duration = existing.duration();
+            let boundary = existing.boundary();
 
             if !ast.participants().contains_key(&updated_from) {
                 return Err(ApplyError::NotFound {
@@ -184,11 +230,55 @@ fn apply_seq_op(
                 updated_order_key,
             );
             updated.set_raw_arrow(normalize_seq_raw_arrow(updated_kind, updated_arrow));
+            updated.set_duration(duration);
+            updated.set_boundary(boundary);
             ast.messages_mut()[index] = updated;
             sort_seq_messages(ast);
             delta.record_updated(seq_message_ref(diagram_id, message_id));
             Ok(())
         }
+        SeqOp::SetMessageDuration { message_id, duration } => {
+            let Some(message) =
+                ast.messages_mut().iter_mut().find(|m| m.message_id() == message_id)
+            else {
+                return Err(ApplyError::NotFound {
+                    kind: ObjectKind::SeqMessage,
+                    object_id: message_id.clone(),
+                });
+            };
+
+            message.set_duration(*duration);
+            delta.record_updated(seq_message_ref(diagram_id, message_id));
+            Ok(())
+        }
+        SeqOp::SetMessageBoundary { message_id, boundary } => {
+            let Some(message) =
+                ast.messages_mut().iter_mut().find(|m| m.message_id() == message_id)
+            else {
+                return Err(ApplyError::NotFound {
+                    kind: ObjectKind::SeqMessage,
+                    object_id: message_id.clone(),
+                });
+            };
+
+            message.set_boundary(*boundary);
+            delta.record_updated(seq_message_ref(diagram_id, message_id));
+            Ok(())
+        }
+        SeqOp::SetMessageAnnotations { message_id, annotations } => {
+            let Some(message) =
+                ast.messages_mut().iter_mut().find(|m| m.message_id() == message_id)
+            else {
+                return Err(ApplyError::NotFound {
+                    kind: ObjectKind::SeqMessage,
+                    object_id: message_id.clone(),
+                });
+            };
+
+            message.set_annotations(annotations.clone());
+            delta.record_updated(seq_message_ref(diagram_id, message_id));
+            Ok(())
+        }
         SeqOp::RemoveMessage { message_id } => {
             let before_len = ast.messages().len();
             ast.messages_mut().retain(|m| m.message_id() != message_id);
@@ -198,9 +288,120 @@ fn apply_seq_op(
                     object_id: message_id.clone(),
                 });
             }
+            prune_seq_activations(diagram_id, ast, delta);
             delta.record_removed(seq_message_ref(diagram_id, message_id));
             Ok(())
         }
+        SeqOp::AddNote { note_id, placement, participant_ids, text, order_key } => {
+            if ast.notes().iter().any(|note| note.note_id() == note_id) {
+                return Err(ApplyError::AlreadyExists {
+                    kind: ObjectKind::SeqNote,
+                    object_id: note_id.clone(),
+                });
+            }
+            validate_seq_note_participants(ast, note_id, *placement, participant_ids)?;
+            ast.notes_mut().push(SequenceNote::new(
+                note_id.clone(),
+                *placement,
+                participant_ids.clone(),
+                text.clone(),
+                *order_key,
+            ));
+            sort_seq_notes(ast);
+            delta.record_added(seq_note_ref(diagram_id, note_id));
+            Ok(())
+        }
+        SeqOp::UpdateNote { note_id, patch } => {
+            let Some(index) = ast.notes().iter().position(|note| note.note_id() == note_id) else {
+                return Err(ApplyError::NotFound {
+                    kind: ObjectKind::SeqNote,
+                    object_id: note_id.clone(),
+                });
+            };
+
+            let existing = &ast.notes()[index];
+            let placement = patch.placement.unwrap_or(existing.placement());
+            let participant_ids = patch
+                .participant_ids
+                .clone()
+                .unwrap_or_else(|| existing.participant_ids().to_vec());
+            validate_seq_note_participants(ast, note_id, placement, &participant_ids)?;
+
+            let note = &mut ast.notes_mut()[index];
+            note.set_placement(placement);
+            note.set_participant_ids(participant_ids);
+            if let Some(text) = &patch.text {
+                note.set_text(text.clone());
+            }
+            if let Some(order_key) = patch.order_key {
+                note.set_order_key(order_key);
+            }
+            sort_seq_notes(ast);
+            delta.record_updated(seq_note_ref(diagram_id, note_id));
+            Ok(())
+        }
+        SeqOp::RemoveNote { note_id } => {
+            let before_len = ast.notes().len();
+            ast.notes_mut().retain(|note| note.note_id() != note_id);
+            if ast.notes().len() == before_len {
+                return Err(ApplyError::NotFound {
+                    kind: ObjectKind::SeqNote,
+                    object_id: note_id.clone(),
+                });
+            }
+            delta.record_removed(seq_note_ref(diagram_id, note_id));
+            Ok(())
+        }
+    }
+}
+
+/// Checks that a note names known participants, one for `LeftOf`/`RightOf` and one or two for
+/// `Over` (the most Mermaid allows).
+fn validate_seq_note_participants(
+    ast: &SequenceAst,
+    note_id: &ObjectId,
+    placement: SequenceNotePlacement,
+    participant_ids: &[ObjectId],
+) -> Result<(), ApplyError> {
+    let max = match placement {
+        SequenceNotePlacement::LeftOf | SequenceNotePlacement::RightOf => 1,
+        SequenceNotePlacement::Over => 2,
+    };
+    if participant_ids.is_empty() || participant_ids.len() > max {
+        return Err(ApplyError::InvalidSeqNoteParticipants {
+            note_id: note_id.clone(),
+            placement,
+            count: participant_ids.len(),
+        });
+    }
+    let unknown = participant_ids.iter().find(|id| !ast.participants().contains_key(*id));
+    if let Some(participant_id) = unknown {
+        return Err(ApplyError::NotFound {
+            kind: ObjectKind::SeqParticipant,
+            object_id: participant_id.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Drops activations whose participant or anchoring messages no longer exist.
+fn prune_seq_activations(diagram_id: &DiagramId, ast: &mut SequenceAst, delta: &mut DeltaBuilder) {
+    let message_exists = |message_id: Option<&ObjectId>, ast: &SequenceAst| {
+        message_id.map_or(true, |id| ast.messages().iter().any(|m| m.message_id() == id))
+    };
+    let stale = ast
+        .activations()
+        .iter()
+        .filter(|activation| {
+            !ast.participants().contains_key(activation.participant_id())
+                || !message_exists(activation.start_message_id(), ast)
+                || !message_exists(activation.end_message_id(), ast)
+        })
+        .map(|activation| activation.activation_id().clone())
+        .collect::<Vec<_>>();
+    ast.activations_mut().retain(|activation| !stale.contains(activation.activation_id()));
+    for activation_id in stale {
+        delta.record_removed(object_ref(diagram_id, &["seq", "activation"], &activation_id));
     }
 }
 
@@ -208,6 +409,10 @@ fn sort_seq_messages(ast: &mut SequenceAst) {
     ast.messages_mut().sort_by(SequenceMessage::cmp_in_order);
 }
 
+fn sort_seq_notes(ast: &mut SequenceAst) {
+    ast.notes_mut().sort_by(SequenceNote::cmp_in_order);
+}
+
 fn normalize_seq_raw_arrow(kind: SequenceMessageKind, raw_arrow: Option<String>) -> Option<String> {
     let raw_arrow = raw_arrow?;
     let trimmed = raw_arrow.trim();
@@ -382,6 +587,74 @@ fn apply_flow_op(
             delta.record_updated(flow_node_ref(diagram_id, node_id));
             Ok(())
         }
+        FlowOp::SetNodeAnnotations { node_id, annotations } => {
+            let Some(existing) = ast.nodes_mut().get_mut(node_id) else {
+                return Err(ApplyError::NotFound {
+                    kind: ObjectKind::FlowNode,
+                    object_id: node_id.clone(),
+                });
+            };
+
+            existing.set_annotations(annotations.clone());
+            delta.record_updated(flow_node_ref(diagram_id, node_id));
+            Ok(())
+        }
+        FlowOp::SetNodeOrderHints { order_hints } => {
+            let unknown = order_hints.keys().find(|node_id| !ast.nodes().contains_key(*node_id));
+            if let Some(node_id) = unknown {
+                return Err(ApplyError::NotFound {
+                    kind: ObjectKind::FlowNode,
+                    object_id: node_id.clone(),
+                });
+            }
+
+            let changed = ast
+                .nodes()
+                .keys()
+                .filter(|node_id| ast.node_order_hints().get(*node_id) != order_hints.get(*node_id))
+                .cloned()
+                .collect::<Vec<_>>();
+            for node_id in changed {
+                delta.record_updated(flow_node_ref(diagram_id, &node_id));
+            }
+            *ast.node_order_hints_mut() = order_hints.clone();
+            Ok(())
+        }
+        FlowOp::SetNodePin { node_id, pin } => {
+            if !ast.nodes().contains_key(node_id) {
+                return Err(ApplyError::NotFound {
+                    kind: ObjectKind::FlowNode,
+                    object_id: node_id.clone(),
+                });
+            }
+
+            let previous = match pin {
+                Some(pin) => ast.node_pins_mut().insert(node_id.clone(), *pin),
+                None => ast.node_pins_mut().remove(node_id),
+            };
+            if previous != *pin {
+                delta.record_updated(flow_node_ref(diagram_id, node_id));
+            }
+            Ok(())
+        }
+        FlowOp::SetNodeEntry { node_id, entry } => {
+            if !ast.nodes().contains_key(node_id) {
+                return Err(ApplyError::NotFound {
+                    kind: ObjectKind::FlowNode,
+                    object_id: node_id.clone(),
+                });
+            }
+
+            let changed = if *entry {
+                ast.entry_nodes_mut().insert(node_id.clone())
+            } else {
+                ast.entry_nodes_mut().remove(node_id)
+            };
+            if changed {
+                delta.record_updated(flow_node_ref(diagram_id, node_id));
+            }
+            Ok(())
+        }
         FlowOp::RemoveNode { node_id } => {
             if ast.nodes_mut().remove(node_id).is_none() {
                 return Err(ApplyError::NotFound {
@@ -389,6 +662,9 @@ fn apply_flow_op(
                     object_id: node_id.clone(),
                 });
             }
+            ast.node_order_hints_mut().remove(node_id);
+            ast.node_pins_mut().remove(node_id);
+            ast.entry_nodes_mut().remove(node_id);
             let to_remove = ast
                 .edges()
                 .iter()
@@ -487,10 +763,35 @@ fn apply_flow_op(
             let mut edge =
                 FlowEdge::new_with(updated_from, updated_to, updated_label, updated_style);
             edge.set_connector(normalize_flow_connector(updated_connector));
+            edge.set_weight(ast.edges().get(edge_id).and_then(FlowEdge::weight));
             ast.edges_mut().insert(edge_id.clone(), edge);
             delta.record_updated(flow_edge_ref(diagram_id, edge_id));
             Ok(())
         }
+        FlowOp::SetEdgeWeight { edge_id, weight } => {
+            let Some(edge) = ast.edges_mut().get_mut(edge_id) else {
+                return Err(ApplyError::NotFound {
+                    kind: ObjectKind::FlowEdge,
+                    object_id: edge_id.clone(),
+                });
+            };
+
+            edge.set_weight(*weight);
+            delta.record_updated(flow_edge_ref(diagram_id, edge_id));
+            Ok(())
+        }
+        FlowOp::SetEdgeAnnotations { edge_id, annotations } => {
+            let Some(edge) = ast.edges_mut().get_mut(edge_id) else {
+                return Err(ApplyError::NotFound {
+                    kind: ObjectKind::FlowEdge,
+                    object_id: edge_id.clone(),
+                });
+            };
+
+            edge.set_annotations(annotations.clone());
+            delta.record_updated(flow_edge_ref(diagram_id, edge_id));
+            Ok(())
+        }
         FlowOp::RemoveEdge { edge_id } => {
             if ast.edges_mut().remove(edge_id).is_none() {
                 return Err(ApplyError::NotFound {
@@ -504,6 +805,54 @@ fn apply_flow_op(
     }
 }
 
+fn apply_xref_op(
+    xrefs: &mut BTreeMap<XRefId, XRef>,
+    op: &XRefOp,
+    delta: &mut DeltaBuilder,
+) -> Result<(), ApplyError> {
+    match op {
+        XRefOp::Add { xref_id, from, to, kind, label } => {
+            if xrefs.contains_key(xref_id) {
+                return Err(ApplyError::XRefAlreadyExists { xref_id: xref_id.clone() });
+            }
+            // Status is recomputed for every xref once all ops are applied.
+            let mut xref = XRef::new(from.clone(), to.clone(), kind.clone(), XRefStatus::Ok);
+            xref.set_label(label.clone());
+            xrefs.insert(xref_id.clone(), xref);
+            delta.record_updated(from.clone());
+            delta.record_updated(to.clone());
+            Ok(())
+        }
+        XRefOp::Update { xref_id, patch } => {
+            let Some(existing) = xrefs.get(xref_id) else {
+                return Err(ApplyError::XRefNotFound { xref_id: xref_id.clone() });
+            };
+            delta.record_updated(existing.from().clone());
+            delta.record_updated(existing.to().clone());
+
+            let from = patch.from.clone().unwrap_or_else(|| existing.from().clone());
+            let to = patch.to.clone().unwrap_or_else(|| existing.to().clone());
+            let kind = patch.kind.clone().unwrap_or_else(|| existing.kind().to_owned());
+            let label =
+                patch.label.clone().or_else(|| existing.label().map(ToOwned::to_owned));
+            let mut xref = XRef::new(from.clone(), to.clone(), kind, existing.status());
+            xref.set_label(label);
+            xrefs.insert(xref_id.clone(), xref);
+            delta.record_updated(from);
+            delta.record_updated(to);
+            Ok(())
+        }
+        XRefOp::Remove { xref_id } => {
+            let Some(removed) = xrefs.remove(xref_id) else {
+                return Err(ApplyError::XRefNotFound { xref_id: xref_id.clone() });
+            };
+            delta.record_updated(removed.from().clone());
+            delta.record_updated(removed.to().clone());
+            Ok(())
+        }
+    }
+}
+
 fn seq_participant_ref(diagram_id: &DiagramId, participant_id: &ObjectId) -> ObjectRef {
     object_ref(diagram_id, &["seq", "participant"], participant_id)
 }
@@ -512,6 +861,10 @@ fn seq_message_ref(diagram_id: &DiagramId, message_id: &ObjectId) -> ObjectRef {
     object_ref(diagram_id, &["seq", "message"], message_id)
 }
 
+fn seq_note_ref(diagram_id: &DiagramId, note_id: &ObjectId) -> ObjectRef {
+    object_ref(diagram_id, &["seq", "note"], note_id)
+}
+
 fn flow_node_ref(diagram_id: &DiagramId, node_id: &ObjectId) -> ObjectRef {
     object_ref(diagram_id, &["flow", "node"], node_id)
 }
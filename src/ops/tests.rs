@@ -7,12 +7,14 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use crate::model::{
-    DiagramAst, DiagramId, FlowchartAst, ObjectId, SequenceAst, SequenceParticipant,
+    DiagramAst, DiagramId, FlowNodePin, FlowchartAst, ObjectAnnotations, ObjectId, ObjectRef,
+    SequenceAst, SequenceMessageBoundary, SequenceMessageKind, SequenceNotePlacement,
+    SequenceParticipant, Session, SessionId, XRefId, XRefStatus,
 };
 
 use super::{
-    apply_ops, FlowEdgePatch, FlowNodePatch, FlowOp, Op, SeqMessagePatch, SeqOp,
-    SeqParticipantPatch,
+    apply_ops, apply_session_ops, ApplyError, FlowEdgePatch, FlowNodePatch, FlowOp, Op, OpKind,
+    SeqMessagePatch, SeqNotePatch, SeqOp, SeqParticipantPatch, XRefOp, XRefPatch,
 };
 
 #[test]
@@ -123,6 +125,182 @@ fn apply_seq_set_participant_note_sets_note_and_records_delta_updated() {
     assert_eq!(participant.note(), Some("invariant"));
 }
 
+fn seq_diagram_with_participants(names: &[&str]) -> crate::model::Diagram {
+    let mut ast = SequenceAst::default();
+    for name in names {
+        let participant_id = ObjectId::new(format!("p:{name}")).expect("participant id");
+        ast.participants_mut().insert(participant_id, SequenceParticipant::new(*name));
+    }
+    crate::model::Diagram::new(
+        DiagramId::new("d:seq-notes").expect("diagram id"),
+        "seq",
+        DiagramAst::Sequence(ast),
+    )
+}
+
+#[test]
+fn apply_seq_note_ops_add_update_and_remove_timeline_notes() {
+    let mut diagram = seq_diagram_with_participants(&["alice", "bob"]);
+    let note_id = ObjectId::new("note:1").expect("note id");
+    let alice_id = ObjectId::new("p:alice").expect("participant id");
+    let bob_id = ObjectId::new("p:bob").expect("participant id");
+    let note_ref = crate::model::ObjectRef::new(
+        diagram.diagram_id().clone(),
+        crate::model::CategoryPath::new(vec!["seq".to_owned(), "note".to_owned()])
+            .expect("category"),
+        note_id.clone(),
+    );
+
+    let result = apply_ops(
+        &mut diagram,
+        0,
+        &[Op::Seq(SeqOp::AddNote {
+            note_id: note_id.clone(),
+            placement: SequenceNotePlacement::RightOf,
+            participant_ids: vec![alice_id.clone()],
+            text: "draft".to_owned(),
+            order_key: 1000,
+        })],
+    )
+    .expect("add");
+    assert_eq!(result.delta.added, vec![note_ref.clone()]);
+
+    let result = apply_ops(
+        &mut diagram,
+        1,
+        &[Op::Seq(SeqOp::UpdateNote {
+            note_id: note_id.clone(),
+            patch: SeqNotePatch {
+                placement: Some(SequenceNotePlacement::Over),
+                participant_ids: Some(vec![alice_id.clone(), bob_id.clone()]),
+                text: Some("handshake".to_owned()),
+                order_key: None,
+            },
+        })],
+    )
+    .expect("update");
+    assert_eq!(result.delta.updated, vec![note_ref.clone()]);
+
+    let DiagramAst::Sequence(ast) = diagram.ast() else {
+        panic!("expected sequence ast");
+    };
+    let note = &ast.notes()[0];
+    assert_eq!(note.placement(), SequenceNotePlacement::Over);
+    assert_eq!(note.participant_ids(), &[alice_id, bob_id][..]);
+    assert_eq!(note.text(), "handshake");
+    assert_eq!(note.order_key(), 1000);
+
+    let result =
+        apply_ops(&mut diagram, 2, &[Op::Seq(SeqOp::RemoveNote { note_id })]).expect("remove");
+    assert_eq!(result.delta.removed, vec![note_ref]);
+    let DiagramAst::Sequence(ast) = diagram.ast() else {
+        panic!("expected sequence ast");
+    };
+    assert!(ast.notes().is_empty());
+}
+
+#[test]
+fn apply_seq_set_message_boundary_sets_keeps_and_clears_the_boundary() {
+    let mut diagram = seq_diagram_with_participants(&["alice", "bob"]);
+    let message_id = ObjectId::new("m:1").expect("message id");
+    let alice_id = ObjectId::new("p:alice").expect("participant id");
+    let bob_id = ObjectId::new("p:bob").expect("participant id");
+    let set_boundary =
+        |boundary| Op::Seq(SeqOp::SetMessageBoundary { message_id: message_id.clone(), boundary });
+    let message = |diagram: &crate::model::Diagram| {
+        let DiagramAst::Sequence(ast) = diagram.ast() else {
+            panic!("expected sequence ast");
+        };
+        ast.messages()[0].clone()
+    };
+
+    apply_ops(
+        &mut diagram,
+        0,
+        &[
+            Op::Seq(SeqOp::AddMessage {
+                message_id: message_id.clone(),
+                from_participant_id: alice_id.clone(),
+                to_participant_id: bob_id.clone(),
+                kind: SequenceMessageKind::Sync,
+                arrow: None,
+                text: "hi".to_owned(),
+                order_key: 1000,
+            }),
+            set_boundary(Some(SequenceMessageBoundary::Found)),
+        ],
+    )
+    .expect("found");
+    let found = message(&diagram);
+    assert_eq!((found.sender(), found.receiver()), (None, Some(&bob_id)));
+
+    // Updating a boundary message keeps the boundary.
+    let result = apply_ops(
+        &mut diagram,
+        1,
+        &[Op::Seq(SeqOp::UpdateMessage {
+            message_id: message_id.clone(),
+            patch: SeqMessagePatch { text: Some("wake".to_owned()), ..SeqMessagePatch::default() },
+        })],
+    )
+    .expect("update");
+    assert_eq!(result.delta.updated.len(), 1);
+    assert_eq!(message(&diagram).boundary(), Some(SequenceMessageBoundary::Found));
+
+    apply_ops(&mut diagram, 2, &[set_boundary(None)]).expect("clear");
+    let cleared = message(&diagram);
+    assert!(cleared.is_self_message());
+    assert_eq!(cleared.endpoints(), Some((&bob_id, &bob_id)));
+
+    let err = apply_ops(
+        &mut diagram,
+        3,
+        &[Op::Seq(SeqOp::SetMessageBoundary {
+            message_id: ObjectId::new("m:missing").expect("message id"),
+            boundary: Some(SequenceMessageBoundary::Lost),
+        })],
+    )
+    .expect_err("missing message");
+    assert!(matches!(err, ApplyError::NotFound { .. }), "{err:?}");
+}
+
+#[test]
+fn apply_seq_note_ops_reject_bad_participants() {
+    let mut diagram = seq_diagram_with_participants(&["alice", "bob"]);
+    let note_id = ObjectId::new("note:1").expect("note id");
+    let add = |placement, participant_ids: &[&str]| {
+        Op::Seq(SeqOp::AddNote {
+            note_id: note_id.clone(),
+            placement,
+            participant_ids: participant_ids
+                .iter()
+                .map(|id| ObjectId::new(*id).expect("participant id"))
+                .collect(),
+            text: "n".to_owned(),
+            order_key: 0,
+        })
+    };
+
+    let err =
+        apply_ops(&mut diagram, 0, &[add(SequenceNotePlacement::LeftOf, &["p:alice", "p:bob"])])
+            .unwrap_err();
+    assert!(matches!(err, ApplyError::InvalidSeqNoteParticipants { count: 2, .. }));
+    let err = apply_ops(&mut diagram, 0, &[add(SequenceNotePlacement::Over, &[])]).unwrap_err();
+    assert!(matches!(err, ApplyError::InvalidSeqNoteParticipants { count: 0, .. }));
+    let err =
+        apply_ops(&mut diagram, 0, &[add(SequenceNotePlacement::Over, &["p:carol"])]).unwrap_err();
+    assert!(matches!(err, ApplyError::NotFound { kind: super::ObjectKind::SeqParticipant, .. }));
+
+    let err = apply_ops(
+        &mut diagram,
+        0,
+        &[Op::Seq(SeqOp::UpdateNote { note_id: note_id.clone(), patch: SeqNotePatch::default() })],
+    )
+    .unwrap_err();
+    assert!(matches!(err, ApplyError::NotFound { kind: super::ObjectKind::SeqNote, .. }));
+    assert_eq!(diagram.rev(), 0);
+}
+
 #[test]
 fn apply_conflicts_on_stale_base_rev() {
     let diagram_id = DiagramId::new("d:1").expect("diagram id");
@@ -626,6 +804,253 @@ fn apply_flow_set_node_note_clears_note_and_records_delta_updated() {
     assert_eq!(node.note(), None);
 }
 
+#[test]
+fn apply_flow_set_edge_annotations_replaces_tags_and_rejects_missing_edges() {
+    let diagram_id = DiagramId::new("d:flow-annotations").expect("diagram id");
+    let mut diagram = crate::model::Diagram::new(
+        diagram_id,
+        "flow",
+        DiagramAst::Flowchart(FlowchartAst::default()),
+    );
+    let id = |id: &str| ObjectId::new(id).expect("object id");
+    let add_node = |node: &str| {
+        Op::Flow(FlowOp::AddNode { node_id: id(node), label: node.to_owned(), shape: None })
+    };
+    apply_ops(
+        &mut diagram,
+        0,
+        &[
+            add_node("n:a"),
+            add_node("n:b"),
+            Op::Flow(FlowOp::AddEdge {
+                edge_id: id("e:1"),
+                from_node_id: id("n:a"),
+                to_node_id: id("n:b"),
+                label: None,
+                connector: None,
+                style: None,
+            }),
+        ],
+    )
+    .expect("setup apply");
+
+    let annotations = ObjectAnnotations::new(
+        ["billing"],
+        std::collections::BTreeMap::from([("owner".to_owned(), "team-a".to_owned())]),
+    );
+    let result = apply_ops(
+        &mut diagram,
+        1,
+        &[Op::Flow(FlowOp::SetEdgeAnnotations {
+            edge_id: id("e:1"),
+            annotations: annotations.clone(),
+        })],
+    )
+    .expect("apply annotations");
+
+    let expected = ObjectRef::new(
+        diagram.diagram_id().clone(),
+        crate::model::CategoryPath::new(vec!["flow".to_owned(), "edge".to_owned()])
+            .expect("category"),
+        id("e:1"),
+    );
+    assert_eq!(result.delta.updated, vec![expected]);
+    let DiagramAst::Flowchart(ast) = diagram.ast() else {
+        panic!("expected flowchart ast");
+    };
+    assert_eq!(ast.edges().get(&id("e:1")).expect("edge").annotations(), &annotations);
+
+    let err = apply_ops(
+        &mut diagram,
+        2,
+        &[Op::Flow(FlowOp::SetEdgeAnnotations {
+            edge_id: id("e:missing"),
+            annotations: ObjectAnnotations::default(),
+        })],
+    )
+    .unwrap_err();
+    assert!(matches!(err, ApplyError::NotFound { .. }));
+}
+
+#[test]
+fn apply_seq_set_participant_order_records_moved_participants_and_drops_removed_ones() {
+    let diagram_id = DiagramId::new("d:seq-order").expect("diagram id");
+    let mut diagram =
+        crate::model::Diagram::new(diagram_id, "seq", DiagramAst::Sequence(SequenceAst::default()));
+    let id = |id: &str| ObjectId::new(id).expect("participant id");
+    let add = |participant: &str| {
+        Op::Seq(SeqOp::AddParticipant {
+            participant_id: id(participant),
+            mermaid_name: participant.trim_start_matches("p:").to_owned(),
+        })
+    };
+    apply_ops(&mut diagram, 0, &[add("p:a"), add("p:b"), add("p:c")]).expect("setup apply");
+
+    let order = vec![id("p:b"), id("p:a")];
+    let result =
+        apply_ops(&mut diagram, 1, &[Op::Seq(SeqOp::SetParticipantOrder { order: order.clone() })])
+            .expect("apply order");
+    assert_eq!(result.delta.updated.len(), 2);
+    let DiagramAst::Sequence(ast) = diagram.ast() else {
+        panic!("expected sequence ast");
+    };
+    assert_eq!(ast.participant_order(), order.as_slice());
+    assert_eq!(ast.participants_in_order(), vec![&id("p:b"), &id("p:a"), &id("p:c")]);
+
+    apply_ops(&mut diagram, 2, &[Op::Seq(SeqOp::SetParticipantOrder { order: vec![id("p:x")] })])
+        .unwrap_err();
+
+    apply_ops(&mut diagram, 2, &[Op::Seq(SeqOp::RemoveParticipant { participant_id: id("p:b") })])
+        .expect("remove participant");
+    let DiagramAst::Sequence(ast) = diagram.ast() else {
+        panic!("expected sequence ast");
+    };
+    assert_eq!(ast.participant_order(), &[id("p:a")]);
+}
+
+#[test]
+fn apply_flow_set_node_order_hints_records_changed_nodes_and_rejects_unknown() {
+    let diagram_id = DiagramId::new("d:flow-hints").expect("diagram id");
+    let mut diagram = crate::model::Diagram::new(
+        diagram_id,
+        "flow",
+        DiagramAst::Flowchart(FlowchartAst::default()),
+    );
+
+    let n1 = ObjectId::new("n:1").expect("node id");
+    let n2 = ObjectId::new("n:2").expect("node id");
+    apply_ops(
+        &mut diagram,
+        0,
+        &[
+            Op::Flow(FlowOp::AddNode { node_id: n1.clone(), label: "One".to_owned(), shape: None }),
+            Op::Flow(FlowOp::AddNode { node_id: n2.clone(), label: "Two".to_owned(), shape: None }),
+        ],
+    )
+    .expect("setup apply");
+
+    let hints = std::collections::BTreeMap::from([(n1.clone(), 1), (n2.clone(), 0)]);
+    let result = apply_ops(
+        &mut diagram,
+        1,
+        &[Op::Flow(FlowOp::SetNodeOrderHints { order_hints: hints.clone() })],
+    )
+    .expect("apply hints");
+    assert_eq!(result.delta.updated.len(), 2);
+
+    let DiagramAst::Flowchart(ast) = diagram.ast() else {
+        panic!("expected flowchart ast");
+    };
+    assert_eq!(ast.node_order_hints(), &hints);
+
+    let unknown = std::collections::BTreeMap::from([(ObjectId::new("n:x").expect("id"), 0)]);
+    apply_ops(&mut diagram, 2, &[Op::Flow(FlowOp::SetNodeOrderHints { order_hints: unknown })])
+        .unwrap_err();
+
+    let result = apply_ops(&mut diagram, 2, &[Op::Flow(FlowOp::RemoveNode { node_id: n1 })])
+        .expect("remove node");
+    assert_eq!(result.new_rev, 3);
+    let DiagramAst::Flowchart(ast) = diagram.ast() else {
+        panic!("expected flowchart ast");
+    };
+    assert_eq!(ast.node_order_hints().keys().collect::<Vec<_>>(), vec![&n2]);
+}
+
+#[test]
+fn apply_flow_set_node_entry_toggles_membership_and_drops_removed_nodes() {
+    let diagram_id = DiagramId::new("d:flow-entry").expect("diagram id");
+    let mut diagram = crate::model::Diagram::new(
+        diagram_id,
+        "flow",
+        DiagramAst::Flowchart(FlowchartAst::default()),
+    );
+
+    let n1 = ObjectId::new("n:1").expect("node id");
+    let n2 = ObjectId::new("n:2").expect("node id");
+    apply_ops(
+        &mut diagram,
+        0,
+        &[
+            Op::Flow(FlowOp::AddNode { node_id: n1.clone(), label: "One".to_owned(), shape: None }),
+            Op::Flow(FlowOp::AddNode { node_id: n2.clone(), label: "Two".to_owned(), shape: None }),
+            Op::Flow(FlowOp::SetNodeEntry { node_id: n1.clone(), entry: true }),
+            Op::Flow(FlowOp::SetNodeEntry { node_id: n2.clone(), entry: true }),
+        ],
+    )
+    .expect("setup apply");
+
+    let result = apply_ops(
+        &mut diagram,
+        1,
+        &[
+            Op::Flow(FlowOp::SetNodeEntry { node_id: n1.clone(), entry: true }),
+            Op::Flow(FlowOp::SetNodeEntry { node_id: n2.clone(), entry: false }),
+        ],
+    )
+    .expect("toggle entry");
+    assert_eq!(result.delta.updated.len(), 1);
+    let DiagramAst::Flowchart(ast) = diagram.ast() else {
+        panic!("expected flowchart ast");
+    };
+    assert!(ast.is_entry_node(&n1));
+    assert!(!ast.is_entry_node(&n2));
+
+    let unknown = ObjectId::new("n:x").expect("id");
+    apply_ops(&mut diagram, 2, &[Op::Flow(FlowOp::SetNodeEntry { node_id: unknown, entry: true })])
+        .unwrap_err();
+
+    apply_ops(&mut diagram, 2, &[Op::Flow(FlowOp::RemoveNode { node_id: n1 })])
+        .expect("remove node");
+    let DiagramAst::Flowchart(ast) = diagram.ast() else {
+        panic!("expected flowchart ast");
+    };
+    assert!(ast.entry_nodes().is_empty());
+}
+
+#[test]
+fn apply_flow_set_node_pin_pins_unpins_and_drops_removed_nodes() {
+    let diagram_id = DiagramId::new("d:flow-pin").expect("diagram id");
+    let mut diagram = crate::model::Diagram::new(
+        diagram_id,
+        "flow",
+        DiagramAst::Flowchart(FlowchartAst::default()),
+    );
+
+    let n1 = ObjectId::new("n:1").expect("node id");
+    let pin = Some(FlowNodePin::new(1, 0));
+    let result = apply_ops(
+        &mut diagram,
+        0,
+        &[
+            Op::Flow(FlowOp::AddNode { node_id: n1.clone(), label: "One".to_owned(), shape: None }),
+            Op::Flow(FlowOp::SetNodePin { node_id: n1.clone(), pin }),
+        ],
+    )
+    .expect("pin node");
+    assert_eq!(result.delta.added.len(), 1);
+    let DiagramAst::Flowchart(ast) = diagram.ast() else {
+        panic!("expected flowchart ast");
+    };
+    assert_eq!(ast.node_pins().get(&n1).copied(), pin);
+
+    // Re-pinning to the same spot changes nothing.
+    let result =
+        apply_ops(&mut diagram, 1, &[Op::Flow(FlowOp::SetNodePin { node_id: n1.clone(), pin })])
+            .expect("repin");
+    assert!(result.delta.updated.is_empty());
+
+    let unknown = ObjectId::new("n:x").expect("id");
+    apply_ops(&mut diagram, 2, &[Op::Flow(FlowOp::SetNodePin { node_id: unknown, pin })])
+        .unwrap_err();
+
+    apply_ops(&mut diagram, 2, &[Op::Flow(FlowOp::RemoveNode { node_id: n1 })])
+        .expect("remove node");
+    let DiagramAst::Flowchart(ast) = diagram.ast() else {
+        panic!("expected flowchart ast");
+    };
+    assert!(ast.node_pins().is_empty());
+}
+
 #[test]
 fn apply_flow_edge_label_style_updates_and_are_preserved_on_endpoint_changes() {
     let diagram_id = DiagramId::new("d:edge-patch").expect("diagram id");
@@ -751,3 +1176,68 @@ fn apply_seq_remove_participant_records_cascading_message_removal_in_delta() {
     assert!(ast.participants().contains_key(&bob));
     assert!(ast.messages().is_empty());
 }
+
+#[test]
+fn apply_session_xref_ops_recompute_status_and_record_endpoint_delta() {
+    let diagram_id = DiagramId::new("x").expect("diagram id");
+    let mut ast = FlowchartAst::default();
+    ast.nodes_mut()
+        .insert(ObjectId::new("n:a").expect("node id"), crate::model::FlowNode::new("A"));
+    let mut session = Session::new(SessionId::new("s:x").expect("session id"));
+    session.diagrams_mut().insert(
+        diagram_id.clone(),
+        crate::model::Diagram::new(diagram_id, "flow", DiagramAst::Flowchart(ast)),
+    );
+
+    let node_a = ObjectRef::parse("d:x/flow/node/n:a").expect("ref");
+    let node_b = ObjectRef::parse("d:x/flow/node/n:b").expect("ref");
+    let xref_id = XRefId::new("x:1").expect("xref id");
+
+    let add = [Op::XRef(XRefOp::Add {
+        xref_id: xref_id.clone(),
+        from: node_a.clone(),
+        to: node_b.clone(),
+        kind: "relates_to".to_owned(),
+        label: Some("link".to_owned()),
+    })];
+    let result = apply_session_ops(&mut session, &add).expect("add");
+    assert_eq!(result.applied, 1);
+    assert_eq!(result.delta.updated, vec![node_a.clone(), node_b.clone()]);
+    assert_eq!(session.xrefs()[&xref_id].status(), XRefStatus::DanglingTo);
+    assert_eq!(
+        apply_session_ops(&mut session, &add),
+        Err(ApplyError::XRefAlreadyExists { xref_id: xref_id.clone() })
+    );
+
+    let update = [Op::XRef(XRefOp::Update {
+        xref_id: xref_id.clone(),
+        patch: XRefPatch { to: Some(node_a.clone()), ..XRefPatch::default() },
+    })];
+    let result = apply_session_ops(&mut session, &update).expect("update");
+    assert_eq!(result.delta.updated, vec![node_a.clone(), node_b]);
+    let xref = &session.xrefs()[&xref_id];
+    assert_eq!(xref.status(), XRefStatus::Ok);
+    assert_eq!((xref.kind(), xref.label()), ("relates_to", Some("link")));
+
+    // A failing op leaves every earlier op in the batch unapplied.
+    let missing = XRefId::new("x:missing").expect("xref id");
+    let batch = [
+        Op::XRef(XRefOp::Remove { xref_id: xref_id.clone() }),
+        Op::XRef(XRefOp::Remove { xref_id: missing.clone() }),
+    ];
+    assert_eq!(
+        apply_session_ops(&mut session, &batch),
+        Err(ApplyError::XRefNotFound { xref_id: missing })
+    );
+    assert!(session.xrefs().contains_key(&xref_id));
+
+    let result = apply_session_ops(&mut session, &batch[..1]).expect("remove");
+    assert_eq!(result.delta.updated, vec![node_a]);
+    assert!(session.xrefs().is_empty());
+
+    let flow = [Op::Flow(FlowOp::RemoveNode { node_id: ObjectId::new("n:a").expect("node id") })];
+    assert_eq!(
+        apply_session_ops(&mut session, &flow),
+        Err(ApplyError::UnsupportedOp { op_kind: OpKind::Flow })
+    );
+}
@@ -0,0 +1,203 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Structural comparison of two versions of a diagram.
+//!
+//! Objects are matched by ref and compared by their [`ObjectFacts`], so a diff reads the same
+//! for every diagram kind: what was added, what was removed and which facts of the rest changed.
+//! Ordering alone (e.g. a sequence message moving) is not a change.
+
+use std::collections::BTreeMap;
+
+use crate::model::{Diagram, ObjectRef, Session};
+
+use super::objects::{object_facts, ObjectFacts};
+
+/// How an object differs between the two versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiffStatus {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl DiffStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+            Self::Changed => "changed",
+        }
+    }
+}
+
+/// One object that differs; `fields` names the facts that changed (`label`, `note`, `tags`,
+/// `metadata`, `anchors`, `links`) and is empty for added and removed objects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectDiff {
+    pub object_ref: ObjectRef,
+    pub status: DiffStatus,
+    pub fields: Vec<&'static str>,
+}
+
+/// Every object that differs between two versions of a diagram, in ref order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiagramDiff {
+    pub objects: Vec<ObjectDiff>,
+}
+
+impl DiagramDiff {
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    pub fn status(&self, object_ref: &ObjectRef) -> Option<DiffStatus> {
+        self.objects.iter().find(|diff| &diff.object_ref == object_ref).map(|diff| diff.status)
+    }
+
+    pub fn count(&self, status: DiffStatus) -> usize {
+        self.objects.iter().filter(|diff| diff.status == status).count()
+    }
+
+    /// The refs with `status`, in ref order.
+    pub fn refs(&self, status: DiffStatus) -> impl Iterator<Item = &ObjectRef> {
+        self.objects.iter().filter(move |diff| diff.status == status).map(|diff| &diff.object_ref)
+    }
+}
+
+/// Compares `before` with `after`; both are read as versions of the same diagram, so objects
+/// match by category and id. Xref counts are left out since xrefs belong to the session.
+pub fn diff_diagrams(session: &Session, before: &Diagram, after: &Diagram) -> DiagramDiff {
+    let facts_by_ref = |diagram: &Diagram| {
+        object_facts(session, diagram)
+            .into_iter()
+            .map(|facts| (facts.object_ref.clone(), facts))
+            .collect::<BTreeMap<_, _>>()
+    };
+    let before = facts_by_ref(before);
+    let after = facts_by_ref(after);
+
+    let mut objects = Vec::new();
+    for (object_ref, old) in &before {
+        match after.get(object_ref) {
+            None => objects.push(ObjectDiff {
+                object_ref: object_ref.clone(),
+                status: DiffStatus::Removed,
+                fields: Vec::new(),
+            }),
+            Some(new) => {
+                let fields = changed_fields(old, new);
+                if !fields.is_empty() {
+                    objects.push(ObjectDiff {
+                        object_ref: object_ref.clone(),
+                        status: DiffStatus::Changed,
+                        fields,
+                    });
+                }
+            }
+        }
+    }
+    for object_ref in after.keys().filter(|object_ref| !before.contains_key(*object_ref)) {
+        objects.push(ObjectDiff {
+            object_ref: object_ref.clone(),
+            status: DiffStatus::Added,
+            fields: Vec::new(),
+        });
+    }
+    objects.sort_by(|a, b| a.object_ref.cmp(&b.object_ref));
+    DiagramDiff { objects }
+}
+
+fn changed_fields(old: &ObjectFacts, new: &ObjectFacts) -> Vec<&'static str> {
+    let sorted = |items: &[String]| {
+        let mut items = items.to_vec();
+        items.sort();
+        items
+    };
+    let sorted_refs = |refs: &[ObjectRef]| {
+        let mut refs = refs.to_vec();
+        refs.sort();
+        refs
+    };
+
+    let mut fields = Vec::new();
+    if old.label != new.label {
+        fields.push("label");
+    }
+    if old.note != new.note {
+        fields.push("note");
+    }
+    if sorted(&old.tags) != sorted(&new.tags) {
+        fields.push("tags");
+    }
+    if old.metadata != new.metadata {
+        fields.push("metadata");
+    }
+    if sorted_refs(&old.anchors) != sorted_refs(&new.anchors) {
+        fields.push("anchors");
+    }
+    if (old.incoming, old.outgoing) != (new.incoming, new.outgoing) {
+        fields.push("links");
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_diagrams, DiffStatus};
+    use crate::format::mermaid::{parse_flowchart, parse_sequence_diagram};
+    use crate::model::{Diagram, DiagramAst, DiagramId, Session, SessionId};
+
+    fn diagram(ast: DiagramAst) -> Diagram {
+        Diagram::new(DiagramId::new("d").expect("diagram id"), "D", ast)
+    }
+
+    fn statuses(diff: &super::DiagramDiff) -> Vec<(String, DiffStatus, Vec<&'static str>)> {
+        diff.objects
+            .iter()
+            .map(|diff| (diff.object_ref.to_string(), diff.status, diff.fields.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn classifies_flowchart_objects_as_added_removed_and_changed() {
+        let session = Session::new(SessionId::new("s").expect("session id"));
+        let flow = |src: &str| diagram(DiagramAst::Flowchart(parse_flowchart(src).expect("parse")));
+        let before = flow("flowchart LR\nA[Start] --> B[Mid]\nB --> C[End]\n");
+        let after = flow("flowchart LR\nA[Begin] --> B[Mid]\nB --> D((Done))\n");
+
+        let diff = diff_diagrams(&session, &before, &after);
+        assert_eq!(
+            statuses(&diff),
+            vec![
+                ("d:d/flow/edge/e:0002".to_owned(), DiffStatus::Changed, vec!["anchors"]),
+                ("d:d/flow/node/n:A".to_owned(), DiffStatus::Changed, vec!["label"]),
+                ("d:d/flow/node/n:C".to_owned(), DiffStatus::Removed, vec![]),
+                ("d:d/flow/node/n:D".to_owned(), DiffStatus::Added, vec![]),
+            ]
+        );
+        assert_eq!(diff.count(DiffStatus::Changed), 2);
+        assert!(diff_diagrams(&session, &after, &after).is_empty());
+    }
+
+    #[test]
+    fn message_text_edits_are_changes_and_new_notes_additions() {
+        let session = Session::new(SessionId::new("s").expect("session id"));
+        let seq =
+            |src: &str| diagram(DiagramAst::Sequence(parse_sequence_diagram(src).expect("parse")));
+        let before = seq("sequenceDiagram\nparticipant A\nparticipant B\nA->>B: hi\n");
+        let after =
+            seq("sequenceDiagram\nparticipant A\nparticipant B\nA->>B: hello\nNote over B: n\n");
+
+        let diff = diff_diagrams(&session, &before, &after);
+        let message = diff.objects.iter().find(|diff| diff.fields.contains(&"label"));
+        assert!(message.is_some_and(|diff| diff.status == DiffStatus::Changed), "{diff:?}");
+        assert_eq!(diff.count(DiffStatus::Added), 1);
+        assert_eq!(diff.count(DiffStatus::Removed), 0);
+    }
+}
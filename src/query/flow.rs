@@ -6,7 +6,8 @@
 // This file is part of Nereid and is proprietary software.
 // Unauthorized copying, modification, or distribution is prohibited.
 
-use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, VecDeque};
 
 use crate::model::{FlowchartAst, ObjectId};
 
@@ -169,6 +170,211 @@ pub fn reachable(ast: &FlowchartAst, from_node_id: &ObjectId) -> Vec<ObjectId> {
     reachable_with_direction(ast, from_node_id, ReachDirection::Out)
 }
 
+/// Traversal roots: the explicitly marked entry nodes, or (when none are marked) every node
+/// without incoming edges, or every node when the graph has no such source.
+pub fn entry_points(ast: &FlowchartAst) -> Vec<ObjectId> {
+    let marked = ast
+        .entry_nodes()
+        .iter()
+        .filter(|node_id| ast.nodes().contains_key(*node_id))
+        .cloned()
+        .collect::<Vec<_>>();
+    if !marked.is_empty() {
+        return marked;
+    }
+
+    let sources = degrees(ast)
+        .into_iter()
+        .filter(|(node_id, degree)| degree.in_degree == 0 && ast.nodes().contains_key(node_id))
+        .map(|(node_id, _)| node_id)
+        .collect::<Vec<_>>();
+    if !sources.is_empty() {
+        return sources;
+    }
+
+    ast.nodes().keys().cloned().collect()
+}
+
+/// Nodes reachable from any of the `entry_points`, sorted and including the roots.
+pub fn reachable_from_entry_points(ast: &FlowchartAst) -> Vec<ObjectId> {
+    let known_nodes = ast.nodes().keys().cloned().collect::<BTreeSet<_>>();
+    let outgoing = outgoing_adjacency(ast);
+    let mut visited = BTreeSet::<ObjectId>::new();
+    for root in entry_points(ast) {
+        if !visited.contains(&root) {
+            visited.extend(bfs_reachable(&outgoing, &root, &known_nodes));
+        }
+    }
+    visited.into_iter().collect()
+}
+
+/// One hop of a weighted path: the node reached, the edge taken to reach it (`None` for the
+/// start node) and the total cost up to and including that edge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WeightedPathStep {
+    pub node_id: ObjectId,
+    pub via_edge_id: Option<ObjectId>,
+    pub cumulative_cost: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WeightedPath {
+    pub steps: Vec<WeightedPathStep>,
+    pub cost: u64,
+}
+
+impl WeightedPath {
+    /// `Label (cost)` for each step, joined by `separator`; unknown nodes show their id.
+    pub fn summary(&self, ast: &FlowchartAst, separator: &str) -> String {
+        self.steps
+            .iter()
+            .map(|step| {
+                let label = ast
+                    .nodes()
+                    .get(&step.node_id)
+                    .map_or(step.node_id.as_str(), |node| node.label());
+                format!("{label} ({})", step.cumulative_cost)
+            })
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+}
+
+/// The `(previous node, edge)` a node was reached through while building a weighted path.
+type Predecessor<'a> = Option<(&'a ObjectId, &'a ObjectId)>;
+
+/// Cheapest path from `from_node_id` to `to_node_id` using `FlowEdge::effective_weight` costs.
+///
+/// Ties are broken deterministically (lower node id first, then lower edge id), so repeated calls
+/// return the same path. Returns `None` when either node is unknown or `to` is unreachable.
+pub fn weighted_shortest_path(
+    ast: &FlowchartAst,
+    from_node_id: &ObjectId,
+    to_node_id: &ObjectId,
+) -> Option<WeightedPath> {
+    if !ast.nodes().contains_key(from_node_id) || !ast.nodes().contains_key(to_node_id) {
+        return None;
+    }
+
+    let mut outgoing: BTreeMap<&ObjectId, Vec<(&ObjectId, &ObjectId, u64)>> = BTreeMap::new();
+    for (edge_id, edge) in ast.edges() {
+        if ast.nodes().contains_key(edge.to_node_id()) {
+            outgoing.entry(edge.from_node_id()).or_default().push((
+                edge.to_node_id(),
+                edge_id,
+                edge.effective_weight(),
+            ));
+        }
+    }
+
+    let mut best: BTreeMap<&ObjectId, (u64, Predecessor<'_>)> = BTreeMap::new();
+    let mut done = BTreeSet::<&ObjectId>::new();
+    let mut queue = BinaryHeap::new();
+    best.insert(from_node_id, (0, None));
+    queue.push(Reverse((0u64, from_node_id)));
+
+    while let Some(Reverse((cost, node_id))) = queue.pop() {
+        if !done.insert(node_id) {
+            continue;
+        }
+        if node_id == to_node_id {
+            break;
+        }
+        for &(next_id, edge_id, weight) in outgoing.get(node_id).into_iter().flatten() {
+            let next_cost = cost.saturating_add(weight);
+            let improves = best.get(next_id).map_or(true, |(known, _)| next_cost < *known);
+            if improves && !done.contains(next_id) {
+                best.insert(next_id, (next_cost, Some((node_id, edge_id))));
+                queue.push(Reverse((next_cost, next_id)));
+            }
+        }
+    }
+
+    let (cost, _) = *best.get(to_node_id)?;
+    Some(trace_weighted_path(&best, to_node_id, cost))
+}
+
+/// Longest (critical) path through an acyclic flowchart, treating `FlowEdge::effective_weight` as
+/// the duration of each edge.
+///
+/// Every node may start the path. Ties prefer the lower node id at the end of the path and the
+/// earlier predecessor along it. Returns `None` for empty flowcharts and for flowcharts with a
+/// cycle, where the longest path is unbounded.
+pub fn critical_path(ast: &FlowchartAst) -> Option<WeightedPath> {
+    let mut outgoing: BTreeMap<&ObjectId, Vec<(&ObjectId, &ObjectId, u64)>> = BTreeMap::new();
+    let mut in_degree: BTreeMap<&ObjectId, usize> = ast.nodes().keys().map(|id| (id, 0)).collect();
+    for (edge_id, edge) in ast.edges() {
+        let (from_id, to_id) = (edge.from_node_id(), edge.to_node_id());
+        if !ast.nodes().contains_key(from_id) || !ast.nodes().contains_key(to_id) {
+            continue;
+        }
+        outgoing.entry(from_id).or_default().push((to_id, edge_id, edge.effective_weight()));
+        *in_degree.entry(to_id).or_default() += 1;
+    }
+
+    let mut best: BTreeMap<&ObjectId, (u64, Predecessor<'_>)> =
+        ast.nodes().keys().map(|id| (id, (0, None))).collect();
+    let mut ready = in_degree
+        .iter()
+        .filter(|(_, degree)| **degree == 0)
+        .map(|(id, _)| *id)
+        .collect::<BTreeSet<_>>();
+    let mut visited = 0usize;
+    while let Some(node_id) = ready.pop_first() {
+        visited += 1;
+        let (cost, _) = best[node_id];
+        for &(next_id, edge_id, weight) in outgoing.get(node_id).into_iter().flatten() {
+            let next_cost = cost.saturating_add(weight);
+            let entry = best.get_mut(next_id).expect("known node");
+            if next_cost > entry.0 {
+                *entry = (next_cost, Some((node_id, edge_id)));
+            }
+            let degree = in_degree.get_mut(next_id).expect("known node");
+            *degree -= 1;
+            if *degree == 0 {
+                ready.insert(next_id);
+            }
+        }
+    }
+    if visited < ast.nodes().len() {
+        return None;
+    }
+
+    let mut end: Option<(&ObjectId, u64)> = None;
+    for (node_id, (cost, _)) in &best {
+        if end.map_or(true, |(_, best_cost)| *cost > best_cost) {
+            end = Some((node_id, *cost));
+        }
+    }
+    let (end_id, cost) = end?;
+    Some(trace_weighted_path(&best, end_id, cost))
+}
+
+/// Walks predecessor links back from `end_id` and returns the path in forward order.
+fn trace_weighted_path(
+    best: &BTreeMap<&ObjectId, (u64, Predecessor<'_>)>,
+    end_id: &ObjectId,
+    cost: u64,
+) -> WeightedPath {
+    let mut steps = Vec::new();
+    let mut cursor = end_id;
+    loop {
+        let (cumulative_cost, via) = best[cursor];
+        steps.push(WeightedPathStep {
+            node_id: cursor.clone(),
+            via_edge_id: via.map(|(_, edge_id)| edge_id.clone()),
+            cumulative_cost,
+        });
+        match via {
+            Some((prev_id, _)) => cursor = prev_id,
+            None => break,
+        }
+    }
+    steps.reverse();
+
+    WeightedPath { steps, cost }
+}
+
 pub fn paths(
     ast: &FlowchartAst,
     from_node_id: &ObjectId,
@@ -240,6 +446,41 @@ pub fn paths(
     results
 }
 
+/// Nodes and edges along the paths [`paths`] finds between two nodes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlowRoute {
+    pub paths: Vec<Vec<ObjectId>>,
+    /// Every node on any of the paths, sorted.
+    pub node_ids: Vec<ObjectId>,
+    /// Every edge joining consecutive nodes of a path, parallel edges included, sorted.
+    pub edge_ids: Vec<ObjectId>,
+}
+
+/// The route from `from_node_id` to `to_node_id`: the shortest path for `limit` 1 and
+/// `max_extra_hops` 0, otherwise up to `limit` paths as in [`paths`]. Empty when `to` is
+/// unreachable.
+pub fn route(
+    ast: &FlowchartAst,
+    from_node_id: &ObjectId,
+    to_node_id: &ObjectId,
+    limit: usize,
+    max_extra_hops: usize,
+) -> FlowRoute {
+    let paths = paths(ast, from_node_id, to_node_id, limit, max_extra_hops);
+    let node_ids = paths.iter().flatten().cloned().collect::<BTreeSet<_>>();
+    let hops = paths
+        .iter()
+        .flat_map(|path| path.windows(2).map(|hop| (&hop[0], &hop[1])))
+        .collect::<BTreeSet<_>>();
+    let edge_ids = ast
+        .edges()
+        .iter()
+        .filter(|(_, edge)| hops.contains(&(edge.from_node_id(), edge.to_node_id())))
+        .map(|(edge_id, _)| edge_id.clone())
+        .collect();
+    FlowRoute { paths, node_ids: node_ids.into_iter().collect(), edge_ids }
+}
+
 pub fn cycles(ast: &FlowchartAst) -> Vec<Vec<ObjectId>> {
     let outgoing = outgoing_adjacency(ast);
 
@@ -341,10 +582,194 @@ pub fn dead_ends(ast: &FlowchartAst) -> Vec<ObjectId> {
         .collect()
 }
 
+/// Immediate dominator of every node reachable from `entries`: the last node that every path
+/// from an entry passes before reaching it. Entries, and nodes reachable from several entries
+/// without a shared node on the way, map to `None`.
+pub fn immediate_dominators(
+    ast: &FlowchartAst,
+    entries: &[ObjectId],
+) -> BTreeMap<ObjectId, Option<ObjectId>> {
+    let outgoing = outgoing_adjacency(ast);
+    let node_ids = outgoing.keys().collect::<Vec<_>>();
+    let index = node_ids.iter().enumerate().map(|(i, id)| (*id, i)).collect::<BTreeMap<_, _>>();
+    // A virtual root above the entries keeps several entry points in one tree.
+    let root = node_ids.len();
+    let mut successors = node_ids
+        .iter()
+        .map(|id| outgoing[*id].iter().map(|next| index[next]).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    successors.push(
+        entries
+            .iter()
+            .filter_map(|id| index.get(id).copied())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect(),
+    );
+
+    let mut postorder = Vec::new();
+    let mut visited = vec![false; root + 1];
+    visited[root] = true;
+    let mut stack = vec![(root, 0usize)];
+    while let Some((node, next)) = stack.last_mut() {
+        let node = *node;
+        match successors[node].get(*next) {
+            Some(&succ) => {
+                *next += 1;
+                if !visited[succ] {
+                    visited[succ] = true;
+                    stack.push((succ, 0));
+                }
+            }
+            None => {
+                postorder.push(node);
+                stack.pop();
+            }
+        }
+    }
+    let mut position = vec![usize::MAX; root + 1];
+    for (i, &node) in postorder.iter().enumerate() {
+        position[node] = i;
+    }
+    let mut predecessors = vec![Vec::new(); root + 1];
+    for &node in &postorder {
+        for &succ in &successors[node] {
+            predecessors[succ].push(node);
+        }
+    }
+
+    // Cooper, Harvey and Kennedy's iterative algorithm over reverse postorder.
+    let mut idom = vec![None; root + 1];
+    idom[root] = Some(root);
+    let intersect = |idom: &[Option<usize>], mut a: usize, mut b: usize| {
+        while a != b {
+            while position[a] < position[b] {
+                a = idom[a].expect("processed node");
+            }
+            while position[b] < position[a] {
+                b = idom[b].expect("processed node");
+            }
+        }
+        a
+    };
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &node in postorder.iter().rev().filter(|&&node| node != root) {
+            let new_idom = predecessors[node]
+                .iter()
+                .copied()
+                .filter(|&pred| idom[pred].is_some())
+                .reduce(|a, b| intersect(&idom, a, b));
+            if new_idom.is_some() && idom[node] != new_idom {
+                idom[node] = new_idom;
+                changed = true;
+            }
+        }
+    }
+
+    postorder
+        .into_iter()
+        .filter(|&node| node != root)
+        .map(|node| {
+            let dominator = idom[node].filter(|&dominator| dominator != root);
+            (node_ids[node].clone(), dominator.map(|dominator| node_ids[dominator].clone()))
+        })
+        .collect()
+}
+
+/// Nodes and edges whose removal disconnects the flowchart when edge directions are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlowCutPoints {
+    /// Articulation points, sorted.
+    pub nodes: Vec<ObjectId>,
+    /// Bridge edges, sorted; an edge with a parallel twin is never one.
+    pub bridges: Vec<ObjectId>,
+}
+
+pub fn cut_points(ast: &FlowchartAst) -> FlowCutPoints {
+    let outgoing = outgoing_adjacency(ast);
+    let node_ids = outgoing.keys().collect::<Vec<_>>();
+    let index = node_ids.iter().enumerate().map(|(i, id)| (*id, i)).collect::<BTreeMap<_, _>>();
+    let edge_ids = ast.edges().keys().collect::<Vec<_>>();
+    let mut adjacency = vec![Vec::new(); node_ids.len()];
+    for (edge, flow_edge) in ast.edges().values().enumerate() {
+        let (from, to) = (index[flow_edge.from_node_id()], index[flow_edge.to_node_id()]);
+        if from != to {
+            adjacency[from].push((to, edge));
+            adjacency[to].push((from, edge));
+        }
+    }
+
+    // Iterative Tarjan: discovery times and low links over an undirected DFS forest.
+    let mut discovered = vec![usize::MAX; node_ids.len()];
+    let mut low = vec![0; node_ids.len()];
+    let mut is_cut = vec![false; node_ids.len()];
+    let mut bridges = Vec::new();
+    let mut time = 0;
+    for root in 0..node_ids.len() {
+        if discovered[root] != usize::MAX {
+            continue;
+        }
+        discovered[root] = time;
+        low[root] = time;
+        time += 1;
+        let mut root_children = 0;
+        let mut stack = vec![(root, usize::MAX, 0usize)];
+        while let Some((node, parent_edge, next)) = stack.last_mut() {
+            let (node, parent_edge) = (*node, *parent_edge);
+            if let Some(&(neighbor, edge)) = adjacency[node].get(*next) {
+                *next += 1;
+                if edge == parent_edge {
+                    continue;
+                }
+                if discovered[neighbor] == usize::MAX {
+                    discovered[neighbor] = time;
+                    low[neighbor] = time;
+                    time += 1;
+                    if node == root {
+                        root_children += 1;
+                    }
+                    stack.push((neighbor, edge, 0));
+                } else {
+                    low[node] = low[node].min(discovered[neighbor]);
+                }
+                continue;
+            }
+            stack.pop();
+            if let Some(&(parent, _, _)) = stack.last() {
+                low[parent] = low[parent].min(low[node]);
+                if low[node] > discovered[parent] {
+                    bridges.push(edge_ids[parent_edge].clone());
+                }
+                if parent != root && low[node] >= discovered[parent] {
+                    is_cut[parent] = true;
+                }
+            }
+        }
+        if root_children >= 2 {
+            is_cut[root] = true;
+        }
+    }
+
+    bridges.sort();
+    FlowCutPoints {
+        nodes: node_ids
+            .into_iter()
+            .zip(is_cut)
+            .filter(|(_, cut)| *cut)
+            .map(|(node_id, _)| node_id.clone())
+            .collect(),
+        bridges,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        cycles, dead_ends, degrees, paths, reachable, reachable_with_direction, ReachDirection,
+        critical_path, cut_points, cycles, dead_ends, degrees, entry_points, immediate_dominators,
+        paths, reachable, reachable_from_entry_points, reachable_with_direction, route,
+        weighted_shortest_path, ReachDirection,
     };
     use crate::model::{FlowEdge, FlowNode, FlowchartAst, ObjectId};
 
@@ -463,4 +888,161 @@ mod tests {
         let results = dead_ends(&ast);
         assert_eq!(ids(&results), vec!["n:e", "n:f"]);
     }
+
+    #[test]
+    fn entry_points_prefer_marked_nodes_over_sources() {
+        let mut ast = fixture_ast();
+        assert_eq!(ids(&entry_points(&ast)), vec!["n:a", "n:f"]);
+
+        ast.entry_nodes_mut().insert(ObjectId::new("n:d").expect("d"));
+        ast.entry_nodes_mut().insert(ObjectId::new("n:x").expect("x"));
+        assert_eq!(ids(&entry_points(&ast)), vec!["n:d", "n:x"]);
+        assert_eq!(
+            ids(&reachable_from_entry_points(&ast)),
+            vec!["n:c", "n:d", "n:e", "n:x", "n:y"]
+        );
+    }
+
+    #[test]
+    fn weighted_shortest_path_follows_cheapest_route_with_cumulative_costs() {
+        let mut ast = fixture_ast();
+        let from = ObjectId::new("n:a").expect("from node id");
+        let to = ObjectId::new("n:e").expect("to node id");
+
+        let unweighted = weighted_shortest_path(&ast, &from, &to).expect("path");
+        assert_eq!(unweighted.cost, 3);
+        assert_eq!(
+            ids(&unweighted.steps.iter().map(|step| step.node_id.clone()).collect::<Vec<_>>()),
+            vec!["n:a", "n:b", "n:c", "n:e"]
+        );
+
+        let ab = ObjectId::new("e:ab").expect("edge id");
+        ast.edges_mut().get_mut(&ab).expect("edge").set_weight(Some(5));
+        ast.edges_mut()
+            .get_mut(&ObjectId::new("e:dc").expect("id"))
+            .expect("edge")
+            .set_label(Some("2"));
+
+        let weighted = weighted_shortest_path(&ast, &from, &to).expect("path");
+        let hops = weighted
+            .steps
+            .iter()
+            .map(|step| {
+                (
+                    step.node_id.as_str(),
+                    step.via_edge_id.as_ref().map(|id| id.as_str()),
+                    step.cumulative_cost,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            hops,
+            vec![
+                ("n:a", None, 0),
+                ("n:d", Some("e:ad"), 1),
+                ("n:c", Some("e:dc"), 3),
+                ("n:e", Some("e:ce"), 4),
+            ]
+        );
+        assert_eq!(weighted.cost, 4);
+        assert_eq!(weighted.summary(&ast, " -> "), "N:A (0) -> N:D (1) -> N:C (3) -> N:E (4)");
+        ast.nodes_mut().remove(&ObjectId::new("n:d").expect("node id"));
+        assert_eq!(weighted.summary(&ast, " → "), "N:A (0) → n:d (1) → N:C (3) → N:E (4)");
+
+        let unreachable = ObjectId::new("n:f").expect("node id");
+        assert_eq!(weighted_shortest_path(&ast, &from, &unreachable), None);
+    }
+
+    #[test]
+    fn critical_path_is_the_longest_weighted_chain_of_a_dag() {
+        let mut ast = fixture_ast();
+        assert_eq!(critical_path(&ast), None);
+
+        for node in ["n:x", "n:y", "n:z"] {
+            ast.nodes_mut().remove(&ObjectId::new(node).expect("node id"));
+        }
+        ast.edges_mut().retain(|edge_id, _| !["e:xy", "e:yx", "e:zz"].contains(&edge_id.as_str()));
+
+        let unweighted = critical_path(&ast).expect("critical path");
+        assert_eq!(unweighted.cost, 4);
+        assert_eq!(
+            ids(&unweighted.steps.iter().map(|step| step.node_id.clone()).collect::<Vec<_>>()),
+            vec!["n:a", "n:b", "n:d", "n:c", "n:e"]
+        );
+
+        ast.edges_mut()
+            .get_mut(&ObjectId::new("e:ad").expect("edge id"))
+            .expect("edge")
+            .set_weight(Some(5));
+        let weighted = critical_path(&ast).expect("critical path");
+        let hops = weighted
+            .steps
+            .iter()
+            .map(|step| (step.node_id.as_str(), step.cumulative_cost))
+            .collect::<Vec<_>>();
+        assert_eq!(hops, vec![("n:a", 0), ("n:d", 5), ("n:c", 6), ("n:e", 7)]);
+        assert_eq!(weighted.cost, 7);
+    }
+
+    #[test]
+    fn immediate_dominators_follow_every_path_from_the_entries() {
+        let ast = fixture_ast();
+        let a = ObjectId::new("n:a").expect("node id");
+        let idoms = immediate_dominators(&ast, std::slice::from_ref(&a))
+            .into_iter()
+            .map(|(node, idom)| (node.to_string(), idom.map(|idom| idom.to_string())))
+            .collect::<Vec<_>>();
+        let expected = [
+            ("n:a", None),
+            ("n:b", Some("n:a")),
+            ("n:c", Some("n:a")),
+            ("n:d", Some("n:a")),
+            ("n:e", Some("n:c")),
+        ];
+        assert_eq!(idoms, expected.map(|(node, idom)| (node.to_owned(), idom.map(str::to_owned))));
+
+        // Two entries meeting in `n:c` share no dominator; `n:e` still needs `n:c`.
+        let d = ObjectId::new("n:d").expect("node id");
+        let e = ObjectId::new("n:e").expect("node id");
+        let c = ObjectId::new("n:c").expect("node id");
+        let mut ast = ast;
+        ast.edges_mut().remove(&ObjectId::new("e:ad").expect("edge id"));
+        ast.edges_mut().remove(&ObjectId::new("e:bd").expect("edge id"));
+        let idoms = immediate_dominators(&ast, &[a, d]);
+        assert_eq!(idoms[&c], None);
+        assert_eq!(idoms[&e], Some(c));
+        assert!(!idoms.contains_key(&ObjectId::new("n:x").expect("node id")));
+    }
+
+    #[test]
+    fn cut_points_find_articulation_nodes_and_bridges_ignoring_direction() {
+        let ast = fixture_ast();
+        let cuts = cut_points(&ast);
+        assert_eq!(ids(&cuts.nodes), vec!["n:c"]);
+        assert_eq!(ids(&cuts.bridges), vec!["e:ce"]);
+
+        // A parallel edge keeps `x` and `y` connected, so neither edge is a bridge.
+        let mut ast = ast;
+        ast.edges_mut().remove(&ObjectId::new("e:yx").expect("edge id"));
+        assert_eq!(ids(&cut_points(&ast).bridges), vec!["e:ce", "e:xy"]);
+    }
+
+    #[test]
+    fn route_collects_nodes_and_edges_along_shortest_or_bounded_paths() {
+        let ast = fixture_ast();
+        let a = ObjectId::new("n:a").expect("a");
+        let e = ObjectId::new("n:e").expect("e");
+
+        let shortest = route(&ast, &a, &e, 1, 0);
+        assert_eq!(paths_ids(&shortest.paths), vec![vec!["n:a", "n:b", "n:c", "n:e"]]);
+        assert_eq!(ids(&shortest.node_ids), vec!["n:a", "n:b", "n:c", "n:e"]);
+        assert_eq!(ids(&shortest.edge_ids), vec!["e:ab", "e:bc", "e:ce"]);
+
+        let bounded = route(&ast, &a, &e, 10, 1);
+        assert_eq!(bounded.paths.len(), 3);
+        assert_eq!(ids(&bounded.node_ids), vec!["n:a", "n:b", "n:c", "n:d", "n:e"]);
+        assert_eq!(ids(&bounded.edge_ids), vec!["e:ab", "e:ad", "e:bc", "e:bd", "e:ce", "e:dc"]);
+
+        assert_eq!(route(&ast, &e, &a, 1, 0), Default::default());
+    }
 }
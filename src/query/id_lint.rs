@@ -0,0 +1,72 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Lint for object ids that break the session's id prefix conventions.
+
+use crate::model::{IdPrefixRule, ObjectRef, Session};
+
+use super::objects::object_facts;
+
+/// An object whose id carries none of the prefixes its category's rule allows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdPrefixViolation {
+    pub object_ref: ObjectRef,
+    pub rule: IdPrefixRule,
+}
+
+/// Every object of a non-archived diagram whose id breaks an id prefix rule for its category,
+/// ordered by diagram id. Categories without a rule are not checked.
+pub fn id_prefix_violations(session: &Session) -> Vec<IdPrefixViolation> {
+    let rules = session.id_prefix_rules();
+    if rules.is_empty() {
+        return Vec::new();
+    }
+
+    let mut violations = Vec::new();
+    for diagram in session.diagrams().values().filter(|diagram| !diagram.is_archived()) {
+        for facts in object_facts(session, diagram) {
+            let object_ref = facts.object_ref;
+            let broken = rules.iter().find(|rule| {
+                rule.category() == object_ref.category() && !rule.allows(object_ref.object_id())
+            });
+            if let Some(rule) = broken {
+                violations.push(IdPrefixViolation { object_ref, rule: rule.clone() });
+            }
+        }
+    }
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::id_prefix_violations;
+    use crate::format::mermaid::parse_flowchart;
+    use crate::model::{Diagram, DiagramAst, DiagramId, Session, SessionId};
+
+    #[test]
+    fn reports_objects_whose_ids_break_their_category_rule() {
+        let ast = parse_flowchart("flowchart LR\nA --> B\n").expect("parse");
+        let mut session = Session::new(SessionId::new("s").expect("session id"));
+        let diagram_id = DiagramId::new("flow").expect("diagram id");
+        session.diagrams_mut().insert(
+            diagram_id.clone(),
+            Diagram::new(diagram_id, "Flow", DiagramAst::Flowchart(ast)),
+        );
+        assert!(id_prefix_violations(&session).is_empty());
+
+        session.set_id_prefix_rules([
+            "flow/node => svc|n".parse().expect("rule"),
+            "flow/edge => call".parse().expect("rule"),
+        ]);
+        let violations = id_prefix_violations(&session)
+            .into_iter()
+            .map(|violation| format!("{} ({})", violation.object_ref, violation.rule))
+            .collect::<Vec<_>>();
+        assert_eq!(violations, vec!["d:flow/flow/edge/e:0001 (flow/edge => call)"]);
+    }
+}
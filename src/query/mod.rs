@@ -10,6 +10,13 @@
 //!
 //! Queries provide derived views (e.g. routes/relationships) that power the UI and MCP tools.
 
+pub mod diff;
 pub mod flow;
+pub mod id_lint;
+pub mod objects;
+pub mod scaffold;
+pub mod search;
 pub mod sequence;
 pub mod session_routes;
+pub mod subgraph;
+pub mod validate;
@@ -0,0 +1,409 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Per-object facts (derived tags, anchors, degree) and the conditional styles they trigger.
+
+use std::collections::BTreeMap;
+
+use crate::model::{
+    CategoryPath, Diagram, DiagramAst, ObjectAnnotations, ObjectId, ObjectRef, ObjectStyle, Session,
+};
+
+/// What is known about one object without looking at its rendering.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectFacts {
+    pub object_ref: ObjectRef,
+    pub label: String,
+    pub note: Option<String>,
+    /// Derived tags, e.g. `shape:round`, `entry`, `subgraph:sg:api`, `role:actor`,
+    /// `kind:async`, `self`, `note`, `xref` or a `#hashtag` from the note, plus the object's
+    /// own tags (see [`ObjectAnnotations`]).
+    pub tags: Vec<String>,
+    /// User-assigned key/value metadata.
+    pub metadata: BTreeMap<String, String>,
+    /// Refs this object hangs off: edge/message endpoints, note and group participants, the
+    /// enclosing subgraph or block section.
+    pub anchors: Vec<ObjectRef>,
+    pub incoming: u64,
+    pub outgoing: u64,
+    pub xrefs_incoming: u64,
+    pub xrefs_outgoing: u64,
+}
+
+impl ObjectFacts {
+    /// Incoming plus outgoing links within the diagram; xrefs are not counted.
+    pub fn degree(&self) -> u64 {
+        self.incoming + self.outgoing
+    }
+}
+
+/// Every object of `diagram` with derived tags, anchors and adjacency counts: participants and
+/// messages, nodes and edges (or states, classes and their links) first, followed by notes,
+/// blocks, sections, groups and subgraphs.
+///
+/// Words written as `#tag` in an object's note become tags as well, so notes can carry
+/// user-defined tags such as `#deprecated` without editing the object's annotations.
+pub fn object_facts(session: &Session, diagram: &Diagram) -> Vec<ObjectFacts> {
+    use crate::model::seq_ast::{
+        SequenceBlock, SequenceBlockKind, SequenceMessageBoundary, SequenceNotePlacement,
+        SequenceSectionKind,
+    };
+    use crate::model::SequenceMessageKind;
+
+    let object_ref = |category: [&str; 2], object_id: &ObjectId| {
+        let category =
+            CategoryPath::new(category.iter().map(|segment| (*segment).to_owned()).collect())
+                .expect("valid category");
+        ObjectRef::new(diagram.diagram_id().clone(), category, object_id.clone())
+    };
+    let item = |category: [&str; 2], object_id: &ObjectId, label: &str| ObjectFacts {
+        object_ref: object_ref(category, object_id),
+        label: label.to_owned(),
+        note: None,
+        tags: Vec::new(),
+        metadata: BTreeMap::new(),
+        anchors: Vec::new(),
+        incoming: 0,
+        outgoing: 0,
+        xrefs_incoming: 0,
+        xrefs_outgoing: 0,
+    };
+    let annotate = |entry: &mut ObjectFacts, annotations: &ObjectAnnotations| {
+        for tag in annotations.tags() {
+            if !entry.tags.contains(tag) {
+                entry.tags.push(tag.clone());
+            }
+        }
+        entry.metadata = annotations.metadata().clone();
+    };
+    let block_kind = |kind: SequenceBlockKind| match kind {
+        SequenceBlockKind::Alt => "alt",
+        SequenceBlockKind::Opt => "opt",
+        SequenceBlockKind::Loop => "loop",
+        SequenceBlockKind::Par => "par",
+    };
+
+    let mut items = Vec::new();
+    match diagram.ast() {
+        DiagramAst::Sequence(ast) => {
+            for (participant_id, participant) in ast.participants() {
+                let mut entry =
+                    item(["seq", "participant"], participant_id, participant.mermaid_name());
+                entry.note = participant.note().map(ToOwned::to_owned);
+                entry.tags.extend(participant.role().map(|role| format!("role:{role}")));
+                if let Some(group) = ast.participant_group(participant_id) {
+                    entry.tags.push(format!("group:{}", group.group_id()));
+                    entry.anchors.push(object_ref(["seq", "group"], group.group_id()));
+                }
+                if entry.note.is_some() {
+                    entry.tags.push("note".to_owned());
+                }
+                for message in ast.messages() {
+                    let boundary = message.boundary();
+                    if message.to_participant_id() == participant_id
+                        && boundary != Some(SequenceMessageBoundary::Lost)
+                    {
+                        entry.incoming += 1;
+                    }
+                    if message.from_participant_id() == participant_id
+                        && boundary != Some(SequenceMessageBoundary::Found)
+                    {
+                        entry.outgoing += 1;
+                    }
+                }
+                annotate(&mut entry, participant.annotations());
+                items.push(entry);
+            }
+
+            for message in ast.messages_in_order() {
+                let mut entry = item(["seq", "message"], message.message_id(), message.text());
+                entry.tags.push(
+                    match message.kind() {
+                        SequenceMessageKind::Sync => "kind:sync",
+                        SequenceMessageKind::Async => "kind:async",
+                        SequenceMessageKind::Return => "kind:return",
+                    }
+                    .to_owned(),
+                );
+                if message.is_self_message() {
+                    entry.tags.push("self".to_owned());
+                }
+                if message.boundary().is_some() {
+                    entry.tags.push("boundary".to_owned());
+                }
+                if message.duration().is_some() {
+                    entry.tags.push("duration".to_owned());
+                }
+                let (from, to) = (message.from_participant_id(), message.to_participant_id());
+                entry.anchors.push(object_ref(["seq", "participant"], from));
+                if to != from {
+                    entry.anchors.push(object_ref(["seq", "participant"], to));
+                }
+                annotate(&mut entry, message.annotations());
+                items.push(entry);
+            }
+
+            for note in ast.notes_in_order() {
+                let mut entry = item(["seq", "note"], note.note_id(), note.text());
+                entry.tags.push(
+                    match note.placement() {
+                        SequenceNotePlacement::LeftOf => "placement:left_of",
+                        SequenceNotePlacement::RightOf => "placement:right_of",
+                        SequenceNotePlacement::Over => "placement:over",
+                    }
+                    .to_owned(),
+                );
+                entry.anchors.extend(
+                    note.participant_ids().iter().map(|id| object_ref(["seq", "participant"], id)),
+                );
+                items.push(entry);
+            }
+
+            // Depth-first, so nested blocks follow their parent.
+            let mut pending: Vec<(Option<&ObjectId>, &SequenceBlock)> =
+                ast.blocks().iter().rev().map(|block| (None, block)).collect();
+            while let Some((parent, block)) = pending.pop() {
+                let kind = block_kind(block.kind());
+                let mut entry =
+                    item(["seq", "block"], block.block_id(), block.header().unwrap_or(kind));
+                entry.tags.push(format!("kind:{kind}"));
+                entry.anchors.extend(parent.map(|parent| object_ref(["seq", "block"], parent)));
+                items.push(entry);
+
+                for section in block.sections() {
+                    let section_kind = match section.kind() {
+                        SequenceSectionKind::Main => kind,
+                        SequenceSectionKind::Else => "else",
+                        SequenceSectionKind::And => "and",
+                    };
+                    let label = section.header().unwrap_or(section_kind);
+                    let mut entry = item(["seq", "section"], section.section_id(), label);
+                    entry.tags.push(format!("kind:{section_kind}"));
+                    entry.anchors.push(object_ref(["seq", "block"], block.block_id()));
+                    items.push(entry);
+                }
+                let parent = Some(block.block_id());
+                pending.extend(block.blocks().iter().rev().map(|child| (parent, child)));
+            }
+
+            for activation in ast.activations() {
+                let participant_id = activation.participant_id();
+                let name = ast
+                    .participants()
+                    .get(participant_id)
+                    .map_or(participant_id.as_str(), |participant| participant.mermaid_name());
+                let label = format!("activate {name}");
+                let mut entry = item(["seq", "activation"], activation.activation_id(), &label);
+                entry.anchors.push(object_ref(["seq", "participant"], participant_id));
+                entry.anchors.extend(
+                    [activation.start_message_id(), activation.end_message_id()]
+                        .into_iter()
+                        .flatten()
+                        .map(|message_id| object_ref(["seq", "message"], message_id)),
+                );
+                items.push(entry);
+            }
+
+            for group in ast.groups() {
+                let title = group.title().unwrap_or("");
+                let mut entry = item(["seq", "group"], group.group_id(), title);
+                entry.tags.extend(group.color().map(|color| format!("color:{color}")));
+                entry.anchors.extend(
+                    group.participant_ids().iter().map(|id| object_ref(["seq", "participant"], id)),
+                );
+                items.push(entry);
+            }
+        }
+        DiagramAst::Flowchart(ast) => {
+            for (node_id, node) in ast.nodes() {
+                let mut entry = item(["flow", "node"], node_id, node.label());
+                entry.note = node.note().map(ToOwned::to_owned);
+                entry.tags.push(format!("shape:{}", node.shape()));
+                if ast.is_entry_node(node_id) {
+                    entry.tags.push("entry".to_owned());
+                }
+                if let Some(subgraph_id) = ast.node_subgraph(node_id) {
+                    entry.tags.push(format!("subgraph:{subgraph_id}"));
+                    entry.anchors.push(object_ref(["flow", "subgraph"], subgraph_id));
+                }
+                if entry.note.is_some() {
+                    entry.tags.push("note".to_owned());
+                }
+                for edge in ast.edges().values() {
+                    entry.incoming += u64::from(edge.to_node_id() == node_id);
+                    entry.outgoing += u64::from(edge.from_node_id() == node_id);
+                }
+                annotate(&mut entry, node.annotations());
+                items.push(entry);
+            }
+
+            for (edge_id, edge) in ast.edges() {
+                let mut entry = item(["flow", "edge"], edge_id, edge.label().unwrap_or(""));
+                entry.tags.extend(edge.connector().map(|value| format!("connector:{value}")));
+                entry.tags.extend(edge.style().map(|style| format!("style:{style}")));
+                if edge.weight().is_some() {
+                    entry.tags.push("weighted".to_owned());
+                }
+                entry.anchors.push(object_ref(["flow", "node"], edge.from_node_id()));
+                entry.anchors.push(object_ref(["flow", "node"], edge.to_node_id()));
+                annotate(&mut entry, edge.annotations());
+                items.push(entry);
+            }
+
+            for (subgraph_id, subgraph) in ast.subgraphs() {
+                let mut entry = item(["flow", "subgraph"], subgraph_id, subgraph.label());
+                if let Some(parent) = subgraph.parent() {
+                    entry.tags.push("nested".to_owned());
+                    entry.anchors.push(object_ref(["flow", "subgraph"], parent));
+                }
+                let members = ast.subgraph_members(subgraph_id);
+                for edge in ast.edges().values() {
+                    let from_inside = members.contains(edge.from_node_id());
+                    let to_inside = members.contains(edge.to_node_id());
+                    entry.incoming += u64::from(to_inside && !from_inside);
+                    entry.outgoing += u64::from(from_inside && !to_inside);
+                }
+                items.push(entry);
+            }
+        }
+        DiagramAst::State(ast) => {
+            for (state_id, state) in ast.states() {
+                let mut entry = item(["state", "node"], state_id, state.label());
+                entry.note = state.note().map(ToOwned::to_owned);
+                entry.tags.push(format!("kind:{}", state.kind().as_str()));
+                if entry.note.is_some() {
+                    entry.tags.push("note".to_owned());
+                }
+                for transition in ast.transitions().values() {
+                    entry.incoming += u64::from(transition.to_state_id() == state_id);
+                    entry.outgoing += u64::from(transition.from_state_id() == state_id);
+                }
+                items.push(entry);
+            }
+
+            for (transition_id, transition) in ast.transitions() {
+                let label = transition.label().unwrap_or("");
+                let mut entry = item(["state", "transition"], transition_id, label);
+                entry.anchors.push(object_ref(["state", "node"], transition.from_state_id()));
+                entry.anchors.push(object_ref(["state", "node"], transition.to_state_id()));
+                items.push(entry);
+            }
+        }
+        DiagramAst::Class(ast) => {
+            for (class_id, class) in ast.classes() {
+                let mut entry = item(["class", "node"], class_id, class.label());
+                entry.note = class.note().map(ToOwned::to_owned);
+                entry.tags.extend(class.annotation().map(|ann| format!("annotation:{ann}")));
+                if entry.note.is_some() {
+                    entry.tags.push("note".to_owned());
+                }
+                for relation in ast.relations().values() {
+                    entry.incoming += u64::from(relation.to_class_id() == class_id);
+                    entry.outgoing += u64::from(relation.from_class_id() == class_id);
+                }
+                items.push(entry);
+            }
+
+            for (relation_id, relation) in ast.relations() {
+                let label = relation.label().unwrap_or("");
+                let mut entry = item(["class", "relation"], relation_id, label);
+                entry.tags.push(format!("kind:{}", relation.kind().as_str()));
+                entry.anchors.push(object_ref(["class", "node"], relation.from_class_id()));
+                entry.anchors.push(object_ref(["class", "node"], relation.to_class_id()));
+                items.push(entry);
+            }
+        }
+    }
+
+    let mut xref_counts = BTreeMap::<&ObjectRef, (u64, u64)>::new();
+    for xref in session.xrefs().values() {
+        xref_counts.entry(xref.from()).or_default().1 += 1;
+        xref_counts.entry(xref.to()).or_default().0 += 1;
+    }
+    for entry in &mut items {
+        if let Some(&(incoming, outgoing)) = xref_counts.get(&entry.object_ref) {
+            entry.xrefs_incoming = incoming;
+            entry.xrefs_outgoing = outgoing;
+            entry.tags.push("xref".to_owned());
+        }
+        let hashtags = entry
+            .note
+            .iter()
+            .flat_map(|note| note.split_whitespace())
+            .filter_map(|word| word.strip_prefix('#'))
+            .map(|tag| tag.trim_end_matches(|ch: char| ch.is_ascii_punctuation()))
+            .filter(|tag| !tag.is_empty())
+            .map(ToOwned::to_owned)
+            .collect::<Vec<_>>();
+        for tag in hashtags {
+            if !entry.tags.contains(&tag) {
+                entry.tags.push(tag);
+            }
+        }
+    }
+    items
+}
+
+/// Styles from the session's rules for every object of `diagram` matched by at least one rule.
+pub fn object_styles(session: &Session, diagram: &Diagram) -> BTreeMap<ObjectRef, ObjectStyle> {
+    if session.style_rules().is_empty() {
+        return BTreeMap::new();
+    }
+    object_facts(session, diagram)
+        .into_iter()
+        .filter_map(|facts| {
+            let mut style = ObjectStyle::default();
+            for rule in session.style_rules() {
+                if rule.matches(&facts.tags, facts.degree()) {
+                    style.apply(rule.effect());
+                }
+            }
+            (style != ObjectStyle::default()).then_some((facts.object_ref, style))
+        })
+        .collect()
+}
+
+/// [`object_styles`] across every diagram of the session.
+pub fn session_object_styles(session: &Session) -> BTreeMap<ObjectRef, ObjectStyle> {
+    session.diagrams().values().flat_map(|diagram| object_styles(session, diagram)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::object_styles;
+    use crate::format::mermaid::parse_flowchart;
+    use crate::model::{Diagram, DiagramAst, DiagramId, ObjectId, ObjectStyle, Session, SessionId};
+
+    #[test]
+    fn style_rules_match_note_hashtags_and_degree() {
+        let mut ast = parse_flowchart("flowchart TD\nA --> B\nA --> C\nA --> D\n").expect("parse");
+        ast.nodes_mut()
+            .get_mut(&ObjectId::new("n:B").expect("node id"))
+            .expect("node B")
+            .set_note(Some("Replaced by C. #deprecated"));
+        let diagram_id = DiagramId::new("flow").expect("diagram id");
+        let diagram = Diagram::new(diagram_id, "Flow", DiagramAst::Flowchart(ast));
+        let mut session = Session::new(SessionId::new("s").expect("session id"));
+        assert!(object_styles(&session, &diagram).is_empty());
+
+        session.set_style_rules(
+            ["tag=deprecated => dim", "degree>=3 => heavy"].map(|rule| rule.parse().expect("rule")),
+        );
+        let styles = object_styles(&session, &diagram)
+            .into_iter()
+            .map(|(object_ref, style)| (object_ref.to_string(), style))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            styles,
+            vec![
+                ("d:flow/flow/node/n:A".to_owned(), ObjectStyle { dim: false, heavy: true }),
+                ("d:flow/flow/node/n:B".to_owned(), ObjectStyle { dim: true, heavy: false }),
+            ]
+        );
+    }
+}
@@ -0,0 +1,395 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Diagrams scaffolded from other diagrams, linked back to their sources through xrefs.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::model::{
+    CategoryPath, Diagram, DiagramAst, DiagramId, FlowEdge, FlowNode, FlowchartAst, ObjectId,
+    ObjectRef, SequenceAst, SequenceMessage, SequenceMessageKind, SequenceParticipant, Session,
+    XRef, XRefId, XRefStatus,
+};
+use crate::ops::idgen;
+
+/// XRef kind linking a scaffolded object (`from`) to the object it was derived from (`to`).
+pub const DERIVED_FROM_XREF_KIND: &str = "derived_from";
+
+/// An object of a scaffolded diagram and the source object it was derived from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScaffoldLink {
+    pub category: CategoryPath,
+    pub object_id: ObjectId,
+    pub source: ObjectRef,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScaffoldError {
+    NotAFlowchart { diagram_id: DiagramId },
+    NotASequence { diagram_id: DiagramId },
+    EmptyPath,
+    UnknownNode { node_id: ObjectId },
+    NotConnected { from_node_id: ObjectId, to_node_id: ObjectId },
+}
+
+impl fmt::Display for ScaffoldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAFlowchart { diagram_id } => write!(f, "{diagram_id} is not a flowchart"),
+            Self::NotASequence { diagram_id } => {
+                write!(f, "{diagram_id} is not a sequence diagram")
+            }
+            Self::EmptyPath => f.write_str("path has no nodes"),
+            Self::UnknownNode { node_id } => write!(f, "flow node not found (id={node_id})"),
+            Self::NotConnected { from_node_id, to_node_id } => {
+                write!(f, "no edge from {from_node_id} to {to_node_id}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScaffoldError {}
+
+/// Scaffolds a sequence diagram from a path through `flow` given as consecutive node ids.
+///
+/// Every node on the path becomes a participant named after its label (repeat visits reuse it)
+/// and every hop becomes a message, in path order, carrying the edge label or else the target
+/// node label. The first edge (by id) between two consecutive nodes is used for each hop.
+pub fn sequence_from_flow_path(
+    flow: &Diagram,
+    path: &[ObjectId],
+) -> Result<(SequenceAst, Vec<ScaffoldLink>), ScaffoldError> {
+    let DiagramAst::Flowchart(ast) = flow.ast() else {
+        return Err(ScaffoldError::NotAFlowchart { diagram_id: flow.diagram_id().clone() });
+    };
+    if path.is_empty() {
+        return Err(ScaffoldError::EmptyPath);
+    }
+
+    let mut seq = SequenceAst::default();
+    let mut links = Vec::new();
+    let mut participant_ids = BTreeMap::<&ObjectId, ObjectId>::new();
+    for node_id in path {
+        let node = ast
+            .nodes()
+            .get(node_id)
+            .ok_or_else(|| ScaffoldError::UnknownNode { node_id: node_id.clone() })?;
+        if participant_ids.contains_key(node_id) {
+            continue;
+        }
+        let name = idgen::mermaid_ident(node.label(), |name| {
+            seq.participants().values().any(|participant| participant.mermaid_name() == name)
+        });
+        let participant_id = ObjectId::new(format!("p:{name}")).expect("valid participant id");
+        seq.participants_mut().insert(participant_id.clone(), SequenceParticipant::new(name));
+        links.push(ScaffoldLink {
+            category: category(&["seq", "participant"]),
+            object_id: participant_id.clone(),
+            source: ObjectRef::new(
+                flow.diagram_id().clone(),
+                category(&["flow", "node"]),
+                node_id.clone(),
+            ),
+        });
+        participant_ids.insert(node_id, participant_id);
+    }
+
+    for (idx, hop) in path.windows(2).enumerate() {
+        let (from_node_id, to_node_id) = (&hop[0], &hop[1]);
+        let (edge_id, edge) = ast
+            .edges()
+            .iter()
+            .find(|(_, edge)| {
+                edge.from_node_id() == from_node_id && edge.to_node_id() == to_node_id
+            })
+            .ok_or_else(|| ScaffoldError::NotConnected {
+                from_node_id: from_node_id.clone(),
+                to_node_id: to_node_id.clone(),
+            })?;
+        let text = edge.label().unwrap_or_else(|| ast.nodes()[to_node_id].label());
+        let message_id = ObjectId::new(format!("m:{:04}", idx + 1)).expect("valid message id");
+        seq.messages_mut().push(SequenceMessage::new(
+            message_id.clone(),
+            participant_ids[from_node_id].clone(),
+            participant_ids[to_node_id].clone(),
+            SequenceMessageKind::Sync,
+            text,
+            idx as i64 * 1000,
+        ));
+        links.push(ScaffoldLink {
+            category: category(&["seq", "message"]),
+            object_id: message_id,
+            source: ObjectRef::new(
+                flow.diagram_id().clone(),
+                category(&["flow", "edge"]),
+                edge_id.clone(),
+            ),
+        });
+    }
+
+    Ok((seq, links))
+}
+
+/// Scaffolds a flowchart of who talks to whom in the sequence diagram `seq`.
+///
+/// Every participant becomes a node labelled with its name and every ordered sender/receiver
+/// pair becomes one edge whose weight and label carry the number of messages it stands for.
+/// Nodes link back to their participants and edges to each of their messages. Found and lost
+/// messages have no sender or receiver and are left out.
+pub fn flowchart_from_sequence(
+    seq: &Diagram,
+) -> Result<(FlowchartAst, Vec<ScaffoldLink>), ScaffoldError> {
+    let DiagramAst::Sequence(ast) = seq.ast() else {
+        return Err(ScaffoldError::NotASequence { diagram_id: seq.diagram_id().clone() });
+    };
+    let source = |segments: &[&str], object_id: &ObjectId| {
+        ObjectRef::new(seq.diagram_id().clone(), category(segments), object_id.clone())
+    };
+
+    let mut flow = FlowchartAst::default();
+    let mut links = Vec::new();
+    let mut node_ids = BTreeMap::<&ObjectId, ObjectId>::new();
+    for (participant_id, participant) in ast.participants() {
+        let node_id = idgen::labeled_object_id("n", participant.mermaid_name(), |node_id| {
+            flow.nodes().contains_key(node_id)
+        });
+        flow.nodes_mut().insert(node_id.clone(), FlowNode::new(participant.mermaid_name()));
+        links.push(ScaffoldLink {
+            category: category(&["flow", "node"]),
+            object_id: node_id.clone(),
+            source: source(&["seq", "participant"], participant_id),
+        });
+        node_ids.insert(participant_id, node_id);
+    }
+
+    let mut edges = Vec::<(&ObjectId, &ObjectId, Vec<&ObjectId>)>::new();
+    for message in ast.messages_in_order() {
+        let Some((from, to)) = message.endpoints() else {
+            continue;
+        };
+        match edges.iter_mut().find(|(edge_from, edge_to, _)| *edge_from == from && *edge_to == to)
+        {
+            Some((_, _, message_ids)) => message_ids.push(message.message_id()),
+            None => edges.push((from, to, vec![message.message_id()])),
+        }
+    }
+    for (idx, (from, to, message_ids)) in edges.into_iter().enumerate() {
+        let (Some(from_node_id), Some(to_node_id)) = (node_ids.get(from), node_ids.get(to)) else {
+            continue;
+        };
+        let edge_id = ObjectId::new(format!("e:{:04}", idx + 1)).expect("valid edge id");
+        let count = message_ids.len();
+        let mut edge = FlowEdge::new(from_node_id.clone(), to_node_id.clone());
+        edge.set_label(Some(if count == 1 {
+            "1 message".to_owned()
+        } else {
+            format!("{count} messages")
+        }));
+        edge.set_weight(Some(count as u64));
+        flow.edges_mut().insert(edge_id.clone(), edge);
+        links.extend(message_ids.into_iter().map(|message_id| ScaffoldLink {
+            category: category(&["flow", "edge"]),
+            object_id: edge_id.clone(),
+            source: source(&["seq", "message"], message_id),
+        }));
+    }
+
+    Ok((flow, links))
+}
+
+/// Orders `edge_ids` of `ast` into a simple path and returns its node ids, or `None` when the
+/// edges do not form one chain (branches, gaps, cycles or unknown edges).
+pub fn flow_path_from_edges(
+    ast: &FlowchartAst,
+    edge_ids: &BTreeSet<ObjectId>,
+) -> Option<Vec<ObjectId>> {
+    let mut next = BTreeMap::new();
+    for edge_id in edge_ids {
+        let edge = ast.edges().get(edge_id)?;
+        if next.insert(edge.from_node_id(), edge.to_node_id()).is_some() {
+            return None;
+        }
+    }
+    let targets = next.values().copied().collect::<BTreeSet<_>>();
+    let mut starts = next.keys().filter(|node_id| !targets.contains(*node_id));
+    let (Some(mut node_id), None) = (starts.next().copied(), starts.next()) else {
+        return None;
+    };
+
+    let mut path = vec![node_id.clone()];
+    while let Some(to_node_id) = next.get(node_id) {
+        node_id = to_node_id;
+        path.push(node_id.clone());
+    }
+    (path.len() == edge_ids.len() + 1).then_some(path)
+}
+
+/// Diagram id for a scaffold of `source`: `<source>-<suffix>`, numbered when taken.
+pub fn scaffold_diagram_id(session: &Session, source: &DiagramId, suffix: &str) -> DiagramId {
+    idgen::diagram_id(session, &format!("{source}-{suffix}"))
+}
+
+/// `derived_from` xrefs from the objects of scaffolded diagram `diagram_id` to their sources,
+/// with `x:N` ids minted from `session` (see [`idgen::next_xref_id`]).
+pub fn scaffold_xrefs(
+    session: &mut Session,
+    diagram_id: &DiagramId,
+    links: &[ScaffoldLink],
+) -> Vec<(XRefId, XRef)> {
+    links
+        .iter()
+        .map(|link| {
+            let xref_id = idgen::next_xref_id(session);
+            let from =
+                ObjectRef::new(diagram_id.clone(), link.category.clone(), link.object_id.clone());
+            let xref = XRef::new(from, link.source.clone(), DERIVED_FROM_XREF_KIND, XRefStatus::Ok);
+            (xref_id, xref)
+        })
+        .collect()
+}
+
+fn category(segments: &[&str]) -> CategoryPath {
+    CategoryPath::new(segments.iter().map(|segment| (*segment).to_owned()).collect())
+        .expect("valid category path")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{
+        flow_path_from_edges, flowchart_from_sequence, scaffold_xrefs, sequence_from_flow_path,
+        ScaffoldError,
+    };
+    use crate::format::mermaid::{parse_flowchart, parse_sequence_diagram};
+    use crate::model::{Diagram, DiagramAst, DiagramId, ObjectId, Session, SessionId};
+
+    fn oid(value: &str) -> ObjectId {
+        ObjectId::new(value).expect("object id")
+    }
+
+    fn checkout_flow() -> Diagram {
+        let ast = parse_flowchart(
+            "flowchart LR\nA[Web shop] -->|place order| B[Order API]\nB --> C[Payments]\nB --> D[Stock]\nC -->|paid| B\n",
+        )
+        .expect("parse");
+        Diagram::new(
+            DiagramId::new("checkout").expect("diagram id"),
+            "Checkout",
+            DiagramAst::Flowchart(ast),
+        )
+    }
+
+    #[test]
+    fn flow_path_scaffolds_participants_messages_and_links() {
+        let flow = checkout_flow();
+        let path = [oid("n:A"), oid("n:B"), oid("n:C"), oid("n:B")];
+        let (seq, links) = sequence_from_flow_path(&flow, &path).expect("scaffold");
+
+        let names = seq.participants().values().map(|p| p.mermaid_name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["Order_API", "Payments", "Web_shop"]);
+        let messages = seq
+            .messages_in_order()
+            .into_iter()
+            .map(|m| (m.from_participant_id().as_str(), m.to_participant_id().as_str(), m.text()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                ("p:Web_shop", "p:Order_API", "place order"),
+                ("p:Order_API", "p:Payments", "Payments"),
+                ("p:Payments", "p:Order_API", "paid"),
+            ]
+        );
+        assert_eq!(links.len(), 6);
+
+        let mut session = Session::new(SessionId::new("s").expect("session id"));
+        let seq_id = DiagramId::new("checkout-seq").expect("diagram id");
+        let xrefs = scaffold_xrefs(&mut session, &seq_id, &links);
+        let (xref_id, xref) = &xrefs[3];
+        assert_eq!(xref_id.as_str(), "x:4");
+        assert_eq!(xref.from().to_string(), "d:checkout-seq/seq/message/m:0001");
+        assert_eq!(xref.to().to_string(), "d:checkout/flow/edge/e:0001");
+        session.xrefs_mut().extend(xrefs);
+        assert_eq!(scaffold_xrefs(&mut session, &seq_id, &links[..1])[0].0.as_str(), "x:7");
+
+        assert_eq!(
+            sequence_from_flow_path(&flow, &[oid("n:C"), oid("n:D")]),
+            Err(ScaffoldError::NotConnected { from_node_id: oid("n:C"), to_node_id: oid("n:D") })
+        );
+    }
+
+    #[test]
+    fn selected_edges_order_into_a_path_only_when_they_chain() {
+        let flow = checkout_flow();
+        let DiagramAst::Flowchart(ast) = flow.ast() else { unreachable!() };
+        let edge_between = |from: &str, to: &str| {
+            ast.edges()
+                .iter()
+                .find(|(_, e)| e.from_node_id() == &oid(from) && e.to_node_id() == &oid(to))
+                .map(|(edge_id, _)| edge_id.clone())
+                .expect("edge")
+        };
+        let chain = BTreeSet::from([edge_between("n:B", "n:C"), edge_between("n:A", "n:B")]);
+        assert_eq!(
+            flow_path_from_edges(ast, &chain),
+            Some(vec![oid("n:A"), oid("n:B"), oid("n:C")])
+        );
+
+        let branch = BTreeSet::from([edge_between("n:B", "n:C"), edge_between("n:B", "n:D")]);
+        assert_eq!(flow_path_from_edges(ast, &branch), None);
+        let cycle = BTreeSet::from([edge_between("n:B", "n:C"), edge_between("n:C", "n:B")]);
+        assert_eq!(flow_path_from_edges(ast, &cycle), None);
+    }
+
+    #[test]
+    fn sequence_interactions_scaffold_weighted_flowchart() {
+        // The trailing found/lost messages have no sender/receiver pair and add no edges.
+        let ast = parse_sequence_diagram(
+            "sequenceDiagram\nAlice->>Bob: hi\nBob-->>Alice: hey\nAlice->>Bob: bye\n\
+             %% nereid:boundary=found\nBob->>Bob: ping\n%% nereid:boundary=lost\nAlice-)Alice: gone\n",
+        )
+        .expect("parse");
+        let seq = Diagram::new(
+            DiagramId::new("chat").expect("diagram id"),
+            "Chat",
+            DiagramAst::Sequence(ast),
+        );
+        let (flow, links) = flowchart_from_sequence(&seq).expect("scaffold");
+
+        let labels = flow.nodes().values().map(|node| node.label()).collect::<Vec<_>>();
+        assert_eq!(labels, vec!["Alice", "Bob"]);
+        let edges = flow
+            .edges()
+            .values()
+            .map(|e| (e.from_node_id().as_str(), e.to_node_id().as_str(), e.label(), e.weight()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            edges,
+            vec![
+                ("n:Alice", "n:Bob", Some("2 messages"), Some(2)),
+                ("n:Bob", "n:Alice", Some("1 message"), Some(1)),
+            ]
+        );
+        let edge_sources = links
+            .iter()
+            .filter(|link| link.object_id == oid("e:0001"))
+            .map(|link| link.source.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(edge_sources, vec!["d:chat/seq/message/m:0001", "d:chat/seq/message/m:0003"]);
+        assert_eq!(links.len(), 5);
+
+        assert_eq!(
+            flowchart_from_sequence(&checkout_flow()),
+            Err(ScaffoldError::NotASequence {
+                diagram_id: DiagramId::new("checkout").expect("diagram id")
+            })
+        );
+    }
+}
@@ -0,0 +1,285 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Session-wide full-text search.
+//!
+//! [`SearchIndex`] collects the searchable text of a session: object labels (a message's label is
+//! its text) and notes across every non-archived diagram, walkthrough node titles and bodies, and
+//! xref labels. A document matches when every query term occurs in one of its words; exact words
+//! score above word prefixes, which score above inner substrings, and labels and titles weigh more
+//! than notes and bodies. Each target is reported once, with its best matching field.
+
+use std::collections::BTreeMap;
+
+use crate::model::{ObjectRef, Session, WalkthroughId, WalkthroughNodeId, XRefId};
+use crate::query::objects::object_facts;
+
+/// What a search hit points at.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SearchTarget {
+    Object(ObjectRef),
+    WalkthroughNode { walkthrough_id: WalkthroughId, node_id: WalkthroughNodeId },
+    XRef(XRefId),
+}
+
+impl SearchTarget {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Object(_) => "object",
+            Self::WalkthroughNode { .. } => "walkthrough_node",
+            Self::XRef(_) => "xref",
+        }
+    }
+
+    /// Stable textual id: the object ref, `<walkthrough_id>/<node_id>`, or the xref id.
+    pub fn id(&self) -> String {
+        match self {
+            Self::Object(object_ref) => object_ref.to_string(),
+            Self::WalkthroughNode { walkthrough_id, node_id } => {
+                format!("{walkthrough_id}/{node_id}")
+            }
+            Self::XRef(xref_id) => xref_id.to_string(),
+        }
+    }
+}
+
+/// Which text of a target a document holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SearchField {
+    Label,
+    Note,
+    Title,
+    Body,
+}
+
+impl SearchField {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Label => "label",
+            Self::Note => "note",
+            Self::Title => "title",
+            Self::Body => "body",
+        }
+    }
+
+    fn weight(self) -> u64 {
+        match self {
+            Self::Label | Self::Title => 4,
+            Self::Note => 2,
+            Self::Body => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchDocument {
+    pub target: SearchTarget,
+    pub field: SearchField,
+    pub text: String,
+    words: Vec<String>,
+}
+
+impl SearchDocument {
+    fn new(target: SearchTarget, field: SearchField, text: &str) -> Option<Self> {
+        let words = terms(text);
+        (!words.is_empty()).then(|| Self { target, field, text: text.to_owned(), words })
+    }
+
+    /// Score of this document for `query_terms`, or `None` if a term does not occur.
+    fn score(&self, query_terms: &[String]) -> Option<u64> {
+        let mut total = 0;
+        for term in query_terms {
+            let best = self
+                .words
+                .iter()
+                .map(|word| {
+                    if word == term {
+                        3
+                    } else if word.starts_with(term.as_str()) {
+                        2
+                    } else if word.contains(term.as_str()) {
+                        1
+                    } else {
+                        0
+                    }
+                })
+                .max()
+                .unwrap_or(0);
+            if best == 0 {
+                return None;
+            }
+            total += best;
+        }
+        Some(total * self.field.weight())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    pub target: SearchTarget,
+    /// The best matching field of the target.
+    pub field: SearchField,
+    pub text: String,
+    pub score: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchIndex {
+    documents: Vec<SearchDocument>,
+}
+
+impl SearchIndex {
+    pub fn build(session: &Session) -> Self {
+        let mut documents = Vec::new();
+
+        for diagram in session.diagrams().values().filter(|diagram| !diagram.is_archived()) {
+            for facts in object_facts(session, diagram) {
+                let target = SearchTarget::Object(facts.object_ref);
+                documents.extend(SearchDocument::new(
+                    target.clone(),
+                    SearchField::Label,
+                    &facts.label,
+                ));
+                if let Some(note) = facts.note.as_deref() {
+                    documents.extend(SearchDocument::new(target, SearchField::Note, note));
+                }
+            }
+        }
+
+        for (walkthrough_id, walkthrough) in session.walkthroughs() {
+            for node in walkthrough.nodes() {
+                let target = SearchTarget::WalkthroughNode {
+                    walkthrough_id: walkthrough_id.clone(),
+                    node_id: node.node_id().clone(),
+                };
+                documents.extend(SearchDocument::new(
+                    target.clone(),
+                    SearchField::Title,
+                    node.title(),
+                ));
+                if let Some(body) = node.body_md() {
+                    documents.extend(SearchDocument::new(target, SearchField::Body, body));
+                }
+            }
+        }
+
+        for (xref_id, xref) in session.xrefs() {
+            if let Some(label) = xref.label() {
+                let target = SearchTarget::XRef(xref_id.clone());
+                documents.extend(SearchDocument::new(target, SearchField::Label, label));
+            }
+        }
+
+        Self { documents }
+    }
+
+    pub fn documents(&self) -> &[SearchDocument] {
+        &self.documents
+    }
+
+    /// Ranked hits for `query`, best first; ties break by target so results are deterministic.
+    /// A blank query matches nothing.
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        let query_terms = terms(query);
+        if query_terms.is_empty() {
+            return Vec::new();
+        }
+
+        let mut best = BTreeMap::<&SearchTarget, SearchHit>::new();
+        for document in &self.documents {
+            let Some(score) = document.score(&query_terms) else {
+                continue;
+            };
+            let replace = best.get(&document.target).map_or(true, |hit| score > hit.score);
+            if replace {
+                best.insert(
+                    &document.target,
+                    SearchHit {
+                        target: document.target.clone(),
+                        field: document.field,
+                        text: document.text.clone(),
+                        score,
+                    },
+                );
+            }
+        }
+
+        let mut hits = best.into_values().collect::<Vec<_>>();
+        hits.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.target.cmp(&b.target)));
+        hits
+    }
+}
+
+/// Lowercased alphanumeric words of `text`.
+fn terms(text: &str) -> Vec<String> {
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SearchField, SearchIndex, SearchTarget};
+    use crate::format::mermaid::{parse_flowchart, parse_sequence_diagram};
+    use crate::model::{
+        CategoryPath, Diagram, DiagramAst, DiagramId, ObjectId, ObjectRef, Session, SessionId,
+        Walkthrough, WalkthroughId, WalkthroughNode, WalkthroughNodeId, XRef, XRefId, XRefStatus,
+    };
+
+    #[test]
+    fn search_ranks_labels_messages_notes_walkthroughs_and_xrefs() {
+        let mut session = Session::new(SessionId::new("s").expect("session id"));
+        let flow_id = DiagramId::new("flow").expect("diagram id");
+        let flow = parse_flowchart("flowchart TD\nA[Checkout] --> B[Payment]\n").expect("flow");
+        session.diagrams_mut().insert(
+            flow_id.clone(),
+            Diagram::new(flow_id.clone(), "Flow", DiagramAst::Flowchart(flow)),
+        );
+        let seq_id = DiagramId::new("seq").expect("diagram id");
+        let seq = parse_sequence_diagram(
+            "sequenceDiagram\nparticipant A\nparticipant B\nA->>B: start checkout\n",
+        )
+        .expect("seq");
+        session
+            .diagrams_mut()
+            .insert(seq_id.clone(), Diagram::new(seq_id.clone(), "Seq", DiagramAst::Sequence(seq)));
+
+        let walkthrough_id = WalkthroughId::new("w").expect("walkthrough id");
+        let mut walkthrough = Walkthrough::new(walkthrough_id.clone(), "Tour");
+        let mut step =
+            WalkthroughNode::new(WalkthroughNodeId::new("n1").expect("node id"), "Intro");
+        step.set_body_md(Some("The checkouts are retried".to_owned()));
+        walkthrough.nodes_mut().push(step);
+        session.walkthroughs_mut().insert(walkthrough_id.clone(), walkthrough);
+
+        let node_ref = ObjectRef::new(
+            flow_id.clone(),
+            CategoryPath::new(vec!["flow".to_owned(), "node".to_owned()]).expect("category"),
+            ObjectId::new("n:A").expect("node id"),
+        );
+        let xref_id = XRefId::new("x:1").expect("xref id");
+        let mut xref = XRef::new(node_ref.clone(), node_ref.clone(), "calls", XRefStatus::Ok);
+        xref.set_label(Some("checkout handoff".to_owned()));
+        session.xrefs_mut().insert(xref_id.clone(), xref);
+
+        let index = SearchIndex::build(&session);
+        let hits = index.search("Checkout");
+        let targets = hits.iter().map(|hit| hit.target.kind()).collect::<Vec<_>>();
+        assert_eq!(targets, vec!["object", "object", "xref", "walkthrough_node"]);
+        assert_eq!(hits[0].target, SearchTarget::Object(node_ref));
+        assert_eq!(hits[1].text, "start checkout");
+        assert_eq!(hits[2].target, SearchTarget::XRef(xref_id));
+        assert_eq!(hits[3].field, SearchField::Body);
+        assert_eq!(hits[3].target.id(), "w/n1");
+
+        assert_eq!(index.search("start checkout").len(), 1);
+        assert!(index.search("  ").is_empty());
+        assert!(index.search("missing").is_empty());
+    }
+}
@@ -6,10 +6,15 @@
 // This file is part of Nereid and is proprietary software.
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use std::collections::BTreeMap;
+
 use crate::model::ids::ObjectId;
 use crate::model::seq_ast::{SequenceAst, SequenceMessage};
 use regex::RegexBuilder;
 
+/// Duration assumed for messages without explicit duration metadata.
+pub const DEFAULT_MESSAGE_DURATION: u64 = 1;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MessageSearchMode {
     Substring,
@@ -57,10 +62,7 @@ pub fn messages_between<'a>(
 ) -> Vec<&'a SequenceMessage> {
     ast.messages_in_order()
         .into_iter()
-        .filter(|msg| {
-            msg.from_participant_id() == from_participant_id
-                && msg.to_participant_id() == to_participant_id
-        })
+        .filter(|msg| msg.endpoints() == Some((from_participant_id, to_participant_id)))
         .collect()
 }
 
@@ -88,9 +90,72 @@ pub fn trace_after<'a>(
     Some(messages[start_index..end_index].to_vec())
 }
 
+/// One message on a critical path and the time at which it finishes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CriticalMessage<'a> {
+    pub message: &'a SequenceMessage,
+    pub duration: u64,
+    pub finish: u64,
+}
+
+/// Longest chain of dependent messages, weighted by message durations.
+///
+/// Messages run in `order_key` order and a message depends on the latest earlier message that
+/// touched either of its lifelines, so it finishes `duration` after the later of the two. The
+/// returned chain ends at the message that finishes last (the earliest one on ties); its final
+/// `finish` is the total duration. Messages without duration metadata count as
+/// [`DEFAULT_MESSAGE_DURATION`].
+pub fn critical_path(ast: &SequenceAst) -> Vec<CriticalMessage<'_>> {
+    let messages = ast.messages_in_order();
+    let mut finishes = Vec::<u64>::with_capacity(messages.len());
+    let mut previous = Vec::<Option<usize>>::with_capacity(messages.len());
+    let mut latest_on_lifeline = BTreeMap::<&ObjectId, usize>::new();
+
+    for (index, message) in messages.iter().enumerate() {
+        let lifelines = [message.from_participant_id(), message.to_participant_id()];
+        let mut depends_on: Option<usize> = None;
+        for candidate in lifelines.iter().filter_map(|id| latest_on_lifeline.get(id).copied()) {
+            if depends_on.map_or(true, |best| finishes[candidate] > finishes[best]) {
+                depends_on = Some(candidate);
+            }
+        }
+        let start = depends_on.map_or(0, |dep| finishes[dep]);
+        let duration = message.duration().unwrap_or(DEFAULT_MESSAGE_DURATION);
+        finishes.push(start.saturating_add(duration));
+        previous.push(depends_on);
+        for lifeline in lifelines {
+            latest_on_lifeline.insert(lifeline, index);
+        }
+    }
+
+    let mut end: Option<usize> = None;
+    for (index, finish) in finishes.iter().enumerate() {
+        if end.map_or(true, |best| *finish > finishes[best]) {
+            end = Some(index);
+        }
+    }
+
+    let mut chain = Vec::new();
+    let mut cursor = end;
+    while let Some(index) = cursor {
+        let message = messages[index];
+        chain.push(CriticalMessage {
+            message,
+            duration: message.duration().unwrap_or(DEFAULT_MESSAGE_DURATION),
+            finish: finishes[index],
+        });
+        cursor = previous[index];
+    }
+    chain.reverse();
+    chain
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{message_search, messages_between, trace_after, trace_before, MessageSearchMode};
+    use super::{
+        critical_path, message_search, messages_between, trace_after, trace_before,
+        MessageSearchMode,
+    };
     use crate::model::ids::ObjectId;
     use crate::model::seq_ast::{
         SequenceAst, SequenceMessage, SequenceMessageKind, SequenceParticipant,
@@ -223,4 +288,38 @@ mod tests {
         assert_eq!(trace_before(&ast, &missing, 1), None);
         assert_eq!(trace_after(&ast, &missing, 1), None);
     }
+
+    #[test]
+    fn critical_path_follows_the_slowest_chain_of_shared_lifelines() {
+        let mut ast = SequenceAst::default();
+        for name in ["A", "B", "C", "D"] {
+            let participant_id = ObjectId::new(format!("p:{name}")).expect("participant id");
+            ast.participants_mut().insert(participant_id, SequenceParticipant::new(name));
+        }
+        let mut push = |id: &str, from: &str, to: &str, order_key: i64, duration: Option<u64>| {
+            let mut message = SequenceMessage::new(
+                ObjectId::new(id).expect("message id"),
+                ObjectId::new(from).expect("participant id"),
+                ObjectId::new(to).expect("participant id"),
+                SequenceMessageKind::Sync,
+                id,
+                order_key,
+            );
+            message.set_duration(duration);
+            ast.messages_mut().push(message);
+        };
+        push("m:1", "p:A", "p:B", 1000, Some(5));
+        push("m:2", "p:C", "p:D", 2000, None);
+        push("m:3", "p:C", "p:D", 3000, None);
+        push("m:4", "p:B", "p:C", 4000, Some(2));
+
+        let path = critical_path(&ast);
+        let steps = path
+            .iter()
+            .map(|step| (step.message.message_id().as_str(), step.duration, step.finish))
+            .collect::<Vec<_>>();
+        assert_eq!(steps, vec![("m:1", 5, 5), ("m:4", 2, 7)]);
+
+        assert!(critical_path(&SequenceAst::default()).is_empty());
+    }
 }
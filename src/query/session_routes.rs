@@ -46,6 +46,22 @@ fn seq_section_category() -> CategoryPath {
     CategoryPath::new(vec!["seq".to_owned(), "section".to_owned()]).expect("static category")
 }
 
+fn state_node_category() -> CategoryPath {
+    CategoryPath::new(vec!["state".to_owned(), "node".to_owned()]).expect("static category")
+}
+
+fn state_transition_category() -> CategoryPath {
+    CategoryPath::new(vec!["state".to_owned(), "transition".to_owned()]).expect("static category")
+}
+
+fn class_node_category() -> CategoryPath {
+    CategoryPath::new(vec!["class".to_owned(), "node".to_owned()]).expect("static category")
+}
+
+fn class_relation_category() -> CategoryPath {
+    CategoryPath::new(vec!["class".to_owned(), "relation".to_owned()]).expect("static category")
+}
+
 fn flow_node_ref(diagram_id: &DiagramId, node_id: &ObjectId) -> ObjectRef {
     ObjectRef::new(diagram_id.clone(), flow_node_category(), node_id.clone())
 }
@@ -54,6 +70,22 @@ fn flow_edge_ref(diagram_id: &DiagramId, edge_id: &ObjectId) -> ObjectRef {
     ObjectRef::new(diagram_id.clone(), flow_edge_category(), edge_id.clone())
 }
 
+fn state_node_ref(diagram_id: &DiagramId, state_id: &ObjectId) -> ObjectRef {
+    ObjectRef::new(diagram_id.clone(), state_node_category(), state_id.clone())
+}
+
+fn state_transition_ref(diagram_id: &DiagramId, transition_id: &ObjectId) -> ObjectRef {
+    ObjectRef::new(diagram_id.clone(), state_transition_category(), transition_id.clone())
+}
+
+fn class_node_ref(diagram_id: &DiagramId, class_id: &ObjectId) -> ObjectRef {
+    ObjectRef::new(diagram_id.clone(), class_node_category(), class_id.clone())
+}
+
+fn class_relation_ref(diagram_id: &DiagramId, relation_id: &ObjectId) -> ObjectRef {
+    ObjectRef::new(diagram_id.clone(), class_relation_category(), relation_id.clone())
+}
+
 fn seq_message_ref(diagram_id: &DiagramId, message_id: &ObjectId) -> ObjectRef {
     ObjectRef::new(diagram_id.clone(), seq_message_category(), message_id.clone())
 }
@@ -185,6 +217,46 @@ fn derive_adjacency(session: &Session) -> BTreeMap<ObjectRef, BTreeSet<ObjectRef
                     add_block(diagram_id, block, &mut adjacency, None);
                 }
             }
+            DiagramAst::State(ast) => {
+                for state_id in ast.states().keys() {
+                    insert_node(&mut adjacency, state_node_ref(diagram_id, state_id));
+                }
+                for (transition_id, transition) in ast.transitions() {
+                    let transition_ref = state_transition_ref(diagram_id, transition_id);
+                    let from = state_node_ref(diagram_id, transition.from_state_id());
+                    let to = state_node_ref(diagram_id, transition.to_state_id());
+                    insert_node(&mut adjacency, transition_ref.clone());
+                    insert_node(&mut adjacency, from.clone());
+                    insert_node(&mut adjacency, to.clone());
+
+                    insert_edge(&mut adjacency, from.clone(), to.clone());
+
+                    insert_edge(&mut adjacency, from.clone(), transition_ref.clone());
+                    insert_edge(&mut adjacency, to.clone(), transition_ref.clone());
+                    insert_edge(&mut adjacency, transition_ref.clone(), from);
+                    insert_edge(&mut adjacency, transition_ref, to);
+                }
+            }
+            DiagramAst::Class(ast) => {
+                for class_id in ast.classes().keys() {
+                    insert_node(&mut adjacency, class_node_ref(diagram_id, class_id));
+                }
+                for (relation_id, relation) in ast.relations() {
+                    let relation_ref = class_relation_ref(diagram_id, relation_id);
+                    let from = class_node_ref(diagram_id, relation.from_class_id());
+                    let to = class_node_ref(diagram_id, relation.to_class_id());
+                    insert_node(&mut adjacency, relation_ref.clone());
+                    insert_node(&mut adjacency, from.clone());
+                    insert_node(&mut adjacency, to.clone());
+
+                    insert_edge(&mut adjacency, from.clone(), to.clone());
+
+                    insert_edge(&mut adjacency, from.clone(), relation_ref.clone());
+                    insert_edge(&mut adjacency, to.clone(), relation_ref.clone());
+                    insert_edge(&mut adjacency, relation_ref.clone(), from);
+                    insert_edge(&mut adjacency, relation_ref, to);
+                }
+            }
         }
     }
 
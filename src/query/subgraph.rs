@@ -0,0 +1,358 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Induced sub-diagrams for exporting a fragment of a larger diagram.
+
+use std::collections::BTreeSet;
+
+use crate::model::seq_ast::{SequenceAst, SequenceBlock};
+use crate::model::{ClassAst, Diagram, DiagramAst, FlowchartAst, ObjectId, ObjectRef, StateAst};
+
+/// Returns a copy of `diagram` reduced to the referenced objects plus induced relations.
+///
+/// Flowcharts keep the referenced nodes, the endpoints of referenced edges, the members of
+/// referenced subgraphs (with their enclosing subgraphs), and every edge whose endpoints are both
+/// kept. Sequence diagrams keep the referenced participants, the endpoints of referenced messages
+/// (blocks/sections contribute their messages, groups their participants), every message between
+/// kept participants, and notes anchored only on kept participants. State diagrams keep the
+/// referenced states, the endpoints of referenced transitions, and every transition between kept
+/// states. Class diagrams keep the referenced classes, the endpoints of referenced relations, and
+/// every relation between kept classes. Refs to other diagrams or unknown objects are ignored.
+pub fn induced_subdiagram<'a>(
+    diagram: &Diagram,
+    object_refs: impl IntoIterator<Item = &'a ObjectRef>,
+) -> Diagram {
+    let selected = object_refs
+        .into_iter()
+        .filter(|object_ref| object_ref.diagram_id() == diagram.diagram_id())
+        .map(|object_ref| (object_ref.category().segments().join("/"), object_ref.object_id()))
+        .collect::<Vec<_>>();
+
+    let ast = match diagram.ast() {
+        DiagramAst::Flowchart(ast) => DiagramAst::Flowchart(induced_flowchart(ast, &selected)),
+        DiagramAst::Sequence(ast) => DiagramAst::Sequence(induced_sequence(ast, &selected)),
+        DiagramAst::State(ast) => DiagramAst::State(induced_state(ast, &selected)),
+        DiagramAst::Class(ast) => DiagramAst::Class(induced_class(ast, &selected)),
+    };
+
+    let mut subdiagram = Diagram::new(diagram.diagram_id().clone(), diagram.name(), ast);
+    subdiagram.set_rev(diagram.rev());
+    subdiagram.set_description(diagram.description());
+    subdiagram
+}
+
+fn induced_flowchart(ast: &FlowchartAst, selected: &[(String, &ObjectId)]) -> FlowchartAst {
+    let mut node_ids = BTreeSet::<ObjectId>::new();
+    let mut edge_ids = BTreeSet::<ObjectId>::new();
+    for (category, object_id) in selected {
+        match category.as_str() {
+            "flow/node" if ast.nodes().contains_key(*object_id) => {
+                node_ids.insert((*object_id).clone());
+            }
+            "flow/edge" => {
+                if let Some(edge) = ast.edges().get(*object_id) {
+                    node_ids.insert(edge.from_node_id().clone());
+                    node_ids.insert(edge.to_node_id().clone());
+                    edge_ids.insert((*object_id).clone());
+                }
+            }
+            "flow/subgraph" => node_ids.extend(ast.subgraph_members(object_id)),
+            _ => {}
+        }
+    }
+
+    let mut out = FlowchartAst::default();
+    out.set_default_edge_style(ast.default_edge_style());
+    for node_id in &node_ids {
+        let Some(node) = ast.nodes().get(node_id) else {
+            continue;
+        };
+        out.nodes_mut().insert(node_id.clone(), node.clone());
+        if let Some(subgraph_id) = ast.node_subgraph(node_id) {
+            out.node_subgraphs_mut().insert(node_id.clone(), subgraph_id.clone());
+        }
+        for subgraph_id in ast.node_subgraph_chain(node_id) {
+            if let Some(subgraph) = ast.subgraphs().get(subgraph_id) {
+                out.subgraphs_mut().insert(subgraph_id.clone(), subgraph.clone());
+            }
+        }
+    }
+    for (edge_id, edge) in ast.edges() {
+        let induced =
+            node_ids.contains(edge.from_node_id()) && node_ids.contains(edge.to_node_id());
+        if induced || edge_ids.contains(edge_id) {
+            out.edges_mut().insert(edge_id.clone(), edge.clone());
+        }
+    }
+    out
+}
+
+fn induced_sequence(ast: &SequenceAst, selected: &[(String, &ObjectId)]) -> SequenceAst {
+    let mut participant_ids = BTreeSet::<ObjectId>::new();
+    let mut message_ids = BTreeSet::<ObjectId>::new();
+    for (category, object_id) in selected {
+        match category.as_str() {
+            "seq/participant" if ast.participants().contains_key(*object_id) => {
+                participant_ids.insert((*object_id).clone());
+            }
+            "seq/message" => {
+                message_ids.insert((*object_id).clone());
+            }
+            "seq/block" => {
+                if let Some(block) = ast.find_block(object_id) {
+                    collect_block_message_ids(block, &mut message_ids);
+                }
+            }
+            "seq/section" => {
+                if let Some(section) = ast.find_section(object_id) {
+                    message_ids.extend(section.message_ids().iter().cloned());
+                }
+            }
+            "seq/group" => {
+                if let Some(group) = ast.find_group(object_id) {
+                    participant_ids.extend(
+                        group
+                            .participant_ids()
+                            .iter()
+                            .filter(|id| ast.participants().contains_key(*id))
+                            .cloned(),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for message in ast.messages() {
+        if message_ids.contains(message.message_id()) {
+            participant_ids.insert(message.from_participant_id().clone());
+            participant_ids.insert(message.to_participant_id().clone());
+        }
+    }
+
+    let mut out = SequenceAst::default();
+    for participant_id in &participant_ids {
+        if let Some(participant) = ast.participants().get(participant_id) {
+            out.participants_mut().insert(participant_id.clone(), participant.clone());
+        }
+    }
+    let kept_messages = ast
+        .messages()
+        .iter()
+        .filter(|message| {
+            message_ids.contains(message.message_id())
+                || (participant_ids.contains(message.from_participant_id())
+                    && participant_ids.contains(message.to_participant_id()))
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    let kept_message_ids =
+        kept_messages.iter().map(|message| message.message_id().clone()).collect::<BTreeSet<_>>();
+    *out.messages_mut() = kept_messages;
+    *out.blocks_mut() =
+        ast.blocks().iter().filter_map(|block| prune_block(block, &kept_message_ids)).collect();
+    *out.notes_mut() = ast
+        .notes()
+        .iter()
+        .filter(|note| note.participant_ids().iter().all(|id| participant_ids.contains(id)))
+        .cloned()
+        .collect();
+    for group in ast.groups() {
+        let mut pruned = group.clone();
+        pruned.participant_ids_mut().retain(|id| participant_ids.contains(id));
+        if !pruned.participant_ids().is_empty() {
+            out.groups_mut().push(pruned);
+        }
+    }
+    out
+}
+
+fn collect_block_message_ids(block: &SequenceBlock, out: &mut BTreeSet<ObjectId>) {
+    for section in block.sections() {
+        out.extend(section.message_ids().iter().cloned());
+    }
+    for nested in block.blocks() {
+        collect_block_message_ids(nested, out);
+    }
+}
+
+fn induced_state(ast: &StateAst, selected: &[(String, &ObjectId)]) -> StateAst {
+    let mut state_ids = BTreeSet::<ObjectId>::new();
+    let mut transition_ids = BTreeSet::<ObjectId>::new();
+    for (category, object_id) in selected {
+        match category.as_str() {
+            "state/node" if ast.states().contains_key(*object_id) => {
+                state_ids.insert((*object_id).clone());
+            }
+            "state/transition" => {
+                if let Some(transition) = ast.transitions().get(*object_id) {
+                    state_ids.insert(transition.from_state_id().clone());
+                    state_ids.insert(transition.to_state_id().clone());
+                    transition_ids.insert((*object_id).clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut out = StateAst::default();
+    for state_id in &state_ids {
+        if let Some(state) = ast.states().get(state_id) {
+            out.states_mut().insert(state_id.clone(), state.clone());
+        }
+    }
+    for (transition_id, transition) in ast.transitions() {
+        let induced = state_ids.contains(transition.from_state_id())
+            && state_ids.contains(transition.to_state_id());
+        if induced || transition_ids.contains(transition_id) {
+            out.transitions_mut().insert(transition_id.clone(), transition.clone());
+        }
+    }
+    out
+}
+
+fn induced_class(ast: &ClassAst, selected: &[(String, &ObjectId)]) -> ClassAst {
+    let mut class_ids = BTreeSet::<ObjectId>::new();
+    let mut relation_ids = BTreeSet::<ObjectId>::new();
+    for (category, object_id) in selected {
+        match category.as_str() {
+            "class/node" if ast.classes().contains_key(*object_id) => {
+                class_ids.insert((*object_id).clone());
+            }
+            "class/relation" => {
+                if let Some(relation) = ast.relations().get(*object_id) {
+                    class_ids.insert(relation.from_class_id().clone());
+                    class_ids.insert(relation.to_class_id().clone());
+                    relation_ids.insert((*object_id).clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut out = ClassAst::default();
+    for class_id in &class_ids {
+        if let Some(class) = ast.classes().get(class_id) {
+            out.classes_mut().insert(class_id.clone(), class.clone());
+        }
+    }
+    for (relation_id, relation) in ast.relations() {
+        let induced = class_ids.contains(relation.from_class_id())
+            && class_ids.contains(relation.to_class_id());
+        if induced || relation_ids.contains(relation_id) {
+            out.relations_mut().insert(relation_id.clone(), relation.clone());
+        }
+    }
+    out
+}
+
+fn prune_block(block: &SequenceBlock, kept: &BTreeSet<ObjectId>) -> Option<SequenceBlock> {
+    let mut pruned = block.clone();
+    for section in pruned.sections_mut() {
+        section.message_ids_mut().retain(|message_id| kept.contains(message_id));
+    }
+    *pruned.blocks_mut() =
+        block.blocks().iter().filter_map(|nested| prune_block(nested, kept)).collect();
+
+    let has_messages = pruned.sections().iter().any(|section| !section.message_ids().is_empty());
+    (has_messages || !pruned.blocks().is_empty()).then_some(pruned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::induced_subdiagram;
+    use crate::format::mermaid::{parse_flowchart, parse_sequence_diagram};
+    use crate::model::{CategoryPath, Diagram, DiagramAst, DiagramId, ObjectId, ObjectRef};
+
+    fn object_ref(diagram_id: &str, category: &[&str], object_id: &str) -> ObjectRef {
+        ObjectRef::new(
+            DiagramId::new(diagram_id).expect("diagram id"),
+            CategoryPath::new(category.iter().map(|s| (*s).to_owned()).collect())
+                .expect("category"),
+            ObjectId::new(object_id).expect("object id"),
+        )
+    }
+
+    #[test]
+    fn flowchart_subset_keeps_induced_edges_and_edge_endpoints() {
+        let ast = parse_flowchart("flowchart TD\nA --> B\nB --> C\nA --> C\nC --> D\nE --> D\n")
+            .expect("parse");
+        let diagram =
+            Diagram::new(DiagramId::new("flow").expect("id"), "Flow", DiagramAst::Flowchart(ast));
+        let edge_e_d = match diagram.ast() {
+            DiagramAst::Flowchart(ast) => ast
+                .edges()
+                .iter()
+                .find(|(_, edge)| edge.from_node_id().as_str() == "n:E")
+                .map(|(edge_id, _)| edge_id.as_str().to_owned())
+                .expect("edge"),
+            _ => unreachable!(),
+        };
+
+        let refs = [
+            object_ref("flow", &["flow", "node"], "n:A"),
+            object_ref("flow", &["flow", "node"], "n:C"),
+            object_ref("flow", &["flow", "edge"], &edge_e_d),
+            object_ref("other", &["flow", "node"], "n:B"),
+        ];
+        let subset = induced_subdiagram(&diagram, &refs);
+
+        let DiagramAst::Flowchart(ast) = subset.ast() else { panic!("expected flowchart") };
+        let nodes = ast.nodes().keys().map(|id| id.as_str()).collect::<Vec<_>>();
+        assert_eq!(nodes, vec!["n:A", "n:C", "n:D", "n:E"]);
+        let mut edges = ast
+            .edges()
+            .values()
+            .map(|edge| (edge.from_node_id().as_str(), edge.to_node_id().as_str()))
+            .collect::<Vec<_>>();
+        edges.sort();
+        assert_eq!(edges, vec![("n:A", "n:C"), ("n:C", "n:D"), ("n:E", "n:D")]);
+    }
+
+    #[test]
+    fn sequence_subset_keeps_messages_between_kept_participants() {
+        let ast = parse_sequence_diagram(
+            "sequenceDiagram\nparticipant A\nparticipant B\nparticipant C\nA->>B: one\nB->>C: two\nNote over B,C: bc\nB-->>A: three\nNote left of A: a\n",
+        )
+        .expect("parse");
+        let diagram =
+            Diagram::new(DiagramId::new("seq").expect("id"), "Seq", DiagramAst::Sequence(ast));
+
+        let refs = [
+            object_ref("seq", &["seq", "participant"], "p:A"),
+            object_ref("seq", &["seq", "participant"], "p:B"),
+        ];
+        let subset = induced_subdiagram(&diagram, &refs);
+
+        let DiagramAst::Sequence(ast) = subset.ast() else { panic!("expected sequence") };
+        assert_eq!(ast.participants().len(), 2);
+        let texts = ast.messages_in_order().iter().map(|m| m.text()).collect::<Vec<_>>();
+        assert_eq!(texts, vec!["one", "three"]);
+        let notes = ast.notes().iter().map(|note| note.text()).collect::<Vec<_>>();
+        assert_eq!(notes, vec!["a"]);
+    }
+    #[test]
+    fn sequence_subset_expands_group_refs_to_members() {
+        let ast = parse_sequence_diagram(
+            "sequenceDiagram\nbox Edge\nparticipant A\nparticipant B\nend\nparticipant C\nA->>B: one\nB->>C: two\n",
+        )
+        .expect("parse");
+        let diagram =
+            Diagram::new(DiagramId::new("seq").expect("id"), "Seq", DiagramAst::Sequence(ast));
+
+        let refs = [object_ref("seq", &["seq", "group"], "g:0001")];
+        let subset = induced_subdiagram(&diagram, &refs);
+
+        let DiagramAst::Sequence(ast) = subset.ast() else { panic!("expected sequence") };
+        let participants = ast.participants().keys().map(|id| id.as_str()).collect::<Vec<_>>();
+        assert_eq!(participants, vec!["p:A", "p:B"]);
+        assert_eq!(ast.groups().len(), 1);
+        let texts = ast.messages_in_order().iter().map(|m| m.text()).collect::<Vec<_>>();
+        assert_eq!(texts, vec!["one"]);
+    }
+}
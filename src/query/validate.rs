@@ -0,0 +1,315 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Session integrity checks that cut across diagrams, xrefs and walkthroughs.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::format::mermaid::flowchart::mermaid_id_for_node;
+use crate::model::{CategoryPath, Diagram, DiagramAst, ObjectId, ObjectRef, Session};
+
+/// How badly a finding breaks the session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The diagram cannot be exported or rendered faithfully.
+    Error,
+    /// A link points nowhere; the diagrams themselves stay intact.
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What kind of problem a finding is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiagnosticCode {
+    /// An xref endpoint that does not exist.
+    DanglingXRef,
+    /// Two objects of one diagram that export to the same Mermaid identifier.
+    DuplicateMermaidId,
+    /// A walkthrough step referencing an object that does not exist.
+    OrphanWalkthroughRef,
+    /// A sequence message from or to a participant that does not exist.
+    MissingParticipant,
+    /// A flowchart edge from or to a node that does not exist.
+    MissingNode,
+}
+
+impl DiagnosticCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::DanglingXRef => "dangling_xref",
+            Self::DuplicateMermaidId => "duplicate_mermaid_id",
+            Self::OrphanWalkthroughRef => "orphan_walkthrough_ref",
+            Self::MissingParticipant => "missing_participant",
+            Self::MissingNode => "missing_node",
+        }
+    }
+
+    pub fn severity(self) -> Severity {
+        match self {
+            Self::DuplicateMermaidId | Self::MissingParticipant | Self::MissingNode => {
+                Severity::Error
+            }
+            Self::DanglingXRef | Self::OrphanWalkthroughRef => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One integrity problem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub code: DiagnosticCode,
+    /// What carries the problem: an object ref, xref id or `walkthrough_id/node_id`.
+    pub subject: String,
+    /// Objects involved; missing ones included.
+    pub object_refs: Vec<ObjectRef>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        self.code.severity()
+    }
+}
+
+/// Every integrity problem of `session`, errors first, then by code and subject.
+pub fn validate_session(session: &Session) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for diagram in session.diagrams().values() {
+        match diagram.ast() {
+            DiagramAst::Flowchart(_) => check_flowchart(diagram, &mut diagnostics),
+            DiagramAst::Sequence(_) => check_sequence(diagram, &mut diagnostics),
+            DiagramAst::State(_) | DiagramAst::Class(_) => {}
+        }
+    }
+
+    for (xref_id, xref) in session.xrefs() {
+        let missing = [xref.from(), xref.to()]
+            .into_iter()
+            .filter(|object_ref| !session.object_ref_exists(object_ref))
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            continue;
+        }
+        let names = missing.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ");
+        diagnostics.push(Diagnostic {
+            code: DiagnosticCode::DanglingXRef,
+            subject: xref_id.to_string(),
+            object_refs: vec![xref.from().clone(), xref.to().clone()],
+            message: format!("xref {xref_id} points to missing {names}"),
+        });
+    }
+
+    for (walkthrough_id, walkthrough) in session.walkthroughs() {
+        for node in walkthrough.nodes() {
+            for object_ref in node.refs().iter().filter(|r| !session.object_ref_exists(r)) {
+                diagnostics.push(Diagnostic {
+                    code: DiagnosticCode::OrphanWalkthroughRef,
+                    subject: format!("{walkthrough_id}/{}", node.node_id()),
+                    object_refs: vec![object_ref.clone()],
+                    message: format!(
+                        "walkthrough step {:?} references missing {object_ref}",
+                        node.title()
+                    ),
+                });
+            }
+        }
+    }
+
+    diagnostics.sort_by(|a, b| {
+        (a.severity(), a.code, &a.subject).cmp(&(b.severity(), b.code, &b.subject))
+    });
+    diagnostics
+}
+
+fn check_flowchart(diagram: &Diagram, diagnostics: &mut Vec<Diagnostic>) {
+    let DiagramAst::Flowchart(ast) = diagram.ast() else {
+        return;
+    };
+    let node_ref = |node_id: &ObjectId| object_ref(diagram, &["flow", "node"], node_id);
+
+    for (edge_id, edge) in ast.edges() {
+        for node_id in [edge.from_node_id(), edge.to_node_id()] {
+            if ast.nodes().contains_key(node_id) {
+                continue;
+            }
+            let edge_ref = object_ref(diagram, &["flow", "edge"], edge_id);
+            diagnostics.push(Diagnostic {
+                code: DiagnosticCode::MissingNode,
+                subject: edge_ref.to_string(),
+                message: format!("edge {edge_id} references missing node {node_id}"),
+                object_refs: vec![edge_ref, node_ref(node_id)],
+            });
+        }
+    }
+
+    let mut by_mermaid_id = BTreeMap::<&str, Vec<ObjectRef>>::new();
+    for (node_id, node) in ast.nodes() {
+        if let Some(mermaid_id) = mermaid_id_for_node(node_id, node) {
+            by_mermaid_id.entry(mermaid_id).or_default().push(node_ref(node_id));
+        }
+    }
+    for (subgraph_id, subgraph) in ast.subgraphs() {
+        if let Some(mermaid_id) = subgraph.mermaid_id() {
+            let subgraph_ref = object_ref(diagram, &["flow", "subgraph"], subgraph_id);
+            by_mermaid_id.entry(mermaid_id).or_default().push(subgraph_ref);
+        }
+    }
+    push_duplicates(by_mermaid_id, diagnostics);
+}
+
+fn check_sequence(diagram: &Diagram, diagnostics: &mut Vec<Diagnostic>) {
+    let DiagramAst::Sequence(ast) = diagram.ast() else {
+        return;
+    };
+    let participant_ref =
+        |participant_id: &ObjectId| object_ref(diagram, &["seq", "participant"], participant_id);
+
+    for message in ast.messages() {
+        for participant_id in [message.from_participant_id(), message.to_participant_id()] {
+            if ast.participants().contains_key(participant_id) {
+                continue;
+            }
+            let message_ref = object_ref(diagram, &["seq", "message"], message.message_id());
+            diagnostics.push(Diagnostic {
+                code: DiagnosticCode::MissingParticipant,
+                subject: message_ref.to_string(),
+                message: format!(
+                    "message {} references missing participant {participant_id}",
+                    message.message_id()
+                ),
+                object_refs: vec![message_ref, participant_ref(participant_id)],
+            });
+        }
+    }
+
+    let mut by_mermaid_name = BTreeMap::<&str, Vec<ObjectRef>>::new();
+    for (participant_id, participant) in ast.participants() {
+        by_mermaid_name
+            .entry(participant.mermaid_name())
+            .or_default()
+            .push(participant_ref(participant_id));
+    }
+    push_duplicates(by_mermaid_name, diagnostics);
+}
+
+fn push_duplicates(by_mermaid_id: BTreeMap<&str, Vec<ObjectRef>>, out: &mut Vec<Diagnostic>) {
+    for (mermaid_id, object_refs) in by_mermaid_id.into_iter().filter(|(_, refs)| refs.len() > 1) {
+        let names =
+            object_refs.iter().map(|r| r.object_id().to_string()).collect::<Vec<_>>().join(", ");
+        out.push(Diagnostic {
+            code: DiagnosticCode::DuplicateMermaidId,
+            subject: object_refs[0].to_string(),
+            message: format!("{names} all export as Mermaid id {mermaid_id:?}"),
+            object_refs,
+        });
+    }
+}
+
+fn object_ref(diagram: &Diagram, category: &[&str], object_id: &ObjectId) -> ObjectRef {
+    let category =
+        CategoryPath::new(category.iter().map(|segment| (*segment).to_owned()).collect())
+            .expect("valid category path");
+    ObjectRef::new(diagram.diagram_id().clone(), category, object_id.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate_session, DiagnosticCode, Severity};
+    use crate::format::mermaid::{parse_flowchart, parse_sequence_diagram};
+    use crate::model::{
+        Diagram, DiagramAst, DiagramId, FlowNode, FlowchartAst, ObjectId, ObjectRef, SequenceAst,
+        Session, SessionId, Walkthrough, WalkthroughId, WalkthroughNode, WalkthroughNodeId, XRef,
+        XRefId, XRefStatus,
+    };
+
+    fn asts() -> (FlowchartAst, SequenceAst) {
+        let flow = parse_flowchart("flowchart LR\nA --> B\n").expect("parse flowchart");
+        let seq = parse_sequence_diagram("sequenceDiagram\nAlice->>Bob: Hi\n").expect("parse seq");
+        (flow, seq)
+    }
+
+    fn session(flow: FlowchartAst, seq: SequenceAst) -> Session {
+        let mut session = Session::new(SessionId::new("s").expect("session id"));
+        for (id, ast) in [("flow", DiagramAst::Flowchart(flow)), ("seq", DiagramAst::Sequence(seq))]
+        {
+            let diagram_id = DiagramId::new(id).expect("diagram id");
+            session.diagrams_mut().insert(diagram_id.clone(), Diagram::new(diagram_id, id, ast));
+        }
+        session
+    }
+
+    #[test]
+    fn clean_session_has_no_diagnostics() {
+        let (flow, seq) = asts();
+        assert!(validate_session(&session(flow, seq)).is_empty());
+    }
+
+    #[test]
+    fn reports_broken_references_and_duplicate_mermaid_ids() {
+        let (mut flow, mut seq) = asts();
+        flow.nodes_mut().remove(&ObjectId::new("n:B").expect("node id"));
+        flow.nodes_mut().insert(
+            ObjectId::new("n:A2").expect("node id"),
+            FlowNode::new_with("Again", "rect", Some("A".to_owned())),
+        );
+        seq.participants_mut().remove(&ObjectId::new("p:Bob").expect("participant id"));
+        let mut session = session(flow, seq);
+
+        let missing: ObjectRef = "d:flow/flow/node/n:gone".parse().expect("object ref");
+        let alice: ObjectRef = "d:seq/seq/participant/p:Alice".parse().expect("object ref");
+        session.xrefs_mut().insert(
+            XRefId::new("x:1").expect("xref id"),
+            XRef::new(alice, missing.clone(), "uses", XRefStatus::Ok),
+        );
+        let mut walkthrough = Walkthrough::new(WalkthroughId::new("w:1").expect("id"), "Tour");
+        let mut step = WalkthroughNode::new(WalkthroughNodeId::new("wn:1").expect("id"), "Step");
+        step.refs_mut().push(missing);
+        walkthrough.nodes_mut().push(step);
+        session.walkthroughs_mut().insert(walkthrough.walkthrough_id().clone(), walkthrough);
+
+        let report = validate_session(&session)
+            .into_iter()
+            .map(|diagnostic| (diagnostic.severity(), diagnostic.code, diagnostic.subject))
+            .collect::<Vec<_>>();
+        let subjects = report.iter().map(|(_, _, subject)| subject.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            report.iter().map(|(severity, code, _)| (*severity, *code)).collect::<Vec<_>>(),
+            vec![
+                (Severity::Error, DiagnosticCode::DuplicateMermaidId),
+                (Severity::Error, DiagnosticCode::MissingParticipant),
+                (Severity::Error, DiagnosticCode::MissingNode),
+                (Severity::Warning, DiagnosticCode::DanglingXRef),
+                (Severity::Warning, DiagnosticCode::OrphanWalkthroughRef),
+            ]
+        );
+        assert_eq!(subjects[0], "d:flow/flow/node/n:A");
+        assert!(subjects[1].starts_with("d:seq/seq/message/"));
+        assert_eq!(subjects[2..], ["d:flow/flow/edge/e:0001", "x:1", "w:1/wn:1"]);
+    }
+}
@@ -0,0 +1,242 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Render budgets for large sessions.
+//!
+//! A budget caps how long a diagram may take to lay out and render, and how many canvas cells the
+//! result may cover. Renders over budget are redone at a lower level of detail (notes hidden,
+//! compact flowchart spacing); the TUI switches into its large-session mode when that happens.
+
+use std::env;
+use std::fmt;
+use std::time::Duration;
+
+use crate::layout::DEFAULT_CROSSING_SWEEPS;
+use crate::model::Diagram;
+
+use super::diagram::{DiagramRenderError, IncrementalRenderer, RenderTimings};
+use super::text::text_len;
+use super::{AnnotatedRender, RenderOptions};
+
+/// Environment variable overriding [`RenderBudget::max_render_time`] (milliseconds).
+pub const MAX_RENDER_MS_ENV: &str = "NEREID_MAX_RENDER_MS";
+/// Environment variable overriding [`RenderBudget::max_canvas_cells`].
+pub const MAX_CANVAS_CELLS_ENV: &str = "NEREID_MAX_CANVAS_CELLS";
+/// Environment variable overriding [`RenderBudget::crossing_sweeps`].
+pub const CROSSING_SWEEPS_ENV: &str = "NEREID_CROSSING_SWEEPS";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderBudget {
+    /// Upper bound for layout plus render time.
+    pub max_render_time: Duration,
+    /// Upper bound for `width * height` of the rendered text.
+    pub max_canvas_cells: usize,
+    /// Crossing-reduction sweeps spent on each flowchart layout.
+    pub crossing_sweeps: usize,
+}
+
+impl Default for RenderBudget {
+    fn default() -> Self {
+        Self {
+            max_render_time: Duration::from_millis(150),
+            max_canvas_cells: 200_000,
+            crossing_sweeps: DEFAULT_CROSSING_SWEEPS,
+        }
+    }
+}
+
+impl RenderBudget {
+    /// Reads the budget from [`MAX_RENDER_MS_ENV`], [`MAX_CANVAS_CELLS_ENV`] and
+    /// [`CROSSING_SWEEPS_ENV`], keeping defaults for unset or blank variables.
+    pub fn from_env() -> Result<Self, String> {
+        Self::from_lookup(|name| env::var(name).ok())
+    }
+
+    /// Same as [`RenderBudget::from_env`] with a custom variable lookup.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let read = |name: &str| -> Result<Option<u64>, String> {
+            match lookup(name) {
+                Some(value) if !value.trim().is_empty() => {
+                    value.trim().parse::<u64>().map(Some).map_err(|_| {
+                        format!("{name} must be a non-negative integer, got {value:?}")
+                    })
+                }
+                _ => Ok(None),
+            }
+        };
+
+        let mut budget = Self::default();
+        if let Some(millis) = read(MAX_RENDER_MS_ENV)? {
+            budget.max_render_time = Duration::from_millis(millis);
+        }
+        if let Some(cells) = read(MAX_CANVAS_CELLS_ENV)? {
+            budget.max_canvas_cells = usize::try_from(cells).unwrap_or(usize::MAX);
+        }
+        if let Some(sweeps) = read(CROSSING_SWEEPS_ENV)? {
+            budget.crossing_sweeps = usize::try_from(sweeps).unwrap_or(usize::MAX);
+        }
+        Ok(budget)
+    }
+
+    /// Returns the first limit that `text`/`timings` exceed, if any.
+    pub fn overrun(&self, text: &str, timings: RenderTimings) -> Option<BudgetOverrun> {
+        let elapsed = timings.layout + timings.render;
+        if elapsed > self.max_render_time {
+            return Some(BudgetOverrun::RenderTime { elapsed, budget: self.max_render_time });
+        }
+        let cells = canvas_cells(text);
+        (cells > self.max_canvas_cells)
+            .then_some(BudgetOverrun::CanvasCells { cells, budget: self.max_canvas_cells })
+    }
+}
+
+/// Which budget limit a render exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetOverrun {
+    RenderTime { elapsed: Duration, budget: Duration },
+    CanvasCells { cells: usize, budget: usize },
+}
+
+impl fmt::Display for BudgetOverrun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RenderTime { elapsed, budget } => write!(
+                f,
+                "render took {:.1} ms (budget {} ms)",
+                elapsed.as_secs_f64() * 1000.0,
+                budget.as_millis()
+            ),
+            Self::CanvasCells { cells, budget } => {
+                write!(f, "canvas has {cells} cells (budget {budget})")
+            }
+        }
+    }
+}
+
+/// Width times height of rendered text, in character cells.
+pub fn canvas_cells(text: &str) -> usize {
+    let width = text.split('\n').map(text_len).max().unwrap_or(0);
+    width.saturating_mul(text.split('\n').count())
+}
+
+/// Reduced-detail variant of `options`: notes hidden and compact flowchart spacing.
+pub fn level_of_detail_options(options: RenderOptions) -> RenderOptions {
+    RenderOptions {
+        show_notes: false,
+        flowchart_extra_col_gap: 0,
+        flowchart_compact: true,
+        ..options
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BudgetedRender {
+    pub rendered: AnnotatedRender,
+    pub timings: RenderTimings,
+    /// Set when the full-detail render was over budget and `rendered` uses
+    /// [`level_of_detail_options`] instead.
+    pub overrun: Option<BudgetOverrun>,
+}
+
+/// Renders `diagram` with `options`, falling back to [`level_of_detail_options`] when the result
+/// exceeds `budget`. The level-of-detail render is returned even if it is still over budget.
+pub fn render_diagram_within_budget(
+    diagram: &Diagram,
+    options: RenderOptions,
+    budget: &RenderBudget,
+) -> Result<BudgetedRender, DiagramRenderError> {
+    render_diagram_within_budget_incremental(
+        &mut IncrementalRenderer::default(),
+        diagram,
+        options,
+        budget,
+    )
+}
+
+/// Same as [`render_diagram_within_budget`], laying out through `renderer` so edits that keep the
+/// diagram structure skip layout and edge routing.
+pub fn render_diagram_within_budget_incremental(
+    renderer: &mut IncrementalRenderer,
+    diagram: &Diagram,
+    options: RenderOptions,
+    budget: &RenderBudget,
+) -> Result<BudgetedRender, DiagramRenderError> {
+    let (rendered, timings) = renderer.render_annotated_timed(diagram, options)?;
+    let lod_options = level_of_detail_options(options);
+    let Some(overrun) = budget.overrun(&rendered.text, timings) else {
+        return Ok(BudgetedRender { rendered, timings, overrun: None });
+    };
+    if lod_options == options {
+        return Ok(BudgetedRender { rendered, timings, overrun: None });
+    }
+
+    let (rendered, timings) = renderer.render_annotated_timed(diagram, lod_options)?;
+    Ok(BudgetedRender { rendered, timings, overrun: Some(overrun) })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{
+        canvas_cells, render_diagram_within_budget, BudgetOverrun, RenderBudget,
+        CROSSING_SWEEPS_ENV, MAX_CANVAS_CELLS_ENV, MAX_RENDER_MS_ENV,
+    };
+    use crate::format::mermaid::parse_flowchart;
+    use crate::model::{Diagram, DiagramAst, DiagramId};
+    use crate::render::RenderOptions;
+
+    #[test]
+    fn budget_reads_overrides_and_rejects_garbage() {
+        let budget = RenderBudget::from_lookup(|name| match name {
+            MAX_RENDER_MS_ENV => Some("40".to_owned()),
+            MAX_CANVAS_CELLS_ENV => Some(" ".to_owned()),
+            CROSSING_SWEEPS_ENV => Some("2".to_owned()),
+            _ => None,
+        })
+        .expect("budget");
+        assert_eq!(budget.max_render_time, Duration::from_millis(40));
+        assert_eq!(budget.max_canvas_cells, RenderBudget::default().max_canvas_cells);
+        assert_eq!(budget.crossing_sweeps, 2);
+
+        let err = RenderBudget::from_lookup(|name| {
+            (name == MAX_CANVAS_CELLS_ENV).then(|| "lots".to_owned())
+        })
+        .expect_err("invalid cells");
+        assert!(err.contains(MAX_CANVAS_CELLS_ENV));
+    }
+
+    #[test]
+    fn over_budget_render_falls_back_to_level_of_detail() {
+        let ast = parse_flowchart("flowchart LR\nA[Start] --> B[Middle]\nB --> C[End]\n")
+            .expect("parse flowchart");
+        let diagram =
+            Diagram::new(DiagramId::new("flow").expect("id"), "Flow", DiagramAst::Flowchart(ast));
+        let options =
+            RenderOptions { show_notes: true, flowchart_extra_col_gap: 4, ..Default::default() };
+
+        let roomy = render_diagram_within_budget(&diagram, options, &RenderBudget::default())
+            .expect("render");
+        assert_eq!(roomy.overrun, None);
+
+        let tight = RenderBudget {
+            max_render_time: Duration::from_secs(60),
+            max_canvas_cells: 1,
+            ..RenderBudget::default()
+        };
+        let reduced = render_diagram_within_budget(&diagram, options, &tight).expect("render");
+        assert_eq!(
+            reduced.overrun,
+            Some(BudgetOverrun::CanvasCells {
+                cells: canvas_cells(&roomy.rendered.text),
+                budget: 1
+            })
+        );
+        assert!(canvas_cells(&reduced.rendered.text) < canvas_cells(&roomy.rendered.text));
+    }
+}
@@ -0,0 +1,677 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Unicode rendering for class diagrams: UML-style boxes with a name compartment plus attribute
+//! and method compartments, joined by orthogonal relation lines.
+//!
+//! Relations between neighbouring columns turn in a lane of the gap between them; longer ones
+//! drop to a bus row below every box. The `to` end carries the marker (`◁`/`▷` inheritance and
+//! realization, `◆` composition, `◇` aggregation, `◀`/`▶` association and dependency), dashed
+//! relations use `┄`/`┆`, and cardinalities sit next to their class. Self-relations loop through
+//! the gap right of their class and draw no label.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::layout::ClassLayout;
+use crate::model::class_ast::{ClassAst, ClassNode, ClassRelationKind};
+use crate::model::ids::{DiagramId, ObjectId};
+use crate::model::{CategoryPath, ObjectRef};
+
+use super::text::{canvas_to_string_trimmed, text_len};
+use super::{
+    clamp_highlight_index_to_text, AnnotatedRender, Canvas, CanvasError, HighlightIndex, LineSpan,
+    RenderOptions, UNICODE_BOX_HORIZONTAL, UNICODE_BOX_VERTICAL, UNICODE_DOTTED_HORIZONTAL,
+    UNICODE_DOTTED_VERTICAL,
+};
+
+const OBJECT_LABEL_PREFIX: &str = "▴ ";
+const ROW_GAP: usize = 1;
+const MIN_GAP_WIDTH: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClassRenderError {
+    Canvas(CanvasError),
+    MissingClass { class_id: ObjectId },
+}
+
+impl fmt::Display for ClassRenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Canvas(err) => write!(f, "canvas error: {err}"),
+            Self::MissingClass { class_id } => write!(f, "missing class {class_id} in layout"),
+        }
+    }
+}
+
+impl std::error::Error for ClassRenderError {}
+
+impl From<CanvasError> for ClassRenderError {
+    fn from(value: CanvasError) -> Self {
+        Self::Canvas(value)
+    }
+}
+
+pub fn render_class_unicode(
+    ast: &ClassAst,
+    layout: &ClassLayout,
+) -> Result<String, ClassRenderError> {
+    render_class_unicode_with_options(ast, layout, RenderOptions::default())
+}
+
+pub fn render_class_unicode_with_options(
+    ast: &ClassAst,
+    layout: &ClassLayout,
+    options: RenderOptions,
+) -> Result<String, ClassRenderError> {
+    let diagram_id = DiagramId::new("class").expect("valid diagram id");
+    Ok(render_class_unicode_annotated_with_options(&diagram_id, ast, layout, options)?.text)
+}
+
+/// Renders like [`render_class_unicode`], keyed by `class/node` and `class/relation` refs.
+pub fn render_class_unicode_annotated_with_options(
+    diagram_id: &DiagramId,
+    ast: &ClassAst,
+    layout: &ClassLayout,
+    options: RenderOptions,
+) -> Result<AnnotatedRender, ClassRenderError> {
+    let plan = ClassPlan::build(ast, layout, options)?;
+    let mut canvas = Canvas::new(plan.width, plan.height)?;
+    let mut highlight_index = HighlightIndex::new();
+
+    let node_category = category(["class", "node"]);
+    for (class_id, class_box) in &plan.boxes {
+        class_box.draw(&mut canvas)?;
+        let spans = (class_box.y0..=class_box.y1).map(|y| (y, class_box.x0, class_box.x1));
+        let object_ref =
+            ObjectRef::new(diagram_id.clone(), node_category.clone(), class_id.clone());
+        highlight_index.insert(object_ref, spans.collect());
+    }
+
+    let relation_category = category(["class", "relation"]);
+    let mut owners = BTreeMap::<(usize, usize), Option<bool>>::new();
+    for route in &plan.routes {
+        let cells = route.draw(&mut canvas)?;
+        for &cell in &cells {
+            // Cells shared by two routes stay solid.
+            owners
+                .entry(cell)
+                .and_modify(|owner| *owner = None)
+                .or_insert(Some(route.kind.is_dashed()));
+        }
+        let object_ref = ObjectRef::new(
+            diagram_id.clone(),
+            relation_category.clone(),
+            route.relation_id.clone(),
+        );
+        highlight_index.insert(object_ref, cell_spans(cells));
+    }
+    for route in &plan.routes {
+        route.draw_text(&mut canvas)?;
+    }
+
+    let dashed = owners.into_iter().filter(|(_, owner)| *owner == Some(true)).map(|(cell, _)| cell);
+    let text = restyle_dashed(canvas_to_string_trimmed(&canvas), dashed);
+    clamp_highlight_index_to_text(&mut highlight_index, &text);
+    Ok(AnnotatedRender { text, highlight_index })
+}
+
+fn category<const N: usize>(segments: [&str; N]) -> CategoryPath {
+    CategoryPath::new(segments.map(ToOwned::to_owned).to_vec()).expect("valid category")
+}
+
+/// Redraws the straight runs of dashed relations; corners and tees keep their solid glyphs.
+fn restyle_dashed(text: String, cells: impl Iterator<Item = (usize, usize)>) -> String {
+    let mut lines =
+        text.split('\n').map(|line| line.chars().collect::<Vec<_>>()).collect::<Vec<_>>();
+    for (x, y) in cells {
+        let Some(ch) = lines.get_mut(y).and_then(|line| line.get_mut(x)) else {
+            continue;
+        };
+        *ch = match *ch {
+            UNICODE_BOX_HORIZONTAL => UNICODE_DOTTED_HORIZONTAL,
+            UNICODE_BOX_VERTICAL => UNICODE_DOTTED_VERTICAL,
+            other => other,
+        };
+    }
+    lines
+        .into_iter()
+        .map(|line| line.into_iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Merges cells into per-row spans.
+fn cell_spans(mut cells: Vec<(usize, usize)>) -> Vec<LineSpan> {
+    cells.sort_by_key(|&(x, y)| (y, x));
+    cells.dedup();
+    let mut spans = Vec::<LineSpan>::new();
+    for (x, y) in cells {
+        match spans.last_mut() {
+            Some((span_y, _, x1)) if *span_y == y && *x1 + 1 == x => *x1 = x,
+            _ => spans.push((y, x, x)),
+        }
+    }
+    spans
+}
+
+#[derive(Debug, Clone)]
+enum BoxRow {
+    Centered(String),
+    Member(String),
+    Separator,
+}
+
+#[derive(Debug, Clone)]
+struct ClassBox {
+    rows: Vec<BoxRow>,
+    x0: usize,
+    x1: usize,
+    y0: usize,
+    y1: usize,
+}
+
+impl ClassBox {
+    fn new(class: &ClassNode, options: RenderOptions) -> Self {
+        let mut rows = Vec::new();
+        if let Some(annotation) = class.annotation() {
+            rows.push(BoxRow::Centered(format!("«{annotation}»")));
+        }
+        let label = if options.prefix_object_labels {
+            format!("{OBJECT_LABEL_PREFIX}{}", class.label())
+        } else {
+            class.label().to_owned()
+        };
+        rows.push(BoxRow::Centered(label));
+        for compartment in [class.attributes().collect::<Vec<_>>(), class.methods().collect()] {
+            if !compartment.is_empty() {
+                rows.push(BoxRow::Separator);
+                rows.extend(compartment.into_iter().map(|m| BoxRow::Member(m.text().to_owned())));
+            }
+        }
+        Self { rows, x0: 0, x1: 0, y0: 0, y1: 0 }
+    }
+
+    /// Pads the box until `count` rows can take a relation end.
+    fn reserve_attach_rows(&mut self, count: usize) {
+        while self.attach_rows().len() < count {
+            self.rows.push(BoxRow::Member(String::new()));
+        }
+    }
+
+    /// Rows (relative to the top border) a relation can attach to.
+    fn attach_rows(&self) -> Vec<usize> {
+        let rows = self.rows.iter().enumerate();
+        rows.filter(|(_, row)| !matches!(row, BoxRow::Separator)).map(|(idx, _)| idx + 1).collect()
+    }
+
+    fn width(&self) -> usize {
+        let text_width = self.rows.iter().map(|row| match row {
+            BoxRow::Centered(text) | BoxRow::Member(text) => text_len(text),
+            BoxRow::Separator => 0,
+        });
+        text_width.max().unwrap_or(0) + 4
+    }
+
+    fn height(&self) -> usize {
+        self.rows.len() + 2
+    }
+
+    fn draw(&self, canvas: &mut Canvas) -> Result<(), CanvasError> {
+        canvas.draw_box(self.x0, self.y0, self.x1, self.y1)?;
+        let inner = self.x1 - self.x0 - 3;
+        for (idx, row) in self.rows.iter().enumerate() {
+            let y = self.y0 + 1 + idx;
+            match row {
+                BoxRow::Separator => canvas.draw_hline(self.x0, self.x1, y)?,
+                BoxRow::Centered(text) => {
+                    let x = self.x0 + 2 + inner.saturating_sub(text_len(text)) / 2;
+                    canvas.write_str(x, y, text)?;
+                }
+                BoxRow::Member(text) => canvas.write_str(self.x0 + 2, y, text)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone)]
+struct RouteEnd {
+    class_id: ObjectId,
+    side: Side,
+    y: usize,
+    cardinality: Option<String>,
+    marker: Option<char>,
+}
+
+#[derive(Debug, Clone)]
+struct Route {
+    relation_id: ObjectId,
+    kind: ClassRelationKind,
+    label: Option<String>,
+    /// The end in the left column; both ends attach on the right for same-column routes.
+    a: RouteEnd,
+    b: RouteEnd,
+    /// Corner points from `a`'s box border to `b`'s box border, filled in by the plan.
+    points: Vec<(usize, usize)>,
+    /// Where the label starts, on the segment entering `b`.
+    label_x: usize,
+}
+
+impl Route {
+    /// Draws the line and returns its cells, without the box borders it starts and ends on.
+    fn draw(&self, canvas: &mut Canvas) -> Result<Vec<(usize, usize)>, CanvasError> {
+        let mut cells = Vec::new();
+        for pair in self.points.windows(2) {
+            let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+            if (x0, y0) == (x1, y1) {
+                continue;
+            }
+            if y0 == y1 {
+                canvas.draw_hline(x0, x1, y0)?;
+                cells.extend((x0.min(x1)..=x0.max(x1)).map(|x| (x, y0)));
+            } else {
+                canvas.draw_vline(x0, y0, y1)?;
+                cells.extend((y0.min(y1)..=y0.max(y1)).map(|y| (x0, y)));
+            }
+        }
+        let (first, last) = (self.points[0], self.points[self.points.len() - 1]);
+        cells.retain(|&cell| cell != first && cell != last);
+        Ok(cells)
+    }
+
+    fn draw_text(&self, canvas: &mut Canvas) -> Result<(), CanvasError> {
+        for (end, border) in
+            [(&self.a, self.points[0]), (&self.b, self.points[self.points.len() - 1])]
+        {
+            let (x, y) = border;
+            // The marker sits next to the border, the cardinality next to the marker.
+            let marker_x = if end.side == Side::Right { x + 1 } else { x - 1 };
+            if let Some(marker) = end.marker {
+                canvas.set_exact(marker_x, y, marker)?;
+            }
+            if let Some(cardinality) = &end.cardinality {
+                let card_x = if end.side == Side::Right {
+                    marker_x + 1
+                } else {
+                    marker_x - text_len(cardinality)
+                };
+                canvas.write_str(card_x, y, cardinality)?;
+            }
+        }
+        if let Some(label) = &self.label {
+            if self.a.class_id != self.b.class_id {
+                canvas.write_str(self.label_x, self.b.y, label)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A lane user: one vertical run of a route in a gap.
+#[derive(Debug, Clone)]
+struct LaneRun {
+    route: usize,
+    y0: usize,
+    y1: usize,
+    /// Rows where the route runs from the gap's left edge (or right edge) to its lane.
+    left_rows: Vec<usize>,
+    right_rows: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Gap {
+    runs: Vec<LaneRun>,
+    lanes: Vec<usize>,
+    lane_count: usize,
+    left_text: usize,
+    right_text: usize,
+    x0: usize,
+}
+
+impl Gap {
+    fn lane_x(&self, lane: usize) -> usize {
+        self.x0 + self.left_text + 2 + 2 * lane
+    }
+
+    fn text_x(&self) -> usize {
+        self.x0 + self.left_text + 2 + 2 * self.lane_count
+    }
+
+    fn width(&self) -> usize {
+        let right_text = if self.right_text > 0 { self.right_text + 1 } else { 0 };
+        (self.text_x() + right_text + 1 - self.x0).max(MIN_GAP_WIDTH)
+    }
+
+    /// Greedily gives every run the first lane that keeps it clear of the runs placed before.
+    fn assign_lanes(&mut self) {
+        self.lanes = vec![0; self.runs.len()];
+        let mut placed = Vec::<usize>::new();
+        while let Some(idx) = self.next_run(&placed) {
+            let run = &self.runs[idx];
+            let fits = |lane: usize, strict: bool| {
+                placed.iter().all(|&other_idx| {
+                    let (other, other_lane) = (&self.runs[other_idx], self.lanes[other_idx]);
+                    if other_lane == lane {
+                        return run.y1 < other.y0 || other.y1 < run.y0;
+                    }
+                    if !strict {
+                        return true;
+                    }
+                    // A run leaving left must turn before a run entering right on the same row.
+                    let before = run.left_rows.iter().any(|y| other.right_rows.contains(y));
+                    let after = run.right_rows.iter().any(|y| other.left_rows.contains(y));
+                    (!before || lane < other_lane) && (!after || lane > other_lane)
+                })
+            };
+            let lane = (0..=self.lane_count)
+                .find(|&lane| fits(lane, true))
+                .or_else(|| (0..=self.lane_count).find(|&lane| fits(lane, false)))
+                .unwrap_or(self.lane_count);
+            self.lanes[idx] = lane;
+            self.lane_count = self.lane_count.max(lane + 1);
+            placed.push(idx);
+        }
+    }
+
+    /// The topmost unplaced run that no unplaced run has to turn before, if any is left.
+    fn next_run(&self, placed: &[usize]) -> Option<usize> {
+        let unplaced = (0..self.runs.len()).filter(|idx| !placed.contains(idx)).collect::<Vec<_>>();
+        let must_follow = |idx: usize| {
+            let run = &self.runs[idx];
+            unplaced.iter().any(|&other_idx| {
+                other_idx != idx
+                    && self.runs[other_idx].left_rows.iter().any(|y| run.right_rows.contains(y))
+            })
+        };
+        let key = |&idx: &usize| (self.runs[idx].y0, self.runs[idx].y1, self.runs[idx].route);
+        let free = unplaced.iter().copied().filter(|&idx| !must_follow(idx)).min_by_key(key);
+        // Runs that have to turn before each other fall back to top-down order.
+        free.or_else(|| unplaced.iter().copied().min_by_key(key))
+    }
+}
+
+struct ClassPlan {
+    boxes: BTreeMap<ObjectId, ClassBox>,
+    routes: Vec<Route>,
+    width: usize,
+    height: usize,
+}
+
+impl ClassPlan {
+    fn build(
+        ast: &ClassAst,
+        layout: &ClassLayout,
+        options: RenderOptions,
+    ) -> Result<Self, ClassRenderError> {
+        let position = |class_id: &ObjectId| {
+            layout
+                .position(class_id)
+                .ok_or_else(|| ClassRenderError::MissingClass { class_id: class_id.clone() })
+        };
+        let mut boxes = BTreeMap::new();
+        for class_id in layout.columns().iter().flatten() {
+            let class = ast
+                .classes()
+                .get(class_id)
+                .ok_or_else(|| ClassRenderError::MissingClass { class_id: class_id.clone() })?;
+            boxes.insert(class_id.clone(), ClassBox::new(class, options));
+        }
+
+        // Ends on the right of the left column's box and on the left of the right column's box.
+        let mut routes = Vec::new();
+        for (relation_id, relation) in ast.relations() {
+            let (from, to) = (relation.from_class_id(), relation.to_class_id());
+            let (from_column, _) = position(from)?;
+            let (to_column, _) = position(to)?;
+            let from_first = from_column <= to_column;
+            let end = |class_id: &ObjectId, side, cardinality: Option<&str>, marker| RouteEnd {
+                class_id: class_id.clone(),
+                side,
+                y: 0,
+                cardinality: cardinality.map(ToOwned::to_owned),
+                marker,
+            };
+            let b_side = if from_column == to_column { Side::Right } else { Side::Left };
+            let (a, b) = if from_first {
+                (
+                    end(from, Side::Right, relation.from_cardinality(), None),
+                    end(to, b_side, relation.to_cardinality(), marker(relation.kind(), b_side)),
+                )
+            } else {
+                (
+                    end(
+                        to,
+                        Side::Right,
+                        relation.to_cardinality(),
+                        marker(relation.kind(), Side::Right),
+                    ),
+                    end(from, Side::Left, relation.from_cardinality(), None),
+                )
+            };
+            routes.push(Route {
+                relation_id: relation_id.clone(),
+                kind: relation.kind(),
+                label: relation.label().map(ToOwned::to_owned),
+                a,
+                b,
+                points: Vec::new(),
+                label_x: 0,
+            });
+        }
+
+        // Size boxes so every end gets a row of its own, then stack each column.
+        let mut end_counts = BTreeMap::<(ObjectId, Side), usize>::new();
+        for route in &routes {
+            for end in [&route.a, &route.b] {
+                *end_counts.entry((end.class_id.clone(), end.side)).or_default() += 1;
+            }
+        }
+        for ((class_id, _), count) in &end_counts {
+            if let Some(class_box) = boxes.get_mut(class_id) {
+                class_box.reserve_attach_rows(*count);
+            }
+        }
+        let mut boxes_bottom = 0;
+        for column in layout.columns() {
+            let mut y = 0;
+            for class_id in column {
+                let class_box = boxes.get_mut(class_id).expect("box per class");
+                class_box.y0 = y;
+                class_box.y1 = y + class_box.height() - 1;
+                boxes_bottom = boxes_bottom.max(class_box.y1);
+                y = class_box.y1 + 1 + ROW_GAP;
+            }
+        }
+
+        // Hand out attach rows per box side, ordered by where the other end sits.
+        let mut side_ends =
+            BTreeMap::<(ObjectId, Side), Vec<(usize, ObjectId, usize, bool)>>::new();
+        for (idx, route) in routes.iter().enumerate() {
+            for (is_a, end, other) in [(true, &route.a, &route.b), (false, &route.b, &route.a)] {
+                let other_y = boxes[&other.class_id].y0;
+                side_ends.entry((end.class_id.clone(), end.side)).or_default().push((
+                    other_y,
+                    route.relation_id.clone(),
+                    idx,
+                    is_a,
+                ));
+            }
+        }
+        for ((class_id, _), mut ends) in side_ends {
+            ends.sort();
+            let class_box = &boxes[&class_id];
+            let rows = class_box.attach_rows();
+            for (slot, (_, _, idx, is_a)) in ends.into_iter().enumerate() {
+                let y = class_box.y0 + rows[slot.min(rows.len() - 1)];
+                let route = &mut routes[idx];
+                if is_a {
+                    route.a.y = y;
+                } else {
+                    route.b.y = y;
+                }
+            }
+        }
+
+        // Collect lane runs per gap; gap `c` lies right of column `c`.
+        let column_count = layout.columns().len();
+        let mut gaps = vec![Gap::default(); column_count];
+        let mut bus_rows = BTreeMap::<usize, usize>::new();
+        for (idx, route) in routes.iter().enumerate() {
+            let (a_column, _) = position(&route.a.class_id)?;
+            let (b_column, _) = position(&route.b.class_id)?;
+            let (ya, yb) = (route.a.y, route.b.y);
+            let run = |y0: usize, y1: usize, left_rows: Vec<usize>, right_rows: Vec<usize>| {
+                LaneRun { route: idx, y0: y0.min(y1), y1: y0.max(y1), left_rows, right_rows }
+            };
+            if a_column == b_column {
+                gaps[a_column].runs.push(run(ya, yb, vec![ya, yb], Vec::new()));
+            } else if a_column + 1 == b_column {
+                gaps[a_column].runs.push(run(ya, yb, vec![ya], vec![yb]));
+            } else {
+                let bus = boxes_bottom + 2 + bus_rows.len();
+                bus_rows.insert(idx, bus);
+                gaps[a_column].runs.push(run(ya, bus, vec![ya], vec![bus]));
+                gaps[b_column - 1].runs.push(run(bus, yb, vec![bus], vec![yb]));
+            }
+            let a_gap = &mut gaps[a_column];
+            a_gap.left_text =
+                a_gap.left_text.max(route.a.cardinality.as_deref().map_or(0, text_len));
+            if a_column == b_column {
+                a_gap.left_text =
+                    a_gap.left_text.max(route.b.cardinality.as_deref().map_or(0, text_len));
+            } else {
+                let b_gap = &mut gaps[b_column - 1];
+                let label = route.label.as_deref().map_or(0, text_len);
+                let card = route.b.cardinality.as_deref().map_or(0, text_len);
+                let text = label + card + usize::from(label > 0 && card > 0);
+                b_gap.right_text = b_gap.right_text.max(text);
+            }
+        }
+        for gap in &mut gaps {
+            gap.assign_lanes();
+        }
+
+        // Place columns and gaps left to right.
+        let mut x = 0;
+        for (column_idx, column) in layout.columns().iter().enumerate() {
+            let width = column.iter().map(|class_id| boxes[class_id].width()).max().unwrap_or(0);
+            for class_id in column {
+                let class_box = boxes.get_mut(class_id).expect("box per class");
+                class_box.x0 = x;
+                class_box.x1 = x + width - 1;
+            }
+            let gap = &mut gaps[column_idx];
+            gap.x0 = x + width;
+            x = gap.x0;
+            if column_idx + 1 < column_count || !gap.runs.is_empty() {
+                x += gap.width();
+            }
+        }
+
+        let mut lane_of = BTreeMap::<(usize, usize), usize>::new();
+        for (gap_idx, gap) in gaps.iter().enumerate() {
+            for (run, lane) in gap.runs.iter().zip(&gap.lanes) {
+                lane_of.insert((run.route, gap_idx), gap.lane_x(*lane));
+            }
+        }
+        for (idx, route) in routes.iter_mut().enumerate() {
+            let (a_column, _) = position(&route.a.class_id)?;
+            let (b_column, _) = position(&route.b.class_id)?;
+            let (a_box, b_box) = (&boxes[&route.a.class_id], &boxes[&route.b.class_id]);
+            let (ya, yb) = (route.a.y, route.b.y);
+            let b_x = if route.b.side == Side::Right { b_box.x1 } else { b_box.x0 };
+            let first_lane = lane_of[&(idx, a_column)];
+            route.points = match bus_rows.get(&idx) {
+                None => vec![(a_box.x1, ya), (first_lane, ya), (first_lane, yb), (b_x, yb)],
+                Some(&bus) => {
+                    let second_lane = lane_of[&(idx, b_column - 1)];
+                    vec![
+                        (a_box.x1, ya),
+                        (first_lane, ya),
+                        (first_lane, bus),
+                        (second_lane, bus),
+                        (second_lane, yb),
+                        (b_x, yb),
+                    ]
+                }
+            };
+            if a_column != b_column {
+                route.label_x = gaps[b_column - 1].text_x();
+            }
+        }
+
+        // Bus rows start one blank row below the lowest box.
+        let height = match bus_rows.len() {
+            0 => boxes_bottom + 1,
+            count => boxes_bottom + 2 + count,
+        };
+        Ok(Self { boxes, routes, width: x.max(1), height })
+    }
+}
+
+/// Marker drawn next to a box on `side`, pointing into the box.
+fn marker(kind: ClassRelationKind, side: Side) -> Option<char> {
+    let (left, right) = match kind {
+        ClassRelationKind::Inheritance | ClassRelationKind::Realization => ('▷', '◁'),
+        ClassRelationKind::Composition => ('◆', '◆'),
+        ClassRelationKind::Aggregation => ('◇', '◇'),
+        ClassRelationKind::Association | ClassRelationKind::Dependency => ('▶', '◀'),
+        ClassRelationKind::Link | ClassRelationKind::DashedLink => return None,
+    };
+    Some(if side == Side::Left { left } else { right })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_class_unicode_annotated_with_options;
+    use crate::format::mermaid::parse_class_diagram;
+    use crate::layout::layout_class;
+    use crate::model::{DiagramId, ObjectRef};
+    use crate::render::RenderOptions;
+
+    #[test]
+    fn renders_compartments_markers_cardinalities_and_class_refs() {
+        let ast = parse_class_diagram(
+            "classDiagram\nclass Animal {\n<<abstract>>\n+String name\n+speak() String\n}\n\
+             Animal <|-- Duck\nAnimal <|-- Fish\nPond \"1\" o-- \"*\" Duck : holds\n\
+             Duck ..> Fish\nAnimal --> Animal : self\nZoo *-- Duck\n",
+        )
+        .expect("parse");
+        let layout = layout_class(&ast).expect("layout");
+        let diagram_id = DiagramId::new("d-class").expect("diagram id");
+        let rendered = render_class_unicode_annotated_with_options(
+            &diagram_id,
+            &ast,
+            &layout,
+            RenderOptions::default(),
+        )
+        .expect("render");
+
+        for text in ["«abstract»", "+speak() String", "◁", "◇1", "◆", "▶", "┄", "holds", "*─┤"]
+        {
+            assert!(rendered.text.contains(text), "missing {text:?} in\n{}", rendered.text);
+        }
+        assert!(!rendered.text.contains("self"), "self-relation labels are not drawn");
+        for object_ref in [
+            "d:d-class/class/node/c:Animal",
+            "d:d-class/class/node/c:Zoo",
+            "d:d-class/class/relation/r:0002",
+            "d:d-class/class/relation/r:0005",
+        ] {
+            let object_ref = ObjectRef::parse(object_ref).expect("ref");
+            assert!(rendered.highlight_index.contains_key(&object_ref), "missing {object_ref}");
+        }
+    }
+}
@@ -6,19 +6,34 @@
 // This file is part of Nereid and is proprietary software.
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use std::collections::BTreeMap;
 use std::fmt;
+use std::time::{Duration, Instant};
 
-use crate::layout::{layout_flowchart, layout_sequence, FlowchartLayoutError, SequenceLayoutError};
+use crate::layout::{
+    layout_class, layout_flowchart_with_options, layout_sequence, layout_state, FlowchartLayout,
+    FlowchartLayoutError, SequenceLayoutError,
+};
 use crate::model::diagram::{Diagram, DiagramAst};
+use crate::model::flow_ast::{FlowDirection, FlowNodePin, FlowchartAst};
+use crate::model::ids::{DiagramId, ObjectId};
 
+use super::class::{
+    render_class_unicode_annotated_with_options, render_class_unicode_with_options,
+    ClassRenderError,
+};
 use super::flowchart::{
-    render_flowchart_unicode_annotated_with_options, render_flowchart_unicode_with_options,
-    FlowchartRenderError,
+    render_flowchart_unicode_annotated_with_options,
+    render_flowchart_unicode_annotated_with_routes, render_flowchart_unicode_with_options,
+    FlowchartRenderError, FlowchartRouteCache,
 };
 use super::sequence::{
     render_sequence_unicode_annotated_with_options, render_sequence_unicode_with_options,
     SequenceRenderError,
 };
+use super::state::{
+    render_state_unicode_annotated_with_options, render_state_unicode_with_options,
+};
 use super::{AnnotatedRender, RenderOptions};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -27,6 +42,10 @@ pub enum DiagramRenderError {
     FlowchartLayout(FlowchartLayoutError),
     SequenceRender(SequenceRenderError),
     FlowchartRender(FlowchartRenderError),
+    StateLayout(FlowchartLayoutError),
+    StateRender(FlowchartRenderError),
+    ClassLayout(FlowchartLayoutError),
+    ClassRender(ClassRenderError),
 }
 
 impl fmt::Display for DiagramRenderError {
@@ -36,6 +55,10 @@ impl fmt::Display for DiagramRenderError {
             Self::FlowchartLayout(err) => write!(f, "flowchart layout error: {err}"),
             Self::SequenceRender(err) => write!(f, "sequence render error: {err}"),
             Self::FlowchartRender(err) => write!(f, "flowchart render error: {err}"),
+            Self::StateLayout(err) => write!(f, "state layout error: {err}"),
+            Self::StateRender(err) => write!(f, "state render error: {err}"),
+            Self::ClassLayout(err) => write!(f, "class layout error: {err}"),
+            Self::ClassRender(err) => write!(f, "class render error: {err}"),
         }
     }
 }
@@ -47,6 +70,10 @@ impl std::error::Error for DiagramRenderError {
             Self::FlowchartLayout(err) => Some(err),
             Self::SequenceRender(err) => Some(err),
             Self::FlowchartRender(err) => Some(err),
+            Self::StateLayout(err) => Some(err),
+            Self::StateRender(err) => Some(err),
+            Self::ClassLayout(err) => Some(err),
+            Self::ClassRender(err) => Some(err),
         }
     }
 }
@@ -89,9 +116,19 @@ pub fn render_diagram_unicode_with_options(
             Ok(render_sequence_unicode_with_options(ast, &layout, options)?)
         }
         DiagramAst::Flowchart(ast) => {
-            let layout = layout_flowchart(ast)?;
+            let layout = layout_flowchart_with_options(ast, options.flowchart_layout_options())?;
             Ok(render_flowchart_unicode_with_options(ast, &layout, options)?)
         }
+        DiagramAst::State(ast) => {
+            let layout = layout_state(ast).map_err(DiagramRenderError::StateLayout)?;
+            render_state_unicode_with_options(&layout, options)
+                .map_err(DiagramRenderError::StateRender)
+        }
+        DiagramAst::Class(ast) => {
+            let layout = layout_class(ast).map_err(DiagramRenderError::ClassLayout)?;
+            render_class_unicode_with_options(ast, &layout, options)
+                .map_err(DiagramRenderError::ClassRender)
+        }
     }
 }
 
@@ -105,36 +142,218 @@ pub fn render_diagram_unicode_annotated_with_options(
     diagram: &Diagram,
     options: RenderOptions,
 ) -> Result<AnnotatedRender, DiagramRenderError> {
+    render_diagram_unicode_annotated_timed(diagram, options).map(|(rendered, _)| rendered)
+}
+
+/// Wall-clock time spent in each phase of an annotated render.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderTimings {
+    pub layout: Duration,
+    pub render: Duration,
+}
+
+/// Same as [`render_diagram_unicode_annotated_with_options`], also reporting how long layout and
+/// rendering took.
+pub fn render_diagram_unicode_annotated_timed(
+    diagram: &Diagram,
+    options: RenderOptions,
+) -> Result<(AnnotatedRender, RenderTimings), DiagramRenderError> {
+    let started = Instant::now();
     match diagram.ast() {
         DiagramAst::Sequence(ast) => {
             let layout = layout_sequence(ast)?;
-            Ok(render_sequence_unicode_annotated_with_options(
+            let laid_out = Instant::now();
+            let rendered = render_sequence_unicode_annotated_with_options(
                 diagram.diagram_id(),
                 ast,
                 &layout,
                 options,
-            )?)
+            )?;
+            let timings = RenderTimings { layout: laid_out - started, render: laid_out.elapsed() };
+            Ok((rendered, timings))
         }
         DiagramAst::Flowchart(ast) => {
-            let layout = layout_flowchart(ast)?;
-            Ok(render_flowchart_unicode_annotated_with_options(
+            let layout = layout_flowchart_with_options(ast, options.flowchart_layout_options())?;
+            let laid_out = Instant::now();
+            let rendered = render_flowchart_unicode_annotated_with_options(
                 diagram.diagram_id(),
                 ast,
                 &layout,
                 options,
-            )?)
+            )?;
+            let timings = RenderTimings { layout: laid_out - started, render: laid_out.elapsed() };
+            Ok((rendered, timings))
+        }
+        DiagramAst::State(ast) => {
+            let layout = layout_state(ast).map_err(DiagramRenderError::StateLayout)?;
+            let laid_out = Instant::now();
+            let rendered =
+                render_state_unicode_annotated_with_options(diagram.diagram_id(), &layout, options)
+                    .map_err(DiagramRenderError::StateRender)?;
+            let timings = RenderTimings { layout: laid_out - started, render: laid_out.elapsed() };
+            Ok((rendered, timings))
+        }
+        DiagramAst::Class(ast) => {
+            let layout = layout_class(ast).map_err(DiagramRenderError::ClassLayout)?;
+            let laid_out = Instant::now();
+            let rendered = render_class_unicode_annotated_with_options(
+                diagram.diagram_id(),
+                ast,
+                &layout,
+                options,
+            )
+            .map_err(DiagramRenderError::ClassRender)?;
+            let timings = RenderTimings { layout: laid_out - started, render: laid_out.elapsed() };
+            Ok((rendered, timings))
         }
     }
 }
 
+/// Renders diagrams one after another, keeping the flowchart layout and edge routes of the previous
+/// render while everything layout and routing read from the AST stays the same.
+///
+/// Reuse is decided by comparing a small snapshot of those layout inputs (node and edge sets, edge
+/// endpoints, direction, subgraph nesting, in-layer order hints and pins) against the new AST, so
+/// label, shape and note edits, as well as option changes (zoom, notes, level of detail), skip
+/// layout and routing. The canvas itself is always redrawn in full, since label widths can shift
+/// every column. Any other change, including a different crossing-sweep budget, and every other
+/// diagram kind, renders from scratch.
+#[derive(Debug, Clone, Default)]
+pub struct IncrementalRenderer {
+    flowchart: Option<FlowchartGeometry>,
+    reused_layout: bool,
+}
+
+#[derive(Debug, Clone)]
+struct FlowchartGeometry {
+    diagram_id: DiagramId,
+    structure: FlowchartStructure,
+    crossing_sweeps: usize,
+    layout: FlowchartLayout,
+    routes: FlowchartRouteCache,
+}
+
+/// The parts of a [`FlowchartAst`] that flowchart layout and edge routing read.
+#[derive(Debug, Clone)]
+struct FlowchartStructure {
+    direction: Option<FlowDirection>,
+    nodes: Vec<ObjectId>,
+    edges: Vec<(ObjectId, ObjectId, ObjectId)>,
+    subgraph_parents: Vec<(ObjectId, Option<ObjectId>)>,
+    node_subgraphs: BTreeMap<ObjectId, ObjectId>,
+    node_order_hints: BTreeMap<ObjectId, u32>,
+    node_pins: BTreeMap<ObjectId, FlowNodePin>,
+}
+
+impl FlowchartStructure {
+    fn of(ast: &FlowchartAst) -> Self {
+        Self {
+            direction: ast.direction(),
+            nodes: ast.nodes().keys().cloned().collect(),
+            edges: ast
+                .edges()
+                .iter()
+                .map(|(edge_id, edge)| {
+                    (edge_id.clone(), edge.from_node_id().clone(), edge.to_node_id().clone())
+                })
+                .collect(),
+            subgraph_parents: ast
+                .subgraphs()
+                .iter()
+                .map(|(subgraph_id, subgraph)| (subgraph_id.clone(), subgraph.parent().cloned()))
+                .collect(),
+            node_subgraphs: ast.node_subgraphs().clone(),
+            node_order_hints: ast.node_order_hints().clone(),
+            node_pins: ast.node_pins().clone(),
+        }
+    }
+
+    /// Whether `ast` still has this structure; compares in place without building a new snapshot.
+    fn matches(&self, ast: &FlowchartAst) -> bool {
+        self.direction == ast.direction()
+            && self.nodes.iter().eq(ast.nodes().keys())
+            && self.edges.iter().map(|(edge_id, from, to)| (edge_id, from, to)).eq(ast
+                .edges()
+                .iter()
+                .map(|(edge_id, edge)| (edge_id, edge.from_node_id(), edge.to_node_id())))
+            && self
+                .subgraph_parents
+                .iter()
+                .map(|(subgraph_id, parent)| (subgraph_id, parent.as_ref()))
+                .eq(ast
+                    .subgraphs()
+                    .iter()
+                    .map(|(subgraph_id, subgraph)| (subgraph_id, subgraph.parent())))
+            && &self.node_subgraphs == ast.node_subgraphs()
+            && &self.node_order_hints == ast.node_order_hints()
+            && &self.node_pins == ast.node_pins()
+    }
+}
+
+impl IncrementalRenderer {
+    /// Same as [`render_diagram_unicode_annotated_timed`], reusing what it can from the previous
+    /// call.
+    pub fn render_annotated_timed(
+        &mut self,
+        diagram: &Diagram,
+        options: RenderOptions,
+    ) -> Result<(AnnotatedRender, RenderTimings), DiagramRenderError> {
+        let previous = self.flowchart.take();
+        self.reused_layout = false;
+        let DiagramAst::Flowchart(ast) = diagram.ast() else {
+            return render_diagram_unicode_annotated_timed(diagram, options);
+        };
+
+        let started = Instant::now();
+        let reusable = previous.filter(|previous| {
+            &previous.diagram_id == diagram.diagram_id()
+                && previous.crossing_sweeps == options.flowchart_crossing_sweeps
+                && previous.structure.matches(ast)
+        });
+        let mut geometry = match reusable {
+            Some(previous) => {
+                self.reused_layout = true;
+                previous
+            }
+            None => FlowchartGeometry {
+                diagram_id: diagram.diagram_id().clone(),
+                structure: FlowchartStructure::of(ast),
+                crossing_sweeps: options.flowchart_crossing_sweeps,
+                layout: layout_flowchart_with_options(ast, options.flowchart_layout_options())?,
+                routes: FlowchartRouteCache::default(),
+            },
+        };
+        let laid_out = Instant::now();
+        let rendered = render_flowchart_unicode_annotated_with_routes(
+            diagram.diagram_id(),
+            ast,
+            &geometry.layout,
+            options,
+            &mut geometry.routes,
+        )?;
+        let timings = RenderTimings { layout: laid_out - started, render: laid_out.elapsed() };
+        self.flowchart = Some(geometry);
+        Ok((rendered, timings))
+    }
+
+    /// Whether the last render kept the previous layout and edge routes.
+    pub fn reused_layout(&self) -> bool {
+        self.reused_layout
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::render_diagram_unicode;
+    use super::{
+        render_diagram_unicode, render_diagram_unicode_annotated_timed, IncrementalRenderer,
+    };
+    use crate::model::flow_ast::{FlowEdge, FlowNodePin};
     use crate::model::ids::ObjectId;
     use crate::model::seq_ast::{
         SequenceAst, SequenceMessage, SequenceMessageKind, SequenceParticipant,
     };
     use crate::model::{Diagram, DiagramAst, DiagramId};
+    use crate::render::RenderOptions;
 
     fn oid(value: &str) -> ObjectId {
         ObjectId::new(value).expect("object id")
@@ -181,4 +400,34 @@ mod tests {
             "┌───┐   ┌───┐   ┌───┐\n│ A ├──▶│ B ├──▶│ D │\n└───┘  │└───┘  │└───┘\n       │       │\n       │       │\n       │┌───┐  │\n       └┤ C ├──┘\n        └───┘"
         );
     }
+
+    #[test]
+    fn incremental_renderer_reuses_layout_for_label_edits() {
+        let mut ast = crate::model::fixtures::flowchart_small_dag();
+        let diagram_id = DiagramId::new("d-flow").expect("diagram id");
+        let mut diagram =
+            Diagram::new(diagram_id.clone(), "Example", DiagramAst::Flowchart(ast.clone()));
+        let mut renderer = IncrementalRenderer::default();
+        renderer.render_annotated_timed(&diagram, RenderOptions::default()).expect("render");
+        assert!(!renderer.reused_layout());
+
+        ast.nodes_mut().get_mut(&oid("n:b")).expect("node").set_label("Much longer B");
+        diagram = Diagram::new(diagram_id.clone(), "Example", DiagramAst::Flowchart(ast.clone()));
+        let (incremental, _) =
+            renderer.render_annotated_timed(&diagram, RenderOptions::default()).expect("render");
+        assert!(renderer.reused_layout());
+        let (full, _) = render_diagram_unicode_annotated_timed(&diagram, RenderOptions::default())
+            .expect("render");
+        assert_eq!(incremental.text, full.text);
+
+        ast.node_pins_mut().insert(oid("n:c"), FlowNodePin::new(1, 0));
+        diagram = Diagram::new(diagram_id.clone(), "Example", DiagramAst::Flowchart(ast.clone()));
+        renderer.render_annotated_timed(&diagram, RenderOptions::default()).expect("render");
+        assert!(!renderer.reused_layout());
+
+        ast.edges_mut().insert(oid("e:ad"), FlowEdge::new(oid("n:a"), oid("n:d")));
+        diagram = Diagram::new(diagram_id, "Example", DiagramAst::Flowchart(ast));
+        renderer.render_annotated_timed(&diagram, RenderOptions::default()).expect("render");
+        assert!(!renderer.reused_layout());
+    }
 }
@@ -6,20 +6,30 @@
 // This file is part of Nereid and is proprietary software.
 // Unauthorized copying, modification, or distribution is prohibited.
 
-use std::collections::{BTreeMap, BTreeSet};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap};
 use std::fmt;
 
-use crate::layout::flowchart::route_flowchart_edges_orthogonal_key_order;
+use crate::layout::flowchart::{
+    reserve_flowchart_edge_label_gaps, route_flowchart_edges_orthogonal_key_order,
+};
 use crate::layout::{FlowchartLayout, GridPoint};
 use crate::model::flow_ast::FlowchartAst;
 use crate::model::ids::{DiagramId, ObjectId};
 use crate::model::{CategoryPath, ObjectRef};
 
+use compact::Compaction;
+use subgraph::SubgraphFrames;
+
 use super::text::{canvas_to_string_trimmed, text_len, truncate_with_ellipsis};
-use super::RenderOptions;
 use super::{
     clamp_highlight_index_to_text, AnnotatedRender, Canvas, CanvasError, HighlightIndex, LineSpan,
 };
+use super::{FlowchartEdgeGlyphs, FlowchartNodeScale, RenderOptions};
+use super::{
+    UNICODE_BOX_HORIZONTAL, UNICODE_BOX_VERTICAL, UNICODE_DOTTED_HORIZONTAL,
+    UNICODE_DOTTED_VERTICAL, UNICODE_THICK_HORIZONTAL, UNICODE_THICK_VERTICAL,
+};
 
 const BOX_HEIGHT_NO_NOTES: usize = 3;
 const BOX_HEIGHT_WITH_NOTES: usize = 4;
@@ -27,11 +37,20 @@ const MIN_COL_GAP: usize = 1;
 const ROW_GAP: usize = 2;
 const MIN_BOX_INNER_WIDTH: usize = 3;
 const OBJECT_LABEL_PREFIX: &str = "▴ ";
+/// Replaces the top-left box corner of nodes marked as entry points.
+const ENTRY_NODE_MARKER: char = '▸';
+/// Label characters kept in [`FlowchartNodeScale::Mini`] boxes, ellipsis included.
+const MINI_NODE_LABEL_WIDTH: usize = 5;
+/// Glyph of a node with a blank label at [`FlowchartNodeScale::Glyph`].
+const EMPTY_NODE_GLYPH: char = '●';
 const LANE_MIN_X_CLEARANCE: usize = 2;
 const STUB_ROW_KEEPOUT_RADIUS: usize = 1;
 // Keep global widening effectively disabled; per-gap lane assignment handles local widening.
 const MAX_GLOBAL_CLEARANCE_WIDEN_STEPS: usize = 1;
 
+/// Cells taken by each placed edge label, by edge id.
+type EdgeLabelSpans = Vec<(ObjectId, Vec<LineSpan>)>;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ConnectorDrawPass {
     Vertical,
@@ -89,6 +108,365 @@ struct FlowchartRenderPlan {
     edge_gap_lanes: Vec<Vec<Option<usize>>>,
     width: usize,
     height: usize,
+    /// Copy of the AST without detour edges when any exist; the router only sees this one.
+    routed_ast: Option<FlowchartAst>,
+    detours: Vec<DetourEdge>,
+    /// Labeled frames around subgraph members, drawn by [`FlowchartRenderPlan::frame_subgraphs`].
+    subgraph_frames: SubgraphFrames,
+}
+
+/// Raw edge routes of one layout, keyed by the ids of the routed edges.
+///
+/// Routes only depend on the layout and the edge endpoints, so a cache stays valid for as long as
+/// the graph structure does: label, shape and note edits repaint without routing again.
+#[derive(Debug, Clone, Default)]
+pub struct FlowchartRouteCache {
+    routes: BTreeMap<Vec<ObjectId>, Vec<Vec<GridPoint>>>,
+}
+
+impl FlowchartRouteCache {
+    fn routes(&mut self, ast: &FlowchartAst, layout: &FlowchartLayout) -> &[Vec<GridPoint>] {
+        let edge_ids = ast.edges().keys().cloned().collect::<Vec<_>>();
+        self.routes
+            .entry(edge_ids)
+            .or_insert_with(|| route_flowchart_edges_orthogonal_key_order(ast, layout))
+    }
+}
+
+/// Edge drawn outside the layered router as its own channel leaving and entering the bottom borders
+/// of its endpoint boxes.
+///
+/// Self-loops and every parallel edge after the first between the same two nodes take a detour, so
+/// each stays visually separate from the routed copy and from the other detours.
+#[derive(Debug, Clone)]
+struct DetourEdge {
+    edge_id: ObjectId,
+    stroke: ConnectorStroke,
+    /// Cells from the source border cell to the target border cell, one step at a time.
+    path: Vec<(usize, usize)>,
+    start: Option<EndpointCapKind>,
+    end: Option<EndpointCapKind>,
+}
+
+impl DetourEdge {
+    fn spans(&self) -> Vec<LineSpan> {
+        let mut cells = self.path.iter().map(|&(x, y)| (y, x)).collect::<Vec<_>>();
+        cells.sort();
+        cells.dedup();
+        let mut spans = Vec::<LineSpan>::new();
+        for (y, x) in cells {
+            match spans.last_mut() {
+                Some((span_y, _, x1)) if *span_y == y && *x1 + 1 == x => *x1 = x,
+                _ => spans.push((y, x, x)),
+            }
+        }
+        spans
+    }
+
+    fn draw(&self, canvas: &mut Canvas) -> Result<(), CanvasError> {
+        let last = self.path.len().saturating_sub(1);
+        for (idx, &(x, y)) in self.path.iter().enumerate() {
+            if idx == 0 || idx == last {
+                // Merges with the box border into `┬`.
+                canvas.set(x, y, super::UNICODE_BOX_TEE_DOWN)?;
+                continue;
+            }
+            let toward = |(nx, ny): (usize, usize)| (nx.cmp(&x), ny.cmp(&y));
+            canvas.set(
+                x,
+                y,
+                detour_cell_char(toward(self.path[idx - 1]), toward(self.path[idx + 1])),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Endpoint caps as `(x, y, glyph)`; overlaid on the text so the canvas keeps its junctions.
+    fn caps(&self) -> impl Iterator<Item = (usize, usize, char)> + '_ {
+        let ends = [(self.path.first(), self.start), (self.path.last(), self.end)];
+        ends.into_iter().filter_map(|(cell, cap)| {
+            let (&(x, y), kind) = (cell?, cap?);
+            Some((x, y, endpoint_cap_char(kind, 0, 1)))
+        })
+    }
+}
+
+/// Box-drawing glyph joining the two neighbours of a path cell, given as directions from the cell.
+fn detour_cell_char(a: (Ordering, Ordering), b: (Ordering, Ordering)) -> char {
+    let has = |dir: (Ordering, Ordering)| a == dir || b == dir;
+    let (left, right) =
+        (has((Ordering::Less, Ordering::Equal)), has((Ordering::Greater, Ordering::Equal)));
+    let (up, down) =
+        (has((Ordering::Equal, Ordering::Less)), has((Ordering::Equal, Ordering::Greater)));
+    match (left, right, up, down) {
+        (true, true, _, _) => UNICODE_BOX_HORIZONTAL,
+        (_, _, true, true) => UNICODE_BOX_VERTICAL,
+        (false, true, false, true) => super::UNICODE_BOX_TOP_LEFT,
+        (true, false, false, true) => super::UNICODE_BOX_TOP_RIGHT,
+        (false, true, true, false) => super::UNICODE_BOX_BOTTOM_LEFT,
+        _ => super::UNICODE_BOX_BOTTOM_RIGHT,
+    }
+}
+
+const DETOUR_BEND_COST: usize = 3;
+const DETOUR_CROSSING_COST: usize = 4;
+
+/// Finds a detour path from the bottom border of `from` to the bottom border of `to` through the
+/// free cells of `grid` (the rendered diagram so far, padded with blank rows below).
+///
+/// Boxes (whose borders look like connector lines in `grid`), labels, corners and caps are never entered. Straight connector cells may only be crossed
+/// at a right angle, and crossings and bends cost extra, so channels hug the boxes when the space
+/// below them is free and go around other connectors otherwise. Each border cell carries at most one
+/// detour end; a self-loop leaves at least one border cell between its two ends.
+fn route_detour(
+    grid: &[Vec<char>],
+    node_renders: &BTreeMap<ObjectId, NodeRender>,
+    from: NodeRender,
+    to: NodeRender,
+    self_loop: bool,
+) -> Option<Vec<(usize, usize)>> {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    enum Dir {
+        Up,
+        Down,
+        Left,
+        Right,
+    }
+    let cell = |x: usize, y: usize| grid.get(y).and_then(|row| row.get(x)).copied();
+    let free_border =
+        |x: usize, y: usize| cell(x, y) == Some(UNICODE_BOX_HORIZONTAL) && cell(x, y + 1).is_some();
+    // Cost of entering `(x, y)` moving along `dir`, or `None` when it is blocked that way.
+    let in_box = |x: usize, y: usize| {
+        node_renders.values().any(|render| {
+            (render.box_x0..=render.box_x1).contains(&x)
+                && (render.box_y0..=render.box_y1).contains(&y)
+        })
+    };
+    let enter_cost = |x: usize, y: usize, dir: Dir| match cell(x, y).filter(|_| !in_box(x, y))? {
+        ' ' => Some(1),
+        ch if matches!(dir, Dir::Up | Dir::Down) && horizontal_line_char(ch) => {
+            Some(1 + DETOUR_CROSSING_COST)
+        }
+        ch if matches!(dir, Dir::Left | Dir::Right) && vertical_line_char(ch) => {
+            Some(1 + DETOUR_CROSSING_COST)
+        }
+        _ => None,
+    };
+
+    let sources = (from.box_x0 + 1..from.box_x1).filter(|&x| free_border(x, from.box_y1));
+    let sources = if self_loop {
+        sources.collect::<Vec<_>>()
+    } else {
+        // Prefer leaving near the target side, like the routed connector does.
+        sources.rev().collect::<Vec<_>>()
+    };
+
+    let mut best: Option<(usize, Vec<(usize, usize)>)> = None;
+    for source_x in sources {
+        let source = (source_x, from.box_y1 + 1);
+        let Some(source_cost) = enter_cost(source.0, source.1, Dir::Down) else {
+            continue;
+        };
+        let mut dist = BTreeMap::<(usize, usize, Dir), usize>::new();
+        let mut prev = BTreeMap::<(usize, usize, Dir), (usize, usize, Dir)>::new();
+        let mut heap = BinaryHeap::new();
+        dist.insert((source.0, source.1, Dir::Down), source_cost);
+        heap.push(Reverse((source_cost, source.1, source.0, Dir::Down)));
+
+        let mut found: Option<(usize, (usize, usize, Dir))> = None;
+        while let Some(Reverse((cost, y, x, dir))) = heap.pop() {
+            if dist.get(&(x, y, dir)).is_some_and(|&known| known < cost) {
+                continue;
+            }
+            if found.as_ref().is_some_and(|(found_cost, _)| *found_cost <= cost) {
+                break;
+            }
+            let crossing = cell(x, y) != Some(' ');
+
+            // Leave upwards into the target border.
+            let is_target = y == to.box_y1 + 1
+                && (to.box_x0 + 1..to.box_x1).contains(&x)
+                && free_border(x, to.box_y1)
+                && (!self_loop || x.abs_diff(source_x) >= 2)
+                && dir != Dir::Down
+                && (dir == Dir::Up || !crossing);
+            if is_target {
+                let total = cost + if dir == Dir::Up { 0 } else { DETOUR_BEND_COST };
+                if found.as_ref().map_or(true, |(found_cost, _)| total < *found_cost) {
+                    found = Some((total, (x, y, dir)));
+                }
+            }
+
+            for next_dir in [Dir::Up, Dir::Down, Dir::Left, Dir::Right] {
+                let reverse = matches!(
+                    (dir, next_dir),
+                    (Dir::Up, Dir::Down)
+                        | (Dir::Down, Dir::Up)
+                        | (Dir::Left, Dir::Right)
+                        | (Dir::Right, Dir::Left)
+                );
+                if reverse || (crossing && next_dir != dir) {
+                    continue;
+                }
+                let (nx, ny) = match next_dir {
+                    Dir::Up => (Some(x), y.checked_sub(1)),
+                    Dir::Down => (Some(x), Some(y + 1)),
+                    Dir::Left => (x.checked_sub(1), Some(y)),
+                    Dir::Right => (Some(x + 1), Some(y)),
+                };
+                let (Some(nx), Some(ny)) = (nx, ny) else {
+                    continue;
+                };
+                let Some(step) = enter_cost(nx, ny, next_dir) else {
+                    continue;
+                };
+                let bend = if next_dir == dir { 0 } else { DETOUR_BEND_COST };
+                let next_cost = cost + step + bend;
+                let key = (nx, ny, next_dir);
+                if dist.get(&key).map_or(true, |&known| next_cost < known) {
+                    dist.insert(key, next_cost);
+                    prev.insert(key, (x, y, dir));
+                    heap.push(Reverse((next_cost, ny, nx, next_dir)));
+                }
+            }
+        }
+
+        let Some((total, mut state)) = found else {
+            continue;
+        };
+        if best.as_ref().is_some_and(|(best_cost, _)| *best_cost <= total) {
+            continue;
+        }
+        let mut path = vec![(state.0, to.box_y1), (state.0, state.1)];
+        while let Some(&previous) = prev.get(&state) {
+            path.push((previous.0, previous.1));
+            state = previous;
+        }
+        path.push((source_x, from.box_y1));
+        path.reverse();
+        best = Some((total, path));
+    }
+    best.map(|(_, path)| path)
+}
+
+fn horizontal_line_char(ch: char) -> bool {
+    matches!(ch, UNICODE_BOX_HORIZONTAL | UNICODE_DOTTED_HORIZONTAL | UNICODE_THICK_HORIZONTAL)
+}
+
+fn vertical_line_char(ch: char) -> bool {
+    matches!(ch, UNICODE_BOX_VERTICAL | UNICODE_DOTTED_VERTICAL | UNICODE_THICK_VERTICAL)
+}
+
+/// Bottom border cells each node needs for its detour ends: one per parallel edge end, plus a
+/// spare cell between the two ends of a self-loop.
+fn detour_border_cells(
+    ast: &FlowchartAst,
+    detour_ids: &BTreeSet<ObjectId>,
+) -> BTreeMap<ObjectId, usize> {
+    let mut cells = BTreeMap::<ObjectId, usize>::new();
+    for edge in detour_ids.iter().filter_map(|edge_id| ast.edges().get(edge_id)) {
+        if edge.from_node_id() == edge.to_node_id() {
+            *cells.entry(edge.from_node_id().clone()).or_default() += 3;
+        } else {
+            *cells.entry(edge.from_node_id().clone()).or_default() += 1;
+            *cells.entry(edge.to_node_id().clone()).or_default() += 1;
+        }
+    }
+    cells
+}
+
+/// Picks the edges that bypass the router: self-loops, plus every parallel edge after the first
+/// between the same two nodes.
+fn detour_edge_ids(ast: &FlowchartAst) -> BTreeSet<ObjectId> {
+    let mut seen_pairs = BTreeSet::<(&ObjectId, &ObjectId)>::new();
+    let mut detours = BTreeSet::<ObjectId>::new();
+    for (edge_id, edge) in ast.edges() {
+        let (from, to) = (edge.from_node_id(), edge.to_node_id());
+        if from == to || !seen_pairs.insert((from, to)) {
+            detours.insert(edge_id.clone());
+        }
+    }
+    detours
+}
+
+/// Line stroke of a connector, derived from its Mermaid operator (`-.->` dotted, `==>` thick).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConnectorStroke {
+    Solid,
+    Dotted,
+    Thick,
+}
+
+impl ConnectorStroke {
+    fn from_connector(connector: Option<&str>) -> Self {
+        let op = connector.unwrap_or("-->");
+        if op.contains('.') {
+            Self::Dotted
+        } else if op.contains("==") {
+            Self::Thick
+        } else {
+            Self::Solid
+        }
+    }
+
+    fn restyle(self, ch: char) -> char {
+        match (self, ch) {
+            (Self::Dotted, UNICODE_BOX_HORIZONTAL) => UNICODE_DOTTED_HORIZONTAL,
+            (Self::Dotted, UNICODE_BOX_VERTICAL) => UNICODE_DOTTED_VERTICAL,
+            (Self::Thick, UNICODE_BOX_HORIZONTAL) => UNICODE_THICK_HORIZONTAL,
+            (Self::Thick, UNICODE_BOX_VERTICAL) => UNICODE_THICK_VERTICAL,
+            _ => ch,
+        }
+    }
+}
+
+/// Redraws the straight runs of dotted/thick connectors with their stroke glyphs.
+///
+/// Corners, tees, and caps keep their solid glyphs. Node box cells and cells shared by more than
+/// one connector stay solid so borders and bundled lanes never mix strokes.
+fn restyle_connector_strokes(
+    text: String,
+    strokes: &[ConnectorStroke],
+    spans_by_edge: &[Vec<LineSpan>],
+    node_renders: &BTreeMap<ObjectId, NodeRender>,
+) -> String {
+    let in_box = |x: usize, y: usize| {
+        node_renders.values().any(|render| {
+            (render.box_x0..=render.box_x1).contains(&x)
+                && (render.box_y0..=render.box_y1).contains(&y)
+        })
+    };
+
+    let mut owners = BTreeMap::<(usize, usize), Option<(usize, ConnectorStroke)>>::new();
+    for (edge_idx, (stroke, spans)) in strokes.iter().zip(spans_by_edge).enumerate() {
+        for &(y, x0, x1) in spans {
+            for x in x0..=x1 {
+                owners
+                    .entry((y, x))
+                    .and_modify(|owner| {
+                        if owner.is_some_and(|(owner_idx, _)| owner_idx != edge_idx) {
+                            *owner = None;
+                        }
+                    })
+                    .or_insert(Some((edge_idx, *stroke)));
+            }
+        }
+    }
+
+    let mut lines =
+        text.split('\n').map(|line| line.chars().collect::<Vec<_>>()).collect::<Vec<_>>();
+    for ((y, x), owner) in owners {
+        let Some((_, stroke)) = owner else {
+            continue;
+        };
+        if in_box(x, y) {
+            continue;
+        }
+        if let Some(ch) = lines.get_mut(y).and_then(|line| line.get_mut(x)) {
+            *ch = stroke.restyle(*ch);
+        }
+    }
+    lines.into_iter().map(String::from_iter).collect::<Vec<_>>().join("\n")
 }
 
 fn overlay_edge_caps_on_text(mut text: String, edge_caps: &[EdgeCapPlacement]) -> String {
@@ -430,9 +808,119 @@ impl FlowchartRenderPlan {
         ast: &FlowchartAst,
         layout: &FlowchartLayout,
         options: RenderOptions,
+    ) -> Result<Self, FlowchartRenderError> {
+        Self::build_with_routes(ast, layout, options, &mut FlowchartRouteCache::default())
+    }
+
+    fn build_with_routes(
+        ast: &FlowchartAst,
+        layout: &FlowchartLayout,
+        options: RenderOptions,
+        routes: &mut FlowchartRouteCache,
+    ) -> Result<Self, FlowchartRenderError> {
+        let mut detour_ids = detour_edge_ids(ast);
+        let mut plan = loop {
+            if detour_ids.is_empty() {
+                break Self::build_routed(ast, layout, options, &BTreeMap::new(), routes)?;
+            }
+            let (plan, unroutable) =
+                Self::build_detoured(ast, layout, options, &detour_ids, routes)?;
+            if unroutable.is_empty() {
+                break plan;
+            }
+            // Edges boxed in by their neighbours go back to the router and share its connector.
+            detour_ids.retain(|edge_id| !unroutable.contains(edge_id));
+        };
+        plan.subgraph_frames = SubgraphFrames::plan(ast, &plan.node_renders);
+        Ok(plan)
+    }
+
+    /// Plans the router's part without `detour_ids`, then routes those edges as detours around
+    /// it. Also returns the parallel edges that found no way out of their endpoint boxes.
+    fn build_detoured(
+        ast: &FlowchartAst,
+        layout: &FlowchartLayout,
+        options: RenderOptions,
+        detour_ids: &BTreeSet<ObjectId>,
+        routes: &mut FlowchartRouteCache,
+    ) -> Result<(Self, BTreeSet<ObjectId>), FlowchartRenderError> {
+        let mut routed_ast = ast.clone();
+        routed_ast.edges_mut().retain(|edge_id, _| !detour_ids.contains(edge_id));
+        let border_cells = detour_border_cells(ast, detour_ids);
+        let mut plan = Self::build_routed(&routed_ast, layout, options, &border_cells, routes)?;
+        plan.routed_ast = Some(routed_ast);
+
+        // Route each detour around what is already drawn, including earlier detours. Blank
+        // rows below the diagram guarantee a way around.
+        let routed_text = plan.render_text(ast)?;
+        let grid_height = plan.height + 2 * detour_ids.len() + 1;
+        let mut grid = routed_text
+            .split('\n')
+            .map(|line| line.chars().collect::<Vec<_>>())
+            .chain(std::iter::repeat(Vec::new()))
+            .take(grid_height)
+            .map(|mut row| {
+                row.resize(plan.width, ' ');
+                row
+            })
+            .collect::<Vec<_>>();
+
+        let mut unroutable = BTreeSet::new();
+        for edge_id in detour_ids {
+            let edge = &ast.edges()[edge_id];
+            let from = plan.node_renders.get(edge.from_node_id()).copied().ok_or_else(|| {
+                FlowchartRenderError::MissingPlacement { node_id: edge.from_node_id().clone() }
+            })?;
+            let to = plan.node_renders.get(edge.to_node_id()).copied().ok_or_else(|| {
+                FlowchartRenderError::MissingPlacement { node_id: edge.to_node_id().clone() }
+            })?;
+            let self_loop = edge.from_node_id() == edge.to_node_id();
+            let Some(path) = route_detour(&grid, &plan.node_renders, from, to, self_loop) else {
+                // The router cannot draw self-loops, so only parallel edges fall back to it.
+                if !self_loop {
+                    unroutable.insert(edge_id.clone());
+                }
+                continue;
+            };
+            let (start, end) = edge_endpoint_cap_kinds(edge.connector());
+            let detour = DetourEdge {
+                edge_id: edge_id.clone(),
+                stroke: ConnectorStroke::from_connector(edge.connector()),
+                path,
+                start,
+                end,
+            };
+
+            let mut canvas = Canvas::new(plan.width, grid_height)?;
+            detour.draw(&mut canvas)?;
+            for &(x, y) in &detour.path {
+                // Crossings are taken for good; no further detour may turn or cross there.
+                grid[y][x] = match grid[y][x] {
+                    ' ' => canvas.get(x, y)?,
+                    _ => super::UNICODE_BOX_CROSS,
+                };
+            }
+            let bottom = detour.path.iter().map(|&(_, y)| y).max().unwrap_or_default();
+            plan.height = plan.height.max(bottom + 1);
+            plan.detours.push(detour);
+        }
+        Ok((plan, unroutable))
+    }
+
+    /// Plans the layered router's part; `min_inner_widths` widens the boxes of listed nodes.
+    fn build_routed(
+        ast: &FlowchartAst,
+        layout: &FlowchartLayout,
+        options: RenderOptions,
+        min_inner_widths: &BTreeMap<ObjectId, usize>,
+        routes: &mut FlowchartRouteCache,
     ) -> Result<Self, FlowchartRenderError> {
         let box_height = flow_box_height(options);
-        let raw_routes = route_flowchart_edges_orthogonal_key_order(ast, layout);
+        let raw_routes = routes.routes(ast, layout);
+        let label_gaps = match options.flowchart_node_scale {
+            FlowchartNodeScale::Full => reserve_flowchart_edge_label_gaps(ast, layout, raw_routes),
+            FlowchartNodeScale::Mini | FlowchartNodeScale::Glyph => Vec::new(),
+        };
         let min_col_gap = MIN_COL_GAP.saturating_add(options.flowchart_extra_col_gap);
         let attempt_count = MAX_GLOBAL_CLEARANCE_WIDEN_STEPS;
 
@@ -449,7 +937,8 @@ impl FlowchartRenderPlan {
         for _ in 0..attempt_count {
             let initial_gap_widths =
                 vec![attempt_min_col_gap; layout.layers().len().saturating_sub(1)];
-            let initial_layer_metrics = layer_metrics(ast, layout, &initial_gap_widths, options)?;
+            let initial_layer_metrics =
+                layer_metrics(ast, layout, &initial_gap_widths, options, min_inner_widths)?;
             let (initial_node_renders, base_height) =
                 node_renders(layout, &initial_layer_metrics, box_height)?;
 
@@ -457,26 +946,29 @@ impl FlowchartRenderPlan {
                 ast,
                 &initial_node_renders,
                 initial_layer_metrics.len(),
-                &raw_routes,
+                raw_routes,
                 box_height,
                 attempt_min_col_gap,
             );
             normalize_edge_gap_lanes_for_bridge_alignment(
                 ast,
                 &initial_node_renders,
-                &raw_routes,
+                raw_routes,
                 &mut edge_gap_lanes,
                 &mut gap_widths,
                 attempt_min_col_gap,
                 initial_layer_metrics.len(),
             );
+            for (gap_width, reserved) in gap_widths.iter_mut().zip(&label_gaps) {
+                *gap_width = (*gap_width).max(*reserved);
+            }
 
-            let layer_metrics = layer_metrics(ast, layout, &gap_widths, options)?;
+            let layer_metrics = layer_metrics(ast, layout, &gap_widths, options, min_inner_widths)?;
             let (node_renders, _base_height) = node_renders(layout, &layer_metrics, box_height)?;
-            let routes = align_routes_to_endpoint_rows(ast, &node_renders, &raw_routes);
+            let routes = align_routes_to_endpoint_rows(ast, &node_renders, raw_routes);
 
             let width = layer_metrics.last().map(|layer| layer.x1 + 1).unwrap_or(1);
-            let height = routed_height(base_height, &raw_routes, box_height);
+            let height = routed_height(base_height, raw_routes, box_height);
 
             let has_touch = has_non_endpoint_edge_touch(
                 ast,
@@ -510,6 +1002,9 @@ impl FlowchartRenderPlan {
                     edge_gap_lanes,
                     width,
                     height,
+                    routed_ast: None,
+                    detours: Vec::new(),
+                    subgraph_frames: SubgraphFrames::default(),
                 });
             }
 
@@ -520,7 +1015,7 @@ impl FlowchartRenderPlan {
 
         let (layer_metrics, gap_widths, node_renders, edge_gap_lanes, width, height) =
             fallback.expect("at least one flowchart build attempt");
-        let routes = align_routes_to_endpoint_rows(ast, &node_renders, &raw_routes);
+        let routes = align_routes_to_endpoint_rows(ast, &node_renders, raw_routes);
         let edge_caps = assign_edge_cap_placements(
             ast,
             &node_renders,
@@ -543,10 +1038,14 @@ impl FlowchartRenderPlan {
             edge_gap_lanes,
             width,
             height,
+            routed_ast: None,
+            detours: Vec::new(),
+            subgraph_frames: SubgraphFrames::default(),
         })
     }
 
     fn render_text(&self, ast: &FlowchartAst) -> Result<String, FlowchartRenderError> {
+        let ast = self.routed_ast.as_ref().unwrap_or(ast);
         let mut canvas = Canvas::new(self.width, self.height)?;
 
         for render in self.node_renders.values() {
@@ -592,6 +1091,10 @@ impl FlowchartRenderPlan {
             }
         }
 
+        for detour in &self.detours {
+            detour.draw(&mut canvas)?;
+        }
+
         // Draw labels last so routed connectors can never clobber node text cells.
         for (node_id, render) in &self.node_renders {
             let node = ast
@@ -604,12 +1107,15 @@ impl FlowchartRenderPlan {
                 .get(render.layer)
                 .ok_or(FlowchartRenderError::InvalidLayer { layer: render.layer })?;
 
-            let node_label = prefixed_object_label(node.label(), self.options);
+            let node_label = node_label(node.label(), self.options);
             let clipped = truncate_with_ellipsis(&node_label, layer.inner_width);
             let clipped_len = text_len(&clipped);
             let left_pad = (layer.inner_width.saturating_sub(clipped_len)) / 2;
             let label_x = render.box_x0 + 1 + left_pad;
             canvas.write_str(label_x, render.box_y0 + 1, &clipped)?;
+            if ast.is_entry_node(node_id) {
+                canvas.set(render.box_x0, render.box_y0, ENTRY_NODE_MARKER)?;
+            }
 
             if self.options.show_notes {
                 if let Some(note) = node.note() {
@@ -622,8 +1128,162 @@ impl FlowchartRenderPlan {
             }
         }
 
-        let text = canvas_to_string_trimmed(&canvas);
-        Ok(overlay_edge_caps_on_text(text, &self.edge_caps))
+        let mut text =
+            overlay_edge_caps_on_text(canvas_to_string_trimmed(&canvas), &self.edge_caps);
+        if !self.detours.is_empty() {
+            let mut lines =
+                text.split('\n').map(|line| line.chars().collect::<Vec<_>>()).collect::<Vec<_>>();
+            for (x, y, ch) in self.detours.iter().flat_map(DetourEdge::caps) {
+                if let Some(cell) = lines.get_mut(y).and_then(|line| line.get_mut(x)) {
+                    *cell = ch;
+                }
+            }
+            text = lines.into_iter().map(String::from_iter).collect::<Vec<_>>().join("\n");
+        }
+        let mut strokes = ast
+            .edges()
+            .values()
+            .map(|edge| ConnectorStroke::from_connector(edge.connector()))
+            .collect::<Vec<_>>();
+        strokes.extend(self.detours.iter().map(|detour| detour.stroke));
+        if strokes.iter().all(|stroke| *stroke == ConnectorStroke::Solid) {
+            return Ok(text);
+        }
+        let mut spans_by_edge = self.connector_spans_by_edge(ast)?;
+        spans_by_edge.extend(self.detours.iter().map(DetourEdge::spans));
+        Ok(restyle_connector_strokes(text, &strokes, &spans_by_edge, &self.node_renders))
+    }
+
+    fn frame_subgraphs(
+        &self,
+        text: String,
+        annotate: Option<(&DiagramId, &mut HighlightIndex)>,
+    ) -> String {
+        frame_subgraphs(&self.subgraph_frames, text, annotate)
+    }
+
+    /// Applies the optional post-render compaction pass (see `RenderOptions::flowchart_compact`).
+    fn compact(&self, text: String, highlight_index: Option<&mut HighlightIndex>) -> String {
+        if !self.options.flowchart_compact {
+            return text;
+        }
+        compact_flowchart_text(
+            text,
+            &self.subgraph_frames,
+            &self.node_renders,
+            self.options.flowchart_node_scale,
+            highlight_index,
+        )
+    }
+
+    /// Erases the node boxes down to their glyphs at [`FlowchartNodeScale::Glyph`].
+    fn squash(&self, text: String, annotate: Option<(&DiagramId, &mut HighlightIndex)>) -> String {
+        if self.options.flowchart_node_scale != FlowchartNodeScale::Glyph {
+            return text;
+        }
+        glyph::squash_node_boxes(text, &self.node_renders, annotate)
+    }
+
+    /// Crops what [`Self::squash`] left blank along the top and left edges.
+    fn crop_squashed(&self, text: String, highlight_index: Option<&mut HighlightIndex>) -> String {
+        if self.options.flowchart_node_scale != FlowchartNodeScale::Glyph {
+            return text;
+        }
+        glyph::crop_leading_blanks(text, highlight_index)
+    }
+
+    /// Writes the edge labels onto `text` at [`FlowchartNodeScale::Full`] and returns the cells each
+    /// labeled edge's text took.
+    fn place_edge_labels(
+        &self,
+        ast: &FlowchartAst,
+        text: String,
+    ) -> Result<(String, EdgeLabelSpans), FlowchartRenderError> {
+        if self.options.flowchart_node_scale != FlowchartNodeScale::Full {
+            return Ok((text, Vec::new()));
+        }
+        let routed_ast = self.routed_ast.as_ref().unwrap_or(ast);
+        let mut spans_by_edge = routed_ast
+            .edges()
+            .keys()
+            .cloned()
+            .zip(self.connector_spans_by_edge(routed_ast)?)
+            .collect::<Vec<_>>();
+        spans_by_edge
+            .extend(self.detours.iter().map(|detour| (detour.edge_id.clone(), detour.spans())));
+        let edges = spans_by_edge
+            .iter()
+            .map(|(edge_id, spans)| {
+                (ast.edges().get(edge_id).and_then(|edge| edge.label()), spans.as_slice())
+            })
+            .collect::<Vec<_>>();
+        let (text, label_spans) = label::place_edge_labels(text, &edges);
+        let placed = spans_by_edge
+            .into_iter()
+            .map(|(edge_id, _)| edge_id)
+            .zip(label_spans)
+            .filter(|(_, spans)| !spans.is_empty())
+            .collect();
+        Ok((text, placed))
+    }
+
+    /// Cell spans covered by each edge's connector (in `ast.edges()` order), including caps.
+    fn connector_spans_by_edge(
+        &self,
+        ast: &FlowchartAst,
+    ) -> Result<Vec<Vec<LineSpan>>, FlowchartRenderError> {
+        let mut spans_by_edge = Vec::<Vec<LineSpan>>::with_capacity(ast.edges().len());
+        let vertical_occupied = connector_vertical_occupancy_mask(
+            ast,
+            &self.layer_metrics,
+            &self.gap_widths,
+            &self.node_renders,
+            &self.routes,
+            self.box_height,
+            &self.edge_gap_lanes,
+            self.width,
+            self.height,
+        );
+
+        for (idx, edge) in ast.edges().values().enumerate() {
+            let from = self.node_renders.get(edge.from_node_id()).copied().ok_or_else(|| {
+                FlowchartRenderError::MissingPlacement { node_id: edge.from_node_id().clone() }
+            })?;
+            let to = self.node_renders.get(edge.to_node_id()).copied().ok_or_else(|| {
+                FlowchartRenderError::MissingPlacement { node_id: edge.to_node_id().clone() }
+            })?;
+
+            let mut spans = match self.routes.get(idx) {
+                Some(route) => routed_connector_spans_bridged(
+                    from,
+                    to,
+                    &self.layer_metrics,
+                    &self.gap_widths,
+                    route,
+                    self.box_height,
+                    idx,
+                    &self.edge_gap_lanes,
+                    &vertical_occupied,
+                    self.width,
+                ),
+                None => connector_spans_bridged(from, to, &vertical_occupied, self.width),
+            };
+
+            if let Some(caps) = self.edge_caps.get(idx) {
+                if let Some(cap) = caps.start {
+                    spans.push((cap.y, cap.x, cap.x));
+                }
+                if let Some(cap) = caps.end {
+                    spans.push((cap.y, cap.x, cap.x));
+                }
+            }
+
+            spans.sort();
+            spans.dedup();
+            spans_by_edge.push(spans);
+        }
+
+        Ok(spans_by_edge)
     }
 
     fn render_highlight_index(
@@ -683,61 +1343,83 @@ impl FlowchartRenderPlan {
             }
         }
 
-        let vertical_occupied = connector_vertical_occupancy_mask(
-            ast,
-            &self.layer_metrics,
-            &self.gap_widths,
-            &self.node_renders,
-            &self.routes,
-            self.box_height,
-            &self.edge_gap_lanes,
-            self.width,
-            self.height,
-        );
+        let routed_ast = self.routed_ast.as_ref().unwrap_or(ast);
+        let spans_by_edge = self.connector_spans_by_edge(routed_ast)?;
+        let routed_spans = routed_ast.edges().keys().zip(spans_by_edge);
+        let detour_spans = self.detours.iter().map(|detour| (&detour.edge_id, detour.spans()));
+        for (edge_id, spans) in routed_spans.chain(detour_spans) {
+            let object_ref =
+                ObjectRef::new(diagram_id.clone(), flow_edge_category.clone(), edge_id.clone());
+            highlight_index.insert(object_ref, spans);
+        }
 
-        for (idx, (edge_id, edge)) in ast.edges().iter().enumerate() {
-            let from = self.node_renders.get(edge.from_node_id()).copied().ok_or_else(|| {
-                FlowchartRenderError::MissingPlacement { node_id: edge.from_node_id().clone() }
-            })?;
-            let to = self.node_renders.get(edge.to_node_id()).copied().ok_or_else(|| {
-                FlowchartRenderError::MissingPlacement { node_id: edge.to_node_id().clone() }
-            })?;
+        Ok(highlight_index)
+    }
+}
 
-            let mut spans = match self.routes.get(idx) {
-                Some(route) => routed_connector_spans_bridged(
-                    from,
-                    to,
-                    &self.layer_metrics,
-                    &self.gap_widths,
-                    route,
-                    self.box_height,
-                    idx,
-                    &self.edge_gap_lanes,
-                    &vertical_occupied,
-                    self.width,
-                ),
-                None => connector_spans_bridged(from, to, &vertical_occupied, self.width),
-            };
+/// Draws the subgraph frames around `text`, shifting it right/down when a frame needs room before
+/// the first column or row.
+///
+/// With `annotate`, existing highlight spans are shifted to match and each framed subgraph gets a
+/// `flow/subgraph` entry covering its drawn frame cells.
+fn frame_subgraphs(
+    frames: &SubgraphFrames,
+    text: String,
+    annotate: Option<(&DiagramId, &mut HighlightIndex)>,
+) -> String {
+    if frames.is_empty() {
+        return text;
+    }
 
-            if let Some(caps) = self.edge_caps.get(idx) {
-                if let Some(cap) = caps.start {
-                    spans.push((cap.y, cap.x, cap.x));
-                }
-                if let Some(cap) = caps.end {
-                    spans.push((cap.y, cap.x, cap.x));
-                }
+    let (text, spans_by_subgraph) = frames.overlay(&text);
+    if let Some((diagram_id, highlight_index)) = annotate {
+        let (ox, oy) = frames.offset();
+        for spans in highlight_index.values_mut() {
+            for (y, x0, x1) in spans.iter_mut() {
+                *y += oy;
+                *x0 += ox;
+                *x1 += ox;
             }
-
-            spans.sort();
-            spans.dedup();
-
-            let object_ref =
-                ObjectRef::new(diagram_id.clone(), flow_edge_category.clone(), edge_id.clone());
+        }
+        let category =
+            CategoryPath::new(vec!["flow".to_owned(), "subgraph".to_owned()]).expect("valid");
+        for (subgraph_id, spans) in spans_by_subgraph {
+            let object_ref = ObjectRef::new(diagram_id.clone(), category.clone(), subgraph_id);
             highlight_index.insert(object_ref, spans);
         }
+    }
+    text
+}
 
-        Ok(highlight_index)
+/// Compacts `text` (see `RenderOptions::flowchart_compact`), keeping the node boxes (or, at
+/// [`FlowchartNodeScale::Glyph`], the node glyphs) intact.
+fn compact_flowchart_text(
+    text: String,
+    frames: &SubgraphFrames,
+    node_renders: &BTreeMap<ObjectId, NodeRender>,
+    node_scale: FlowchartNodeScale,
+    highlight_index: Option<&mut HighlightIndex>,
+) -> String {
+    let (ox, oy) = frames.offset();
+    let protected = if node_scale == FlowchartNodeScale::Glyph {
+        glyph::protected_glyph_cells(node_renders, (ox, oy))
+    } else {
+        node_renders
+            .values()
+            .map(|render| {
+                (render.box_x0 + ox, render.box_y0 + oy, render.box_x1 + ox, render.box_y1 + oy)
+            })
+            .collect::<Vec<_>>()
+    };
+    let compaction = Compaction::plan(&text, &protected);
+    if compaction.is_identity() {
+        return text;
     }
+
+    if let Some(highlight_index) = highlight_index {
+        compaction.apply_highlight_index(highlight_index);
+    }
+    compaction.apply_text(&text)
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -770,7 +1452,8 @@ impl From<CanvasError> for FlowchartRenderError {
 /// Deterministic baseline Unicode renderer for a flowchart.
 ///
 /// This renderer consumes layered coordinates from `FlowchartLayout` and uses the AST only for node
-/// labels.
+/// and edge labels. Edge labels are written onto their connector mid-route, in room the layout
+/// reserves between layers (see `reserve_flowchart_edge_label_gaps`).
 ///
 /// Limitations (baseline):
 /// - Edge routing uses `route_flowchart_edges_orthogonal` polylines, rendered through shared lanes
@@ -789,8 +1472,22 @@ pub fn render_flowchart_unicode_with_options(
     layout: &FlowchartLayout,
     options: RenderOptions,
 ) -> Result<String, FlowchartRenderError> {
+    if options.flowchart_edge_glyphs != FlowchartEdgeGlyphs::Box {
+        // Redrawing edges needs to know which cells belong to them.
+        let diagram_id = DiagramId::new("flow").expect("valid diagram id");
+        return render_flowchart_unicode_annotated_with_options(&diagram_id, ast, layout, options)
+            .map(|render| render.text);
+    }
+    if layout.direction().is_vertical() {
+        if let Some(render) = vertical::render_vertical(None, ast, layout, options)? {
+            return Ok(render.text);
+        }
+    }
     let plan = FlowchartRenderPlan::build(ast, layout, options)?;
-    plan.render_text(ast)
+    let (text, _) = plan.place_edge_labels(ast, plan.render_text(ast)?)?;
+    let text = plan.squash(text, None);
+    let text = plan.frame_subgraphs(text, None);
+    Ok(plan.crop_squashed(plan.compact(text, None), None))
 }
 
 pub fn render_flowchart_unicode_annotated(
@@ -812,16 +1509,72 @@ pub fn render_flowchart_unicode_annotated_with_options(
     layout: &FlowchartLayout,
     options: RenderOptions,
 ) -> Result<AnnotatedRender, FlowchartRenderError> {
-    let plan = FlowchartRenderPlan::build(ast, layout, options)?;
-    let text = plan.render_text(ast)?;
+    render_flowchart_unicode_annotated_with_routes(
+        diagram_id,
+        ast,
+        layout,
+        options,
+        &mut FlowchartRouteCache::default(),
+    )
+}
+
+/// Same as [`render_flowchart_unicode_annotated_with_options`], taking edge routes from `routes`
+/// and adding the ones it had to compute.
+///
+/// `routes` must only have been filled for `layout` and the current edge endpoints.
+pub fn render_flowchart_unicode_annotated_with_routes(
+    diagram_id: &DiagramId,
+    ast: &FlowchartAst,
+    layout: &FlowchartLayout,
+    options: RenderOptions,
+    routes: &mut FlowchartRouteCache,
+) -> Result<AnnotatedRender, FlowchartRenderError> {
+    if layout.direction().is_vertical() {
+        if let Some(render) = vertical::render_vertical(Some(diagram_id), ast, layout, options)? {
+            return Ok(hires::redraw_edges(render, options.flowchart_edge_glyphs));
+        }
+    }
+    let plan = FlowchartRenderPlan::build_with_routes(ast, layout, options, routes)?;
+    let (text, label_spans) = plan.place_edge_labels(ast, plan.render_text(ast)?)?;
     let mut highlight_index = plan.render_highlight_index(diagram_id, ast)?;
+    add_edge_label_spans(diagram_id, &mut highlight_index, label_spans);
+    let text = plan.squash(text, Some((diagram_id, &mut highlight_index)));
+    let text = plan.frame_subgraphs(text, Some((diagram_id, &mut highlight_index)));
 
     clamp_highlight_index_to_text(&mut highlight_index, &text);
-    Ok(AnnotatedRender { text, highlight_index })
+    let text = plan.compact(text, Some(&mut highlight_index));
+    let text = plan.crop_squashed(text, Some(&mut highlight_index));
+    Ok(hires::redraw_edges(
+        AnnotatedRender { text, highlight_index },
+        options.flowchart_edge_glyphs,
+    ))
 }
 
 // Extracted flowchart rendering internals and routing helpers.
 include!("flowchart/helpers.rs");
 
+/// Adds the cells of each placed edge label to its edge's highlight spans.
+fn add_edge_label_spans(
+    diagram_id: &DiagramId,
+    highlight_index: &mut HighlightIndex,
+    label_spans: EdgeLabelSpans,
+) {
+    let flow_edge_category =
+        CategoryPath::new(vec!["flow".to_owned(), "edge".to_owned()]).expect("valid");
+    for (edge_id, spans) in label_spans {
+        let object_ref = ObjectRef::new(diagram_id.clone(), flow_edge_category.clone(), edge_id);
+        let entry = highlight_index.entry(object_ref).or_default();
+        entry.extend(spans);
+        entry.sort();
+        entry.dedup();
+    }
+}
+
+mod compact;
+mod glyph;
+mod hires;
+mod label;
+mod subgraph;
 #[cfg(test)]
 mod tests;
+mod vertical;
@@ -0,0 +1,176 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Post-render compaction for flowchart text.
+//!
+//! The layered renderer reserves generous gaps and lanes so routing never runs out of room. Once
+//! the text is drawn, many of those rows/columns only repeat their neighbour: empty padding or a
+//! straight connector run. Collapsing such duplicates shrinks the canvas (and shortens long
+//! orthogonal detours) without changing topology, because a dropped row/column never contains a
+//! corner, tee, arrowhead, label, or node box cell.
+
+use super::super::text::text_len;
+use super::super::{
+    HighlightIndex, UNICODE_BOX_HORIZONTAL, UNICODE_BOX_VERTICAL, UNICODE_DOTTED_HORIZONTAL,
+    UNICODE_DOTTED_VERTICAL, UNICODE_THICK_HORIZONTAL, UNICODE_THICK_VERTICAL,
+};
+
+/// Rows/columns kept by a compaction pass over a rendered grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Compaction {
+    row_map: Vec<Option<usize>>,
+    col_map: Vec<Option<usize>>,
+}
+
+/// Inclusive `(x0, y0, x1, y1)` cell rectangle that must survive compaction untouched.
+pub(super) type ProtectedRect = (usize, usize, usize, usize);
+
+impl Compaction {
+    /// Plans which rows/columns of `text` can be dropped.
+    ///
+    /// A row is dropped when it only contains blanks and vertical connector cells, equals the row
+    /// above it, and does not intersect a protected rectangle. Columns are treated the same way
+    /// with horizontal connector cells.
+    pub(super) fn plan(text: &str, protected: &[ProtectedRect]) -> Self {
+        let grid = text_grid(text);
+        let height = grid.len();
+        let width = grid.first().map_or(0, Vec::len);
+
+        let row_protected =
+            |y: usize| protected.iter().any(|&(_, y0, _, y1)| (y0..=y1).contains(&y));
+        let col_protected =
+            |x: usize| protected.iter().any(|&(x0, _, x1, _)| (x0..=x1).contains(&x));
+
+        let drop_row = (0..height)
+            .map(|y| {
+                y > 0
+                    && !row_protected(y)
+                    && grid[y] == grid[y - 1]
+                    && grid[y].iter().all(|&ch| is_vertical_run_cell(ch))
+            })
+            .collect::<Vec<_>>();
+        let drop_col = (0..width)
+            .map(|x| {
+                x > 0
+                    && !col_protected(x)
+                    && grid.iter().all(|row| row[x] == row[x - 1] && is_horizontal_run_cell(row[x]))
+            })
+            .collect::<Vec<_>>();
+
+        Self { row_map: keep_map(&drop_row), col_map: keep_map(&drop_col) }
+    }
+
+    /// Returns `true` when the pass would not drop anything.
+    pub(super) fn is_identity(&self) -> bool {
+        self.row_map.iter().chain(&self.col_map).all(Option::is_some)
+    }
+
+    /// Applies the pass to rendered text, keeping the trimmed line conventions of the renderer.
+    pub(super) fn apply_text(&self, text: &str) -> String {
+        let mut lines = Vec::<String>::new();
+        for (y, line) in text_grid(text).into_iter().enumerate() {
+            if self.row_map[y].is_none() {
+                continue;
+            }
+            let line = line
+                .into_iter()
+                .enumerate()
+                .filter(|(x, _)| self.col_map[*x].is_some())
+                .map(|(_, ch)| ch)
+                .collect::<String>();
+            lines.push(line.trim_end_matches(' ').to_owned());
+        }
+        while matches!(lines.last(), Some(line) if line.is_empty()) {
+            lines.pop();
+        }
+        lines.join("\n")
+    }
+
+    /// Remaps highlight spans onto the compacted grid, dropping spans that vanish entirely.
+    pub(super) fn apply_highlight_index(&self, highlight_index: &mut HighlightIndex) {
+        highlight_index.retain(|_, spans| {
+            *spans = spans
+                .iter()
+                .filter_map(|&(y, x0, x1)| {
+                    let y = (*self.row_map.get(y)?)?;
+                    let mut kept = (x0..=x1).filter_map(|x| self.col_map.get(x).copied().flatten());
+                    let first = kept.next()?;
+                    let last = kept.next_back().unwrap_or(first);
+                    Some((y, first, last))
+                })
+                .collect();
+            spans.dedup();
+            !spans.is_empty()
+        });
+    }
+}
+
+fn is_vertical_run_cell(ch: char) -> bool {
+    matches!(ch, ' ' | UNICODE_BOX_VERTICAL | UNICODE_DOTTED_VERTICAL | UNICODE_THICK_VERTICAL)
+}
+
+fn is_horizontal_run_cell(ch: char) -> bool {
+    matches!(
+        ch,
+        ' ' | UNICODE_BOX_HORIZONTAL | UNICODE_DOTTED_HORIZONTAL | UNICODE_THICK_HORIZONTAL
+    )
+}
+
+fn text_grid(text: &str) -> Vec<Vec<char>> {
+    let width = text.split('\n').map(text_len).max().unwrap_or(0);
+    text.split('\n')
+        .map(|line| {
+            let mut row = line.chars().collect::<Vec<_>>();
+            row.resize(width, ' ');
+            row
+        })
+        .collect()
+}
+
+fn keep_map(dropped: &[bool]) -> Vec<Option<usize>> {
+    let mut next = 0usize;
+    dropped
+        .iter()
+        .map(|&drop| {
+            if drop {
+                None
+            } else {
+                next += 1;
+                Some(next - 1)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Compaction;
+    use crate::render::HighlightIndex;
+
+    #[test]
+    fn collapses_repeated_connector_rows_and_columns() {
+        let text = "┌─┐        ┌─┐\n│A├────────┤B│\n└┬┘        └─┘\n │\n │\n │\n ▼";
+        let compaction = Compaction::plan(text, &[(0, 0, 2, 2), (11, 0, 13, 2)]);
+
+        assert!(!compaction.is_identity());
+        assert_eq!(compaction.apply_text(text), "┌─┐ ┌─┐\n│A├─┤B│\n└┬┘ └─┘\n │\n ▼");
+    }
+
+    #[test]
+    fn remaps_highlight_spans_onto_kept_cells() {
+        let text = "┌─┐        ┌─┐\n│A├────────┤B│\n└─┘        └─┘";
+        let compaction = Compaction::plan(text, &[(0, 0, 2, 2), (11, 0, 13, 2)]);
+
+        let object_ref = "d:flow/flow/edge/e:0000".parse().expect("object ref");
+        let mut highlight_index = HighlightIndex::new();
+        highlight_index.insert(object_ref, vec![(1, 3, 10), (1, 4, 9)]);
+        compaction.apply_highlight_index(&mut highlight_index);
+
+        assert_eq!(highlight_index.into_values().next(), Some(vec![(1, 3, 3)]));
+    }
+}
@@ -0,0 +1,223 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! One-character node glyphs for zoomed-out flowcharts (`FlowchartNodeScale::Glyph`).
+//!
+//! Both renderers first draw every node as an ordinary box around its one-character label, so
+//! edges are routed exactly as at full scale. This pass then erases each box down to the label and
+//! redraws the edge ends that touched its border as connectors running into the glyph. The blank
+//! rows and columns the borders leave behind are what compaction then drops.
+
+use std::collections::BTreeMap;
+
+use crate::model::ids::{DiagramId, ObjectId};
+use crate::model::{CategoryPath, ObjectRef};
+
+use super::super::{box_char_from_edges, box_edges_from_char, BoxEdges, HighlightIndex};
+use super::compact::ProtectedRect;
+use super::NodeRender;
+
+/// The cell holding the glyph of a node drawn at `render`.
+pub(super) fn glyph_cell(render: &NodeRender) -> (usize, usize) {
+    ((render.box_x0 + render.box_x1) / 2, render.mid_y())
+}
+
+/// Compaction must keep each glyph, but nothing else of the erased boxes.
+pub(super) fn protected_glyph_cells(
+    node_renders: &BTreeMap<ObjectId, NodeRender>,
+    (ox, oy): (usize, usize),
+) -> Vec<ProtectedRect> {
+    node_renders
+        .values()
+        .map(|render| {
+            let (x, y) = glyph_cell(render);
+            (x + ox, y + oy, x + ox, y + oy)
+        })
+        .collect()
+}
+
+/// Replaces every node box in `text` with its glyph; with `annotate`, node spans shrink to the
+/// glyph cell.
+pub(super) fn squash_node_boxes(
+    text: String,
+    node_renders: &BTreeMap<ObjectId, NodeRender>,
+    annotate: Option<(&DiagramId, &mut HighlightIndex)>,
+) -> String {
+    let mut grid =
+        text.split('\n').map(|line| line.chars().collect::<Vec<_>>()).collect::<Vec<_>>();
+    let cell = |grid: &[Vec<char>], x: Option<usize>, y: Option<usize>| {
+        y.and_then(|y| grid.get(y)).and_then(|row| x.and_then(|x| row.get(x))).copied()
+    };
+
+    for render in node_renders.values() {
+        let (x0, x1, y0, y1) = (render.box_x0, render.box_x1, render.box_y0, render.box_y1);
+        let (cx, cy) = glyph_cell(render);
+        let glyph = cell(&grid, Some(cx), Some(cy)).unwrap_or(' ');
+
+        // Border cells where a connector meets the box, per side: either the border cell is a
+        // tee pointing out or the cell outside continues into it.
+        let port = |border: (usize, usize), outside: (Option<usize>, Option<usize>), out| {
+            enters(cell(&grid, Some(border.0), Some(border.1)), out)
+                || enters(cell(&grid, outside.0, outside.1), reverse(out))
+        };
+        let top = (x0..=x1)
+            .filter(|&x| port((x, y0), (Some(x), y0.checked_sub(1)), BoxEdges::UP))
+            .collect::<Vec<_>>();
+        let bottom = (x0..=x1)
+            .filter(|&x| port((x, y1), (Some(x), Some(y1 + 1)), BoxEdges::DOWN))
+            .collect::<Vec<_>>();
+        let left = (y0..=y1)
+            .filter(|&y| port((x0, y), (x0.checked_sub(1), Some(y)), BoxEdges::LEFT))
+            .collect::<Vec<_>>();
+        let right = (y0..=y1)
+            .filter(|&y| port((x1, y), (Some(x1 + 1), Some(y)), BoxEdges::RIGHT))
+            .collect::<Vec<_>>();
+
+        let mut links = Vec::<((usize, usize), BoxEdges)>::new();
+        for (ports, row, toward) in [(&top, y0, BoxEdges::UP), (&bottom, y1, BoxEdges::DOWN)] {
+            if ports.is_empty() {
+                continue;
+            }
+            links.extend(ports.iter().map(|&x| ((x, row), toward)));
+            let min = ports.iter().copied().chain([cx]).min().unwrap_or(cx);
+            let max = ports.iter().copied().chain([cx]).max().unwrap_or(cx);
+            link_run(&mut links, (min, row), (max, row));
+            link_run(&mut links, (cx, row), (cx, cy));
+        }
+        for (ports, col, toward) in [(&left, x0, BoxEdges::LEFT), (&right, x1, BoxEdges::RIGHT)] {
+            if ports.is_empty() {
+                continue;
+            }
+            links.extend(ports.iter().map(|&y| ((col, y), toward)));
+            let min = ports.iter().copied().chain([cy]).min().unwrap_or(cy);
+            let max = ports.iter().copied().chain([cy]).max().unwrap_or(cy);
+            link_run(&mut links, (col, min), (col, max));
+            link_run(&mut links, (col, cy), (cx, cy));
+        }
+        let mut edges = BTreeMap::<(usize, usize), BoxEdges>::new();
+        for (cell, edge) in links {
+            let current = edges.entry(cell).or_insert(BoxEdges::NONE);
+            *current = current.union(edge);
+        }
+
+        for y in y0..=y1 {
+            let Some(row) = grid.get_mut(y) else {
+                continue;
+            };
+            for x in x0..=x1.min(row.len().saturating_sub(1)) {
+                row[x] = match edges.get(&(x, y)) {
+                    _ if (x, y) == (cx, cy) => glyph,
+                    Some(&edge) => box_char_from_edges(edge),
+                    None => ' ',
+                };
+            }
+        }
+    }
+
+    if let Some((diagram_id, highlight_index)) = annotate {
+        let category =
+            CategoryPath::new(vec!["flow".to_owned(), "node".to_owned()]).expect("valid");
+        for (node_id, render) in node_renders {
+            let object_ref = ObjectRef::new(diagram_id.clone(), category.clone(), node_id.clone());
+            let (x, y) = glyph_cell(render);
+            highlight_index.insert(object_ref, vec![(y, x, x)]);
+        }
+    }
+
+    grid.into_iter()
+        .map(|row| String::from_iter(row).trim_end().to_owned())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Links the cells of the straight run from `a` to `b` to their neighbours along it.
+fn link_run(links: &mut Vec<((usize, usize), BoxEdges)>, a: (usize, usize), b: (usize, usize)) {
+    if a.1 == b.1 {
+        for x in a.0.min(b.0)..a.0.max(b.0) {
+            links.push(((x, a.1), BoxEdges::RIGHT));
+            links.push(((x + 1, a.1), BoxEdges::LEFT));
+        }
+    } else {
+        for y in a.1.min(b.1)..a.1.max(b.1) {
+            links.push(((a.0, y), BoxEdges::DOWN));
+            links.push(((a.0, y + 1), BoxEdges::UP));
+        }
+    }
+}
+
+fn reverse(edge: BoxEdges) -> BoxEdges {
+    match edge {
+        BoxEdges::LEFT => BoxEdges::RIGHT,
+        BoxEdges::RIGHT => BoxEdges::LEFT,
+        BoxEdges::UP => BoxEdges::DOWN,
+        _ => BoxEdges::UP,
+    }
+}
+
+/// Whether `cell` holds a connector (or edge cap) continuing toward `direction`.
+fn enters(cell: Option<char>, direction: BoxEdges) -> bool {
+    let Some(ch) = cell else {
+        return false;
+    };
+    let pointing = match ch {
+        '▶' => BoxEdges::RIGHT,
+        '◀' => BoxEdges::LEFT,
+        '▼' => BoxEdges::DOWN,
+        '▲' => BoxEdges::UP,
+        '○' | '✕' => return true,
+        super::super::UNICODE_DOTTED_HORIZONTAL | super::super::UNICODE_THICK_HORIZONTAL => {
+            BoxEdges::LEFT.union(BoxEdges::RIGHT)
+        }
+        super::super::UNICODE_DOTTED_VERTICAL | super::super::UNICODE_THICK_VERTICAL => {
+            BoxEdges::UP.union(BoxEdges::DOWN)
+        }
+        _ => box_edges_from_char(ch).unwrap_or(BoxEdges::NONE),
+    };
+    pointing.contains(direction)
+}
+
+/// Drops the blank rows and columns the erased boxes can leave along the top and left edges, moving
+/// `highlight_index` along.
+pub(super) fn crop_leading_blanks(
+    text: String,
+    highlight_index: Option<&mut HighlightIndex>,
+) -> String {
+    let lines = text.split('\n').collect::<Vec<_>>();
+    let blank = |line: &str| line.chars().all(|ch| ch == ' ');
+    let oy = lines.iter().take_while(|line| blank(line)).count();
+    let ox = lines
+        .iter()
+        .filter(|line| !blank(line))
+        .map(|line| line.chars().take_while(|&ch| ch == ' ').count())
+        .min()
+        .unwrap_or(0);
+    if (ox, oy) == (0, 0) {
+        return text;
+    }
+
+    if let Some(highlight_index) = highlight_index {
+        highlight_index.retain(|_, spans| {
+            spans.retain_mut(|(y, x0, x1)| {
+                if *y < oy || *x1 < ox {
+                    return false;
+                }
+                *y -= oy;
+                *x0 = x0.saturating_sub(ox);
+                *x1 -= ox;
+                true
+            });
+            !spans.is_empty()
+        });
+    }
+    lines
+        .iter()
+        .skip(oy)
+        .map(|line| line.chars().skip(ox).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -1240,6 +1240,7 @@ fn layer_metrics(
     layout: &FlowchartLayout,
     gap_widths: &[usize],
     options: RenderOptions,
+    min_inner_widths: &BTreeMap<ObjectId, usize>,
 ) -> Result<Vec<LayerMetrics>, FlowchartRenderError> {
     let mut out = Vec::<LayerMetrics>::with_capacity(layout.layers().len());
     let mut cursor_x = 0usize;
@@ -1253,14 +1254,18 @@ fn layer_metrics(
                     .ok_or_else(|| FlowchartRenderError::MissingNode {
                         node_id: node_id.clone(),
                     })
-                    .map(|node| text_len(&prefixed_object_label(node.label(), options)))
+                    .map(|node| text_len(&node_label(node.label(), options)))
             })
             .collect::<Result<Vec<_>, _>>()?
             .into_iter()
             .max()
             .unwrap_or(0);
 
-        let mut inner_width = (max_label_len + 2).max(MIN_BOX_INNER_WIDTH);
+        let min_inner_width = layer_nodes
+            .iter()
+            .filter_map(|node_id| min_inner_widths.get(node_id).copied())
+            .fold(MIN_BOX_INNER_WIDTH, usize::max);
+        let mut inner_width = (max_label_len + 2).max(min_inner_width);
         let mut total_width = inner_width + 2;
 
         // Keep widths odd so connectors naturally align to center cells.
@@ -1292,6 +1297,17 @@ fn prefixed_object_label(label: &str, options: RenderOptions) -> String {
     }
 }
 
+/// Text drawn inside a node box at `options.flowchart_node_scale`.
+fn node_label(label: &str, options: RenderOptions) -> String {
+    match options.flowchart_node_scale {
+        FlowchartNodeScale::Full => prefixed_object_label(label, options),
+        FlowchartNodeScale::Mini => truncate_with_ellipsis(label.trim(), MINI_NODE_LABEL_WIDTH),
+        FlowchartNodeScale::Glyph => {
+            label.trim().chars().next().unwrap_or(EMPTY_NODE_GLYPH).to_string()
+        }
+    }
+}
+
 fn node_renders(
     layout: &FlowchartLayout,
     layer_metrics: &[LayerMetrics],
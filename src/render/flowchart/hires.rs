@@ -0,0 +1,246 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! High-resolution edge glyphs for rendered flowcharts.
+//!
+//! Every solid or dotted connector cell of an edge is redrawn on a finer dot grid (braille: 2×4
+//! dots per cell, quadrant blocks: 2×2): straight runs become dot lines through the cell, and a
+//! corner joining two perpendicular sides is cut diagonally from one side to the other instead of
+//! turning at a right angle. Strokes may spill into a neighbouring connector cell, so each cell's
+//! glyph is the union of all dots landing on it. Node boxes, arrowheads, labels and thick
+//! connectors keep their glyphs.
+//!
+//! Each edge's highlight spans are rebuilt from the cells its dots land on, so the cells the TUI
+//! highlights are exactly the ones showing that edge.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::super::{
+    cells_to_spans, AnnotatedRender, FlowchartEdgeGlyphs, LineSpan, UNICODE_BOX_BOTTOM_LEFT,
+    UNICODE_BOX_BOTTOM_RIGHT, UNICODE_BOX_CROSS, UNICODE_BOX_HORIZONTAL, UNICODE_BOX_TEE_DOWN,
+    UNICODE_BOX_TEE_LEFT, UNICODE_BOX_TEE_RIGHT, UNICODE_BOX_TEE_UP, UNICODE_BOX_TOP_LEFT,
+    UNICODE_BOX_TOP_RIGHT, UNICODE_BOX_VERTICAL, UNICODE_DOTTED_HORIZONTAL,
+    UNICODE_DOTTED_VERTICAL, UNICODE_THICK_HORIZONTAL, UNICODE_THICK_VERTICAL,
+};
+
+const NORTH: u8 = 1 << 0;
+const EAST: u8 = 1 << 1;
+const SOUTH: u8 = 1 << 2;
+const WEST: u8 = 1 << 3;
+
+/// Quadrant glyphs indexed by mask: top-left 1, top-right 2, bottom-left 4, bottom-right 8.
+const QUADRANTS: [char; 16] =
+    [' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█'];
+
+/// Sides a connector glyph joins, and whether it is dotted; `None` for anything else.
+fn connector_sides(ch: char) -> Option<(u8, bool)> {
+    Some(match ch {
+        UNICODE_BOX_HORIZONTAL => (WEST | EAST, false),
+        UNICODE_BOX_VERTICAL => (NORTH | SOUTH, false),
+        UNICODE_DOTTED_HORIZONTAL => (WEST | EAST, true),
+        UNICODE_DOTTED_VERTICAL => (NORTH | SOUTH, true),
+        UNICODE_BOX_TOP_LEFT => (EAST | SOUTH, false),
+        UNICODE_BOX_TOP_RIGHT => (WEST | SOUTH, false),
+        UNICODE_BOX_BOTTOM_LEFT => (NORTH | EAST, false),
+        UNICODE_BOX_BOTTOM_RIGHT => (NORTH | WEST, false),
+        UNICODE_BOX_TEE_RIGHT => (NORTH | EAST | SOUTH, false),
+        UNICODE_BOX_TEE_LEFT => (NORTH | WEST | SOUTH, false),
+        UNICODE_BOX_TEE_DOWN => (WEST | EAST | SOUTH, false),
+        UNICODE_BOX_TEE_UP => (WEST | EAST | NORTH, false),
+        UNICODE_BOX_CROSS => (NORTH | EAST | SOUTH | WEST, false),
+        _ => return None,
+    })
+}
+
+/// Dot grid of one glyph family: dots per cell and the dot row straight runs pass through.
+#[derive(Debug, Clone, Copy)]
+struct DotGrid {
+    width: i64,
+    height: i64,
+    hub_row: i64,
+}
+
+impl DotGrid {
+    fn new(glyphs: FlowchartEdgeGlyphs) -> Option<Self> {
+        match glyphs {
+            FlowchartEdgeGlyphs::Box => None,
+            FlowchartEdgeGlyphs::Braille => Some(Self { width: 2, height: 4, hub_row: 1 }),
+            FlowchartEdgeGlyphs::Blocks => Some(Self { width: 2, height: 2, hub_row: 0 }),
+        }
+    }
+
+    /// Dot where the straight runs through cell `(y, x)` meet.
+    fn hub(self, (y, x): (usize, usize)) -> (i64, i64) {
+        (y as i64 * self.height + self.hub_row, x as i64 * self.width)
+    }
+
+    /// Dot halfway between the hub of `cell` and the hub of its neighbour on `side`.
+    fn port(self, cell: (usize, usize), side: u8) -> (i64, i64) {
+        let (y, x) = self.hub(cell);
+        match side {
+            NORTH => (y - self.height / 2, x),
+            SOUTH => (y + self.height / 2, x),
+            WEST => (y, x - self.width / 2),
+            _ => (y, x + self.width / 2),
+        }
+    }
+
+    fn cell_of(self, (y, x): (i64, i64)) -> Option<(usize, usize)> {
+        Some((
+            usize::try_from(y.div_euclid(self.height)).ok()?,
+            usize::try_from(x.div_euclid(self.width)).ok()?,
+        ))
+    }
+
+    /// Glyph for the dots set in `mask`, one bit per dot in row-major order.
+    fn glyph(self, mask: u8) -> char {
+        if self.height == 2 {
+            return QUADRANTS[usize::from(mask & 0x0f)];
+        }
+        // Braille numbers the dots down the left column, then the right, with the fourth row last.
+        let mut bits = 0u32;
+        for (bit, (row, col)) in
+            (0..4).flat_map(|row| (0..2).map(move |col| (row, col))).enumerate()
+        {
+            if mask & (1 << bit) != 0 {
+                bits |= if row < 3 { 1 << (row + 3 * col) } else { 1 << (6 + col) };
+            }
+        }
+        char::from_u32(0x2800 + bits).unwrap_or(' ')
+    }
+
+    fn bit(self, (y, x): (i64, i64)) -> u8 {
+        let (row, col) = (y.rem_euclid(self.height), x.rem_euclid(self.width));
+        1 << (row * self.width + col)
+    }
+}
+
+/// Redraws the edge connectors of `render` with `glyphs`; box glyphs leave it untouched.
+pub(super) fn redraw_edges(
+    render: AnnotatedRender,
+    glyphs: FlowchartEdgeGlyphs,
+) -> AnnotatedRender {
+    let Some(grid) = DotGrid::new(glyphs) else {
+        return render;
+    };
+    let AnnotatedRender { text, mut highlight_index } = render;
+    let mut lines =
+        text.split('\n').map(|line| line.chars().collect::<Vec<_>>()).collect::<Vec<_>>();
+
+    let is_edge = |object_ref: &crate::model::ObjectRef| {
+        object_ref.category().segments().last().is_some_and(|segment| segment == "edge")
+    };
+    let cells_of = |spans: &[LineSpan]| {
+        spans.iter().flat_map(|&(y, x0, x1)| (x0..=x1).map(move |x| (y, x))).collect::<Vec<_>>()
+    };
+    let blocked = highlight_index
+        .iter()
+        .filter(|(object_ref, _)| !is_edge(object_ref))
+        .flat_map(|(_, spans)| cells_of(spans))
+        .collect::<BTreeSet<_>>();
+    let char_at = |lines: &[Vec<char>], (y, x): (usize, usize)| {
+        lines.get(y).and_then(|line| line.get(x)).copied()
+    };
+
+    // Edges drawing each connector cell, by index into the edge refs.
+    let edge_refs = highlight_index
+        .keys()
+        .filter(|object_ref| is_edge(object_ref))
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut connectors = BTreeMap::<(usize, usize), (u8, bool, BTreeSet<usize>)>::new();
+    let mut thick_edges = BTreeSet::new();
+    for (edge_idx, edge_ref) in edge_refs.iter().enumerate() {
+        for cell in cells_of(&highlight_index[edge_ref]) {
+            if blocked.contains(&cell) {
+                continue;
+            }
+            let ch = char_at(&lines, cell);
+            if matches!(ch, Some(UNICODE_THICK_HORIZONTAL | UNICODE_THICK_VERTICAL)) {
+                thick_edges.insert(edge_idx);
+            }
+            let Some((sides, dotted)) = ch.and_then(connector_sides) else {
+                continue;
+            };
+            connectors.entry(cell).or_insert((sides, dotted, BTreeSet::new())).2.insert(edge_idx);
+        }
+    }
+    // Thick edges keep their box glyphs, so their corners must too.
+    connectors.retain(|_, (_, _, owners)| !owners.is_subset(&thick_edges));
+
+    let mut masks = BTreeMap::<(usize, usize), u8>::new();
+    let mut drawn = vec![BTreeSet::<(usize, usize)>::new(); edge_refs.len()];
+    for (&cell, (sides, dotted, owners)) in &connectors {
+        let ports = [NORTH, EAST, SOUTH, WEST]
+            .into_iter()
+            .filter(|side| sides & side != 0)
+            .map(|side| grid.port(cell, side))
+            .collect::<Vec<_>>();
+        let mut dots = match ports.as_slice() {
+            [a, b] if a.0 != b.0 && a.1 != b.1 => line_dots(*a, *b),
+            _ => Vec::new(),
+        };
+        // A diagonal can pass just outside its own cell; turn at the hub there instead.
+        if !dots.iter().any(|&dot| grid.cell_of(dot) == Some(cell)) {
+            dots = ports.iter().flat_map(|&port| line_dots(grid.hub(cell), port)).collect();
+        }
+        for dot in dots {
+            if *dotted && (dot.0 + dot.1) % 2 != 0 {
+                continue;
+            }
+            let Some(target) = grid.cell_of(dot).filter(|target| connectors.contains_key(target))
+            else {
+                continue;
+            };
+            *masks.entry(target).or_default() |= grid.bit(dot);
+            for &owner in owners {
+                drawn[owner].insert(target);
+            }
+        }
+    }
+
+    for (&(y, x), &mask) in &masks {
+        if mask != 0 {
+            lines[y][x] = grid.glyph(mask);
+        }
+    }
+    for (edge_ref, drawn) in edge_refs.iter().zip(drawn) {
+        let spans = highlight_index.get_mut(edge_ref).expect("edge ref from the index");
+        let cells = cells_of(spans)
+            .into_iter()
+            .filter(|cell| !masks.get(cell).is_some_and(|&mask| mask != 0))
+            .chain(drawn.into_iter().filter(|cell| masks.get(cell).is_some_and(|&mask| mask != 0)))
+            .collect::<BTreeSet<_>>();
+        *spans = cells_to_spans(&cells);
+    }
+
+    let text = lines.into_iter().map(String::from_iter).collect::<Vec<_>>().join("\n");
+    AnnotatedRender { text, highlight_index }
+}
+
+/// Dots on the straight line from `from` to `to`, both included (Bresenham).
+fn line_dots(from: (i64, i64), to: (i64, i64)) -> Vec<(i64, i64)> {
+    let (mut y, mut x) = from;
+    let (dy, dx) = ((to.0 - y).abs(), (to.1 - x).abs());
+    let (step_y, step_x) = ((to.0 - y).signum(), (to.1 - x).signum());
+    let mut err = dx - dy;
+    let mut dots = vec![(y, x)];
+    while (y, x) != to {
+        let doubled = 2 * err;
+        if doubled > -dy {
+            err -= dy;
+            x += step_x;
+        }
+        if doubled < dx {
+            err += dx;
+            y += step_y;
+        }
+        dots.push((y, x));
+    }
+    dots
+}
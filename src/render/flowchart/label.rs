@@ -0,0 +1,188 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Edge labels written onto drawn flowchart connectors.
+//!
+//! A label replaces the middle of the longest straight horizontal run its connector does not share
+//! with another edge, keeping at least one line cell on either side. Connectors without such a
+//! run get the label in the blank cells beside their longest vertical run, or below (or above)
+//! their longest horizontal run, instead. As a last resort it is shortened with an ellipsis to fit
+//! the longest horizontal run; labels that fit nowhere are left out rather than drawn over boxes,
+//! text or other connectors.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::super::text::{text_len, truncate_with_ellipsis};
+use super::super::{
+    LineSpan, UNICODE_BOX_HORIZONTAL, UNICODE_BOX_VERTICAL, UNICODE_DOTTED_HORIZONTAL,
+    UNICODE_DOTTED_VERTICAL, UNICODE_THICK_HORIZONTAL, UNICODE_THICK_VERTICAL,
+};
+
+/// A straight run of connector cells: `(fixed, start, end)` with `fixed` the row of a horizontal
+/// run or the column of a vertical one.
+type Run = (usize, usize, usize);
+
+/// Writes each edge's label onto `text` and returns the cells every label took, in the order of
+/// `edges`: `(label, connector spans)` for every drawn edge, labeled or not.
+pub(super) fn place_edge_labels(
+    text: String,
+    edges: &[(Option<&str>, &[LineSpan])],
+) -> (String, Vec<Vec<LineSpan>>) {
+    let mut placed = vec![Vec::new(); edges.len()];
+    if edges.iter().all(|(label, _)| label.map_or(true, |label| label.trim().is_empty())) {
+        return (text, placed);
+    }
+
+    let mut owners = BTreeMap::<(usize, usize), usize>::new();
+    for (_, spans) in edges {
+        let cells = spans
+            .iter()
+            .flat_map(|&(y, x0, x1)| (x0..=x1).map(move |x| (y, x)))
+            .collect::<BTreeSet<_>>();
+        for cell in cells {
+            *owners.entry(cell).or_default() += 1;
+        }
+    }
+
+    let mut grid =
+        text.split('\n').map(|line| line.chars().collect::<Vec<_>>()).collect::<Vec<_>>();
+    for ((label, spans), placed) in edges.iter().zip(&mut placed) {
+        let Some(label) = label.map(str::trim).filter(|label| !label.is_empty()) else {
+            continue;
+        };
+        let own = |y: usize, x: usize| owners.get(&(y, x)) == Some(&1);
+        let horizontal = longest_run(spans, |y, x| own(y, x) && is_horizontal(cell(&grid, y, x)));
+        let vertical =
+            longest_vertical_run(spans, |y, x| own(y, x) && is_vertical(cell(&grid, y, x)));
+
+        let len = text_len(label);
+        let span = match (horizontal, vertical) {
+            (Some((y, x0, x1)), _) if x1 - x0 + 1 >= len + 2 => {
+                Some(write(&mut grid, y, x0 + (x1 - x0 + 1 - len) / 2, label))
+            }
+            _ => None,
+        };
+        let span = span
+            .or_else(|| vertical.and_then(|run| write_beside(&mut grid, run, label)))
+            .or_else(|| horizontal.and_then(|run| write_across(&mut grid, run, label)))
+            .or_else(|| {
+                let (y, x0, x1) = horizontal?;
+                let room = (x1 - x0 + 1).checked_sub(2).filter(|&room| room >= 2)?;
+                let clipped = truncate_with_ellipsis(label, room);
+                let clipped_len = text_len(&clipped);
+                Some(write(&mut grid, y, x0 + (x1 - x0 + 1 - clipped_len) / 2, &clipped))
+            });
+        placed.extend(span);
+    }
+
+    let text = grid.into_iter().map(String::from_iter).collect::<Vec<_>>().join("\n");
+    (text, placed)
+}
+
+fn cell(grid: &[Vec<char>], y: usize, x: usize) -> char {
+    grid.get(y).and_then(|line| line.get(x)).copied().unwrap_or(' ')
+}
+
+fn is_horizontal(ch: char) -> bool {
+    matches!(ch, UNICODE_BOX_HORIZONTAL | UNICODE_DOTTED_HORIZONTAL | UNICODE_THICK_HORIZONTAL)
+}
+
+fn is_vertical(ch: char) -> bool {
+    matches!(ch, UNICODE_BOX_VERTICAL | UNICODE_DOTTED_VERTICAL | UNICODE_THICK_VERTICAL)
+}
+
+/// Longest horizontal run of span cells passing `keep`, the topmost-leftmost one on ties.
+fn longest_run(spans: &[LineSpan], keep: impl Fn(usize, usize) -> bool) -> Option<Run> {
+    let cells = spans
+        .iter()
+        .flat_map(|&(y, x0, x1)| (x0..=x1).map(move |x| (y, x)))
+        .filter(|&(y, x)| keep(y, x))
+        .collect::<BTreeSet<_>>();
+    longest(cells)
+}
+
+/// Longest vertical run of span cells passing `keep`, as `(x, y0, y1)`.
+fn longest_vertical_run(spans: &[LineSpan], keep: impl Fn(usize, usize) -> bool) -> Option<Run> {
+    let cells = spans
+        .iter()
+        .flat_map(|&(y, x0, x1)| (x0..=x1).map(move |x| (y, x)))
+        .filter(|&(y, x)| keep(y, x))
+        .map(|(y, x)| (x, y))
+        .collect::<BTreeSet<_>>();
+    longest(cells)
+}
+
+/// Longest run of consecutive `(fixed, along)` cells, which arrive sorted.
+fn longest(cells: BTreeSet<(usize, usize)>) -> Option<Run> {
+    let mut best = None::<Run>;
+    let mut current = None::<Run>;
+    for (fixed, along) in cells {
+        current = match current {
+            Some((run_fixed, start, end)) if run_fixed == fixed && end + 1 == along => {
+                Some((fixed, start, along))
+            }
+            _ => Some((fixed, along, along)),
+        };
+        let (_, start, end) = current.expect("just set");
+        if best.map_or(true, |(_, best_start, best_end)| end - start > best_end - best_start) {
+            best = current;
+        }
+    }
+    best
+}
+
+/// Writes `label` next to the vertical run `(x, y0, y1)`, on the row closest to its middle where
+/// the label and one cell after it are blank, trying the right side before the left.
+fn write_beside(grid: &mut Vec<Vec<char>>, (x, y0, y1): Run, label: &str) -> Option<LineSpan> {
+    let len = text_len(label);
+    let middle = y0 + (y1 - y0) / 2;
+    let mut rows = (y0..=y1).collect::<Vec<_>>();
+    rows.sort_by_key(|&y| y.abs_diff(middle));
+    let blank = |grid: &[Vec<char>], y: usize, xs: std::ops::RangeInclusive<usize>| {
+        xs.into_iter().all(|x| cell(grid, y, x) == ' ')
+    };
+    for y in rows {
+        if blank(grid, y, x + 1..=x + len + 1) {
+            return Some(write(grid, y, x + 1, label));
+        }
+        if let Some(start) = x.checked_sub(len) {
+            if blank(grid, y, start.saturating_sub(1)..=x - 1) {
+                return Some(write(grid, y, start, label));
+            }
+        }
+    }
+    None
+}
+
+/// Writes `label` centered on the horizontal run `(y, x0, x1)` in the row below it, or else the
+/// row above, where the label and one cell on either side are blank.
+fn write_across(grid: &mut Vec<Vec<char>>, (y, x0, x1): Run, label: &str) -> Option<LineSpan> {
+    let len = text_len(label);
+    let x = (x0 + x1 + 1).checked_sub(len)? / 2;
+    let rows = [Some(y + 1), y.checked_sub(1)];
+    let row = rows.into_iter().flatten().find(|&row| {
+        (x.saturating_sub(1)..=x + len).all(|cell_x| cell(grid, row, cell_x) == ' ')
+    })?;
+    Some(write(grid, row, x, label))
+}
+
+/// Writes `label` from `(x, y)` onward, padding the line with blanks as needed.
+fn write(grid: &mut Vec<Vec<char>>, y: usize, x: usize, label: &str) -> LineSpan {
+    if grid.len() <= y {
+        grid.resize(y + 1, Vec::new());
+    }
+    let line = &mut grid[y];
+    let len = text_len(label);
+    if line.len() < x + len {
+        line.resize(x + len, ' ');
+    }
+    for (offset, ch) in label.chars().enumerate() {
+        line[x + offset] = ch;
+    }
+    (y, x, x + len - 1)
+}
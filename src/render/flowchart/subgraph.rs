@@ -0,0 +1,245 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Labeled frames around flowchart subgraphs.
+//!
+//! A frame outlines the union of one rectangle per layer (the member boxes plus a one-cell
+//! margin), joined across the gaps between neighbouring member layers: across the column gap
+//! where their rows overlap, or across the row gap of vertical layouts where their columns do.
+//! The layout keeps subgraph members contiguous within a layer, so a layer rectangle never covers
+//! a foreign node; joins that would are left out, as are joins between layers without shared
+//! rows, so such subgraphs are drawn as several pieces. Frames are overlaid after everything else
+//! and only on blank cells, so boxes, connectors and labels always stay intact. Nested subgraphs
+//! share border cells with their parent where their members line up.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::model::flow_ast::FlowchartAst;
+use crate::model::ids::ObjectId;
+
+use super::super::text::{text_len, truncate_with_ellipsis};
+use super::super::{
+    cells_to_spans, LineSpan, UNICODE_BOX_CROSS, UNICODE_BOX_TEE_DOWN, UNICODE_BOX_TEE_LEFT,
+    UNICODE_BOX_TEE_RIGHT, UNICODE_BOX_TEE_UP, UNICODE_DOTTED_HORIZONTAL, UNICODE_DOTTED_VERTICAL,
+};
+use super::NodeRender;
+
+const FRAME_TOP_LEFT: char = '╭';
+const FRAME_TOP_RIGHT: char = '╮';
+const FRAME_BOTTOM_LEFT: char = '╰';
+const FRAME_BOTTOM_RIGHT: char = '╯';
+
+type Cell = (i64, i64);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SubgraphFrame {
+    subgraph_id: ObjectId,
+    label: String,
+    region: BTreeSet<Cell>,
+}
+
+/// Frames of all subgraphs with members, in plan coordinates (which may start at `-1`).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(super) struct SubgraphFrames {
+    frames: Vec<SubgraphFrame>,
+}
+
+impl SubgraphFrames {
+    pub(super) fn plan(ast: &FlowchartAst, node_renders: &BTreeMap<ObjectId, NodeRender>) -> Self {
+        let mut frames = Vec::new();
+        for (subgraph_id, subgraph) in ast.subgraphs() {
+            let members = ast.subgraph_members(subgraph_id);
+            let region = frame_region(&members, node_renders);
+            if !region.is_empty() {
+                frames.push(SubgraphFrame {
+                    subgraph_id: subgraph_id.clone(),
+                    label: subgraph.label().to_owned(),
+                    region,
+                });
+            }
+        }
+        Self { frames }
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Columns/rows prepended to the text so frames around the first layer/row fit.
+    pub(super) fn offset(&self) -> (usize, usize) {
+        let cells = self.frames.iter().flat_map(|frame| frame.region.iter());
+        let (min_x, min_y) =
+            cells.fold((0i64, 0i64), |(min_x, min_y), &(x, y)| (min_x.min(x), min_y.min(y)));
+        (min_x.unsigned_abs() as usize, min_y.unsigned_abs() as usize)
+    }
+
+    /// Shifts `text` by [`SubgraphFrames::offset`] and draws every frame with its label.
+    ///
+    /// Returns the new text plus the cells covered by each frame, as highlight spans.
+    pub(super) fn overlay(&self, text: &str) -> (String, Vec<(ObjectId, Vec<LineSpan>)>) {
+        let (ox, oy) = self.offset();
+        let mut grid = vec![Vec::<char>::new(); oy];
+        grid.extend(text.split('\n').map(|line| {
+            let mut row = vec![' '; ox];
+            row.extend(line.chars());
+            row
+        }));
+
+        let mut spans_by_frame = Vec::with_capacity(self.frames.len());
+        for frame in &self.frames {
+            let mut drawn = BTreeSet::<(usize, usize)>::new();
+            let shift = |(x, y): Cell| ((x + ox as i64) as usize, (y + oy as i64) as usize);
+            for &cell in &frame.region {
+                let Some(glyph) = border_glyph(&frame.region, cell) else {
+                    continue;
+                };
+                let (x, y) = shift(cell);
+                if set_if_blank(&mut grid, x, y, glyph) {
+                    drawn.insert((y, x));
+                }
+            }
+
+            // Label on the top border, right after the top-left corner. Only this frame's own
+            // border cells are overwritten, so the label stops at the first foreign glyph.
+            if let Some(&(x0, y0)) = frame.region.iter().min_by_key(|&&(x, y)| (y, x)) {
+                let run = (x0..).take_while(|&x| frame.region.contains(&(x, y0))).count();
+                let label = truncate_with_ellipsis(&frame.label, run.saturating_sub(4));
+                if text_len(&label) > 0 {
+                    let (lx, ly) = shift((x0 + 1, y0));
+                    for (idx, ch) in format!(" {label} ").chars().enumerate() {
+                        if !drawn.contains(&(ly, lx + idx)) {
+                            break;
+                        }
+                        grid[ly][lx + idx] = ch;
+                    }
+                }
+            }
+
+            spans_by_frame.push((frame.subgraph_id.clone(), cells_to_spans(&drawn)));
+        }
+
+        let text = grid
+            .into_iter()
+            .map(|row| String::from_iter(row).trim_end_matches(' ').to_owned())
+            .collect::<Vec<_>>()
+            .join("\n");
+        (text, spans_by_frame)
+    }
+}
+
+/// Union of per-layer member rectangles (with margin) plus joins between neighbouring layers
+/// that share rows (side by side) or columns (stacked).
+fn frame_region(
+    members: &BTreeSet<ObjectId>,
+    node_renders: &BTreeMap<ObjectId, NodeRender>,
+) -> BTreeSet<Cell> {
+    // Per layer: (x0, y0, x1, y1) hull of the member boxes.
+    let mut blocks = BTreeMap::<usize, (i64, i64, i64, i64)>::new();
+    for render in members.iter().filter_map(|node_id| node_renders.get(node_id)) {
+        let (x0, y0, x1, y1) = (
+            render.box_x0 as i64,
+            render.box_y0 as i64,
+            render.box_x1 as i64,
+            render.box_y1 as i64,
+        );
+        blocks
+            .entry(render.layer)
+            .and_modify(|block| {
+                *block = (block.0.min(x0), block.1.min(y0), block.2.max(x1), block.3.max(y1))
+            })
+            .or_insert((x0, y0, x1, y1));
+    }
+
+    let foreign = node_renders
+        .iter()
+        .filter(|(node_id, _)| !members.contains(*node_id))
+        .map(|(_, render)| {
+            (render.box_x0 as i64, render.box_y0 as i64, render.box_x1 as i64, render.box_y1 as i64)
+        })
+        .collect::<Vec<_>>();
+    let hits_foreign = |(x0, y0, x1, y1): (i64, i64, i64, i64)| {
+        foreign.iter().any(|&(fx0, fy0, fx1, fy1)| x0 <= fx1 && fx0 <= x1 && y0 <= fy1 && fy0 <= y1)
+    };
+
+    let mut region = BTreeSet::new();
+    let mut fill = |(x0, y0, x1, y1): (i64, i64, i64, i64)| {
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                region.insert((x, y));
+            }
+        }
+    };
+    let blocks = blocks.into_values().collect::<Vec<_>>();
+    for &(x0, y0, x1, y1) in &blocks {
+        fill((x0 - 1, y0 - 1, x1 + 1, y1 + 1));
+    }
+    for pair in blocks.windows(2) {
+        let ((ax0, ay0, ax1, ay1), (bx0, by0, bx1, by1)) = (pair[0], pair[1]);
+        let join = (ax1 + 2, ay0.max(by0) - 1, bx0 - 2, ay1.min(by1) + 1);
+        if join.0 <= join.2 && ay0 <= by1 && by0 <= ay1 && !hits_foreign(join) {
+            fill(join);
+        }
+        let join = (ax0.max(bx0) - 1, ay1 + 2, ax1.min(bx1) + 1, by0 - 2);
+        if join.1 <= join.3 && ax0 <= bx1 && bx0 <= ax1 && !hits_foreign(join) {
+            fill(join);
+        }
+    }
+    region
+}
+
+/// Glyph for `cell` if it lies on the outline of `region`.
+fn border_glyph(region: &BTreeSet<Cell>, (x, y): Cell) -> Option<char> {
+    let inside = |dx: i64, dy: i64| region.contains(&(x + dx, y + dy));
+    let on_border = |cx: i64, cy: i64| {
+        region.contains(&(cx, cy))
+            && (-1..=1).any(|dy| (-1..=1).any(|dx| !region.contains(&(cx + dx, cy + dy))))
+    };
+    if !on_border(x, y) {
+        return None;
+    }
+
+    // Two border cells are linked when the outline runs between them, i.e. a cell beside the
+    // pair is outside the region.
+    let up =
+        on_border(x, y - 1) && !(inside(-1, 0) && inside(-1, -1) && inside(1, 0) && inside(1, -1));
+    let down =
+        on_border(x, y + 1) && !(inside(-1, 0) && inside(-1, 1) && inside(1, 0) && inside(1, 1));
+    let left =
+        on_border(x - 1, y) && !(inside(0, -1) && inside(-1, -1) && inside(0, 1) && inside(-1, 1));
+    let right =
+        on_border(x + 1, y) && !(inside(0, -1) && inside(1, -1) && inside(0, 1) && inside(1, 1));
+
+    Some(match (up, down, left, right) {
+        (false, true, false, true) => FRAME_TOP_LEFT,
+        (false, true, true, false) => FRAME_TOP_RIGHT,
+        (true, false, false, true) => FRAME_BOTTOM_LEFT,
+        (true, false, true, false) => FRAME_BOTTOM_RIGHT,
+        (true, true, false, true) => UNICODE_BOX_TEE_RIGHT,
+        (true, true, true, false) => UNICODE_BOX_TEE_LEFT,
+        (false, true, true, true) => UNICODE_BOX_TEE_DOWN,
+        (true, false, true, true) => UNICODE_BOX_TEE_UP,
+        (true, true, true, true) => UNICODE_BOX_CROSS,
+        (_, _, true, _) | (_, _, _, true) => UNICODE_DOTTED_HORIZONTAL,
+        _ => UNICODE_DOTTED_VERTICAL,
+    })
+}
+
+fn set_if_blank(grid: &mut Vec<Vec<char>>, x: usize, y: usize, ch: char) -> bool {
+    if grid.len() <= y {
+        grid.resize(y + 1, Vec::new());
+    }
+    let row = &mut grid[y];
+    if row.len() <= x {
+        row.resize(x + 1, ' ');
+    }
+    if row[x] != ' ' {
+        return false;
+    }
+    row[x] = ch;
+    true
+}
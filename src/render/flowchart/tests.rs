@@ -16,7 +16,7 @@ use crate::layout::layout_flowchart;
 use crate::model::flow_ast::{FlowEdge, FlowNode, FlowchartAst};
 use crate::model::ids::ObjectId;
 use crate::model::{DiagramId, ObjectRef};
-use crate::render::{HighlightIndex, RenderOptions};
+use crate::render::{FlowchartEdgeGlyphs, FlowchartNodeScale, HighlightIndex, RenderOptions};
 use std::collections::{BTreeMap, BTreeSet};
 
 const DETERMINISM_REPEAT_RUNS: usize = 100;
@@ -288,6 +288,24 @@ fn snapshot_small_dag() {
         );
 }
 
+#[test]
+fn entry_nodes_get_a_marker_in_the_top_left_corner() {
+    let mut ast = crate::model::fixtures::flowchart_small_dag();
+    let entry_id = ast
+        .nodes()
+        .iter()
+        .find(|(_, node)| node.label() == "B")
+        .map(|(node_id, _)| node_id.clone())
+        .expect("node B");
+    ast.entry_nodes_mut().insert(entry_id);
+
+    let layout = layout_flowchart(&ast).expect("layout");
+    let rendered = render_flowchart_unicode(&ast, &layout).expect("render");
+
+    assert!(rendered.starts_with("┌───┐   ▸───┐   ┌───┐\n"), "unexpected render:\n{rendered}");
+    assert_eq!(rendered.matches('▸').count(), 1);
+}
+
 #[test]
 fn snapshot_single_node_notes_toggle() {
     let mut ast = FlowchartAst::default();
@@ -304,6 +322,7 @@ fn snapshot_single_node_notes_toggle() {
             show_notes: false,
             prefix_object_labels: false,
             flowchart_extra_col_gap: 0,
+            ..RenderOptions::default()
         },
     )
     .expect("render");
@@ -312,7 +331,12 @@ fn snapshot_single_node_notes_toggle() {
     let notes_on = render_flowchart_unicode_with_options(
         &ast,
         &layout,
-        RenderOptions { show_notes: true, prefix_object_labels: false, flowchart_extra_col_gap: 0 },
+        RenderOptions {
+            show_notes: true,
+            prefix_object_labels: false,
+            flowchart_extra_col_gap: 0,
+            ..RenderOptions::default()
+        },
     )
     .expect("render");
     assert_eq!(notes_on, "┌───────┐\n│ Node  │\n│ note  │\n└───────┘");
@@ -358,6 +382,7 @@ fn renders_flow_arrowheads_on_both_endpoints_when_connector_requests_both() {
             show_notes: false,
             prefix_object_labels: false,
             flowchart_extra_col_gap: 3,
+            ..RenderOptions::default()
         },
     )
     .expect("render");
@@ -366,6 +391,44 @@ fn renders_flow_arrowheads_on_both_endpoints_when_connector_requests_both() {
     assert!(rendered.contains('▶'), "expected end arrowhead for `<-->` connector:\n{rendered}");
 }
 
+#[test]
+fn renders_dotted_thick_and_open_connectors_with_distinct_glyphs() {
+    use crate::format::mermaid::parse_flowchart;
+
+    let ast =
+        parse_flowchart("flowchart LR\nA -.-> B\nC ==> D\nE --- F\nG --> H\n").expect("parse");
+    let layout = layout_flowchart(&ast).expect("layout");
+    let diagram_id = DiagramId::new("d").expect("diagram id");
+    let options = RenderOptions { flowchart_extra_col_gap: 3, ..RenderOptions::default() };
+    let annotated =
+        render_flowchart_unicode_annotated_with_options(&diagram_id, &ast, &layout, options)
+            .expect("render");
+    let lines =
+        annotated.text.split('\n').map(|l| l.chars().collect::<Vec<_>>()).collect::<Vec<_>>();
+    let edge_chars = |from: &str| {
+        let (edge_id, _) = ast
+            .edges()
+            .iter()
+            .find(|(_, edge)| edge.from_node_id().as_str() == from)
+            .expect("edge");
+        let object_ref: ObjectRef =
+            format!("d:d/flow/edge/{}", edge_id.as_str()).parse().expect("edge ref");
+        annotated.highlight_index[&object_ref]
+            .iter()
+            .flat_map(|&(y, x0, x1)| lines[y][x0..=x1].to_vec())
+            .collect::<String>()
+    };
+
+    let dotted = edge_chars("n:A");
+    assert!(dotted.contains('┄') && !dotted.contains('─'), "dotted: {dotted}");
+    let thick = edge_chars("n:C");
+    assert!(thick.contains('━') && !thick.contains('─'), "thick: {thick}");
+    let open = edge_chars("n:E");
+    assert!(open.contains('─') && !open.contains('▶'), "open: {open}");
+    let solid = edge_chars("n:G");
+    assert!(solid.contains('─') && solid.contains('▶'), "solid: {solid}");
+}
+
 #[test]
 fn renders_distinct_mixed_endpoint_caps_for_multiple_incoming_edges_to_same_node() {
     use crate::format::mermaid::parse_flowchart;
@@ -385,6 +448,7 @@ fn renders_distinct_mixed_endpoint_caps_for_multiple_incoming_edges_to_same_node
             show_notes: false,
             prefix_object_labels: false,
             flowchart_extra_col_gap: 3,
+            ..RenderOptions::default()
         },
     )
     .expect("render");
@@ -412,7 +476,12 @@ fn demo_arc_arrow_caps_keep_visible_connector_tail_cells() {
     let rendered = render_flowchart_unicode_with_options(
         &ast,
         &layout,
-        RenderOptions { show_notes: true, prefix_object_labels: false, flowchart_extra_col_gap: 0 },
+        RenderOptions {
+            show_notes: true,
+            prefix_object_labels: false,
+            flowchart_extra_col_gap: 0,
+            ..RenderOptions::default()
+        },
     )
     .expect("render");
 
@@ -463,7 +532,12 @@ fn demo_arc_harpoon_keeps_min_three_columns_between_adjacent_nodes_with_tui_gap(
     let plan = super::FlowchartRenderPlan::build(
         &ast,
         &layout,
-        RenderOptions { show_notes: true, prefix_object_labels: false, flowchart_extra_col_gap: 2 },
+        RenderOptions {
+            show_notes: true,
+            prefix_object_labels: false,
+            flowchart_extra_col_gap: 2,
+            ..RenderOptions::default()
+        },
     )
     .expect("plan");
 
@@ -506,13 +580,23 @@ fn demo_arc_edge_0013_arrow_keeps_corner_tail_cell() {
         &diagram_id,
         &ast,
         &layout,
-        RenderOptions { show_notes: true, prefix_object_labels: false, flowchart_extra_col_gap: 2 },
+        RenderOptions {
+            show_notes: true,
+            prefix_object_labels: false,
+            flowchart_extra_col_gap: 2,
+            ..RenderOptions::default()
+        },
     )
     .expect("render");
     let plan = super::FlowchartRenderPlan::build(
         &ast,
         &layout,
-        RenderOptions { show_notes: true, prefix_object_labels: false, flowchart_extra_col_gap: 2 },
+        RenderOptions {
+            show_notes: true,
+            prefix_object_labels: false,
+            flowchart_extra_col_gap: 2,
+            ..RenderOptions::default()
+        },
     )
     .expect("plan");
     let edge_idx = ast
@@ -558,13 +642,23 @@ fn demo_arc_edge_0014_arrow_keeps_tee_tail_cell() {
         &diagram_id,
         &ast,
         &layout,
-        RenderOptions { show_notes: true, prefix_object_labels: false, flowchart_extra_col_gap: 2 },
+        RenderOptions {
+            show_notes: true,
+            prefix_object_labels: false,
+            flowchart_extra_col_gap: 2,
+            ..RenderOptions::default()
+        },
     )
     .expect("render");
     let plan = super::FlowchartRenderPlan::build(
         &ast,
         &layout,
-        RenderOptions { show_notes: true, prefix_object_labels: false, flowchart_extra_col_gap: 2 },
+        RenderOptions {
+            show_notes: true,
+            prefix_object_labels: false,
+            flowchart_extra_col_gap: 2,
+            ..RenderOptions::default()
+        },
     )
     .expect("plan");
     let e0004_idx = ast
@@ -782,6 +876,7 @@ fn demo_motifs_lions_sleep_keeps_one_cell_clearance_with_tui_gap() {
             show_notes: false,
             prefix_object_labels: false,
             flowchart_extra_col_gap: 2,
+            ..RenderOptions::default()
         },
     )
     .expect("render");
@@ -829,6 +924,7 @@ fn demo_luck_salao_resolve_keeps_clearance_from_parents_separation_with_tui_gap(
             show_notes: false,
             prefix_object_labels: false,
             flowchart_extra_col_gap: 2,
+            ..RenderOptions::default()
         },
     )
     .expect("render");
@@ -873,6 +969,7 @@ fn demo_routing_selected_edges_keep_clearance_with_tui_gap() {
             show_notes: false,
             prefix_object_labels: false,
             flowchart_extra_col_gap: 2,
+            ..RenderOptions::default()
         },
     )
     .expect("render");
@@ -915,7 +1012,12 @@ fn demo_routing_edge_0015_does_not_touch_metrics_node_box_with_tui_gap() {
         &diagram_id,
         &ast,
         &layout,
-        RenderOptions { show_notes: true, prefix_object_labels: false, flowchart_extra_col_gap: 2 },
+        RenderOptions {
+            show_notes: true,
+            prefix_object_labels: false,
+            flowchart_extra_col_gap: 2,
+            ..RenderOptions::default()
+        },
     )
     .expect("render");
 
@@ -941,8 +1043,12 @@ fn demo_luck_edge_0009_avoids_node_interiors_with_tui_gap_and_notes() {
     let ast = parse_flowchart(&input).expect("parse luck fixture");
     let layout = layout_flowchart(&ast).expect("layout");
 
-    let options =
-        RenderOptions { show_notes: true, prefix_object_labels: false, flowchart_extra_col_gap: 2 };
+    let options = RenderOptions {
+        show_notes: true,
+        prefix_object_labels: false,
+        flowchart_extra_col_gap: 2,
+        ..RenderOptions::default()
+    };
     let plan = super::FlowchartRenderPlan::build(&ast, &layout, options).expect("plan");
     let interior_cells = node_interior_cells(&plan.node_renders);
 
@@ -989,8 +1095,12 @@ fn demo_luck_edge_0009_avoids_unrelated_node_boxes_with_tui_gap_and_notes() {
     let ast = parse_flowchart(&input).expect("parse luck fixture");
     let layout = layout_flowchart(&ast).expect("layout");
 
-    let options =
-        RenderOptions { show_notes: true, prefix_object_labels: false, flowchart_extra_col_gap: 2 };
+    let options = RenderOptions {
+        show_notes: true,
+        prefix_object_labels: false,
+        flowchart_extra_col_gap: 2,
+        ..RenderOptions::default()
+    };
     let plan = super::FlowchartRenderPlan::build(&ast, &layout, options).expect("plan");
 
     let edge_id = oid("e:0009");
@@ -1040,8 +1150,12 @@ fn demo_shark_types_edges_avoid_unrelated_node_boxes_with_tui_gap_and_notes() {
     let ast = parse_flowchart(&input).expect("parse shark types fixture");
     let layout = layout_flowchart(&ast).expect("layout");
 
-    let options =
-        RenderOptions { show_notes: true, prefix_object_labels: false, flowchart_extra_col_gap: 2 };
+    let options = RenderOptions {
+        show_notes: true,
+        prefix_object_labels: false,
+        flowchart_extra_col_gap: 2,
+        ..RenderOptions::default()
+    };
     let plan = super::FlowchartRenderPlan::build(&ast, &layout, options).expect("plan");
 
     for (edge_idx, (edge_id, edge)) in ast.edges().iter().enumerate() {
@@ -1085,8 +1199,12 @@ fn demo_shark_types_edges_do_not_cross_any_node_label_rows_with_tui_gap_and_note
     let ast = parse_flowchart(&input).expect("parse shark types fixture");
     let layout = layout_flowchart(&ast).expect("layout");
 
-    let options =
-        RenderOptions { show_notes: true, prefix_object_labels: false, flowchart_extra_col_gap: 2 };
+    let options = RenderOptions {
+        show_notes: true,
+        prefix_object_labels: false,
+        flowchart_extra_col_gap: 2,
+        ..RenderOptions::default()
+    };
     let plan = super::FlowchartRenderPlan::build(&ast, &layout, options).expect("plan");
     let label_cells_by_node = node_label_row_cells(&plan.node_renders);
 
@@ -1133,7 +1251,12 @@ fn demo_shark_types_connectors_do_not_enter_any_node_interiors_with_tui_gap_and_
 
     assert_flowchart_connectors_do_not_enter_node_interiors_with_options(
         &ast,
-        RenderOptions { show_notes: true, prefix_object_labels: false, flowchart_extra_col_gap: 2 },
+        RenderOptions {
+            show_notes: true,
+            prefix_object_labels: false,
+            flowchart_extra_col_gap: 2,
+            ..RenderOptions::default()
+        },
     );
 }
 
@@ -1159,6 +1282,7 @@ fn demo_gear_mast_skiff_keeps_clearance_from_tools_club_with_tui_gap() {
             show_notes: false,
             prefix_object_labels: false,
             flowchart_extra_col_gap: 2,
+            ..RenderOptions::default()
         },
     )
     .expect("render");
@@ -1193,6 +1317,7 @@ fn demo_gear_edge_0014_vertical_lane_keeps_one_column_clearance_from_gap_boundar
             show_notes: false,
             prefix_object_labels: false,
             flowchart_extra_col_gap: 2,
+            ..RenderOptions::default()
         },
     )
     .expect("plan");
@@ -1377,8 +1502,12 @@ fn demo_om01_cast_tui_gap_preserves_terrace_label_and_avoids_node_interior_cross
     let ast = parse_flowchart(&input).expect("parse cast fixture");
 
     let layout = layout_flowchart(&ast).expect("layout");
-    let options =
-        RenderOptions { show_notes: true, prefix_object_labels: false, flowchart_extra_col_gap: 2 };
+    let options = RenderOptions {
+        show_notes: true,
+        prefix_object_labels: false,
+        flowchart_extra_col_gap: 2,
+        ..RenderOptions::default()
+    };
 
     assert_flowchart_connectors_do_not_enter_node_interiors_with_options(&ast, options);
 
@@ -1404,7 +1533,12 @@ fn demo_om01_cast_edge_0010_stays_in_single_gap_between_adjacent_layers() {
     let plan = super::FlowchartRenderPlan::build(
         &ast,
         &layout,
-        RenderOptions { show_notes: true, prefix_object_labels: false, flowchart_extra_col_gap: 2 },
+        RenderOptions {
+            show_notes: true,
+            prefix_object_labels: false,
+            flowchart_extra_col_gap: 2,
+            ..RenderOptions::default()
+        },
     )
     .expect("plan");
 
@@ -1444,7 +1578,12 @@ fn demo_om02_gear_edge_0007_stays_in_single_gap_between_adjacent_layers() {
     let plan = super::FlowchartRenderPlan::build(
         &ast,
         &layout,
-        RenderOptions { show_notes: true, prefix_object_labels: false, flowchart_extra_col_gap: 2 },
+        RenderOptions {
+            show_notes: true,
+            prefix_object_labels: false,
+            flowchart_extra_col_gap: 2,
+            ..RenderOptions::default()
+        },
     )
     .expect("plan");
 
@@ -1568,3 +1707,375 @@ fn demo_routing_fixture_reports_expected_routing_quality_diagnostics() {
     assert_eq!(baseline_diagnostics.overlap_proxy_count, 5);
     assert_eq!(baseline_diagnostics.min_clearance_violation_count, 12);
 }
+
+#[test]
+fn compaction_shrinks_demo_flowcharts_and_keeps_labels_and_spans() {
+    use crate::format::mermaid::parse_flowchart;
+
+    let root = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("data")
+        .join("demo-session")
+        .join("diagrams");
+    let mut saved_area = 0usize;
+    for name in ["demo-flow", "demo-t-flow-routing", "om-03-arc", "om-20-motifs"] {
+        let input = std::fs::read_to_string(root.join(format!("{name}.mmd"))).expect("read");
+        let ast = parse_flowchart(&input).expect("parse");
+        let layout = layout_flowchart(&ast).expect("layout");
+        let diagram_id = DiagramId::new(name).expect("diagram id");
+        let base_options = RenderOptions { show_notes: true, ..RenderOptions::default() };
+        let compact_options = RenderOptions { flowchart_compact: true, ..base_options };
+
+        let base =
+            render_flowchart_unicode_with_options(&ast, &layout, base_options).expect("render");
+        let compact = render_flowchart_unicode_annotated_with_options(
+            &diagram_id,
+            &ast,
+            &layout,
+            compact_options,
+        )
+        .expect("render compact");
+
+        let dims = |text: &str| {
+            let width = text.split('\n').map(|line| line.chars().count()).max().unwrap_or(0);
+            (width, text.split('\n').count())
+        };
+        let (base_w, base_h) = dims(&base);
+        let (compact_w, compact_h) = dims(&compact.text);
+        assert!(compact_w <= base_w && compact_h <= base_h, "{name} grew");
+        saved_area += base_w * base_h - compact_w * compact_h;
+
+        for node in ast.nodes().values() {
+            assert!(compact.text.contains(node.label()), "{name} lost label {}", node.label());
+        }
+        assert_highlight_spans_in_bounds(name, &compact.text, &compact.highlight_index);
+        assert_eq!(
+            render_flowchart_unicode_with_options(&ast, &layout, compact_options).expect("text"),
+            compact.text
+        );
+    }
+    assert!(saved_area > 0, "compaction should shrink at least one demo flowchart");
+}
+
+#[test]
+fn renders_self_loop_as_channel_below_node() {
+    use crate::format::mermaid::parse_flowchart;
+
+    let ast = parse_flowchart("flowchart LR\nA --> A\nA --> B\n").expect("parse");
+    let layout = layout_flowchart(&ast).expect("layout");
+    let diagram_id = DiagramId::new("d").expect("diagram id");
+    let annotated = render_flowchart_unicode_annotated(&diagram_id, &ast, &layout).expect("render");
+
+    assert_eq!(annotated.text, "┌───┐ ┌───┐\n│ A ├▶│ B │\n└┬─▲┘ └───┘\n └─┘");
+    let self_loop: ObjectRef = "d:d/flow/edge/e:0001".parse().expect("edge ref");
+    assert_eq!(annotated.highlight_index[&self_loop], vec![(2, 1, 1), (2, 3, 3), (3, 1, 3)]);
+}
+
+#[test]
+fn renders_parallel_edges_with_separate_connectors_and_spans() {
+    use crate::format::mermaid::parse_flowchart;
+
+    let ast = parse_flowchart("flowchart LR\nA --> B\nA -.-> B\n").expect("parse");
+    let layout = layout_flowchart(&ast).expect("layout");
+    let diagram_id = DiagramId::new("d").expect("diagram id");
+    let annotated = render_flowchart_unicode_annotated(&diagram_id, &ast, &layout).expect("render");
+
+    assert_eq!(annotated.text, "┌───┐ ┌───┐\n│ A ├▶│ B │\n└──┬┘ └▲──┘\n   └┄┄┄┘");
+    let first: ObjectRef = "d:d/flow/edge/e:0001".parse().expect("edge ref");
+    let second: ObjectRef = "d:d/flow/edge/e:0002".parse().expect("edge ref");
+    let first_cells = spans_to_cells(&annotated.highlight_index[&first]);
+    let second_cells = spans_to_cells(&annotated.highlight_index[&second]);
+    assert!(!first_cells.is_empty() && !second_cells.is_empty());
+    assert!(first_cells.is_disjoint(&second_cells), "parallel edges share highlight cells");
+}
+
+#[test]
+fn subgraphs_get_labeled_frames_and_highlight_spans() {
+    let ast = crate::format::mermaid::parse_flowchart(
+        "flowchart LR\nX[Client] --> A\nsubgraph api[Public API]\nA[Gateway] --> B[Auth]\nend\nB --> C[Store]\n",
+    )
+    .expect("parse");
+    let layout = layout_flowchart(&ast).expect("layout");
+    let diagram_id = DiagramId::new("d-flow").expect("diagram id");
+    let rendered = render_flowchart_unicode_annotated(&diagram_id, &ast, &layout).expect("render");
+
+    assert!(rendered.text.contains("╭ Public API ┄"), "{}", rendered.text);
+    assert_eq!(rendered.text, render_flowchart_unicode(&ast, &layout).expect("plain render"));
+    assert_highlight_spans_in_bounds("subgraph", &rendered.text, &rendered.highlight_index);
+
+    let subgraph_ref = ObjectRef::parse("d:d-flow/flow/subgraph/sg:api").expect("ref");
+    let frame_cells = spans_to_cells(&rendered.highlight_index[&subgraph_ref]);
+    let node_ref = ObjectRef::parse("d:d-flow/flow/node/n:A").expect("ref");
+    let node_cells = spans_to_cells(&rendered.highlight_index[&node_ref]);
+    assert!(frame_cells.is_disjoint(&node_cells));
+
+    // The frame encloses its members but not the client box.
+    let lines = rendered.text.split('\n').collect::<Vec<_>>();
+    let char_x = |y: usize, ch: char| lines[y].chars().position(|c| c == ch);
+    let client_x = char_x(2, 'C').expect("client");
+    let gateway_x = char_x(2, 'G').expect("gateway");
+    let frame_x = char_x(1, '┆').expect("frame side");
+    assert!(client_x < frame_x && frame_x < gateway_x, "{}", rendered.text);
+}
+
+#[test]
+fn renders_each_extra_parallel_edge_in_its_own_channel() {
+    use crate::format::mermaid::parse_flowchart;
+
+    let ast = parse_flowchart("flowchart LR\nA --> B\nA -.-> B\nA ==> B\n").expect("parse");
+    let layout = layout_flowchart(&ast).expect("layout");
+    let diagram_id = DiagramId::new("d").expect("diagram id");
+    let annotated = render_flowchart_unicode_annotated(&diagram_id, &ast, &layout).expect("render");
+
+    assert_eq!(annotated.text, "┌───┐ ┌───┐\n│ A ├▶│ B │\n└─┬┬┘ └▲▲─┘\n  ┃└┄┄┄┘┃\n  └━━━━━┘");
+    let cells = ["e:0001", "e:0002", "e:0003"]
+        .map(|edge_id| {
+            let edge: ObjectRef = format!("d:d/flow/edge/{edge_id}").parse().expect("edge ref");
+            spans_to_cells(&annotated.highlight_index[&edge])
+        })
+        .to_vec();
+    for (idx, a) in cells.iter().enumerate() {
+        for b in &cells[idx + 1..] {
+            assert!(a.is_disjoint(b), "parallel edges share highlight cells");
+        }
+    }
+}
+
+#[test]
+fn routes_cross_row_parallel_edges_around_boxes_and_connectors() {
+    use crate::format::mermaid::parse_flowchart;
+
+    let ast =
+        parse_flowchart("flowchart LR\nA --> C\nB --> D\nA --> D\nA -.-> D\n").expect("parse");
+    let layout = layout_flowchart(&ast).expect("layout");
+    let diagram_id = DiagramId::new("d").expect("diagram id");
+    let annotated = render_flowchart_unicode_annotated(&diagram_id, &ast, &layout).expect("render");
+
+    // The detour leaves below A, steps around B, crosses B's connector and enters D from below.
+    assert_eq!(
+        annotated.text,
+        [
+            "┌───┐          ┌───┐",
+            "│ A ├──┬──────▶│ C │",
+            "└──┬┘  │       └───┘",
+            "   ┆   │",
+            "   └┄┄┐│",
+            "┌───┐ ┆│       ┌───┐",
+            "│ B ├─┼┴──────▶│ D │",
+            "└───┘ ┆        └▲──┘",
+            "      └┄┄┄┄┄┄┄┄┄┘",
+        ]
+        .join("\n")
+    );
+    let detour: ObjectRef = "d:d/flow/edge/e:0004".parse().expect("edge ref");
+    let detour_cells = spans_to_cells(&annotated.highlight_index[&detour]);
+    for node in ["n:A", "n:B", "n:C", "n:D"] {
+        let node: ObjectRef = format!("d:d/flow/node/{node}").parse().expect("node ref");
+        let node_cells = spans_to_cells(&annotated.highlight_index[&node]);
+        // Only the two border cells where the detour attaches are shared with boxes.
+        assert!(detour_cells.intersection(&node_cells).count() <= 1, "detour crosses {node}");
+    }
+}
+
+#[test]
+fn widens_boxes_to_fit_every_self_loop() {
+    use crate::format::mermaid::parse_flowchart;
+
+    let ast = parse_flowchart("flowchart LR\nA --> A\nA --> A\nA --> B\n").expect("parse");
+    let layout = layout_flowchart(&ast).expect("layout");
+    let diagram_id = DiagramId::new("d").expect("diagram id");
+    let annotated = render_flowchart_unicode_annotated(&diagram_id, &ast, &layout).expect("render");
+
+    assert_eq!(annotated.text, "┌───────┐ ┌───┐\n│   A   ├▶│ B │\n└┬─▲┬─▲─┘ └───┘\n └─┘└─┘");
+}
+
+#[test]
+fn vertical_directions_stack_layers_and_point_arrows_along_the_flow() {
+    use crate::format::mermaid::parse_flowchart;
+
+    let ast = parse_flowchart("flowchart TD\nA --> B\nA --> C\n").expect("parse");
+    let layout = layout_flowchart(&ast).expect("layout");
+    let diagram_id = DiagramId::new("d").expect("diagram id");
+    let annotated = render_flowchart_unicode_annotated(&diagram_id, &ast, &layout).expect("render");
+
+    assert_eq!(
+        annotated.text,
+        "    ┌───┐\n    │ A │\n    └┬─┬┘\n     │ │\n   ┌─┘ └─┐\n   ▼     ▼\n┌──┴┐   ┌┴──┐\n\
+         │ B │   │ C │\n└───┘   └───┘"
+    );
+    assert_eq!(annotated.text, render_flowchart_unicode(&ast, &layout).expect("plain render"));
+    assert_highlight_spans_in_bounds("td", &annotated.text, &annotated.highlight_index);
+    let edge_ref: ObjectRef = "d:d/flow/edge/e:0001".parse().expect("object ref");
+    let edge_text = collect_spanned_text(
+        &annotated.text,
+        annotated.highlight_index.get(&edge_ref).expect("edge spans"),
+    );
+    assert!(edge_text.contains('▼'), "{edge_text:?}");
+
+    let ast = parse_flowchart("flowchart BT\nA --> B\n").expect("parse");
+    let layout = layout_flowchart(&ast).expect("layout");
+    assert_eq!(
+        render_flowchart_unicode(&ast, &layout).expect("render"),
+        "┌───┐\n│ B │\n└─┬─┘\n  ▲\n  │\n  │\n┌─┴─┐\n│ A │\n└───┘"
+    );
+
+    let ast = parse_flowchart("flowchart RL\nA --> B\n").expect("parse");
+    let layout = layout_flowchart(&ast).expect("layout");
+    let rendered = render_flowchart_unicode(&ast, &layout).expect("render");
+    assert!(rendered.starts_with("┌───┐ ┌───┐\n│ B │◀┤ A │"), "{rendered}");
+}
+
+#[test]
+fn zoomed_out_node_scales_shrink_large_flowcharts_and_keep_nodes_selectable() {
+    let mut source = "flowchart LR\n".to_owned();
+    for layer in 0..6 {
+        for row in 0..8 {
+            source.push_str(&format!("L{layer}R{row}[Layer {layer} row {row}]\n"));
+            if layer > 0 {
+                source.push_str(&format!("L{}R{row} --> L{layer}R{row}\n", layer - 1));
+            }
+        }
+    }
+    let ast = crate::format::mermaid::parse_flowchart(&source).expect("parse");
+    let layout = layout_flowchart(&ast).expect("layout");
+    let diagram_id = DiagramId::new("d-large").expect("diagram id");
+    let render = |flowchart_node_scale| {
+        let options =
+            RenderOptions { flowchart_compact: true, flowchart_node_scale, ..Default::default() };
+        render_flowchart_unicode_annotated_with_options(&diagram_id, &ast, &layout, options)
+            .expect("render")
+    };
+    let size = |text: &str| {
+        let width = text.lines().map(|line| line.chars().count()).max().unwrap_or(0);
+        (width, text.lines().count())
+    };
+
+    let full = render(FlowchartNodeScale::Full);
+    let mini = render(FlowchartNodeScale::Mini);
+    let glyph = render(FlowchartNodeScale::Glyph);
+    let (full_width, full_height) = size(&full.text);
+    let (mini_width, mini_height) = size(&mini.text);
+    let (glyph_width, glyph_height) = size(&glyph.text);
+    assert!(mini_width * 3 < full_width * 2, "mini:\n{}", mini.text);
+    assert_eq!(mini_height, full_height);
+    assert!(mini.text.contains("Laye…"), "mini:\n{}", mini.text);
+    assert!(glyph_width < mini_width, "glyph:\n{}", glyph.text);
+    assert!(glyph_height <= full_height / 2, "glyph:\n{}", glyph.text);
+
+    assert_highlight_spans_in_bounds("d-large", &glyph.text, &glyph.highlight_index);
+    let lines = glyph.text.lines().map(|line| line.chars().collect::<Vec<_>>()).collect::<Vec<_>>();
+    for node_id in ast.nodes().keys() {
+        let object_ref = ObjectRef::new(
+            diagram_id.clone(),
+            crate::model::CategoryPath::new(vec!["flow".to_owned(), "node".to_owned()])
+                .expect("category"),
+            node_id.clone(),
+        );
+        let &[(y, x0, x1)] = glyph.highlight_index[&object_ref].as_slice() else {
+            panic!("{object_ref} should span its glyph only");
+        };
+        assert_eq!((x0, lines[y][x0]), (x1, 'L'), "glyph:\n{}", glyph.text);
+    }
+    assert!(!glyph.text.contains('┌') && !glyph.text.contains('┘'), "glyph:\n{}", glyph.text);
+}
+
+#[test]
+fn edge_labels_sit_mid_route_and_highlight_with_their_edge() {
+    use crate::format::mermaid::parse_flowchart;
+
+    let ast = parse_flowchart("flowchart LR\nA{Valid?} -->|yes| B[Save]\nA -->|no| C[Reject]\n")
+        .expect("parse");
+    let layout = layout_flowchart(&ast).expect("layout");
+    let diagram_id = DiagramId::new("d").expect("diagram id");
+    let annotated = render_flowchart_unicode_annotated(&diagram_id, &ast, &layout).expect("render");
+
+    assert!(annotated.text.contains("─yes─▶"), "{}", annotated.text);
+    assert!(annotated.text.contains("─no─"), "{}", annotated.text);
+    for (edge_id, label) in [("e:0001", "yes"), ("e:0002", "no")] {
+        let edge: ObjectRef = format!("d:d/flow/edge/{edge_id}").parse().expect("edge ref");
+        let spanned = collect_spanned_text(&annotated.text, &annotated.highlight_index[&edge]);
+        assert!(spanned.contains(label), "{edge_id} spans miss {label:?}: {spanned:?}");
+    }
+    assert_highlight_spans_in_bounds("edge-labels", &annotated.text, &annotated.highlight_index);
+}
+
+#[test]
+fn edge_labels_go_beside_vertical_connectors_and_below_detours() {
+    use crate::format::mermaid::parse_flowchart;
+
+    let ast = parse_flowchart("flowchart TD\nA -->|go| B\n").expect("parse");
+    let layout = layout_flowchart(&ast).expect("layout");
+    let rendered = render_flowchart_unicode(&ast, &layout).expect("render");
+    assert_eq!(rendered, "┌───┐\n│ A │\n└─┬─┘\n  │go\n  │\n  ▼\n┌─┴─┐\n│ B │\n└───┘");
+
+    let ast = parse_flowchart("flowchart LR\nA --> B\nA -.->|retry| B\n").expect("parse");
+    let layout = layout_flowchart(&ast).expect("layout");
+    let diagram_id = DiagramId::new("d").expect("diagram id");
+    let annotated = render_flowchart_unicode_annotated(&diagram_id, &ast, &layout).expect("render");
+    assert_eq!(annotated.text, "┌───┐ ┌───┐\n│ A ├▶│ B │\n└──┬┘ └▲──┘\n   └┄┄┄┘\n   retry");
+    let detour: ObjectRef = "d:d/flow/edge/e:0002".parse().expect("edge ref");
+    assert!(annotated.highlight_index[&detour].contains(&(4, 3, 7)));
+}
+
+#[test]
+fn braille_edge_glyphs_cut_corners_and_keep_highlights_on_drawn_cells() {
+    use crate::format::mermaid::parse_flowchart;
+
+    let ast = parse_flowchart("flowchart TD\na --> b\na --> c\nb --> d\nc --> d\n").expect("parse");
+    let layout = layout_flowchart(&ast).expect("layout");
+    let options = RenderOptions {
+        flowchart_edge_glyphs: FlowchartEdgeGlyphs::Braille,
+        ..RenderOptions::default()
+    };
+    let diagram_id = DiagramId::new("d").expect("diagram id");
+    let annotated =
+        render_flowchart_unicode_annotated_with_options(&diagram_id, &ast, &layout, options)
+            .expect("render");
+    assert_eq!(
+        annotated.text,
+        [
+            "    ┌───┐",
+            "    │ a │",
+            "    └┬─┬┘",
+            "     ⡇ ⡇",
+            "   ⡰⠒⠁ ⠑⠒⡄",
+            "   ▼     ▼",
+            "┌──┴┐   ┌┴──┐",
+            "│ b │   │ c │",
+            "└──┬┘   └┬──┘",
+            "   ⡇     ⡇",
+            "   ⠑⠒⡄ ⡰⠒⠁",
+            "     ▼ ▼",
+            "    ┌┴─┴┐",
+            "    │ d │",
+            "    └───┘",
+        ]
+        .join("\n")
+    );
+    assert_eq!(
+        render_flowchart_unicode_with_options(&ast, &layout, options).expect("render"),
+        annotated.text
+    );
+
+    let lines =
+        annotated.text.lines().map(|line| line.chars().collect::<Vec<_>>()).collect::<Vec<_>>();
+    let mut edge_cells = BTreeSet::new();
+    for (object_ref, spans) in &annotated.highlight_index {
+        if object_ref.category().segments() != ["flow", "edge"] {
+            continue;
+        }
+        for &(y, x0, x1) in spans {
+            assert!(x1 < lines[y].len(), "{object_ref} span ends past line {y}");
+            for (x, ch) in lines[y].iter().enumerate().take(x1 + 1).skip(x0) {
+                assert_ne!(*ch, ' ', "{object_ref} highlights blank cell ({y}, {x})");
+                edge_cells.insert((y, x));
+            }
+        }
+    }
+    for (y, line) in lines.iter().enumerate() {
+        for (x, ch) in line.iter().enumerate() {
+            if ('\u{2801}'..='\u{28ff}').contains(ch) {
+                assert!(edge_cells.contains(&(y, x)), "braille cell ({y}, {x}) belongs to no edge");
+            }
+        }
+    }
+}
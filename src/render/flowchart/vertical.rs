@@ -0,0 +1,644 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Row-layered rendering for vertical flowcharts (`flowchart TD`/`TB`/`BT`).
+//!
+//! Each layout layer becomes a row of boxes, centered on the widest row and stacked in drawing
+//! order. Edges are routed one at a time through the free cells of the canvas: they leave the
+//! source box through the border facing the flow (bottom for `TD`, top for `BT`) and enter the
+//! target box through the opposite border, with at most one edge end per border cell. Like
+//! detours in the layered renderer, connectors are only crossed at right angles and bends cost
+//! extra. An edge that can only get through by running along another connector, or not at all,
+//! makes the gaps between rows grow and routing start over; if no gap works, the caller falls
+//! back to the layered renderer.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+
+use crate::layout::FlowchartLayout;
+use crate::model::flow_ast::FlowchartAst;
+use crate::model::ids::{DiagramId, ObjectId};
+use crate::model::{CategoryPath, ObjectRef};
+
+use super::super::text::{text_len, truncate_with_ellipsis};
+use super::super::{
+    AnnotatedRender, Canvas, FlowchartNodeScale, HighlightIndex, LineSpan, RenderOptions,
+    UNICODE_BOX_HORIZONTAL, UNICODE_BOX_TEE_DOWN, UNICODE_BOX_TEE_UP,
+};
+use super::{
+    add_edge_label_spans, compact_flowchart_text, detour_cell_char, edge_endpoint_cap_kinds,
+    endpoint_cap_char, flow_box_height, frame_subgraphs, glyph, horizontal_line_char, label,
+    node_label, restyle_connector_strokes, vertical_line_char, ConnectorStroke, EndpointCapKind,
+    FlowchartRenderError, NodeRender, SubgraphFrames, ENTRY_NODE_MARKER, MIN_BOX_INNER_WIDTH,
+};
+
+const COL_GAP: usize = 3;
+const MIN_ROW_GAP: usize = 3;
+const MAX_ROW_GAP_STEPS: usize = 4;
+/// Blank cells around the boxes so edges can go around the outermost ones; cropped afterwards.
+const MARGIN: usize = 2;
+const BEND_COST: usize = 3;
+const CROSSING_COST: usize = 4;
+const OVERLAP_COST: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Dir {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Dir {
+    const ALL: [Self; 4] = [Self::Up, Self::Down, Self::Left, Self::Right];
+
+    fn is_vertical(self) -> bool {
+        matches!(self, Self::Up | Self::Down)
+    }
+
+    fn reverse(self) -> Self {
+        match self {
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+
+    fn step(self, x: usize, y: usize) -> Option<(usize, usize)> {
+        match self {
+            Self::Up => Some((x, y.checked_sub(1)?)),
+            Self::Down => Some((x, y + 1)),
+            Self::Left => Some((x.checked_sub(1)?, y)),
+            Self::Right => Some((x + 1, y)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct VerticalEdge {
+    edge_id: ObjectId,
+    stroke: ConnectorStroke,
+    /// Cells from the source border cell to the target border cell, one step at a time.
+    path: Vec<(usize, usize)>,
+    start: Option<EndpointCapKind>,
+    end: Option<EndpointCapKind>,
+    /// Cells shared lengthwise with an earlier connector.
+    overlaps: usize,
+}
+
+impl VerticalEdge {
+    /// Endpoint caps as `(x, y, glyph)`, on the cells next to the box borders.
+    fn caps(&self) -> Vec<(usize, usize, char)> {
+        let n = self.path.len();
+        if n < 4 {
+            return Vec::new();
+        }
+        let cap = |kind: Option<EndpointCapKind>, border: (usize, usize), cell: (usize, usize)| {
+            let outward_dx = cell.0 as i32 - border.0 as i32;
+            let outward_dy = cell.1 as i32 - border.1 as i32;
+            kind.map(|kind| (cell.0, cell.1, endpoint_cap_char(kind, outward_dx, outward_dy)))
+        };
+        [
+            cap(self.start, self.path[0], self.path[1]),
+            cap(self.end, self.path[n - 1], self.path[n - 2]),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn spans(&self) -> Vec<LineSpan> {
+        let mut cells = self.path.iter().map(|&(x, y)| (y, x)).collect::<Vec<_>>();
+        cells.sort();
+        cells.dedup();
+        let mut spans = Vec::<LineSpan>::new();
+        for (y, x) in cells {
+            match spans.last_mut() {
+                Some((span_y, _, x1)) if *span_y == y && *x1 + 1 == x => *x1 = x,
+                _ => spans.push((y, x, x)),
+            }
+        }
+        spans
+    }
+
+    fn draw(&self, canvas: &mut Canvas) -> Result<(), FlowchartRenderError> {
+        let last = self.path.len() - 1;
+        for (idx, &(x, y)) in self.path.iter().enumerate() {
+            let neighbour = match idx {
+                0 => Some(self.path[1]),
+                _ if idx == last => Some(self.path[last - 1]),
+                _ => None,
+            };
+            let glyph = match neighbour {
+                // Merges with the box border into `┬`/`┴`.
+                Some((_, ny)) if ny > y => UNICODE_BOX_TEE_DOWN,
+                Some(_) => UNICODE_BOX_TEE_UP,
+                None => {
+                    let toward = |(nx, ny): (usize, usize)| (nx.cmp(&x), ny.cmp(&y));
+                    detour_cell_char(toward(self.path[idx - 1]), toward(self.path[idx + 1]))
+                }
+            };
+            canvas.set(x, y, glyph)?;
+        }
+        Ok(())
+    }
+}
+
+/// Boxes and routed edges of a vertical flowchart, drawn on a canvas with a blank margin.
+struct VerticalPlan {
+    node_renders: BTreeMap<ObjectId, NodeRender>,
+    /// In `ast.edges()` order.
+    edges: Vec<VerticalEdge>,
+    canvas: Canvas,
+}
+
+impl VerticalPlan {
+    /// `None` when some edge finds no way through even with the widest row gaps.
+    fn build(
+        ast: &FlowchartAst,
+        layout: &FlowchartLayout,
+        options: RenderOptions,
+    ) -> Result<Option<Self>, FlowchartRenderError> {
+        let mut best = None::<(usize, Self)>;
+        for step in 0..MAX_ROW_GAP_STEPS {
+            let Some(plan) = Self::build_with_row_gap(ast, layout, options, MIN_ROW_GAP + step)?
+            else {
+                continue;
+            };
+            let overlaps = plan.edges.iter().map(|edge| edge.overlaps).sum::<usize>();
+            if overlaps == 0 {
+                return Ok(Some(plan));
+            }
+            if best.as_ref().map_or(true, |(best_overlaps, _)| overlaps < *best_overlaps) {
+                best = Some((overlaps, plan));
+            }
+        }
+        Ok(best.map(|(_, plan)| plan))
+    }
+
+    fn build_with_row_gap(
+        ast: &FlowchartAst,
+        layout: &FlowchartLayout,
+        options: RenderOptions,
+        row_gap: usize,
+    ) -> Result<Option<Self>, FlowchartRenderError> {
+        let box_height = flow_box_height(options);
+        let col_gap = COL_GAP + options.flowchart_extra_col_gap;
+        let downward = !layout.direction().is_reversed();
+
+        // Every edge end needs its own border cell, with a blank cell between neighbours.
+        let mut ends = BTreeMap::<(&ObjectId, bool), usize>::new();
+        for edge in ast.edges().values() {
+            *ends.entry((edge.from_node_id(), downward)).or_default() += 1;
+            *ends.entry((edge.to_node_id(), !downward)).or_default() += 1;
+        }
+        let inner_width = |node_id: &ObjectId| -> Result<usize, FlowchartRenderError> {
+            let node = ast
+                .nodes()
+                .get(node_id)
+                .ok_or_else(|| FlowchartRenderError::MissingNode { node_id: node_id.clone() })?;
+            let label = text_len(&node_label(node.label(), options)) + 2;
+            let ports = [true, false]
+                .iter()
+                .map(|&bottom| ends.get(&(node_id, bottom)).copied().unwrap_or(0))
+                .max()
+                .unwrap_or(0);
+            let width = label.max(MIN_BOX_INNER_WIDTH).max((2 * ports).saturating_sub(1));
+            // Keep widths odd so a centered connector has a center cell.
+            Ok(width | 1)
+        };
+
+        let rows = layout
+            .layers()
+            .iter()
+            .map(|layer| {
+                layer
+                    .iter()
+                    .map(|node_id| Ok((node_id, inner_width(node_id)? + 2)))
+                    .collect::<Result<Vec<_>, FlowchartRenderError>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let row_width = |row: &[(&ObjectId, usize)]| {
+            row.iter().map(|(_, width)| width).sum::<usize>()
+                + row.len().saturating_sub(1) * col_gap
+        };
+        let max_row_width = rows.iter().map(|row| row_width(row)).max().unwrap_or(0);
+
+        let mut node_renders = BTreeMap::new();
+        for (layer, row) in rows.iter().enumerate() {
+            let mut x = MARGIN + (max_row_width - row_width(row)) / 2;
+            let y0 = MARGIN + layer * (box_height + row_gap);
+            for (index_in_layer, (node_id, width)) in row.iter().enumerate() {
+                node_renders.insert(
+                    (*node_id).clone(),
+                    NodeRender {
+                        layer,
+                        index_in_layer,
+                        box_x0: x,
+                        box_x1: x + width - 1,
+                        box_y0: y0,
+                        box_y1: y0 + box_height - 1,
+                    },
+                );
+                x += width + col_gap;
+            }
+        }
+
+        let content_height = (rows.len() * (box_height + row_gap)).saturating_sub(row_gap);
+        let mut canvas = Canvas::new(max_row_width + 2 * MARGIN, content_height + 2 * MARGIN)?;
+        for render in node_renders.values() {
+            canvas.draw_box(render.box_x0, render.box_y0, render.box_x1, render.box_y1)?;
+        }
+
+        let mut in_box = vec![false; canvas.width() * canvas.height()];
+        // The rows right above and below a box are kept for its own edge ends, so a connector
+        // running along the border can never cut the box off.
+        let mut port_row = vec![false; canvas.width() * canvas.height()];
+        for render in node_renders.values() {
+            for x in render.box_x0..=render.box_x1 {
+                for y in render.box_y0..=render.box_y1 {
+                    in_box[y * canvas.width() + x] = true;
+                }
+                port_row[(render.box_y0 - 1) * canvas.width() + x] = true;
+                port_row[(render.box_y1 + 1) * canvas.width() + x] = true;
+            }
+        }
+
+        // Short edges first, so the long ones go around what is already there.
+        let mut order = ast
+            .edges()
+            .iter()
+            .enumerate()
+            .map(|(idx, (_, edge))| {
+                let render = |node_id: &ObjectId| {
+                    node_renders.get(node_id).copied().ok_or_else(|| {
+                        FlowchartRenderError::MissingPlacement { node_id: node_id.clone() }
+                    })
+                };
+                let (from, to) = (render(edge.from_node_id())?, render(edge.to_node_id())?);
+                Ok((from.layer.abs_diff(to.layer), idx, from, to))
+            })
+            .collect::<Result<Vec<_>, FlowchartRenderError>>()?;
+        order.sort_by_key(|&(span, idx, _, _)| (span, idx));
+
+        let edge_list = ast.edges().iter().collect::<Vec<_>>();
+        let mut routed = vec![None; edge_list.len()];
+        for (_, idx, from, to) in order {
+            let (edge_id, edge) = edge_list[idx];
+            let Some((path, overlaps)) =
+                route_edge(&canvas, &in_box, &port_row, from, to, downward)
+            else {
+                return Ok(None);
+            };
+            let (start, end) = edge_endpoint_cap_kinds(edge.connector());
+            let routed_edge = VerticalEdge {
+                edge_id: edge_id.clone(),
+                stroke: ConnectorStroke::from_connector(edge.connector()),
+                path,
+                start,
+                end,
+                overlaps,
+            };
+            routed_edge.draw(&mut canvas)?;
+            // Caps are overlaid on the text later; keep other edges from running through them.
+            for (x, y, _) in routed_edge.caps() {
+                in_box[y * canvas.width() + x] = true;
+            }
+            routed[idx] = Some(routed_edge);
+        }
+        let edges = routed.into_iter().flatten().collect();
+
+        for (node_id, render) in &node_renders {
+            let node = &ast.nodes()[node_id];
+            let inner_width = render.box_x1 - render.box_x0 - 1;
+            let mut write_centered = |text: &str, y: usize| {
+                let clipped = truncate_with_ellipsis(text, inner_width);
+                let left_pad = inner_width.saturating_sub(text_len(&clipped)) / 2;
+                canvas.write_str(render.box_x0 + 1 + left_pad, y, &clipped)
+            };
+            write_centered(&node_label(node.label(), options), render.box_y0 + 1)?;
+            if options.show_notes {
+                if let Some(note) = node.note() {
+                    write_centered(note, render.box_y0 + 2)?;
+                }
+            }
+            if ast.is_entry_node(node_id) {
+                canvas.set(render.box_x0, render.box_y0, ENTRY_NODE_MARKER)?;
+            }
+        }
+
+        Ok(Some(Self { node_renders, edges, canvas }))
+    }
+
+    /// Crops the blank margin, moving boxes and edge paths along, and returns the text.
+    fn crop(&mut self) -> Result<String, FlowchartRenderError> {
+        let mut lines = Vec::with_capacity(self.canvas.height());
+        for y in 0..self.canvas.height() {
+            let mut line = String::with_capacity(self.canvas.width());
+            for x in 0..self.canvas.width() {
+                line.push(self.canvas.get(x, y)?);
+            }
+            lines.push(line);
+        }
+        // Overlaid rather than drawn so the corners next to them keep their glyphs.
+        for (x, y, glyph) in self.edges.iter().flat_map(VerticalEdge::caps) {
+            let mut chars = lines[y].chars().collect::<Vec<_>>();
+            chars[x] = glyph;
+            lines[y] = chars.into_iter().collect();
+        }
+        let blank = |line: &String| line.chars().all(|ch| ch == ' ');
+        let oy = lines.iter().take_while(|line| blank(line)).count();
+        let ox = lines
+            .iter()
+            .filter(|line| !blank(line))
+            .map(|line| line.chars().take_while(|&ch| ch == ' ').count())
+            .min()
+            .unwrap_or(0);
+
+        for render in self.node_renders.values_mut() {
+            render.box_x0 -= ox;
+            render.box_x1 -= ox;
+            render.box_y0 -= oy;
+            render.box_y1 -= oy;
+        }
+        for edge in &mut self.edges {
+            for (x, y) in &mut edge.path {
+                *x -= ox;
+                *y -= oy;
+            }
+        }
+
+        let mut lines = lines
+            .into_iter()
+            .skip(oy)
+            .map(|line| line.chars().skip(ox).collect::<String>().trim_end().to_owned())
+            .collect::<Vec<_>>();
+        while lines.last().is_some_and(String::is_empty) {
+            lines.pop();
+        }
+        Ok(lines.join("\n"))
+    }
+
+    fn highlight_index(
+        &self,
+        diagram_id: &DiagramId,
+        ast: &FlowchartAst,
+        options: RenderOptions,
+    ) -> HighlightIndex {
+        let category = |kind: &str| {
+            CategoryPath::new(vec!["flow".to_owned(), kind.to_owned()]).expect("valid")
+        };
+        let mut highlight_index = HighlightIndex::new();
+        for (node_id, render) in &self.node_renders {
+            let object_ref = ObjectRef::new(diagram_id.clone(), category("node"), node_id.clone());
+            let spans = (render.box_y0..=render.box_y1)
+                .map(|y| (y, render.box_x0, render.box_x1))
+                .collect();
+            highlight_index.insert(object_ref, spans);
+
+            let note = ast.nodes().get(node_id).and_then(|node| node.note());
+            if let Some(note) = note.filter(|_| options.show_notes) {
+                let inner_width = render.box_x1 - render.box_x0 - 1;
+                let clipped_len = text_len(&truncate_with_ellipsis(note, inner_width));
+                if clipped_len > 0 {
+                    let note_x = render.box_x0 + 1 + inner_width.saturating_sub(clipped_len) / 2;
+                    let note_ref =
+                        ObjectRef::new(diagram_id.clone(), category("note"), node_id.clone());
+                    highlight_index.insert(
+                        note_ref,
+                        vec![(render.box_y0 + 2, note_x, note_x + clipped_len - 1)],
+                    );
+                }
+            }
+        }
+        for edge in &self.edges {
+            let object_ref =
+                ObjectRef::new(diagram_id.clone(), category("edge"), edge.edge_id.clone());
+            highlight_index.insert(object_ref, edge.spans());
+        }
+        highlight_index
+    }
+}
+
+/// Cheapest path from a free border cell of `from` to a free border cell of `to`, plus the number
+/// of cells it shares lengthwise with existing connectors.
+fn route_edge(
+    canvas: &Canvas,
+    in_box: &[bool],
+    port_row: &[bool],
+    from: NodeRender,
+    to: NodeRender,
+    downward: bool,
+) -> Option<(Vec<(usize, usize)>, usize)> {
+    let (width, height) = (canvas.width(), canvas.height());
+    let cell = |x: usize, y: usize| {
+        (x < width && y < height).then(|| canvas.get(x, y).expect("in bounds"))
+    };
+    // Cost of entering `(x, y)` moving along `dir`, or `None` when it is blocked that way.
+    let enter_cost = |x: usize, y: usize, dir: Dir| {
+        let idx = y * width + x;
+        let ch = cell(x, y).filter(|_| !in_box[idx] && (!port_row[idx] || dir.is_vertical()))?;
+        match ch {
+            ' ' => Some(1),
+            ch if horizontal_line_char(ch) || vertical_line_char(ch) => {
+                if horizontal_line_char(ch) == dir.is_vertical() {
+                    Some(1 + CROSSING_COST)
+                } else {
+                    Some(1 + OVERLAP_COST)
+                }
+            }
+            _ => None,
+        }
+    };
+
+    let flow = if downward { Dir::Down } else { Dir::Up };
+    let (source_y, target_y) =
+        if downward { (from.box_y1, to.box_y0) } else { (from.box_y0, to.box_y1) };
+    let free_border = |x: usize, y: usize| cell(x, y) == Some(UNICODE_BOX_HORIZONTAL);
+    let center = |render: NodeRender| (render.box_x0 + render.box_x1) / 2;
+    let state_index = |x: usize, y: usize, dir: Dir| (y * width + x) * 4 + dir as usize;
+
+    let mut dist = vec![usize::MAX; width * height * 4];
+    let mut prev = vec![usize::MAX; width * height * 4];
+    let mut heap = BinaryHeap::new();
+    for x in from.box_x0 + 1..from.box_x1 {
+        if !free_border(x, source_y) {
+            continue;
+        }
+        let Some((sx, sy)) = flow.step(x, source_y) else {
+            continue;
+        };
+        if cell(sx, sy) != Some(' ') {
+            continue;
+        }
+        let cost = 1 + x.abs_diff(center(from));
+        let idx = state_index(sx, sy, flow);
+        if cost < dist[idx] {
+            dist[idx] = cost;
+            heap.push(Reverse((cost, sy, sx, flow)));
+        }
+    }
+
+    let mut found: Option<(usize, (usize, usize, Dir))> = None;
+    while let Some(Reverse((cost, y, x, dir))) = heap.pop() {
+        if dist[state_index(x, y, dir)] < cost {
+            continue;
+        }
+        if found.as_ref().is_some_and(|(found_cost, _)| *found_cost <= cost) {
+            break;
+        }
+        let blank = cell(x, y) == Some(' ');
+
+        let at_target = dir == flow
+            && blank
+            && (to.box_x0 + 1..to.box_x1).contains(&x)
+            && flow.step(x, y).is_some_and(|(_, ny)| ny == target_y)
+            && free_border(x, target_y);
+        if at_target {
+            let total = cost + x.abs_diff(center(to));
+            if found.as_ref().map_or(true, |(found_cost, _)| total < *found_cost) {
+                found = Some((total, (x, y, dir)));
+            }
+        }
+
+        for next_dir in Dir::ALL {
+            if next_dir == dir.reverse() || (!blank && next_dir != dir) {
+                continue;
+            }
+            let Some((nx, ny)) = next_dir.step(x, y) else {
+                continue;
+            };
+            let Some(step) = (nx < width && ny < height).then(|| enter_cost(nx, ny, next_dir))
+            else {
+                continue;
+            };
+            let Some(step) = step else {
+                continue;
+            };
+            let bend = if next_dir == dir { 0 } else { BEND_COST };
+            let next_cost = cost + step + bend;
+            let idx = state_index(nx, ny, next_dir);
+            if next_cost < dist[idx] {
+                dist[idx] = next_cost;
+                prev[idx] = state_index(x, y, dir);
+                heap.push(Reverse((next_cost, ny, nx, next_dir)));
+            }
+        }
+    }
+
+    let (_, (x, y, dir)) = found?;
+    let mut path = vec![(x, target_y)];
+    let mut idx = state_index(x, y, dir);
+    loop {
+        let cell_idx = idx / 4;
+        path.push((cell_idx % width, cell_idx / width));
+        if prev[idx] == usize::MAX {
+            break;
+        }
+        idx = prev[idx];
+    }
+    let &(first_x, _) = path.last().expect("path has a source cell");
+    path.push((first_x, source_y));
+    path.reverse();
+
+    let overlaps = path[1..path.len() - 1]
+        .windows(2)
+        .filter(|pair| {
+            let (x, y) = pair[1];
+            let moving_vertically = pair[0].0 == x;
+            cell(x, y).is_some_and(|ch| {
+                (horizontal_line_char(ch) && !moving_vertically)
+                    || (vertical_line_char(ch) && moving_vertically)
+            })
+        })
+        .count();
+    Some((path, overlaps))
+}
+
+/// Renders a flowchart laid out with a vertical direction; `diagram_id` turns on annotation.
+///
+/// Returns `None` when the edges cannot all be routed between the rows, so the caller can fall
+/// back to the layered renderer.
+pub(super) fn render_vertical(
+    diagram_id: Option<&DiagramId>,
+    ast: &FlowchartAst,
+    layout: &FlowchartLayout,
+    options: RenderOptions,
+) -> Result<Option<AnnotatedRender>, FlowchartRenderError> {
+    let Some(mut plan) = VerticalPlan::build(ast, layout, options)? else {
+        return Ok(None);
+    };
+    let mut text = plan.crop()?;
+
+    let strokes = plan.edges.iter().map(|edge| edge.stroke).collect::<Vec<_>>();
+    if strokes.iter().any(|stroke| *stroke != ConnectorStroke::Solid) {
+        let spans_by_edge = plan.edges.iter().map(VerticalEdge::spans).collect::<Vec<_>>();
+        text = restyle_connector_strokes(text, &strokes, &spans_by_edge, &plan.node_renders);
+    }
+    let mut label_spans = Vec::new();
+    if options.flowchart_node_scale == FlowchartNodeScale::Full {
+        let spans_by_edge = plan.edges.iter().map(VerticalEdge::spans).collect::<Vec<_>>();
+        let edges = plan
+            .edges
+            .iter()
+            .zip(&spans_by_edge)
+            .map(|(edge, spans)| {
+                (ast.edges().get(&edge.edge_id).and_then(|edge| edge.label()), spans.as_slice())
+            })
+            .collect::<Vec<_>>();
+        let (labeled, placed) = label::place_edge_labels(text, &edges);
+        text = labeled;
+        label_spans = plan
+            .edges
+            .iter()
+            .map(|edge| edge.edge_id.clone())
+            .zip(placed)
+            .filter(|(_, spans)| !spans.is_empty())
+            .collect();
+    }
+
+    let mut highlight_index = diagram_id.map(|diagram_id| {
+        let mut index = plan.highlight_index(diagram_id, ast, options);
+        add_edge_label_spans(diagram_id, &mut index, label_spans);
+        (diagram_id, index)
+    });
+    if options.flowchart_node_scale == FlowchartNodeScale::Glyph {
+        text = glyph::squash_node_boxes(
+            text,
+            &plan.node_renders,
+            highlight_index.as_mut().map(|(diagram_id, index)| (*diagram_id, index)),
+        );
+    }
+    let frames = SubgraphFrames::plan(ast, &plan.node_renders);
+    let text = frame_subgraphs(
+        &frames,
+        text,
+        highlight_index.as_mut().map(|(diagram_id, index)| (*diagram_id, index)),
+    );
+
+    let mut highlight_index = highlight_index.map(|(_, index)| index).unwrap_or_default();
+    super::super::clamp_highlight_index_to_text(&mut highlight_index, &text);
+    let text = if options.flowchart_compact {
+        compact_flowchart_text(
+            text,
+            &frames,
+            &plan.node_renders,
+            options.flowchart_node_scale,
+            Some(&mut highlight_index),
+        )
+    } else {
+        text
+    };
+    let text = if options.flowchart_node_scale == FlowchartNodeScale::Glyph {
+        glyph::crop_leading_blanks(text, Some(&mut highlight_index))
+    } else {
+        text
+    };
+    Ok(Some(AnnotatedRender { text, highlight_index }))
+}
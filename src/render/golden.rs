@@ -0,0 +1,257 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Golden render snapshots (`nereid render-check`).
+//!
+//! A golden directory holds the expected Unicode render of every diagram (`<diagram-id>.txt`)
+//! and walkthrough (`<walkthrough-id>.wt.txt`) of a session. [`write_goldens`] records the
+//! current renders; [`check_goldens`] compares against them so CI can fail when a diagram's
+//! render changes without its golden being updated in the same change.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::model::{DiagramId, Session, WalkthroughId};
+
+use super::{
+    render_diagram_unicode, render_walkthrough_unicode, DiagramRenderError, WalkthroughRenderError,
+};
+
+const DIAGRAM_SUFFIX: &str = ".txt";
+const WALKTHROUGH_SUFFIX: &str = ".wt.txt";
+
+#[derive(Debug)]
+pub enum GoldenError {
+    Io { path: PathBuf, source: io::Error },
+    DiagramRender { diagram_id: DiagramId, source: DiagramRenderError },
+    WalkthroughRender { walkthrough_id: WalkthroughId, source: WalkthroughRenderError },
+}
+
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => {
+                write!(f, "golden io error at {}: {source}", path.display())
+            }
+            Self::DiagramRender { diagram_id, source } => {
+                write!(f, "failed to render diagram {diagram_id}: {source}")
+            }
+            Self::WalkthroughRender { walkthrough_id, source } => {
+                write!(f, "failed to render walkthrough {walkthrough_id}: {source}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for GoldenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::DiagramRender { source, .. } => Some(source),
+            Self::WalkthroughRender { source, .. } => Some(source),
+        }
+    }
+}
+
+/// A render that no longer matches its golden.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoldenMismatch {
+    /// Golden file name, relative to the golden directory.
+    pub file_name: String,
+    /// 1-based number of the first differing line.
+    pub line: usize,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+}
+
+/// Outcome of [`check_goldens`]; file names are relative to the golden directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GoldenReport {
+    pub matched: Vec<String>,
+    pub mismatched: Vec<GoldenMismatch>,
+    /// Renders without a golden.
+    pub missing: Vec<String>,
+    /// Goldens without a diagram or walkthrough.
+    pub stale: Vec<String>,
+}
+
+impl GoldenReport {
+    /// Whether every render matches its golden and no golden is missing or stale.
+    pub fn is_clean(&self) -> bool {
+        self.mismatched.is_empty() && self.missing.is_empty() && self.stale.is_empty()
+    }
+}
+
+/// Current renders of `session`, keyed by golden file name.
+pub fn golden_renders(session: &Session) -> Result<Vec<(String, String)>, GoldenError> {
+    let mut renders = Vec::new();
+    for (diagram_id, diagram) in session.diagrams() {
+        let text = render_diagram_unicode(diagram).map_err(|source| {
+            GoldenError::DiagramRender { diagram_id: diagram_id.clone(), source }
+        })?;
+        renders.push((format!("{diagram_id}{DIAGRAM_SUFFIX}"), with_trailing_newline(text)));
+    }
+    for (walkthrough_id, walkthrough) in session.walkthroughs() {
+        let text = render_walkthrough_unicode(walkthrough).map_err(|source| {
+            GoldenError::WalkthroughRender { walkthrough_id: walkthrough_id.clone(), source }
+        })?;
+        renders
+            .push((format!("{walkthrough_id}{WALKTHROUGH_SUFFIX}"), with_trailing_newline(text)));
+    }
+    Ok(renders)
+}
+
+/// Writes the current renders of `session` into `dir` and removes stale goldens; returns the
+/// written file names.
+pub fn write_goldens(session: &Session, dir: &Path) -> Result<Vec<String>, GoldenError> {
+    let renders = golden_renders(session)?;
+    fs::create_dir_all(dir)
+        .map_err(|source| GoldenError::Io { path: dir.to_path_buf(), source })?;
+    for file_name in golden_file_names(dir)? {
+        if !renders.iter().any(|(name, _)| *name == file_name) {
+            let path = dir.join(&file_name);
+            fs::remove_file(&path).map_err(|source| GoldenError::Io { path, source })?;
+        }
+    }
+
+    let mut written = Vec::with_capacity(renders.len());
+    for (file_name, text) in renders {
+        let path = dir.join(&file_name);
+        fs::write(&path, text).map_err(|source| GoldenError::Io { path, source })?;
+        written.push(file_name);
+    }
+    Ok(written)
+}
+
+/// Compares the current renders of `session` with the goldens in `dir`.
+pub fn check_goldens(session: &Session, dir: &Path) -> Result<GoldenReport, GoldenError> {
+    let renders = golden_renders(session)?;
+    let existing = if dir.exists() { golden_file_names(dir)? } else { Vec::new() };
+
+    let mut report = GoldenReport::default();
+    for (file_name, actual) in &renders {
+        if !existing.contains(file_name) {
+            report.missing.push(file_name.clone());
+            continue;
+        }
+        let path = dir.join(file_name);
+        let expected =
+            fs::read_to_string(&path).map_err(|source| GoldenError::Io { path, source })?;
+        match first_difference(&expected, actual) {
+            None => report.matched.push(file_name.clone()),
+            Some((line, expected, actual)) => report.mismatched.push(GoldenMismatch {
+                file_name: file_name.clone(),
+                line,
+                expected,
+                actual,
+            }),
+        }
+    }
+    report.stale = existing
+        .into_iter()
+        .filter(|file_name| !renders.iter().any(|(name, _)| name == file_name))
+        .collect();
+    Ok(report)
+}
+
+/// Golden files in `dir`, sorted by name.
+fn golden_file_names(dir: &Path) -> Result<Vec<String>, GoldenError> {
+    let entries =
+        fs::read_dir(dir).map_err(|source| GoldenError::Io { path: dir.to_path_buf(), source })?;
+    let mut names = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|source| GoldenError::Io { path: dir.to_path_buf(), source })?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.ends_with(DIAGRAM_SUFFIX) && entry.path().is_file() {
+            names.push(name);
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+fn with_trailing_newline(mut text: String) -> String {
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
+
+/// First differing line (1-based) with the expected and actual line there.
+fn first_difference(
+    expected: &str,
+    actual: &str,
+) -> Option<(usize, Option<String>, Option<String>)> {
+    if expected == actual {
+        return None;
+    }
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut line = 1;
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(a), Some(b)) if a == b => line += 1,
+            (None, None) => return Some((line, None, None)),
+            (a, b) => return Some((line, a.map(str::to_owned), b.map(str::to_owned))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_goldens, write_goldens, GoldenMismatch};
+    use crate::tui::demo_session;
+
+    #[test]
+    fn written_goldens_check_clean_until_a_render_changes() {
+        let session = demo_session();
+        let dir = std::env::temp_dir().join(format!(
+            "nereid-golden-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        ));
+
+        let report = check_goldens(&session, &dir).expect("check without goldens");
+        assert!(report.matched.is_empty());
+        assert_eq!(report.missing.len(), session.diagrams().len() + session.walkthroughs().len());
+
+        let written = write_goldens(&session, &dir).expect("write goldens");
+        let report = check_goldens(&session, &dir).expect("check");
+        assert!(report.is_clean(), "unexpected report: {report:?}");
+        assert_eq!(report.matched, written);
+
+        let first = written.first().expect("demo golden").clone();
+        let golden = std::fs::read_to_string(dir.join(&first)).expect("read golden");
+        let edited = golden.replacen('\n', " edited\n", 1);
+        std::fs::write(dir.join(&first), &edited).expect("edit golden");
+        std::fs::write(dir.join("gone.txt"), "old\n").expect("stale golden");
+        let report = check_goldens(&session, &dir).expect("check after edit");
+        assert!(!report.is_clean());
+        assert_eq!(
+            report.mismatched,
+            vec![GoldenMismatch {
+                file_name: first.clone(),
+                line: 1,
+                expected: edited.lines().next().map(str::to_owned),
+                actual: golden.lines().next().map(str::to_owned),
+            }]
+        );
+        assert_eq!(report.stale, vec!["gone.txt".to_owned()]);
+
+        write_goldens(&session, &dir).expect("update goldens");
+        assert!(check_goldens(&session, &dir).expect("check after update").is_clean());
+        assert!(!dir.join("gone.txt").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
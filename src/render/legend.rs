@@ -0,0 +1,183 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Legend for the markers drawn on a diagram.
+//!
+//! Only markers that can appear on the given diagram are listed: xref direction prefixes for the
+//! directions actually present, notes and sequence block frames when the diagram has them, the
+//! focus/agent/selection highlights, and every active conditional styling rule. The TUI draws the
+//! entries as an overlay with colored swatches; [`legend_block`] formats them as plain text to
+//! append below a render.
+
+use std::collections::BTreeSet;
+
+use crate::model::{Diagram, DiagramAst, Session, StyleEffect};
+
+/// Which kind of marker a legend entry explains, so the TUI can draw a matching swatch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegendMarker {
+    XRefDirection,
+    Note,
+    SequenceBlock,
+    Focus,
+    AgentHighlight,
+    Selection,
+    StyleRule(StyleEffect),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegendEntry {
+    pub marker: LegendMarker,
+    /// What the marker looks like, e.g. `▴ label` or a rule condition.
+    pub sample: String,
+    pub meaning: String,
+}
+
+impl LegendEntry {
+    fn new(marker: LegendMarker, sample: impl Into<String>, meaning: impl Into<String>) -> Self {
+        Self { marker, sample: sample.into(), meaning: meaning.into() }
+    }
+}
+
+/// Legend entries for the markers that can appear on `diagram` within `session`.
+pub fn diagram_legend(session: &Session, diagram: &Diagram) -> Vec<LegendEntry> {
+    let mut entries = Vec::new();
+
+    let diagram_id = diagram.diagram_id();
+    let mut outgoing = BTreeSet::new();
+    let mut incoming = BTreeSet::new();
+    for xref in session.xrefs().values() {
+        if xref.from().diagram_id() == diagram_id {
+            outgoing.insert(xref.from());
+        }
+        if xref.to().diagram_id() == diagram_id {
+            incoming.insert(xref.to());
+        }
+    }
+    if outgoing.iter().any(|object_ref| !incoming.contains(object_ref)) {
+        entries.push(LegendEntry::new(
+            LegendMarker::XRefDirection,
+            "▴ label",
+            "has outgoing xrefs",
+        ));
+    }
+    if incoming.iter().any(|object_ref| !outgoing.contains(object_ref)) {
+        entries.push(LegendEntry::new(
+            LegendMarker::XRefDirection,
+            "▾ label",
+            "has incoming xrefs",
+        ));
+    }
+    if outgoing.intersection(&incoming).next().is_some() {
+        entries.push(LegendEntry::new(
+            LegendMarker::XRefDirection,
+            "▾▴ label",
+            "has xrefs in both directions",
+        ));
+    }
+
+    let (has_notes, has_blocks) = match diagram.ast() {
+        DiagramAst::Flowchart(ast) => {
+            (ast.nodes().values().any(|node| node.note().is_some()), false)
+        }
+        DiagramAst::Sequence(ast) => (
+            !ast.notes().is_empty()
+                || ast.participants().values().any(|participant| participant.note().is_some()),
+            !ast.blocks().is_empty(),
+        ),
+        DiagramAst::State(ast) => {
+            (ast.states().values().any(|state| state.note().is_some()), false)
+        }
+        DiagramAst::Class(ast) => {
+            (ast.classes().values().any(|class| class.note().is_some()), false)
+        }
+    };
+    if has_notes {
+        entries.push(LegendEntry::new(LegendMarker::Note, "note", "object note (muted text)"));
+    }
+    if has_blocks {
+        entries.push(LegendEntry::new(
+            LegendMarker::SequenceBlock,
+            "loop/alt",
+            "sequence block frame; its selected section is shaded",
+        ));
+    }
+
+    entries.push(LegendEntry::new(LegendMarker::Focus, "focus", "object under the cursor"));
+    entries.push(LegendEntry::new(
+        LegendMarker::AgentHighlight,
+        "agent",
+        "highlighted by the agent",
+    ));
+    entries.push(LegendEntry::new(
+        LegendMarker::Selection,
+        "selected",
+        "selected objects; the rest is dimmed",
+    ));
+
+    for rule in session.style_rules() {
+        let condition = rule.condition().to_string();
+        let meaning = match rule.effect() {
+            StyleEffect::Dim => "rendered dim (style rule)",
+            StyleEffect::Heavy => "rendered with heavy borders (style rule)",
+        };
+        entries.push(LegendEntry::new(LegendMarker::StyleRule(rule.effect()), condition, meaning));
+    }
+
+    entries
+}
+
+/// Formats `entries` as a plain-text block with aligned samples, headed `Legend`.
+pub fn legend_block(entries: &[LegendEntry]) -> String {
+    let width = entries.iter().map(|entry| entry.sample.chars().count()).max().unwrap_or(0);
+    let mut out = "Legend".to_owned();
+    for entry in entries {
+        out.push_str(&format!("\n  {:<width$}  {}", entry.sample, entry.meaning));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diagram_legend, legend_block, LegendMarker};
+    use crate::format::mermaid::parse_flowchart;
+    use crate::model::{
+        CategoryPath, Diagram, DiagramAst, DiagramId, ObjectId, ObjectRef, Session, SessionId,
+        StyleEffect, XRef, XRefId, XRefStatus,
+    };
+
+    #[test]
+    fn legend_lists_present_markers_and_active_style_rules() {
+        let ast = parse_flowchart("flowchart TD\nA --> B\n").expect("parse");
+        let flow_id = DiagramId::new("flow").expect("diagram id");
+        let diagram = Diagram::new(flow_id.clone(), "Flow", DiagramAst::Flowchart(ast));
+        let mut session = Session::new(SessionId::new("s").expect("session id"));
+        let node = |diagram_id: &DiagramId, node_id: &str| {
+            ObjectRef::new(
+                diagram_id.clone(),
+                CategoryPath::new(vec!["flow".to_owned(), "node".to_owned()]).expect("category"),
+                ObjectId::new(node_id).expect("node id"),
+            )
+        };
+        let other_id = DiagramId::new("other").expect("diagram id");
+        session.xrefs_mut().insert(
+            XRefId::new("x:1").expect("xref id"),
+            XRef::new(node(&flow_id, "n:A"), node(&other_id, "n:Z"), "calls", XRefStatus::Ok),
+        );
+        session.set_style_rules(["degree>=1 => heavy".parse().expect("rule")]);
+
+        let entries = diagram_legend(&session, &diagram);
+        let samples = entries.iter().map(|entry| entry.sample.as_str()).collect::<Vec<_>>();
+        assert_eq!(samples, vec!["▴ label", "focus", "agent", "selected", "degree>=1"]);
+        assert_eq!(entries[4].marker, LegendMarker::StyleRule(StyleEffect::Heavy));
+
+        let block = legend_block(&entries);
+        assert!(block.starts_with("Legend\n  ▴ label    has outgoing xrefs\n"), "{block}");
+        assert!(block.ends_with("  degree>=1  rendered with heavy borders (style rule)"));
+    }
+}
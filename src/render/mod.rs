@@ -11,19 +11,27 @@
 //! Renderers produce Unicode/ASCII text output as well as a stable highlight index that the TUI
 //! and MCP integrations can use for cell-accurate selection/highlighting.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 
+use crate::layout::{FlowchartLayoutOptions, DEFAULT_CROSSING_SWEEPS};
 use crate::model::ObjectRef;
 
+pub mod budget;
+pub mod class;
 pub mod diagram;
 pub mod flowchart;
+pub mod golden;
+pub mod legend;
 pub mod sequence;
+pub mod state;
 #[cfg(test)]
 mod test_utils;
 mod text;
+pub mod tidy;
 pub mod walkthrough;
 
+pub use class::{render_class_unicode, ClassRenderError};
 pub use diagram::{render_diagram_unicode, render_diagram_unicode_annotated, DiagramRenderError};
 pub use flowchart::{
     render_flowchart_unicode, render_flowchart_unicode_annotated, FlowchartRenderError,
@@ -31,13 +39,86 @@ pub use flowchart::{
 pub use sequence::{
     render_sequence_unicode, render_sequence_unicode_annotated, SequenceRenderError,
 };
+pub use state::render_state_unicode;
 pub use walkthrough::{render_walkthrough_unicode, WalkthroughRenderError};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct RenderOptions {
     pub show_notes: bool,
     pub prefix_object_labels: bool,
     pub flowchart_extra_col_gap: usize,
+    /// Collapse redundant blank/straight-connector rows and columns after flowchart rendering.
+    pub flowchart_compact: bool,
+    pub flowchart_node_scale: FlowchartNodeScale,
+    pub flowchart_edge_glyphs: FlowchartEdgeGlyphs,
+    /// Crossing-reduction budget of the flowchart layout (see [`FlowchartLayoutOptions`]).
+    pub flowchart_crossing_sweeps: usize,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            show_notes: false,
+            prefix_object_labels: false,
+            flowchart_extra_col_gap: 0,
+            flowchart_compact: false,
+            flowchart_node_scale: FlowchartNodeScale::default(),
+            flowchart_edge_glyphs: FlowchartEdgeGlyphs::default(),
+            flowchart_crossing_sweeps: DEFAULT_CROSSING_SWEEPS,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Layout options for flowcharts rendered with these options.
+    pub fn flowchart_layout_options(&self) -> FlowchartLayoutOptions {
+        FlowchartLayoutOptions { crossing_sweeps: self.flowchart_crossing_sweeps }
+    }
+}
+
+/// How much of each flowchart node is drawn, for zooming out of graphs too large to read at 1:1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum FlowchartNodeScale {
+    /// A box around the full label.
+    #[default]
+    Full,
+    /// A box around the label truncated to a few characters.
+    Mini,
+    /// Only the first character of the label, with edges running straight into it.
+    Glyph,
+}
+
+/// Glyphs flowchart edges are drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum FlowchartEdgeGlyphs {
+    /// Box-drawing lines, one stroke per cell.
+    #[default]
+    Box,
+    /// Braille dots, 2×4 per cell, with corners cut diagonally.
+    Braille,
+    /// Quadrant blocks, 2×2 per cell, with corners cut diagonally.
+    Blocks,
+}
+
+impl FlowchartEdgeGlyphs {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "box" | "default" => Some(Self::Box),
+            "braille" => Some(Self::Braille),
+            "blocks" | "half-block" => Some(Self::Blocks),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FlowchartEdgeGlyphs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Box => "box",
+            Self::Braille => "braille",
+            Self::Blocks => "blocks",
+        })
+    }
 }
 
 /// A contiguous span of highlighted cells within a single rendered line.
@@ -49,6 +130,18 @@ pub type LineSpan = (usize, usize, usize);
 /// Mapping from stable object references to the spans that should be highlighted for that object.
 pub type HighlightIndex = BTreeMap<ObjectRef, Vec<LineSpan>>;
 
+/// Merges `(y, x)` cells into the fewest [`LineSpan`]s, in row-then-column order.
+pub(crate) fn cells_to_spans(cells: &BTreeSet<(usize, usize)>) -> Vec<LineSpan> {
+    let mut spans = Vec::<LineSpan>::new();
+    for &(y, x) in cells {
+        match spans.last_mut() {
+            Some((span_y, _, x1)) if *span_y == y && *x1 + 1 == x => *x1 = x,
+            _ => spans.push((y, x, x)),
+        }
+    }
+    spans
+}
+
 /// Render output plus an index suitable for stable, cell-accurate UI highlighting.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AnnotatedRender {
@@ -91,6 +184,37 @@ pub(crate) fn clamp_highlight_index_to_text(highlight_index: &mut HighlightIndex
     });
 }
 
+/// The object owning each cell of `text`, row by row.
+///
+/// Where spans of several objects overlap, the narrowest span owns the cell, so a label wins over
+/// the box or section drawn around it.
+pub fn highlight_cell_owners<'a>(
+    text: &str,
+    highlight_index: &'a HighlightIndex,
+) -> Vec<Vec<Option<&'a ObjectRef>>> {
+    let mut owners = text
+        .split('\n')
+        .map(|line| vec![None::<(usize, &ObjectRef)>; text::text_len(line)])
+        .collect::<Vec<_>>();
+    for (object_ref, spans) in highlight_index {
+        for &(y, x0, x1) in spans {
+            let Some(row) = owners.get_mut(y) else {
+                continue;
+            };
+            let width = x1.saturating_sub(x0);
+            for cell in row.iter_mut().take(x1.saturating_add(1)).skip(x0) {
+                if cell.map_or(true, |(owner_width, _)| width < owner_width) {
+                    *cell = Some((width, object_ref));
+                }
+            }
+        }
+    }
+    owners
+        .into_iter()
+        .map(|row| row.into_iter().map(|cell| cell.map(|(_, object_ref)| object_ref)).collect())
+        .collect()
+}
+
 pub const UNICODE_BOX_HORIZONTAL: char = '─';
 pub const UNICODE_BOX_VERTICAL: char = '│';
 pub const UNICODE_BOX_TOP_LEFT: char = '┌';
@@ -102,6 +226,10 @@ pub const UNICODE_BOX_TEE_LEFT: char = '┤';
 pub const UNICODE_BOX_TEE_DOWN: char = '┬';
 pub const UNICODE_BOX_TEE_UP: char = '┴';
 pub const UNICODE_BOX_CROSS: char = '┼';
+pub const UNICODE_DOTTED_HORIZONTAL: char = '┄';
+pub const UNICODE_DOTTED_VERTICAL: char = '┆';
+pub const UNICODE_THICK_HORIZONTAL: char = '━';
+pub const UNICODE_THICK_VERTICAL: char = '┃';
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct BoxEdges(u8);
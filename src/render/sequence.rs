@@ -9,18 +9,19 @@
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 
+use crate::layout::sequence::{SequenceActivationLayout, SequenceNoteLayout};
 use crate::layout::SequenceLayout;
 use crate::model::ids::{DiagramId, ObjectId};
 use crate::model::seq_ast::{
-    SequenceAst, SequenceBlock, SequenceBlockKind, SequenceMessage, SequenceMessageKind,
-    SequenceSection, SequenceSectionKind,
+    SequenceAst, SequenceBlock, SequenceBlockKind, SequenceMessage, SequenceMessageBoundary,
+    SequenceMessageKind, SequenceNote, SequenceNotePlacement, SequenceSection, SequenceSectionKind,
 };
 use crate::model::{CategoryPath, ObjectRef};
 
 use super::text::{text_len, truncate_with_ellipsis};
 use super::{
-    clamp_highlight_index_to_text, AnnotatedRender, Canvas, CanvasError, HighlightIndex, LineSpan,
-    RenderOptions,
+    cells_to_spans, clamp_highlight_index_to_text, AnnotatedRender, Canvas, CanvasError,
+    HighlightIndex, LineSpan, RenderOptions,
 };
 
 const BOX_HEIGHT_NO_NOTES: usize = 3;
@@ -45,6 +46,12 @@ const SELF_MESSAGE_LABEL_LEFT_PADDING: usize = 1;
 const SELF_MESSAGE_LABEL_PRE_CORNER_RESERVE: usize = 1;
 const SELF_MESSAGE_LABEL_RIGHT_RESERVE: usize = 1 + SELF_MESSAGE_LABEL_PRE_CORNER_RESERVE;
 const OBJECT_LABEL_PREFIX: &str = "▴ ";
+const BOUNDARY_MESSAGE_DOT: char = '●';
+const GROUP_FRAME_TITLE_ROWS: usize = 1;
+const NOTE_BOX_HEIGHT: usize = 3;
+const NOTE_TEXT_PADDING: usize = 1;
+const NOTE_SIDE_GAP: usize = 2;
+const NOTE_OVER_OVERHANG: usize = 2;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct ParticipantRender<'a> {
@@ -63,6 +70,7 @@ pub enum SequenceRenderError {
     Canvas(CanvasError),
     MissingParticipant { participant_id: ObjectId },
     MissingMessage { message_id: ObjectId },
+    MissingNote { note_id: ObjectId },
     InvalidParticipantColumn { col: usize },
     InvalidBlockMembership { block_id: ObjectId, reason: String },
 }
@@ -75,6 +83,7 @@ impl fmt::Display for SequenceRenderError {
                 write!(f, "missing participant {participant_id} in AST")
             }
             Self::MissingMessage { message_id } => write!(f, "missing message {message_id} in AST"),
+            Self::MissingNote { note_id } => write!(f, "missing note {note_id} in AST"),
             Self::InvalidParticipantColumn { col } => {
                 write!(f, "invalid participant column: {col}")
             }
@@ -116,6 +125,8 @@ pub fn render_sequence_unicode_with_options(
     options: RenderOptions,
 ) -> Result<String, SequenceRenderError> {
     let box_height = seq_box_height(options);
+    let box_top_y = seq_box_top_y(ast);
+    let header_bottom_y = box_top_y + box_height;
     let participants = participants_in_col_order(layout);
     let messages_by_id = messages_by_id(ast);
 
@@ -129,8 +140,16 @@ pub fn render_sequence_unicode_with_options(
         let name = participant.mermaid_name();
         let note = if options.show_notes { participant.note() } else { None };
 
-        let (box_inner_width, box_total_width) = box_widths_prefixed(name, options);
+        let (mut box_inner_width, mut box_total_width) = box_widths_prefixed(name, options);
         let box_x0 = cursor_x;
+        let title_extra = group_title_extra_width(
+            ast,
+            participant_id,
+            &participant_renders,
+            (box_x0, box_x0 + box_total_width - 1),
+        );
+        box_inner_width += title_extra;
+        box_total_width += title_extra;
         let box_x1 = box_x0 + box_total_width - 1;
         let lifeline_x = box_x0 + (box_total_width / 2);
 
@@ -148,9 +167,14 @@ pub fn render_sequence_unicode_with_options(
         cursor_x = box_x1 + 1 + COL_GAP;
     }
 
-    let width = participant_renders.last().map(|p| p.box_x1 + 1 + RIGHT_MARGIN).unwrap_or(1);
+    let note_boxes = place_note_boxes(ast, layout, &mut participant_renders, options)?;
+    let width = participant_renders
+        .last()
+        .map(|p| p.box_x1 + 1 + RIGHT_MARGIN)
+        .unwrap_or(1)
+        .max(note_boxes.iter().map(|note| note.x1 + 1).max().unwrap_or(0));
 
-    let message_top_y = box_height + HEADER_GAP;
+    let message_top_y = header_bottom_y + HEADER_GAP;
     let self_loop_rows = collect_self_loop_rows(layout);
     let row_y_by_row = build_message_row_positions_with_spacing_budget(
         ast,
@@ -159,7 +183,7 @@ pub fn render_sequence_unicode_with_options(
         &self_loop_rows,
     )?;
     let bottom_y =
-        compute_sequence_bottom_y(ast, layout, &row_y_by_row, &self_loop_rows, box_height)?;
+        compute_sequence_bottom_y(ast, layout, &row_y_by_row, &self_loop_rows, header_bottom_y)?;
     let height = bottom_y + 1;
 
     // Layer 1: participants, lifelines, and message connectors/text.
@@ -169,13 +193,13 @@ pub fn render_sequence_unicode_with_options(
     let next_lifeline_x_by_col = next_lifeline_x_by_col(&participant_renders);
 
     for p in &participant_renders {
-        connector_layer.draw_box(p.box_x0, 0, p.box_x1, box_height - 1)?;
+        connector_layer.draw_box(p.box_x0, box_top_y, p.box_x1, header_bottom_y - 1)?;
 
         let display_name = prefixed_object_label(p.name, options);
         let name_len = text_len(&display_name);
         let left_pad = (p.box_inner_width.saturating_sub(name_len)) / 2;
         let name_x = p.box_x0 + 1 + left_pad;
-        connector_layer.write_str(name_x, 1, &display_name)?;
+        connector_layer.write_str(name_x, box_top_y + 1, &display_name)?;
 
         if options.show_notes {
             if let Some(note) = p.note {
@@ -183,14 +207,18 @@ pub fn render_sequence_unicode_with_options(
                 let clipped_len = text_len(&clipped);
                 let left_pad = (p.box_inner_width.saturating_sub(clipped_len)) / 2;
                 let note_x = p.box_x0 + 1 + left_pad;
-                connector_layer.write_str(note_x, 2, &clipped)?;
+                connector_layer.write_str(note_x, box_top_y + 2, &clipped)?;
             }
         }
 
-        connector_layer.draw_vline(p.lifeline_x, box_height, bottom_y)?;
+        connector_layer.draw_vline(p.lifeline_x, header_bottom_y, bottom_y)?;
         lifeline_x_by_col.insert(p.col, p.lifeline_x);
     }
 
+    for frame in group_frames(ast, &participant_renders, header_bottom_y) {
+        draw_group_frame(&mut connector_layer, &frame)?;
+    }
+
     for msg_layout in layout.messages() {
         let msg = messages_by_id.get(msg_layout.message_id()).ok_or_else(|| {
             SequenceRenderError::MissingMessage { message_id: msg_layout.message_id().clone() }
@@ -207,6 +235,18 @@ pub fn render_sequence_unicode_with_options(
         let message_text = prefixed_object_label(msg.text(), options);
         let self_right_limit =
             self_message_right_limit(msg_layout.from_col(), &next_lifeline_x_by_col, width);
+        if let Some(boundary) = msg.boundary() {
+            draw_boundary_message(
+                &mut connector_layer,
+                from_x,
+                y,
+                msg.kind(),
+                boundary,
+                &message_text,
+                self_right_limit,
+            )?;
+            continue;
+        }
         let self_stub_len_target =
             self_message_stub_len_target(layout, msg_layout.message_id(), &message_text);
         draw_message(
@@ -221,6 +261,25 @@ pub fn render_sequence_unicode_with_options(
         )?;
     }
 
+    for note in &note_boxes {
+        let y = row_y_for(note.row, &row_y_by_row, message_top_y);
+        draw_note(&mut connector_layer, note, y)?;
+    }
+
+    let mut activation_bars = Vec::with_capacity(layout.activations().len());
+    for activation in layout.activations() {
+        let x = *lifeline_x_by_col
+            .get(&activation.col())
+            .ok_or(SequenceRenderError::InvalidParticipantColumn { col: activation.col() })?;
+        let y_range = activation_y_range(
+            activation,
+            &row_y_by_row,
+            message_top_y,
+            (header_bottom_y, bottom_y),
+        );
+        activation_bars.push((x, y_range));
+    }
+
     // Layer 2: block/section frame geometry.
     let mut frame_layer = Canvas::new(width, height)?;
     let overlays = draw_sequence_block_decorations(
@@ -235,7 +294,12 @@ pub fn render_sequence_unicode_with_options(
     blend_low_priority_layer(&mut composited, &frame_layer)?;
 
     // Layer 3: labels are projected as final overlays by `canvas_to_string_trimmed_with_overlays`.
-    Ok(canvas_to_string_trimmed_with_overlays(&composited, &overlays))
+    let text = canvas_to_string_trimmed_with_overlays(&composited, &overlays);
+    // Activation bars thicken what is left of the lifelines once everything else is drawn.
+    if activation_bars.is_empty() {
+        return Ok(text);
+    }
+    Ok(thicken_activation_bars(&text, &activation_bars))
 }
 
 pub fn render_sequence_unicode_annotated(
@@ -258,6 +322,8 @@ pub fn render_sequence_unicode_annotated_with_options(
     options: RenderOptions,
 ) -> Result<AnnotatedRender, SequenceRenderError> {
     let box_height = seq_box_height(options);
+    let box_top_y = seq_box_top_y(ast);
+    let header_bottom_y = box_top_y + box_height;
     let text = render_sequence_unicode_with_options(ast, layout, options)?;
 
     let participants = participants_in_col_order(layout);
@@ -271,8 +337,16 @@ pub fn render_sequence_unicode_annotated_with_options(
         let name = participant.mermaid_name();
         let note = if options.show_notes { participant.note() } else { None };
 
-        let (box_inner_width, box_total_width) = box_widths_prefixed(name, options);
+        let (mut box_inner_width, mut box_total_width) = box_widths_prefixed(name, options);
         let box_x0 = cursor_x;
+        let title_extra = group_title_extra_width(
+            ast,
+            participant_id,
+            &participant_renders,
+            (box_x0, box_x0 + box_total_width - 1),
+        );
+        box_inner_width += title_extra;
+        box_total_width += title_extra;
         let box_x1 = box_x0 + box_total_width - 1;
         let lifeline_x = box_x0 + (box_total_width / 2);
 
@@ -290,9 +364,14 @@ pub fn render_sequence_unicode_annotated_with_options(
         cursor_x = box_x1 + 1 + COL_GAP;
     }
 
-    let width = participant_renders.last().map(|p| p.box_x1 + 1 + RIGHT_MARGIN).unwrap_or(1);
+    let note_boxes = place_note_boxes(ast, layout, &mut participant_renders, options)?;
+    let width = participant_renders
+        .last()
+        .map(|p| p.box_x1 + 1 + RIGHT_MARGIN)
+        .unwrap_or(1)
+        .max(note_boxes.iter().map(|note| note.x1 + 1).max().unwrap_or(0));
 
-    let message_top_y = box_height + HEADER_GAP;
+    let message_top_y = header_bottom_y + HEADER_GAP;
     let self_loop_rows = collect_self_loop_rows(layout);
     let row_y_by_row = build_message_row_positions_with_spacing_budget(
         ast,
@@ -301,7 +380,7 @@ pub fn render_sequence_unicode_annotated_with_options(
         &self_loop_rows,
     )?;
     let bottom_y =
-        compute_sequence_bottom_y(ast, layout, &row_y_by_row, &self_loop_rows, box_height)?;
+        compute_sequence_bottom_y(ast, layout, &row_y_by_row, &self_loop_rows, header_bottom_y)?;
 
     let mut lifeline_x_by_col = BTreeMap::<usize, usize>::new();
     for p in &participant_renders {
@@ -319,6 +398,10 @@ pub fn render_sequence_unicode_annotated_with_options(
         CategoryPath::new(vec!["seq".to_owned(), "block".to_owned()]).expect("valid");
     let seq_section_category =
         CategoryPath::new(vec!["seq".to_owned(), "section".to_owned()]).expect("valid");
+    let seq_group_category =
+        CategoryPath::new(vec!["seq".to_owned(), "group".to_owned()]).expect("valid");
+    let seq_activation_category =
+        CategoryPath::new(vec!["seq".to_owned(), "activation".to_owned()]).expect("valid");
 
     let mut highlight_index = HighlightIndex::new();
     let mut connector_object_refs = Vec::<ObjectRef>::new();
@@ -333,10 +416,10 @@ pub fn render_sequence_unicode_annotated_with_options(
         );
 
         let mut spans = Vec::<LineSpan>::new();
-        for y in 0..box_height {
+        for y in box_top_y..header_bottom_y {
             spans.push((y, p.box_x0, p.box_x1));
         }
-        for y in box_height..=bottom_y {
+        for y in header_bottom_y..=bottom_y {
             spans.push((y, p.lifeline_x, p.lifeline_x));
         }
 
@@ -352,7 +435,7 @@ pub fn render_sequence_unicode_annotated_with_options(
                 if clipped_len > 0 {
                     let left_pad = (p.box_inner_width.saturating_sub(clipped_len)) / 2;
                     let note_x = p.box_x0 + 1 + left_pad;
-                    let note_y = 2usize;
+                    let note_y = box_top_y + 2;
                     let note_ref = ObjectRef::new(
                         diagram_id.clone(),
                         seq_note_category.clone(),
@@ -378,7 +461,13 @@ pub fn render_sequence_unicode_annotated_with_options(
 
         let y = row_y_for(msg_layout.row(), &row_y_by_row, message_top_y);
         let mut spans = Vec::<LineSpan>::new();
-        if from_x == to_x {
+        if msg_layout.boundary().is_some() {
+            let right_limit =
+                self_message_right_limit(msg_layout.from_col(), &next_lifeline_x_by_col, width);
+            if let Some(end_x) = boundary_message_end(from_x, right_limit) {
+                spans.push((y, from_x, end_x));
+            }
+        } else if from_x == to_x {
             let self_right_limit =
                 self_message_right_limit(msg_layout.from_col(), &next_lifeline_x_by_col, width);
             let self_stub_len_target = layout
@@ -412,6 +501,47 @@ pub fn render_sequence_unicode_annotated_with_options(
         highlight_index.insert(object_ref, spans);
     }
 
+    for activation in layout.activations() {
+        let x = *lifeline_x_by_col
+            .get(&activation.col())
+            .ok_or(SequenceRenderError::InvalidParticipantColumn { col: activation.col() })?;
+        let (y0, y1) = activation_y_range(
+            activation,
+            &row_y_by_row,
+            message_top_y,
+            (header_bottom_y, bottom_y),
+        );
+        let object_ref = ObjectRef::new(
+            diagram_id.clone(),
+            seq_activation_category.clone(),
+            activation.activation_id().clone(),
+        );
+        connector_object_refs.push(object_ref.clone());
+        highlight_index.insert(object_ref, (y0..=y1).map(|y| (y, x, x)).collect());
+    }
+
+    for frame in group_frames(ast, &participant_renders, header_bottom_y) {
+        let mut spans = vec![(0, frame.x0, frame.x1), (frame.y1, frame.x0, frame.x1)];
+        for y in 1..frame.y1 {
+            spans.push((y, frame.x0, frame.x0));
+            spans.push((y, frame.x1, frame.x1));
+        }
+        spans.sort();
+        let object_ref =
+            ObjectRef::new(diagram_id.clone(), seq_group_category.clone(), frame.group_id.clone());
+        connector_object_refs.push(object_ref.clone());
+        highlight_index.insert(object_ref, spans);
+    }
+
+    for note in &note_boxes {
+        let y = row_y_for(note.row, &row_y_by_row, message_top_y);
+        let spans = (y..y + NOTE_BOX_HEIGHT).map(|y| (y, note.x0, note.x1)).collect::<Vec<_>>();
+        let object_ref =
+            ObjectRef::new(diagram_id.clone(), seq_note_category.clone(), note.note_id.clone());
+        connector_object_refs.push(object_ref.clone());
+        highlight_index.insert(object_ref, spans);
+    }
+
     if !ast.blocks().is_empty() {
         let mut message_row_by_id = BTreeMap::<ObjectId, usize>::new();
         for msg in layout.messages() {
@@ -497,37 +627,6 @@ fn spans_to_cells(spans: &[LineSpan]) -> BTreeSet<(usize, usize)> {
     cells
 }
 
-fn cells_to_spans(cells: &BTreeSet<(usize, usize)>) -> Vec<LineSpan> {
-    let mut by_row = BTreeMap::<usize, Vec<usize>>::new();
-    for (y, x) in cells {
-        by_row.entry(*y).or_default().push(*x);
-    }
-
-    let mut spans = Vec::<LineSpan>::new();
-    for (y, mut xs) in by_row {
-        if xs.is_empty() {
-            continue;
-        }
-
-        xs.sort_unstable();
-        xs.dedup();
-
-        let mut start = xs[0];
-        let mut prev = xs[0];
-        for &x in xs.iter().skip(1) {
-            if x == prev.saturating_add(1) {
-                prev = x;
-                continue;
-            }
-            spans.push((y, start, prev));
-            start = x;
-            prev = x;
-        }
-        spans.push((y, start, prev));
-    }
-    spans
-}
-
 fn text_lines_as_cells(text: &str) -> Vec<Vec<char>> {
     text.split('\n').map(|line| line.chars().collect::<Vec<_>>()).collect::<Vec<_>>()
 }
@@ -639,7 +738,10 @@ fn inflate_row_positions_with_spacing_budget(
     layout: &SequenceLayout,
     row_y_by_row: &mut BTreeMap<usize, usize>,
 ) {
-    if layout.messages().is_empty() || row_y_by_row.is_empty() {
+    let Some(last_row) = layout.last_row() else {
+        return;
+    };
+    if row_y_by_row.is_empty() {
         return;
     }
 
@@ -648,7 +750,6 @@ fn inflate_row_positions_with_spacing_budget(
         return;
     }
 
-    let last_row = layout.messages().iter().map(|m| m.row()).max().unwrap_or(0);
     let mut cumulative_extra = 0usize;
 
     for row in 0..=last_row {
@@ -16,6 +16,15 @@ fn seq_box_height(options: RenderOptions) -> usize {
     }
 }
 
+/// First row of the participant header boxes; `box` groups reserve a title row above them.
+fn seq_box_top_y(ast: &SequenceAst) -> usize {
+    if ast.groups().iter().any(|group| !group.participant_ids().is_empty()) {
+        GROUP_FRAME_TITLE_ROWS
+    } else {
+        0
+    }
+}
+
 fn participants_in_col_order(layout: &SequenceLayout) -> Vec<(usize, &ObjectId)> {
     let mut participants = layout
         .participant_cols()
@@ -105,7 +114,7 @@ fn collect_self_loop_rows(layout: &SequenceLayout) -> BTreeSet<usize> {
     layout
         .messages()
         .iter()
-        .filter(|msg| msg.from_col() == msg.to_col())
+        .filter(|msg| msg.is_self_loop())
         .map(|msg| msg.row())
         .collect::<BTreeSet<_>>()
 }
@@ -125,11 +134,15 @@ fn compute_sequence_bottom_y(
     self_loop_rows: &BTreeSet<usize>,
     box_height: usize,
 ) -> Result<usize, SequenceRenderError> {
-    let Some(last_row) = layout.messages().iter().map(|m| m.row()).max() else {
+    let Some(last_row) = layout.last_row() else {
         return Ok(box_height);
     };
 
     let mut bottom_y = row_y_for(last_row, row_y_by_row, 0).saturating_add(1);
+    for note in layout.notes() {
+        let note_y = row_y_for(note.row(), row_y_by_row, 0);
+        bottom_y = bottom_y.max(note_y.saturating_add(NOTE_BOX_HEIGHT));
+    }
     if !ast.blocks().is_empty() {
         let message_row_by_id = message_row_map(layout);
         if let Some(block_bottom) =
@@ -149,9 +162,9 @@ fn build_message_row_positions(
     self_loop_rows: &BTreeSet<usize>,
 ) -> Result<BTreeMap<usize, usize>, SequenceRenderError> {
     let mut row_y_by_row = BTreeMap::<usize, usize>::new();
-    if layout.messages().is_empty() {
+    let Some(last_row) = layout.last_row() else {
         return Ok(row_y_by_row);
-    }
+    };
 
     let mut message_row_by_id = BTreeMap::<ObjectId, usize>::new();
     for msg in layout.messages() {
@@ -163,7 +176,7 @@ fn build_message_row_positions(
     let top_level_transition_rows =
         collect_top_level_block_transition_rows(ast, &message_row_by_id)?;
     let block_end_rows = collect_block_end_rows(ast, &message_row_by_id)?;
-    let last_row = layout.messages().iter().map(|m| m.row()).max().unwrap_or(0);
+    let note_rows = layout.notes().iter().map(|note| note.row()).collect::<BTreeSet<_>>();
     let mut post_block_row_extra = BTreeMap::<usize, usize>::new();
     for end_row in block_end_rows {
         let next_row = end_row.saturating_add(1);
@@ -208,6 +221,14 @@ fn build_message_row_positions(
         if let Some(extra) = post_block_row_extra.get(&row) {
             extra_rows = extra_rows.saturating_add(*extra);
         }
+        // Note boxes are taller than a message row; keep a blank line below them (and below a
+        // self-loop that a note follows).
+        if row > 0 && note_rows.contains(&(row - 1)) {
+            extra_rows = extra_rows.saturating_add(NOTE_BOX_HEIGHT - 1);
+        }
+        if row > 0 && note_rows.contains(&row) && self_loop_rows.contains(&(row - 1)) {
+            extra_rows = extra_rows.saturating_add(1);
+        }
         let y = message_top_y
             .saturating_add(row.saturating_mul(ROW_SPACING))
             .saturating_add(extra_rows);
@@ -491,6 +512,271 @@ fn draw_self_message(
     Ok(())
 }
 
+/// Right end of a found/lost message stub, sharing the self-message stub geometry.
+fn boundary_message_end(anchor_x: usize, right_limit: usize) -> Option<usize> {
+    self_message_stub_end(anchor_x, right_limit, None)
+}
+
+/// Draws a found (`●──◀│`, read right-to-left) or lost (`│──▶●`) message beside a
+/// lifeline.
+fn draw_boundary_message(
+    canvas: &mut Canvas,
+    anchor_x: usize,
+    y: usize,
+    kind: SequenceMessageKind,
+    boundary: SequenceMessageBoundary,
+    text: &str,
+    right_limit: usize,
+) -> Result<(), SequenceRenderError> {
+    let right_limit = right_limit.min(canvas.width().saturating_sub(1));
+    let Some(end_x) = boundary_message_end(anchor_x, right_limit) else {
+        return Ok(());
+    };
+
+    canvas.draw_hline(anchor_x, end_x.saturating_sub(1), y)?;
+    canvas.set(end_x, y, BOUNDARY_MESSAGE_DOT)?;
+    match boundary {
+        SequenceMessageBoundary::Found => {
+            let head_x = anchor_x + 1;
+            canvas.set(head_x, y, arrow_head(kind, ArrowDir::Left))?;
+            write_message_text(canvas, head_x + 1, end_x.saturating_sub(1), y, text)?;
+        }
+        SequenceMessageBoundary::Lost => {
+            let head_x = end_x.saturating_sub(1);
+            canvas.set(head_x, y, arrow_head(kind, ArrowDir::Right))?;
+            write_message_text(canvas, anchor_x + 1, head_x.saturating_sub(1), y, text)?;
+        }
+    }
+    Ok(())
+}
+
+/// Timeline note box in canvas coordinates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct NoteBox<'a> {
+    note_id: &'a ObjectId,
+    row: usize,
+    x0: usize,
+    x1: usize,
+    text: String,
+}
+
+/// Places timeline note boxes beside/over their lifelines.
+///
+/// Participants are shifted right when a note would otherwise start left of the canvas edge.
+fn place_note_boxes<'a>(
+    ast: &'a SequenceAst,
+    layout: &SequenceLayout,
+    participant_renders: &mut [ParticipantRender<'_>],
+    options: RenderOptions,
+) -> Result<Vec<NoteBox<'a>>, SequenceRenderError> {
+    let notes_by_id =
+        ast.notes().iter().map(|note| (note.note_id(), note)).collect::<BTreeMap<_, _>>();
+    let lifeline_x_by_col =
+        participant_renders.iter().map(|p| (p.col, p.lifeline_x)).collect::<BTreeMap<_, _>>();
+
+    let mut extents = Vec::<(&SequenceNote, usize, isize, isize, String)>::new();
+    for note_layout in layout.notes() {
+        let note = *notes_by_id.get(note_layout.note_id()).ok_or_else(|| {
+            SequenceRenderError::MissingNote { note_id: note_layout.note_id().clone() }
+        })?;
+        let text = prefixed_object_label(note.text(), options);
+        let (x0, x1) = note_box_extent(note_layout, &lifeline_x_by_col, text_len(&text))?;
+        extents.push((note, note_layout.row(), x0, x1, text));
+    }
+
+    let shift = extents.iter().map(|(_, _, x0, _, _)| -*x0).max().unwrap_or(0).max(0);
+    if shift > 0 {
+        for p in participant_renders.iter_mut() {
+            p.box_x0 += shift as usize;
+            p.box_x1 += shift as usize;
+            p.lifeline_x += shift as usize;
+        }
+    }
+
+    Ok(extents
+        .into_iter()
+        .map(|(note, row, x0, x1, text)| NoteBox {
+            note_id: note.note_id(),
+            row,
+            x0: (x0 + shift) as usize,
+            x1: (x1 + shift) as usize,
+            text,
+        })
+        .collect())
+}
+
+/// Unclamped horizontal extent of a note box holding `text_width` cells of text.
+fn note_box_extent(
+    note: &SequenceNoteLayout,
+    lifeline_x_by_col: &BTreeMap<usize, usize>,
+    text_width: usize,
+) -> Result<(isize, isize), SequenceRenderError> {
+    let lifeline_x = |col: usize| {
+        lifeline_x_by_col
+            .get(&col)
+            .map(|x| *x as isize)
+            .ok_or(SequenceRenderError::InvalidParticipantColumn { col })
+    };
+    let box_width = (text_width + 2 * (1 + NOTE_TEXT_PADDING)) as isize;
+    let gap = NOTE_SIDE_GAP as isize;
+
+    Ok(match note.placement() {
+        SequenceNotePlacement::LeftOf => {
+            let x1 = lifeline_x(note.from_col())? - gap;
+            (x1 - box_width + 1, x1)
+        }
+        SequenceNotePlacement::RightOf => {
+            let x0 = lifeline_x(note.from_col())? + gap;
+            (x0, x0 + box_width - 1)
+        }
+        SequenceNotePlacement::Over => {
+            let overhang = NOTE_OVER_OVERHANG as isize;
+            let mut x0 = lifeline_x(note.from_col())? - overhang;
+            let mut x1 = lifeline_x(note.to_col())? + overhang;
+            let extra = box_width - (x1 - x0 + 1);
+            if extra > 0 {
+                x0 -= extra / 2;
+                x1 += extra - extra / 2;
+            }
+            (x0, x1)
+        }
+    })
+}
+
+/// Draws a note box over whatever lies beneath it; crossed lifelines end in tees on its border.
+fn draw_note(canvas: &mut Canvas, note: &NoteBox<'_>, y: usize) -> Result<(), SequenceRenderError> {
+    let y1 = y + NOTE_BOX_HEIGHT - 1;
+    let crossed = (note.x0 + 1..note.x1)
+        .filter(|x| canvas.has_box_vertical(*x, y).unwrap_or(false))
+        .collect::<Vec<_>>();
+
+    for cy in y..=y1 {
+        for x in note.x0..=note.x1 {
+            canvas.set_exact(x, cy, ' ')?;
+        }
+    }
+    canvas.draw_box(note.x0, y, note.x1, y1)?;
+    for x in crossed {
+        canvas.set_exact(x, y, super::UNICODE_BOX_TEE_UP)?;
+        canvas.set_exact(x, y1, super::UNICODE_BOX_TEE_DOWN)?;
+    }
+
+    let inset = 1 + NOTE_TEXT_PADDING;
+    write_message_text(canvas, note.x0 + inset, note.x1.saturating_sub(inset), y + 1, &note.text)?;
+    Ok(())
+}
+
+/// Lifeline rows `(y0, y1)` covered by an activation bar; open ends reach the header or the
+/// bottom of the diagram.
+fn activation_y_range(
+    activation: &SequenceActivationLayout,
+    row_y_by_row: &BTreeMap<usize, usize>,
+    message_top_y: usize,
+    (header_bottom_y, bottom_y): (usize, usize),
+) -> (usize, usize) {
+    let y_of = |row| row_y_for(row, row_y_by_row, message_top_y);
+    let y0 = activation.start_row().map_or(header_bottom_y, y_of);
+    let y1 = activation.end_row().map_or(bottom_y, y_of);
+    (y0, y1.max(y0))
+}
+
+/// Thickens the plain lifeline cells `(x, y0..=y1)` of every activation bar in the rendered text;
+/// message, note and frame cells crossing a lifeline are left alone.
+fn thicken_activation_bars(text: &str, bars: &[(usize, (usize, usize))]) -> String {
+    let mut lines = text_lines_as_cells(text);
+    for &(x, (y0, y1)) in bars {
+        for line in lines.iter_mut().take(y1 + 1).skip(y0) {
+            if let Some(ch) = line.get_mut(x).filter(|ch| **ch == super::UNICODE_BOX_VERTICAL) {
+                *ch = super::UNICODE_THICK_VERTICAL;
+            }
+        }
+    }
+    lines.iter().map(|line| line.iter().collect::<String>()).collect::<Vec<_>>().join("\n")
+}
+
+/// Frame around the header boxes of one participant group; spans rows `0..=y1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct GroupFrame<'a> {
+    group_id: &'a ObjectId,
+    title: Option<&'a str>,
+    x0: usize,
+    x1: usize,
+    y1: usize,
+}
+
+fn group_frames<'a>(
+    ast: &'a SequenceAst,
+    participant_renders: &[ParticipantRender<'_>],
+    header_bottom_y: usize,
+) -> Vec<GroupFrame<'a>> {
+    ast.groups()
+        .iter()
+        .filter_map(|group| {
+            let members = participant_renders.iter().filter(|p| {
+                ast.participant_group(p.participant_id)
+                    .is_some_and(|owner| owner.group_id() == group.group_id())
+            });
+            let (x0, x1) = members.fold(None, |bounds: Option<(usize, usize)>, p| {
+                Some(bounds.map_or((p.box_x0, p.box_x1), |(x0, x1)| {
+                    (x0.min(p.box_x0), x1.max(p.box_x1))
+                }))
+            })?;
+            Some(GroupFrame {
+                group_id: group.group_id(),
+                title: group.title(),
+                x0: x0.saturating_sub(1),
+                x1: x1 + 1,
+                y1: header_bottom_y,
+            })
+        })
+        .collect()
+}
+
+/// Extra box width the last member of a titled `box` group needs so the group frame fits
+/// `┌─ title ─┐`; `box_x0..=box_x1` is the member's box before widening.
+fn group_title_extra_width(
+    ast: &SequenceAst,
+    participant_id: &ObjectId,
+    placed: &[ParticipantRender<'_>],
+    (box_x0, box_x1): (usize, usize),
+) -> usize {
+    let Some(group) = ast.participant_group(participant_id) else {
+        return 0;
+    };
+    let Some(title) = group.title() else {
+        return 0;
+    };
+    let in_group = |id: &ObjectId| {
+        ast.participant_group(id).is_some_and(|owner| owner.group_id() == group.group_id())
+    };
+    // Members take adjacent columns, so the placed ones are a run at the end of `placed`.
+    let placed_members =
+        placed.iter().rev().take_while(|p| in_group(p.participant_id)).collect::<Vec<_>>();
+    let member_count = ast.participants().keys().filter(|id| in_group(id)).count();
+    if placed_members.len() + 1 < member_count {
+        return 0;
+    }
+    let group_x0 = placed_members.last().map_or(box_x0, |first| first.box_x0);
+    // The frame spans one cell beyond the member boxes on each side.
+    let frame_width = box_x1 - group_x0 + 3;
+    (text_len(title) + 6).saturating_sub(frame_width)
+}
+
+/// Draws `┌─ title ──┐` above the member boxes; its bottom edge tees into the lifelines.
+fn draw_group_frame(canvas: &mut Canvas, frame: &GroupFrame<'_>) -> Result<(), CanvasError> {
+    canvas.draw_box(frame.x0, 0, frame.x1, frame.y1)?;
+    let Some(title) = frame.title else {
+        return Ok(());
+    };
+    // Keep `┌─` and `─┐` around the padded title.
+    let available = (frame.x1 - frame.x0 + 1).saturating_sub(6);
+    if available == 0 {
+        return Ok(());
+    }
+    let clipped = truncate_with_ellipsis(title, available);
+    canvas.write_str(frame.x0 + 2, 0, &format!(" {clipped} "))
+}
+
 fn arrow_head(kind: SequenceMessageKind, dir: ArrowDir) -> char {
     match kind {
         SequenceMessageKind::Sync => match dir {
@@ -125,6 +125,7 @@ fn snapshot_participant_notes_toggle() {
             show_notes: false,
             prefix_object_labels: false,
             flowchart_extra_col_gap: 0,
+            ..RenderOptions::default()
         },
     )
     .expect("render");
@@ -136,7 +137,12 @@ fn snapshot_participant_notes_toggle() {
     let notes_on = render_sequence_unicode_with_options(
         &ast,
         &layout,
-        RenderOptions { show_notes: true, prefix_object_labels: false, flowchart_extra_col_gap: 0 },
+        RenderOptions {
+            show_notes: true,
+            prefix_object_labels: false,
+            flowchart_extra_col_gap: 0,
+            ..RenderOptions::default()
+        },
     )
     .expect("render");
     assert_eq!(
@@ -587,3 +593,166 @@ A->>B: Post\n";
         assert_highlight_spans_in_bounds(fixture_id, &annotated.text, &annotated.highlight_index);
     }
 }
+
+#[test]
+fn renders_found_and_lost_messages_beside_lifelines() {
+    let ast = parse_sequence_diagram(
+        "sequenceDiagram\nparticipant A\nparticipant B\n%% nereid:boundary=found\nA->>A: in\n\
+         %% nereid:boundary=lost\nB->>B: out\n",
+    )
+    .expect("parse");
+    let layout = layout_sequence(&ast).expect("layout");
+    let diagram_id = DiagramId::new("seq").expect("diagram id");
+    let annotated = render_sequence_unicode_annotated(&diagram_id, &ast, &layout).expect("render");
+    let lines =
+        annotated.text.split('\n').map(|l| l.chars().collect::<Vec<_>>()).collect::<Vec<_>>();
+
+    let message_cells = |message_id: &str| {
+        let object_ref: ObjectRef =
+            format!("d:seq/seq/message/{message_id}").parse().expect("message ref");
+        let spans = &annotated.highlight_index[&object_ref];
+        assert_eq!(spans.len(), 1, "{message_id} spans");
+        let (y, x0, x1) = spans[0];
+        lines[y][x0..=x1].iter().collect::<String>()
+    };
+
+    let found = message_cells("m:0001");
+    assert!(found.chars().nth(1) == Some('◀') && found.ends_with('●'), "found: {found}");
+    let lost = message_cells("m:0002");
+    assert!(lost.contains("out") && lost.ends_with("▶●"), "lost: {lost}");
+    assert_highlight_spans_in_bounds("found_lost", &annotated.text, &annotated.highlight_index);
+}
+
+#[test]
+fn renders_notes_left_right_and_over_lifelines() {
+    let ast = parse_sequence_diagram(
+        "sequenceDiagram\nparticipant A\nparticipant B\nNote left of A: pre\nA->>B: hi\nNote right of B: side\nNote over A,B: both\nNote over A: one\n",
+    )
+    .expect("parse");
+    let layout = layout_sequence(&ast).expect("layout");
+    let diagram_id = DiagramId::new("seq").expect("diagram id");
+    let annotated = render_sequence_unicode_annotated(&diagram_id, &ast, &layout).expect("render");
+
+    assert_eq!(
+        annotated.text,
+        concat!(
+            "      ┌───┐        ┌───┐\n",
+            "      │ A │        │ B │\n",
+            "      └───┘        └───┘\n",
+            "        │            │\n",
+            "        │            │\n",
+            "┌─────┐ │            │\n",
+            "│ pre │ │            │\n",
+            "└─────┘ │            │\n",
+            "        │            │\n",
+            "        ├────hi─────▶│\n",
+            "        │            │\n",
+            "        │            │ ┌──────┐\n",
+            "        │            │ │ side │\n",
+            "        │            │ └──────┘\n",
+            "        │            │\n",
+            "      ┌─┴────────────┴─┐\n",
+            "      │      both      │\n",
+            "      └─┬────────────┬─┘\n",
+            "        │            │\n",
+            "     ┌──┴──┐         │\n",
+            "     │ one │         │\n",
+            "     └──┬──┘         │\n",
+            "        │            │",
+        )
+    );
+
+    let note_ref: ObjectRef = "d:seq/seq/note/note:0003".parse().expect("note ref");
+    let spans = &annotated.highlight_index[&note_ref];
+    assert_eq!(spans.first(), Some(&(15, 6, 23)));
+    assert_eq!(spans.last(), Some(&(17, 6, 23)));
+    assert_highlight_spans_in_bounds("notes", &annotated.text, &annotated.highlight_index);
+}
+
+#[test]
+fn widens_single_participant_box_to_fit_group_title() {
+    let ast = parse_sequence_diagram(
+        "sequenceDiagram\nbox Aqua Payments team\nparticipant A\nend\nparticipant B\nA->>B: hi\n",
+    )
+    .expect("parse");
+    let layout = layout_sequence(&ast).expect("layout");
+    let diagram_id = DiagramId::new("seq").expect("diagram id");
+    let annotated = render_sequence_unicode_annotated(&diagram_id, &ast, &layout).expect("render");
+
+    assert_eq!(
+        annotated.text,
+        concat!(
+            "┌─ Payments team ─┐\n",
+            "│┌───────────────┐│       ┌───┐\n",
+            "││       A       ││       │ B │\n",
+            "│└───────────────┘│       └───┘\n",
+            "└────────┬────────┘         │\n",
+            "         │                  │\n",
+            "         ├───────hi────────▶│\n",
+            "         │                  │",
+        )
+    );
+    let group_ref: ObjectRef = "d:seq/seq/group/g:0001".parse().expect("group ref");
+    assert_eq!(annotated.highlight_index[&group_ref].last(), Some(&(4, 0, 18)));
+    assert_highlight_spans_in_bounds("group title", &annotated.text, &annotated.highlight_index);
+}
+
+#[test]
+fn renders_participant_group_frame_above_lifelines() {
+    let ast = parse_sequence_diagram(
+        "sequenceDiagram\nbox Back end\nparticipant C\nparticipant A\nend\nparticipant B\nA->>B: hi\nB->>C: go\n",
+    )
+    .expect("parse");
+    let layout = layout_sequence(&ast).expect("layout");
+    let diagram_id = DiagramId::new("seq").expect("diagram id");
+    let annotated = render_sequence_unicode_annotated(&diagram_id, &ast, &layout).expect("render");
+
+    assert_eq!(
+        annotated.text,
+        concat!(
+            "┌─ Back end ───────┐\n",
+            "│┌───┐        ┌───┐│       ┌───┐\n",
+            "││ C │        │ A ││       │ B │\n",
+            "│└───┘        └───┘│       └───┘\n",
+            "└──┬────────────┬──┘         │\n",
+            "   │            │            │\n",
+            "   │            ├────hi─────▶│\n",
+            "   │            │            │\n",
+            "   │◀───────────go───────────┤\n",
+            "   │            │            │",
+        )
+    );
+
+    let group_ref: ObjectRef = "d:seq/seq/group/g:0001".parse().expect("group ref");
+    let spans = &annotated.highlight_index[&group_ref];
+    assert_eq!(spans.first(), Some(&(0, 0, 1)));
+    assert_eq!(spans.last(), Some(&(4, 0, 19)));
+    assert_highlight_spans_in_bounds("group", &annotated.text, &annotated.highlight_index);
+}
+
+#[test]
+fn activation_bars_thicken_lifelines_and_are_highlightable() {
+    let ast = parse_sequence_diagram(
+        "sequenceDiagram\nAlice->>+Bob: Hi\nBob->>Bob: Think\nBob-->>-Alice: Bye\nAlice->>Bob: Ok\n",
+    )
+    .expect("parse");
+    let layout = layout_sequence(&ast).expect("layout");
+    let rendered = render_sequence_unicode(&ast, &layout).expect("render");
+    assert_eq!(
+        rendered,
+        " ┌───────┐        ┌─────┐\n │ Alice │        │ Bob │\n └───────┘        └─────┘\n     │               │\n     │               │\n     ├──────Hi──────▶┃\n     │               ┃\n     │               ┃Think──┐\n     │               ◀───────┘\n     │◁─────Bye──────┐\n     │               │\n     ├──────Ok──────▶│\n     │               │"
+    );
+
+    let diagram_id = DiagramId::new("seq").expect("diagram id");
+    let annotated = render_sequence_unicode_annotated(&diagram_id, &ast, &layout).expect("render");
+    assert_highlight_spans_in_bounds("activation", &annotated.text, &annotated.highlight_index);
+    let activation_ref = ObjectRef::new(
+        diagram_id,
+        crate::model::CategoryPath::new(vec!["seq".to_owned(), "activation".to_owned()])
+            .expect("category"),
+        ObjectId::new("act:0001").expect("activation id"),
+    );
+    let spans = &annotated.highlight_index[&activation_ref];
+    assert_eq!(spans.first(), Some(&(5, 21, 21)));
+    assert_eq!(spans.last(), Some(&(9, 21, 21)));
+}
@@ -0,0 +1,167 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Unicode rendering for state diagrams, on top of the flowchart renderer.
+
+use crate::layout::StateLayout;
+use crate::model::ids::DiagramId;
+use crate::model::{CategoryPath, ObjectRef};
+
+use super::flowchart::{
+    render_flowchart_unicode_annotated_with_options, render_flowchart_unicode_with_options,
+    FlowchartRenderError,
+};
+use super::{AnnotatedRender, RenderOptions};
+
+pub fn render_state_unicode(layout: &StateLayout) -> Result<String, FlowchartRenderError> {
+    render_state_unicode_with_options(layout, RenderOptions::default())
+}
+
+pub fn render_state_unicode_with_options(
+    layout: &StateLayout,
+    options: RenderOptions,
+) -> Result<String, FlowchartRenderError> {
+    render_flowchart_unicode_with_options(layout.flowchart(), layout.layout(), options)
+}
+
+/// Renders like [`render_state_unicode`], keyed by `state/node` and `state/transition` refs.
+pub fn render_state_unicode_annotated_with_options(
+    diagram_id: &DiagramId,
+    layout: &StateLayout,
+    options: RenderOptions,
+) -> Result<AnnotatedRender, FlowchartRenderError> {
+    let rendered = render_flowchart_unicode_annotated_with_options(
+        diagram_id,
+        layout.flowchart(),
+        layout.layout(),
+        options,
+    )?;
+    let highlight_index = rendered
+        .highlight_index
+        .into_iter()
+        .filter_map(|(object_ref, spans)| {
+            let category = match object_ref.category().segments() {
+                [_, kind] if kind == "node" => "node",
+                [_, kind] if kind == "edge" => "transition",
+                _ => return None,
+            };
+            let category = CategoryPath::new(vec!["state".to_owned(), category.to_owned()])
+                .expect("valid category");
+            let object_id = object_ref.object_id().clone();
+            Some((ObjectRef::new(object_ref.diagram_id().clone(), category, object_id), spans))
+        })
+        .collect();
+    Ok(AnnotatedRender { text: rendered.text, highlight_index })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_state_unicode_annotated_with_options;
+    use crate::format::mermaid::parse_state_diagram;
+    use crate::layout::layout_state;
+    use crate::model::{DiagramId, ObjectRef};
+    use crate::render::RenderOptions;
+
+    #[test]
+    fn renders_looping_states_with_pseudo_state_glyphs_and_state_refs() {
+        let ast = parse_state_diagram(
+            "stateDiagram-v2\n[*] --> Idle\nIdle --> Busy : work\nBusy --> Idle\nBusy --> [*]\n",
+        )
+        .expect("parse");
+        let layout = layout_state(&ast).expect("layout");
+        let diagram_id = DiagramId::new("d-state").expect("diagram id");
+        let rendered = render_state_unicode_annotated_with_options(
+            &diagram_id,
+            &layout,
+            RenderOptions::default(),
+        )
+        .expect("render");
+
+        for text in ["●", "◉", "Idle", "Busy"] {
+            assert!(rendered.text.contains(text), "missing {text:?} in\n{}", rendered.text);
+        }
+        let idle = ObjectRef::parse("d:d-state/state/node/s:Idle").expect("ref");
+        let work = ObjectRef::parse("d:d-state/state/transition/t:0001").expect("ref");
+        let back = ObjectRef::parse("d:d-state/state/transition/t:0002").expect("ref");
+        assert!(rendered.highlight_index.contains_key(&idle));
+        assert!(rendered.highlight_index.contains_key(&work));
+        assert!(rendered.highlight_index.contains_key(&back));
+        assert!(rendered
+            .highlight_index
+            .keys()
+            .all(|object_ref| object_ref.category().segments()[0] == "state"));
+    }
+
+    fn span_text(text: &str, (y, x0, x1): (usize, usize, usize)) -> String {
+        let line = text.lines().nth(y).expect("span line");
+        line.chars().skip(x0).take(x1 + 1 - x0).collect()
+    }
+
+    #[test]
+    fn highlight_spans_cover_state_boxes_and_transition_paths() {
+        let ast = parse_state_diagram(
+            "stateDiagram-v2\nstate check <<choice>>\n[*] --> check\ncheck --> Done : ok\n\
+             check --> Retry : fail\nRetry --> check\nDone --> [*]\n",
+        )
+        .expect("parse");
+        let layout = layout_state(&ast).expect("layout");
+        let diagram_id = DiagramId::new("d-state").expect("diagram id");
+        let rendered = render_state_unicode_annotated_with_options(
+            &diagram_id,
+            &layout,
+            RenderOptions::default(),
+        )
+        .expect("render");
+        let spans = |object_ref: &str| {
+            let object_ref = ObjectRef::parse(object_ref).expect("ref");
+            rendered.highlight_index.get(&object_ref).cloned().unwrap_or_default()
+        };
+        let covered = |object_ref: &str| {
+            spans(object_ref)
+                .into_iter()
+                .map(|span| span_text(&rendered.text, span))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        // Node spans cover the whole box, including the pseudo-state glyphs.
+        for (object_ref, label) in [
+            ("d:d-state/state/node/s:[start]", "●"),
+            ("d:d-state/state/node/s:[end]", "◉"),
+            ("d:d-state/state/node/s:check", "◇"),
+            ("d:d-state/state/node/s:Done", "Done"),
+            ("d:d-state/state/node/s:Retry", "Retry"),
+        ] {
+            let text = covered(object_ref);
+            assert!(text.contains(label), "{object_ref} spans miss {label:?}:\n{text}");
+        }
+
+        // Transition spans follow connector cells.
+        for transition in 0..5 {
+            let object_ref = format!("d:d-state/state/transition/t:{transition:04}");
+            let text = covered(&object_ref);
+            assert!(
+                text.chars().any(|ch| "─│┌┐└┘├┤┬┴┼▶◀▲▼".contains(ch)),
+                "{object_ref} covers no connector cells:\n{text}"
+            );
+        }
+
+        // Every indexed span lies inside the rendered text and there are no flowchart refs left.
+        let lines = rendered.text.lines().map(|line| line.chars().count()).collect::<Vec<_>>();
+        for (object_ref, spans) in &rendered.highlight_index {
+            assert_eq!(object_ref.category().segments()[0], "state");
+            for &(y, x0, x1) in spans {
+                assert!(
+                    y < lines.len() && x0 <= x1 && x1 < lines[y],
+                    "{object_ref}: {y},{x0},{x1}"
+                );
+            }
+        }
+        assert_eq!(rendered.highlight_index.len(), ast.states().len() + ast.transitions().len());
+    }
+}
@@ -0,0 +1,160 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Previews for tidying a flowchart layout.
+//!
+//! A tidy re-runs layout with extra crossing minimization and renders the result with the
+//! compaction pass. The MCP `diagram.tidy_layout` tool and the TUI `:tidy` command both show a
+//! [`TidyPreview`] first; applying it persists [`TidyPreview::order_hints`] as node order hints.
+
+use std::collections::BTreeMap;
+
+use crate::layout::{
+    count_flowchart_crossings, layout_flowchart_tidy_with_options, layout_flowchart_with_options,
+    FlowchartLayout,
+};
+use crate::model::flow_ast::FlowchartAst;
+use crate::model::ObjectId;
+
+use super::flowchart::render_flowchart_unicode_with_options;
+use super::text::text_len;
+use super::{DiagramRenderError, RenderOptions};
+
+/// Render size of one layout, without and with compaction, plus its edge crossings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TidyMetrics {
+    pub width: usize,
+    pub height: usize,
+    pub compacted_width: usize,
+    pub compacted_height: usize,
+    pub crossings: usize,
+}
+
+/// A node whose position within its layer changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TidyMove {
+    pub node_id: ObjectId,
+    pub layer: usize,
+    pub from_index: usize,
+    pub to_index: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TidyPreview {
+    /// Node order hints that reproduce the tidy layout.
+    pub order_hints: BTreeMap<ObjectId, u32>,
+    /// Whether `order_hints` differ from the hints the flowchart already has.
+    pub hints_changed: bool,
+    pub before: TidyMetrics,
+    pub after: TidyMetrics,
+    /// Nodes in layer order whose in-layer position changes.
+    pub moves: Vec<TidyMove>,
+    /// Compacted render with the tidy node order.
+    pub preview: String,
+}
+
+/// Computes the tidy layout of `ast` and measures it against the current one; every layout
+/// spends the crossing-sweep budget of `options`.
+pub fn tidy_flowchart(
+    ast: &FlowchartAst,
+    options: RenderOptions,
+) -> Result<TidyPreview, DiagramRenderError> {
+    let layout_options = options.flowchart_layout_options();
+    let before_layout = layout_flowchart_with_options(ast, layout_options)?;
+    let order_hints = layout_flowchart_tidy_with_options(ast, layout_options)?.order_hints();
+    let mut tidy_ast = ast.clone();
+    *tidy_ast.node_order_hints_mut() = order_hints.clone();
+    // Lay out again through the hints, exactly as the persisted tidy will be.
+    let after_layout = layout_flowchart_with_options(&tidy_ast, layout_options)?;
+
+    let (before, _) = measure(ast, &before_layout, options)?;
+    let (after, preview) = measure(&tidy_ast, &after_layout, options)?;
+
+    let mut moves = before_layout
+        .node_placements()
+        .iter()
+        .filter_map(|(node_id, placement)| {
+            let to_index = after_layout.node_placements().get(node_id)?.index_in_layer();
+            (to_index != placement.index_in_layer()).then(|| TidyMove {
+                node_id: node_id.clone(),
+                layer: placement.layer(),
+                from_index: placement.index_in_layer(),
+                to_index,
+            })
+        })
+        .collect::<Vec<_>>();
+    moves.sort_by_key(|mv| (mv.layer, mv.to_index));
+
+    Ok(TidyPreview {
+        hints_changed: ast.node_order_hints() != &order_hints,
+        order_hints,
+        before,
+        after,
+        moves,
+        preview,
+    })
+}
+
+/// Metrics of `layout` plus its compacted render.
+fn measure(
+    ast: &FlowchartAst,
+    layout: &FlowchartLayout,
+    options: RenderOptions,
+) -> Result<(TidyMetrics, String), DiagramRenderError> {
+    let render = |flowchart_compact| {
+        render_flowchart_unicode_with_options(
+            ast,
+            layout,
+            RenderOptions { flowchart_compact, ..options },
+        )
+    };
+    let size =
+        |text: &str| (text.split('\n').map(text_len).max().unwrap_or(0), text.split('\n').count());
+    let (width, height) = size(&render(false)?);
+    let compacted = render(true)?;
+    let (compacted_width, compacted_height) = size(&compacted);
+    let metrics = TidyMetrics {
+        width,
+        height,
+        compacted_width,
+        compacted_height,
+        crossings: count_flowchart_crossings(ast, layout),
+    };
+    Ok((metrics, compacted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tidy_flowchart;
+    use crate::format::mermaid::parse_flowchart;
+    use crate::model::ObjectId;
+    use crate::render::RenderOptions;
+
+    #[test]
+    fn tidy_reorders_crossing_nodes_and_reports_compaction() {
+        let mut ast =
+            parse_flowchart("flowchart LR\na --> d\na --> c\nb --> e\nd --> e\nb --> d\n")
+                .expect("parse flowchart");
+        let preview = tidy_flowchart(&ast, RenderOptions::default()).expect("tidy");
+
+        assert_eq!((preview.before.crossings, preview.after.crossings), (1, 0));
+        assert!(preview.hints_changed);
+        assert!(!preview.moves.is_empty());
+        assert!(preview.moves.iter().all(|mv| mv.from_index != mv.to_index));
+        assert!(preview.after.compacted_height <= preview.after.height);
+        assert!(preview.after.compacted_width <= preview.after.width);
+        assert_eq!(preview.preview.split('\n').count(), preview.after.compacted_height);
+
+        *ast.node_order_hints_mut() = preview.order_hints.clone();
+        let again = tidy_flowchart(&ast, RenderOptions::default()).expect("re-tidy");
+        assert!(!again.hints_changed);
+        assert!(again.moves.is_empty());
+        assert_eq!(again.before, preview.after);
+        assert!(again.order_hints.contains_key(&ObjectId::new("n:e").expect("node id")));
+    }
+}
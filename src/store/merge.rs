@@ -0,0 +1,337 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Three-way merge of a diagram edited locally while its file changed on disk.
+//!
+//! Flowchart nodes and edges and sequence participants and messages are merged object by object
+//! against the common base: an object changed on one side only takes that side's version. The
+//! rest of the diagram (subgraphs, blocks, notes, hints, ...) is merged as one unit, and state and
+//! class diagrams are merged whole. Edges both sides added under the same positional `e:NNNN` id
+//! are kept apart by renumbering the local one. An object changed differently on both sides, or
+//! an edge or message left pointing at an object the other side deleted, is a conflict.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::model::{DiagramAst, FlowchartAst, ObjectId, SequenceAst, SequenceMessage};
+use crate::ops::idgen;
+
+/// Which version wins for conflicting objects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeSide {
+    Local,
+    Disk,
+}
+
+/// An object (or, without `object_id`, the rest of the diagram) both sides changed differently.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    /// `node`, `edge`, `participant`, `message` or `diagram`.
+    pub kind: &'static str,
+    pub object_id: Option<ObjectId>,
+}
+
+impl MergeConflict {
+    fn object(kind: &'static str, object_id: &ObjectId) -> Self {
+        Self { kind, object_id: Some(object_id.clone()) }
+    }
+
+    fn diagram() -> Self {
+        Self { kind: "diagram", object_id: None }
+    }
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.object_id {
+            Some(object_id) => write!(f, "{} {object_id}", self.kind),
+            None => f.write_str("diagram structure"),
+        }
+    }
+}
+
+/// Merges the `local` and `disk` edits of `base`.
+///
+/// Without `prefer`, any conflict fails the merge and all of them are returned. With `prefer`,
+/// conflicting objects take the preferred side and edges or messages whose endpoints were deleted
+/// are dropped.
+pub fn merge_diagram_asts(
+    base: &DiagramAst,
+    local: &DiagramAst,
+    disk: &DiagramAst,
+    prefer: Option<MergeSide>,
+) -> Result<DiagramAst, Vec<MergeConflict>> {
+    let mut conflicts = Vec::new();
+    let merged = match (base, local, disk) {
+        (
+            DiagramAst::Flowchart(base),
+            DiagramAst::Flowchart(local),
+            DiagramAst::Flowchart(disk),
+        ) => {
+            let nodes = merge_maps(
+                "node",
+                [base.nodes(), local.nodes(), disk.nodes()],
+                prefer,
+                &mut conflicts,
+            );
+            let local_edges =
+                renumber_concurrent_additions("e", base.edges(), local.edges(), disk.edges());
+            let mut edges = merge_maps(
+                "edge",
+                [base.edges(), &local_edges, disk.edges()],
+                prefer,
+                &mut conflicts,
+            );
+            edges.retain(|edge_id, edge| {
+                let known = nodes.contains_key(edge.from_node_id())
+                    && nodes.contains_key(edge.to_node_id());
+                if !known && prefer.is_none() {
+                    conflicts.push(MergeConflict::object("edge", edge_id));
+                }
+                known
+            });
+            let strip = |ast: &FlowchartAst| {
+                let mut ast = ast.clone();
+                ast.nodes_mut().clear();
+                ast.edges_mut().clear();
+                ast
+            };
+            let mut merged =
+                merge_part(&strip(base), &strip(local), &strip(disk), prefer, &mut conflicts);
+            *merged.nodes_mut() = nodes;
+            *merged.edges_mut() = edges;
+            DiagramAst::Flowchart(merged)
+        }
+        (DiagramAst::Sequence(base), DiagramAst::Sequence(local), DiagramAst::Sequence(disk)) => {
+            let participants = merge_maps(
+                "participant",
+                [base.participants(), local.participants(), disk.participants()],
+                prefer,
+                &mut conflicts,
+            );
+            let by_id = |ast: &SequenceAst| {
+                ast.messages()
+                    .iter()
+                    .map(|message| (message.message_id().clone(), message.clone()))
+                    .collect::<BTreeMap<_, _>>()
+            };
+            let mut messages = merge_maps(
+                "message",
+                [&by_id(base), &by_id(local), &by_id(disk)],
+                prefer,
+                &mut conflicts,
+            )
+            .into_values()
+            .collect::<Vec<_>>();
+            messages.retain(|message| {
+                let known = participants.contains_key(message.from_participant_id())
+                    && participants.contains_key(message.to_participant_id());
+                if !known && prefer.is_none() {
+                    conflicts.push(MergeConflict::object("message", message.message_id()));
+                }
+                known
+            });
+            messages.sort_by(SequenceMessage::cmp_in_order);
+            let strip = |ast: &SequenceAst| {
+                let mut ast = ast.clone();
+                ast.participants_mut().clear();
+                ast.messages_mut().clear();
+                ast
+            };
+            let mut merged =
+                merge_part(&strip(base), &strip(local), &strip(disk), prefer, &mut conflicts);
+            *merged.participants_mut() = participants;
+            *merged.messages_mut() = messages;
+            let anchored = |message_id: Option<&ObjectId>, merged: &SequenceAst| {
+                message_id.map_or(true, |id| merged.messages().iter().any(|m| m.message_id() == id))
+            };
+            let stale = merged
+                .activations()
+                .iter()
+                .filter(|activation| {
+                    !merged.participants().contains_key(activation.participant_id())
+                        || !anchored(activation.start_message_id(), &merged)
+                        || !anchored(activation.end_message_id(), &merged)
+                })
+                .map(|activation| activation.activation_id().clone())
+                .collect::<Vec<_>>();
+            merged
+                .activations_mut()
+                .retain(|activation| !stale.contains(activation.activation_id()));
+            DiagramAst::Sequence(merged)
+        }
+        _ => merge_part(base, local, disk, prefer, &mut conflicts),
+    };
+
+    if conflicts.is_empty() {
+        Ok(merged)
+    } else {
+        Err(conflicts)
+    }
+}
+
+/// Merges `[base, local, disk]` object by object, recording a conflict per key both sides
+/// changed differently.
+fn merge_maps<T: Clone + PartialEq>(
+    kind: &'static str,
+    [base, local, disk]: [&BTreeMap<ObjectId, T>; 3],
+    prefer: Option<MergeSide>,
+    conflicts: &mut Vec<MergeConflict>,
+) -> BTreeMap<ObjectId, T> {
+    let ids = base.keys().chain(local.keys()).chain(disk.keys()).collect::<BTreeSet<_>>();
+    let mut merged = BTreeMap::new();
+    for id in ids {
+        let value =
+            merge_value(base.get(id), local.get(id), disk.get(id), prefer).unwrap_or_else(|()| {
+                conflicts.push(MergeConflict::object(kind, id));
+                local.get(id)
+            });
+        if let Some(value) = value {
+            merged.insert(id.clone(), value.clone());
+        }
+    }
+    merged
+}
+
+/// `local` with objects both sides added under the same numbered id moved to the next free
+/// `<prefix>:NNNN`, so positional ids minted on each side do not collide.
+fn renumber_concurrent_additions<T: Clone + PartialEq>(
+    prefix: &str,
+    base: &BTreeMap<ObjectId, T>,
+    local: &BTreeMap<ObjectId, T>,
+    disk: &BTreeMap<ObjectId, T>,
+) -> BTreeMap<ObjectId, T> {
+    let mut renumbered = local.clone();
+    for (id, value) in local {
+        if base.contains_key(id) || !disk.get(id).is_some_and(|other| other != value) {
+            continue;
+        }
+        renumbered.remove(id);
+        let next = idgen::numbered_object_id(prefix, 1, |candidate| {
+            [base, local, disk, &renumbered].iter().any(|map| map.contains_key(candidate))
+        });
+        renumbered.insert(next, value.clone());
+    }
+    renumbered
+}
+
+/// Merges a value that has no finer-grained identity, recording a `diagram` conflict.
+fn merge_part<T: Clone + PartialEq>(
+    base: &T,
+    local: &T,
+    disk: &T,
+    prefer: Option<MergeSide>,
+    conflicts: &mut Vec<MergeConflict>,
+) -> T {
+    let merged = merge_value(Some(base), Some(local), Some(disk), prefer).unwrap_or_else(|()| {
+        conflicts.push(MergeConflict::diagram());
+        Some(local)
+    });
+    merged.unwrap_or(local).clone()
+}
+
+/// The merged version of one value (`None` when absent), or `Err` when both sides changed it
+/// differently and nothing is preferred.
+fn merge_value<'a, T: PartialEq>(
+    base: Option<&'a T>,
+    local: Option<&'a T>,
+    disk: Option<&'a T>,
+    prefer: Option<MergeSide>,
+) -> Result<Option<&'a T>, ()> {
+    if local == disk || disk == base {
+        return Ok(local);
+    }
+    if local == base {
+        return Ok(disk);
+    }
+    match prefer {
+        Some(MergeSide::Local) => Ok(local),
+        Some(MergeSide::Disk) => Ok(disk),
+        None => Err(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_diagram_asts, MergeConflict, MergeSide};
+    use crate::format::mermaid::{parse_flowchart, parse_sequence_diagram};
+    use crate::model::{DiagramAst, ObjectId};
+
+    fn flow(src: &str) -> DiagramAst {
+        DiagramAst::Flowchart(parse_flowchart(src).expect("parse"))
+    }
+
+    fn node_labels(ast: &DiagramAst) -> Vec<(String, String)> {
+        let DiagramAst::Flowchart(ast) = ast else { panic!("flowchart") };
+        ast.nodes()
+            .iter()
+            .map(|(node_id, node)| (node_id.to_string(), node.label().to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn disjoint_flowchart_edits_merge_object_by_object() {
+        let base = flow("flowchart LR\nA[Start] --> B[End]\n");
+        let local = flow("flowchart LR\nA[Begin] --> B[End]\nB --> C[Local]\n");
+        let disk = flow("flowchart LR\nA[Start] --> B[Done]\nB --> D[Disk]\n");
+
+        let merged = merge_diagram_asts(&base, &local, &disk, None).expect("merge");
+        assert_eq!(
+            node_labels(&merged),
+            vec![
+                ("n:A".to_owned(), "Begin".to_owned()),
+                ("n:B".to_owned(), "Done".to_owned()),
+                ("n:C".to_owned(), "Local".to_owned()),
+                ("n:D".to_owned(), "Disk".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn conflicting_edits_are_reported_and_resolved_by_the_preferred_side() {
+        let base = flow("flowchart LR\nA[Start] --> B[End]\n");
+        let local = flow("flowchart LR\nA[Mine] --> B[End]\n");
+        let disk = flow("flowchart LR\nA[Theirs]\nB[End]\nB --> C[New]\n");
+
+        let conflicts = merge_diagram_asts(&base, &local, &disk, None).expect_err("conflict");
+        assert_eq!(
+            conflicts,
+            vec![MergeConflict { kind: "node", object_id: Some(ObjectId::new("n:A").unwrap()) }]
+        );
+        assert_eq!(conflicts[0].to_string(), "node n:A");
+
+        let mine = merge_diagram_asts(&base, &local, &disk, Some(MergeSide::Local)).expect("mine");
+        assert_eq!(node_labels(&mine)[0].1, "Mine");
+        assert_eq!(node_labels(&mine).len(), 3);
+        let theirs = merge_diagram_asts(&base, &local, &disk, Some(MergeSide::Disk)).expect("disk");
+        assert_eq!(node_labels(&theirs)[0].1, "Theirs");
+    }
+
+    #[test]
+    fn edges_and_messages_to_deleted_objects_conflict() {
+        let base = flow("flowchart LR\nA --> B\nC\n");
+        let local = flow("flowchart LR\nA --> B\n");
+        let disk = flow("flowchart LR\nA --> B\nB --> C\n");
+        let conflicts = merge_diagram_asts(&base, &local, &disk, None).expect_err("conflict");
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].kind, "edge");
+        let DiagramAst::Flowchart(mine) =
+            merge_diagram_asts(&base, &local, &disk, Some(MergeSide::Local)).expect("mine")
+        else {
+            panic!("flowchart");
+        };
+        assert_eq!(mine.edges().len(), 1);
+
+        let seq = |src: &str| DiagramAst::Sequence(parse_sequence_diagram(src).expect("parse"));
+        let base = seq("sequenceDiagram\nparticipant A\nparticipant B\nA->>B: hi\n");
+        let local = seq("sequenceDiagram\nparticipant A\nA->>A: self\n");
+        let disk = seq("sequenceDiagram\nparticipant A\nparticipant B\nA->>B: hello\n");
+        let conflicts = merge_diagram_asts(&base, &local, &disk, None).expect_err("conflict");
+        assert!(conflicts.iter().all(|conflict| conflict.kind == "message"), "{conflicts:?}");
+    }
+}
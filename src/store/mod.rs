@@ -9,11 +9,21 @@
 //! Persistence for sessions on disk.
 //!
 //! The store module reads/writes the session folder format (meta file plus diagram/walkthrough
-//! files) used by both the TUI and MCP server.
+//! files) used by both the TUI and MCP server, lists the session folders of a workspace and
+//! scaffolds new sessions from built-in templates.
 
+pub mod merge;
 pub mod session_folder;
+pub mod template;
+pub mod watcher;
+pub mod workspace;
 
+pub use merge::{merge_diagram_asts, MergeConflict, MergeSide};
 pub use session_folder::{
-    DiagramMeta, DiagramStableIdMap, DiagramXRef, SessionFolder, SessionMeta, SessionMetaDiagram,
-    StoreError, WriteDurability, XRefStatus,
+    DiagramMeta, DiagramStableIdMap, DiagramXRef, JournalActor, JournalEntry, LoadMode,
+    SessionFolder, SessionMeta, SessionMetaDiagram, StoreError, WriteDurability, XRefStatus,
+    ARCHIVE_VERSION,
 };
+pub use template::SessionTemplate;
+pub use watcher::SessionFolderWatcher;
+pub use workspace::{Workspace, WorkspaceSession};
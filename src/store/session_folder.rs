@@ -6,10 +6,12 @@
 // This file is part of Nereid and is proprietary software.
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use std::collections::hash_map::DefaultHasher;
 use std::collections::hash_map::Entry;
 use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 use std::fmt;
 use std::fs;
+use std::hash::{Hash, Hasher};
 use std::io;
 use std::io::Write;
 use std::path::{Component, Path, PathBuf};
@@ -19,22 +21,36 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
 
 use crate::format::mermaid::{
-    export_flowchart, export_sequence_diagram, parse_flowchart, parse_sequence_diagram,
-    MermaidFlowchartExportError, MermaidFlowchartParseError, MermaidSequenceExportError,
-    MermaidSequenceParseError,
+    anchored_ids, export_class_diagram, export_flowchart, export_sequence_diagram,
+    export_state_diagram, parse_class_diagram, parse_flowchart_with_id_rules,
+    parse_sequence_diagram_with_id_rules, parse_state_diagram, MermaidClassExportError,
+    MermaidClassParseError, MermaidFlowchartExportError, MermaidFlowchartParseError,
+    MermaidSequenceExportError, MermaidSequenceParseError, MermaidStateExportError,
+    MermaidStateParseError,
+};
+use crate::layout::{
+    layout_class, layout_flowchart, layout_sequence, layout_state, FlowchartLayoutError,
+    SequenceLayoutError,
 };
-use crate::layout::{layout_flowchart, layout_sequence, FlowchartLayoutError, SequenceLayoutError};
 use crate::model::{
-    Diagram, DiagramAst, DiagramId, DiagramKind, FlowEdge, FlowNode, FlowchartAst, IdError,
-    ObjectId, ObjectRef, ParseObjectRefError, SequenceAst, SequenceMessage, SequenceMessageKind,
-    Session, SessionId, Walkthrough, WalkthroughEdge, WalkthroughId, WalkthroughNode,
-    WalkthroughNodeId, XRef, XRefId, XRefStatus as ModelXRefStatus,
+    Diagram, DiagramAst, DiagramHandle, DiagramId, DiagramKind, DiagramObjectIndex, FlowEdge,
+    FlowNode, FlowNodePin, FlowchartAst, IdError, IdPrefixRule, ObjectAnnotations, ObjectId,
+    ObjectRef, ParseIdPrefixRuleError, ParseObjectRefError, ParseStyleRuleError, SelectionEntry,
+    SelectionLog, SequenceAst, SequenceMessage, SequenceMessageBoundary, SequenceMessageKind,
+    SequenceNotePlacement, Session, SessionId, StyleRule, Walkthrough, WalkthroughEdge,
+    WalkthroughId, WalkthroughNode, WalkthroughNodeId, XRef, XRefId, XRefStatus as ModelXRefStatus,
+};
+use crate::ops::{
+    FlowEdgePatch, FlowNodePatch, FlowOp, Op, SeqMessagePatch, SeqNotePatch, SeqOp,
+    SeqParticipantPatch, XRefOp, XRefPatch,
 };
 use crate::render::{
-    render_flowchart_unicode, render_sequence_unicode, render_walkthrough_unicode,
-    FlowchartRenderError, SequenceRenderError, WalkthroughRenderError,
+    render_class_unicode, render_flowchart_unicode, render_sequence_unicode, render_state_unicode,
+    render_walkthrough_unicode, FlowchartRenderError, SequenceRenderError, WalkthroughRenderError,
 };
 
+use super::template::SessionTemplate;
+
 const SESSION_META_FILENAME: &str = "nereid-session.meta.json";
 const LEGACY_SESSION_META_FILENAME: &str = "session.meta.json";
 
@@ -169,6 +185,14 @@ impl AsciiExportManager {
                             Ok(layout) => render_flowchart_unicode(&ast, &layout).ok(),
                             Err(_) => None,
                         },
+                        DiagramAst::State(ast) => match layout_state(&ast) {
+                            Ok(layout) => render_state_unicode(&layout).ok(),
+                            Err(_) => None,
+                        },
+                        DiagramAst::Class(ast) => match layout_class(&ast) {
+                            Ok(layout) => render_class_unicode(&ast, &layout).ok(),
+                            Err(_) => None,
+                        },
                     } {
                         if !text.ends_with('\n') {
                             text.push('\n');
@@ -237,6 +261,16 @@ pub enum StoreError {
         path: PathBuf,
         source: Box<MermaidFlowchartParseError>,
     },
+    MermaidStateParse {
+        diagram_id: DiagramId,
+        path: PathBuf,
+        source: Box<MermaidStateParseError>,
+    },
+    MermaidClassParse {
+        diagram_id: DiagramId,
+        path: PathBuf,
+        source: Box<MermaidClassParseError>,
+    },
     MermaidSequenceExport {
         diagram_id: DiagramId,
         path: PathBuf,
@@ -247,6 +281,16 @@ pub enum StoreError {
         path: PathBuf,
         source: Box<MermaidFlowchartExportError>,
     },
+    MermaidStateExport {
+        diagram_id: DiagramId,
+        path: PathBuf,
+        source: Box<MermaidStateExportError>,
+    },
+    MermaidClassExport {
+        diagram_id: DiagramId,
+        path: PathBuf,
+        source: Box<MermaidClassExportError>,
+    },
     SequenceLayout {
         diagram_id: DiagramId,
         path: PathBuf,
@@ -282,6 +326,14 @@ pub enum StoreError {
         value: String,
         source: Box<ParseObjectRefError>,
     },
+    InvalidStyleRule {
+        value: String,
+        source: Box<ParseStyleRuleError>,
+    },
+    InvalidIdPrefixRule {
+        value: String,
+        source: Box<ParseIdPrefixRuleError>,
+    },
     InvalidRelativePath {
         field: &'static str,
         value: PathBuf,
@@ -293,6 +345,21 @@ pub enum StoreError {
     SymlinkRefused {
         path: PathBuf,
     },
+    Watch {
+        path: PathBuf,
+        source: Box<notify::Error>,
+    },
+    UnknownWorkspaceSession {
+        workspace_dir: PathBuf,
+        name: String,
+    },
+    SessionExists {
+        session_dir: PathBuf,
+    },
+    InvalidArchive {
+        path: PathBuf,
+        reason: String,
+    },
 }
 
 impl fmt::Display for StoreError {
@@ -316,6 +383,22 @@ impl fmt::Display for StoreError {
                 f,
                 "cannot parse Mermaid flowchart diagram {diagram_id} from {path:?}: {source}"
             ),
+            Self::MermaidStateParse {
+                diagram_id,
+                path,
+                source,
+            } => write!(
+                f,
+                "cannot parse Mermaid state diagram {diagram_id} from {path:?}: {source}"
+            ),
+            Self::MermaidClassParse {
+                diagram_id,
+                path,
+                source,
+            } => write!(
+                f,
+                "cannot parse Mermaid class diagram {diagram_id} from {path:?}: {source}"
+            ),
             Self::MermaidSequenceExport {
                 diagram_id,
                 path,
@@ -332,6 +415,22 @@ impl fmt::Display for StoreError {
                 f,
                 "cannot export Mermaid flowchart diagram {diagram_id} to {path:?}: {source}"
             ),
+            Self::MermaidStateExport {
+                diagram_id,
+                path,
+                source,
+            } => write!(
+                f,
+                "cannot export Mermaid state diagram {diagram_id} to {path:?}: {source}"
+            ),
+            Self::MermaidClassExport {
+                diagram_id,
+                path,
+                source,
+            } => write!(
+                f,
+                "cannot export Mermaid class diagram {diagram_id} to {path:?}: {source}"
+            ),
             Self::SequenceLayout {
                 diagram_id,
                 path,
@@ -382,6 +481,12 @@ impl fmt::Display for StoreError {
                 value,
                 source,
             } => write!(f, "invalid object ref for {field}: {value:?}: {source}"),
+            Self::InvalidStyleRule { value, source } => {
+                write!(f, "invalid style rule for style_rules[]: {value:?}: {source}")
+            }
+            Self::InvalidIdPrefixRule { value, source } => {
+                write!(f, "invalid id prefix rule for id_prefix_rules[]: {value:?}: {source}")
+            }
             Self::InvalidRelativePath { field, value } => {
                 write!(f, "invalid relative path for {field}: {value:?}")
             }
@@ -392,6 +497,16 @@ impl fmt::Display for StoreError {
             Self::SymlinkRefused { path } => {
                 write!(f, "refusing to write through symlink at {path:?}")
             }
+            Self::Watch { path, source } => write!(f, "cannot watch {path:?}: {source}"),
+            Self::UnknownWorkspaceSession { workspace_dir, name } => {
+                write!(f, "no session folder named {name:?} in workspace {workspace_dir:?}")
+            }
+            Self::SessionExists { session_dir } => {
+                write!(f, "a session already exists in {session_dir:?}")
+            }
+            Self::InvalidArchive { path, reason } => {
+                write!(f, "invalid session archive {path:?}: {reason}")
+            }
         }
     }
 }
@@ -405,6 +520,10 @@ impl std::error::Error for StoreError {
             Self::MermaidFlowchartParse { source, .. } => Some(source),
             Self::MermaidSequenceExport { source, .. } => Some(source),
             Self::MermaidFlowchartExport { source, .. } => Some(source),
+            Self::MermaidStateParse { source, .. } => Some(source),
+            Self::MermaidStateExport { source, .. } => Some(source),
+            Self::MermaidClassParse { source, .. } => Some(source),
+            Self::MermaidClassExport { source, .. } => Some(source),
             Self::SequenceLayout { source, .. } => Some(source),
             Self::SequenceRender { source, .. } => Some(source),
             Self::FlowchartLayout { source, .. } => Some(source),
@@ -412,9 +531,15 @@ impl std::error::Error for StoreError {
             Self::WalkthroughRender { source, .. } => Some(source),
             Self::InvalidId { source, .. } => Some(source),
             Self::InvalidObjectRef { source, .. } => Some(source),
+            Self::InvalidStyleRule { source, .. } => Some(source),
+            Self::InvalidIdPrefixRule { source, .. } => Some(source),
             Self::InvalidRelativePath { .. } => None,
             Self::PathOutsideSession { .. } => None,
             Self::SymlinkRefused { .. } => None,
+            Self::Watch { source, .. } => Some(source),
+            Self::UnknownWorkspaceSession { .. } => None,
+            Self::SessionExists { .. } => None,
+            Self::InvalidArchive { .. } => None,
         }
     }
 }
@@ -422,12 +547,21 @@ impl std::error::Error for StoreError {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SessionMeta {
     pub session_id: SessionId,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    pub style_rules: Vec<StyleRule>,
+    pub id_prefix_rules: Vec<IdPrefixRule>,
     pub active_diagram_id: Option<DiagramId>,
     pub active_walkthrough_id: Option<WalkthroughId>,
     pub walkthrough_ids: Option<Vec<WalkthroughId>>,
+    pub diagram_order: Vec<DiagramId>,
     pub diagrams: Vec<SessionMetaDiagram>,
     pub xrefs: Vec<SessionXRef>,
+    pub xref_id_counter: u64,
     pub selected_object_refs: Vec<ObjectRef>,
+    /// Selection and deselection stamps backing `selected_object_refs`; see [`SelectionLog`].
+    pub selection_log: SelectionLog,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -437,6 +571,9 @@ pub struct SessionMetaDiagram {
     pub kind: DiagramKind,
     pub mmd_path: PathBuf,
     pub rev: u64,
+    pub description: Option<String>,
+    pub archived: bool,
+    pub read_only: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -459,6 +596,16 @@ pub struct DiagramMeta {
     pub sequence_messages: Vec<DiagramSequenceMessageMeta>,
     pub flow_node_notes: BTreeMap<ObjectId, String>,
     pub sequence_participant_notes: BTreeMap<ObjectId, String>,
+    pub flow_node_annotations: BTreeMap<ObjectId, ObjectAnnotations>,
+    pub sequence_participant_annotations: BTreeMap<ObjectId, ObjectAnnotations>,
+    pub flow_node_order_hints: BTreeMap<ObjectId, u32>,
+    pub flow_node_pins: BTreeMap<ObjectId, FlowNodePin>,
+    pub flow_entry_nodes: BTreeSet<ObjectId>,
+    pub sequence_participant_order: Vec<ObjectId>,
+    /// Hash of the `.mmd` contents this sidecar was written alongside.
+    pub mmd_fingerprint: Option<u64>,
+    /// The diagram's objects as of `mmd_fingerprint`, read by lazy loads instead of parsing.
+    pub objects: DiagramObjectIndex,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
@@ -484,6 +631,8 @@ pub struct DiagramFlowEdgeMeta {
     pub to_node_id: ObjectId,
     pub label: Option<String>,
     pub style: Option<String>,
+    pub weight: Option<u64>,
+    pub annotations: ObjectAnnotations,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -493,6 +642,8 @@ pub struct DiagramSequenceMessageMeta {
     pub to_participant_id: ObjectId,
     pub kind: SequenceMessageKind,
     pub text: String,
+    pub duration: Option<u64>,
+    pub annotations: ObjectAnnotations,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -507,6 +658,7 @@ pub enum XRefStatus {
 pub struct SessionFolder {
     root: PathBuf,
     durability: WriteDurability,
+    load_mode: LoadMode,
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
@@ -525,6 +677,19 @@ pub enum WriteDurability {
     Durable,
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LoadMode {
+    /// Parses every diagram file while loading the session.
+    #[default]
+    Eager,
+
+    /// Reads only the session meta up front and parses each diagram file on first access.
+    ///
+    /// Diagram files that fail to parse show up as empty diagrams with a load error instead of
+    /// failing the whole load, and saving leaves their files untouched.
+    Lazy,
+}
+
 fn encode_persisted_id_segment(segment: &str) -> String {
     if !needs_windows_safe_filename_segment_encoding(segment) {
         return segment.to_owned();
@@ -587,7 +752,11 @@ fn is_windows_device_name(base: &str) -> bool {
 
 impl SessionFolder {
     pub fn new(root: impl Into<PathBuf>) -> Self {
-        Self { root: root.into(), durability: WriteDurability::default() }
+        Self {
+            root: root.into(),
+            durability: WriteDurability::default(),
+            load_mode: LoadMode::default(),
+        }
     }
 
     pub fn with_durability(mut self, durability: WriteDurability) -> Self {
@@ -599,6 +768,15 @@ impl SessionFolder {
         self.durability
     }
 
+    pub fn with_load_mode(mut self, load_mode: LoadMode) -> Self {
+        self.load_mode = load_mode;
+        self
+    }
+
+    pub fn load_mode(&self) -> LoadMode {
+        self.load_mode
+    }
+
     pub fn root(&self) -> &Path {
         &self.root
     }
@@ -611,6 +789,11 @@ impl SessionFolder {
         self.root.join(LEGACY_SESSION_META_FILENAME)
     }
 
+    /// Returns `true` when the folder holds a session meta file (current or legacy name).
+    pub fn has_session(&self) -> bool {
+        self.meta_path().is_file() || self.legacy_meta_path().is_file()
+    }
+
     pub fn default_diagram_mmd_path(&self, diagram_id: &DiagramId) -> PathBuf {
         let file_stem = encode_persisted_id_segment(diagram_id.as_str());
         self.root.join("diagrams").join(format!("{file_stem}.mmd"))
@@ -733,13 +916,26 @@ impl SessionFolder {
 
         let mut meta = SessionMeta {
             session_id: session.session_id().clone(),
+            title: session.title().map(ToOwned::to_owned),
+            description: session.description().map(ToOwned::to_owned),
+            tags: session.tags().to_vec(),
+            style_rules: session.style_rules().to_vec(),
+            id_prefix_rules: session.id_prefix_rules().to_vec(),
             active_diagram_id: session.active_diagram_id().cloned(),
             active_walkthrough_id: session.active_walkthrough_id().cloned(),
             walkthrough_ids: Some(Vec::new()),
+            diagram_order: session.diagram_order().to_vec(),
             diagrams: Vec::new(),
             xrefs: Vec::new(),
-            selected_object_refs: session.selected_object_refs().iter().cloned().collect(),
+            xref_id_counter: session.xref_id_counter(),
+            selected_object_refs: Vec::new(),
+            selection_log: session.selection_log().clone(),
         };
+        meta.selection_log.record(session.selected_object_refs(), now_millis());
+        if let Some(existing_meta) = existing_meta.as_ref() {
+            meta.selection_log.merge(&existing_meta.selection_log);
+        }
+        meta.selected_object_refs = meta.selection_log.selected().into_iter().collect();
 
         for (diagram_id, diagram) in session.diagrams() {
             let mmd_path = self.default_diagram_mmd_path(diagram_id);
@@ -753,8 +949,14 @@ impl SessionFolder {
                 && mmd_path.is_file()
                 && meta_path.is_file();
 
-            if !diagram_rev_unchanged {
-                export_diagram_mmd(self, diagram, &mmd_path)?;
+            // Parses lazy diagrams that need writing; ones that failed to parse keep their files.
+            let ast_available = |diagram: &Diagram| {
+                diagram.ast();
+                diagram.load_error().is_none()
+            };
+
+            if !diagram_rev_unchanged && ast_available(diagram) {
+                let mmd_fingerprint = export_diagram_mmd(self, diagram, &mmd_path)?;
 
                 let flow_edges = match diagram.ast() {
                     DiagramAst::Flowchart(ast) => ast
@@ -766,9 +968,13 @@ impl SessionFolder {
                             to_node_id: edge.to_node_id().clone(),
                             label: edge.label().map(ToOwned::to_owned),
                             style: edge.style().map(ToOwned::to_owned),
+                            weight: edge.weight(),
+                            annotations: edge.annotations().clone(),
                         })
                         .collect(),
-                    DiagramAst::Sequence(_) => Vec::new(),
+                    DiagramAst::Sequence(_) | DiagramAst::State(_) | DiagramAst::Class(_) => {
+                        Vec::new()
+                    }
                 };
 
                 let sequence_messages = match diagram.ast() {
@@ -781,9 +987,13 @@ impl SessionFolder {
                             to_participant_id: msg.to_participant_id().clone(),
                             kind: msg.kind(),
                             text: msg.text().to_owned(),
+                            duration: msg.duration(),
+                            annotations: msg.annotations().clone(),
                         })
                         .collect(),
-                    DiagramAst::Flowchart(_) => Vec::new(),
+                    DiagramAst::Flowchart(_) | DiagramAst::State(_) | DiagramAst::Class(_) => {
+                        Vec::new()
+                    }
                 };
 
                 let flow_node_notes = match diagram.ast() {
@@ -794,7 +1004,42 @@ impl SessionFolder {
                             node.note().map(|note| (node_id.clone(), note.to_owned()))
                         })
                         .collect(),
-                    DiagramAst::Sequence(_) => BTreeMap::new(),
+                    DiagramAst::Sequence(_) | DiagramAst::State(_) | DiagramAst::Class(_) => {
+                        BTreeMap::new()
+                    }
+                };
+
+                let flow_node_annotations = match diagram.ast() {
+                    DiagramAst::Flowchart(ast) => ast
+                        .nodes()
+                        .iter()
+                        .filter(|(_, node)| !node.annotations().is_empty())
+                        .map(|(node_id, node)| (node_id.clone(), node.annotations().clone()))
+                        .collect(),
+                    DiagramAst::Sequence(_) | DiagramAst::State(_) | DiagramAst::Class(_) => {
+                        BTreeMap::new()
+                    }
+                };
+
+                let flow_node_order_hints = match diagram.ast() {
+                    DiagramAst::Flowchart(ast) => ast.node_order_hints().clone(),
+                    DiagramAst::Sequence(_) | DiagramAst::State(_) | DiagramAst::Class(_) => {
+                        BTreeMap::new()
+                    }
+                };
+
+                let flow_node_pins = match diagram.ast() {
+                    DiagramAst::Flowchart(ast) => ast.node_pins().clone(),
+                    DiagramAst::Sequence(_) | DiagramAst::State(_) | DiagramAst::Class(_) => {
+                        BTreeMap::new()
+                    }
+                };
+
+                let flow_entry_nodes = match diagram.ast() {
+                    DiagramAst::Flowchart(ast) => ast.entry_nodes().clone(),
+                    DiagramAst::Sequence(_) | DiagramAst::State(_) | DiagramAst::Class(_) => {
+                        BTreeSet::new()
+                    }
                 };
 
                 let sequence_participant_notes = match diagram.ast() {
@@ -805,7 +1050,30 @@ impl SessionFolder {
                             participant.note().map(|note| (participant_id.clone(), note.to_owned()))
                         })
                         .collect(),
-                    DiagramAst::Flowchart(_) => BTreeMap::new(),
+                    DiagramAst::Flowchart(_) | DiagramAst::State(_) | DiagramAst::Class(_) => {
+                        BTreeMap::new()
+                    }
+                };
+
+                let sequence_participant_annotations = match diagram.ast() {
+                    DiagramAst::Sequence(ast) => ast
+                        .participants()
+                        .iter()
+                        .filter(|(_, participant)| !participant.annotations().is_empty())
+                        .map(|(participant_id, participant)| {
+                            (participant_id.clone(), participant.annotations().clone())
+                        })
+                        .collect(),
+                    DiagramAst::Flowchart(_) | DiagramAst::State(_) | DiagramAst::Class(_) => {
+                        BTreeMap::new()
+                    }
+                };
+
+                let sequence_participant_order = match diagram.ast() {
+                    DiagramAst::Sequence(ast) => ast.participant_order().to_vec(),
+                    DiagramAst::Flowchart(_) | DiagramAst::State(_) | DiagramAst::Class(_) => {
+                        Vec::new()
+                    }
                 };
 
                 self.save_diagram_meta(&DiagramMeta {
@@ -817,19 +1085,36 @@ impl SessionFolder {
                     sequence_messages,
                     flow_node_notes,
                     sequence_participant_notes,
+                    flow_node_annotations,
+                    sequence_participant_annotations,
+                    flow_node_order_hints,
+                    flow_node_pins,
+                    flow_entry_nodes,
+                    sequence_participant_order,
+                    mmd_fingerprint: Some(mmd_fingerprint),
+                    objects: diagram.ast().object_index(),
                 })?;
             }
 
-            if !diagram_rev_unchanged || !ascii_path.is_file() {
+            if (!diagram_rev_unchanged || !ascii_path.is_file()) && ast_available(diagram) {
                 self.schedule_diagram_ascii_export(&mmd_path, diagram)?;
             }
 
+            // Diagrams that failed to parse were not written, so meta keeps the rev on disk.
+            let rev = match diagram.load_error() {
+                Some(_) => existing_diagram_revs.get(diagram_id).copied().unwrap_or(diagram.rev()),
+                None => diagram.rev(),
+            };
+
             meta.diagrams.push(SessionMetaDiagram {
                 diagram_id: diagram_id.clone(),
                 name: diagram.name().to_owned(),
                 kind: diagram.kind(),
                 mmd_path,
-                rev: diagram.rev(),
+                rev,
+                description: diagram.description().map(ToOwned::to_owned),
+                archived: diagram.is_archived(),
+                read_only: diagram.is_read_only(),
             });
         }
 
@@ -879,6 +1164,16 @@ impl SessionFolder {
         Ok(())
     }
 
+    /// Writes the built-in `template` into this folder, which must not hold a session yet, and
+    /// loads the new session.
+    pub fn init_from_template(&self, template: SessionTemplate) -> Result<Session, StoreError> {
+        if self.has_session() {
+            return Err(StoreError::SessionExists { session_dir: self.root.clone() });
+        }
+        self.save_session(&template.session(self.initial_session_id()))?;
+        self.load_session()
+    }
+
     fn garbage_collect_walkthrough_files(
         &self,
         walkthrough_ids: &[WalkthroughId],
@@ -935,63 +1230,54 @@ impl SessionFolder {
         let meta = self.load_meta()?;
 
         let mut session = Session::new(meta.session_id);
+        session.set_title(meta.title);
+        session.set_description(meta.description);
+        session.set_tags(meta.tags);
+        session.set_style_rules(meta.style_rules);
+        session.set_id_prefix_rules(meta.id_prefix_rules);
+        session.set_xref_id_counter(meta.xref_id_counter);
+        session.set_diagram_order(meta.diagram_order);
         session.set_active_diagram_id(meta.active_diagram_id);
         session.set_active_walkthrough_id(meta.active_walkthrough_id);
-        session.set_selected_object_refs(meta.selected_object_refs.into_iter().collect());
+        session.set_selection_log(meta.selection_log);
         let walkthrough_ids = meta.walkthrough_ids.clone();
 
         for diagram_meta in meta.diagrams {
             let diagram_id = diagram_meta.diagram_id;
             let mmd_path = diagram_meta.mmd_path;
-            let mmd = fs::read_to_string(&mmd_path)
-                .map_err(|source| StoreError::Io { path: mmd_path.clone(), source })?;
-
-            let sidecar = match self.load_diagram_meta(&mmd_path) {
-                Ok(sidecar) => Some(sidecar),
-                Err(StoreError::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
-                    None
-                }
-                Err(err) => return Err(err),
-            };
-
-            let mut ast = match diagram_meta.kind {
-                DiagramKind::Sequence => {
-                    DiagramAst::Sequence(parse_sequence_diagram(&mmd).map_err(|source| {
-                        StoreError::MermaidSequenceParse {
-                            diagram_id: diagram_id.clone(),
-                            path: mmd_path.clone(),
-                            source: Box::new(source),
-                        }
-                    })?)
-                }
-                DiagramKind::Flowchart => {
-                    DiagramAst::Flowchart(parse_flowchart(&mmd).map_err(|source| {
-                        StoreError::MermaidFlowchartParse {
-                            diagram_id: diagram_id.clone(),
-                            path: mmd_path.clone(),
-                            source: Box::new(source),
+            let ast = match self.load_mode {
+                LoadMode::Eager => DiagramHandle::loaded(self.load_diagram_ast(
+                    &diagram_id,
+                    diagram_meta.kind,
+                    &mmd_path,
+                    session.id_prefix_rules(),
+                )?),
+                LoadMode::Lazy => {
+                    let source = self.lazy_diagram_source(&mmd_path);
+                    let folder = self.clone();
+                    let loader_diagram_id = diagram_id.clone();
+                    let kind = diagram_meta.kind;
+                    let id_prefix_rules = session.id_prefix_rules().to_vec();
+                    let mut handle = DiagramHandle::lazy(kind, move || {
+                        folder
+                            .load_diagram_ast(&loader_diagram_id, kind, &mmd_path, &id_prefix_rules)
+                            .map_err(|err| err.to_string())
+                    });
+                    if let Some((fingerprint, objects)) = source {
+                        handle = handle.with_source_fingerprint(fingerprint);
+                        if let Some(objects) = objects {
+                            handle = handle.with_object_index(objects);
                         }
-                    })?)
-                }
-            };
-
-            if let Some(sidecar) = sidecar.as_ref() {
-                match &mut ast {
-                    DiagramAst::Flowchart(flow_ast) => {
-                        reconcile_flowchart_nodes(flow_ast, sidecar);
-                        reconcile_flowchart_edges(flow_ast, sidecar);
-                        reconcile_flowchart_notes(flow_ast, sidecar);
-                    }
-                    DiagramAst::Sequence(seq_ast) => {
-                        reconcile_sequence_participants(seq_ast, sidecar);
-                        reconcile_sequence_messages(seq_ast, sidecar);
-                        reconcile_sequence_participant_notes(seq_ast, sidecar);
                     }
+                    handle
                 }
-            }
+            };
 
-            let mut diagram = Diagram::new(diagram_id.clone(), diagram_meta.name, ast);
+            let mut diagram = Diagram::from_handle(diagram_id.clone(), diagram_meta.name, ast);
             diagram.set_rev(diagram_meta.rev);
+            diagram.set_description(diagram_meta.description);
+            diagram.set_archived(diagram_meta.archived);
+            diagram.set_read_only(diagram_meta.read_only);
             session.diagrams_mut().insert(diagram_id, diagram);
         }
 
@@ -1001,6 +1287,8 @@ impl SessionFolder {
             xref.set_label(xref_meta.label);
             session.xrefs_mut().insert(xref_meta.xref_id, xref);
         }
+        // Lazy diagrams answer from their sidecar object index, so this only parses diagrams
+        // whose index is missing or stale.
         refresh_xref_statuses(&mut session);
 
         match walkthrough_ids {
@@ -1049,6 +1337,111 @@ impl SessionFolder {
         Ok(session)
     }
 
+    /// Fingerprints a diagram's `.mmd` and sidecar without parsing them, along with the sidecar's
+    /// object index when it was written for the `.mmd` as it is now.
+    ///
+    /// Returns `None` when the `.mmd` cannot be read; the loader reports that error on access.
+    fn lazy_diagram_source(&self, mmd_path: &Path) -> Option<(u64, Option<DiagramObjectIndex>)> {
+        let mmd = fs::read(mmd_path).ok()?;
+        let sidecar = self.diagram_meta_path(mmd_path).ok().and_then(|path| fs::read(path).ok());
+
+        let mut hasher = DefaultHasher::new();
+        mmd.hash(&mut hasher);
+        sidecar.hash(&mut hasher);
+        let fingerprint = hasher.finish();
+
+        let mmd_fingerprint = mmd_fingerprint(&mmd);
+        let objects = self
+            .load_diagram_meta(mmd_path)
+            .ok()
+            .filter(|meta| meta.mmd_fingerprint == Some(mmd_fingerprint))
+            .map(|meta| meta.objects);
+        Some((fingerprint, objects))
+    }
+
+    /// Reads and parses one diagram file, reconciling its ids with the sidecar meta.
+    fn load_diagram_ast(
+        &self,
+        diagram_id: &DiagramId,
+        kind: DiagramKind,
+        mmd_path: &Path,
+        id_prefix_rules: &[IdPrefixRule],
+    ) -> Result<DiagramAst, StoreError> {
+        let mmd = fs::read_to_string(mmd_path)
+            .map_err(|source| StoreError::Io { path: mmd_path.to_path_buf(), source })?;
+
+        let sidecar = match self.load_diagram_meta(mmd_path) {
+            Ok(sidecar) => Some(sidecar),
+            Err(StoreError::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err),
+        };
+
+        let mut ast = match kind {
+            DiagramKind::Sequence => DiagramAst::Sequence(
+                parse_sequence_diagram_with_id_rules(&mmd, id_prefix_rules).map_err(|source| {
+                    StoreError::MermaidSequenceParse {
+                        diagram_id: diagram_id.clone(),
+                        path: mmd_path.to_path_buf(),
+                        source: Box::new(source),
+                    }
+                })?,
+            ),
+            DiagramKind::Flowchart => DiagramAst::Flowchart(
+                parse_flowchart_with_id_rules(&mmd, id_prefix_rules).map_err(|source| {
+                    StoreError::MermaidFlowchartParse {
+                        diagram_id: diagram_id.clone(),
+                        path: mmd_path.to_path_buf(),
+                        source: Box::new(source),
+                    }
+                })?,
+            ),
+            DiagramKind::State => {
+                DiagramAst::State(parse_state_diagram(&mmd).map_err(|source| {
+                    StoreError::MermaidStateParse {
+                        diagram_id: diagram_id.clone(),
+                        path: mmd_path.to_path_buf(),
+                        source: Box::new(source),
+                    }
+                })?)
+            }
+            DiagramKind::Class => {
+                DiagramAst::Class(parse_class_diagram(&mmd).map_err(|source| {
+                    StoreError::MermaidClassParse {
+                        diagram_id: diagram_id.clone(),
+                        path: mmd_path.to_path_buf(),
+                        source: Box::new(source),
+                    }
+                })?)
+            }
+        };
+
+        if let Some(sidecar) = sidecar.as_ref() {
+            let anchored = anchored_ids(&mmd);
+            match &mut ast {
+                DiagramAst::Flowchart(flow_ast) => {
+                    reconcile_flowchart_nodes(flow_ast, sidecar);
+                    reconcile_flowchart_edges(flow_ast, sidecar, &anchored);
+                    reconcile_flowchart_notes(flow_ast, sidecar);
+                    reconcile_flowchart_node_annotations(flow_ast, sidecar);
+                    reconcile_flowchart_order_hints(flow_ast, sidecar);
+                    reconcile_flowchart_node_pins(flow_ast, sidecar);
+                    reconcile_flowchart_entry_nodes(flow_ast, sidecar);
+                }
+                DiagramAst::Sequence(seq_ast) => {
+                    reconcile_sequence_participants(seq_ast, sidecar);
+                    reconcile_sequence_messages(seq_ast, sidecar, &anchored);
+                    reconcile_sequence_participant_notes(seq_ast, sidecar);
+                    reconcile_sequence_participant_annotations(seq_ast, sidecar);
+                    reconcile_sequence_participant_order(seq_ast, sidecar);
+                }
+                // State and class ids derive from Mermaid names, so they are stable as parsed.
+                DiagramAst::State(_) | DiagramAst::Class(_) => {}
+            }
+        }
+
+        Ok(ast)
+    }
+
     pub fn load_meta(&self) -> Result<SessionMeta, StoreError> {
         let meta_path = self.meta_path();
         let (meta_path, meta_str) = match fs::read_to_string(&meta_path) {
@@ -1104,11 +1497,156 @@ impl SessionFolder {
         Ok(())
     }
 
-    pub fn save_selected_object_refs(&self, session: &Session) -> Result<(), StoreError> {
+    /// Persists the selection merged with the one on disk, so toggles another process saved
+    /// meanwhile are kept; `session` adopts the merged selection.
+    pub fn save_selected_object_refs(&self, session: &mut Session) -> Result<(), StoreError> {
         match self.load_meta() {
             Ok(mut meta) => {
+                session.merge_selection(&meta.selection_log, now_millis());
                 meta.selected_object_refs =
                     session.selected_object_refs().iter().cloned().collect();
+                meta.selection_log = session.selection_log().clone();
+                self.save_meta(&meta)?;
+                Ok(())
+            }
+            Err(StoreError::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+                self.save_session(session)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Persists only the session title, description and tags.
+    pub fn save_session_info(&self, session: &Session) -> Result<(), StoreError> {
+        match self.load_meta() {
+            Ok(mut meta) => {
+                meta.title = session.title().map(ToOwned::to_owned);
+                meta.description = session.description().map(ToOwned::to_owned);
+                meta.tags = session.tags().to_vec();
+                self.save_meta(&meta)?;
+                Ok(())
+            }
+            Err(StoreError::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+                self.save_session(session)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Persists only the conditional styling rules.
+    pub fn save_style_rules(&self, session: &Session) -> Result<(), StoreError> {
+        match self.load_meta() {
+            Ok(mut meta) => {
+                meta.style_rules = session.style_rules().to_vec();
+                self.save_meta(&meta)?;
+                Ok(())
+            }
+            Err(StoreError::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+                self.save_session(session)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Persists only the id prefix conventions.
+    pub fn save_id_prefix_rules(&self, session: &Session) -> Result<(), StoreError> {
+        match self.load_meta() {
+            Ok(mut meta) => {
+                meta.id_prefix_rules = session.id_prefix_rules().to_vec();
+                self.save_meta(&meta)?;
+                Ok(())
+            }
+            Err(StoreError::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+                self.save_session(session)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Persists only the curated diagram order.
+    pub fn save_diagram_order(&self, session: &Session) -> Result<(), StoreError> {
+        match self.load_meta() {
+            Ok(mut meta) => {
+                meta.diagram_order = session.diagram_order().to_vec();
+                self.save_meta(&meta)?;
+                Ok(())
+            }
+            Err(StoreError::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+                self.save_session(session)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn save_diagram_description(
+        &self,
+        session: &Session,
+        diagram_id: &DiagramId,
+    ) -> Result<(), StoreError> {
+        match self.load_meta() {
+            Ok(mut meta) => {
+                let description = session
+                    .diagrams()
+                    .get(diagram_id)
+                    .and_then(|diagram| diagram.description())
+                    .map(ToOwned::to_owned);
+                if let Some(entry) =
+                    meta.diagrams.iter_mut().find(|entry| &entry.diagram_id == diagram_id)
+                {
+                    entry.description = description;
+                }
+                self.save_meta(&meta)?;
+                Ok(())
+            }
+            Err(StoreError::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+                self.save_session(session)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Persists only the archived flag of `diagram_id`.
+    pub fn save_diagram_archived(
+        &self,
+        session: &Session,
+        diagram_id: &DiagramId,
+    ) -> Result<(), StoreError> {
+        match self.load_meta() {
+            Ok(mut meta) => {
+                let archived =
+                    session.diagrams().get(diagram_id).is_some_and(|diagram| diagram.is_archived());
+                if let Some(entry) =
+                    meta.diagrams.iter_mut().find(|entry| &entry.diagram_id == diagram_id)
+                {
+                    entry.archived = archived;
+                }
+                self.save_meta(&meta)?;
+                Ok(())
+            }
+            Err(StoreError::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+                self.save_session(session)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Persists only the read-only flag of `diagram_id`.
+    pub fn save_diagram_read_only(
+        &self,
+        session: &Session,
+        diagram_id: &DiagramId,
+    ) -> Result<(), StoreError> {
+        match self.load_meta() {
+            Ok(mut meta) => {
+                let read_only = session
+                    .diagrams()
+                    .get(diagram_id)
+                    .is_some_and(|diagram| diagram.is_read_only());
+                if let Some(entry) =
+                    meta.diagrams.iter_mut().find(|entry| &entry.diagram_id == diagram_id)
+                {
+                    entry.read_only = read_only;
+                }
                 self.save_meta(&meta)?;
                 Ok(())
             }
@@ -1223,6 +1761,26 @@ impl SessionFolder {
         diagram_meta_from_json(self.root(), meta_json)
     }
 
+    /// Hash of the Mermaid file backing `diagram_id`, or `None` if the session has no such
+    /// diagram.
+    ///
+    /// Edits made directly to a `.mmd` file keep the stored rev, so comparing fingerprints is how
+    /// callers notice that the file changed under them.
+    pub fn diagram_mmd_fingerprint(
+        &self,
+        diagram_id: &DiagramId,
+    ) -> Result<Option<u64>, StoreError> {
+        let meta = self.load_meta()?;
+        let Some(diagram_meta) =
+            meta.diagrams.into_iter().find(|diagram| &diagram.diagram_id == diagram_id)
+        else {
+            return Ok(None);
+        };
+        let mmd = fs::read(&diagram_meta.mmd_path)
+            .map_err(|source| StoreError::Io { path: diagram_meta.mmd_path.clone(), source })?;
+        Ok(Some(mmd_fingerprint(&mmd)))
+    }
+
     pub fn save_diagram_meta(&self, meta: &DiagramMeta) -> Result<(), StoreError> {
         let meta_path = self.diagram_meta_path(&meta.mmd_path)?;
 
@@ -1243,6 +1801,8 @@ impl SessionFolder {
 
 // Extracted persistence and reconciliation helpers for `SessionFolder`.
 include!("session_folder/helpers.rs");
+include!("session_folder/journal.rs");
+include!("session_folder/archive.rs");
 
 #[cfg(test)]
 mod tests;
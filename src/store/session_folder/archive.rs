@@ -0,0 +1,175 @@
+// Portable single-file session archives (`.nereid.tar.zst`) for `SessionFolder`.
+//
+// An archive is a zstd-compressed tar whose first entry is a small JSON manifest naming the
+// archive format version; the session meta file, diagram `.mmd` files with their `.meta.json`
+// sidecars and walkthrough files follow at their paths relative to the session folder. Text
+// renders and `ops.log` are derived or transient and stay behind.
+
+pub(crate) const ARCHIVE_MANIFEST_FILENAME: &str = "nereid-archive.json";
+
+/// Version of the archive layout written by [`SessionFolder::export_archive`]; imports refuse
+/// archives with any other version.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ArchiveManifestJson {
+    version: u32,
+    session_id: String,
+}
+
+impl SessionFolder {
+    /// Writes the session as a versioned `.nereid.tar.zst` archive at `path` and returns the
+    /// number of session files it holds.
+    pub fn export_archive(&self, path: &Path) -> Result<usize, StoreError> {
+        let meta = self.load_meta()?;
+        let meta_path =
+            if self.meta_path().is_file() { self.meta_path() } else { self.legacy_meta_path() };
+
+        let mut files = vec![(PathBuf::from(SESSION_META_FILENAME), meta_path)];
+        for diagram in &meta.diagrams {
+            let relative = to_relative_path(self.root(), &diagram.mmd_path, "mmd_path")?;
+            files.push((relative, self.root.join(&diagram.mmd_path)));
+            let sidecar = self.diagram_meta_path(&diagram.mmd_path)?;
+            if sidecar.is_file() {
+                files.push((to_relative_path(self.root(), &sidecar, "meta_path")?, sidecar));
+            }
+        }
+        for walkthrough_path in self.walkthrough_paths(&meta)? {
+            files.push((
+                to_relative_path(self.root(), &walkthrough_path, "wt_path")?,
+                walkthrough_path,
+            ));
+        }
+
+        let manifest = ArchiveManifestJson {
+            version: ARCHIVE_VERSION,
+            session_id: meta.session_id.as_str().to_owned(),
+        };
+        let manifest = serde_json::to_vec_pretty(&manifest)
+            .map_err(|source| StoreError::Json { path: path.to_path_buf(), source })?;
+
+        let io_err = |source| StoreError::Io { path: path.to_path_buf(), source };
+        let file = fs::File::create(path).map_err(io_err)?;
+        let mut builder = tar::Builder::new(zstd::Encoder::new(file, 0).map_err(io_err)?);
+        append_archive_entry(&mut builder, Path::new(ARCHIVE_MANIFEST_FILENAME), &manifest)
+            .map_err(io_err)?;
+        for (relative, source_path) in &files {
+            let contents = fs::read(source_path)
+                .map_err(|source| StoreError::Io { path: source_path.clone(), source })?;
+            append_archive_entry(&mut builder, relative, &contents).map_err(io_err)?;
+        }
+        let file = builder.into_inner().and_then(|encoder| encoder.finish()).map_err(io_err)?;
+        if self.durability == WriteDurability::Durable {
+            file.sync_all().map_err(io_err)?;
+        }
+        Ok(files.len())
+    }
+
+    /// Unpacks the archive at `path` into this folder, which must not hold a session yet, and
+    /// loads the imported session.
+    pub fn import_archive(&self, path: &Path) -> Result<Session, StoreError> {
+        if self.has_session() {
+            return Err(StoreError::SessionExists { session_dir: self.root.clone() });
+        }
+
+        let invalid =
+            |reason: String| StoreError::InvalidArchive { path: path.to_path_buf(), reason };
+        let io_err = |source| StoreError::Io { path: path.to_path_buf(), source };
+        let file = fs::File::open(path).map_err(io_err)?;
+        let mut archive = tar::Archive::new(zstd::Decoder::new(file).map_err(io_err)?);
+
+        let mut manifest = None::<ArchiveManifestJson>;
+        let mut files = Vec::new();
+        for entry in archive.entries().map_err(io_err)? {
+            let mut entry = entry.map_err(io_err)?;
+            let entry_type = entry.header().entry_type();
+            if entry_type.is_dir() {
+                continue;
+            }
+            let relative = entry.path().map_err(io_err)?.into_owned();
+            if !entry_type.is_file() {
+                return Err(invalid(format!("{relative:?} is not a regular file")));
+            }
+            let mut contents = Vec::new();
+            io::Read::read_to_end(&mut entry, &mut contents).map_err(io_err)?;
+
+            if manifest.is_none() {
+                if relative != Path::new(ARCHIVE_MANIFEST_FILENAME) {
+                    return Err(invalid(format!("missing {ARCHIVE_MANIFEST_FILENAME}")));
+                }
+                let parsed: ArchiveManifestJson = serde_json::from_slice(&contents)
+                    .map_err(|source| StoreError::Json { path: path.to_path_buf(), source })?;
+                if parsed.version != ARCHIVE_VERSION {
+                    return Err(invalid(format!(
+                        "unsupported archive version {} (expected {ARCHIVE_VERSION})",
+                        parsed.version
+                    )));
+                }
+                manifest = Some(parsed);
+                continue;
+            }
+            validate_relative_path("archive entry", &relative)?;
+            files.push((relative, contents));
+        }
+        if manifest.is_none() {
+            return Err(invalid(format!("missing {ARCHIVE_MANIFEST_FILENAME}")));
+        }
+        if !files.iter().any(|(relative, _)| relative == Path::new(SESSION_META_FILENAME)) {
+            return Err(invalid(format!("missing {SESSION_META_FILENAME}")));
+        }
+
+        for (relative, contents) in &files {
+            write_atomic_in_session(
+                &self.root,
+                &self.root.join(relative),
+                contents,
+                self.durability,
+            )?;
+        }
+        self.load_session()
+    }
+
+    /// Walkthrough files the session meta lists, or every `.wt.json` file when it lists none.
+    fn walkthrough_paths(&self, meta: &SessionMeta) -> Result<Vec<PathBuf>, StoreError> {
+        if let Some(walkthrough_ids) = &meta.walkthrough_ids {
+            return Ok(walkthrough_ids
+                .iter()
+                .map(|walkthrough_id| {
+                    let path = self.walkthrough_json_path(walkthrough_id);
+                    if path.is_file() {
+                        path
+                    } else {
+                        self.legacy_walkthrough_json_path(walkthrough_id)
+                    }
+                })
+                .collect());
+        }
+
+        let walkthroughs_dir = self.root.join("walkthroughs");
+        let entries = match fs::read_dir(&walkthroughs_dir) {
+            Ok(entries) => entries,
+            Err(source) if source.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(source) => return Err(StoreError::Io { path: walkthroughs_dir, source }),
+        };
+        let mut paths = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.to_string_lossy().ends_with(".wt.json"))
+            .collect::<Vec<_>>();
+        paths.sort();
+        Ok(paths)
+    }
+}
+
+/// Appends one regular file entry with fixed mode and mtime, so archives of equal sessions match.
+fn append_archive_entry<W: io::Write>(
+    builder: &mut tar::Builder<W>,
+    relative: &Path,
+    contents: &[u8],
+) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(0);
+    builder.append_data(&mut header, relative, contents)
+}
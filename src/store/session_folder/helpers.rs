@@ -8,11 +8,12 @@
 
 /// Session folder persistence helpers:
 /// export, stable-id reconciliation, sidecar/json conversion, and safe filesystem writes.
+/// Writes the diagram's Mermaid source and returns the fingerprint of what was written.
 fn export_diagram_mmd(
     folder: &SessionFolder,
     diagram: &Diagram,
     mmd_path: &Path,
-) -> Result<(), StoreError> {
+) -> Result<u64, StoreError> {
     let mmd = match diagram.ast() {
         DiagramAst::Sequence(ast) => {
             export_sequence_diagram(ast).map_err(|source| StoreError::MermaidSequenceExport {
@@ -28,11 +29,31 @@ fn export_diagram_mmd(
                 source: Box::new(source),
             })?
         }
+        DiagramAst::State(ast) => {
+            export_state_diagram(ast).map_err(|source| StoreError::MermaidStateExport {
+                diagram_id: diagram.diagram_id().clone(),
+                path: mmd_path.to_path_buf(),
+                source: Box::new(source),
+            })?
+        }
+        DiagramAst::Class(ast) => {
+            export_class_diagram(ast).map_err(|source| StoreError::MermaidClassExport {
+                diagram_id: diagram.diagram_id().clone(),
+                path: mmd_path.to_path_buf(),
+                source: Box::new(source),
+            })?
+        }
     };
 
     write_atomic_in_session(folder.root(), mmd_path, mmd.as_bytes(), folder.durability)?;
 
-    Ok(())
+    Ok(mmd_fingerprint(mmd.as_bytes()))
+}
+
+fn mmd_fingerprint(mmd: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    mmd.hash(&mut hasher);
+    hasher.finish()
 }
 
 fn stable_id_map_from_ast(ast: &DiagramAst) -> DiagramStableIdMap {
@@ -70,6 +91,7 @@ fn stable_id_map_from_ast(ast: &DiagramAst) -> DiagramStableIdMap {
                 by_name: BTreeMap::new(),
             }
         }
+        DiagramAst::State(_) | DiagramAst::Class(_) => DiagramStableIdMap::default(),
     }
 }
 
@@ -165,6 +187,10 @@ fn reconcile_sequence_participants(ast: &mut SequenceAst, sidecar: &DiagramMeta)
         })
         .collect();
     *ast.messages_mut() = next_messages;
+    for activation in ast.activations_mut() {
+        let participant_id = remap_id(&remap, activation.participant_id());
+        activation.set_participant_id(participant_id);
+    }
 }
 
 fn reconcile_flowchart_nodes(ast: &mut FlowchartAst, sidecar: &DiagramMeta) {
@@ -226,21 +252,25 @@ fn reconcile_flowchart_nodes(ast: &mut FlowchartAst, sidecar: &DiagramMeta) {
         .collect();
     *ast.edges_mut() = next_edges;
 
-    if ast.node_groups().is_empty() {
+    if ast.node_subgraphs().is_empty() {
         return;
     }
 
-    let mut next_node_groups = BTreeMap::<ObjectId, ObjectId>::new();
-    for (node_id, group_id) in ast.node_groups() {
+    let mut next_node_subgraphs = BTreeMap::<ObjectId, ObjectId>::new();
+    for (node_id, subgraph_id) in ast.node_subgraphs() {
         let mapped_node_id = remap_id(&remap, node_id);
-        next_node_groups
+        next_node_subgraphs
             .entry(mapped_node_id)
-            .or_insert(group_id.clone());
+            .or_insert(subgraph_id.clone());
     }
-    *ast.node_groups_mut() = next_node_groups;
+    *ast.node_subgraphs_mut() = next_node_subgraphs;
 }
 
-fn reconcile_flowchart_edges(ast: &mut FlowchartAst, sidecar: &DiagramMeta) {
+fn reconcile_flowchart_edges(
+    ast: &mut FlowchartAst,
+    sidecar: &DiagramMeta,
+    anchored: &BTreeSet<ObjectId>,
+) {
     if sidecar.flow_edges.is_empty() {
         return;
     }
@@ -270,8 +300,8 @@ fn reconcile_flowchart_edges(ast: &mut FlowchartAst, sidecar: &DiagramMeta) {
         }
     }
 
-    let mut by_fingerprint: BTreeMap<FlowEdgeFingerprint, VecDeque<(ObjectId, Option<String>)>> =
-        BTreeMap::new();
+    type StableEdge = (ObjectId, Option<String>, Option<u64>, ObjectAnnotations);
+    let mut by_fingerprint: BTreeMap<FlowEdgeFingerprint, VecDeque<StableEdge>> = BTreeMap::new();
     let mut taken_ids: BTreeSet<ObjectId> = BTreeSet::new();
 
     for entry in &sidecar.flow_edges {
@@ -284,9 +314,26 @@ fn reconcile_flowchart_edges(ast: &mut FlowchartAst, sidecar: &DiagramMeta) {
         by_fingerprint
             .entry(fingerprint)
             .or_default()
-            .push_back((entry.edge_id.clone(), entry.style.clone()));
+            .push_back((
+                entry.edge_id.clone(),
+                entry.style.clone(),
+                entry.weight,
+                entry.annotations.clone(),
+            ));
     }
 
+    // Edges parsed under a `%% nereid:id=` anchor keep that id; fingerprints only place the rest.
+    let pinned = ast
+        .edges()
+        .keys()
+        .filter(|edge_id| anchored.contains(*edge_id))
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    for queue in by_fingerprint.values_mut() {
+        queue.retain(|(edge_id, ..)| !pinned.contains(edge_id));
+    }
+    taken_ids.extend(pinned.iter().cloned());
+
     let mut max_numeric = 0u64;
     for edge_id in taken_ids.iter().chain(ast.edges().keys()) {
         if let Some(value) = numeric_edge_id(edge_id) {
@@ -297,6 +344,21 @@ fn reconcile_flowchart_edges(ast: &mut FlowchartAst, sidecar: &DiagramMeta) {
 
     let mut next_edges: BTreeMap<ObjectId, FlowEdge> = BTreeMap::new();
     for (parsed_edge_id, edge) in ast.edges() {
+        if pinned.contains(parsed_edge_id) {
+            let mut updated = edge.clone();
+            if let Some(entry) = sidecar
+                .flow_edges
+                .iter()
+                .find(|entry| &entry.edge_id == parsed_edge_id)
+            {
+                updated.set_style(entry.style.clone());
+                updated.set_weight(entry.weight);
+                updated.set_annotations(entry.annotations.clone());
+            }
+            next_edges.insert(parsed_edge_id.clone(), updated);
+            continue;
+        }
+
         let fingerprint = FlowEdgeFingerprint {
             from_node_id: edge.from_node_id().clone(),
             to_node_id: edge.to_node_id().clone(),
@@ -304,9 +366,11 @@ fn reconcile_flowchart_edges(ast: &mut FlowchartAst, sidecar: &DiagramMeta) {
         };
 
         if let Some(queue) = by_fingerprint.get_mut(&fingerprint) {
-            if let Some((stable_id, style)) = queue.pop_front() {
+            if let Some((stable_id, style, weight, annotations)) = queue.pop_front() {
                 let mut updated = edge.clone();
                 updated.set_style(style.clone());
+                updated.set_weight(weight);
+                updated.set_annotations(annotations);
 
                 let target_id = if next_edges.contains_key(&stable_id) {
                     allocate_edge_id(&mut next_numeric, &mut taken_ids)
@@ -335,7 +399,11 @@ fn reconcile_flowchart_edges(ast: &mut FlowchartAst, sidecar: &DiagramMeta) {
     *ast.edges_mut() = next_edges;
 }
 
-fn reconcile_sequence_messages(ast: &mut SequenceAst, sidecar: &DiagramMeta) {
+fn reconcile_sequence_messages(
+    ast: &mut SequenceAst,
+    sidecar: &DiagramMeta,
+    anchored: &BTreeSet<ObjectId>,
+) {
     if sidecar.sequence_messages.is_empty() {
         return;
     }
@@ -377,9 +445,17 @@ fn reconcile_sequence_messages(ast: &mut SequenceAst, sidecar: &DiagramMeta) {
 
     let mut by_fingerprint: BTreeMap<MessageFingerprint, VecDeque<ObjectId>> = BTreeMap::new();
     let mut taken_ids: BTreeSet<ObjectId> = BTreeSet::new();
+    let mut durations: BTreeMap<ObjectId, u64> = BTreeMap::new();
+    let mut annotations_by_id: BTreeMap<ObjectId, ObjectAnnotations> = BTreeMap::new();
 
     for entry in &sidecar.sequence_messages {
         taken_ids.insert(entry.message_id.clone());
+        if let Some(duration) = entry.duration {
+            durations.insert(entry.message_id.clone(), duration);
+        }
+        if !entry.annotations.is_empty() {
+            annotations_by_id.insert(entry.message_id.clone(), entry.annotations.clone());
+        }
         let fingerprint = MessageFingerprint {
             from_participant_id: entry.from_participant_id.clone(),
             to_participant_id: entry.to_participant_id.clone(),
@@ -392,6 +468,19 @@ fn reconcile_sequence_messages(ast: &mut SequenceAst, sidecar: &DiagramMeta) {
             .push_back(entry.message_id.clone());
     }
 
+    // Messages parsed under a `%% nereid:id=` anchor keep that id; fingerprints only place the rest.
+    let pinned = ast
+        .messages()
+        .iter()
+        .map(|msg| msg.message_id())
+        .filter(|message_id| anchored.contains(*message_id))
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    for queue in by_fingerprint.values_mut() {
+        queue.retain(|message_id| !pinned.contains(message_id));
+    }
+    taken_ids.extend(pinned.iter().cloned());
+
     let mut max_numeric = 0u64;
     for message_id in taken_ids
         .iter()
@@ -417,6 +506,7 @@ fn reconcile_sequence_messages(ast: &mut SequenceAst, sidecar: &DiagramMeta) {
         };
 
         let message_id = match by_fingerprint.get_mut(&fingerprint) {
+            _ if pinned.contains(msg.message_id()) => msg.message_id().clone(),
             Some(queue) => match queue.pop_front() {
                 Some(stable_id) => {
                     if assigned_ids.contains(&stable_id) {
@@ -449,6 +539,8 @@ fn reconcile_sequence_messages(ast: &mut SequenceAst, sidecar: &DiagramMeta) {
         assigned_ids.insert(message_id.clone());
         remap.insert(original_message_id, message_id.clone());
 
+        let duration = durations.get(&message_id).copied();
+        let annotations = annotations_by_id.get(&message_id).cloned().unwrap_or_default();
         let mut updated = SequenceMessage::new(
             message_id,
             msg.from_participant_id().clone(),
@@ -458,11 +550,20 @@ fn reconcile_sequence_messages(ast: &mut SequenceAst, sidecar: &DiagramMeta) {
             msg.order_key(),
         );
         updated.set_raw_arrow(msg.raw_arrow().map(ToOwned::to_owned));
+        updated.set_boundary(msg.boundary());
+        updated.set_duration(duration);
+        updated.set_annotations(annotations);
         next_messages.push(updated);
     }
 
     *ast.messages_mut() = next_messages;
     remap_sequence_block_message_ids(ast.blocks_mut(), &remap);
+    for activation in ast.activations_mut() {
+        let start = activation.start_message_id().map(|message_id| remap_id(&remap, message_id));
+        let end = activation.end_message_id().map(|message_id| remap_id(&remap, message_id));
+        activation.set_start_message_id(start);
+        activation.set_end_message_id(end);
+    }
 }
 
 fn remap_sequence_block_message_ids(
@@ -498,6 +599,44 @@ fn reconcile_flowchart_notes(ast: &mut FlowchartAst, sidecar: &DiagramMeta) {
     }
 }
 
+fn reconcile_flowchart_node_annotations(ast: &mut FlowchartAst, sidecar: &DiagramMeta) {
+    for (node_id, annotations) in &sidecar.flow_node_annotations {
+        if let Some(node) = ast.nodes_mut().get_mut(node_id) {
+            node.set_annotations(annotations.clone());
+        }
+    }
+}
+
+fn reconcile_flowchart_order_hints(ast: &mut FlowchartAst, sidecar: &DiagramMeta) {
+    let hints = sidecar
+        .flow_node_order_hints
+        .iter()
+        .filter(|(node_id, _)| ast.nodes().contains_key(*node_id))
+        .map(|(node_id, hint)| (node_id.clone(), *hint))
+        .collect();
+    *ast.node_order_hints_mut() = hints;
+}
+
+fn reconcile_flowchart_node_pins(ast: &mut FlowchartAst, sidecar: &DiagramMeta) {
+    let pins = sidecar
+        .flow_node_pins
+        .iter()
+        .filter(|(node_id, _)| ast.nodes().contains_key(*node_id))
+        .map(|(node_id, pin)| (node_id.clone(), *pin))
+        .collect();
+    *ast.node_pins_mut() = pins;
+}
+
+fn reconcile_flowchart_entry_nodes(ast: &mut FlowchartAst, sidecar: &DiagramMeta) {
+    let entry_nodes = sidecar
+        .flow_entry_nodes
+        .iter()
+        .filter(|node_id| ast.nodes().contains_key(*node_id))
+        .cloned()
+        .collect();
+    *ast.entry_nodes_mut() = entry_nodes;
+}
+
 fn reconcile_sequence_participant_notes(ast: &mut SequenceAst, sidecar: &DiagramMeta) {
     if sidecar.sequence_participant_notes.is_empty() {
         return;
@@ -510,6 +649,24 @@ fn reconcile_sequence_participant_notes(ast: &mut SequenceAst, sidecar: &Diagram
     }
 }
 
+fn reconcile_sequence_participant_annotations(ast: &mut SequenceAst, sidecar: &DiagramMeta) {
+    for (participant_id, annotations) in &sidecar.sequence_participant_annotations {
+        if let Some(participant) = ast.participants_mut().get_mut(participant_id) {
+            participant.set_annotations(annotations.clone());
+        }
+    }
+}
+
+fn reconcile_sequence_participant_order(ast: &mut SequenceAst, sidecar: &DiagramMeta) {
+    let order = sidecar
+        .sequence_participant_order
+        .iter()
+        .filter(|participant_id| ast.participants().contains_key(*participant_id))
+        .cloned()
+        .collect();
+    *ast.participant_order_mut() = order;
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct WalkthroughJson {
     walkthrough_id: String,
@@ -651,18 +808,41 @@ fn walkthrough_from_json(walkthrough_json: WalkthroughJson) -> Result<Walkthroug
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct SessionMetaJson {
     session_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    style_rules: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    id_prefix_rules: Vec<String>,
     #[serde(default)]
     active_diagram_id: Option<String>,
     #[serde(default)]
     active_walkthrough_id: Option<String>,
     #[serde(default)]
     walkthrough_ids: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    diagram_order: Vec<String>,
     #[serde(default)]
     diagrams: Vec<SessionMetaDiagramJson>,
     #[serde(default)]
     xrefs: Vec<SessionXRefJson>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    xref_id_counter: Option<u64>,
     #[serde(default)]
     selected_object_refs: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    selection_log: Vec<SelectionLogEntryJson>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SelectionLogEntryJson {
+    object_ref: String,
+    selected: bool,
+    stamp: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -673,6 +853,12 @@ struct SessionMetaDiagramJson {
     mmd_path: String,
     #[serde(default)]
     rev: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    archived: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    read_only: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -691,6 +877,8 @@ struct SessionXRefJson {
 enum DiagramKindJson {
     Sequence,
     Flowchart,
+    State,
+    Class,
 }
 
 impl From<DiagramKind> for DiagramKindJson {
@@ -698,6 +886,8 @@ impl From<DiagramKind> for DiagramKindJson {
         match kind {
             DiagramKind::Sequence => Self::Sequence,
             DiagramKind::Flowchart => Self::Flowchart,
+            DiagramKind::State => Self::State,
+            DiagramKind::Class => Self::Class,
         }
     }
 }
@@ -707,6 +897,8 @@ impl From<DiagramKindJson> for DiagramKind {
         match kind {
             DiagramKindJson::Sequence => Self::Sequence,
             DiagramKindJson::Flowchart => Self::Flowchart,
+            DiagramKindJson::State => Self::State,
+            DiagramKindJson::Class => Self::Class,
         }
     }
 }
@@ -727,6 +919,9 @@ fn session_meta_to_json(
                 kind: diagram.kind.into(),
                 mmd_path: relative_mmd_path.to_string_lossy().into_owned(),
                 rev: diagram.rev,
+                description: diagram.description.clone(),
+                archived: diagram.archived,
+                read_only: diagram.read_only,
             })
         })
         .collect::<Result<Vec<_>, StoreError>>()?;
@@ -746,19 +941,36 @@ fn session_meta_to_json(
 
     Ok(SessionMetaJson {
         session_id: meta.session_id.to_string(),
+        title: meta.title.clone(),
+        description: meta.description.clone(),
+        tags: meta.tags.clone(),
+        style_rules: meta.style_rules.iter().map(ToString::to_string).collect(),
+        id_prefix_rules: meta.id_prefix_rules.iter().map(ToString::to_string).collect(),
         active_diagram_id: meta.active_diagram_id.as_ref().map(ToString::to_string),
         active_walkthrough_id: meta.active_walkthrough_id.as_ref().map(ToString::to_string),
         walkthrough_ids: meta
             .walkthrough_ids
             .as_ref()
             .map(|ids| ids.iter().map(ToString::to_string).collect()),
+        diagram_order: meta.diagram_order.iter().map(ToString::to_string).collect(),
         diagrams,
         xrefs,
+        xref_id_counter: Some(meta.xref_id_counter).filter(|counter| *counter > 0),
         selected_object_refs: meta
             .selected_object_refs
             .iter()
             .map(ToString::to_string)
             .collect(),
+        selection_log: meta
+            .selection_log
+            .entries()
+            .iter()
+            .map(|(object_ref, entry)| SelectionLogEntryJson {
+                object_ref: object_ref.to_string(),
+                selected: entry.selected,
+                stamp: entry.stamp,
+            })
+            .collect(),
     })
 }
 
@@ -795,6 +1007,18 @@ fn session_meta_from_json(
         })
         .transpose()?;
 
+    let diagram_order = meta_json
+        .diagram_order
+        .into_iter()
+        .map(|value| {
+            DiagramId::new(value.clone()).map_err(|source| StoreError::InvalidId {
+                field: "diagram_order[]",
+                value,
+                source: Box::new(source),
+            })
+        })
+        .collect::<Result<Vec<_>, StoreError>>()?;
+
     let walkthrough_ids = meta_json
         .walkthrough_ids
         .map(|values| {
@@ -832,6 +1056,9 @@ fn session_meta_from_json(
                 kind: diagram_json.kind.into(),
                 mmd_path: session_dir.join(relative_mmd_path),
                 rev: diagram_json.rev,
+                description: diagram_json.description,
+                archived: diagram_json.archived,
+                read_only: diagram_json.read_only,
             })
         })
         .collect::<Result<Vec<_>, StoreError>>()?;
@@ -883,18 +1110,68 @@ fn session_meta_from_json(
                 source: Box::new(source),
             })
         })
-        .collect::<Result<BTreeSet<_>, StoreError>>()?
+        .collect::<Result<BTreeSet<_>, StoreError>>()?;
+
+    // Session files written before the log existed only list the selection.
+    let selection_log = if meta_json.selection_log.is_empty() {
+        SelectionLog::from_selected(selected_object_refs)
+    } else {
+        let mut selection_log = SelectionLog::default();
+        for entry_json in meta_json.selection_log {
+            let object_ref = ObjectRef::parse(&entry_json.object_ref).map_err(|source| {
+                StoreError::InvalidObjectRef {
+                    field: "selection_log[].object_ref",
+                    value: entry_json.object_ref,
+                    source: Box::new(source),
+                }
+            })?;
+            selection_log.insert(
+                object_ref,
+                SelectionEntry { stamp: entry_json.stamp, selected: entry_json.selected },
+            );
+        }
+        selection_log
+    };
+    let selected_object_refs = selection_log.selected().into_iter().collect::<Vec<_>>();
+
+    let style_rules = meta_json
+        .style_rules
+        .into_iter()
+        .map(|value| {
+            value.parse::<StyleRule>().map_err(|source| StoreError::InvalidStyleRule {
+                value,
+                source: Box::new(source),
+            })
+        })
+        .collect::<Result<Vec<_>, StoreError>>()?;
+
+    let id_prefix_rules = meta_json
+        .id_prefix_rules
         .into_iter()
-        .collect::<Vec<_>>();
+        .map(|value| {
+            value.parse::<IdPrefixRule>().map_err(|source| StoreError::InvalidIdPrefixRule {
+                value,
+                source: Box::new(source),
+            })
+        })
+        .collect::<Result<Vec<_>, StoreError>>()?;
 
     Ok(SessionMeta {
         session_id,
+        title: meta_json.title,
+        description: meta_json.description,
+        tags: meta_json.tags,
+        style_rules,
+        id_prefix_rules,
         active_diagram_id,
         active_walkthrough_id,
         walkthrough_ids,
+        diagram_order,
         diagrams,
         xrefs,
+        xref_id_counter: meta_json.xref_id_counter.unwrap_or(0),
         selected_object_refs,
+        selection_log,
     })
 }
 
@@ -914,6 +1191,22 @@ struct DiagramMetaJson {
     flow_node_notes: BTreeMap<String, String>,
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     sequence_participant_notes: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    flow_node_annotations: BTreeMap<String, ObjectAnnotationsJson>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    sequence_participant_annotations: BTreeMap<String, ObjectAnnotationsJson>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    flow_node_order_hints: BTreeMap<String, u32>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    flow_node_pins: BTreeMap<String, FlowNodePinJson>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    flow_entry_nodes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    sequence_participant_order: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mmd_fingerprint: Option<u64>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    objects: BTreeMap<String, Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -944,6 +1237,10 @@ struct DiagramFlowEdgeMetaJson {
     label: Option<String>,
     #[serde(default)]
     style: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weight: Option<u64>,
+    #[serde(default, flatten)]
+    annotations: ObjectAnnotationsJson,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -953,6 +1250,48 @@ struct DiagramSequenceMessageMetaJson {
     to_participant_id: String,
     kind: SequenceMessageKindJson,
     text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration: Option<u64>,
+    #[serde(default, flatten)]
+    annotations: ObjectAnnotationsJson,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ObjectAnnotationsJson {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: BTreeMap<String, String>,
+}
+
+impl From<&ObjectAnnotations> for ObjectAnnotationsJson {
+    fn from(annotations: &ObjectAnnotations) -> Self {
+        Self { tags: annotations.tags().to_vec(), metadata: annotations.metadata().clone() }
+    }
+}
+
+impl From<ObjectAnnotationsJson> for ObjectAnnotations {
+    fn from(json: ObjectAnnotationsJson) -> Self {
+        ObjectAnnotations::new(json.tags, json.metadata)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct FlowNodePinJson {
+    layer: u32,
+    index: u32,
+}
+
+impl From<&FlowNodePin> for FlowNodePinJson {
+    fn from(pin: &FlowNodePin) -> Self {
+        Self { layer: pin.layer(), index: pin.index() }
+    }
+}
+
+impl From<FlowNodePinJson> for FlowNodePin {
+    fn from(json: FlowNodePinJson) -> Self {
+        FlowNodePin::new(json.layer, json.index)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -983,6 +1322,31 @@ impl From<SequenceMessageKindJson> for SequenceMessageKind {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SequenceMessageBoundaryJson {
+    Found,
+    Lost,
+}
+
+impl From<SequenceMessageBoundary> for SequenceMessageBoundaryJson {
+    fn from(boundary: SequenceMessageBoundary) -> Self {
+        match boundary {
+            SequenceMessageBoundary::Found => Self::Found,
+            SequenceMessageBoundary::Lost => Self::Lost,
+        }
+    }
+}
+
+impl From<SequenceMessageBoundaryJson> for SequenceMessageBoundary {
+    fn from(boundary: SequenceMessageBoundaryJson) -> Self {
+        match boundary {
+            SequenceMessageBoundaryJson::Found => Self::Found,
+            SequenceMessageBoundaryJson::Lost => Self::Lost,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 enum XRefStatusJson {
@@ -1069,6 +1433,8 @@ fn diagram_meta_to_json(
             to_node_id: edge.to_node_id.to_string(),
             label: edge.label.clone(),
             style: edge.style.clone(),
+            weight: edge.weight,
+            annotations: (&edge.annotations).into(),
         })
         .collect();
 
@@ -1081,6 +1447,8 @@ fn diagram_meta_to_json(
             to_participant_id: msg.to_participant_id.to_string(),
             kind: msg.kind.into(),
             text: msg.text.clone(),
+            duration: msg.duration,
+            annotations: (&msg.annotations).into(),
         })
         .collect();
 
@@ -1096,6 +1464,45 @@ fn diagram_meta_to_json(
         .map(|(participant_id, note)| (participant_id.to_string(), note.clone()))
         .collect();
 
+    let flow_node_annotations: BTreeMap<String, ObjectAnnotationsJson> = meta
+        .flow_node_annotations
+        .iter()
+        .map(|(node_id, annotations)| (node_id.to_string(), annotations.into()))
+        .collect();
+
+    let sequence_participant_annotations: BTreeMap<String, ObjectAnnotationsJson> = meta
+        .sequence_participant_annotations
+        .iter()
+        .map(|(participant_id, annotations)| (participant_id.to_string(), annotations.into()))
+        .collect();
+
+    let flow_node_order_hints: BTreeMap<String, u32> = meta
+        .flow_node_order_hints
+        .iter()
+        .map(|(node_id, hint)| (node_id.to_string(), *hint))
+        .collect();
+
+    let flow_node_pins: BTreeMap<String, FlowNodePinJson> = meta
+        .flow_node_pins
+        .iter()
+        .map(|(node_id, pin)| (node_id.to_string(), pin.into()))
+        .collect();
+
+    let flow_entry_nodes: Vec<String> =
+        meta.flow_entry_nodes.iter().map(|node_id| node_id.to_string()).collect();
+
+    let sequence_participant_order: Vec<String> = meta
+        .sequence_participant_order
+        .iter()
+        .map(|participant_id| participant_id.to_string())
+        .collect();
+
+    let objects: BTreeMap<String, Vec<String>> = meta
+        .objects
+        .iter()
+        .map(|(category, ids)| (category.clone(), ids.iter().map(ToString::to_string).collect()))
+        .collect();
+
     Ok(DiagramMetaJson {
         diagram_id: meta.diagram_id.to_string(),
         mmd_path: relative_mmd_path.to_string_lossy().into_owned(),
@@ -1105,6 +1512,14 @@ fn diagram_meta_to_json(
         sequence_messages,
         flow_node_notes,
         sequence_participant_notes,
+        flow_node_annotations,
+        sequence_participant_annotations,
+        flow_node_order_hints,
+        flow_node_pins,
+        flow_entry_nodes,
+        sequence_participant_order,
+        mmd_fingerprint: meta.mmd_fingerprint,
+        objects,
     })
 }
 
@@ -1173,6 +1588,8 @@ fn diagram_meta_from_json(
                 to_node_id,
                 label: edge_json.label,
                 style: edge_json.style,
+                weight: edge_json.weight,
+                annotations: edge_json.annotations.into(),
             })
         })
         .collect::<Result<Vec<_>, StoreError>>()?;
@@ -1210,6 +1627,8 @@ fn diagram_meta_from_json(
                     to_participant_id,
                     kind: msg_json.kind.into(),
                     text: msg_json.text,
+                    duration: msg_json.duration,
+                    annotations: msg_json.annotations.into(),
                 })
             })
             .collect::<Result<Vec<_>, StoreError>>()?;
@@ -1242,6 +1661,104 @@ fn diagram_meta_from_json(
         })
         .collect::<Result<BTreeMap<_, _>, StoreError>>()?;
 
+    let flow_node_annotations = meta_json
+        .flow_node_annotations
+        .into_iter()
+        .map(|(node_id, annotations)| {
+            let node_id =
+                ObjectId::new(node_id.clone()).map_err(|source| StoreError::InvalidId {
+                    field: "flow_node_annotations keys",
+                    value: node_id,
+                    source: Box::new(source),
+                })?;
+            Ok((node_id, annotations.into()))
+        })
+        .collect::<Result<BTreeMap<_, _>, StoreError>>()?;
+
+    let sequence_participant_annotations = meta_json
+        .sequence_participant_annotations
+        .into_iter()
+        .map(|(participant_id, annotations)| {
+            let participant_id =
+                ObjectId::new(participant_id.clone()).map_err(|source| StoreError::InvalidId {
+                    field: "sequence_participant_annotations keys",
+                    value: participant_id,
+                    source: Box::new(source),
+                })?;
+            Ok((participant_id, annotations.into()))
+        })
+        .collect::<Result<BTreeMap<_, _>, StoreError>>()?;
+
+    let flow_node_order_hints = meta_json
+        .flow_node_order_hints
+        .into_iter()
+        .map(|(node_id, hint)| {
+            let node_id =
+                ObjectId::new(node_id.clone()).map_err(|source| StoreError::InvalidId {
+                    field: "flow_node_order_hints keys",
+                    value: node_id,
+                    source: Box::new(source),
+                })?;
+            Ok((node_id, hint))
+        })
+        .collect::<Result<BTreeMap<_, _>, StoreError>>()?;
+
+    let flow_node_pins = meta_json
+        .flow_node_pins
+        .into_iter()
+        .map(|(node_id, pin)| {
+            let node_id =
+                ObjectId::new(node_id.clone()).map_err(|source| StoreError::InvalidId {
+                    field: "flow_node_pins keys",
+                    value: node_id,
+                    source: Box::new(source),
+                })?;
+            Ok((node_id, pin.into()))
+        })
+        .collect::<Result<BTreeMap<_, _>, StoreError>>()?;
+
+    let flow_entry_nodes = meta_json
+        .flow_entry_nodes
+        .into_iter()
+        .map(|node_id| {
+            ObjectId::new(node_id.clone()).map_err(|source| StoreError::InvalidId {
+                field: "flow_entry_nodes",
+                value: node_id,
+                source: Box::new(source),
+            })
+        })
+        .collect::<Result<BTreeSet<_>, StoreError>>()?;
+
+    let sequence_participant_order = meta_json
+        .sequence_participant_order
+        .into_iter()
+        .map(|participant_id| {
+            ObjectId::new(participant_id.clone()).map_err(|source| StoreError::InvalidId {
+                field: "sequence_participant_order",
+                value: participant_id,
+                source: Box::new(source),
+            })
+        })
+        .collect::<Result<Vec<_>, StoreError>>()?;
+
+    let objects = meta_json
+        .objects
+        .into_iter()
+        .map(|(category, ids)| {
+            let ids = ids
+                .into_iter()
+                .map(|object_id| {
+                    ObjectId::new(object_id.clone()).map_err(|source| StoreError::InvalidId {
+                        field: "objects",
+                        value: object_id,
+                        source: Box::new(source),
+                    })
+                })
+                .collect::<Result<BTreeSet<_>, StoreError>>()?;
+            Ok((category, ids))
+        })
+        .collect::<Result<DiagramObjectIndex, StoreError>>()?;
+
     Ok(DiagramMeta {
         diagram_id,
         mmd_path,
@@ -1251,6 +1768,14 @@ fn diagram_meta_from_json(
         sequence_messages,
         flow_node_notes,
         sequence_participant_notes,
+        flow_node_annotations,
+        sequence_participant_annotations,
+        flow_node_order_hints,
+        flow_node_pins,
+        flow_entry_nodes,
+        sequence_participant_order,
+        mmd_fingerprint: meta_json.mmd_fingerprint,
+        objects,
     })
 }
 
@@ -1526,3 +2051,8 @@ fn refresh_xref_statuses(session: &mut Session) {
         }
     }
 }
+
+/// Wall-clock milliseconds used to stamp selection changes.
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
@@ -0,0 +1,778 @@
+// Append-only journal of applied op batches (`ops.log`) for `SessionFolder`.
+//
+// Every line is one JSON `JournalEntryJson`. The TUI appends when it applies a change (before the
+// debounced diagram sync) and the MCP server before it persists, so batches that never reached the
+// diagram files can be replayed after a crash.
+
+pub(crate) const OPS_LOG_FILENAME: &str = "ops.log";
+
+/// Who applied a journaled op batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalActor {
+    Human,
+    Agent,
+}
+
+/// One applied op batch, as recorded in the session's `ops.log`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    /// Wall-clock milliseconds when the batch was applied.
+    pub at: u64,
+    pub actor: JournalActor,
+    pub diagram_id: DiagramId,
+    pub base_rev: u64,
+    pub new_rev: u64,
+    pub ops: Vec<Op>,
+}
+
+impl JournalEntry {
+    /// An entry stamped with the current time.
+    pub fn new(
+        actor: JournalActor,
+        diagram_id: DiagramId,
+        base_rev: u64,
+        new_rev: u64,
+        ops: Vec<Op>,
+    ) -> Self {
+        Self { at: now_millis(), actor, diagram_id, base_rev, new_rev, ops }
+    }
+}
+
+impl SessionFolder {
+    pub fn ops_log_path(&self) -> PathBuf {
+        self.root.join(OPS_LOG_FILENAME)
+    }
+
+    /// Appends `entry` as one line to `ops.log`, creating the session folder if needed.
+    pub fn append_ops_journal(&self, entry: &JournalEntry) -> Result<(), StoreError> {
+        let path = self.ops_log_path();
+        fs::create_dir_all(&self.root)
+            .map_err(|source| StoreError::Io { path: self.root.clone(), source })?;
+        if fs::symlink_metadata(&path).is_ok_and(|meta| meta.file_type().is_symlink()) {
+            return Err(StoreError::SymlinkRefused { path });
+        }
+
+        let mut line = serde_json::to_string(&journal_entry_to_json(entry))
+            .map_err(|source| StoreError::Json { path: path.clone(), source })?;
+        line.push('\n');
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|source| StoreError::Io { path: path.clone(), source })?;
+        file.write_all(line.as_bytes())
+            .map_err(|source| StoreError::Io { path: path.clone(), source })?;
+        if self.durability == WriteDurability::Durable {
+            file.sync_data().map_err(|source| StoreError::Io { path, source })?;
+        }
+        Ok(())
+    }
+
+    /// Reads every entry of `ops.log`, oldest first; empty without a journal. A torn last line
+    /// left by an interrupted append is skipped.
+    pub fn load_ops_journal(&self) -> Result<Vec<JournalEntry>, StoreError> {
+        let path = self.ops_log_path();
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(source) => return Err(StoreError::Io { path, source }),
+        };
+
+        let complete = text.ends_with('\n');
+        let lines = text.lines().filter(|line| !line.trim().is_empty()).collect::<Vec<_>>();
+        let mut entries = Vec::with_capacity(lines.len());
+        for (idx, line) in lines.iter().enumerate() {
+            match serde_json::from_str::<JournalEntryJson>(line) {
+                Ok(json) => entries.push(journal_entry_from_json(json)?),
+                Err(_) if !complete && idx + 1 == lines.len() => break,
+                Err(source) => return Err(StoreError::Json { path, source }),
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Reapplies journaled batches `session` has not seen yet: per diagram, in journal order, each
+    /// entry whose `base_rev` is the diagram's current revision. A diagram stops replaying at the
+    /// first batch that no longer applies. Returns the replayed entries.
+    pub fn replay_ops_journal(
+        &self,
+        session: &mut Session,
+    ) -> Result<Vec<JournalEntry>, StoreError> {
+        let mut replayed = Vec::new();
+        let mut stalled = BTreeSet::new();
+        for entry in self.load_ops_journal()? {
+            if entry.ops.is_empty() || stalled.contains(&entry.diagram_id) {
+                continue;
+            }
+            let Some(diagram) = session.diagrams_mut().get_mut(&entry.diagram_id) else {
+                continue;
+            };
+            if diagram.rev() != entry.base_rev {
+                continue;
+            }
+            match crate::ops::apply_ops(diagram, entry.base_rev, &entry.ops) {
+                Ok(_) => replayed.push(entry),
+                Err(_) => {
+                    stalled.insert(entry.diagram_id);
+                }
+            }
+        }
+        Ok(replayed)
+    }
+
+    /// Replays unsynced batches from `ops.log` into `session` and persists the result; returns
+    /// how many batches were recovered.
+    pub fn recover_from_ops_journal(&self, session: &mut Session) -> Result<usize, StoreError> {
+        let replayed = self.replay_ops_journal(session)?;
+        if !replayed.is_empty() {
+            self.save_session(session)?;
+        }
+        Ok(replayed.len())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct JournalEntryJson {
+    at: u64,
+    actor: JournalActorJson,
+    diagram_id: String,
+    base_rev: u64,
+    new_rev: u64,
+    ops: Vec<OpJson>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum JournalActorJson {
+    Human,
+    Agent,
+}
+
+/// Journal form of [`Op`]; the `type` names follow the MCP op vocabulary.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum OpJson {
+    SeqAddParticipant {
+        participant_id: String,
+        mermaid_name: String,
+    },
+    SeqUpdateParticipant {
+        participant_id: String,
+        mermaid_name: Option<String>,
+    },
+    SeqSetParticipantNote {
+        participant_id: String,
+        note: Option<String>,
+    },
+    SeqSetParticipantAnnotations {
+        participant_id: String,
+        annotations: ObjectAnnotationsJson,
+    },
+    SeqRemoveParticipant {
+        participant_id: String,
+    },
+    SeqSetParticipantOrder {
+        order: Vec<String>,
+    },
+    SeqAddMessage {
+        message_id: String,
+        from_participant_id: String,
+        to_participant_id: String,
+        kind: SequenceMessageKindJson,
+        arrow: Option<String>,
+        text: String,
+        order_key: i64,
+    },
+    SeqUpdateMessage {
+        message_id: String,
+        from_participant_id: Option<String>,
+        to_participant_id: Option<String>,
+        kind: Option<SequenceMessageKindJson>,
+        arrow: Option<String>,
+        text: Option<String>,
+        order_key: Option<i64>,
+    },
+    SeqSetMessageDuration {
+        message_id: String,
+        duration: Option<u64>,
+    },
+    SeqSetMessageBoundary {
+        message_id: String,
+        boundary: Option<SequenceMessageBoundaryJson>,
+    },
+    SeqSetMessageAnnotations {
+        message_id: String,
+        annotations: ObjectAnnotationsJson,
+    },
+    SeqRemoveMessage {
+        message_id: String,
+    },
+    SeqAddNote {
+        note_id: String,
+        placement: SequenceNotePlacementJson,
+        participant_ids: Vec<String>,
+        text: String,
+        order_key: i64,
+    },
+    SeqUpdateNote {
+        note_id: String,
+        placement: Option<SequenceNotePlacementJson>,
+        participant_ids: Option<Vec<String>>,
+        text: Option<String>,
+        order_key: Option<i64>,
+    },
+    SeqRemoveNote {
+        note_id: String,
+    },
+    FlowAddNode {
+        node_id: String,
+        label: String,
+        shape: Option<String>,
+    },
+    FlowUpdateNode {
+        node_id: String,
+        label: Option<String>,
+        shape: Option<String>,
+    },
+    FlowSetNodeMermaidId {
+        node_id: String,
+        mermaid_id: Option<String>,
+    },
+    FlowSetNodeNote {
+        node_id: String,
+        note: Option<String>,
+    },
+    FlowSetNodeAnnotations {
+        node_id: String,
+        annotations: ObjectAnnotationsJson,
+    },
+    FlowSetNodeOrderHints {
+        order_hints: BTreeMap<String, u32>,
+    },
+    FlowSetNodePin {
+        node_id: String,
+        pin: Option<FlowNodePinJson>,
+    },
+    FlowSetNodeEntry {
+        node_id: String,
+        entry: bool,
+    },
+    FlowRemoveNode {
+        node_id: String,
+    },
+    FlowAddEdge {
+        edge_id: String,
+        from_node_id: String,
+        to_node_id: String,
+        label: Option<String>,
+        connector: Option<String>,
+        style: Option<String>,
+    },
+    FlowUpdateEdge {
+        edge_id: String,
+        from_node_id: Option<String>,
+        to_node_id: Option<String>,
+        label: Option<String>,
+        connector: Option<String>,
+        style: Option<String>,
+    },
+    FlowSetEdgeWeight {
+        edge_id: String,
+        weight: Option<u64>,
+    },
+    FlowSetEdgeAnnotations {
+        edge_id: String,
+        annotations: ObjectAnnotationsJson,
+    },
+    FlowRemoveEdge {
+        edge_id: String,
+    },
+    XrefAdd {
+        xref_id: String,
+        from: String,
+        to: String,
+        kind: String,
+        label: Option<String>,
+    },
+    XrefUpdate {
+        xref_id: String,
+        from: Option<String>,
+        to: Option<String>,
+        kind: Option<String>,
+        label: Option<String>,
+    },
+    XrefRemove {
+        xref_id: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SequenceNotePlacementJson {
+    LeftOf,
+    RightOf,
+    Over,
+}
+
+impl From<SequenceNotePlacement> for SequenceNotePlacementJson {
+    fn from(placement: SequenceNotePlacement) -> Self {
+        match placement {
+            SequenceNotePlacement::LeftOf => Self::LeftOf,
+            SequenceNotePlacement::RightOf => Self::RightOf,
+            SequenceNotePlacement::Over => Self::Over,
+        }
+    }
+}
+
+impl From<SequenceNotePlacementJson> for SequenceNotePlacement {
+    fn from(placement: SequenceNotePlacementJson) -> Self {
+        match placement {
+            SequenceNotePlacementJson::LeftOf => Self::LeftOf,
+            SequenceNotePlacementJson::RightOf => Self::RightOf,
+            SequenceNotePlacementJson::Over => Self::Over,
+        }
+    }
+}
+
+fn journal_entry_to_json(entry: &JournalEntry) -> JournalEntryJson {
+    JournalEntryJson {
+        at: entry.at,
+        actor: match entry.actor {
+            JournalActor::Human => JournalActorJson::Human,
+            JournalActor::Agent => JournalActorJson::Agent,
+        },
+        diagram_id: entry.diagram_id.as_str().to_owned(),
+        base_rev: entry.base_rev,
+        new_rev: entry.new_rev,
+        ops: entry.ops.iter().map(op_to_json).collect(),
+    }
+}
+
+fn journal_entry_from_json(json: JournalEntryJson) -> Result<JournalEntry, StoreError> {
+    let diagram_id = DiagramId::new(json.diagram_id.clone()).map_err(|source| {
+        StoreError::InvalidId { field: "diagram_id", value: json.diagram_id, source: Box::new(source) }
+    })?;
+    Ok(JournalEntry {
+        at: json.at,
+        actor: match json.actor {
+            JournalActorJson::Human => JournalActor::Human,
+            JournalActorJson::Agent => JournalActor::Agent,
+        },
+        diagram_id,
+        base_rev: json.base_rev,
+        new_rev: json.new_rev,
+        ops: json.ops.into_iter().map(op_from_json).collect::<Result<_, _>>()?,
+    })
+}
+
+fn op_to_json(op: &Op) -> OpJson {
+    let id = id_string;
+    let opt_id = |id: &Option<ObjectId>| id.as_ref().map(id_string);
+    match op {
+        Op::Seq(op) => match op {
+            SeqOp::AddParticipant { participant_id, mermaid_name } => OpJson::SeqAddParticipant {
+                participant_id: id(participant_id),
+                mermaid_name: mermaid_name.clone(),
+            },
+            SeqOp::UpdateParticipant { participant_id, patch } => OpJson::SeqUpdateParticipant {
+                participant_id: id(participant_id),
+                mermaid_name: patch.mermaid_name.clone(),
+            },
+            SeqOp::SetParticipantNote { participant_id, note } => OpJson::SeqSetParticipantNote {
+                participant_id: id(participant_id),
+                note: note.clone(),
+            },
+            SeqOp::SetParticipantAnnotations { participant_id, annotations } => {
+                OpJson::SeqSetParticipantAnnotations {
+                    participant_id: id(participant_id),
+                    annotations: annotations.into(),
+                }
+            }
+            SeqOp::RemoveParticipant { participant_id } => {
+                OpJson::SeqRemoveParticipant { participant_id: id(participant_id) }
+            }
+            SeqOp::SetParticipantOrder { order } => {
+                OpJson::SeqSetParticipantOrder { order: order.iter().map(id_string).collect() }
+            }
+            SeqOp::AddMessage {
+                message_id,
+                from_participant_id,
+                to_participant_id,
+                kind,
+                arrow,
+                text,
+                order_key,
+            } => OpJson::SeqAddMessage {
+                message_id: id(message_id),
+                from_participant_id: id(from_participant_id),
+                to_participant_id: id(to_participant_id),
+                kind: (*kind).into(),
+                arrow: arrow.clone(),
+                text: text.clone(),
+                order_key: *order_key,
+            },
+            SeqOp::UpdateMessage { message_id, patch } => OpJson::SeqUpdateMessage {
+                message_id: id(message_id),
+                from_participant_id: opt_id(&patch.from_participant_id),
+                to_participant_id: opt_id(&patch.to_participant_id),
+                kind: patch.kind.map(Into::into),
+                arrow: patch.arrow.clone(),
+                text: patch.text.clone(),
+                order_key: patch.order_key,
+            },
+            SeqOp::SetMessageDuration { message_id, duration } => {
+                OpJson::SeqSetMessageDuration { message_id: id(message_id), duration: *duration }
+            }
+            SeqOp::SetMessageBoundary { message_id, boundary } => {
+                OpJson::SeqSetMessageBoundary {
+                    message_id: id(message_id),
+                    boundary: boundary.map(Into::into),
+                }
+            }
+            SeqOp::SetMessageAnnotations { message_id, annotations } => {
+                OpJson::SeqSetMessageAnnotations {
+                    message_id: id(message_id),
+                    annotations: annotations.into(),
+                }
+            }
+            SeqOp::RemoveMessage { message_id } => {
+                OpJson::SeqRemoveMessage { message_id: id(message_id) }
+            }
+            SeqOp::AddNote { note_id, placement, participant_ids, text, order_key } => {
+                OpJson::SeqAddNote {
+                    note_id: id(note_id),
+                    placement: (*placement).into(),
+                    participant_ids: participant_ids.iter().map(id_string).collect(),
+                    text: text.clone(),
+                    order_key: *order_key,
+                }
+            }
+            SeqOp::UpdateNote { note_id, patch } => OpJson::SeqUpdateNote {
+                note_id: id(note_id),
+                placement: patch.placement.map(Into::into),
+                participant_ids: patch
+                    .participant_ids
+                    .as_ref()
+                    .map(|participant_ids| participant_ids.iter().map(id_string).collect()),
+                text: patch.text.clone(),
+                order_key: patch.order_key,
+            },
+            SeqOp::RemoveNote { note_id } => OpJson::SeqRemoveNote { note_id: id(note_id) },
+        },
+        Op::Flow(op) => match op {
+            FlowOp::AddNode { node_id, label, shape } => OpJson::FlowAddNode {
+                node_id: id(node_id),
+                label: label.clone(),
+                shape: shape.clone(),
+            },
+            FlowOp::UpdateNode { node_id, patch } => OpJson::FlowUpdateNode {
+                node_id: id(node_id),
+                label: patch.label.clone(),
+                shape: patch.shape.clone(),
+            },
+            FlowOp::SetNodeMermaidId { node_id, mermaid_id } => OpJson::FlowSetNodeMermaidId {
+                node_id: id(node_id),
+                mermaid_id: mermaid_id.clone(),
+            },
+            FlowOp::SetNodeNote { node_id, note } => {
+                OpJson::FlowSetNodeNote { node_id: id(node_id), note: note.clone() }
+            }
+            FlowOp::SetNodeAnnotations { node_id, annotations } => {
+                OpJson::FlowSetNodeAnnotations { node_id: id(node_id), annotations: annotations.into() }
+            }
+            FlowOp::SetNodeOrderHints { order_hints } => OpJson::FlowSetNodeOrderHints {
+                order_hints: order_hints.iter().map(|(node_id, hint)| (id(node_id), *hint)).collect(),
+            },
+            FlowOp::SetNodePin { node_id, pin } => {
+                OpJson::FlowSetNodePin { node_id: id(node_id), pin: pin.as_ref().map(Into::into) }
+            }
+            FlowOp::SetNodeEntry { node_id, entry } => {
+                OpJson::FlowSetNodeEntry { node_id: id(node_id), entry: *entry }
+            }
+            FlowOp::RemoveNode { node_id } => OpJson::FlowRemoveNode { node_id: id(node_id) },
+            FlowOp::AddEdge { edge_id, from_node_id, to_node_id, label, connector, style } => {
+                OpJson::FlowAddEdge {
+                    edge_id: id(edge_id),
+                    from_node_id: id(from_node_id),
+                    to_node_id: id(to_node_id),
+                    label: label.clone(),
+                    connector: connector.clone(),
+                    style: style.clone(),
+                }
+            }
+            FlowOp::UpdateEdge { edge_id, patch } => OpJson::FlowUpdateEdge {
+                edge_id: id(edge_id),
+                from_node_id: opt_id(&patch.from_node_id),
+                to_node_id: opt_id(&patch.to_node_id),
+                label: patch.label.clone(),
+                connector: patch.connector.clone(),
+                style: patch.style.clone(),
+            },
+            FlowOp::SetEdgeWeight { edge_id, weight } => {
+                OpJson::FlowSetEdgeWeight { edge_id: id(edge_id), weight: *weight }
+            }
+            FlowOp::SetEdgeAnnotations { edge_id, annotations } => {
+                OpJson::FlowSetEdgeAnnotations { edge_id: id(edge_id), annotations: annotations.into() }
+            }
+            FlowOp::RemoveEdge { edge_id } => OpJson::FlowRemoveEdge { edge_id: id(edge_id) },
+        },
+        Op::XRef(op) => match op {
+            XRefOp::Add { xref_id, from, to, kind, label } => OpJson::XrefAdd {
+                xref_id: xref_id.as_str().to_owned(),
+                from: from.to_string(),
+                to: to.to_string(),
+                kind: kind.clone(),
+                label: label.clone(),
+            },
+            XRefOp::Update { xref_id, patch } => OpJson::XrefUpdate {
+                xref_id: xref_id.as_str().to_owned(),
+                from: patch.from.as_ref().map(ToString::to_string),
+                to: patch.to.as_ref().map(ToString::to_string),
+                kind: patch.kind.clone(),
+                label: patch.label.clone(),
+            },
+            XRefOp::Remove { xref_id } => {
+                OpJson::XrefRemove { xref_id: xref_id.as_str().to_owned() }
+            }
+        },
+    }
+}
+
+fn op_from_json(json: OpJson) -> Result<Op, StoreError> {
+    let id = |field: &'static str, value: String| {
+        ObjectId::new(value.clone())
+            .map_err(|source| StoreError::InvalidId { field, value, source: Box::new(source) })
+    };
+    let opt_id = |field: &'static str, value: Option<String>| value.map(|v| id(field, v)).transpose();
+    let object_ref = |field: &'static str, value: String| {
+        ObjectRef::parse(&value)
+            .map_err(|source| StoreError::InvalidObjectRef { field, value, source: Box::new(source) })
+    };
+    let xref_id = |value: String| {
+        XRefId::new(value.clone()).map_err(|source| StoreError::InvalidId {
+            field: "ops[].xref_id",
+            value,
+            source: Box::new(source),
+        })
+    };
+
+    Ok(match json {
+        OpJson::SeqAddParticipant { participant_id, mermaid_name } => {
+            Op::Seq(SeqOp::AddParticipant {
+                participant_id: id("ops[].participant_id", participant_id)?,
+                mermaid_name,
+            })
+        }
+        OpJson::SeqUpdateParticipant { participant_id, mermaid_name } => {
+            Op::Seq(SeqOp::UpdateParticipant {
+                participant_id: id("ops[].participant_id", participant_id)?,
+                patch: SeqParticipantPatch { mermaid_name },
+            })
+        }
+        OpJson::SeqSetParticipantNote { participant_id, note } => {
+            Op::Seq(SeqOp::SetParticipantNote {
+                participant_id: id("ops[].participant_id", participant_id)?,
+                note,
+            })
+        }
+        OpJson::SeqSetParticipantAnnotations { participant_id, annotations } => {
+            Op::Seq(SeqOp::SetParticipantAnnotations {
+                participant_id: id("ops[].participant_id", participant_id)?,
+                annotations: annotations.into(),
+            })
+        }
+        OpJson::SeqRemoveParticipant { participant_id } => Op::Seq(SeqOp::RemoveParticipant {
+            participant_id: id("ops[].participant_id", participant_id)?,
+        }),
+        OpJson::SeqSetParticipantOrder { order } => Op::Seq(SeqOp::SetParticipantOrder {
+            order: order
+                .into_iter()
+                .map(|participant_id| id("ops[].order[]", participant_id))
+                .collect::<Result<_, _>>()?,
+        }),
+        OpJson::SeqAddMessage {
+            message_id,
+            from_participant_id,
+            to_participant_id,
+            kind,
+            arrow,
+            text,
+            order_key,
+        } => Op::Seq(SeqOp::AddMessage {
+            message_id: id("ops[].message_id", message_id)?,
+            from_participant_id: id("ops[].from_participant_id", from_participant_id)?,
+            to_participant_id: id("ops[].to_participant_id", to_participant_id)?,
+            kind: kind.into(),
+            arrow,
+            text,
+            order_key,
+        }),
+        OpJson::SeqUpdateMessage {
+            message_id,
+            from_participant_id,
+            to_participant_id,
+            kind,
+            arrow,
+            text,
+            order_key,
+        } => Op::Seq(SeqOp::UpdateMessage {
+            message_id: id("ops[].message_id", message_id)?,
+            patch: SeqMessagePatch {
+                from_participant_id: opt_id("ops[].from_participant_id", from_participant_id)?,
+                to_participant_id: opt_id("ops[].to_participant_id", to_participant_id)?,
+                kind: kind.map(Into::into),
+                arrow,
+                text,
+                order_key,
+            },
+        }),
+        OpJson::SeqSetMessageDuration { message_id, duration } => {
+            Op::Seq(SeqOp::SetMessageDuration {
+                message_id: id("ops[].message_id", message_id)?,
+                duration,
+            })
+        }
+        OpJson::SeqSetMessageBoundary { message_id, boundary } => {
+            Op::Seq(SeqOp::SetMessageBoundary {
+                message_id: id("ops[].message_id", message_id)?,
+                boundary: boundary.map(Into::into),
+            })
+        }
+        OpJson::SeqSetMessageAnnotations { message_id, annotations } => {
+            Op::Seq(SeqOp::SetMessageAnnotations {
+                message_id: id("ops[].message_id", message_id)?,
+                annotations: annotations.into(),
+            })
+        }
+        OpJson::SeqRemoveMessage { message_id } => {
+            Op::Seq(SeqOp::RemoveMessage { message_id: id("ops[].message_id", message_id)? })
+        }
+        OpJson::SeqAddNote { note_id, placement, participant_ids, text, order_key } => {
+            Op::Seq(SeqOp::AddNote {
+                note_id: id("ops[].note_id", note_id)?,
+                placement: placement.into(),
+                participant_ids: participant_ids
+                    .into_iter()
+                    .map(|participant_id| id("ops[].participant_ids[]", participant_id))
+                    .collect::<Result<_, _>>()?,
+                text,
+                order_key,
+            })
+        }
+        OpJson::SeqUpdateNote { note_id, placement, participant_ids, text, order_key } => {
+            Op::Seq(SeqOp::UpdateNote {
+                note_id: id("ops[].note_id", note_id)?,
+                patch: SeqNotePatch {
+                    placement: placement.map(Into::into),
+                    participant_ids: participant_ids
+                        .map(|participant_ids| {
+                            participant_ids
+                                .into_iter()
+                                .map(|participant_id| id("ops[].participant_ids[]", participant_id))
+                                .collect::<Result<_, _>>()
+                        })
+                        .transpose()?,
+                    text,
+                    order_key,
+                },
+            })
+        }
+        OpJson::SeqRemoveNote { note_id } => {
+            Op::Seq(SeqOp::RemoveNote { note_id: id("ops[].note_id", note_id)? })
+        }
+        OpJson::FlowAddNode { node_id, label, shape } => {
+            Op::Flow(FlowOp::AddNode { node_id: id("ops[].node_id", node_id)?, label, shape })
+        }
+        OpJson::FlowUpdateNode { node_id, label, shape } => Op::Flow(FlowOp::UpdateNode {
+            node_id: id("ops[].node_id", node_id)?,
+            patch: FlowNodePatch { label, shape },
+        }),
+        OpJson::FlowSetNodeMermaidId { node_id, mermaid_id } => {
+            Op::Flow(FlowOp::SetNodeMermaidId { node_id: id("ops[].node_id", node_id)?, mermaid_id })
+        }
+        OpJson::FlowSetNodeNote { node_id, note } => {
+            Op::Flow(FlowOp::SetNodeNote { node_id: id("ops[].node_id", node_id)?, note })
+        }
+        OpJson::FlowSetNodeAnnotations { node_id, annotations } => {
+            Op::Flow(FlowOp::SetNodeAnnotations {
+                node_id: id("ops[].node_id", node_id)?,
+                annotations: annotations.into(),
+            })
+        }
+        OpJson::FlowSetNodeOrderHints { order_hints } => Op::Flow(FlowOp::SetNodeOrderHints {
+            order_hints: order_hints
+                .into_iter()
+                .map(|(node_id, hint)| Ok((id("ops[].order_hints", node_id)?, hint)))
+                .collect::<Result<_, StoreError>>()?,
+        }),
+        OpJson::FlowSetNodePin { node_id, pin } => Op::Flow(FlowOp::SetNodePin {
+            node_id: id("ops[].node_id", node_id)?,
+            pin: pin.map(Into::into),
+        }),
+        OpJson::FlowSetNodeEntry { node_id, entry } => {
+            Op::Flow(FlowOp::SetNodeEntry { node_id: id("ops[].node_id", node_id)?, entry })
+        }
+        OpJson::FlowRemoveNode { node_id } => {
+            Op::Flow(FlowOp::RemoveNode { node_id: id("ops[].node_id", node_id)? })
+        }
+        OpJson::FlowAddEdge { edge_id, from_node_id, to_node_id, label, connector, style } => {
+            Op::Flow(FlowOp::AddEdge {
+                edge_id: id("ops[].edge_id", edge_id)?,
+                from_node_id: id("ops[].from_node_id", from_node_id)?,
+                to_node_id: id("ops[].to_node_id", to_node_id)?,
+                label,
+                connector,
+                style,
+            })
+        }
+        OpJson::FlowUpdateEdge { edge_id, from_node_id, to_node_id, label, connector, style } => {
+            Op::Flow(FlowOp::UpdateEdge {
+                edge_id: id("ops[].edge_id", edge_id)?,
+                patch: FlowEdgePatch {
+                    from_node_id: opt_id("ops[].from_node_id", from_node_id)?,
+                    to_node_id: opt_id("ops[].to_node_id", to_node_id)?,
+                    label,
+                    connector,
+                    style,
+                },
+            })
+        }
+        OpJson::FlowSetEdgeWeight { edge_id, weight } => {
+            Op::Flow(FlowOp::SetEdgeWeight { edge_id: id("ops[].edge_id", edge_id)?, weight })
+        }
+        OpJson::FlowSetEdgeAnnotations { edge_id, annotations } => {
+            Op::Flow(FlowOp::SetEdgeAnnotations {
+                edge_id: id("ops[].edge_id", edge_id)?,
+                annotations: annotations.into(),
+            })
+        }
+        OpJson::FlowRemoveEdge { edge_id } => {
+            Op::Flow(FlowOp::RemoveEdge { edge_id: id("ops[].edge_id", edge_id)? })
+        }
+        OpJson::XrefAdd { xref_id: raw_id, from, to, kind, label } => Op::XRef(XRefOp::Add {
+            xref_id: xref_id(raw_id)?,
+            from: object_ref("ops[].from", from)?,
+            to: object_ref("ops[].to", to)?,
+            kind,
+            label,
+        }),
+        OpJson::XrefUpdate { xref_id: raw_id, from, to, kind, label } => {
+            Op::XRef(XRefOp::Update {
+                xref_id: xref_id(raw_id)?,
+                patch: XRefPatch {
+                    from: from.map(|from| object_ref("ops[].from", from)).transpose()?,
+                    to: to.map(|to| object_ref("ops[].to", to)).transpose()?,
+                    kind,
+                    label,
+                },
+            })
+        }
+        OpJson::XrefRemove { xref_id: raw_id } => {
+            Op::XRef(XRefOp::Remove { xref_id: xref_id(raw_id)? })
+        }
+    })
+}
+
+fn id_string(id: &ObjectId) -> String {
+    id.as_str().to_owned()
+}
@@ -6,6 +6,7 @@
 // This file is part of Nereid and is proprietary software.
 // Unauthorized copying, modification, or distribution is prohibited.
 
+use std::collections::BTreeSet;
 use std::env;
 use std::io;
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -14,16 +15,20 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use rstest::{fixture, rstest};
 
 use super::{
-    DiagramMeta, DiagramStableIdMap, DiagramXRef, SessionFolder, SessionMeta, SessionMetaDiagram,
-    StoreError, XRefStatus as StoreXRefStatus,
+    DiagramMeta, DiagramStableIdMap, DiagramXRef, LoadMode, SessionFolder, SessionMeta,
+    SessionMetaDiagram, SessionTemplate, StoreError, XRefStatus as StoreXRefStatus,
+};
+use crate::format::mermaid::{
+    export_flowchart, export_sequence_diagram, parse_class_diagram, parse_sequence_diagram,
+    parse_state_diagram,
 };
-use crate::format::mermaid::{export_flowchart, export_sequence_diagram};
 use crate::layout::{layout_flowchart, layout_sequence};
 use crate::model::{
-    CategoryPath, Diagram, DiagramAst, DiagramId, DiagramKind, FlowEdge, FlowNode, FlowchartAst,
-    ObjectId, ObjectRef, SequenceAst, SequenceMessage, SequenceMessageKind, SequenceParticipant,
-    Session, SessionId, Walkthrough, WalkthroughEdge, WalkthroughId, WalkthroughNode,
-    WalkthroughNodeId, XRef, XRefId, XRefStatus as ModelXRefStatus,
+    CategoryPath, Diagram, DiagramAst, DiagramId, DiagramKind, FlowEdge, FlowNode, FlowNodePin,
+    FlowchartAst, ObjectAnnotations, ObjectId, ObjectRef, SelectionLog, SequenceAst,
+    SequenceMessage, SequenceMessageKind, SequenceParticipant, Session, SessionId, Walkthrough,
+    WalkthroughEdge, WalkthroughId, WalkthroughNode, WalkthroughNodeId, XRef, XRefId,
+    XRefStatus as ModelXRefStatus,
 };
 use crate::render::{
     render_flowchart_unicode, render_sequence_unicode, render_walkthrough_unicode,
@@ -85,18 +90,29 @@ fn save_stores_relative_paths_and_load_resolves_them(ctx: SessionFolderTestCtx)
     let diagram_id = DiagramId::new("d1").unwrap();
     let meta = SessionMeta {
         session_id: SessionId::new("s1").unwrap(),
+        title: None,
+        description: None,
+        tags: Vec::new(),
+        style_rules: Vec::new(),
+        id_prefix_rules: Vec::new(),
         active_diagram_id: Some(diagram_id.clone()),
         active_walkthrough_id: None,
         walkthrough_ids: None,
+        diagram_order: Vec::new(),
         diagrams: vec![SessionMetaDiagram {
             diagram_id,
             name: "Auth Flow".to_owned(),
             kind: DiagramKind::Flowchart,
             mmd_path: session_dir.join("diagrams/auth-flow.mmd"),
             rev: 0,
+            description: None,
+            archived: false,
+            read_only: false,
         }],
         xrefs: Vec::new(),
+        xref_id_counter: 0,
         selected_object_refs: Vec::new(),
+        selection_log: SelectionLog::default(),
     };
 
     folder.save_meta(&meta).unwrap();
@@ -180,18 +196,29 @@ fn load_or_init_session_does_not_hide_missing_diagram_errors(ctx: SessionFolderT
     let missing_mmd_path = ctx.session_dir.join("diagrams/missing.mmd");
     let meta = SessionMeta {
         session_id: SessionId::new("s1").unwrap(),
+        title: None,
+        description: None,
+        tags: Vec::new(),
+        style_rules: Vec::new(),
+        id_prefix_rules: Vec::new(),
         active_diagram_id: None,
         active_walkthrough_id: None,
         walkthrough_ids: Some(Vec::new()),
+        diagram_order: Vec::new(),
         diagrams: vec![SessionMetaDiagram {
             diagram_id: DiagramId::new("d1").unwrap(),
             name: "Missing diagram".to_owned(),
             kind: DiagramKind::Flowchart,
             mmd_path: missing_mmd_path.clone(),
             rev: 0,
+            description: None,
+            archived: false,
+            read_only: false,
         }],
         xrefs: Vec::new(),
+        xref_id_counter: 0,
         selected_object_refs: Vec::new(),
+        selection_log: SelectionLog::default(),
     };
     ctx.folder.save_meta(&meta).unwrap();
 
@@ -238,6 +265,96 @@ fn save_active_diagram_id_updates_meta_and_loads_back(ctx: SessionFolderTestCtx)
     assert_eq!(loaded.active_diagram_id(), Some(&d2));
 }
 
+#[rstest]
+fn save_selected_object_refs_merges_toggles_from_a_stale_writer(ctx: SessionFolderTestCtx) {
+    let folder = &ctx.folder;
+
+    let diagram_id = DiagramId::new("d1").unwrap();
+    let mut ast = FlowchartAst::default();
+    for node_id in ["n:a", "n:b", "n:c"] {
+        ast.nodes_mut().insert(ObjectId::new(node_id).unwrap(), FlowNode::new(node_id));
+    }
+    let mut session = Session::new(SessionId::new("s1").unwrap());
+    session
+        .diagrams_mut()
+        .insert(diagram_id.clone(), Diagram::new(diagram_id, "D1", DiagramAst::Flowchart(ast)));
+    let node = |id: &str| ObjectRef::parse(&format!("d:d1/flow/node/{id}")).unwrap();
+    session.set_selected_object_refs(BTreeSet::from([node("n:a")]));
+    folder.save_session(&session).unwrap();
+
+    // Both sides start from the same selection; the agent saves first.
+    let mut agent = folder.load_session().unwrap();
+    let mut human = folder.load_session().unwrap();
+    agent.selected_object_refs_mut().insert(node("n:b"));
+    folder.save_selected_object_refs(&mut agent).unwrap();
+
+    human.selected_object_refs_mut().remove(&node("n:a"));
+    human.selected_object_refs_mut().insert(node("n:c"));
+    folder.save_selected_object_refs(&mut human).unwrap();
+
+    let expected = BTreeSet::from([node("n:b"), node("n:c")]);
+    assert_eq!(human.selected_object_refs(), &expected);
+    let loaded = folder.load_session().unwrap();
+    assert_eq!(loaded.selected_object_refs(), &expected);
+    assert_eq!(loaded.selection_log(), human.selection_log());
+
+    // Full saves from the stale agent session keep the human's toggles too.
+    folder.save_session(&agent).unwrap();
+    assert_eq!(folder.load_session().unwrap().selected_object_refs(), &expected);
+}
+
+#[rstest]
+fn save_session_info_updates_meta_and_loads_back(ctx: SessionFolderTestCtx) {
+    let folder = &ctx.folder;
+
+    let mut session = Session::new(SessionId::new("s1").unwrap());
+    folder.save_session(&session).unwrap();
+    let meta_str = std::fs::read_to_string(folder.meta_path()).unwrap();
+    assert!(!meta_str.contains("\"title\""));
+
+    session.set_title(Some("Checkout flows"));
+    session.set_description(Some("Payment and refund paths"));
+    session.set_tags([" payments ", "", "refunds", "payments"]);
+    folder.save_session_info(&session).unwrap();
+
+    let meta = folder.load_meta().unwrap();
+    assert_eq!(meta.title.as_deref(), Some("Checkout flows"));
+    assert_eq!(meta.tags, vec!["payments".to_owned(), "refunds".to_owned()]);
+
+    let loaded = folder.load_session().unwrap();
+    assert_eq!(loaded.title(), Some("Checkout flows"));
+    assert_eq!(loaded.description(), Some("Payment and refund paths"));
+    assert_eq!(loaded.tags(), ["payments".to_owned(), "refunds".to_owned()]);
+}
+
+#[rstest]
+fn save_diagram_description_updates_meta_and_loads_back(ctx: SessionFolderTestCtx) {
+    let folder = &ctx.folder;
+
+    let diagram_id = DiagramId::new("d1").unwrap();
+    let mut session = Session::new(SessionId::new("s1").unwrap());
+    session.diagrams_mut().insert(
+        diagram_id.clone(),
+        Diagram::new(
+            diagram_id.clone(),
+            "Diagram 1",
+            DiagramAst::Flowchart(FlowchartAst::default()),
+        ),
+    );
+    folder.save_session(&session).unwrap();
+    let meta_str = std::fs::read_to_string(folder.meta_path()).unwrap();
+    assert!(!meta_str.contains("\"description\""));
+
+    let diagram = session.diagrams_mut().get_mut(&diagram_id).unwrap();
+    diagram.set_description(Some("## Scope\n\nHappy path only.\n"));
+    folder.save_diagram_description(&session, &diagram_id).unwrap();
+
+    let loaded = folder.load_session().unwrap();
+    let loaded_diagram = loaded.diagrams().get(&diagram_id).unwrap();
+    assert_eq!(loaded_diagram.description(), Some("## Scope\n\nHappy path only."));
+    assert_eq!(loaded_diagram.rev(), 0);
+}
+
 #[rstest]
 fn save_diagram_meta_stores_relative_paths_and_load_resolves_them(ctx: SessionFolderTestCtx) {
     let session_dir = &ctx.session_dir;
@@ -267,6 +384,14 @@ fn save_diagram_meta_stores_relative_paths_and_load_resolves_them(ctx: SessionFo
         sequence_messages: Vec::new(),
         flow_node_notes: Default::default(),
         sequence_participant_notes: Default::default(),
+        flow_node_annotations: Default::default(),
+        sequence_participant_annotations: Default::default(),
+        flow_node_order_hints: Default::default(),
+        flow_node_pins: Default::default(),
+        flow_entry_nodes: Default::default(),
+        sequence_participant_order: Vec::new(),
+        mmd_fingerprint: None,
+        objects: Default::default(),
     };
 
     folder.save_diagram_meta(&meta).unwrap();
@@ -297,6 +422,14 @@ fn save_diagram_meta_rejects_paths_outside_session(ctx: SessionFolderTestCtx) {
         sequence_messages: Vec::new(),
         flow_node_notes: Default::default(),
         sequence_participant_notes: Default::default(),
+        flow_node_annotations: Default::default(),
+        sequence_participant_annotations: Default::default(),
+        flow_node_order_hints: Default::default(),
+        flow_node_pins: Default::default(),
+        flow_entry_nodes: Default::default(),
+        sequence_participant_order: Vec::new(),
+        mmd_fingerprint: None,
+        objects: Default::default(),
     };
 
     let err = folder.save_diagram_meta(&meta).unwrap_err();
@@ -910,6 +1043,249 @@ fn save_and_load_session_round_trips_inline_notes_via_sidecar(ctx: SessionFolder
     assert_eq!(loaded, session);
 }
 
+#[rstest]
+fn save_and_load_session_round_trips_annotations_via_sidecar(ctx: SessionFolderTestCtx) {
+    let folder = &ctx.folder;
+    let annotations = |tag: &str| {
+        ObjectAnnotations::new(
+            [tag],
+            std::collections::BTreeMap::from([("owner".to_owned(), "team-a".to_owned())]),
+        )
+    };
+
+    let mut session = Session::new(SessionId::new("s1").unwrap());
+
+    let seq_id = DiagramId::new("d1").unwrap();
+    let mut seq_ast = SequenceAst::default();
+    let p_alice = ObjectId::new("p:Alice").unwrap();
+    let p_bob = ObjectId::new("p:Bob").unwrap();
+    let mut alice = SequenceParticipant::new("Alice");
+    alice.set_annotations(annotations("frontend"));
+    seq_ast.participants_mut().insert(p_alice.clone(), alice);
+    seq_ast.participants_mut().insert(p_bob.clone(), SequenceParticipant::new("Bob"));
+    let mut hello = SequenceMessage::new(
+        ObjectId::new("m:0001").unwrap(),
+        p_alice,
+        p_bob,
+        SequenceMessageKind::Sync,
+        "Hello",
+        1000,
+    );
+    hello.set_annotations(annotations("billing"));
+    seq_ast.messages_mut().push(hello);
+    session
+        .diagrams_mut()
+        .insert(seq_id.clone(), Diagram::new(seq_id, "Seq", DiagramAst::Sequence(seq_ast)));
+
+    let flow_id = DiagramId::new("d2").unwrap();
+    let mut flow_ast = FlowchartAst::default();
+    let node_start_id = ObjectId::new("n:start").unwrap();
+    let node_end_id = ObjectId::new("n:end").unwrap();
+    let mut node_start = FlowNode::new("Start");
+    node_start.set_mermaid_id(Some("start"));
+    node_start.set_annotations(annotations("legacy"));
+    flow_ast.nodes_mut().insert(node_start_id.clone(), node_start);
+    let mut node_end = FlowNode::new("End");
+    node_end.set_mermaid_id(Some("end"));
+    flow_ast.nodes_mut().insert(node_end_id.clone(), node_end);
+    let mut edge = FlowEdge::new(node_start_id, node_end_id);
+    edge.set_annotations(annotations("hot-path"));
+    flow_ast.edges_mut().insert(ObjectId::new("e:0001").unwrap(), edge);
+    session
+        .diagrams_mut()
+        .insert(flow_id.clone(), Diagram::new(flow_id, "Flow", DiagramAst::Flowchart(flow_ast)));
+
+    folder.save_session(&session).unwrap();
+    let loaded = folder.load_session().unwrap();
+
+    assert_eq!(loaded, session);
+}
+
+#[rstest]
+fn save_and_load_flowchart_round_trips_node_order_hints_via_sidecar(ctx: SessionFolderTestCtx) {
+    let folder = &ctx.folder;
+
+    let mut session = Session::new(SessionId::new("s1").unwrap());
+
+    let flow_id = DiagramId::new("d1").unwrap();
+    let mut flow_ast = FlowchartAst::default();
+    let node_a = ObjectId::new("n:a").unwrap();
+    let node_b = ObjectId::new("n:b").unwrap();
+    let mut a = FlowNode::new("A");
+    a.set_mermaid_id(Some("a"));
+    let mut b = FlowNode::new("B");
+    b.set_mermaid_id(Some("b"));
+    flow_ast.nodes_mut().insert(node_a.clone(), a);
+    flow_ast.nodes_mut().insert(node_b.clone(), b);
+    flow_ast.node_order_hints_mut().insert(node_a, 1);
+    flow_ast.node_order_hints_mut().insert(node_b, 0);
+    session.diagrams_mut().insert(
+        flow_id.clone(),
+        Diagram::new(flow_id, "Flow Hints", DiagramAst::Flowchart(flow_ast)),
+    );
+
+    folder.save_session(&session).unwrap();
+    let loaded = folder.load_session().unwrap();
+
+    assert_eq!(loaded, session);
+}
+
+#[rstest]
+fn save_and_load_flowchart_round_trips_node_pins_via_sidecar(ctx: SessionFolderTestCtx) {
+    let folder = &ctx.folder;
+
+    let mut session = Session::new(SessionId::new("s1").unwrap());
+
+    let flow_id = DiagramId::new("d1").unwrap();
+    let mut flow_ast = FlowchartAst::default();
+    let node_a = ObjectId::new("n:a").unwrap();
+    let node_b = ObjectId::new("n:b").unwrap();
+    let mut a = FlowNode::new("A");
+    a.set_mermaid_id(Some("a"));
+    let mut b = FlowNode::new("B");
+    b.set_mermaid_id(Some("b"));
+    flow_ast.nodes_mut().insert(node_a, a);
+    flow_ast.nodes_mut().insert(node_b.clone(), b);
+    flow_ast.node_pins_mut().insert(node_b.clone(), FlowNodePin::new(3, 1));
+    session.diagrams_mut().insert(
+        flow_id.clone(),
+        Diagram::new(flow_id, "Flow Pins", DiagramAst::Flowchart(flow_ast)),
+    );
+
+    folder.save_session(&session).unwrap();
+    let mmd_path = folder.load_meta().unwrap().diagrams[0].mmd_path.clone();
+    let sidecar = folder.load_diagram_meta(&mmd_path).unwrap();
+    assert_eq!(sidecar.flow_node_pins.get(&node_b), Some(&FlowNodePin::new(3, 1)));
+
+    let loaded = folder.load_session().unwrap();
+    assert_eq!(loaded, session);
+}
+
+#[rstest]
+fn save_and_load_sequence_round_trips_participant_order_via_sidecar(ctx: SessionFolderTestCtx) {
+    let folder = &ctx.folder;
+
+    let mut session = Session::new(SessionId::new("s1").unwrap());
+
+    let seq_id = DiagramId::new("d1").unwrap();
+    let mut seq_ast =
+        parse_sequence_diagram("sequenceDiagram\nparticipant A\nparticipant B\nA->>B: hi\n")
+            .unwrap();
+    *seq_ast.participant_order_mut() =
+        vec![ObjectId::new("p:B").unwrap(), ObjectId::new("p:A").unwrap()];
+    session
+        .diagrams_mut()
+        .insert(seq_id.clone(), Diagram::new(seq_id, "Seq Order", DiagramAst::Sequence(seq_ast)));
+
+    folder.save_session(&session).unwrap();
+    let mmd = std::fs::read_to_string(ctx.session_dir.join("diagrams/d1.mmd")).unwrap();
+    assert!(mmd.starts_with("sequenceDiagram\nparticipant B\nparticipant A\n"), "{mmd}");
+    let loaded = folder.load_session().unwrap();
+
+    assert_eq!(loaded, session);
+}
+
+#[rstest]
+fn save_and_load_session_round_trips_xref_id_counter(ctx: SessionFolderTestCtx) {
+    let folder = &ctx.folder;
+
+    let mut session = Session::new(SessionId::new("s1").unwrap());
+    folder.save_session(&session).unwrap();
+    let meta_json = std::fs::read_to_string(ctx.session_dir.join("nereid-session.meta.json"));
+    assert!(!meta_json.unwrap().contains("xref_id_counter"));
+
+    session.set_xref_id_counter(7);
+    folder.save_session(&session).unwrap();
+    let loaded = folder.load_session().unwrap();
+
+    assert_eq!(loaded.xref_id_counter(), 7);
+    assert_eq!(loaded, session);
+}
+
+#[rstest]
+fn save_and_load_session_round_trips_state_diagrams(ctx: SessionFolderTestCtx) {
+    let folder = &ctx.folder;
+
+    let mut session = Session::new(SessionId::new("s1").unwrap());
+
+    let state_id = DiagramId::new("d-state").unwrap();
+    let state_ast = parse_state_diagram(
+        "stateDiagram-v2\n[*] --> Idle\nIdle --> Busy : work\nBusy --> Idle\nnote right of Busy : slow\n",
+    )
+    .unwrap();
+    session.diagrams_mut().insert(
+        state_id.clone(),
+        Diagram::new(state_id.clone(), "Worker", DiagramAst::State(state_ast)),
+    );
+
+    folder.save_session(&session).unwrap();
+    let loaded = folder.load_session().unwrap();
+
+    assert_eq!(loaded, session);
+    folder.flush_ascii_exports();
+    let ascii = std::fs::read_to_string(folder.root().join("diagrams/d-state.ascii.txt")).unwrap();
+    assert!(ascii.contains("Busy"), "unexpected ascii export:\n{ascii}");
+}
+
+#[rstest]
+fn save_and_load_session_round_trips_class_diagrams(ctx: SessionFolderTestCtx) {
+    let folder = &ctx.folder;
+
+    let mut session = Session::new(SessionId::new("s1").unwrap());
+
+    let class_id = DiagramId::new("d-class").unwrap();
+    let class_ast = parse_class_diagram(
+        "classDiagram\nclass Animal {\n<<abstract>>\n+name String\n+speak()\n}\n\
+         Animal <|-- Duck\nPond \"1\" o-- \"*\" Duck : holds\nnote for Duck \"quacks\"\n",
+    )
+    .unwrap();
+    let mut diagram = Diagram::new(class_id.clone(), "Zoo", DiagramAst::Class(class_ast));
+    diagram.set_archived(true);
+    diagram.set_read_only(true);
+    session.diagrams_mut().insert(class_id.clone(), diagram);
+
+    folder.save_session(&session).unwrap();
+    let loaded = folder.load_session().unwrap();
+
+    assert_eq!(loaded, session);
+    folder.flush_ascii_exports();
+    let ascii = std::fs::read_to_string(folder.root().join("diagrams/d-class.ascii.txt")).unwrap();
+    assert!(ascii.contains("«abstract»"), "unexpected ascii export:\n{ascii}");
+}
+
+#[rstest]
+fn save_and_load_flowchart_round_trips_entry_nodes_via_sidecar(ctx: SessionFolderTestCtx) {
+    let folder = &ctx.folder;
+
+    let mut session = Session::new(SessionId::new("s1").unwrap());
+
+    let flow_id = DiagramId::new("d1").unwrap();
+    let mut flow_ast = FlowchartAst::default();
+    let node_a = ObjectId::new("n:a").unwrap();
+    let node_b = ObjectId::new("n:b").unwrap();
+    flow_ast.nodes_mut().insert(node_a.clone(), FlowNode::new("A"));
+    flow_ast.nodes_mut().insert(node_b.clone(), FlowNode::new("B"));
+    flow_ast
+        .edges_mut()
+        .insert(ObjectId::new("e:ab").unwrap(), FlowEdge::new(node_a, node_b.clone()));
+    flow_ast.entry_nodes_mut().insert(node_b);
+    session.diagrams_mut().insert(
+        flow_id.clone(),
+        Diagram::new(flow_id.clone(), "Flow Entry", DiagramAst::Flowchart(flow_ast)),
+    );
+
+    folder.save_session(&session).unwrap();
+    let mmd_path = folder.load_meta().unwrap().diagrams[0].mmd_path.clone();
+    let sidecar = folder.load_diagram_meta(&mmd_path).unwrap();
+    assert_eq!(sidecar.flow_entry_nodes.len(), 1);
+
+    let loaded = folder.load_session().unwrap();
+    let DiagramAst::Flowchart(loaded_ast) = loaded.diagrams().get(&flow_id).unwrap().ast() else {
+        panic!("expected flowchart ast");
+    };
+    assert_eq!(loaded_ast.entry_nodes().iter().map(|id| id.as_str()).collect::<Vec<_>>(), ["n:b"]);
+}
+
 #[rstest]
 fn save_and_load_flowchart_preserves_edge_ids_and_style_via_sidecar(ctx: SessionFolderTestCtx) {
     let folder = &ctx.folder;
@@ -1047,6 +1423,250 @@ fn save_and_load_sequence_preserves_message_ids_via_sidecar_even_when_parse_orde
     assert_eq!(second.text(), "Second");
 }
 
+#[rstest]
+fn save_and_load_sequence_keeps_message_durations_with_their_stable_ids(ctx: SessionFolderTestCtx) {
+    let folder = &ctx.folder;
+
+    let mut session = Session::new(SessionId::new("s1").unwrap());
+
+    let seq_id = DiagramId::new("d1").unwrap();
+    let mut seq_ast = SequenceAst::default();
+    let p_alice = ObjectId::new("p:Alice").unwrap();
+    let p_bob = ObjectId::new("p:Bob").unwrap();
+    seq_ast.participants_mut().insert(p_alice.clone(), SequenceParticipant::new("Alice"));
+    seq_ast.participants_mut().insert(p_bob.clone(), SequenceParticipant::new("Bob"));
+    let mut slow = SequenceMessage::new(
+        ObjectId::new("m:slow").unwrap(),
+        p_alice.clone(),
+        p_bob.clone(),
+        SequenceMessageKind::Sync,
+        "Slow",
+        1000,
+    );
+    slow.set_duration(Some(250));
+    seq_ast.messages_mut().push(slow);
+    seq_ast.messages_mut().push(SequenceMessage::new(
+        ObjectId::new("m:fast").unwrap(),
+        p_bob,
+        p_alice,
+        SequenceMessageKind::Return,
+        "Fast",
+        2000,
+    ));
+    session
+        .diagrams_mut()
+        .insert(seq_id.clone(), Diagram::new(seq_id.clone(), "Seq", DiagramAst::Sequence(seq_ast)));
+
+    folder.save_session(&session).unwrap();
+
+    let mmd_path = folder.default_diagram_mmd_path(&seq_id);
+    std::fs::write(&mmd_path, "sequenceDiagram\n  Bob -->> Alice: Fast\n  Alice ->> Bob: Slow\n")
+        .unwrap();
+
+    let loaded = folder.load_session().unwrap();
+    let DiagramAst::Sequence(loaded_ast) = loaded.diagrams().get(&seq_id).unwrap().ast() else {
+        panic!("expected sequence ast");
+    };
+    let durations = loaded_ast
+        .messages()
+        .iter()
+        .map(|msg| (msg.message_id().as_str(), msg.duration()))
+        .collect::<Vec<_>>();
+    assert_eq!(durations, [("m:fast", None), ("m:slow", Some(250))]);
+}
+
+#[rstest]
+fn load_session_keeps_anchored_message_ids_when_their_text_changes(ctx: SessionFolderTestCtx) {
+    let folder = &ctx.folder;
+
+    let mut session = Session::new(SessionId::new("s1").unwrap());
+
+    let seq_id = DiagramId::new("d1").unwrap();
+    let mut seq_ast = SequenceAst::default();
+    let p_alice = ObjectId::new("p:Alice").unwrap();
+    let p_bob = ObjectId::new("p:Bob").unwrap();
+    seq_ast.participants_mut().insert(p_alice.clone(), SequenceParticipant::new("Alice"));
+    seq_ast.participants_mut().insert(p_bob.clone(), SequenceParticipant::new("Bob"));
+    let mut slow = SequenceMessage::new(
+        ObjectId::new("m:slow").unwrap(),
+        p_alice.clone(),
+        p_bob.clone(),
+        SequenceMessageKind::Sync,
+        "Slow",
+        1000,
+    );
+    slow.set_duration(Some(250));
+    seq_ast.messages_mut().push(slow);
+    seq_ast.messages_mut().push(SequenceMessage::new(
+        ObjectId::new("m:fast").unwrap(),
+        p_bob,
+        p_alice,
+        SequenceMessageKind::Return,
+        "Fast",
+        2000,
+    ));
+    session
+        .diagrams_mut()
+        .insert(seq_id.clone(), Diagram::new(seq_id.clone(), "Seq", DiagramAst::Sequence(seq_ast)));
+
+    folder.save_session(&session).unwrap();
+
+    // Both texts change, so only the anchors can tell the messages apart.
+    let mmd_path = folder.default_diagram_mmd_path(&seq_id);
+    let mmd = std::fs::read_to_string(&mmd_path).unwrap();
+    assert!(mmd.contains("%% nereid:id=m:slow\nAlice->>Bob: Slow\n"), "{mmd}");
+    std::fs::write(&mmd_path, mmd.replace("Slow", "Slower").replace("Fast", "Faster")).unwrap();
+
+    let loaded = folder.load_session().unwrap();
+    let DiagramAst::Sequence(loaded_ast) = loaded.diagrams().get(&seq_id).unwrap().ast() else {
+        panic!("expected sequence ast");
+    };
+    let messages = loaded_ast
+        .messages()
+        .iter()
+        .map(|msg| (msg.message_id().as_str(), msg.text(), msg.duration()))
+        .collect::<Vec<_>>();
+    assert_eq!(messages, [("m:slow", "Slower", Some(250)), ("m:fast", "Faster", None)]);
+}
+
+fn two_flowchart_session() -> Session {
+    let mut session = Session::new(SessionId::new("s1").unwrap());
+    for (diagram_id, node_id) in [("d1", "n:a"), ("d2", "n:b")] {
+        let diagram_id = DiagramId::new(diagram_id).unwrap();
+        let mut ast = FlowchartAst::default();
+        ast.nodes_mut().insert(ObjectId::new(node_id).unwrap(), FlowNode::new(node_id));
+        session.diagrams_mut().insert(
+            diagram_id.clone(),
+            Diagram::new(diagram_id, "Flow", DiagramAst::Flowchart(ast)),
+        );
+    }
+    session
+}
+
+#[rstest]
+fn lazy_load_parses_each_diagram_on_first_access(ctx: SessionFolderTestCtx) {
+    let folder = &ctx.folder;
+    folder.save_session(&two_flowchart_session()).unwrap();
+
+    let eager = folder.load_session().unwrap();
+    let lazy =
+        SessionFolder::new(folder.root()).with_load_mode(LoadMode::Lazy).load_session().unwrap();
+
+    let d1 = DiagramId::new("d1").unwrap();
+    let d2 = DiagramId::new("d2").unwrap();
+    assert!(lazy.diagrams().values().all(|diagram| !diagram.is_loaded()));
+    assert_eq!(lazy.diagrams()[&d1].kind(), DiagramKind::Flowchart);
+
+    assert_eq!(lazy.diagrams()[&d1].ast(), eager.diagrams()[&d1].ast());
+    assert!(lazy.diagrams()[&d1].is_loaded());
+    assert!(!lazy.diagrams()[&d2].is_loaded());
+
+    assert_eq!(lazy, eager);
+}
+
+#[rstest]
+fn lazy_load_refreshes_xref_statuses_from_the_sidecar_object_index(ctx: SessionFolderTestCtx) {
+    let folder = &ctx.folder;
+    let mut session = two_flowchart_session();
+    let node_ref = |diagram_id: &str, node_id: &str| {
+        ObjectRef::new(
+            DiagramId::new(diagram_id).unwrap(),
+            CategoryPath::new(vec!["flow".to_owned(), "node".to_owned()]).unwrap(),
+            ObjectId::new(node_id).unwrap(),
+        )
+    };
+    let linked = XRefId::new("x:1").unwrap();
+    let dangling = XRefId::new("x:2").unwrap();
+    // Saved with stale statuses that the load has to correct.
+    session.xrefs_mut().insert(
+        linked.clone(),
+        XRef::new(
+            node_ref("d1", "n:a"),
+            node_ref("d2", "n:b"),
+            "flow",
+            ModelXRefStatus::DanglingTo,
+        ),
+    );
+    session.xrefs_mut().insert(
+        dangling.clone(),
+        XRef::new(node_ref("d1", "n:a"), node_ref("d2", "n:gone"), "flow", ModelXRefStatus::Ok),
+    );
+    folder.save_session(&session).unwrap();
+
+    let lazy_folder = SessionFolder::new(folder.root()).with_load_mode(LoadMode::Lazy);
+    let lazy = lazy_folder.load_session().unwrap();
+    assert_eq!(lazy.xrefs()[&linked].status(), ModelXRefStatus::Ok);
+    assert_eq!(lazy.xrefs()[&dangling].status(), ModelXRefStatus::DanglingTo);
+    assert!(lazy.diagrams().values().all(|diagram| !diagram.is_loaded()));
+
+    // A direct `.mmd` edit makes the index stale, so that diagram is parsed instead.
+    let d2 = DiagramId::new("d2").unwrap();
+    std::fs::write(folder.default_diagram_mmd_path(&d2), "flowchart TD\n  gone\n").unwrap();
+    let lazy = lazy_folder.load_session().unwrap();
+    assert_eq!(lazy.xrefs()[&linked].status(), ModelXRefStatus::DanglingTo);
+    assert!(lazy.diagrams()[&d2].is_loaded());
+    assert!(!lazy.diagrams()[&DiagramId::new("d1").unwrap()].is_loaded());
+}
+
+#[rstest]
+fn lazy_sessions_loaded_from_the_same_files_compare_equal_without_parsing(
+    ctx: SessionFolderTestCtx,
+) {
+    let folder = &ctx.folder;
+    folder.save_session(&two_flowchart_session()).unwrap();
+
+    let lazy_folder = SessionFolder::new(folder.root()).with_load_mode(LoadMode::Lazy);
+    let first = lazy_folder.load_session().unwrap();
+    let second = lazy_folder.load_session().unwrap();
+    assert_eq!(first, second);
+    assert!(first.diagrams().values().chain(second.diagrams().values()).all(|d| !d.is_loaded()));
+
+    let d1 = DiagramId::new("d1").unwrap();
+    std::fs::write(folder.default_diagram_mmd_path(&d1), "flowchart TD\n  other\n").unwrap();
+    let changed = lazy_folder.load_session().unwrap();
+    assert_ne!(first, changed);
+}
+
+#[rstest]
+fn lazy_load_leaves_unparseable_diagram_files_untouched(ctx: SessionFolderTestCtx) {
+    use crate::ops::{apply_ops, ApplyError, FlowOp, Op};
+
+    let folder = &ctx.folder;
+    folder.save_session(&two_flowchart_session()).unwrap();
+
+    let d1 = DiagramId::new("d1").unwrap();
+    let mmd_path = folder.default_diagram_mmd_path(&d1);
+    std::fs::write(&mmd_path, "flowchart LR\n  a --> \n").unwrap();
+    assert!(folder.load_session().is_err());
+
+    let lazy_folder = SessionFolder::new(folder.root()).with_load_mode(LoadMode::Lazy);
+    let mut session = lazy_folder.load_session().unwrap();
+    let diagram = session.diagrams_mut().get_mut(&d1).unwrap();
+    assert_eq!(diagram.ast(), &DiagramAst::Flowchart(FlowchartAst::default()));
+    assert!(diagram.load_error().is_some());
+
+    let ops = [Op::Flow(FlowOp::AddNode {
+        node_id: ObjectId::new("n:new").unwrap(),
+        label: "New".to_owned(),
+        shape: None,
+    })];
+    assert!(matches!(
+        apply_ops(diagram, 0, &ops),
+        Err(ApplyError::LoadFailed { diagram_id, .. }) if diagram_id == d1
+    ));
+    assert_eq!(diagram.rev(), 0);
+
+    diagram.bump_rev();
+    lazy_folder.save_session(&session).unwrap();
+    lazy_folder.flush_ascii_exports();
+
+    assert_eq!(std::fs::read_to_string(&mmd_path).unwrap(), "flowchart LR\n  a --> \n");
+    let meta = lazy_folder.load_meta().unwrap();
+    assert_eq!(meta.diagrams.len(), 2);
+    let d1_meta = meta.diagrams.iter().find(|diagram| diagram.diagram_id == d1).unwrap();
+    assert_eq!(d1_meta.rev, 0);
+}
+
 #[rstest]
 fn load_session_does_not_reuse_message_ids_from_sidecar_for_new_messages(
     ctx: SessionFolderTestCtx,
@@ -1220,3 +1840,149 @@ fn load_session_errors_when_diagram_sidecar_is_invalid_json(ctx: SessionFolderTe
         other => panic!("expected Json error, got: {other:?}"),
     }
 }
+
+#[rstest]
+fn ops_journal_replays_unsynced_batches_once(ctx: SessionFolderTestCtx) {
+    use super::{JournalActor, JournalEntry};
+    use crate::ops::{FlowOp, Op};
+    use std::io::Write as _;
+
+    let folder = &ctx.folder;
+    let session = folder.load_or_init_session().unwrap();
+    let diagram_id = DiagramId::new("flow").unwrap();
+    let base_rev = session.diagrams().get(&diagram_id).unwrap().rev();
+
+    let node_id = ObjectId::new("n:b").unwrap();
+    let ops = vec![Op::Flow(FlowOp::AddNode {
+        node_id: node_id.clone(),
+        label: "Bye".to_owned(),
+        shape: Some("round".to_owned()),
+    })];
+    let entry =
+        JournalEntry::new(JournalActor::Agent, diagram_id.clone(), base_rev, base_rev + 1, ops);
+    folder.append_ops_journal(&entry).unwrap();
+
+    // A crash mid-append leaves a torn last line, which loading ignores.
+    let mut file = std::fs::OpenOptions::new().append(true).open(folder.ops_log_path()).unwrap();
+    file.write_all(br#"{"at":1,"actor":"hum"#).unwrap();
+    drop(file);
+    assert_eq!(folder.load_ops_journal().unwrap(), vec![entry]);
+
+    let mut recovered = folder.load_session().unwrap();
+    assert_eq!(folder.recover_from_ops_journal(&mut recovered).unwrap(), 1);
+    let diagram = recovered.diagrams().get(&diagram_id).unwrap();
+    assert_eq!(diagram.rev(), base_rev + 1);
+    match diagram.ast() {
+        DiagramAst::Flowchart(ast) => assert_eq!(ast.nodes()[&node_id].label(), "Bye"),
+        other => panic!("expected flowchart, got: {other:?}"),
+    }
+
+    // Recovery persisted the batch, so a second pass finds nothing left to replay.
+    let mut reloaded = folder.load_session().unwrap();
+    assert_eq!(reloaded.diagrams().get(&diagram_id).unwrap().rev(), base_rev + 1);
+    assert!(folder.replay_ops_journal(&mut reloaded).unwrap().is_empty());
+}
+
+#[rstest]
+fn ops_journal_round_trips_sequence_note_ops(ctx: SessionFolderTestCtx) {
+    use super::{JournalActor, JournalEntry};
+    use crate::model::SequenceNotePlacement;
+    use crate::ops::{Op, SeqNotePatch, SeqOp};
+
+    let folder = &ctx.folder;
+    let note_id = ObjectId::new("note:1").unwrap();
+    let participant_ids = vec![ObjectId::new("p:a").unwrap(), ObjectId::new("p:b").unwrap()];
+    let ops = vec![
+        Op::Seq(SeqOp::AddNote {
+            note_id: note_id.clone(),
+            placement: SequenceNotePlacement::Over,
+            participant_ids: participant_ids.clone(),
+            text: "handshake".to_owned(),
+            order_key: 1500,
+        }),
+        Op::Seq(SeqOp::UpdateNote {
+            note_id: note_id.clone(),
+            patch: SeqNotePatch {
+                placement: Some(SequenceNotePlacement::LeftOf),
+                participant_ids: Some(participant_ids[..1].to_vec()),
+                text: None,
+                order_key: Some(2500),
+            },
+        }),
+        Op::Seq(SeqOp::RemoveNote { note_id }),
+    ];
+    let entry = JournalEntry::new(JournalActor::Human, DiagramId::new("seq").unwrap(), 0, 3, ops);
+    folder.append_ops_journal(&entry).unwrap();
+
+    assert_eq!(folder.load_ops_journal().unwrap(), vec![entry]);
+}
+
+#[rstest]
+fn session_archive_round_trips_into_an_empty_folder(ctx: SessionFolderTestCtx) {
+    let folder = &ctx.folder;
+    let mut session = folder.load_or_init_session().unwrap();
+    let walkthrough_id = WalkthroughId::new("w1").unwrap();
+    session
+        .walkthroughs_mut()
+        .insert(walkthrough_id.clone(), Walkthrough::new(walkthrough_id, "Tour"));
+    let node_ref = ObjectRef::parse("d:flow/flow/node/n:hello").unwrap();
+    session.xrefs_mut().insert(
+        XRefId::new("x:1").unwrap(),
+        XRef::new(node_ref.clone(), node_ref, "relates_to", ModelXRefStatus::Ok),
+    );
+    folder.save_session(&session).unwrap();
+    folder.flush_ascii_exports();
+
+    let archive_path = ctx.tmp.path().join("shared.nereid.tar.zst");
+    // Meta, the diagram with its sidecar and the walkthrough; text renders stay behind.
+    assert_eq!(folder.export_archive(&archive_path).unwrap(), 4);
+
+    let copy = SessionFolder::new(ctx.tmp.path().join("copy"));
+    let imported = copy.import_archive(&archive_path).unwrap();
+    assert_eq!(imported, folder.load_session().unwrap());
+    assert!(!copy
+        .diagram_ascii_path(&copy.default_diagram_mmd_path(&DiagramId::new("flow").unwrap()))
+        .unwrap()
+        .exists());
+
+    match copy.import_archive(&archive_path) {
+        Err(StoreError::SessionExists { session_dir }) => assert_eq!(session_dir, copy.root()),
+        other => panic!("expected SessionExists, got: {other:?}"),
+    }
+
+    let future_path = ctx.tmp.path().join("future.nereid.tar.zst");
+    let encoder = zstd::Encoder::new(std::fs::File::create(&future_path).unwrap(), 0).unwrap();
+    let mut builder = tar::Builder::new(encoder);
+    let manifest = br#"{"version":99,"session_id":"s:x"}"#;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    builder.append_data(&mut header, "nereid-archive.json", &manifest[..]).unwrap();
+    builder.into_inner().unwrap().finish().unwrap();
+    match SessionFolder::new(ctx.tmp.path().join("future")).import_archive(&future_path) {
+        Err(StoreError::InvalidArchive { reason, .. }) => {
+            assert!(reason.contains("unsupported archive version 99"), "{reason}")
+        }
+        other => panic!("expected InvalidArchive, got: {other:?}"),
+    }
+}
+
+#[rstest]
+fn init_from_template_scaffolds_an_empty_folder_once(ctx: SessionFolderTestCtx) {
+    let folder = SessionFolder::new(ctx.tmp.path().join("shop"));
+    let session = folder.init_from_template(SessionTemplate::Microservices).unwrap();
+    assert_eq!(session, SessionTemplate::Microservices.session(SessionId::new("s:shop").unwrap()));
+    assert_eq!(session.title(), Some("Microservice map"));
+    assert_eq!(session.active_diagram_id().map(DiagramId::as_str), Some("services"));
+    let DiagramAst::Sequence(checkout) =
+        session.diagrams().get(&DiagramId::new("checkout").unwrap()).unwrap().ast()
+    else {
+        panic!("checkout should be a sequence diagram");
+    };
+    assert_eq!(checkout.participants_in_order().first().map(|id| id.as_str()), Some("p:web"));
+    assert!(session.xrefs().values().all(|xref| xref.status() == ModelXRefStatus::Ok));
+
+    match folder.init_from_template(SessionTemplate::Context) {
+        Err(StoreError::SessionExists { session_dir }) => assert_eq!(session_dir, folder.root()),
+        other => panic!("expected SessionExists, got: {other:?}"),
+    }
+}
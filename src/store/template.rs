@@ -0,0 +1,359 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Built-in templates for scaffolding new sessions.
+//!
+//! A template is a few Mermaid diagrams plus the xrefs tying them together. `nereid new
+//! <template>` and the MCP `session.init_from_template` tool write one into a folder without a
+//! session via [`SessionFolder::init_from_template`](super::SessionFolder::init_from_template).
+
+use std::fmt;
+
+use crate::format::mermaid::{parse_flowchart, parse_sequence_diagram};
+use crate::model::{
+    Diagram, DiagramAst, DiagramId, ObjectId, ObjectRef, Session, SessionId, XRef, XRefId,
+    XRefStatus,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SessionTemplate {
+    /// Service map of a small shop backend plus the checkout call sequence through it.
+    Microservices,
+    /// One HTTP request through proxy, app, cache and database, with the app's pipeline.
+    RequestLifecycle,
+    /// C4-style system context with people and external systems, plus the container view.
+    Context,
+}
+
+struct TemplateDiagram {
+    diagram_id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    mermaid: &'static str,
+}
+
+struct TemplateXRef {
+    from: &'static str,
+    to: &'static str,
+    kind: &'static str,
+    label: &'static str,
+}
+
+impl SessionTemplate {
+    pub const ALL: [Self; 3] = [Self::Microservices, Self::RequestLifecycle, Self::Context];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Microservices => "microservices",
+            Self::RequestLifecycle => "request-lifecycle",
+            Self::Context => "context",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        Self::ALL.into_iter().find(|template| template.name().eq_ignore_ascii_case(value))
+    }
+
+    /// Session title the template starts with.
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Microservices => "Microservice map",
+            Self::RequestLifecycle => "Request lifecycle",
+            Self::Context => "System context",
+        }
+    }
+
+    /// One line on what the template holds, for listings.
+    pub fn summary(self) -> &'static str {
+        match self {
+            Self::Microservices => "service map with the checkout sequence through it",
+            Self::RequestLifecycle => "HTTP request sequence with the app's middleware pipeline",
+            Self::Context => "C4-style system context and container diagrams",
+        }
+    }
+
+    /// Builds the template's session: diagrams in template order, the first one active.
+    pub fn session(self, session_id: SessionId) -> Session {
+        let mut session = Session::new(session_id);
+        session.set_title(Some(self.title()));
+        session.set_description(Some(self.summary()));
+
+        let diagrams = self.diagrams();
+        for template_diagram in diagrams {
+            let diagram_id = DiagramId::new(template_diagram.diagram_id).expect("valid diagram id");
+            let mermaid = template_diagram.mermaid;
+            let ast = if mermaid.starts_with("sequenceDiagram") {
+                let mut ast = parse_sequence_diagram(mermaid).expect("valid template");
+                // Keep participants in declaration order rather than by id.
+                *ast.participant_order_mut() = mermaid
+                    .lines()
+                    .filter_map(|line| line.strip_prefix("participant "))
+                    .map(|name| ObjectId::new(format!("p:{name}")).expect("valid participant id"))
+                    .collect();
+                DiagramAst::Sequence(ast)
+            } else {
+                DiagramAst::Flowchart(parse_flowchart(mermaid).expect("valid template"))
+            };
+            let mut diagram = Diagram::new(diagram_id.clone(), template_diagram.name, ast);
+            diagram.set_description(Some(template_diagram.description));
+            session.diagrams_mut().insert(diagram_id, diagram);
+        }
+        session.set_diagram_order(
+            diagrams
+                .iter()
+                .map(|diagram| DiagramId::new(diagram.diagram_id).expect("valid diagram id")),
+        );
+        session.set_active_diagram_id(
+            diagrams.first().map(|diagram| DiagramId::new(diagram.diagram_id).expect("valid")),
+        );
+
+        let xrefs = self.xrefs();
+        for (idx, template_xref) in xrefs.iter().enumerate() {
+            let from = template_xref.from.parse::<ObjectRef>().expect("valid template ref");
+            let to = template_xref.to.parse::<ObjectRef>().expect("valid template ref");
+            let mut xref = XRef::new(from, to, template_xref.kind, XRefStatus::Ok);
+            xref.set_label(Some(template_xref.label.to_owned()));
+            let xref_id = XRefId::new(format!("x:{}", idx + 1)).expect("valid xref id");
+            session.xrefs_mut().insert(xref_id, xref);
+        }
+        session.set_xref_id_counter(xrefs.len() as u64);
+        session
+    }
+
+    fn diagrams(self) -> &'static [TemplateDiagram] {
+        match self {
+            Self::Microservices => &[
+                TemplateDiagram {
+                    diagram_id: "services",
+                    name: "Service map",
+                    description: "Clients, services, stores and the event bus between them.",
+                    mermaid: "flowchart LR\n\
+                        web(Web app) -->|HTTPS| gateway[API gateway]\n\
+                        mobile(Mobile app) -->|HTTPS| gateway\n\
+                        gateway -->|verify token| auth[Auth service]\n\
+                        gateway --> catalog[Catalog service]\n\
+                        gateway --> orders[Order service]\n\
+                        orders -->|charge| payments[Payment service]\n\
+                        orders -->|reserve| inventory[Inventory service]\n\
+                        orders -.->|OrderPlaced| bus(Event bus)\n\
+                        bus -.-> notifications[Notification service]\n\
+                        catalog --> catalog_db(Catalog DB)\n\
+                        orders --> orders_db(Orders DB)\n",
+                },
+                TemplateDiagram {
+                    diagram_id: "checkout",
+                    name: "Checkout sequence",
+                    description: "Placing an order, from the web app through the services.",
+                    mermaid: "sequenceDiagram\n\
+                        participant web\n\
+                        participant gateway\n\
+                        participant auth\n\
+                        participant orders\n\
+                        participant inventory\n\
+                        participant payments\n\
+                        web->>gateway: POST /orders\n\
+                        gateway->>auth: verify token\n\
+                        auth-->>gateway: claims\n\
+                        gateway->>orders: place order\n\
+                        orders->>inventory: reserve items\n\
+                        inventory-->>orders: reserved\n\
+                        orders->>payments: charge card\n\
+                        payments-->>orders: charged\n\
+                        orders-->>gateway: order id\n\
+                        gateway-->>web: 201 Created\n",
+                },
+            ],
+            Self::RequestLifecycle => &[
+                TemplateDiagram {
+                    diagram_id: "request",
+                    name: "Request sequence",
+                    description: "A GET request served from the cache or the database.",
+                    mermaid: "sequenceDiagram\n\
+                        participant client\n\
+                        participant proxy\n\
+                        participant app\n\
+                        participant cache\n\
+                        participant db\n\
+                        client->>proxy: GET /items/42\n\
+                        proxy->>app: forward request\n\
+                        app->>cache: get item:42\n\
+                        alt cache hit\n\
+                        cache-->>app: item\n\
+                        else cache miss\n\
+                        app->>db: SELECT item 42\n\
+                        db-->>app: row\n\
+                        app-)cache: set item:42\n\
+                        end\n\
+                        app-->>proxy: 200 OK\n\
+                        proxy-->>client: response\n",
+                },
+                TemplateDiagram {
+                    diagram_id: "pipeline",
+                    name: "App pipeline",
+                    description: "Middleware steps the app runs for every request.",
+                    mermaid: "flowchart TD\n\
+                        receive(Receive request) --> authenticate[Authenticate]\n\
+                        authenticate -->|invalid| reject(401 Unauthorized)\n\
+                        authenticate --> route[Route]\n\
+                        route -->|unknown path| not_found(404 Not Found)\n\
+                        route --> handle[Run handler]\n\
+                        handle --> cached{Cached?}\n\
+                        cached -->|yes| respond(Send response)\n\
+                        cached -->|no| query[Query database]\n\
+                        query --> respond\n",
+                },
+            ],
+            Self::Context => &[
+                TemplateDiagram {
+                    diagram_id: "context",
+                    name: "System context",
+                    description: "Who uses the system and which external systems it relies on.",
+                    mermaid: "flowchart TB\n\
+                        customer(Customer) -->|browses and buys| system[Online shop]\n\
+                        staff(Staff) -->|manages catalog| system\n\
+                        system -->|signs in via| idp[Identity provider]\n\
+                        system -->|takes payments via| psp[Payment provider]\n\
+                        system -->|sends mail via| mail[Email provider]\n",
+                },
+                TemplateDiagram {
+                    diagram_id: "containers",
+                    name: "Containers",
+                    description: "Deployable parts of the online shop and how they talk.",
+                    mermaid: "flowchart LR\n\
+                        spa[Web frontend] -->|JSON/HTTPS| api[API]\n\
+                        admin[Admin UI] -->|JSON/HTTPS| api\n\
+                        api -->|SQL| db(Database)\n\
+                        api -.->|jobs| queue(Job queue)\n\
+                        queue -.-> worker[Worker]\n\
+                        worker -->|SQL| db\n",
+                },
+            ],
+        }
+    }
+
+    fn xrefs(self) -> &'static [TemplateXRef] {
+        match self {
+            Self::Microservices => &[
+                TemplateXRef {
+                    from: "d:services/flow/node/n:web",
+                    to: "d:checkout/seq/participant/p:web",
+                    kind: "appears_in",
+                    label: "checkout caller",
+                },
+                TemplateXRef {
+                    from: "d:services/flow/node/n:gateway",
+                    to: "d:checkout/seq/participant/p:gateway",
+                    kind: "appears_in",
+                    label: "entry point",
+                },
+                TemplateXRef {
+                    from: "d:services/flow/node/n:auth",
+                    to: "d:checkout/seq/participant/p:auth",
+                    kind: "appears_in",
+                    label: "token check",
+                },
+                TemplateXRef {
+                    from: "d:services/flow/node/n:orders",
+                    to: "d:checkout/seq/participant/p:orders",
+                    kind: "appears_in",
+                    label: "order owner",
+                },
+                TemplateXRef {
+                    from: "d:services/flow/node/n:inventory",
+                    to: "d:checkout/seq/participant/p:inventory",
+                    kind: "appears_in",
+                    label: "reservation",
+                },
+                TemplateXRef {
+                    from: "d:services/flow/node/n:payments",
+                    to: "d:checkout/seq/participant/p:payments",
+                    kind: "appears_in",
+                    label: "charge",
+                },
+            ],
+            Self::RequestLifecycle => &[
+                TemplateXRef {
+                    from: "d:request/seq/participant/p:app",
+                    to: "d:pipeline/flow/node/n:receive",
+                    kind: "detail",
+                    label: "what the app does",
+                },
+                TemplateXRef {
+                    from: "d:request/seq/participant/p:cache",
+                    to: "d:pipeline/flow/node/n:cached",
+                    kind: "used_in",
+                    label: "cache lookup",
+                },
+                TemplateXRef {
+                    from: "d:request/seq/participant/p:db",
+                    to: "d:pipeline/flow/node/n:query",
+                    kind: "used_in",
+                    label: "cache miss",
+                },
+            ],
+            Self::Context => &[
+                TemplateXRef {
+                    from: "d:context/flow/node/n:system",
+                    to: "d:containers/flow/node/n:api",
+                    kind: "detail",
+                    label: "inside the shop",
+                },
+                TemplateXRef {
+                    from: "d:context/flow/node/n:customer",
+                    to: "d:containers/flow/node/n:spa",
+                    kind: "uses",
+                    label: "shops in",
+                },
+                TemplateXRef {
+                    from: "d:context/flow/node/n:staff",
+                    to: "d:containers/flow/node/n:admin",
+                    kind: "uses",
+                    label: "works in",
+                },
+            ],
+        }
+    }
+}
+
+impl fmt::Display for SessionTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SessionTemplate;
+    use crate::model::{SessionId, XRefStatus};
+    use crate::render::render_diagram_unicode;
+
+    #[test]
+    fn every_template_builds_renderable_diagrams_with_resolved_xrefs() {
+        for template in SessionTemplate::ALL {
+            assert_eq!(SessionTemplate::parse(template.name()), Some(template));
+            let session = template.session(SessionId::new("s:scaffold").expect("session id"));
+            assert!(session.diagrams().len() >= 2, "{template}");
+            assert!(session.active_diagram_id().is_some(), "{template}");
+            for diagram in session.diagrams().values() {
+                render_diagram_unicode(diagram)
+                    .unwrap_or_else(|err| panic!("{template}/{}: {err}", diagram.diagram_id()));
+            }
+            for xref in session.xrefs().values() {
+                for object_ref in [xref.from(), xref.to()] {
+                    assert!(
+                        session.object_ref_exists(object_ref),
+                        "{template}: {object_ref} does not resolve"
+                    );
+                }
+                assert_eq!(xref.status(), XRefStatus::Ok);
+            }
+        }
+        assert_eq!(SessionTemplate::parse("C4"), None);
+    }
+}
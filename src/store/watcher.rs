@@ -0,0 +1,210 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Watches a session folder for edits made by other processes.
+//!
+//! File events are collected in the background and reported as one batch once the folder has
+//! been quiet for the debounce window, so an editor's save (temp file, rename, sidecar update)
+//! triggers a single reload. Derived files (`.ascii.txt` exports, in-flight temp files, the
+//! `ops.log` journal) are ignored because they never change what a reload produces.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use super::{SessionFolder, StoreError};
+
+/// Quiet period after the last file event before a batch is reported.
+pub const DEFAULT_WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+pub struct SessionFolderWatcher {
+    // Dropping the watcher stops the background thread feeding `events`.
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    debounce: Duration,
+    changed_paths: BTreeSet<PathBuf>,
+    last_event_at: Option<Instant>,
+}
+
+impl SessionFolderWatcher {
+    pub fn new(folder: &SessionFolder) -> Result<Self, StoreError> {
+        Self::with_debounce(folder, DEFAULT_WATCH_DEBOUNCE)
+    }
+
+    pub fn with_debounce(folder: &SessionFolder, debounce: Duration) -> Result<Self, StoreError> {
+        let (tx, events) = mpsc::channel();
+        let path = folder.root().to_path_buf();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        })
+        .map_err(|source| StoreError::Watch { path: path.clone(), source: Box::new(source) })?;
+        watcher
+            .watch(&path, RecursiveMode::Recursive)
+            .map_err(|source| StoreError::Watch { path, source: Box::new(source) })?;
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            debounce,
+            changed_paths: BTreeSet::new(),
+            last_event_at: None,
+        })
+    }
+
+    /// Drains pending file events and returns the changed session files once the folder has
+    /// been quiet for the debounce window; `None` while nothing changed or events still arrive.
+    pub fn poll_changes(&mut self, now: Instant) -> Option<BTreeSet<PathBuf>> {
+        while let Ok(event) = self.events.try_recv() {
+            // Backend errors (e.g. a dropped inotify queue) mean events may have been missed, so
+            // they count as a change of the whole folder.
+            let Ok(event) = event else {
+                self.last_event_at = Some(now);
+                continue;
+            };
+            if matches!(event.kind, EventKind::Access(_)) {
+                continue;
+            }
+            // Every relevant event restarts the debounce window, including repeated writes to a
+            // path that is already part of the batch.
+            let mut paths =
+                event.paths.into_iter().filter(|path| is_session_source_path(path)).peekable();
+            if paths.peek().is_some() {
+                self.last_event_at = Some(now);
+            }
+            self.changed_paths.extend(paths);
+        }
+
+        let last_event_at = self.last_event_at?;
+        if now.saturating_duration_since(last_event_at) < self.debounce {
+            return None;
+        }
+        self.last_event_at = None;
+        Some(std::mem::take(&mut self.changed_paths))
+    }
+}
+
+/// Whether `path` is a file a session reload reads, as opposed to a derived export or a temp file
+/// that is about to be renamed into place.
+pub fn is_session_source_path(path: &Path) -> bool {
+    let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    !file_name.starts_with(".nereid.tmp.")
+        && !file_name.ends_with(".ascii.txt")
+        && file_name != super::session_folder::OPS_LOG_FILENAME
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::time::{Duration, Instant};
+
+    use super::{is_session_source_path, SessionFolderWatcher};
+    use crate::store::SessionFolder;
+
+    #[test]
+    fn ignores_exports_and_temp_files() {
+        assert!(is_session_source_path(Path::new("s/diagrams/flow.mmd")));
+        assert!(is_session_source_path(Path::new("s/diagrams/flow.meta.json")));
+        assert!(is_session_source_path(Path::new("s/nereid-session.meta.json")));
+        assert!(!is_session_source_path(Path::new("s/diagrams/flow.ascii.txt")));
+        assert!(!is_session_source_path(Path::new("s/diagrams/.nereid.tmp.1.2")));
+        assert!(!is_session_source_path(Path::new("s/ops.log")));
+    }
+
+    fn temp_session_root(label: &str) -> std::path::PathBuf {
+        let root = std::env::temp_dir().join(format!(
+            "nereid-watch-{label}-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(root.join("diagrams")).expect("create session dir");
+        root
+    }
+
+    #[test]
+    fn reports_external_writes_once_the_folder_is_quiet() {
+        let root = temp_session_root("quiet");
+        let folder = SessionFolder::new(&root);
+        let mut watcher = SessionFolderWatcher::with_debounce(&folder, Duration::from_millis(50))
+            .expect("watch session dir");
+
+        let mmd_path = root.join("diagrams").join("flow.mmd");
+        std::fs::write(&mmd_path, "flowchart LR\nA --> B\n").expect("write mmd");
+        std::fs::write(root.join("diagrams").join("flow.ascii.txt"), "A").expect("write ascii");
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let changed = loop {
+            if let Some(changed) = watcher.poll_changes(Instant::now()) {
+                break changed;
+            }
+            assert!(Instant::now() < deadline, "no change reported");
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        assert_eq!(
+            changed
+                .into_iter()
+                .map(|path| path.file_name().unwrap().to_owned())
+                .collect::<Vec<_>>(),
+            vec![std::ffi::OsString::from("flow.mmd")]
+        );
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn repeated_writes_to_one_file_keep_extending_the_debounce_window() {
+        let root = temp_session_root("repeat");
+        let folder = SessionFolder::new(&root);
+        let debounce = Duration::from_millis(50);
+        let mut watcher =
+            SessionFolderWatcher::with_debounce(&folder, debounce).expect("watch session dir");
+        let mmd_path = root.join("diagrams").join("flow.mmd");
+
+        // Poll with synthetic clock values so only event delivery depends on real time: each
+        // write lands 40ms after the previous one, well inside the 50ms window.
+        let base = Instant::now();
+        let mut now = base;
+        for step in 0..5u64 {
+            now = base + Duration::from_millis(40 * step);
+            std::fs::write(&mmd_path, format!("flowchart LR\nA --> B{step}\n")).expect("write");
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while watcher.last_event_at != Some(now) {
+                assert_eq!(watcher.poll_changes(now), None, "batch reported during write {step}");
+                assert!(Instant::now() < deadline, "write {step} did not restart the window");
+                std::thread::sleep(Duration::from_millis(5));
+            }
+        }
+        // 190ms after the first write, but only 30ms after the last one.
+        assert_eq!(watcher.poll_changes(now + Duration::from_millis(30)), None);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let changed = loop {
+            now += debounce;
+            if let Some(changed) = watcher.poll_changes(now) {
+                break changed;
+            }
+            assert!(Instant::now() < deadline, "no change reported");
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        assert_eq!(
+            changed
+                .into_iter()
+                .map(|path| path.file_name().unwrap().to_owned())
+                .collect::<Vec<_>>(),
+            vec![std::ffi::OsString::from("flow.mmd")]
+        );
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
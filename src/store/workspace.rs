@@ -0,0 +1,270 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Parent directories holding several session folders.
+//!
+//! Every direct subdirectory with a session meta file is one session, named after the
+//! subdirectory. The CLI opens one of them at a time; the TUI (`:sessions`) and the MCP server
+//! (`workspace.open`) swap the active session without restarting, and tell each other through the
+//! shared [`UiState`](crate::ui::UiState).
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::model::{Session, SessionId};
+
+use super::{LoadMode, SessionFolder, SessionTemplate, StoreError, WriteDurability};
+
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    root: PathBuf,
+    durability: WriteDurability,
+    load_mode: LoadMode,
+}
+
+/// A session folder found in a [`Workspace`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceSession {
+    /// Name of the session's subdirectory.
+    pub name: String,
+    pub session_id: SessionId,
+    pub title: Option<String>,
+    pub diagram_count: usize,
+}
+
+impl Workspace {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            durability: WriteDurability::default(),
+            load_mode: LoadMode::default(),
+        }
+    }
+
+    /// Sets the durability of the session folders the workspace opens.
+    pub fn with_durability(mut self, durability: WriteDurability) -> Self {
+        self.durability = durability;
+        self
+    }
+
+    /// Sets how the session folders the workspace opens load their diagrams.
+    pub fn with_load_mode(mut self, load_mode: LoadMode) -> Self {
+        self.load_mode = load_mode;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Session folders directly inside the workspace, by name; other subdirectories are skipped.
+    pub fn list_sessions(&self) -> Result<Vec<WorkspaceSession>, StoreError> {
+        let entries = fs::read_dir(&self.root)
+            .map_err(|source| StoreError::Io { path: self.root.clone(), source })?;
+        let mut sessions = Vec::new();
+        for entry in entries {
+            let entry =
+                entry.map_err(|source| StoreError::Io { path: self.root.clone(), source })?;
+            let Some(name) = entry.file_name().to_str().map(str::to_owned) else {
+                continue;
+            };
+            if name.starts_with('.') || !entry.path().is_dir() {
+                continue;
+            }
+            let folder = self.folder(&name);
+            if !folder.has_session() {
+                continue;
+            }
+            let meta = folder.load_meta()?;
+            sessions.push(WorkspaceSession {
+                name,
+                session_id: meta.session_id,
+                title: meta.title,
+                diagram_count: meta.diagrams.len(),
+            });
+        }
+        sessions.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(sessions)
+    }
+
+    /// The session folder called `name`, which must be one of [`Self::list_sessions`].
+    pub fn session_folder(&self, name: &str) -> Result<SessionFolder, StoreError> {
+        let folder = self.folder(name);
+        if !is_session_name(name) || !folder.has_session() {
+            return Err(StoreError::UnknownWorkspaceSession {
+                workspace_dir: self.root.clone(),
+                name: name.to_owned(),
+            });
+        }
+        Ok(folder)
+    }
+
+    /// Loads the session `name` and replays edits from its `ops.log` that never reached the
+    /// diagram files; returns the folder with the session and the number of recovered batches.
+    pub fn open_session(&self, name: &str) -> Result<(SessionFolder, Session, usize), StoreError> {
+        let folder = self.session_folder(name)?;
+        let mut session = folder.load_session()?;
+        let recovered = folder.recover_from_ops_journal(&mut session)?;
+        Ok((folder, session, recovered))
+    }
+
+    /// Scaffolds a new session folder `name` from `template` and returns it with the session; the
+    /// name must be a plain directory name not holding a session yet.
+    pub fn create_session(
+        &self,
+        name: &str,
+        template: SessionTemplate,
+    ) -> Result<(SessionFolder, Session), StoreError> {
+        let folder = self.new_folder(name)?;
+        let session = folder.init_from_template(template)?;
+        Ok((folder, session))
+    }
+
+    /// Unpacks the session archive at `path` into a new session folder `name` and returns it
+    /// with the imported session; the name rules match [`Self::create_session`].
+    pub fn import_session(
+        &self,
+        name: &str,
+        path: &Path,
+    ) -> Result<(SessionFolder, Session), StoreError> {
+        let folder = self.new_folder(name)?;
+        let session = folder.import_archive(path)?;
+        Ok((folder, session))
+    }
+
+    /// Name of the workspace session stored in `folder`, if it lies directly inside the workspace.
+    pub fn session_name(&self, folder: &SessionFolder) -> Option<String> {
+        let root = folder.root();
+        (root.parent()? == self.root).then(|| root.file_name()?.to_str().map(str::to_owned))?
+    }
+
+    fn new_folder(&self, name: &str) -> Result<SessionFolder, StoreError> {
+        if !is_session_name(name) {
+            return Err(StoreError::InvalidRelativePath {
+                field: "session name",
+                value: PathBuf::from(name),
+            });
+        }
+        Ok(self.folder(name))
+    }
+
+    fn folder(&self, name: &str) -> SessionFolder {
+        SessionFolder::new(self.root.join(name))
+            .with_durability(self.durability)
+            .with_load_mode(self.load_mode)
+    }
+}
+
+/// Whether `name` is a single, non-hidden path component, as session folder names are.
+fn is_session_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!((components.next(), components.next()), (Some(Component::Normal(_)), None))
+        && !name.starts_with('.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Workspace;
+    use crate::store::{SessionFolder, SessionTemplate, StoreError};
+
+    fn temp_workspace_root(label: &str) -> std::path::PathBuf {
+        let root = std::env::temp_dir().join(format!(
+            "nereid-workspace-{label}-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&root).expect("create workspace dir");
+        root
+    }
+
+    #[test]
+    fn lists_and_opens_session_subfolders_by_name() {
+        let root = temp_workspace_root("list");
+        for name in ["beta", "alpha"] {
+            SessionFolder::new(root.join(name)).load_or_init_session().expect("init session");
+        }
+        std::fs::create_dir_all(root.join("notes")).expect("plain dir");
+
+        let workspace = Workspace::new(&root);
+        let sessions = workspace.list_sessions().expect("list");
+        let names = sessions.iter().map(|session| session.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["alpha", "beta"]);
+        assert_eq!(sessions[0].session_id.as_str(), "s:alpha");
+        assert_eq!(sessions[0].diagram_count, 1);
+
+        let (folder, session, recovered) = workspace.open_session("beta").expect("open");
+        assert_eq!(folder.root(), root.join("beta"));
+        assert_eq!(session.session_id().as_str(), "s:beta");
+        assert_eq!(recovered, 0);
+        assert_eq!(workspace.session_name(&folder).as_deref(), Some("beta"));
+
+        for name in ["notes", "missing", "../beta", "alpha/diagrams"] {
+            assert!(
+                matches!(
+                    workspace.session_folder(name),
+                    Err(StoreError::UnknownWorkspaceSession { .. })
+                ),
+                "{name} is not a workspace session"
+            );
+        }
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn creates_session_folders_from_templates() {
+        let root = temp_workspace_root("template");
+        let workspace = Workspace::new(&root);
+
+        let (folder, session) =
+            workspace.create_session("shop", SessionTemplate::Context).expect("create");
+        assert_eq!(folder.root(), root.join("shop"));
+        assert_eq!(session.session_id().as_str(), "s:shop");
+        assert_eq!(workspace.list_sessions().expect("list")[0].diagram_count, 2);
+
+        assert!(matches!(
+            workspace.create_session("shop", SessionTemplate::Microservices),
+            Err(StoreError::SessionExists { .. })
+        ));
+        assert!(matches!(
+            workspace.create_session("../escape", SessionTemplate::Microservices),
+            Err(StoreError::InvalidRelativePath { .. })
+        ));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn imports_session_archives_into_new_session_folders() {
+        let root = temp_workspace_root("import");
+        let workspace = Workspace::new(&root);
+        let (source, _) =
+            workspace.create_session("shop", SessionTemplate::Context).expect("create");
+        let archive_path = root.join("shop.nereid.tar.zst");
+        source.export_archive(&archive_path).expect("export");
+
+        let (folder, session) = workspace.import_session("copy", &archive_path).expect("import");
+        assert_eq!(folder.root(), root.join("copy"));
+        assert_eq!(session.session_id().as_str(), "s:shop");
+        assert_eq!(session.diagrams().len(), 2);
+
+        assert!(matches!(
+            workspace.import_session("shop", &archive_path),
+            Err(StoreError::SessionExists { .. })
+        ));
+        assert!(matches!(
+            workspace.import_session("../escape", &archive_path),
+            Err(StoreError::InvalidRelativePath { .. })
+        ));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,210 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Accessibility mode: linear, screen-reader friendly descriptions of the focused object.
+//!
+//! Box drawings mean nothing to a screen reader, so whenever the focus moves the TUI describes
+//! the focused object and its connections in one sentence. The description goes to a dedicated
+//! line above the footer and, optionally, to the terminal title (OSC 2) and an append-only log
+//! file that a reader can follow from another terminal.
+
+use std::env;
+use std::fs;
+use std::io::{self, Write as _};
+use std::path::PathBuf;
+
+use crate::model::Session;
+use crate::ui::messages::{tr, trf};
+
+use super::{object_relation_rows, RelationRow, SelectableObject};
+
+pub(crate) const A11Y_ENV: &str = "NEREID_A11Y";
+pub(crate) const A11Y_LOG_ENV: &str = "NEREID_A11Y_LOG";
+
+/// Relations spelled out per description; the rest are only counted.
+const MAX_DESCRIBED_RELATIONS: usize = 8;
+
+/// Where focus descriptions are announced.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct A11yOutput {
+    /// Shows the description on its own line above the footer.
+    pub(crate) line: bool,
+    /// Also sets the terminal title to the description.
+    pub(crate) osc: bool,
+    /// Appends every description as one line to this file.
+    pub(crate) log_path: Option<PathBuf>,
+}
+
+impl A11yOutput {
+    pub(crate) fn from_env() -> Result<Self, String> {
+        Self::from_lookup(|name| env::var(name).ok())
+    }
+
+    /// Reads `NEREID_A11Y` (`off`, `line` or `osc`, comma separated) and `NEREID_A11Y_LOG` (a
+    /// file path, which turns the line on as well).
+    pub(crate) fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let value = |name: &str| lookup(name).filter(|value| !value.trim().is_empty());
+
+        let mut output = Self::default();
+        if let Some(modes) = value(A11Y_ENV) {
+            for mode in modes.split(',').map(|mode| mode.trim().to_ascii_lowercase()) {
+                match mode.as_str() {
+                    "off" | "0" => {}
+                    "line" | "on" | "1" => output.line = true,
+                    "osc" => {
+                        output.line = true;
+                        output.osc = true;
+                    }
+                    _ => {
+                        return Err(format!(
+                            "{A11Y_ENV} must be `off`, `line` or `osc`, got {mode:?}"
+                        ))
+                    }
+                }
+            }
+        }
+        if let Some(path) = value(A11Y_LOG_ENV) {
+            output.line = true;
+            output.log_path = Some(PathBuf::from(path.trim()));
+        }
+        Ok(output)
+    }
+
+    pub(crate) fn enabled(&self) -> bool {
+        self.line || self.osc || self.log_path.is_some()
+    }
+
+    /// Sends `description` to the terminal title and the log file, whichever are configured.
+    pub(crate) fn emit(&self, description: &str) -> io::Result<()> {
+        if self.osc {
+            let mut stdout = io::stdout();
+            stdout.write_all(osc_title_sequence(description).as_bytes())?;
+            stdout.flush()?;
+        }
+        if let Some(path) = self.log_path.as_ref() {
+            let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{description}")?;
+        }
+        Ok(())
+    }
+}
+
+/// OSC 2 (set window title), with control characters dropped so the text cannot end it early.
+pub(crate) fn osc_title_sequence(text: &str) -> String {
+    let title = text.chars().filter(|ch| !ch.is_control()).collect::<String>();
+    format!("\x1b]2;{title}\x07")
+}
+
+/// One sentence naming the focused object and its diagram, then its connections grouped by
+/// relation (as in the Relations panel).
+pub(crate) fn describe_focus(session: &Session, focused: Option<&SelectableObject>) -> String {
+    let diagram = session.active_diagram_id().and_then(|id| session.diagrams().get(id));
+    let diagram_name =
+        diagram.map_or_else(|| session.session_id().to_string(), |d| d.name().to_owned());
+    let Some(object) = focused else {
+        return trf("a11y.nothing_focused", &[("diagram", &diagram_name)]);
+    };
+
+    let mut description =
+        trf("a11y.focus", &[("label", &spoken(&object.label)), ("diagram", &diagram_name)]);
+
+    let rows = object_relation_rows(session, &object.object_ref);
+    if rows.is_empty() {
+        description.push(' ');
+        description.push_str(tr("a11y.no_connections"));
+        return description;
+    }
+    description.push(' ');
+    description.push_str(&describe_relations(&rows));
+    description
+}
+
+fn describe_relations(rows: &[RelationRow]) -> String {
+    let mut groups = Vec::<(&'static str, Vec<String>)>::new();
+    for row in rows.iter().take(MAX_DESCRIBED_RELATIONS) {
+        let label = spoken(&row.label);
+        match groups.last_mut() {
+            Some((tag, labels)) if *tag == row.tag => labels.push(label),
+            _ => groups.push((row.tag, vec![label])),
+        }
+    }
+
+    let mut text = groups
+        .into_iter()
+        .map(|(tag, labels)| format!("{}: {}", relation_tag_label(tag), labels.join(", ")))
+        .collect::<Vec<_>>()
+        .join("; ");
+    let hidden = rows.len().saturating_sub(MAX_DESCRIBED_RELATIONS);
+    if hidden > 0 {
+        text.push_str("; ");
+        text.push_str(&trf("a11y.more_connections", &[("count", &hidden)]));
+    }
+    text.push('.');
+    text
+}
+
+/// `text` with arrows spelled out, since readers announce `→` as a symbol name.
+fn spoken(text: &str) -> String {
+    text.replace('→', &format!(" {} ", tr("a11y.arrow_to")))
+        .replace('←', &format!(" {} ", tr("a11y.arrow_from")))
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn relation_tag_label(tag: &str) -> &'static str {
+    match tag {
+        "out" => tr("a11y.tag.out"),
+        "in" => tr("a11y.tag.in"),
+        "from" => tr("a11y.tag.from"),
+        "to" => tr("a11y.tag.to"),
+        "prev" => tr("a11y.tag.prev"),
+        "next" => tr("a11y.tag.next"),
+        "member" => tr("a11y.tag.member"),
+        "within" => tr("a11y.tag.within"),
+        "xref→" => tr("a11y.tag.xref_out"),
+        "xref←" => tr("a11y.tag.xref_in"),
+        _ => tr("a11y.tag.other"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{osc_title_sequence, A11yOutput, A11Y_ENV, A11Y_LOG_ENV};
+
+    #[test]
+    fn output_reads_modes_and_log_path() {
+        assert!(!A11yOutput::from_lookup(|_| None).expect("default").enabled());
+
+        let output = A11yOutput::from_lookup(|name| match name {
+            A11Y_ENV => Some("OSC".to_owned()),
+            A11Y_LOG_ENV => Some(" /tmp/nereid-a11y.log ".to_owned()),
+            _ => None,
+        })
+        .expect("output");
+        assert_eq!(
+            output,
+            A11yOutput {
+                line: true,
+                osc: true,
+                log_path: Some(PathBuf::from("/tmp/nereid-a11y.log")),
+            }
+        );
+
+        let err = A11yOutput::from_lookup(|name| (name == A11Y_ENV).then(|| "speech".to_owned()))
+            .expect_err("unknown mode");
+        assert!(err.contains(A11Y_ENV));
+    }
+
+    #[test]
+    fn osc_title_drops_control_characters() {
+        assert_eq!(osc_title_sequence("a\x07b\x1b]c"), "\x1b]2;ab]c\x07");
+    }
+}
@@ -24,6 +24,9 @@ enum Focus {
     Diagram,
     Objects,
     XRefs,
+    Relations,
+    Recent,
+    Timeline,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -37,15 +40,32 @@ impl Focus {
         match self {
             Self::Diagram => Self::Objects,
             Self::Objects => Self::XRefs,
-            Self::XRefs => Self::Diagram,
+            Self::XRefs => Self::Relations,
+            Self::Relations => Self::Recent,
+            Self::Recent => Self::Timeline,
+            Self::Timeline => Self::Diagram,
         }
     }
 
     fn cycle_back(self) -> Self {
         match self {
-            Self::Diagram => Self::XRefs,
+            Self::Diagram => Self::Timeline,
             Self::Objects => Self::Diagram,
             Self::XRefs => Self::Objects,
+            Self::Relations => Self::XRefs,
+            Self::Recent => Self::Relations,
+            Self::Timeline => Self::Recent,
+        }
+    }
+
+    fn key_scope(self) -> KeyScope {
+        match self {
+            Self::Diagram => KeyScope::Diagram,
+            Self::Objects => KeyScope::Objects,
+            Self::XRefs => KeyScope::XRefs,
+            Self::Relations => KeyScope::Relations,
+            Self::Recent => KeyScope::Recent,
+            Self::Timeline => KeyScope::Timeline,
         }
     }
 }
@@ -58,10 +78,14 @@ fn panel_border_style_for_focus(active: Focus, panel: Focus, owner: FocusOwner)
     Style::default().fg(focus_color_for_owner(owner))
 }
 
+fn theme() -> &'static Theme {
+    Theme::current()
+}
+
 fn focus_color_for_owner(owner: FocusOwner) -> Color {
     match owner {
-        FocusOwner::Human => FOCUS_COLOR,
-        FocusOwner::Agent => AGENT_FOCUS_COLOR,
+        FocusOwner::Human => theme().focus,
+        FocusOwner::Agent => theme().agent_focus,
     }
 }
 
@@ -91,7 +115,7 @@ fn diagram_view_title(
     };
     let counter = diagram_counter_label(diagram_index, diagram_total);
     Line::from(vec![
-        Span::raw("─ Diagram ".to_owned()),
+        Span::raw(format!("─ {} ", tr("title.diagram"))),
         Span::styled(counter, Style::default().fg(Color::LightGreen)),
         Span::raw(" ".to_owned()),
         Span::styled(diagram_id.to_owned(), Style::default().fg(id_color)),
@@ -99,6 +123,15 @@ fn diagram_view_title(
     ])
 }
 
+/// The Mermaid direction of a flowchart (`TD`, `LR`, ...) as a muted title span.
+fn diagram_direction_span(diagram: &Diagram) -> Option<Span<'static>> {
+    let DiagramAst::Flowchart(ast) = diagram.ast() else {
+        return None;
+    };
+    let direction = ast.direction()?.as_mermaid();
+    Some(Span::styled(format!("{direction} "), Style::default().fg(theme().dimmed)))
+}
+
 fn diagram_counter_label(diagram_index: Option<usize>, diagram_total: usize) -> String {
     if diagram_total == 0 {
         return "[0/0]".to_owned();
@@ -138,6 +171,253 @@ fn pad_text(mut text: Text<'static>, left_pad: usize, top_pad: usize) -> Text<'s
     text
 }
 
+/// Splits the inner diagram area into `(column ruler, row ruler, content)` rectangles.
+///
+/// The row ruler is wide enough for the largest row index of the diagram plus one separator cell.
+fn split_ruler_areas(inner: Rect, diagram_line_count: usize) -> (Rect, Rect, Rect) {
+    let label_width = diagram_line_count.saturating_sub(1).to_string().len() as u16;
+    let gutter = (label_width + 1).min(inner.width);
+    let header = 1.min(inner.height);
+    let content_width = inner.width - gutter;
+    let content_height = inner.height - header;
+    let top = Rect::new(inner.x + gutter, inner.y, content_width, header);
+    let left = Rect::new(inner.x, inner.y + header, gutter, content_height);
+    let content = Rect::new(inner.x + gutter, inner.y + header, content_width, content_height);
+    (top, left, content)
+}
+
+/// Builds the column ruler for a viewport whose first cell shows diagram column `origin_x`.
+///
+/// Every tenth column carries its index; the columns halfway between get a minor tick.
+fn column_ruler_text(origin_x: i32, width: usize) -> String {
+    let mut cells = vec![' '; width];
+    for (offset, cell) in cells.iter_mut().enumerate() {
+        let x = origin_x + offset as i32;
+        if x >= 0 && x % RULER_MAJOR_STEP == RULER_MINOR_STEP {
+            *cell = '·';
+        }
+    }
+    for offset in 0..width {
+        let x = origin_x + offset as i32;
+        if x < 0 || x % RULER_MAJOR_STEP != 0 {
+            continue;
+        }
+        for (label_offset, ch) in x.to_string().chars().enumerate() {
+            if let Some(cell) = cells.get_mut(offset + label_offset) {
+                *cell = ch;
+            }
+        }
+    }
+    cells.into_iter().collect()
+}
+
+/// Right-aligned row index label for diagram row `y`, blank outside the rendered diagram.
+fn row_ruler_label(y: i32, diagram_line_count: usize, width: usize) -> String {
+    let label_width = width.saturating_sub(1);
+    if y < 0 || y as usize >= diagram_line_count {
+        return " ".repeat(width);
+    }
+    format!("{y:>label_width$} ")
+}
+
+fn render_column_ruler(buf: &mut Buffer, area: Rect, origin_x: i32) {
+    if area.is_empty() {
+        return;
+    }
+    let text = column_ruler_text(origin_x, area.width as usize);
+    buf.set_string(area.x, area.y, text, Style::default().fg(theme().ruler));
+}
+
+fn render_row_ruler(buf: &mut Buffer, area: Rect, origin_y: i32, diagram_line_count: usize) {
+    for row in 0..area.height {
+        let y = origin_y + i32::from(row);
+        let label = row_ruler_label(y, diagram_line_count, area.width as usize);
+        let style = if y % GRID_ROW_STEP == 0 {
+            Style::default().fg(Color::Gray)
+        } else {
+            Style::default().fg(theme().ruler)
+        };
+        buf.set_string(area.x, area.y + row, label, style);
+    }
+}
+
+/// Marks grid intersections (every tenth column, every fifth row) on blank diagram cells.
+fn apply_grid_overlay(buf: &mut Buffer, area: Rect, origin_x: i32, origin_y: i32) {
+    for row in 0..area.height {
+        let y = origin_y + i32::from(row);
+        if y < 0 || y % GRID_ROW_STEP != 0 {
+            continue;
+        }
+        for col in 0..area.width {
+            let x = origin_x + i32::from(col);
+            if x < 0 || x % RULER_MAJOR_STEP != 0 {
+                continue;
+            }
+            if let Some(cell) = buf.cell_mut((area.x + col, area.y + row)) {
+                if cell.symbol() == " " {
+                    cell.set_symbol("·").set_fg(theme().ruler);
+                }
+            }
+        }
+    }
+}
+
+/// Screen cell range `(x, y_top, y_bottom)` of an object's bounding box, when any of it is visible.
+fn peek_anchor_on_screen(
+    content: Rect,
+    ((x0, x1), (y0, y1)): ((usize, usize), (usize, usize)),
+    pan_x: i32,
+    pan_y: i32,
+) -> Option<(u16, u16, u16)> {
+    let to_screen = |pos: usize, pan: i32, origin: u16, len: u16| {
+        let offset = pos as i32 - pan;
+        (0..i32::from(len)).contains(&offset).then(|| origin + offset as u16)
+    };
+    let visible_x = (x0..=x1).find_map(|x| to_screen(x, pan_x, content.x, content.width))?;
+    let top = to_screen(y0, pan_y, content.y, content.height);
+    let bottom = to_screen(y1, pan_y, content.y, content.height);
+    match (top, bottom) {
+        (None, None) => None,
+        (top, bottom) => Some((
+            visible_x,
+            top.unwrap_or(content.y),
+            bottom.unwrap_or(content.y + content.height - 1),
+        )),
+    }
+}
+
+/// Places a `width`x`height` popover below the anchor, above it when there is no room, and
+/// clamps it into `content`. Without an anchor the popover sits in the top-left corner.
+fn peek_popover_rect(
+    content: Rect,
+    anchor: Option<(u16, u16, u16)>,
+    width: u16,
+    height: u16,
+) -> Rect {
+    let width = width.min(content.width);
+    let height = height.min(content.height);
+    let max_x = content.x + content.width - width;
+    let max_y = content.y + content.height - height;
+    let (x, y) = match anchor {
+        Some((x, top, bottom)) => {
+            let below = bottom + 1;
+            let y = if below + height <= content.y + content.height {
+                below
+            } else {
+                top.saturating_sub(height).max(content.y)
+            };
+            (x.min(max_x), y.min(max_y))
+        }
+        None => (content.x, content.y),
+    };
+    Rect::new(x, y, width, height)
+}
+
+fn render_peek_popover(
+    frame: &mut Frame<'_>,
+    content: Rect,
+    anchor: Option<(u16, u16, u16)>,
+    title: &str,
+    lines: Vec<impl Into<Line<'static>>>,
+) {
+    if content.is_empty() {
+        return;
+    }
+    let lines = lines.into_iter().map(Into::into).collect::<Vec<Line<'static>>>();
+    let text_width = lines.iter().map(Line::width).max().unwrap_or(0);
+    let width = (text_width.max(title.chars().count()) + 2).min(u16::MAX as usize) as u16;
+    let height = (lines.len() + 2).min(u16::MAX as usize) as u16;
+    let area = peek_popover_rect(content, anchor, width, height);
+
+    let peek = Paragraph::new(Text::from(lines)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title.to_owned())
+            .border_style(Style::default().fg(theme().footer_key)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(peek, area);
+}
+
+fn form_rect(area: Rect, field_count: usize) -> Rect {
+    let width = (area.width * 6 / 10).max(40).min(area.width);
+    let height = (field_count as u16 + 4).min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+fn render_session_form(frame: &mut Frame<'_>, form: &SessionInfoForm, area: Rect) {
+    let title = format!("─ {} ─", tr("title.session_info"));
+    render_form(frame, &title, &SESSION_INFO_FIELDS, &form.values, form.field, area);
+}
+
+fn render_insert_form(frame: &mut Frame<'_>, form: &InsertForm, area: Rect) {
+    let title = if form.sequence { "title.insert_sequence" } else { "title.insert_node" };
+    let title = format!("─ {} ─", tr(title));
+    render_form(frame, &title, form.labels(), &form.values, form.field, area);
+}
+
+fn render_form(
+    frame: &mut Frame<'_>,
+    title: &str,
+    labels: &[&'static str],
+    values: &[String],
+    field: usize,
+    area: Rect,
+) {
+    let area = form_rect(area, labels.len());
+    if area.is_empty() {
+        return;
+    }
+    let labels = labels.iter().map(|key| tr(key)).collect::<Vec<_>>();
+    let label_width = labels.iter().map(|label| label.chars().count()).max().unwrap_or(0);
+
+    let mut lines = labels
+        .iter()
+        .zip(values)
+        .enumerate()
+        .map(|(idx, (label, value))| {
+            let label_style = if idx == field {
+                Style::default()
+                    .fg(theme().footer_key)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme().footer_label)
+            };
+            Line::from(vec![
+                Span::styled(format!("{label:<label_width$}  "), label_style),
+                Span::raw(value.clone()),
+            ])
+        })
+        .collect::<Vec<_>>();
+    lines.push(Line::from(""));
+    let mut hint = Vec::new();
+    push_footer_entry_with_separator(&mut hint, tr("form.next"), "Tab", " | ");
+    push_footer_entry_with_separator(&mut hint, tr("form.save"), "Enter", " | ");
+    push_footer_entry_with_separator(&mut hint, tr("form.cancel"), "Esc", " | ");
+    lines.push(Line::from(hint));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title.to_owned())
+        .border_style(Style::default().fg(theme().focus));
+    let inner = block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+
+    let value_len = values[field].chars().count() as u16;
+    let cursor_x = inner
+        .x
+        .saturating_add(label_width as u16 + 2)
+        .saturating_add(value_len)
+        .min(inner.right().saturating_sub(1));
+    frame.set_cursor_position((cursor_x, inner.y + field as u16));
+}
+
 fn style_for_diagram_char(mut style: Style, ch: char) -> Style {
     if is_direction_marker(ch) {
         style.fg = Some(Color::Cyan);
@@ -157,14 +437,14 @@ fn objects_item_bg(
 ) -> Option<Color> {
     if is_cursor {
         if is_selected && objects_has_focus {
-            Some(Color::LightGreen)
+            Some(theme().focus)
         } else if is_selected {
-            Some(Color::DarkGray)
+            Some(theme().selection_bg)
         } else {
             Some(focus_color_for_owner(owner))
         }
     } else if is_selected {
-        Some(Color::DarkGray)
+        Some(theme().selection_bg)
     } else {
         None
     }
@@ -172,11 +452,11 @@ fn objects_item_bg(
 
 fn xref_item_style(status: XRefStatus, indirectly_selected: bool) -> Style {
     if indirectly_selected {
-        Style::default().fg(Color::White).bg(Color::DarkGray)
+        Style::default().fg(theme().selection_fg).bg(theme().selection_bg)
     } else {
         match status {
             XRefStatus::Ok => Style::default(),
-            _ => Style::default().fg(Color::Red),
+            _ => Style::default().fg(theme().xref_dangling),
         }
     }
 }
@@ -191,6 +471,36 @@ fn xrefs_cursor_highlight_style(focus: Focus, owner: FocusOwner) -> Style {
     }
 }
 
+fn relations_cursor_highlight_style(focus: Focus, owner: FocusOwner) -> Style {
+    if focus == Focus::Relations {
+        Style::default()
+            .fg(Color::White)
+            .bg(focus_color_for_owner(owner))
+    } else {
+        Style::default()
+    }
+}
+
+fn recent_cursor_highlight_style(focus: Focus, owner: FocusOwner) -> Style {
+    if focus == Focus::Recent {
+        Style::default()
+            .fg(Color::White)
+            .bg(focus_color_for_owner(owner))
+    } else {
+        Style::default()
+    }
+}
+
+fn timeline_cursor_highlight_style(focus: Focus, owner: FocusOwner) -> Style {
+    if focus == Focus::Timeline {
+        Style::default()
+            .fg(Color::White)
+            .bg(focus_color_for_owner(owner))
+    } else {
+        Style::default()
+    }
+}
+
 fn xref_involves_selected(selected: Option<&ObjectRef>, xref: &XRef) -> bool {
     selected.is_some_and(|selected| xref.from() == selected || xref.to() == selected)
 }
@@ -260,66 +570,71 @@ fn footer_help_line(app: &App, toast_suffix: &str, compact: bool) -> Line<'stati
             HintMode::Inactive => "f",
             HintMode::AwaitingFirst { .. } | HintMode::AwaitingSecond { .. } => "2 letters",
         };
-        push_footer_entry_with_separator(&mut spans, "AI", follow_ai, " | ");
+        push_footer_entry_with_separator(&mut spans, tr("footer.ai"), follow_ai, " | ");
         push_footer_entry_with_separator_maybe_disabled(
             &mut spans,
-            "HINT",
+            tr("footer.hint"),
             compact_hint,
             " | ",
             diagram_hotkeys_disabled,
         );
-        push_footer_entry_with_separator(&mut spans, "HELP", "?", " | ");
-        push_footer_entry_with_separator(&mut spans, "QUIT", "q", " | ");
+        push_footer_entry_with_separator(&mut spans, tr("footer.help"), "?", " | ");
+        push_footer_entry_with_separator(&mut spans, tr("footer.quit"), "q", " | ");
     } else {
         match app.focus {
+            Focus::Diagram if app.visual_selection.is_some() => {
+                push_footer_entry(&mut spans, tr("footer.visual"), "arrows");
+                push_footer_entry(&mut spans, tr("footer.select"), "⏎");
+                push_footer_entry(&mut spans, tr("footer.cancel"), "Esc");
+            }
             Focus::Diagram => match app.hint_mode {
                 HintMode::Inactive => {
                     let notes = if app.show_notes { "n◼ " } else { "n◻ " };
                     push_footer_entry_maybe_disabled(
                         &mut spans,
-                        "DIAGRAM",
+                        tr("footer.diagram"),
                         "[]",
                         diagram_hotkeys_disabled,
                     );
                     push_footer_entry_maybe_disabled(
                         &mut spans,
-                        "HINT",
+                        tr("footer.hint"),
                         "f",
                         diagram_hotkeys_disabled,
                     );
                     push_footer_entry_maybe_disabled(
                         &mut spans,
-                        "CHAIN",
+                        tr("footer.chain"),
                         "c",
                         diagram_hotkeys_disabled,
                     );
                     push_footer_entry_maybe_disabled(
                         &mut spans,
-                        "EDIT",
+                        tr("footer.edit"),
                         "e",
                         diagram_hotkeys_disabled,
                     );
                     push_footer_entry_maybe_disabled(
                         &mut spans,
-                        "SELECT",
+                        tr("footer.select"),
                         "⏡",
                         diagram_hotkeys_disabled,
                     );
                     push_footer_entry_maybe_disabled(
                         &mut spans,
-                        "YANK",
+                        tr("footer.yank"),
                         "y",
                         diagram_hotkeys_disabled,
                     );
                     push_footer_entry_maybe_disabled(
                         &mut spans,
-                        "XREF",
+                        tr("footer.xref"),
                         "g/t",
                         diagram_hotkeys_disabled,
                     );
                     push_footer_entry_maybe_disabled(
                         &mut spans,
-                        "NOTES",
+                        tr("footer.notes"),
                         notes,
                         diagram_hotkeys_disabled,
                     );
@@ -329,13 +644,13 @@ fn footer_help_line(app: &App, toast_suffix: &str, compact: bool) -> Line<'stati
                         HintKind::Jump => {
                             push_footer_entry_maybe_disabled(
                                 &mut spans,
-                                "HINT",
+                                tr("footer.hint"),
                                 "2 letters",
                                 diagram_hotkeys_disabled,
                             );
                             push_footer_entry_maybe_disabled(
                                 &mut spans,
-                                "CANCEL",
+                                tr("footer.cancel"),
                                 "Esc",
                                 diagram_hotkeys_disabled,
                             );
@@ -343,13 +658,13 @@ fn footer_help_line(app: &App, toast_suffix: &str, compact: bool) -> Line<'stati
                         HintKind::SelectChain => {
                             push_footer_entry_maybe_disabled(
                                 &mut spans,
-                                "CHAIN",
+                                tr("footer.chain"),
                                 "2 letters",
                                 diagram_hotkeys_disabled,
                             );
                             push_footer_entry_maybe_disabled(
                                 &mut spans,
-                                "DONE",
+                                tr("footer.done"),
                                 "Esc",
                                 diagram_hotkeys_disabled,
                             );
@@ -358,24 +673,37 @@ fn footer_help_line(app: &App, toast_suffix: &str, compact: bool) -> Line<'stati
                 }
             },
             Focus::Objects => {
-                push_footer_entry(&mut spans, "SELECT", "⏡");
-                push_footer_entry(&mut spans, "FILTER", "-");
-                push_footer_entry(&mut spans, "HINT", "f");
-                push_footer_entry(&mut spans, "CHAIN", "c");
-                push_footer_entry(&mut spans, "YANK", "y");
-                push_footer_entry(&mut spans, "JUMP", "g/t");
-                push_footer_entry(&mut spans, "DIAGRAM", "[]");
+                push_footer_entry(&mut spans, tr("footer.select"), "⏡");
+                push_footer_entry(&mut spans, tr("footer.filter"), "-");
+                push_footer_entry(&mut spans, tr("footer.hint"), "f");
+                push_footer_entry(&mut spans, tr("footer.chain"), "c");
+                push_footer_entry(&mut spans, tr("footer.yank"), "y");
+                push_footer_entry(&mut spans, tr("footer.jump"), "g/t");
+                push_footer_entry(&mut spans, tr("footer.diagram"), "[]");
             }
             Focus::XRefs => {
-                push_footer_entry(&mut spans, "FILTER", "-/I");
-                push_footer_entry(&mut spans, "JUMP", "g/t");
-                push_footer_entry(&mut spans, "DIAGRAM", "[]");
+                push_footer_entry(&mut spans, tr("footer.filter"), "-/I");
+                push_footer_entry(&mut spans, tr("footer.jump"), "g/t");
+                push_footer_entry(&mut spans, tr("footer.diagram"), "[]");
+            }
+            Focus::Relations => {
+                push_footer_entry(&mut spans, tr("footer.jump"), "⏎/g");
+                push_footer_entry(&mut spans, tr("footer.diagram"), "[]");
+            }
+            Focus::Recent => {
+                push_footer_entry(&mut spans, tr("footer.jump"), "⏎/g");
+                push_footer_entry(&mut spans, tr("footer.swap"), "^");
+            }
+            Focus::Timeline => {
+                push_footer_entry(&mut spans, tr("footer.travel"), "⏎/g");
+                push_footer_entry(&mut spans, tr("footer.present"), "Esc");
             }
         }
 
-        push_footer_entry(&mut spans, "AI", follow_ai);
-        push_footer_entry(&mut spans, "HELP", "?");
-        push_footer_entry(&mut spans, "QUIT", "q");
+        push_footer_entry(&mut spans, tr("footer.ai"), follow_ai);
+        push_agent_cursor_legend(&mut spans, &app.agent_cursors);
+        push_footer_entry(&mut spans, tr("footer.help"), "?");
+        push_footer_entry(&mut spans, tr("footer.quit"), "q");
     }
 
     let toast_message = toast_suffix
@@ -383,10 +711,10 @@ fn footer_help_line(app: &App, toast_suffix: &str, compact: bool) -> Line<'stati
         .unwrap_or(toast_suffix)
         .trim();
     if !toast_message.is_empty() {
-        spans.push(Span::styled(" | ", Style::default().fg(FOOTER_LABEL_COLOR)));
+        spans.push(Span::styled(" | ", Style::default().fg(theme().footer_label)));
         spans.push(Span::styled(
-            "Toast:".to_owned(),
-            Style::default().fg(FOOTER_LABEL_COLOR),
+            tr("footer.toast").to_owned(),
+            Style::default().fg(theme().footer_label),
         ));
         spans.push(Span::raw(toast_message.to_owned()));
     }
@@ -414,7 +742,7 @@ fn search_footer_line(app: &App, toast_suffix: &str) -> Line<'static> {
         Span::styled(
             search_prefix.to_string(),
             Style::default()
-                .fg(FOOTER_KEY_COLOR)
+                .fg(theme().footer_key)
                 .add_modifier(Modifier::BOLD),
         ),
         Span::raw(query.to_owned()),
@@ -425,20 +753,20 @@ fn search_footer_line(app: &App, toast_suffix: &str) -> Line<'static> {
     }
 
     if app.search_mode == SearchMode::Results {
-        push_footer_entry_with_separator(&mut spans, "Next", "n/N", " | ");
+        push_footer_entry_with_separator(&mut spans, tr("footer.next"), "n/N", " | ");
     }
-    push_footer_entry_with_separator(&mut spans, "Accept", "Enter", " | ");
-    push_footer_entry_with_separator(&mut spans, "Close", "Esc", " | ");
+    push_footer_entry_with_separator(&mut spans, tr("footer.accept"), "Enter", " | ");
+    push_footer_entry_with_separator(&mut spans, tr("footer.close"), "Esc", " | ");
 
     let toast_message = toast_suffix
         .strip_prefix(" | ")
         .unwrap_or(toast_suffix)
         .trim();
     if !toast_message.is_empty() {
-        spans.push(Span::styled(" | ", Style::default().fg(FOOTER_LABEL_COLOR)));
+        spans.push(Span::styled(" | ", Style::default().fg(theme().footer_label)));
         spans.push(Span::styled(
-            "Toast:".to_owned(),
-            Style::default().fg(FOOTER_LABEL_COLOR),
+            tr("footer.toast").to_owned(),
+            Style::default().fg(theme().footer_label),
         ));
         spans.push(Span::raw(toast_message.to_owned()));
     }
@@ -446,16 +774,211 @@ fn search_footer_line(app: &App, toast_suffix: &str) -> Line<'static> {
     Line::from(spans)
 }
 
+const SEARCH_PANEL_LABEL_WIDTH: usize = 32;
+
+/// Rows of the search results panel: a header per diagram group, then one `label  ref  score`
+/// row per hit. Also returns the row index of the current result.
+fn search_results_panel_lines(app: &App) -> (Vec<Line<'static>>, Option<usize>) {
+    let label_width = app
+        .search_results
+        .iter()
+        .map(|hit| hit.label.chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(SEARCH_PANEL_LABEL_WIDTH);
+    let score_width =
+        app.search_results.iter().map(|hit| hit.score.to_string().len()).max().unwrap_or(0);
+    let header_style = help_header_style();
+    let ref_style = Style::default().fg(theme().footer_label);
+    let score_style = Style::default().fg(Color::LightGreen);
+
+    let mut lines = Vec::new();
+    let mut current = None;
+    let mut group: Option<&DiagramId> = None;
+    for (idx, hit) in app.search_results.iter().enumerate() {
+        let diagram_id = hit.object_ref.diagram_id();
+        if group != Some(diagram_id) {
+            let count = app.search_results[idx..]
+                .iter()
+                .take_while(|next| next.object_ref.diagram_id() == diagram_id)
+                .count();
+            lines.push(Line::from(Span::styled(format!("{diagram_id} ({count})"), header_style)));
+            group = Some(diagram_id);
+        }
+        if idx == app.search_result_index {
+            current = Some(lines.len());
+        }
+
+        let mut label = hit.label.chars().take(label_width).collect::<String>();
+        if hit.label.chars().count() > label_width {
+            label.pop();
+            label.push('…');
+        }
+        lines.push(Line::from(vec![
+            Span::raw(format!("  {label:<label_width$}  ")),
+            Span::styled(hit.object_ref.to_string(), ref_style),
+            Span::styled(format!("  {:>score_width$}", hit.score), score_style),
+        ]));
+    }
+    (lines, current)
+}
+
+/// Draws the browsable results list along the bottom of the diagram pane. The diagram above it
+/// previews the current result, which `n/N` (or `j/k`) move through.
+fn render_search_results_panel(frame: &mut Frame<'_>, app: &App, content: Rect) {
+    if content.is_empty() || app.search_results.is_empty() {
+        return;
+    }
+    let (lines, current) = search_results_panel_lines(app);
+    let max_height = (content.height / 2).max(5).min(content.height);
+    let height = (lines.len() as u16).saturating_add(2).min(max_height);
+    let area = Rect::new(content.x, content.bottom() - height, content.width, height);
+
+    let title = trf(
+        "title.search_results",
+        &[
+            ("index", &app.search_result_index.saturating_add(1)),
+            ("total", &app.search_results.len()),
+        ],
+    );
+    let title = format!(" {title} ");
+    let list = List::new(lines.into_iter().map(ListItem::new).collect::<Vec<_>>())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .border_style(Style::default().fg(theme().footer_key)),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default();
+    state.select(current);
+    frame.render_widget(Clear, area);
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+const BREADCRUMB_SEPARATOR: &str = " › ";
+
+/// Session › diagram › object breadcrumb shown on the first row of the diagram pane.
+/// Inspector fallback when nothing is selected: the active diagram's long-form description.
+fn active_diagram_description_text(app: &App) -> String {
+    let Some(diagram) = app
+        .active_diagram_id()
+        .and_then(|diagram_id| app.session.diagrams().get(diagram_id))
+    else {
+        return tr("inspector.no_selection").to_owned();
+    };
+    match diagram.description() {
+        Some(description) => format!("{}\n\n{description}", diagram.name()),
+        None => format!("{}\n\n{}", diagram.name(), tr("inspector.no_description")),
+    }
+}
+
+fn breadcrumb_line(app: &App) -> Line<'static> {
+    let separator_style = Style::default().fg(Color::DarkGray);
+    let mut spans = vec![Span::styled(
+        app.session.session_id().to_string(),
+        Style::default().fg(Color::Gray),
+    )];
+    if let Some(title) = app.session.title() {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(
+            title.to_owned(),
+            Style::default().fg(Color::White),
+        ));
+    }
+    for tag in app.session.tags() {
+        spans.push(Span::styled(
+            format!(" #{tag}"),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+
+    let active = app
+        .active_diagram_id()
+        .and_then(|diagram_id| app.session.diagrams().get(diagram_id));
+    if let Some(diagram) = active {
+        let kind = match diagram.kind() {
+            DiagramKind::Sequence => "sequence",
+            DiagramKind::Flowchart => "flowchart",
+            DiagramKind::State => "state",
+            DiagramKind::Class => "class",
+        };
+        spans.push(Span::styled(BREADCRUMB_SEPARATOR, separator_style));
+        spans.push(Span::styled(
+            diagram.name().to_owned(),
+            Style::default().fg(Color::White),
+        ));
+        spans.push(Span::styled(
+            format!(" ({kind}, rev {})", diagram.rev()),
+            Style::default().fg(Color::Gray),
+        ));
+    }
+    if let Some(object_ref) = app.selected_ref() {
+        spans.push(Span::styled(BREADCRUMB_SEPARATOR, separator_style));
+        spans.push(Span::styled(
+            object_ref.to_string(),
+            Style::default().fg(theme().focus),
+        ));
+    }
+
+    Line::from(spans)
+}
+
+/// The location copied when the breadcrumb is clicked: the focused object ref, else the active
+/// diagram ref.
+fn breadcrumb_location(app: &App) -> Option<String> {
+    app.selected_ref()
+        .map(ToString::to_string)
+        .or_else(|| app.active_diagram_id().map(|diagram_id| format!("d:{diagram_id}")))
+}
+
+/// Splits the first row off the diagram pane for the breadcrumb when there is room to spare.
+fn split_breadcrumb_area(area: Rect) -> (Option<Rect>, Rect) {
+    if area.height < 2 {
+        return (None, area);
+    }
+
+    let breadcrumb = Rect::new(area.x, area.y, area.width, 1);
+    let rest = Rect::new(area.x, area.y + 1, area.width, area.height - 1);
+    (Some(breadcrumb), rest)
+}
+
+fn command_footer_line(command: &str, toast_suffix: &str) -> Line<'static> {
+    let mut spans = vec![
+        Span::styled(
+            ":".to_owned(),
+            Style::default()
+                .fg(theme().footer_key)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(command.to_owned()),
+        Span::raw("   "),
+    ];
+    push_footer_entry_with_separator(&mut spans, tr("footer.run"), "Enter", " | ");
+    push_footer_entry_with_separator(&mut spans, tr("footer.close"), "Esc", " | ");
+
+    let toast_message = toast_suffix
+        .strip_prefix(" | ")
+        .unwrap_or(toast_suffix)
+        .trim();
+    if !toast_message.is_empty() {
+        spans.push(Span::styled(" | ", Style::default().fg(theme().footer_label)));
+        spans.push(Span::raw(toast_message.to_owned()));
+    }
+
+    Line::from(spans)
+}
+
 fn footer_brand_line() -> Line<'static> {
     Line::from(vec![Span::styled(
         FOOTER_BRAND.to_owned(),
-        Style::default().fg(FOOTER_BRAND_COLOR),
+        Style::default().fg(theme().footer_brand),
     )])
 }
 
 fn help_key_style() -> Style {
     Style::default()
-        .fg(FOOTER_KEY_COLOR)
+        .fg(theme().footer_key)
         .add_modifier(Modifier::BOLD)
 }
 
@@ -515,242 +1038,257 @@ fn render_help(frame: &mut Frame<'_>, app: &mut App, main_area: Rect) {
     .max()
     .unwrap_or(0);
 
-    let mut lines = vec![Line::from(Span::styled("--- Global ---", header_style))];
-    lines.push(help_kv("?", "Help (toggle)", key_col_width, key_style));
-    lines.push(help_kv("q", "Quit", key_col_width, key_style));
-    lines.push(help_kv("1", "Focus Diagram", key_col_width, key_style));
-    lines.push(help_kv(
-        "2/3",
-        "Toggle+focus Objects/XRefs",
-        key_col_width,
-        key_style,
-    ));
-    lines.push(help_kv(
-        "4",
-        "Toggle inspector panel",
-        key_col_width,
-        key_style,
-    ));
-    lines.push(help_kv(
-        "a",
-        "Toggle follow AI highlight",
-        key_col_width,
-        key_style,
-    ));
-    lines.push(help_kv(
-        "d",
-        "Deselect all in current diagram",
-        key_col_width,
-        key_style,
-    ));
+    let keymap = Keymap::current();
+    let keys = |scope, actions: &[Action]| keymap.label(scope, actions);
+
+    let mut lines = vec![Line::from(Span::styled(tr("help.section.global"), header_style))];
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::Help]), tr("help.help_toggle"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::Quit]), tr("help.quit"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::FocusDiagram]), tr("help.focus_diagram"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::ToggleObjects, Action::ToggleXRefs]), tr("help.toggle_focus_objects_xrefs"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::ToggleInspector]), tr("help.toggle_inspector_panel"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::ToggleRelations]), tr("help.toggle_focus_relations"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::ToggleRecent]), tr("help.toggle_focus_recent_objects"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::ToggleTimeline]), tr("help.toggle_focus_activity_timeline"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::AlternateObject]), tr("help.swap_to_previously_focused_object"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::Undo, Action::Redo]), tr("help.undo_redo_diagram_edit_any_diagram"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::FollowAi]), tr("help.toggle_follow_ai_highlight"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::DeselectAll]), tr("help.deselect_all_in_current_diagram"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::FocusNext, Action::FocusPrev]), tr("help.focus_next_previous_panel"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::WindowSplit]), tr("help.split_open"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::WindowFocusOther, Action::WindowFocusLeft, Action::WindowFocusRight]), tr("help.split_focus"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::WindowClose, Action::WindowCloseOther]), tr("help.split_close"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::DiagramPrev, Action::DiagramNext]), tr("help.previous_next_diagram"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::MoveDiagramEarlier, Action::MoveDiagramLater]), tr("help.move_diagram_earlier_later"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::EditSession]), tr("help.edit_session_title_description_tags"), key_col_width, key_style));
     lines.push(help_kv(
-        "Tab/Shift-Tab",
-        "Focus next/previous panel",
+        "Click breadcrumb",
+        tr("help.copy_current_location_ref"),
         key_col_width,
         key_style,
     ));
     lines.push(help_kv(
-        "[/]",
-        "Previous/next diagram",
+        "Click diagram/list",
+        tr("help.focus_under_pointer"),
         key_col_width,
         key_style,
     ));
-    lines.push(help_kv("/", "Regular search", key_col_width, key_style));
-    lines.push(help_kv("\\", "Fuzzy search", key_col_width, key_style));
     lines.push(help_kv(
-        "n/N",
-        "Search next/previous result",
+        "Wheel",
+        tr("help.pan_diagram_shift_horizontally"),
         key_col_width,
         key_style,
     ));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::Search]), tr("help.regular_search"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::FuzzySearch]), tr("help.fuzzy_search"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Global, &[Action::SearchNext, Action::SearchPrev]), tr("help.search_next_previous_result"), key_col_width, key_style));
     lines.push(Line::from(""));
 
-    lines.push(Line::from(Span::styled("--- Search ---", header_style)));
-    lines.push(help_kv("Type", "Update query", key_col_width, key_style));
+    lines.push(Line::from(Span::styled(tr("help.section.search"), header_style)));
+    lines.push(help_kv("Type", tr("help.update_query"), key_col_width, key_style));
     lines.push(help_kv(
         "Enter",
-        "Commit results mode",
+        tr("help.commit_open_results_panel"),
         key_col_width,
         key_style,
     ));
     lines.push(help_kv(
-        "Backspace",
-        "Delete query char",
+        "n/N, j/k",
+        tr("help.browse_results_panel"),
         key_col_width,
         key_style,
     ));
-    lines.push(help_kv("Esc", "Clear search", key_col_width, key_style));
-    lines.push(Line::from(""));
-
-    lines.push(Line::from(Span::styled("--- Diagram ---", header_style)));
     lines.push(help_kv(
-        "↑↓←→ / h/j/k/l",
-        "Pan diagram by 1",
+        "Enter (results)",
+        tr("help.keep_current_result_close_panel"),
         key_col_width,
         key_style,
     ));
     lines.push(help_kv(
-        "H/J/K/L",
-        "Pan diagram by 10",
+        "Backspace",
+        tr("help.delete_query_char"),
         key_col_width,
         key_style,
     ));
+    lines.push(help_kv("Esc", tr("help.clear_search"), key_col_width, key_style));
+    lines.push(Line::from(""));
+
+    lines.push(Line::from(Span::styled(tr("help.section.diagram"), header_style)));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::PanLeft, Action::PanDown, Action::PanUp, Action::PanRight]), tr("help.pan_diagram_accelerates_when_held"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::PanLeftFar, Action::PanDownFar, Action::PanUpFar, Action::PanRightFar]), tr("help.pan_diagram_by_10"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::PageUp, Action::PageDown]), tr("help.pan_by_page"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::SnapStart, Action::SnapEnd]), tr("help.snap_to_top_left_bottom_right"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::ToggleNotes]), tr("help.toggle_notes_when_not_searching"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::ToggleRuler]), tr("help.toggle_ruler_grid_overlay"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::ZoomIn, Action::ZoomOut]), tr("help.zoom_in_out"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::Peek]), tr("help.peek_selected_object_diagram_and_objects"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::Insert]), tr("help.insert_node_flow_or_participant_message"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::MoveParticipantLeft, Action::MoveParticipantRight]), tr("help.move_selected_participant_left_right_seq"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::NudgeNodeLeft, Action::NudgeNodeUp, Action::NudgeNodeDown, Action::NudgeNodeRight]), tr("help.nudge_selected_node_flow"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::Stats]), tr("help.diagram_stats_size_and_render_cost"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::Legend]), tr("help.legend_xref_markers_highlights_style_rules"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::Center]), tr("help.center_viewport_on_selected_object"), key_col_width, key_style));
     lines.push(help_kv(
-        "PgUp/PgDn",
-        "Pan by page",
+        ":goto r,c",
+        tr("help.center_viewport_on_row_column"),
         key_col_width,
         key_style,
     ));
     lines.push(help_kv(
-        "Home",
-        "Reset pan to origin",
+        ":path a b",
+        tr("help.select_cheapest_weighted_flow_path"),
         key_col_width,
         key_style,
     ));
     lines.push(help_kv(
-        "n",
-        "Toggle notes (when not searching)",
+        ":critical",
+        tr("help.select_critical_longest_duration_path"),
         key_col_width,
         key_style,
     ));
-    lines.push(help_kv("f", "Hint jump mode", key_col_width, key_style));
-    lines.push(help_kv("c", "Chain hint mode", key_col_width, key_style));
+    lines.push(help_kv(":cuts", tr("help.select_cut_points"), key_col_width, key_style));
     lines.push(help_kv(
-        "e",
-        "Edit active diagram in $EDITOR",
+        &keys(KeyScope::Diagram, &[Action::HighlightPath]),
+        tr("help.highlight_path_between_selected_nodes"),
         key_col_width,
         key_style,
     ));
     lines.push(help_kv(
-        "Space",
-        "Toggle selected object",
+        ":tidy",
+        tr("help.preview_tidy_layout"),
         key_col_width,
         key_style,
     ));
     lines.push(help_kv(
-        "y",
-        "Yank selected object ref",
+        ":matcher [m]",
+        tr("help.fuzzy_matcher_nereid_or_skim_toggle"),
         key_col_width,
         key_style,
     ));
+    lines.push(help_kv(":edges [e]", tr("help.edge_glyphs_cycle"), key_col_width, key_style));
+    lines.push(help_kv(":a11y", tr("help.a11y_line_toggle"), key_col_width, key_style));
+    lines.push(help_kv(":check", tr("help.check_session"), key_col_width, key_style));
+    lines.push(help_kv(":sessions [name]", tr("help.sessions"), key_col_width, key_style));
+    lines.push(help_kv(":paste-new", tr("help.paste_new"), key_col_width, key_style));
+    lines.push(help_kv(":note [text]", tr("help.note_command"), key_col_width, key_style));
+    lines.push(help_kv(":diff [side]", tr("help.diff"), key_col_width, key_style));
+    lines.push(help_kv(":unpin [all]", tr("help.unpin"), key_col_width, key_style));
     lines.push(help_kv(
-        "g/t",
-        "Jump inbound/outbound",
+        ":archive",
+        tr("help.archive_diagram"),
         key_col_width,
         key_style,
     ));
     lines.push(help_kv(
-        "Hint: 2 letters",
-        "Choose hint target",
+        ":lock",
+        tr("help.lock_diagram"),
         key_col_width,
         key_style,
     ));
     lines.push(help_kv(
-        "Hint Esc",
-        "Cancel hint mode",
+        ":open <id>",
+        tr("help.open_diagram_by_id"),
         key_col_width,
         key_style,
     ));
-    lines.push(Line::from(""));
-
-    lines.push(Line::from(Span::styled("--- Objects ---", header_style)));
     lines.push(help_kv(
-        "↑/↓ or j/k",
-        "Move object cursor",
+        ":toseq",
+        tr("help.sequence_diagram_from_selected_flow_path"),
         key_col_width,
         key_style,
     ));
     lines.push(help_kv(
-        "Home/End",
-        "First/last object",
+        ":toflow",
+        tr("help.interaction_flowchart_from_sequence_diagram"),
         key_col_width,
         key_style,
     ));
     lines.push(help_kv(
-        "Space",
-        "Toggle selected object",
+        ":style <rule>",
+        tr("help.add_style_rule"),
         key_col_width,
         key_style,
     ));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::HintJump]), tr("help.hint_jump_mode"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::HintSelect]), tr("help.chain_hint_mode"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::VisualSelect]), tr("help.box_select_arrows_grow_enter_adds"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::EditDiagram]), tr("help.edit_active_diagram_in_editor"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::EditDescription]), tr("help.edit_diagram_description_in_editor"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::ToggleSelected]), tr("help.toggle_selected_object"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::Yank]), tr("help.yank_selected_object_ref"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Diagram, &[Action::JumpIncoming, Action::JumpOutgoing]), tr("help.jump_inbound_outbound"), key_col_width, key_style));
     lines.push(help_kv(
-        "-",
-        "Filter selected-only",
-        key_col_width,
-        key_style,
-    ));
-    lines.push(help_kv("f", "Hint jump mode", key_col_width, key_style));
-    lines.push(help_kv("c", "Chain hint mode", key_col_width, key_style));
-    lines.push(help_kv(
-        "y",
-        "Yank selected object ref",
+        "Hint: 2 letters",
+        tr("help.choose_hint_target"),
         key_col_width,
         key_style,
     ));
     lines.push(help_kv(
-        "g/t",
-        "Jump inbound/outbound",
+        "Hint Esc",
+        tr("help.cancel_hint_mode"),
         key_col_width,
         key_style,
     ));
     lines.push(Line::from(""));
 
-    lines.push(Line::from(Span::styled("--- XRefs ---", header_style)));
-    lines.push(help_kv(
-        "↑/↓ or j/k",
-        "Move xref cursor",
-        key_col_width,
-        key_style,
-    ));
-    lines.push(help_kv(
-        "Home/End",
-        "First/last xref",
-        key_col_width,
-        key_style,
-    ));
-    lines.push(help_kv(
-        "-",
-        "Toggle dangling-only filter",
-        key_col_width,
-        key_style,
-    ));
-    lines.push(help_kv(
-        "I",
-        "Toggle involving-selection filter",
-        key_col_width,
-        key_style,
-    ));
-    lines.push(help_kv(
-        "g/t",
-        "Jump to from/to endpoint",
-        key_col_width,
-        key_style,
-    ));
+    lines.push(Line::from(Span::styled(tr("help.section.objects"), header_style)));
+    lines.push(help_kv(&keys(KeyScope::Objects, &[Action::CursorNext, Action::CursorPrev]), tr("help.move_object_cursor"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Objects, &[Action::CursorFirst, Action::CursorLast]), tr("help.first_last_object"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Objects, &[Action::ToggleSelected]), tr("help.toggle_selected_object"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Objects, &[Action::SelectedOnly]), tr("help.filter_selected_only"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Objects, &[Action::MoveMessageEarlier, Action::MoveMessageLater]), tr("help.move_selected_message_up_down_seq"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Objects, &[Action::HintJump]), tr("help.hint_jump_mode"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Objects, &[Action::HintSelect]), tr("help.chain_hint_mode"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Objects, &[Action::Yank]), tr("help.yank_selected_object_ref"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Objects, &[Action::JumpIncoming, Action::JumpOutgoing]), tr("help.jump_inbound_outbound"), key_col_width, key_style));
+    lines.push(Line::from(""));
+
+    lines.push(Line::from(Span::styled(tr("help.section.xrefs"), header_style)));
+    lines.push(help_kv(&keys(KeyScope::XRefs, &[Action::CursorNext, Action::CursorPrev]), tr("help.move_xref_cursor"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::XRefs, &[Action::CursorFirst, Action::CursorLast]), tr("help.first_last_xref"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::XRefs, &[Action::DanglingOnly]), tr("help.toggle_dangling_only_filter"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::XRefs, &[Action::InvolvingOnly]), tr("help.toggle_involving_selection_filter"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::XRefs, &[Action::JumpFrom, Action::JumpTo]), tr("help.jump_to_from_to_endpoint"), key_col_width, key_style));
+    lines.push(Line::from(""));
+
+    lines.push(Line::from(Span::styled(tr("help.section.relations"), header_style)));
+    lines.push(help_kv(&keys(KeyScope::Relations, &[Action::CursorNext, Action::CursorPrev]), tr("help.move_relation_cursor"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Relations, &[Action::CursorFirst, Action::CursorLast]), tr("help.first_last_relation"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Relations, &[Action::Jump]), tr("help.jump_to_related_object"), key_col_width, key_style));
     lines.push(Line::from(""));
 
-    lines.push(Line::from(Span::styled("--- Help ---", header_style)));
+    lines.push(Line::from(Span::styled(tr("help.section.recent"), header_style)));
+    lines.push(help_kv(&keys(KeyScope::Recent, &[Action::CursorNext, Action::CursorPrev]), tr("help.move_recent_cursor"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Recent, &[Action::Jump]), tr("help.jump_to_recent_object"), key_col_width, key_style));
+    lines.push(Line::from(""));
+
+    lines.push(Line::from(Span::styled(tr("help.section.timeline"), header_style)));
+    lines.push(help_kv(&keys(KeyScope::Timeline, &[Action::CursorNext, Action::CursorPrev]), tr("help.move_timeline_cursor"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Timeline, &[Action::Jump]), tr("help.time_travel_to_event"), key_col_width, key_style));
+    lines.push(help_kv(&keys(KeyScope::Timeline, &[Action::LeaveTimeTravel]), tr("help.leave_time_travel"), key_col_width, key_style));
+    lines.push(Line::from(""));
+
+    lines.push(Line::from(Span::styled(tr("help.section.help"), header_style)));
     lines.push(help_kv(
         "j/k, ↑/↓, PgUp/PgDn, Home/End",
-        "Scroll help",
+        tr("help.scroll_help"),
         key_col_width,
         key_style,
     ));
-    lines.push(help_kv("Esc/?", "Close help", key_col_width, key_style));
+    lines.push(help_kv("Esc/?", tr("help.close_help"), key_col_width, key_style));
     lines.push(Line::from(vec![
-        Span::styled("Note: ", dim_style),
+        Span::styled(tr("help.note"), dim_style),
         Span::styled("g/t", key_style),
-        Span::styled(
-            " follow the first matching xref (toast if multiple).",
-            dim_style,
-        ),
+        Span::styled(tr("help.note_xref_follow"), dim_style),
     ]));
 
     let block = Block::default()
         .borders(Borders::ALL)
-        .title("─ Help ─")
-        .border_style(Style::default().fg(FOCUS_COLOR))
+        .title(format!("─ {} ─", tr("title.help")))
+        .border_style(Style::default().fg(theme().focus))
         .title_style(
             Style::default()
-                .fg(FOCUS_COLOR)
+                .fg(theme().focus)
                 .add_modifier(Modifier::BOLD),
         );
     let inner = block.inner(area);
@@ -770,6 +1308,34 @@ fn render_help(frame: &mut Frame<'_>, app: &mut App, main_area: Rect) {
     frame.render_widget(paragraph, area);
 }
 
+/// Highlight color of agent cursor palette slot `color`; the first agent keeps the agent focus
+/// color.
+fn agent_cursor_color(color: usize) -> Color {
+    const OTHERS: [Color; 5] =
+        [Color::Magenta, Color::Green, Color::Yellow, Color::Cyan, Color::LightRed];
+    match color {
+        0 => theme().agent_focus,
+        color => OTHERS[(color - 1) % OTHERS.len()],
+    }
+}
+
+/// `Agents:■name ■name` with each swatch in the agent's cursor color, while any agent has one.
+fn push_agent_cursor_legend(spans: &mut Vec<Span<'static>>, cursors: &[AgentCursor]) {
+    if cursors.is_empty() {
+        return;
+    }
+    let mut cursors = cursors.iter().collect::<Vec<_>>();
+    cursors.sort_by_key(|cursor| cursor.color);
+    push_footer_entry(spans, tr("footer.agents"), "");
+    for (index, cursor) in cursors.into_iter().enumerate() {
+        if index > 0 {
+            spans.push(Span::raw(" "));
+        }
+        spans.push(Span::styled("■", Style::default().fg(agent_cursor_color(cursor.color))));
+        spans.push(Span::raw(cursor.name.clone()));
+    }
+}
+
 fn push_footer_entry(spans: &mut Vec<Span<'static>>, label: &str, value: &str) {
     push_footer_entry_maybe_disabled(spans, label, value, false);
 }
@@ -802,12 +1368,12 @@ fn push_footer_entry_with_separator_maybe_disabled(
     if !spans.is_empty() {
         spans.push(Span::styled(
             separator.to_owned(),
-            Style::default().fg(FOOTER_LABEL_COLOR),
+            Style::default().fg(theme().footer_label),
         ));
     }
     spans.push(Span::styled(
         format!("{}:", footer_label_ucfirst(label)),
-        Style::default().fg(FOOTER_LABEL_COLOR),
+        Style::default().fg(theme().footer_label),
     ));
     spans.extend(footer_value_spans(value, disabled));
 }
@@ -827,7 +1393,7 @@ fn footer_value_spans(value: &str, disabled: bool) -> Vec<Span<'static>> {
     let color = if disabled {
         Color::DarkGray
     } else {
-        FOOTER_KEY_COLOR
+        theme().footer_key
     };
     vec![Span::styled(
         value.to_owned(),
@@ -11,18 +11,21 @@
 //! Provides the interactive TUI shell (ratatui + crossterm), including a built-in demo session.
 
 use std::{
-    collections::{BTreeMap, BTreeSet},
+    collections::{BTreeMap, BTreeSet, VecDeque},
     env,
     error::Error,
     fs, io,
     path::Path,
-    process::Command,
+    process::{Command, Stdio},
     sync::Arc,
     time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    },
     execute,
     style::Print,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
@@ -34,29 +37,74 @@ use ratatui::{
 use tokio::sync::Mutex;
 
 use crate::format::mermaid::{
-    export_flowchart, export_sequence_diagram, parse_flowchart, parse_sequence_diagram,
+    detect_diagram_kind, export_class_diagram, export_flowchart, export_sequence_diagram,
+    export_state_diagram, parse_class_diagram, parse_flowchart_with_id_rules,
+    parse_sequence_diagram_with_id_rules, parse_state_diagram,
 };
+use crate::layout::{layout_flowchart_with_options, FlowchartLayout, FlowchartLayoutOptions};
+use crate::model::seq_ast::{SequenceBlock, SequenceBlockKind, SequenceSectionKind};
 use crate::model::{
-    CategoryPath, Diagram, DiagramAst, DiagramId, DiagramKind, FlowchartAst, ObjectId, ObjectRef,
-    SequenceAst, SequenceMessage, SequenceMessageKind, SequenceParticipant, Session, SessionId,
+    CategoryPath, ClassAst, Diagram, DiagramAst, DiagramId, DiagramKind, FlowNodePin, FlowchartAst,
+    IdPrefixRule, ObjectId, ObjectRef, ObjectStyle, SequenceAst, SequenceMessage,
+    SequenceMessageKind, SequenceParticipant, Session, SessionId, StateAst, StyleEffect, StyleRule,
     XRef, XRefId, XRefStatus,
 };
-use crate::render::{HighlightIndex, LineSpan, RenderOptions};
-use crate::store::SessionFolder;
-use crate::ui::UiState;
+use crate::ops::history::{diff_delta, diff_ops, History, HistoryEntry, HistoryError, HistoryStep};
+use crate::ops::{apply_ops, idgen, FlowOp, Op, SeqMessagePatch, SeqNotePatch, SeqOp};
+use crate::query::diff::{diff_diagrams, DiagramDiff, DiffStatus};
+use crate::query::id_lint::id_prefix_violations;
+use crate::query::objects::object_facts;
+use crate::query::scaffold::{
+    flow_path_from_edges, flowchart_from_sequence, scaffold_diagram_id, scaffold_xrefs,
+    sequence_from_flow_path, ScaffoldLink,
+};
+use crate::query::search::{SearchField, SearchIndex, SearchTarget};
+use crate::query::validate::{validate_session, Diagnostic, Severity};
+use crate::render::budget::{
+    level_of_detail_options, render_diagram_within_budget_incremental, BudgetOverrun,
+    BudgetedRender, RenderBudget,
+};
+use crate::render::diagram::{IncrementalRenderer, RenderTimings};
+use crate::render::legend::{diagram_legend, LegendMarker};
+use crate::render::tidy::{tidy_flowchart, TidyPreview};
+use crate::render::{
+    AnnotatedRender, FlowchartEdgeGlyphs, FlowchartNodeScale, HighlightIndex, LineSpan,
+    RenderOptions,
+};
+use crate::store::{
+    merge_diagram_asts, JournalActor, JournalEntry, MergeConflict, MergeSide, SessionFolder,
+    SessionFolderWatcher, Workspace, WorkspaceSession,
+};
+use crate::ui::keymap::{Action, KeyLookup, KeyPress, KeyScope, Keymap};
+use crate::ui::messages::{tr, trf, Messages};
+use crate::ui::{
+    AgentCursor, DiagramProposal, HumanScreen, HumanViewport, ProposalStatus, Theme, UiState,
+    ViewRequest, ViewZoom,
+};
 
+mod a11y;
 mod hints;
+mod search;
+mod timeline;
+
+use a11y::{describe_focus, A11yOutput};
+use search::{skim_score, FuzzyMatcher, SearchScoring};
+use timeline::{clock_label, Activity, ActivityLog, Actor, DiagramAt};
 
-const FOCUS_COLOR: Color = Color::LightGreen;
-const AGENT_FOCUS_COLOR: Color = Color::LightBlue;
-const INSPECTOR_COLOR: Color = Color::DarkGray;
-const FOOTER_LABEL_COLOR: Color = Color::Gray;
-const FOOTER_KEY_COLOR: Color = Color::Cyan;
-const FOOTER_BRAND_COLOR: Color = Color::White;
 const FOOTER_BRAND: &str = "🅽 🅴 🆁 🅴 🅸 🅳 ";
 const NODE_HINT_CHARS: &str = "ASDFJKLEWCMPGH";
 const CENTER_BORDER_PADDING: i32 = 1;
+const PAN_REPEAT_WINDOW: Duration = Duration::from_millis(150);
+const PAN_REPEATS_PER_STEP: u32 = 4;
+const RECENT_OBJECTS_LIMIT: usize = 20;
+const PAN_MAX_STEP: i32 = 8;
+const WHEEL_PAN_STEP: i32 = 3;
+const FOCUS_FOLLOW_MARGIN: usize = 2;
 const TUI_FLOWCHART_EXTRA_COL_GAP: usize = 2;
+const TUI_FLOWCHART_SPACIOUS_COL_GAP: usize = 6;
+const RULER_MAJOR_STEP: i32 = 10;
+const RULER_MINOR_STEP: i32 = 5;
+const GRID_ROW_STEP: i32 = 5;
 
 /// Runs the interactive terminal UI.
 ///
@@ -66,11 +114,21 @@ pub fn run() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 pub fn run_with_session(session: crate::model::Session) -> Result<(), Box<dyn std::error::Error>> {
+    let render_budget = RenderBudget::from_env()?;
+    let search_scoring = SearchScoring::from_env()?;
+    let a11y = A11yOutput::from_env()?;
+    Theme::load(None)?.install();
+    Messages::load(None)?.install();
+    Keymap::load(None)?.install();
     let mut terminal = TerminalSession::new()?;
     let mut app = App::new(session);
+    app.set_render_budget(render_budget);
+    app.search_scoring = search_scoring;
+    app.a11y = a11y;
 
     while !app.should_quit {
         app.flush_pending_diagram_sync();
+        app.announce_focus();
         terminal.draw(|frame| draw(frame, &mut app))?;
 
         if event::poll(Duration::from_millis(250))? {
@@ -81,10 +139,11 @@ pub fn run_with_session(session: crate::model::Session) -> Result<(), Box<dyn st
                         let result =
                             terminal.run_external_action(|| app.execute_external_action(action));
                         if let Err(err) = result {
-                            app.set_toast(format!("External action failed: {err}"));
+                            app.set_toast(trf("toast.external_action_failed", &[("err", &err)]));
                         }
                     }
                 }
+                Event::Mouse(mouse) => app.handle_mouse(mouse),
                 _ => {}
             }
         }
@@ -106,16 +165,43 @@ pub fn run_with_session_with_ui_state(
     ui_state: Option<Arc<Mutex<UiState>>>,
     session_folder: Option<SessionFolder>,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    run_with_session_in_workspace(session, agent_highlights, ui_state, session_folder, None)
+}
+
+/// Like [`run_with_session_with_ui_state`], with `:sessions` switching between the sessions of
+/// `workspace`; `session_folder` should be one of them.
+pub fn run_with_session_in_workspace(
+    session: crate::model::Session,
+    agent_highlights: Arc<Mutex<BTreeSet<ObjectRef>>>,
+    ui_state: Option<Arc<Mutex<UiState>>>,
+    session_folder: Option<SessionFolder>,
+    workspace: Option<Workspace>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let render_budget = RenderBudget::from_env()?;
+    let search_scoring = SearchScoring::from_env()?;
+    let a11y = A11yOutput::from_env()?;
+    let config_dir = session_folder.as_ref().map(SessionFolder::root);
+    Theme::load(config_dir)?.install();
+    Messages::load(config_dir)?.install();
+    Keymap::load(config_dir)?.install();
     let mut terminal = TerminalSession::new()?;
     let mut app = App::new_with_ui(session, agent_highlights);
+    app.set_render_budget(render_budget);
+    app.search_scoring = search_scoring;
+    app.a11y = a11y;
     app.ui_state = ui_state;
     app.session_folder = session_folder;
+    app.workspace = workspace;
+    app.watch_session_folder();
     app.publish_focus_to_ui_state();
 
     while !app.should_quit {
         app.sync_from_ui_state();
+        app.sync_from_session_watcher();
         app.flush_pending_diagram_sync();
+        app.announce_focus();
         terminal.draw(|frame| draw(frame, &mut app))?;
+        app.publish_viewport_to_ui_state();
 
         if event::poll(Duration::from_millis(250))? {
             match event::read()? {
@@ -125,10 +211,11 @@ pub fn run_with_session_with_ui_state(
                         let result =
                             terminal.run_external_action(|| app.execute_external_action(action));
                         if let Err(err) = result {
-                            app.set_toast(format!("External action failed: {err}"));
+                            app.set_toast(trf("toast.external_action_failed", &[("err", &err)]));
                         }
                     }
                 }
+                Event::Mouse(mouse) => app.handle_mouse(mouse),
                 _ => {}
             }
         }
@@ -140,15 +227,20 @@ pub fn run_with_session_with_ui_state(
 fn draw(frame: &mut Frame<'_>, app: &mut App) {
     let area = frame.area();
 
+    let a11y_height = u16::from(app.a11y.line && app.a11y_description.is_some());
     let layout = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .constraints([Constraint::Min(0), Constraint::Length(a11y_height), Constraint::Length(1)])
         .split(area);
     let main_area = layout[0];
-    let status_area = layout[1];
+    let a11y_area = layout[1];
+    let status_area = layout[2];
 
     let sidebar_panel_count = usize::from(app.objects_visible)
         + usize::from(app.xrefs_visible)
+        + usize::from(app.relations_visible)
+        + usize::from(app.recent_visible)
+        + usize::from(app.timeline_visible)
         + usize::from(app.inspector_visible);
     let compact_footer = footer_uses_compact_mode(main_area, sidebar_panel_count);
     let sidebar_panels_visible = sidebar_panel_count > 0;
@@ -179,6 +271,9 @@ fn draw(frame: &mut Frame<'_>, app: &mut App) {
     enum SidebarPanel {
         Objects,
         XRefs,
+        Relations,
+        Recent,
+        Timeline,
         Inspector,
     }
     let mut sidebar_panels = Vec::<SidebarPanel>::new();
@@ -188,12 +283,24 @@ fn draw(frame: &mut Frame<'_>, app: &mut App) {
     if app.xrefs_visible {
         sidebar_panels.push(SidebarPanel::XRefs);
     }
+    if app.relations_visible {
+        sidebar_panels.push(SidebarPanel::Relations);
+    }
+    if app.recent_visible {
+        sidebar_panels.push(SidebarPanel::Recent);
+    }
+    if app.timeline_visible {
+        sidebar_panels.push(SidebarPanel::Timeline);
+    }
     if app.inspector_visible {
         sidebar_panels.push(SidebarPanel::Inspector);
     }
 
     let mut objects_area = None::<Rect>;
     let mut xrefs_area = None::<Rect>;
+    let mut relations_area = None::<Rect>;
+    let mut recent_area = None::<Rect>;
+    let mut timeline_area = None::<Rect>;
     let mut inspector_area = None::<Rect>;
     if !sidebar_panels.is_empty() {
         let Some(sidebar_content_area) = sidebar_content_area else {
@@ -202,11 +309,12 @@ fn draw(frame: &mut Frame<'_>, app: &mut App) {
         let constraints = match sidebar_panels.len() {
             1 => vec![Constraint::Min(0)],
             2 => vec![Constraint::Percentage(50), Constraint::Percentage(50)],
-            _ => vec![
+            3 => vec![
                 Constraint::Percentage(30),
                 Constraint::Percentage(30),
                 Constraint::Percentage(40),
             ],
+            panels => (0..panels).map(|_| Constraint::Ratio(1, panels as u32)).collect(),
         };
         let content = Layout::default()
             .direction(Direction::Vertical)
@@ -216,6 +324,9 @@ fn draw(frame: &mut Frame<'_>, app: &mut App) {
             match panel {
                 SidebarPanel::Objects => objects_area = Some(content[idx]),
                 SidebarPanel::XRefs => xrefs_area = Some(content[idx]),
+                SidebarPanel::Relations => relations_area = Some(content[idx]),
+                SidebarPanel::Recent => recent_area = Some(content[idx]),
+                SidebarPanel::Timeline => timeline_area = Some(content[idx]),
                 SidebarPanel::Inspector => inspector_area = Some(content[idx]),
             }
         }
@@ -228,40 +339,30 @@ fn draw(frame: &mut Frame<'_>, app: &mut App) {
         }
     }
 
-    let active_diagram_id =
-        app.active_diagram_id().map(ToString::to_string).unwrap_or_else(|| "—".to_owned());
-    let diagram_ids = app.session.diagrams().keys().collect::<Vec<_>>();
-    let diagram_total = diagram_ids.len();
-    let diagram_index = app
-        .active_diagram_id()
-        .and_then(|active| diagram_ids.iter().position(|diagram_id| *diagram_id == active))
-        .map(|idx| idx + 1);
-    let diagram_title = diagram_view_title(
-        &active_diagram_id,
-        app.focus == Focus::Diagram,
-        diagram_index,
-        diagram_total,
-    );
-    let diagram_border_style =
-        panel_border_style_for_focus(app.focus, Focus::Diagram, app.focus_owner);
-    let viewport_width = diagram_area.width.saturating_sub(2) as usize;
-    let viewport_height = diagram_area.height.saturating_sub(2) as usize;
-    app.center_diagram_if_needed(viewport_width, viewport_height);
-    let (scroll_x, scroll_y, left_pad, top_pad) = app.diagram_render_offsets();
-    let mut diagram_text = app.diagram_text();
-    if left_pad > 0 || top_pad > 0 {
-        diagram_text = pad_text(diagram_text, left_pad, top_pad);
+    app.close_split_of_removed_diagram();
+    let (diagram_area, parked_area) = match app.split.as_ref() {
+        Some(pane) => {
+            let halves = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(diagram_area);
+            if pane.on_left {
+                (halves[1], Some(halves[0]))
+            } else {
+                (halves[0], Some(halves[1]))
+            }
+        }
+        None => (diagram_area, None),
+    };
+    if let Some(parked_area) = parked_area {
+        draw_parked_pane(frame, app, parked_area);
     }
-    let diagram = Paragraph::new(diagram_text)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(diagram_title)
-                .border_style(diagram_border_style),
-        )
-        .scroll((scroll_y, scroll_x));
-    frame.render_widget(diagram, diagram_area);
+    // The focused pane is drawn last so the click and breadcrumb areas are its own.
+    let focused = app.focus == Focus::Diagram;
+    draw_diagram_pane(frame, app, diagram_area, focused);
+    let diagram_content_area = app.diagram_content_area.unwrap_or(diagram_area);
 
+    app.objects_hit_area = None;
     if let Some(objects_area) = objects_area {
         let objects_border_style =
             panel_border_style_for_focus(app.focus, Focus::Objects, app.focus_owner);
@@ -269,17 +370,30 @@ fn draw(frame: &mut Frame<'_>, app: &mut App) {
         if app.objects_selected_only {
             objects_suffix = Some("— selected only".to_owned());
         }
-        let objects_title = view_title("Objects", '2', objects_suffix.as_deref());
+        if let Some(tag) = app.objects_tag_filter.as_deref() {
+            objects_suffix = Some(match objects_suffix {
+                Some(suffix) => format!("{suffix}, tag={tag}"),
+                None => format!("— tag={tag}"),
+            });
+        }
+        let objects_title = view_title(tr("title.objects"), '2', objects_suffix.as_deref());
         let marker_style = Style::default().fg(Color::White).add_modifier(Modifier::BOLD);
         let visible_objects = app.visible_object_indices();
         let selected_object_refs = app.session.selected_object_refs();
         let has_active_selection_in_objects = !selected_object_refs.is_empty();
         let objects_has_focus = app.focus == Focus::Objects;
         let cursor_visible_idx = app.objects_state.selected();
-        let items = visible_objects
+        let window = lazy_list_window(
+            visible_objects.len(),
+            cursor_visible_idx,
+            objects_area.height.saturating_sub(2) as usize,
+            app.large_mode.is_some(),
+        );
+        let items = visible_objects[window.clone()]
             .iter()
             .enumerate()
-            .map(|(visible_idx, &idx)| {
+            .map(|(offset, &idx)| {
+                let visible_idx = window.start + offset;
                 let obj = &app.objects[idx];
                 let is_selected = selected_object_refs.contains(&obj.object_ref);
                 let is_cursor = cursor_visible_idx == Some(visible_idx);
@@ -312,9 +426,24 @@ fn draw(frame: &mut Frame<'_>, app: &mut App) {
                     .border_style(objects_border_style),
             )
             .highlight_style(Style::default());
-        frame.render_stateful_widget(objects_list, objects_area, &mut app.objects_state);
+        if window.start > 0 || window.end < visible_objects.len() {
+            let mut window_state = ListState::default();
+            window_state.select(cursor_visible_idx.map(|cursor| cursor - window.start));
+            frame.render_stateful_widget(objects_list, objects_area, &mut window_state);
+            app.objects_hit_area = Some(ListHitArea {
+                area: objects_area,
+                first_visible_idx: window.start + window_state.offset(),
+            });
+        } else {
+            frame.render_stateful_widget(objects_list, objects_area, &mut app.objects_state);
+            app.objects_hit_area = Some(ListHitArea {
+                area: objects_area,
+                first_visible_idx: app.objects_state.offset(),
+            });
+        }
     }
 
+    app.xrefs_hit_area = None;
     let selected_ref = app.selected_ref();
     if let Some(xrefs_area) = xrefs_area {
         let xrefs_border_style =
@@ -331,9 +460,16 @@ fn draw(frame: &mut Frame<'_>, app: &mut App) {
         } else {
             Some(format!("— {}", xrefs_title_suffix.join(", ")))
         };
-        let xrefs_title = view_title("XRefs", '3', xrefs_suffix.as_deref());
+        let xrefs_title = view_title(tr("title.xrefs"), '3', xrefs_suffix.as_deref());
         let visible_xrefs = app.visible_xref_indices();
-        let xref_items = visible_xrefs
+        let xref_cursor = app.xrefs_state.selected();
+        let xref_window = lazy_list_window(
+            visible_xrefs.len(),
+            xref_cursor,
+            xrefs_area.height.saturating_sub(2) as usize,
+            app.large_mode.is_some(),
+        );
+        let xref_items = visible_xrefs[xref_window.clone()]
             .iter()
             .map(|&idx| {
                 let xref = &app.xrefs[idx];
@@ -352,7 +488,146 @@ fn draw(frame: &mut Frame<'_>, app: &mut App) {
                     .border_style(xrefs_border_style),
             )
             .highlight_style(xrefs_cursor_highlight_style(app.focus, app.focus_owner));
-        frame.render_stateful_widget(xrefs_list, xrefs_area, &mut app.xrefs_state);
+        if xref_window.start > 0 || xref_window.end < visible_xrefs.len() {
+            let mut window_state = ListState::default();
+            window_state.select(xref_cursor.map(|cursor| cursor - xref_window.start));
+            frame.render_stateful_widget(xrefs_list, xrefs_area, &mut window_state);
+            app.xrefs_hit_area = Some(ListHitArea {
+                area: xrefs_area,
+                first_visible_idx: xref_window.start + window_state.offset(),
+            });
+        } else {
+            frame.render_stateful_widget(xrefs_list, xrefs_area, &mut app.xrefs_state);
+            app.xrefs_hit_area =
+                Some(ListHitArea { area: xrefs_area, first_visible_idx: app.xrefs_state.offset() });
+        }
+    }
+
+    if let Some(relations_area) = relations_area {
+        let relations_border_style =
+            panel_border_style_for_focus(app.focus, Focus::Relations, app.focus_owner);
+        let selected_ref = app.selected_ref().cloned();
+        let relations_suffix =
+            selected_ref.as_ref().map(|object_ref| format!("— {}", object_ref.object_id()));
+        let relations_title = view_title(tr("title.relations"), '5', relations_suffix.as_deref());
+        let rows = app.relation_rows();
+        let tag_style = Style::default().fg(theme().footer_key);
+        let relation_items = rows
+            .iter()
+            .map(|row| {
+                let style = if app.object_exists_in_session(&row.target) {
+                    Style::default().fg(Color::White)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:<6} ", row.tag), tag_style),
+                    Span::styled(row.label.clone(), style),
+                ]))
+            })
+            .collect::<Vec<_>>();
+        let relations_state = &mut app.relations_state;
+        if rows.is_empty() {
+            relations_state.select(None);
+        } else {
+            let cursor = relations_state.selected().unwrap_or(0).min(rows.len() - 1);
+            relations_state.select(Some(cursor));
+        }
+        let relations_list = List::new(relation_items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(relations_title)
+                    .border_style(relations_border_style),
+            )
+            .highlight_style(relations_cursor_highlight_style(app.focus, app.focus_owner));
+        frame.render_stateful_widget(relations_list, relations_area, &mut app.relations_state);
+    }
+
+    if let Some(recent_area) = recent_area {
+        let recent_border_style =
+            panel_border_style_for_focus(app.focus, Focus::Recent, app.focus_owner);
+        let recent_title = view_title(tr("title.recent"), '6', None);
+        let ref_style = Style::default().fg(theme().footer_label);
+        let recent_items = app
+            .recent_objects
+            .iter()
+            .map(|recent| {
+                let style = if app.object_exists_in_session(&recent.object_ref) {
+                    Style::default().fg(Color::White)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(recent.label.clone(), style),
+                    Span::styled(format!("  {}", recent.object_ref), ref_style),
+                ]))
+            })
+            .collect::<Vec<_>>();
+        let len = recent_items.len();
+        let recent_state = &mut app.recent_state;
+        if len == 0 {
+            recent_state.select(None);
+        } else {
+            let cursor = recent_state.selected().unwrap_or(0).min(len - 1);
+            recent_state.select(Some(cursor));
+        }
+        let recent_list = List::new(recent_items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(recent_title)
+                    .border_style(recent_border_style),
+            )
+            .highlight_style(recent_cursor_highlight_style(app.focus, app.focus_owner));
+        frame.render_stateful_widget(recent_list, recent_area, &mut app.recent_state);
+    }
+
+    if let Some(timeline_area) = timeline_area {
+        let timeline_border_style =
+            panel_border_style_for_focus(app.focus, Focus::Timeline, app.focus_owner);
+        let travel_tail = app
+            .time_travel
+            .and_then(|seq| app.activity.event(seq))
+            .map(|event| format!("— ⏱ {}", clock_label(event.at)));
+        let timeline_title = view_title(tr("title.timeline"), '7', travel_tail.as_deref());
+        let clock_style = Style::default().fg(theme().footer_label);
+        let timeline_items = app
+            .activity
+            .events()
+            .iter()
+            .rev()
+            .map(|event| {
+                let actor_color = match event.actor {
+                    Actor::Human => theme().focus,
+                    Actor::Agent => theme().agent_focus,
+                };
+                let marker = if app.time_travel == Some(event.seq) { "⏱ " } else { "  " };
+                ListItem::new(Line::from(vec![
+                    Span::raw(marker),
+                    Span::styled(clock_label(event.at), clock_style),
+                    Span::styled(format!(" {:<5} ", event.actor), Style::default().fg(actor_color)),
+                    Span::styled(event.summary(), Style::default().fg(Color::White)),
+                ]))
+            })
+            .collect::<Vec<_>>();
+        let len = timeline_items.len();
+        let timeline_state = &mut app.timeline_state;
+        if len == 0 {
+            timeline_state.select(None);
+        } else {
+            let cursor = timeline_state.selected().unwrap_or(0).min(len - 1);
+            timeline_state.select(Some(cursor));
+        }
+        let timeline_list = List::new(timeline_items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(timeline_title)
+                    .border_style(timeline_border_style),
+            )
+            .highlight_style(timeline_cursor_highlight_style(app.focus, app.focus_owner));
+        frame.render_stateful_widget(timeline_list, timeline_area, &mut app.timeline_state);
     }
 
     if let Some(inspector_area) = inspector_area {
@@ -361,9 +636,10 @@ fn draw(frame: &mut Frame<'_>, app: &mut App) {
                 Some(selected) => {
                     let from_missing = !app.object_exists_in_session(selected.xref.from());
                     let to_missing = !app.object_exists_in_session(selected.xref.to());
+                    let missing = tr("inspector.missing");
                     (
                         view_title(
-                            "Inspector",
+                            tr("title.inspector"),
                             '4',
                             Some(&format!(
                                 "— XRef {} ({})",
@@ -371,52 +647,120 @@ fn draw(frame: &mut Frame<'_>, app: &mut App) {
                                 selected.xref.status()
                             )),
                         ),
-                        format!(
-                            "ID: {}\nKind: {}\nStatus: {}\nLabel: {}\nFrom: {}{}\nTo: {}{}",
-                            selected.xref_id,
-                            selected.xref.kind(),
-                            selected.xref.status(),
-                            selected.xref.label().unwrap_or("—"),
-                            selected.xref.from(),
-                            if from_missing { " (missing)" } else { "" },
-                            selected.xref.to(),
-                            if to_missing { " (missing)" } else { "" },
+                        trf(
+                            "inspector.xref",
+                            &[
+                                ("xref_id", &selected.xref_id),
+                                ("kind", &selected.xref.kind()),
+                                ("status", &selected.xref.status()),
+                                ("label", &selected.xref.label().unwrap_or("—")),
+                                ("from", &selected.xref.from()),
+                                ("from_missing", &if from_missing { missing } else { "" }),
+                                ("to", &selected.xref.to()),
+                                ("to_missing", &if to_missing { missing } else { "" }),
+                            ],
                         ),
                     )
                 }
-                None => (view_title("Inspector", '4', Some("— XRef")), "No selection".to_owned()),
+                None => (
+                    view_title(tr("title.inspector"), '4', Some("— XRef")),
+                    tr("inspector.no_selection").to_owned(),
+                ),
             },
             _ => match app.selected_object() {
                 Some(obj) => {
                     let category = obj.object_ref.category().segments().join("/");
                     (
-                        view_title("Inspector", '4', Some(&format!("— {}", obj.object_ref))),
-                        format!(
-                            "Label: {}\nNote: {}\nRef: {}\nDiagram: {}\nCategory: {}\nObject: {}",
-                            obj.label,
-                            obj.note.as_deref().unwrap_or("—"),
-                            obj.object_ref,
-                            obj.object_ref.diagram_id(),
-                            category,
-                            obj.object_ref.object_id()
+                        view_title(
+                            tr("title.inspector"),
+                            '4',
+                            Some(&format!("— {}", obj.object_ref)),
+                        ),
+                        trf(
+                            "inspector.object",
+                            &[
+                                ("label", &obj.label),
+                                ("note", &obj.note.as_deref().unwrap_or("—")),
+                                ("object_ref", &obj.object_ref),
+                                ("diagram_id", &obj.object_ref.diagram_id()),
+                                ("category", &category),
+                                ("object_id", &obj.object_ref.object_id()),
+                            ],
                         ),
                     )
                 }
-                None => (view_title("Inspector", '4', None), "No selection".to_owned()),
+                None => (
+                    view_title(tr("title.inspector"), '4', None),
+                    active_diagram_description_text(app),
+                ),
             },
         };
         let inspector = Paragraph::new(inspector_text)
-            .style(Style::default().fg(INSPECTOR_COLOR))
+            .style(Style::default().fg(theme().inspector))
             .wrap(Wrap { trim: false })
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .border_style(Style::default().fg(INSPECTOR_COLOR))
+                    .border_style(Style::default().fg(theme().inspector))
                     .title(inspector_title),
             );
         frame.render_widget(inspector, inspector_area);
     }
 
+    if app.peek_visible {
+        if let Some(obj) = app.selected_object() {
+            let lines = object_peek_lines(&app.session, obj);
+            let anchor = app
+                .base_highlight_index
+                .get(&obj.object_ref)
+                .and_then(|spans| line_spans_bounds(spans))
+                .and_then(|bounds| {
+                    peek_anchor_on_screen(diagram_content_area, bounds, app.pan_x, app.pan_y)
+                });
+            let title = format!(" {} ", obj.object_ref.object_id());
+            render_peek_popover(frame, diagram_content_area, anchor, &title, lines);
+        }
+    }
+
+    if app.stats_visible {
+        let lines = diagram_stats_lines(app);
+        render_peek_popover(frame, diagram_content_area, None, " Stats ", lines);
+    }
+
+    if app.legend_visible {
+        let lines = diagram_legend_lines(app);
+        render_peek_popover(frame, diagram_content_area, None, " Legend ", lines);
+    }
+
+    if let Some(diagnostics) = &app.check_report {
+        let lines = check_report_lines(diagnostics);
+        render_peek_popover(frame, diagram_content_area, None, " Check ", lines);
+    }
+
+    if let Some(prompt) = &app.merge_prompt {
+        let title = format!(" Merge {} ", prompt.pending.diagram_id);
+        render_peek_popover(frame, diagram_content_area, None, &title, merge_prompt_lines(prompt));
+    }
+
+    if let Some(prompt) = &app.tidy_prompt {
+        let title = format!(" Tidy {} ", prompt.diagram_id);
+        render_peek_popover(frame, diagram_content_area, None, &title, tidy_prompt_lines(prompt));
+    }
+
+    if let Some(picker) = &app.session_picker {
+        let lines = session_picker_lines(picker, app.workspace_session_name().as_deref());
+        render_peek_popover(frame, diagram_content_area, None, " Sessions ", lines);
+    }
+
+    if app.search_mode == SearchMode::Results {
+        render_search_results_panel(frame, app, diagram_content_area);
+    }
+
+    if a11y_height > 0 {
+        let description = app.a11y_description.clone().unwrap_or_default();
+        frame.render_widget(Paragraph::new(description), a11y_area);
+    }
+
     let toast_snapshot = app.toast.as_ref().map(|toast| (toast.message.clone(), toast.expires_at));
     let toast_suffix = match toast_snapshot {
         Some((message, expires_at)) if expires_at > Instant::now() => format!(" | {message}"),
@@ -426,6 +770,20 @@ fn draw(frame: &mut Frame<'_>, app: &mut App) {
         }
         None => String::new(),
     };
+    if let Some(command) = app.command_line.as_deref() {
+        let status = Paragraph::new(command_footer_line(command, &toast_suffix));
+        frame.render_widget(status, status_area);
+        let brand = Paragraph::new(footer_brand_line()).alignment(Alignment::Right);
+        frame.render_widget(brand, status_area);
+        let cursor_x = status_area
+            .x
+            .saturating_add(1)
+            .saturating_add(command.chars().count() as u16)
+            .min(status_area.x.saturating_add(status_area.width.saturating_sub(1)));
+        frame.set_cursor_position((cursor_x, status_area.y));
+        return;
+    }
+
     if app.search_mode != SearchMode::Inactive {
         let query = app.search_query.as_str();
         let status = Paragraph::new(search_footer_line(app, &toast_suffix));
@@ -448,11 +806,136 @@ fn draw(frame: &mut Frame<'_>, app: &mut App) {
     let brand = Paragraph::new(footer_brand_line()).alignment(Alignment::Right);
     frame.render_widget(brand, status_area);
 
+    if let Some(form) = app.session_form.as_ref() {
+        render_session_form(frame, form, main_area);
+    }
+    if let Some(form) = app.insert_form.as_ref() {
+        render_insert_form(frame, form, main_area);
+    }
+
     if app.show_help {
         render_help(frame, app, main_area);
     }
 }
 
+/// Draws the focused diagram pane, or with `is_focused_pane` unset the parked one of a split.
+fn draw_diagram_pane(
+    frame: &mut Frame<'_>,
+    app: &mut App,
+    diagram_area: Rect,
+    is_focused_pane: bool,
+) {
+    let focused = is_focused_pane && app.focus == Focus::Diagram;
+    let active_diagram_id =
+        app.active_diagram_id().map(ToString::to_string).unwrap_or_else(|| "—".to_owned());
+    let diagram_ids = app.session.listed_diagram_ids();
+    let diagram_total = diagram_ids.len();
+    let diagram_index = app
+        .active_diagram_id()
+        .and_then(|active| diagram_ids.iter().position(|diagram_id| *diagram_id == active))
+        .map(|idx| idx + 1);
+    let mut diagram_title =
+        diagram_view_title(&active_diagram_id, focused, diagram_index, diagram_total);
+    let active_diagram = app.active_diagram_id().and_then(|id| app.session.diagrams().get(id));
+    if let Some(span) = active_diagram.and_then(diagram_direction_span) {
+        diagram_title.spans.push(span);
+    }
+    if active_diagram.is_some_and(|diagram| diagram.is_read_only()) {
+        diagram_title.spans.push(Span::raw("🔒 "));
+    }
+    if active_diagram.is_some_and(|diagram| diagram.is_archived()) {
+        diagram_title.spans.push(Span::styled("archived ", Style::default().fg(theme().dimmed)));
+    }
+    if let Some(event) = app.time_travel.and_then(|seq| app.activity.event(seq)) {
+        let marker = format!("⏱ {} ", clock_label(event.at));
+        diagram_title.spans.push(Span::styled(marker, Style::default().fg(Color::LightYellow)));
+    }
+    if let Some(preview) = &app.proposal_preview {
+        // Removed objects are not drawn, so the title counts them.
+        let marker = match preview.removed {
+            0 => format!("{} ", preview.proposal_id),
+            removed => format!("{} ({removed} removed) ", preview.proposal_id),
+        };
+        diagram_title.spans.push(Span::styled(marker, Style::default().fg(Color::LightMagenta)));
+    }
+    let diff_view = app
+        .diff_view
+        .as_ref()
+        .filter(|view| Some(view.saved.diagram_id()) == app.active_diagram_id());
+    if let Some(view) = diff_view {
+        let marker = format!(
+            "{} +{} -{} ~{} ",
+            if view.show_saved { "saved" } else { "diff" },
+            view.diff.count(DiffStatus::Added),
+            view.diff.count(DiffStatus::Removed),
+            view.diff.count(DiffStatus::Changed)
+        );
+        diagram_title.spans.push(Span::styled(marker, Style::default().fg(theme().diff_changed)));
+    }
+    let diagram_border_style = if is_focused_pane {
+        panel_border_style_for_focus(app.focus, Focus::Diagram, app.focus_owner)
+    } else {
+        Style::default()
+    };
+    let diagram_block = Block::default()
+        .borders(Borders::ALL)
+        .title(diagram_title)
+        .border_style(diagram_border_style);
+    let (breadcrumb_area, diagram_inner_area) =
+        split_breadcrumb_area(diagram_block.inner(diagram_area));
+    frame.render_widget(diagram_block, diagram_area);
+    app.breadcrumb_area = breadcrumb_area;
+    if let Some(breadcrumb_area) = breadcrumb_area {
+        frame.render_widget(Paragraph::new(breadcrumb_line(app)), breadcrumb_area);
+    }
+    let (ruler_areas, diagram_content_area) = if app.ruler_visible {
+        let diagram_line_count = app.base_diagram.split('\n').count();
+        let (top_ruler, left_ruler, content) =
+            split_ruler_areas(diagram_inner_area, diagram_line_count);
+        (Some((top_ruler, left_ruler)), content)
+    } else {
+        (None, diagram_inner_area)
+    };
+    let viewport_width = diagram_content_area.width as usize;
+    let viewport_height = diagram_content_area.height as usize;
+    app.diagram_viewport = (viewport_width, viewport_height);
+    app.center_diagram_if_needed(viewport_width, viewport_height);
+    app.clamp_pan();
+    let (scroll_x, scroll_y, left_pad, top_pad) = app.diagram_render_offsets();
+    let mut diagram_text = app.diagram_text();
+    if left_pad > 0 || top_pad > 0 {
+        diagram_text = pad_text(diagram_text, left_pad, top_pad);
+    }
+    let diagram = Paragraph::new(diagram_text).scroll((scroll_y, scroll_x));
+    frame.render_widget(diagram, diagram_content_area);
+    app.diagram_content_area = Some(diagram_content_area);
+    if let Some((top_ruler, left_ruler)) = ruler_areas {
+        // The ruler is painted onto the frame buffer only; rendered diagram text and exports
+        // never see it.
+        let diagram_line_count = app.base_diagram.split('\n').count();
+        let buf = frame.buffer_mut();
+        apply_grid_overlay(buf, diagram_content_area, app.pan_x, app.pan_y);
+        render_column_ruler(buf, top_ruler, app.pan_x);
+        render_row_ruler(buf, left_ruler, app.pan_y, diagram_line_count);
+    }
+}
+
+/// Draws the unfocused pane of a split by swapping its view in for the duration of the draw.
+fn draw_parked_pane(frame: &mut Frame<'_>, app: &mut App, area: Rect) {
+    let Some(mut pane) = app.split.take() else {
+        return;
+    };
+    // Hints and visual selections belong to the focused pane.
+    let hint_mode = std::mem::take(&mut app.hint_mode);
+    let visual_selection = app.visual_selection.take();
+    app.swap_pane_view(&mut pane);
+    draw_diagram_pane(frame, app, area, false);
+    app.swap_pane_view(&mut pane);
+    app.hint_mode = hint_mode;
+    app.visual_selection = visual_selection;
+    app.split = Some(pane);
+}
+
 // Extracted panel/header/footer/help rendering helpers.
 include!("chrome.rs");
 
@@ -470,6 +953,21 @@ struct SelectableXRef {
     xref: XRef,
 }
 
+/// An entry of the recently-focused objects list, labelled as it was when focused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RecentObject {
+    object_ref: ObjectRef,
+    label: String,
+}
+
+/// One row of the relations pane: an object related to the focused one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RelationRow {
+    tag: &'static str,
+    label: String,
+    target: ObjectRef,
+}
+
 #[derive(Debug, Clone)]
 struct Toast {
     message: String,
@@ -492,7 +990,18 @@ enum SearchKind {
 #[derive(Debug, Clone)]
 struct SearchCandidate {
     object_ref: ObjectRef,
+    label: String,
     haystack: String,
+    /// Prose from the session search index (notes, walkthrough steps, xref labels); only regular
+    /// search matches it, since fuzzy subsequences over prose match almost anything.
+    full_text: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SearchHit {
+    object_ref: ObjectRef,
+    label: String,
+    score: i64,
 }
 
 #[derive(Debug, Clone)]
@@ -505,6 +1014,235 @@ struct HintTarget {
     fill_char: char,
 }
 
+/// Consecutive single-step pans in one direction, used to accelerate held movement keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PanRepeat {
+    direction: (i32, i32),
+    last_at: Instant,
+    count: u32,
+}
+
+/// Screen area of a sidebar list and the visible index of its first row, for mouse clicks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ListHitArea {
+    area: Rect,
+    first_visible_idx: usize,
+}
+
+impl ListHitArea {
+    /// Visible index of the row at `position`, if it falls inside the list's borders.
+    fn row_at(self, position: Position) -> Option<usize> {
+        let inner = Block::default().borders(Borders::ALL).inner(self.area);
+        inner.contains(position).then(|| self.first_visible_idx + usize::from(position.y - inner.y))
+    }
+}
+
+const SESSION_INFO_FIELDS: [&str; 3] = ["form.title", "form.description", "form.tags"];
+
+/// Field-by-field editor for the session title, description and comma-separated tags.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SessionInfoForm {
+    field: usize,
+    values: [String; 3],
+}
+
+impl SessionInfoForm {
+    fn from_session(session: &Session) -> Self {
+        Self {
+            field: 0,
+            values: [
+                session.title().unwrap_or_default().to_owned(),
+                session.description().unwrap_or_default().to_owned(),
+                session.tags().join(", "),
+            ],
+        }
+    }
+
+    fn apply_to(&self, session: &mut Session) {
+        let [title, description, tags] = &self.values;
+        session.set_title(Some(title.as_str()));
+        session.set_description(Some(description.as_str()));
+        session.set_tags(tags.split(','));
+    }
+}
+
+/// Message keys of the insert prompt fields.
+const FLOW_INSERT_FIELDS: [&str; 3] = ["form.label", "form.shape", "form.from"];
+const SEQ_INSERT_FIELDS: [&str; 3] = ["form.name_text", "form.from", "form.to"];
+/// Node shapes the insert prompt accepts; the ones Mermaid export round-trips.
+const FLOW_INSERT_SHAPES: [&str; 3] = ["rect", "round", "diamond"];
+
+/// Prompt opened by `o` in the Diagram panel for adding an object to the active diagram.
+///
+/// Flowcharts get a node, plus an edge from `From` when set. Sequence diagrams get a participant,
+/// or a message when both `From` and `To` are set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct InsertForm {
+    diagram_id: DiagramId,
+    sequence: bool,
+    field: usize,
+    values: [String; 3],
+}
+
+impl InsertForm {
+    fn labels(&self) -> &'static [&'static str; 3] {
+        if self.sequence {
+            &SEQ_INSERT_FIELDS
+        } else {
+            &FLOW_INSERT_FIELDS
+        }
+    }
+
+    /// The ops adding the prompted object to `ast`, and the ref of the object to select after.
+    fn ops(&self, ast: &DiagramAst) -> Result<(Vec<Op>, ObjectRef), String> {
+        let [text, second, third] = &self.values;
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(format!("{} must not be empty", self.labels()[0]));
+        }
+        match ast {
+            DiagramAst::Flowchart(ast) => {
+                let shape = match second.trim() {
+                    "" => None,
+                    shape if FLOW_INSERT_SHAPES.contains(&shape) => Some(shape.to_owned()),
+                    shape => {
+                        return Err(format!(
+                            "Unknown shape {shape:?} (expected {})",
+                            FLOW_INSERT_SHAPES.join(", ")
+                        ))
+                    }
+                };
+                let node_id =
+                    idgen::labeled_object_id("n", text, |id| ast.nodes().contains_key(id));
+                let mut ops = vec![Op::Flow(FlowOp::AddNode {
+                    node_id: node_id.clone(),
+                    label: text.to_owned(),
+                    shape,
+                })];
+                if !third.trim().is_empty() {
+                    let from_node_id = resolve_flow_node(ast, third.trim())
+                        .ok_or_else(|| format!("Unknown node: {}", third.trim()))?;
+                    let taken = |id: &ObjectId| ast.edges().contains_key(id);
+                    ops.push(Op::Flow(FlowOp::AddEdge {
+                        edge_id: idgen::numbered_object_id("e", ast.edges().len() + 1, taken),
+                        from_node_id,
+                        to_node_id: node_id.clone(),
+                        label: None,
+                        connector: None,
+                        style: None,
+                    }));
+                }
+                let node_ref = ObjectRef::new(
+                    self.diagram_id.clone(),
+                    category_path(&["flow", "node"]),
+                    node_id,
+                );
+                Ok((ops, node_ref))
+            }
+            DiagramAst::Sequence(ast) => match (second.trim(), third.trim()) {
+                ("", "") => {
+                    let participant_id = idgen::labeled_object_id("p", text, |id| {
+                        ast.participants().contains_key(id)
+                    });
+                    let mermaid_name = participant_id.as_str()["p:".len()..].to_owned();
+                    let participant_ref = ObjectRef::new(
+                        self.diagram_id.clone(),
+                        category_path(&["seq", "participant"]),
+                        participant_id.clone(),
+                    );
+                    let op = SeqOp::AddParticipant { participant_id, mermaid_name };
+                    Ok((vec![Op::Seq(op)], participant_ref))
+                }
+                (from, to) if !from.is_empty() && !to.is_empty() => {
+                    let resolve = |token: &str| {
+                        resolve_seq_participant(ast, token)
+                            .ok_or_else(|| format!("Unknown participant: {token}"))
+                    };
+                    let (from_participant_id, to_participant_id) = (resolve(from)?, resolve(to)?);
+                    let taken = |id: &ObjectId| ast.messages().iter().any(|m| m.message_id() == id);
+                    let message_id =
+                        idgen::numbered_object_id("m", ast.messages().len() + 1, taken);
+                    let order_key =
+                        ast.messages().iter().map(SequenceMessage::order_key).max().unwrap_or(0)
+                            + 1000;
+                    let message_ref = ObjectRef::new(
+                        self.diagram_id.clone(),
+                        category_path(&["seq", "message"]),
+                        message_id.clone(),
+                    );
+                    let op = SeqOp::AddMessage {
+                        message_id,
+                        from_participant_id,
+                        to_participant_id,
+                        kind: SequenceMessageKind::Sync,
+                        arrow: None,
+                        text: text.to_owned(),
+                        order_key,
+                    };
+                    Ok((vec![Op::Seq(op)], message_ref))
+                }
+                _ => Err("Messages need both From and To".to_owned()),
+            },
+            _ => Err("Inserting objects needs a flowchart or sequence diagram".to_owned()),
+        }
+    }
+}
+
+/// Commands accepted on the `:` command line of the TUI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TuiCommand {
+    Goto {
+        row: usize,
+        col: usize,
+    },
+    Path {
+        from: String,
+        to: String,
+    },
+    Critical,
+    /// Selects the articulation nodes and bridge edges of the active flowchart.
+    Cuts,
+    Tidy,
+    Matcher(Option<FuzzyMatcher>),
+    /// Sets the flowchart edge glyphs, cycling through them when `None`.
+    Edges(Option<FlowchartEdgeGlyphs>),
+    /// Toggles the accessibility line.
+    A11y,
+    Archive(bool),
+    ReadOnly(bool),
+    Open(String),
+    ToSeq,
+    ToFlow,
+    StyleList,
+    StyleAdd(StyleRule),
+    StyleClear,
+    IdPrefixList,
+    IdPrefixAdd(IdPrefixRule),
+    IdPrefixClear,
+    LintIds,
+    /// Runs the session integrity checks and shows the report.
+    Check,
+    /// Opens a workspace session by name; `None` shows the session picker.
+    Sessions(Option<String>),
+    /// Creates a diagram from the Mermaid on the system clipboard.
+    PasteNew,
+    /// Limits the Objects panel to objects carrying a tag; `None` clears the filter.
+    Filter(Option<String>),
+    /// Previews an agent proposal; `None` toggles between the newest one and the original.
+    Proposal(Option<String>),
+    AcceptProposal,
+    DiscardProposal,
+    /// Sets the note of the selected object; `None` prefills the command line with the current
+    /// note for editing.
+    Note(Option<String>),
+    /// Clears the note of the selected object, or removes a selected sequence note.
+    Unnote,
+    /// Compares the active diagram with its saved version; `None` toggles the view.
+    Diff(Option<DiffSide>),
+    /// Unpins the selected flowchart node, or (`true`) every node of the active flowchart.
+    Unpin(bool),
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum HintKind {
     Jump,
@@ -529,44 +1267,235 @@ enum HintMode {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ExternalAction {
     EditActiveDiagram,
+    EditActiveDiagramDescription,
+}
+
+/// Outcome of writing a pending diagram edit to the session folder.
+enum DiagramSyncOutcome {
+    Saved,
+    /// The diagram changed on disk too; the merge of both was saved.
+    Merged(Box<Diagram>),
+    /// Both sides changed the same objects; nothing was saved.
+    Conflicts(Vec<MergeConflict>),
 }
 
+/// An unsynced edit whose merge with the disk version needs a side picked for `conflicts`.
 #[derive(Debug, Clone)]
-struct PendingDiagramSync {
+struct MergePrompt {
+    pending: PendingDiagramSync,
+    conflicts: Vec<MergeConflict>,
+}
+
+/// A `:tidy` preview waiting for confirmation before its order hints are persisted.
+#[derive(Debug, Clone)]
+struct TidyPrompt {
     diagram_id: DiagramId,
-    expected_disk_rev: u64,
+    /// Diagram rev the preview was computed at; a newer rev makes the preview stale.
+    rev: u64,
+    preview: TidyPreview,
 }
 
-struct App {
-    session: Session,
-    session_folder: Option<SessionFolder>,
+/// The `:sessions` picker over the sessions of the workspace.
+#[derive(Debug, Clone)]
+struct SessionPicker {
+    sessions: Vec<WorkspaceSession>,
+    index: usize,
+}
+
+/// An agent proposal shown in the diagram pane in place of the diagram it rewrites.
+#[derive(Debug, Clone)]
+struct ProposalPreview {
+    proposal_id: String,
+    /// The original with the proposed AST.
+    diagram: Diagram,
+    /// Objects the proposal adds or changes; `None` when ops cannot express the rewrite.
+    changed: Option<BTreeSet<ObjectRef>>,
+    removed: usize,
+}
+
+/// `:diff`: the active diagram compared with its saved version, objects colored by
+/// [`DiffStatus`].
+#[derive(Debug, Clone)]
+struct DiffView {
+    /// The version on disk when the view opened.
+    saved: Diagram,
+    /// Recomputed whenever the diagram renders, so edits show up while the view is open.
+    diff: DiagramDiff,
+    /// Shows the saved version, where removed objects are, instead of the current one.
+    show_saved: bool,
+}
+
+impl DiffView {
+    /// Added objects on the current side and removed ones on the saved side, plus changed
+    /// objects on both, with their theme color.
+    fn colored_refs(&self) -> Vec<(&ObjectRef, Color)> {
+        self.diff
+            .objects
+            .iter()
+            .filter_map(|object| {
+                let color = match (object.status, self.show_saved) {
+                    (DiffStatus::Added, false) => theme().diff_added,
+                    (DiffStatus::Removed, true) => theme().diff_removed,
+                    (DiffStatus::Changed, _) => theme().diff_changed,
+                    _ => return None,
+                };
+                Some((&object.object_ref, color))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DiffSide {
+    Current,
+    Saved,
+}
+
+/// A `v` rectangle in diagram cells: `anchor` stays put while the arrow keys move `cursor`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct VisualSelection {
+    anchor: (usize, usize),
+    cursor: (usize, usize),
+}
+
+impl VisualSelection {
+    /// Inclusive `((row0, row1), (col0, col1))` bounds of the rectangle.
+    fn bounds(&self) -> ((usize, usize), (usize, usize)) {
+        let (rows, cols) = ((self.anchor.0, self.cursor.0), (self.anchor.1, self.cursor.1));
+        ((rows.0.min(rows.1), rows.0.max(rows.1)), (cols.0.min(cols.1), cols.0.max(cols.1)))
+    }
+
+    fn intersects(&self, spans: &[LineSpan]) -> bool {
+        let ((row0, row1), (col0, col1)) = self.bounds();
+        spans.iter().any(|&(y, x0, x1)| (row0..=row1).contains(&y) && x0 <= col1 && x1 >= col0)
+    }
+}
+
+/// Diagram revision and view settings a pane's buffers were rendered with.
+type PaneRenderKey = (u64, bool, ViewZoom, FlowchartEdgeGlyphs, Option<u64>);
+
+/// The unfocused pane of a `Ctrl-w v` split.
+///
+/// The focused pane always lives in the `App` view fields; moving focus swaps them with the
+/// ones parked here, so every diagram command works on whichever pane has focus.
+#[derive(Debug, Default)]
+struct SplitPane {
+    diagram_id: Option<DiagramId>,
     base_diagram: String,
     base_highlight_index: HighlightIndex,
-    show_notes: bool,
-    hint_mode: HintMode,
-    hint_select_chain_prev: Option<ObjectRef>,
+    object_styles: BTreeMap<ObjectRef, ObjectStyle>,
+    render_timings: RenderTimings,
+    renderer: IncrementalRenderer,
+    large_mode: Option<(DiagramId, BudgetOverrun)>,
     pan_x: i32,
     pan_y: i32,
     center_diagram_on_next_draw: bool,
-    focus: Focus,
-    focus_owner: FocusOwner,
+    diagram_viewport: (usize, usize),
+    breadcrumb_area: Option<Rect>,
+    diagram_content_area: Option<Rect>,
+    objects: Vec<SelectableObject>,
+    objects_state: ListState,
+    visible_object_indices: Vec<usize>,
+    /// What the parked buffers show; a different key means they are re-rendered on swap-in.
+    rendered: Option<PaneRenderKey>,
+    /// Drawn left of the focused pane rather than right of it.
+    on_left: bool,
+}
+
+#[derive(Debug, Clone)]
+struct PendingDiagramSync {
+    diagram_id: DiagramId,
+    expected_disk_rev: u64,
+    /// Fingerprint of the diagram's `.mmd` file before the edit, to catch external edits that
+    /// keep the stored rev.
+    disk_fingerprint: Option<u64>,
+    /// The diagram as last synced, the base for merging concurrent disk edits.
+    base_ast: DiagramAst,
+}
+
+struct App {
+    session: Session,
+    session_folder: Option<SessionFolder>,
+    /// Set when started on a workspace, whose other sessions `:sessions` can switch to.
+    workspace: Option<Workspace>,
+    session_watcher: Option<SessionFolderWatcher>,
+    /// Set when the watcher saw external edits that have not been reloaded yet.
+    external_change_pending: bool,
+    base_diagram: String,
+    base_highlight_index: HighlightIndex,
+    /// Styling rule effects for the objects of the rendered diagram.
+    object_styles: BTreeMap<ObjectRef, ObjectStyle>,
+    render_timings: RenderTimings,
+    render_budget: RenderBudget,
+    /// Keeps the flowchart layout of the last render so structure-preserving edits skip relayout.
+    renderer: IncrementalRenderer,
+    /// Set while the active diagram renders at reduced detail because it blew the budget.
+    large_mode: Option<(DiagramId, BudgetOverrun)>,
+    show_notes: bool,
+    zoom: ViewZoom,
+    edge_glyphs: FlowchartEdgeGlyphs,
+    hint_mode: HintMode,
+    hint_select_chain_prev: Option<ObjectRef>,
+    pan_x: i32,
+    pan_y: i32,
+    center_diagram_on_next_draw: bool,
+    diagram_viewport: (usize, usize),
+    /// Keys typed so far of a multi-key binding such as `z z`.
+    pending_keys: Vec<KeyPress>,
+    /// The second diagram pane, while the view is split.
+    split: Option<SplitPane>,
+    pan_repeat: Option<PanRepeat>,
+    focus: Focus,
+    focus_owner: FocusOwner,
     ui_state: Option<Arc<Mutex<UiState>>>,
     ui_state_rev: u64,
     ui_state_session_rev: u64,
     agent_highlights: Arc<Mutex<BTreeSet<ObjectRef>>>,
+    /// Per-agent cursors from the shared UI state, least recently active first.
+    agent_cursors: Vec<AgentCursor>,
     objects: Vec<SelectableObject>,
     objects_state: ListState,
     visible_object_indices: Vec<usize>,
     objects_visible: bool,
     objects_selected_only: bool,
+    objects_tag_filter: Option<String>,
     xrefs: Vec<SelectableXRef>,
     xrefs_state: ListState,
     visible_xref_indices: Vec<usize>,
     xrefs_visible: bool,
     xrefs_dangling_only: bool,
     xrefs_involving_only: bool,
+    relations_state: ListState,
+    relations_visible: bool,
+    /// Most recently focused objects across diagrams, newest first.
+    recent_objects: VecDeque<RecentObject>,
+    recent_state: ListState,
+    recent_visible: bool,
+    /// Diagram edits, xref changes, selections and agent actions, with diagram snapshots.
+    activity: ActivityLog,
+    timeline_state: ListState,
+    timeline_visible: bool,
+    /// Activity event whose moment the diagram pane shows instead of the present.
+    time_travel: Option<u64>,
+    proposal_preview: Option<ProposalPreview>,
+    diff_view: Option<DiffView>,
+    /// Pending agent proposals already announced, so each one is announced once.
+    announced_proposals: BTreeSet<String>,
+    /// Agent highlight already logged, so the log only records changes.
+    logged_agent_highlight: Option<ObjectRef>,
     inspector_visible: bool,
     palette_visible: bool,
+    ruler_visible: bool,
+    peek_visible: bool,
+    stats_visible: bool,
+    /// Findings of the last `:check`, shown as an overlay while set.
+    check_report: Option<Vec<Diagnostic>>,
+    legend_visible: bool,
+    /// Accessibility mode outputs; the focus description is announced when any is enabled.
+    a11y: A11yOutput,
+    /// Focus the last description was made for, so each focus change is announced once.
+    a11y_announced: Option<(Option<DiagramId>, Option<ObjectRef>)>,
+    a11y_description: Option<String>,
     follow_ai: bool,
     show_help: bool,
     help_scroll: u16,
@@ -574,12 +1503,27 @@ struct App {
     toast: Option<Toast>,
     search_mode: SearchMode,
     search_kind: SearchKind,
+    search_scoring: SearchScoring,
     search_query: String,
     search_candidates: Vec<SearchCandidate>,
-    search_results: Vec<ObjectRef>,
+    search_results: Vec<SearchHit>,
     search_result_index: usize,
+    command_line: Option<String>,
+    session_form: Option<SessionInfoForm>,
+    insert_form: Option<InsertForm>,
+    tidy_prompt: Option<TidyPrompt>,
+    merge_prompt: Option<MergePrompt>,
+    session_picker: Option<SessionPicker>,
+    visual_selection: Option<VisualSelection>,
+    breadcrumb_area: Option<Rect>,
+    /// Where the last draw put the diagram text, so clicks can be mapped to diagram cells.
+    diagram_content_area: Option<Rect>,
+    objects_hit_area: Option<ListHitArea>,
+    xrefs_hit_area: Option<ListHitArea>,
     pending_external_action: Option<ExternalAction>,
     pending_diagram_sync: Option<PendingDiagramSync>,
+    /// Undo/redo stacks for diagram edits, shared across diagrams.
+    history: History,
     should_quit: bool,
 }
 
@@ -594,17 +1538,11 @@ impl App {
     ) -> Self {
         ensure_active_diagram_id(&mut session);
 
-        let (base_diagram, base_highlight_index, objects) = match session
+        let objects = session
             .active_diagram_id()
             .and_then(|diagram_id| session.diagrams().get(diagram_id))
-        {
-            Some(diagram) => {
-                let (text, highlight_index) =
-                    render_diagram_annotated_for_tui(&session, diagram, true);
-                (text, highlight_index, objects_from_diagram(diagram))
-            }
-            None => ("No diagrams in session".to_owned(), HighlightIndex::new(), Vec::new()),
-        };
+            .map(objects_from_diagram)
+            .unwrap_or_default();
 
         let mut objects_state = ListState::default();
         if !objects.is_empty() {
@@ -618,36 +1556,74 @@ impl App {
             xrefs_state.select(Some(0));
         }
         let visible_xref_indices: Vec<usize> = (0..xrefs.len()).collect();
-        Self {
+        let activity = ActivityLog::new(&session);
+        let mut app = Self {
             session,
             session_folder: None,
-            base_diagram,
-            base_highlight_index,
+            workspace: None,
+            session_watcher: None,
+            external_change_pending: false,
+            base_diagram: String::new(),
+            base_highlight_index: HighlightIndex::new(),
+            object_styles: BTreeMap::new(),
+            render_timings: RenderTimings::default(),
+            render_budget: RenderBudget::default(),
+            renderer: IncrementalRenderer::default(),
+            large_mode: None,
             show_notes: true,
+            zoom: ViewZoom::Normal,
+            edge_glyphs: FlowchartEdgeGlyphs::default(),
             hint_mode: HintMode::Inactive,
             hint_select_chain_prev: None,
             pan_x: 0,
             pan_y: 0,
             center_diagram_on_next_draw: true,
+            diagram_viewport: (0, 0),
+            pending_keys: Vec::new(),
+            split: None,
+            pan_repeat: None,
             focus: Focus::Diagram,
             focus_owner: FocusOwner::Human,
             ui_state: None,
             ui_state_rev: 0,
             ui_state_session_rev: 0,
             agent_highlights,
+            agent_cursors: Vec::new(),
             objects,
             objects_state,
             visible_object_indices,
             objects_visible: false,
             objects_selected_only: false,
+            objects_tag_filter: None,
             xrefs,
             xrefs_state,
             visible_xref_indices,
             xrefs_visible: false,
             xrefs_dangling_only: false,
             xrefs_involving_only: false,
+            relations_state: ListState::default(),
+            relations_visible: false,
+            recent_objects: VecDeque::new(),
+            recent_state: ListState::default(),
+            recent_visible: false,
+            activity,
+            timeline_state: ListState::default(),
+            timeline_visible: false,
+            time_travel: None,
+            proposal_preview: None,
+            diff_view: None,
+            announced_proposals: BTreeSet::new(),
+            logged_agent_highlight: None,
             inspector_visible: false,
             palette_visible: false,
+            ruler_visible: false,
+            peek_visible: false,
+            stats_visible: false,
+            check_report: None,
+            legend_visible: false,
+            a11y: A11yOutput::default(),
+            a11y_announced: None,
+            a11y_description: None,
             follow_ai: true,
             show_help: false,
             help_scroll: 0,
@@ -655,14 +1631,30 @@ impl App {
             toast: None,
             search_mode: SearchMode::Inactive,
             search_kind: SearchKind::Regular,
+            search_scoring: SearchScoring::default(),
             search_query: String::new(),
             search_candidates: Vec::new(),
             search_results: Vec::new(),
             search_result_index: 0,
+            command_line: None,
+            session_form: None,
+            insert_form: None,
+            tidy_prompt: None,
+            merge_prompt: None,
+            session_picker: None,
+            visual_selection: None,
+            breadcrumb_area: None,
+            diagram_content_area: None,
+            objects_hit_area: None,
+            xrefs_hit_area: None,
             pending_external_action: None,
             pending_diagram_sync: None,
+            history: History::default(),
             should_quit: false,
-        }
+        };
+        app.render_active_diagram_buffer(false);
+        app.record_recent_object();
+        app
     }
 
     fn active_diagram_id(&self) -> Option<&DiagramId> {
@@ -683,12 +1675,82 @@ impl App {
         }
     }
 
+    /// Describes the focused object once per focus change when accessibility mode is on.
+    fn announce_focus(&mut self) {
+        if !self.a11y.enabled() {
+            return;
+        }
+        let focus = (self.active_diagram_id().cloned(), self.selected_ref().cloned());
+        if self.a11y_announced.as_ref() == Some(&focus) {
+            return;
+        }
+        self.a11y_announced = Some(focus);
+        let description = describe_focus(&self.session, self.selected_object());
+        if let Err(err) = self.a11y.emit(&description) {
+            self.set_toast(trf("toast.a11y_output_failed", &[("err", &err)]));
+        }
+        self.a11y_description = Some(description);
+    }
+
+    /// Shows or hides the accessibility line; turning it on announces the current focus again.
+    fn toggle_a11y_line(&mut self) {
+        self.a11y.line = !self.a11y.line;
+        self.a11y_announced = None;
+        if self.a11y.line {
+            self.set_toast(tr("toast.a11y_line_on"));
+        } else {
+            self.set_toast(tr("toast.a11y_line_off"));
+        }
+    }
+
+    fn publish_viewport_to_ui_state(&mut self) {
+        let Some(ui_state) = self.ui_state.as_ref() else {
+            return;
+        };
+
+        let (width, height) = self.diagram_viewport;
+        let (lines, objects) = visible_diagram_cells(
+            &self.base_diagram,
+            &self.base_highlight_index,
+            (self.pan_x, self.pan_y),
+            (width, height),
+        );
+        let screen =
+            HumanScreen { diagram_id: self.session.active_diagram_id().cloned(), lines, objects };
+        let mut ui_state = ui_state.blocking_lock();
+        ui_state.set_human_viewport(HumanViewport {
+            scroll_x: self.pan_x,
+            scroll_y: self.pan_y,
+            width,
+            height,
+            zoom: self.zoom,
+        });
+        ui_state.set_human_screen(screen);
+    }
+
     fn sync_from_ui_state(&mut self) {
+        let mut view_requests = Vec::new();
+        let mut expired_agent_highlight = None;
         if let Some(ui_state) = self.ui_state.as_ref() {
-            let snapshot = ui_state.blocking_lock().clone();
+            let snapshot = {
+                let mut ui_state = ui_state.blocking_lock();
+                view_requests = ui_state.take_view_requests();
+                ui_state.clone()
+            };
+            expired_agent_highlight = snapshot.expired_agent_highlight().cloned();
+            self.agent_cursors = snapshot.agent_cursors().values().cloned().collect();
+            self.agent_cursors.sort_by_key(|cursor| cursor.updated_at_ms);
             if snapshot.rev() != self.ui_state_rev {
                 self.ui_state_rev = snapshot.rev();
                 self.follow_ai = snapshot.follow_ai();
+                self.sync_proposals(snapshot.proposals());
+                // Follow a `workspace.open` made by the agent.
+                if let Some(name) = snapshot
+                    .workspace_session()
+                    .filter(|&name| self.workspace_session_name().as_deref() != Some(name))
+                {
+                    self.open_workspace_session(name);
+                }
             }
 
             if snapshot.session_rev() != self.ui_state_session_rev
@@ -705,13 +1767,54 @@ impl App {
             }
         }
 
+        let agent_highlight = self.agent_highlights.blocking_lock().iter().next().cloned();
+        if agent_highlight != self.logged_agent_highlight {
+            if let Some(object_ref) = &agent_highlight {
+                self.log_agent_action(format!("highlight {object_ref}"));
+            } else if let Some(object_ref) = self
+                .logged_agent_highlight
+                .take()
+                .filter(|previous| expired_agent_highlight.as_ref() == Some(previous))
+            {
+                self.log_agent_action(format!("highlight {object_ref} expired (no heartbeat)"));
+            }
+            self.logged_agent_highlight = agent_highlight;
+        }
+
         if !self.follow_ai {
             return;
         }
 
+        for request in view_requests {
+            self.log_agent_action(view_request_summary(&request));
+            self.apply_view_request(request);
+        }
         self.follow_agent_highlight();
     }
 
+    fn apply_view_request(&mut self, request: ViewRequest) {
+        match request {
+            ViewRequest::CenterOn(object_ref) => {
+                if !self.object_exists_in_session(&object_ref) {
+                    return;
+                }
+                self.focus_owner = FocusOwner::Agent;
+                self.jump_to_object_ref(&object_ref);
+                self.center_viewport_on_object_ref(&object_ref);
+            }
+            ViewRequest::Scroll { dx, dy } => self.pan_by(dx, dy),
+            ViewRequest::SetActiveDiagram(diagram_id) => {
+                if self.session.diagrams().contains_key(&diagram_id)
+                    && self.active_diagram_id() != Some(&diagram_id)
+                {
+                    self.focus_owner = FocusOwner::Agent;
+                    self.set_active_diagram_id(diagram_id);
+                }
+            }
+            ViewRequest::Zoom(zoom) => self.set_zoom(zoom),
+        }
+    }
+
     fn sync_session_from_disk(&mut self) -> Result<(), String> {
         let Some(session_folder) = self.session_folder.as_ref() else {
             return Ok(());
@@ -724,10 +1827,13 @@ impl App {
         ensure_active_diagram_id(&mut disk_session);
 
         if disk_session == self.session {
+            // Keeps the disk session's source fingerprints so later syncs skip parsing.
+            self.session = disk_session;
             return Ok(());
         }
 
         self.session = disk_session;
+        self.log_session_activity(Actor::Agent);
         self.retain_existing_selected_refs();
         self.refresh_xref_statuses();
         self.xrefs = xrefs_from_session(&self.session);
@@ -741,6 +1847,60 @@ impl App {
         Ok(())
     }
 
+    fn watch_session_folder(&mut self) {
+        let Some(session_folder) = self.session_folder.as_ref() else {
+            return;
+        };
+        match SessionFolderWatcher::new(session_folder) {
+            Ok(watcher) => self.session_watcher = Some(watcher),
+            Err(err) => self.set_toast(trf("toast.external_edits_not_watched", &[("err", &err)])),
+        }
+    }
+
+    /// Reloads the session once the watcher reports files changed by another process.
+    ///
+    /// An unsynced local edit is flushed first; if its diagram changed on disk meanwhile, the
+    /// edit is merged into the disk version, and the reload waits while the merge prompt is open.
+    fn sync_from_session_watcher(&mut self) {
+        if let Some(watcher) = self.session_watcher.as_mut() {
+            if watcher.poll_changes(Instant::now()).is_some() {
+                self.external_change_pending = true;
+            }
+        }
+        if !self.external_change_pending {
+            return;
+        }
+
+        if self.merge_prompt.is_some() {
+            return;
+        }
+        if let Some(pending) = self.pending_diagram_sync.as_ref() {
+            if !self.pending_sync_conflicts_with_disk(pending) {
+                // Let the flush persist the edit; the reload follows on the next tick.
+                return;
+            }
+            self.flush_pending_diagram_sync();
+            if self.merge_prompt.is_some() {
+                return;
+            }
+        }
+
+        self.external_change_pending = false;
+        if let Err(err) = self.sync_session_from_disk() {
+            self.set_toast(err);
+        }
+    }
+
+    fn pending_sync_conflicts_with_disk(&self, pending: &PendingDiagramSync) -> bool {
+        let Some(session_folder) = self.session_folder.as_ref() else {
+            return false;
+        };
+        match session_folder.diagram_mmd_fingerprint(&pending.diagram_id) {
+            Ok(fingerprint) => fingerprint != pending.disk_fingerprint,
+            Err(_) => true,
+        }
+    }
+
     fn follow_agent_highlight(&mut self) -> bool {
         let Some(object_ref) = self.agent_highlights.blocking_lock().iter().next().cloned() else {
             return false;
@@ -762,21 +1922,18 @@ impl App {
 
     fn refresh_active_diagram_view(&mut self) {
         self.cancel_hint_mode();
-        let (base_diagram, base_highlight_index, objects) = match self
+        self.render_active_diagram_buffer(true);
+        let objects = self
             .session
             .active_diagram_id()
             .and_then(|diagram_id| self.session.diagrams().get(diagram_id))
-        {
-            Some(diagram) => {
-                let (text, highlight_index) =
-                    render_diagram_annotated_for_tui(&self.session, diagram, self.show_notes);
-                (text, highlight_index, objects_from_diagram(diagram))
-            }
-            None => ("No diagrams in session".to_owned(), HighlightIndex::new(), Vec::new()),
-        };
-
-        self.base_diagram = base_diagram;
-        self.base_highlight_index = base_highlight_index;
+            .map(|diagram| match &self.proposal_preview {
+                Some(preview) if preview.diagram.diagram_id() == diagram.diagram_id() => {
+                    objects_from_diagram(&preview.diagram)
+                }
+                _ => objects_from_diagram(diagram),
+            })
+            .unwrap_or_default();
         self.center_diagram_on_next_draw = true;
         self.pan_x = 0;
         self.pan_y = 0;
@@ -794,21 +1951,112 @@ impl App {
         self.publish_focus_to_ui_state();
     }
 
+    fn set_render_budget(&mut self, budget: RenderBudget) {
+        if self.render_budget != budget {
+            self.render_budget = budget;
+            self.render_active_diagram_buffer(false);
+        }
+    }
+
     fn rerender_active_diagram_buffer(&mut self) {
         self.cancel_hint_mode();
-        let (base_diagram, base_highlight_index) = match self
+        self.render_active_diagram_buffer(false);
+    }
+
+    /// Renders the active diagram into the view buffers within `render_budget`.
+    ///
+    /// Over-budget diagrams fall back to level-of-detail rendering and put the app into large
+    /// mode. With `keep_large_mode`, a diagram already in large mode skips the full-detail attempt
+    /// so content refreshes stay cheap; explicit view changes pass `false` to re-check the budget.
+    fn render_active_diagram_buffer(&mut self, keep_large_mode: bool) {
+        let Some(mut diagram) = self
             .session
             .active_diagram_id()
             .and_then(|diagram_id| self.session.diagrams().get(diagram_id))
-        {
-            Some(diagram) => {
-                render_diagram_annotated_for_tui(&self.session, diagram, self.show_notes)
-            }
-            None => ("No diagrams in session".to_owned(), HighlightIndex::new()),
+        else {
+            self.base_diagram = "No diagrams in session".to_owned();
+            self.base_highlight_index = HighlightIndex::new();
+            self.object_styles.clear();
+            self.render_timings = RenderTimings::default();
+            self.large_mode = None;
+            return;
         };
+        if let Some(seq) = self.time_travel {
+            match self.activity.diagram_at(diagram.diagram_id(), seq) {
+                DiagramAt::Current => {}
+                DiagramAt::Snapshot(snapshot) => diagram = snapshot,
+                DiagramAt::Missing => {
+                    let at = self.activity.event(seq).map(|event| clock_label(event.at));
+                    self.base_diagram = format!(
+                        "{} did not exist at {}",
+                        diagram.diagram_id(),
+                        at.as_deref().unwrap_or("that moment")
+                    );
+                    self.base_highlight_index = HighlightIndex::new();
+                    self.object_styles.clear();
+                    self.render_timings = RenderTimings::default();
+                    self.large_mode = None;
+                    return;
+                }
+            }
+        }
 
-        self.base_diagram = base_diagram;
-        self.base_highlight_index = base_highlight_index;
+        let preview = self
+            .proposal_preview
+            .as_ref()
+            .filter(|preview| preview.diagram.diagram_id() == diagram.diagram_id());
+        let preview_changes = preview.and_then(|preview| preview.changed.clone());
+        if let Some(preview) = preview {
+            diagram = &preview.diagram;
+        }
+        if let Some(view) =
+            self.diff_view.as_mut().filter(|view| view.saved.diagram_id() == diagram.diagram_id())
+        {
+            view.diff = diff_diagrams(&self.session, &view.saved, diagram);
+        }
+        if let Some(view) = self
+            .diff_view
+            .as_ref()
+            .filter(|view| view.show_saved && view.saved.diagram_id() == diagram.diagram_id())
+        {
+            diagram = &view.saved;
+        }
+
+        let forced_overrun = self
+            .large_mode
+            .as_ref()
+            .filter(|(diagram_id, _)| keep_large_mode && diagram_id == diagram.diagram_id())
+            .map(|(_, overrun)| *overrun);
+        let render = render_diagram_annotated_for_tui(
+            &mut self.renderer,
+            &self.session,
+            diagram,
+            self.show_notes,
+            self.zoom,
+            self.edge_glyphs,
+            &self.render_budget,
+            forced_overrun,
+        );
+        let entered_large_mode = forced_overrun.is_none() && render.overrun.is_some();
+        self.large_mode = render.overrun.map(|overrun| (diagram.diagram_id().clone(), overrun));
+        self.base_diagram = render.rendered.text;
+        self.base_highlight_index = render.rendered.highlight_index;
+        self.object_styles = crate::query::objects::object_styles(&self.session, diagram);
+        if let Some(changed) = preview_changes {
+            // Mark what the proposal touches against the original; the rest recedes.
+            for object_ref in self.base_highlight_index.keys() {
+                let style = self.object_styles.entry(object_ref.clone()).or_default();
+                if changed.contains(object_ref) {
+                    style.heavy = true;
+                } else {
+                    style.dim = true;
+                }
+            }
+        }
+        self.render_timings = render.timings;
+        if let (true, Some(overrun)) = (entered_large_mode, render.overrun) {
+            self.set_toast(trf("toast.large_diagram_reduced_detail", &[("overrun", &overrun)]));
+        }
     }
 
     fn center_diagram_if_needed(&mut self, viewport_width: usize, viewport_height: usize) {
@@ -819,11 +2067,7 @@ impl App {
             return;
         }
 
-        let diagram_width =
-            self.base_diagram.split('\n').map(|line| line.chars().count()).max().unwrap_or(0)
-                as i32;
-        let raw_line_count = self.base_diagram.split('\n').count() as i32;
-        let diagram_height = raw_line_count.max(0);
+        let (diagram_width, diagram_height) = self.diagram_extent();
         let viewport_width = viewport_width as i32;
         let viewport_height = viewport_height as i32;
 
@@ -837,6 +2081,72 @@ impl App {
         self.center_diagram_on_next_draw = false;
     }
 
+    fn diagram_extent(&self) -> (i32, i32) {
+        let width =
+            self.base_diagram.split('\n').map(|line| line.chars().count()).max().unwrap_or(0);
+        let height = self.base_diagram.split('\n').count();
+        (width as i32, height as i32)
+    }
+
+    /// Inclusive pan range per axis that keeps the diagram edge within one cell of padding.
+    ///
+    /// Diagrams larger than the viewport can be panned until their far edge is visible; smaller
+    /// ones can only move inside the viewport.
+    fn pan_bounds(&self) -> ((i32, i32), (i32, i32)) {
+        let (diagram_width, diagram_height) = self.diagram_extent();
+        let (viewport_width, viewport_height) = self.diagram_viewport;
+        let axis = |diagram: i32, viewport: usize| {
+            let near = -CENTER_BORDER_PADDING;
+            let far = diagram - viewport as i32 + CENTER_BORDER_PADDING;
+            (near.min(far), near.max(far))
+        };
+        (axis(diagram_width, viewport_width), axis(diagram_height, viewport_height))
+    }
+
+    fn clamp_pan(&mut self) {
+        let ((min_x, max_x), (min_y, max_y)) = self.pan_bounds();
+        self.pan_x = self.pan_x.clamp(min_x, max_x);
+        self.pan_y = self.pan_y.clamp(min_y, max_y);
+    }
+
+    fn pan_by(&mut self, dx: i32, dy: i32) {
+        self.pan_x = self.pan_x.saturating_add(dx);
+        self.pan_y = self.pan_y.saturating_add(dy);
+        self.center_diagram_on_next_draw = false;
+        self.clamp_pan();
+    }
+
+    /// Pans one step in `direction`, growing the step while the same key keeps repeating.
+    fn pan_accelerated(&mut self, direction: (i32, i32), now: Instant) {
+        let count = match self.pan_repeat {
+            Some(repeat)
+                if repeat.direction == direction
+                    && now.saturating_duration_since(repeat.last_at) <= PAN_REPEAT_WINDOW =>
+            {
+                repeat.count + 1
+            }
+            _ => 0,
+        };
+        self.pan_repeat = Some(PanRepeat { direction, last_at: now, count });
+
+        let step = (1 + (count / PAN_REPEATS_PER_STEP) as i32).min(PAN_MAX_STEP);
+        self.pan_by(direction.0 * step, direction.1 * step);
+    }
+
+    fn snap_pan_to_start(&mut self) {
+        self.pan_x = -CENTER_BORDER_PADDING;
+        self.pan_y = -CENTER_BORDER_PADDING;
+        self.center_diagram_on_next_draw = false;
+    }
+
+    fn snap_pan_to_end(&mut self) {
+        let (diagram_width, diagram_height) = self.diagram_extent();
+        let (viewport_width, viewport_height) = self.diagram_viewport;
+        self.pan_x = diagram_width - viewport_width as i32 + CENTER_BORDER_PADDING;
+        self.pan_y = diagram_height - viewport_height as i32 + CENTER_BORDER_PADDING;
+        self.center_diagram_on_next_draw = false;
+    }
+
     fn diagram_render_offsets(&self) -> (u16, u16, usize, usize) {
         let scroll_x = clamp_positive_i32_to_u16(self.pan_x);
         let scroll_y = clamp_positive_i32_to_u16(self.pan_y);
@@ -845,6 +2155,71 @@ impl App {
         (scroll_x, scroll_y, left_pad, top_pad)
     }
 
+    /// Pans so that diagram cell `(row, col)` sits in the middle of the diagram viewport.
+    fn center_viewport_on(&mut self, row: usize, col: usize) {
+        let (viewport_width, viewport_height) = self.diagram_viewport;
+        self.pan_x = col as i32 - (viewport_width / 2) as i32;
+        self.pan_y = row as i32 - (viewport_height / 2) as i32;
+        self.center_diagram_on_next_draw = false;
+    }
+
+    fn center_viewport_on_object_ref(&mut self, object_ref: &ObjectRef) -> bool {
+        let Some((row, col)) =
+            self.base_highlight_index.get(object_ref).and_then(|spans| line_spans_center(spans))
+        else {
+            return false;
+        };
+        self.center_viewport_on(row, col);
+        true
+    }
+
+    /// Pans the least amount needed to show the object's spans plus a small margin.
+    ///
+    /// Objects larger than the viewport are aligned to their top-left corner.
+    fn scroll_object_ref_into_view(&mut self, object_ref: &ObjectRef) {
+        let (viewport_width, viewport_height) = self.diagram_viewport;
+        if viewport_width == 0 || viewport_height == 0 {
+            return;
+        }
+        let Some(spans) = self.base_highlight_index.get(object_ref) else {
+            return;
+        };
+        let Some(((x0, x1), (y0, y1))) = line_spans_bounds(spans) else {
+            return;
+        };
+        // A pending recenter (after a diagram switch) would otherwise override the scroll.
+        self.center_diagram_if_needed(viewport_width, viewport_height);
+
+        self.pan_x = scroll_axis_into_view(self.pan_x, x0, x1, viewport_width);
+        self.pan_y = scroll_axis_into_view(self.pan_y, y0, y1, viewport_height);
+        self.clamp_pan();
+    }
+
+    fn center_on_selected_object(&mut self) {
+        let Some(object_ref) = self.selected_ref().cloned() else {
+            self.set_toast(tr("toast.no_object_selected"));
+            return;
+        };
+        if !self.center_viewport_on_object_ref(&object_ref) {
+            self.set_toast(tr("toast.selected_object_not_rendered"));
+        }
+    }
+
+    fn set_zoom(&mut self, zoom: ViewZoom) {
+        if self.zoom == zoom {
+            return;
+        }
+        self.zoom = zoom;
+        self.rerender_active_diagram_buffer();
+        self.set_toast(tr(match zoom {
+            ViewZoom::Glyph => "toast.zoom_glyphs",
+            ViewZoom::Mini => "toast.zoom_mini",
+            ViewZoom::Compact => "toast.zoom_compact",
+            ViewZoom::Normal => "toast.zoom_normal",
+            ViewZoom::Spacious => "toast.zoom_spacious",
+        }));
+    }
+
     fn toggle_show_notes(&mut self) {
         self.show_notes = !self.show_notes;
         self.rerender_active_diagram_buffer();
@@ -852,18 +2227,194 @@ impl App {
 
     fn set_active_diagram_id(&mut self, diagram_id: DiagramId) {
         self.cancel_hint_mode();
+        if self.proposal_preview.as_ref().is_some_and(|p| p.diagram.diagram_id() != &diagram_id) {
+            self.proposal_preview = None;
+        }
+        if self.diff_view.as_ref().is_some_and(|view| view.saved.diagram_id() != &diagram_id) {
+            self.diff_view = None;
+        }
         self.session.set_active_diagram_id(Some(diagram_id));
+        self.persist_active_diagram_id();
+        self.refresh_active_diagram_view();
+    }
+
+    fn persist_active_diagram_id(&mut self) {
         if let Some(session_folder) = self.session_folder.as_ref() {
             if let Err(err) = session_folder.save_active_diagram_id(&self.session) {
-                self.set_toast(format!("Active diagram persist failed: {err}"));
+                self.set_toast(trf("toast.active_diagram_persist_failed", &[("err", &err)]));
             }
         }
-        self.refresh_active_diagram_view();
+    }
+
+    fn pane_render_key(&self, diagram_id: Option<&DiagramId>) -> Option<PaneRenderKey> {
+        let diagram = self.session.diagrams().get(diagram_id?)?;
+        Some((diagram.rev(), self.show_notes, self.zoom, self.edge_glyphs, self.time_travel))
+    }
+
+    /// Swaps the focused pane's view with `pane`, re-rendering the incoming view if its diagram
+    /// or the view settings changed while it was parked.
+    fn swap_pane_view(&mut self, pane: &mut SplitPane) {
+        let incoming = pane.rendered.take();
+        std::mem::swap(&mut self.base_diagram, &mut pane.base_diagram);
+        std::mem::swap(&mut self.base_highlight_index, &mut pane.base_highlight_index);
+        std::mem::swap(&mut self.object_styles, &mut pane.object_styles);
+        std::mem::swap(&mut self.render_timings, &mut pane.render_timings);
+        std::mem::swap(&mut self.renderer, &mut pane.renderer);
+        std::mem::swap(&mut self.large_mode, &mut pane.large_mode);
+        std::mem::swap(&mut self.pan_x, &mut pane.pan_x);
+        std::mem::swap(&mut self.pan_y, &mut pane.pan_y);
+        std::mem::swap(
+            &mut self.center_diagram_on_next_draw,
+            &mut pane.center_diagram_on_next_draw,
+        );
+        std::mem::swap(&mut self.diagram_viewport, &mut pane.diagram_viewport);
+        std::mem::swap(&mut self.breadcrumb_area, &mut pane.breadcrumb_area);
+        std::mem::swap(&mut self.diagram_content_area, &mut pane.diagram_content_area);
+        std::mem::swap(&mut self.objects, &mut pane.objects);
+        std::mem::swap(&mut self.objects_state, &mut pane.objects_state);
+        std::mem::swap(&mut self.visible_object_indices, &mut pane.visible_object_indices);
+        let focused_diagram_id = self.session.active_diagram_id().cloned();
+        self.session.set_active_diagram_id(pane.diagram_id.take());
+        pane.diagram_id = focused_diagram_id;
+
+        pane.rendered = self.pane_render_key(pane.diagram_id.as_ref());
+        if incoming != self.pane_render_key(self.active_diagram_id()) {
+            self.refresh_pane_contents();
+        }
+    }
+
+    /// Re-renders the focused pane and rebuilds its object list, keeping the cursor on the same
+    /// object while it exists.
+    fn refresh_pane_contents(&mut self) {
+        let cursor = self.selected_ref().cloned();
+        self.render_active_diagram_buffer(true);
+        self.objects = self
+            .active_diagram_id()
+            .and_then(|diagram_id| self.session.diagrams().get(diagram_id))
+            .map(objects_from_diagram)
+            .unwrap_or_default();
+        self.recompute_visible_object_indices();
+        let cursor_idx = cursor.and_then(|cursor| {
+            self.visible_object_indices
+                .iter()
+                .position(|&idx| self.objects[idx].object_ref == cursor)
+        });
+        let first_idx = (!self.visible_object_indices.is_empty()).then_some(0);
+        self.objects_state.select(cursor_idx.or(first_idx));
+    }
+
+    /// Opens a second diagram pane beside the focused one (`Ctrl-w v`) and focuses it.
+    ///
+    /// The new pane shows the other end of an xref of the object under the cursor, so both
+    /// diagrams of the link can be inspected at once; without an xref it shows the same diagram.
+    fn open_split_pane(&mut self) {
+        if self.split.is_some() {
+            self.set_toast(tr("toast.split_already_open"));
+            return;
+        }
+        let Some(diagram_id) = self.active_diagram_id().cloned() else {
+            self.set_toast(tr("toast.no_diagram_to_split"));
+            return;
+        };
+        let cursor = self.selected_ref().cloned();
+        let target = cursor.as_ref().and_then(|cursor| self.xref_partner(cursor)).or(cursor);
+
+        let mut pane = SplitPane { on_left: true, ..SplitPane::default() };
+        self.swap_pane_view(&mut pane);
+        self.split = Some(pane);
+        self.focus = Focus::Diagram;
+        let target_diagram_id = target.as_ref().map_or(diagram_id, |t| t.diagram_id().clone());
+        self.set_active_diagram_id(target_diagram_id);
+        if let Some(target) = target {
+            self.select_object_ref_in_focused_pane(&target);
+        }
+    }
+
+    /// The other end of an xref touching `object_ref`, preferring ends in other diagrams.
+    fn xref_partner(&self, object_ref: &ObjectRef) -> Option<ObjectRef> {
+        let partners = self
+            .session
+            .xrefs()
+            .values()
+            .filter_map(|xref| {
+                if xref.from() == object_ref {
+                    Some(xref.to())
+                } else if xref.to() == object_ref {
+                    Some(xref.from())
+                } else {
+                    None
+                }
+            })
+            .filter(|partner| self.object_exists_in_session(partner))
+            .collect::<Vec<_>>();
+        partners
+            .iter()
+            .find(|partner| partner.diagram_id() != object_ref.diagram_id())
+            .or(partners.first())
+            .map(|partner| (*partner).clone())
+    }
+
+    /// Moves focus to the other pane of a split (`Ctrl-w w`).
+    fn focus_other_pane(&mut self) {
+        let Some(mut pane) = self.split.take() else {
+            self.set_toast(tr("toast.no_split"));
+            return;
+        };
+        self.cancel_hint_mode();
+        self.swap_pane_view(&mut pane);
+        pane.on_left = !pane.on_left;
+        self.split = Some(pane);
+        self.focus = Focus::Diagram;
+        self.after_pane_change();
+    }
+
+    /// Focuses the diagram pane on the left (or right) side of a split.
+    fn focus_pane_on_side(&mut self, left: bool) {
+        if self.split.as_ref().is_some_and(|pane| pane.on_left == left) {
+            self.focus_other_pane();
+        }
+        self.focus = Focus::Diagram;
+    }
+
+    /// Closes the focused pane (`Ctrl-w c`); the other one takes its place.
+    fn close_focused_pane(&mut self) {
+        let Some(mut pane) = self.split.take() else {
+            self.set_toast(tr("toast.no_split"));
+            return;
+        };
+        self.cancel_hint_mode();
+        self.swap_pane_view(&mut pane);
+        self.after_pane_change();
+    }
+
+    /// Closes the unfocused pane (`Ctrl-w o`).
+    fn close_other_pane(&mut self) {
+        if self.split.take().is_none() {
+            self.set_toast(tr("toast.no_split"));
+        }
+    }
+
+    fn close_split_of_removed_diagram(&mut self) {
+        let removed = self.split.as_ref().is_some_and(|pane| {
+            pane.diagram_id.as_ref().map_or(true, |id| !self.session.diagrams().contains_key(id))
+        });
+        if removed {
+            self.split = None;
+        }
+    }
+
+    fn after_pane_change(&mut self) {
+        self.persist_active_diagram_id();
+        if self.xrefs_involving_only {
+            self.apply_xref_filters();
+        }
+        self.publish_focus_to_ui_state();
     }
 
     fn switch_diagram_prev(&mut self) {
         self.cancel_hint_mode();
-        let diagram_ids: Vec<DiagramId> = self.session.diagrams().keys().cloned().collect();
+        let diagram_ids: Vec<DiagramId> =
+            self.session.listed_diagram_ids().into_iter().cloned().collect();
         if diagram_ids.is_empty() {
             return;
         }
@@ -871,11 +2422,10 @@ impl App {
         let current_idx = self
             .session
             .active_diagram_id()
-            .and_then(|active| diagram_ids.iter().position(|id| id == active))
-            .unwrap_or(0);
+            .and_then(|active| diagram_ids.iter().position(|id| id == active));
         let prev_idx = match current_idx {
-            0 => diagram_ids.len().saturating_sub(1),
-            n => n - 1,
+            Some(0) | None => diagram_ids.len() - 1,
+            Some(n) => n - 1,
         };
 
         self.set_active_diagram_id(diagram_ids[prev_idx].clone());
@@ -883,21 +2433,37 @@ impl App {
 
     fn switch_diagram_next(&mut self) {
         self.cancel_hint_mode();
-        let diagram_ids: Vec<DiagramId> = self.session.diagrams().keys().cloned().collect();
+        let diagram_ids: Vec<DiagramId> =
+            self.session.listed_diagram_ids().into_iter().cloned().collect();
         if diagram_ids.is_empty() {
             return;
         }
 
-        let current_idx = self
+        let next_idx = self
             .session
             .active_diagram_id()
             .and_then(|active| diagram_ids.iter().position(|id| id == active))
-            .unwrap_or(0);
-        let next_idx = (current_idx + 1) % diagram_ids.len();
+            .map_or(0, |current_idx| (current_idx + 1) % diagram_ids.len());
 
         self.set_active_diagram_id(diagram_ids[next_idx].clone());
     }
 
+    /// Moves the active diagram within the curated order used by `[`/`]`.
+    fn move_active_diagram(&mut self, offset: isize) {
+        self.cancel_hint_mode();
+        let Some(diagram_id) = self.session.active_diagram_id().cloned() else {
+            return;
+        };
+        if !self.session.move_diagram(&diagram_id, offset) {
+            return;
+        }
+        if let Some(session_folder) = self.session_folder.as_ref() {
+            if let Err(err) = session_folder.save_diagram_order(&self.session) {
+                self.set_toast(trf("toast.diagram_order_persist_failed", &[("err", &err)]));
+            }
+        }
+    }
+
     fn visible_object_indices(&self) -> &[usize] {
         &self.visible_object_indices
     }
@@ -956,14 +2522,32 @@ impl App {
                 apply_presence_flags(&mut note_cells_by_line, spans);
             }
         }
-        let sequence_block_color = Color::LightYellow;
-        let sequence_area_bg = Color::Yellow;
+        let sequence_block_color = theme().sequence_block;
+        let sequence_area_bg = theme().sequence_area;
 
-        if let Some(selected_ref) = selected_ref.as_ref() {
-            if let Some(spans) = self.base_highlight_index.get(selected_ref) {
-                apply_highlight_flags(&mut flags_by_line, spans, 0b01);
-                fill_highlight_bridge_gaps(&mut flags_by_line, &self.base_diagram, 0b01);
-                fill_highlight_text_space_gaps(&mut flags_by_line, &self.base_diagram, 0b01);
+        let mut dim_cells_by_line = self
+            .base_diagram
+            .split('\n')
+            .map(|line| vec![false; line.chars().count()])
+            .collect::<Vec<_>>();
+        let mut heavy_cells_by_line = dim_cells_by_line.clone();
+        for (object_ref, object_style) in &self.object_styles {
+            let Some(spans) = self.base_highlight_index.get(object_ref) else {
+                continue;
+            };
+            if object_style.dim {
+                apply_presence_flags(&mut dim_cells_by_line, spans);
+            }
+            if object_style.heavy {
+                apply_presence_flags(&mut heavy_cells_by_line, spans);
+            }
+        }
+
+        if let Some(selected_ref) = selected_ref.as_ref() {
+            if let Some(spans) = self.base_highlight_index.get(selected_ref) {
+                apply_highlight_flags(&mut flags_by_line, spans, 0b01);
+                fill_highlight_bridge_gaps(&mut flags_by_line, &self.base_diagram, 0b01);
+                fill_highlight_text_space_gaps(&mut flags_by_line, &self.base_diagram, 0b01);
                 if is_flow_edge_ref(selected_ref) {
                     fill_highlight_bridge_gaps_unbounded(
                         &mut flags_by_line,
@@ -1006,6 +2590,47 @@ impl App {
                 fill_highlight_text_space_gaps(&mut flags_by_line, &self.base_diagram, 0b10);
             }
         }
+        // Each agent cursor in its own color, the most recently active one on top.
+        let mut agent_cells_by_line = self
+            .base_diagram
+            .split('\n')
+            .map(|line| vec![None::<Color>; line.chars().count()])
+            .collect::<Vec<_>>();
+        for cursor in &self.agent_cursors {
+            let Some(spans) = self.base_highlight_index.get(&cursor.object_ref) else {
+                continue;
+            };
+            let color = agent_cursor_color(cursor.color);
+            for &(y, x0, x1) in spans {
+                if let Some(line) = agent_cells_by_line.get_mut(y) {
+                    for cell in line.iter_mut().take(x1 + 1).skip(x0) {
+                        *cell = Some(color);
+                    }
+                }
+            }
+        }
+        // `:diff` colors, under the focus, selection and agent cursors.
+        let mut diff_cells_by_line = self
+            .base_diagram
+            .split('\n')
+            .map(|line| vec![None::<Color>; line.chars().count()])
+            .collect::<Vec<_>>();
+        let diff_view = self
+            .diff_view
+            .as_ref()
+            .filter(|view| Some(view.saved.diagram_id()) == self.active_diagram_id());
+        for (object_ref, color) in diff_view.map(DiffView::colored_refs).unwrap_or_default() {
+            let Some(spans) = self.base_highlight_index.get(object_ref) else {
+                continue;
+            };
+            for &(y, x0, x1) in spans {
+                if let Some(line) = diff_cells_by_line.get_mut(y) {
+                    for cell in line.iter_mut().take(x1 + 1).skip(x0) {
+                        *cell = Some(color);
+                    }
+                }
+            }
+        }
         let has_active_selection_in_diagram = has_selected_objects_in_diagram;
 
         let mut out = Text::default();
@@ -1013,6 +2638,31 @@ impl App {
             let mut chars = line.chars().collect::<Vec<_>>();
             let mut flags = flags_by_line.get(y).cloned().unwrap_or_default();
             let mut style_overrides = vec![None::<Style>; chars.len()];
+            let agent_cells = agent_cells_by_line.get(y).map(Vec::as_slice).unwrap_or_default();
+            for ((style, color), flag) in style_overrides.iter_mut().zip(agent_cells).zip(&flags) {
+                // The human's focus and selection stay visible over agent cursors.
+                if let Some(color) = color.filter(|_| flag & 0b101 == 0) {
+                    *style = Some(
+                        Style::default()
+                            .add_modifier(Modifier::BOLD)
+                            .fg(theme().selection_fg)
+                            .bg(color),
+                    );
+                }
+            }
+            let diff_cells = diff_cells_by_line.get(y).map(Vec::as_slice).unwrap_or_default();
+            for ((style, color), flag) in style_overrides.iter_mut().zip(diff_cells).zip(&flags) {
+                if let Some(color) = color.filter(|_| style.is_none() && *flag == 0) {
+                    *style = Some(Style::default().fg(color));
+                }
+            }
+            let dim_cells = dim_cells_by_line.get(y).map(Vec::as_slice).unwrap_or_default();
+            let heavy_cells = heavy_cells_by_line.get(y).map(Vec::as_slice).unwrap_or_default();
+            for (ch, heavy) in chars.iter_mut().zip(heavy_cells) {
+                if *heavy {
+                    *ch = heavy_box_char(*ch);
+                }
+            }
 
             if !hint_targets.is_empty() {
                 for target in hint_targets.iter().filter(|target| target.y == y) {
@@ -1026,6 +2676,20 @@ impl App {
                 }
             }
 
+            if let Some(visual) = self.visual_selection {
+                let ((row0, row1), (col0, col1)) = visual.bounds();
+                if (row0..=row1).contains(&y) {
+                    if chars.len() <= col1 {
+                        chars.resize(col1 + 1, ' ');
+                        style_overrides.resize(col1 + 1, None);
+                    }
+                    let visual_style = Style::default().fg(theme().selection_fg).bg(theme().focus);
+                    for style in &mut style_overrides[col0..=col1] {
+                        *style = Some(visual_style);
+                    }
+                }
+            }
+
             let mut line_spans = Vec::<Span<'static>>::new();
             if chars.is_empty() {
                 line_spans.push(Span::raw(String::new()));
@@ -1056,6 +2720,11 @@ impl App {
                         )
                     });
                 current_style = style_for_diagram_char(current_style, chars[0]);
+                current_style = style_for_rule_cell(
+                    current_style,
+                    dim_cells.first().copied().unwrap_or(false),
+                    heavy_cells.first().copied().unwrap_or(false),
+                );
                 let mut buf = String::new();
 
                 for (idx, ch) in chars.iter().enumerate() {
@@ -1084,7 +2753,11 @@ impl App {
                                 sequence_area_bg,
                             )
                         });
-                    let style = style_for_diagram_char(base_style, *ch);
+                    let style = style_for_rule_cell(
+                        style_for_diagram_char(base_style, *ch),
+                        dim_cells.get(idx).copied().unwrap_or(false),
+                        heavy_cells.get(idx).copied().unwrap_or(false),
+                    );
                     if style != current_style {
                         if !buf.is_empty() {
                             line_spans.push(Span::styled(buf, current_style));
@@ -1110,12 +2783,27 @@ impl App {
 
     fn recompute_visible_object_indices(&mut self) {
         self.visible_object_indices.clear();
+        let tagged = self.objects_tag_filter.as_deref().map(|tag| {
+            self.active_diagram_id()
+                .and_then(|id| self.session.diagrams().get(id))
+                .map(|diagram| {
+                    object_facts(&self.session, diagram)
+                        .into_iter()
+                        .filter(|facts| facts.tags.iter().any(|candidate| candidate == tag))
+                        .map(|facts| facts.object_ref)
+                        .collect::<BTreeSet<_>>()
+                })
+                .unwrap_or_default()
+        });
         for (idx, obj) in self.objects.iter().enumerate() {
             if self.objects_selected_only
                 && !self.session.selected_object_refs().contains(&obj.object_ref)
             {
                 continue;
             }
+            if tagged.as_ref().is_some_and(|tagged| !tagged.contains(&obj.object_ref)) {
+                continue;
+            }
             self.visible_object_indices.push(idx);
         }
     }
@@ -1211,12 +2899,37 @@ impl App {
 
     fn toggle_inspector_visible(&mut self) {
         self.inspector_visible = !self.inspector_visible;
-        self.set_toast(if self.inspector_visible { "Inspector shown" } else { "Inspector hidden" });
+        self.set_toast(if self.inspector_visible {
+            tr("toast.inspector_shown")
+        } else {
+            tr("toast.inspector_hidden")
+        });
     }
 
     fn toggle_palette_visible(&mut self) {
         self.palette_visible = !self.palette_visible;
-        self.set_toast(if self.palette_visible { "Palette shown" } else { "Palette hidden" });
+        self.set_toast(if self.palette_visible {
+            tr("toast.palette_shown")
+        } else {
+            tr("toast.palette_hidden")
+        });
+    }
+
+    fn open_peek(&mut self) {
+        if self.selected_object().is_none() {
+            self.set_toast(tr("toast.no_object_selected"));
+            return;
+        }
+        self.peek_visible = true;
+    }
+
+    fn toggle_ruler_visible(&mut self) {
+        self.ruler_visible = !self.ruler_visible;
+        self.set_toast(if self.ruler_visible {
+            tr("toast.ruler_shown")
+        } else {
+            tr("toast.ruler_hidden")
+        });
     }
 
     fn panel_is_visible(&self, focus: Focus) -> bool {
@@ -1224,6 +2937,9 @@ impl App {
             Focus::Diagram => true,
             Focus::Objects => self.objects_visible,
             Focus::XRefs => self.xrefs_visible,
+            Focus::Relations => self.relations_visible,
+            Focus::Recent => self.recent_visible,
+            Focus::Timeline => self.timeline_visible,
         }
     }
 
@@ -1235,43 +2951,64 @@ impl App {
             Focus::Objects
         } else if self.xrefs_visible {
             Focus::XRefs
+        } else if self.relations_visible {
+            Focus::Relations
+        } else if self.recent_visible {
+            Focus::Recent
+        } else if self.timeline_visible {
+            Focus::Timeline
         } else {
             Focus::Diagram
         };
     }
 
+    /// Moves focus to the next visible panel; a split's panes count as two, left then right.
     fn cycle_focus_visible(&mut self) {
+        if self.focus == Focus::Diagram && self.split.as_ref().is_some_and(|pane| !pane.on_left) {
+            self.focus_other_pane();
+            return;
+        }
         let mut next = self.focus;
-        for _ in 0..3 {
+        for _ in 0..6 {
             next = next.cycle();
             if self.panel_is_visible(next) {
-                self.focus = next;
-                return;
+                break;
             }
         }
-        self.focus = Focus::Diagram;
+        if next == Focus::Diagram {
+            self.focus_pane_on_side(true);
+        } else {
+            self.focus = next;
+        }
     }
 
     fn cycle_focus_visible_back(&mut self) {
+        if self.focus == Focus::Diagram && self.split.as_ref().is_some_and(|pane| pane.on_left) {
+            self.focus_other_pane();
+            return;
+        }
         let mut next = self.focus;
-        for _ in 0..3 {
+        for _ in 0..6 {
             next = next.cycle_back();
             if self.panel_is_visible(next) {
-                self.focus = next;
-                return;
+                break;
             }
         }
-        self.focus = Focus::Diagram;
+        if next == Focus::Diagram {
+            self.focus_pane_on_side(false);
+        } else {
+            self.focus = next;
+        }
     }
 
     fn toggle_objects_visible_and_focus(&mut self) {
         self.objects_visible = !self.objects_visible;
         if self.objects_visible {
             self.focus = Focus::Objects;
-            self.set_toast("Objects shown");
+            self.set_toast(tr("toast.objects_shown"));
         } else {
             self.ensure_focus_visible();
-            self.set_toast("Objects hidden");
+            self.set_toast(tr("toast.objects_hidden"));
         }
     }
 
@@ -1279,11 +3016,91 @@ impl App {
         self.xrefs_visible = !self.xrefs_visible;
         if self.xrefs_visible {
             self.focus = Focus::XRefs;
-            self.set_toast("XRefs shown");
+            self.set_toast(tr("toast.xrefs_shown"));
+        } else {
+            self.ensure_focus_visible();
+            self.set_toast(tr("toast.xrefs_hidden"));
+        }
+    }
+
+    fn toggle_relations_visible_and_focus(&mut self) {
+        self.relations_visible = !self.relations_visible;
+        if self.relations_visible {
+            self.focus = Focus::Relations;
+            self.set_toast(tr("toast.relations_shown"));
+        } else {
+            self.ensure_focus_visible();
+            self.set_toast(tr("toast.relations_hidden"));
+        }
+    }
+
+    fn toggle_timeline_visible_and_focus(&mut self) {
+        self.timeline_visible = !self.timeline_visible;
+        if self.timeline_visible {
+            self.focus = Focus::Timeline;
+            self.timeline_state.select(Some(0));
+            self.set_toast(tr("toast.timeline_shown"));
+        } else {
+            self.ensure_focus_visible();
+            self.set_toast(tr("toast.timeline_hidden"));
+        }
+    }
+
+    fn toggle_recent_visible_and_focus(&mut self) {
+        self.recent_visible = !self.recent_visible;
+        if self.recent_visible {
+            self.focus = Focus::Recent;
+            self.recent_state.select(Some(0));
+            self.set_toast(tr("toast.recent_shown"));
         } else {
             self.ensure_focus_visible();
-            self.set_toast("XRefs hidden");
+            self.set_toast(tr("toast.recent_hidden"));
+        }
+    }
+
+    /// Moves the focused object to the front of `recent_objects`.
+    fn record_recent_object(&mut self) {
+        let Some(obj) = self.selected_object() else {
+            return;
+        };
+        if self.recent_objects.front().is_some_and(|recent| recent.object_ref == obj.object_ref) {
+            return;
         }
+        let recent = RecentObject { object_ref: obj.object_ref.clone(), label: obj.label.clone() };
+        let actor = match self.focus_owner {
+            FocusOwner::Human => Actor::Human,
+            FocusOwner::Agent => Actor::Agent,
+        };
+        let activity = Activity::Selected { object_ref: recent.object_ref.clone() };
+        self.activity.push(SystemTime::now(), actor, activity);
+        self.recent_objects.retain(|entry| entry.object_ref != recent.object_ref);
+        self.recent_objects.push_front(recent);
+        self.recent_objects.truncate(RECENT_OBJECTS_LIMIT);
+    }
+
+    /// Jumps to the most recent object other than the focused one, like an editor's alternate
+    /// buffer; pressing it again swaps back.
+    fn switch_to_alternate_object(&mut self) {
+        let current = self.selected_ref().cloned();
+        let Some(target) = self
+            .recent_objects
+            .iter()
+            .map(|recent| &recent.object_ref)
+            .find(|object_ref| {
+                Some(*object_ref) != current.as_ref() && self.object_exists_in_session(object_ref)
+            })
+            .cloned()
+        else {
+            self.set_toast(tr("toast.no_previous_object"));
+            return;
+        };
+        self.jump_to_object_ref(&target);
+    }
+
+    fn relation_rows(&self) -> Vec<RelationRow> {
+        self.selected_ref()
+            .map(|object_ref| object_relation_rows(&self.session, object_ref))
+            .unwrap_or_default()
     }
 
     fn object_index_for_ref(&self, object_ref: &ObjectRef) -> Option<usize> {
@@ -1295,7 +3112,7 @@ impl App {
     }
 
     fn handle_key(&mut self, key: KeyEvent) {
-        if self.handle_key_code(key.code) {
+        if self.handle_key_press(KeyPress::from(key)) {
             self.should_quit = true;
         }
     }
@@ -1317,7 +3134,11 @@ impl App {
         if self.follow_ai {
             self.follow_agent_highlight();
         }
-        self.set_toast(if self.follow_ai { "Follow AI enabled" } else { "Follow AI disabled" });
+        self.set_toast(if self.follow_ai {
+            tr("toast.follow_ai_enabled")
+        } else {
+            tr("toast.follow_ai_disabled")
+        });
     }
 
     fn take_external_action(&mut self) -> Option<ExternalAction> {
@@ -1325,13 +3146,74 @@ impl App {
     }
 
     fn queue_edit_active_diagram(&mut self) {
-        self.pending_external_action = Some(ExternalAction::EditActiveDiagram);
+        if self.ensure_active_diagram_writable() {
+            self.pending_external_action = Some(ExternalAction::EditActiveDiagram);
+        }
+    }
+
+    fn queue_edit_active_diagram_description(&mut self) {
+        if self.ensure_active_diagram_writable() {
+            self.pending_external_action = Some(ExternalAction::EditActiveDiagramDescription);
+        }
     }
 
     fn execute_external_action(&mut self, action: ExternalAction) -> Result<(), String> {
         match action {
             ExternalAction::EditActiveDiagram => self.edit_active_diagram_in_editor(),
+            ExternalAction::EditActiveDiagramDescription => {
+                self.edit_active_diagram_description_in_editor()
+            }
+        }
+    }
+
+    fn edit_active_diagram_description_in_editor(&mut self) -> Result<(), String> {
+        let Some(diagram_id) = self.active_diagram_id().cloned() else {
+            return Err("no active diagram".to_owned());
+        };
+        let Some(diagram) = self.session.diagrams().get(&diagram_id) else {
+            return Err(format!("diagram not found: {diagram_id}"));
+        };
+
+        let original = diagram.description().unwrap_or_default().to_owned();
+        let temp_path = write_temp_editor_file(&diagram_id, "md", &original)?;
+        let editor_command = resolve_editor_command();
+
+        let launch_result = launch_editor_command(&editor_command, &temp_path);
+        let edited = fs::read_to_string(&temp_path).map_err(|err| {
+            format!("failed reading edited description from {}: {err}", temp_path.display())
+        });
+        let _ = fs::remove_file(&temp_path);
+
+        launch_result?;
+        let edited = edited?;
+        if edited.trim() == original.trim() {
+            self.set_toast(trf("toast.description_unchanged", &[("diagram_id", &diagram_id)]));
+            return Ok(());
         }
+
+        self.apply_diagram_description(&diagram_id, &edited)
+    }
+
+    fn apply_diagram_description(
+        &mut self,
+        diagram_id: &DiagramId,
+        description: &str,
+    ) -> Result<(), String> {
+        let Some(diagram) = self.session.diagrams_mut().get_mut(diagram_id) else {
+            return Err(format!("diagram not found: {diagram_id}"));
+        };
+        diagram.set_description(Some(description));
+        self.log_session_activity(Actor::Human);
+
+        let Some(session_folder) = self.session_folder.as_ref() else {
+            self.set_toast(trf("toast.description_updated", &[("diagram_id", &diagram_id)]));
+            return Ok(());
+        };
+        session_folder
+            .save_diagram_description(&self.session, diagram_id)
+            .map_err(|err| format!("Description save failed: {err}"))?;
+        self.set_toast(trf("toast.description_saved", &[("diagram_id", &diagram_id)]));
+        Ok(())
     }
 
     fn edit_active_diagram_in_editor(&mut self) -> Result<(), String> {
@@ -1343,7 +3225,7 @@ impl App {
         };
 
         let original_mermaid = export_diagram_mermaid(&diagram)?;
-        let temp_path = write_temp_mermaid_file(&diagram_id, &original_mermaid)?;
+        let temp_path = write_temp_editor_file(&diagram_id, "mmd", &original_mermaid)?;
         let editor_command = resolve_editor_command();
 
         let launch_result = launch_editor_command(&editor_command, &temp_path);
@@ -1356,7 +3238,7 @@ impl App {
         let edited_mermaid = edited_mermaid?;
 
         if edited_mermaid == original_mermaid {
-            self.set_toast(format!("Edit cancelled (no changes): {diagram_id}"));
+            self.set_toast(trf("toast.edit_cancelled_no_changes", &[("diagram_id", &diagram_id)]));
             return Ok(());
         }
 
@@ -1375,25 +3257,81 @@ impl App {
         baseline_rev: u64,
         mermaid: &str,
     ) -> Result<(), String> {
-        let parsed_ast = parse_mermaid_for_kind(diagram_kind, mermaid)?;
+        let parsed_ast =
+            parse_mermaid_for_kind(diagram_kind, mermaid, self.session.id_prefix_rules())?;
         let Some(current_diagram) = self.session.diagrams().get(diagram_id) else {
             return Err(format!("diagram not found: {diagram_id}"));
         };
         if current_diagram.ast() == &parsed_ast {
-            self.set_toast(format!("No structural changes: {diagram_id}"));
+            self.set_toast(trf("toast.no_structural_changes", &[("diagram_id", &diagram_id)]));
             return Ok(());
         }
 
-        {
-            let Some(diagram) = self.session.diagrams_mut().get_mut(diagram_id) else {
-                return Err(format!("diagram not found: {diagram_id}"));
-            };
-            diagram
-                .set_ast(parsed_ast)
-                .map_err(|err| format!("failed applying edited Mermaid: {err}"))?;
-            diagram.bump_rev();
+        let Some(diagram) = self.session.diagrams_mut().get_mut(diagram_id) else {
+            return Err(format!("diagram not found: {diagram_id}"));
+        };
+        let before = diagram.ast().clone();
+        diagram
+            .set_ast(parsed_ast)
+            .map_err(|err| format!("failed applying edited Mermaid: {err}"))?;
+        diagram.bump_rev();
+        self.history.record_change(&before, diagram);
+
+        self.finish_diagram_change(diagram_id, baseline_rev, &before, "Edited");
+        Ok(())
+    }
+
+    fn undo_diagram_change(&mut self) {
+        let before = self.history_target_ast(self.history.peek_undo());
+        match self.history.undo(&mut self.session) {
+            Ok(step) => self.finish_history_step(step, before.as_ref(), "Undid"),
+            Err(HistoryError::Empty) => self.set_toast(tr("toast.nothing_to_undo")),
+            Err(err) => self.set_toast(trf("toast.undo_failed", &[("err", &err)])),
+        }
+    }
+
+    fn redo_diagram_change(&mut self) {
+        let before = self.history_target_ast(self.history.peek_redo());
+        match self.history.redo(&mut self.session) {
+            Ok(step) => self.finish_history_step(step, before.as_ref(), "Redid"),
+            Err(HistoryError::Empty) => self.set_toast(tr("toast.nothing_to_redo")),
+            Err(err) => self.set_toast(trf("toast.redo_failed", &[("err", &err)])),
+        }
+    }
+
+    /// The current AST of the diagram `entry` would change, as the merge base for its sync.
+    fn history_target_ast(&self, entry: Option<&HistoryEntry>) -> Option<DiagramAst> {
+        let diagram = self.session.diagrams().get(entry?.diagram_id())?;
+        Some(diagram.ast().clone())
+    }
+
+    fn finish_history_step(&mut self, step: HistoryStep, before: Option<&DiagramAst>, verb: &str) {
+        if self.session.active_diagram_id() != Some(&step.diagram_id) {
+            self.set_active_diagram_id(step.diagram_id.clone());
         }
+        // Keep the older disk baseline when this diagram already has an unsynced change.
+        let baseline_rev = match &self.pending_diagram_sync {
+            Some(pending) if pending.diagram_id == step.diagram_id => pending.expected_disk_rev,
+            _ => step.base_rev,
+        };
+        let Some(before) = before else {
+            return;
+        };
+        self.finish_diagram_change(&step.diagram_id, baseline_rev, before, verb);
+    }
 
+    /// Refreshes derived state after `diagram_id` changed in place from `before` and queues the
+    /// disk sync.
+    fn finish_diagram_change(
+        &mut self,
+        diagram_id: &DiagramId,
+        baseline_rev: u64,
+        before: &DiagramAst,
+        verb: &str,
+    ) {
+        self.log_session_activity(Actor::Human);
+        self.time_travel = None;
+        self.proposal_preview = None;
         self.retain_existing_selected_refs();
         self.refresh_xref_statuses();
         self.xrefs = xrefs_from_session(&self.session);
@@ -1407,19 +3345,62 @@ impl App {
             .map(|diagram| diagram.rev())
             .unwrap_or(baseline_rev);
 
-        if self.session_folder.is_some() {
+        if let Some(session_folder) = self.session_folder.as_ref() {
+            // Journal before the debounced sync so a crash in between can be recovered; every
+            // TUI change lands as a single revision step.
+            let ops = self
+                .session
+                .diagrams()
+                .get(diagram_id)
+                .and_then(|diagram| diff_ops(before, diagram.ast()))
+                .filter(|ops| !ops.is_empty());
+            let journal_result = ops.map_or(Ok(()), |ops| {
+                session_folder.append_ops_journal(&JournalEntry::new(
+                    JournalActor::Human,
+                    diagram_id.clone(),
+                    new_rev.saturating_sub(1),
+                    new_rev,
+                    ops,
+                ))
+            });
+            let (disk_fingerprint, base_ast) = match self.pending_diagram_sync.take() {
+                Some(pending) if &pending.diagram_id == diagram_id => {
+                    (pending.disk_fingerprint, pending.base_ast)
+                }
+                _ => (
+                    session_folder.diagram_mmd_fingerprint(diagram_id).ok().flatten(),
+                    before.clone(),
+                ),
+            };
             self.pending_diagram_sync = Some(PendingDiagramSync {
                 diagram_id: diagram_id.clone(),
                 expected_disk_rev: baseline_rev,
+                disk_fingerprint,
+                base_ast,
             });
-            self.set_toast(format!(
-                "Edited {diagram_id} (rev {baseline_rev}->{new_rev}); sync pending"
+            self.set_toast(trf(
+                "toast.diagram_changed_sync_pending",
+                &[
+                    ("verb", &verb),
+                    ("diagram_id", &diagram_id),
+                    ("baseline_rev", &baseline_rev),
+                    ("new_rev", &new_rev),
+                ],
             ));
+            if let Err(err) = journal_result {
+                self.set_toast(trf("toast.ops_journal_failed", &[("err", &err)]));
+            }
         } else {
-            self.set_toast(format!("Edited {diagram_id} (rev {baseline_rev}->{new_rev})"));
+            self.set_toast(trf(
+                "toast.diagram_changed",
+                &[
+                    ("verb", &verb),
+                    ("diagram_id", &diagram_id),
+                    ("baseline_rev", &baseline_rev),
+                    ("new_rev", &new_rev),
+                ],
+            ));
         }
-
-        Ok(())
     }
 
     fn flush_pending_diagram_sync(&mut self) {
@@ -1429,10 +3410,38 @@ impl App {
         let Some(session_folder) = self.session_folder.clone() else {
             return;
         };
+        self.sync_pending_diagram(&session_folder, pending, None);
+    }
 
-        match self.persist_pending_diagram_sync(&session_folder, &pending) {
-            Ok(()) => {
-                self.set_toast(format!("Synced edited diagram: {}", pending.diagram_id));
+    /// Writes `pending` to disk, merging it with concurrent disk edits; conflicts resolved by
+    /// neither `prefer` nor the merge itself open the merge prompt.
+    fn sync_pending_diagram(
+        &mut self,
+        session_folder: &SessionFolder,
+        pending: PendingDiagramSync,
+        prefer: Option<MergeSide>,
+    ) {
+        match self.persist_pending_diagram_sync(session_folder, &pending, prefer) {
+            Ok(DiagramSyncOutcome::Saved) => {
+                self.set_toast(trf(
+                    "toast.synced_edited_diagram",
+                    &[("diagram_id", &pending.diagram_id)],
+                ));
+            }
+            Ok(DiagramSyncOutcome::Merged(diagram)) => {
+                self.session.diagrams_mut().insert(pending.diagram_id.clone(), *diagram);
+                self.retain_existing_selected_refs();
+                self.refresh_xref_statuses();
+                self.xrefs = xrefs_from_session(&self.session);
+                self.apply_xref_filters();
+                self.refresh_active_diagram_view();
+                self.set_toast(trf(
+                    "toast.merged_disk_changes_into",
+                    &[("diagram_id", &pending.diagram_id)],
+                ));
+            }
+            Ok(DiagramSyncOutcome::Conflicts(conflicts)) => {
+                self.merge_prompt = Some(MergePrompt { pending, conflicts });
             }
             Err(err) => {
                 self.set_toast(err);
@@ -1444,7 +3453,8 @@ impl App {
         &self,
         session_folder: &SessionFolder,
         pending: &PendingDiagramSync,
-    ) -> Result<(), String> {
+        prefer: Option<MergeSide>,
+    ) -> Result<DiagramSyncOutcome, String> {
         let Some(local_diagram) = self.session.diagrams().get(&pending.diagram_id).cloned() else {
             return Err(format!(
                 "sync skipped: edited diagram no longer exists: {}",
@@ -1458,19 +3468,61 @@ impl App {
             return Err(format!("sync conflict: diagram removed on disk: {}", pending.diagram_id));
         };
 
-        if disk_diagram.rev() != pending.expected_disk_rev {
-            return Err(format!(
-                "sync conflict for {}: disk rev {} != expected {}",
-                pending.diagram_id,
-                disk_diagram.rev(),
-                pending.expected_disk_rev
-            ));
-        }
-
-        disk_session.diagrams_mut().insert(pending.diagram_id.clone(), local_diagram);
+        let edited_on_disk = disk_diagram.rev() != pending.expected_disk_rev
+            || (pending.disk_fingerprint.is_some()
+                && session_folder.diagram_mmd_fingerprint(&pending.diagram_id).ok().flatten()
+                    != pending.disk_fingerprint);
+        if !edited_on_disk {
+            disk_session.diagrams_mut().insert(pending.diagram_id.clone(), local_diagram);
+            session_folder
+                .save_session(&disk_session)
+                .map_err(|err| format!("sync failed (save): {err}"))?;
+            return Ok(DiagramSyncOutcome::Saved);
+        }
+
+        let merged_ast = match merge_diagram_asts(
+            &pending.base_ast,
+            local_diagram.ast(),
+            disk_diagram.ast(),
+            prefer,
+        ) {
+            Ok(merged_ast) => merged_ast,
+            Err(conflicts) => return Ok(DiagramSyncOutcome::Conflicts(conflicts)),
+        };
+        let merged_rev = local_diagram.rev().max(disk_diagram.rev()) + 1;
+        let mut merged = local_diagram;
+        merged
+            .set_ast(merged_ast)
+            .map_err(|err| format!("sync conflict for {}: {err}", pending.diagram_id))?;
+        merged.set_rev(merged_rev);
+        disk_session.diagrams_mut().insert(pending.diagram_id.clone(), merged.clone());
         session_folder
             .save_session(&disk_session)
-            .map_err(|err| format!("sync failed (save): {err}"))
+            .map_err(|err| format!("sync failed (save): {err}"))?;
+        Ok(DiagramSyncOutcome::Merged(Box::new(merged)))
+    }
+
+    /// Settles the merge prompt: `Some(side)` saves the merge with that side winning conflicts,
+    /// `None` drops the local edit and reloads the disk version.
+    fn resolve_merge_prompt(&mut self, prefer: Option<MergeSide>) {
+        let Some(MergePrompt { pending, .. }) = self.merge_prompt.take() else {
+            return;
+        };
+        let Some(session_folder) = self.session_folder.clone() else {
+            return;
+        };
+        if prefer.is_some() {
+            self.sync_pending_diagram(&session_folder, pending, prefer);
+            return;
+        }
+        self.external_change_pending = false;
+        match self.sync_session_from_disk() {
+            Ok(()) => self.set_toast(trf(
+                "toast.sync_conflict_kept_disk",
+                &[("diagram_id", &pending.diagram_id)],
+            )),
+            Err(err) => self.set_toast(err),
+        }
     }
 
     fn retain_existing_selected_refs(&mut self) {
@@ -1517,7 +3569,20 @@ impl App {
         self.help_scroll_by(direction.signum() * step);
     }
 
+    #[cfg(test)]
     fn handle_key_code(&mut self, code: KeyCode) -> bool {
+        self.handle_key_press(KeyPress::from(code))
+    }
+
+    fn handle_key_press(&mut self, press: KeyPress) -> bool {
+        let quit = self.dispatch_key_press(press);
+        self.record_recent_object();
+        quit
+    }
+
+    /// Prompts, forms and overlays see the bare key code; the keymap sees the modifiers too.
+    fn dispatch_key_press(&mut self, press: KeyPress) -> bool {
+        let code = press.code;
         self.focus_owner = FocusOwner::Human;
 
         if self.show_help {
@@ -1537,74 +3602,278 @@ impl App {
             return false;
         }
 
-        match self.search_mode {
-            SearchMode::Editing => {
-                self.handle_search_edit_key(code);
-                return false;
-            }
-            SearchMode::Results => {
-                if matches!(code, KeyCode::Esc) {
-                    self.clear_search();
-                    return false;
-                }
-            }
-            SearchMode::Inactive => {}
+        if self.command_line.is_some() {
+            self.handle_command_edit_key(code);
+            return false;
         }
 
-        if !matches!(code, KeyCode::Char('?') | KeyCode::Char('/') | KeyCode::Char('\\'))
-            && matches!(self.focus, Focus::Diagram | Focus::Objects)
-            && self.handle_diagram_hint_key(code)
-        {
+        if self.session_form.is_some() {
+            self.handle_session_form_key(code);
             return false;
         }
 
-        match code {
-            KeyCode::Char('q') => return true,
-            KeyCode::Char('1') => self.focus = Focus::Diagram,
-            KeyCode::Char('2') => self.toggle_objects_visible_and_focus(),
-            KeyCode::Char('3') => self.toggle_xrefs_visible_and_focus(),
-            KeyCode::Char('4') => self.toggle_inspector_visible(),
-            KeyCode::Char('|') => self.toggle_palette_visible(),
-            KeyCode::Char('a') => self.toggle_follow_ai(),
-            KeyCode::Char('d') => self.deselect_current_diagram_objects(),
-            KeyCode::Char('/') => self.enter_search_mode(SearchKind::Regular),
-            KeyCode::Char('\\') => self.enter_search_mode(SearchKind::Fuzzy),
-            KeyCode::Char('?') => self.toggle_help(),
-            KeyCode::Char('n') => {
-                if self.search_mode == SearchMode::Inactive && self.focus == Focus::Diagram {
-                    self.toggle_show_notes();
-                } else {
-                    self.search_next();
-                }
-            }
-            KeyCode::Char('N') => self.search_prev(),
-            KeyCode::Tab => {
-                self.cycle_focus_visible();
+        if self.insert_form.is_some() {
+            self.handle_insert_form_key(code);
+            return false;
+        }
+
+        if self.merge_prompt.is_some() {
+            match code {
+                KeyCode::Char('l') => self.resolve_merge_prompt(Some(MergeSide::Local)),
+                KeyCode::Char('d') => self.resolve_merge_prompt(Some(MergeSide::Disk)),
+                KeyCode::Esc => self.resolve_merge_prompt(None),
+                _ => {}
             }
-            KeyCode::BackTab => {
-                self.cycle_focus_visible_back();
+            return false;
+        }
+
+        if self.tidy_prompt.is_some() {
+            match code {
+                KeyCode::Enter | KeyCode::Char('y') => self.apply_tidy_prompt(),
+                KeyCode::Esc | KeyCode::Char('n') => {
+                    self.tidy_prompt = None;
+                    self.set_toast(tr("toast.tidy_cancelled"));
+                }
+                _ => {}
             }
-            KeyCode::Char('[') => self.switch_diagram_prev(),
-            KeyCode::Char(']') => self.switch_diagram_next(),
+            return false;
+        }
 
-            _ => match self.focus {
-                Focus::Diagram => self.handle_diagram_key(code),
-                Focus::Objects => self.handle_objects_key(code),
-                Focus::XRefs => self.handle_xrefs_key(code),
-            },
+        if let Some(picker) = self.session_picker.as_mut() {
+            match code {
+                KeyCode::Down | KeyCode::Char('j') => {
+                    picker.index = (picker.index + 1).min(picker.sessions.len().saturating_sub(1));
+                }
+                KeyCode::Up | KeyCode::Char('k') => picker.index = picker.index.saturating_sub(1),
+                KeyCode::Enter => {
+                    let name =
+                        picker.sessions.get(picker.index).map(|session| session.name.clone());
+                    self.session_picker = None;
+                    if let Some(name) = name {
+                        self.open_workspace_session(&name);
+                    }
+                }
+                KeyCode::Esc | KeyCode::Char('q') => self.session_picker = None,
+                _ => {}
+            }
+            return false;
         }
 
-        false
-    }
+        if self.visual_selection.is_some() {
+            self.handle_visual_selection_key(code);
+            return false;
+        }
 
-    fn enter_search_mode(&mut self, kind: SearchKind) {
-        self.search_mode = SearchMode::Editing;
-        self.search_kind = kind;
-        self.search_query.clear();
-        self.search_result_index = 0;
-        self.search_results.clear();
-        self.search_candidates = search_candidates_from_session(&self.session);
-    }
+        if std::mem::take(&mut self.peek_visible)
+            && matches!(code, KeyCode::Esc | KeyCode::Char('p'))
+        {
+            return false;
+        }
+        if std::mem::take(&mut self.stats_visible)
+            && matches!(code, KeyCode::Esc | KeyCode::Char('i'))
+        {
+            return false;
+        }
+        if std::mem::take(&mut self.legend_visible)
+            && matches!(code, KeyCode::Esc | KeyCode::Char('m'))
+        {
+            return false;
+        }
+        if self.check_report.take().is_some() && code == KeyCode::Esc {
+            return false;
+        }
+
+        let mut keys = std::mem::take(&mut self.pending_keys);
+
+        match self.search_mode {
+            SearchMode::Editing => {
+                self.handle_search_edit_key(code);
+                return false;
+            }
+            SearchMode::Results => match code {
+                KeyCode::Esc => {
+                    self.clear_search();
+                    return false;
+                }
+                KeyCode::Enter => {
+                    self.accept_search_result();
+                    return false;
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.search_next();
+                    return false;
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.search_prev();
+                    return false;
+                }
+                _ => {}
+            },
+            SearchMode::Inactive => {}
+        }
+
+        if !matches!(
+            code,
+            KeyCode::Char('?') | KeyCode::Char('/') | KeyCode::Char('\\') | KeyCode::Char(':')
+        ) && matches!(self.focus, Focus::Diagram | Focus::Objects)
+            && self.handle_diagram_hint_key(code)
+        {
+            return false;
+        }
+
+        let keymap = Keymap::current();
+        let scope = self.focus.key_scope();
+        keys.push(press);
+        let mut lookup = keymap.lookup(scope, &keys);
+        if lookup == KeyLookup::Unbound && keys.len() > 1 {
+            // An abandoned sequence: the last key starts over on its own.
+            keys = vec![press];
+            lookup = keymap.lookup(scope, &keys);
+        }
+        match lookup {
+            KeyLookup::Action(action) => self.run_key_action(action),
+            KeyLookup::Pending => {
+                self.pending_keys = keys;
+                false
+            }
+            KeyLookup::Unbound => false,
+        }
+    }
+
+    /// Runs a bound action for the focused panel; `true` quits.
+    fn run_key_action(&mut self, action: Action) -> bool {
+        match action {
+            Action::Quit => return true,
+            Action::Help => self.toggle_help(),
+            Action::FocusDiagram => self.focus = Focus::Diagram,
+            Action::ToggleObjects => self.toggle_objects_visible_and_focus(),
+            Action::ToggleXRefs => self.toggle_xrefs_visible_and_focus(),
+            Action::ToggleInspector => self.toggle_inspector_visible(),
+            Action::ToggleRelations => self.toggle_relations_visible_and_focus(),
+            Action::ToggleRecent => self.toggle_recent_visible_and_focus(),
+            Action::ToggleTimeline => self.toggle_timeline_visible_and_focus(),
+            Action::FocusNext => self.cycle_focus_visible(),
+            Action::FocusPrev => self.cycle_focus_visible_back(),
+            Action::AlternateObject => self.switch_to_alternate_object(),
+            Action::Undo => self.undo_diagram_change(),
+            Action::Redo => self.redo_diagram_change(),
+            Action::WindowSplit => self.open_split_pane(),
+            Action::WindowFocusOther => self.focus_other_pane(),
+            Action::WindowFocusLeft => self.focus_pane_on_side(true),
+            Action::WindowFocusRight => self.focus_pane_on_side(false),
+            Action::WindowClose => self.close_focused_pane(),
+            Action::WindowCloseOther => self.close_other_pane(),
+            Action::TogglePalette => self.toggle_palette_visible(),
+            Action::FollowAi => self.toggle_follow_ai(),
+            Action::DeselectAll => self.deselect_current_diagram_objects(),
+            Action::Search => self.enter_search_mode(SearchKind::Regular),
+            Action::FuzzySearch => self.enter_search_mode(SearchKind::Fuzzy),
+            Action::Command => self.command_line = Some(String::new()),
+            Action::EditSession => self.open_session_form(),
+            Action::SearchNext => self.search_next(),
+            Action::SearchPrev => self.search_prev(),
+            Action::DiagramPrev => self.switch_diagram_prev(),
+            Action::DiagramNext => self.switch_diagram_next(),
+            Action::MoveDiagramEarlier => self.move_active_diagram(-1),
+            Action::MoveDiagramLater => self.move_active_diagram(1),
+
+            Action::HintJump => self.enter_diagram_hint_mode(),
+            Action::HintSelect => self.enter_diagram_select_hint_mode(),
+            Action::VisualSelect => self.enter_visual_selection(),
+            Action::EditDiagram => self.queue_edit_active_diagram(),
+            Action::EditDescription => self.queue_edit_active_diagram_description(),
+            Action::ToggleSelected => self.toggle_selected_object(),
+            Action::Yank => self.yank_selected_object_ref(),
+            Action::JumpIncoming => self.jump_to_selected_incoming_xref(),
+            Action::JumpOutgoing => self.jump_to_selected_outgoing_xref(),
+            Action::ToggleRuler => self.toggle_ruler_visible(),
+            Action::Peek => self.open_peek(),
+            Action::Insert => self.open_insert_form(),
+            Action::MoveParticipantLeft => self.move_selected_participant(-1),
+            Action::MoveParticipantRight => self.move_selected_participant(1),
+            Action::NudgeNodeUp => self.nudge_selected_flow_node((0, -1)),
+            Action::NudgeNodeDown => self.nudge_selected_flow_node((0, 1)),
+            Action::NudgeNodeLeft => self.nudge_selected_flow_node((-1, 0)),
+            Action::NudgeNodeRight => self.nudge_selected_flow_node((1, 0)),
+            Action::Stats => self.stats_visible = true,
+            Action::Legend => self.legend_visible = true,
+            Action::ZoomIn => self.set_zoom(self.zoom.zoom_in()),
+            Action::ZoomOut => self.set_zoom(self.zoom.zoom_out()),
+            Action::PanUp => self.pan_accelerated((0, -1), Instant::now()),
+            Action::PanDown => self.pan_accelerated((0, 1), Instant::now()),
+            Action::PanLeft => self.pan_accelerated((-1, 0), Instant::now()),
+            Action::PanRight => self.pan_accelerated((1, 0), Instant::now()),
+            Action::PanUpFar | Action::PageUp => self.pan_by(0, -10),
+            Action::PanDownFar | Action::PageDown => self.pan_by(0, 10),
+            Action::PanLeftFar => self.pan_by(-10, 0),
+            Action::PanRightFar => self.pan_by(10, 0),
+            Action::SnapStart => self.snap_pan_to_start(),
+            Action::SnapEnd => self.snap_pan_to_end(),
+            Action::ToggleNotes => {
+                // While results are listed the notes key steps through them instead.
+                if self.search_mode == SearchMode::Inactive {
+                    self.toggle_show_notes();
+                } else {
+                    self.search_next();
+                }
+            }
+            Action::Center => self.center_on_selected_object(),
+            Action::HighlightPath => self.highlight_path_between_selected_nodes(),
+
+            Action::CursorPrev => self.move_cursor(-1),
+            Action::CursorNext => self.move_cursor(1),
+            Action::CursorFirst => self.move_cursor(i32::MIN),
+            Action::CursorLast => self.move_cursor(i32::MAX),
+            Action::SelectedOnly => self.toggle_objects_selected_only(),
+            Action::MoveMessageEarlier => self.move_selected_seq_message(-1),
+            Action::MoveMessageLater => self.move_selected_seq_message(1),
+            Action::DanglingOnly => self.toggle_xrefs_dangling_only(),
+            Action::InvolvingOnly => self.toggle_xrefs_involving_only(),
+            Action::JumpFrom => self.jump_to_xref_from(),
+            Action::JumpTo => self.jump_to_xref_to(),
+            Action::Jump => match self.focus {
+                Focus::Relations => self.jump_to_selected_relation(),
+                Focus::Recent => self.jump_to_selected_recent(),
+                Focus::Timeline => self.travel_to_selected_activity(),
+                Focus::Diagram | Focus::Objects | Focus::XRefs => {}
+            },
+            Action::LeaveTimeTravel => self.leave_time_travel(),
+        }
+
+        false
+    }
+
+    /// Moves the cursor of the focused list panel by `delta`; `i32::MIN`/`i32::MAX` go to the
+    /// first/last row.
+    fn move_cursor(&mut self, delta: i32) {
+        match self.focus {
+            Focus::Objects => match delta {
+                i32::MIN => self.select_first(),
+                i32::MAX => self.select_last(),
+                delta if delta < 0 => self.select_prev(),
+                _ => self.select_next(),
+            },
+            Focus::XRefs => match delta {
+                i32::MIN => self.select_xref_first(),
+                i32::MAX => self.select_xref_last(),
+                delta if delta < 0 => self.select_xref_prev(),
+                _ => self.select_xref_next(),
+            },
+            Focus::Relations => self.select_relation_by(delta),
+            Focus::Recent => self.select_recent_by(delta),
+            Focus::Timeline => self.select_timeline_by(delta),
+            Focus::Diagram => {}
+        }
+    }
+
+    fn enter_search_mode(&mut self, kind: SearchKind) {
+        self.search_mode = SearchMode::Editing;
+        self.search_kind = kind;
+        self.search_query.clear();
+        self.search_result_index = 0;
+        self.search_results.clear();
+        self.search_candidates = search_candidates_from_session(&self.session);
+    }
 
     fn handle_search_edit_key(&mut self, code: KeyCode) {
         match code {
@@ -1622,12 +3891,1189 @@ impl App {
         }
     }
 
-    fn commit_search(&mut self) {
-        if self.search_results.len() > 1 {
-            self.search_mode = SearchMode::Results;
+    fn handle_command_edit_key(&mut self, code: KeyCode) {
+        let Some(command_line) = self.command_line.as_mut() else {
+            return;
+        };
+        match code {
+            KeyCode::Esc => self.command_line = None,
+            KeyCode::Enter => {
+                let input = self.command_line.take().unwrap_or_default();
+                self.run_command(&input);
+            }
+            KeyCode::Backspace => {
+                command_line.pop();
+            }
+            KeyCode::Char(ch) => command_line.push(ch),
+            _ => {}
+        }
+    }
+
+    fn open_session_form(&mut self) {
+        self.session_form = Some(SessionInfoForm::from_session(&self.session));
+    }
+
+    fn handle_session_form_key(&mut self, code: KeyCode) {
+        let Some(form) = self.session_form.as_mut() else {
+            return;
+        };
+        match code {
+            KeyCode::Esc => self.session_form = None,
+            KeyCode::Enter => {
+                if let Some(form) = self.session_form.take() {
+                    self.save_session_form(&form);
+                }
+            }
+            KeyCode::Tab | KeyCode::Down => {
+                form.field = (form.field + 1) % SESSION_INFO_FIELDS.len();
+            }
+            KeyCode::BackTab | KeyCode::Up => {
+                form.field =
+                    (form.field + SESSION_INFO_FIELDS.len() - 1) % SESSION_INFO_FIELDS.len();
+            }
+            KeyCode::Backspace => {
+                form.values[form.field].pop();
+            }
+            KeyCode::Char(ch) => form.values[form.field].push(ch),
+            _ => {}
+        }
+    }
+
+    fn open_insert_form(&mut self) {
+        if !self.ensure_active_diagram_writable() {
+            return;
+        }
+        let Some(diagram) = self.active_diagram_id().and_then(|id| self.session.diagrams().get(id))
+        else {
+            self.set_toast(tr("toast.no_active_diagram"));
+            return;
+        };
+        let sequence = match diagram.ast() {
+            DiagramAst::Flowchart(_) => false,
+            DiagramAst::Sequence(_) => true,
+            _ => {
+                self.set_toast(tr("toast.insert_needs_flow_or_sequence"));
+                return;
+            }
+        };
+        let values = if sequence {
+            Default::default()
         } else {
-            self.search_mode = SearchMode::Inactive;
+            // Start from the selected node so `o` on it adds a connected successor.
+            let from = self
+                .selected_ref()
+                .filter(|object_ref| {
+                    matches!(object_ref.category().segments(), [kind, object]
+                        if kind == "flow" && object == "node")
+                })
+                .map(|object_ref| object_ref.object_id().as_str().to_owned())
+                .unwrap_or_default();
+            [String::new(), "rect".to_owned(), from]
+        };
+        self.insert_form = Some(InsertForm {
+            diagram_id: diagram.diagram_id().clone(),
+            sequence,
+            field: 0,
+            values,
+        });
+    }
+
+    fn handle_insert_form_key(&mut self, code: KeyCode) {
+        let Some(form) = self.insert_form.as_mut() else {
+            return;
+        };
+        match code {
+            KeyCode::Esc => self.insert_form = None,
+            KeyCode::Enter => {
+                if let Some(form) = self.insert_form.take() {
+                    self.apply_insert_form(form);
+                }
+            }
+            KeyCode::Tab | KeyCode::Down => form.field = (form.field + 1) % form.values.len(),
+            KeyCode::BackTab | KeyCode::Up => {
+                form.field = (form.field + form.values.len() - 1) % form.values.len();
+            }
+            KeyCode::Backspace => {
+                form.values[form.field].pop();
+            }
+            KeyCode::Char(ch) => form.values[form.field].push(ch),
+            _ => {}
+        }
+    }
+
+    /// Adds the prompted object, records it for undo and selects it. Invalid input keeps the
+    /// prompt open.
+    fn apply_insert_form(&mut self, form: InsertForm) {
+        let diagram_id = form.diagram_id.clone();
+        let Some(diagram) = self.session.diagrams_mut().get_mut(&diagram_id) else {
+            self.set_toast(trf("toast.diagram_not_found", &[("diagram_id", &diagram_id)]));
+            return;
+        };
+        let rev = diagram.rev();
+        let before = diagram.ast().clone();
+        let applied =
+            form.ops(&before).and_then(|(ops, object_ref)| match apply_ops(diagram, rev, &ops) {
+                Ok(_) => Ok(object_ref),
+                Err(err) => Err(format!("Insert failed: {err}")),
+            });
+        let object_ref = match applied {
+            Ok(object_ref) => object_ref,
+            Err(err) => {
+                self.insert_form = Some(form);
+                self.set_toast(err);
+                return;
+            }
+        };
+        self.history.record_change(&before, diagram);
+        self.finish_diagram_change(&diagram_id, rev, &before, "Inserted into");
+        self.focus = Focus::Diagram;
+        self.select_object_ref(&object_ref);
+        self.center_viewport_on_object_ref(&object_ref);
+    }
+
+    fn save_session_form(&mut self, form: &SessionInfoForm) {
+        form.apply_to(&mut self.session);
+        let Some(session_folder) = self.session_folder.as_ref() else {
+            self.set_toast(tr("toast.session_info_updated"));
+            return;
+        };
+        match session_folder.save_session_info(&self.session) {
+            Ok(()) => self.set_toast(tr("toast.session_info_saved")),
+            Err(err) => self.set_toast(trf("toast.session_info_save_failed", &[("err", &err)])),
+        }
+    }
+
+    fn run_command(&mut self, input: &str) {
+        if input.trim().is_empty() {
+            return;
+        }
+        match parse_tui_command(input) {
+            Ok(TuiCommand::Goto { row, col }) => {
+                self.focus = Focus::Diagram;
+                self.center_viewport_on(row, col);
+                self.set_toast(trf("toast.centered_on", &[("row", &row), ("col", &col)]));
+            }
+            Ok(TuiCommand::Path { from, to }) => self.select_weighted_path(&from, &to),
+            Ok(TuiCommand::Critical) => self.select_critical_path(),
+            Ok(TuiCommand::Cuts) => self.select_cut_points(),
+            Ok(TuiCommand::Tidy) => self.open_tidy_prompt(),
+            Ok(TuiCommand::Matcher(matcher)) => self.set_fuzzy_matcher(matcher),
+            Ok(TuiCommand::Edges(glyphs)) => self.set_edge_glyphs(glyphs),
+            Ok(TuiCommand::A11y) => self.toggle_a11y_line(),
+            Ok(TuiCommand::Archive(archived)) => self.set_active_diagram_archived(archived),
+            Ok(TuiCommand::ReadOnly(read_only)) => self.set_active_diagram_read_only(read_only),
+            Ok(TuiCommand::Open(diagram_id)) => self.open_diagram(&diagram_id),
+            Ok(TuiCommand::ToSeq) => self.scaffold_sequence_from_selection(),
+            Ok(TuiCommand::ToFlow) => self.scaffold_flowchart_from_active(),
+            Ok(TuiCommand::StyleList) => self.list_style_rules(),
+            Ok(TuiCommand::StyleAdd(rule)) => {
+                let mut rules = self.session.style_rules().to_vec();
+                rules.push(rule);
+                self.set_style_rules(rules);
+            }
+            Ok(TuiCommand::StyleClear) => self.set_style_rules(Vec::new()),
+            Ok(TuiCommand::IdPrefixList) => self.list_id_prefix_rules(),
+            Ok(TuiCommand::IdPrefixAdd(rule)) => {
+                let mut rules = self.session.id_prefix_rules().to_vec();
+                rules.push(rule);
+                self.set_id_prefix_rules(rules);
+            }
+            Ok(TuiCommand::IdPrefixClear) => self.set_id_prefix_rules(Vec::new()),
+            Ok(TuiCommand::LintIds) => self.lint_ids(),
+            Ok(TuiCommand::Check) => self.check_session(),
+            Ok(TuiCommand::Sessions(None)) => self.open_session_picker(),
+            Ok(TuiCommand::Sessions(Some(name))) => self.open_workspace_session(&name),
+            Ok(TuiCommand::PasteNew) => self.paste_new_diagram(),
+            Ok(TuiCommand::Filter(tag)) => self.set_objects_tag_filter(tag),
+            Ok(TuiCommand::Proposal(proposal_id)) => self.toggle_proposal_preview(proposal_id),
+            Ok(TuiCommand::AcceptProposal) => self.accept_proposal(),
+            Ok(TuiCommand::DiscardProposal) => self.discard_proposal(),
+            Ok(TuiCommand::Note(Some(text))) => self.set_selected_object_note(Some(text)),
+            Ok(TuiCommand::Note(None)) => self.edit_selected_object_note(),
+            Ok(TuiCommand::Unnote) => self.set_selected_object_note(None),
+            Ok(TuiCommand::Diff(side)) => self.toggle_diff_view(side),
+            Ok(TuiCommand::Unpin(all)) => self.unpin_flow_nodes(all),
+            Err(err) => self.set_toast(err),
+        }
+    }
+
+    fn list_style_rules(&mut self) {
+        let rules = self.session.style_rules();
+        if rules.is_empty() {
+            self.set_toast(tr("toast.no_style_rules"));
+            return;
+        }
+        let rules = rules.iter().map(ToString::to_string).collect::<Vec<_>>();
+        self.set_toast(trf("toast.style_rules", &[("rules", &rules.join("; "))]));
+    }
+
+    /// Replaces the session styling rules, persists them and re-renders the active diagram.
+    fn set_style_rules(&mut self, rules: Vec<StyleRule>) {
+        self.session.set_style_rules(rules);
+        if let Some(session_folder) = self.session_folder.as_ref() {
+            if let Err(err) = session_folder.save_style_rules(&self.session) {
+                self.set_toast(trf("toast.style_rules_persist_failed", &[("err", &err)]));
+                return;
+            }
+        }
+        self.rerender_active_diagram_buffer();
+        self.list_style_rules();
+    }
+
+    fn list_id_prefix_rules(&mut self) {
+        let rules = self.session.id_prefix_rules();
+        if rules.is_empty() {
+            self.set_toast(tr("toast.no_id_prefix_rules"));
+            return;
+        }
+        let rules = rules.iter().map(ToString::to_string).collect::<Vec<_>>();
+        self.set_toast(trf("toast.id_prefix_rules", &[("rules", &rules.join("; "))]));
+    }
+
+    /// Replaces the session id prefix conventions and persists them.
+    fn set_id_prefix_rules(&mut self, rules: Vec<IdPrefixRule>) {
+        self.session.set_id_prefix_rules(rules);
+        if let Some(session_folder) = self.session_folder.as_ref() {
+            if let Err(err) = session_folder.save_id_prefix_rules(&self.session) {
+                self.set_toast(trf("toast.id_prefix_rules_persist_failed", &[("err", &err)]));
+                return;
+            }
+        }
+        self.list_id_prefix_rules();
+    }
+
+    /// Reports object ids that break the id prefix conventions.
+    fn lint_ids(&mut self) {
+        if self.session.id_prefix_rules().is_empty() {
+            self.set_toast(tr("toast.no_id_prefix_rules_to_lint"));
+            return;
+        }
+        let violations = id_prefix_violations(&self.session);
+        let Some(first) = violations.first() else {
+            self.set_toast(tr("toast.id_prefix_rules_followed"));
+            return;
+        };
+        self.set_toast(trf(
+            "toast.id_prefix_violations",
+            &[
+                ("count", &violations.len()),
+                ("object_ref", &first.object_ref),
+                ("rule", &first.rule),
+            ],
+        ));
+    }
+
+    /// Runs the `session.validate` checks; problems are listed in an overlay until a key is hit.
+    fn check_session(&mut self) {
+        let diagnostics = validate_session(&self.session);
+        if diagnostics.is_empty() {
+            self.check_report = None;
+            self.set_toast(tr("toast.session_check_passed"));
+            return;
+        }
+        let errors = diagnostics.iter().filter(|d| d.severity() == Severity::Error).count();
+        self.set_toast(trf(
+            "toast.session_check_problems",
+            &[("errors", &errors), ("warnings", &(diagnostics.len() - errors))],
+        ));
+        self.check_report = Some(diagnostics);
+    }
+
+    /// Name of the open session within the workspace, if started on one.
+    fn workspace_session_name(&self) -> Option<String> {
+        self.workspace.as_ref()?.session_name(self.session_folder.as_ref()?)
+    }
+
+    /// Lists the workspace sessions in a picker, with the open one preselected.
+    fn open_session_picker(&mut self) {
+        let Some(workspace) = self.workspace.as_ref() else {
+            self.set_toast(tr("toast.no_workspace"));
+            return;
+        };
+        let sessions = match workspace.list_sessions() {
+            Ok(sessions) if sessions.is_empty() => {
+                self.set_toast(tr("toast.workspace_empty"));
+                return;
+            }
+            Ok(sessions) => sessions,
+            Err(err) => {
+                self.set_toast(trf("toast.workspace_list_failed", &[("err", &err)]));
+                return;
+            }
+        };
+        let active = self.workspace_session_name();
+        let index =
+            sessions.iter().position(|session| Some(&session.name) == active.as_ref()).unwrap_or(0);
+        self.session_picker = Some(SessionPicker { sessions, index });
+    }
+
+    /// Replaces the open session with the workspace session `name`, after persisting pending
+    /// edits; view state tied to the previous session (split, history, timeline, search) is reset.
+    /// The MCP server follows through the shared UI state.
+    fn open_workspace_session(&mut self, name: &str) {
+        let Some(workspace) = self.workspace.clone() else {
+            self.set_toast(tr("toast.no_workspace"));
+            return;
+        };
+        self.flush_pending_diagram_sync();
+        if self.merge_prompt.is_some() {
+            self.set_toast(tr("toast.session_switch_blocked"));
+            return;
         }
+        let (folder, mut session, recovered) = match workspace.open_session(name) {
+            Ok(opened) => opened,
+            Err(err) => {
+                self.set_toast(trf("toast.session_open_failed", &[("name", &name), ("err", &err)]));
+                return;
+            }
+        };
+        ensure_active_diagram_id(&mut session);
+
+        self.split = None;
+        self.time_travel = None;
+        self.proposal_preview = None;
+        self.tidy_prompt = None;
+        self.check_report = None;
+        self.visual_selection = None;
+        self.clear_search();
+        self.renderer = IncrementalRenderer::default();
+        self.large_mode = None;
+        self.history = History::default();
+        self.recent_objects.clear();
+        self.activity = ActivityLog::new(&session);
+        self.session = session;
+        self.session_folder = Some(folder);
+        self.session_watcher = None;
+        self.external_change_pending = false;
+        self.watch_session_folder();
+
+        self.retain_existing_selected_refs();
+        self.refresh_xref_statuses();
+        self.xrefs = xrefs_from_session(&self.session);
+        self.apply_xref_filters();
+        self.refresh_active_diagram_view();
+
+        if let Some(ui_state) = self.ui_state.as_ref() {
+            let mut ui_state = ui_state.blocking_lock();
+            ui_state.set_workspace_session(name.to_owned());
+            self.ui_state_rev = ui_state.rev();
+        }
+        if recovered > 0 {
+            self.set_toast(trf(
+                "toast.session_opened_recovered",
+                &[("name", &name), ("count", &recovered)],
+            ));
+        } else {
+            self.set_toast(trf("toast.session_opened", &[("name", &name)]));
+        }
+    }
+
+    /// Archives or restores the active diagram; it stays open until the user switches away.
+    fn set_active_diagram_archived(&mut self, archived: bool) {
+        let Some(diagram_id) = self.active_diagram_id().cloned() else {
+            self.set_toast(tr("toast.no_active_diagram"));
+            return;
+        };
+        let Some(diagram) = self.session.diagrams_mut().get_mut(&diagram_id) else {
+            return;
+        };
+        if diagram.is_archived() == archived {
+            return;
+        }
+        diagram.set_archived(archived);
+        if let Some(session_folder) = self.session_folder.as_ref() {
+            if let Err(err) = session_folder.save_diagram_archived(&self.session, &diagram_id) {
+                self.set_toast(trf("toast.archive_persist_failed", &[("err", &err)]));
+                return;
+            }
+        }
+        let key = if archived { "toast.diagram_archived" } else { "toast.diagram_restored" };
+        self.set_toast(trf(key, &[("diagram_id", &diagram_id)]));
+    }
+
+    /// Locks or unlocks the active diagram against edits from the TUI and MCP.
+    fn set_active_diagram_read_only(&mut self, read_only: bool) {
+        let Some(diagram_id) = self.active_diagram_id().cloned() else {
+            self.set_toast(tr("toast.no_active_diagram"));
+            return;
+        };
+        let Some(diagram) = self.session.diagrams_mut().get_mut(&diagram_id) else {
+            return;
+        };
+        if diagram.is_read_only() == read_only {
+            return;
+        }
+        diagram.set_read_only(read_only);
+        if let Some(session_folder) = self.session_folder.as_ref() {
+            if let Err(err) = session_folder.save_diagram_read_only(&self.session, &diagram_id) {
+                self.set_toast(trf("toast.lock_persist_failed", &[("err", &err)]));
+                return;
+            }
+        }
+        let key = if read_only { "toast.diagram_locked" } else { "toast.diagram_unlocked" };
+        self.set_toast(trf(key, &[("diagram_id", &diagram_id)]));
+    }
+
+    /// Toasts and returns `false` when the active diagram is read-only or failed to load.
+    fn ensure_active_diagram_writable(&mut self) -> bool {
+        let Some(diagram) = self.active_diagram_id().and_then(|id| self.session.diagrams().get(id))
+        else {
+            return true;
+        };
+        let diagram_id = diagram.diagram_id().clone();
+        if diagram.is_read_only() {
+            self.set_toast(trf("toast.diagram_read_only", &[("diagram_id", &diagram_id)]));
+            return false;
+        }
+        diagram.ast();
+        let Some(err) = diagram.load_error().map(ToOwned::to_owned) else {
+            return true;
+        };
+        self.set_toast(trf(
+            "toast.diagram_load_failed",
+            &[("diagram_id", &diagram_id), ("err", &err)],
+        ));
+        false
+    }
+
+    /// Opens any diagram by id, including archived ones that `[`/`]` skip.
+    fn open_diagram(&mut self, diagram_id: &str) {
+        match DiagramId::new(diagram_id.to_owned()) {
+            Ok(diagram_id) if self.session.diagrams().contains_key(&diagram_id) => {
+                self.set_active_diagram_id(diagram_id);
+            }
+            _ => self.set_toast(trf("toast.unknown_diagram", &[("diagram_id", &diagram_id)])),
+        }
+    }
+
+    /// Scaffolds a sequence diagram from the selected edges of the active flowchart (e.g. after
+    /// `:path`) and opens it; every new object gets a `derived_from` xref to its source.
+    fn scaffold_sequence_from_selection(&mut self) {
+        let Some(flow) = self.active_diagram_id().and_then(|id| self.session.diagrams().get(id))
+        else {
+            self.set_toast(tr("toast.no_active_diagram"));
+            return;
+        };
+        let DiagramAst::Flowchart(ast) = flow.ast() else {
+            self.set_toast(tr("toast.toseq_needs_a_flowchart"));
+            return;
+        };
+        let edge_ids = self
+            .session
+            .selected_object_refs()
+            .iter()
+            .filter(|object_ref| {
+                object_ref.diagram_id() == flow.diagram_id() && is_flow_edge_ref(object_ref)
+            })
+            .map(|object_ref| object_ref.object_id().clone())
+            .collect::<BTreeSet<_>>();
+        let Some(path) = flow_path_from_edges(ast, &edge_ids) else {
+            self.set_toast(tr("toast.select_chain_first"));
+            return;
+        };
+        let (seq, links) = match sequence_from_flow_path(flow, &path) {
+            Ok(scaffold) => scaffold,
+            Err(err) => {
+                self.set_toast(trf("toast.scaffold_failed", &[("err", &err)]));
+                return;
+            }
+        };
+
+        let source_id = flow.diagram_id().clone();
+        if let Some(seq_id) =
+            self.insert_scaffold(&source_id, "seq", DiagramAst::Sequence(seq), &links)
+        {
+            self.set_toast(trf(
+                "toast.scaffolded_from_hops",
+                &[("seq_id", &seq_id), ("hops", &(path.len() - 1))],
+            ));
+        }
+    }
+
+    /// Scaffolds a participant interaction flowchart from the active sequence diagram and opens
+    /// it; edges are weighted by message count and xref'd to their messages.
+    fn scaffold_flowchart_from_active(&mut self) {
+        let Some(seq) = self.active_diagram_id().and_then(|id| self.session.diagrams().get(id))
+        else {
+            self.set_toast(tr("toast.no_active_diagram"));
+            return;
+        };
+        let Ok((flow, links)) = flowchart_from_sequence(seq) else {
+            self.set_toast(tr("toast.toflow_needs_a_sequence_diagram"));
+            return;
+        };
+        let source_id = seq.diagram_id().clone();
+        let edges = flow.edges().len();
+        if let Some(flow_id) =
+            self.insert_scaffold(&source_id, "flow", DiagramAst::Flowchart(flow), &links)
+        {
+            self.set_toast(trf(
+                "toast.scaffolded_with_interaction_edges",
+                &[("flow_id", &flow_id), ("edges", &edges)],
+            ));
+        }
+    }
+
+    /// Stores a scaffold of `source_id` as `<source_id>-<suffix>` together with its
+    /// `derived_from` xrefs, persists the session and opens the new diagram.
+    fn insert_scaffold(
+        &mut self,
+        source_id: &DiagramId,
+        suffix: &str,
+        ast: DiagramAst,
+        links: &[ScaffoldLink],
+    ) -> Option<DiagramId> {
+        let diagram_id = scaffold_diagram_id(&self.session, source_id, suffix);
+        let diagram = Diagram::new(diagram_id.clone(), diagram_id.as_str(), ast);
+        let mut candidate = self.session.clone();
+        let xrefs = scaffold_xrefs(&mut candidate, &diagram_id, links);
+        candidate.diagrams_mut().insert(diagram_id.clone(), diagram);
+        candidate.xrefs_mut().extend(xrefs);
+        if let Some(session_folder) = self.session_folder.as_ref() {
+            if let Err(err) = session_folder.save_session(&candidate) {
+                self.set_toast(trf("toast.scaffold_persist_failed", &[("err", &err)]));
+                return None;
+            }
+        }
+        self.session = candidate;
+        self.log_session_activity(Actor::Human);
+        self.xrefs = xrefs_from_session(&self.session);
+        self.apply_xref_filters();
+        self.set_active_diagram_id(diagram_id.clone());
+        Some(diagram_id)
+    }
+
+    fn paste_new_diagram(&mut self) {
+        match read_clipboard(&resolve_paste_commands()) {
+            Ok(mermaid) => self.create_diagram_from_mermaid(&mermaid),
+            Err(err) => self.set_toast(trf("toast.clipboard_error", &[("err", &err)])),
+        }
+    }
+
+    /// Parses `mermaid` into a new diagram with an id after its kind (`flow`, `seq-2`, ...),
+    /// persists the session and opens the diagram.
+    fn create_diagram_from_mermaid(&mut self, mermaid: &str) {
+        let Some(kind) = detect_diagram_kind(mermaid) else {
+            self.set_toast(tr("toast.paste_not_mermaid"));
+            return;
+        };
+        let ast = match parse_mermaid_for_kind(kind, mermaid, self.session.id_prefix_rules()) {
+            Ok(ast) => ast,
+            Err(err) => {
+                self.set_toast(trf("toast.paste_parse_failed", &[("err", &err)]));
+                return;
+            }
+        };
+        let diagram_id = idgen::diagram_id_for_kind(&self.session, kind);
+        let diagram = Diagram::new(diagram_id.clone(), diagram_id.as_str(), ast);
+        let mut candidate = self.session.clone();
+        candidate.diagrams_mut().insert(diagram_id.clone(), diagram);
+        if let Some(session_folder) = self.session_folder.as_ref() {
+            if let Err(err) = session_folder.save_session(&candidate) {
+                self.set_toast(trf("toast.paste_persist_failed", &[("err", &err)]));
+                return;
+            }
+        }
+        self.session = candidate;
+        self.log_session_activity(Actor::Human);
+        self.set_active_diagram_id(diagram_id.clone());
+        self.set_toast(trf("toast.pasted_new_diagram", &[("diagram_id", &diagram_id)]));
+    }
+
+    /// Previews a crossing-minimized, compacted layout of the active flowchart; nothing is
+    /// persisted until the prompt is confirmed.
+    fn open_tidy_prompt(&mut self) {
+        if !self.ensure_active_diagram_writable() {
+            return;
+        }
+        let Some(diagram) = self.active_diagram_id().and_then(|id| self.session.diagrams().get(id))
+        else {
+            self.set_toast(tr("toast.no_active_diagram"));
+            return;
+        };
+        let DiagramAst::Flowchart(ast) = diagram.ast() else {
+            self.set_toast(tr("toast.tidy_needs_a_flowchart"));
+            return;
+        };
+        let (diagram_id, rev) = (diagram.diagram_id().clone(), diagram.rev());
+        let options = RenderOptions {
+            flowchart_crossing_sweeps: self.render_budget.crossing_sweeps,
+            ..RenderOptions::default()
+        };
+        match tidy_flowchart(ast, options) {
+            Ok(preview) if !preview.hints_changed => {
+                self.set_toast(trf("toast.layout_already_tidy", &[("diagram_id", &diagram_id)]));
+            }
+            Ok(preview) => self.tidy_prompt = Some(TidyPrompt { diagram_id, rev, preview }),
+            Err(err) => self.set_toast(trf("toast.tidy_failed", &[("err", &err)])),
+        }
+    }
+
+    /// Moves the selected sequence participant `offset` lifelines to the right (left when
+    /// negative), persisting the new order as the diagram's explicit participant order.
+    fn move_selected_participant(&mut self, offset: isize) {
+        let Some(selected) = self.selected_ref().cloned() else {
+            self.set_toast(tr("toast.select_a_participant_to_move"));
+            return;
+        };
+        let is_participant = matches!(
+            selected.category().segments(),
+            [a, b] if a == "seq" && b == "participant"
+        );
+        if !is_participant || self.active_diagram_id() != Some(selected.diagram_id()) {
+            self.set_toast(tr("toast.select_a_participant_to_move"));
+            return;
+        }
+        if !self.ensure_active_diagram_writable() {
+            return;
+        }
+        let diagram_id = selected.diagram_id().clone();
+        let Some(diagram) = self.session.diagrams().get(&diagram_id) else {
+            return;
+        };
+        let DiagramAst::Sequence(seq_ast) = diagram.ast() else {
+            return;
+        };
+        let current = seq_ast.participants_in_order().into_iter().cloned().collect::<Vec<_>>();
+        let Some(index) = current.iter().position(|id| id == selected.object_id()) else {
+            return;
+        };
+        let Some(target) =
+            index.checked_add_signed(offset).filter(|target| *target < current.len())
+        else {
+            return;
+        };
+        let mut order = current.clone();
+        order.swap(index, target);
+        let mut moved = seq_ast.clone();
+        *moved.participant_order_mut() = order;
+        // Box groups stay together, so a member cannot leave its box this way.
+        if moved.participants_in_order().into_iter().eq(current.iter()) {
+            self.set_toast(tr("toast.participants_stay_inside_their_box"));
+            return;
+        }
+
+        let Some(diagram) = self.session.diagrams_mut().get_mut(&diagram_id) else {
+            return;
+        };
+        let rev = diagram.rev();
+        let before = diagram.ast().clone();
+        diagram
+            .set_ast(DiagramAst::Sequence(moved))
+            .expect("sequence ast matches sequence diagram");
+        diagram.bump_rev();
+        self.history.record_change(&before, diagram);
+        self.finish_diagram_change(&diagram_id, rev, &before, "Moved participant in");
+    }
+
+    /// Moves the selected flowchart node one grid step in screen direction `(dx, dy)` and pins it
+    /// there, so later layouts keep it in place.
+    fn nudge_selected_flow_node(&mut self, step: (i32, i32)) {
+        let Some(selected) = self.selected_ref().cloned() else {
+            self.set_toast(tr("toast.select_a_node_to_move"));
+            return;
+        };
+        let is_node = matches!(
+            selected.category().segments(),
+            [a, b] if a == "flow" && b == "node"
+        );
+        if !is_node || self.active_diagram_id() != Some(selected.diagram_id()) {
+            self.set_toast(tr("toast.select_a_node_to_move"));
+            return;
+        }
+        if !self.ensure_active_diagram_writable() {
+            return;
+        }
+        let diagram_id = selected.diagram_id().clone();
+        let Some(diagram) = self.session.diagrams().get(&diagram_id) else {
+            return;
+        };
+        let DiagramAst::Flowchart(flow_ast) = diagram.ast() else {
+            return;
+        };
+        let options =
+            FlowchartLayoutOptions { crossing_sweeps: self.render_budget.crossing_sweeps };
+        let Some(pin) = nudged_pin(flow_ast, selected.object_id(), step, options) else {
+            self.set_toast(tr("toast.node_cannot_move_further"));
+            return;
+        };
+        let node_id = selected.object_id().clone();
+        self.apply_pin_ops(
+            &diagram_id,
+            &[Op::Flow(FlowOp::SetNodePin { node_id, pin: Some(pin) })],
+            "Pinned node in",
+        );
+        self.select_object_ref(&selected);
+    }
+
+    /// Unpins the selected flowchart node, or every pinned node of the active flowchart, so
+    /// auto-layout places them again.
+    fn unpin_flow_nodes(&mut self, all: bool) {
+        let (diagram_id, node_id) = if all {
+            let Some(diagram_id) = self.active_diagram_id().cloned() else {
+                self.set_toast(tr("toast.no_active_diagram"));
+                return;
+            };
+            (diagram_id, None)
+        } else {
+            let Some(selected) = self.selected_ref().cloned() else {
+                self.set_toast(tr("toast.select_a_node_to_move"));
+                return;
+            };
+            let is_node = matches!(
+                selected.category().segments(),
+                [a, b] if a == "flow" && b == "node"
+            );
+            if !is_node || self.active_diagram_id() != Some(selected.diagram_id()) {
+                self.set_toast(tr("toast.select_a_node_to_move"));
+                return;
+            }
+            (selected.diagram_id().clone(), Some(selected.object_id().clone()))
+        };
+        if !self.ensure_active_diagram_writable() {
+            return;
+        }
+        let Some(diagram) = self.session.diagrams().get(&diagram_id) else {
+            return;
+        };
+        let ops = match diagram.ast() {
+            DiagramAst::Flowchart(flow_ast) => flow_ast
+                .node_pins()
+                .keys()
+                .filter(|pinned| node_id.is_none() || node_id.as_ref() == Some(*pinned))
+                .map(|pinned| Op::Flow(FlowOp::SetNodePin { node_id: pinned.clone(), pin: None }))
+                .collect::<Vec<_>>(),
+            _ => Vec::new(),
+        };
+        if ops.is_empty() {
+            self.set_toast(tr("toast.no_pinned_nodes"));
+            return;
+        }
+        self.apply_pin_ops(&diagram_id, &ops, "Unpinned nodes in");
+    }
+
+    fn apply_pin_ops(&mut self, diagram_id: &DiagramId, ops: &[Op], verb: &str) {
+        let Some(diagram) = self.session.diagrams_mut().get_mut(diagram_id) else {
+            return;
+        };
+        let rev = diagram.rev();
+        let before = diagram.ast().clone();
+        if let Err(err) = apply_ops(diagram, rev, ops) {
+            self.set_toast(trf("toast.pin_failed", &[("err", &err)]));
+            return;
+        }
+        self.history.record_change(&before, diagram);
+        self.finish_diagram_change(diagram_id, rev, &before, verb);
+    }
+
+    /// Moves the selected sequence message one place earlier or later by giving it a new `order_key`.
+    fn move_selected_seq_message(&mut self, offset: isize) {
+        let Some(selected) = self.selected_ref().cloned() else {
+            self.set_toast(tr("toast.select_a_message_to_move"));
+            return;
+        };
+        let is_message = matches!(
+            selected.category().segments(),
+            [a, b] if a == "seq" && b == "message"
+        );
+        if !is_message || self.active_diagram_id() != Some(selected.diagram_id()) {
+            self.set_toast(tr("toast.select_a_message_to_move"));
+            return;
+        }
+        if !self.ensure_active_diagram_writable() {
+            return;
+        }
+        let diagram_id = selected.diagram_id().clone();
+        let Some(diagram) = self.session.diagrams_mut().get_mut(&diagram_id) else {
+            self.set_toast(trf("toast.diagram_not_found", &[("diagram_id", &diagram_id)]));
+            return;
+        };
+        let DiagramAst::Sequence(ast) = diagram.ast() else {
+            return;
+        };
+        let Some(ops) = seq_message_move_ops(ast, selected.object_id(), offset) else {
+            return;
+        };
+        let rev = diagram.rev();
+        let before = diagram.ast().clone();
+        if let Err(err) = apply_ops(diagram, rev, &ops) {
+            self.set_toast(trf("toast.move_message_failed", &[("err", &err)]));
+            return;
+        }
+        self.history.record_change(&before, diagram);
+        self.finish_diagram_change(&diagram_id, rev, &before, "Moved message in");
+        self.select_object_ref(&selected);
+    }
+
+    /// Compares the active diagram with its version on disk, or switches the side shown;
+    /// `None` closes an open view.
+    fn toggle_diff_view(&mut self, side: Option<DiffSide>) {
+        let show_saved = match side {
+            None if self.diff_view.is_some() => {
+                self.diff_view = None;
+                self.rerender_active_diagram_buffer();
+                self.set_toast(tr("toast.diff_closed"));
+                return;
+            }
+            None | Some(DiffSide::Current) => false,
+            Some(DiffSide::Saved) => true,
+        };
+        let Some(diagram_id) = self.active_diagram_id().cloned() else {
+            self.set_toast(tr("toast.no_active_diagram"));
+            return;
+        };
+        if let Some(view) = self.diff_view.as_mut() {
+            view.show_saved = show_saved;
+        } else {
+            let Some(session_folder) = self.session_folder.as_ref() else {
+                self.set_toast(tr("toast.diff_needs_session_folder"));
+                return;
+            };
+            let saved = match session_folder.load_session() {
+                Ok(disk_session) => disk_session.diagrams().get(&diagram_id).cloned(),
+                Err(err) => {
+                    self.set_toast(trf("toast.diff_load_failed", &[("err", &err)]));
+                    return;
+                }
+            };
+            let Some(saved) = saved else {
+                self.set_toast(trf("toast.diagram_not_saved", &[("diagram_id", &diagram_id)]));
+                return;
+            };
+            self.diff_view = Some(DiffView { saved, diff: DiagramDiff::default(), show_saved });
+        }
+        self.rerender_active_diagram_buffer();
+
+        let Some(view) = self.diff_view.as_ref() else {
+            return;
+        };
+        let counts = format!(
+            "+{} -{} ~{}",
+            view.diff.count(DiffStatus::Added),
+            view.diff.count(DiffStatus::Removed),
+            view.diff.count(DiffStatus::Changed)
+        );
+        let key =
+            if show_saved { "toast.diff_showing_saved" } else { "toast.diff_showing_current" };
+        self.set_toast(trf(key, &[("diagram_id", &diagram_id), ("counts", &counts)]));
+    }
+
+    /// Opens the command line on `note <current note>` for the selected object.
+    fn edit_selected_object_note(&mut self) {
+        let Some(selected) = self.selected_ref().cloned() else {
+            self.set_toast(tr("toast.select_an_object_with_a_note"));
+            return;
+        };
+        let Some(diagram) = self.session.diagrams().get(selected.diagram_id()) else {
+            return;
+        };
+        match selected_object_note(diagram.ast(), &selected) {
+            Some(note) => {
+                self.command_line = Some(format!("note {}", note.unwrap_or_default()));
+            }
+            None => self.set_toast(tr("toast.select_an_object_with_a_note")),
+        }
+    }
+
+    /// Sets (`Some`) or clears (`None`) the note of the selected flowchart node, sequence
+    /// participant or sequence note; clearing a sequence note removes it.
+    fn set_selected_object_note(&mut self, note: Option<String>) {
+        let Some(selected) = self.selected_ref().cloned() else {
+            self.set_toast(tr("toast.select_an_object_with_a_note"));
+            return;
+        };
+        if self.active_diagram_id() != Some(selected.diagram_id()) {
+            self.set_toast(tr("toast.select_an_object_with_a_note"));
+            return;
+        }
+        if !self.ensure_active_diagram_writable() {
+            return;
+        }
+        let diagram_id = selected.diagram_id().clone();
+        let Some(diagram) = self.session.diagrams_mut().get_mut(&diagram_id) else {
+            self.set_toast(trf("toast.diagram_not_found", &[("diagram_id", &diagram_id)]));
+            return;
+        };
+        let Some(ops) = object_note_ops(diagram.ast(), &selected, note) else {
+            self.set_toast(tr("toast.select_an_object_with_a_note"));
+            return;
+        };
+        let rev = diagram.rev();
+        let before = diagram.ast().clone();
+        if let Err(err) = apply_ops(diagram, rev, &ops) {
+            self.set_toast(trf("toast.note_edit_failed", &[("err", &err)]));
+            return;
+        }
+        self.history.record_change(&before, diagram);
+        self.finish_diagram_change(&diagram_id, rev, &before, "Edited note in");
+        self.select_object_ref(&selected);
+    }
+
+    /// Persists the confirmed tidy preview as node order hints (undoable like any other edit).
+    fn apply_tidy_prompt(&mut self) {
+        let Some(TidyPrompt { diagram_id, rev, preview }) = self.tidy_prompt.take() else {
+            return;
+        };
+        let Some(diagram) = self.session.diagrams_mut().get_mut(&diagram_id) else {
+            self.set_toast(trf("toast.diagram_not_found", &[("diagram_id", &diagram_id)]));
+            return;
+        };
+        if diagram.rev() != rev {
+            self.set_toast(trf("toast.tidy_preview_stale", &[("diagram_id", &diagram_id)]));
+            return;
+        }
+        let before = diagram.ast().clone();
+        let mut ast = before.clone();
+        if let DiagramAst::Flowchart(flow_ast) = &mut ast {
+            *flow_ast.node_order_hints_mut() = preview.order_hints;
+        }
+        diagram.set_ast(ast).expect("flowchart ast matches flowchart diagram");
+        diagram.bump_rev();
+        self.history.record_change(&before, diagram);
+        self.finish_diagram_change(&diagram_id, rev, &before, "Tidied");
+    }
+
+    /// Switches the fuzzy search algorithm (toggling when `matcher` is `None`) and re-ranks an
+    /// open fuzzy search.
+    fn set_fuzzy_matcher(&mut self, matcher: Option<FuzzyMatcher>) {
+        self.search_scoring.matcher = matcher.unwrap_or(match self.search_scoring.matcher {
+            FuzzyMatcher::Nereid => FuzzyMatcher::Skim,
+            FuzzyMatcher::Skim => FuzzyMatcher::Nereid,
+        });
+        if self.search_mode != SearchMode::Inactive && self.search_kind == SearchKind::Fuzzy {
+            self.update_search_results();
+        }
+        self.set_toast(trf("toast.fuzzy_matcher", &[("matcher", &self.search_scoring.matcher)]));
+    }
+
+    /// Switches how flowchart edges are drawn (cycling when `glyphs` is `None`) and re-renders.
+    fn set_edge_glyphs(&mut self, glyphs: Option<FlowchartEdgeGlyphs>) {
+        self.edge_glyphs = glyphs.unwrap_or(match self.edge_glyphs {
+            FlowchartEdgeGlyphs::Box => FlowchartEdgeGlyphs::Braille,
+            FlowchartEdgeGlyphs::Braille => FlowchartEdgeGlyphs::Blocks,
+            FlowchartEdgeGlyphs::Blocks => FlowchartEdgeGlyphs::Box,
+        });
+        self.rerender_active_diagram_buffer();
+        self.set_toast(trf("toast.edge_glyphs", &[("glyphs", &self.edge_glyphs)]));
+    }
+
+    /// Selects the cheapest weighted path between two nodes of the active flowchart and reports
+    /// the cumulative cost at each hop.
+    fn select_weighted_path(&mut self, from: &str, to: &str) {
+        let Some(diagram) = self.active_diagram_id().and_then(|id| self.session.diagrams().get(id))
+        else {
+            self.set_toast(tr("toast.no_active_diagram"));
+            return;
+        };
+        let DiagramAst::Flowchart(ast) = diagram.ast() else {
+            self.set_toast(tr("toast.weighted_paths_need_a_flowchart"));
+            return;
+        };
+        let diagram_id = diagram.diagram_id().clone();
+        let (Some(from_id), Some(to_id)) =
+            (resolve_flow_node(ast, from), resolve_flow_node(ast, to))
+        else {
+            self.set_toast(trf("toast.unknown_path_nodes", &[("from", &from), ("to", &to)]));
+            return;
+        };
+        let Some(path) = crate::query::flow::weighted_shortest_path(ast, &from_id, &to_id) else {
+            self.set_toast(trf("toast.no_path", &[("from_id", &from_id), ("to_id", &to_id)]));
+            return;
+        };
+
+        let summary = path.summary(ast, " → ");
+        let path_refs = weighted_path_refs(&diagram_id, &path);
+        self.replace_diagram_selection(
+            &diagram_id,
+            path_refs,
+            format!("Path cost {}: {summary}", path.cost),
+        );
+    }
+
+    /// Adds the shortest path between the two selected nodes of the active flowchart, in either
+    /// direction, to the selection.
+    fn highlight_path_between_selected_nodes(&mut self) {
+        let Some(diagram) = self.active_diagram_id().and_then(|id| self.session.diagrams().get(id))
+        else {
+            self.set_toast(tr("toast.no_active_diagram"));
+            return;
+        };
+        let DiagramAst::Flowchart(ast) = diagram.ast() else {
+            self.set_toast(tr("toast.path_needs_two_selected_nodes"));
+            return;
+        };
+        let diagram_id = diagram.diagram_id().clone();
+        let node_category = category_path(&["flow", "node"]);
+        let selected = self
+            .session
+            .selected_object_refs()
+            .iter()
+            .filter(|object_ref| {
+                object_ref.diagram_id() == &diagram_id && object_ref.category() == &node_category
+            })
+            .map(|object_ref| object_ref.object_id().clone())
+            .collect::<Vec<_>>();
+        let [a, b] = selected.as_slice() else {
+            self.set_toast(tr("toast.path_needs_two_selected_nodes"));
+            return;
+        };
+
+        let (from_id, to_id, route) = [(a, b), (b, a)]
+            .into_iter()
+            .map(|(from, to)| (from, to, crate::query::flow::route(ast, from, to, 1, 0)))
+            .find(|(_, _, route)| !route.paths.is_empty())
+            .unwrap_or_else(|| (a, b, Default::default()));
+        if route.paths.is_empty() {
+            self.set_toast(trf("toast.no_path", &[("from_id", &a), ("to_id", &b)]));
+            return;
+        }
+
+        let edge_category = category_path(&["flow", "edge"]);
+        let refs = route
+            .node_ids
+            .iter()
+            .map(|node_id| {
+                ObjectRef::new(diagram_id.clone(), node_category.clone(), node_id.clone())
+            })
+            .chain(route.edge_ids.iter().map(|edge_id| {
+                ObjectRef::new(diagram_id.clone(), edge_category.clone(), edge_id.clone())
+            }))
+            .collect::<Vec<_>>();
+        let mut message = trf(
+            "toast.path_highlighted",
+            &[
+                ("from_id", from_id),
+                ("to_id", to_id),
+                ("nodes", &route.node_ids.len()),
+                ("edges", &route.edge_ids.len()),
+            ],
+        );
+        self.session.selected_object_refs_mut().extend(refs);
+        self.apply_object_filters();
+        if let Some(session_folder) = self.session_folder.as_ref() {
+            if let Err(err) = session_folder.save_selected_object_refs(&mut self.session) {
+                message = format!("{message} (persist failed: {err})");
+            }
+        }
+        self.set_toast(message);
+    }
+
+    /// Selects the nodes and edges whose removal would split the active flowchart.
+    fn select_cut_points(&mut self) {
+        let Some(diagram) = self.active_diagram_id().and_then(|id| self.session.diagrams().get(id))
+        else {
+            self.set_toast(tr("toast.no_active_diagram"));
+            return;
+        };
+        let DiagramAst::Flowchart(ast) = diagram.ast() else {
+            self.set_toast(tr("toast.cuts_need_a_flowchart"));
+            return;
+        };
+        let diagram_id = diagram.diagram_id().clone();
+        let cuts = crate::query::flow::cut_points(ast);
+        let (nodes, bridges) = (cuts.nodes.len(), cuts.bridges.len());
+        let node_category = category_path(&["flow", "node"]);
+        let edge_category = category_path(&["flow", "edge"]);
+        let refs =
+            cuts.nodes
+                .into_iter()
+                .map(|node_id| ObjectRef::new(diagram_id.clone(), node_category.clone(), node_id))
+                .chain(cuts.bridges.into_iter().map(|edge_id| {
+                    ObjectRef::new(diagram_id.clone(), edge_category.clone(), edge_id)
+                }))
+                .collect::<Vec<_>>();
+        let message = trf("toast.cut_points", &[("nodes", &nodes), ("bridges", &bridges)]);
+        self.replace_diagram_selection(&diagram_id, refs, message);
+    }
+
+    fn select_critical_path(&mut self) {
+        let Some(diagram) = self.active_diagram_id().and_then(|id| self.session.diagrams().get(id))
+        else {
+            self.set_toast(tr("toast.no_active_diagram"));
+            return;
+        };
+        let diagram_id = diagram.diagram_id().clone();
+        let (path_refs, message) = match diagram.ast() {
+            DiagramAst::Sequence(ast) => {
+                let path = crate::query::sequence::critical_path(ast);
+                let Some(total) = path.last().map(|step| step.finish) else {
+                    self.set_toast(tr("toast.no_messages_to_chain"));
+                    return;
+                };
+                let message_category = category_path(&["seq", "message"]);
+                let refs = path
+                    .iter()
+                    .map(|step| {
+                        ObjectRef::new(
+                            diagram_id.clone(),
+                            message_category.clone(),
+                            step.message.message_id().clone(),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                let summary = path
+                    .iter()
+                    .map(|step| format!("{} ({})", step.message.text(), step.finish))
+                    .collect::<Vec<_>>()
+                    .join(" → ");
+                (refs, format!("Critical path {total}: {summary}"))
+            }
+            DiagramAst::Flowchart(ast) => {
+                let Some(path) = crate::query::flow::critical_path(ast) else {
+                    self.set_toast(tr("toast.critical_path_needs_acyclic"));
+                    return;
+                };
+                let summary = path.summary(ast, " → ");
+                (
+                    weighted_path_refs(&diagram_id, &path),
+                    format!("Critical path {}: {summary}", path.cost),
+                )
+            }
+            DiagramAst::State(_) | DiagramAst::Class(_) => {
+                self.set_toast(tr("toast.critical_path_needs_flow_or_sequence"));
+                return;
+            }
+        };
+        self.replace_diagram_selection(&diagram_id, path_refs, message);
+    }
+
+    /// Replaces the selection within `diagram_id` by `refs`, focuses the first one and persists.
+    fn replace_diagram_selection(
+        &mut self,
+        diagram_id: &DiagramId,
+        refs: Vec<ObjectRef>,
+        message: String,
+    ) {
+        self.session
+            .selected_object_refs_mut()
+            .retain(|object_ref| object_ref.diagram_id() != diagram_id);
+        self.session.selected_object_refs_mut().extend(refs.iter().cloned());
+        self.apply_object_filters();
+        if let Some(first) = refs.first() {
+            self.select_object_ref(first);
+        }
+
+        let mut message = message;
+        if let Some(session_folder) = self.session_folder.as_ref() {
+            if let Err(err) = session_folder.save_selected_object_refs(&mut self.session) {
+                message = format!("{message} (persist failed: {err})");
+            }
+        }
+        self.set_toast(message);
+    }
+
+    fn commit_search(&mut self) {
+        if self.search_results.len() > 1 {
+            self.search_mode = SearchMode::Results;
+        } else {
+            self.search_mode = SearchMode::Inactive;
+        }
+    }
+
+    /// Closes the results panel, keeping the current result selected.
+    fn accept_search_result(&mut self) {
+        self.jump_to_current_search_result();
+        self.clear_search();
     }
 
     fn clear_search(&mut self) {
@@ -1644,6 +5090,7 @@ impl App {
             &self.search_query,
             self.search_kind,
             self.active_diagram_id(),
+            &self.search_scoring,
         );
         self.search_result_index = 0;
         self.jump_to_current_search_result();
@@ -1657,7 +5104,9 @@ impl App {
     }
 
     fn jump_to_current_search_result(&mut self) {
-        let Some(object_ref) = self.search_results.get(self.search_result_index).cloned() else {
+        let Some(object_ref) =
+            self.search_results.get(self.search_result_index).map(|hit| hit.object_ref.clone())
+        else {
             return;
         };
         self.select_object_ref(&object_ref);
@@ -1686,37 +5135,102 @@ impl App {
         self.jump_to_current_search_result();
     }
 
-    fn handle_diagram_key(&mut self, code: KeyCode) {
-        match code {
-            KeyCode::Char('f') => self.enter_diagram_hint_mode(),
-            KeyCode::Char('c') => self.enter_diagram_select_hint_mode(),
-            KeyCode::Char('e') => self.queue_edit_active_diagram(),
-            KeyCode::Char(' ') => self.toggle_selected_object(),
-            KeyCode::Char('y') => self.yank_selected_object_ref(),
-            KeyCode::Char('g') => self.jump_to_selected_incoming_xref(),
-            KeyCode::Char('t') => self.jump_to_selected_outgoing_xref(),
-            KeyCode::Up | KeyCode::Char('k') => self.pan_y = self.pan_y.saturating_sub(1),
-            KeyCode::Down | KeyCode::Char('j') => self.pan_y = self.pan_y.saturating_add(1),
-            KeyCode::Left | KeyCode::Char('h') => self.pan_x = self.pan_x.saturating_sub(1),
-            KeyCode::Right | KeyCode::Char('l') => self.pan_x = self.pan_x.saturating_add(1),
-            KeyCode::Char('K') => self.pan_y = self.pan_y.saturating_sub(10),
-            KeyCode::Char('J') => self.pan_y = self.pan_y.saturating_add(10),
-            KeyCode::Char('H') => self.pan_x = self.pan_x.saturating_sub(10),
-            KeyCode::Char('L') => self.pan_x = self.pan_x.saturating_add(10),
-
-            KeyCode::PageUp => self.pan_y = self.pan_y.saturating_sub(10),
-            KeyCode::PageDown => self.pan_y = self.pan_y.saturating_add(10),
-            KeyCode::Home => {
-                self.pan_x = 0;
-                self.pan_y = 0;
-            }
-            KeyCode::End => {
-                self.pan_x = i32::MAX;
-                self.pan_y = i32::MAX;
+    /// Starts a rectangle at the focused object, or at the viewport center without one.
+    fn enter_visual_selection(&mut self) {
+        self.cancel_hint_mode();
+        let start = self
+            .selected_ref()
+            .and_then(|object_ref| self.base_highlight_index.get(object_ref))
+            .and_then(|spans| line_spans_center(spans))
+            .unwrap_or_else(|| {
+                let (viewport_width, viewport_height) = self.diagram_viewport;
+                (
+                    (self.pan_y + (viewport_height / 2) as i32).max(0) as usize,
+                    (self.pan_x + (viewport_width / 2) as i32).max(0) as usize,
+                )
+            });
+        let start = self.clamp_to_diagram(start);
+        self.visual_selection = Some(VisualSelection { anchor: start, cursor: start });
+        self.set_toast(tr("toast.visual_started"));
+    }
+
+    fn handle_visual_selection_key(&mut self, code: KeyCode) {
+        let (dy, dx) = match code {
+            KeyCode::Esc => {
+                self.visual_selection = None;
+                self.set_toast(tr("toast.visual_selection_cancelled"));
+                return;
+            }
+            KeyCode::Enter | KeyCode::Char('v') => {
+                self.confirm_visual_selection();
+                return;
             }
+            KeyCode::Up | KeyCode::Char('k') => (-1, 0),
+            KeyCode::Down | KeyCode::Char('j') => (1, 0),
+            KeyCode::Left | KeyCode::Char('h') => (0, -1),
+            KeyCode::Right | KeyCode::Char('l') => (0, 1),
+            KeyCode::Char('K') => (-10, 0),
+            KeyCode::Char('J') => (10, 0),
+            KeyCode::Char('H') => (0, -10),
+            KeyCode::Char('L') => (0, 10),
+            _ => return,
+        };
+        let Some(visual) = self.visual_selection else {
+            return;
+        };
+        let (row, col) = visual.cursor;
+        let cursor = self.clamp_to_diagram((
+            (row as i64 + dy).max(0) as usize,
+            (col as i64 + dx).max(0) as usize,
+        ));
+        self.visual_selection = Some(VisualSelection { cursor, ..visual });
 
-            _ => {}
+        // Keep the growing edge on screen.
+        let (viewport_width, viewport_height) = self.diagram_viewport;
+        let (row, col) = (cursor.0 as i32, cursor.1 as i32);
+        if viewport_height > 0 {
+            self.pan_y = self.pan_y.clamp(row - viewport_height as i32 + 1, row);
+        }
+        if viewport_width > 0 {
+            self.pan_x = self.pan_x.clamp(col - viewport_width as i32 + 1, col);
+        }
+    }
+
+    /// Adds every object whose spans touch the rectangle to the selection and persists it.
+    fn confirm_visual_selection(&mut self) {
+        let Some(visual) = self.visual_selection.take() else {
+            return;
+        };
+        let refs = self
+            .base_highlight_index
+            .iter()
+            .filter(|(_, spans)| visual.intersects(spans))
+            .map(|(object_ref, _)| object_ref.clone())
+            .collect::<Vec<_>>();
+        let mut inserted = 0usize;
+        for object_ref in &refs {
+            inserted +=
+                usize::from(self.session.selected_object_refs_mut().insert(object_ref.clone()));
+        }
+        if inserted == 0 {
+            self.set_toast(trf("toast.no_new_objects_in_box", &[("count", &refs.len())]));
+            return;
+        }
+        self.apply_object_filters();
+        let mut message = format!("Selected {inserted} objects");
+        if let Some(session_folder) = self.session_folder.as_ref() {
+            if let Err(err) = session_folder.save_selected_object_refs(&mut self.session) {
+                message = format!("{message} (persist failed: {err})");
+            }
         }
+        self.set_toast(message);
+    }
+
+    /// Clamps a `(row, col)` cell to the rendered diagram.
+    fn clamp_to_diagram(&self, (row, col): (usize, usize)) -> (usize, usize) {
+        let lines = self.base_diagram.split('\n').collect::<Vec<_>>();
+        let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+        (row.min(lines.len().saturating_sub(1)), col.min(width.saturating_sub(1)))
     }
 
     fn cancel_hint_mode(&mut self) {
@@ -1749,7 +5263,10 @@ impl App {
                             HintKind::Jump => {}
                             HintKind::SelectChain => {
                                 self.hint_mode = HintMode::AwaitingFirst { kind, targets };
-                                self.set_toast(format!("No hint targets for '{first}'"));
+                                self.set_toast(trf(
+                                    "toast.no_hint_targets_for",
+                                    &[("first", &first)],
+                                ));
                             }
                         }
                     } else {
@@ -1786,7 +5303,7 @@ impl App {
                         }
                         (HintKind::SelectChain, None) => {
                             self.hint_mode = HintMode::AwaitingSecond { kind, first, targets };
-                            self.set_toast("No matching hint".to_owned());
+                            self.set_toast(tr("toast.no_matching_hint"));
                         }
                     }
 
@@ -1863,7 +5380,7 @@ impl App {
                 let mut refs = Vec::new();
                 let participant_category = category_path(&["seq", "participant"]);
 
-                for participant_id in ast.participants().keys() {
+                for participant_id in ast.participants_in_order() {
                     refs.push(ObjectRef::new(
                         diagram_id.clone(),
                         participant_category.clone(),
@@ -1884,6 +5401,36 @@ impl App {
 
                 refs
             }
+            DiagramAst::State(ast) => {
+                let state_category = category_path(&["state", "node"]);
+                let transition_category = category_path(&["state", "transition"]);
+                let states = ast.states().keys().map(|state_id| {
+                    ObjectRef::new(diagram_id.clone(), state_category.clone(), state_id.clone())
+                });
+                let transitions = ast.transitions().keys().map(|transition_id| {
+                    ObjectRef::new(
+                        diagram_id.clone(),
+                        transition_category.clone(),
+                        transition_id.clone(),
+                    )
+                });
+                states.chain(transitions).collect()
+            }
+            DiagramAst::Class(ast) => {
+                let class_category = category_path(&["class", "node"]);
+                let relation_category = category_path(&["class", "relation"]);
+                let classes = ast.classes().keys().map(|class_id| {
+                    ObjectRef::new(diagram_id.clone(), class_category.clone(), class_id.clone())
+                });
+                let relations = ast.relations().keys().map(|relation_id| {
+                    ObjectRef::new(
+                        diagram_id.clone(),
+                        relation_category.clone(),
+                        relation_id.clone(),
+                    )
+                });
+                classes.chain(relations).collect()
+            }
         };
 
         let mut placements = Vec::<(ObjectRef, (usize, usize, usize, char))>::new();
@@ -1894,7 +5441,7 @@ impl App {
                 continue;
             };
             let (y, inner_x0, inner_x1, fill_char) = match object_ref.category().segments() {
-                [a, b] if a == "flow" && b == "node" => {
+                [a, b] if (a == "flow" || a == "state" || a == "class") && b == "node" => {
                     let Some((y0, x0, x1)) = hint_bounds_from_spans(spans) else {
                         continue;
                     };
@@ -1902,7 +5449,11 @@ impl App {
                     let inner_x1 = x1.saturating_sub(1);
                     (y0.saturating_add(1), inner_x0, inner_x1, ' ')
                 }
-                [a, b] if a == "flow" && b == "edge" => {
+                [a, b]
+                    if (a == "flow" && b == "edge")
+                        || (a == "state" && b == "transition")
+                        || (a == "class" && b == "relation") =>
+                {
                     let Some((y, inner_x0, inner_x1)) = flow_edge_hint_bounds(spans, &lines) else {
                         continue;
                     };
@@ -1934,6 +5485,8 @@ impl App {
                 continue;
             };
             if !is_flow_edge_ref(&object_ref)
+                && !is_state_transition_ref(&object_ref)
+                && !is_class_relation_ref(&object_ref)
                 && !hint_range_has_text(line, inner_x0, inner_x1, fill_char)
             {
                 continue;
@@ -1952,7 +5505,10 @@ impl App {
         let k = NODE_HINT_CHARS.chars().count();
         let max_two_char = k.saturating_mul(k);
         if hint_count > max_two_char {
-            self.set_toast(format!("Too many hint targets ({hint_count}, max {max_two_char})"));
+            self.set_toast(trf(
+                "toast.too_many_hint_targets",
+                &[("hint_count", &hint_count), ("max_two_char", &max_two_char)],
+            ));
             return None;
         }
 
@@ -2014,7 +5570,7 @@ impl App {
 
         if inserted_any {
             if let Some(session_folder) = self.session_folder.as_ref() {
-                if let Err(err) = session_folder.save_selected_object_refs(&self.session) {
+                if let Err(err) = session_folder.save_selected_object_refs(&mut self.session) {
                     message = format!("{message} (persist failed: {err})");
                 }
             }
@@ -2081,20 +5637,14 @@ impl App {
                 let mut message_id = messages
                     .iter()
                     .rev()
-                    .find(|msg| {
-                        msg.from_participant_id() == current_id
-                            && msg.to_participant_id() == previous_id
-                    })
+                    .find(|msg| msg.endpoints() == Some((current_id, previous_id)))
                     .map(|msg| msg.message_id().clone());
 
                 if message_id.is_none() {
                     message_id = messages
                         .iter()
                         .rev()
-                        .find(|msg| {
-                            msg.from_participant_id() == previous_id
-                                && msg.to_participant_id() == current_id
-                        })
+                        .find(|msg| msg.endpoints() == Some((previous_id, current_id)))
                         .map(|msg| msg.message_id().clone());
                 }
 
@@ -2106,42 +5656,44 @@ impl App {
                     )
                 })
             }
-        }
-    }
-
-    fn handle_objects_key(&mut self, code: KeyCode) {
-        match code {
-            KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('h') => self.select_prev(),
-            KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('l') => self.select_next(),
-
-            KeyCode::Home => self.select_first(),
-            KeyCode::End => self.select_last(),
-            KeyCode::Char(' ') => self.toggle_selected_object(),
-            KeyCode::Char('-') => self.toggle_objects_selected_only(),
-            KeyCode::Char('y') => self.yank_selected_object_ref(),
-            KeyCode::Char('f') => self.enter_diagram_hint_mode(),
-            KeyCode::Char('c') => self.enter_diagram_select_hint_mode(),
-            KeyCode::Char('g') => self.jump_to_selected_incoming_xref(),
-            KeyCode::Char('t') => self.jump_to_selected_outgoing_xref(),
-
-            _ => {}
-        }
-    }
-
-    fn handle_xrefs_key(&mut self, code: KeyCode) {
-        match code {
-            KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('h') => self.select_xref_prev(),
-            KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('l') => self.select_xref_next(),
-
-            KeyCode::Home => self.select_xref_first(),
-            KeyCode::End => self.select_xref_last(),
+            DiagramAst::State(ast) => {
+                let is_state = |r: &ObjectRef| matches!(r.category().segments(), [a, b] if a == "state" && b == "node");
+                if !is_state(current) || !is_state(previous) {
+                    return None;
+                }
 
-            KeyCode::Char('-') => self.toggle_xrefs_dangling_only(),
-            KeyCode::Char('I') => self.toggle_xrefs_involving_only(),
-            KeyCode::Char('g') => self.jump_to_xref_from(),
-            KeyCode::Char('t') => self.jump_to_xref_to(),
+                let (current_id, previous_id) = (current.object_id(), previous.object_id());
+                let connects = |from: &ObjectId, to: &ObjectId| {
+                    ast.transitions().iter().find(|(_, transition)| {
+                        transition.from_state_id() == from && transition.to_state_id() == to
+                    })
+                };
+                let (transition_id, _) = connects(current_id, previous_id)
+                    .or_else(|| connects(previous_id, current_id))?;
+                Some(ObjectRef::new(
+                    current.diagram_id().clone(),
+                    category_path(&["state", "transition"]),
+                    transition_id.clone(),
+                ))
+            }
+            DiagramAst::Class(ast) => {
+                let is_class = |r: &ObjectRef| matches!(r.category().segments(), [a, b] if a == "class" && b == "node");
+                if !is_class(current) || !is_class(previous) {
+                    return None;
+                }
 
-            _ => {}
+                let (current_id, previous_id) = (current.object_id(), previous.object_id());
+                let (relation_id, _) = ast.relations().iter().find(|(_, relation)| {
+                    let (from, to) = (relation.from_class_id(), relation.to_class_id());
+                    (from == current_id && to == previous_id)
+                        || (from == previous_id && to == current_id)
+                })?;
+                Some(ObjectRef::new(
+                    current.diagram_id().clone(),
+                    category_path(&["class", "relation"]),
+                    relation_id.clone(),
+                ))
+            }
         }
     }
 
@@ -2210,7 +5762,7 @@ impl App {
 
     fn toggle_selected_object(&mut self) {
         let Some(object_ref) = self.selected_ref().cloned() else {
-            self.set_toast("No object selected");
+            self.set_toast(tr("toast.no_object_selected"));
             return;
         };
 
@@ -2226,7 +5778,7 @@ impl App {
         let verb = if now_selected { "Selected" } else { "Deselected" };
         let mut message = format!("{verb} {object_ref}");
         if let Some(session_folder) = self.session_folder.as_ref() {
-            if let Err(err) = session_folder.save_selected_object_refs(&self.session) {
+            if let Err(err) = session_folder.save_selected_object_refs(&mut self.session) {
                 message = format!("{message} (persist failed: {err})");
             }
         }
@@ -2245,7 +5797,7 @@ impl App {
         let removed = before.saturating_sub(self.session.selected_object_refs().len());
 
         if removed == 0 {
-            self.set_toast("No selected objects in current diagram");
+            self.set_toast(tr("toast.no_selected_objects_in_diagram"));
             return;
         }
 
@@ -2253,20 +5805,32 @@ impl App {
 
         let mut message = format!("Deselected {removed} object(s) in {active_diagram_id}");
         if let Some(session_folder) = self.session_folder.as_ref() {
-            if let Err(err) = session_folder.save_selected_object_refs(&self.session) {
+            if let Err(err) = session_folder.save_selected_object_refs(&mut self.session) {
                 message = format!("{message} (persist failed: {err})");
             }
         }
         self.set_toast(message);
     }
 
+    fn set_objects_tag_filter(&mut self, tag: Option<String>) {
+        self.objects_tag_filter = tag;
+        self.apply_object_filters();
+        let message = match self.objects_tag_filter.as_deref() {
+            Some(tag) => {
+                format!("Showing objects tagged {tag} ({})", self.visible_object_indices.len())
+            }
+            None => "Cleared object filter".to_owned(),
+        };
+        self.set_toast(message);
+    }
+
     fn toggle_objects_selected_only(&mut self) {
         self.objects_selected_only = !self.objects_selected_only;
         self.apply_object_filters();
         self.set_toast(if self.objects_selected_only {
-            "Showing selected objects"
+            tr("toast.showing_selected_objects")
         } else {
-            "Showing all objects"
+            tr("toast.showing_all_objects")
         });
     }
 
@@ -2317,7 +5881,21 @@ impl App {
         self.select_object_ref(object_ref);
     }
 
+    /// Selects `object_ref`, moving focus to the other pane of a split when that one shows its
+    /// diagram.
     fn select_object_ref(&mut self, object_ref: &ObjectRef) {
+        let in_other_pane = self.active_diagram_id() != Some(object_ref.diagram_id())
+            && self
+                .split
+                .as_ref()
+                .is_some_and(|pane| pane.diagram_id.as_ref() == Some(object_ref.diagram_id()));
+        if in_other_pane {
+            self.focus_other_pane();
+        }
+        self.select_object_ref_in_focused_pane(object_ref);
+    }
+
+    fn select_object_ref_in_focused_pane(&mut self, object_ref: &ObjectRef) {
         if self.session.diagrams().get(object_ref.diagram_id()).is_none() {
             return;
         }
@@ -2339,6 +5917,10 @@ impl App {
             self.objects_selected_only = false;
             self.apply_object_filters();
         }
+        if self.objects_tag_filter.is_some() && !self.visible_object_indices.contains(&object_idx) {
+            self.objects_tag_filter = None;
+            self.apply_object_filters();
+        }
 
         let Some(visible_idx) =
             self.visible_object_indices.iter().position(|&idx| idx == object_idx)
@@ -2346,12 +5928,341 @@ impl App {
             return;
         };
         self.objects_state.select(Some(visible_idx));
+        self.scroll_object_ref_into_view(object_ref);
         if self.xrefs_involving_only {
             self.apply_xref_filters();
         }
         self.publish_focus_to_ui_state();
     }
 
+    fn select_relation_by(&mut self, delta: i32) {
+        let len = self.relation_rows().len();
+        if len == 0 {
+            self.relations_state.select(None);
+            return;
+        }
+        let current = self.relations_state.selected().unwrap_or(0).min(len - 1) as i64;
+        let next = (current + i64::from(delta)).clamp(0, len as i64 - 1);
+        self.relations_state.select(Some(next as usize));
+    }
+
+    fn select_recent_by(&mut self, delta: i32) {
+        let len = self.recent_objects.len();
+        if len == 0 {
+            self.recent_state.select(None);
+            return;
+        }
+        let current = self.recent_state.selected().unwrap_or(0).min(len - 1) as i64;
+        let next = (current + i64::from(delta)).clamp(0, len as i64 - 1);
+        self.recent_state.select(Some(next as usize));
+    }
+
+    fn jump_to_selected_recent(&mut self) {
+        let Some(target) = self
+            .recent_state
+            .selected()
+            .and_then(|idx| self.recent_objects.get(idx))
+            .map(|recent| recent.object_ref.clone())
+        else {
+            self.set_toast(tr("toast.no_recent_object_selected"));
+            return;
+        };
+        if !self.object_exists_in_session(&target) {
+            self.set_toast(trf("toast.missing_object", &[("target", &target)]));
+            return;
+        }
+        self.jump_to_object_ref(&target);
+        self.recent_state.select(Some(0));
+    }
+
+    /// Timeline rows are newest first; returns the event under the cursor.
+    fn selected_activity_seq(&self) -> Option<u64> {
+        let events = self.activity.events();
+        let idx = self.timeline_state.selected()?;
+        events.len().checked_sub(idx + 1).and_then(|pos| events.get(pos)).map(|event| event.seq)
+    }
+
+    fn select_timeline_by(&mut self, delta: i32) {
+        let len = self.activity.events().len();
+        if len == 0 {
+            self.timeline_state.select(None);
+            return;
+        }
+        let current = self.timeline_state.selected().unwrap_or(0).min(len - 1) as i64;
+        let next = (current + i64::from(delta)).clamp(0, len as i64 - 1);
+        self.timeline_state.select(Some(next as usize));
+        if self.time_travel.is_some() {
+            self.travel_to_selected_activity();
+        }
+    }
+
+    /// Shows the diagrams as they were right after the selected activity, switching to the
+    /// diagram the activity concerns.
+    fn travel_to_selected_activity(&mut self) {
+        let Some(event) = self.selected_activity_seq().and_then(|seq| self.activity.event(seq))
+        else {
+            self.set_toast(tr("toast.no_activity_selected"));
+            return;
+        };
+        let (seq, at) = (event.seq, event.at);
+        let diagram_id = event
+            .diagram_id()
+            .filter(|diagram_id| self.session.diagrams().contains_key(*diagram_id))
+            .cloned();
+
+        self.time_travel = Some(seq);
+        match diagram_id {
+            Some(diagram_id) if self.active_diagram_id() != Some(&diagram_id) => {
+                self.set_active_diagram_id(diagram_id);
+            }
+            _ => self.rerender_active_diagram_buffer(),
+        }
+        self.set_toast(trf("toast.time_travel", &[("at", &clock_label(at))]));
+    }
+
+    /// Announces newly pending proposals and drops a preview whose proposal got settled.
+    fn sync_proposals(&mut self, proposals: &BTreeMap<String, DiagramProposal>) {
+        let pending = proposals
+            .values()
+            .filter(|proposal| proposal.status == ProposalStatus::Pending)
+            .collect::<Vec<_>>();
+        for proposal in &pending {
+            if self.announced_proposals.insert(proposal.proposal_id.clone()) {
+                let summary = proposal.summary.as_deref().unwrap_or("rewrite");
+                self.log_agent_action(format!(
+                    "propose {} for {}",
+                    proposal.proposal_id, proposal.diagram_id
+                ));
+                self.set_toast(trf(
+                    "toast.agent_proposed",
+                    &[
+                        ("proposal_id", &proposal.proposal_id),
+                        ("diagram_id", &proposal.diagram_id),
+                        ("summary", &summary),
+                    ],
+                ));
+            }
+        }
+
+        let settled = self.proposal_preview.as_ref().is_some_and(|preview| {
+            !pending.iter().any(|proposal| proposal.proposal_id == preview.proposal_id)
+        });
+        if settled {
+            self.proposal_preview = None;
+            self.refresh_active_diagram_view();
+            self.set_toast(tr("toast.proposal_withdrawn"));
+        }
+    }
+
+    fn pending_proposals(&self) -> Vec<DiagramProposal> {
+        let Some(ui_state) = self.ui_state.as_ref() else {
+            return Vec::new();
+        };
+        let ui_state = ui_state.blocking_lock();
+        ui_state
+            .proposals()
+            .values()
+            .filter(|proposal| proposal.status == ProposalStatus::Pending)
+            .cloned()
+            .collect()
+    }
+
+    /// Shows a pending proposal in place of its diagram, marking what it changes; without an id
+    /// it returns to the original when previewing, else opens the newest proposal (preferring
+    /// the active diagram's).
+    fn toggle_proposal_preview(&mut self, proposal_id: Option<String>) {
+        if proposal_id.is_none() && self.proposal_preview.is_some() {
+            self.proposal_preview = None;
+            self.refresh_active_diagram_view();
+            self.set_toast(tr("toast.showing_original"));
+            return;
+        }
+
+        let pending = self.pending_proposals();
+        let proposal = match proposal_id.as_deref() {
+            Some(proposal_id) => pending.iter().find(|p| p.proposal_id == proposal_id),
+            None => pending
+                .iter()
+                .rev()
+                .find(|p| Some(&p.diagram_id) == self.active_diagram_id())
+                .or_else(|| pending.last()),
+        };
+        let Some(proposal) = proposal else {
+            self.set_toast(match proposal_id {
+                Some(proposal_id) => {
+                    trf("toast.no_pending_proposal", &[("proposal_id", &proposal_id)])
+                }
+                None => tr("toast.no_pending_proposals").to_owned(),
+            });
+            return;
+        };
+        let Some(original) = self.session.diagrams().get(&proposal.diagram_id) else {
+            self.set_toast(trf(
+                "toast.diagram_no_longer_exists",
+                &[("diagram_id", &proposal.diagram_id)],
+            ));
+            return;
+        };
+
+        let mut diagram = original.clone();
+        if let Err(err) = diagram.set_ast(proposal.ast.clone()) {
+            self.set_toast(trf(
+                "toast.cannot_preview",
+                &[("proposal_id", &proposal.proposal_id), ("err", &err)],
+            ));
+            return;
+        }
+        let delta = diff_delta(original.diagram_id(), original.ast(), &proposal.ast);
+        let counts = delta.as_ref().map(|delta| {
+            format!("+{} -{} ~{}", delta.added.len(), delta.removed.len(), delta.updated.len())
+        });
+        let stale = original.rev() != proposal.base_rev;
+        let diagram_id = proposal.diagram_id.clone();
+        let summary = proposal.summary.clone();
+        self.proposal_preview = Some(ProposalPreview {
+            proposal_id: proposal.proposal_id.clone(),
+            diagram,
+            removed: delta.as_ref().map_or(0, |delta| delta.removed.len()),
+            changed: delta.map(|delta| delta.added.into_iter().chain(delta.updated).collect()),
+        });
+
+        self.time_travel = None;
+        let proposal_id = self.proposal_preview.as_ref().map(|p| p.proposal_id.clone());
+        if self.active_diagram_id() != Some(&diagram_id) {
+            self.set_active_diagram_id(diagram_id.clone());
+        } else {
+            self.refresh_active_diagram_view();
+        }
+
+        let mut message =
+            format!("Previewing {} for {diagram_id}", proposal_id.unwrap_or_default());
+        if let Some(counts) = counts {
+            message.push_str(&format!(" ({counts})"));
+        }
+        if let Some(summary) = summary {
+            message.push_str(&format!(": {summary}"));
+        }
+        message.push_str(if stale {
+            "; stale, the diagram changed since"
+        } else {
+            "; :accept, :discard, :proposal shows the original"
+        });
+        self.set_toast(message);
+    }
+
+    /// Replaces the previewed proposal's diagram with it, as an undoable edit.
+    fn accept_proposal(&mut self) {
+        let Some(preview) = self.proposal_preview.clone() else {
+            self.set_toast(tr("toast.no_proposal_previewed"));
+            return;
+        };
+        let diagram_id = preview.diagram.diagram_id().clone();
+        let Some(proposal) =
+            self.pending_proposals().into_iter().find(|p| p.proposal_id == preview.proposal_id)
+        else {
+            self.proposal_preview = None;
+            self.refresh_active_diagram_view();
+            self.set_toast(trf(
+                "toast.proposal_no_longer_pending",
+                &[("proposal_id", &preview.proposal_id)],
+            ));
+            return;
+        };
+        if !self.ensure_active_diagram_writable() {
+            return;
+        }
+        let Some(diagram) = self.session.diagrams_mut().get_mut(&diagram_id) else {
+            return;
+        };
+        if diagram.rev() != proposal.base_rev {
+            let current_rev = diagram.rev();
+            self.set_toast(trf(
+                "toast.proposal_stale",
+                &[
+                    ("diagram_id", &diagram_id),
+                    ("proposal_id", &proposal.proposal_id),
+                    ("base_rev", &proposal.base_rev),
+                    ("current_rev", &current_rev),
+                ],
+            ));
+            return;
+        }
+
+        let before = diagram.ast().clone();
+        if let Err(err) = diagram.set_ast(proposal.ast) {
+            self.set_toast(trf(
+                "toast.cannot_accept",
+                &[("proposal_id", &proposal.proposal_id), ("err", &err)],
+            ));
+            return;
+        }
+        diagram.bump_rev();
+        let new_rev = diagram.rev();
+        self.history.record_change(&before, diagram);
+        if let Some(ui_state) = self.ui_state.as_ref() {
+            ui_state
+                .blocking_lock()
+                .resolve_proposal(&proposal.proposal_id, ProposalStatus::Accepted { rev: new_rev });
+        }
+
+        let baseline_rev = match &self.pending_diagram_sync {
+            Some(pending) if pending.diagram_id == diagram_id => pending.expected_disk_rev,
+            _ => proposal.base_rev,
+        };
+        let verb = format!("Accepted {} into", proposal.proposal_id);
+        self.finish_diagram_change(&diagram_id, baseline_rev, &before, &verb);
+    }
+
+    fn discard_proposal(&mut self) {
+        let Some(preview) = self.proposal_preview.take() else {
+            self.set_toast(tr("toast.no_proposal_previewed"));
+            return;
+        };
+        if let Some(ui_state) = self.ui_state.as_ref() {
+            ui_state
+                .blocking_lock()
+                .resolve_proposal(&preview.proposal_id, ProposalStatus::Discarded);
+        }
+        self.refresh_active_diagram_view();
+        self.set_toast(trf("toast.discarded", &[("proposal_id", &preview.proposal_id)]));
+    }
+
+    fn leave_time_travel(&mut self) {
+        if self.time_travel.take().is_some() {
+            self.rerender_active_diagram_buffer();
+            self.set_toast(tr("toast.back_to_the_present"));
+        }
+    }
+
+    /// Logs whatever changed in the session since the last call, attributed to `actor`.
+    fn log_session_activity(&mut self, actor: Actor) {
+        self.activity.observe_session(SystemTime::now(), actor, &self.session);
+    }
+
+    fn log_agent_action(&mut self, summary: String) {
+        self.activity.push(SystemTime::now(), Actor::Agent, Activity::AgentAction { summary });
+    }
+
+    fn jump_to_selected_relation(&mut self) {
+        let rows = self.relation_rows();
+        let Some(row) = self.relations_state.selected().and_then(|idx| rows.get(idx)) else {
+            self.set_toast(tr("toast.no_relation_selected"));
+            return;
+        };
+        let target = row.target.clone();
+        if !self.object_exists_in_session(&target) {
+            self.set_toast(trf("toast.missing_object", &[("target", &target)]));
+            return;
+        }
+
+        self.jump_to_object_ref(&target);
+        // Blocks and sections are not listed as objects; bring them into view instead.
+        if self.object_index_for_ref(&target).is_none() {
+            self.center_viewport_on_object_ref(&target);
+        }
+        self.relations_state.select(Some(0));
+    }
+
     fn jump_to_xref_from(&mut self) {
         let Some(idx) = self.selected_xref_index() else {
             return;
@@ -2370,7 +6281,7 @@ impl App {
 
     fn jump_to_selected_outgoing_xref(&mut self) {
         let Some(selected_ref) = self.selected_ref().cloned() else {
-            self.set_toast("No object selected");
+            self.set_toast(tr("toast.no_object_selected"));
             return;
         };
 
@@ -2383,65 +6294,173 @@ impl App {
             .collect::<Vec<_>>();
 
         let Some(first_idx) = matches.first().copied() else {
-            self.set_toast(format!("No outgoing xref for {selected_ref}"));
+            self.set_toast(trf("toast.no_outgoing_xref_for", &[("selected_ref", &selected_ref)]));
+            return;
+        };
+        let target_ref = self.xrefs[first_idx].xref.to().clone();
+        self.jump_to_object_ref(&target_ref);
+
+        if matches.len() > 1 {
+            let xref_id = &self.xrefs[first_idx].xref_id;
+            self.set_toast(trf(
+                "toast.outgoing_xrefs_followed_first",
+                &[("count", &matches.len()), ("xref_id", &xref_id)],
+            ));
+        }
+    }
+
+    fn jump_to_selected_incoming_xref(&mut self) {
+        let Some(selected_ref) = self.selected_ref().cloned() else {
+            self.set_toast(tr("toast.no_object_selected"));
+            return;
+        };
+
+        let matches = self
+            .xrefs
+            .iter()
+            .enumerate()
+            .filter(|(_, xref)| xref.xref.to() == &selected_ref)
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+
+        let Some(first_idx) = matches.first().copied() else {
+            self.set_toast(trf("toast.no_incoming_xref_for", &[("selected_ref", &selected_ref)]));
+            return;
+        };
+        let source_ref = self.xrefs[first_idx].xref.from().clone();
+        self.jump_to_object_ref(&source_ref);
+
+        if matches.len() > 1 {
+            let xref_id = &self.xrefs[first_idx].xref_id;
+            self.set_toast(trf(
+                "toast.incoming_xrefs_followed_first",
+                &[("count", &matches.len()), ("xref_id", &xref_id)],
+            ));
+        }
+    }
+
+    fn set_toast(&mut self, message: impl Into<String>) {
+        self.toast = Some(Toast {
+            message: message.into(),
+            expires_at: Instant::now() + Duration::from_secs(2),
+        });
+    }
+
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        let position = Position::new(mouse.column, mouse.row);
+        let over_diagram = self.diagram_content_area.is_some_and(|area| area.contains(position));
+        let horizontal = mouse.modifiers.contains(KeyModifiers::SHIFT);
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => self.handle_mouse_click(position),
+            MouseEventKind::ScrollUp if over_diagram && horizontal => {
+                self.pan_by(-WHEEL_PAN_STEP, 0)
+            }
+            MouseEventKind::ScrollDown if over_diagram && horizontal => {
+                self.pan_by(WHEEL_PAN_STEP, 0)
+            }
+            MouseEventKind::ScrollUp if over_diagram => self.pan_by(0, -WHEEL_PAN_STEP),
+            MouseEventKind::ScrollDown if over_diagram => self.pan_by(0, WHEEL_PAN_STEP),
+            MouseEventKind::ScrollLeft if over_diagram => self.pan_by(-WHEEL_PAN_STEP, 0),
+            MouseEventKind::ScrollRight if over_diagram => self.pan_by(WHEEL_PAN_STEP, 0),
+            _ => {}
+        }
+    }
+
+    fn handle_mouse_click(&mut self, position: Position) {
+        if self.breadcrumb_area.is_some_and(|area| area.contains(position)) {
+            self.copy_breadcrumb_location();
+            return;
+        }
+
+        let in_other_pane = self.split.as_ref().is_some_and(|pane| {
+            pane.diagram_content_area.is_some_and(|area| area.contains(position))
+        });
+        if in_other_pane {
+            self.focus_other_pane();
+        }
+        if let Some(area) = self.diagram_content_area.filter(|area| area.contains(position)) {
+            self.focus = Focus::Diagram;
+            // Negative pan is rendered as padding, so the same offset maps both cases.
+            let col = self.pan_x + i32::from(position.x - area.x);
+            let row = self.pan_y + i32::from(position.y - area.y);
+            let (Ok(row), Ok(col)) = (usize::try_from(row), usize::try_from(col)) else {
+                return;
+            };
+            if let Some(object_ref) = self.object_ref_at(row, col) {
+                self.select_object_ref(&object_ref);
+            }
+            return;
+        }
+
+        if let Some(visible_idx) = self.objects_hit_area.and_then(|hit| hit.row_at(position)) {
+            self.focus = Focus::Objects;
+            if visible_idx < self.visible_object_indices().len() {
+                self.objects_state.select(Some(visible_idx));
+                if self.xrefs_involving_only {
+                    self.apply_xref_filters();
+                }
+                self.publish_focus_to_ui_state();
+            }
             return;
-        };
-        let target_ref = self.xrefs[first_idx].xref.to().clone();
-        self.jump_to_object_ref(&target_ref);
+        }
 
-        if matches.len() > 1 {
-            let xref_id = &self.xrefs[first_idx].xref_id;
-            self.set_toast(format!("{} outgoing xrefs; followed first ({xref_id})", matches.len()));
+        if let Some(visible_idx) = self.xrefs_hit_area.and_then(|hit| hit.row_at(position)) {
+            self.focus = Focus::XRefs;
+            if visible_idx < self.visible_xref_indices().len() {
+                self.xrefs_state.select(Some(visible_idx));
+            }
         }
     }
 
-    fn jump_to_selected_incoming_xref(&mut self) {
-        let Some(selected_ref) = self.selected_ref().cloned() else {
-            self.set_toast("No object selected");
-            return;
-        };
-
-        let matches = self
-            .xrefs
+    /// Object drawn at diagram cell `(row, col)`; the narrowest span wins where objects overlap,
+    /// so the most specific object under the cursor is picked.
+    fn object_ref_at(&self, row: usize, col: usize) -> Option<ObjectRef> {
+        self.base_highlight_index
             .iter()
-            .enumerate()
-            .filter(|(_, xref)| xref.xref.to() == &selected_ref)
-            .map(|(idx, _)| idx)
-            .collect::<Vec<_>>();
+            .filter_map(|(object_ref, spans)| {
+                spans
+                    .iter()
+                    .filter(|&&(y, x0, x1)| y == row && (x0..=x1).contains(&col))
+                    .map(|&(_, x0, x1)| x1 - x0)
+                    .min()
+                    .map(|width| (width, object_ref))
+            })
+            .min_by_key(|(width, _)| *width)
+            .map(|(_, object_ref)| object_ref.clone())
+    }
 
-        let Some(first_idx) = matches.first().copied() else {
-            self.set_toast(format!("No incoming xref for {selected_ref}"));
+    fn copy_breadcrumb_location(&mut self) {
+        let Some(location) = breadcrumb_location(self) else {
+            self.set_toast(tr("toast.no_location_to_copy"));
             return;
         };
-        let source_ref = self.xrefs[first_idx].xref.from().clone();
-        self.jump_to_object_ref(&source_ref);
 
-        if matches.len() > 1 {
-            let xref_id = &self.xrefs[first_idx].xref_id;
-            self.set_toast(format!("{} incoming xrefs; followed first ({xref_id})", matches.len()));
+        match copy_to_clipboard(&location) {
+            Ok(backend) => {
+                self.set_toast(trf(
+                    "toast.copied",
+                    &[("location", &location), ("backend", &backend)],
+                ));
+            }
+            Err(err) => {
+                self.set_toast(trf("toast.clipboard_error", &[("err", &err)]));
+            }
         }
     }
 
-    fn set_toast(&mut self, message: impl Into<String>) {
-        self.toast = Some(Toast {
-            message: message.into(),
-            expires_at: Instant::now() + Duration::from_secs(2),
-        });
-    }
-
     fn yank_selected_object_ref(&mut self) {
         let Some(object_ref) = self.selected_ref() else {
-            self.set_toast("No object selected");
+            self.set_toast(tr("toast.no_object_selected"));
             return;
         };
 
         let object_ref = object_ref.to_string();
         match copy_to_clipboard(&object_ref) {
             Ok(backend) => {
-                self.set_toast(format!("Yanked object ref ({backend})"));
+                self.set_toast(trf("toast.yanked_object_ref", &[("backend", &backend)]));
             }
             Err(err) => {
-                self.set_toast(format!("Clipboard error: {err}"));
+                self.set_toast(trf("toast.clipboard_error", &[("err", &err)]));
             }
         }
     }
@@ -2456,7 +6475,7 @@ impl TerminalSession {
         enable_raw_mode()?;
 
         let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen).map_err(|err| {
+        execute!(stdout, EnterAlternateScreen, EnableMouseCapture).map_err(|err| {
             teardown_terminal();
             err
         })?;
@@ -2505,9 +6524,11 @@ impl<'a> TerminalSuspendGuard<'a> {
         terminal.show_cursor()?;
         disable_raw_mode()?;
 
-        if let Err(err) = execute!(terminal.backend_mut(), LeaveAlternateScreen) {
+        if let Err(err) =
+            execute!(terminal.backend_mut(), DisableMouseCapture, LeaveAlternateScreen)
+        {
             let _ = enable_raw_mode();
-            let _ = execute!(terminal.backend_mut(), EnterAlternateScreen);
+            let _ = execute!(terminal.backend_mut(), EnterAlternateScreen, EnableMouseCapture);
             let _ = terminal.hide_cursor();
             let _ = ratatui::backend::Backend::flush(terminal.backend_mut());
             return Err(err);
@@ -2521,7 +6542,7 @@ impl<'a> TerminalSuspendGuard<'a> {
 impl Drop for TerminalSuspendGuard<'_> {
     fn drop(&mut self) {
         let _ = enable_raw_mode();
-        let _ = execute!(self.terminal.backend_mut(), EnterAlternateScreen);
+        let _ = execute!(self.terminal.backend_mut(), EnterAlternateScreen, EnableMouseCapture);
         let _ = self.terminal.clear();
         let _ = self.terminal.hide_cursor();
         let _ = ratatui::backend::Backend::flush(self.terminal.backend_mut());
@@ -2531,7 +6552,7 @@ impl Drop for TerminalSuspendGuard<'_> {
 fn teardown_terminal() {
     let _ = disable_raw_mode();
     let mut stdout = io::stdout();
-    let _ = execute!(stdout, LeaveAlternateScreen);
+    let _ = execute!(stdout, DisableMouseCapture, LeaveAlternateScreen);
 }
 
 fn copy_to_clipboard(text: &str) -> Result<&'static str, String> {
@@ -2556,8 +6577,59 @@ fn resolve_editor_command() -> String {
         .unwrap_or_else(|| "vi".to_owned())
 }
 
-fn write_temp_mermaid_file(
+/// `NEREID_PASTE_COMMAND` split into a program and its arguments, or the usual clipboard readers
+/// in the order they are tried. Clipboard reads over OSC 52 are not used, since most terminals
+/// refuse them.
+fn resolve_paste_commands() -> Vec<Vec<String>> {
+    if let Some(command) =
+        env::var("NEREID_PASTE_COMMAND").ok().filter(|value| !value.trim().is_empty())
+    {
+        return vec![command.split_whitespace().map(ToOwned::to_owned).collect()];
+    }
+    [
+        &["pbpaste"][..],
+        &["wl-paste", "--no-newline"],
+        &["xclip", "-selection", "clipboard", "-o"],
+        &["xsel", "--clipboard", "--output"],
+    ]
+    .iter()
+    .map(|argv| argv.iter().map(|arg| (*arg).to_owned()).collect())
+    .collect()
+}
+
+/// Runs each command directly, without a shell, and returns the output of the first that
+/// succeeds.
+fn read_clipboard(commands: &[Vec<String>]) -> Result<String, String> {
+    let mut last_error = "no paste command configured".to_owned();
+    for argv in commands {
+        let Some((program, args)) = argv.split_first() else {
+            continue;
+        };
+        let output = match Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+        {
+            Ok(output) => output,
+            Err(err) => {
+                last_error = format!("failed to run paste command `{program}`: {err}");
+                continue;
+            }
+        };
+        if !output.status.success() {
+            last_error = format!("paste command `{program}` failed with status {}", output.status);
+            continue;
+        }
+        return String::from_utf8(output.stdout)
+            .map_err(|_| "clipboard does not hold text".to_owned());
+    }
+    Err(last_error)
+}
+
+fn write_temp_editor_file(
     diagram_id: &DiagramId,
+    extension: &str,
     content: &str,
 ) -> Result<std::path::PathBuf, String> {
     let ts = SystemTime::now()
@@ -2570,10 +6642,9 @@ fn write_temp_mermaid_file(
         .chars()
         .map(|ch| if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' { ch } else { '_' })
         .collect::<String>();
-    temp_path.push(format!("nereid-{safe_id}-{ts}.mmd"));
-    fs::write(&temp_path, content).map_err(|err| {
-        format!("failed to create temporary Mermaid file {}: {err}", temp_path.display())
-    })?;
+    temp_path.push(format!("nereid-{safe_id}-{ts}.{extension}"));
+    fs::write(&temp_path, content)
+        .map_err(|err| format!("failed to create temporary file {}: {err}", temp_path.display()))?;
     Ok(temp_path)
 }
 
@@ -2604,17 +6675,31 @@ fn export_diagram_mermaid(diagram: &Diagram) -> Result<String, String> {
             .map_err(|err| format!("failed to export sequence Mermaid: {err}")),
         DiagramAst::Flowchart(ast) => export_flowchart(ast)
             .map_err(|err| format!("failed to export flowchart Mermaid: {err}")),
+        DiagramAst::State(ast) => export_state_diagram(ast)
+            .map_err(|err| format!("failed to export state Mermaid: {err}")),
+        DiagramAst::Class(ast) => export_class_diagram(ast)
+            .map_err(|err| format!("failed to export class Mermaid: {err}")),
     }
 }
 
-fn parse_mermaid_for_kind(kind: DiagramKind, source: &str) -> Result<DiagramAst, String> {
+fn parse_mermaid_for_kind(
+    kind: DiagramKind,
+    source: &str,
+    id_rules: &[IdPrefixRule],
+) -> Result<DiagramAst, String> {
     match kind {
-        DiagramKind::Sequence => parse_sequence_diagram(source)
+        DiagramKind::Sequence => parse_sequence_diagram_with_id_rules(source, id_rules)
             .map(DiagramAst::Sequence)
             .map_err(|err| format!("sequence parse failed: {err}")),
-        DiagramKind::Flowchart => parse_flowchart(source)
+        DiagramKind::Flowchart => parse_flowchart_with_id_rules(source, id_rules)
             .map(DiagramAst::Flowchart)
             .map_err(|err| format!("flowchart parse failed: {err}")),
+        DiagramKind::State => parse_state_diagram(source)
+            .map(DiagramAst::State)
+            .map_err(|err| format!("state parse failed: {err}")),
+        DiagramKind::Class => parse_class_diagram(source)
+            .map(DiagramAst::Class)
+            .map_err(|err| format!("class parse failed: {err}")),
     }
 }
 
@@ -2623,16 +6708,234 @@ fn ensure_active_diagram_id(session: &mut Session) -> Option<DiagramId> {
         return Some(active);
     }
 
-    let first = session.diagrams().keys().next().cloned()?;
+    let first = session
+        .listed_diagram_ids()
+        .first()
+        .copied()
+        .or_else(|| session.diagrams().keys().next())
+        .cloned()?;
 
     session.set_active_diagram_id(Some(first.clone()));
     Some(first)
 }
 
-fn render_diagram_annotated(diagram: &Diagram, options: RenderOptions) -> (String, HighlightIndex) {
-    match crate::render::diagram::render_diagram_unicode_annotated_with_options(diagram, options) {
-        Ok(rendered) => (rendered.text, rendered.highlight_index),
-        Err(err) => (format!("Diagram render error:\n{err}"), HighlightIndex::new()),
+fn parse_tui_command(input: &str) -> Result<TuiCommand, String> {
+    let input = input.trim();
+    let (name, args) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+    match name {
+        "goto" => {
+            let usage = || "Usage: goto <row>,<col>".to_owned();
+            let (row, col) = args.split_once(',').ok_or_else(usage)?;
+            let row = row.trim().parse::<usize>().map_err(|_| usage())?;
+            let col = col.trim().parse::<usize>().map_err(|_| usage())?;
+            Ok(TuiCommand::Goto { row, col })
+        }
+        "path" => {
+            let mut nodes = args.split_whitespace();
+            match (nodes.next(), nodes.next(), nodes.next()) {
+                (Some(from), Some(to), None) => {
+                    Ok(TuiCommand::Path { from: from.to_owned(), to: to.to_owned() })
+                }
+                _ => Err("Usage: path <from> <to>".to_owned()),
+            }
+        }
+        "critical" if args.trim().is_empty() => Ok(TuiCommand::Critical),
+        "critical" => Err("Usage: critical".to_owned()),
+        "cuts" if args.trim().is_empty() => Ok(TuiCommand::Cuts),
+        "cuts" => Err("Usage: cuts".to_owned()),
+        "tidy" if args.trim().is_empty() => Ok(TuiCommand::Tidy),
+        "tidy" => Err("Usage: tidy".to_owned()),
+        "matcher" if args.trim().is_empty() => Ok(TuiCommand::Matcher(None)),
+        "matcher" => FuzzyMatcher::parse(args)
+            .map(|matcher| TuiCommand::Matcher(Some(matcher)))
+            .ok_or_else(|| "Usage: matcher [nereid|skim]".to_owned()),
+        "edges" if args.trim().is_empty() => Ok(TuiCommand::Edges(None)),
+        "edges" => FlowchartEdgeGlyphs::parse(args)
+            .map(|glyphs| TuiCommand::Edges(Some(glyphs)))
+            .ok_or_else(|| "Usage: edges [box|braille|blocks]".to_owned()),
+        "a11y" if args.trim().is_empty() => Ok(TuiCommand::A11y),
+        "a11y" => Err("Usage: a11y".to_owned()),
+        "archive" if args.trim().is_empty() => Ok(TuiCommand::Archive(true)),
+        "unarchive" if args.trim().is_empty() => Ok(TuiCommand::Archive(false)),
+        "lock" if args.trim().is_empty() => Ok(TuiCommand::ReadOnly(true)),
+        "unlock" if args.trim().is_empty() => Ok(TuiCommand::ReadOnly(false)),
+        "archive" | "unarchive" | "lock" | "unlock" => Err(format!("Usage: {name}")),
+        "toseq" if args.trim().is_empty() => Ok(TuiCommand::ToSeq),
+        "toflow" if args.trim().is_empty() => Ok(TuiCommand::ToFlow),
+        "toseq" | "toflow" => Err(format!("Usage: {name}")),
+        "style" => match args.trim() {
+            "" => Ok(TuiCommand::StyleList),
+            "clear" => Ok(TuiCommand::StyleClear),
+            rule => rule
+                .parse::<StyleRule>()
+                .map(TuiCommand::StyleAdd)
+                .map_err(|err| format!("Usage: style [<rule>|clear] ({err})")),
+        },
+        "idprefix" => match args.trim() {
+            "" => Ok(TuiCommand::IdPrefixList),
+            "clear" => Ok(TuiCommand::IdPrefixClear),
+            rule => rule
+                .parse::<IdPrefixRule>()
+                .map(TuiCommand::IdPrefixAdd)
+                .map_err(|err| format!("Usage: idprefix [<rule>|clear] ({err})")),
+        },
+        "lint" if args.trim().is_empty() => Ok(TuiCommand::LintIds),
+        "lint" => Err("Usage: lint".to_owned()),
+        "check" if args.trim().is_empty() => Ok(TuiCommand::Check),
+        "check" => Err("Usage: check".to_owned()),
+        "paste-new" if args.trim().is_empty() => Ok(TuiCommand::PasteNew),
+        "paste-new" => Err("Usage: paste-new".to_owned()),
+        "sessions" => match args.split_whitespace().collect::<Vec<_>>()[..] {
+            [] => Ok(TuiCommand::Sessions(None)),
+            [name] => Ok(TuiCommand::Sessions(Some(name.to_owned()))),
+            _ => Err("Usage: sessions [<name>]".to_owned()),
+        },
+        "proposal" => match args.split_whitespace().collect::<Vec<_>>()[..] {
+            [] => Ok(TuiCommand::Proposal(None)),
+            [proposal_id] => Ok(TuiCommand::Proposal(Some(proposal_id.to_owned()))),
+            _ => Err("Usage: proposal [<proposal_id>]".to_owned()),
+        },
+        "accept" if args.trim().is_empty() => Ok(TuiCommand::AcceptProposal),
+        "discard" if args.trim().is_empty() => Ok(TuiCommand::DiscardProposal),
+        "accept" | "discard" => Err(format!("Usage: {name}")),
+        "note" => match args.trim() {
+            "" => Ok(TuiCommand::Note(None)),
+            text => Ok(TuiCommand::Note(Some(text.to_owned()))),
+        },
+        "unnote" if args.trim().is_empty() => Ok(TuiCommand::Unnote),
+        "unnote" => Err("Usage: unnote".to_owned()),
+        "diff" => match args.trim() {
+            "" => Ok(TuiCommand::Diff(None)),
+            "current" => Ok(TuiCommand::Diff(Some(DiffSide::Current))),
+            "saved" => Ok(TuiCommand::Diff(Some(DiffSide::Saved))),
+            _ => Err("Usage: diff [current|saved]".to_owned()),
+        },
+        "unpin" => match args.trim() {
+            "" => Ok(TuiCommand::Unpin(false)),
+            "all" => Ok(TuiCommand::Unpin(true)),
+            _ => Err("Usage: unpin [all]".to_owned()),
+        },
+        "filter" => match args.trim() {
+            "" => Ok(TuiCommand::Filter(None)),
+            filter => match filter.split_once('=') {
+                Some(("tag", tag)) if !tag.trim().is_empty() => {
+                    Ok(TuiCommand::Filter(Some(tag.trim().to_owned())))
+                }
+                _ => Err("Usage: filter [tag=<tag>]".to_owned()),
+            },
+        },
+        "open" => match args.split_whitespace().collect::<Vec<_>>()[..] {
+            [diagram_id] => Ok(TuiCommand::Open(diagram_id.to_owned())),
+            _ => Err("Usage: open <diagram_id>".to_owned()),
+        },
+        _ => Err(format!("Unknown command: {name}")),
+    }
+}
+
+fn view_request_summary(request: &ViewRequest) -> String {
+    match request {
+        ViewRequest::CenterOn(object_ref) => format!("center on {object_ref}"),
+        ViewRequest::Scroll { dx, dy } => format!("scroll by {dx},{dy}"),
+        ViewRequest::SetActiveDiagram(diagram_id) => format!("show {diagram_id}"),
+        ViewRequest::Zoom(zoom) => format!("zoom {zoom:?}").to_lowercase(),
+    }
+}
+
+/// Range of list rows to materialize for a panel of `rows` visible rows.
+///
+/// Outside large mode every row is built. In large mode only one screenful around `cursor` is,
+/// so panels over huge diagrams cost the same as small ones.
+fn lazy_list_window(
+    len: usize,
+    cursor: Option<usize>,
+    rows: usize,
+    large_mode: bool,
+) -> std::ops::Range<usize> {
+    if !large_mode || len <= rows {
+        return 0..len;
+    }
+    let cursor = cursor.unwrap_or(0).min(len.saturating_sub(1));
+    let start = cursor.saturating_sub(rows / 2).min(len - rows);
+    start..start + rows
+}
+
+/// Crops `text` to the `width`×`height` viewport whose top-left cell is diagram cell
+/// `(pan_x, pan_y)`, returning one line per viewport row plus the visible spans of every object,
+/// relative to the viewport.
+fn visible_diagram_cells(
+    text: &str,
+    highlight_index: &HighlightIndex,
+    (pan_x, pan_y): (i32, i32),
+    (width, height): (usize, usize),
+) -> (Vec<String>, BTreeMap<ObjectRef, Vec<LineSpan>>) {
+    let (pan_x, pan_y) = (i64::from(pan_x), i64::from(pan_y));
+    let (width, height) = (width as i64, height as i64);
+    let text_lines = text.split('\n').collect::<Vec<_>>();
+
+    let lines = (pan_y..pan_y + height)
+        .map(|y| {
+            let Some(line) = usize::try_from(y).ok().and_then(|y| text_lines.get(y)) else {
+                return String::new();
+            };
+            let left_pad = (-pan_x).clamp(0, width) as usize;
+            let skip = pan_x.max(0) as usize;
+            let take = (width - left_pad as i64).max(0) as usize;
+            let visible = line.chars().skip(skip).take(take).collect::<String>();
+            let visible = visible.trim_end();
+            if visible.is_empty() {
+                String::new()
+            } else {
+                format!("{}{visible}", " ".repeat(left_pad))
+            }
+        })
+        .collect();
+
+    let objects = highlight_index
+        .iter()
+        .filter_map(|(object_ref, spans)| {
+            let spans = spans
+                .iter()
+                .filter_map(|&(y, x0, x1)| {
+                    let (y, x0, x1) = (y as i64 - pan_y, x0 as i64 - pan_x, x1 as i64 - pan_x);
+                    if !(0..height).contains(&y) || x1 < 0 || x0 >= width {
+                        return None;
+                    }
+                    Some((y as usize, x0.max(0) as usize, x1.min(width - 1) as usize))
+                })
+                .collect::<Vec<_>>();
+            (!spans.is_empty()).then(|| (object_ref.clone(), spans))
+        })
+        .collect();
+
+    (lines, objects)
+}
+
+/// Returns the inclusive `((x0, x1), (y0, y1))` bounding box of `spans`.
+fn line_spans_bounds(spans: &[LineSpan]) -> Option<((usize, usize), (usize, usize))> {
+    let y0 = spans.iter().map(|&(y, _, _)| y).min()?;
+    let y1 = spans.iter().map(|&(y, _, _)| y).max()?;
+    let x0 = spans.iter().map(|&(_, x0, _)| x0).min()?;
+    let x1 = spans.iter().map(|&(_, _, x1)| x1).max()?;
+    Some(((x0, x1), (y0, y1)))
+}
+
+/// Returns the `(row, col)` center of the bounding box of `spans`.
+fn line_spans_center(spans: &[LineSpan]) -> Option<(usize, usize)> {
+    let ((x0, x1), (y0, y1)) = line_spans_bounds(spans)?;
+    Some(((y0 + y1) / 2, (x0 + x1) / 2))
+}
+
+/// Returns the pan along one axis that shows `start..=end` with the smallest move from `pan`.
+fn scroll_axis_into_view(pan: i32, start: usize, end: usize, viewport: usize) -> i32 {
+    let extent = end - start + 1;
+    let margin = FOCUS_FOLLOW_MARGIN.min(viewport.saturating_sub(extent) / 2) as i32;
+    let (start, end, viewport) = (start as i32, end as i32, viewport as i32);
+    if start - margin < pan || end - start + 1 > viewport {
+        start - margin
+    } else if end + margin > pan + viewport - 1 {
+        end + margin - viewport + 1
+    } else {
+        pan
     }
 }
 
@@ -2725,26 +7028,89 @@ fn prefix_xref_direction_labels_for_tui(diagram: &mut Diagram, session: &Session
                 *message = updated;
             }
         }
+        DiagramAst::State(state_ast) => {
+            let state_category = category_path(&["state", "node"]);
+            for (state_id, state) in state_ast.states_mut() {
+                let object_ref = ObjectRef::new(
+                    diagram.diagram_id().clone(),
+                    state_category.clone(),
+                    state_id.clone(),
+                );
+                let has_incoming = incoming_refs.contains(&object_ref);
+                let has_outgoing = outgoing_refs.contains(&object_ref);
+                let label = prefixed_direction_label(state.label(), has_incoming, has_outgoing);
+                state.set_label(label);
+            }
+        }
+        DiagramAst::Class(class_ast) => {
+            let class_category = category_path(&["class", "node"]);
+            for (class_id, class) in class_ast.classes_mut() {
+                let object_ref = ObjectRef::new(
+                    diagram.diagram_id().clone(),
+                    class_category.clone(),
+                    class_id.clone(),
+                );
+                let has_incoming = incoming_refs.contains(&object_ref);
+                let has_outgoing = outgoing_refs.contains(&object_ref);
+                let label = prefixed_direction_label(class.label(), has_incoming, has_outgoing);
+                class.set_label(label);
+            }
+        }
     }
 
     diagram.set_ast(ast).expect("diagram kind should remain unchanged");
 }
 
+/// Renders `diagram` for the TUI within `budget`; `forced_overrun` skips straight to
+/// level-of-detail rendering for a diagram already known to be over budget.
+#[allow(clippy::too_many_arguments)]
 fn render_diagram_annotated_for_tui(
+    renderer: &mut IncrementalRenderer,
     session: &Session,
     diagram: &Diagram,
     show_notes: bool,
-) -> (String, HighlightIndex) {
+    zoom: ViewZoom,
+    edge_glyphs: FlowchartEdgeGlyphs,
+    budget: &RenderBudget,
+    forced_overrun: Option<BudgetOverrun>,
+) -> BudgetedRender {
     let mut render_diagram = diagram.clone();
     prefix_xref_direction_labels_for_tui(&mut render_diagram, session);
-    render_diagram_annotated(
-        &render_diagram,
-        RenderOptions {
-            show_notes,
-            prefix_object_labels: false,
-            flowchart_extra_col_gap: TUI_FLOWCHART_EXTRA_COL_GAP,
+    let (flowchart_extra_col_gap, flowchart_compact, flowchart_node_scale) = match zoom {
+        ViewZoom::Glyph => (0, true, FlowchartNodeScale::Glyph),
+        ViewZoom::Mini => (0, true, FlowchartNodeScale::Mini),
+        ViewZoom::Compact => (0, true, FlowchartNodeScale::Full),
+        ViewZoom::Normal => (TUI_FLOWCHART_EXTRA_COL_GAP, false, FlowchartNodeScale::Full),
+        ViewZoom::Spacious => (TUI_FLOWCHART_SPACIOUS_COL_GAP, false, FlowchartNodeScale::Full),
+    };
+    let options = RenderOptions {
+        // Squashed nodes have no room left for their notes.
+        show_notes: show_notes && flowchart_node_scale == FlowchartNodeScale::Full,
+        prefix_object_labels: false,
+        flowchart_extra_col_gap,
+        flowchart_compact,
+        flowchart_node_scale,
+        flowchart_edge_glyphs: edge_glyphs,
+        flowchart_crossing_sweeps: budget.crossing_sweeps,
+    };
+    let result = match forced_overrun {
+        Some(overrun) => {
+            renderer.render_annotated_timed(&render_diagram, level_of_detail_options(options)).map(
+                |(rendered, timings)| BudgetedRender { rendered, timings, overrun: Some(overrun) },
+            )
+        }
+        None => {
+            render_diagram_within_budget_incremental(renderer, &render_diagram, options, budget)
+        }
+    };
+    result.unwrap_or_else(|err| BudgetedRender {
+        rendered: AnnotatedRender {
+            text: format!("Diagram render error:\n{err}"),
+            highlight_index: HighlightIndex::new(),
         },
-    )
+        timings: RenderTimings::default(),
+        overrun: None,
+    })
 }
 
 fn apply_highlight_flags(flags_by_line: &mut [Vec<u8>], spans: &[LineSpan], flag: u8) {
@@ -2766,11 +7132,11 @@ fn apply_highlight_flags(flags_by_line: &mut [Vec<u8>], spans: &[LineSpan], flag
 }
 
 fn is_box_drawing_verticalish(ch: char) -> bool {
-    matches!(ch, '│' | '┌' | '┐' | '└' | '┘' | '├' | '┤' | '┬' | '┴' | '┼')
+    matches!(ch, '│' | '┆' | '┃' | '┌' | '┐' | '└' | '┘' | '├' | '┤' | '┬' | '┴' | '┼')
 }
 
 fn is_box_drawing_horizontalish(ch: char) -> bool {
-    matches!(ch, '─' | '┌' | '┐' | '└' | '┘' | '├' | '┤' | '┬' | '┴' | '┼')
+    matches!(ch, '─' | '┄' | '━' | '┌' | '┐' | '└' | '┘' | '├' | '┤' | '┬' | '┴' | '┼')
 }
 
 fn fill_highlight_bridge_gaps(flags_by_line: &mut [Vec<u8>], diagram: &str, flag: u8) {
@@ -2900,6 +7266,20 @@ fn is_flow_edge_ref(object_ref: &ObjectRef) -> bool {
     )
 }
 
+fn is_state_transition_ref(object_ref: &ObjectRef) -> bool {
+    matches!(
+        object_ref.category().segments(),
+        [a, b] if a == "state" && b == "transition"
+    )
+}
+
+fn is_class_relation_ref(object_ref: &ObjectRef) -> bool {
+    matches!(
+        object_ref.category().segments(),
+        [a, b] if a == "class" && b == "relation"
+    )
+}
+
 fn is_sequence_block_or_section_ref(object_ref: &ObjectRef) -> bool {
     matches!(
         object_ref.category().segments(),
@@ -2911,6 +7291,90 @@ fn is_sequence_section_ref(object_ref: &ObjectRef) -> bool {
     matches!(object_ref.category().segments(), [a, b] if a == "seq" && b == "section")
 }
 
+/// The note of the object `object_ref` names in `ast`: `None` when it cannot carry one,
+/// `Some(None)` when it can but has none.
+///
+/// Flowchart note refs name their node, so they edit the node's note.
+fn selected_object_note(ast: &DiagramAst, object_ref: &ObjectRef) -> Option<Option<String>> {
+    let object_id = object_ref.object_id();
+    match (ast, object_ref.category().segments()) {
+        (DiagramAst::Flowchart(ast), [a, b]) if a == "flow" && (b == "node" || b == "note") => {
+            let node = ast.nodes().get(object_id)?;
+            Some(node.note().map(ToOwned::to_owned))
+        }
+        (DiagramAst::Sequence(ast), [a, b]) if a == "seq" && b == "participant" => {
+            let participant = ast.participants().get(object_id)?;
+            Some(participant.note().map(ToOwned::to_owned))
+        }
+        (DiagramAst::Sequence(ast), [a, b]) if a == "seq" && b == "note" => {
+            let note = ast.notes().iter().find(|note| note.note_id() == object_id)?;
+            Some(Some(note.text().to_owned()))
+        }
+        _ => None,
+    }
+}
+
+/// The pin that moves `node_id` one grid step in screen direction `(dx, dy)`, or `None` when
+/// layout would leave it where it is (the layer's end, or edges holding it near its sources).
+fn nudged_pin(
+    ast: &FlowchartAst,
+    node_id: &ObjectId,
+    (dx, dy): (i32, i32),
+    options: FlowchartLayoutOptions,
+) -> Option<FlowNodePin> {
+    // Pins count layers from the sources, whatever the direction.
+    let grid_position = |layout: &FlowchartLayout| {
+        let placement = layout.placement(node_id)?;
+        let layer = if layout.direction().is_reversed() {
+            layout.layers().len() - 1 - placement.layer()
+        } else {
+            placement.layer()
+        };
+        Some((layer, placement.index_in_layer()))
+    };
+
+    let layout = layout_flowchart_with_options(ast, options).ok()?;
+    let (layer, index) = grid_position(&layout)?;
+    let direction = layout.direction();
+    let (along, across) = if direction.is_vertical() { (dy, dx) } else { (dx, dy) };
+    let along = if direction.is_reversed() { -along } else { along };
+    let pin = FlowNodePin::new(
+        u32::try_from(layer.checked_add_signed(along as isize)?).ok()?,
+        u32::try_from(index.checked_add_signed(across as isize)?).ok()?,
+    );
+
+    let mut pinned = ast.clone();
+    pinned.node_pins_mut().insert(node_id.clone(), pin);
+    let moved = layout_flowchart_with_options(&pinned, options).ok()?;
+    (grid_position(&moved)? != (layer, index)).then_some(pin)
+}
+
+/// Ops setting or clearing the note of the object `object_ref` names, or `None` when it cannot
+/// carry one.
+fn object_note_ops(
+    ast: &DiagramAst,
+    object_ref: &ObjectRef,
+    note: Option<String>,
+) -> Option<Vec<Op>> {
+    selected_object_note(ast, object_ref)?;
+    let object_id = object_ref.object_id().clone();
+    let op = match (ast, object_ref.category().segments()) {
+        (DiagramAst::Flowchart(_), _) => Op::Flow(FlowOp::SetNodeNote { node_id: object_id, note }),
+        (DiagramAst::Sequence(_), [_, kind]) if kind == "participant" => {
+            Op::Seq(SeqOp::SetParticipantNote { participant_id: object_id, note })
+        }
+        (DiagramAst::Sequence(_), _) => match note {
+            Some(text) => Op::Seq(SeqOp::UpdateNote {
+                note_id: object_id,
+                patch: SeqNotePatch { text: Some(text), ..SeqNotePatch::default() },
+            }),
+            None => Op::Seq(SeqOp::RemoveNote { note_id: object_id }),
+        },
+        _ => return None,
+    };
+    Some(vec![op])
+}
+
 fn is_note_ref(object_ref: &ObjectRef) -> bool {
     matches!(
         object_ref.category().segments(),
@@ -2918,6 +7382,36 @@ fn is_note_ref(object_ref: &ObjectRef) -> bool {
     )
 }
 
+/// Applies conditional styling rule effects on top of a diagram cell style.
+fn style_for_rule_cell(style: Style, dim: bool, heavy: bool) -> Style {
+    let mut style = style;
+    if dim {
+        style = style.add_modifier(Modifier::DIM);
+    }
+    if heavy {
+        style = style.add_modifier(Modifier::BOLD);
+    }
+    style
+}
+
+/// Maps light box-drawing characters to their heavy counterparts.
+fn heavy_box_char(ch: char) -> char {
+    match ch {
+        '─' => '━',
+        '│' => '┃',
+        '┌' | '╭' => '┏',
+        '┐' | '╮' => '┓',
+        '└' | '╰' => '┗',
+        '┘' | '╯' => '┛',
+        '├' => '┣',
+        '┤' => '┫',
+        '┬' => '┳',
+        '┴' => '┻',
+        '┼' => '╋',
+        other => other,
+    }
+}
+
 fn apply_presence_flags(flags_by_line: &mut [Vec<bool>], spans: &[LineSpan]) {
     for (y, x0, x1) in spans {
         let Some(line) = flags_by_line.get_mut(*y) else {
@@ -3131,6 +7625,10 @@ fn is_box_drawing_char(ch: char) -> bool {
             | crate::render::UNICODE_BOX_TEE_DOWN
             | crate::render::UNICODE_BOX_TEE_UP
             | crate::render::UNICODE_BOX_CROSS
+            | crate::render::UNICODE_DOTTED_HORIZONTAL
+            | crate::render::UNICODE_DOTTED_VERTICAL
+            | crate::render::UNICODE_THICK_HORIZONTAL
+            | crate::render::UNICODE_THICK_VERTICAL
     )
 }
 
@@ -3421,77 +7919,671 @@ fn apply_hint_target_to_line(
         line_chars[new_label_start + offset] = ch;
     }
 
-    line_chars[space_x] = ' ';
-    style_overrides[space_x] = None;
-    line_chars[tag_x0] = target.label[0];
-    line_chars[tag_x1] = target.label[1];
+    line_chars[space_x] = ' ';
+    style_overrides[space_x] = None;
+    line_chars[tag_x0] = target.label[0];
+    line_chars[tag_x1] = target.label[1];
+
+    if typed_first.is_some_and(|first| first == target.label[0]) {
+        style_overrides[tag_x0] = Some(typed_style);
+        style_overrides[tag_x1] = Some(base_style);
+    } else {
+        style_overrides[tag_x0] = Some(base_style);
+        style_overrides[tag_x1] = Some(base_style);
+    }
+}
+
+fn style_for_highlight_flag(
+    flag: u8,
+    has_active_selection_in_diagram: bool,
+    focus_owner: FocusOwner,
+) -> Style {
+    let selected = flag & 0b100 != 0;
+    let background_flags = flag & 0b011;
+    let in_focus = background_flags != 0;
+
+    let base = Style::default().add_modifier(Modifier::BOLD);
+    let focus_bg = focus_color_for_owner(focus_owner);
+    let mut style = match background_flags {
+        0b01 => base.fg(theme().selection_fg).bg(focus_bg),
+        0b10 => base.fg(theme().selection_fg).bg(theme().agent_focus),
+        0b11 => base.fg(theme().selection_fg).bg(theme().agent_focus),
+        _ => Style::default(),
+    };
+
+    if selected {
+        style = if in_focus {
+            base.fg(theme().selection_fg).bg(theme().focus)
+        } else {
+            base.fg(theme().selection_fg).bg(theme().selection_bg)
+        };
+    } else if background_flags == 0 && has_active_selection_in_diagram {
+        style = Style::default().fg(theme().dimmed);
+    }
+
+    style
+}
+
+#[allow(clippy::too_many_arguments)]
+fn style_for_diagram_cell(
+    flag: u8,
+    has_active_selection_in_diagram: bool,
+    focus_owner: FocusOwner,
+    is_note_cell: bool,
+    is_sequence_block_cell: bool,
+    sequence_block_color: Color,
+    is_sequence_area_cell: bool,
+    sequence_area_bg: Color,
+) -> Style {
+    let mut style = style_for_highlight_flag(flag, has_active_selection_in_diagram, focus_owner);
+
+    if flag & 0b111 == 0 {
+        if is_sequence_area_cell {
+            style = style.bg(sequence_area_bg);
+        }
+        if is_sequence_block_cell {
+            style = style.fg(sequence_block_color);
+        }
+        if is_note_cell {
+            style = style.fg(theme().note);
+        }
+    }
+
+    style
+}
+
+/// Render duration above which the stats overlay suggests splitting the diagram.
+const STATS_SLOW_RENDER: Duration = Duration::from_millis(50);
+
+/// Legend overlay lines for the active diagram: a swatch drawn in the diagram's own style, then
+/// what it means.
+fn diagram_legend_lines(app: &App) -> Vec<Line<'static>> {
+    let Some(diagram) = app.active_diagram_id().and_then(|id| app.session.diagrams().get(id))
+    else {
+        return vec![Line::from("No active diagram")];
+    };
+    let entries = diagram_legend(&app.session, diagram);
+    let width = entries.iter().map(|entry| entry.sample.chars().count()).max().unwrap_or(0);
+    entries
+        .into_iter()
+        .map(|entry| {
+            let style = match entry.marker {
+                LegendMarker::XRefDirection => Style::default(),
+                LegendMarker::Note => Style::default().fg(theme().note),
+                LegendMarker::SequenceBlock => Style::default().fg(theme().sequence_block),
+                LegendMarker::Focus => style_for_highlight_flag(0b01, false, app.focus_owner),
+                LegendMarker::AgentHighlight => {
+                    style_for_highlight_flag(0b10, false, app.focus_owner)
+                }
+                LegendMarker::Selection => style_for_highlight_flag(0b100, false, app.focus_owner),
+                LegendMarker::StyleRule(effect) => style_for_rule_cell(
+                    Style::default(),
+                    effect == StyleEffect::Dim,
+                    effect == StyleEffect::Heavy,
+                ),
+            };
+            let padding = width - entry.sample.chars().count();
+            let mut spans = entry
+                .sample
+                .chars()
+                .map(|ch| Span::styled(ch.to_string(), style_for_diagram_char(style, ch)))
+                .collect::<Vec<_>>();
+            spans.push(Span::raw(format!("{:padding$}  {}", "", entry.meaning)));
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// One line per workspace session, marking the highlighted (`>`) and the open (`*`) one.
+fn session_picker_lines(picker: &SessionPicker, active: Option<&str>) -> Vec<String> {
+    let mut lines = picker
+        .sessions
+        .iter()
+        .enumerate()
+        .map(|(index, session)| {
+            let cursor = if index == picker.index { '>' } else { ' ' };
+            let open = if active == Some(session.name.as_str()) { '*' } else { ' ' };
+            let title =
+                session.title.as_deref().map(|title| format!(" {title} ·")).unwrap_or_default();
+            format!("{cursor}{open} {}{title} {} diagram(s)", session.name, session.diagram_count)
+        })
+        .collect::<Vec<_>>();
+    lines.push(String::new());
+    lines.push("Enter open · Esc close".to_owned());
+    lines
+}
+
+/// One line per `:check` finding: severity, code and what is wrong.
+fn check_report_lines(diagnostics: &[Diagnostic]) -> Vec<String> {
+    diagnostics
+        .iter()
+        .map(|diagnostic| {
+            format!("{} {}: {}", diagnostic.severity(), diagnostic.code, diagnostic.message)
+        })
+        .collect()
+}
+
+/// Size and render cost lines for the stats overlay of the active diagram.
+fn diagram_stats_lines(app: &App) -> Vec<String> {
+    let Some(diagram) = app.active_diagram_id().and_then(|id| app.session.diagrams().get(id))
+    else {
+        return vec!["No active diagram".to_owned()];
+    };
+    let counts = match diagram.ast() {
+        DiagramAst::Flowchart(ast) => {
+            format!("{} nodes · {} edges", ast.nodes().len(), ast.edges().len())
+        }
+        DiagramAst::Sequence(ast) => {
+            format!("{} participants · {} messages", ast.participants().len(), ast.messages().len())
+        }
+        DiagramAst::State(ast) => {
+            format!("{} states · {} transitions", ast.states().len(), ast.transitions().len())
+        }
+        DiagramAst::Class(ast) => {
+            format!("{} classes · {} relations", ast.classes().len(), ast.relations().len())
+        }
+    };
+    let (width, height) = app.diagram_extent();
+    let spans = app.base_highlight_index.values().map(Vec::len).sum::<usize>();
+    let millis = |duration: Duration| format!("{:.1} ms", duration.as_secs_f64() * 1000.0);
+    let timings = app.render_timings;
+
+    let mut lines = vec![
+        format!("Objects     {counts}"),
+        format!("Canvas      {width}×{height} cells"),
+        format!("Layout      {}", millis(timings.layout)),
+        format!("Render      {}", millis(timings.render)),
+        format!("Highlights  {} objects · {spans} spans", app.base_highlight_index.len()),
+    ];
+    if let Some((_, overrun)) = &app.large_mode {
+        lines.push(format!("Large mode  reduced detail, lazy panels ({overrun})"));
+    } else if timings.layout + timings.render >= STATS_SLOW_RENDER {
+        lines.push("Slow to redraw: consider splitting this diagram".to_owned());
+    }
+    lines
+}
+
+/// The conflicting objects of a pending merge and the keys that settle it.
+fn merge_prompt_lines(prompt: &MergePrompt) -> Vec<String> {
+    let mut lines = vec!["Changed both locally and on disk:".to_owned()];
+    lines.extend(prompt.conflicts.iter().map(|conflict| format!("  {conflict}")));
+    lines.push(String::new());
+    lines.push("l keep local · d keep disk · Esc drop local edit".to_owned());
+    lines
+}
+
+/// Before/after comparison, moved nodes and the preview render of a pending `:tidy`.
+fn tidy_prompt_lines(prompt: &TidyPrompt) -> Vec<String> {
+    let TidyPreview { before, after, moves, preview, .. } = &prompt.preview;
+    let mut lines = vec![
+        format!("Crossings   {} → {}", before.crossings, after.crossings),
+        format!(
+            "Size        {}×{} → {}×{} ({}×{} compacted)",
+            before.width,
+            before.height,
+            after.width,
+            after.height,
+            after.compacted_width,
+            after.compacted_height
+        ),
+    ];
+    for mv in moves {
+        lines.push(format!(
+            "Move        {} (layer {}): {} → {}",
+            mv.node_id,
+            mv.layer + 1,
+            mv.from_index + 1,
+            mv.to_index + 1
+        ));
+    }
+    lines.push(String::new());
+    lines.extend(preview.split('\n').map(str::to_owned));
+    lines.push(String::new());
+    lines.push("Enter/y apply · Esc/n cancel".to_owned());
+    lines
+}
+
+/// Summary lines for the quick-peek popover of `obj`.
+fn object_peek_lines(session: &Session, obj: &SelectableObject) -> Vec<String> {
+    let object_ref = &obj.object_ref;
+    let mut lines = vec![
+        format!("Label: {}", obj.label),
+        format!("Note: {}", obj.note.as_deref().unwrap_or("—")),
+    ];
+
+    let anchors = session
+        .diagrams()
+        .get(object_ref.diagram_id())
+        .map(|diagram| object_anchor_labels(diagram, object_ref))
+        .unwrap_or_default();
+    if !anchors.is_empty() {
+        lines.push(format!("Anchors: {}", anchors.join(", ")));
+    }
+
+    let outgoing = session.xrefs().values().filter(|xref| xref.from() == object_ref);
+    let incoming = session.xrefs().values().filter(|xref| xref.to() == object_ref);
+    for (prefix, xref, other) in outgoing
+        .map(|xref| ("→", xref, xref.to()))
+        .chain(incoming.map(|xref| ("←", xref, xref.from())))
+    {
+        let label = xref.label().map(|label| format!(" ({label})")).unwrap_or_default();
+        lines.push(format!("XRef {prefix} {other} [{}]{label}", xref.kind()));
+    }
+
+    for (walkthrough_id, walkthrough) in session.walkthroughs() {
+        for node in walkthrough.nodes().iter().filter(|node| node.refs().contains(object_ref)) {
+            let tags = if node.tags().is_empty() {
+                String::new()
+            } else {
+                format!(" #{}", node.tags().join(" #"))
+            };
+            lines.push(format!("Walkthrough {walkthrough_id}: {}{tags}", node.title()));
+        }
+    }
+
+    lines
+}
 
-    if typed_first.is_some_and(|first| first == target.label[0]) {
-        style_overrides[tag_x0] = Some(typed_style);
-        style_overrides[tag_x1] = Some(base_style);
-    } else {
-        style_overrides[tag_x0] = Some(base_style);
-        style_overrides[tag_x1] = Some(base_style);
+/// Human-readable names of the objects `object_ref` is attached to (edge/message endpoints, note
+/// anchors, group members, the subgraph containing a flowchart node or subgraph).
+fn object_anchor_labels(diagram: &Diagram, object_ref: &ObjectRef) -> Vec<String> {
+    let category = object_ref.category().segments().join("/");
+    let object_id = object_ref.object_id();
+    match diagram.ast() {
+        DiagramAst::Flowchart(ast) => {
+            let node_label = |node_id: &ObjectId| {
+                ast.nodes()
+                    .get(node_id)
+                    .map_or_else(|| node_id.to_string(), |node| node.label().to_owned())
+            };
+            match category.as_str() {
+                "flow/edge" => ast
+                    .edges()
+                    .get(object_id)
+                    .map(|edge| {
+                        vec![format!(
+                            "{} → {}",
+                            node_label(edge.from_node_id()),
+                            node_label(edge.to_node_id())
+                        )]
+                    })
+                    .unwrap_or_default(),
+                "flow/node" => ast.node_subgraph(object_id).map_or_else(Vec::new, |subgraph_id| {
+                    ast.subgraphs()
+                        .get(subgraph_id)
+                        .map(|subgraph| vec![subgraph.label().to_owned()])
+                        .unwrap_or_default()
+                }),
+                "flow/subgraph" => ast
+                    .subgraphs()
+                    .get(object_id)
+                    .and_then(|subgraph| subgraph.parent())
+                    .and_then(|parent_id| ast.subgraphs().get(parent_id))
+                    .map(|parent| vec![parent.label().to_owned()])
+                    .unwrap_or_default(),
+                _ => Vec::new(),
+            }
+        }
+        DiagramAst::Sequence(ast) => {
+            let participant_label = |participant_id: &ObjectId| {
+                ast.participants().get(participant_id).map_or_else(
+                    || participant_id.to_string(),
+                    |participant| participant.mermaid_name().to_owned(),
+                )
+            };
+            match category.as_str() {
+                "seq/message" => ast
+                    .messages()
+                    .iter()
+                    .find(|message| message.message_id() == object_id)
+                    .map(|message| {
+                        // Found/lost messages show the missing end as the rendered `●`.
+                        let end =
+                            |id: Option<&ObjectId>| id.map_or("●".to_owned(), participant_label);
+                        vec![format!("{} → {}", end(message.sender()), end(message.receiver()))]
+                    })
+                    .unwrap_or_default(),
+                "seq/note" => ast
+                    .notes()
+                    .iter()
+                    .find(|note| note.note_id() == object_id)
+                    .map(|note| note.participant_ids().iter().map(participant_label).collect())
+                    .unwrap_or_default(),
+                "seq/group" => ast
+                    .find_group(object_id)
+                    .map(|group| group.participant_ids().iter().map(participant_label).collect())
+                    .unwrap_or_default(),
+                _ => Vec::new(),
+            }
+        }
+        DiagramAst::State(ast) => match category.as_str() {
+            "state/transition" => ast
+                .transitions()
+                .get(object_id)
+                .map(|transition| {
+                    vec![format!(
+                        "{} → {}",
+                        state_label(ast, transition.from_state_id()),
+                        state_label(ast, transition.to_state_id())
+                    )]
+                })
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        },
+        DiagramAst::Class(ast) => match category.as_str() {
+            "class/relation" => ast
+                .relations()
+                .get(object_id)
+                .map(|relation| {
+                    vec![format!(
+                        "{} → {}",
+                        class_label(ast, relation.from_class_id()),
+                        class_label(ast, relation.to_class_id())
+                    )]
+                })
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        },
     }
 }
 
-fn style_for_highlight_flag(
-    flag: u8,
-    has_active_selection_in_diagram: bool,
-    focus_owner: FocusOwner,
-) -> Style {
-    let selected = flag & 0b100 != 0;
-    let background_flags = flag & 0b011;
-    let in_focus = background_flags != 0;
-
-    let base = Style::default().add_modifier(Modifier::BOLD);
-    let focus_bg = focus_color_for_owner(focus_owner);
-    let mut style = match background_flags {
-        0b01 => base.fg(Color::White).bg(focus_bg),
-        0b10 => base.fg(Color::White).bg(AGENT_FOCUS_COLOR),
-        0b11 => base.fg(Color::White).bg(AGENT_FOCUS_COLOR),
-        _ => Style::default(),
-    };
+/// Structural neighbours, xrefs and containers of `object_ref`, in display order.
+fn object_relation_rows(session: &Session, object_ref: &ObjectRef) -> Vec<RelationRow> {
+    let mut rows = session
+        .diagrams()
+        .get(object_ref.diagram_id())
+        .map(|diagram| structural_relation_rows(diagram, object_ref))
+        .unwrap_or_default();
 
-    if selected {
-        style = if in_focus {
-            Style::default().fg(Color::White).bg(Color::LightGreen).add_modifier(Modifier::BOLD)
+    for xref in session.xrefs().values() {
+        let (tag, other) = if xref.from() == object_ref {
+            ("xref→", xref.to())
+        } else if xref.to() == object_ref {
+            ("xref←", xref.from())
         } else {
-            Style::default().fg(Color::White).bg(Color::DarkGray).add_modifier(Modifier::BOLD)
+            continue;
         };
-    } else if background_flags == 0 && has_active_selection_in_diagram {
-        style = Style::default().fg(Color::DarkGray);
+        let label = xref.label().map(|label| format!(" ({label})")).unwrap_or_default();
+        rows.push(RelationRow {
+            tag,
+            label: format!("{other} [{}]{label}", xref.kind()),
+            target: other.clone(),
+        });
     }
+    rows.sort_by_key(|row| relation_tag_rank(row.tag));
+    rows
+}
 
-    style
+fn relation_tag_rank(tag: &str) -> u8 {
+    match tag {
+        "out" | "from" | "prev" | "member" => 0,
+        "in" | "to" | "next" => 1,
+        "xref→" => 2,
+        "xref←" => 3,
+        _ => 4,
+    }
 }
 
-#[allow(clippy::too_many_arguments)]
-fn style_for_diagram_cell(
-    flag: u8,
-    has_active_selection_in_diagram: bool,
-    focus_owner: FocusOwner,
-    is_note_cell: bool,
-    is_sequence_block_cell: bool,
-    sequence_block_color: Color,
-    is_sequence_area_cell: bool,
-    sequence_area_bg: Color,
-) -> Style {
-    let mut style = style_for_highlight_flag(flag, has_active_selection_in_diagram, focus_owner);
+fn structural_relation_rows(diagram: &Diagram, object_ref: &ObjectRef) -> Vec<RelationRow> {
+    let diagram_id = diagram.diagram_id();
+    let category = object_ref.category().segments().join("/");
+    let object_id = object_ref.object_id();
+    let mut rows = Vec::new();
+    let mut push = |tag: &'static str, label: String, segments: &[&str], id: &ObjectId| {
+        rows.push(RelationRow {
+            tag,
+            label,
+            target: ObjectRef::new(diagram_id.clone(), category_path(segments), id.clone()),
+        });
+    };
 
-    if flag & 0b111 == 0 {
-        if is_sequence_area_cell {
-            style = style.bg(sequence_area_bg);
+    match diagram.ast() {
+        DiagramAst::Flowchart(ast) => {
+            let node_label = |node_id: &ObjectId| {
+                ast.nodes()
+                    .get(node_id)
+                    .map_or_else(|| node_id.to_string(), |node| node.label().to_owned())
+            };
+            let edge_suffix =
+                |label: Option<&str>| label.map(|label| format!(": {label}")).unwrap_or_default();
+            match category.as_str() {
+                "flow/node" => {
+                    for (edge_id, edge) in ast.edges() {
+                        if edge.from_node_id() == object_id {
+                            let label = format!(
+                                "{edge_id} → {}{}",
+                                node_label(edge.to_node_id()),
+                                edge_suffix(edge.label())
+                            );
+                            push("out", label, &["flow", "edge"], edge_id);
+                        }
+                        if edge.to_node_id() == object_id {
+                            let label = format!(
+                                "{edge_id} ← {}{}",
+                                node_label(edge.from_node_id()),
+                                edge_suffix(edge.label())
+                            );
+                            push("in", label, &["flow", "edge"], edge_id);
+                        }
+                    }
+                    for subgraph_id in ast.node_subgraph_chain(object_id).into_iter().rev() {
+                        let label = ast.subgraphs().get(subgraph_id).map_or_else(
+                            || format!("subgraph {subgraph_id}"),
+                            |subgraph| format!("subgraph {}", subgraph.label()),
+                        );
+                        push("within", label, &["flow", "subgraph"], subgraph_id);
+                    }
+                }
+                "flow/subgraph" => {
+                    if let Some(parent_id) =
+                        ast.subgraphs().get(object_id).and_then(|subgraph| subgraph.parent())
+                    {
+                        let label = ast.subgraphs().get(parent_id).map_or_else(
+                            || format!("subgraph {parent_id}"),
+                            |parent| format!("subgraph {}", parent.label()),
+                        );
+                        push("within", label, &["flow", "subgraph"], parent_id);
+                    }
+                    for node_id in ast.subgraph_members(object_id) {
+                        push("member", node_label(&node_id), &["flow", "node"], &node_id);
+                    }
+                }
+                "flow/edge" => {
+                    if let Some(edge) = ast.edges().get(object_id) {
+                        let from = edge.from_node_id();
+                        let to = edge.to_node_id();
+                        push("from", node_label(from), &["flow", "node"], from);
+                        push("to", node_label(to), &["flow", "node"], to);
+                    }
+                }
+                _ => {}
+            }
         }
-        if is_sequence_block_cell {
-            style = style.fg(sequence_block_color);
+        DiagramAst::Sequence(ast) => {
+            let participant_label = |participant_id: &ObjectId| {
+                ast.participants().get(participant_id).map_or_else(
+                    || participant_id.to_string(),
+                    |participant| participant.mermaid_name().to_owned(),
+                )
+            };
+            let messages = ast.messages_in_order();
+            match category.as_str() {
+                "seq/participant" => {
+                    for message in &messages {
+                        let message_id = message.message_id();
+                        let end =
+                            |id: Option<&ObjectId>| id.map_or("●".to_owned(), participant_label);
+                        if message.sender() == Some(object_id) {
+                            let label = format!(
+                                "{message_id} → {}: {}",
+                                end(message.receiver()),
+                                message.text()
+                            );
+                            push("out", label, &["seq", "message"], message_id);
+                        }
+                        if message.receiver() == Some(object_id) {
+                            let label = format!(
+                                "{message_id} ← {}: {}",
+                                end(message.sender()),
+                                message.text()
+                            );
+                            push("in", label, &["seq", "message"], message_id);
+                        }
+                    }
+                    if let Some(group) = ast.participant_group(object_id) {
+                        let label = format!("box {}", group.title().unwrap_or("—"));
+                        push("within", label, &["seq", "group"], group.group_id());
+                    }
+                }
+                "seq/message" => {
+                    let Some(idx) =
+                        messages.iter().position(|message| message.message_id() == object_id)
+                    else {
+                        return rows;
+                    };
+                    let message = messages[idx];
+                    if let Some(from) = message.sender() {
+                        push("from", participant_label(from), &["seq", "participant"], from);
+                    }
+                    if let Some(to) = message.receiver() {
+                        push("to", participant_label(to), &["seq", "participant"], to);
+                    }
+                    if let Some(prev) = idx.checked_sub(1).map(|prev| messages[prev]) {
+                        let label = format!("{}: {}", prev.message_id(), prev.text());
+                        push("prev", label, &["seq", "message"], prev.message_id());
+                    }
+                    if let Some(next) = messages.get(idx + 1) {
+                        let label = format!("{}: {}", next.message_id(), next.text());
+                        push("next", label, &["seq", "message"], next.message_id());
+                    }
+                    let mut containers = Vec::new();
+                    collect_message_containers(ast.blocks(), object_id, &mut containers);
+                    for (segments, id, label) in containers {
+                        push("within", label, segments, id);
+                    }
+                }
+                "seq/group" => {
+                    if let Some(group) = ast.find_group(object_id) {
+                        for participant_id in group.participant_ids() {
+                            let label = participant_label(participant_id);
+                            push("member", label, &["seq", "participant"], participant_id);
+                        }
+                    }
+                }
+                _ => {}
+            }
         }
-        if is_note_cell {
-            style = style.fg(Color::DarkGray);
+        DiagramAst::State(ast) => {
+            let transition_suffix =
+                |label: Option<&str>| label.map(|label| format!(": {label}")).unwrap_or_default();
+            match category.as_str() {
+                "state/node" => {
+                    for (transition_id, transition) in ast.transitions() {
+                        let suffix = transition_suffix(transition.label());
+                        if transition.from_state_id() == object_id {
+                            let to = state_label(ast, transition.to_state_id());
+                            let label = format!("{transition_id} → {to}{suffix}");
+                            push("out", label, &["state", "transition"], transition_id);
+                        }
+                        if transition.to_state_id() == object_id {
+                            let from = state_label(ast, transition.from_state_id());
+                            let label = format!("{transition_id} ← {from}{suffix}");
+                            push("in", label, &["state", "transition"], transition_id);
+                        }
+                    }
+                }
+                "state/transition" => {
+                    if let Some(transition) = ast.transitions().get(object_id) {
+                        let from = transition.from_state_id();
+                        let to = transition.to_state_id();
+                        push("from", state_label(ast, from), &["state", "node"], from);
+                        push("to", state_label(ast, to), &["state", "node"], to);
+                    }
+                }
+                _ => {}
+            }
         }
+        DiagramAst::Class(ast) => match category.as_str() {
+            "class/node" => {
+                for (relation_id, relation) in ast.relations() {
+                    let kind = relation.kind().as_str();
+                    if relation.from_class_id() == object_id {
+                        let to = class_label(ast, relation.to_class_id());
+                        let label = format!("{relation_id} → {to} ({kind})");
+                        push("out", label, &["class", "relation"], relation_id);
+                    }
+                    if relation.to_class_id() == object_id {
+                        let from = class_label(ast, relation.from_class_id());
+                        let label = format!("{relation_id} ← {from} ({kind})");
+                        push("in", label, &["class", "relation"], relation_id);
+                    }
+                }
+            }
+            "class/relation" => {
+                if let Some(relation) = ast.relations().get(object_id) {
+                    let from = relation.from_class_id();
+                    let to = relation.to_class_id();
+                    push("from", class_label(ast, from), &["class", "node"], from);
+                    push("to", class_label(ast, to), &["class", "node"], to);
+                }
+            }
+            _ => {}
+        },
     }
+    rows
+}
 
-    style
+/// Display label of a state; pseudo-states show their kind (`start`, `choice`, ...).
+fn state_label(ast: &StateAst, state_id: &ObjectId) -> String {
+    ast.states().get(state_id).map_or_else(
+        || state_id.to_string(),
+        |state| match state.kind() {
+            crate::model::StateNodeKind::State => state.label().to_owned(),
+            kind => format!("[{}]", kind.as_str()),
+        },
+    )
+}
+
+fn class_label(ast: &ClassAst, class_id: &ObjectId) -> String {
+    ast.classes()
+        .get(class_id)
+        .map_or_else(|| class_id.to_string(), |class| class.label().to_owned())
+}
+
+/// Collects the blocks/sections enclosing `message_id`, outermost first.
+fn collect_message_containers<'a>(
+    blocks: &'a [SequenceBlock],
+    message_id: &ObjectId,
+    out: &mut Vec<(&'static [&'static str], &'a ObjectId, String)>,
+) -> bool {
+    for block in blocks {
+        let depth = out.len();
+        let keyword = match block.kind() {
+            SequenceBlockKind::Alt => "alt",
+            SequenceBlockKind::Opt => "opt",
+            SequenceBlockKind::Loop => "loop",
+            SequenceBlockKind::Par => "par",
+        };
+        let label = block.header().map_or_else(|| keyword.to_owned(), |h| format!("{keyword} {h}"));
+        out.push((&["seq", "block"], block.block_id(), label));
+        if let Some(section) =
+            block.sections().iter().find(|section| section.message_ids().contains(message_id))
+        {
+            let kind = match section.kind() {
+                SequenceSectionKind::Main => "main",
+                SequenceSectionKind::Else => "else",
+                SequenceSectionKind::And => "and",
+            };
+            let label = section.header().unwrap_or(kind).to_owned();
+            out.push((&["seq", "section"], section.section_id(), label));
+            return true;
+        }
+        if collect_message_containers(block.blocks(), message_id, out) {
+            return true;
+        }
+        out.truncate(depth);
+    }
+    false
 }
 
 fn objects_from_diagram(diagram: &Diagram) -> Vec<SelectableObject> {
@@ -3500,6 +8592,8 @@ fn objects_from_diagram(diagram: &Diagram) -> Vec<SelectableObject> {
     let mut objects = match diagram.ast() {
         DiagramAst::Sequence(ast) => objects_from_sequence_ast(&diagram_id, ast),
         DiagramAst::Flowchart(ast) => objects_from_flowchart_ast(&diagram_id, ast),
+        DiagramAst::State(ast) => objects_from_state_ast(&diagram_id, ast),
+        DiagramAst::Class(ast) => objects_from_class_ast(&diagram_id, ast),
     };
 
     objects.sort_by_cached_key(|obj| obj.object_ref.to_string());
@@ -3509,6 +8603,9 @@ fn objects_from_diagram(diagram: &Diagram) -> Vec<SelectableObject> {
 fn objects_from_sequence_ast(diagram_id: &DiagramId, ast: &SequenceAst) -> Vec<SelectableObject> {
     let participant_category = category_path(&["seq", "participant"]);
     let message_category = category_path(&["seq", "message"]);
+    let group_category = category_path(&["seq", "group"]);
+    let activation_category = category_path(&["seq", "activation"]);
+    let note_category = category_path(&["seq", "note"]);
 
     let mut out = Vec::new();
 
@@ -3541,6 +8638,49 @@ fn objects_from_sequence_ast(diagram_id: &DiagramId, ast: &SequenceAst) -> Vec<S
         });
     }
 
+    for note in ast.notes() {
+        let object_ref =
+            ObjectRef::new(diagram_id.clone(), note_category.clone(), note.note_id().clone());
+        let participants =
+            note.participant_ids().iter().map(ObjectId::as_str).collect::<Vec<_>>().join(",");
+        out.push(SelectableObject {
+            label: format!("note {} {}: {}", note.note_id(), participants, note.text()),
+            note: Some(note.text().to_owned()),
+            object_ref,
+        });
+    }
+
+    for group in ast.groups() {
+        let object_ref =
+            ObjectRef::new(diagram_id.clone(), group_category.clone(), group.group_id().clone());
+        out.push(SelectableObject {
+            label: format!("group {} ({})", group.group_id(), group.title().unwrap_or("box")),
+            note: None,
+            object_ref,
+        });
+    }
+
+    let anchor =
+        |message_id: Option<&ObjectId>| message_id.map_or("…", ObjectId::as_str).to_owned();
+    for activation in ast.activations() {
+        let object_ref = ObjectRef::new(
+            diagram_id.clone(),
+            activation_category.clone(),
+            activation.activation_id().clone(),
+        );
+        out.push(SelectableObject {
+            label: format!(
+                "activation {} {} ({}..{})",
+                activation.activation_id(),
+                activation.participant_id(),
+                anchor(activation.start_message_id()),
+                anchor(activation.end_message_id())
+            ),
+            note: None,
+            object_ref,
+        });
+    }
+
     out
 }
 
@@ -3568,9 +8708,177 @@ fn objects_from_flowchart_ast(diagram_id: &DiagramId, ast: &FlowchartAst) -> Vec
         });
     }
 
+    let subgraph_category = category_path(&["flow", "subgraph"]);
+    for (subgraph_id, subgraph) in ast.subgraphs() {
+        let object_ref =
+            ObjectRef::new(diagram_id.clone(), subgraph_category.clone(), subgraph_id.clone());
+        out.push(SelectableObject {
+            label: format!("subgraph {} ({})", subgraph_id, subgraph.label()),
+            note: None,
+            object_ref,
+        });
+    }
+
+    out
+}
+
+fn objects_from_state_ast(diagram_id: &DiagramId, ast: &StateAst) -> Vec<SelectableObject> {
+    let state_category = category_path(&["state", "node"]);
+    let transition_category = category_path(&["state", "transition"]);
+
+    let mut out = Vec::new();
+
+    for (state_id, state) in ast.states() {
+        let object_ref =
+            ObjectRef::new(diagram_id.clone(), state_category.clone(), state_id.clone());
+        out.push(SelectableObject {
+            label: format!("state {} ({})", state_id, state_label(ast, state_id)),
+            note: state.note().map(|note| note.to_owned()),
+            object_ref,
+        });
+    }
+
+    for (transition_id, transition) in ast.transitions() {
+        let object_ref =
+            ObjectRef::new(diagram_id.clone(), transition_category.clone(), transition_id.clone());
+        out.push(SelectableObject {
+            label: format!(
+                "transition {} {}→{}",
+                transition_id,
+                transition.from_state_id(),
+                transition.to_state_id()
+            ),
+            note: None,
+            object_ref,
+        });
+    }
+
+    out
+}
+
+fn objects_from_class_ast(diagram_id: &DiagramId, ast: &ClassAst) -> Vec<SelectableObject> {
+    let class_category = category_path(&["class", "node"]);
+    let relation_category = category_path(&["class", "relation"]);
+
+    let mut out = Vec::new();
+
+    for (class_id, class) in ast.classes() {
+        let object_ref =
+            ObjectRef::new(diagram_id.clone(), class_category.clone(), class_id.clone());
+        out.push(SelectableObject {
+            label: format!("class {} ({})", class_id, class.label()),
+            note: class.note().map(|note| note.to_owned()),
+            object_ref,
+        });
+    }
+
+    for (relation_id, relation) in ast.relations() {
+        let object_ref =
+            ObjectRef::new(diagram_id.clone(), relation_category.clone(), relation_id.clone());
+        out.push(SelectableObject {
+            label: format!(
+                "{} {} {}→{}",
+                relation.kind().as_str(),
+                relation_id,
+                relation.from_class_id(),
+                relation.to_class_id()
+            ),
+            note: None,
+            object_ref,
+        });
+    }
+
     out
 }
 
+/// Node and edge refs of a weighted flow path, in traversal order.
+fn weighted_path_refs(
+    diagram_id: &DiagramId,
+    path: &crate::query::flow::WeightedPath,
+) -> Vec<ObjectRef> {
+    let node_category = category_path(&["flow", "node"]);
+    let edge_category = category_path(&["flow", "edge"]);
+    let mut refs = Vec::new();
+    for step in &path.steps {
+        if let Some(edge_id) = &step.via_edge_id {
+            refs.push(ObjectRef::new(diagram_id.clone(), edge_category.clone(), edge_id.clone()));
+        }
+        refs.push(ObjectRef::new(diagram_id.clone(), node_category.clone(), step.node_id.clone()));
+    }
+    refs
+}
+
+/// Resolves a participant by id, id without the `p:` prefix, or Mermaid name.
+fn resolve_seq_participant(ast: &SequenceAst, token: &str) -> Option<ObjectId> {
+    let by_id = |id: &str| ObjectId::new(id).ok().filter(|id| ast.participants().contains_key(id));
+    by_id(token).or_else(|| by_id(&format!("p:{token}"))).or_else(|| {
+        ast.participants()
+            .iter()
+            .find(|(_, participant)| participant.mermaid_name().eq_ignore_ascii_case(token))
+            .map(|(participant_id, _)| participant_id.clone())
+    })
+}
+
+/// Gap between `order_key`s when messages have to be renumbered to make room for a move.
+const SEQ_ORDER_KEY_STEP: i64 = 1000;
+
+/// Ops moving `message_id` one place earlier (`offset < 0`) or later in the message order: a
+/// single `order_key` between its new neighbours, or renumbering every message in steps of
+/// [`SEQ_ORDER_KEY_STEP`] when they leave no gap. `None` when it already is first or last.
+fn seq_message_move_ops(
+    ast: &SequenceAst,
+    message_id: &ObjectId,
+    offset: isize,
+) -> Option<Vec<Op>> {
+    let messages = ast.messages();
+    let index = messages.iter().position(|message| message.message_id() == message_id)?;
+    let target = index.checked_add_signed(offset).filter(|&target| target < messages.len())?;
+    if target == index {
+        return None;
+    }
+    let mut order = messages.iter().collect::<Vec<_>>();
+    let moved = order.remove(index);
+    order.insert(target, moved);
+
+    let update = |message: &SequenceMessage, order_key: i64| {
+        Op::Seq(SeqOp::UpdateMessage {
+            message_id: message.message_id().clone(),
+            patch: SeqMessagePatch { order_key: Some(order_key), ..SeqMessagePatch::default() },
+        })
+    };
+    let before = target.checked_sub(1).map(|i| order[i].order_key());
+    let after = order.get(target + 1).map(|message| message.order_key());
+    let order_key = match (before, after) {
+        (Some(before), Some(after)) if after - before >= 2 => Some(before + (after - before) / 2),
+        (None, Some(after)) => after.checked_sub(SEQ_ORDER_KEY_STEP),
+        (Some(before), None) => before.checked_add(SEQ_ORDER_KEY_STEP),
+        _ => None,
+    };
+    if let Some(order_key) = order_key {
+        return Some(vec![update(moved, order_key)]);
+    }
+    let renumbered = (1..).map(|step| step * SEQ_ORDER_KEY_STEP);
+    Some(
+        order
+            .into_iter()
+            .zip(renumbered)
+            .filter(|(message, order_key)| message.order_key() != *order_key)
+            .map(|(message, order_key)| update(message, order_key))
+            .collect(),
+    )
+}
+
+/// Resolves a `:path` argument to a flow node: exact id, id without the `n:` prefix, or label.
+fn resolve_flow_node(ast: &FlowchartAst, token: &str) -> Option<ObjectId> {
+    let by_id = |id: &str| ObjectId::new(id).ok().filter(|id| ast.nodes().contains_key(id));
+    by_id(token).or_else(|| by_id(&format!("n:{token}"))).or_else(|| {
+        ast.nodes()
+            .iter()
+            .find(|(_, node)| node.label().eq_ignore_ascii_case(token))
+            .map(|(node_id, _)| node_id.clone())
+    })
+}
+
 fn category_path(segments: &[&str]) -> CategoryPath {
     CategoryPath::new(segments.iter().map(|s| (*s).to_owned()).collect())
         .expect("valid CategoryPath")
@@ -3586,12 +8894,55 @@ struct SubsequenceStats {
 
 fn search_candidates_from_session(session: &Session) -> Vec<SearchCandidate> {
     let mut candidates = Vec::new();
-    for diagram in session.diagrams().values() {
+    for diagram in session.diagrams().values().filter(|diagram| !diagram.is_archived()) {
         for obj in objects_from_diagram(diagram) {
             let object_ref_text = obj.object_ref.to_string();
             let haystack = format!("{object_ref_text} {}", obj.label).to_lowercase();
-            candidates.push(SearchCandidate { haystack, object_ref: obj.object_ref });
+            candidates.push(SearchCandidate {
+                haystack,
+                label: obj.label,
+                object_ref: obj.object_ref,
+                full_text: false,
+            });
+        }
+    }
+
+    // Object labels are covered above; the session index adds notes, walkthrough steps and xref
+    // labels, each jumping to the object it belongs to.
+    for document in SearchIndex::build(session).documents() {
+        let (object_ref, label) = match &document.target {
+            SearchTarget::Object(_) if document.field == SearchField::Label => continue,
+            SearchTarget::Object(object_ref) => (object_ref.clone(), "note".to_owned()),
+            SearchTarget::WalkthroughNode { walkthrough_id, node_id } => {
+                let walkthrough = &session.walkthroughs()[walkthrough_id];
+                let Some(object_ref) = walkthrough
+                    .nodes()
+                    .iter()
+                    .find(|node| node.node_id() == node_id)
+                    .and_then(|node| node.refs().first())
+                else {
+                    continue;
+                };
+                (object_ref.clone(), format!("walkthrough {walkthrough_id}/{node_id}"))
+            }
+            SearchTarget::XRef(xref_id) => {
+                (session.xrefs()[xref_id].from().clone(), format!("xref {xref_id}"))
+            }
+        };
+        let listed = session
+            .diagrams()
+            .get(object_ref.diagram_id())
+            .is_some_and(|diagram| !diagram.is_archived());
+        if !listed {
+            continue;
         }
+        let text = document.text.lines().next().unwrap_or_default();
+        candidates.push(SearchCandidate {
+            haystack: format!("{object_ref} {}", document.text).to_lowercase(),
+            label: format!("{label}: {text}"),
+            object_ref,
+            full_text: true,
+        });
     }
 
     candidates.sort_by(|a, b| a.haystack.cmp(&b.haystack));
@@ -3603,7 +8954,8 @@ fn ranked_search_results(
     query: &str,
     kind: SearchKind,
     active_diagram_id: Option<&DiagramId>,
-) -> Vec<ObjectRef> {
+    scoring: &SearchScoring,
+) -> Vec<SearchHit> {
     let needle = query.trim();
     if needle.is_empty() {
         return Vec::new();
@@ -3614,7 +8966,11 @@ fn ranked_search_results(
     for (idx, candidate) in candidates.iter().enumerate() {
         let score = match kind {
             SearchKind::Regular => regular_score(&needle, &candidate.haystack),
-            SearchKind::Fuzzy => fuzzy_score(&needle, &candidate.haystack),
+            SearchKind::Fuzzy if candidate.full_text => continue,
+            SearchKind::Fuzzy => match scoring.matcher {
+                FuzzyMatcher::Nereid => fuzzy_score(&needle, &candidate.haystack, scoring),
+                FuzzyMatcher::Skim => skim_score(&needle, &candidate.haystack, scoring),
+            },
         };
         let Some(score) = score else {
             continue;
@@ -3628,9 +8984,11 @@ fn ranked_search_results(
 
     for matches in groups.values_mut() {
         matches.sort_by(|(score_a, idx_a), (score_b, idx_b)| {
+            let (hay_a, hay_b) = (&candidates[*idx_a].haystack, &candidates[*idx_b].haystack);
             score_b
                 .cmp(score_a)
-                .then_with(|| candidates[*idx_a].haystack.cmp(&candidates[*idx_b].haystack))
+                .then_with(|| hay_a.len().cmp(&hay_b.len()))
+                .then_with(|| hay_a.cmp(hay_b))
         });
     }
 
@@ -3638,24 +8996,27 @@ fn ranked_search_results(
     let mut ordered_groups = groups
         .into_iter()
         .map(|(diagram_id, matches)| {
-            let best_score = matches.first().map(|(score, _)| *score).unwrap_or(i64::MIN);
+            let mut best_score = matches.first().map(|(score, _)| *score).unwrap_or(i64::MIN);
+            if active_diagram.as_deref() == Some(diagram_id.as_str()) {
+                best_score = best_score.saturating_add(scoring.active_diagram_boost);
+            }
             (diagram_id, best_score, matches)
         })
         .collect::<Vec<_>>();
 
     ordered_groups.sort_by(|(diagram_a, best_a, _), (diagram_b, best_b, _)| {
-        let a_is_active = active_diagram.as_deref() == Some(diagram_a.as_str());
-        let b_is_active = active_diagram.as_deref() == Some(diagram_b.as_str());
-        b_is_active
-            .cmp(&a_is_active)
-            .then_with(|| best_b.cmp(best_a))
-            .then_with(|| diagram_a.cmp(diagram_b))
+        best_b.cmp(best_a).then_with(|| diagram_a.cmp(diagram_b))
     });
 
     let mut out = Vec::new();
     for (_, _, matches) in ordered_groups {
-        for (_, idx) in matches {
-            out.push(candidates[idx].object_ref.clone());
+        for (score, idx) in matches {
+            let candidate = &candidates[idx];
+            out.push(SearchHit {
+                object_ref: candidate.object_ref.clone(),
+                label: candidate.label.clone(),
+                score,
+            });
         }
     }
     out
@@ -3689,7 +9050,7 @@ fn regular_score(needle: &str, haystack: &str) -> Option<i64> {
     Some(score)
 }
 
-fn fuzzy_score(needle: &str, haystack: &str) -> Option<i64> {
+fn fuzzy_score(needle: &str, haystack: &str, scoring: &SearchScoring) -> Option<i64> {
     let needle = needle.trim();
     if needle.is_empty() {
         return None;
@@ -3702,9 +9063,9 @@ fn fuzzy_score(needle: &str, haystack: &str) -> Option<i64> {
     let mut score = ratio_score;
     score -= subseq.span as i64;
     score -= (subseq.first as i64) / 4;
-    score += (subseq.consecutive as i64) * 40;
+    score += (subseq.consecutive as i64) * scoring.consecutive_bonus;
     if subseq.start_boundary {
-        score += 150;
+        score += scoring.boundary_bonus;
     }
     if haystack.contains(needle) {
         score += 2000;
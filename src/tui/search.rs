@@ -0,0 +1,204 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Search scoring knobs and the skim-style fuzzy matcher.
+
+use std::env;
+use std::fmt;
+
+pub(crate) const SEARCH_MATCHER_ENV: &str = "NEREID_SEARCH_MATCHER";
+pub(crate) const SEARCH_BOUNDARY_BONUS_ENV: &str = "NEREID_SEARCH_BOUNDARY_BONUS";
+pub(crate) const SEARCH_CONSECUTIVE_BONUS_ENV: &str = "NEREID_SEARCH_CONSECUTIVE_BONUS";
+pub(crate) const SEARCH_ACTIVE_BOOST_ENV: &str = "NEREID_SEARCH_ACTIVE_BOOST";
+
+// Skim/fzf-style alignment scores, scaled so the default boundary bonus is half a match.
+const SKIM_SCORE_MATCH: i64 = 320;
+const SKIM_GAP_START: i64 = -60;
+const SKIM_GAP_EXTENSION: i64 = -20;
+const SKIM_FIRST_CHAR_MULTIPLIER: i64 = 2;
+
+/// Algorithm used by fuzzy (`\`) search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum FuzzyMatcher {
+    /// Similarity ratio plus subsequence stats; favours short haystacks.
+    #[default]
+    Nereid,
+    /// Best-alignment scoring in the style of skim/fzf; ignores where in a long ref the match is.
+    Skim,
+}
+
+impl FuzzyMatcher {
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "nereid" | "default" => Some(Self::Nereid),
+            "skim" => Some(Self::Skim),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FuzzyMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Nereid => "nereid",
+            Self::Skim => "skim",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SearchScoring {
+    pub(crate) matcher: FuzzyMatcher,
+    /// Added when a fuzzy match starts right after `/`, `:`, `-`, `_` or a space.
+    pub(crate) boundary_bonus: i64,
+    /// Added for every needle character matched right after the previous one.
+    pub(crate) consecutive_bonus: i64,
+    /// Added to the active diagram's best score when ordering diagram groups.
+    pub(crate) active_diagram_boost: i64,
+}
+
+impl Default for SearchScoring {
+    fn default() -> Self {
+        Self {
+            matcher: FuzzyMatcher::default(),
+            boundary_bonus: 150,
+            consecutive_bonus: 40,
+            active_diagram_boost: 1_000_000,
+        }
+    }
+}
+
+impl SearchScoring {
+    /// Reads scoring overrides from the `NEREID_SEARCH_*` variables, keeping defaults for unset or
+    /// blank ones.
+    pub(crate) fn from_env() -> Result<Self, String> {
+        Self::from_lookup(|name| env::var(name).ok())
+    }
+
+    pub(crate) fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let value = |name: &str| lookup(name).filter(|value| !value.trim().is_empty());
+        let int = |name: &str| -> Result<Option<i64>, String> {
+            value(name)
+                .map(|value| {
+                    value
+                        .trim()
+                        .parse::<i64>()
+                        .map_err(|_| format!("{name} must be an integer, got {value:?}"))
+                })
+                .transpose()
+        };
+
+        let mut scoring = Self::default();
+        if let Some(matcher) = value(SEARCH_MATCHER_ENV) {
+            scoring.matcher = FuzzyMatcher::parse(&matcher).ok_or_else(|| {
+                format!("{SEARCH_MATCHER_ENV} must be `nereid` or `skim`, got {matcher:?}")
+            })?;
+        }
+        if let Some(bonus) = int(SEARCH_BOUNDARY_BONUS_ENV)? {
+            scoring.boundary_bonus = bonus;
+        }
+        if let Some(bonus) = int(SEARCH_CONSECUTIVE_BONUS_ENV)? {
+            scoring.consecutive_bonus = bonus;
+        }
+        if let Some(boost) = int(SEARCH_ACTIVE_BOOST_ENV)? {
+            scoring.active_diagram_boost = boost;
+        }
+        Ok(scoring)
+    }
+}
+
+/// Scores `needle` against `haystack` by their best alignment, skim/fzf style.
+///
+/// Every matched character earns a fixed score plus `boundary_bonus` when it starts a word
+/// (doubled for the first needle character); extending a run earns the larger of that and
+/// `consecutive_bonus` instead. Gaps between matches cost a start penalty plus a smaller
+/// per-character extension. Unlike the default matcher, neither the haystack length nor the match
+/// offset count, so long object refs are not penalised for their `d:<diagram>/<category>/` prefix.
+/// Both inputs are expected lowercase.
+pub(crate) fn skim_score(needle: &str, haystack: &str, scoring: &SearchScoring) -> Option<i64> {
+    let needle = needle.trim().chars().collect::<Vec<_>>();
+    let haystack = haystack.chars().collect::<Vec<_>>();
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return None;
+    }
+
+    let bonus_at = |idx: usize| {
+        if idx == 0 || super::is_boundary_char(haystack[idx - 1]) {
+            scoring.boundary_bonus
+        } else {
+            0
+        }
+    };
+
+    // `prev[j]` is the best score for the needle so far with its last character matched at `j`.
+    let mut prev = haystack
+        .iter()
+        .enumerate()
+        .map(|(idx, &ch)| {
+            (ch == needle[0]).then(|| SKIM_SCORE_MATCH + bonus_at(idx) * SKIM_FIRST_CHAR_MULTIPLIER)
+        })
+        .collect::<Vec<_>>();
+
+    for &want in &needle[1..] {
+        let mut row = vec![None; haystack.len()];
+        // Best `prev[k]` with at least one skipped character before `j`, gap penalty applied.
+        let mut gapped: Option<i64> = None;
+        for j in 1..haystack.len() {
+            if j >= 2 {
+                let opened = prev[j - 2].map(|score| score + SKIM_GAP_START);
+                let extended = gapped.map(|score| score + SKIM_GAP_EXTENSION);
+                gapped = opened.max(extended);
+            }
+            if haystack[j] != want {
+                continue;
+            }
+            let bonus = bonus_at(j);
+            let consecutive = prev[j - 1].map(|score| score + scoring.consecutive_bonus.max(bonus));
+            let best = consecutive.max(gapped.map(|score| score + bonus));
+            row[j] = best.map(|score| score + SKIM_SCORE_MATCH);
+        }
+        prev = row;
+    }
+
+    prev.into_iter().flatten().max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        skim_score, FuzzyMatcher, SearchScoring, SEARCH_ACTIVE_BOOST_ENV, SEARCH_MATCHER_ENV,
+    };
+
+    #[test]
+    fn scoring_reads_overrides_and_rejects_unknown_matchers() {
+        let scoring = SearchScoring::from_lookup(|name| match name {
+            SEARCH_MATCHER_ENV => Some("Skim".to_owned()),
+            SEARCH_ACTIVE_BOOST_ENV => Some("0".to_owned()),
+            _ => None,
+        })
+        .expect("scoring");
+        assert_eq!(scoring.matcher, FuzzyMatcher::Skim);
+        assert_eq!(scoring.active_diagram_boost, 0);
+        assert_eq!(scoring.boundary_bonus, SearchScoring::default().boundary_bonus);
+
+        let err = SearchScoring::from_lookup(|name| {
+            (name == SEARCH_MATCHER_ENV).then(|| "fzy".to_owned())
+        })
+        .expect_err("unknown matcher");
+        assert!(err.contains(SEARCH_MATCHER_ENV));
+    }
+
+    #[test]
+    fn skim_score_prefers_word_starts_and_runs_over_scattered_hits() {
+        let scoring = SearchScoring::default();
+        let word_start = skim_score("cache", "d:seq/seq/participant/p:cache cache", &scoring);
+        let scattered = skim_score("cache", "d:seq/seq/message/m:0001 check active", &scoring);
+        assert!(word_start > scattered, "{word_start:?} vs {scattered:?}");
+        assert_eq!(skim_score("zz", "d:seq/seq/participant/p:a", &scoring), None);
+    }
+}
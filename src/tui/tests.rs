@@ -7,25 +7,44 @@
 // Unauthorized copying, modification, or distribution is prohibited.
 
 use super::{
-    apply_highlight_flags, category_path, demo_session, demo_session_fallback,
-    diagram_counter_label, diagram_view_title, ensure_active_diagram_id, export_diagram_mermaid,
+    active_diagram_description_text, apply_grid_overlay, apply_highlight_flags, breadcrumb_line,
+    breadcrumb_location, category_path, column_ruler_text, demo_session, demo_session_fallback,
+    diagram_counter_label, diagram_direction_span, diagram_legend_lines, diagram_stats_lines,
+    diagram_view_title, ensure_active_diagram_id, export_diagram_mermaid,
     fill_highlight_bridge_gaps, fill_highlight_bridge_gaps_unbounded,
-    fill_highlight_corner_branch_extensions, footer_help_line, objects_item_bg, osc52_sequence,
-    panel_border_style_for_focus, ranked_search_results, search_candidates_from_session,
-    search_footer_line, stack_main_panes_vertically, style_for_diagram_cell,
+    fill_highlight_corner_branch_extensions, footer_help_line, lazy_list_window, line_spans_bounds,
+    line_spans_center, merge_prompt_lines, object_peek_lines, object_relation_rows,
+    objects_item_bg, osc52_sequence, panel_border_style_for_focus, parse_tui_command,
+    peek_anchor_on_screen, peek_popover_rect, ranked_search_results, row_ruler_label,
+    scroll_axis_into_view, search_candidates_from_session, search_footer_line,
+    search_results_panel_lines, split_breadcrumb_area, split_ruler_areas,
+    stack_main_panes_vertically, style_for_diagram_cell, tidy_prompt_lines, visible_diagram_cells,
     xref_involves_selected, xref_item_style, xrefs_cursor_highlight_style, App, ExternalAction,
-    Focus, FocusOwner, HintKind, HintMode, SearchKind, SearchMode, SelectableObject,
+    Focus, FocusOwner, FuzzyMatcher, HintKind, HintMode, ListHitArea, PendingDiagramSync,
+    SearchHit, SearchKind, SearchMode, SearchScoring, SelectableObject, TuiCommand,
+    VisualSelection,
 };
 use crate::format::mermaid::{parse_flowchart, parse_sequence_diagram};
 use crate::model::{
-    Diagram, DiagramAst, DiagramId, ObjectId, ObjectRef, Session, SessionId, XRef, XRefId,
-    XRefStatus,
+    Diagram, DiagramAst, DiagramId, DiagramKind, FlowNodePin, FlowchartAst, ObjectId, ObjectRef,
+    Session, SessionId, Walkthrough, WalkthroughId, WalkthroughNode, WalkthroughNodeId, XRef,
+    XRefId, XRefStatus,
 };
-use crate::render::{diagram::render_diagram_unicode_annotated_with_options, RenderOptions};
-use crate::store::SessionFolder;
-use crossterm::event::KeyCode;
-use ratatui::{layout::Rect, style::Color};
-use std::collections::BTreeSet;
+use crate::ops::{apply_ops, Op, SeqMessagePatch, SeqOp};
+use crate::render::budget::RenderBudget;
+use crate::render::{
+    diagram::render_diagram_unicode_annotated_with_options, FlowchartEdgeGlyphs, RenderOptions,
+};
+use crate::store::{SessionFolder, Workspace};
+use crate::ui::{HumanViewport, ProposalStatus, UiState, ViewRequest, ViewZoom};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier},
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Duration, Instant};
 
 fn text_to_string(text: &ratatui::text::Text<'_>) -> String {
     text.lines
@@ -218,6 +237,509 @@ fn xref_involves_selected_matches_from_and_to_endpoints() {
     assert!(!xref_involves_selected(None, &xref_from));
 }
 
+#[test]
+fn object_peek_lists_anchors_xrefs_and_walkthrough_refs() {
+    let mut session = Session::new(SessionId::new("s1").expect("session id"));
+    let diagram_id = DiagramId::new("seq").expect("diagram id");
+    let ast = parse_sequence_diagram(
+        "sequenceDiagram\nparticipant Alice\nparticipant Bob\nAlice->>Bob: Hello\n",
+    )
+    .expect("parse sequence");
+    let diagram = Diagram::new(diagram_id.clone(), "Seq", DiagramAst::Sequence(ast));
+    session.diagrams_mut().insert(diagram_id, diagram);
+
+    let message: ObjectRef = "d:seq/seq/message/m:0001".parse().expect("message ref");
+    let other: ObjectRef = "d:seq/seq/participant/p:Bob".parse().expect("other ref");
+    let mut outgoing = XRef::new(message.clone(), other.clone(), "implements", XRefStatus::Ok);
+    outgoing.set_label(Some("api".to_owned()));
+    session.xrefs_mut().insert(XRefId::new("x:1").expect("xref id"), outgoing);
+    session.xrefs_mut().insert(
+        XRefId::new("x:2").expect("xref id"),
+        XRef::new(other, message.clone(), "calls", XRefStatus::Ok),
+    );
+
+    let mut walkthrough =
+        Walkthrough::new(WalkthroughId::new("w:intro").expect("walkthrough id"), "Intro");
+    let mut node = WalkthroughNode::new(WalkthroughNodeId::new("wn:1").expect("node id"), "Greet");
+    node.refs_mut().push(message.clone());
+    node.tags_mut().push("entry".to_owned());
+    walkthrough.nodes_mut().push(node);
+    session.walkthroughs_mut().insert(walkthrough.walkthrough_id().clone(), walkthrough);
+
+    let obj = SelectableObject { label: "Hello".to_owned(), note: None, object_ref: message };
+    assert_eq!(
+        object_peek_lines(&session, &obj),
+        vec![
+            "Label: Hello",
+            "Note: —",
+            "Anchors: Alice → Bob",
+            "XRef → d:seq/seq/participant/p:Bob [implements] (api)",
+            "XRef ← d:seq/seq/participant/p:Bob [calls]",
+            "Walkthrough w:intro: Greet #entry",
+        ]
+    );
+}
+
+#[test]
+fn object_peek_lists_containing_subgraph_as_anchor() {
+    let mut session = Session::new(SessionId::new("s1").expect("session id"));
+    let diagram_id = DiagramId::new("flow").expect("diagram id");
+    let ast = parse_flowchart(
+        "flowchart LR\nsubgraph api [Public API]\nsubgraph auth [Auth]\nA\nend\nB\nend\nC\n",
+    )
+    .expect("parse flowchart");
+    let diagram = Diagram::new(diagram_id.clone(), "Flow", DiagramAst::Flowchart(ast));
+    session.diagrams_mut().insert(diagram_id, diagram);
+
+    let anchors = |object_ref: &str| {
+        let obj = SelectableObject {
+            label: "x".to_owned(),
+            note: None,
+            object_ref: object_ref.parse().expect("ref"),
+        };
+        object_peek_lines(&session, &obj)
+            .into_iter()
+            .filter(|line| line.starts_with("Anchors: "))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(anchors("d:flow/flow/node/n:A"), vec!["Anchors: Auth"]);
+    assert_eq!(anchors("d:flow/flow/node/n:B"), vec!["Anchors: Public API"]);
+    assert_eq!(anchors("d:flow/flow/subgraph/sg:auth"), vec!["Anchors: Public API"]);
+    assert!(anchors("d:flow/flow/node/n:C").is_empty());
+    assert!(anchors("d:flow/flow/subgraph/sg:api").is_empty());
+}
+
+#[test]
+fn peek_popover_prefers_below_anchor_and_stays_inside_content() {
+    let content = Rect::new(1, 1, 40, 12);
+
+    assert_eq!(peek_popover_rect(content, Some((5, 2, 3)), 10, 4), Rect::new(5, 4, 10, 4));
+    // No room below: flip above the anchor.
+    assert_eq!(peek_popover_rect(content, Some((5, 9, 10)), 10, 4), Rect::new(5, 5, 10, 4));
+    // Clamped against the right edge and shrunk to the content size.
+    assert_eq!(peek_popover_rect(content, Some((38, 2, 2)), 10, 4), Rect::new(31, 3, 10, 4));
+    assert_eq!(peek_popover_rect(content, None, 60, 20), content);
+
+    assert_eq!(peek_anchor_on_screen(content, ((3, 6), (4, 5)), 0, 0), Some((4, 5, 6)));
+    assert_eq!(peek_anchor_on_screen(content, ((3, 6), (4, 5)), 10, 0), None);
+    assert_eq!(peek_anchor_on_screen(content, ((3, 6), (4, 20)), 0, 10), Some((4, 1, 11)));
+}
+
+#[test]
+fn peek_hotkeys_open_and_close_popover() {
+    let mut app = App::new(demo_session());
+    app.follow_ai = false;
+    assert!(app.selected_ref().is_some());
+
+    app.handle_key_code(KeyCode::Char('p'));
+    assert!(app.peek_visible);
+    app.handle_key_code(KeyCode::Esc);
+    assert!(!app.peek_visible);
+    assert_eq!(app.focus, Focus::Diagram);
+
+    // `K` pans the diagram; the peek key is `p` in every focus.
+    app.handle_key_code(KeyCode::Char('K'));
+    assert!(!app.peek_visible);
+
+    app.handle_key_code(KeyCode::Char('2'));
+    app.handle_key_code(KeyCode::Char('K'));
+    assert!(!app.peek_visible);
+    app.handle_key_code(KeyCode::Char('p'));
+    assert!(app.peek_visible);
+    // Other keys close the popover and still run.
+    app.handle_key_code(KeyCode::Char('j'));
+    assert!(!app.peek_visible);
+    assert_eq!(app.objects_state.selected(), Some(1));
+}
+
+#[test]
+fn stats_overlay_reports_counts_canvas_and_highlight_index() {
+    let mut app = App::new(single_flowchart_session());
+    app.follow_ai = false;
+
+    app.handle_key_code(KeyCode::Char('i'));
+    assert!(app.stats_visible);
+
+    let (width, height) = app.diagram_extent();
+    let lines = diagram_stats_lines(&app);
+    assert_eq!(lines[0], "Objects     2 nodes · 1 edges");
+    assert_eq!(lines[1], format!("Canvas      {width}×{height} cells"));
+    assert!(lines[2].starts_with("Layout      ") && lines[2].ends_with(" ms"));
+    assert!(lines[4].starts_with("Highlights  3 objects · "));
+
+    app.handle_key_code(KeyCode::Esc);
+    assert!(!app.stats_visible);
+    assert_eq!(app.focus, Focus::Diagram);
+}
+
+#[test]
+fn legend_overlay_explains_highlights_and_style_rules() {
+    let mut app = App::new(single_flowchart_session());
+    app.follow_ai = false;
+    app.run_command("style degree>=1 => heavy");
+
+    app.handle_key_code(KeyCode::Char('m'));
+    assert!(app.legend_visible);
+    let lines = diagram_legend_lines(&app)
+        .iter()
+        .map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect::<String>())
+        .collect::<Vec<_>>();
+    assert!(lines.iter().any(|line| line.ends_with("  highlighted by the agent")));
+    assert_eq!(
+        lines.last().map(String::as_str),
+        Some("degree>=1  rendered with heavy borders (style rule)")
+    );
+
+    app.handle_key_code(KeyCode::Char('m'));
+    assert!(!app.legend_visible);
+    assert_eq!(app.focus, Focus::Diagram);
+}
+
+#[test]
+fn recent_objects_track_focus_and_caret_swaps_to_previous_object() {
+    let mut app = App::new(demo_session());
+    app.follow_ai = false;
+    let first = app.selected_ref().cloned().expect("initial selection");
+    assert_eq!(app.recent_objects.front().map(|recent| &recent.object_ref), Some(&first));
+
+    app.handle_key_code(KeyCode::Char('2'));
+    app.handle_key_code(KeyCode::Char('j'));
+    let second = app.selected_ref().cloned().expect("second selection");
+    assert_ne!(first, second);
+
+    app.handle_key_code(KeyCode::Char(']'));
+    let third = app.selected_ref().cloned().expect("object in next diagram");
+    assert_ne!(third.diagram_id(), first.diagram_id());
+    let order = app.recent_objects.iter().map(|recent| &recent.object_ref).collect::<Vec<_>>();
+    assert_eq!(order, vec![&third, &second, &first]);
+
+    app.handle_key_code(KeyCode::Char('^'));
+    assert_eq!(app.selected_ref(), Some(&second));
+    // Terminals report Ctrl-^ as Ctrl-6, which the keymap binds too.
+    app.handle_key(KeyEvent::new(KeyCode::Char('6'), KeyModifiers::CONTROL));
+    assert_eq!(app.selected_ref(), Some(&third));
+
+    app.handle_key_code(KeyCode::Char('6'));
+    assert_eq!(app.focus, Focus::Recent);
+    app.handle_key_code(KeyCode::End);
+    app.handle_key_code(KeyCode::Enter);
+    assert_eq!(app.selected_ref(), Some(&first));
+    assert_eq!(app.recent_objects.front().map(|recent| &recent.object_ref), Some(&first));
+    assert_eq!(app.recent_objects.len(), 3);
+}
+
+#[test]
+fn over_budget_diagram_switches_to_large_mode_until_budget_fits() {
+    let mut app = App::new(single_flowchart_session());
+    app.follow_ai = false;
+    let full_detail = app.base_diagram.clone();
+    assert!(app.large_mode.is_none());
+
+    app.set_render_budget(RenderBudget {
+        max_render_time: Duration::from_secs(60),
+        max_canvas_cells: 1,
+        ..RenderBudget::default()
+    });
+    assert!(app.large_mode.is_some());
+    assert_ne!(app.base_diagram, full_detail);
+    assert!(app
+        .toast
+        .as_ref()
+        .is_some_and(|toast| toast.message.starts_with("Large diagram: reduced detail (canvas")));
+    assert!(diagram_stats_lines(&app).last().is_some_and(|line| line.starts_with("Large mode")));
+
+    app.refresh_active_diagram_view();
+    assert!(app.large_mode.is_some());
+
+    app.set_render_budget(RenderBudget::default());
+    assert!(app.large_mode.is_none());
+    assert_eq!(app.base_diagram, full_detail);
+}
+
+#[test]
+fn lazy_list_window_only_materializes_rows_around_cursor_in_large_mode() {
+    assert_eq!(lazy_list_window(100, Some(50), 10, false), 0..100);
+    assert_eq!(lazy_list_window(100, Some(50), 10, true), 45..55);
+    assert_eq!(lazy_list_window(100, Some(98), 10, true), 90..100);
+    assert_eq!(lazy_list_window(100, None, 10, true), 0..10);
+    assert_eq!(lazy_list_window(5, Some(4), 10, true), 0..5);
+}
+
+#[test]
+fn relation_rows_list_neighbours_xrefs_and_containers() {
+    let mut session = Session::new(SessionId::new("s1").expect("session id"));
+    let diagram_id = DiagramId::new("seq").expect("diagram id");
+    let ast = parse_sequence_diagram(
+        "sequenceDiagram\nparticipant A\nparticipant B\nA->>B: one\nalt ok\nB->>A: two\nend\nA->>B: three\n",
+    )
+    .expect("parse sequence");
+    let diagram = Diagram::new(diagram_id.clone(), "Seq", DiagramAst::Sequence(ast));
+    session.diagrams_mut().insert(diagram_id, diagram);
+
+    let message: ObjectRef = "d:seq/seq/message/m:0002".parse().expect("message ref");
+    let other: ObjectRef = "d:seq/seq/participant/p:A".parse().expect("other ref");
+    session.xrefs_mut().insert(
+        XRefId::new("x:1").expect("xref id"),
+        XRef::new(other, message.clone(), "calls", XRefStatus::Ok),
+    );
+
+    let rows = object_relation_rows(&session, &message)
+        .into_iter()
+        .map(|row| format!("{} {} {}", row.tag, row.label, row.target))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rows,
+        vec![
+            "from B d:seq/seq/participant/p:B",
+            "prev m:0001: one d:seq/seq/message/m:0001",
+            "to A d:seq/seq/participant/p:A",
+            "next m:0003: three d:seq/seq/message/m:0003",
+            "xref← d:seq/seq/participant/p:A [calls] d:seq/seq/participant/p:A",
+            "within alt ok d:seq/seq/block/b:0001",
+            "within main d:seq/seq/section/sec:0001:00",
+        ]
+    );
+
+    let participant: ObjectRef = "d:seq/seq/participant/p:B".parse().expect("participant ref");
+    let tags = object_relation_rows(&session, &participant)
+        .into_iter()
+        .map(|row| (row.tag, row.target.object_id().to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tags,
+        vec![
+            ("out", "m:0002".to_owned()),
+            ("in", "m:0001".to_owned()),
+            ("in", "m:0003".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn relations_pane_jumps_to_selected_relation() {
+    let mut app = App::new(single_flowchart_session());
+    app.follow_ai = false;
+    let node: ObjectRef = "d:flow/flow/node/n:A".parse().expect("node ref");
+    app.select_object_ref(&node);
+
+    app.handle_key_code(KeyCode::Char('5'));
+    assert!(app.relations_visible);
+    assert_eq!(app.focus, Focus::Relations);
+    let rows = app.relation_rows();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].tag, "out");
+    assert_eq!(rows[0].label, format!("{} → End", rows[0].target.object_id()));
+
+    app.relations_state.select(Some(0));
+    app.handle_key_code(KeyCode::Enter);
+    let edge = rows[0].target.clone();
+    assert_eq!(app.selected_ref(), Some(&edge));
+    let tags = app.relation_rows().into_iter().map(|row| row.tag).collect::<Vec<_>>();
+    assert_eq!(tags, vec!["from", "to"]);
+
+    app.handle_key_code(KeyCode::Char('j'));
+    app.handle_key_code(KeyCode::Char('g'));
+    assert_eq!(
+        app.selected_ref().map(ToString::to_string).as_deref(),
+        Some("d:flow/flow/node/n:B")
+    );
+
+    app.handle_key_code(KeyCode::Char('5'));
+    assert!(!app.relations_visible);
+    assert_eq!(app.focus, Focus::Diagram);
+}
+
+#[test]
+fn breadcrumb_tracks_session_diagram_and_focused_object() {
+    let mut app = App::new(single_flowchart_session());
+    app.follow_ai = false;
+    let node: ObjectRef = "d:flow/flow/node/n:A".parse().expect("node ref");
+    app.select_object_ref(&node);
+
+    assert_eq!(
+        line_to_string(&breadcrumb_line(&app)),
+        "s1 › Flow (flowchart, rev 0) › d:flow/flow/node/n:A"
+    );
+    assert_eq!(breadcrumb_location(&app).as_deref(), Some("d:flow/flow/node/n:A"));
+
+    app.objects_state.select(None);
+    assert_eq!(line_to_string(&breadcrumb_line(&app)), "s1 › Flow (flowchart, rev 0)");
+    assert_eq!(breadcrumb_location(&app).as_deref(), Some("d:flow"));
+}
+
+#[test]
+fn session_form_edits_title_description_and_tags() {
+    let mut app = App::new(single_flowchart_session());
+    app.follow_ai = false;
+
+    app.handle_key_code(KeyCode::Char('S'));
+    assert!(app.session_form.is_some());
+    for ch in "Demo".chars() {
+        app.handle_key_code(KeyCode::Char(ch));
+    }
+    app.handle_key_code(KeyCode::Tab);
+    app.handle_key_code(KeyCode::Tab);
+    for ch in "a, b,,a".chars() {
+        app.handle_key_code(KeyCode::Char(ch));
+    }
+    app.handle_key_code(KeyCode::Enter);
+
+    assert!(app.session_form.is_none());
+    assert_eq!(app.session.title(), Some("Demo"));
+    assert_eq!(app.session.description(), None);
+    assert_eq!(app.session.tags(), ["a".to_owned(), "b".to_owned()]);
+    assert!(line_to_string(&breadcrumb_line(&app)).starts_with("s1 Demo #a #b › Flow"));
+
+    app.handle_key_code(KeyCode::Char('S'));
+    app.handle_key_code(KeyCode::Backspace);
+    app.handle_key_code(KeyCode::Esc);
+    assert!(app.session_form.is_none());
+    assert_eq!(app.session.title(), Some("Demo"));
+}
+
+fn oid(value: &str) -> ObjectId {
+    ObjectId::new(value).expect("object id")
+}
+
+fn type_text(app: &mut App, text: &str) {
+    for ch in text.chars() {
+        app.handle_key_code(KeyCode::Char(ch));
+    }
+}
+
+#[test]
+fn visual_mode_selects_every_object_touching_the_box() {
+    let mut app = App::new(single_flowchart_session());
+    app.follow_ai = false;
+    app.focus = Focus::Diagram;
+    let diagram_id = DiagramId::new("flow").expect("diagram id");
+    let flow_ref = |category: &str, id: &str| {
+        ObjectRef::new(
+            diagram_id.clone(),
+            category_path(&["flow", category]),
+            ObjectId::new(id).expect("object id"),
+        )
+    };
+    app.select_object_ref(&flow_ref("node", "n:A"));
+
+    app.handle_key_code(KeyCode::Char('v'));
+    app.handle_key_code(KeyCode::Esc);
+    assert!(app.visual_selection.is_none());
+    assert!(app.session.selected_object_refs().is_empty());
+
+    app.handle_key_code(KeyCode::Char('v'));
+    app.handle_key_code(KeyCode::Enter);
+    assert_eq!(
+        app.session.selected_object_refs().iter().cloned().collect::<Vec<_>>(),
+        vec![flow_ref("node", "n:A")]
+    );
+
+    app.handle_key_code(KeyCode::Char('v'));
+    for key in ['K', 'H', 'H'] {
+        app.handle_key_code(KeyCode::Char(key));
+    }
+    assert_eq!(app.visual_selection.expect("visual selection").cursor, (0, 0));
+    app.visual_selection = Some(VisualSelection { anchor: (0, 0), cursor: (0, 0) });
+    for _ in 0..10 {
+        app.handle_key_code(KeyCode::Char('J'));
+        app.handle_key_code(KeyCode::Char('L'));
+    }
+    let _ = app.diagram_text();
+    app.handle_key_code(KeyCode::Enter);
+    assert!(app.visual_selection.is_none());
+    assert!(app.session.selected_object_refs().contains(&flow_ref("node", "n:B")));
+    assert!(app.session.selected_object_refs().contains(&flow_ref("edge", "e:0001")));
+    assert_eq!(app.toast.as_ref().map(|toast| toast.message.as_str()), Some("Selected 2 objects"));
+}
+
+#[test]
+fn insert_form_adds_connected_flow_node_and_selects_it() {
+    let mut app = App::new(single_flowchart_session());
+    app.follow_ai = false;
+    let diagram_id = DiagramId::new("flow").expect("diagram id");
+    let node_b = ObjectRef::new(
+        diagram_id.clone(),
+        category_path(&["flow", "node"]),
+        ObjectId::new("n:B").expect("object id"),
+    );
+    app.select_object_ref(&node_b);
+
+    app.handle_key_code(KeyCode::Char('o'));
+    let form = app.insert_form.as_ref().expect("insert form");
+    assert_eq!(form.values, [String::new(), "rect".to_owned(), "n:B".to_owned()]);
+    type_text(&mut app, "Ship it!");
+    app.handle_key_code(KeyCode::Tab);
+    app.handle_key_code(KeyCode::Backspace);
+    app.handle_key_code(KeyCode::Enter);
+    assert!(app.insert_form.is_some(), "unknown shape keeps the prompt open");
+    for _ in 0..3 {
+        app.handle_key_code(KeyCode::Backspace);
+    }
+    type_text(&mut app, "round");
+    app.handle_key_code(KeyCode::Enter);
+    assert!(app.insert_form.is_none());
+
+    let diagram = app.session.diagrams().get(&diagram_id).expect("diagram");
+    assert_eq!(diagram.rev(), 1);
+    let DiagramAst::Flowchart(ast) = diagram.ast() else { panic!("flowchart") };
+    let node = ast.nodes().get(&oid("n:Ship_it")).expect("new node");
+    assert_eq!((node.label(), node.shape()), ("Ship it!", "round"));
+    assert!(ast
+        .edges()
+        .values()
+        .any(|edge| edge.from_node_id() == &oid("n:B") && edge.to_node_id() == &oid("n:Ship_it")));
+    assert_eq!(app.selected_ref().map(|r| r.object_id().as_str()), Some("n:Ship_it"));
+
+    app.handle_key_code(KeyCode::Char('u'));
+    let diagram = app.session.diagrams().get(&diagram_id).expect("diagram");
+    let DiagramAst::Flowchart(ast) = diagram.ast() else { panic!("flowchart") };
+    assert!(!ast.nodes().contains_key(&oid("n:Ship_it")));
+}
+
+#[test]
+fn insert_form_adds_sequence_participants_and_messages() {
+    let mut session = Session::new(SessionId::new("s1").expect("session id"));
+    let diagram_id = DiagramId::new("seq").expect("diagram id");
+    let ast = parse_sequence_diagram("sequenceDiagram\nparticipant Alice\n").expect("parse");
+    let diagram = Diagram::new(diagram_id.clone(), "Seq", DiagramAst::Sequence(ast));
+    session.diagrams_mut().insert(diagram_id.clone(), diagram);
+    session.set_active_diagram_id(Some(diagram_id.clone()));
+    let mut app = App::new(session);
+    app.follow_ai = false;
+
+    app.handle_key_code(KeyCode::Char('o'));
+    type_text(&mut app, "Bob");
+    app.handle_key_code(KeyCode::Enter);
+    assert_eq!(app.selected_ref().map(|r| r.object_id().as_str()), Some("p:Bob"));
+
+    app.handle_key_code(KeyCode::Char('o'));
+    type_text(&mut app, "Hello");
+    app.handle_key_code(KeyCode::Tab);
+    type_text(&mut app, "Bob");
+    app.handle_key_code(KeyCode::Tab);
+    type_text(&mut app, "alice");
+    app.handle_key_code(KeyCode::Enter);
+
+    let diagram = app.session.diagrams().get(&diagram_id).expect("diagram");
+    let DiagramAst::Sequence(ast) = diagram.ast() else { panic!("sequence") };
+    let message = ast.messages().first().expect("message");
+    assert_eq!(message.text(), "Hello");
+    assert_eq!(message.from_participant_id(), &oid("p:Bob"));
+    assert_eq!(message.to_participant_id(), &oid("p:Alice"));
+    assert_eq!(app.selected_ref().map(|r| r.object_id().as_str()), Some("m:0001"));
+}
+
+#[test]
+fn breadcrumb_takes_first_row_only_when_pane_has_room() {
+    let (breadcrumb, rest) = split_breadcrumb_area(Rect::new(1, 1, 20, 5));
+    assert_eq!(breadcrumb, Some(Rect::new(1, 1, 20, 1)));
+    assert_eq!(rest, Rect::new(1, 2, 20, 4));
+
+    let (breadcrumb, rest) = split_breadcrumb_area(Rect::new(1, 1, 20, 1));
+    assert_eq!(breadcrumb, None);
+    assert_eq!(rest, Rect::new(1, 1, 20, 1));
+}
+
 #[test]
 fn selected_highlight_does_not_fill_space_gaps() {
     let diagram = "─ ─";
@@ -386,6 +908,62 @@ fn palette_hotkey_toggles_visibility() {
     assert!(!app.palette_visible);
 }
 
+#[test]
+fn ruler_hotkey_toggles_overlay_without_touching_diagram_text() {
+    let mut app = App::new(demo_session());
+    app.follow_ai = false;
+    let before = text_to_string(&app.diagram_text());
+    assert!(!app.ruler_visible);
+
+    app.handle_key_code(KeyCode::Char('#'));
+    assert!(app.ruler_visible);
+    assert_eq!(text_to_string(&app.diagram_text()), before);
+
+    app.handle_key_code(KeyCode::Char('#'));
+    assert!(!app.ruler_visible);
+}
+
+#[test]
+fn ruler_labels_follow_diagram_coordinates() {
+    assert_eq!(column_ruler_text(0, 24), "0    ·    10   ·    20  ");
+    assert_eq!(column_ruler_text(-3, 10), "   0    · ");
+    assert_eq!(column_ruler_text(94, 10), " ·    100 ");
+
+    assert_eq!(row_ruler_label(7, 120, 4), "  7 ");
+    assert_eq!(row_ruler_label(-1, 120, 4), "    ");
+    assert_eq!(row_ruler_label(120, 120, 4), "    ");
+
+    let (top, left, content) = split_ruler_areas(Rect::new(1, 1, 40, 20), 120);
+    assert_eq!(top, Rect::new(5, 1, 36, 1));
+    assert_eq!(left, Rect::new(1, 2, 4, 19));
+    assert_eq!(content, Rect::new(5, 2, 36, 19));
+}
+
+#[test]
+fn grid_overlay_only_marks_blank_cells_on_grid_intersections() {
+    let area = Rect::new(0, 0, 12, 6);
+    let mut buf = Buffer::empty(area);
+    buf.set_string(0, 0, "ab", ratatui::style::Style::default());
+
+    apply_grid_overlay(&mut buf, area, -2, -1);
+
+    let rows = (0..area.height)
+        .map(|y| (0..area.width).map(|x| buf[(x, y)].symbol()).collect::<String>())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rows,
+        vec![
+            "ab          ",
+            "  ·         ",
+            "            ",
+            "            ",
+            "            ",
+            "            ",
+        ]
+    );
+    assert_eq!(buf[(2, 1)].fg, Color::DarkGray);
+}
+
 #[test]
 fn help_hotkey_toggles_visibility() {
     let mut app = App::new(demo_session());
@@ -554,6 +1132,19 @@ fn diagram_title_renders_counter_in_bright_green() {
     assert_eq!(counter_span.style.fg, Some(Color::LightGreen));
 }
 
+#[test]
+fn diagram_title_shows_flowchart_direction() {
+    let session = demo_session();
+    let diagram = |id: &str| {
+        session.diagrams().get(&DiagramId::new(id).expect("diagram id")).expect("demo diagram")
+    };
+
+    let span = diagram_direction_span(diagram("demo-flow")).expect("direction span");
+    assert_eq!(span.content.as_ref(), "TD ");
+    assert_eq!(diagram_direction_span(diagram("om-02-gear")).expect("span").content, "LR ");
+    assert!(diagram_direction_span(diagram("demo-seq")).is_none());
+}
+
 #[test]
 fn search_footer_shows_accept_and_close_with_key_style() {
     let mut app = App::new(demo_session());
@@ -561,132 +1152,977 @@ fn search_footer_shows_accept_and_close_with_key_style() {
     app.search_query = "arrow".to_owned();
     let line = search_footer_line(&app, "");
 
-    let rendered = line_to_string(&line);
-    assert!(rendered.contains("/arrow"));
-    assert!(rendered.contains("Accept:Enter"));
-    assert!(rendered.contains("Close:Esc"));
+    let rendered = line_to_string(&line);
+    assert!(rendered.contains("/arrow"));
+    assert!(rendered.contains("Accept:Enter"));
+    assert!(rendered.contains("Close:Esc"));
+
+    let enter_span =
+        line.spans.iter().find(|span| span.content.as_ref() == "Enter").expect("Enter span");
+    assert_eq!(enter_span.style.fg, Some(Color::Cyan));
+}
+
+#[test]
+fn search_footer_adds_three_spaces_after_query() {
+    let mut app = App::new(demo_session());
+    app.search_mode = SearchMode::Editing;
+    app.search_query = "arrow".to_owned();
+
+    let line = line_to_string(&search_footer_line(&app, ""));
+    assert!(line.contains("/arrow   0 | Accept:Enter"));
+}
+
+#[test]
+fn search_footer_renders_count_in_bright_green() {
+    let mut app = App::new(demo_session());
+    app.search_mode = SearchMode::Results;
+    app.search_query = "arrow".to_owned();
+    app.search_results.push(SearchHit {
+        object_ref: "d:demo-00-index/flow/node/n:seq_demo".parse().expect("object ref"),
+        label: "seq_demo".to_owned(),
+        score: 0,
+    });
+
+    let line = search_footer_line(&app, "");
+    let count_span =
+        line.spans.iter().find(|span| span.content.as_ref() == "1/1").expect("count span");
+    assert_eq!(count_span.style.fg, Some(Color::LightGreen));
+}
+
+#[test]
+fn search_results_footer_keeps_next_hint_and_adds_accept_close() {
+    let mut app = App::new(demo_session());
+    app.search_mode = SearchMode::Results;
+    app.search_query = "arrow".to_owned();
+    app.search_results.push(SearchHit {
+        object_ref: "d:demo-00-index/flow/node/n:seq_demo".parse().expect("object ref"),
+        label: "seq_demo".to_owned(),
+        score: 0,
+    });
+
+    let line = line_to_string(&search_footer_line(&app, ""));
+    assert!(line.contains("Next:n/N"));
+    assert!(line.contains("Accept:Enter"));
+    assert!(line.contains("Close:Esc"));
+}
+
+#[test]
+fn key_a_toggles_follow_ai_mode() {
+    let mut app = App::new(demo_session());
+    assert!(app.follow_ai);
+
+    app.handle_key_code(KeyCode::Char('a'));
+    assert!(!app.follow_ai);
+
+    app.handle_key_code(KeyCode::Char('a'));
+    assert!(app.follow_ai);
+}
+
+#[test]
+fn key_e_queues_edit_external_action() {
+    let mut app = App::new(demo_session());
+    assert_eq!(app.take_external_action(), None);
+
+    app.handle_key_code(KeyCode::Char('e'));
+
+    assert_eq!(app.take_external_action(), Some(ExternalAction::EditActiveDiagram));
+}
+
+#[test]
+fn key_shift_e_queues_description_edit_and_applies_without_rev_bump() {
+    let mut app = App::new(single_flowchart_session());
+    app.follow_ai = false;
+    app.handle_key_code(KeyCode::Char('E'));
+    assert_eq!(app.take_external_action(), Some(ExternalAction::EditActiveDiagramDescription));
+    assert_eq!(active_diagram_description_text(&app), "Flow\n\nNo description (E to edit)");
+
+    let diagram_id = app.active_diagram_id().cloned().expect("active diagram");
+    app.apply_diagram_description(&diagram_id, "  Login **happy path**.\n").expect("apply");
+
+    let diagram = app.session.diagrams().get(&diagram_id).expect("diagram");
+    assert_eq!(diagram.description(), Some("Login **happy path**."));
+    assert_eq!(diagram.rev(), 0);
+    assert_eq!(active_diagram_description_text(&app), "Flow\n\nLogin **happy path**.");
+}
+
+#[test]
+fn applying_edited_mermaid_updates_active_diagram_and_rev() {
+    let mut app = App::new(single_flowchart_session());
+    let diagram_id = app.active_diagram_id().cloned().expect("active diagram");
+    let diagram = app.session.diagrams().get(&diagram_id).cloned().expect("diagram");
+    let baseline_rev = diagram.rev();
+
+    let edited_mermaid =
+        export_diagram_mermaid(&diagram).expect("export").replacen("Start", "Start edited", 1);
+
+    app.apply_edited_mermaid_to_diagram(&diagram_id, diagram.kind(), baseline_rev, &edited_mermaid)
+        .expect("apply edited mermaid");
+
+    let updated = app.session.diagrams().get(&diagram_id).expect("updated diagram");
+    assert_eq!(updated.rev(), baseline_rev + 1);
+    assert!(app.pending_diagram_sync.is_none());
+}
+
+#[test]
+fn applying_comment_only_change_does_not_bump_rev() {
+    let mut app = App::new(single_flowchart_session());
+    let diagram_id = app.active_diagram_id().cloned().expect("active diagram");
+    let diagram = app.session.diagrams().get(&diagram_id).cloned().expect("diagram");
+    let baseline_rev = diagram.rev();
+
+    let edited_mermaid =
+        format!("{}\n%% comment-only edit", export_diagram_mermaid(&diagram).expect("export"));
+
+    app.apply_edited_mermaid_to_diagram(&diagram_id, diagram.kind(), baseline_rev, &edited_mermaid)
+        .expect("apply edited mermaid");
+
+    let updated = app.session.diagrams().get(&diagram_id).expect("updated diagram");
+    assert_eq!(updated.rev(), baseline_rev);
+}
+
+#[test]
+fn applying_edited_mermaid_keeps_anchored_edge_ids_and_the_selection() {
+    let mut session = Session::new(SessionId::new("s1").expect("session id"));
+    let diagram_id = DiagramId::new("flow").expect("diagram id");
+    let ast = parse_flowchart("flowchart LR\nA --> B\nB --> C\n").expect("parse flowchart");
+    let diagram = Diagram::new(diagram_id.clone(), "Flow", DiagramAst::Flowchart(ast));
+    session.diagrams_mut().insert(diagram_id.clone(), diagram);
+    session.set_active_diagram_id(Some(diagram_id.clone()));
+    let mut app = App::new(session);
+    app.follow_ai = false;
+    let edge_ref = ObjectRef::new(
+        diagram_id.clone(),
+        category_path(&["flow", "edge"]),
+        ObjectId::new("e:0002").expect("edge id"),
+    );
+    app.select_object_ref(&edge_ref);
+
+    let diagram = app.session.diagrams()[&diagram_id].clone();
+    let exported = export_diagram_mermaid(&diagram).expect("export");
+    let edited =
+        exported.replace("%% nereid:id=e:0001\nA --> B\n", "").replace("B --> C", "B -->|then| C");
+    app.apply_edited_mermaid_to_diagram(&diagram_id, diagram.kind(), diagram.rev(), &edited)
+        .expect("apply edited mermaid");
+
+    let DiagramAst::Flowchart(ast) = app.session.diagrams()[&diagram_id].ast() else {
+        unreachable!("flowchart");
+    };
+    let edge = &ast.edges()[&ObjectId::new("e:0002").expect("edge id")];
+    assert_eq!(ast.edges().len(), 1);
+    assert_eq!(edge.label(), Some("then"));
+    assert_eq!(app.selected_ref(), Some(&edge_ref));
+}
+
+#[test]
+fn undo_and_redo_replay_edits_across_diagram_switches() {
+    let mut app = App::new(demo_session());
+    app.follow_ai = false;
+    let diagram_id = app.active_diagram_id().cloned().expect("active diagram");
+    let diagram = app.session.diagrams().get(&diagram_id).cloned().expect("diagram");
+    let baseline_rev = diagram.rev();
+
+    let original_mermaid = export_diagram_mermaid(&diagram).expect("export");
+    let edited_mermaid = original_mermaid.replacen("Engine features", "Engine internals", 1);
+    assert_ne!(edited_mermaid, original_mermaid);
+    app.apply_edited_mermaid_to_diagram(&diagram_id, diagram.kind(), baseline_rev, &edited_mermaid)
+        .expect("apply edited mermaid");
+    let edited_ast = app.session.diagrams().get(&diagram_id).expect("diagram").ast().clone();
+
+    app.handle_key_code(KeyCode::Char(']'));
+    assert_ne!(app.active_diagram_id(), Some(&diagram_id));
+
+    app.handle_key_code(KeyCode::Char('u'));
+    assert_eq!(app.active_diagram_id(), Some(&diagram_id));
+    let undone = app.session.diagrams().get(&diagram_id).expect("diagram");
+    assert_eq!(undone.ast(), diagram.ast());
+    assert_eq!(undone.rev(), baseline_rev + 2);
+
+    app.handle_key(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL));
+    let redone = app.session.diagrams().get(&diagram_id).expect("diagram");
+    assert_eq!(redone.ast(), &edited_ast);
+    assert_eq!(redone.rev(), baseline_rev + 3);
+
+    app.handle_key(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL));
+    assert_eq!(app.session.diagrams().get(&diagram_id).expect("diagram").rev(), baseline_rev + 3);
+}
+
+#[test]
+fn enabling_follow_ai_jumps_to_agent_highlight_diagram() {
+    let mut app = App::new(demo_session());
+    let target: ObjectRef = "d:demo-seq/seq/participant/p:alice".parse().expect("object ref");
+    app.agent_highlights.blocking_lock().insert(target.clone());
+    app.follow_ai = false;
+
+    app.handle_key_code(KeyCode::Char('a'));
+
+    assert_eq!(app.active_diagram_id().map(ToString::to_string).as_deref(), Some("demo-seq"));
+    assert_eq!(app.selected_ref(), Some(&target));
+}
+
+#[test]
+fn sync_ignores_agent_highlight_when_follow_ai_is_disabled() {
+    let mut app = App::new(demo_session());
+    let target: ObjectRef = "d:demo-seq/seq/participant/p:alice".parse().expect("object ref");
+    app.agent_highlights.blocking_lock().insert(target);
+    app.follow_ai = false;
+
+    app.sync_from_ui_state();
+
+    assert_eq!(app.active_diagram_id().map(ToString::to_string).as_deref(), Some("demo-00-index"));
+}
+
+#[test]
+fn sync_logs_agent_highlight_expiry_in_timeline() {
+    let mut app = App::new(demo_session());
+    let ui_state = std::sync::Arc::new(tokio::sync::Mutex::new(UiState::default()));
+    app.ui_state = Some(ui_state.clone());
+    app.follow_ai = false;
+    let target: ObjectRef = "d:demo-seq/seq/participant/p:alice".parse().expect("object ref");
+    app.agent_highlights.blocking_lock().insert(target.clone());
+    app.sync_from_ui_state();
+
+    app.agent_highlights.blocking_lock().clear();
+    ui_state.blocking_lock().set_expired_agent_highlight(Some(target.clone()));
+    app.sync_from_ui_state();
+
+    let summaries = app.activity.events().iter().map(|event| event.summary()).collect::<Vec<_>>();
+    assert!(summaries.contains(&format!("highlight {target}")), "{summaries:?}");
+    assert_eq!(summaries.last(), Some(&format!("highlight {target} expired (no heartbeat)")));
+}
+
+#[test]
+fn sync_centers_viewport_on_requested_object_when_following_ai() {
+    let mut app = App::new(demo_session());
+    let ui_state = std::sync::Arc::new(tokio::sync::Mutex::new(UiState::default()));
+    app.ui_state = Some(ui_state.clone());
+    app.diagram_viewport = (20, 10);
+    let target: ObjectRef = "d:demo-seq/seq/participant/p:alice".parse().expect("object ref");
+    ui_state.blocking_lock().push_view_request(ViewRequest::CenterOn(target.clone()));
+
+    app.sync_from_ui_state();
+
+    assert_eq!(app.active_diagram_id().map(ToString::to_string).as_deref(), Some("demo-seq"));
+    assert_eq!(app.selected_ref(), Some(&target));
+    let (row, col) = line_spans_center(&app.base_highlight_index[&target]).expect("spans");
+    assert_eq!((app.pan_x, app.pan_y), (col as i32 - 10, row as i32 - 5));
+    assert!(!app.center_diagram_on_next_draw);
+    assert!(ui_state.blocking_lock().view_requests().is_empty());
+}
+
+#[test]
+fn proposal_commands_preview_accept_and_discard_agent_rewrites() {
+    let mut session = Session::new(SessionId::new("s1").expect("session id"));
+    let diagram_id = DiagramId::new("flow").expect("diagram id");
+    let ast = parse_flowchart("flowchart TD\nA --> B\n").expect("parse");
+    let diagram = Diagram::new(diagram_id.clone(), "Flow", DiagramAst::Flowchart(ast));
+    session.diagrams_mut().insert(diagram_id.clone(), diagram);
+    session.set_active_diagram_id(Some(diagram_id.clone()));
+    let mut app = App::new(session);
+    let ui_state = std::sync::Arc::new(tokio::sync::Mutex::new(UiState::default()));
+    app.ui_state = Some(ui_state.clone());
+    let proposed = parse_flowchart("flowchart TD\nA --> B\nB --> C\n").expect("parse");
+    let first = ui_state.blocking_lock().add_proposal(
+        diagram_id.clone(),
+        0,
+        Some("add C".to_owned()),
+        DiagramAst::Flowchart(proposed.clone()),
+    );
+    let toast = |app: &App| app.toast.as_ref().map(|toast| toast.message.clone());
+
+    app.sync_from_ui_state();
+    assert_eq!(
+        toast(&app).as_deref(),
+        Some("Agent proposed proposal:1 for flow: add C (:proposal to preview)")
+    );
+
+    app.run_command("proposal");
+    let node_c: ObjectRef = "d:flow/flow/node/n:C".parse().expect("object ref");
+    assert!(app.objects.iter().any(|object| object.object_ref == node_c));
+    assert!(app.object_styles[&node_c].heavy);
+    assert!(app.object_styles.values().any(|style| style.dim));
+
+    app.run_command("proposal");
+    assert!(app.proposal_preview.is_none());
+    assert!(!app.objects.iter().any(|object| object.object_ref == node_c));
+
+    app.run_command("proposal proposal:1");
+    app.run_command("accept");
+    let accepted = &app.session.diagrams()[&diagram_id];
+    assert_eq!(accepted.ast(), &DiagramAst::Flowchart(proposed));
+    assert_eq!(accepted.rev(), 1);
+    assert_eq!(
+        ui_state.blocking_lock().proposals()[&first].status,
+        ProposalStatus::Accepted { rev: 1 }
+    );
+
+    let stale = ui_state.blocking_lock().add_proposal(
+        diagram_id.clone(),
+        0,
+        None,
+        DiagramAst::Flowchart(FlowchartAst::default()),
+    );
+    app.run_command("proposal");
+    app.run_command("accept");
+    assert_eq!(app.session.diagrams()[&diagram_id].rev(), 1);
+    app.run_command("discard");
+    assert_eq!(ui_state.blocking_lock().proposals()[&stale].status, ProposalStatus::Discarded);
+    assert!(app.proposal_preview.is_none());
+    assert_eq!(parse_tui_command("accept now"), Err("Usage: accept".to_owned()));
+}
+
+#[test]
+fn sync_drops_view_requests_when_follow_ai_is_disabled() {
+    let mut app = App::new(demo_session());
+    let ui_state = std::sync::Arc::new(tokio::sync::Mutex::new(UiState::default()));
+    ui_state.blocking_lock().set_follow_ai(false);
+    app.ui_state = Some(ui_state.clone());
+    let target: ObjectRef = "d:demo-seq/seq/participant/p:alice".parse().expect("object ref");
+    ui_state.blocking_lock().push_view_request(ViewRequest::CenterOn(target));
+
+    app.sync_from_ui_state();
+
+    assert_eq!(app.active_diagram_id().map(ToString::to_string).as_deref(), Some("demo-00-index"));
+    assert!(ui_state.blocking_lock().view_requests().is_empty());
+}
+
+#[test]
+fn sync_applies_scroll_diagram_and_zoom_requests_in_order() {
+    let mut app = App::new(demo_session());
+    let ui_state = std::sync::Arc::new(tokio::sync::Mutex::new(UiState::default()));
+    app.ui_state = Some(ui_state.clone());
+    app.diagram_viewport = (10, 4);
+    {
+        let mut ui_state = ui_state.blocking_lock();
+        ui_state.push_view_request(ViewRequest::SetActiveDiagram(
+            DiagramId::new("demo-seq").expect("diagram id"),
+        ));
+        ui_state.push_view_request(ViewRequest::Scroll { dx: 4, dy: 2 });
+        ui_state.push_view_request(ViewRequest::Zoom(ViewZoom::Compact));
+    }
+
+    app.sync_from_ui_state();
+    app.publish_viewport_to_ui_state();
+
+    assert_eq!(app.active_diagram_id().map(ToString::to_string).as_deref(), Some("demo-seq"));
+    assert_eq!((app.pan_x, app.pan_y), (4, 2));
+    assert_eq!(app.zoom, ViewZoom::Compact);
+    assert_eq!(
+        ui_state.blocking_lock().human_viewport(),
+        HumanViewport { scroll_x: 4, scroll_y: 2, width: 10, height: 4, zoom: ViewZoom::Compact }
+    );
+}
+
+#[test]
+fn visible_diagram_cells_crop_text_and_spans_to_the_viewport() {
+    let target: ObjectRef = "d:flow/flow/node/n:B".parse().expect("object ref");
+    let hidden: ObjectRef = "d:flow/flow/node/n:C".parse().expect("object ref");
+    let highlight_index =
+        BTreeMap::from([(target.clone(), vec![(0, 2, 5), (1, 2, 5)]), (hidden, vec![(3, 0, 2)])]);
+
+    let (lines, objects) =
+        visible_diagram_cells("abcdef\nghijkl\nmnopqr\nstuvwx", &highlight_index, (-1, 0), (4, 2));
+    assert_eq!(lines, vec![" abc", " ghi"]);
+    assert_eq!(objects, BTreeMap::from([(target.clone(), vec![(0, 3, 3), (1, 3, 3)])]));
+
+    let (lines, objects) =
+        visible_diagram_cells("abcdef\nghijkl", &highlight_index, (3, 1), (10, 3));
+    assert_eq!(lines, vec!["jkl", "", ""]);
+    assert_eq!(objects, BTreeMap::from([(target, vec![(0, 0, 2)])]));
+}
+
+#[test]
+fn mouse_clicks_select_diagram_objects_and_list_rows_and_wheel_pans() {
+    let mut app = App::new(single_flowchart_session());
+    app.follow_ai = false;
+    let mouse = |kind, column, row| MouseEvent { kind, column, row, modifiers: KeyModifiers::NONE };
+    let target: ObjectRef = "d:flow/flow/node/n:B".parse().expect("object ref");
+    assert_ne!(app.selected_ref(), Some(&target));
+    let (y, x0, _) = app.base_highlight_index[&target][0];
+
+    // The diagram is drawn at (1, 2) with one column of left padding from the negative pan.
+    app.diagram_content_area = Some(Rect::new(1, 2, 40, 10));
+    app.diagram_viewport = (40, 10);
+    app.pan_x = -1;
+    app.pan_y = 0;
+    app.focus = Focus::Objects;
+    let click = MouseEventKind::Down(MouseButton::Left);
+    app.handle_mouse(mouse(click, 1 + 1 + x0 as u16, 2 + y as u16));
+    assert_eq!(app.selected_ref(), Some(&target));
+    assert_eq!(app.focus, Focus::Diagram);
+
+    app.diagram_viewport = (4, 2);
+    app.pan_x = 0;
+    app.pan_y = 0;
+    app.handle_mouse(mouse(MouseEventKind::ScrollDown, 5, 5));
+    assert_eq!((app.pan_x, app.pan_y), (0, 3));
+    let shift_scroll =
+        MouseEvent { modifiers: KeyModifiers::SHIFT, ..mouse(MouseEventKind::ScrollDown, 5, 5) };
+    app.handle_mouse(shift_scroll);
+    assert_eq!((app.pan_x, app.pan_y), (3, 3));
+    app.handle_mouse(mouse(MouseEventKind::ScrollUp, 60, 5));
+    assert_eq!((app.pan_x, app.pan_y), (3, 3));
+
+    app.objects_visible = true;
+    app.objects_hit_area =
+        Some(ListHitArea { area: Rect::new(50, 0, 20, 6), first_visible_idx: 0 });
+    app.handle_mouse(mouse(click, 52, 1));
+    assert_eq!(app.objects_state.selected(), Some(0));
+    assert_eq!(app.focus, Focus::Objects);
+    app.handle_mouse(mouse(click, 52, 5));
+    assert_eq!(app.objects_state.selected(), Some(0));
+}
+
+#[test]
+fn zoom_keys_step_through_flowchart_spacing() {
+    let mut app = App::new(single_flowchart_session());
+    app.follow_ai = false;
+    let width = |app: &App| app.base_diagram.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+    let normal = width(&app);
+
+    app.handle_key_code(KeyCode::Char('+'));
+    assert_eq!(app.zoom, ViewZoom::Spacious);
+    assert!(width(&app) > normal);
+    app.handle_key_code(KeyCode::Char('+'));
+    assert_eq!(app.zoom, ViewZoom::Spacious);
+
+    app.handle_key_code(KeyCode::Char('-'));
+    app.handle_key_code(KeyCode::Char('-'));
+    assert_eq!(app.zoom, ViewZoom::Compact);
+    let compact = width(&app);
+    assert!(compact < normal);
+
+    app.handle_key_code(KeyCode::Char('-'));
+    assert_eq!(app.zoom, ViewZoom::Mini);
+    app.handle_key_code(KeyCode::Char('-'));
+    app.handle_key_code(KeyCode::Char('-'));
+    assert_eq!(app.zoom, ViewZoom::Glyph);
+    assert!(width(&app) < compact, "{}", app.base_diagram);
+    assert!(app.base_diagram.starts_with('S'), "{}", app.base_diagram);
+}
+
+#[test]
+fn zz_centers_viewport_on_selected_object() {
+    let mut app = App::new(demo_session());
+    app.follow_ai = false;
+    app.diagram_viewport = (30, 8);
+    let target = app.selected_ref().cloned().expect("selected object");
+    let (row, col) = line_spans_center(&app.base_highlight_index[&target]).expect("spans");
+
+    app.handle_key_code(KeyCode::Char('z'));
+    assert_eq!((app.pan_x, app.pan_y), (0, 0));
+    app.handle_key_code(KeyCode::Char('j'));
+    app.handle_key_code(KeyCode::Char('z'));
+    assert_eq!((app.pan_x, app.pan_y), (0, 1));
+
+    app.handle_key_code(KeyCode::Char('z'));
+    assert_eq!((app.pan_x, app.pan_y), (col as i32 - 15, row as i32 - 4));
+}
+
+#[test]
+fn goto_command_centers_viewport_on_coordinate() {
+    let mut app = App::new(demo_session());
+    app.diagram_viewport = (20, 10);
+
+    for ch in ":goto 12, 40".chars() {
+        app.handle_key_code(KeyCode::Char(ch));
+    }
+    assert_eq!(app.command_line.as_deref(), Some("goto 12, 40"));
+    app.handle_key_code(KeyCode::Enter);
+
+    assert_eq!(app.command_line, None);
+    assert_eq!((app.pan_x, app.pan_y), (30, 7));
+
+    assert_eq!(parse_tui_command("goto 3,4"), Ok(TuiCommand::Goto { row: 3, col: 4 }));
+    assert!(parse_tui_command("goto 3").is_err());
+    assert!(parse_tui_command("goto -1,2").is_err());
+    assert_eq!(parse_tui_command("zoom 2"), Err("Unknown command: zoom".to_owned()));
+    assert_eq!(line_spans_center(&[(2, 4, 8), (4, 2, 6)]), Some((3, 5)));
+    assert_eq!(line_spans_center(&[]), None);
+}
+
+#[test]
+fn style_command_applies_rules_to_the_diagram_view() {
+    let mut session = Session::new(SessionId::new("s1").expect("session id"));
+    let diagram_id = DiagramId::new("flow").expect("diagram id");
+    let ast = parse_flowchart("flowchart TD\nA --> B\nA --> C\nA --> D\n").expect("parse");
+    let diagram = Diagram::new(diagram_id.clone(), "Flow", DiagramAst::Flowchart(ast));
+    session.diagrams_mut().insert(diagram_id.clone(), diagram);
+    session.set_active_diagram_id(Some(diagram_id));
+    let mut app = App::new(session);
+    app.follow_ai = false;
+    let cells = |app: &App| {
+        app.diagram_text()
+            .lines
+            .iter()
+            .flat_map(|line| line.spans.iter())
+            .flat_map(|span| span.content.chars().map(move |ch| (ch, span.style)))
+            .collect::<Vec<_>>()
+    };
+    assert!(!cells(&app).iter().any(|(ch, _)| *ch == '━'));
+
+    app.run_command("style degree>=3 => heavy");
+    app.run_command("style degree<2 => dim");
+    assert_eq!(
+        app.toast.as_ref().map(|toast| toast.message.as_str()),
+        Some("Style rules: degree>=3 => heavy; degree<2 => dim")
+    );
+    let styled = cells(&app);
+    assert!(styled
+        .iter()
+        .any(|(ch, style)| *ch == '━' && style.add_modifier.contains(Modifier::BOLD)));
+    assert!(styled.iter().any(|(_, style)| style.add_modifier.contains(Modifier::DIM)));
+
+    app.run_command("style clear");
+    assert!(app.session.style_rules().is_empty());
+    assert!(!cells(&app).iter().any(|(ch, _)| *ch == '━'));
+    assert_eq!(parse_tui_command("style"), Ok(TuiCommand::StyleList));
+    assert!(parse_tui_command("style degree => heavy").is_err());
+}
+
+#[test]
+fn idprefix_and_lint_commands_check_ids_and_edits_follow_the_conventions() {
+    let mut session = Session::new(SessionId::new("s1").expect("session id"));
+    let diagram_id = DiagramId::new("flow").expect("diagram id");
+    let ast = parse_flowchart("flowchart TD\nsvc_orders --> B\n").expect("parse");
+    let diagram = Diagram::new(diagram_id.clone(), "Flow", DiagramAst::Flowchart(ast));
+    session.diagrams_mut().insert(diagram_id.clone(), diagram);
+    session.set_active_diagram_id(Some(diagram_id.clone()));
+    let mut app = App::new(session);
+    let toast = |app: &App| app.toast.as_ref().map(|toast| toast.message.clone());
+
+    app.run_command("lint");
+    assert_eq!(toast(&app).as_deref(), Some("No id prefix rules to lint against"));
+    app.run_command("idprefix flow/node => svc|db");
+    assert_eq!(toast(&app).as_deref(), Some("Id prefix rules: flow/node => svc|db"));
+    app.run_command("lint");
+    assert_eq!(
+        toast(&app).as_deref(),
+        Some("2 id(s) break the id prefix rules, e.g. d:flow/flow/node/n:B (flow/node => svc|db)")
+    );
+
+    let rev = app.session.diagrams()[&diagram_id].rev();
+    app.apply_edited_mermaid_to_diagram(
+        &diagram_id,
+        DiagramKind::Flowchart,
+        rev,
+        "flowchart TD\nsvc_orders --> db_orders\n",
+    )
+    .expect("edit");
+    let node_ids = match app.session.diagrams()[&diagram_id].ast() {
+        DiagramAst::Flowchart(ast) => ast.nodes().keys().map(ToString::to_string).collect(),
+        _ => Vec::new(),
+    };
+    assert_eq!(node_ids, vec!["db:orders", "svc:orders"]);
+    app.run_command("lint");
+    assert_eq!(toast(&app).as_deref(), Some("All object ids follow the id prefix rules"));
+
+    app.run_command("idprefix clear");
+    assert!(app.session.id_prefix_rules().is_empty());
+    assert!(parse_tui_command("idprefix flow/node").is_err());
+}
+
+#[test]
+fn filter_command_limits_objects_panel_to_tagged_objects() {
+    let mut session = Session::new(SessionId::new("s1").expect("session id"));
+    let diagram_id = DiagramId::new("flow").expect("diagram id");
+    let mut ast = parse_flowchart("flowchart TD\nA --> B\nB --> C\n").expect("parse");
+    let node_b = ObjectId::new("n:B").expect("node id");
+    ast.nodes_mut()
+        .get_mut(&node_b)
+        .expect("node B")
+        .set_annotations(crate::model::ObjectAnnotations::new(["legacy"], BTreeMap::new()));
+    let diagram = Diagram::new(diagram_id.clone(), "Flow", DiagramAst::Flowchart(ast));
+    session.diagrams_mut().insert(diagram_id.clone(), diagram);
+    session.set_active_diagram_id(Some(diagram_id.clone()));
+    let mut app = App::new(session);
+    let visible = |app: &App| {
+        app.visible_object_indices
+            .iter()
+            .map(|&idx| app.objects[idx].object_ref.to_string())
+            .collect::<Vec<_>>()
+    };
+    let all = visible(&app).len();
+
+    app.run_command("filter tag=legacy");
+    assert_eq!(visible(&app), vec!["d:flow/flow/node/n:B"]);
+    assert_eq!(
+        app.toast.as_ref().map(|toast| toast.message.as_str()),
+        Some("Showing objects tagged legacy (1)")
+    );
+
+    let node_c = ObjectRef::new(
+        diagram_id,
+        crate::model::CategoryPath::new(vec!["flow".to_owned(), "node".to_owned()])
+            .expect("category"),
+        ObjectId::new("n:C").expect("node id"),
+    );
+    app.select_object_ref(&node_c);
+    assert_eq!(app.objects_tag_filter, None);
+    assert_eq!(visible(&app).len(), all);
 
-    let enter_span =
-        line.spans.iter().find(|span| span.content.as_ref() == "Enter").expect("Enter span");
-    assert_eq!(enter_span.style.fg, Some(Color::Cyan));
+    assert_eq!(parse_tui_command("filter"), Ok(TuiCommand::Filter(None)));
+    assert!(parse_tui_command("filter label=x").is_err());
 }
 
 #[test]
-fn search_footer_adds_three_spaces_after_query() {
-    let mut app = App::new(demo_session());
-    app.search_mode = SearchMode::Editing;
-    app.search_query = "arrow".to_owned();
+fn path_command_selects_cheapest_weighted_route() {
+    let mut session = Session::new(SessionId::new("s1").expect("session id"));
+    let diagram_id = DiagramId::new("flow").expect("diagram id");
+    let ast = parse_flowchart(
+        "flowchart LR\nA[Start] -->|w=5| B[Mid]\nB --> D[Done]\nA --> C[Side]\nC -->|2| D\n",
+    )
+    .expect("parse flowchart");
+    let diagram = Diagram::new(diagram_id.clone(), "Flow", DiagramAst::Flowchart(ast));
+    session.diagrams_mut().insert(diagram_id.clone(), diagram);
+    session.set_active_diagram_id(Some(diagram_id));
+    let mut app = App::new(session);
+    app.follow_ai = false;
 
-    let line = line_to_string(&search_footer_line(&app, ""));
-    assert!(line.contains("/arrow   0 | Accept:Enter"));
-}
+    app.run_command("path start D");
 
-#[test]
-fn search_footer_renders_count_in_bright_green() {
-    let mut app = App::new(demo_session());
-    app.search_mode = SearchMode::Results;
-    app.search_query = "arrow".to_owned();
-    app.search_results.push("d:demo-00-index/flow/node/n:seq_demo".parse().expect("object ref"));
+    let selected = app
+        .session
+        .selected_object_refs()
+        .iter()
+        .filter(|object_ref| object_ref.category().segments()[1] == "node")
+        .map(|object_ref| object_ref.object_id().as_str().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(selected, vec!["n:A", "n:C", "n:D"]);
+    assert_eq!(app.session.selected_object_refs().len(), 5);
+    assert_eq!(
+        app.toast.as_ref().map(|toast| toast.message.as_str()),
+        Some("Path cost 3: Start (0) → Side (1) → Done (3)")
+    );
 
-    let line = search_footer_line(&app, "");
-    let count_span =
-        line.spans.iter().find(|span| span.content.as_ref() == "1/1").expect("count span");
-    assert_eq!(count_span.style.fg, Some(Color::LightGreen));
+    assert_eq!(
+        parse_tui_command("path a b"),
+        Ok(TuiCommand::Path { from: "a".to_owned(), to: "b".to_owned() })
+    );
+    assert!(parse_tui_command("path a").is_err());
 }
 
 #[test]
-fn search_results_footer_keeps_next_hint_and_adds_accept_close() {
-    let mut app = App::new(demo_session());
-    app.search_mode = SearchMode::Results;
-    app.search_query = "arrow".to_owned();
-    app.search_results.push("d:demo-00-index/flow/node/n:seq_demo".parse().expect("object ref"));
+fn toseq_and_toflow_commands_scaffold_linked_diagrams() {
+    let mut session = Session::new(SessionId::new("s1").expect("session id"));
+    let diagram_id = DiagramId::new("flow").expect("diagram id");
+    let ast = parse_flowchart("flowchart LR\nA[Start] --> B[Mid]\nB -->|done| D[Done]\n")
+        .expect("parse flowchart");
+    let diagram = Diagram::new(diagram_id.clone(), "Flow", DiagramAst::Flowchart(ast));
+    session.diagrams_mut().insert(diagram_id.clone(), diagram);
+    session.set_active_diagram_id(Some(diagram_id));
+    let mut app = App::new(session);
+    app.follow_ai = false;
 
-    let line = line_to_string(&search_footer_line(&app, ""));
-    assert!(line.contains("Next:n/N"));
-    assert!(line.contains("Accept:Enter"));
-    assert!(line.contains("Close:Esc"));
+    app.run_command("toseq");
+    assert_eq!(
+        app.toast.as_ref().map(|toast| toast.message.as_str()),
+        Some("Select a connected chain of edges first (e.g. :path a b)")
+    );
+
+    app.run_command("path A D");
+    app.run_command("toseq");
+
+    assert_eq!(app.active_diagram_id().map(|id| id.as_str()), Some("flow-seq"));
+    let DiagramAst::Sequence(seq) = app.session.diagrams()[app.active_diagram_id().unwrap()].ast()
+    else {
+        panic!("expected sequence diagram");
+    };
+    let messages = seq.messages_in_order().into_iter().map(|m| m.text()).collect::<Vec<_>>();
+    assert_eq!(messages, vec!["Mid", "done"]);
+    assert_eq!(app.session.xrefs().len(), 5);
+    assert!(app.xrefs.iter().all(|item| item.xref.kind() == "derived_from"));
+    assert_eq!(parse_tui_command("toseq now"), Err("Usage: toseq".to_owned()));
+
+    app.run_command("toflow");
+    let flow_id = app.active_diagram_id().cloned().expect("flowchart");
+    assert_eq!(flow_id.as_str(), "flow-seq-flow");
+    let DiagramAst::Flowchart(flow) = app.session.diagrams()[&flow_id].ast() else {
+        panic!("expected flowchart");
+    };
+    assert_eq!(flow.nodes().len(), 3);
+    assert_eq!(flow.edges().values().filter_map(|edge| edge.weight()).sum::<u64>(), 2);
+    assert_eq!(app.session.xrefs().len(), 10);
 }
 
 #[test]
-fn key_a_toggles_follow_ai_mode() {
-    let mut app = App::new(demo_session());
-    assert!(app.follow_ai);
+fn critical_command_selects_longest_duration_chain() {
+    let mut session = Session::new(SessionId::new("s1").expect("session id"));
+    let diagram_id = DiagramId::new("seq").expect("diagram id");
+    let mut ast = parse_sequence_diagram("sequenceDiagram\nA->>B: one\nC->>D: two\nB->>C: three\n")
+        .expect("parse sequence");
+    ast.messages_mut()[1].set_duration(Some(4));
+    let diagram = Diagram::new(diagram_id.clone(), "Seq", DiagramAst::Sequence(ast));
+    session.diagrams_mut().insert(diagram_id.clone(), diagram);
+    session.set_active_diagram_id(Some(diagram_id));
+    let mut app = App::new(session);
+    app.follow_ai = false;
 
-    app.handle_key_code(KeyCode::Char('a'));
-    assert!(!app.follow_ai);
+    app.run_command("critical");
 
-    app.handle_key_code(KeyCode::Char('a'));
-    assert!(app.follow_ai);
+    assert_eq!(app.session.selected_object_refs().len(), 2);
+    assert_eq!(
+        app.toast.as_ref().map(|toast| toast.message.as_str()),
+        Some("Critical path 5: two (4) → three (5)")
+    );
+    assert_eq!(parse_tui_command("critical"), Ok(TuiCommand::Critical));
+    assert!(parse_tui_command("critical now").is_err());
 }
 
 #[test]
-fn key_e_queues_edit_external_action() {
-    let mut app = App::new(demo_session());
-    assert_eq!(app.take_external_action(), None);
+fn cuts_command_selects_articulation_nodes_and_bridges() {
+    let mut session = Session::new(SessionId::new("s1").expect("session id"));
+    let diagram_id = DiagramId::new("flow").expect("diagram id");
+    let ast = parse_flowchart("flowchart LR\na --> b\nb --> c\nc --> a\nc --> d\n")
+        .expect("parse flowchart");
+    let diagram = Diagram::new(diagram_id.clone(), "Flow", DiagramAst::Flowchart(ast));
+    session.diagrams_mut().insert(diagram_id.clone(), diagram);
+    session.set_active_diagram_id(Some(diagram_id));
+    let mut app = App::new(session);
+    app.follow_ai = false;
 
-    app.handle_key_code(KeyCode::Char('e'));
+    app.run_command("cuts");
 
-    assert_eq!(app.take_external_action(), Some(ExternalAction::EditActiveDiagram));
+    let selected =
+        app.session.selected_object_refs().iter().map(ToString::to_string).collect::<Vec<_>>();
+    assert_eq!(selected, ["d:flow/flow/edge/e:0004", "d:flow/flow/node/n:c"]);
+    assert_eq!(
+        app.toast.as_ref().map(|toast| toast.message.as_str()),
+        Some("1 cut points, 1 bridges selected")
+    );
+    assert_eq!(parse_tui_command("cuts"), Ok(TuiCommand::Cuts));
+    assert!(parse_tui_command("cuts now").is_err());
 }
 
 #[test]
-fn applying_edited_mermaid_updates_active_diagram_and_rev() {
-    let mut app = App::new(single_flowchart_session());
-    let diagram_id = app.active_diagram_id().cloned().expect("active diagram");
-    let diagram = app.session.diagrams().get(&diagram_id).cloned().expect("diagram");
-    let baseline_rev = diagram.rev();
-
-    let edited_mermaid =
-        export_diagram_mermaid(&diagram).expect("export").replacen("Start", "Start edited", 1);
+fn p_key_adds_path_between_two_selected_nodes_in_either_direction() {
+    let mut session = Session::new(SessionId::new("s1").expect("session id"));
+    let diagram_id = DiagramId::new("flow").expect("diagram id");
+    let ast = parse_flowchart("flowchart LR\nz --> a\na --> b\nb --> c\nx --> c\n")
+        .expect("parse flowchart");
+    let diagram = Diagram::new(diagram_id.clone(), "Flow", DiagramAst::Flowchart(ast));
+    session.diagrams_mut().insert(diagram_id.clone(), diagram);
+    session.set_active_diagram_id(Some(diagram_id.clone()));
+    let mut app = App::new(session);
+    app.follow_ai = false;
+    let node = |id: &str| {
+        ObjectRef::new(
+            diagram_id.clone(),
+            crate::model::CategoryPath::new(vec!["flow".to_owned(), "node".to_owned()])
+                .expect("category"),
+            ObjectId::new(id).expect("node id"),
+        )
+    };
 
-    app.apply_edited_mermaid_to_diagram(&diagram_id, diagram.kind(), baseline_rev, &edited_mermaid)
-        .expect("apply edited mermaid");
+    app.session.selected_object_refs_mut().insert(node("n:c"));
+    app.handle_key_code(KeyCode::Char('P'));
+    assert_eq!(
+        app.toast.as_ref().map(|toast| toast.message.as_str()),
+        Some("Select two flow nodes to highlight the path between them")
+    );
 
-    let updated = app.session.diagrams().get(&diagram_id).expect("updated diagram");
-    assert_eq!(updated.rev(), baseline_rev + 1);
-    assert!(app.pending_diagram_sync.is_none());
+    app.session.selected_object_refs_mut().insert(node("n:z"));
+    app.handle_key_code(KeyCode::Char('P'));
+    let selected =
+        app.session.selected_object_refs().iter().map(ToString::to_string).collect::<Vec<_>>();
+    assert_eq!(
+        selected,
+        [
+            "d:flow/flow/edge/e:0001",
+            "d:flow/flow/edge/e:0002",
+            "d:flow/flow/edge/e:0003",
+            "d:flow/flow/node/n:a",
+            "d:flow/flow/node/n:b",
+            "d:flow/flow/node/n:c",
+            "d:flow/flow/node/n:z",
+        ]
+    );
+    assert_eq!(
+        app.toast.as_ref().map(|toast| toast.message.as_str()),
+        Some("Path n:z → n:c: 4 nodes, 3 edges selected")
+    );
 }
 
 #[test]
-fn applying_comment_only_change_does_not_bump_rev() {
-    let mut app = App::new(single_flowchart_session());
-    let diagram_id = app.active_diagram_id().cloned().expect("active diagram");
-    let diagram = app.session.diagrams().get(&diagram_id).cloned().expect("diagram");
-    let baseline_rev = diagram.rev();
-
-    let edited_mermaid =
-        format!("{}\n%% comment-only edit", export_diagram_mermaid(&diagram).expect("export"));
+fn tidy_command_previews_and_persists_order_hints_only_after_confirmation() {
+    let mut session = Session::new(SessionId::new("s1").expect("session id"));
+    let diagram_id = DiagramId::new("flow").expect("diagram id");
+    let ast = parse_flowchart("flowchart LR\na --> d\na --> c\nb --> e\nd --> e\nb --> d\n")
+        .expect("parse flowchart");
+    let diagram = Diagram::new(diagram_id.clone(), "Flow", DiagramAst::Flowchart(ast));
+    session.diagrams_mut().insert(diagram_id.clone(), diagram);
+    session.set_active_diagram_id(Some(diagram_id.clone()));
+    let mut app = App::new(session);
+    app.follow_ai = false;
+    let hints = |app: &App| match app.session.diagrams()[&diagram_id].ast() {
+        DiagramAst::Flowchart(ast) => ast.node_order_hints().clone(),
+        _ => unreachable!("flowchart"),
+    };
 
-    app.apply_edited_mermaid_to_diagram(&diagram_id, diagram.kind(), baseline_rev, &edited_mermaid)
-        .expect("apply edited mermaid");
+    app.run_command("tidy");
+    let prompt = app.tidy_prompt.clone().expect("tidy preview");
+    let lines = tidy_prompt_lines(&prompt);
+    assert_eq!(lines[0], "Crossings   1 → 0");
+    assert!(lines[1].starts_with("Size        ") && lines[1].ends_with(" compacted)"), "{lines:?}");
+    assert!(lines.iter().any(|line| line.starts_with("Move        n:")), "{lines:?}");
+    assert_eq!(lines.last().map(String::as_str), Some("Enter/y apply · Esc/n cancel"));
+
+    // Other keys leave the prompt open; Esc drops it without touching the diagram.
+    app.handle_key_code(KeyCode::Char('j'));
+    assert!(app.tidy_prompt.is_some());
+    app.handle_key_code(KeyCode::Esc);
+    assert!(app.tidy_prompt.is_none());
+    assert!(hints(&app).is_empty());
+    assert_eq!(app.session.diagrams()[&diagram_id].rev(), 0);
+
+    app.run_command("tidy");
+    app.handle_key_code(KeyCode::Enter);
+    assert!(app.tidy_prompt.is_none());
+    assert_eq!(hints(&app), prompt.preview.order_hints);
+    assert_eq!(app.session.diagrams()[&diagram_id].rev(), 1);
+
+    app.run_command("tidy");
+    assert!(app.tidy_prompt.is_none());
+    assert_eq!(
+        app.toast.as_ref().map(|toast| toast.message.as_str()),
+        Some("Layout already tidy: flow")
+    );
 
-    let updated = app.session.diagrams().get(&diagram_id).expect("updated diagram");
-    assert_eq!(updated.rev(), baseline_rev);
+    app.handle_key_code(KeyCode::Char('u'));
+    assert!(hints(&app).is_empty());
+    assert_eq!(parse_tui_command("tidy"), Ok(TuiCommand::Tidy));
+    assert!(parse_tui_command("tidy now").is_err());
 }
 
 #[test]
-fn enabling_follow_ai_jumps_to_agent_highlight_diagram() {
-    let mut app = App::new(demo_session());
-    let target: ObjectRef = "d:demo-seq/seq/participant/p:alice".parse().expect("object ref");
-    app.agent_highlights.blocking_lock().insert(target.clone());
+fn angle_brackets_move_the_selected_participant_between_lifelines() {
+    let mut session = Session::new(SessionId::new("s1").expect("session id"));
+    let diagram_id = DiagramId::new("seq").expect("diagram id");
+    let ast = parse_sequence_diagram(
+        "sequenceDiagram\nparticipant A\nbox Team\nparticipant B\nparticipant C\nend\nA->>B: hi\n",
+    )
+    .expect("parse sequence");
+    let diagram = Diagram::new(diagram_id.clone(), "Seq", DiagramAst::Sequence(ast));
+    session.diagrams_mut().insert(diagram_id.clone(), diagram);
+    session.set_active_diagram_id(Some(diagram_id.clone()));
+    let mut app = App::new(session);
     app.follow_ai = false;
+    let order = |app: &App| match app.session.diagrams()[&diagram_id].ast() {
+        DiagramAst::Sequence(ast) => {
+            ast.participants_in_order().into_iter().map(ToString::to_string).collect::<Vec<_>>()
+        }
+        _ => unreachable!("sequence"),
+    };
+    let participant = |name: &str| {
+        ObjectRef::new(
+            diagram_id.clone(),
+            category_path(&["seq", "participant"]),
+            ObjectId::new(format!("p:{name}")).expect("participant id"),
+        )
+    };
 
-    app.handle_key_code(KeyCode::Char('a'));
+    app.select_object_ref(&participant("A"));
+    app.handle_key_code(KeyCode::Char('>'));
+    assert_eq!(order(&app), ["p:B", "p:C", "p:A"]);
+    assert_eq!(app.session.diagrams()[&diagram_id].rev(), 1);
+    let exported = export_diagram_mermaid(&app.session.diagrams()[&diagram_id]).expect("export");
+    assert!(exported.starts_with(
+        "sequenceDiagram\nbox Team\nparticipant B\nparticipant C\nend\nparticipant A\n"
+    ));
 
-    assert_eq!(app.active_diagram_id().map(ToString::to_string).as_deref(), Some("demo-seq"));
-    assert_eq!(app.selected_ref(), Some(&target));
+    // Box members only swap places within their box.
+    app.select_object_ref(&participant("C"));
+    app.handle_key_code(KeyCode::Char('<'));
+    assert_eq!(order(&app), ["p:C", "p:B", "p:A"]);
+    app.handle_key_code(KeyCode::Char('<'));
+    assert_eq!(app.session.diagrams()[&diagram_id].rev(), 2);
+    app.select_object_ref(&participant("B"));
+    app.handle_key_code(KeyCode::Char('>'));
+    assert_eq!(order(&app), ["p:C", "p:B", "p:A"]);
+    assert_eq!(
+        app.toast.as_ref().map(|toast| toast.message.as_str()),
+        Some("Participants stay inside their box")
+    );
+
+    app.handle_key_code(KeyCode::Char('u'));
+    assert_eq!(order(&app), ["p:B", "p:C", "p:A"]);
 }
 
 #[test]
-fn sync_ignores_agent_highlight_when_follow_ai_is_disabled() {
-    let mut app = App::new(demo_session());
-    let target: ObjectRef = "d:demo-seq/seq/participant/p:alice".parse().expect("object ref");
-    app.agent_highlights.blocking_lock().insert(target);
+fn shift_jk_in_objects_moves_the_selected_message_through_the_order() {
+    let mut session = Session::new(SessionId::new("s1").expect("session id"));
+    let diagram_id = DiagramId::new("seq").expect("diagram id");
+    let ast = parse_sequence_diagram(
+        "sequenceDiagram\nparticipant A\nparticipant B\nA->>B: one\nB->>A: two\nA->>B: three\n",
+    )
+    .expect("parse sequence");
+    let diagram = Diagram::new(diagram_id.clone(), "Seq", DiagramAst::Sequence(ast));
+    session.diagrams_mut().insert(diagram_id.clone(), diagram);
+    session.set_active_diagram_id(Some(diagram_id.clone()));
+    let mut app = App::new(session);
     app.follow_ai = false;
+    let texts = |app: &App| match app.session.diagrams()[&diagram_id].ast() {
+        DiagramAst::Sequence(ast) => {
+            ast.messages().iter().map(|message| message.text().to_owned()).collect::<Vec<_>>()
+        }
+        _ => unreachable!("sequence"),
+    };
+    let third = ObjectRef::new(
+        diagram_id.clone(),
+        category_path(&["seq", "message"]),
+        ObjectId::new("m:0003").expect("message id"),
+    );
 
-    app.sync_from_ui_state();
+    app.select_object_ref(&third);
+    app.focus = Focus::Objects;
+    app.handle_key_code(KeyCode::Char('K'));
+    assert_eq!(texts(&app), ["one", "three", "two"]);
+    assert_eq!(app.selected_ref(), Some(&third));
+    app.handle_key_code(KeyCode::Char('K'));
+    assert_eq!(texts(&app), ["three", "one", "two"]);
+    // Already first: nothing changes.
+    app.handle_key_code(KeyCode::Char('K'));
+    assert_eq!(app.session.diagrams()[&diagram_id].rev(), 2);
+    let exported = export_diagram_mermaid(&app.session.diagrams()[&diagram_id]).expect("export");
+    assert!(exported.contains(
+        "%% nereid:id=m:0003\nA->>B: three\n%% nereid:id=m:0001\nA->>B: one\n\
+         %% nereid:id=m:0002\nB->>A: two\n"
+    ));
 
-    assert_eq!(app.active_diagram_id().map(ToString::to_string).as_deref(), Some("demo-00-index"));
+    // Adjacent order keys leave no gap, so the move renumbers every message.
+    let diagram = app.session.diagrams_mut().get_mut(&diagram_id).expect("diagram");
+    let rev = diagram.rev();
+    let crowd = [("m:0003", 1), ("m:0001", 2), ("m:0002", 3)].map(|(message_id, order_key)| {
+        Op::Seq(SeqOp::UpdateMessage {
+            message_id: ObjectId::new(message_id).expect("message id"),
+            patch: SeqMessagePatch { order_key: Some(order_key), ..SeqMessagePatch::default() },
+        })
+    });
+    apply_ops(diagram, rev, &crowd).expect("crowd order keys");
+    app.handle_key_code(KeyCode::Char('J'));
+    assert_eq!(texts(&app), ["one", "three", "two"]);
+    match app.session.diagrams()[&diagram_id].ast() {
+        DiagramAst::Sequence(ast) => assert_eq!(
+            ast.messages().iter().map(|message| message.order_key()).collect::<Vec<_>>(),
+            [1000, 2000, 3000]
+        ),
+        _ => unreachable!("sequence"),
+    }
+
+    app.handle_key_code(KeyCode::Char('u'));
+    assert_eq!(texts(&app), ["three", "one", "two"]);
+
+    let participant = ObjectRef::new(
+        diagram_id.clone(),
+        category_path(&["seq", "participant"]),
+        ObjectId::new("p:A").expect("participant id"),
+    );
+    app.select_object_ref(&participant);
+    app.focus = Focus::Objects;
+    app.handle_key_code(KeyCode::Char('J'));
+    assert_eq!(
+        app.toast.as_ref().map(|toast| toast.message.as_str()),
+        Some("Select a sequence message to move")
+    );
 }
 
 #[test]
@@ -1516,6 +2952,91 @@ fn diagram_switching_updates_active_diagram_and_objects() {
     assert_eq!(before_diagram, back_diagram);
 }
 
+#[test]
+fn moving_diagram_reorders_switching_and_persists_order() {
+    let session = demo_session();
+    let tmp_dir = std::env::temp_dir().join(format!(
+        "nereid-tui-diagram-order-{}-{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+    ));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp session dir");
+
+    let folder = SessionFolder::new(&tmp_dir);
+    folder.save_session(&session).expect("save session");
+
+    let mut app = App::new(session);
+    app.session_folder = Some(folder.clone());
+
+    let ids = app.session.ordered_diagram_ids().into_iter().cloned().collect::<Vec<_>>();
+    let (first, second) = (ids[0].clone(), ids[1].clone());
+    app.set_active_diagram_id(first.clone());
+    app.handle_key_code(KeyCode::Char('}'));
+    assert_eq!(app.session.ordered_diagram_ids()[..2], [&second, &first]);
+    assert_eq!(app.active_diagram_id(), Some(&first));
+
+    app.handle_key_code(KeyCode::Char('['));
+    assert_eq!(app.active_diagram_id(), Some(&second));
+
+    let loaded = folder.load_session().expect("reload session");
+    assert_eq!(loaded.diagram_order(), app.session.diagram_order());
+    assert_eq!(loaded.ordered_diagram_ids()[..2], [&second, &first]);
+
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
+
+#[test]
+fn archived_diagrams_are_skipped_by_cycling_but_open_on_demand() {
+    let mut app = App::new(demo_session());
+    let ids = app.session.listed_diagram_ids().into_iter().cloned().collect::<Vec<_>>();
+    let (first, second, third) = (ids[0].clone(), ids[1].clone(), ids[2].clone());
+
+    app.set_active_diagram_id(second.clone());
+    app.run_command("archive");
+    assert!(app.session.diagrams()[&second].is_archived());
+    assert!(!app.session.listed_diagram_ids().contains(&&second));
+
+    app.handle_key_code(KeyCode::Char(']'));
+    assert_eq!(app.active_diagram_id(), Some(&first));
+    app.handle_key_code(KeyCode::Char(']'));
+    assert_eq!(app.active_diagram_id(), Some(&third));
+    assert!(search_candidates_from_session(&app.session)
+        .iter()
+        .all(|candidate| candidate.object_ref.diagram_id() != &second));
+
+    app.run_command(&format!("open {second}"));
+    assert_eq!(app.active_diagram_id(), Some(&second));
+    app.run_command("unarchive");
+    assert!(app.session.listed_diagram_ids().contains(&&second));
+}
+
+#[test]
+fn locked_diagrams_refuse_tui_edits_until_unlocked() {
+    let mut app = App::new(single_flowchart_session());
+    app.follow_ai = false;
+    let diagram_id = DiagramId::new("flow").expect("diagram id");
+
+    app.run_command("lock");
+    assert!(app.session.diagrams()[&diagram_id].is_read_only());
+    for key in ['o', 'e', 'E'] {
+        app.handle_key_code(KeyCode::Char(key));
+    }
+    app.run_command("tidy");
+    assert!(app.insert_form.is_none() && app.tidy_prompt.is_none());
+    assert!(app.pending_external_action.is_none());
+    assert_eq!(
+        app.toast.as_ref().map(|toast| toast.message.as_str()),
+        Some("flow is read-only; :unlock it to edit")
+    );
+
+    app.run_command("unlock");
+    app.handle_key_code(KeyCode::Char('o'));
+    assert!(app.insert_form.is_some());
+}
+
 #[test]
 fn diagram_switching_persists_active_diagram_with_session_folder() {
     let session = demo_session();
@@ -1540,11 +3061,103 @@ fn diagram_switching_persists_active_diagram_with_session_folder() {
     let after = app.active_diagram_id().cloned().expect("active diagram after switch");
     assert_ne!(before, after);
 
-    let meta = folder.load_meta().expect("load session meta");
-    assert_eq!(meta.active_diagram_id, Some(after.clone()));
+    let meta = folder.load_meta().expect("load session meta");
+    assert_eq!(meta.active_diagram_id, Some(after.clone()));
+
+    let loaded = folder.load_session().expect("reload session");
+    assert_eq!(loaded.active_diagram_id(), Some(&after));
+
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
+
+#[test]
+fn external_edits_reload_the_session_and_merge_with_unsynced_local_edits() {
+    let session = single_flowchart_session();
+    let tmp_dir = std::env::temp_dir().join(format!(
+        "nereid-tui-external-edit-{}-{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+    ));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp session dir");
+    let folder = SessionFolder::new(&tmp_dir);
+    folder.save_session(&session).expect("save session");
+
+    let mut app = App::new(session);
+    app.session_folder = Some(folder.clone());
+    let diagram_id = DiagramId::new("flow").expect("diagram id");
+    let node_count = |app: &App| match app.session.diagrams()[&diagram_id].ast() {
+        DiagramAst::Flowchart(ast) => ast.nodes().len(),
+        _ => 0,
+    };
+    let mmd_path = folder.default_diagram_mmd_path(&diagram_id);
+
+    std::fs::write(&mmd_path, "flowchart LR\nA[Start]\nB[End]\nC[Later]\nA --> B\nB --> C\n")
+        .expect("external edit");
+    app.sync_from_session_watcher();
+    assert_eq!(node_count(&app), 2, "reloads only after the watcher reports a change");
+    app.external_change_pending = true;
+    app.sync_from_session_watcher();
+    assert_eq!(node_count(&app), 3);
+    assert!(!app.external_change_pending);
+
+    app.pending_diagram_sync = Some(PendingDiagramSync {
+        diagram_id: diagram_id.clone(),
+        expected_disk_rev: app.session.diagrams()[&diagram_id].rev(),
+        disk_fingerprint: folder.diagram_mmd_fingerprint(&diagram_id).expect("fingerprint"),
+        base_ast: app.session.diagrams()[&diagram_id].ast().clone(),
+    });
+    app.external_change_pending = true;
+    app.sync_from_session_watcher();
+    assert!(app.pending_diagram_sync.is_some(), "own writes do not conflict");
+    assert!(app.external_change_pending);
+
+    app.pending_diagram_sync = None;
+    app.external_change_pending = false;
+
+    let edit_locally = |app: &mut App, mermaid: &str| {
+        let rev = app.session.diagrams()[&diagram_id].rev();
+        app.apply_edited_mermaid_to_diagram(&diagram_id, DiagramKind::Flowchart, rev, mermaid)
+            .expect("local edit");
+    };
+    edit_locally(&mut app, "flowchart LR\nA[Start]\nB[Mine]\nC[Later]\nA --> B\nB --> C\n");
+    std::fs::write(
+        &mmd_path,
+        "flowchart LR\nA[Start]\nB[End]\nC[Later]\nD[Disk]\nA --> B\nB --> C\n",
+    )
+    .expect("external edit");
+    app.external_change_pending = true;
+    app.sync_from_session_watcher();
+    assert!(app.pending_diagram_sync.is_none() && app.merge_prompt.is_none());
+    assert_eq!(node_count(&app), 4);
+    let mmd = std::fs::read_to_string(&mmd_path).expect("read mmd");
+    assert!(mmd.contains("B[Mine]") && mmd.contains("D[Disk]"), "{mmd}");
+    assert!(app
+        .toast
+        .as_ref()
+        .is_some_and(|toast| toast.message == "Merged disk changes into flow"));
+
+    edit_locally(
+        &mut app,
+        "flowchart LR\nA[Local]\nB[Mine]\nC[Later]\nD[Disk]\nA --> B\nB --> C\n",
+    );
+    std::fs::write(&mmd_path, "flowchart LR\nA[Theirs]\nB[Mine]\nC[Later]\nA --> B\nB --> C\n")
+        .expect("external edit");
+    app.external_change_pending = true;
+    app.sync_from_session_watcher();
+    let prompt = app.merge_prompt.clone().expect("merge prompt");
+    assert_eq!(merge_prompt_lines(&prompt)[1], "  node n:A");
+    assert!(app.external_change_pending, "reload waits for the merge decision");
 
-    let loaded = folder.load_session().expect("reload session");
-    assert_eq!(loaded.active_diagram_id(), Some(&after));
+    app.handle_key_code(KeyCode::Char('l'));
+    assert!(app.merge_prompt.is_none());
+    let mmd = std::fs::read_to_string(&mmd_path).expect("read mmd");
+    assert!(mmd.contains("A[Local]") && !mmd.contains("D[Disk]"), "{mmd}");
+    app.sync_from_session_watcher();
+    assert!(!app.external_change_pending);
+    assert_eq!(node_count(&app), 3);
 
     let _ = std::fs::remove_dir_all(&tmp_dir);
 }
@@ -1671,20 +3284,172 @@ fn fuzzy_search_results_cycle_with_n_and_shift_n() {
     assert_eq!(app.selected_ref().map(ToString::to_string), focused);
 }
 
+#[test]
+fn search_results_panel_groups_hits_and_tracks_current_result() {
+    let mut app = App::new(demo_session());
+    app.handle_key_code(KeyCode::Char('/'));
+    app.handle_key_code(KeyCode::Char('p'));
+    app.handle_key_code(KeyCode::Char(':'));
+    app.handle_key_code(KeyCode::Enter);
+    assert_eq!(app.search_mode, SearchMode::Results);
+    assert!(app.search_results.len() > 2);
+
+    let (lines, current) = search_results_panel_lines(&app);
+    let first_diagram = app.search_results[0].object_ref.diagram_id().to_string();
+    assert!(line_to_string(&lines[0]).starts_with(&first_diagram));
+    assert_eq!(current, Some(1));
+    let first_hit = &app.search_results[0];
+    let first_row = line_to_string(&lines[1]);
+    assert!(first_row.contains(&first_hit.object_ref.to_string()), "{first_row}");
+    assert!(first_row.trim_end().ends_with(&first_hit.score.to_string()), "{first_row}");
+    let headers = lines.len() - app.search_results.len();
+    assert!(headers >= 1);
+
+    app.handle_key_code(KeyCode::Char('j'));
+    assert_eq!(app.search_result_index, 1);
+    let (_, current) = search_results_panel_lines(&app);
+    assert!(current > Some(1));
+
+    let expected = app.search_results[1].object_ref.clone();
+    app.handle_key_code(KeyCode::Enter);
+    assert_eq!(app.search_mode, SearchMode::Inactive);
+    assert_eq!(app.selected_ref(), Some(&expected));
+}
+
+#[test]
+fn slash_search_finds_notes_walkthrough_steps_and_xref_labels() {
+    let mut session = single_flowchart_session();
+    let diagram_id = DiagramId::new("flow").expect("diagram id");
+    let node_ref = |node_id: &str| {
+        ObjectRef::new(
+            diagram_id.clone(),
+            category_path(&["flow", "node"]),
+            ObjectId::new(node_id).expect("node id"),
+        )
+    };
+    let diagram = session.diagrams_mut().get_mut(&diagram_id).expect("diagram");
+    let DiagramAst::Flowchart(mut ast) = diagram.ast().clone() else {
+        panic!("flowchart");
+    };
+    ast.nodes_mut()
+        .get_mut(&ObjectId::new("n:A").expect("node id"))
+        .expect("node")
+        .set_note(Some("retries with backoff"));
+    diagram.set_ast(DiagramAst::Flowchart(ast)).expect("set ast");
+
+    let walkthrough_id = WalkthroughId::new("w:tour").expect("walkthrough id");
+    let mut walkthrough = Walkthrough::new(walkthrough_id.clone(), "Tour");
+    let mut step = WalkthroughNode::new(WalkthroughNodeId::new("n:1").expect("node id"), "Ship");
+    step.set_body_md(Some("Deploy the canary first".to_owned()));
+    step.refs_mut().push(node_ref("n:B"));
+    walkthrough.nodes_mut().push(step);
+    session.walkthroughs_mut().insert(walkthrough_id, walkthrough);
+
+    let mut xref = XRef::new(node_ref("n:A"), node_ref("n:B"), "calls", XRefStatus::Ok);
+    xref.set_label(Some("billing handoff".to_owned()));
+    session.xrefs_mut().insert(XRefId::new("x:1").expect("xref id"), xref);
+
+    let mut app = App::new(session);
+    let mut search = |query: &str| {
+        app.handle_key_code(KeyCode::Char('/'));
+        for ch in query.chars() {
+            app.handle_key_code(KeyCode::Char(ch));
+        }
+        app.handle_key_code(KeyCode::Enter);
+        let hits = app
+            .search_results
+            .iter()
+            .map(|hit| (hit.object_ref.object_id().to_string(), hit.label.clone()))
+            .collect::<Vec<_>>();
+        app.handle_key_code(KeyCode::Esc);
+        hits
+    };
+
+    assert_eq!(
+        search("backoff"),
+        vec![("n:A".to_owned(), "note: retries with backoff".to_owned())]
+    );
+    assert_eq!(
+        search("canary"),
+        vec![("n:B".to_owned(), "walkthrough w:tour/n:1: Deploy the canary first".to_owned())]
+    );
+    assert_eq!(search("billing"), vec![("n:A".to_owned(), "xref x:1: billing handoff".to_owned())]);
+}
+
+#[test]
+fn skim_matcher_and_active_boost_follow_search_scoring() {
+    let session = demo_session();
+    let candidates = search_candidates_from_session(&session);
+    let active = DiagramId::new("om-02-gear").expect("diagram id");
+    let skim = SearchScoring { matcher: FuzzyMatcher::Skim, ..SearchScoring::default() };
+
+    let unboosted = ranked_search_results(
+        &candidates,
+        "ok",
+        SearchKind::Fuzzy,
+        Some(&active),
+        &SearchScoring { active_diagram_boost: 0, ..skim },
+    );
+    let top = &unboosted.first().expect("results").object_ref;
+    assert_ne!(top.diagram_id(), &active);
+    assert!(top.object_id().as_str().starts_with("m:ok"), "{top}");
+
+    let boosted = ranked_search_results(&candidates, "ok", SearchKind::Fuzzy, Some(&active), &skim);
+    assert_eq!(boosted.first().map(|hit| hit.object_ref.diagram_id()), Some(&active));
+
+    let mut app = App::new(demo_session());
+    app.run_command("matcher");
+    assert_eq!(app.search_scoring.matcher, FuzzyMatcher::Skim);
+    assert_eq!(app.toast.as_ref().map(|t| t.message.as_str()), Some("Fuzzy matcher: skim"));
+    app.run_command("matcher nereid");
+    assert_eq!(app.search_scoring.matcher, FuzzyMatcher::Nereid);
+    assert!(parse_tui_command("matcher fzy").is_err());
+}
+
+#[test]
+fn edges_command_switches_flowchart_edge_glyphs_and_rerenders() {
+    let mut app = App::new(demo_session());
+    app.set_active_diagram_id(DiagramId::new("demo-flow").expect("diagram id"));
+    let boxed = app.base_diagram.clone();
+    assert!(boxed.contains('─'), "{boxed}");
+
+    app.run_command("edges braille");
+    assert_eq!(app.edge_glyphs, FlowchartEdgeGlyphs::Braille);
+    assert_eq!(app.toast.as_ref().map(|t| t.message.as_str()), Some("Edge glyphs: braille"));
+    assert!(app.base_diagram.chars().any(|ch| ('\u{2801}'..='\u{28ff}').contains(&ch)));
+    for spans in app.base_highlight_index.values() {
+        assert!(spans.iter().all(|&(y, _, x1)| {
+            app.base_diagram.lines().nth(y).is_some_and(|line| x1 < line.chars().count())
+        }));
+    }
+
+    app.run_command("edges");
+    assert_eq!(app.edge_glyphs, FlowchartEdgeGlyphs::Blocks);
+    app.run_command("edges");
+    assert_eq!(app.edge_glyphs, FlowchartEdgeGlyphs::Box);
+    assert_eq!(app.base_diagram, boxed);
+    assert!(parse_tui_command("edges round").is_err());
+}
+
 #[test]
 fn search_results_group_current_diagram_first_for_regular_and_fuzzy() {
     let session = demo_session();
     let candidates = search_candidates_from_session(&session);
     let active = DiagramId::new("demo-seq").expect("diagram id");
 
-    let regular =
-        ranked_search_results(&candidates, "participant", SearchKind::Regular, Some(&active));
+    let regular = ranked_search_results(
+        &candidates,
+        "participant",
+        SearchKind::Regular,
+        Some(&active),
+        &SearchScoring::default(),
+    );
     assert!(!regular.is_empty());
-    assert_eq!(regular.first().map(|oref| oref.diagram_id().as_str()), Some("demo-seq"));
+    assert_eq!(regular.first().map(|hit| hit.object_ref.diagram_id().as_str()), Some("demo-seq"));
     let mut seen_regular = BTreeSet::<String>::new();
     let mut last_regular = String::new();
-    for object_ref in regular {
-        let diagram = object_ref.diagram_id().to_string();
+    for hit in regular {
+        let diagram = hit.object_ref.diagram_id().to_string();
         if diagram != last_regular {
             assert!(
                 !seen_regular.contains(&diagram),
@@ -1695,13 +3460,19 @@ fn search_results_group_current_diagram_first_for_regular_and_fuzzy() {
         }
     }
 
-    let fuzzy = ranked_search_results(&candidates, "particpant", SearchKind::Fuzzy, Some(&active));
+    let fuzzy = ranked_search_results(
+        &candidates,
+        "particpant",
+        SearchKind::Fuzzy,
+        Some(&active),
+        &SearchScoring::default(),
+    );
     assert!(!fuzzy.is_empty());
-    assert_eq!(fuzzy.first().map(|oref| oref.diagram_id().as_str()), Some("demo-seq"));
+    assert_eq!(fuzzy.first().map(|hit| hit.object_ref.diagram_id().as_str()), Some("demo-seq"));
     let mut seen_fuzzy = BTreeSet::<String>::new();
     let mut last_fuzzy = String::new();
-    for object_ref in fuzzy {
-        let diagram = object_ref.diagram_id().to_string();
+    for hit in fuzzy {
+        let diagram = hit.object_ref.diagram_id().to_string();
         if diagram != last_fuzzy {
             assert!(
                 !seen_fuzzy.contains(&diagram),
@@ -1713,6 +3484,98 @@ fn search_results_group_current_diagram_first_for_regular_and_fuzzy() {
     }
 }
 
+#[test]
+fn held_pan_keys_accelerate_and_reset_on_pause_or_direction_change() {
+    let mut app = App::new(demo_session());
+    app.base_diagram = vec!["x".repeat(200); 200].join("\n");
+    app.diagram_viewport = (20, 10);
+    let start = Instant::now();
+
+    let mut steps = Vec::new();
+    for repeat in 0..10u64 {
+        let before = app.pan_y;
+        app.pan_accelerated((0, 1), start + Duration::from_millis(30 * repeat));
+        steps.push(app.pan_y - before);
+    }
+    assert_eq!(steps, vec![1, 1, 1, 1, 2, 2, 2, 2, 3, 3]);
+
+    let before = app.pan_y;
+    app.pan_accelerated((0, 1), start + Duration::from_millis(30 * 9 + 500));
+    assert_eq!(app.pan_y - before, 1);
+
+    let before = app.pan_x;
+    app.pan_accelerated((1, 0), start + Duration::from_millis(30 * 9 + 520));
+    assert_eq!(app.pan_x - before, 1);
+}
+
+#[test]
+fn pan_is_clamped_and_home_end_snap_to_diagram_edges() {
+    let mut app = App::new(demo_session());
+    app.base_diagram = vec!["x".repeat(50); 30].join("\n");
+    app.diagram_viewport = (20, 10);
+
+    app.handle_key_code(KeyCode::End);
+    assert_eq!((app.pan_x, app.pan_y), (31, 21));
+    app.handle_key_code(KeyCode::Char('L'));
+    app.handle_key_code(KeyCode::PageDown);
+    assert_eq!((app.pan_x, app.pan_y), (31, 21));
+
+    app.handle_key_code(KeyCode::Home);
+    assert_eq!((app.pan_x, app.pan_y), (-1, -1));
+    app.handle_key_code(KeyCode::Char('H'));
+    app.handle_key_code(KeyCode::Up);
+    assert_eq!((app.pan_x, app.pan_y), (-1, -1));
+
+    // A diagram smaller than the viewport can only move within it.
+    app.base_diagram = "abc".to_owned();
+    app.handle_key_code(KeyCode::End);
+    assert_eq!((app.pan_x, app.pan_y), (-16, -8));
+    app.handle_key_code(KeyCode::Char('H'));
+    assert_eq!(app.pan_x, -16);
+    app.handle_key_code(KeyCode::Char('L'));
+    app.handle_key_code(KeyCode::Char('L'));
+    assert_eq!(app.pan_x, -1);
+}
+
+#[test]
+fn scroll_axis_into_view_moves_minimally_with_margin() {
+    // Already visible: no movement.
+    assert_eq!(scroll_axis_into_view(10, 14, 18, 20), 10);
+    // Off the far edge: just enough to show the end plus margin.
+    assert_eq!(scroll_axis_into_view(0, 30, 33, 20), 16);
+    // Off the near edge: align start minus margin.
+    assert_eq!(scroll_axis_into_view(40, 30, 33, 20), 28);
+    // Larger than the viewport: align start without margin.
+    assert_eq!(scroll_axis_into_view(0, 30, 60, 20), 30);
+}
+
+#[test]
+fn selecting_offscreen_object_scrolls_it_into_view() {
+    let mut app = App::new(demo_session());
+    app.follow_ai = false;
+    app.set_active_diagram_id(DiagramId::new("demo-flow").expect("diagram id"));
+    app.diagram_viewport = (12, 6);
+    app.center_diagram_if_needed(12, 6);
+
+    let (target, ((x0, x1), (y0, y1))) = app
+        .base_highlight_index
+        .iter()
+        .filter(|(object_ref, _)| object_ref.category().segments() == ["flow", "node"])
+        .filter_map(|(object_ref, spans)| Some((object_ref.clone(), line_spans_bounds(spans)?)))
+        .max_by_key(|(_, ((x0, _), (y0, _)))| (*y0, *x0))
+        .expect("flow node");
+    app.select_object_ref(&target);
+
+    assert_eq!(app.selected_ref(), Some(&target));
+    let (pan_x, pan_y) = (app.pan_x, app.pan_y);
+    assert!(pan_x <= x0 as i32 && x1 as i32 <= pan_x + 11 || x1 - x0 >= 12, "x {pan_x}");
+    assert!(pan_y <= y0 as i32 && y1 as i32 <= pan_y + 5 || y1 - y0 >= 6, "y {pan_y}");
+
+    let before = (app.pan_x, app.pan_y);
+    app.select_object_ref(&target);
+    assert_eq!((app.pan_x, app.pan_y), before);
+}
+
 #[test]
 fn scroll_supports_negative_offsets() {
     let mut app = App::new(demo_session());
@@ -1816,6 +3679,7 @@ fn demo_fixture_diagrams_render_without_errors() {
                 show_notes: true,
                 prefix_object_labels: false,
                 flowchart_extra_col_gap: 0,
+                ..RenderOptions::default()
             },
         );
         assert!(
@@ -1841,6 +3705,7 @@ fn demo_flow_routing_nodes_have_labels() {
             show_notes: false,
             prefix_object_labels: false,
             flowchart_extra_col_gap: 0,
+            ..RenderOptions::default()
         },
     )
     .expect("render");
@@ -2324,3 +4189,473 @@ fn diagram_select_hints_selects_message_between_consecutive_sequence_participant
     assert!(app.session.selected_object_refs().contains(&bob_ref));
     assert!(app.session.selected_object_refs().contains(&message_ref));
 }
+
+#[test]
+fn timeline_logs_edits_and_time_travel_shows_past_diagram_state() {
+    let mut app = App::new(demo_session());
+    app.follow_ai = false;
+    let diagram_id = app.active_diagram_id().cloned().expect("active diagram");
+    let diagram = app.session.diagrams().get(&diagram_id).cloned().expect("diagram");
+    let edited_mermaid = export_diagram_mermaid(&diagram).expect("export").replacen(
+        "Engine features",
+        "Engine internals",
+        1,
+    );
+    app.apply_edited_mermaid_to_diagram(
+        &diagram_id,
+        diagram.kind(),
+        diagram.rev(),
+        &edited_mermaid,
+    )
+    .expect("apply edited mermaid");
+
+    let summaries = app.activity.events().iter().map(|event| event.summary()).collect::<Vec<_>>();
+    assert!(summaries[0].starts_with("select "), "{summaries:?}");
+    assert_eq!(summaries.last(), Some(&format!("edit {diagram_id} (rev {})", diagram.rev() + 1)));
+
+    app.handle_key_code(KeyCode::Char('7'));
+    assert_eq!(app.focus, Focus::Timeline);
+    app.handle_key_code(KeyCode::End);
+    app.handle_key_code(KeyCode::Enter);
+    assert!(app.time_travel.is_some());
+    let past = text_to_string(&app.diagram_text());
+    assert!(past.contains("Engine features"), "{past}");
+
+    // Scrubbing while time traveling moves the view along with the cursor.
+    app.handle_key_code(KeyCode::Home);
+    let edited = text_to_string(&app.diagram_text());
+    assert!(edited.contains("Engine internals"), "{edited}");
+
+    app.handle_key_code(KeyCode::End);
+    app.handle_key_code(KeyCode::Esc);
+    assert_eq!(app.time_travel, None);
+    assert!(text_to_string(&app.diagram_text()).contains("Engine internals"));
+}
+
+#[test]
+fn a11y_mode_describes_the_focused_object_once_per_focus_change() {
+    let mut app = App::new(single_flowchart_session());
+    app.announce_focus();
+    assert_eq!(app.a11y_description, None);
+
+    app.run_command("a11y");
+    assert!(app.a11y.line);
+    app.announce_focus();
+    assert_eq!(
+        app.a11y_description.as_deref(),
+        Some("edge e:0001 n:A to n:B in Flow. from: Start; to: End.")
+    );
+
+    app.a11y_description = None;
+    app.announce_focus();
+    assert_eq!(app.a11y_description, None);
+
+    app.select_next();
+    app.announce_focus();
+    assert_eq!(
+        app.a11y_description.as_deref(),
+        Some("node n:A (Start) in Flow. outgoing: e:0001 to End.")
+    );
+}
+
+#[test]
+fn split_view_shows_xref_partner_with_independent_pan_and_selection() {
+    let mut session = demo_session();
+    let flow_id = DiagramId::new("demo-flow").expect("diagram id");
+    let seq_id = DiagramId::new("demo-seq").expect("diagram id");
+    let from: ObjectRef = "d:demo-flow/flow/node/n:a".parse().expect("object ref");
+    let to: ObjectRef = "d:demo-seq/seq/participant/p:alice".parse().expect("object ref");
+    session.xrefs_mut().insert(
+        XRefId::new("x:split").expect("xref id"),
+        XRef::new(from.clone(), to.clone(), "cross", XRefStatus::Ok),
+    );
+    let ctrl_w = KeyEvent::new(KeyCode::Char('w'), KeyModifiers::CONTROL);
+
+    let mut app = App::new(session);
+    app.set_active_diagram_id(flow_id.clone());
+    app.select_object_ref(&from);
+    app.handle_key(ctrl_w);
+    app.handle_key_code(KeyCode::Char('v'));
+
+    assert_eq!(app.active_diagram_id(), Some(&seq_id));
+    assert_eq!(app.selected_ref(), Some(&to));
+    assert_eq!(app.split.as_ref().and_then(|pane| pane.diagram_id.as_ref()), Some(&flow_id));
+
+    let mut terminal =
+        ratatui::Terminal::new(ratatui::backend::TestBackend::new(160, 40)).expect("terminal");
+    terminal.draw(|frame| super::draw(frame, &mut app)).expect("draw");
+    let screen: String =
+        terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+    assert!(screen.contains("demo-flow") && screen.contains("demo-seq"));
+
+    app.pan_x = 7;
+    app.handle_key(ctrl_w);
+    app.handle_key(ctrl_w);
+    assert_eq!(app.active_diagram_id(), Some(&flow_id));
+    assert_eq!(app.selected_ref(), Some(&from));
+    app.handle_key(ctrl_w);
+    app.handle_key_code(KeyCode::Char('w'));
+    assert_eq!(app.active_diagram_id(), Some(&seq_id));
+    assert_eq!(app.pan_x, 7);
+
+    // Jumps into the diagram the other pane shows move focus there instead of replacing this one.
+    app.select_object_ref(&from);
+    assert_eq!(app.active_diagram_id(), Some(&flow_id));
+    assert_eq!(app.split.as_ref().and_then(|pane| pane.diagram_id.as_ref()), Some(&seq_id));
+
+    // The flow pane sits on the left, so Tab moves on to the sequence pane on the right.
+    app.focus = Focus::Diagram;
+    app.handle_key_code(KeyCode::Tab);
+    assert_eq!(app.active_diagram_id(), Some(&seq_id));
+    assert_eq!(app.focus, Focus::Diagram);
+
+    app.handle_key(ctrl_w);
+    app.handle_key_code(KeyCode::Char('o'));
+    assert!(app.split.is_none());
+    assert_eq!(app.active_diagram_id(), Some(&seq_id));
+    assert_eq!(app.selected_ref(), Some(&to));
+}
+
+#[test]
+fn check_command_lists_integrity_problems_until_dismissed() {
+    let mut app = App::new(single_flowchart_session());
+    app.run_command("check");
+    assert!(app.check_report.is_none());
+
+    app.session.xrefs_mut().insert(
+        XRefId::new("x:gone").expect("xref id"),
+        XRef::new(
+            "d:flow/flow/node/n:A".parse().expect("from ref"),
+            "d:flow/flow/node/n:gone".parse().expect("to ref"),
+            "uses",
+            XRefStatus::DanglingTo,
+        ),
+    );
+    app.run_command("check");
+    let lines = super::check_report_lines(app.check_report.as_deref().expect("report"));
+    assert_eq!(
+        lines,
+        ["warning dangling_xref: xref x:gone points to missing d:flow/flow/node/n:gone"]
+    );
+
+    app.handle_key_code(KeyCode::Esc);
+    assert!(app.check_report.is_none());
+}
+
+#[test]
+fn sessions_command_switches_between_workspace_sessions() {
+    let root = std::env::temp_dir().join(format!(
+        "nereid-tui-workspace-{}-{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+    ));
+    let alpha = SessionFolder::new(root.join("alpha"));
+    alpha.save_session(&single_flowchart_session()).expect("save alpha");
+    SessionFolder::new(root.join("beta")).save_session(&demo_session()).expect("save beta");
+
+    let mut app = App::new(single_flowchart_session());
+    app.run_command("sessions");
+    assert!(app.session_picker.is_none(), "no workspace, no picker");
+
+    let ui_state = std::sync::Arc::new(tokio::sync::Mutex::new(UiState::default()));
+    app.session_folder = Some(alpha);
+    app.workspace = Some(Workspace::new(&root));
+    app.ui_state = Some(ui_state.clone());
+
+    app.run_command("sessions");
+    let picker = app.session_picker.as_ref().expect("picker");
+    let lines = super::session_picker_lines(picker, app.workspace_session_name().as_deref());
+    assert!(lines[0].starts_with(">* alpha"), "{lines:?}");
+    assert!(lines[1].starts_with("   beta"), "{lines:?}");
+
+    app.handle_key_code(KeyCode::Char('j'));
+    app.handle_key_code(KeyCode::Enter);
+    assert!(app.session_picker.is_none());
+    assert_eq!(app.workspace_session_name().as_deref(), Some("beta"));
+    assert_eq!(app.session.session_id(), demo_session().session_id());
+    assert_eq!(ui_state.blocking_lock().workspace_session(), Some("beta"));
+
+    ui_state.blocking_lock().set_workspace_session("alpha".to_owned());
+    app.sync_from_ui_state();
+    assert_eq!(app.workspace_session_name().as_deref(), Some("alpha"));
+    assert!(app.session.diagrams().contains_key(&DiagramId::new("flow").expect("diagram id")));
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn agent_cursors_render_in_their_own_colors_with_a_footer_legend() {
+    let mut app = App::new(single_flowchart_session());
+    let ui_state = std::sync::Arc::new(tokio::sync::Mutex::new(UiState::default()));
+    app.ui_state = Some(ui_state.clone());
+    let a: ObjectRef = "d:flow/flow/node/n:A".parse().expect("object ref");
+    let b: ObjectRef = "d:flow/flow/node/n:B".parse().expect("object ref");
+    {
+        let mut ui_state = ui_state.blocking_lock();
+        ui_state.set_agent_cursor("one".into(), "Alpha".into(), a.clone(), 1);
+        ui_state.set_agent_cursor("two".into(), "Beta".into(), b.clone(), 2);
+    }
+    app.sync_from_ui_state();
+    app.focus_owner = FocusOwner::Agent;
+    app.objects_state.select(None);
+
+    let text = app.diagram_text();
+    let bg_of = |object_ref: &ObjectRef| {
+        let &(y, x0, _) = app.base_highlight_index[object_ref].first().expect("span");
+        let mut col = 0;
+        text.lines[y]
+            .spans
+            .iter()
+            .find(|span| {
+                col += span.content.chars().count();
+                col > x0
+            })
+            .and_then(|span| span.style.bg)
+    };
+    assert_eq!(bg_of(&a), Some(super::agent_cursor_color(0)));
+    assert_eq!(bg_of(&b), Some(super::agent_cursor_color(1)));
+    assert_ne!(super::agent_cursor_color(0), super::agent_cursor_color(1));
+
+    let footer = super::footer_help_line(&app, "", false)
+        .spans
+        .iter()
+        .map(|span| span.content.as_ref())
+        .collect::<String>();
+    assert!(footer.contains("Agents:■Alpha ■Beta"), "{footer}");
+}
+
+#[test]
+fn note_command_edits_the_note_of_the_selected_object() {
+    assert_eq!(parse_tui_command("note"), Ok(TuiCommand::Note(None)));
+    assert_eq!(parse_tui_command("note  a b "), Ok(TuiCommand::Note(Some("a b".to_owned()))));
+    assert_eq!(parse_tui_command("unnote"), Ok(TuiCommand::Unnote));
+    assert!(parse_tui_command("unnote now").is_err());
+
+    let mut session = Session::new(SessionId::new("s1").expect("session id"));
+    let diagram_id = DiagramId::new("seq").expect("diagram id");
+    let ast = parse_sequence_diagram(
+        "sequenceDiagram\nparticipant A\nparticipant B\nA->>B: hi\nNote over A,B: draft\n",
+    )
+    .expect("parse sequence");
+    let diagram = Diagram::new(diagram_id.clone(), "Seq", DiagramAst::Sequence(ast));
+    session.diagrams_mut().insert(diagram_id.clone(), diagram);
+    session.set_active_diagram_id(Some(diagram_id.clone()));
+    let mut app = App::new(session);
+    app.follow_ai = false;
+    let sequence = |app: &App| match app.session.diagrams()[&diagram_id].ast() {
+        DiagramAst::Sequence(ast) => ast.clone(),
+        _ => unreachable!("sequence"),
+    };
+    let participant_id = ObjectId::new("p:A").expect("participant id");
+    let note_id = ObjectId::new("note:0001").expect("note id");
+
+    app.select_object_ref(&ObjectRef::new(
+        diagram_id.clone(),
+        category_path(&["seq", "participant"]),
+        participant_id.clone(),
+    ));
+    app.run_command("note entry point");
+    assert_eq!(sequence(&app).participants()[&participant_id].note(), Some("entry point"));
+    app.run_command("note");
+    assert_eq!(app.command_line.as_deref(), Some("note entry point"));
+    app.command_line = None;
+    app.run_command("unnote");
+    assert_eq!(sequence(&app).participants()[&participant_id].note(), None);
+
+    let note_ref = ObjectRef::new(diagram_id.clone(), category_path(&["seq", "note"]), note_id);
+    app.select_object_ref(&note_ref);
+    app.run_command("note final");
+    assert_eq!(sequence(&app).notes()[0].text(), "final");
+    app.handle_key_code(KeyCode::Char('u'));
+    assert_eq!(sequence(&app).notes()[0].text(), "draft");
+    app.select_object_ref(&note_ref);
+    app.run_command("unnote");
+    assert!(sequence(&app).notes().is_empty());
+
+    app.select_object_ref(&ObjectRef::new(
+        diagram_id.clone(),
+        category_path(&["seq", "message"]),
+        ObjectId::new("m:0001").expect("message id"),
+    ));
+    let rev = app.session.diagrams()[&diagram_id].rev();
+    app.run_command("note nope");
+    assert_eq!(app.session.diagrams()[&diagram_id].rev(), rev);
+    assert_eq!(
+        app.toast.as_ref().map(|toast| toast.message.as_str()),
+        Some("Select a node, participant or note to edit its note")
+    );
+}
+
+#[test]
+fn diff_command_colors_changes_against_the_saved_version() {
+    use crate::ops::FlowOp;
+
+    assert_eq!(parse_tui_command("diff"), Ok(TuiCommand::Diff(None)));
+    assert_eq!(parse_tui_command("diff saved"), Ok(TuiCommand::Diff(Some(super::DiffSide::Saved))));
+    assert!(parse_tui_command("diff both").is_err());
+
+    let session = single_flowchart_session();
+    let diagram_id = DiagramId::new("flow").expect("diagram id");
+    let tmp_dir = std::env::temp_dir().join(format!(
+        "nereid-tui-diff-{}-{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+    ));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp session dir");
+    let folder = SessionFolder::new(&tmp_dir);
+    folder.save_session(&session).expect("save session");
+
+    let mut app = App::new(session);
+    app.follow_ai = false;
+    app.run_command("diff");
+    assert!(app.diff_view.is_none());
+    assert_eq!(
+        app.toast.as_ref().map(|toast| toast.message.as_str()),
+        Some("Nothing saved to compare with: no session folder")
+    );
+    app.session_folder = Some(folder);
+
+    let diagram = app.session.diagrams_mut().get_mut(&diagram_id).expect("diagram");
+    let node_id = |id: &str| ObjectId::new(id).expect("node id");
+    let ops = [
+        Op::Flow(FlowOp::AddNode {
+            node_id: node_id("n:C"),
+            label: "Extra".to_owned(),
+            shape: None,
+        }),
+        Op::Flow(FlowOp::RemoveNode { node_id: node_id("n:B") }),
+        Op::Flow(FlowOp::SetNodeNote { node_id: node_id("n:A"), note: Some("why".to_owned()) }),
+    ];
+    apply_ops(diagram, 0, &ops).expect("edit");
+    app.refresh_active_diagram_view();
+    let cells_colored = |app: &App, color: Color| {
+        app.diagram_text()
+            .lines
+            .iter()
+            .flat_map(|line| line.spans.iter())
+            .any(|span| span.style.fg == Some(color) && !span.content.trim().is_empty())
+    };
+
+    app.run_command("diff");
+    let view = app.diff_view.as_ref().expect("diff view");
+    assert!(!view.show_saved);
+    let ref_of = |kind: &str, id: &str| {
+        ObjectRef::new(
+            diagram_id.clone(),
+            category_path(&["flow", kind]),
+            ObjectId::new(id).expect("id"),
+        )
+    };
+    assert_eq!(
+        view.diff.status(&ref_of("node", "n:C")),
+        Some(crate::query::diff::DiffStatus::Added)
+    );
+    assert_eq!(
+        view.diff.status(&ref_of("node", "n:B")),
+        Some(crate::query::diff::DiffStatus::Removed)
+    );
+    assert_eq!(
+        view.diff.status(&ref_of("node", "n:A")),
+        Some(crate::query::diff::DiffStatus::Changed)
+    );
+    assert!(cells_colored(&app, super::theme().diff_added));
+    assert!(!app.base_diagram.contains("End"));
+
+    app.run_command("diff saved");
+    assert!(app.diff_view.as_ref().is_some_and(|view| view.show_saved));
+    assert!(app.base_diagram.contains("End"));
+    assert!(cells_colored(&app, super::theme().diff_removed));
+
+    app.run_command("diff");
+    assert!(app.diff_view.is_none());
+    assert!(!app.base_diagram.contains("End"));
+}
+
+#[test]
+fn paste_new_creates_a_diagram_named_after_the_pasted_kind() {
+    assert_eq!(parse_tui_command("paste-new"), Ok(TuiCommand::PasteNew));
+    assert!(parse_tui_command("paste-new now").is_err());
+
+    let argv = |args: &[&str]| args.iter().map(|arg| (*arg).to_owned()).collect::<Vec<_>>();
+    let printf = argv(&["printf", "%%%% pasted\\nflowchart LR\\nX --> Y\\n"]);
+    let clipboard =
+        super::read_clipboard(&[argv(&["nereid-no-such-paste"]), argv(&["false"]), printf])
+            .expect("read clipboard");
+    assert!(clipboard.starts_with("%% pasted\n"));
+    assert!(super::read_clipboard(&[argv(&["false"])]).is_err());
+    assert!(super::read_clipboard(&[]).is_err());
+
+    let mut app = App::new(single_flowchart_session());
+    app.create_diagram_from_mermaid(&clipboard);
+    let pasted = DiagramId::new("flow-2").expect("diagram id");
+    assert_eq!(app.active_diagram_id(), Some(&pasted));
+    let DiagramAst::Flowchart(ast) = app.session.diagrams()[&pasted].ast() else {
+        panic!("flowchart");
+    };
+    assert!(ast.nodes().contains_key(&ObjectId::new("n:X").expect("node id")));
+    assert_eq!(
+        app.toast.as_ref().map(|toast| toast.message.as_str()),
+        Some("Created flow-2 from the clipboard")
+    );
+
+    app.create_diagram_from_mermaid("just some text");
+    assert_eq!(app.session.diagrams().len(), 2);
+    assert_eq!(
+        app.toast.as_ref().map(|toast| toast.message.as_str()),
+        Some("Clipboard holds no sequence, flowchart, state or class Mermaid")
+    );
+}
+
+#[test]
+fn shift_arrows_pin_the_selected_flow_node_and_unpin_releases_it() {
+    assert_eq!(parse_tui_command("unpin"), Ok(TuiCommand::Unpin(false)));
+    assert_eq!(parse_tui_command("unpin all"), Ok(TuiCommand::Unpin(true)));
+    assert!(parse_tui_command("unpin some").is_err());
+
+    let mut session = Session::new(SessionId::new("s1").expect("session id"));
+    let diagram_id = DiagramId::new("flow").expect("diagram id");
+    let ast = parse_flowchart("flowchart LR\na --> b\na --> c\n").expect("parse flowchart");
+    let diagram = Diagram::new(diagram_id.clone(), "Flow", DiagramAst::Flowchart(ast));
+    session.diagrams_mut().insert(diagram_id.clone(), diagram);
+    session.set_active_diagram_id(Some(diagram_id.clone()));
+    let mut app = App::new(session);
+    app.follow_ai = false;
+    app.focus = Focus::Diagram;
+    let pins = |app: &App| match app.session.diagrams()[&diagram_id].ast() {
+        DiagramAst::Flowchart(ast) => ast.node_pins().clone(),
+        _ => unreachable!("flowchart"),
+    };
+    let toast = |app: &App| app.toast.as_ref().map(|toast| toast.message.clone());
+    let shift = |code| KeyEvent::new(code, KeyModifiers::SHIFT);
+    let node_c = ObjectId::new("n:c").expect("node id");
+    let c_ref =
+        ObjectRef::new(diagram_id.clone(), category_path(&["flow", "node"]), node_c.clone());
+
+    app.select_object_ref(&c_ref);
+    app.handle_key(shift(KeyCode::Up));
+    assert_eq!(pins(&app).get(&node_c), Some(&FlowNodePin::new(1, 0)));
+    assert_eq!(app.selected_ref(), Some(&c_ref));
+
+    // Already first in its layer, and `n:a` keeps it from moving towards the sources.
+    let rev = app.session.diagrams()[&diagram_id].rev();
+    app.handle_key(shift(KeyCode::Up));
+    app.handle_key(shift(KeyCode::Left));
+    assert_eq!(app.session.diagrams()[&diagram_id].rev(), rev);
+    assert_eq!(toast(&app).as_deref(), Some("The node cannot move further that way"));
+
+    app.handle_key(shift(KeyCode::Right));
+    assert_eq!(pins(&app).get(&node_c), Some(&FlowNodePin::new(2, 0)));
+    app.handle_key_code(KeyCode::Char('u'));
+    assert_eq!(pins(&app).get(&node_c), Some(&FlowNodePin::new(1, 0)));
+
+    app.run_command("unpin all");
+    assert!(pins(&app).is_empty());
+    app.run_command("unpin all");
+    assert_eq!(toast(&app).as_deref(), Some("No pinned nodes to unpin"));
+}
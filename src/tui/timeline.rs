@@ -0,0 +1,280 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! Session activity log behind the Timeline panel and the time-travel view.
+//!
+//! Diagram and xref changes are found by diffing each observed session against the previous one,
+//! so the log does not care whether a change came from the editor, undo/redo or an agent writing
+//! to the session folder. Every diagram change keeps a copy of the diagram, which is what lets the
+//! diagram pane show any diagram as it was at a logged moment.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::model::{Diagram, DiagramId, ObjectRef, Session, XRef, XRefId};
+
+pub(crate) const ACTIVITY_LOG_LIMIT: usize = 500;
+
+/// Who caused an activity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Actor {
+    Human,
+    Agent,
+}
+
+impl fmt::Display for Actor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Human => "you",
+            Self::Agent => "agent",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Activity {
+    /// A diagram was created or changed; holds the diagram right after the change.
+    DiagramChanged {
+        diagram: Box<Diagram>,
+    },
+    DiagramRemoved {
+        diagram_id: DiagramId,
+    },
+    XRefsChanged {
+        added: usize,
+        removed: usize,
+        changed: usize,
+    },
+    Selected {
+        object_ref: ObjectRef,
+    },
+    AgentAction {
+        summary: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ActivityEvent {
+    pub(crate) seq: u64,
+    pub(crate) at: SystemTime,
+    pub(crate) actor: Actor,
+    pub(crate) activity: Activity,
+}
+
+impl ActivityEvent {
+    pub(crate) fn summary(&self) -> String {
+        match &self.activity {
+            Activity::DiagramChanged { diagram } => {
+                format!("edit {} (rev {})", diagram.diagram_id(), diagram.rev())
+            }
+            Activity::DiagramRemoved { diagram_id } => format!("remove {diagram_id}"),
+            Activity::XRefsChanged { added, removed, changed } => {
+                let parts = [(added, "added"), (removed, "removed"), (changed, "changed")]
+                    .into_iter()
+                    .filter(|(count, _)| **count > 0)
+                    .map(|(count, verb)| format!("{count} {verb}"))
+                    .collect::<Vec<_>>();
+                format!("xrefs {}", parts.join(", "))
+            }
+            Activity::Selected { object_ref } => format!("select {object_ref}"),
+            Activity::AgentAction { summary } => summary.clone(),
+        }
+    }
+
+    /// Diagram the time-travel view should show for this event, if it concerns one.
+    pub(crate) fn diagram_id(&self) -> Option<&DiagramId> {
+        match &self.activity {
+            Activity::DiagramChanged { diagram } => Some(diagram.diagram_id()),
+            Activity::DiagramRemoved { diagram_id } => Some(diagram_id),
+            Activity::Selected { object_ref } => Some(object_ref.diagram_id()),
+            Activity::XRefsChanged { .. } | Activity::AgentAction { .. } => None,
+        }
+    }
+}
+
+/// A diagram as of some logged moment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DiagramAt<'a> {
+    /// No change was logged for the diagram, so it looks the same as now.
+    Current,
+    Snapshot(&'a Diagram),
+    /// The diagram did not exist at that moment.
+    Missing,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ActivityLog {
+    events: VecDeque<ActivityEvent>,
+    next_seq: u64,
+    limit: usize,
+    /// Each diagram as it was before its oldest logged change (`None`: it did not exist yet).
+    baselines: BTreeMap<DiagramId, Option<Diagram>>,
+    seen_diagrams: BTreeMap<DiagramId, Diagram>,
+    seen_xrefs: BTreeMap<XRefId, XRef>,
+}
+
+impl ActivityLog {
+    pub(crate) fn new(session: &Session) -> Self {
+        Self::with_limit(session, ACTIVITY_LOG_LIMIT)
+    }
+
+    pub(crate) fn with_limit(session: &Session, limit: usize) -> Self {
+        Self {
+            events: VecDeque::new(),
+            next_seq: 1,
+            limit: limit.max(1),
+            baselines: BTreeMap::new(),
+            seen_diagrams: session.diagrams().clone(),
+            seen_xrefs: session.xrefs().clone(),
+        }
+    }
+
+    /// Logged events, oldest first.
+    pub(crate) fn events(&self) -> &VecDeque<ActivityEvent> {
+        &self.events
+    }
+
+    pub(crate) fn event(&self, seq: u64) -> Option<&ActivityEvent> {
+        self.events.iter().find(|event| event.seq == seq)
+    }
+
+    /// Appends an event and returns its sequence number.
+    ///
+    /// A selection right after another selection by the same actor replaces it, so browsing
+    /// objects does not flush edits out of the log.
+    pub(crate) fn push(&mut self, at: SystemTime, actor: Actor, activity: Activity) -> u64 {
+        if matches!(activity, Activity::Selected { .. }) {
+            if let Some(last) = self.events.back_mut() {
+                if last.actor == actor && matches!(last.activity, Activity::Selected { .. }) {
+                    last.at = at;
+                    last.activity = activity;
+                    return last.seq;
+                }
+            }
+        }
+
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.events.push_back(ActivityEvent { seq, at, actor, activity });
+        while self.events.len() > self.limit {
+            let Some(evicted) = self.events.pop_front() else {
+                break;
+            };
+            match evicted.activity {
+                Activity::DiagramChanged { diagram } => {
+                    self.baselines.insert(diagram.diagram_id().clone(), Some(*diagram));
+                }
+                Activity::DiagramRemoved { diagram_id } => {
+                    self.baselines.insert(diagram_id, None);
+                }
+                _ => {}
+            }
+        }
+        seq
+    }
+
+    /// Logs every diagram and xref difference between `session` and the last observed session.
+    pub(crate) fn observe_session(&mut self, at: SystemTime, actor: Actor, session: &Session) {
+        let mut seen_diagrams = std::mem::take(&mut self.seen_diagrams);
+        for (diagram_id, diagram) in session.diagrams() {
+            let before = seen_diagrams.remove(diagram_id);
+            if before.as_ref() == Some(diagram) {
+                continue;
+            }
+            self.baselines.entry(diagram_id.clone()).or_insert(before);
+            let diagram = Box::new(diagram.clone());
+            self.push(at, actor, Activity::DiagramChanged { diagram });
+        }
+        for (diagram_id, diagram) in seen_diagrams {
+            self.baselines.entry(diagram_id.clone()).or_insert(Some(diagram));
+            self.push(at, actor, Activity::DiagramRemoved { diagram_id });
+        }
+        self.seen_diagrams = session.diagrams().clone();
+
+        let xrefs = session.xrefs();
+        let added = xrefs.keys().filter(|id| !self.seen_xrefs.contains_key(*id)).count();
+        let removed = self.seen_xrefs.keys().filter(|id| !xrefs.contains_key(*id)).count();
+        let changed = xrefs
+            .iter()
+            .filter(|(id, xref)| self.seen_xrefs.get(*id).is_some_and(|seen| seen != *xref))
+            .count();
+        if added + removed + changed > 0 {
+            self.push(at, actor, Activity::XRefsChanged { added, removed, changed });
+            self.seen_xrefs = xrefs.clone();
+        }
+    }
+
+    /// `diagram_id` as it was right after event `seq`.
+    pub(crate) fn diagram_at(&self, diagram_id: &DiagramId, seq: u64) -> DiagramAt<'_> {
+        let logged =
+            self.events.iter().rev().filter(|event| event.seq <= seq).find_map(
+                |event| match &event.activity {
+                    Activity::DiagramChanged { diagram } if diagram.diagram_id() == diagram_id => {
+                        Some(DiagramAt::Snapshot(diagram))
+                    }
+                    Activity::DiagramRemoved { diagram_id: removed } if removed == diagram_id => {
+                        Some(DiagramAt::Missing)
+                    }
+                    _ => None,
+                },
+            );
+        logged.unwrap_or_else(|| match self.baselines.get(diagram_id) {
+            Some(Some(diagram)) => DiagramAt::Snapshot(diagram),
+            Some(None) => DiagramAt::Missing,
+            None => DiagramAt::Current,
+        })
+    }
+}
+
+/// `HH:MM:SS` (UTC) of `at`.
+pub(crate) fn clock_label(at: SystemTime) -> String {
+    let secs = at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() % 86_400;
+    format!("{:02}:{:02}:{:02}", secs / 3_600, secs / 60 % 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{clock_label, Activity, ActivityLog, Actor, DiagramAt};
+    use crate::model::fixtures::flowchart_small_dag;
+    use crate::model::{Diagram, DiagramAst, DiagramId, Session, SessionId};
+
+    #[test]
+    fn log_keeps_diagram_states_across_eviction() {
+        let diagram_id = DiagramId::new("flow").expect("diagram id");
+        let mut session = Session::new(SessionId::new("s").expect("session id"));
+        session.diagrams_mut().insert(
+            diagram_id.clone(),
+            Diagram::new(diagram_id.clone(), "Flow", DiagramAst::Flowchart(flowchart_small_dag())),
+        );
+        let original = session.diagrams()[&diagram_id].clone();
+        let mut log = ActivityLog::with_limit(&session, 2);
+        let at = UNIX_EPOCH + Duration::from_secs(3_723);
+
+        let first = log.push(at, Actor::Human, Activity::AgentAction { summary: "hi".into() });
+        session.diagrams_mut().get_mut(&diagram_id).expect("diagram").bump_rev();
+        log.observe_session(at, Actor::Agent, &session);
+        let edit = log.events().back().expect("edit").seq;
+        assert_eq!(log.diagram_at(&diagram_id, first), DiagramAt::Snapshot(&original));
+        assert_eq!(
+            log.diagram_at(&diagram_id, edit),
+            DiagramAt::Snapshot(&session.diagrams()[&diagram_id])
+        );
+
+        session.diagrams_mut().remove(&diagram_id);
+        log.observe_session(at, Actor::Agent, &session);
+        log.observe_session(at, Actor::Agent, &session);
+        assert_eq!(log.events().len(), 2);
+        assert!(log.event(first).is_none());
+        assert_eq!(log.diagram_at(&diagram_id, 0), DiagramAt::Snapshot(&original));
+        assert_eq!(log.diagram_at(&diagram_id, u64::MAX), DiagramAt::Missing);
+        assert_eq!(clock_label(at), "01:02:03");
+    }
+}
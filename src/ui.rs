@@ -11,7 +11,127 @@
 //! This lightweight state is used to propagate selection context between the interactive TUI and
 //! programmatic integrations (MCP).
 
-use crate::model::{DiagramId, ObjectRef};
+pub mod keymap;
+pub mod messages;
+pub mod theme;
+
+use std::collections::BTreeMap;
+
+use crate::model::{DiagramAst, DiagramId, ObjectRef};
+
+pub use theme::Theme;
+
+/// Rendering density of the TUI diagram pane.
+///
+/// Text diagrams cannot scale glyphs, so zooming trades flowchart column gaps instead: `Compact`
+/// also collapses redundant rows/columns, `Spacious` widens the gaps. Zooming out further squashes
+/// flowchart nodes into mini boxes with truncated labels (`Mini`) and then into one-character
+/// glyphs (`Glyph`). Sequence diagrams render the same at every level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum ViewZoom {
+    Glyph,
+    Mini,
+    Compact,
+    #[default]
+    Normal,
+    Spacious,
+}
+
+impl ViewZoom {
+    pub fn zoom_in(self) -> Self {
+        match self {
+            Self::Glyph => Self::Mini,
+            Self::Mini => Self::Compact,
+            Self::Compact => Self::Normal,
+            Self::Normal | Self::Spacious => Self::Spacious,
+        }
+    }
+
+    pub fn zoom_out(self) -> Self {
+        match self {
+            Self::Glyph | Self::Mini => Self::Glyph,
+            Self::Compact => Self::Mini,
+            Self::Normal => Self::Compact,
+            Self::Spacious => Self::Normal,
+        }
+    }
+}
+
+/// Last viewport the human saw, as published by the TUI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HumanViewport {
+    /// Diagram column shown in the first viewport column (negative when padded).
+    pub scroll_x: i32,
+    /// Diagram row shown in the first viewport row (negative when padded).
+    pub scroll_y: i32,
+    pub width: usize,
+    pub height: usize,
+    pub zoom: ViewZoom,
+}
+
+/// Diagram cells the human currently sees, as published by the TUI.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct HumanScreen {
+    pub diagram_id: Option<DiagramId>,
+    /// Visible diagram lines with pan offsets applied; padding left/above the diagram is blank.
+    pub lines: Vec<String>,
+    /// Objects with at least one visible cell, as `(line, start_col, end_col)` spans relative to
+    /// `lines` (inclusive columns).
+    pub objects: BTreeMap<ObjectRef, Vec<(usize, usize, usize)>>,
+}
+
+/// Viewport change requested by an integration, applied by the TUI on its next tick.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViewRequest {
+    /// Activate the object's diagram and center the viewport on the object.
+    CenterOn(ObjectRef),
+    /// Pan the viewport by a relative number of cells.
+    Scroll { dx: i32, dy: i32 },
+    /// Show another diagram in the diagram pane.
+    SetActiveDiagram(DiagramId),
+    /// Switch the rendering density.
+    Zoom(ViewZoom),
+}
+
+/// Where an agent's [`DiagramProposal`] stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProposalStatus {
+    Pending,
+    /// The human replaced the original with the proposal, producing `rev`.
+    Accepted {
+        rev: u64,
+    },
+    /// The human (or the proposing agent) dropped the proposal.
+    Discarded,
+}
+
+/// A rewrite of a session diagram proposed by an agent.
+///
+/// Proposals stay out of the session (and the session folder) until the human previews and
+/// accepts one in the TUI, which replaces the original diagram's AST.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagramProposal {
+    pub proposal_id: String,
+    pub diagram_id: DiagramId,
+    /// Revision of the original the proposal was made against; accepting requires it unchanged.
+    pub base_rev: u64,
+    pub summary: Option<String>,
+    pub ast: DiagramAst,
+    pub status: ProposalStatus,
+}
+
+/// Where one agent (MCP client) points the human, tracked per client id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentCursor {
+    /// Shown in the TUI legend.
+    pub name: String,
+    /// Palette slot of the agent's highlight color; kept while the cursor exists and reused once
+    /// it is cleared.
+    pub color: usize,
+    pub object_ref: ObjectRef,
+    /// Unix time in milliseconds of the last `attention.agent.set` or heartbeat.
+    pub updated_at_ms: u64,
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct UiState {
@@ -20,6 +140,14 @@ pub struct UiState {
     human_active_object_ref: Option<ObjectRef>,
     follow_ai: bool,
     session_rev: u64,
+    human_viewport: HumanViewport,
+    human_screen: HumanScreen,
+    view_requests: Vec<ViewRequest>,
+    proposals: BTreeMap<String, DiagramProposal>,
+    next_proposal: u64,
+    expired_agent_highlight: Option<ObjectRef>,
+    workspace_session: Option<String>,
+    agent_cursors: BTreeMap<String, AgentCursor>,
 }
 
 impl Default for UiState {
@@ -30,6 +158,14 @@ impl Default for UiState {
             human_active_object_ref: None,
             follow_ai: true,
             session_rev: 0,
+            human_viewport: HumanViewport::default(),
+            human_screen: HumanScreen::default(),
+            view_requests: Vec::new(),
+            proposals: BTreeMap::new(),
+            next_proposal: 1,
+            expired_agent_highlight: None,
+            workspace_session: None,
+            agent_cursors: BTreeMap::new(),
         }
     }
 }
@@ -84,8 +220,183 @@ impl UiState {
         self.rev = self.rev.wrapping_add(1);
     }
 
+    /// The agent spotlight that was last cleared because the agent stopped sending heartbeats;
+    /// reset when the agent sets or clears its spotlight again.
+    pub fn expired_agent_highlight(&self) -> Option<&ObjectRef> {
+        self.expired_agent_highlight.as_ref()
+    }
+
+    pub fn set_expired_agent_highlight(&mut self, object_ref: Option<ObjectRef>) {
+        if self.expired_agent_highlight == object_ref {
+            return;
+        }
+        self.expired_agent_highlight = object_ref;
+        self.rev = self.rev.wrapping_add(1);
+    }
+
+    /// Workspace session the TUI and MCP server should have open, set on startup and by whichever
+    /// switched last; `None` when not running on a workspace.
+    pub fn workspace_session(&self) -> Option<&str> {
+        self.workspace_session.as_deref()
+    }
+
+    pub fn set_workspace_session(&mut self, name: String) {
+        if self.workspace_session.as_deref() == Some(name.as_str()) {
+            return;
+        }
+        self.workspace_session = Some(name);
+        self.rev = self.rev.wrapping_add(1);
+    }
+
+    /// Agent cursors by client id.
+    pub fn agent_cursors(&self) -> &BTreeMap<String, AgentCursor> {
+        &self.agent_cursors
+    }
+
+    /// Points the cursor of `client_id` at `object_ref`; a new client gets the lowest free color.
+    pub fn set_agent_cursor(
+        &mut self,
+        client_id: String,
+        name: String,
+        object_ref: ObjectRef,
+        now_ms: u64,
+    ) -> &AgentCursor {
+        let color = match self.agent_cursors.get(&client_id) {
+            Some(cursor) => cursor.color,
+            None => (0..)
+                .find(|color| self.agent_cursors.values().all(|cursor| cursor.color != *color))
+                .unwrap_or_default(),
+        };
+        self.rev = self.rev.wrapping_add(1);
+        let cursor = AgentCursor { name, color, object_ref, updated_at_ms: now_ms };
+        self.agent_cursors.insert(client_id.clone(), cursor);
+        &self.agent_cursors[&client_id]
+    }
+
+    /// Keeps the cursor of `client_id` alive; `false` when it has none.
+    pub fn touch_agent_cursor(&mut self, client_id: &str, now_ms: u64) -> bool {
+        let Some(cursor) = self.agent_cursors.get_mut(client_id) else {
+            return false;
+        };
+        cursor.updated_at_ms = now_ms;
+        true
+    }
+
+    pub fn clear_agent_cursor(&mut self, client_id: &str) -> Option<AgentCursor> {
+        let cursor = self.agent_cursors.remove(client_id)?;
+        self.rev = self.rev.wrapping_add(1);
+        Some(cursor)
+    }
+
+    /// Drops cursors not set or kept alive since `cutoff_ms`, returning them.
+    pub fn expire_agent_cursors(&mut self, cutoff_ms: u64) -> Vec<AgentCursor> {
+        let expired = self
+            .agent_cursors
+            .iter()
+            .filter(|(_, cursor)| cursor.updated_at_ms <= cutoff_ms)
+            .map(|(client_id, _)| client_id.clone())
+            .collect::<Vec<_>>();
+        let expired = expired
+            .iter()
+            .filter_map(|client_id| self.agent_cursors.remove(client_id))
+            .collect::<Vec<_>>();
+        if !expired.is_empty() {
+            self.rev = self.rev.wrapping_add(1);
+        }
+        expired
+    }
+
+    /// Keeps the cursors whose object passes `keep`, e.g. after a diagram was deleted.
+    pub fn retain_agent_cursors(&mut self, keep: impl Fn(&ObjectRef) -> bool) {
+        let before = self.agent_cursors.len();
+        self.agent_cursors.retain(|_, cursor| keep(&cursor.object_ref));
+        if self.agent_cursors.len() != before {
+            self.rev = self.rev.wrapping_add(1);
+        }
+    }
+
     pub fn bump_session_rev(&mut self) {
         self.session_rev = self.session_rev.wrapping_add(1);
         self.rev = self.rev.wrapping_add(1);
     }
+
+    pub fn human_viewport(&self) -> HumanViewport {
+        self.human_viewport
+    }
+
+    pub fn set_human_viewport(&mut self, viewport: HumanViewport) {
+        if self.human_viewport == viewport {
+            return;
+        }
+        self.human_viewport = viewport;
+        self.rev = self.rev.wrapping_add(1);
+    }
+
+    pub fn human_screen(&self) -> &HumanScreen {
+        &self.human_screen
+    }
+
+    pub fn set_human_screen(&mut self, screen: HumanScreen) {
+        if self.human_screen == screen {
+            return;
+        }
+        self.human_screen = screen;
+        self.rev = self.rev.wrapping_add(1);
+    }
+
+    pub fn view_requests(&self) -> &[ViewRequest] {
+        &self.view_requests
+    }
+
+    pub fn push_view_request(&mut self, request: ViewRequest) {
+        self.view_requests.push(request);
+        self.rev = self.rev.wrapping_add(1);
+    }
+
+    /// Drains pending view requests in the order they were pushed.
+    pub fn take_view_requests(&mut self) -> Vec<ViewRequest> {
+        std::mem::take(&mut self.view_requests)
+    }
+
+    pub fn proposals(&self) -> &BTreeMap<String, DiagramProposal> {
+        &self.proposals
+    }
+
+    /// Stores a pending proposal for `diagram_id` and returns its id (`proposal:<n>`).
+    pub fn add_proposal(
+        &mut self,
+        diagram_id: DiagramId,
+        base_rev: u64,
+        summary: Option<String>,
+        ast: DiagramAst,
+    ) -> String {
+        let proposal_id = format!("proposal:{}", self.next_proposal);
+        self.next_proposal += 1;
+        self.proposals.insert(
+            proposal_id.clone(),
+            DiagramProposal {
+                proposal_id: proposal_id.clone(),
+                diagram_id,
+                base_rev,
+                summary,
+                ast,
+                status: ProposalStatus::Pending,
+            },
+        );
+        self.rev = self.rev.wrapping_add(1);
+        proposal_id
+    }
+
+    /// Settles a pending proposal; returns `false` when it is unknown or already settled.
+    pub fn resolve_proposal(&mut self, proposal_id: &str, status: ProposalStatus) -> bool {
+        let Some(proposal) = self.proposals.get_mut(proposal_id) else {
+            return false;
+        };
+        if proposal.status != ProposalStatus::Pending || status == ProposalStatus::Pending {
+            return false;
+        }
+        proposal.status = status;
+        self.rev = self.rev.wrapping_add(1);
+        true
+    }
 }
@@ -0,0 +1,940 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! TUI key bindings.
+//!
+//! A [`Keymap`] binds key sequences to [`Action`]s per [`KeyScope`]: `global` bindings apply in
+//! every panel, panel bindings (`diagram`, `objects`, …) take precedence while that panel has the
+//! focus. The defaults can be changed from a `keymap.toml` found next to `theme.toml` (the session
+//! folder, else `$XDG_CONFIG_HOME/nereid`):
+//!
+//! ```toml
+//! [global]
+//! quit = "Q"
+//!
+//! [diagram]
+//! center = "z z"           # a sequence: z, then z
+//! pan_up = ["k", "Up", "w"]
+//! peek = []                # unbound
+//! ```
+//!
+//! Setting an action replaces all of its default keys in that scope and takes the keys from any
+//! other action of the scope that had them. Keys are single characters or `Space`, `Enter`, `Esc`,
+//! `Tab`, `Shift-Tab`, `Backspace`, `Delete`, `Up`, `Down`, `Left`, `Right`, `Home`, `End`,
+//! `PageUp`, `PageDown` and `F1`…`F12`, optionally prefixed with `Ctrl-`, `Alt-` or `Shift-`
+//! (`"Ctrl-w v"`, `"Shift-Left"`). Prompts, forms and overlays keep their fixed keys.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::theme::{config_path, strip_comment, unquote};
+
+pub const KEYMAP_FILE_NAME: &str = "keymap.toml";
+
+static CURRENT: OnceLock<Keymap> = OnceLock::new();
+
+/// Where a binding applies: everywhere, or while one panel has the focus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum KeyScope {
+    Global,
+    Diagram,
+    Objects,
+    XRefs,
+    Relations,
+    Recent,
+    Timeline,
+}
+
+impl KeyScope {
+    pub const ALL: [Self; 7] = [
+        Self::Global,
+        Self::Diagram,
+        Self::Objects,
+        Self::XRefs,
+        Self::Relations,
+        Self::Recent,
+        Self::Timeline,
+    ];
+
+    /// Table name in `keymap.toml`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Global => "global",
+            Self::Diagram => "diagram",
+            Self::Objects => "objects",
+            Self::XRefs => "xrefs",
+            Self::Relations => "relations",
+            Self::Recent => "recent",
+            Self::Timeline => "timeline",
+        }
+    }
+}
+
+/// Something a key can do outside prompts and overlays.
+///
+/// Cursor and jump actions act on the focused list panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Help,
+    FocusDiagram,
+    ToggleObjects,
+    ToggleXRefs,
+    ToggleInspector,
+    ToggleRelations,
+    ToggleRecent,
+    ToggleTimeline,
+    FocusNext,
+    FocusPrev,
+    AlternateObject,
+    Undo,
+    Redo,
+    WindowSplit,
+    WindowFocusOther,
+    WindowFocusLeft,
+    WindowFocusRight,
+    WindowClose,
+    WindowCloseOther,
+    TogglePalette,
+    FollowAi,
+    DeselectAll,
+    Search,
+    FuzzySearch,
+    Command,
+    EditSession,
+    SearchNext,
+    SearchPrev,
+    DiagramPrev,
+    DiagramNext,
+    MoveDiagramEarlier,
+    MoveDiagramLater,
+    HintJump,
+    HintSelect,
+    VisualSelect,
+    EditDiagram,
+    EditDescription,
+    ToggleSelected,
+    Yank,
+    JumpIncoming,
+    JumpOutgoing,
+    ToggleRuler,
+    Peek,
+    Insert,
+    MoveParticipantLeft,
+    MoveParticipantRight,
+    NudgeNodeUp,
+    NudgeNodeDown,
+    NudgeNodeLeft,
+    NudgeNodeRight,
+    Stats,
+    Legend,
+    ZoomIn,
+    ZoomOut,
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    PanUpFar,
+    PanDownFar,
+    PanLeftFar,
+    PanRightFar,
+    PageUp,
+    PageDown,
+    SnapStart,
+    SnapEnd,
+    ToggleNotes,
+    Center,
+    HighlightPath,
+    CursorPrev,
+    CursorNext,
+    CursorFirst,
+    CursorLast,
+    SelectedOnly,
+    MoveMessageEarlier,
+    MoveMessageLater,
+    DanglingOnly,
+    InvolvingOnly,
+    JumpFrom,
+    JumpTo,
+    Jump,
+    LeaveTimeTravel,
+}
+
+impl Action {
+    pub const ALL: [Self; 83] = [
+        Self::Quit,
+        Self::Help,
+        Self::FocusDiagram,
+        Self::ToggleObjects,
+        Self::ToggleXRefs,
+        Self::ToggleInspector,
+        Self::ToggleRelations,
+        Self::ToggleRecent,
+        Self::ToggleTimeline,
+        Self::FocusNext,
+        Self::FocusPrev,
+        Self::AlternateObject,
+        Self::Undo,
+        Self::Redo,
+        Self::WindowSplit,
+        Self::WindowFocusOther,
+        Self::WindowFocusLeft,
+        Self::WindowFocusRight,
+        Self::WindowClose,
+        Self::WindowCloseOther,
+        Self::TogglePalette,
+        Self::FollowAi,
+        Self::DeselectAll,
+        Self::Search,
+        Self::FuzzySearch,
+        Self::Command,
+        Self::EditSession,
+        Self::SearchNext,
+        Self::SearchPrev,
+        Self::DiagramPrev,
+        Self::DiagramNext,
+        Self::MoveDiagramEarlier,
+        Self::MoveDiagramLater,
+        Self::HintJump,
+        Self::HintSelect,
+        Self::VisualSelect,
+        Self::EditDiagram,
+        Self::EditDescription,
+        Self::ToggleSelected,
+        Self::Yank,
+        Self::JumpIncoming,
+        Self::JumpOutgoing,
+        Self::ToggleRuler,
+        Self::Peek,
+        Self::Insert,
+        Self::MoveParticipantLeft,
+        Self::MoveParticipantRight,
+        Self::NudgeNodeUp,
+        Self::NudgeNodeDown,
+        Self::NudgeNodeLeft,
+        Self::NudgeNodeRight,
+        Self::Stats,
+        Self::Legend,
+        Self::ZoomIn,
+        Self::ZoomOut,
+        Self::PanUp,
+        Self::PanDown,
+        Self::PanLeft,
+        Self::PanRight,
+        Self::PanUpFar,
+        Self::PanDownFar,
+        Self::PanLeftFar,
+        Self::PanRightFar,
+        Self::PageUp,
+        Self::PageDown,
+        Self::SnapStart,
+        Self::SnapEnd,
+        Self::ToggleNotes,
+        Self::Center,
+        Self::HighlightPath,
+        Self::CursorPrev,
+        Self::CursorNext,
+        Self::CursorFirst,
+        Self::CursorLast,
+        Self::SelectedOnly,
+        Self::MoveMessageEarlier,
+        Self::MoveMessageLater,
+        Self::DanglingOnly,
+        Self::InvolvingOnly,
+        Self::JumpFrom,
+        Self::JumpTo,
+        Self::Jump,
+        Self::LeaveTimeTravel,
+    ];
+
+    /// Key in `keymap.toml`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Quit => "quit",
+            Self::Help => "help",
+            Self::FocusDiagram => "focus_diagram",
+            Self::ToggleObjects => "toggle_objects",
+            Self::ToggleXRefs => "toggle_xrefs",
+            Self::ToggleInspector => "toggle_inspector",
+            Self::ToggleRelations => "toggle_relations",
+            Self::ToggleRecent => "toggle_recent",
+            Self::ToggleTimeline => "toggle_timeline",
+            Self::FocusNext => "focus_next",
+            Self::FocusPrev => "focus_prev",
+            Self::AlternateObject => "alternate_object",
+            Self::Undo => "undo",
+            Self::Redo => "redo",
+            Self::WindowSplit => "window_split",
+            Self::WindowFocusOther => "window_focus_other",
+            Self::WindowFocusLeft => "window_focus_left",
+            Self::WindowFocusRight => "window_focus_right",
+            Self::WindowClose => "window_close",
+            Self::WindowCloseOther => "window_close_other",
+            Self::TogglePalette => "toggle_palette",
+            Self::FollowAi => "follow_ai",
+            Self::DeselectAll => "deselect_all",
+            Self::Search => "search",
+            Self::FuzzySearch => "fuzzy_search",
+            Self::Command => "command",
+            Self::EditSession => "edit_session",
+            Self::SearchNext => "search_next",
+            Self::SearchPrev => "search_prev",
+            Self::DiagramPrev => "diagram_prev",
+            Self::DiagramNext => "diagram_next",
+            Self::MoveDiagramEarlier => "move_diagram_earlier",
+            Self::MoveDiagramLater => "move_diagram_later",
+            Self::HintJump => "hint_jump",
+            Self::HintSelect => "hint_select",
+            Self::VisualSelect => "visual_select",
+            Self::EditDiagram => "edit_diagram",
+            Self::EditDescription => "edit_description",
+            Self::ToggleSelected => "toggle_selected",
+            Self::Yank => "yank",
+            Self::JumpIncoming => "jump_incoming",
+            Self::JumpOutgoing => "jump_outgoing",
+            Self::ToggleRuler => "toggle_ruler",
+            Self::Peek => "peek",
+            Self::Insert => "insert",
+            Self::MoveParticipantLeft => "move_participant_left",
+            Self::MoveParticipantRight => "move_participant_right",
+            Self::NudgeNodeUp => "nudge_node_up",
+            Self::NudgeNodeDown => "nudge_node_down",
+            Self::NudgeNodeLeft => "nudge_node_left",
+            Self::NudgeNodeRight => "nudge_node_right",
+            Self::Stats => "stats",
+            Self::Legend => "legend",
+            Self::ZoomIn => "zoom_in",
+            Self::ZoomOut => "zoom_out",
+            Self::PanUp => "pan_up",
+            Self::PanDown => "pan_down",
+            Self::PanLeft => "pan_left",
+            Self::PanRight => "pan_right",
+            Self::PanUpFar => "pan_up_far",
+            Self::PanDownFar => "pan_down_far",
+            Self::PanLeftFar => "pan_left_far",
+            Self::PanRightFar => "pan_right_far",
+            Self::PageUp => "page_up",
+            Self::PageDown => "page_down",
+            Self::SnapStart => "snap_start",
+            Self::SnapEnd => "snap_end",
+            Self::ToggleNotes => "toggle_notes",
+            Self::Center => "center",
+            Self::HighlightPath => "highlight_path",
+            Self::CursorPrev => "cursor_prev",
+            Self::CursorNext => "cursor_next",
+            Self::CursorFirst => "cursor_first",
+            Self::CursorLast => "cursor_last",
+            Self::SelectedOnly => "selected_only",
+            Self::MoveMessageEarlier => "move_message_earlier",
+            Self::MoveMessageLater => "move_message_later",
+            Self::DanglingOnly => "dangling_only",
+            Self::InvolvingOnly => "involving_only",
+            Self::JumpFrom => "jump_from",
+            Self::JumpTo => "jump_to",
+            Self::Jump => "jump",
+            Self::LeaveTimeTravel => "leave_time_travel",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+/// The built-in bindings, in the order the help overlay lists alternative keys.
+const DEFAULT_BINDINGS: &[(KeyScope, Action, &[&str])] = &[
+    (KeyScope::Global, Action::Quit, &["q"]),
+    (KeyScope::Global, Action::Help, &["?"]),
+    (KeyScope::Global, Action::FocusDiagram, &["1"]),
+    (KeyScope::Global, Action::ToggleObjects, &["2"]),
+    (KeyScope::Global, Action::ToggleXRefs, &["3"]),
+    (KeyScope::Global, Action::ToggleInspector, &["4"]),
+    (KeyScope::Global, Action::ToggleRelations, &["5"]),
+    (KeyScope::Global, Action::ToggleRecent, &["6"]),
+    (KeyScope::Global, Action::ToggleTimeline, &["7"]),
+    (KeyScope::Global, Action::FocusNext, &["Tab"]),
+    (KeyScope::Global, Action::FocusPrev, &["Shift-Tab"]),
+    // Terminals report Ctrl-^ as Ctrl-6.
+    (KeyScope::Global, Action::AlternateObject, &["^", "Ctrl-^", "Ctrl-6"]),
+    (KeyScope::Global, Action::Undo, &["u"]),
+    (KeyScope::Global, Action::Redo, &["Ctrl-r"]),
+    (KeyScope::Global, Action::WindowSplit, &["Ctrl-w v"]),
+    (KeyScope::Global, Action::WindowFocusOther, &["Ctrl-w w", "Ctrl-w Ctrl-w"]),
+    (KeyScope::Global, Action::WindowFocusLeft, &["Ctrl-w h", "Ctrl-w Left"]),
+    (KeyScope::Global, Action::WindowFocusRight, &["Ctrl-w l", "Ctrl-w Right"]),
+    (KeyScope::Global, Action::WindowClose, &["Ctrl-w c", "Ctrl-w q"]),
+    (KeyScope::Global, Action::WindowCloseOther, &["Ctrl-w o"]),
+    (KeyScope::Global, Action::TogglePalette, &["|"]),
+    (KeyScope::Global, Action::FollowAi, &["a"]),
+    (KeyScope::Global, Action::DeselectAll, &["d"]),
+    (KeyScope::Global, Action::Search, &["/"]),
+    (KeyScope::Global, Action::FuzzySearch, &["\\"]),
+    (KeyScope::Global, Action::Command, &[":"]),
+    (KeyScope::Global, Action::EditSession, &["S"]),
+    (KeyScope::Global, Action::SearchNext, &["n"]),
+    (KeyScope::Global, Action::SearchPrev, &["N"]),
+    (KeyScope::Global, Action::DiagramPrev, &["["]),
+    (KeyScope::Global, Action::DiagramNext, &["]"]),
+    (KeyScope::Global, Action::MoveDiagramEarlier, &["{"]),
+    (KeyScope::Global, Action::MoveDiagramLater, &["}"]),
+    (KeyScope::Diagram, Action::HintJump, &["f"]),
+    (KeyScope::Diagram, Action::HintSelect, &["c"]),
+    (KeyScope::Diagram, Action::VisualSelect, &["v"]),
+    (KeyScope::Diagram, Action::EditDiagram, &["e"]),
+    (KeyScope::Diagram, Action::EditDescription, &["E"]),
+    (KeyScope::Diagram, Action::ToggleSelected, &["Space"]),
+    (KeyScope::Diagram, Action::Yank, &["y"]),
+    (KeyScope::Diagram, Action::JumpIncoming, &["g"]),
+    (KeyScope::Diagram, Action::JumpOutgoing, &["t"]),
+    (KeyScope::Diagram, Action::ToggleRuler, &["#"]),
+    (KeyScope::Diagram, Action::Peek, &["p"]),
+    (KeyScope::Diagram, Action::Insert, &["o"]),
+    (KeyScope::Diagram, Action::MoveParticipantLeft, &["<"]),
+    (KeyScope::Diagram, Action::MoveParticipantRight, &[">"]),
+    (KeyScope::Diagram, Action::NudgeNodeUp, &["Shift-Up"]),
+    (KeyScope::Diagram, Action::NudgeNodeDown, &["Shift-Down"]),
+    (KeyScope::Diagram, Action::NudgeNodeLeft, &["Shift-Left"]),
+    (KeyScope::Diagram, Action::NudgeNodeRight, &["Shift-Right"]),
+    (KeyScope::Diagram, Action::Stats, &["i"]),
+    (KeyScope::Diagram, Action::Legend, &["m"]),
+    (KeyScope::Diagram, Action::ZoomIn, &["+"]),
+    (KeyScope::Diagram, Action::ZoomOut, &["-"]),
+    (KeyScope::Diagram, Action::PanUp, &["Up", "k"]),
+    (KeyScope::Diagram, Action::PanDown, &["Down", "j"]),
+    (KeyScope::Diagram, Action::PanLeft, &["Left", "h"]),
+    (KeyScope::Diagram, Action::PanRight, &["Right", "l"]),
+    (KeyScope::Diagram, Action::PanUpFar, &["K"]),
+    (KeyScope::Diagram, Action::PanDownFar, &["J"]),
+    (KeyScope::Diagram, Action::PanLeftFar, &["H"]),
+    (KeyScope::Diagram, Action::PanRightFar, &["L"]),
+    (KeyScope::Diagram, Action::PageUp, &["PageUp"]),
+    (KeyScope::Diagram, Action::PageDown, &["PageDown"]),
+    (KeyScope::Diagram, Action::SnapStart, &["Home"]),
+    (KeyScope::Diagram, Action::SnapEnd, &["End"]),
+    (KeyScope::Diagram, Action::ToggleNotes, &["n"]),
+    (KeyScope::Diagram, Action::Center, &["z z"]),
+    (KeyScope::Diagram, Action::HighlightPath, &["P"]),
+    (KeyScope::Objects, Action::CursorPrev, &["Up", "k", "h"]),
+    (KeyScope::Objects, Action::CursorNext, &["Down", "j", "l"]),
+    (KeyScope::Objects, Action::CursorFirst, &["Home"]),
+    (KeyScope::Objects, Action::CursorLast, &["End"]),
+    (KeyScope::Objects, Action::ToggleSelected, &["Space"]),
+    (KeyScope::Objects, Action::SelectedOnly, &["-"]),
+    (KeyScope::Objects, Action::Peek, &["p"]),
+    (KeyScope::Objects, Action::Yank, &["y"]),
+    (KeyScope::Objects, Action::HintJump, &["f"]),
+    (KeyScope::Objects, Action::HintSelect, &["c"]),
+    (KeyScope::Objects, Action::JumpIncoming, &["g"]),
+    (KeyScope::Objects, Action::JumpOutgoing, &["t"]),
+    (KeyScope::Objects, Action::MoveMessageEarlier, &["K"]),
+    (KeyScope::Objects, Action::MoveMessageLater, &["J"]),
+    (KeyScope::Objects, Action::HighlightPath, &["P"]),
+    (KeyScope::XRefs, Action::CursorPrev, &["Up", "k", "h"]),
+    (KeyScope::XRefs, Action::CursorNext, &["Down", "j", "l"]),
+    (KeyScope::XRefs, Action::CursorFirst, &["Home"]),
+    (KeyScope::XRefs, Action::CursorLast, &["End"]),
+    (KeyScope::XRefs, Action::DanglingOnly, &["-"]),
+    (KeyScope::XRefs, Action::InvolvingOnly, &["I"]),
+    (KeyScope::XRefs, Action::JumpFrom, &["g"]),
+    (KeyScope::XRefs, Action::JumpTo, &["t"]),
+    (KeyScope::Relations, Action::CursorPrev, &["Up", "k", "h"]),
+    (KeyScope::Relations, Action::CursorNext, &["Down", "j", "l"]),
+    (KeyScope::Relations, Action::CursorFirst, &["Home"]),
+    (KeyScope::Relations, Action::CursorLast, &["End"]),
+    (KeyScope::Relations, Action::Jump, &["Enter", "g"]),
+    (KeyScope::Recent, Action::CursorPrev, &["Up", "k"]),
+    (KeyScope::Recent, Action::CursorNext, &["Down", "j"]),
+    (KeyScope::Recent, Action::CursorFirst, &["Home"]),
+    (KeyScope::Recent, Action::CursorLast, &["End"]),
+    (KeyScope::Recent, Action::Jump, &["Enter", "g"]),
+    (KeyScope::Timeline, Action::CursorPrev, &["Up", "k"]),
+    (KeyScope::Timeline, Action::CursorNext, &["Down", "j"]),
+    (KeyScope::Timeline, Action::CursorFirst, &["Home"]),
+    (KeyScope::Timeline, Action::CursorLast, &["End"]),
+    (KeyScope::Timeline, Action::Jump, &["Enter", "g"]),
+    (KeyScope::Timeline, Action::LeaveTimeTravel, &["Esc"]),
+];
+
+/// One key press with the modifiers that matter for bindings.
+///
+/// Shift is folded into characters (`J`, not `Shift-j`) and into `Shift-Tab`, the way terminals
+/// report them, so it only remains on keys like `Shift-Left`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyPress {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyPress {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers =
+            modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        let code = match code {
+            KeyCode::Char(ch) if modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(ch.to_uppercase().next().unwrap_or(ch))
+            }
+            KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            code => code,
+        };
+        if matches!(code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self { code, modifiers }
+    }
+}
+
+impl From<KeyCode> for KeyPress {
+    fn from(code: KeyCode) -> Self {
+        Self { code, modifiers: KeyModifiers::NONE }
+    }
+}
+
+impl From<KeyEvent> for KeyPress {
+    fn from(event: KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Binding {
+    scope: KeyScope,
+    keys: Vec<KeyPress>,
+    action: Action,
+}
+
+/// What the keys typed so far mean in a scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyLookup {
+    Action(Action),
+    /// The keys start a longer sequence; wait for the next key.
+    Pending,
+    Unbound,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+impl Keymap {
+    /// The installed keymap, or the defaults if none was installed.
+    pub fn current() -> &'static Self {
+        CURRENT.get_or_init(Self::default)
+    }
+
+    /// Makes `self` the keymap returned by [`Keymap::current`]; only the first call has an effect.
+    pub fn install(self) {
+        let _ = CURRENT.set(self);
+    }
+
+    /// Loads `keymap.toml` from `session_dir`, else from the user config directory; the defaults
+    /// when neither exists.
+    pub fn load(session_dir: Option<&Path>) -> Result<Self, KeymapError> {
+        let candidates = session_dir
+            .map(|dir| dir.join(KEYMAP_FILE_NAME))
+            .into_iter()
+            .chain(config_path(KEYMAP_FILE_NAME));
+        for path in candidates {
+            match fs::read_to_string(&path) {
+                Ok(text) => {
+                    return Self::parse(&text)
+                        .map_err(|message| KeymapError::Parse { path, message })
+                }
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(source) => return Err(KeymapError::Io { path, source }),
+            }
+        }
+        Ok(Self::default())
+    }
+
+    /// Parses the `keymap.toml` subset: one table per scope with `action = "keys"` or
+    /// `action = ["keys", …]` entries.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut keymap = Self::default();
+        let mut scope = None;
+
+        for (idx, raw_line) in text.lines().enumerate() {
+            let line_no = idx + 1;
+            let line = strip_comment(raw_line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(table) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+                let table = table.trim();
+                scope = Some(
+                    KeyScope::ALL
+                        .into_iter()
+                        .find(|scope| scope.name() == table)
+                        .ok_or_else(|| format!("line {line_no}: unknown table [{table}]"))?,
+                );
+                continue;
+            }
+            let scope = scope
+                .ok_or_else(|| format!("line {line_no}: bindings need a table like [global]"))?;
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {line_no}: expected `action = \"keys\"`"))?;
+            let action = Action::from_name(key.trim())
+                .ok_or_else(|| format!("line {line_no}: unknown action {:?}", key.trim()))?;
+            let sequences = string_list(value.trim())
+                .ok_or_else(|| format!("line {line_no}: expected a quoted value or a list"))?
+                .into_iter()
+                .filter(|sequence| !sequence.trim().is_empty())
+                .map(|sequence| {
+                    parse_key_sequence(sequence)
+                        .map_err(|message| format!("line {line_no}: {message}"))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            keymap.rebind(scope, action, sequences);
+        }
+
+        keymap.check_prefixes()?;
+        Ok(keymap)
+    }
+
+    /// Replaces the keys of `action` in `scope`, taking them from other actions of the scope.
+    fn rebind(&mut self, scope: KeyScope, action: Action, sequences: Vec<Vec<KeyPress>>) {
+        self.bindings.retain(|binding| {
+            binding.scope != scope
+                || (binding.action != action && !sequences.contains(&binding.keys))
+        });
+        self.bindings.extend(sequences.into_iter().map(|keys| Binding { scope, keys, action }));
+    }
+
+    /// Rejects sequences that can never complete because a shorter one in the scope fires first.
+    fn check_prefixes(&self) -> Result<(), String> {
+        for short in &self.bindings {
+            if let Some(long) = self.bindings.iter().find(|long| {
+                long.scope == short.scope
+                    && long.keys.len() > short.keys.len()
+                    && long.keys.starts_with(&short.keys)
+            }) {
+                return Err(format!(
+                    "[{}] {:?} is the start of {:?}",
+                    short.scope.name(),
+                    key_sequence_label(&short.keys),
+                    key_sequence_label(&long.keys)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolves the keys typed so far, trying `scope` before the global bindings.
+    pub fn lookup(&self, scope: KeyScope, keys: &[KeyPress]) -> KeyLookup {
+        let scopes = if scope == KeyScope::Global {
+            &[KeyScope::Global][..]
+        } else {
+            &[scope, KeyScope::Global][..]
+        };
+        for &scope in scopes {
+            let mut matches = self
+                .bindings
+                .iter()
+                .filter(|binding| binding.scope == scope && binding.keys.starts_with(keys));
+            if let Some(binding) = matches.next() {
+                return if binding.keys.len() == keys.len() {
+                    KeyLookup::Action(binding.action)
+                } else {
+                    KeyLookup::Pending
+                };
+            }
+        }
+        KeyLookup::Unbound
+    }
+
+    /// Key sequences bound to `action` in `scope`, in binding order.
+    pub fn keys(&self, scope: KeyScope, action: Action) -> impl Iterator<Item = &[KeyPress]> {
+        self.bindings
+            .iter()
+            .filter(move |binding| binding.scope == scope && binding.action == action)
+            .map(|binding| binding.keys.as_slice())
+    }
+
+    /// Help label for `actions` in `scope`: their first keys joined by `/`, then their second keys,
+    /// and so on (`↑/↓ or k/j`); `—` when none is bound.
+    pub fn label(&self, scope: KeyScope, actions: &[Action]) -> String {
+        let keys = actions
+            .iter()
+            .map(|&action| self.keys(scope, action).map(key_sequence_label).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let rows = keys.iter().map(Vec::len).max().unwrap_or(0);
+        if rows == 0 {
+            return "—".to_owned();
+        }
+        (0..rows)
+            .map(|row| keys.iter().filter_map(|keys| keys.get(row).cloned()).collect::<Vec<_>>())
+            .map(|row| row.join("/"))
+            .collect::<Vec<_>>()
+            .join(" or ")
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = DEFAULT_BINDINGS
+            .iter()
+            .flat_map(|&(scope, action, sequences)| {
+                sequences.iter().map(move |sequence| Binding {
+                    scope,
+                    keys: parse_key_sequence(sequence).expect("valid default key"),
+                    action,
+                })
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+/// Parses space-separated key names (`"z z"`, `"Ctrl-w v"`, `"Shift-Left"`) into the keys
+/// pressed in order.
+pub fn parse_key_sequence(text: &str) -> Result<Vec<KeyPress>, String> {
+    text.split_whitespace().map(parse_key_press).collect()
+}
+
+/// One key name with optional `Ctrl-`/`Alt-`/`Shift-` prefixes; a lone `-` stays the minus key.
+fn parse_key_press(name: &str) -> Result<KeyPress, String> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = name;
+    while rest.chars().nth(1).is_some() && rest != "Shift-Tab" {
+        let Some((modifier, after)) = [
+            (KeyModifiers::CONTROL, "Ctrl-"),
+            (KeyModifiers::ALT, "Alt-"),
+            (KeyModifiers::SHIFT, "Shift-"),
+        ]
+        .into_iter()
+        .find_map(|(modifier, prefix)| Some((modifier, rest.strip_prefix(prefix)?))) else {
+            break;
+        };
+        if after.is_empty() {
+            return Err(format!("unknown key {name:?}"));
+        }
+        modifiers |= modifier;
+        rest = after;
+    }
+    let code = parse_key(rest).map_err(|_| format!("unknown key {name:?}"))?;
+    Ok(KeyPress::new(code, modifiers))
+}
+
+fn parse_key(name: &str) -> Result<KeyCode, String> {
+    let mut chars = name.chars();
+    if let (Some(ch), None) = (chars.next(), chars.next()) {
+        return Ok(KeyCode::Char(ch));
+    }
+    Ok(match name {
+        "Space" => KeyCode::Char(' '),
+        "Enter" => KeyCode::Enter,
+        "Esc" => KeyCode::Esc,
+        "Tab" => KeyCode::Tab,
+        "Shift-Tab" | "BackTab" => KeyCode::BackTab,
+        "Backspace" => KeyCode::Backspace,
+        "Delete" => KeyCode::Delete,
+        "Up" => KeyCode::Up,
+        "Down" => KeyCode::Down,
+        "Left" => KeyCode::Left,
+        "Right" => KeyCode::Right,
+        "Home" => KeyCode::Home,
+        "End" => KeyCode::End,
+        "PageUp" => KeyCode::PageUp,
+        "PageDown" => KeyCode::PageDown,
+        _ => match name.strip_prefix('F').and_then(|n| n.parse::<u8>().ok()) {
+            Some(n @ 1..=12) => KeyCode::F(n),
+            _ => return Err(format!("unknown key {name:?}")),
+        },
+    })
+}
+
+/// How the help overlay shows a key sequence: `zz`, `Shift-Tab`, `↑`, `Ctrl-w v`.
+pub fn key_sequence_label(keys: &[KeyPress]) -> String {
+    let labels = keys.iter().map(|&key| key_press_label(key)).collect::<Vec<_>>();
+    let all_chars = keys
+        .iter()
+        .all(|key| key.modifiers.is_empty() && matches!(key.code, KeyCode::Char(ch) if ch != ' '));
+    labels.join(if all_chars { "" } else { " " })
+}
+
+fn key_press_label(key: KeyPress) -> String {
+    let mut label = String::new();
+    for (modifier, prefix) in [
+        (KeyModifiers::CONTROL, "Ctrl-"),
+        (KeyModifiers::ALT, "Alt-"),
+        (KeyModifiers::SHIFT, "Shift-"),
+    ] {
+        if key.modifiers.contains(modifier) {
+            label.push_str(prefix);
+        }
+    }
+    label + &key_label(key.code)
+}
+
+fn key_label(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => "Space".to_owned(),
+        KeyCode::Char(ch) => ch.to_string(),
+        KeyCode::Up => "↑".to_owned(),
+        KeyCode::Down => "↓".to_owned(),
+        KeyCode::Left => "←".to_owned(),
+        KeyCode::Right => "→".to_owned(),
+        KeyCode::PageUp => "PgUp".to_owned(),
+        KeyCode::PageDown => "PgDn".to_owned(),
+        KeyCode::BackTab => "Shift-Tab".to_owned(),
+        KeyCode::Delete => "Del".to_owned(),
+        KeyCode::F(n) => format!("F{n}"),
+        other => format!("{other:?}"),
+    }
+}
+
+/// Quoted strings of a `"…"` value or a `["…", …]` list.
+fn string_list(value: &str) -> Option<Vec<&str>> {
+    let Some(inner) = value.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) else {
+        return unquote(value).map(|value| vec![value]);
+    };
+    let mut items = Vec::new();
+    let mut rest = inner.trim();
+    while !rest.is_empty() {
+        let quote = rest.chars().next().filter(|ch| matches!(ch, '"' | '\''))?;
+        let end = rest[1..].find(quote)? + 1;
+        items.push(&rest[1..end]);
+        rest = rest[end + 1..].trim_start();
+        rest = rest.strip_prefix(',').map_or(rest, str::trim_start);
+    }
+    Some(items)
+}
+
+#[derive(Debug)]
+pub enum KeymapError {
+    Io { path: PathBuf, source: io::Error },
+    Parse { path: PathBuf, message: String },
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "failed to read {}: {source}", path.display()),
+            Self::Parse { path, message } => {
+                write!(f, "invalid keymap {}: {message}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for KeymapError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Parse { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use super::{parse_key_sequence, Action, KeyLookup, KeyPress, KeyScope, Keymap};
+
+    fn chars(text: &str) -> Vec<KeyPress> {
+        text.chars().map(|ch| KeyPress::from(KeyCode::Char(ch))).collect()
+    }
+
+    #[test]
+    fn defaults_resolve_panel_bindings_before_global_ones() {
+        let keymap = Keymap::default();
+        let lookup = |scope, keys: &[KeyPress]| keymap.lookup(scope, keys);
+
+        assert_eq!(lookup(KeyScope::Diagram, &chars("n")), KeyLookup::Action(Action::ToggleNotes));
+        assert_eq!(lookup(KeyScope::Objects, &chars("n")), KeyLookup::Action(Action::SearchNext));
+        assert_eq!(lookup(KeyScope::Diagram, &chars("z")), KeyLookup::Pending);
+        assert_eq!(lookup(KeyScope::Diagram, &chars("zz")), KeyLookup::Action(Action::Center));
+        assert_eq!(lookup(KeyScope::Objects, &chars("z")), KeyLookup::Unbound);
+        assert_eq!(
+            keymap.label(KeyScope::Objects, &[Action::CursorPrev, Action::CursorNext]),
+            "↑/↓ or k/j or h/l"
+        );
+        assert_eq!(keymap.label(KeyScope::Diagram, &[Action::Center]), "zz");
+    }
+
+    #[test]
+    fn parses_rebinds_sequences_and_unbinds() {
+        let keymap = Keymap::parse(
+            "# vim-ish\n[global]\nquit = \"Q\"\n\n[diagram]\npeek = []\nhint_jump = ['f', \"x x\"]\n\
+             center = \"z\" # steals nothing, z z goes away\n",
+        )
+        .expect("keymap");
+        assert_eq!(keymap.lookup(KeyScope::Recent, &chars("Q")), KeyLookup::Action(Action::Quit));
+        assert_eq!(keymap.lookup(KeyScope::Recent, &chars("q")), KeyLookup::Unbound);
+        assert_eq!(keymap.lookup(KeyScope::Diagram, &chars("p")), KeyLookup::Unbound);
+        assert_eq!(keymap.lookup(KeyScope::Diagram, &chars("x")), KeyLookup::Pending);
+        assert_eq!(
+            keymap.lookup(KeyScope::Diagram, &chars("z")),
+            KeyLookup::Action(Action::Center)
+        );
+        assert_eq!(keymap.label(KeyScope::Diagram, &[Action::HintJump]), "f or xx");
+        assert_eq!(keymap.label(KeyScope::Diagram, &[Action::Peek]), "—");
+
+        // Taking a key moves it away from the action that had it.
+        let keymap = Keymap::parse("[objects]\nyank = \"p\"").expect("keymap");
+        assert_eq!(keymap.lookup(KeyScope::Objects, &chars("p")), KeyLookup::Action(Action::Yank));
+        assert_eq!(keymap.label(KeyScope::Objects, &[Action::Peek]), "—");
+
+        assert!(Keymap::parse("quit = \"Q\"").unwrap_err().contains("need a table"));
+        assert!(Keymap::parse("[panels]").unwrap_err().contains("unknown table"));
+        assert!(Keymap::parse("[global]\nfly = \"x\"").unwrap_err().contains("unknown action"));
+        assert!(Keymap::parse("[global]\nquit = \"Hyper\"").unwrap_err().contains("unknown key"));
+        assert!(Keymap::parse("[diagram]\nyank = \"z\"").unwrap_err().contains("is the start of"));
+    }
+
+    #[test]
+    fn modifiers_bind_redo_window_commands_and_nudges() {
+        let keymap = Keymap::default();
+        let ctrl = |ch| KeyPress::new(KeyCode::Char(ch), KeyModifiers::CONTROL);
+        let shift = |code| KeyPress::from(KeyEvent::new(code, KeyModifiers::SHIFT));
+
+        assert_eq!(keymap.lookup(KeyScope::Objects, &[ctrl('r')]), KeyLookup::Action(Action::Redo));
+        assert_eq!(keymap.lookup(KeyScope::Objects, &chars("r")), KeyLookup::Unbound);
+        assert_eq!(keymap.lookup(KeyScope::Diagram, &[ctrl('w')]), KeyLookup::Pending);
+        assert_eq!(
+            keymap.lookup(KeyScope::Diagram, &[ctrl('w'), ctrl('w')]),
+            KeyLookup::Action(Action::WindowFocusOther)
+        );
+        assert_eq!(
+            keymap.lookup(KeyScope::Recent, &[ctrl('6')]),
+            KeyLookup::Action(Action::AlternateObject)
+        );
+        assert_eq!(
+            keymap.lookup(KeyScope::Diagram, &[shift(KeyCode::Left)]),
+            KeyLookup::Action(Action::NudgeNodeLeft)
+        );
+        assert_eq!(
+            keymap.lookup(KeyScope::Diagram, &[shift(KeyCode::Char('j'))]),
+            KeyLookup::Action(Action::PanDownFar)
+        );
+        assert_eq!(keymap.label(KeyScope::Global, &[Action::Undo, Action::Redo]), "u/Ctrl-r");
+        assert_eq!(keymap.label(KeyScope::Global, &[Action::WindowSplit]), "Ctrl-w v");
+        assert_eq!(keymap.label(KeyScope::Diagram, &[Action::NudgeNodeUp]), "Shift-↑");
+
+        assert_eq!(parse_key_sequence("Shift-Tab"), Ok(vec![KeyPress::from(KeyCode::BackTab)]));
+        assert_eq!(parse_key_sequence("Shift-x"), Ok(chars("X")));
+        assert_eq!(parse_key_sequence("Ctrl--"), Ok(vec![ctrl('-')]));
+        assert!(parse_key_sequence("Ctrl-").is_err());
+
+        let keymap =
+            Keymap::parse("[global]\nredo = \"U\"\nwindow_split = \"Ctrl-w s\"\n").expect("keymap");
+        assert_eq!(keymap.lookup(KeyScope::Global, &chars("U")), KeyLookup::Action(Action::Redo));
+        assert_eq!(keymap.lookup(KeyScope::Global, &[ctrl('r')]), KeyLookup::Unbound);
+        assert_eq!(
+            keymap.lookup(KeyScope::Global, &[ctrl('w'), KeyPress::from(KeyCode::Char('s'))]),
+            KeyLookup::Action(Action::WindowSplit)
+        );
+    }
+}
@@ -0,0 +1,1025 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! TUI message catalog.
+//!
+//! Panel titles, footer labels, help text, toasts and accessibility descriptions are looked up by
+//! key. English is built in and `locale` picks another bundled catalog (`de`); single messages can
+//! be overridden from a `messages.toml` found next to `theme.toml` (the session folder, else
+//! `$XDG_CONFIG_HOME/nereid`):
+//!
+//! ```toml
+//! locale = "de"
+//!
+//! [messages]
+//! "toast.no_object_selected" = "Nichts ausgewählt"
+//! "footer.quit" = "RAUS"
+//! ```
+//!
+//! Messages may contain `{name}` placeholders, filled in by [`trf`]. Keys missing from a locale
+//! fall back to English.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Write as _};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::theme::{config_path, strip_comment, unquote};
+
+pub const MESSAGES_FILE_NAME: &str = "messages.toml";
+
+static CURRENT: OnceLock<Messages> = OnceLock::new();
+
+/// Bundled catalogs by locale name; the first one is the fallback for every other.
+const LOCALES: &[(&str, &[(&str, &str)])] = &[("en", EN), ("de", DE)];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Messages {
+    locale: &'static str,
+    messages: BTreeMap<&'static str, Cow<'static, str>>,
+}
+
+impl Messages {
+    /// The bundled catalog for `name`, on top of English.
+    pub fn locale(name: &str) -> Option<Self> {
+        let &(locale, catalog) = LOCALES.iter().find(|(locale, _)| *locale == name)?;
+        let messages =
+            EN.iter().chain(catalog).map(|&(key, text)| (key, Cow::Borrowed(text))).collect();
+        Some(Self { locale, messages })
+    }
+
+    pub fn locale_name(&self) -> &'static str {
+        self.locale
+    }
+
+    /// The installed catalog, or English if none was installed.
+    pub fn current() -> &'static Self {
+        CURRENT.get_or_init(Self::default)
+    }
+
+    /// Makes `self` the catalog returned by [`Messages::current`]; only the first call has an
+    /// effect.
+    pub fn install(self) {
+        let _ = CURRENT.set(self);
+    }
+
+    /// Loads `messages.toml` from `session_dir`, else from the user config directory; English
+    /// when neither exists.
+    pub fn load(session_dir: Option<&Path>) -> Result<Self, MessagesError> {
+        let candidates = session_dir
+            .map(|dir| dir.join(MESSAGES_FILE_NAME))
+            .into_iter()
+            .chain(config_path(MESSAGES_FILE_NAME));
+        for path in candidates {
+            match fs::read_to_string(&path) {
+                Ok(text) => {
+                    return Self::parse(&text)
+                        .map_err(|message| MessagesError::Parse { path, message })
+                }
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(source) => return Err(MessagesError::Io { path, source }),
+            }
+        }
+        Ok(Self::default())
+    }
+
+    /// Parses the `messages.toml` subset: an optional top-level `locale` and a `[messages]` table
+    /// of overrides keyed by message key.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut locale = None;
+        let mut overrides = Vec::new();
+        let mut in_messages = false;
+
+        for (idx, raw_line) in text.lines().enumerate() {
+            let line_no = idx + 1;
+            let line = strip_comment(raw_line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(table) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+                if table.trim() != "messages" {
+                    return Err(format!("line {line_no}: unknown table [{}]", table.trim()));
+                }
+                in_messages = true;
+                continue;
+            }
+            let (key, value) = split_key_value(line)
+                .ok_or_else(|| format!("line {line_no}: expected `key = \"value\"`"))?;
+            let value =
+                unquote(value).ok_or_else(|| format!("line {line_no}: expected a quoted value"))?;
+
+            if in_messages {
+                overrides.push((line_no, key.to_owned(), unescape(value)));
+            } else if key == "locale" {
+                locale = Some(
+                    Self::locale(value)
+                        .ok_or_else(|| format!("line {line_no}: unknown locale {value:?}"))?,
+                );
+            } else {
+                return Err(format!("line {line_no}: unknown key {key:?}"));
+            }
+        }
+
+        let mut messages = locale.unwrap_or_default();
+        for (line_no, key, text) in overrides {
+            *messages
+                .messages
+                .get_mut(key.as_str())
+                .ok_or_else(|| format!("line {line_no}: unknown message {key:?}"))? =
+                Cow::Owned(text);
+        }
+        Ok(messages)
+    }
+
+    /// The message for `key`, or `key` itself when no catalog has it.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.messages.get(key).map_or(key, |text| text.as_ref())
+    }
+
+    /// The message for `key` with its `{name}` placeholders replaced from `args`; unknown
+    /// placeholders are kept as written.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let template = self.get(key);
+        let mut out = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            let tail = &rest[start..];
+            let arg = tail.find('}').and_then(|end| {
+                let name = &tail[1..end];
+                args.iter().find(|(arg, _)| *arg == name).map(|(_, value)| (end, value))
+            });
+            match arg {
+                Some((end, value)) => {
+                    let _ = write!(out, "{value}");
+                    rest = &tail[end + 1..];
+                }
+                None => {
+                    out.push('{');
+                    rest = &tail[1..];
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        Self::locale("en").expect("bundled English catalog")
+    }
+}
+
+/// The installed message for `key`.
+pub fn tr(key: &'static str) -> &'static str {
+    Messages::current().get(key)
+}
+
+/// The installed message for `key` with `{name}` placeholders filled in from `args`.
+pub fn trf(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    Messages::current().format(key, args)
+}
+
+/// Splits `key = value`, where the key may be quoted (message keys contain dots).
+fn split_key_value(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = if let Some(rest) = line.strip_prefix('"') {
+        let end = rest.find('"')?;
+        let value = rest[end + 1..].trim_start().strip_prefix('=')?;
+        (&rest[..end], value)
+    } else {
+        let (key, value) = line.split_once('=')?;
+        (key.trim(), value)
+    };
+    Some((key, value.trim()))
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        match (ch, chars.clone().next()) {
+            ('\\', Some('n')) => out.push('\n'),
+            ('\\', Some(next @ ('"' | '\\'))) => out.push(next),
+            _ => {
+                out.push(ch);
+                continue;
+            }
+        }
+        chars.next();
+    }
+    out
+}
+
+#[derive(Debug)]
+pub enum MessagesError {
+    Io { path: PathBuf, source: io::Error },
+    Parse { path: PathBuf, message: String },
+}
+
+impl fmt::Display for MessagesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "failed to read {}: {source}", path.display()),
+            Self::Parse { path, message } => {
+                write!(f, "invalid messages {}: {message}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for MessagesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Parse { .. } => None,
+        }
+    }
+}
+
+const EN: &[(&str, &str)] = &[
+    ("title.diagram", "Diagram"),
+    ("title.session_info", "Session info"),
+    ("title.insert_sequence", "Insert participant/message"),
+    ("title.insert_node", "Insert node"),
+    ("title.search_results", "Results {index}/{total}"),
+    ("title.help", "Help"),
+    ("title.objects", "Objects"),
+    ("title.xrefs", "XRefs"),
+    ("title.relations", "Relations"),
+    ("title.recent", "Recent"),
+    ("title.timeline", "Timeline"),
+    ("title.inspector", "Inspector"),
+    ("form.next", "Next"),
+    ("form.save", "Save"),
+    ("form.cancel", "Cancel"),
+    ("form.title", "Title"),
+    ("form.description", "Description"),
+    ("form.tags", "Tags"),
+    ("form.label", "Label"),
+    ("form.shape", "Shape"),
+    ("form.from", "From"),
+    ("form.name_text", "Name/text"),
+    ("form.to", "To"),
+    ("footer.next", "Next"),
+    ("footer.cancel", "CANCEL"),
+    ("footer.ai", "AI"),
+    ("footer.agents", "Agents"),
+    ("footer.hint", "HINT"),
+    ("footer.help", "HELP"),
+    ("footer.quit", "QUIT"),
+    ("footer.visual", "VISUAL"),
+    ("footer.select", "SELECT"),
+    ("footer.diagram", "DIAGRAM"),
+    ("footer.chain", "CHAIN"),
+    ("footer.edit", "EDIT"),
+    ("footer.yank", "YANK"),
+    ("footer.xref", "XREF"),
+    ("footer.notes", "NOTES"),
+    ("footer.done", "DONE"),
+    ("footer.filter", "FILTER"),
+    ("footer.jump", "JUMP"),
+    ("footer.swap", "SWAP"),
+    ("footer.travel", "TRAVEL"),
+    ("footer.present", "PRESENT"),
+    ("footer.accept", "Accept"),
+    ("footer.close", "Close"),
+    ("footer.run", "Run"),
+    ("footer.toast", "Toast:"),
+    ("inspector.no_selection", "No selection"),
+    ("inspector.no_description", "No description (E to edit)"),
+    ("inspector.xref", "ID: {xref_id}\nKind: {kind}\nStatus: {status}\nLabel: {label}\nFrom: {from}{from_missing}\nTo: {to}{to_missing}"),
+    ("inspector.missing", " (missing)"),
+    ("inspector.object", "Label: {label}\nNote: {note}\nRef: {object_ref}\nDiagram: {diagram_id}\nCategory: {category}\nObject: {object_id}"),
+    ("help.help_toggle", "Help (toggle)"),
+    ("help.quit", "Quit"),
+    ("help.focus_diagram", "Focus Diagram"),
+    ("help.toggle_focus_objects_xrefs", "Toggle+focus Objects/XRefs"),
+    ("help.toggle_inspector_panel", "Toggle inspector panel"),
+    ("help.toggle_focus_relations", "Toggle+focus Relations"),
+    ("help.toggle_focus_recent_objects", "Toggle+focus Recent objects"),
+    ("help.toggle_focus_activity_timeline", "Toggle+focus activity Timeline"),
+    ("help.swap_to_previously_focused_object", "Swap to previously focused object"),
+    ("help.undo_redo_diagram_edit_any_diagram", "Undo / redo diagram edit (any diagram)"),
+    ("help.toggle_follow_ai_highlight", "Toggle follow AI highlight"),
+    ("help.deselect_all_in_current_diagram", "Deselect all in current diagram"),
+    ("help.focus_next_previous_panel", "Focus next/previous panel"),
+    ("help.previous_next_diagram", "Previous/next diagram"),
+    ("help.move_diagram_earlier_later", "Move diagram earlier/later"),
+    ("help.edit_session_title_description_tags", "Edit session title/description/tags"),
+    ("help.copy_current_location_ref", "Copy current location ref"),
+    ("help.focus_under_pointer", "Focus the object or row under the pointer"),
+    ("help.pan_diagram_shift_horizontally", "Pan diagram (Shift: horizontally)"),
+    ("help.regular_search", "Regular search"),
+    ("help.fuzzy_search", "Fuzzy search"),
+    ("help.search_next_previous_result", "Search next/previous result"),
+    ("help.update_query", "Update query"),
+    ("help.commit_open_results_panel", "Commit: open results panel"),
+    ("help.browse_results_panel", "Browse results panel"),
+    ("help.keep_current_result_close_panel", "Keep current result, close panel"),
+    ("help.delete_query_char", "Delete query char"),
+    ("help.clear_search", "Clear search"),
+    ("help.pan_diagram_accelerates_when_held", "Pan diagram (accelerates when held)"),
+    ("help.pan_diagram_by_10", "Pan diagram by 10"),
+    ("help.pan_by_page", "Pan by page"),
+    ("help.snap_to_top_left_bottom_right", "Snap to top-left/bottom-right edge"),
+    ("help.toggle_notes_when_not_searching", "Toggle notes (when not searching)"),
+    ("help.toggle_ruler_grid_overlay", "Toggle ruler/grid overlay"),
+    ("help.zoom_in_out", "Zoom in/out (flowchart spacing, mini boxes, glyphs)"),
+    ("help.peek_selected_object_diagram_and_objects", "Peek selected object (Diagram and Objects)"),
+    ("help.insert_node_flow_or_participant_message", "Insert node (flow) or participant/message (seq)"),
+    ("help.move_selected_participant_left_right_seq", "Move selected participant left/right (seq)"),
+    ("help.nudge_selected_node_flow", "Move selected node one grid step and pin it there (flow)"),
+    ("help.diagram_stats_size_and_render_cost", "Diagram stats: size and render cost"),
+    ("help.legend_xref_markers_highlights_style_rules", "Legend: xref markers, highlights, style rules"),
+    ("help.center_viewport_on_selected_object", "Center viewport on selected object"),
+    ("help.center_viewport_on_row_column", "Center viewport on row/column"),
+    ("help.select_cheapest_weighted_flow_path", "Select cheapest weighted flow path"),
+    ("help.select_critical_longest_duration_path", "Select critical (longest-duration) path"),
+    ("help.select_cut_points", "Select nodes and edges whose removal splits the flowchart"),
+    ("help.highlight_path_between_selected_nodes", "Add the path between the two selected flow nodes"),
+    ("help.preview_tidy_layout", "Preview a tidier flowchart layout; Enter applies"),
+    ("help.fuzzy_matcher_nereid_or_skim_toggle", "Fuzzy matcher: nereid or skim (toggle)"),
+    ("help.edge_glyphs_cycle", "Flowchart edges: box, braille or blocks (cycle)"),
+    ("help.archive_diagram", "Hide diagram from [/] and search (:unarchive)"),
+    ("help.lock_diagram", "Make diagram read-only for TUI and MCP (:unlock)"),
+    ("help.open_diagram_by_id", "Open a diagram by id, archived too"),
+    ("help.sequence_diagram_from_selected_flow_path", "Sequence diagram from selected flow path"),
+    ("help.interaction_flowchart_from_sequence_diagram", "Interaction flowchart from sequence diagram"),
+    ("help.add_style_rule", "Add styling rule, e.g. tag=deprecated => dim"),
+    ("help.hint_jump_mode", "Hint jump mode"),
+    ("help.chain_hint_mode", "Chain hint mode"),
+    ("help.box_select_arrows_grow_enter_adds", "Box-select: arrows grow, Enter adds to selection"),
+    ("help.edit_active_diagram_in_editor", "Edit active diagram in $EDITOR"),
+    ("help.edit_diagram_description_in_editor", "Edit diagram description in $EDITOR"),
+    ("help.toggle_selected_object", "Toggle selected object"),
+    ("help.yank_selected_object_ref", "Yank selected object ref"),
+    ("help.jump_inbound_outbound", "Jump inbound/outbound"),
+    ("help.choose_hint_target", "Choose hint target"),
+    ("help.cancel_hint_mode", "Cancel hint mode"),
+    ("help.move_object_cursor", "Move object cursor"),
+    ("help.first_last_object", "First/last object"),
+    ("help.filter_selected_only", "Filter selected-only"),
+    ("help.move_selected_message_up_down_seq", "Move selected message earlier/later (seq)"),
+    ("help.move_xref_cursor", "Move xref cursor"),
+    ("help.first_last_xref", "First/last xref"),
+    ("help.toggle_dangling_only_filter", "Toggle dangling-only filter"),
+    ("help.toggle_involving_selection_filter", "Toggle involving-selection filter"),
+    ("help.jump_to_from_to_endpoint", "Jump to from/to endpoint"),
+    ("help.move_relation_cursor", "Move relation cursor"),
+    ("help.first_last_relation", "First/last relation"),
+    ("help.jump_to_related_object", "Jump to related object"),
+    ("help.move_recent_cursor", "Move recent cursor"),
+    ("help.jump_to_recent_object", "Jump to recent object"),
+    ("help.move_timeline_cursor", "Move timeline cursor (scrubs while time traveling)"),
+    ("help.time_travel_to_event", "Show diagrams as they were at that moment"),
+    ("help.leave_time_travel", "Leave time travel"),
+    ("help.scroll_help", "Scroll help"),
+    ("help.close_help", "Close help"),
+    ("help.section.global", "--- Global ---"),
+    ("help.section.search", "--- Search ---"),
+    ("help.section.diagram", "--- Diagram ---"),
+    ("help.section.objects", "--- Objects ---"),
+    ("help.section.xrefs", "--- XRefs ---"),
+    ("help.section.relations", "--- Relations ---"),
+    ("help.section.recent", "--- Recent ---"),
+    ("help.section.timeline", "--- Timeline ---"),
+    ("help.section.help", "--- Help ---"),
+    ("help.note", "Note: "),
+    ("help.note_xref_follow", " follow the first matching xref (toast if multiple)."),
+    ("toast.external_action_failed", "External action failed: {err}"),
+    ("toast.external_edits_not_watched", "External edits will not auto-reload: {err}"),
+    ("toast.large_diagram_reduced_detail", "Large diagram: reduced detail ({overrun})"),
+    ("toast.no_object_selected", "No object selected"),
+    ("toast.selected_object_not_rendered", "Selected object is not rendered"),
+    ("toast.active_diagram_persist_failed", "Active diagram persist failed: {err}"),
+    ("toast.diagram_order_persist_failed", "Diagram order persist failed: {err}"),
+    ("toast.inspector_shown", "Inspector shown"),
+    ("toast.inspector_hidden", "Inspector hidden"),
+    ("toast.palette_shown", "Palette shown"),
+    ("toast.palette_hidden", "Palette hidden"),
+    ("toast.ruler_shown", "Ruler shown"),
+    ("toast.ruler_hidden", "Ruler hidden"),
+    ("toast.objects_shown", "Objects shown"),
+    ("toast.objects_hidden", "Objects hidden"),
+    ("toast.xrefs_shown", "XRefs shown"),
+    ("toast.xrefs_hidden", "XRefs hidden"),
+    ("toast.relations_shown", "Relations shown"),
+    ("toast.relations_hidden", "Relations hidden"),
+    ("toast.timeline_shown", "Timeline shown"),
+    ("toast.timeline_hidden", "Timeline hidden"),
+    ("toast.recent_shown", "Recent shown"),
+    ("toast.recent_hidden", "Recent hidden"),
+    ("toast.no_previous_object", "No previous object"),
+    ("toast.follow_ai_enabled", "Follow AI enabled"),
+    ("toast.follow_ai_disabled", "Follow AI disabled"),
+    ("toast.description_unchanged", "Description unchanged: {diagram_id}"),
+    ("toast.description_updated", "Description updated: {diagram_id}"),
+    ("toast.description_saved", "Description saved: {diagram_id}"),
+    ("toast.edit_cancelled_no_changes", "Edit cancelled (no changes): {diagram_id}"),
+    ("toast.no_structural_changes", "No structural changes: {diagram_id}"),
+    ("toast.nothing_to_undo", "Nothing to undo"),
+    ("toast.undo_failed", "Undo failed: {err}"),
+    ("toast.nothing_to_redo", "Nothing to redo"),
+    ("toast.redo_failed", "Redo failed: {err}"),
+    ("toast.tidy_cancelled", "Tidy cancelled"),
+    ("toast.no_active_diagram", "No active diagram"),
+    ("toast.insert_needs_flow_or_sequence", "Inserting objects needs a flowchart or sequence diagram"),
+    ("toast.diagram_not_found", "Diagram not found: {diagram_id}"),
+    ("toast.session_info_updated", "Session info updated"),
+    ("toast.session_info_saved", "Session info saved"),
+    ("toast.session_info_save_failed", "Session info save failed: {err}"),
+    ("toast.centered_on", "Centered on {row},{col}"),
+    ("toast.no_style_rules", "No style rules"),
+    ("toast.style_rules_persist_failed", "Style rules persist failed: {err}"),
+    ("toast.no_id_prefix_rules", "No id prefix rules"),
+    ("toast.id_prefix_rules_persist_failed", "Id prefix rules persist failed: {err}"),
+    ("toast.no_id_prefix_rules_to_lint", "No id prefix rules to lint against"),
+    ("toast.id_prefix_rules_followed", "All object ids follow the id prefix rules"),
+    ("toast.archive_persist_failed", "Archive persist failed: {err}"),
+    ("toast.lock_persist_failed", "Lock persist failed: {err}"),
+    ("toast.diagram_read_only", "{diagram_id} is read-only; :unlock it to edit"),
+    ("toast.diagram_load_failed", "{diagram_id} failed to load; fix its file to edit: {err}"),
+    ("toast.unknown_diagram", "Unknown diagram: {diagram_id}"),
+    ("toast.toseq_needs_a_flowchart", "toseq needs a flowchart"),
+    ("toast.select_chain_first", "Select a connected chain of edges first (e.g. :path a b)"),
+    ("toast.scaffold_failed", "Scaffold failed: {err}"),
+    ("toast.toflow_needs_a_sequence_diagram", "toflow needs a sequence diagram"),
+    ("toast.scaffolded_with_interaction_edges", "Scaffolded {flow_id} with {edges} interaction edges"),
+    ("toast.scaffold_persist_failed", "Scaffold persist failed: {err}"),
+    ("toast.tidy_needs_a_flowchart", "Tidy needs a flowchart"),
+    ("toast.layout_already_tidy", "Layout already tidy: {diagram_id}"),
+    ("toast.tidy_failed", "Tidy failed: {err}"),
+    ("toast.select_a_participant_to_move", "Select a participant to move"),
+    ("toast.select_a_message_to_move", "Select a sequence message to move"),
+    ("toast.move_message_failed", "Moving the message failed: {err}"),
+    ("toast.select_an_object_with_a_note", "Select a node, participant or note to edit its note"),
+    ("toast.note_edit_failed", "Editing the note failed: {err}"),
+    ("toast.diff_closed", "Diff closed"),
+    ("toast.diff_needs_session_folder", "Nothing saved to compare with: no session folder"),
+    ("toast.diff_load_failed", "Loading the saved version failed: {err}"),
+    ("toast.diagram_not_saved", "{diagram_id} has no saved version yet"),
+    (
+        "toast.diff_showing_current",
+        "{diagram_id} since its saved version: {counts}; :diff saved shows removed objects",
+    ),
+    ("toast.diff_showing_saved", "Saved version of {diagram_id}: {counts}; :diff closes"),
+    ("toast.select_a_node_to_move", "Select a flowchart node to move"),
+    ("toast.node_cannot_move_further", "The node cannot move further that way"),
+    ("toast.no_pinned_nodes", "No pinned nodes to unpin"),
+    ("toast.pin_failed", "Pinning failed: {err}"),
+    ("toast.participants_stay_inside_their_box", "Participants stay inside their box"),
+    ("toast.tidy_preview_stale", "{diagram_id} changed since the preview; run :tidy again"),
+    ("toast.weighted_paths_need_a_flowchart", "Weighted paths need a flowchart"),
+    ("toast.unknown_path_nodes", "Unknown node: {from} or {to}"),
+    ("toast.no_path", "No path from {from_id} to {to_id}"),
+    ("toast.no_messages_to_chain", "No messages to chain"),
+    ("toast.critical_path_needs_acyclic", "Critical path needs a non-empty acyclic flowchart"),
+    ("toast.cuts_need_a_flowchart", "Cut points need a flowchart"),
+    ("toast.cut_points", "{nodes} cut points, {bridges} bridges selected"),
+    ("toast.path_needs_two_selected_nodes", "Select two flow nodes to highlight the path between them"),
+    ("toast.path_highlighted", "Path {from_id} → {to_id}: {nodes} nodes, {edges} edges selected"),
+    ("toast.critical_path_needs_flow_or_sequence", "Critical path needs a flowchart or sequence diagram"),
+    ("toast.visual_started", "Visual: arrows grow the box, Enter selects, Esc cancels"),
+    ("toast.visual_selection_cancelled", "Visual selection cancelled"),
+    ("toast.no_hint_targets_for", "No hint targets for '{first}'"),
+    ("toast.no_matching_hint", "No matching hint"),
+    ("toast.too_many_hint_targets", "Too many hint targets ({hint_count}, max {max_two_char})"),
+    ("toast.no_selected_objects_in_diagram", "No selected objects in current diagram"),
+    ("toast.no_recent_object_selected", "No recent object selected"),
+    ("toast.missing_object", "Missing object {target}"),
+    ("toast.no_activity_selected", "No activity selected"),
+    ("toast.proposal_withdrawn", "The previewed proposal was withdrawn"),
+    ("toast.showing_original", "Showing the original (:proposal returns to the proposal)"),
+    ("toast.no_proposal_previewed", "No proposal previewed (:proposal)"),
+    ("toast.back_to_the_present", "Back to the present"),
+    ("toast.no_relation_selected", "No relation selected"),
+    ("toast.no_outgoing_xref_for", "No outgoing xref for {selected_ref}"),
+    ("toast.no_incoming_xref_for", "No incoming xref for {selected_ref}"),
+    ("toast.no_location_to_copy", "No location to copy"),
+    ("toast.copied", "Copied {location} ({backend})"),
+    ("toast.clipboard_error", "Clipboard error: {err}"),
+    ("toast.paste_not_mermaid", "Clipboard holds no sequence, flowchart, state or class Mermaid"),
+    ("toast.paste_parse_failed", "Pasted Mermaid does not parse: {err}"),
+    ("toast.paste_persist_failed", "Pasted diagram not saved: {err}"),
+    ("toast.pasted_new_diagram", "Created {diagram_id} from the clipboard"),
+    ("toast.yanked_object_ref", "Yanked object ref ({backend})"),
+    ("toast.diagram_changed", "{verb} {diagram_id} (rev {baseline_rev}->{new_rev})"),
+    ("toast.diagram_archived", "Archived {diagram_id}"),
+    ("toast.diagram_restored", "Restored {diagram_id}"),
+    ("toast.diagram_locked", "Locked {diagram_id}"),
+    ("toast.diagram_unlocked", "Unlocked {diagram_id}"),
+    ("toast.diagram_changed_sync_pending", "{verb} {diagram_id} (rev {baseline_rev}->{new_rev}); sync pending"),
+    ("toast.synced_edited_diagram", "Synced edited diagram: {diagram_id}"),
+    ("toast.ops_journal_failed", "Ops journal write failed: {err}"),
+    ("toast.merged_disk_changes_into", "Merged disk changes into {diagram_id}"),
+    ("toast.style_rules", "Style rules: {rules}"),
+    ("toast.id_prefix_rules", "Id prefix rules: {rules}"),
+    ("toast.scaffolded_from_hops", "Scaffolded {seq_id} from {hops} hops"),
+    ("toast.fuzzy_matcher", "Fuzzy matcher: {matcher}"),
+    ("toast.edge_glyphs", "Edge glyphs: {glyphs}"),
+    ("toast.no_new_objects_in_box", "No new objects in box ({count} already selected)"),
+    ("toast.time_travel", "Time travel: {at} (Esc in Timeline returns)"),
+    ("toast.diagram_no_longer_exists", "{diagram_id} no longer exists"),
+    ("toast.cannot_preview", "Cannot preview {proposal_id}: {err}"),
+    ("toast.proposal_no_longer_pending", "{proposal_id} is no longer pending"),
+    ("toast.cannot_accept", "Cannot accept {proposal_id}: {err}"),
+    ("toast.discarded", "Discarded {proposal_id}"),
+    ("toast.outgoing_xrefs_followed_first", "{count} outgoing xrefs; followed first ({xref_id})"),
+    ("toast.incoming_xrefs_followed_first", "{count} incoming xrefs; followed first ({xref_id})"),
+    ("toast.zoom_glyphs", "Zoom: glyphs"),
+    ("toast.zoom_mini", "Zoom: mini boxes"),
+    ("toast.zoom_compact", "Zoom: compact"),
+    ("toast.zoom_normal", "Zoom: normal"),
+    ("toast.zoom_spacious", "Zoom: spacious"),
+    ("toast.id_prefix_violations", "{count} id(s) break the id prefix rules, e.g. {object_ref} ({rule})"),
+    ("toast.showing_selected_objects", "Showing selected objects"),
+    ("toast.showing_all_objects", "Showing all objects"),
+    ("toast.no_pending_proposal", "No pending proposal {proposal_id}"),
+    ("toast.no_pending_proposals", "No pending proposals"),
+    ("toast.sync_conflict_kept_disk", "Sync conflict: kept disk version of {diagram_id}; dropped local edit"),
+    ("toast.agent_proposed", "Agent proposed {proposal_id} for {diagram_id}: {summary} (:proposal to preview)"),
+    ("toast.proposal_stale", "{diagram_id} changed since {proposal_id} (rev {base_rev}->{current_rev}); ask for a new proposal"),
+    ("toast.a11y_line_on", "Accessibility line on"),
+    ("toast.a11y_line_off", "Accessibility line off"),
+    ("toast.a11y_output_failed", "Accessibility output failed: {err}"),
+    ("help.a11y_line_toggle", "Accessibility line: describe the focused object (toggle)"),
+    ("a11y.focus", "{label} in {diagram}."),
+    ("a11y.nothing_focused", "Nothing focused in {diagram}."),
+    ("a11y.no_connections", "No connections."),
+    ("a11y.more_connections", "{count} more"),
+    ("a11y.arrow_to", "to"),
+    ("a11y.arrow_from", "from"),
+    ("a11y.tag.out", "outgoing"),
+    ("a11y.tag.in", "incoming"),
+    ("a11y.tag.from", "from"),
+    ("a11y.tag.to", "to"),
+    ("a11y.tag.prev", "previous"),
+    ("a11y.tag.next", "next"),
+    ("a11y.tag.member", "members"),
+    ("a11y.tag.within", "within"),
+    ("a11y.tag.xref_out", "xref to"),
+    ("a11y.tag.xref_in", "xref from"),
+    ("a11y.tag.other", "related"),
+    ("toast.split_already_open", "The view is already split"),
+    ("toast.no_diagram_to_split", "No diagram to split"),
+    ("toast.no_split", "The view is not split (Ctrl-w v splits it)"),
+    ("help.split_open", "Split: open a second diagram pane (xref partner of the focused object)"),
+    ("help.split_focus", "Split: focus the other / left / right pane"),
+    ("help.split_close", "Split: close the focused / the other pane"),
+    ("toast.session_check_passed", "Session check passed: no problems found"),
+    ("toast.session_check_problems", "Session check: {errors} error(s), {warnings} warning(s) (Esc closes)"),
+    ("help.check_session", "Check session integrity (dangling xrefs, missing nodes, ...)"),
+    ("toast.no_workspace", "No workspace (start nereid with --workspace <dir>)"),
+    ("toast.workspace_empty", "The workspace has no sessions"),
+    ("toast.workspace_list_failed", "Cannot list workspace sessions: {err}"),
+    ("toast.session_opened", "Opened session {name}"),
+    ("toast.session_opened_recovered", "Opened session {name} (recovered {count} unsynced edit(s))"),
+    ("toast.session_open_failed", "Cannot open session {name}: {err}"),
+    ("toast.session_switch_blocked", "Resolve the merge prompt before switching sessions"),
+    ("help.sessions", "Switch to another session of the workspace (picker, or by name)"),
+    ("help.paste_new", "New diagram from Mermaid on the clipboard"),
+    ("help.note_command", "Set, edit or clear the selected object's note"),
+    ("help.diff", "Compare with the saved version (current/saved side)"),
+    ("help.unpin", "Unpin the selected node (or all) for auto-layout"),
+];
+
+const DE: &[(&str, &str)] = &[
+    ("title.diagram", "Diagramm"),
+    ("title.session_info", "Sitzungsinfo"),
+    ("title.insert_sequence", "Teilnehmer/Nachricht einfügen"),
+    ("title.insert_node", "Knoten einfügen"),
+    ("title.search_results", "Treffer {index}/{total}"),
+    ("title.help", "Hilfe"),
+    ("title.objects", "Objekte"),
+    ("title.xrefs", "XRefs"),
+    ("title.relations", "Beziehungen"),
+    ("title.recent", "Zuletzt"),
+    ("title.timeline", "Verlauf"),
+    ("title.inspector", "Inspektor"),
+    ("form.next", "Weiter"),
+    ("form.save", "Speichern"),
+    ("form.cancel", "Abbrechen"),
+    ("form.title", "Titel"),
+    ("form.description", "Beschreibung"),
+    ("form.tags", "Tags"),
+    ("form.label", "Beschriftung"),
+    ("form.shape", "Form"),
+    ("form.from", "Von"),
+    ("form.name_text", "Name/Text"),
+    ("form.to", "Nach"),
+    ("footer.next", "Weiter"),
+    ("footer.cancel", "ABBRUCH"),
+    ("footer.ai", "KI"),
+    ("footer.agents", "Agenten"),
+    ("footer.hint", "HINWEIS"),
+    ("footer.help", "HILFE"),
+    ("footer.quit", "ENDE"),
+    ("footer.visual", "VISUELL"),
+    ("footer.select", "AUSWAHL"),
+    ("footer.diagram", "DIAGRAMM"),
+    ("footer.chain", "KETTE"),
+    ("footer.edit", "BEARBEITEN"),
+    ("footer.yank", "KOPIEREN"),
+    ("footer.xref", "XREF"),
+    ("footer.notes", "NOTIZEN"),
+    ("footer.done", "FERTIG"),
+    ("footer.filter", "FILTER"),
+    ("footer.jump", "SPRUNG"),
+    ("footer.swap", "TAUSCH"),
+    ("footer.travel", "ZEITREISE"),
+    ("footer.present", "JETZT"),
+    ("footer.accept", "Übernehmen"),
+    ("footer.close", "Schließen"),
+    ("footer.run", "Ausführen"),
+    ("footer.toast", "Meldung:"),
+    ("inspector.no_selection", "Keine Auswahl"),
+    ("inspector.no_description", "Keine Beschreibung (E zum Bearbeiten)"),
+    ("inspector.xref", "ID: {xref_id}\nArt: {kind}\nStatus: {status}\nBeschriftung: {label}\nVon: {from}{from_missing}\nNach: {to}{to_missing}"),
+    ("inspector.missing", " (fehlt)"),
+    ("inspector.object", "Beschriftung: {label}\nNotiz: {note}\nRef: {object_ref}\nDiagramm: {diagram_id}\nKategorie: {category}\nObjekt: {object_id}"),
+    ("help.help_toggle", "Hilfe (umschalten)"),
+    ("help.quit", "Beenden"),
+    ("help.focus_diagram", "Diagramm fokussieren"),
+    ("help.toggle_focus_objects_xrefs", "Objekte/XRefs ein-/ausblenden und fokussieren"),
+    ("help.toggle_inspector_panel", "Inspektor ein-/ausblenden"),
+    ("help.toggle_focus_relations", "Beziehungen ein-/ausblenden und fokussieren"),
+    ("help.toggle_focus_recent_objects", "Zuletzt verwendete Objekte ein-/ausblenden und fokussieren"),
+    ("help.toggle_focus_activity_timeline", "Aktivitätsverlauf ein-/ausblenden und fokussieren"),
+    ("help.swap_to_previously_focused_object", "Zum zuvor fokussierten Objekt wechseln"),
+    ("help.undo_redo_diagram_edit_any_diagram", "Diagrammänderung rückgängig / wiederholen (alle Diagramme)"),
+    ("help.toggle_follow_ai_highlight", "KI-Hervorhebung folgen umschalten"),
+    ("help.deselect_all_in_current_diagram", "Auswahl im aktuellen Diagramm aufheben"),
+    ("help.focus_next_previous_panel", "Nächstes/vorheriges Panel fokussieren"),
+    ("help.previous_next_diagram", "Vorheriges/nächstes Diagramm"),
+    ("help.move_diagram_earlier_later", "Diagramm nach vorne/hinten verschieben"),
+    ("help.edit_session_title_description_tags", "Sitzungstitel/-beschreibung/-tags bearbeiten"),
+    ("help.copy_current_location_ref", "Ref der aktuellen Position kopieren"),
+    ("help.focus_under_pointer", "Objekt oder Zeile unter dem Zeiger fokussieren"),
+    ("help.pan_diagram_shift_horizontally", "Diagramm verschieben (Shift: horizontal)"),
+    ("help.regular_search", "Normale Suche"),
+    ("help.fuzzy_search", "Unscharfe Suche"),
+    ("help.search_next_previous_result", "Nächster/vorheriger Suchtreffer"),
+    ("help.update_query", "Suchbegriff ändern"),
+    ("help.commit_open_results_panel", "Bestätigen: Trefferliste öffnen"),
+    ("help.browse_results_panel", "Trefferliste durchblättern"),
+    ("help.keep_current_result_close_panel", "Aktuellen Treffer behalten, Liste schließen"),
+    ("help.delete_query_char", "Zeichen im Suchbegriff löschen"),
+    ("help.clear_search", "Suche leeren"),
+    ("help.pan_diagram_accelerates_when_held", "Diagramm verschieben (beschleunigt beim Halten)"),
+    ("help.pan_diagram_by_10", "Diagramm um 10 verschieben"),
+    ("help.pan_by_page", "Seitenweise verschieben"),
+    ("help.snap_to_top_left_bottom_right", "Zum Rand oben links/unten rechts springen"),
+    ("help.toggle_notes_when_not_searching", "Notizen umschalten (außerhalb der Suche)"),
+    ("help.toggle_ruler_grid_overlay", "Lineal/Raster umschalten"),
+    ("help.zoom_in_out", "Vergrößern/verkleinern (Flussdiagramm-Abstände, Mini-Boxen, Symbole)"),
+    ("help.peek_selected_object_diagram_and_objects", "Ausgewähltes Objekt ansehen (Diagramm und Objekte)"),
+    ("help.insert_node_flow_or_participant_message", "Knoten (Fluss) oder Teilnehmer/Nachricht (Sequenz) einfügen"),
+    ("help.move_selected_participant_left_right_seq", "Ausgewählten Teilnehmer nach links/rechts (Sequenz)"),
+    ("help.nudge_selected_node_flow", "Ausgewählten Knoten um ein Rasterfeld schieben und anheften (Fluss)"),
+    ("help.diagram_stats_size_and_render_cost", "Diagrammstatistik: Größe und Renderaufwand"),
+    ("help.legend_xref_markers_highlights_style_rules", "Legende: XRef-Marker, Hervorhebungen, Stilregeln"),
+    ("help.center_viewport_on_selected_object", "Ansicht auf ausgewähltes Objekt zentrieren"),
+    ("help.center_viewport_on_row_column", "Ansicht auf Zeile/Spalte zentrieren"),
+    ("help.select_cheapest_weighted_flow_path", "Günstigsten gewichteten Pfad auswählen"),
+    ("help.select_critical_longest_duration_path", "Kritischen Pfad (längste Dauer) auswählen"),
+    ("help.select_cut_points", "Knoten und Kanten auswählen, deren Entfernen das Flussdiagramm teilt"),
+    ("help.highlight_path_between_selected_nodes", "Pfad zwischen den zwei ausgewählten Knoten hinzufügen"),
+    ("help.preview_tidy_layout", "Aufgeräumtes Flussdiagramm-Layout vorschauen; Enter übernimmt"),
+    ("help.fuzzy_matcher_nereid_or_skim_toggle", "Unscharfe Suche: nereid oder skim (umschalten)"),
+    ("help.edge_glyphs_cycle", "Flowchart-Kanten: box, braille oder blocks (durchschalten)"),
+    ("help.archive_diagram", "Diagramm in [/] und Suche ausblenden (:unarchive)"),
+    ("help.lock_diagram", "Diagramm für TUI und MCP schreibschützen (:unlock)"),
+    ("help.open_diagram_by_id", "Diagramm per ID öffnen, auch archivierte"),
+    ("help.sequence_diagram_from_selected_flow_path", "Sequenzdiagramm aus ausgewähltem Pfad"),
+    ("help.interaction_flowchart_from_sequence_diagram", "Interaktions-Flussdiagramm aus Sequenzdiagramm"),
+    ("help.add_style_rule", "Stilregel hinzufügen, z. B. tag=deprecated => dim"),
+    ("help.hint_jump_mode", "Sprunghinweise"),
+    ("help.chain_hint_mode", "Kettenhinweise"),
+    ("help.box_select_arrows_grow_enter_adds", "Rahmenauswahl: Pfeile vergrößern, Enter fügt zur Auswahl hinzu"),
+    ("help.edit_active_diagram_in_editor", "Aktives Diagramm in $EDITOR bearbeiten"),
+    ("help.edit_diagram_description_in_editor", "Diagrammbeschreibung in $EDITOR bearbeiten"),
+    ("help.toggle_selected_object", "Objekt aus-/abwählen"),
+    ("help.yank_selected_object_ref", "Ref des ausgewählten Objekts kopieren"),
+    ("help.jump_inbound_outbound", "Eingehend/ausgehend springen"),
+    ("help.choose_hint_target", "Hinweisziel wählen"),
+    ("help.cancel_hint_mode", "Hinweismodus abbrechen"),
+    ("help.move_object_cursor", "Objektcursor bewegen"),
+    ("help.first_last_object", "Erstes/letztes Objekt"),
+    ("help.filter_selected_only", "Nur ausgewählte filtern"),
+    ("help.move_selected_message_up_down_seq", "Ausgewählte Nachricht nach oben/unten (Sequenz)"),
+    ("help.move_xref_cursor", "XRef-Cursor bewegen"),
+    ("help.first_last_xref", "Erste/letzte XRef"),
+    ("help.toggle_dangling_only_filter", "Filter für verwaiste XRefs umschalten"),
+    ("help.toggle_involving_selection_filter", "Filter für die Auswahl betreffende XRefs umschalten"),
+    ("help.jump_to_from_to_endpoint", "Zu Start-/Endpunkt springen"),
+    ("help.move_relation_cursor", "Beziehungscursor bewegen"),
+    ("help.first_last_relation", "Erste/letzte Beziehung"),
+    ("help.jump_to_related_object", "Zum verknüpften Objekt springen"),
+    ("help.move_recent_cursor", "Cursor in „Zuletzt“ bewegen"),
+    ("help.jump_to_recent_object", "Zum zuletzt verwendeten Objekt springen"),
+    ("help.move_timeline_cursor", "Verlaufscursor bewegen (spult während der Zeitreise)"),
+    ("help.time_travel_to_event", "Diagramme zu diesem Zeitpunkt anzeigen"),
+    ("help.leave_time_travel", "Zeitreise verlassen"),
+    ("help.scroll_help", "Hilfe blättern"),
+    ("help.close_help", "Hilfe schließen"),
+    ("help.section.global", "--- Allgemein ---"),
+    ("help.section.search", "--- Suche ---"),
+    ("help.section.diagram", "--- Diagramm ---"),
+    ("help.section.objects", "--- Objekte ---"),
+    ("help.section.xrefs", "--- XRefs ---"),
+    ("help.section.relations", "--- Beziehungen ---"),
+    ("help.section.recent", "--- Zuletzt ---"),
+    ("help.section.timeline", "--- Verlauf ---"),
+    ("help.section.help", "--- Hilfe ---"),
+    ("help.note", "Hinweis: "),
+    ("help.note_xref_follow", " folgt der ersten passenden XRef (Meldung bei mehreren)."),
+    ("toast.external_action_failed", "Externe Aktion fehlgeschlagen: {err}"),
+    ("toast.external_edits_not_watched", "Externe Änderungen werden nicht automatisch geladen: {err}"),
+    ("toast.large_diagram_reduced_detail", "Großes Diagramm: weniger Details ({overrun})"),
+    ("toast.no_object_selected", "Kein Objekt ausgewählt"),
+    ("toast.selected_object_not_rendered", "Ausgewähltes Objekt wird nicht dargestellt"),
+    ("toast.active_diagram_persist_failed", "Aktives Diagramm konnte nicht gespeichert werden: {err}"),
+    ("toast.diagram_order_persist_failed", "Diagrammreihenfolge konnte nicht gespeichert werden: {err}"),
+    ("toast.inspector_shown", "Inspektor eingeblendet"),
+    ("toast.inspector_hidden", "Inspektor ausgeblendet"),
+    ("toast.palette_shown", "Palette eingeblendet"),
+    ("toast.palette_hidden", "Palette ausgeblendet"),
+    ("toast.ruler_shown", "Lineal eingeblendet"),
+    ("toast.ruler_hidden", "Lineal ausgeblendet"),
+    ("toast.objects_shown", "Objekte eingeblendet"),
+    ("toast.objects_hidden", "Objekte ausgeblendet"),
+    ("toast.xrefs_shown", "XRefs eingeblendet"),
+    ("toast.xrefs_hidden", "XRefs ausgeblendet"),
+    ("toast.relations_shown", "Beziehungen eingeblendet"),
+    ("toast.relations_hidden", "Beziehungen ausgeblendet"),
+    ("toast.timeline_shown", "Verlauf eingeblendet"),
+    ("toast.timeline_hidden", "Verlauf ausgeblendet"),
+    ("toast.recent_shown", "Zuletzt eingeblendet"),
+    ("toast.recent_hidden", "Zuletzt ausgeblendet"),
+    ("toast.no_previous_object", "Kein vorheriges Objekt"),
+    ("toast.follow_ai_enabled", "KI folgen aktiviert"),
+    ("toast.follow_ai_disabled", "KI folgen deaktiviert"),
+    ("toast.description_unchanged", "Beschreibung unverändert: {diagram_id}"),
+    ("toast.description_updated", "Beschreibung aktualisiert: {diagram_id}"),
+    ("toast.description_saved", "Beschreibung gespeichert: {diagram_id}"),
+    ("toast.edit_cancelled_no_changes", "Bearbeitung abgebrochen (keine Änderungen): {diagram_id}"),
+    ("toast.no_structural_changes", "Keine strukturellen Änderungen: {diagram_id}"),
+    ("toast.nothing_to_undo", "Nichts rückgängig zu machen"),
+    ("toast.undo_failed", "Rückgängig fehlgeschlagen: {err}"),
+    ("toast.nothing_to_redo", "Nichts zu wiederholen"),
+    ("toast.redo_failed", "Wiederholen fehlgeschlagen: {err}"),
+    ("toast.tidy_cancelled", "Aufräumen abgebrochen"),
+    ("toast.no_active_diagram", "Kein aktives Diagramm"),
+    ("toast.insert_needs_flow_or_sequence", "Einfügen braucht ein Fluss- oder Sequenzdiagramm"),
+    ("toast.diagram_not_found", "Diagramm nicht gefunden: {diagram_id}"),
+    ("toast.session_info_updated", "Sitzungsinfo aktualisiert"),
+    ("toast.session_info_saved", "Sitzungsinfo gespeichert"),
+    ("toast.session_info_save_failed", "Sitzungsinfo konnte nicht gespeichert werden: {err}"),
+    ("toast.centered_on", "Zentriert auf {row},{col}"),
+    ("toast.no_style_rules", "Keine Stilregeln"),
+    ("toast.style_rules_persist_failed", "Stilregeln konnten nicht gespeichert werden: {err}"),
+    ("toast.no_id_prefix_rules", "Keine ID-Präfixregeln"),
+    ("toast.id_prefix_rules_persist_failed", "ID-Präfixregeln konnten nicht gespeichert werden: {err}"),
+    ("toast.no_id_prefix_rules_to_lint", "Keine ID-Präfixregeln zum Prüfen"),
+    ("toast.id_prefix_rules_followed", "Alle Objekt-IDs folgen den ID-Präfixregeln"),
+    ("toast.archive_persist_failed", "Archivierung konnte nicht gespeichert werden: {err}"),
+    ("toast.lock_persist_failed", "Schreibschutz konnte nicht gespeichert werden: {err}"),
+    ("toast.diagram_read_only", "{diagram_id} ist schreibgeschützt; zum Bearbeiten :unlock"),
+    (
+        "toast.diagram_load_failed",
+        "{diagram_id} konnte nicht geladen werden; Datei zum Bearbeiten reparieren: {err}",
+    ),
+    ("toast.unknown_diagram", "Unbekanntes Diagramm: {diagram_id}"),
+    ("toast.toseq_needs_a_flowchart", "toseq braucht ein Flussdiagramm"),
+    ("toast.select_chain_first", "Zuerst eine zusammenhängende Kette von Kanten auswählen (z. B. :path a b)"),
+    ("toast.scaffold_failed", "Gerüst fehlgeschlagen: {err}"),
+    ("toast.toflow_needs_a_sequence_diagram", "toflow braucht ein Sequenzdiagramm"),
+    ("toast.scaffolded_with_interaction_edges", "{flow_id} mit {edges} Interaktionskanten erzeugt"),
+    ("toast.scaffold_persist_failed", "Gerüst konnte nicht gespeichert werden: {err}"),
+    ("toast.tidy_needs_a_flowchart", "Aufräumen braucht ein Flussdiagramm"),
+    ("toast.layout_already_tidy", "Layout bereits aufgeräumt: {diagram_id}"),
+    ("toast.tidy_failed", "Aufräumen fehlgeschlagen: {err}"),
+    ("toast.select_a_participant_to_move", "Einen Teilnehmer zum Verschieben auswählen"),
+    ("toast.select_a_message_to_move", "Eine Sequenznachricht zum Verschieben auswählen"),
+    ("toast.move_message_failed", "Verschieben der Nachricht fehlgeschlagen: {err}"),
+    (
+        "toast.select_an_object_with_a_note",
+        "Einen Knoten, Teilnehmer oder eine Notiz zum Bearbeiten der Notiz auswählen",
+    ),
+    ("toast.note_edit_failed", "Bearbeiten der Notiz fehlgeschlagen: {err}"),
+    ("toast.diff_closed", "Vergleich geschlossen"),
+    ("toast.diff_needs_session_folder", "Nichts Gespeichertes zum Vergleichen: kein Sitzungsordner"),
+    ("toast.diff_load_failed", "Laden der gespeicherten Version fehlgeschlagen: {err}"),
+    ("toast.diagram_not_saved", "{diagram_id} hat noch keine gespeicherte Version"),
+    (
+        "toast.diff_showing_current",
+        "{diagram_id} seit der gespeicherten Version: {counts}; :diff saved zeigt Entferntes",
+    ),
+    (
+        "toast.diff_showing_saved",
+        "Gespeicherte Version von {diagram_id}: {counts}; :diff schließt",
+    ),
+    ("toast.select_a_node_to_move", "Einen Flussdiagramm-Knoten zum Verschieben auswählen"),
+    ("toast.node_cannot_move_further", "Der Knoten lässt sich nicht weiter in diese Richtung schieben"),
+    ("toast.no_pinned_nodes", "Keine angehefteten Knoten zum Lösen"),
+    ("toast.pin_failed", "Anheften fehlgeschlagen: {err}"),
+    ("toast.participants_stay_inside_their_box", "Teilnehmer bleiben in ihrer Box"),
+    ("toast.tidy_preview_stale", "{diagram_id} hat sich seit der Vorschau geändert; :tidy erneut ausführen"),
+    ("toast.weighted_paths_need_a_flowchart", "Gewichtete Pfade brauchen ein Flussdiagramm"),
+    ("toast.unknown_path_nodes", "Unbekannter Knoten: {from} oder {to}"),
+    ("toast.no_path", "Kein Pfad von {from_id} nach {to_id}"),
+    ("toast.no_messages_to_chain", "Keine Nachrichten zum Verketten"),
+    ("toast.critical_path_needs_acyclic", "Kritischer Pfad braucht ein nicht leeres, azyklisches Flussdiagramm"),
+    ("toast.cuts_need_a_flowchart", "Schnittpunkte brauchen ein Flussdiagramm"),
+    ("toast.cut_points", "{nodes} Schnittpunkte, {bridges} Brücken ausgewählt"),
+    ("toast.path_needs_two_selected_nodes", "Zwei Knoten auswählen, um den Pfad dazwischen hervorzuheben"),
+    ("toast.path_highlighted", "Pfad {from_id} → {to_id}: {nodes} Knoten, {edges} Kanten ausgewählt"),
+    ("toast.critical_path_needs_flow_or_sequence", "Kritischer Pfad braucht ein Fluss- oder Sequenzdiagramm"),
+    ("toast.visual_started", "Visuell: Pfeile vergrößern die Box, Enter wählt aus, Esc bricht ab"),
+    ("toast.visual_selection_cancelled", "Visuelle Auswahl abgebrochen"),
+    ("toast.no_hint_targets_for", "Keine Hinweisziele für '{first}'"),
+    ("toast.no_matching_hint", "Kein passender Hinweis"),
+    ("toast.too_many_hint_targets", "Zu viele Hinweisziele ({hint_count}, höchstens {max_two_char})"),
+    ("toast.no_selected_objects_in_diagram", "Keine ausgewählten Objekte im aktuellen Diagramm"),
+    ("toast.no_recent_object_selected", "Kein zuletzt verwendetes Objekt ausgewählt"),
+    ("toast.missing_object", "Objekt fehlt: {target}"),
+    ("toast.no_activity_selected", "Keine Aktivität ausgewählt"),
+    ("toast.proposal_withdrawn", "Der angezeigte Vorschlag wurde zurückgezogen"),
+    ("toast.showing_original", "Original wird angezeigt (:proposal zeigt wieder den Vorschlag)"),
+    ("toast.no_proposal_previewed", "Kein Vorschlag in der Vorschau (:proposal)"),
+    ("toast.back_to_the_present", "Zurück in der Gegenwart"),
+    ("toast.no_relation_selected", "Keine Beziehung ausgewählt"),
+    ("toast.no_outgoing_xref_for", "Keine ausgehende XRef für {selected_ref}"),
+    ("toast.no_incoming_xref_for", "Keine eingehende XRef für {selected_ref}"),
+    ("toast.no_location_to_copy", "Keine Position zum Kopieren"),
+    ("toast.copied", "{location} kopiert ({backend})"),
+    ("toast.clipboard_error", "Fehler der Zwischenablage: {err}"),
+    ("toast.paste_not_mermaid", "Zwischenablage enthält kein Sequenz-, Fluss-, Zustands- oder Klassen-Mermaid"),
+    ("toast.paste_parse_failed", "Eingefügtes Mermaid lässt sich nicht parsen: {err}"),
+    ("toast.paste_persist_failed", "Eingefügtes Diagramm nicht gespeichert: {err}"),
+    ("toast.pasted_new_diagram", "{diagram_id} aus der Zwischenablage erstellt"),
+    ("toast.yanked_object_ref", "Objekt-Ref kopiert ({backend})"),
+    ("toast.diagram_changed", "{verb} {diagram_id} (Rev {baseline_rev}->{new_rev})"),
+    ("toast.diagram_archived", "{diagram_id} archiviert"),
+    ("toast.diagram_restored", "{diagram_id} wiederhergestellt"),
+    ("toast.diagram_locked", "{diagram_id} schreibgeschützt"),
+    ("toast.diagram_unlocked", "{diagram_id} freigegeben"),
+    ("toast.diagram_changed_sync_pending", "{verb} {diagram_id} (Rev {baseline_rev}->{new_rev}); Abgleich ausstehend"),
+    ("toast.synced_edited_diagram", "Bearbeitetes Diagramm abgeglichen: {diagram_id}"),
+    ("toast.ops_journal_failed", "Op-Journal konnte nicht geschrieben werden: {err}"),
+    ("toast.merged_disk_changes_into", "Änderungen von der Festplatte in {diagram_id} übernommen"),
+    ("toast.style_rules", "Stilregeln: {rules}"),
+    ("toast.id_prefix_rules", "ID-Präfixregeln: {rules}"),
+    ("toast.scaffolded_from_hops", "{seq_id} aus {hops} Schritten erzeugt"),
+    ("toast.fuzzy_matcher", "Unscharfe Suche: {matcher}"),
+    ("toast.edge_glyphs", "Kantenzeichen: {glyphs}"),
+    ("toast.no_new_objects_in_box", "Keine neuen Objekte in der Box ({count} bereits ausgewählt)"),
+    ("toast.time_travel", "Zeitreise: {at} (Esc im Verlauf kehrt zurück)"),
+    ("toast.diagram_no_longer_exists", "{diagram_id} existiert nicht mehr"),
+    ("toast.cannot_preview", "Vorschau von {proposal_id} nicht möglich: {err}"),
+    ("toast.proposal_no_longer_pending", "{proposal_id} ist nicht mehr offen"),
+    ("toast.cannot_accept", "{proposal_id} kann nicht übernommen werden: {err}"),
+    ("toast.discarded", "{proposal_id} verworfen"),
+    ("toast.outgoing_xrefs_followed_first", "{count} ausgehende XRefs; der ersten gefolgt ({xref_id})"),
+    ("toast.incoming_xrefs_followed_first", "{count} eingehende XRefs; der ersten gefolgt ({xref_id})"),
+    ("toast.zoom_glyphs", "Zoom: Symbole"),
+    ("toast.zoom_mini", "Zoom: Mini-Boxen"),
+    ("toast.zoom_compact", "Zoom: kompakt"),
+    ("toast.zoom_normal", "Zoom: normal"),
+    ("toast.zoom_spacious", "Zoom: großzügig"),
+    ("toast.id_prefix_violations", "{count} ID(s) verletzen die ID-Präfixregeln, z. B. {object_ref} ({rule})"),
+    ("toast.showing_selected_objects", "Nur ausgewählte Objekte"),
+    ("toast.showing_all_objects", "Alle Objekte"),
+    ("toast.no_pending_proposal", "Kein offener Vorschlag {proposal_id}"),
+    ("toast.no_pending_proposals", "Keine offenen Vorschläge"),
+    ("toast.sync_conflict_kept_disk", "Abgleichskonflikt: Festplattenversion von {diagram_id} behalten; lokale Änderung verworfen"),
+    ("toast.agent_proposed", "Agent schlägt {proposal_id} für {diagram_id} vor: {summary} (:proposal zur Vorschau)"),
+    ("toast.proposal_stale", "{diagram_id} hat sich seit {proposal_id} geändert (Rev {base_rev}->{current_rev}); neuen Vorschlag anfordern"),
+    ("toast.a11y_line_on", "Barrierefreiheitszeile an"),
+    ("toast.a11y_line_off", "Barrierefreiheitszeile aus"),
+    ("toast.a11y_output_failed", "Barrierefreiheitsausgabe fehlgeschlagen: {err}"),
+    ("help.a11y_line_toggle", "Barrierefreiheitszeile: fokussiertes Objekt beschreiben (umschalten)"),
+    ("a11y.focus", "{label} in {diagram}."),
+    ("a11y.nothing_focused", "Nichts fokussiert in {diagram}."),
+    ("a11y.no_connections", "Keine Verbindungen."),
+    ("a11y.more_connections", "{count} weitere"),
+    ("a11y.arrow_to", "nach"),
+    ("a11y.arrow_from", "von"),
+    ("a11y.tag.out", "ausgehend"),
+    ("a11y.tag.in", "eingehend"),
+    ("a11y.tag.from", "von"),
+    ("a11y.tag.to", "nach"),
+    ("a11y.tag.prev", "vorher"),
+    ("a11y.tag.next", "danach"),
+    ("a11y.tag.member", "Mitglieder"),
+    ("a11y.tag.within", "in"),
+    ("a11y.tag.xref_out", "Xref nach"),
+    ("a11y.tag.xref_in", "Xref von"),
+    ("a11y.tag.other", "verwandt"),
+    ("toast.split_already_open", "Die Ansicht ist bereits geteilt"),
+    ("toast.no_diagram_to_split", "Kein Diagramm zum Teilen"),
+    ("toast.no_split", "Die Ansicht ist nicht geteilt (Strg-w v teilt sie)"),
+    ("help.split_open", "Teilen: zweiten Diagrammbereich öffnen (Xref-Partner des fokussierten Objekts)"),
+    ("help.split_focus", "Teilen: anderen / linken / rechten Bereich fokussieren"),
+    ("help.split_close", "Teilen: fokussierten / anderen Bereich schließen"),
+    ("toast.session_check_passed", "Sitzungsprüfung bestanden: keine Probleme gefunden"),
+    ("toast.session_check_problems", "Sitzungsprüfung: {errors} Fehler, {warnings} Warnung(en) (Esc schließt)"),
+    ("help.check_session", "Sitzung auf Integrität prüfen (verwaiste Xrefs, fehlende Knoten, ...)"),
+    ("toast.no_workspace", "Kein Arbeitsbereich (nereid mit --workspace <dir> starten)"),
+    ("toast.workspace_empty", "Der Arbeitsbereich enthält keine Sitzungen"),
+    ("toast.workspace_list_failed", "Sitzungen des Arbeitsbereichs nicht lesbar: {err}"),
+    ("toast.session_opened", "Sitzung {name} geöffnet"),
+    ("toast.session_opened_recovered", "Sitzung {name} geöffnet ({count} nicht synchronisierte Änderung(en) wiederhergestellt)"),
+    ("toast.session_open_failed", "Sitzung {name} kann nicht geöffnet werden: {err}"),
+    ("toast.session_switch_blocked", "Vor dem Sitzungswechsel die Zusammenführung abschließen"),
+    ("help.sessions", "Zu einer anderen Sitzung des Arbeitsbereichs wechseln (Auswahl oder Name)"),
+    ("help.paste_new", "Neues Diagramm aus Mermaid in der Zwischenablage"),
+    ("help.note_command", "Notiz des ausgewählten Objekts setzen, bearbeiten oder entfernen"),
+    ("help.diff", "Mit der gespeicherten Version vergleichen (aktuelle/gespeicherte Seite)"),
+    ("help.unpin", "Ausgewählten Knoten (oder alle) für das Auto-Layout lösen"),
+];
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{Messages, DE, EN, MESSAGES_FILE_NAME};
+
+    fn placeholders(text: &str) -> BTreeSet<&str> {
+        text.split('{').skip(1).filter_map(|part| part.split_once('}')).map(|(n, _)| n).collect()
+    }
+
+    #[test]
+    fn bundled_locales_translate_every_key_with_the_same_placeholders() {
+        let english = EN.iter().copied().collect::<std::collections::BTreeMap<_, _>>();
+        assert_eq!(english.len(), EN.len(), "duplicate English keys");
+        assert_eq!(DE.len(), EN.len(), "German catalog is incomplete");
+        for (key, text) in DE {
+            let source = english.get(key).unwrap_or_else(|| panic!("unknown key {key}"));
+            assert_eq!(placeholders(text), placeholders(source), "{key}");
+        }
+    }
+
+    #[test]
+    fn parses_locale_overrides_and_formats_placeholders() {
+        let messages = Messages::parse(
+            "# team catalog\nlocale = \"de\"\n\n[messages]\n\"footer.quit\" = \"RAUS\" # short\n\
+             toast.copied = 'Kopiert: {location}'\n",
+        )
+        .expect("messages");
+        assert_eq!(messages.locale_name(), "de");
+        assert_eq!(messages.get("footer.quit"), "RAUS");
+        assert_eq!(messages.get("title.help"), "Hilfe");
+        assert_eq!(
+            messages.format("toast.copied", &[("location", &"d:flow"), ("backend", &"osc52")]),
+            "Kopiert: d:flow"
+        );
+        assert_eq!(
+            Messages::default().format("toast.centered_on", &[("row", &3), ("col", &"{x}")]),
+            "Centered on 3,{x}"
+        );
+        assert_eq!(Messages::default().get("no.such.key"), "no.such.key");
+
+        assert!(Messages::parse("locale = \"xx\"").unwrap_err().contains("unknown locale"));
+        assert!(Messages::parse("[messages]\nnope = \"x\"")
+            .unwrap_err()
+            .contains("unknown message"));
+        assert!(Messages::parse("[colors]").unwrap_err().contains("unknown table"));
+    }
+
+    #[test]
+    fn loads_the_session_messages_file() {
+        let dir = std::env::temp_dir().join(format!(
+            "nereid-messages-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        std::fs::write(
+            dir.join(MESSAGES_FILE_NAME),
+            "[messages]\n\"inspector.missing\" = \" (gone)\\n\"\n",
+        )
+        .expect("write");
+
+        let messages = Messages::load(Some(&dir)).expect("load messages");
+        assert_eq!(messages.get("inspector.missing"), " (gone)\n");
+        assert_eq!(messages.get("title.help"), "Help");
+
+        std::fs::write(dir.join(MESSAGES_FILE_NAME), "locale = de").expect("write");
+        assert!(Messages::load(Some(&dir)).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
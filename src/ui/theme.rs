@@ -0,0 +1,326 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+//! TUI color theme.
+//!
+//! A [`Theme`] starts from a preset (`dark`, the default, or `light`) and can override single
+//! elements from a `theme.toml` found in the session folder or in `$XDG_CONFIG_HOME/nereid`
+//! (`~/.config/nereid` when unset). Only the small TOML subset needed for that is read:
+//!
+//! ```toml
+//! preset = "light"
+//!
+//! [colors]
+//! selection_bg = "#d0d7de"
+//! xref_dangling = "light-red"
+//! ```
+//!
+//! Colors are ratatui color names (`light-green`, `dark-gray`, …), ANSI indexes or `#rrggbb`.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use ratatui::style::Color;
+
+pub const THEME_FILE_NAME: &str = "theme.toml";
+
+static CURRENT: OnceLock<Theme> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Human focus: focused panel borders and focused objects.
+    pub focus: Color,
+    /// Agent focus and agent highlights.
+    pub agent_focus: Color,
+    /// Background of selected objects outside the focus.
+    pub selection_bg: Color,
+    /// Text on focus, agent and selection backgrounds.
+    pub selection_fg: Color,
+    /// Diagram text dimmed while another object is selected.
+    pub dimmed: Color,
+    pub inspector: Color,
+    pub footer_label: Color,
+    pub footer_key: Color,
+    pub footer_brand: Color,
+    pub ruler: Color,
+    pub xref_dangling: Color,
+    pub note: Color,
+    /// Sequence block frames (`loop`, `alt`, …).
+    pub sequence_block: Color,
+    /// Background of the selected sequence block section.
+    pub sequence_area: Color,
+    /// `:diff` objects added since the saved version.
+    pub diff_added: Color,
+    /// `:diff` objects the current version removed, shown on the saved side.
+    pub diff_removed: Color,
+    /// `:diff` objects whose label, note, tags, anchors or links changed.
+    pub diff_changed: Color,
+}
+
+impl Theme {
+    pub const DARK: Self = Self {
+        focus: Color::LightGreen,
+        agent_focus: Color::LightBlue,
+        selection_bg: Color::DarkGray,
+        selection_fg: Color::White,
+        dimmed: Color::DarkGray,
+        inspector: Color::DarkGray,
+        footer_label: Color::Gray,
+        footer_key: Color::Cyan,
+        footer_brand: Color::White,
+        ruler: Color::DarkGray,
+        xref_dangling: Color::Red,
+        note: Color::DarkGray,
+        sequence_block: Color::LightYellow,
+        sequence_area: Color::Yellow,
+        diff_added: Color::LightGreen,
+        diff_removed: Color::LightRed,
+        diff_changed: Color::LightYellow,
+    };
+
+    pub const LIGHT: Self = Self {
+        focus: Color::Green,
+        agent_focus: Color::Blue,
+        selection_bg: Color::Gray,
+        selection_fg: Color::Black,
+        dimmed: Color::Gray,
+        inspector: Color::Gray,
+        footer_label: Color::DarkGray,
+        footer_key: Color::Blue,
+        footer_brand: Color::Black,
+        ruler: Color::Gray,
+        xref_dangling: Color::Red,
+        note: Color::Gray,
+        sequence_block: Color::Rgb(0x9a, 0x67, 0x00),
+        sequence_area: Color::LightYellow,
+        diff_added: Color::Green,
+        diff_removed: Color::Red,
+        diff_changed: Color::Rgb(0x9a, 0x67, 0x00),
+    };
+
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::DARK),
+            "light" => Some(Self::LIGHT),
+            _ => None,
+        }
+    }
+
+    /// The installed theme, or the dark preset if none was installed.
+    pub fn current() -> &'static Self {
+        CURRENT.get_or_init(Self::default)
+    }
+
+    /// Makes `self` the theme returned by [`Theme::current`]; only the first call has an effect.
+    pub fn install(self) {
+        let _ = CURRENT.set(self);
+    }
+
+    /// Loads `theme.toml` from `session_dir`, else from the user config directory; the dark
+    /// preset when neither exists.
+    pub fn load(session_dir: Option<&Path>) -> Result<Self, ThemeError> {
+        let candidates = session_dir
+            .map(|dir| dir.join(THEME_FILE_NAME))
+            .into_iter()
+            .chain(config_path(THEME_FILE_NAME));
+        for path in candidates {
+            match fs::read_to_string(&path) {
+                Ok(text) => {
+                    return Self::parse(&text)
+                        .map_err(|message| ThemeError::Parse { path, message })
+                }
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(source) => return Err(ThemeError::Io { path, source }),
+            }
+        }
+        Ok(Self::default())
+    }
+
+    /// Parses the `theme.toml` subset: an optional top-level `preset` and a `[colors]` table.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut preset = None;
+        let mut overrides = Vec::new();
+        let mut in_colors = false;
+
+        for (idx, raw_line) in text.lines().enumerate() {
+            let line_no = idx + 1;
+            let line = strip_comment(raw_line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(table) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+                if table.trim() != "colors" {
+                    return Err(format!("line {line_no}: unknown table [{}]", table.trim()));
+                }
+                in_colors = true;
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {line_no}: expected `key = \"value\"`"))?;
+            let (key, value) = (key.trim(), unquote(value.trim()));
+            let value = value.ok_or_else(|| format!("line {line_no}: expected a quoted value"))?;
+
+            if in_colors {
+                let color = Color::from_str(value)
+                    .map_err(|_| format!("line {line_no}: invalid color {value:?}"))?;
+                overrides.push((line_no, key, color));
+            } else if key == "preset" {
+                preset = Some(
+                    Self::preset(value)
+                        .ok_or_else(|| format!("line {line_no}: unknown preset {value:?}"))?,
+                );
+            } else {
+                return Err(format!("line {line_no}: unknown key {key:?}"));
+            }
+        }
+
+        let mut theme = preset.unwrap_or_default();
+        for (line_no, key, color) in overrides {
+            *theme
+                .element_mut(key)
+                .ok_or_else(|| format!("line {line_no}: unknown color {key:?}"))? = color;
+        }
+        Ok(theme)
+    }
+
+    fn element_mut(&mut self, key: &str) -> Option<&mut Color> {
+        Some(match key {
+            "focus" => &mut self.focus,
+            "agent_focus" => &mut self.agent_focus,
+            "selection_bg" => &mut self.selection_bg,
+            "selection_fg" => &mut self.selection_fg,
+            "dimmed" => &mut self.dimmed,
+            "inspector" => &mut self.inspector,
+            "footer_label" => &mut self.footer_label,
+            "footer_key" => &mut self.footer_key,
+            "footer_brand" => &mut self.footer_brand,
+            "ruler" => &mut self.ruler,
+            "xref_dangling" => &mut self.xref_dangling,
+            "note" => &mut self.note,
+            "sequence_block" => &mut self.sequence_block,
+            "sequence_area" => &mut self.sequence_area,
+            "diff_added" => &mut self.diff_added,
+            "diff_removed" => &mut self.diff_removed,
+            "diff_changed" => &mut self.diff_changed,
+            _ => return None,
+        })
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DARK
+    }
+}
+
+/// `file_name` in the user config directory, `$XDG_CONFIG_HOME/nereid` or `~/.config/nereid`.
+pub(super) fn config_path(file_name: &str) -> Option<PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join("nereid").join(file_name))
+}
+
+pub(super) fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (idx, ch) in line.char_indices() {
+        match ch {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..idx],
+            _ => {}
+        }
+    }
+    line
+}
+
+pub(super) fn unquote(value: &str) -> Option<&str> {
+    value
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|rest| rest.strip_suffix('\'')))
+}
+
+#[derive(Debug)]
+pub enum ThemeError {
+    Io { path: PathBuf, source: io::Error },
+    Parse { path: PathBuf, message: String },
+}
+
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "failed to read {}: {source}", path.display()),
+            Self::Parse { path, message } => {
+                write!(f, "invalid theme {}: {message}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ThemeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Parse { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::Color;
+
+    use super::{Theme, THEME_FILE_NAME};
+
+    #[test]
+    fn parses_preset_and_element_overrides() {
+        let theme = Theme::parse(
+            "# light terminal\npreset = \"light\"\n\n[colors]\nselection_bg = \"#d0d7de\" # grey\n\
+             xref_dangling = 'light-red'\n",
+        )
+        .expect("theme");
+        assert_eq!(theme.focus, Theme::LIGHT.focus);
+        assert_eq!(theme.selection_bg, Color::Rgb(0xd0, 0xd7, 0xde));
+        assert_eq!(theme.xref_dangling, Color::LightRed);
+        assert_eq!(Theme::parse("").expect("empty theme"), Theme::DARK);
+
+        assert!(Theme::parse("preset = \"neon\"").unwrap_err().contains("unknown preset"));
+        assert!(Theme::parse("[colors]\nfocus = \"nope\"").unwrap_err().contains("invalid color"));
+        assert!(Theme::parse("[colors]\nborder = \"red\"").unwrap_err().contains("unknown color"));
+        assert!(Theme::parse("[keys]").unwrap_err().contains("unknown table"));
+    }
+
+    #[test]
+    fn loads_the_session_theme_file() {
+        let dir = std::env::temp_dir().join(format!(
+            "nereid-theme-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        std::fs::write(dir.join(THEME_FILE_NAME), "[colors]\nnote = \"magenta\"\n").expect("write");
+
+        let theme = Theme::load(Some(&dir)).expect("load theme");
+        assert_eq!(theme.note, Color::Magenta);
+        assert_eq!(theme.focus, Theme::DARK.focus);
+
+        std::fs::write(dir.join(THEME_FILE_NAME), "preset = dark").expect("write");
+        assert!(Theme::load(Some(&dir)).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
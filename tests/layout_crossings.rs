@@ -0,0 +1,34 @@
+// SPDX-FileCopyrightText: 2026 Bruno Meilick
+// SPDX-License-Identifier: LicenseRef-Nereid-FreeUse-NoCopy-NoDerivatives
+//
+// All rights reserved.
+//
+// This file is part of Nereid and is proprietary software.
+// Unauthorized copying, modification, or distribution is prohibited.
+
+use nereid::layout::{
+    count_flowchart_crossings, layout_flowchart_tidy_with_options, layout_flowchart_with_options,
+    FlowchartLayoutOptions, DEFAULT_CROSSING_SWEEPS,
+};
+
+#[path = "../benches/fixtures/mod.rs"]
+mod fixtures;
+
+#[test]
+fn crossing_sweeps_reduce_crossings_on_the_medium_dense_fixture() {
+    let ast = fixtures::flow::fixture(fixtures::flow::Case::MediumDense);
+    let crossings = |crossing_sweeps| {
+        let options = FlowchartLayoutOptions { crossing_sweeps };
+        let layout = layout_flowchart_with_options(&ast, options).expect("layout");
+        count_flowchart_crossings(&ast, &layout)
+    };
+
+    let unswept = crossings(0);
+    let swept = crossings(DEFAULT_CROSSING_SWEEPS);
+    assert!(swept < unswept, "{DEFAULT_CROSSING_SWEEPS} sweeps: {swept}, none: {unswept}");
+
+    let options = FlowchartLayoutOptions::default();
+    let tidy = layout_flowchart_tidy_with_options(&ast, options).expect("tidy layout");
+    let tidied = count_flowchart_crossings(&ast, &tidy);
+    assert!(tidied <= swept, "tidy: {tidied}, layout: {swept}");
+}